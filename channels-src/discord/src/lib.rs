@@ -342,11 +342,12 @@ fn handle_slash_command(interaction: &DiscordInteraction) {
         }
     };
 
+    // Each Discord channel (threads are channels too) is its own conversation
     channel_host::emit_message(&EmittedMessage {
         user_id,
         user_name: Some(user_name),
         content,
-        thread_id: None,
+        thread_id: Some(channel_id).filter(|id| !id.is_empty()),
         metadata_json,
     });
 }
@@ -394,7 +395,7 @@ fn handle_message_component(interaction: &DiscordInteraction, message: &DiscordM
         user_id,
         user_name: Some(user_name),
         content: format!("[Button clicked] {}", message.content),
-        thread_id: None,
+        thread_id: Some(channel_id).filter(|id| !id.is_empty()),
         metadata_json,
     });
 }
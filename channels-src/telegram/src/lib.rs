@@ -69,6 +69,14 @@ struct TelegramMessage {
     /// Chat the message belongs to.
     chat: TelegramChat,
 
+    /// Forum topic the message belongs to (supergroups with topics).
+    #[serde(default)]
+    message_thread_id: Option<i64>,
+
+    /// Whether the message was sent in a forum topic.
+    #[serde(default)]
+    is_topic_message: bool,
+
     /// Message text.
     text: Option<String>,
 
@@ -197,6 +205,10 @@ struct TelegramMessageMetadata {
 
     /// Whether this is a private (DM) chat.
     is_private: bool,
+
+    /// Forum topic, so typing indicators land in the right topic.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    message_thread_id: Option<i64>,
 }
 
 /// Channel configuration injected by host.
@@ -576,10 +588,13 @@ impl Guest for TelegramChannel {
         };
 
         // POST /sendChatAction with action "typing"
-        let payload = serde_json::json!({
+        let mut payload = serde_json::json!({
             "chat_id": metadata.chat_id,
             "action": "typing"
         });
+        if let Some(topic) = metadata.message_thread_id {
+            payload["message_thread_id"] = serde_json::json!(topic);
+        }
 
         let payload_bytes = match serde_json::to_vec(&payload) {
             Ok(b) => b,
@@ -614,6 +629,14 @@ impl Guest for TelegramChannel {
     }
 }
 
+/// Thread ID reported to the agent: the chat, plus the forum topic if any.
+fn conversation_key(chat_id: i64, topic: Option<i64>) -> String {
+    match topic {
+        Some(topic) => format!("{}:{}", chat_id, topic),
+        None => chat_id.to_string(),
+    }
+}
+
 // ============================================================================
 // Send Message Helper
 // ============================================================================
@@ -1017,12 +1040,18 @@ fn handle_message(message: TelegramMessage) {
         from.first_name.clone()
     };
 
+    // Each chat, and each forum topic within one, is its own conversation
+    let topic = message
+        .message_thread_id
+        .filter(|_| message.is_topic_message);
+
     // Build metadata for response routing
     let metadata = TelegramMessageMetadata {
         chat_id: message.chat.id,
         message_id: message.message_id,
         user_id: from.id,
         is_private,
+        message_thread_id: topic,
     };
 
     let metadata_json = serde_json::to_string(&metadata).unwrap_or_else(|_| "{}".to_string());
@@ -1052,7 +1081,7 @@ fn handle_message(message: TelegramMessage) {
         user_id: from.id.to_string(),
         user_name: Some(user_name),
         content: content_to_emit,
-        thread_id: None, // Telegram doesn't have threads in the same way
+        thread_id: Some(conversation_key(message.chat.id, topic)),
         metadata_json,
     });
 
@@ -1139,6 +1168,23 @@ export!(TelegramChannel);
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_conversation_key() {
+        assert_eq!(conversation_key(-100123, None), "-100123");
+        assert_eq!(conversation_key(-100123, Some(7)), "-100123:7");
+
+        let message: TelegramMessage = serde_json::from_value(serde_json::json!({
+            "message_id": 1,
+            "chat": { "id": -100123, "type": "supergroup" },
+            "message_thread_id": 7,
+            "is_topic_message": true,
+            "text": "hi"
+        }))
+        .unwrap();
+        assert_eq!(message.message_thread_id, Some(7));
+        assert!(message.is_topic_message);
+    }
+
     #[test]
     fn test_clean_message_text() {
         // Without bot_username: strips any leading @mention
@@ -0,0 +1,15 @@
+-- Structured per-job LLM transcripts for replay and regression testing.
+--
+-- One row per LLM call: the full request (messages, tool definitions,
+-- sampling params) and the model's response (text, tool calls, usage).
+
+CREATE TABLE IF NOT EXISTS job_transcript_entries (
+    id         BIGSERIAL   PRIMARY KEY,
+    job_id     UUID        NOT NULL,
+    seq        INTEGER     NOT NULL,
+    model      TEXT        NOT NULL,
+    request    JSONB       NOT NULL,
+    response   JSONB       NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    UNIQUE (job_id, seq)
+);
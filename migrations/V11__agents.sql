@@ -0,0 +1,18 @@
+-- Agent registry: named agents with their own workspace scope, model,
+-- toolset, and channels.
+--
+-- An agent's ID doubles as the agent_id of its memory_documents, so its
+-- workspace is isolated from the main agent's (agent_id IS NULL).
+
+CREATE TABLE IF NOT EXISTS agents (
+    id             UUID        PRIMARY KEY,
+    name           TEXT        NOT NULL UNIQUE,
+    description    TEXT        NOT NULL DEFAULT '',
+    enabled        BOOLEAN     NOT NULL DEFAULT true,
+    model          TEXT,                          -- NULL = configured model
+    tools          JSONB       NOT NULL DEFAULT '["*"]',
+    channels       JSONB       NOT NULL DEFAULT '[]',  -- empty = every channel
+    workspace_seed JSONB       NOT NULL DEFAULT '{}',  -- path -> content
+    created_at     TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at     TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
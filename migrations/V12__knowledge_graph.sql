@@ -0,0 +1,32 @@
+-- Knowledge graph extracted from workspace documents.
+--
+-- Entities are scoped like memory_documents (user_id, agent_id) and matched
+-- by name_key, a lowercased, whitespace-collapsed form of the name. Edges
+-- remember the document they were extracted from so re-indexing a document
+-- replaces its edges.
+
+CREATE TABLE IF NOT EXISTS graph_entities (
+    id         UUID        PRIMARY KEY,
+    user_id    TEXT        NOT NULL,
+    agent_id   UUID,
+    name       TEXT        NOT NULL,
+    name_key   TEXT        NOT NULL,
+    kind       TEXT        NOT NULL DEFAULT '',
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_graph_entities_scope
+    ON graph_entities(user_id, agent_id, name_key);
+
+CREATE TABLE IF NOT EXISTS graph_edges (
+    id          UUID        PRIMARY KEY,
+    source_id   UUID        NOT NULL REFERENCES graph_entities(id) ON DELETE CASCADE,
+    relation    TEXT        NOT NULL,
+    target_id   UUID        NOT NULL REFERENCES graph_entities(id) ON DELETE CASCADE,
+    document_id UUID        REFERENCES memory_documents(id) ON DELETE CASCADE,
+    created_at  TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_graph_edges_source ON graph_edges(source_id);
+CREATE INDEX IF NOT EXISTS idx_graph_edges_target ON graph_edges(target_id);
+CREATE INDEX IF NOT EXISTS idx_graph_edges_document ON graph_edges(document_id);
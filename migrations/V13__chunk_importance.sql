@@ -0,0 +1,15 @@
+-- Importance signals for memory chunks, blended into search ranking.
+--
+-- Rows are keyed by document and a hash of the chunk text rather than by
+-- chunk ID, so retrieval counts survive re-indexing when a chunk's text is
+-- unchanged. `importance` is the static part of the score (document type and
+-- explicit "remember this" markers), set when the chunk is indexed.
+
+CREATE TABLE IF NOT EXISTS memory_chunk_signals (
+    document_id       UUID        NOT NULL REFERENCES memory_documents(id) ON DELETE CASCADE,
+    content_hash      TEXT        NOT NULL,
+    importance        REAL        NOT NULL DEFAULT 0.5,
+    retrieval_count   BIGINT      NOT NULL DEFAULT 0,
+    last_retrieved_at TIMESTAMPTZ,
+    PRIMARY KEY (document_id, content_hash)
+);
@@ -0,0 +1,19 @@
+-- Memories pinned into every system prompt.
+--
+-- A pin targets a whole document (path), one section of a document (path +
+-- heading), or a standalone fact (content). Pins are scoped like
+-- memory_documents and resolved when the prompt is built, so a pinned
+-- document or section always shows its current text.
+
+CREATE TABLE IF NOT EXISTS memory_pins (
+    id         UUID        PRIMARY KEY,
+    user_id    TEXT        NOT NULL,
+    agent_id   UUID,
+    kind       TEXT        NOT NULL,
+    path       TEXT,
+    heading    TEXT,
+    content    TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_memory_pins_scope ON memory_pins(user_id, agent_id);
@@ -0,0 +1,14 @@
+-- Wiki-style [[links]] between workspace documents.
+--
+-- Each row is one distinct link target of a document, normalized without a
+-- trailing ".md". Targets may be bare names ("bob") that resolve to any
+-- document whose path ends in that name, so backlink lookups match both the
+-- full path and its "/"-bounded suffixes.
+
+CREATE TABLE IF NOT EXISTS memory_links (
+    document_id UUID NOT NULL REFERENCES memory_documents(id) ON DELETE CASCADE,
+    target      TEXT NOT NULL,
+    PRIMARY KEY (document_id, target)
+);
+
+CREATE INDEX IF NOT EXISTS idx_memory_links_target ON memory_links(target);
@@ -0,0 +1,19 @@
+-- Explicit workspace directories.
+--
+-- Directories are otherwise implied by the paths of their documents. A row
+-- here keeps a directory listed while it has no documents (created with
+-- mkdir, or left behind when its last document was deleted) and carries
+-- its metadata. Paths are normalized, without slashes at either end.
+
+CREATE TABLE IF NOT EXISTS memory_directories (
+    id         UUID        PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id    TEXT        NOT NULL,
+    agent_id   UUID,
+    path       TEXT        NOT NULL,
+    metadata   JSONB       NOT NULL DEFAULT '{}',
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_memory_directories_path
+    ON memory_directories(user_id, path text_pattern_ops);
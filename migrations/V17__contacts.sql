@@ -0,0 +1,20 @@
+-- Contact book: the people the agent can reach on the user's behalf.
+--
+-- Handles are the sender IDs a person has on each channel, stored as a JSON
+-- array of {"channel", "handle"} objects; a handle belongs to at most one
+-- contact per user, which the application enforces when linking.
+
+CREATE TABLE IF NOT EXISTS contacts (
+    id           UUID        PRIMARY KEY,
+    user_id      TEXT        NOT NULL,
+    name         TEXT        NOT NULL,
+    relationship TEXT        NOT NULL DEFAULT '',
+    emails       JSONB       NOT NULL DEFAULT '[]',
+    handles      JSONB       NOT NULL DEFAULT '[]',
+    notes        TEXT        NOT NULL DEFAULT '',
+    last_seen_at TIMESTAMPTZ,
+    created_at   TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at   TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_contacts_user ON contacts(user_id, name);
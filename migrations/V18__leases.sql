@@ -0,0 +1,14 @@
+-- Leases: which ironclaw instance owns a piece of work (a job, the heartbeat,
+-- the routine ticker) when several instances share one database.
+--
+-- A lease is held until expires_at and renewed by its owner while the work
+-- runs; once it has expired any instance may take it over.
+
+CREATE TABLE IF NOT EXISTS leases (
+    name        TEXT        PRIMARY KEY,
+    owner       TEXT        NOT NULL,
+    acquired_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    expires_at  TIMESTAMPTZ NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_leases_owner ON leases(owner);
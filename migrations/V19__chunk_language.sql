@@ -0,0 +1,15 @@
+-- Per-chunk language for full-text search.
+--
+-- Chunks were always indexed with the English text search configuration,
+-- which stems and drops stop words wrongly for other languages. The
+-- language detected at index time is stored as a regconfig and the
+-- generated tsvector uses it. Existing chunks stay English until their
+-- document is next written or `ironclaw memory reindex` runs.
+
+ALTER TABLE memory_chunks ADD COLUMN IF NOT EXISTS language REGCONFIG NOT NULL DEFAULT 'english';
+
+DROP INDEX IF EXISTS idx_memory_chunks_tsv;
+ALTER TABLE memory_chunks DROP COLUMN content_tsv;
+ALTER TABLE memory_chunks
+    ADD COLUMN content_tsv TSVECTOR GENERATED ALWAYS AS (to_tsvector(language, content)) STORED;
+CREATE INDEX idx_memory_chunks_tsv ON memory_chunks USING GIN(content_tsv);
@@ -0,0 +1,14 @@
+-- Prior revisions of workspace documents.
+--
+-- Every content update stores the content it replaces, numbered per
+-- document from 1, so a bad edit to MEMORY.md or an identity file can be
+-- inspected and reverted. `saved_at` is when that content was written. Only
+-- the newest revisions of each document are kept.
+
+CREATE TABLE IF NOT EXISTS memory_document_versions (
+    document_id UUID        NOT NULL REFERENCES memory_documents(id) ON DELETE CASCADE,
+    version     INTEGER     NOT NULL,
+    content     TEXT        NOT NULL,
+    saved_at    TIMESTAMPTZ NOT NULL,
+    PRIMARY KEY (document_id, version)
+);
@@ -0,0 +1,18 @@
+-- Learned sparse vectors of workspace chunks.
+--
+-- With a sparse encoder (SPLADE, BM42) configured, each chunk is encoded as
+-- weights over the model's vocabulary, stored here one row per nonzero
+-- term. Search ranks chunks by the dot product of their weights with the
+-- query's, looked up through the term index, as a third leg alongside
+-- full-text and vector search. Chunks indexed without an encoder have no
+-- rows until their document is next written or `ironclaw memory reindex`
+-- runs.
+
+CREATE TABLE IF NOT EXISTS memory_chunk_terms (
+    chunk_id UUID    NOT NULL REFERENCES memory_chunks(id) ON DELETE CASCADE,
+    term     INTEGER NOT NULL,
+    weight   REAL    NOT NULL,
+    PRIMARY KEY (chunk_id, term)
+);
+
+CREATE INDEX IF NOT EXISTS idx_memory_chunk_terms_term ON memory_chunk_terms(term);
@@ -0,0 +1,30 @@
+-- Append-only audit log: messages, tool executions, approvals, and
+-- workspace mutations, with actor attribution.
+--
+-- Rows are never updated; the trigger below enforces that. Rows are only
+-- deleted by retention pruning (AUDIT_RETENTION_DAYS).
+
+CREATE TABLE IF NOT EXISTS audit_log (
+    id         BIGSERIAL   PRIMARY KEY,
+    kind       TEXT        NOT NULL,
+    actor      TEXT        NOT NULL,
+    channel    TEXT,
+    target     TEXT,
+    outcome    TEXT,
+    details    JSONB       NOT NULL DEFAULT '{}',
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_audit_log_created ON audit_log (created_at);
+CREATE INDEX IF NOT EXISTS idx_audit_log_actor ON audit_log (actor, id DESC);
+CREATE INDEX IF NOT EXISTS idx_audit_log_kind ON audit_log (kind, id DESC);
+
+CREATE OR REPLACE FUNCTION audit_log_reject_update() RETURNS TRIGGER AS $$
+BEGIN
+    RAISE EXCEPTION 'audit_log is append-only';
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER audit_log_no_update
+    BEFORE UPDATE ON audit_log
+    FOR EACH ROW EXECUTE FUNCTION audit_log_reject_update();
@@ -6,26 +6,42 @@ use futures::StreamExt;
 use tokio::sync::Mutex;
 use uuid::Uuid;
 
+use crate::agent::citations::Citations;
 use crate::agent::compaction::ContextCompactor;
 use crate::agent::context_monitor::ContextMonitor;
+use crate::agent::episode::EpisodeRecorder;
 use crate::agent::heartbeat::spawn_heartbeat;
 use crate::agent::routine_engine::{RoutineEngine, spawn_cron_ticker};
 use crate::agent::self_repair::{DefaultSelfRepair, RepairResult, SelfRepair};
 use crate::agent::session::{PendingApproval, Session, ThreadState};
 use crate::agent::session_manager::SessionManager;
+use crate::agent::shutdown::ShutdownCoordinator;
 use crate::agent::submission::{Submission, SubmissionParser, SubmissionResult};
-use crate::agent::{HeartbeatConfig as AgentHeartbeatConfig, MessageIntent, Router, Scheduler};
-use crate::channels::{ChannelManager, IncomingMessage, OutgoingResponse, StatusUpdate};
+use crate::agent::template::{JobTemplates, TemplateLauncher};
+use crate::agent::{
+    HeartbeatConfig as AgentHeartbeatConfig, LimitExceeded, MessageIntent, Router, Scheduler,
+    UserLimiter,
+};
+use crate::audit::{AuditEvent, AuditLog};
+use crate::channels::{
+    ChannelManager, IncomingMessage, OutgoingResponse, RoutineHooks, StatusUpdate,
+};
 use crate::config::{AgentConfig, HeartbeatConfig, RoutineConfig};
+use crate::contacts::ContactBook;
 use crate::context::ContextManager;
-use crate::context::JobContext;
+use crate::context::{JobContext, SessionScope};
 use crate::db::Database;
+use crate::db::lease::LeaseManager;
 use crate::error::Error;
 use crate::extensions::ExtensionManager;
-use crate::llm::{ChatMessage, LlmProvider, Reasoning, ReasoningContext, RespondResult};
+use crate::llm::{
+    ChatMessage, LlmProvider, ModelProviders, Reasoning, ReasoningContext, RespondResult,
+};
+use crate::metrics::Metrics;
 use crate::safety::SafetyLayer;
 use crate::tools::ToolRegistry;
-use crate::workspace::Workspace;
+use crate::webhooks::{WebhookEvent, Webhooks};
+use crate::workspace::{Profile, PromptContext, Sensitivity, Workspace};
 
 /// Collapse a tool output string into a single-line preview for display.
 pub(crate) fn truncate_for_preview(output: &str, max_chars: usize) -> String {
@@ -61,6 +77,14 @@ enum AgenticLoopResult {
     },
 }
 
+/// Most LLM calls a chat turn makes before giving up.
+pub(crate) const MAX_TOOL_ITERATIONS: usize = 10;
+
+/// Sent when the model answers in text before using any tool in the first
+/// iterations of a turn.
+pub(crate) const TOOL_NUDGE: &str =
+    "Please proceed and use the available tools to complete this task.";
+
 /// Core dependencies for the agent.
 ///
 /// Bundles the shared components to reduce argument count.
@@ -74,6 +98,17 @@ pub struct AgentDeps {
     pub tools: Arc<ToolRegistry>,
     pub workspace: Option<Arc<Workspace>>,
     pub extension_manager: Option<Arc<ExtensionManager>>,
+    /// Audit log for messages, tool executions, and approval decisions.
+    pub audit: Option<Arc<AuditLog>>,
+    /// Outbound webhooks for job, approval, and budget events.
+    pub webhooks: Option<Arc<Webhooks>>,
+    /// Contact book that message senders are linked into.
+    pub contacts: Option<Arc<ContactBook>>,
+    /// Leases that keep instances sharing a database from firing the same
+    /// heartbeat or cron tick twice.
+    pub leases: Option<Arc<LeaseManager>>,
+    /// Providers for jobs that run on a model other than `llm`'s.
+    pub models: Option<Arc<ModelProviders>>,
 }
 
 /// The main agent that coordinates all components.
@@ -86,8 +121,16 @@ pub struct Agent {
     router: Router,
     session_manager: Arc<SessionManager>,
     context_monitor: ContextMonitor,
+    /// Summarizes finished conversations into the daily log.
+    episodes: Option<Arc<EpisodeRecorder>>,
     heartbeat_config: Option<HeartbeatConfig>,
+    /// Heartbeat interval changes published by config reload.
+    heartbeat_interval_updates: Option<tokio::sync::watch::Receiver<std::time::Duration>>,
+    /// Drains in-flight work when a shutdown signal arrives.
+    shutdown: Arc<ShutdownCoordinator>,
     routine_config: Option<RoutineConfig>,
+    /// Inbound webhook routes that fire routines once the engine starts.
+    routine_hooks: Option<RoutineHooks>,
 }
 
 impl Agent {
@@ -104,19 +147,50 @@ impl Agent {
         context_manager: Option<Arc<ContextManager>>,
         session_manager: Option<Arc<SessionManager>>,
     ) -> Self {
-        let context_manager = context_manager
-            .unwrap_or_else(|| Arc::new(ContextManager::new(config.max_parallel_jobs)));
+        let context_manager = context_manager.unwrap_or_else(|| {
+            let mut manager = ContextManager::new(config.max_parallel_jobs);
+            if config.user_limits.is_enabled() {
+                manager = manager
+                    .with_user_limits(Arc::new(UserLimiter::new(config.user_limits.clone())));
+            }
+            Arc::new(manager)
+        });
 
         let session_manager = session_manager.unwrap_or_else(|| Arc::new(SessionManager::new()));
 
-        let scheduler = Arc::new(Scheduler::new(
+        let mut scheduler = Scheduler::new(
             config.clone(),
             context_manager.clone(),
             deps.llm.clone(),
             deps.safety.clone(),
             deps.tools.clone(),
             deps.store.clone(),
-        ));
+        );
+        if let Some(ref audit) = deps.audit {
+            scheduler = scheduler.with_audit(Arc::clone(audit));
+        }
+        if let Some(ref webhooks) = deps.webhooks {
+            scheduler = scheduler.with_webhooks(Arc::clone(webhooks));
+        }
+        if let Some(ref models) = deps.models {
+            scheduler = scheduler.with_models(Arc::clone(models));
+        }
+        if let Some(ref workspace) = deps.workspace {
+            scheduler = scheduler.with_workspace(Arc::clone(workspace));
+        }
+
+        let episodes = match deps.workspace {
+            Some(ref workspace) if config.episode_summaries => {
+                let llm = deps.cheap_llm.clone().unwrap_or_else(|| deps.llm.clone());
+                Some(Arc::new(EpisodeRecorder::new(llm, Arc::clone(workspace))))
+            }
+            _ => None,
+        };
+        if let Some(ref episodes) = episodes {
+            scheduler = scheduler.with_episodes(Arc::clone(episodes));
+        }
+        let scheduler = Arc::new(scheduler);
+        let shutdown = Arc::new(ShutdownCoordinator::new(config.shutdown_drain_timeout));
 
         Self {
             config,
@@ -127,12 +201,50 @@ impl Agent {
             router: Router::new(),
             session_manager,
             context_monitor: ContextMonitor::new(),
+            episodes,
             heartbeat_config,
+            heartbeat_interval_updates: None,
+            shutdown,
             routine_config,
+            routine_hooks: None,
         }
     }
 
+    /// Let inbound webhooks served by `hooks` fire routines.
+    pub fn with_routine_hooks(mut self, hooks: RoutineHooks) -> Self {
+        self.routine_hooks = Some(hooks);
+        self
+    }
+
+    /// Let the heartbeat follow interval changes published on `rx`.
+    pub fn with_heartbeat_interval_updates(
+        mut self,
+        rx: tokio::sync::watch::Receiver<std::time::Duration>,
+    ) -> Self {
+        self.heartbeat_interval_updates = Some(rx);
+        self
+    }
+
+    /// Use a shared shutdown coordinator, so components created outside
+    /// the agent can register work to flush on shutdown.
+    pub fn with_shutdown(mut self, shutdown: Arc<ShutdownCoordinator>) -> Self {
+        self.shutdown = shutdown;
+        self
+    }
+
     // Convenience accessors
+    fn audit(&self) -> Option<&Arc<AuditLog>> {
+        self.deps.audit.as_ref()
+    }
+
+    fn webhooks(&self) -> Option<&Arc<Webhooks>> {
+        self.deps.webhooks.as_ref()
+    }
+
+    fn contacts(&self) -> Option<&Arc<ContactBook>> {
+        self.deps.contacts.as_ref()
+    }
+
     fn store(&self) -> Option<&Arc<dyn Database>> {
         self.deps.store.as_ref()
     }
@@ -158,6 +270,38 @@ impl Agent {
         self.deps.workspace.as_ref()
     }
 
+    /// Job context for tool calls in a chat thread: who is asking, from
+    /// which channel, whether others can see the chat, and the user's
+    /// timezone from USER.md. Shares the thread's scratch buffer so stashed
+    /// values outlive a single turn.
+    async fn chat_job_context(
+        &self,
+        message: &IncomingMessage,
+        session: &Arc<Mutex<Session>>,
+        thread_id: Uuid,
+    ) -> JobContext {
+        let mut job_ctx =
+            JobContext::with_user(&message.user_id, "chat", "Interactive chat session")
+                .with_agent(self.config.agent_id)
+                .with_channel(
+                    &message.channel,
+                    SessionScope::from_metadata(&message.metadata),
+                );
+        if let Some(ref locale) = self.config.locale {
+            job_ctx = job_ctx.with_locale(locale);
+        }
+        if let Some(workspace) = self.workspace()
+            && let Ok(mut profile) = workspace.profile(Profile::User).await
+            && let Some(timezone) = profile.remove("timezone")
+        {
+            job_ctx = job_ctx.with_timezone(timezone);
+        }
+        if let Some(thread) = session.lock().await.threads.get(&thread_id) {
+            job_ctx.scratch = thread.scratch.clone();
+        }
+        job_ctx
+    }
+
     /// Run the agent main loop.
     pub async fn run(self) -> Result<(), Error> {
         // Start channels
@@ -248,12 +392,39 @@ impl Agent {
         // Spawn session pruning task
         let session_mgr = self.session_manager.clone();
         let session_idle_timeout = self.config.session_idle_timeout;
+        let episodes = self.episodes.clone();
+        let retention = self
+            .config
+            .session_retention
+            .and_then(|r| self.store().map(|store| (Arc::clone(store), r)));
         let pruning_handle = tokio::spawn(async move {
             let mut interval = tokio::time::interval(std::time::Duration::from_secs(600)); // Every 10 min
             interval.tick().await; // Skip immediate first tick
             loop {
                 interval.tick().await;
-                session_mgr.prune_stale_sessions(session_idle_timeout).await;
+                // Stored conversations past retention are deleted for good
+                if let Some((ref store, retention)) = retention {
+                    let cutoff = chrono::Duration::from_std(retention)
+                        .ok()
+                        .and_then(|r| chrono::Utc::now().checked_sub_signed(r))
+                        .unwrap_or(chrono::DateTime::UNIX_EPOCH);
+                    match store.prune_conversations(cutoff).await {
+                        Ok(0) => {}
+                        Ok(n) => tracing::info!("Deleted {} expired conversations", n),
+                        Err(e) => tracing::warn!("Failed to prune conversations: {}", e),
+                    }
+                }
+                let pruned = session_mgr.take_stale_sessions(session_idle_timeout).await;
+                // Idle sessions end their conversations
+                if let Some(ref episodes) = episodes {
+                    for session in pruned {
+                        let sess = session.lock().await;
+                        for thread in sess.threads.values() {
+                            episodes.record_thread(&sess.user_id, thread);
+                            episodes.forget(thread.id);
+                        }
+                    }
+                }
             }
         });
 
@@ -306,11 +477,22 @@ impl Agent {
                         "Heartbeat enabled with {}s interval",
                         hb_config.interval_secs
                     );
+                    let prechecks = crate::agent::PreChecks::from_config(&hb_config.prechecks);
+                    if let Some(ref prechecks) = prechecks {
+                        tracing::info!(
+                            "Heartbeat pre-checks enabled with {} probe(s)",
+                            prechecks.len()
+                        );
+                    }
                     Some(spawn_heartbeat(
                         config,
                         workspace.clone(),
                         self.cheap_llm().clone(),
                         Some(notify_tx),
+                        self.heartbeat_interval_updates.clone(),
+                        self.audit().cloned(),
+                        self.deps.leases.clone(),
+                        prechecks,
                     ))
                 } else {
                     tracing::warn!("Heartbeat enabled but no workspace available");
@@ -323,6 +505,20 @@ impl Agent {
             None
         };
 
+        // Job templates, launched by the run_template tool and by routines
+        let templates = self.workspace().map(|workspace| {
+            let launcher = Arc::new(TemplateLauncher::new(
+                JobTemplates::new(Arc::clone(workspace)),
+                Arc::clone(&self.context_manager),
+                Arc::clone(&self.scheduler),
+                self.store().cloned(),
+            ));
+            self.deps
+                .tools
+                .register_template_tool(Arc::clone(&launcher));
+            launcher
+        });
+
         // Spawn routine engine if enabled
         let routine_handle = if let Some(ref rt_config) = self.routine_config {
             if rt_config.enabled {
@@ -331,13 +527,24 @@ impl Agent {
                     let (notify_tx, mut notify_rx) =
                         tokio::sync::mpsc::channel::<OutgoingResponse>(32);
 
-                    let engine = Arc::new(RoutineEngine::new(
+                    let mut engine = RoutineEngine::new(
                         rt_config.clone(),
                         Arc::clone(store),
                         self.llm().clone(),
+                        Arc::clone(self.safety()),
                         Arc::clone(workspace),
                         notify_tx,
-                    ));
+                    );
+                    if let Some(ref leases) = self.deps.leases {
+                        engine = engine.with_leases(Arc::clone(leases));
+                    }
+                    if let Some(ref templates) = templates {
+                        engine = engine.with_templates(Arc::clone(templates));
+                    }
+                    let engine = Arc::new(engine);
+                    if let Some(ref hooks) = self.routine_hooks {
+                        hooks.set_engine(Arc::clone(&engine)).await;
+                    }
 
                     // Register routine tools
                     self.deps
@@ -402,16 +609,15 @@ impl Agent {
         // Extract engine ref for use in message loop
         let routine_engine_for_loop = routine_handle.as_ref().map(|(_, e)| Arc::clone(e));
 
+        let signal_handle = self.shutdown.listen_for_signals();
+
         // Main message loop
         tracing::info!("Agent {} ready and listening", self.config.name);
 
         loop {
             let message = tokio::select! {
                 biased;
-                _ = tokio::signal::ctrl_c() => {
-                    tracing::info!("Ctrl+C received, shutting down...");
-                    break;
-                }
+                _ = self.shutdown.draining() => break,
                 msg = message_stream.next() => {
                     match msg {
                         Some(m) => m,
@@ -423,7 +629,17 @@ impl Agent {
                 }
             };
 
-            match self.handle_message(&message).await {
+            // A turn already under way may finish its LLM and tool calls
+            // during shutdown, up to the drain deadline.
+            let result = tokio::select! {
+                result = self.handle_message(&message) => result,
+                _ = self.shutdown.deadline_elapsed() => {
+                    tracing::warn!("Shutdown deadline passed mid-turn, abandoning it");
+                    break;
+                }
+            };
+
+            match result {
                 Ok(Some(response)) if !response.is_empty() => {
                     let _ = self
                         .channels
@@ -442,7 +658,7 @@ impl Agent {
                     tracing::error!("Error handling message: {}", e);
                     let _ = self
                         .channels
-                        .respond(&message, OutgoingResponse::text(format!("Error: {}", e)))
+                        .respond(&message, OutgoingResponse::text(e.user_message()))
                         .await;
                 }
             }
@@ -456,8 +672,13 @@ impl Agent {
             }
         }
 
-        // Cleanup
+        // Drain: refuse new work, give running jobs until the deadline,
+        // checkpoint the rest, flush queues, then close channels.
         tracing::info!("Agent shutting down...");
+        if !self.shutdown.is_draining() {
+            self.shutdown.trigger();
+        }
+        self.context_manager.stop_accepting();
         repair_handle.abort();
         pruning_handle.abort();
         if let Some(handle) = heartbeat_handle {
@@ -466,20 +687,48 @@ impl Agent {
         if let Some((cron_handle, _)) = routine_handle {
             cron_handle.abort();
         }
+
+        let running = self.scheduler.running_count().await;
+        if running > 0 {
+            tracing::info!(
+                "Waiting up to {}s for {} running job(s) to finish",
+                self.shutdown.drain_timeout().as_secs(),
+                running
+            );
+        }
+        let drained = tokio::select! {
+            drained = self.scheduler.drain(
+                self.shutdown.deadline().unwrap_or_else(tokio::time::Instant::now),
+            ) => drained,
+            _ = self.shutdown.deadline_elapsed() => false,
+        };
+        if !drained {
+            let checkpointed = self
+                .scheduler
+                .checkpoint_all("Interrupted by shutdown")
+                .await;
+            tracing::info!("Checkpointed {} unfinished job(s)", checkpointed.len());
+        }
+
+        self.shutdown.run_hooks().await;
         self.scheduler.stop_all().await;
         self.channels.shutdown_all().await?;
+        signal_handle.abort();
 
         Ok(())
     }
 
+    #[tracing::instrument(
+        name = "agent.turn",
+        skip_all,
+        fields(user_id = %message.user_id, channel = %message.channel)
+    )]
     async fn handle_message(&self, message: &IncomingMessage) -> Result<Option<String>, Error> {
         // Parse submission type first
         let submission = SubmissionParser::parse(&message.content);
 
         // Hydrate thread from DB if it's a historical thread not in memory
-        if let Some(ref external_thread_id) = message.thread_id {
-            self.maybe_hydrate_thread(message, external_thread_id).await;
-        }
+        self.maybe_hydrate_thread(message).await;
 
         // Resolve session and thread
         let (session, thread_id) = self
@@ -526,6 +775,45 @@ impl Agent {
             message.content.len()
         );
 
+        if let Some(contacts) = self.contacts() {
+            let contacts = Arc::clone(contacts);
+            let (channel, sender, name) = (
+                message.channel.clone(),
+                message.user_id.clone(),
+                message.user_name.clone(),
+            );
+            tokio::spawn(async move {
+                if let Err(e) = contacts.observe(&channel, &sender, name.as_deref()).await {
+                    tracing::warn!("Failed to link sender {} on {}: {}", sender, channel, e);
+                }
+            });
+        }
+
+        if let Some(audit) = self.audit() {
+            audit
+                .record(AuditEvent::message_in(
+                    &message.user_id,
+                    &message.channel,
+                    &message.content,
+                ))
+                .await;
+        }
+
+        // Per-user limits apply to new input only; control commands like
+        // /interrupt must keep working for a user who is over the limit.
+        if matches!(submission, Submission::UserInput { .. })
+            && let Some(limits) = self.context_manager.user_limits()
+            && let Err(exceeded) = limits.check_message(&message.user_id)
+        {
+            tracing::info!("User {} over limit: {:?}", message.user_id, exceeded);
+            if let LimitExceeded::Tokens { budget } = exceeded
+                && let Some(webhooks) = self.webhooks()
+            {
+                webhooks.notify(WebhookEvent::budget_exceeded(&message.user_id, budget));
+            }
+            return Ok(Some(exceeded.to_string()));
+        }
+
         // Process based on submission type
         let result = match submission {
             Submission::UserInput { content } => {
@@ -548,6 +836,17 @@ impl Agent {
             Submission::SwitchThread { thread_id: target } => {
                 self.process_switch_thread(message, target).await
             }
+            Submission::SwitchThreadNumber { number } => {
+                let (threads, _) = self.channel_threads(message).await;
+                match threads.get(number - 1) {
+                    Some(&target) => self.process_switch_thread(message, target).await,
+                    None => Ok(SubmissionResult::error(format!(
+                        "No thread {}; /threads lists them.",
+                        number
+                    ))),
+                }
+            }
+            Submission::ListThreads => self.process_list_threads(message).await,
             Submission::Resume { checkpoint_id } => {
                 self.process_resume(session, thread_id, checkpoint_id).await
             }
@@ -584,6 +883,17 @@ impl Agent {
                 description,
                 parameters,
             } => {
+                if let Some(webhooks) = self.webhooks() {
+                    webhooks.notify(WebhookEvent::approval_requested(
+                        &message.user_id,
+                        &message.channel,
+                        request_id,
+                        &tool_name,
+                        &description,
+                        &parameters,
+                    ));
+                }
+
                 // Each channel renders the approval prompt via send_status.
                 // Web gateway shows an inline card, REPL prints a formatted prompt, etc.
                 let _ = self
@@ -615,13 +925,63 @@ impl Agent {
     /// even when the conversation has zero messages (e.g. a brand-new
     /// assistant thread). Without this, `resolve_thread` would mint a
     /// fresh UUID and all messages would land in the wrong conversation.
-    async fn maybe_hydrate_thread(&self, message: &IncomingMessage, external_thread_id: &str) {
-        // Only hydrate UUID-shaped thread IDs (web gateway uses UUIDs)
-        let thread_uuid = match Uuid::parse_str(external_thread_id) {
-            Ok(id) => id,
-            Err(_) => return,
+    ///
+    /// Other channels name threads their own way (a chat ID, or nothing at
+    /// all for the REPL); for those the latest stored conversation for
+    /// (user, channel, thread) is resumed, so a restart doesn't lose the
+    /// discussion.
+    async fn maybe_hydrate_thread(&self, message: &IncomingMessage) {
+        let external_thread_id = message.thread_id.as_deref();
+        // UUID-shaped thread IDs (web gateway) name the conversation directly
+        if let Some(thread_uuid) = external_thread_id.and_then(|id| Uuid::parse_str(id).ok()) {
+            if let Some(session) = self.hydrate_thread(message, thread_uuid).await {
+                self.session_manager
+                    .register_thread(&message.user_id, &message.channel, thread_uuid, session)
+                    .await;
+            }
+            return;
+        }
+
+        if self
+            .session_manager
+            .has_thread(&message.user_id, &message.channel, external_thread_id)
+            .await
+        {
+            return;
+        }
+        let Some(store) = self.store() else {
+            return;
         };
+        match store
+            .find_conversation(&message.user_id, &message.channel, external_thread_id)
+            .await
+        {
+            Ok(Some(thread_uuid)) => {
+                if let Some(session) = self.hydrate_thread(message, thread_uuid).await {
+                    self.session_manager
+                        .register_thread_as(
+                            &message.user_id,
+                            &message.channel,
+                            external_thread_id,
+                            thread_uuid,
+                            session,
+                        )
+                        .await;
+                }
+            }
+            Ok(None) => {}
+            Err(e) => tracing::warn!("Failed to look up stored conversation: {}", e),
+        }
+    }
 
+    /// Load conversation `thread_uuid` into the user's session, returning
+    /// the session for the caller to register the thread with, or `None`
+    /// if it was already loaded.
+    async fn hydrate_thread(
+        &self,
+        message: &IncomingMessage,
+        thread_uuid: Uuid,
+    ) -> Option<Arc<Mutex<Session>>> {
         // Check if already in memory
         let session = self
             .session_manager
@@ -630,7 +990,7 @@ impl Agent {
         {
             let sess = session.lock().await;
             if sess.threads.contains_key(&thread_uuid) {
-                return;
+                return None;
             }
         }
 
@@ -689,20 +1049,12 @@ impl Agent {
             sess.last_active_at = chrono::Utc::now();
         }
 
-        self.session_manager
-            .register_thread(
-                &message.user_id,
-                &message.channel,
-                thread_uuid,
-                Arc::clone(&session),
-            )
-            .await;
-
         tracing::debug!(
             "Hydrated thread {} from DB ({} messages)",
             thread_uuid,
             msg_count
         );
+        Some(session)
     }
 
     async fn process_user_input(
@@ -836,7 +1188,7 @@ impl Agent {
         }
 
         // Start the turn and get messages
-        let turn_messages = {
+        let mut turn_messages = {
             let mut sess = session.lock().await;
             let thread = sess
                 .threads
@@ -845,6 +1197,12 @@ impl Agent {
             thread.start_turn(content);
             thread.messages()
         };
+        // Attach images (or a text fallback when the model has no vision)
+        crate::llm::vision::attach_to_last_user(
+            &mut turn_messages,
+            &message.attachments,
+            self.llm().supports_vision(),
+        );
 
         // Send thinking status
         let _ = self
@@ -895,7 +1253,7 @@ impl Agent {
                     .await;
 
                 // Fire-and-forget: persist turn to DB
-                self.persist_turn(thread_id, &message.user_id, content, Some(&response));
+                self.persist_turn(thread_id, message, content, Some(&response));
 
                 Ok(SubmissionResult::response(response))
             }
@@ -925,18 +1283,21 @@ impl Agent {
                 thread.fail_turn(e.to_string());
 
                 // Persist the user message even on failure
-                self.persist_turn(thread_id, &message.user_id, content, None);
+                self.persist_turn(thread_id, message, content, None);
 
-                Ok(SubmissionResult::error(e.to_string()))
+                Ok(SubmissionResult::response(e.user_message()))
             }
         }
     }
 
     /// Fire-and-forget: persist a turn (user message + optional assistant response) to the DB.
+    ///
+    /// The conversation is keyed by the message's channel and thread so
+    /// `maybe_hydrate_thread` can pick it up again after a restart.
     fn persist_turn(
         &self,
         thread_id: Uuid,
-        user_id: &str,
+        message: &IncomingMessage,
         user_input: &str,
         response: Option<&str>,
     ) {
@@ -945,13 +1306,15 @@ impl Agent {
             None => return,
         };
 
-        let user_id = user_id.to_string();
+        let user_id = message.user_id.clone();
+        let channel = message.channel.clone();
+        let external_thread_id = message.thread_id.clone();
         let user_input = user_input.to_string();
         let response = response.map(String::from);
 
         tokio::spawn(async move {
             if let Err(e) = store
-                .ensure_conversation(thread_id, "gateway", &user_id, None)
+                .ensure_conversation(thread_id, &channel, &user_id, external_thread_id.as_deref())
                 .await
             {
                 tracing::warn!("Failed to ensure conversation {}: {}", thread_id, e);
@@ -1029,7 +1392,9 @@ impl Agent {
     ) -> Result<AgenticLoopResult, Error> {
         // Load workspace system prompt (identity files: AGENTS.md, SOUL.md, etc.)
         let system_prompt = if let Some(ws) = self.workspace() {
-            match ws.system_prompt().await {
+            let ctx = PromptContext::chat(&message.channel, &message.content)
+                .with_scope(SessionScope::from_metadata(&message.metadata));
+            match ws.system_prompt_with(&message.user_id, &ctx).await {
                 Ok(prompt) if !prompt.is_empty() => Some(prompt),
                 Ok(_) => None,
                 Err(e) => {
@@ -1050,11 +1415,11 @@ impl Agent {
         let mut context_messages = initial_messages;
 
         // Create a JobContext for tool execution (chat doesn't have a real job)
-        let job_ctx = JobContext::with_user(&message.user_id, "chat", "Interactive chat session");
+        let job_ctx = self.chat_job_context(message, &session, thread_id).await;
 
-        const MAX_TOOL_ITERATIONS: usize = 10;
         let mut iteration = 0;
         let mut tools_executed = resume_after_tool;
+        let mut citations = Citations::default();
 
         loop {
             iteration += 1;
@@ -1081,7 +1446,10 @@ impl Agent {
             }
 
             // Refresh tool definitions each iteration so newly built tools become visible
-            let tool_defs = self.tools().tool_definitions().await;
+            let tool_defs = self
+                .tools()
+                .tool_definitions_for_user(&message.user_id)
+                .await;
 
             // Call LLM with current context
             let context = ReasoningContext::new()
@@ -1101,6 +1469,9 @@ impl Agent {
                 output.usage.input_tokens,
                 output.usage.output_tokens
             );
+            if let Some(limits) = self.context_manager.user_limits() {
+                limits.record_tokens(&message.user_id, u64::from(output.usage.total()));
+            }
 
             match output.result {
                 RespondResult::Text(text) => {
@@ -1113,14 +1484,14 @@ impl Agent {
                             iteration
                         );
                         context_messages.push(ChatMessage::assistant(&text));
-                        context_messages.push(ChatMessage::user(
-                            "Please proceed and use the available tools to complete this task.",
-                        ));
+                        context_messages.push(ChatMessage::user(TOOL_NUDGE));
                         continue;
                     }
 
                     // Tools have been executed or we've tried multiple times, return response
-                    return Ok(AgenticLoopResult::Response(text));
+                    return Ok(AgenticLoopResult::Response(
+                        citations.render(&text, self.config.citation_style),
+                    ));
                 }
                 RespondResult::ToolCalls {
                     tool_calls,
@@ -1162,9 +1533,47 @@ impl Agent {
 
                     // Execute each tool (with approval checking)
                     for tc in tool_calls {
+                        // Calls the user isn't permitted to make skip the
+                        // approval prompts and are rejected at dispatch.
+                        let permitted = self.tools().is_allowed(&message.user_id, &tc.name);
+
+                        // External actions carrying the user's private data
+                        // always need approval, even if auto-approved.
+                        if permitted
+                            && let Some(tool) = self.tools().get(&tc.name).await
+                            && tool.is_external_action()
+                            && let egress_findings = self.check_egress(&tc.arguments).await
+                            && !egress_findings.is_empty()
+                        {
+                            tracing::info!(
+                                "Tool {} arguments may contain private data, requiring approval",
+                                tc.name
+                            );
+                            let reasons = egress_findings
+                                .iter()
+                                .map(|f| f.to_string())
+                                .collect::<Vec<_>>()
+                                .join(", ");
+                            let pending = PendingApproval {
+                                request_id: Uuid::new_v4(),
+                                tool_name: tc.name.clone(),
+                                parameters: tc.arguments.clone(),
+                                description: format!(
+                                    "{}\n\nThis may send private data: {}",
+                                    tool.description(),
+                                    reasons
+                                ),
+                                tool_call_id: tc.id.clone(),
+                                context_messages: context_messages.clone(),
+                            };
+
+                            return Ok(AgenticLoopResult::NeedApproval { pending });
+                        }
+
                         // Check if tool requires approval
-                        if let Some(tool) = self.tools().get(&tc.name).await
-                            && tool.requires_approval()
+                        if permitted
+                            && let Some(tool) = self.tools().get(&tc.name).await
+                            && tool.requires_approval_for(&tc.arguments)
                         {
                             // Check if auto-approved for this session
                             let mut is_auto_approved = {
@@ -1308,6 +1717,7 @@ impl Agent {
                         // Add tool result to context for next LLM call
                         let result_content = match tool_result {
                             Ok(output) => {
+                                citations.collect(&tc.name, &output);
                                 // Sanitize output before showing to LLM
                                 let sanitized =
                                     self.safety().sanitize_tool_output(&tc.name, &output);
@@ -1331,7 +1741,29 @@ impl Agent {
         }
     }
 
+    /// Check an external action's arguments against the user's internal and
+    /// private documents. Returns what looked like data from them.
+    async fn check_egress(
+        &self,
+        arguments: &serde_json::Value,
+    ) -> Vec<crate::safety::EgressFinding> {
+        let context = self.egress_context(Sensitivity::Internal).await;
+        let context: Vec<&str> = context.iter().map(String::as_str).collect();
+        let payload = crate::safety::egress_payload_text(arguments);
+        crate::safety::check_egress(&payload, &context)
+    }
+
+    /// Contents of the documents at least as sensitive as `min`.
+    async fn egress_context(&self, min: Sensitivity) -> Vec<String> {
+        egress_context(self.workspace().map(|w| w.as_ref()), min).await
+    }
+
     /// Execute a tool for chat (without full job context).
+    #[tracing::instrument(
+        name = "tool.execute",
+        skip_all,
+        fields(tool = %tool_name, user_id = %job_ctx.user_id)
+    )]
     async fn execute_chat_tool(
         &self,
         tool_name: &str,
@@ -1346,6 +1778,33 @@ impl Agent {
                     name: tool_name.to_string(),
                 })?;
 
+        if !self.tools().is_allowed(&job_ctx.user_id, tool_name) {
+            return Err(crate::error::ToolError::PermissionDenied {
+                name: tool_name.to_string(),
+                user_id: job_ctx.user_id.clone(),
+            }
+            .into());
+        }
+
+        // Private documents never leave a shared conversation, approved or not.
+        if job_ctx.is_shared() && tool.is_external_action() {
+            let private = self.egress_context(Sensitivity::Private).await;
+            let private: Vec<&str> = private.iter().map(String::as_str).collect();
+            let payload = crate::safety::egress_payload_text(params);
+            let findings = crate::safety::check_context_egress(&payload, &private);
+            if !findings.is_empty() {
+                return Err(crate::error::ToolError::PrivateEgress {
+                    name: tool_name.to_string(),
+                    reasons: findings
+                        .iter()
+                        .map(|f| f.to_string())
+                        .collect::<Vec<_>>()
+                        .join(", "),
+                }
+                .into());
+            }
+        }
+
         // Validate tool parameters
         let validation = self.safety().validator().validate_tool_params(params);
         if !validation.is_valid {
@@ -1376,6 +1835,24 @@ impl Agent {
         })
         .await;
         let elapsed = start.elapsed();
+        Metrics::global().record_tool(tool_name, elapsed, matches!(result, Ok(Ok(_))));
+
+        if let Some(audit) = self.audit() {
+            let error = match &result {
+                Ok(Ok(_)) => None,
+                Ok(Err(e)) => Some(e.to_string()),
+                Err(_) => Some("timed out".to_string()),
+            };
+            audit
+                .record(AuditEvent::tool_execution(
+                    &job_ctx.user_id,
+                    tool_name,
+                    params,
+                    error.as_deref(),
+                    elapsed,
+                ))
+                .await;
+        }
 
         match &result {
             Ok(Ok(output)) => {
@@ -1607,10 +2084,15 @@ impl Agent {
         thread_id: Uuid,
     ) -> Result<SubmissionResult, Error> {
         let mut sess = session.lock().await;
+        let user_id = sess.user_id.clone();
         let thread = sess
             .threads
             .get_mut(&thread_id)
             .ok_or_else(|| Error::from(crate::error::JobError::NotFound { id: thread_id }))?;
+        if let Some(ref episodes) = self.episodes {
+            episodes.record_thread(&user_id, thread);
+            episodes.forget(thread_id);
+        }
         thread.turns.clear();
         thread.state = ThreadState::Idle;
 
@@ -1666,6 +2148,18 @@ impl Agent {
             ));
         }
 
+        if let Some(audit) = self.audit() {
+            audit
+                .record(AuditEvent::approval(
+                    &message.user_id,
+                    &message.channel,
+                    &pending.tool_name,
+                    approved,
+                    always,
+                ))
+                .await;
+        }
+
         if approved {
             // If always, add to auto-approved set
             if always {
@@ -1687,8 +2181,7 @@ impl Agent {
             }
 
             // Execute the approved tool and continue the loop
-            let job_ctx =
-                JobContext::with_user(&message.user_id, "chat", "Interactive chat session");
+            let job_ctx = self.chat_job_context(message, &session, thread_id).await;
 
             let _ = self
                 .channels
@@ -1854,7 +2347,7 @@ impl Agent {
                 }
                 Err(e) => {
                     thread.fail_turn(e.to_string());
-                    Ok(SubmissionResult::error(e.to_string()))
+                    Ok(SubmissionResult::response(e.user_message()))
                 }
             }
         } else {
@@ -2021,6 +2514,27 @@ impl Agent {
         }
     }
 
+    /// Whether the client names threads by UUID itself (web gateway), in
+    /// which case `/new` and `/thread` don't reroute its messages.
+    fn client_picks_thread(message: &IncomingMessage) -> bool {
+        message
+            .thread_id
+            .as_deref()
+            .is_some_and(|id| Uuid::parse_str(id).is_ok())
+    }
+
+    /// Threads of the channel thread `message` arrived on, oldest first,
+    /// and the one it currently routes to.
+    async fn channel_threads(&self, message: &IncomingMessage) -> (Vec<Uuid>, Option<Uuid>) {
+        self.session_manager
+            .list_threads(
+                &message.user_id,
+                &message.channel,
+                message.thread_id.as_deref(),
+            )
+            .await
+    }
+
     async fn process_new_thread(
         &self,
         message: &IncomingMessage,
@@ -2029,27 +2543,88 @@ impl Agent {
             .session_manager
             .get_or_create_session(&message.user_id)
             .await;
-        let mut sess = session.lock().await;
-        let thread = sess.create_thread();
-        let thread_id = thread.id;
+        // Starting a new thread ends the current conversation
+        if let Some(ref episodes) = self.episodes {
+            let sess = session.lock().await;
+            if let Some(current) = sess.active_thread.and_then(|id| sess.threads.get(&id)) {
+                episodes.record_thread(&message.user_id, current);
+            }
+        }
+        let thread_id = if Self::client_picks_thread(message) {
+            session.lock().await.create_thread().id
+        } else {
+            self.session_manager
+                .start_thread(
+                    &message.user_id,
+                    &message.channel,
+                    message.thread_id.as_deref(),
+                )
+                .await
+        };
         Ok(SubmissionResult::ok_with_message(format!(
             "New thread: {}",
             thread_id
         )))
     }
 
-    async fn process_switch_thread(
+    async fn process_list_threads(
         &self,
         message: &IncomingMessage,
-        target_thread_id: Uuid,
     ) -> Result<SubmissionResult, Error> {
+        let (threads, current) = self.channel_threads(message).await;
+        if threads.is_empty() {
+            return Ok(SubmissionResult::response("No threads yet."));
+        }
         let session = self
             .session_manager
             .get_or_create_session(&message.user_id)
             .await;
-        let mut sess = session.lock().await;
+        let sess = session.lock().await;
+        let mut out = String::from("Threads (switch with /thread <n>):\n");
+        for (i, id) in threads.iter().enumerate() {
+            let Some(thread) = sess.threads.get(id) else {
+                continue;
+            };
+            let topic = thread
+                .turns
+                .first()
+                .map(|t| truncate_for_preview(&t.user_input, 50))
+                .unwrap_or_else(|| "(empty)".to_string());
+            out.push_str(&format!(
+                "{}{} {} - {} turns, {}\n",
+                if Some(*id) == current { "* " } else { "  " },
+                i + 1,
+                topic,
+                thread.turns.len(),
+                thread.updated_at.format("%Y-%m-%d %H:%M"),
+            ));
+        }
+        Ok(SubmissionResult::response(out.trim_end()))
+    }
 
-        if sess.switch_thread(target_thread_id) {
+    async fn process_switch_thread(
+        &self,
+        message: &IncomingMessage,
+        target_thread_id: Uuid,
+    ) -> Result<SubmissionResult, Error> {
+        let switched = if Self::client_picks_thread(message) {
+            let session = self
+                .session_manager
+                .get_or_create_session(&message.user_id)
+                .await;
+            session.lock().await.switch_thread(target_thread_id)
+        } else {
+            self.session_manager
+                .switch_thread(
+                    &message.user_id,
+                    &message.channel,
+                    message.thread_id.as_deref(),
+                    target_thread_id,
+                )
+                .await
+        };
+
+        if switched {
             Ok(SubmissionResult::ok_with_message(format!(
                 "Switched to thread {}",
                 target_thread_id
@@ -2397,7 +2972,8 @@ impl Agent {
                 "  /clear            Clear current thread\n",
                 "  /interrupt        Stop current operation\n",
                 "  /new              New conversation thread\n",
-                "  /thread <id>      Switch to thread\n",
+                "  /threads          List this chat's threads\n",
+                "  /thread <n|id>    Switch to thread\n",
                 "  /resume <id>      Resume from checkpoint\n",
                 "\n",
                 "Agent:\n",
@@ -2552,6 +3128,21 @@ fn detect_auth_awaiting(
     Some((name, instructions))
 }
 
+/// Contents of the workspace documents at least as sensitive as `min`, for
+/// the egress guard. Empty without a workspace or if they can't be loaded.
+pub(crate) async fn egress_context(workspace: Option<&Workspace>, min: Sensitivity) -> Vec<String> {
+    let Some(workspace) = workspace else {
+        return Vec::new();
+    };
+    match workspace.egress_documents(min).await {
+        Ok(docs) => docs.into_iter().map(|d| d.content).collect(),
+        Err(e) => {
+            tracing::warn!("Could not load documents for the egress guard: {}", e);
+            Vec::new()
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use crate::error::Error;
@@ -2715,4 +3306,61 @@ mod tests {
         // 'h','e','l','l','o',' ','世','界' = 8 chars
         assert_eq!(result, "hello 世界...");
     }
+
+    #[cfg(feature = "libsql")]
+    #[tokio::test]
+    async fn test_conversations_found_by_channel_thread_and_pruned() {
+        use crate::db::libsql_backend::test_db;
+
+        let (db, _dir) = test_db().await;
+
+        let chat = uuid::Uuid::new_v4();
+        let repl = uuid::Uuid::new_v4();
+        db.ensure_conversation(chat, "telegram", "u1", Some("42"))
+            .await
+            .unwrap();
+        db.add_conversation_message(chat, "user", "hello")
+            .await
+            .unwrap();
+        db.ensure_conversation(repl, "repl", "u1", None)
+            .await
+            .unwrap();
+
+        assert_eq!(
+            db.find_conversation("u1", "telegram", Some("42"))
+                .await
+                .unwrap(),
+            Some(chat)
+        );
+        assert_eq!(
+            db.find_conversation("u1", "repl", None).await.unwrap(),
+            Some(repl)
+        );
+        assert_eq!(
+            db.find_conversation("u1", "telegram", None).await.unwrap(),
+            None
+        );
+        assert_eq!(
+            db.find_conversation("u2", "telegram", Some("42"))
+                .await
+                .unwrap(),
+            None
+        );
+
+        // Nothing is older than an hour ago; everything is older than tomorrow.
+        let hour_ago = chrono::Utc::now() - chrono::TimeDelta::hours(1);
+        assert_eq!(db.prune_conversations(hour_ago).await.unwrap(), 0);
+        let tomorrow = chrono::Utc::now() + chrono::TimeDelta::days(1);
+        assert_eq!(db.prune_conversations(tomorrow).await.unwrap(), 2);
+        assert!(
+            db.list_conversation_messages(chat)
+                .await
+                .unwrap()
+                .is_empty()
+        );
+        assert_eq!(
+            db.find_conversation("u1", "repl", None).await.unwrap(),
+            None
+        );
+    }
 }
@@ -0,0 +1,331 @@
+//! In-process message bus between agents.
+//!
+//! Agents register an endpoint under their name; any agent can then send a
+//! task to another by name and await its reply. Every message is recorded as
+//! a [`Dispatch`] so the sender can see what is pending, running, or done.
+//!
+//! Messages carry a delegation depth. A worker that forwards a task sends it
+//! one level deeper, and the bus refuses messages past its maximum depth so
+//! two agents can't bounce a task back and forth forever.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::Serialize;
+use tokio::sync::RwLock;
+use uuid::Uuid;
+
+use crate::agent::supervisor::SpendMeter;
+
+/// Default maximum delegation depth.
+pub const DEFAULT_MAX_DEPTH: u32 = 2;
+
+/// Errors from sending a message on the bus.
+#[derive(Debug, Clone, thiserror::Error)]
+pub enum BusError {
+    #[error("No agent named '{0}' on the bus")]
+    UnknownAgent(String),
+
+    #[error("Delegation depth {depth} exceeds the maximum of {max}")]
+    DepthExceeded { depth: u32, max: u32 },
+
+    #[error("Agent '{agent}' timed out after {timeout:?}")]
+    Timeout { agent: String, timeout: Duration },
+
+    #[error("Agent '{agent}' failed: {reason}")]
+    Failed { agent: String, reason: String },
+}
+
+/// A task sent from one agent to another.
+#[derive(Debug, Clone, Serialize)]
+pub struct AgentMessage {
+    pub id: Uuid,
+    pub from: String,
+    pub to: String,
+    pub task: String,
+    /// 1 for a task sent by a top-level agent, +1 per forward.
+    pub depth: u32,
+    /// Budget of the request this task is part of, charged by the endpoint
+    /// as it spends.
+    #[serde(skip)]
+    pub spend: Option<Arc<SpendMeter>>,
+}
+
+impl AgentMessage {
+    pub fn new(from: impl Into<String>, to: impl Into<String>, task: impl Into<String>) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            from: from.into(),
+            to: to.into(),
+            task: task.into(),
+            depth: 1,
+            spend: None,
+        }
+    }
+
+    /// Forward as part of handling `parent`, one level deeper and on the
+    /// same budget.
+    pub fn within(mut self, parent: &AgentMessage) -> Self {
+        self.depth = parent.depth + 1;
+        self.spend = parent.spend.clone();
+        self
+    }
+
+    /// Charge the endpoint's spend to `spend`.
+    pub fn with_spend(mut self, spend: Arc<SpendMeter>) -> Self {
+        self.spend = Some(spend);
+        self
+    }
+}
+
+/// An endpoint's answer to a message.
+#[derive(Debug, Clone, Serialize)]
+pub struct AgentReply {
+    pub content: String,
+    /// LLM cost incurred while handling the message.
+    pub cost: Decimal,
+}
+
+/// Something that handles messages addressed to an agent.
+#[async_trait]
+pub trait AgentEndpoint: Send + Sync {
+    /// One-line description shown to agents choosing whom to message.
+    fn description(&self) -> &str;
+
+    async fn handle(&self, message: &AgentMessage) -> Result<AgentReply, String>;
+}
+
+/// Progress of a dispatched message.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DispatchStatus {
+    Running,
+    Completed,
+    Failed,
+}
+
+/// A message and what became of it.
+#[derive(Debug, Clone, Serialize)]
+pub struct Dispatch {
+    pub message: AgentMessage,
+    pub status: DispatchStatus,
+    pub reply: Option<AgentReply>,
+    pub error: Option<String>,
+    pub started_at: DateTime<Utc>,
+    pub finished_at: Option<DateTime<Utc>>,
+}
+
+/// Routes messages to registered agent endpoints.
+pub struct AgentBus {
+    endpoints: RwLock<HashMap<String, Arc<dyn AgentEndpoint>>>,
+    dispatches: RwLock<HashMap<Uuid, Dispatch>>,
+    max_depth: u32,
+    timeout: Duration,
+}
+
+impl AgentBus {
+    pub fn new() -> Self {
+        Self {
+            endpoints: RwLock::new(HashMap::new()),
+            dispatches: RwLock::new(HashMap::new()),
+            max_depth: DEFAULT_MAX_DEPTH,
+            timeout: Duration::from_secs(300),
+        }
+    }
+
+    pub fn with_max_depth(mut self, max_depth: u32) -> Self {
+        self.max_depth = max_depth;
+        self
+    }
+
+    /// How long an endpoint may take to answer one message.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Register (or replace) the endpoint for `name`.
+    pub async fn register(&self, name: impl Into<String>, endpoint: Arc<dyn AgentEndpoint>) {
+        self.endpoints.write().await.insert(name.into(), endpoint);
+    }
+
+    pub async fn unregister(&self, name: &str) -> bool {
+        self.endpoints.write().await.remove(name).is_some()
+    }
+
+    /// Registered agent names and descriptions, sorted by name.
+    pub async fn agents(&self) -> Vec<(String, String)> {
+        let mut agents: Vec<(String, String)> = self
+            .endpoints
+            .read()
+            .await
+            .iter()
+            .map(|(name, ep)| (name.clone(), ep.description().to_string()))
+            .collect();
+        agents.sort();
+        agents
+    }
+
+    /// Deliver `message` and wait for the reply.
+    pub async fn send(&self, message: AgentMessage) -> Result<AgentReply, BusError> {
+        if message.depth > self.max_depth {
+            return Err(BusError::DepthExceeded {
+                depth: message.depth,
+                max: self.max_depth,
+            });
+        }
+        let endpoint = self
+            .endpoints
+            .read()
+            .await
+            .get(&message.to)
+            .cloned()
+            .ok_or_else(|| BusError::UnknownAgent(message.to.clone()))?;
+
+        let id = message.id;
+        let agent = message.to.clone();
+        self.dispatches.write().await.insert(
+            id,
+            Dispatch {
+                message: message.clone(),
+                status: DispatchStatus::Running,
+                reply: None,
+                error: None,
+                started_at: Utc::now(),
+                finished_at: None,
+            },
+        );
+
+        let result = match tokio::time::timeout(self.timeout, endpoint.handle(&message)).await {
+            Ok(Ok(reply)) => Ok(reply),
+            Ok(Err(reason)) => Err(BusError::Failed {
+                agent: agent.clone(),
+                reason,
+            }),
+            Err(_) => Err(BusError::Timeout {
+                agent: agent.clone(),
+                timeout: self.timeout,
+            }),
+        };
+
+        if let Some(dispatch) = self.dispatches.write().await.get_mut(&id) {
+            dispatch.finished_at = Some(Utc::now());
+            match result {
+                Ok(ref reply) => {
+                    dispatch.status = DispatchStatus::Completed;
+                    dispatch.reply = Some(reply.clone());
+                }
+                Err(ref e) => {
+                    dispatch.status = DispatchStatus::Failed;
+                    dispatch.error = Some(e.to_string());
+                }
+            }
+        }
+        result
+    }
+
+    /// A dispatched message by ID.
+    pub async fn dispatch(&self, id: Uuid) -> Option<Dispatch> {
+        self.dispatches.read().await.get(&id).cloned()
+    }
+
+    /// Messages sent by `from`, oldest first.
+    pub async fn dispatches_from(&self, from: &str) -> Vec<Dispatch> {
+        let mut sent: Vec<Dispatch> = self
+            .dispatches
+            .read()
+            .await
+            .values()
+            .filter(|d| d.message.from == from)
+            .cloned()
+            .collect();
+        sent.sort_by_key(|d| d.started_at);
+        sent
+    }
+
+    /// Forget finished dispatches older than `age`.
+    pub async fn prune(&self, age: Duration) {
+        let cutoff = Utc::now() - chrono::Duration::from_std(age).unwrap_or_default();
+        self.dispatches
+            .write()
+            .await
+            .retain(|_, d| d.finished_at.is_none_or(|t| t > cutoff));
+    }
+}
+
+impl Default for AgentBus {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Upper;
+
+    #[async_trait]
+    impl AgentEndpoint for Upper {
+        fn description(&self) -> &str {
+            "Shouts"
+        }
+
+        async fn handle(&self, message: &AgentMessage) -> Result<AgentReply, String> {
+            if message.task.is_empty() {
+                return Err("nothing to shout".to_string());
+            }
+            Ok(AgentReply {
+                content: message.task.to_uppercase(),
+                cost: Decimal::ONE,
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_send_records_dispatch() {
+        let bus = AgentBus::new();
+        bus.register("upper", Arc::new(Upper)).await;
+        assert_eq!(
+            bus.agents().await,
+            vec![("upper".to_string(), "Shouts".to_string())]
+        );
+
+        let message = AgentMessage::new("boss", "upper", "hello");
+        let id = message.id;
+        let reply = bus.send(message).await.unwrap();
+        assert_eq!(reply.content, "HELLO");
+
+        let dispatch = bus.dispatch(id).await.unwrap();
+        assert_eq!(dispatch.status, DispatchStatus::Completed);
+        assert!(dispatch.finished_at.is_some());
+
+        let failed = bus.send(AgentMessage::new("boss", "upper", "")).await;
+        assert!(matches!(failed, Err(BusError::Failed { .. })));
+        let sent = bus.dispatches_from("boss").await;
+        assert_eq!(sent.len(), 2);
+        assert_eq!(sent[1].status, DispatchStatus::Failed);
+    }
+
+    #[tokio::test]
+    async fn test_send_refuses_unknown_agent_and_deep_messages() {
+        let bus = AgentBus::new().with_max_depth(1);
+        bus.register("upper", Arc::new(Upper)).await;
+
+        assert!(matches!(
+            bus.send(AgentMessage::new("boss", "nobody", "hi")).await,
+            Err(BusError::UnknownAgent(_))
+        ));
+
+        let parent = AgentMessage::new("boss", "upper", "hi");
+        let forwarded = AgentMessage::new("upper", "upper", "hi").within(&parent);
+        assert!(matches!(
+            bus.send(forwarded).await,
+            Err(BusError::DepthExceeded { depth: 2, max: 1 })
+        ));
+    }
+}
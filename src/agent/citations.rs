@@ -0,0 +1,283 @@
+//! Memory citations in agent responses.
+//!
+//! `memory_search` gives every result a `source` with a short ref
+//! (`mem:3f2a9c`), and the model is asked to cite what it relies on by
+//! writing the ref in brackets. [`Citations`] collects the sources returned
+//! during a turn and, once the response is final, renders the markers in the
+//! configured [`CitationStyle`]:
+//!
+//! ```text
+//! inline     Rent is due on the 1st (per MEMORY.md › Finances, updated Jan 3).
+//! footnotes  Rent is due on the 1st [1].
+//!
+//!            Sources:
+//!            [1] MEMORY.md › Finances, updated Jan 3 (lines 12-13)
+//! off        Rent is due on the 1st.
+//! ```
+//!
+//! Markers that don't match a result of the turn are dropped, so a made-up
+//! ref never reaches the user.
+
+use std::collections::HashMap;
+use std::sync::LazyLock;
+
+use regex::{Captures, Regex};
+
+/// Tool whose results carry sources.
+const SEARCH_TOOL: &str = "memory_search";
+
+/// A bracketed group of one or more refs, with the whitespace before it.
+static MARKER: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"[ \t]*\[(mem:[0-9a-f]{6}(?:\s*[,;]\s*mem:[0-9a-f]{6})*)\]").expect("valid regex")
+});
+
+static REF: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"mem:[0-9a-f]{6}").expect("valid regex"));
+
+/// How cited memory is shown in responses (`AGENT_CITATIONS`).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum CitationStyle {
+    /// "(per MEMORY.md › Finances, updated Jan 3)" after the statement.
+    #[default]
+    Inline,
+    /// Numbered markers with a list of sources at the end.
+    Footnotes,
+    /// Markers are removed.
+    Off,
+}
+
+impl std::str::FromStr for CitationStyle {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "inline" => Ok(Self::Inline),
+            "footnotes" | "footnote" => Ok(Self::Footnotes),
+            "off" | "none" | "false" => Ok(Self::Off),
+            _ => Err(format!(
+                "invalid citation style '{}', expected one of: inline, footnotes, off",
+                s
+            )),
+        }
+    }
+}
+
+impl std::fmt::Display for CitationStyle {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Inline => write!(f, "inline"),
+            Self::Footnotes => write!(f, "footnotes"),
+            Self::Off => write!(f, "off"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Source {
+    citation: String,
+    lines: Option<(u64, u64)>,
+}
+
+/// Sources returned by memory searches during a turn, by ref.
+#[derive(Debug, Default)]
+pub struct Citations {
+    sources: HashMap<String, Source>,
+}
+
+impl Citations {
+    /// Record the sources in the output of tool `tool_name`.
+    pub fn collect(&mut self, tool_name: &str, output: &str) {
+        if tool_name != SEARCH_TOOL {
+            return;
+        }
+        let Ok(output) = serde_json::from_str::<serde_json::Value>(output) else {
+            return;
+        };
+        let Some(results) = output.get("results").and_then(|r| r.as_array()) else {
+            return;
+        };
+        for source in results.iter().filter_map(|r| r.get("source")) {
+            let (Some(id), Some(citation)) = (
+                source.get("ref").and_then(|v| v.as_str()),
+                source.get("citation").and_then(|v| v.as_str()),
+            ) else {
+                continue;
+            };
+            let lines = source
+                .get("lines")
+                .and_then(|l| Some((l.get(0)?.as_u64()?, l.get(1)?.as_u64()?)));
+            self.sources.insert(
+                id.to_string(),
+                Source {
+                    citation: citation.to_string(),
+                    lines,
+                },
+            );
+        }
+    }
+
+    /// `text` with its citation markers rendered in `style`.
+    pub fn render(&self, text: &str, style: CitationStyle) -> String {
+        // Refs in footnote order.
+        let mut notes: Vec<&String> = Vec::new();
+        let body = MARKER.replace_all(text, |caps: &Captures| {
+            let cited = REF
+                .find_iter(&caps[1])
+                .filter_map(|m| self.sources.get_key_value(m.as_str()));
+            match style {
+                CitationStyle::Off => String::new(),
+                CitationStyle::Inline => {
+                    let mut citations: Vec<&str> = Vec::new();
+                    for (_, source) in cited {
+                        if !citations.contains(&source.citation.as_str()) {
+                            citations.push(&source.citation);
+                        }
+                    }
+                    if citations.is_empty() {
+                        String::new()
+                    } else {
+                        format!(" (per {})", citations.join("; "))
+                    }
+                }
+                CitationStyle::Footnotes => {
+                    let mut numbers: Vec<String> = Vec::new();
+                    for (id, _) in cited {
+                        let number = match notes.iter().position(|n| *n == id) {
+                            Some(i) => i + 1,
+                            None => {
+                                notes.push(id);
+                                notes.len()
+                            }
+                        }
+                        .to_string();
+                        if !numbers.contains(&number) {
+                            numbers.push(number);
+                        }
+                    }
+                    if numbers.is_empty() {
+                        String::new()
+                    } else {
+                        format!(" [{}]", numbers.join(", "))
+                    }
+                }
+            }
+        });
+        let mut out = body.into_owned();
+
+        if !notes.is_empty() {
+            out.push_str("\n\nSources:");
+            for (i, id) in notes.iter().enumerate() {
+                let source = &self.sources[*id];
+                out.push_str(&format!("\n[{}] {}", i + 1, source.citation));
+                match source.lines {
+                    Some((first, last)) if first == last => {
+                        out.push_str(&format!(" (line {})", first))
+                    }
+                    Some((first, last)) => out.push_str(&format!(" (lines {}-{})", first, last)),
+                    None => {}
+                }
+            }
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn citations() -> Citations {
+        let output = serde_json::json!({
+            "query": "rent",
+            "results": [
+                {
+                    "path": "MEMORY.md",
+                    "content": "- Rent is due on the 1st",
+                    "source": {
+                        "ref": "mem:3f2a9c",
+                        "path": "MEMORY.md",
+                        "heading": "Finances",
+                        "lines": [12, 13],
+                        "citation": "MEMORY.md › Finances, updated Jan 3",
+                    },
+                },
+                {
+                    "path": "daily/2025-01-02.md",
+                    "content": "Paid rent",
+                    "source": {
+                        "ref": "mem:0b1c2d",
+                        "path": "daily/2025-01-02.md",
+                        "citation": "daily/2025-01-02.md, updated Jan 2",
+                    },
+                },
+            ],
+        });
+        let mut citations = Citations::default();
+        citations.collect("memory_search", &output.to_string());
+        citations
+    }
+
+    #[test]
+    fn test_style_parsing() {
+        assert_eq!("Inline".parse::<CitationStyle>(), Ok(CitationStyle::Inline));
+        assert_eq!(
+            "footnotes".parse::<CitationStyle>(),
+            Ok(CitationStyle::Footnotes)
+        );
+        assert_eq!("none".parse::<CitationStyle>(), Ok(CitationStyle::Off));
+        assert!("margin".parse::<CitationStyle>().is_err());
+        assert_eq!(CitationStyle::Footnotes.to_string(), "footnotes");
+    }
+
+    #[test]
+    fn test_inline() {
+        let text = "Rent is due on the 1st [mem:3f2a9c]. You paid it [mem:0b1c2d, mem:3f2a9c].";
+        assert_eq!(
+            citations().render(text, CitationStyle::Inline),
+            "Rent is due on the 1st (per MEMORY.md › Finances, updated Jan 3). \
+             You paid it (per daily/2025-01-02.md, updated Jan 2; \
+             MEMORY.md › Finances, updated Jan 3)."
+        );
+    }
+
+    #[test]
+    fn test_footnotes() {
+        let text = "Rent is due on the 1st [mem:3f2a9c]. You paid it [mem:0b1c2d][mem:3f2a9c].";
+        assert_eq!(
+            citations().render(text, CitationStyle::Footnotes),
+            "Rent is due on the 1st [1]. You paid it [2] [1].\n\n\
+             Sources:\n\
+             [1] MEMORY.md › Finances, updated Jan 3 (lines 12-13)\n\
+             [2] daily/2025-01-02.md, updated Jan 2"
+        );
+    }
+
+    #[test]
+    fn test_off_and_unknown_refs() {
+        let citations = citations();
+        assert_eq!(
+            citations.render("Rent is due on the 1st [mem:3f2a9c].", CitationStyle::Off),
+            "Rent is due on the 1st."
+        );
+        // Refs that no search returned are dropped in every style.
+        for style in [CitationStyle::Inline, CitationStyle::Footnotes] {
+            assert_eq!(citations.render("Made up [mem:ffffff].", style), "Made up.");
+        }
+        // Other brackets are left alone.
+        assert_eq!(
+            citations.render("See [the docs] and [mem:notes].", CitationStyle::Inline),
+            "See [the docs] and [mem:notes]."
+        );
+    }
+
+    #[test]
+    fn test_only_memory_search_is_collected() {
+        let mut citations = citations();
+        citations.collect(
+            "http",
+            r#"{"results": [{"source": {"ref": "mem:ffffff", "citation": "x"}}]}"#,
+        );
+        citations.collect("memory_search", "not json");
+        assert_eq!(citations.sources.len(), 2);
+    }
+}
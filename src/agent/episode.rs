@@ -0,0 +1,429 @@
+//! Episodic summaries of finished conversations and jobs.
+//!
+//! When a conversation ends (the user starts a new thread, clears the
+//! current one, or the session is pruned for idleness) or a job finishes,
+//! the [`EpisodeRecorder`] asks the LLM for a short episode: the topic, who
+//! took part, what was decided or done, and what was left open. The episode
+//! is appended to the day's log under `daily/`, where it is indexed like any
+//! other workspace document, so a later session can search for "what did we
+//! decide last Tuesday" and land on it.
+//!
+//! Episodes are written in the background; a failed summary is logged and
+//! never blocks the conversation that triggered it.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use uuid::Uuid;
+
+use crate::agent::session::{Thread, Turn};
+use crate::context::JobContext;
+use crate::error::{Error, LlmError};
+use crate::llm::{ChatMessage, CompletionRequest, LlmProvider, Role, extract_json};
+use crate::workspace::Workspace;
+
+/// Longest a single message may be in the transcript sent for summary.
+const MAX_MESSAGE_CHARS: usize = 1_500;
+
+const EPISODE_PROMPT: &str = r#"You write episode summaries for an assistant's long-term memory.
+Read the conversation and reply with only a JSON object:
+
+{"topic": "short title", "participants": ["..."], "outcomes": ["..."], "open_loops": ["..."]}
+
+- topic: a few words naming what the conversation was about
+- participants: the people, agents, and services involved
+- outcomes: decisions made, facts learned, and actions completed
+- open_loops: questions left unanswered, promised follow-ups, and unfinished work
+
+Keep each item to one short sentence. Use empty lists when nothing applies."#;
+
+/// A summarized conversation or job.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+pub struct Episode {
+    pub topic: String,
+    #[serde(default)]
+    pub participants: Vec<String>,
+    #[serde(default)]
+    pub outcomes: Vec<String>,
+    #[serde(default)]
+    pub open_loops: Vec<String>,
+}
+
+impl Episode {
+    /// Render as a daily log entry.
+    fn to_markdown(
+        &self,
+        source: &str,
+        started_at: DateTime<Utc>,
+        ended_at: DateTime<Utc>,
+    ) -> String {
+        let mut entry = format!(
+            "\n## Episode: {} ({})\n\n",
+            self.topic.trim(),
+            ended_at.format("%H:%M UTC")
+        );
+        entry.push_str(&format!(
+            "- When: {} {}–{} UTC\n",
+            started_at.format("%A %Y-%m-%d"),
+            started_at.format("%H:%M"),
+            ended_at.format("%H:%M")
+        ));
+        entry.push_str(&format!("- Source: {source}\n"));
+        if !self.participants.is_empty() {
+            entry.push_str(&format!(
+                "- Participants: {}\n",
+                self.participants.join(", ")
+            ));
+        }
+        for (label, items) in [
+            ("Outcomes", &self.outcomes),
+            ("Open loops", &self.open_loops),
+        ] {
+            if items.is_empty() {
+                continue;
+            }
+            entry.push_str(&format!("- {label}:\n"));
+            for item in items {
+                entry.push_str(&format!("  - {}\n", item.trim()));
+            }
+        }
+        entry
+    }
+}
+
+/// Summarizes finished conversations and jobs into the daily log.
+pub struct EpisodeRecorder {
+    llm: Arc<dyn LlmProvider>,
+    workspace: Arc<Workspace>,
+    min_turns: usize,
+    /// Per thread, the first turn number not yet covered by an episode.
+    recorded: Mutex<HashMap<Uuid, usize>>,
+}
+
+impl EpisodeRecorder {
+    pub fn new(llm: Arc<dyn LlmProvider>, workspace: Arc<Workspace>) -> Self {
+        Self {
+            llm,
+            workspace,
+            min_turns: 2,
+            recorded: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Skip conversations with fewer completed turns than this.
+    pub fn with_min_turns(mut self, min_turns: usize) -> Self {
+        self.min_turns = min_turns.max(1);
+        self
+    }
+
+    /// Summarize the turns of `thread` not already covered by an episode.
+    ///
+    /// Returns immediately; the summary is written in the background.
+    pub fn record_thread(self: &Arc<Self>, user_id: &str, thread: &Thread) {
+        let Some((turns, started_at)) = self.take_new_turns(thread) else {
+            return;
+        };
+        let transcript = format_turns(&turns);
+        let source = format!("conversation {}", thread.id);
+        let participants = format!("Participants: {user_id} (user), assistant\n\n");
+        self.spawn_write(source, format!("{participants}{transcript}"), started_at);
+    }
+
+    /// Stop tracking `thread_id`, e.g. after its turns were cleared.
+    pub fn forget(&self, thread_id: Uuid) {
+        self.recorded
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .remove(&thread_id);
+    }
+
+    /// Summarize a finished job from its conversation with the LLM.
+    ///
+    /// Returns immediately; the summary is written in the background.
+    pub fn record_job(self: &Arc<Self>, job: &JobContext, messages: &[ChatMessage]) {
+        let transcript = format_messages(messages);
+        if transcript.is_empty() {
+            return;
+        }
+        let source = format!("job \"{}\" ({}, {})", job.title, job.job_id, job.state);
+        let header = format!(
+            "Job for {}: {}\n{}\n\n",
+            job.user_id, job.title, job.description
+        );
+        let started_at = job.started_at.unwrap_or(job.created_at);
+        self.spawn_write(source, format!("{header}{transcript}"), started_at);
+    }
+
+    /// Ask the LLM to summarize a transcript.
+    pub async fn summarize(&self, transcript: &str) -> Result<Episode, Error> {
+        let request = CompletionRequest::new(vec![
+            ChatMessage::system(EPISODE_PROMPT),
+            ChatMessage::user(transcript),
+        ])
+        .with_max_tokens(512)
+        .with_temperature(0.2);
+
+        let response = self.llm.complete(request).await?;
+        let json = extract_json(&response.content).unwrap_or(&response.content);
+        let episode: Episode =
+            serde_json::from_str(json).map_err(|e| LlmError::InvalidResponse {
+                provider: self.llm.model_name().to_string(),
+                reason: format!("episode summary is not valid JSON: {e}"),
+            })?;
+        if episode.topic.trim().is_empty() {
+            return Err(LlmError::InvalidResponse {
+                provider: self.llm.model_name().to_string(),
+                reason: "episode summary has no topic".to_string(),
+            }
+            .into());
+        }
+        Ok(episode)
+    }
+
+    /// Summarize `transcript` and append it to today's daily log.
+    pub async fn write(
+        &self,
+        source: &str,
+        transcript: &str,
+        started_at: DateTime<Utc>,
+    ) -> Result<Episode, Error> {
+        let episode = self.summarize(transcript).await?;
+        let now = Utc::now();
+        let entry = episode.to_markdown(source, started_at, now);
+        self.workspace
+            .append(&format!("daily/{}.md", now.format("%Y-%m-%d")), &entry)
+            .await?;
+        Ok(episode)
+    }
+
+    fn spawn_write(
+        self: &Arc<Self>,
+        source: String,
+        transcript: String,
+        started_at: DateTime<Utc>,
+    ) {
+        let recorder = Arc::clone(self);
+        tokio::spawn(async move {
+            match recorder.write(&source, &transcript, started_at).await {
+                Ok(episode) => {
+                    tracing::debug!("Recorded episode '{}' for {}", episode.topic, source)
+                }
+                Err(e) => tracing::warn!("Failed to record episode for {}: {}", source, e),
+            }
+        });
+    }
+
+    /// Completed turns of `thread` past the last recorded one, if there are
+    /// enough of them, and marks them recorded.
+    fn take_new_turns(&self, thread: &Thread) -> Option<(Vec<Turn>, DateTime<Utc>)> {
+        let mut recorded = self.recorded.lock().unwrap_or_else(|e| e.into_inner());
+        let from = recorded.get(&thread.id).copied().unwrap_or(0);
+        let turns: Vec<Turn> = thread
+            .turns
+            .iter()
+            .filter(|t| t.turn_number >= from && t.response.is_some())
+            .cloned()
+            .collect();
+        if turns.len() < self.min_turns {
+            return None;
+        }
+        let next = turns
+            .iter()
+            .map(|t| t.turn_number + 1)
+            .max()
+            .unwrap_or(from);
+        recorded.insert(thread.id, next);
+        let started_at = turns
+            .first()
+            .map(|t| t.started_at)
+            .unwrap_or(thread.created_at);
+        Some((turns, started_at))
+    }
+}
+
+fn truncate(text: &str) -> String {
+    if text.chars().count() <= MAX_MESSAGE_CHARS {
+        return text.to_string();
+    }
+    let cut: String = text.chars().take(MAX_MESSAGE_CHARS).collect();
+    format!("{cut}…")
+}
+
+fn format_turns(turns: &[Turn]) -> String {
+    let mut out = String::new();
+    for turn in turns {
+        out.push_str(&format!("User: {}\n", truncate(&turn.user_input)));
+        if !turn.tool_calls.is_empty() {
+            let tools: Vec<&str> = turn.tool_calls.iter().map(|t| t.name.as_str()).collect();
+            out.push_str(&format!("Tools used: {}\n", tools.join(", ")));
+        }
+        if let Some(ref response) = turn.response {
+            out.push_str(&format!("Assistant: {}\n", truncate(response)));
+        }
+        out.push('\n');
+    }
+    out
+}
+
+fn format_messages(messages: &[ChatMessage]) -> String {
+    messages
+        .iter()
+        .filter(|m| m.role != Role::System && !m.content.trim().is_empty())
+        .map(|m| {
+            let label = match m.role {
+                Role::User => "User".to_string(),
+                Role::Assistant => "Assistant".to_string(),
+                Role::Tool => format!("Tool {}", m.name.as_deref().unwrap_or("unknown")),
+                Role::System => "System".to_string(),
+            };
+            format!("{label}: {}", truncate(&m.content))
+        })
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use async_trait::async_trait;
+    use rust_decimal::Decimal;
+
+    use super::*;
+    use crate::llm::{
+        CompletionResponse, FinishReason, ToolCompletionRequest, ToolCompletionResponse,
+    };
+
+    struct ScriptedLlm {
+        responses: Mutex<Vec<String>>,
+    }
+
+    #[async_trait]
+    impl LlmProvider for ScriptedLlm {
+        fn model_name(&self) -> &str {
+            "scripted"
+        }
+
+        fn cost_per_token(&self) -> (Decimal, Decimal) {
+            (Decimal::ZERO, Decimal::ZERO)
+        }
+
+        async fn complete(&self, _: CompletionRequest) -> Result<CompletionResponse, LlmError> {
+            let mut responses = self.responses.lock().unwrap();
+            Ok(CompletionResponse {
+                content: responses.remove(0),
+                input_tokens: 100,
+                output_tokens: 10,
+                finish_reason: FinishReason::Stop,
+                response_id: None,
+            })
+        }
+
+        async fn complete_with_tools(
+            &self,
+            _: ToolCompletionRequest,
+        ) -> Result<ToolCompletionResponse, LlmError> {
+            unimplemented!()
+        }
+    }
+
+    fn scripted(responses: &[&str]) -> Arc<dyn LlmProvider> {
+        Arc::new(ScriptedLlm {
+            responses: Mutex::new(responses.iter().map(|s| s.to_string()).collect()),
+        })
+    }
+
+    fn thread_with(turns: &[(&str, &str)]) -> Thread {
+        let mut thread = Thread::new(Uuid::new_v4());
+        for (input, response) in turns {
+            thread.start_turn(*input);
+            thread.complete_turn(*response);
+        }
+        thread
+    }
+
+    #[test]
+    fn test_episode_markdown() {
+        let episode = Episode {
+            topic: "Trip planning".to_string(),
+            participants: vec!["alice".to_string(), "assistant".to_string()],
+            outcomes: vec!["Booked the 9am train".to_string()],
+            open_loops: vec![],
+        };
+        let start = DateTime::parse_from_rfc3339("2026-10-13T09:05:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let end = start + chrono::Duration::minutes(20);
+        let md = episode.to_markdown("conversation x", start, end);
+
+        assert!(md.contains("## Episode: Trip planning (09:25 UTC)"));
+        assert!(md.contains("- When: Tuesday 2026-10-13 09:05–09:25 UTC"));
+        assert!(md.contains("- Participants: alice, assistant"));
+        assert!(md.contains("  - Booked the 9am train"));
+        assert!(!md.contains("Open loops"));
+    }
+
+    #[cfg(feature = "libsql")]
+    async fn test_workspace() -> (Arc<Workspace>, tempfile::TempDir) {
+        let (db, dir) = crate::db::libsql_backend::test_db().await;
+        (Arc::new(Workspace::new_with_db("default", db)), dir)
+    }
+
+    #[cfg(feature = "libsql")]
+    #[tokio::test]
+    async fn test_take_new_turns_skips_recorded_and_short_threads() {
+        let (workspace, _dir) = test_workspace().await;
+        let recorder = EpisodeRecorder::new(scripted(&[]), workspace);
+
+        assert!(
+            recorder
+                .take_new_turns(&thread_with(&[("hi", "hello")]))
+                .is_none()
+        );
+
+        let mut thread = thread_with(&[("a", "1"), ("b", "2")]);
+        let (turns, _) = recorder.take_new_turns(&thread).unwrap();
+        assert_eq!(turns.len(), 2);
+        assert!(recorder.take_new_turns(&thread).is_none());
+
+        thread.start_turn("c");
+        thread.complete_turn("3");
+        thread.start_turn("d");
+        thread.complete_turn("4");
+        let (turns, _) = recorder.take_new_turns(&thread).unwrap();
+        assert_eq!(turns[0].user_input, "c");
+
+        recorder.forget(thread.id);
+        assert_eq!(recorder.take_new_turns(&thread).unwrap().0.len(), 4);
+    }
+
+    #[cfg(feature = "libsql")]
+    #[tokio::test]
+    async fn test_write_appends_episode_to_daily_log() {
+        let (workspace, _dir) = test_workspace().await;
+
+        let recorder = EpisodeRecorder::new(
+            scripted(&[
+                r#"Sure: {"topic": "Dentist booking", "participants": ["user"],
+                   "outcomes": ["Chose Tuesday 3pm"], "open_loops": ["Confirm insurance"]}"#,
+                "not json",
+            ]),
+            Arc::clone(&workspace),
+        );
+
+        let episode = recorder
+            .write("conversation x", "User: book the dentist", Utc::now())
+            .await
+            .unwrap();
+        assert_eq!(episode.open_loops, vec!["Confirm insurance"]);
+
+        let log = workspace
+            .read(&format!("daily/{}.md", Utc::now().format("%Y-%m-%d")))
+            .await
+            .unwrap();
+        assert!(log.content.contains("## Episode: Dentist booking"));
+        assert!(log.content.contains("  - Confirm insurance"));
+
+        assert!(recorder.write("x", "y", Utc::now()).await.is_err());
+    }
+}
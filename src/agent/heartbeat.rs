@@ -21,16 +21,27 @@
 //! ```
 //!
 //! The agent will process this checklist on each heartbeat and only notify
-//! if action is needed.
+//! if action is needed. Each run's outcome is written to the audit log, if
+//! one is configured, for usage reports.
+//!
+//! When several instances share a database, a lease makes sure only one of
+//! them runs the heartbeat; another takes over if that instance stops.
+//!
+//! If pre-checks are configured (see [`crate::agent::precheck`]), the LLM is
+//! only called when one of them reports something new.
 
 use std::sync::Arc;
 use std::time::Duration;
 
-use tokio::sync::mpsc;
+use tokio::sync::{mpsc, watch};
 
+use crate::agent::precheck::{PreCheckOutcome, PreChecks};
+use crate::audit::{AuditEvent, AuditLog};
 use crate::channels::OutgoingResponse;
+use crate::db::lease::LeaseManager;
 use crate::llm::{ChatMessage, CompletionRequest, FinishReason, LlmProvider};
-use crate::workspace::Workspace;
+use crate::metrics::Metrics;
+use crate::workspace::{PromptContext, SessionKind, Workspace};
 
 /// Configuration for the heartbeat runner.
 #[derive(Debug, Clone)]
@@ -93,12 +104,32 @@ pub enum HeartbeatResult {
     Failed(String),
 }
 
+impl HeartbeatResult {
+    /// Short name used as the metrics label.
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Ok => "ok",
+            Self::NeedsAttention(_) => "needs_attention",
+            Self::Skipped => "skipped",
+            Self::Failed(_) => "failed",
+        }
+    }
+}
+
 /// Heartbeat runner for proactive periodic execution.
 pub struct HeartbeatRunner {
     config: HeartbeatConfig,
     workspace: Arc<Workspace>,
     llm: Arc<dyn LlmProvider>,
     response_tx: Option<mpsc::Sender<OutgoingResponse>>,
+    /// New intervals pushed by config reload.
+    interval_updates: Option<watch::Receiver<Duration>>,
+    /// Audit log that run outcomes are recorded to.
+    audit: Option<Arc<AuditLog>>,
+    /// Held while this instance is the one running the heartbeat.
+    leases: Option<Arc<LeaseManager>>,
+    /// Probes that decide whether the LLM is called at all.
+    prechecks: Option<Arc<PreChecks>>,
     consecutive_failures: u32,
 }
 
@@ -114,6 +145,10 @@ impl HeartbeatRunner {
             workspace,
             llm,
             response_tx: None,
+            interval_updates: None,
+            audit: None,
+            leases: None,
+            prechecks: None,
             consecutive_failures: 0,
         }
     }
@@ -124,6 +159,59 @@ impl HeartbeatRunner {
         self
     }
 
+    /// Follow interval changes published on `rx`. A change restarts the
+    /// countdown with the new period.
+    pub fn with_interval_updates(mut self, rx: watch::Receiver<Duration>) -> Self {
+        self.interval_updates = Some(rx);
+        self
+    }
+
+    /// Record each run's outcome in the audit log.
+    pub fn with_audit(mut self, audit: Arc<AuditLog>) -> Self {
+        self.audit = Some(audit);
+        self
+    }
+
+    /// Only run when this instance holds the heartbeat lease.
+    pub fn with_leases(mut self, leases: Arc<LeaseManager>) -> Self {
+        self.leases = Some(leases);
+        self
+    }
+
+    /// Run `prechecks` before each heartbeat and skip the LLM when none
+    /// of them sees anything new.
+    pub fn with_prechecks(mut self, prechecks: Arc<PreChecks>) -> Self {
+        self.prechecks = Some(prechecks);
+        self
+    }
+
+    /// Take or keep the heartbeat lease. It outlives the interval, so the
+    /// holder keeps it from tick to tick and others only take over once
+    /// it stops ticking.
+    async fn hold_lease(&self) -> bool {
+        let Some(ref leases) = self.leases else {
+            return true;
+        };
+        let name = match self.workspace.agent_id() {
+            Some(agent_id) => format!("heartbeat:{}:{}", self.workspace.user_id(), agent_id),
+            None => format!("heartbeat:{}", self.workspace.user_id()),
+        };
+        match leases
+            .hold(&name, self.config.interval + leases.ttl())
+            .await
+        {
+            Ok(true) => true,
+            Ok(false) => {
+                tracing::debug!("Heartbeat is run by another instance, skipping");
+                false
+            }
+            Err(e) => {
+                tracing::warn!("Failed to take heartbeat lease, skipping: {}", e);
+                false
+            }
+        }
+    }
+
     /// Run the heartbeat loop.
     ///
     /// This runs forever, checking periodically based on the configured interval.
@@ -143,9 +231,39 @@ impl HeartbeatRunner {
         interval.tick().await;
 
         loop {
-            interval.tick().await;
+            tokio::select! {
+                _ = interval.tick() => {}
+                Some(period) = next_interval(&mut self.interval_updates) => {
+                    tracing::info!("Heartbeat interval changed to {:?}", period);
+                    self.config.interval = period;
+                    interval = tokio::time::interval_at(
+                        tokio::time::Instant::now() + period,
+                        period,
+                    );
+                    continue;
+                }
+            }
+
+            if !self.hold_lease().await {
+                continue;
+            }
 
-            match self.check_heartbeat().await {
+            let result = self.check_heartbeat().await;
+            Metrics::global().record_heartbeat(result.label());
+            if let Some(ref audit) = self.audit {
+                let message = match &result {
+                    HeartbeatResult::NeedsAttention(m) | HeartbeatResult::Failed(m) => Some(m),
+                    HeartbeatResult::Ok | HeartbeatResult::Skipped => None,
+                };
+                audit
+                    .record(AuditEvent::heartbeat(
+                        self.workspace.user_id(),
+                        result.label(),
+                        message.map(String::as_str),
+                    ))
+                    .await;
+            }
+            match result {
                 HeartbeatResult::Ok => {
                     tracing::debug!("Heartbeat OK");
                     self.consecutive_failures = 0;
@@ -183,8 +301,20 @@ impl HeartbeatRunner {
             Err(e) => return HeartbeatResult::Failed(format!("Failed to read checklist: {}", e)),
         };
 
+        // Let the probes decide whether the LLM is needed
+        let precheck_report = match self.prechecks {
+            Some(ref prechecks) => match prechecks.run().await {
+                PreCheckOutcome::Quiet => {
+                    tracing::debug!("Heartbeat pre-checks found nothing new, skipping LLM");
+                    return HeartbeatResult::Ok;
+                }
+                PreCheckOutcome::News(report) => Some(report),
+            },
+            None => None,
+        };
+
         // Build the heartbeat prompt
-        let prompt = format!(
+        let mut prompt = format!(
             "Read the HEARTBEAT.md checklist below and follow it strictly. \
              Do not infer or repeat old tasks. Check each item and report findings.\n\
              \n\
@@ -197,9 +327,23 @@ impl HeartbeatRunner {
              {}",
             checklist
         );
+        if let Some(report) = precheck_report {
+            prompt.push_str(&format!(
+                "\n\n## Pre-check results\n\n\
+                 These were checked just now; \"new\" means not seen on the last heartbeat.\n\n{}",
+                report
+            ));
+        }
 
         // Get the system prompt for context
-        let system_prompt = match self.workspace.system_prompt().await {
+        let system_prompt = match self
+            .workspace
+            .system_prompt_with(
+                self.workspace.user_id(),
+                &PromptContext::session(SessionKind::Heartbeat),
+            )
+            .await
+        {
             Ok(p) => p,
             Err(e) => {
                 tracing::warn!("Failed to get system prompt for heartbeat: {}", e);
@@ -361,17 +505,44 @@ pub fn spawn_heartbeat(
     workspace: Arc<Workspace>,
     llm: Arc<dyn LlmProvider>,
     response_tx: Option<mpsc::Sender<OutgoingResponse>>,
+    interval_updates: Option<watch::Receiver<Duration>>,
+    audit: Option<Arc<AuditLog>>,
+    leases: Option<Arc<LeaseManager>>,
+    prechecks: Option<Arc<PreChecks>>,
 ) -> tokio::task::JoinHandle<()> {
     let mut runner = HeartbeatRunner::new(config, workspace, llm);
     if let Some(tx) = response_tx {
         runner = runner.with_response_channel(tx);
     }
+    if let Some(rx) = interval_updates {
+        runner = runner.with_interval_updates(rx);
+    }
+    if let Some(audit) = audit {
+        runner = runner.with_audit(audit);
+    }
+    if let Some(leases) = leases {
+        runner = runner.with_leases(leases);
+    }
+    if let Some(prechecks) = prechecks {
+        runner = runner.with_prechecks(prechecks);
+    }
 
     tokio::spawn(async move {
         runner.run().await;
     })
 }
 
+/// Wait for the next published interval. Never resolves without a
+/// receiver or once the sender is gone.
+async fn next_interval(rx: &mut Option<watch::Receiver<Duration>>) -> Option<Duration> {
+    if let Some(rx) = rx
+        && rx.changed().await.is_ok()
+    {
+        return Some(*rx.borrow_and_update());
+    }
+    std::future::pending().await
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
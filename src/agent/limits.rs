@@ -0,0 +1,391 @@
+//! Per-user rate limits and token budgets.
+//!
+//! A public-facing bot can be cost-bombed by a single user sending messages
+//! in a loop or asking for job after job. [`UserLimiter`] tracks each
+//! user's recent activity and refuses work past the configured limits:
+//!
+//! - messages per minute (sliding window, checked before the agent loop)
+//! - jobs per hour (sliding window, checked when a job is created)
+//! - tokens per day (reset at midnight UTC, checked before the agent loop
+//!   and charged after every LLM call)
+//!
+//! A limit of 0 means unlimited. Usage is kept in memory, so a restart
+//! starts everyone with a clean slate.
+
+use std::collections::{HashMap, VecDeque};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use chrono::{NaiveDate, Utc};
+
+use crate::config::UserLimitsConfig;
+
+const MINUTE: Duration = Duration::from_secs(60);
+const HOUR: Duration = Duration::from_secs(3600);
+
+/// Why a user's request was refused. `Display` is a reply fit to send back.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum LimitExceeded {
+    #[error(
+        "You're sending messages faster than I can keep up with (limit: {limit} per minute). Please wait {} and try again.",
+        format_wait(*retry_after)
+    )]
+    Messages { limit: u32, retry_after: Duration },
+
+    #[error(
+        "You've started the maximum of {limit} jobs this hour. Please wait {} before starting another.",
+        format_wait(*retry_after)
+    )]
+    Jobs { limit: u32, retry_after: Duration },
+
+    #[error(
+        "You've used today's budget of {budget} tokens. It resets at midnight UTC; see you then!"
+    )]
+    Tokens { budget: u64 },
+}
+
+/// A user's usage against their limits.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct UserUsage {
+    /// Messages in the last minute.
+    pub messages: usize,
+    /// Jobs started in the last hour.
+    pub jobs: usize,
+    /// Tokens used today (UTC).
+    pub tokens: u64,
+}
+
+#[derive(Debug, Default)]
+struct Activity {
+    messages: VecDeque<Instant>,
+    jobs: VecDeque<Instant>,
+    token_day: Option<NaiveDate>,
+    tokens: u64,
+}
+
+impl Activity {
+    fn tokens_on(&self, day: NaiveDate) -> u64 {
+        if self.token_day == Some(day) {
+            self.tokens
+        } else {
+            0
+        }
+    }
+}
+
+/// Tracks per-user activity and enforces [`UserLimitsConfig`].
+#[derive(Debug)]
+pub struct UserLimiter {
+    config: UserLimitsConfig,
+    users: Mutex<HashMap<String, Activity>>,
+}
+
+impl UserLimiter {
+    pub fn new(config: UserLimitsConfig) -> Self {
+        Self {
+            config,
+            users: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn config(&self) -> &UserLimitsConfig {
+        &self.config
+    }
+
+    /// Check an inbound message against the per-minute and daily token
+    /// limits, counting it if allowed.
+    pub fn check_message(&self, user_id: &str) -> Result<(), LimitExceeded> {
+        self.check_message_at(user_id, Instant::now(), Utc::now().date_naive())
+    }
+
+    /// Check a new job against the hourly limit, counting it if allowed.
+    pub fn check_job(&self, user_id: &str) -> Result<(), LimitExceeded> {
+        self.check_job_at(user_id, Instant::now())
+    }
+
+    /// Charge tokens from an LLM call to the user's daily budget.
+    pub fn record_tokens(&self, user_id: &str, tokens: u64) {
+        self.record_tokens_on(user_id, tokens, Utc::now().date_naive());
+    }
+
+    /// The user's current usage.
+    pub fn usage(&self, user_id: &str) -> UserUsage {
+        let now = Instant::now();
+        let today = Utc::now().date_naive();
+        let mut users = self.users.lock().unwrap_or_else(|e| e.into_inner());
+        let Some(activity) = users.get_mut(user_id) else {
+            return UserUsage::default();
+        };
+        prune(&mut activity.messages, now, MINUTE);
+        prune(&mut activity.jobs, now, HOUR);
+        UserUsage {
+            messages: activity.messages.len(),
+            jobs: activity.jobs.len(),
+            tokens: activity.tokens_on(today),
+        }
+    }
+
+    fn check_message_at(
+        &self,
+        user_id: &str,
+        now: Instant,
+        today: NaiveDate,
+    ) -> Result<(), LimitExceeded> {
+        let limit = self.config.messages_per_minute;
+        let budget = self.config.daily_token_budget;
+        if limit == 0 && budget == 0 {
+            return Ok(());
+        }
+        let mut users = self.users.lock().unwrap_or_else(|e| e.into_inner());
+        let activity = users.entry(user_id.to_string()).or_default();
+
+        if budget > 0 && activity.tokens_on(today) >= budget {
+            return Err(LimitExceeded::Tokens { budget });
+        }
+        if limit > 0 {
+            admit(&mut activity.messages, now, MINUTE, limit)
+                .map_err(|retry_after| LimitExceeded::Messages { limit, retry_after })?;
+        }
+        Ok(())
+    }
+
+    fn check_job_at(&self, user_id: &str, now: Instant) -> Result<(), LimitExceeded> {
+        let limit = self.config.jobs_per_hour;
+        if limit == 0 {
+            return Ok(());
+        }
+        let mut users = self.users.lock().unwrap_or_else(|e| e.into_inner());
+        let activity = users.entry(user_id.to_string()).or_default();
+        admit(&mut activity.jobs, now, HOUR, limit)
+            .map_err(|retry_after| LimitExceeded::Jobs { limit, retry_after })
+    }
+
+    fn record_tokens_on(&self, user_id: &str, tokens: u64, today: NaiveDate) {
+        if self.config.daily_token_budget == 0 || tokens == 0 {
+            return;
+        }
+        let mut users = self.users.lock().unwrap_or_else(|e| e.into_inner());
+        let activity = users.entry(user_id.to_string()).or_default();
+        if activity.token_day != Some(today) {
+            activity.token_day = Some(today);
+            activity.tokens = 0;
+        }
+        activity.tokens = activity.tokens.saturating_add(tokens);
+    }
+}
+
+/// Drop timestamps older than `window`.
+fn prune(events: &mut VecDeque<Instant>, now: Instant, window: Duration) {
+    while let Some(&oldest) = events.front() {
+        if now.saturating_duration_since(oldest) >= window {
+            events.pop_front();
+        } else {
+            break;
+        }
+    }
+}
+
+/// Record an event if fewer than `limit` happened within `window`,
+/// otherwise return how long until the oldest one expires.
+fn admit(
+    events: &mut VecDeque<Instant>,
+    now: Instant,
+    window: Duration,
+    limit: u32,
+) -> Result<(), Duration> {
+    prune(events, now, window);
+    if events.len() >= limit as usize {
+        let oldest = events.front().copied().unwrap_or(now);
+        return Err(window.saturating_sub(now.saturating_duration_since(oldest)));
+    }
+    events.push_back(now);
+    Ok(())
+}
+
+/// "a few seconds", "40 seconds", "12 minutes".
+fn format_wait(wait: Duration) -> String {
+    let secs = wait.as_secs().max(1);
+    if secs < 5 {
+        "a few seconds".to_string()
+    } else if secs < 60 {
+        format!("{} seconds", secs)
+    } else {
+        let mins = secs.div_ceil(60);
+        if mins == 1 {
+            "a minute".to_string()
+        } else {
+            format!("{} minutes", mins)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn limiter(messages: u32, jobs: u32, tokens: u64) -> UserLimiter {
+        UserLimiter::new(UserLimitsConfig {
+            messages_per_minute: messages,
+            jobs_per_hour: jobs,
+            daily_token_budget: tokens,
+        })
+    }
+
+    fn day(d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2025, 3, d).unwrap()
+    }
+
+    #[test]
+    fn test_unlimited_by_default() {
+        let limiter = UserLimiter::new(UserLimitsConfig::default());
+        for _ in 0..1000 {
+            assert!(limiter.check_message("alice").is_ok());
+            assert!(limiter.check_job("alice").is_ok());
+            limiter.record_tokens("alice", 1_000_000);
+        }
+    }
+
+    #[test]
+    fn test_messages_per_minute_window() {
+        let limiter = limiter(2, 0, 0);
+        let start = Instant::now();
+        let today = day(1);
+
+        assert!(limiter.check_message_at("alice", start, today).is_ok());
+        assert!(
+            limiter
+                .check_message_at("alice", start + Duration::from_secs(10), today)
+                .is_ok()
+        );
+        let err = limiter
+            .check_message_at("alice", start + Duration::from_secs(20), today)
+            .unwrap_err();
+        assert_eq!(
+            err,
+            LimitExceeded::Messages {
+                limit: 2,
+                retry_after: Duration::from_secs(40)
+            }
+        );
+
+        // Other users have their own window
+        assert!(limiter.check_message_at("bob", start, today).is_ok());
+
+        // The first message ages out after a minute
+        assert!(
+            limiter
+                .check_message_at("alice", start + Duration::from_secs(60), today)
+                .is_ok()
+        );
+    }
+
+    #[test]
+    fn test_rejected_messages_do_not_extend_window() {
+        let limiter = limiter(1, 0, 0);
+        let start = Instant::now();
+        let today = day(1);
+
+        assert!(limiter.check_message_at("alice", start, today).is_ok());
+        for secs in [1, 30, 59] {
+            assert!(
+                limiter
+                    .check_message_at("alice", start + Duration::from_secs(secs), today)
+                    .is_err()
+            );
+        }
+        assert!(
+            limiter
+                .check_message_at("alice", start + Duration::from_secs(60), today)
+                .is_ok()
+        );
+    }
+
+    #[test]
+    fn test_jobs_per_hour() {
+        let limiter = limiter(0, 1, 0);
+        let start = Instant::now();
+
+        assert!(limiter.check_job_at("alice", start).is_ok());
+        assert!(matches!(
+            limiter.check_job_at("alice", start + Duration::from_secs(1800)),
+            Err(LimitExceeded::Jobs { limit: 1, .. })
+        ));
+        assert!(
+            limiter
+                .check_job_at("alice", start + Duration::from_secs(3600))
+                .is_ok()
+        );
+    }
+
+    #[test]
+    fn test_daily_token_budget_resets_next_day() {
+        let limiter = limiter(0, 0, 1000);
+        let now = Instant::now();
+
+        limiter.record_tokens_on("alice", 600, day(1));
+        assert!(limiter.check_message_at("alice", now, day(1)).is_ok());
+        limiter.record_tokens_on("alice", 400, day(1));
+        assert_eq!(
+            limiter.check_message_at("alice", now, day(1)),
+            Err(LimitExceeded::Tokens { budget: 1000 })
+        );
+        assert!(limiter.check_message_at("bob", now, day(1)).is_ok());
+
+        assert!(limiter.check_message_at("alice", now, day(2)).is_ok());
+        limiter.record_tokens_on("alice", 10, day(2));
+        assert!(limiter.check_message_at("alice", now, day(2)).is_ok());
+    }
+
+    #[test]
+    fn test_usage() {
+        let limiter = limiter(10, 10, 1000);
+        assert_eq!(limiter.usage("alice"), UserUsage::default());
+
+        limiter.check_message("alice").unwrap();
+        limiter.check_message("alice").unwrap();
+        limiter.check_job("alice").unwrap();
+        limiter.record_tokens("alice", 250);
+
+        assert_eq!(
+            limiter.usage("alice"),
+            UserUsage {
+                messages: 2,
+                jobs: 1,
+                tokens: 250
+            }
+        );
+    }
+
+    #[test]
+    fn test_replies_are_polite() {
+        let msg = LimitExceeded::Messages {
+            limit: 5,
+            retry_after: Duration::from_secs(42),
+        }
+        .to_string();
+        assert!(msg.contains("5 per minute"));
+        assert!(msg.contains("42 seconds"));
+
+        let msg = LimitExceeded::Jobs {
+            limit: 3,
+            retry_after: Duration::from_secs(1500),
+        }
+        .to_string();
+        assert!(msg.contains("3 jobs"));
+        assert!(msg.contains("25 minutes"));
+
+        assert!(
+            LimitExceeded::Tokens { budget: 50_000 }
+                .to_string()
+                .contains("midnight UTC")
+        );
+    }
+
+    #[test]
+    fn test_format_wait() {
+        assert_eq!(format_wait(Duration::ZERO), "a few seconds");
+        assert_eq!(format_wait(Duration::from_secs(30)), "30 seconds");
+        assert_eq!(format_wait(Duration::from_secs(60)), "a minute");
+        assert_eq!(format_wait(Duration::from_secs(61)), "2 minutes");
+    }
+}
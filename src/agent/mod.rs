@@ -4,33 +4,62 @@
 //! - Message routing from channels
 //! - Job scheduling and execution
 //! - Tool invocation with safety
-//! - Self-repair for stuck jobs
-//! - Proactive heartbeat execution
+//! - Self-repair for stuck jobs and malformed tool calls
+//! - Proactive heartbeat execution, gated by cheap no-LLM pre-checks
 //! - Routine-based scheduled and reactive jobs
+//! - Reusable job templates with variables, toolsets, models, and budgets
+//! - Registered agents with their own workspace, model, tools, and channels
+//! - Supervisor delegation to worker agents over an in-process bus
 //! - Turn-based session management with undo
+//! - Citations of the memory a response relies on
 //! - Context compaction for long conversations
+//! - Episodic summaries of finished conversations and jobs
+//! - Scenario regression tests of prompts and tool descriptions
+//! - Per-user rate limits and daily token budgets
+//! - Graceful shutdown that drains and checkpoints in-flight work
 
 mod agent_loop;
+pub mod bus;
+pub mod citations;
 pub mod compaction;
 pub mod context_monitor;
+pub mod episode;
 mod heartbeat;
+pub mod limits;
+pub mod precheck;
+pub mod registry;
 mod router;
 pub mod routine;
 pub mod routine_engine;
+pub mod scenario;
 mod scheduler;
 mod self_repair;
 pub mod session;
 mod session_manager;
+pub mod shutdown;
 pub mod submission;
+pub mod supervisor;
 pub mod task;
+pub mod template;
+pub mod tool_repair;
+pub mod transcript;
 pub mod undo;
 pub mod worker;
 
 pub(crate) use agent_loop::truncate_for_preview;
 pub use agent_loop::{Agent, AgentDeps};
+pub use bus::{AgentBus, AgentEndpoint, AgentMessage, AgentReply, BusError};
+pub use citations::{CitationStyle, Citations};
 pub use compaction::{CompactionResult, ContextCompactor};
 pub use context_monitor::{CompactionStrategy, ContextBreakdown, ContextMonitor};
+pub use episode::{Episode, EpisodeRecorder};
 pub use heartbeat::{HeartbeatConfig, HeartbeatResult, HeartbeatRunner, spawn_heartbeat};
+pub use limits::{LimitExceeded, UserLimiter, UserUsage};
+pub use precheck::{PreCheckOutcome, PreChecks, Probe};
+pub use registry::{
+    AgentDefinition, AgentRegistry, AgentRegistryError, AgentSearchResult, AgentUpdate,
+    ForkOptions, ResultSource,
+};
 pub use router::{MessageIntent, Router};
 pub use routine::{Routine, RoutineAction, RoutineRun, Trigger};
 pub use routine_engine::RoutineEngine;
@@ -38,7 +67,14 @@ pub use scheduler::Scheduler;
 pub use self_repair::{BrokenTool, RepairResult, RepairTask, SelfRepair, StuckJob};
 pub use session::{PendingApproval, PendingAuth, Session, Thread, ThreadState, Turn, TurnState};
 pub use session_manager::SessionManager;
+pub use shutdown::ShutdownCoordinator;
 pub use submission::{Submission, SubmissionParser, SubmissionResult};
+pub use supervisor::{
+    RegisteredWorker, Supervisor, SupervisorError, SupervisorLimits, SupervisorOutcome,
+    WorkerRuntime,
+};
 pub use task::{Task, TaskContext, TaskHandler, TaskOutput, TaskStatus};
+pub use template::{JobTemplate, JobTemplates, TemplateError, TemplateLauncher};
+pub use transcript::{RecordingProvider, ReplayOptions, ReplayReport, TranscriptEntry, replay};
 pub use undo::{Checkpoint, UndoManager};
 pub use worker::{Worker, WorkerDeps};
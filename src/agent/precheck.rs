@@ -0,0 +1,868 @@
+//! Heartbeat pre-checks.
+//!
+//! Most heartbeats find nothing, but each one still costs an LLM call over
+//! the whole checklist and system prompt. Pre-checks are cheap
+//! deterministic probes run first:
+//!
+//! - unread mail in an IMAP mailbox
+//! - upcoming events in an iCalendar (.ics) feed
+//! - new items in RSS or Atom feeds
+//!
+//! Each probe observes a set of items (message UIDs, events, feed entries).
+//! When pre-checks are configured the heartbeat only calls the LLM if some
+//! probe sees an item it didn't see last time, and passes what the probes
+//! found along with the checklist. A probe that fails counts as news, so a
+//! broken probe never silences the heartbeat.
+
+use std::collections::{HashMap, HashSet};
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Local, NaiveDate, NaiveDateTime, TimeZone, Utc};
+use regex::Regex;
+use secrecy::{ExposeSecret, SecretString};
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader};
+
+use crate::config::{HeartbeatPrecheckConfig, ImapProbeConfig};
+
+/// Longest a probe may take before it counts as failed.
+const PROBE_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Feed entries looked at per fetch, newest first.
+const MAX_FEED_ITEMS: usize = 50;
+
+/// New items listed by name in the report, per probe.
+const MAX_LISTED: usize = 5;
+
+/// Errors from running a probe.
+#[derive(Debug, thiserror::Error)]
+pub enum ProbeError {
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("HTTP error: {0}")]
+    Http(#[from] reqwest::Error),
+
+    #[error("TLS error: {0}")]
+    Tls(String),
+
+    #[error("{0}")]
+    Protocol(String),
+
+    #[error("timed out after {0:?}")]
+    Timeout(Duration),
+}
+
+/// An item a probe sees, such as an unread message or an upcoming event.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ObservedItem {
+    /// Stable identity across observations.
+    pub id: String,
+    /// Short description for the report; empty if not worth listing.
+    pub label: String,
+}
+
+/// What a probe saw on one run.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Observation {
+    /// One line describing the current state, e.g. "3 unread".
+    pub summary: String,
+    pub items: Vec<ObservedItem>,
+}
+
+/// A cheap check run before the heartbeat's LLM call.
+#[async_trait]
+pub trait Probe: Send + Sync {
+    /// Name shown in the report, e.g. "email".
+    fn name(&self) -> &str;
+
+    async fn observe(&self) -> Result<Observation, ProbeError>;
+}
+
+/// Result of running the pre-checks.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PreCheckOutcome {
+    /// No probe saw anything new: the LLM can be skipped.
+    Quiet,
+    /// Something is new (or a probe failed). Holds one report line per
+    /// probe, for the LLM.
+    News(String),
+}
+
+/// The configured probes and the items each saw last time.
+pub struct PreChecks {
+    probes: Vec<Box<dyn Probe>>,
+    /// Keyed by position in `probes`: names are for display and need not be
+    /// unique (two feeds on one host share one).
+    seen: Mutex<HashMap<usize, HashSet<String>>>,
+}
+
+impl std::fmt::Debug for PreChecks {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PreChecks")
+            .field(
+                "probes",
+                &self.probes.iter().map(|p| p.name()).collect::<Vec<_>>(),
+            )
+            .finish()
+    }
+}
+
+impl PreChecks {
+    pub fn new(probes: Vec<Box<dyn Probe>>) -> Self {
+        Self {
+            probes,
+            seen: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// The probes `config` asks for, or `None` if it configures none.
+    pub fn from_config(config: &HeartbeatPrecheckConfig) -> Option<Arc<Self>> {
+        if !config.is_enabled() {
+            return None;
+        }
+        let client = reqwest::Client::builder()
+            .timeout(PROBE_TIMEOUT)
+            .build()
+            .unwrap_or_default();
+        let mut probes: Vec<Box<dyn Probe>> = Vec::new();
+        if let Some(ref imap) = config.imap {
+            probes.push(Box::new(ImapProbe::new(imap.clone())));
+        }
+        if let Some(ref url) = config.calendar_url {
+            probes.push(Box::new(CalendarProbe::new(
+                client.clone(),
+                url,
+                config.calendar_lookahead,
+            )));
+        }
+        for url in &config.feed_urls {
+            probes.push(Box::new(FeedProbe::new(client.clone(), url)));
+        }
+        Some(Arc::new(Self::new(probes)))
+    }
+
+    pub fn len(&self) -> usize {
+        self.probes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.probes.is_empty()
+    }
+
+    /// Run every probe and compare with what each saw last time. The first
+    /// run after startup has nothing to compare with, so anything seen
+    /// counts as new.
+    pub async fn run(&self) -> PreCheckOutcome {
+        let results = futures::future::join_all(self.probes.iter().map(|probe| async move {
+            let result = match tokio::time::timeout(PROBE_TIMEOUT, probe.observe()).await {
+                Ok(result) => result,
+                Err(_) => Err(ProbeError::Timeout(PROBE_TIMEOUT)),
+            };
+            (probe.name(), result)
+        }))
+        .await;
+
+        let mut seen = self.seen.lock().unwrap_or_else(|e| e.into_inner());
+        let mut news = false;
+        let mut lines = Vec::with_capacity(results.len());
+        for (index, (name, result)) in results.into_iter().enumerate() {
+            let observation = match result {
+                Ok(o) => o,
+                Err(e) => {
+                    tracing::warn!("Heartbeat pre-check {} failed: {}", name, e);
+                    news = true;
+                    lines.push(format!("- {}: check failed ({})", name, e));
+                    continue;
+                }
+            };
+            let previous = seen.get(&index);
+            let new: Vec<&ObservedItem> = observation
+                .items
+                .iter()
+                .filter(|item| previous.is_none_or(|p| !p.contains(&item.id)))
+                .collect();
+            let mut line = format!("- {}: {}", name, observation.summary);
+            if !new.is_empty() {
+                news = true;
+                let labels: Vec<&str> = new
+                    .iter()
+                    .map(|i| i.label.as_str())
+                    .filter(|l| !l.is_empty())
+                    .take(MAX_LISTED)
+                    .collect();
+                if labels.is_empty() {
+                    line.push_str(&format!(" ({} new)", new.len()));
+                } else {
+                    line.push_str(&format!(" ({} new: {})", new.len(), labels.join("; ")));
+                }
+            }
+            lines.push(line);
+            seen.insert(index, observation.items.into_iter().map(|i| i.id).collect());
+        }
+
+        if news {
+            PreCheckOutcome::News(lines.join("\n"))
+        } else {
+            PreCheckOutcome::Quiet
+        }
+    }
+}
+
+// ==================== IMAP ====================
+
+/// Unread messages in an IMAP mailbox, over TLS. The mailbox is opened
+/// read-only, so checking never marks anything as read.
+pub struct ImapProbe {
+    config: ImapProbeConfig,
+}
+
+impl ImapProbe {
+    pub fn new(config: ImapProbeConfig) -> Self {
+        Self { config }
+    }
+}
+
+#[async_trait]
+impl Probe for ImapProbe {
+    fn name(&self) -> &str {
+        "email"
+    }
+
+    async fn observe(&self) -> Result<Observation, ProbeError> {
+        let tcp =
+            tokio::net::TcpStream::connect((self.config.host.as_str(), self.config.port)).await?;
+        let server_name =
+            tokio_rustls::rustls::pki_types::ServerName::try_from(self.config.host.clone())
+                .map_err(|e| ProbeError::Tls(e.to_string()))?;
+        let stream = tls_connector()?.connect(server_name, tcp).await?;
+
+        let uids = unseen_uids(
+            stream,
+            &self.config.username,
+            &self.config.password,
+            &self.config.mailbox,
+        )
+        .await?;
+        Ok(Observation {
+            summary: format!("{} unread in {}", uids.len(), self.config.mailbox),
+            items: uids
+                .into_iter()
+                .map(|uid| ObservedItem {
+                    id: uid,
+                    label: String::new(),
+                })
+                .collect(),
+        })
+    }
+}
+
+fn tls_connector() -> Result<tokio_rustls::TlsConnector, ProbeError> {
+    use tokio_rustls::rustls::{ClientConfig, RootCertStore, crypto::ring};
+
+    let mut roots = RootCertStore::empty();
+    for cert in rustls_native_certs::load_native_certs().certs {
+        // Skip certificates the store rejects rather than failing outright.
+        let _ = roots.add(cert);
+    }
+    let config = ClientConfig::builder_with_provider(Arc::new(ring::default_provider()))
+        .with_safe_default_protocol_versions()
+        .map_err(|e| ProbeError::Tls(e.to_string()))?
+        .with_root_certificates(roots)
+        .with_no_client_auth();
+    Ok(tokio_rustls::TlsConnector::from(Arc::new(config)))
+}
+
+/// Log in, open `mailbox` read-only, and return the UIDs of its unseen
+/// messages.
+async fn unseen_uids<S>(
+    stream: S,
+    username: &str,
+    password: &SecretString,
+    mailbox: &str,
+) -> Result<Vec<String>, ProbeError>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    let mut session = ImapSession {
+        stream: BufReader::new(stream),
+        tag: 0,
+    };
+    let greeting = session.read_line().await?;
+    if !greeting.starts_with("* OK") && !greeting.starts_with("* PREAUTH") {
+        return Err(ProbeError::Protocol(format!(
+            "unexpected IMAP greeting: {}",
+            greeting.trim()
+        )));
+    }
+    session
+        .command(&format!(
+            "LOGIN {} {}",
+            quote(username),
+            quote(password.expose_secret())
+        ))
+        .await
+        .map_err(|e| match e {
+            ProbeError::Protocol(_) => ProbeError::Protocol("IMAP login rejected".to_string()),
+            e => e,
+        })?;
+    session
+        .command(&format!("EXAMINE {}", quote(mailbox)))
+        .await?;
+    let untagged = session.command("UID SEARCH UNSEEN").await?;
+    let _ = session.command("LOGOUT").await;
+
+    Ok(untagged
+        .iter()
+        .filter_map(|line| line.strip_prefix("* SEARCH"))
+        .flat_map(|rest| rest.split_whitespace().map(str::to_string))
+        .collect())
+}
+
+struct ImapSession<S> {
+    stream: BufReader<S>,
+    tag: u32,
+}
+
+impl<S: AsyncRead + AsyncWrite + Unpin> ImapSession<S> {
+    async fn read_line(&mut self) -> Result<String, ProbeError> {
+        let mut line = String::new();
+        if self.stream.read_line(&mut line).await? == 0 {
+            return Err(ProbeError::Protocol(
+                "IMAP server closed the connection".to_string(),
+            ));
+        }
+        Ok(line)
+    }
+
+    /// Send `command` and return its untagged response lines. Fails unless
+    /// the server answers OK.
+    async fn command(&mut self, command: &str) -> Result<Vec<String>, ProbeError> {
+        self.tag += 1;
+        let tag = format!("a{}", self.tag);
+        self.stream
+            .get_mut()
+            .write_all(format!("{} {}\r\n", tag, command).as_bytes())
+            .await?;
+        self.stream.get_mut().flush().await?;
+
+        let mut untagged = Vec::new();
+        loop {
+            let line = self.read_line().await?;
+            let Some(status) = line.strip_prefix(&format!("{} ", tag)) else {
+                untagged.push(line.trim_end().to_string());
+                continue;
+            };
+            if status.starts_with("OK") {
+                return Ok(untagged);
+            }
+            let verb = command.split_whitespace().next().unwrap_or(command);
+            return Err(ProbeError::Protocol(format!(
+                "IMAP {} failed: {}",
+                verb,
+                status.trim()
+            )));
+        }
+    }
+}
+
+/// An IMAP quoted string.
+fn quote(value: &str) -> String {
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+// ==================== Calendar ====================
+
+/// Events starting within the lookahead window of an iCalendar feed.
+///
+/// Recurring events only count at their first occurrence (`RRULE` is not
+/// expanded), and times with a `TZID` are read as local time.
+pub struct CalendarProbe {
+    client: reqwest::Client,
+    url: String,
+    lookahead: Duration,
+}
+
+impl CalendarProbe {
+    pub fn new(client: reqwest::Client, url: &str, lookahead: Duration) -> Self {
+        Self {
+            client,
+            url: url.to_string(),
+            lookahead,
+        }
+    }
+}
+
+#[async_trait]
+impl Probe for CalendarProbe {
+    fn name(&self) -> &str {
+        "calendar"
+    }
+
+    async fn observe(&self) -> Result<Observation, ProbeError> {
+        let ics = self
+            .client
+            .get(&self.url)
+            .send()
+            .await?
+            .error_for_status()?
+            .text()
+            .await?;
+        let now = Utc::now();
+        let until = now
+            + chrono::Duration::from_std(self.lookahead)
+                .unwrap_or_else(|_| chrono::Duration::hours(1));
+        Ok(calendar_observation(&ics, now, until, self.lookahead))
+    }
+}
+
+/// A `VEVENT` of an iCalendar document.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct CalendarEvent {
+    uid: String,
+    summary: String,
+    start: DateTime<Utc>,
+}
+
+fn calendar_observation(
+    ics: &str,
+    now: DateTime<Utc>,
+    until: DateTime<Utc>,
+    lookahead: Duration,
+) -> Observation {
+    let mut events: Vec<CalendarEvent> = parse_events(ics)
+        .into_iter()
+        .filter(|e| e.start >= now && e.start <= until)
+        .collect();
+    events.sort_by_key(|e| e.start);
+
+    let window = format!("the next {} min", lookahead.as_secs() / 60);
+    let summary = match events.first() {
+        Some(next) => format!(
+            "{} event(s) in {}; next: {} at {}",
+            events.len(),
+            window,
+            next.summary,
+            next.start.with_timezone(&Local).format("%H:%M")
+        ),
+        None => format!("no events in {}", window),
+    };
+    Observation {
+        summary,
+        items: events
+            .into_iter()
+            .map(|e| ObservedItem {
+                id: format!("{}@{}", e.uid, e.start.timestamp()),
+                label: format!(
+                    "{} at {}",
+                    e.summary,
+                    e.start.with_timezone(&Local).format("%H:%M")
+                ),
+            })
+            .collect(),
+    }
+}
+
+fn parse_events(ics: &str) -> Vec<CalendarEvent> {
+    // Long lines are folded: a line starting with a space or tab continues
+    // the previous one.
+    let unfolded = ics
+        .replace("\r\n ", "")
+        .replace("\r\n\t", "")
+        .replace("\n ", "")
+        .replace("\n\t", "");
+
+    let mut events = Vec::new();
+    let mut current: Option<(String, String, Option<DateTime<Utc>>)> = None;
+    for line in unfolded.lines() {
+        let line = line.trim_end_matches('\r');
+        if line == "BEGIN:VEVENT" {
+            current = Some((String::new(), String::new(), None));
+            continue;
+        }
+        if line == "END:VEVENT" {
+            if let Some((uid, summary, Some(start))) = current.take() {
+                events.push(CalendarEvent {
+                    uid,
+                    summary: if summary.is_empty() {
+                        "(untitled)".to_string()
+                    } else {
+                        summary
+                    },
+                    start,
+                });
+            }
+            continue;
+        }
+        let Some((uid, summary, start)) = current.as_mut() else {
+            continue;
+        };
+        let Some((name, value)) = line.split_once(':') else {
+            continue;
+        };
+        let (name, params) = name.split_once(';').unwrap_or((name, ""));
+        match name {
+            "UID" => *uid = value.to_string(),
+            "SUMMARY" => {
+                *summary = value
+                    .replace("\\,", ",")
+                    .replace("\\;", ";")
+                    .replace("\\n", " ")
+                    .replace("\\\\", "\\")
+            }
+            "DTSTART" => *start = parse_ics_time(params, value),
+            _ => {}
+        }
+    }
+    events
+}
+
+/// Parse a `DTSTART` value: UTC (`...Z`), floating or `TZID` (taken as
+/// local time), or a whole day (`VALUE=DATE`, from local midnight).
+fn parse_ics_time(params: &str, value: &str) -> Option<DateTime<Utc>> {
+    if (params.contains("VALUE=DATE") && !params.contains("VALUE=DATE-TIME")) || value.len() == 8 {
+        let date = NaiveDate::parse_from_str(value, "%Y%m%d").ok()?;
+        let midnight = date.and_hms_opt(0, 0, 0)?;
+        return Local
+            .from_local_datetime(&midnight)
+            .earliest()
+            .map(|t| t.with_timezone(&Utc));
+    }
+    if let Some(utc) = value.strip_suffix('Z') {
+        let naive = NaiveDateTime::parse_from_str(utc, "%Y%m%dT%H%M%S").ok()?;
+        return Some(Utc.from_utc_datetime(&naive));
+    }
+    let naive = NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%S").ok()?;
+    Local
+        .from_local_datetime(&naive)
+        .earliest()
+        .map(|t| t.with_timezone(&Utc))
+}
+
+// ==================== Feeds ====================
+
+/// New entries of an RSS or Atom feed.
+pub struct FeedProbe {
+    client: reqwest::Client,
+    url: String,
+    name: String,
+}
+
+impl FeedProbe {
+    pub fn new(client: reqwest::Client, url: &str) -> Self {
+        let host = url::Url::from_str(url)
+            .ok()
+            .and_then(|u| u.host_str().map(str::to_string))
+            .unwrap_or_else(|| url.to_string());
+        Self {
+            client,
+            url: url.to_string(),
+            name: format!("feed {}", host),
+        }
+    }
+}
+
+#[async_trait]
+impl Probe for FeedProbe {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    async fn observe(&self) -> Result<Observation, ProbeError> {
+        let body = self
+            .client
+            .get(&self.url)
+            .send()
+            .await?
+            .error_for_status()?
+            .text()
+            .await?;
+        Ok(feed_observation(&body))
+    }
+}
+
+fn feed_observation(body: &str) -> Observation {
+    static ENTRY: std::sync::LazyLock<Regex> = std::sync::LazyLock::new(|| {
+        Regex::new(r"(?s)<(?:item|entry)\b[^>]*>(.*?)</(?:item|entry)>").expect("valid regex")
+    });
+    static ID: std::sync::LazyLock<Regex> = std::sync::LazyLock::new(|| {
+        Regex::new(r"(?s)<(?:guid|id)\b[^>]*>(.*?)</(?:guid|id)>").expect("valid regex")
+    });
+    static LINK: std::sync::LazyLock<Regex> = std::sync::LazyLock::new(|| {
+        Regex::new(r#"(?s)<link\b[^>]*?(?:href="([^"]+)"[^>]*)?>(?:(.*?)</link>)?"#)
+            .expect("valid regex")
+    });
+    static TITLE: std::sync::LazyLock<Regex> = std::sync::LazyLock::new(|| {
+        Regex::new(r"(?s)<title\b[^>]*>(.*?)</title>").expect("valid regex")
+    });
+
+    let text = |s: &str| {
+        s.trim()
+            .trim_start_matches("<![CDATA[")
+            .trim_end_matches("]]>")
+            .replace("&amp;", "&")
+            .replace("&lt;", "<")
+            .replace("&gt;", ">")
+            .replace("&quot;", "\"")
+            .replace("&#39;", "'")
+            .trim()
+            .to_string()
+    };
+
+    let items: Vec<ObservedItem> = ENTRY
+        .captures_iter(body)
+        .take(MAX_FEED_ITEMS)
+        .filter_map(|entry| {
+            let entry = entry.get(1)?.as_str();
+            let title = TITLE
+                .captures(entry)
+                .map(|c| text(&c[1]))
+                .unwrap_or_default();
+            let id = ID
+                .captures(entry)
+                .map(|c| text(&c[1]))
+                .or_else(|| {
+                    LINK.captures(entry)
+                        .and_then(|c| c.get(1).or_else(|| c.get(2)).map(|m| text(m.as_str())))
+                })
+                .filter(|id| !id.is_empty())
+                .or_else(|| (!title.is_empty()).then(|| title.clone()))?;
+            Some(ObservedItem { id, label: title })
+        })
+        .collect();
+
+    let summary = match items.first() {
+        Some(latest) if !latest.label.is_empty() => {
+            format!("{} items, latest: {}", items.len(), latest.label)
+        }
+        _ => format!("{} items", items.len()),
+    };
+    Observation { summary, items }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Reports the items it is given, or fails when given none.
+    struct FixedProbe(Mutex<Option<Vec<&'static str>>>);
+
+    #[async_trait]
+    impl Probe for FixedProbe {
+        fn name(&self) -> &str {
+            "fixed"
+        }
+
+        async fn observe(&self) -> Result<Observation, ProbeError> {
+            let items = self
+                .0
+                .lock()
+                .unwrap()
+                .clone()
+                .ok_or_else(|| ProbeError::Protocol("unreachable".to_string()))?;
+            Ok(Observation {
+                summary: format!("{} items", items.len()),
+                items: items
+                    .into_iter()
+                    .map(|id| ObservedItem {
+                        id: id.to_string(),
+                        label: format!("item {}", id),
+                    })
+                    .collect(),
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_only_new_items_are_news() {
+        let probe = Arc::new(Mutex::new(Some(vec!["1", "2"])));
+        struct Shared(Arc<Mutex<Option<Vec<&'static str>>>>);
+        #[async_trait]
+        impl Probe for Shared {
+            fn name(&self) -> &str {
+                "fixed"
+            }
+            async fn observe(&self) -> Result<Observation, ProbeError> {
+                FixedProbe(Mutex::new(self.0.lock().unwrap().clone()))
+                    .observe()
+                    .await
+            }
+        }
+        let checks = PreChecks::new(vec![Box::new(Shared(Arc::clone(&probe)))]);
+
+        // Nothing to compare with on the first run.
+        assert_eq!(
+            checks.run().await,
+            PreCheckOutcome::News("- fixed: 2 items (2 new: item 1; item 2)".to_string())
+        );
+        assert_eq!(checks.run().await, PreCheckOutcome::Quiet);
+
+        // An item going away is not news; one appearing is.
+        *probe.lock().unwrap() = Some(vec!["2"]);
+        assert_eq!(checks.run().await, PreCheckOutcome::Quiet);
+        *probe.lock().unwrap() = Some(vec!["2", "3"]);
+        assert_eq!(
+            checks.run().await,
+            PreCheckOutcome::News("- fixed: 2 items (1 new: item 3)".to_string())
+        );
+
+        // A failing probe never keeps the heartbeat quiet.
+        *probe.lock().unwrap() = None;
+        match checks.run().await {
+            PreCheckOutcome::News(report) => assert!(report.contains("check failed")),
+            PreCheckOutcome::Quiet => panic!("failure should count as news"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_probes_sharing_a_name_are_tracked_apart() {
+        // Like two feeds on one host: same name, different items.
+        let checks = PreChecks::new(vec![
+            Box::new(FixedProbe(Mutex::new(Some(vec!["a"])))),
+            Box::new(FixedProbe(Mutex::new(Some(vec!["b"])))),
+        ]);
+
+        assert!(matches!(checks.run().await, PreCheckOutcome::News(_)));
+        assert_eq!(checks.run().await, PreCheckOutcome::Quiet);
+    }
+
+    #[tokio::test]
+    async fn test_imap_unseen_uids() {
+        let (client, server) = tokio::io::duplex(4096);
+        let server = tokio::spawn(async move {
+            let mut server = BufReader::new(server);
+            server
+                .get_mut()
+                .write_all(b"* OK IMAP4rev1 ready\r\n")
+                .await
+                .unwrap();
+            let mut commands = Vec::new();
+            let mut line = String::new();
+            while server.read_line(&mut line).await.unwrap() > 0 {
+                let (tag, command) = line.trim_end().split_once(' ').unwrap();
+                let reply = match command.split_whitespace().next().unwrap() {
+                    "UID" => format!("* SEARCH 17 42\r\n{} OK SEARCH completed\r\n", tag),
+                    "EXAMINE" => format!("* 3 EXISTS\r\n{} OK [READ-ONLY] done\r\n", tag),
+                    _ => format!("{} OK done\r\n", tag),
+                };
+                commands.push(command.to_string());
+                server.get_mut().write_all(reply.as_bytes()).await.unwrap();
+                line.clear();
+                if command == "LOGOUT" {
+                    break;
+                }
+            }
+            commands
+        });
+
+        let uids = unseen_uids(
+            client,
+            "ada@example.com",
+            &SecretString::from("pa\"ss"),
+            "INBOX",
+        )
+        .await
+        .unwrap();
+        assert_eq!(uids, vec!["17", "42"]);
+        assert_eq!(
+            server.await.unwrap(),
+            vec![
+                r#"LOGIN "ada@example.com" "pa\"ss""#,
+                r#"EXAMINE "INBOX""#,
+                "UID SEARCH UNSEEN",
+                "LOGOUT",
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_imap_login_rejected() {
+        let (client, server) = tokio::io::duplex(4096);
+        tokio::spawn(async move {
+            let mut server = BufReader::new(server);
+            server.get_mut().write_all(b"* OK ready\r\n").await.unwrap();
+            let mut line = String::new();
+            server.read_line(&mut line).await.unwrap();
+            server
+                .get_mut()
+                .write_all(b"a1 NO [AUTHENTICATIONFAILED] bad password secret\r\n")
+                .await
+                .unwrap();
+        });
+
+        let err = unseen_uids(client, "ada", &SecretString::from("secret"), "INBOX")
+            .await
+            .unwrap_err();
+        assert_eq!(err.to_string(), "IMAP login rejected");
+    }
+
+    #[test]
+    fn test_calendar_upcoming_events() {
+        let ics = "BEGIN:VCALENDAR\r\n\
+                   BEGIN:VEVENT\r\nUID:standup\r\nSUMMARY:Stand\r\n up\r\nDTSTART:20261015T093000Z\r\nEND:VEVENT\r\n\
+                   BEGIN:VEVENT\r\nUID:lunch\r\nSUMMARY:Lunch\\, team\r\nDTSTART:20261015T120000Z\r\nEND:VEVENT\r\n\
+                   BEGIN:VEVENT\r\nUID:past\r\nSUMMARY:Old\r\nDTSTART:20261014T093000Z\r\nEND:VEVENT\r\n\
+                   END:VCALENDAR\r\n";
+        let events = parse_events(ics);
+        assert_eq!(events.len(), 3);
+        assert_eq!(events[0].summary, "Standup");
+        assert_eq!(events[1].summary, "Lunch, team");
+
+        let now = Utc.with_ymd_and_hms(2026, 10, 15, 9, 0, 0).unwrap();
+        let hour = Duration::from_secs(3600);
+        let observation = calendar_observation(ics, now, now + chrono::Duration::hours(1), hour);
+        assert_eq!(observation.items.len(), 1);
+        assert!(
+            observation
+                .summary
+                .starts_with("1 event(s) in the next 60 min; next: Standup")
+        );
+
+        let later = calendar_observation(ics, now, now + chrono::Duration::hours(4), hour);
+        assert_eq!(later.items.len(), 2);
+        assert_ne!(later.items[0].id, later.items[1].id);
+
+        assert_eq!(
+            parse_ics_time("", "20261015T093000Z"),
+            Some(Utc.with_ymd_and_hms(2026, 10, 15, 9, 30, 0).unwrap())
+        );
+        assert!(parse_ics_time("VALUE=DATE", "20261015").is_some());
+        assert!(parse_ics_time("TZID=Europe/Berlin", "20261015T093000").is_some());
+        assert_eq!(parse_ics_time("", "garbage"), None);
+    }
+
+    #[test]
+    fn test_feed_items() {
+        let rss = r#"<rss><channel><title>Blog</title>
+            <item><title><![CDATA[Release 2.0]]></title><guid isPermaLink="false">post-2</guid></item>
+            <item><title>Hello &amp; welcome</title><link>https://example.com/1</link></item>
+            </channel></rss>"#;
+        let observation = feed_observation(rss);
+        assert_eq!(
+            observation.items,
+            vec![
+                ObservedItem {
+                    id: "post-2".to_string(),
+                    label: "Release 2.0".to_string()
+                },
+                ObservedItem {
+                    id: "https://example.com/1".to_string(),
+                    label: "Hello & welcome".to_string()
+                },
+            ]
+        );
+        assert_eq!(observation.summary, "2 items, latest: Release 2.0");
+
+        let atom = r#"<feed><entry><id>tag:x,1</id><title>One</title><link href="https://x/1"/></entry>
+            <entry><title>Two</title><link rel="alternate" href="https://x/2"/></entry></feed>"#;
+        let ids: Vec<String> = feed_observation(atom)
+            .items
+            .into_iter()
+            .map(|i| i.id)
+            .collect();
+        assert_eq!(ids, vec!["tag:x,1", "https://x/2"]);
+        assert_eq!(feed_observation("not a feed").summary, "0 items");
+    }
+}
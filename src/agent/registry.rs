@@ -0,0 +1,777 @@
+//! Registry of named agents.
+//!
+//! Agents used to be implicit: one process, one configuration. The registry
+//! makes them first-class records in the database, each with:
+//!
+//! - its own workspace scope (the agent's ID is the workspace agent ID),
+//!   seeded with documents when the agent is created,
+//! - an optional model that replaces the configured one,
+//! - a toolset of tool name patterns (`*`, `memory_*`, exact names),
+//! - the channels it serves (`cli`, `http`, `gateway`, or a WASM channel
+//!   name; empty means every configured channel).
+//!
+//! Start a registered agent with `ironclaw run --agent <name>`. Running
+//! without `--agent` is the main agent, which keeps the global
+//! configuration. Disabled agents refuse to start.
+//!
+//! An agent (including the main one) can be forked into a new agent with the
+//! same configuration and a copy of its identity documents, optionally with
+//! its curated `MEMORY.md`. Daily logs and other notes stay behind.
+//!
+//! [`AgentRegistry::search`] searches every agent's workspace at once for
+//! the owner. Each result names the agent it came from; documents in a
+//! shared namespace are searched once and attributed to the namespace and
+//! the agents that mount it, rather than repeated for every member.
+
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+
+use chrono::{DateTime, Utc};
+use futures::TryStreamExt;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::db::Database;
+use crate::error::{DatabaseError, WorkspaceError};
+use crate::workspace::shared::{
+    DEFAULT_MEMBER, NamespaceError, SharedNamespaces, mount_path, namespace_scope,
+};
+use crate::workspace::{EmbeddingProvider, SearchConfig, SearchResult, Workspace, paths};
+
+/// Name reserved for the main agent (the workspace without an agent ID).
+pub const MAIN_AGENT_NAME: &str = "default";
+
+/// Channel names handled by the core runtime; anything else in an agent's
+/// channel list names a WASM channel.
+pub const BUILTIN_CHANNELS: &[&str] = &["cli", "http", "gateway"];
+
+/// Workspace documents that define who an agent is, copied by a fork along
+/// with everything under `context/`.
+const IDENTITY_PATHS: &[&str] = &[
+    paths::IDENTITY,
+    paths::SOUL,
+    paths::AGENTS,
+    paths::USER,
+    paths::HEARTBEAT,
+    paths::BOOT,
+    paths::TOOLS,
+];
+
+/// Errors from registry operations.
+#[derive(Debug, thiserror::Error)]
+pub enum AgentRegistryError {
+    #[error("Agent not found: {0}")]
+    NotFound(String),
+
+    #[error("Agent already exists: {0}")]
+    AlreadyExists(String),
+
+    #[error("Agent '{0}' is disabled")]
+    Disabled(String),
+
+    #[error("Invalid agent: {0}")]
+    Invalid(String),
+
+    #[error(transparent)]
+    Database(#[from] DatabaseError),
+
+    #[error(transparent)]
+    Workspace(#[from] WorkspaceError),
+
+    #[error(transparent)]
+    Namespace(#[from] NamespaceError),
+}
+
+/// A registered agent.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AgentDefinition {
+    pub id: Uuid,
+    /// Unique name (lowercase letters, digits, `-`, `_`).
+    pub name: String,
+    pub description: String,
+    pub enabled: bool,
+    /// Model to use instead of the configured one.
+    pub model: Option<String>,
+    /// Tools the agent may call (`*` for all, `prefix*` globs allowed).
+    pub tools: Vec<String>,
+    /// Channels the agent serves; empty means every configured channel.
+    pub channels: Vec<String>,
+    /// Documents written into the agent's workspace on creation (path to
+    /// content). Existing documents are never overwritten.
+    pub workspace_seed: BTreeMap<String, String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl AgentDefinition {
+    /// A new enabled agent with every tool and channel.
+    pub fn new(name: impl Into<String>) -> Self {
+        let now = Utc::now();
+        Self {
+            id: Uuid::new_v4(),
+            name: name.into(),
+            description: String::new(),
+            enabled: true,
+            model: None,
+            tools: vec!["*".to_string()],
+            channels: Vec::new(),
+            workspace_seed: BTreeMap::new(),
+            created_at: now,
+            updated_at: now,
+        }
+    }
+
+    /// Check the name, tool patterns, channels, and seed paths.
+    pub fn validate(&self) -> Result<(), AgentRegistryError> {
+        validate_name(&self.name)?;
+        if self.model.as_deref().is_some_and(|m| m.trim().is_empty()) {
+            return Err(AgentRegistryError::Invalid("model is empty".into()));
+        }
+        if self.tools.iter().any(|t| t.trim().is_empty()) {
+            return Err(AgentRegistryError::Invalid("empty tool pattern".into()));
+        }
+        if self.channels.iter().any(|c| c.trim().is_empty()) {
+            return Err(AgentRegistryError::Invalid("empty channel name".into()));
+        }
+        if self
+            .workspace_seed
+            .keys()
+            .any(|p| p.trim().trim_matches('/').is_empty())
+        {
+            return Err(AgentRegistryError::Invalid("empty seed path".into()));
+        }
+        Ok(())
+    }
+
+    /// Whether the agent serves `channel`.
+    pub fn serves_channel(&self, channel: &str) -> bool {
+        self.channels.is_empty() || self.channels.iter().any(|c| c == channel)
+    }
+
+    /// A new, enabled agent named `name` with this agent's configuration.
+    pub fn fork(&self, name: impl Into<String>) -> Self {
+        let mut fork = Self::new(name);
+        fork.description = self.description.clone();
+        fork.model = self.model.clone();
+        fork.tools = self.tools.clone();
+        fork.channels = self.channels.clone();
+        fork.workspace_seed = self.workspace_seed.clone();
+        fork
+    }
+}
+
+/// What [`AgentRegistry::fork`] copies from the source workspace.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ForkOptions {
+    /// Copy identity documents (`IDENTITY.md`, `SOUL.md`, `context/`,
+    /// `personas/`, ...).
+    #[serde(default = "default_true")]
+    pub copy_identity: bool,
+    /// Copy the curated long-term memory (`MEMORY.md`).
+    #[serde(default)]
+    pub copy_memory: bool,
+}
+
+impl Default for ForkOptions {
+    fn default() -> Self {
+        Self {
+            copy_identity: true,
+            copy_memory: false,
+        }
+    }
+}
+
+fn default_true() -> bool {
+    true
+}
+
+impl ForkOptions {
+    fn copies(&self, path: &str) -> bool {
+        if path == paths::MEMORY {
+            return self.copy_memory;
+        }
+        self.copy_identity
+            && (IDENTITY_PATHS.contains(&path)
+                || path.starts_with(paths::CONTEXT_DIR)
+                || path.starts_with(paths::PERSONAS_DIR))
+    }
+}
+
+/// Changes applied by [`AgentRegistry::configure`]. Absent fields are left
+/// alone; an empty `model` clears the override.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AgentUpdate {
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub model: Option<String>,
+    #[serde(default)]
+    pub tools: Option<Vec<String>>,
+    #[serde(default)]
+    pub channels: Option<Vec<String>>,
+    #[serde(default)]
+    pub enabled: Option<bool>,
+}
+
+impl AgentUpdate {
+    fn apply(self, agent: &mut AgentDefinition) {
+        if let Some(description) = self.description {
+            agent.description = description;
+        }
+        if let Some(model) = self.model {
+            agent.model = Some(model).filter(|m| !m.trim().is_empty());
+        }
+        if let Some(tools) = self.tools {
+            agent.tools = tools;
+        }
+        if let Some(channels) = self.channels {
+            agent.channels = channels;
+        }
+        if let Some(enabled) = self.enabled {
+            agent.enabled = enabled;
+        }
+    }
+}
+
+/// Where a result of [`AgentRegistry::search`] was found.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ResultSource {
+    /// An agent's own workspace (`id` is `None` for the main agent).
+    Agent { id: Option<Uuid>, name: String },
+    /// A shared namespace, with the names of the agents that mount it.
+    Namespace { name: String, members: Vec<String> },
+}
+
+/// A search result from one of the owner's workspaces.
+#[derive(Debug, Clone)]
+pub struct AgentSearchResult {
+    pub source: ResultSource,
+    /// Document path as the source's agents see it (namespace documents
+    /// include the mount directory).
+    pub path: String,
+    pub result: SearchResult,
+}
+
+/// Creates, configures, and looks up registered agents.
+pub struct AgentRegistry {
+    store: Arc<dyn Database>,
+    /// Workspace owner the agents' workspaces belong to.
+    owner_id: String,
+    /// Embedding provider for the agents' workspaces.
+    embeddings: Option<Arc<dyn EmbeddingProvider>>,
+}
+
+impl AgentRegistry {
+    pub fn new(store: Arc<dyn Database>) -> Self {
+        Self {
+            store,
+            owner_id: "default".to_string(),
+            embeddings: None,
+        }
+    }
+
+    /// Embed documents written to, and queries searched in, the agents'
+    /// workspaces.
+    pub fn with_embeddings(mut self, provider: Arc<dyn EmbeddingProvider>) -> Self {
+        self.embeddings = Some(provider);
+        self
+    }
+
+    /// Register a new agent and seed its workspace.
+    pub async fn create(
+        &self,
+        mut agent: AgentDefinition,
+    ) -> Result<AgentDefinition, AgentRegistryError> {
+        agent.validate()?;
+        if self.store.get_agent_by_name(&agent.name).await?.is_some() {
+            return Err(AgentRegistryError::AlreadyExists(agent.name));
+        }
+        let now = Utc::now();
+        agent.created_at = now;
+        agent.updated_at = now;
+        self.store.create_agent(&agent).await?;
+        self.seed_workspace(&agent).await?;
+        Ok(agent)
+    }
+
+    /// All registered agents, by name.
+    pub async fn list(&self) -> Result<Vec<AgentDefinition>, AgentRegistryError> {
+        Ok(self.store.list_agents().await?)
+    }
+
+    /// Look up an agent by ID or name.
+    pub async fn get(&self, key: &str) -> Result<AgentDefinition, AgentRegistryError> {
+        let found = match Uuid::parse_str(key) {
+            Ok(id) => self.store.get_agent(id).await?,
+            Err(_) => self.store.get_agent_by_name(key).await?,
+        };
+        found.ok_or_else(|| AgentRegistryError::NotFound(key.to_string()))
+    }
+
+    /// Apply `update` to an agent and persist it.
+    pub async fn configure(
+        &self,
+        key: &str,
+        update: AgentUpdate,
+    ) -> Result<AgentDefinition, AgentRegistryError> {
+        let mut agent = self.get(key).await?;
+        update.apply(&mut agent);
+        agent.validate()?;
+        agent.updated_at = Utc::now();
+        self.store.update_agent(&agent).await?;
+        Ok(agent)
+    }
+
+    /// Enable or disable an agent.
+    pub async fn set_enabled(
+        &self,
+        key: &str,
+        enabled: bool,
+    ) -> Result<AgentDefinition, AgentRegistryError> {
+        self.configure(
+            key,
+            AgentUpdate {
+                enabled: Some(enabled),
+                ..Default::default()
+            },
+        )
+        .await
+    }
+
+    /// Remove an agent from the registry. Its workspace documents are kept.
+    pub async fn remove(&self, key: &str) -> Result<AgentDefinition, AgentRegistryError> {
+        let agent = self.get(key).await?;
+        self.store.delete_agent(agent.id).await?;
+        Ok(agent)
+    }
+
+    /// Look up an agent that is about to be started.
+    pub async fn resolve_for_run(&self, key: &str) -> Result<AgentDefinition, AgentRegistryError> {
+        let agent = self.get(key).await?;
+        if !agent.enabled {
+            return Err(AgentRegistryError::Disabled(agent.name));
+        }
+        Ok(agent)
+    }
+
+    /// Register a copy of the agent `key` (or the main agent, as
+    /// [`MAIN_AGENT_NAME`]) named `name`, copying the workspace documents
+    /// selected by `options`. Copied documents replace seeded ones.
+    pub async fn fork(
+        &self,
+        key: &str,
+        name: &str,
+        options: ForkOptions,
+    ) -> Result<AgentDefinition, AgentRegistryError> {
+        let (source, source_workspace) = if key == MAIN_AGENT_NAME {
+            (
+                AgentDefinition::new(MAIN_AGENT_NAME),
+                Workspace::new_with_db(&self.owner_id, Arc::clone(&self.store)),
+            )
+        } else {
+            let source = self.get(key).await?;
+            let workspace = self.workspace(&source);
+            (source, workspace)
+        };
+
+        let fork = self.create(source.fork(name)).await?;
+        let workspace = self.workspace(&fork);
+        let mut paths = std::pin::pin!(source_workspace.paths());
+        while let Some(path) = paths.try_next().await? {
+            if !options.copies(&path) {
+                continue;
+            }
+            let doc = source_workspace.read(&path).await?;
+            workspace.write(&path, &doc.content).await?;
+        }
+        Ok(fork)
+    }
+
+    /// The agent's own workspace.
+    pub fn workspace(&self, agent: &AgentDefinition) -> Workspace {
+        self.scope(Some(agent.id))
+    }
+
+    /// The workspace stored under `agent_id` (`None` for the main agent),
+    /// without shared mounts.
+    fn scope(&self, agent_id: Option<Uuid>) -> Workspace {
+        let mut workspace = Workspace::new_with_db(&self.owner_id, Arc::clone(&self.store));
+        if let Some(id) = agent_id {
+            workspace = workspace.with_agent(id);
+        }
+        if let Some(ref provider) = self.embeddings {
+            workspace = workspace.with_embeddings(Arc::clone(provider));
+        }
+        workspace
+    }
+
+    /// Search the workspaces of the main agent, every registered agent
+    /// (enabled or not), and every shared namespace. Returns the
+    /// `config.limit` best results overall, best first.
+    pub async fn search(
+        &self,
+        query: &str,
+        config: SearchConfig,
+    ) -> Result<Vec<AgentSearchResult>, AgentRegistryError> {
+        let agents = self.list().await?;
+        let mut names: HashMap<String, String> = agents
+            .iter()
+            .map(|a| (a.id.to_string(), a.name.clone()))
+            .collect();
+        names.insert(DEFAULT_MEMBER.to_string(), MAIN_AGENT_NAME.to_string());
+
+        let mut scopes = vec![(
+            None,
+            ResultSource::Agent {
+                id: None,
+                name: MAIN_AGENT_NAME.to_string(),
+            },
+            String::new(),
+        )];
+        for agent in &agents {
+            scopes.push((
+                Some(agent.id),
+                ResultSource::Agent {
+                    id: Some(agent.id),
+                    name: agent.name.clone(),
+                },
+                String::new(),
+            ));
+        }
+        let namespaces = SharedNamespaces::load(Arc::clone(&self.store), &self.owner_id).await?;
+        for (name, ns) in &namespaces.namespaces {
+            let mut members: Vec<String> = ns
+                .members
+                .keys()
+                .map(|m| names.get(m).cloned().unwrap_or_else(|| m.clone()))
+                .collect();
+            members.sort();
+            scopes.push((
+                Some(namespace_scope(name)),
+                ResultSource::Namespace {
+                    name: name.clone(),
+                    members,
+                },
+                format!("{}/", mount_path(name, ns)),
+            ));
+        }
+
+        let mut found = Vec::new();
+        for (agent_id, source, prefix) in scopes {
+            let results = self
+                .scope(agent_id)
+                .search_with_config(query, config.clone())
+                .await?;
+            let mut paths: HashMap<Uuid, String> = HashMap::new();
+            for result in results {
+                let path = match paths.get(&result.document_id) {
+                    Some(path) => path.clone(),
+                    None => {
+                        let doc = self.store.get_document_by_id(result.document_id).await?;
+                        let path = format!("{}{}", prefix, doc.path);
+                        paths.insert(result.document_id, path.clone());
+                        path
+                    }
+                };
+                found.push(AgentSearchResult {
+                    source: source.clone(),
+                    path,
+                    result,
+                });
+            }
+        }
+        found.sort_by(|a, b| b.result.score.total_cmp(&a.result.score));
+        found.truncate(config.limit);
+        Ok(found)
+    }
+
+    async fn seed_workspace(&self, agent: &AgentDefinition) -> Result<(), AgentRegistryError> {
+        let workspace = self.workspace(agent);
+        for (path, content) in &agent.workspace_seed {
+            if !workspace.exists(path).await? {
+                workspace.write(path, content).await?;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn validate_name(name: &str) -> Result<(), AgentRegistryError> {
+    if name.is_empty() || name.len() > 64 {
+        return Err(AgentRegistryError::Invalid(
+            "name must be 1-64 characters".into(),
+        ));
+    }
+    if !name
+        .chars()
+        .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-' || c == '_')
+    {
+        return Err(AgentRegistryError::Invalid(format!(
+            "name '{}' may only contain lowercase letters, digits, '-' and '_'",
+            name
+        )));
+    }
+    if name == MAIN_AGENT_NAME {
+        return Err(AgentRegistryError::Invalid(format!(
+            "'{}' is reserved for the main agent",
+            MAIN_AGENT_NAME
+        )));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_name() {
+        assert!(validate_name("research-1").is_ok());
+        assert!(validate_name("Research").is_err());
+        assert!(validate_name("has space").is_err());
+        assert!(validate_name("").is_err());
+        assert!(validate_name(MAIN_AGENT_NAME).is_err());
+    }
+
+    #[test]
+    fn test_update_clears_model_with_empty_string() {
+        let mut agent = AgentDefinition::new("writer");
+        agent.model = Some("gpt-4o".to_string());
+
+        AgentUpdate {
+            model: Some(String::new()),
+            channels: Some(vec!["telegram".to_string()]),
+            ..Default::default()
+        }
+        .apply(&mut agent);
+
+        assert_eq!(agent.model, None);
+        assert!(agent.serves_channel("telegram"));
+        assert!(!agent.serves_channel("gateway"));
+        assert_eq!(agent.tools, vec!["*"]);
+    }
+
+    #[test]
+    fn test_fork_options_select_documents() {
+        let defaults = ForkOptions::default();
+        assert!(defaults.copies("IDENTITY.md"));
+        assert!(defaults.copies("context/vision.md"));
+        assert!(defaults.copies("personas/work/SOUL.md"));
+        assert!(!defaults.copies("MEMORY.md"));
+        assert!(!defaults.copies("daily/2026-01-01.md"));
+
+        let memory_only = ForkOptions {
+            copy_identity: false,
+            copy_memory: true,
+        };
+        assert!(memory_only.copies("MEMORY.md"));
+        assert!(!memory_only.copies("SOUL.md"));
+    }
+
+    #[cfg(feature = "libsql")]
+    #[tokio::test]
+    async fn test_fork_copies_config_and_identity() {
+        use crate::db::libsql_backend::test_db;
+
+        let (db, _dir) = test_db().await;
+        let registry = AgentRegistry::new(db);
+
+        let mut source = AgentDefinition::new("writer");
+        source.model = Some("gpt-4o".to_string());
+        source.tools = vec!["memory_*".to_string()];
+        source
+            .workspace_seed
+            .insert("IDENTITY.md".to_string(), "# Seed".to_string());
+        let source = registry.create(source).await.unwrap();
+        let ws = registry.workspace(&source);
+        ws.write("IDENTITY.md", "# Trained writer").await.unwrap();
+        ws.write("MEMORY.md", "likes short sentences")
+            .await
+            .unwrap();
+        ws.write("daily/2026-01-01.md", "notes").await.unwrap();
+
+        let fork = registry
+            .fork("writer", "editor", ForkOptions::default())
+            .await
+            .unwrap();
+        assert_ne!(fork.id, source.id);
+        assert_eq!(fork.model.as_deref(), Some("gpt-4o"));
+        assert_eq!(fork.tools, vec!["memory_*"]);
+
+        let forked = registry.workspace(&fork);
+        assert_eq!(
+            forked.read("IDENTITY.md").await.unwrap().content,
+            "# Trained writer"
+        );
+        assert!(!forked.exists("MEMORY.md").await.unwrap());
+        assert!(!forked.exists("daily/2026-01-01.md").await.unwrap());
+
+        let with_memory = registry
+            .fork(
+                "writer",
+                "archivist",
+                ForkOptions {
+                    copy_identity: false,
+                    copy_memory: true,
+                },
+            )
+            .await
+            .unwrap();
+        let archive = registry.workspace(&with_memory);
+        assert_eq!(
+            archive.read("MEMORY.md").await.unwrap().content,
+            "likes short sentences"
+        );
+        // Not copied, so the seed is kept.
+        assert_eq!(archive.read("IDENTITY.md").await.unwrap().content, "# Seed");
+    }
+
+    #[cfg(feature = "libsql")]
+    #[tokio::test]
+    async fn test_registry_lifecycle() {
+        use crate::db::libsql_backend::test_db;
+
+        let (db, _dir) = test_db().await;
+        let registry = AgentRegistry::new(db);
+
+        let mut agent = AgentDefinition::new("research");
+        agent.tools = vec!["memory_*".to_string(), "http".to_string()];
+        agent
+            .workspace_seed
+            .insert("IDENTITY.md".to_string(), "# Researcher".to_string());
+        let created = registry.create(agent).await.unwrap();
+
+        assert!(matches!(
+            registry.create(AgentDefinition::new("research")).await,
+            Err(AgentRegistryError::AlreadyExists(_))
+        ));
+
+        let doc = registry
+            .workspace(&created)
+            .read("IDENTITY.md")
+            .await
+            .unwrap();
+        assert_eq!(doc.content, "# Researcher");
+        assert_eq!(doc.agent_id, Some(created.id));
+
+        let by_id = registry.get(&created.id.to_string()).await.unwrap();
+        assert_eq!(by_id.name, "research");
+        assert_eq!(by_id.workspace_seed, created.workspace_seed);
+
+        let configured = registry
+            .configure(
+                "research",
+                AgentUpdate {
+                    model: Some("claude-sonnet-4".to_string()),
+                    ..Default::default()
+                },
+            )
+            .await
+            .unwrap();
+        assert_eq!(configured.model.as_deref(), Some("claude-sonnet-4"));
+        assert_eq!(configured.tools, vec!["memory_*", "http"]);
+
+        registry.set_enabled("research", false).await.unwrap();
+        assert!(matches!(
+            registry.resolve_for_run("research").await,
+            Err(AgentRegistryError::Disabled(_))
+        ));
+        assert_eq!(registry.list().await.unwrap().len(), 1);
+
+        registry.remove("research").await.unwrap();
+        assert!(matches!(
+            registry.get("research").await,
+            Err(AgentRegistryError::NotFound(_))
+        ));
+    }
+
+    #[cfg(feature = "libsql")]
+    #[tokio::test]
+    async fn test_search_attributes_results_to_agents_and_namespaces() {
+        use crate::db::libsql_backend::test_db;
+        use crate::workspace::shared::SHARED_NAMESPACES_SETTING_KEY;
+
+        let (store, _dir) = test_db().await;
+        let registry = AgentRegistry::new(Arc::clone(&store));
+
+        let research = registry
+            .create(AgentDefinition::new("research"))
+            .await
+            .unwrap();
+        registry
+            .workspace(&research)
+            .write("notes/k8s.md", "Kubernetes cluster upgrade notes")
+            .await
+            .unwrap();
+        registry
+            .scope(None)
+            .write("MEMORY.md", "The user runs Kubernetes at work")
+            .await
+            .unwrap();
+        registry
+            .scope(None)
+            .write("daily/2026-01-01.md", "Nothing relevant")
+            .await
+            .unwrap();
+
+        store
+            .set_setting(
+                "default",
+                SHARED_NAMESPACES_SETTING_KEY,
+                &serde_json::json!({
+                    "namespaces": {
+                        "team": {
+                            "members": {
+                                "default": "read_write",
+                                research.id.to_string(): "read"
+                            }
+                        }
+                    }
+                }),
+            )
+            .await
+            .unwrap();
+        registry
+            .scope(Some(namespace_scope("team")))
+            .write("runbook.md", "Kubernetes on-call runbook")
+            .await
+            .unwrap();
+
+        let results = registry
+            .search("kubernetes", SearchConfig::default().with_limit(10))
+            .await
+            .unwrap();
+        let mut found: Vec<(ResultSource, &str)> = results
+            .iter()
+            .map(|r| (r.source.clone(), r.path.as_str()))
+            .collect();
+        found.sort_by(|a, b| a.1.cmp(b.1));
+        assert_eq!(
+            found,
+            vec![
+                (
+                    ResultSource::Agent {
+                        id: None,
+                        name: "default".to_string()
+                    },
+                    "MEMORY.md"
+                ),
+                (
+                    ResultSource::Agent {
+                        id: Some(research.id),
+                        name: "research".to_string()
+                    },
+                    "notes/k8s.md"
+                ),
+                (
+                    ResultSource::Namespace {
+                        name: "team".to_string(),
+                        members: vec!["default".to_string(), "research".to_string()]
+                    },
+                    "shared/team/runbook.md"
+                ),
+            ]
+        );
+    }
+}
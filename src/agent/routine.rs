@@ -17,6 +17,7 @@
 //!                                     └──────────────┘
 //! ```
 
+use std::collections::BTreeMap;
 use std::collections::hash_map::DefaultHasher;
 use std::hash::{Hash, Hasher};
 use std::str::FromStr;
@@ -63,11 +64,13 @@ pub enum Trigger {
         /// Regex pattern to match against message content.
         pattern: String,
     },
-    /// Fire on incoming webhook POST to /hooks/routine/{id}.
+    /// Fire on incoming webhook POST to /hooks/routine/{id}. The request
+    /// body is passed to the routine as context.
     Webhook {
         /// Optional webhook path suffix (defaults to routine id).
         path: Option<String>,
-        /// Optional shared secret for HMAC validation.
+        /// Shared secret the request must be signed or authorized with.
+        /// Without one the routine can't be fired over HTTP.
         secret: Option<String>,
     },
     /// Only fires via tool call or CLI.
@@ -124,6 +127,18 @@ impl Trigger {
         }
     }
 
+    /// Whether a POST to `/hooks/routine/{path}` is meant for this trigger:
+    /// `path` is its configured path, or the routine's ID if it has none.
+    pub fn matches_webhook(&self, routine_id: Uuid, path: &str) -> bool {
+        match self {
+            Trigger::Webhook {
+                path: Some(own), ..
+            } => own.trim_matches('/') == path.trim_matches('/'),
+            Trigger::Webhook { path: None, .. } => routine_id.to_string() == path,
+            _ => false,
+        }
+    }
+
     /// Serialize trigger-specific config to JSON for DB storage.
     pub fn to_config_json(&self) -> serde_json::Value {
         match self {
@@ -166,6 +181,15 @@ pub enum RoutineAction {
         #[serde(default = "default_max_iterations")]
         max_iterations: u32,
     },
+    /// Job launched from a job template (see [`crate::agent::template`]).
+    /// A webhook payload fills the template's `payload` variable, if it has one.
+    Template {
+        /// Template name.
+        template: String,
+        /// Variable values.
+        #[serde(default)]
+        variables: BTreeMap<String, String>,
+    },
 }
 
 fn default_max_tokens() -> u32 {
@@ -182,6 +206,7 @@ impl RoutineAction {
         match self {
             RoutineAction::Lightweight { .. } => "lightweight",
             RoutineAction::FullJob { .. } => "full_job",
+            RoutineAction::Template { .. } => "template",
         }
     }
 
@@ -235,6 +260,26 @@ impl RoutineAction {
                     max_iterations,
                 })
             }
+            "template" => {
+                let template = config
+                    .get("template")
+                    .and_then(|v| v.as_str())
+                    .ok_or("template action missing 'template'")?
+                    .to_string();
+                let variables = config
+                    .get("variables")
+                    .and_then(|v| v.as_object())
+                    .map(|obj| {
+                        obj.iter()
+                            .filter_map(|(k, v)| v.as_str().map(|v| (k.clone(), v.to_string())))
+                            .collect()
+                    })
+                    .unwrap_or_default();
+                Ok(RoutineAction::Template {
+                    template,
+                    variables,
+                })
+            }
             other => Err(format!("unknown action type: {other}")),
         }
     }
@@ -260,6 +305,13 @@ impl RoutineAction {
                 "description": description,
                 "max_iterations": max_iterations,
             }),
+            RoutineAction::Template {
+                template,
+                variables,
+            } => serde_json::json!({
+                "template": template,
+                "variables": variables,
+            }),
         }
     }
 }
@@ -362,6 +414,38 @@ pub struct RoutineRun {
     pub created_at: DateTime<Utc>,
 }
 
+/// Credentials sent with an inbound webhook request.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WebhookAuth<'a> {
+    /// `sha256=<hex>` HMAC-SHA256 of the body, keyed with the secret
+    /// (`X-Hub-Signature-256` or `X-Ironclaw-Signature`).
+    Signature(&'a str),
+    /// The secret itself (`Authorization: Bearer <secret>`), for senders
+    /// that can't sign.
+    Token(&'a str),
+}
+
+/// Check inbound webhook credentials against a routine's `secret`.
+pub fn verify_webhook(secret: &str, body: &[u8], auth: WebhookAuth<'_>) -> bool {
+    use subtle::ConstantTimeEq;
+
+    match auth {
+        WebhookAuth::Signature(signature) => {
+            let Some(hex) = signature.trim().strip_prefix("sha256=") else {
+                return false;
+            };
+            let key = ring::hmac::Key::new(ring::hmac::HMAC_SHA256, secret.as_bytes());
+            let tag = ring::hmac::sign(&key, body);
+            let expected: String = tag.as_ref().iter().map(|b| format!("{:02x}", b)).collect();
+            expected
+                .as_bytes()
+                .ct_eq(hex.to_ascii_lowercase().as_bytes())
+                .into()
+        }
+        WebhookAuth::Token(token) => token.as_bytes().ct_eq(secret.as_bytes()).into(),
+    }
+}
+
 /// Compute a content hash for event dedup.
 pub fn content_hash(content: &str) -> u64 {
     let mut hasher = DefaultHasher::new();
@@ -378,8 +462,11 @@ pub fn next_cron_fire(schedule: &str) -> Result<Option<DateTime<Utc>>, String> {
 
 #[cfg(test)]
 mod tests {
+    use uuid::Uuid;
+
     use crate::agent::routine::{
-        RoutineAction, RoutineGuardrails, RunStatus, Trigger, content_hash, next_cron_fire,
+        RoutineAction, RoutineGuardrails, RunStatus, Trigger, WebhookAuth, content_hash,
+        next_cron_fire, verify_webhook,
     };
 
     #[test]
@@ -434,6 +521,20 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_action_template_roundtrip() {
+        let action = RoutineAction::Template {
+            template: "weekly".to_string(),
+            variables: BTreeMap::from([("repo".to_string(), "ironclaw".to_string())]),
+        };
+        let json = action.to_config_json();
+        let parsed = RoutineAction::from_db("template", json).expect("parse template");
+        assert!(
+            matches!(parsed, RoutineAction::Template { template, variables }
+            if template == "weekly" && variables["repo"] == "ironclaw")
+        );
+    }
+
     #[test]
     fn test_run_status_display_parse() {
         for status in [
@@ -506,4 +607,66 @@ mod tests {
         );
         assert_eq!(Trigger::Manual.type_tag(), "manual");
     }
+
+    #[test]
+    fn test_matches_webhook() {
+        let id = Uuid::new_v4();
+        let named = Trigger::Webhook {
+            path: Some("ci-failed".to_string()),
+            secret: None,
+        };
+        assert!(named.matches_webhook(id, "ci-failed"));
+        assert!(named.matches_webhook(id, "/ci-failed/"));
+        assert!(!named.matches_webhook(id, &id.to_string()));
+
+        let unnamed = Trigger::Webhook {
+            path: None,
+            secret: None,
+        };
+        assert!(unnamed.matches_webhook(id, &id.to_string()));
+        assert!(!unnamed.matches_webhook(id, "ci-failed"));
+        assert!(!Trigger::Manual.matches_webhook(id, &id.to_string()));
+    }
+
+    #[test]
+    fn test_verify_webhook() {
+        let body = br#"{"alert":"disk full"}"#;
+        let signature = "sha256=7fc3d4ce0b3346c978cd23e0e2537800148e65c7f5ab0760fa711265a74ee1ac";
+        assert!(verify_webhook(
+            "hunter2",
+            body,
+            WebhookAuth::Signature(signature)
+        ));
+        assert!(verify_webhook(
+            "hunter2",
+            body,
+            WebhookAuth::Signature(&signature.to_uppercase().replace("SHA256=", "sha256="))
+        ));
+        assert!(!verify_webhook(
+            "hunter3",
+            body,
+            WebhookAuth::Signature(signature)
+        ));
+        assert!(!verify_webhook(
+            "hunter2",
+            b"{}",
+            WebhookAuth::Signature(signature)
+        ));
+        assert!(!verify_webhook(
+            "hunter2",
+            body,
+            WebhookAuth::Signature(signature.trim_start_matches("sha256="))
+        ));
+
+        assert!(verify_webhook(
+            "hunter2",
+            body,
+            WebhookAuth::Token("hunter2")
+        ));
+        assert!(!verify_webhook(
+            "hunter2",
+            body,
+            WebhookAuth::Token("hunter")
+        ));
+    }
 }
@@ -0,0 +1,692 @@
+//! Scenario regression tests for prompts and tool descriptions.
+//!
+//! A [`Scenario`] is a scripted conversation: user messages, canned tool
+//! outputs, and expectations about what the agent does with them. The
+//! [`ScenarioRunner`] plays it against a real model the way a chat turn
+//! runs: the same [`Reasoning`] prompt, the same tool-call loop and nudge,
+//! the same sanitizing of tool output. Tools never execute; every call is
+//! answered from the scenario's mocks, and a call without a mock fails the
+//! turn.
+//!
+//! Each turn can assert on the tools called (an exact sequence, or tools
+//! that must or must not appear) and on the final answer (substrings, or a
+//! regex). Scenarios are JSON files, which YAML tooling also reads:
+//!
+//! ```json
+//! {
+//!   "name": "dentist lookup",
+//!   "mocks": [{"tool": "memory_search", "output": "Dentist: Tuesday 3pm"}],
+//!   "turns": [{
+//!     "user": "When is my dentist appointment?",
+//!     "expect": {"tool_calls": ["memory_search"], "answer_contains": ["Tuesday"]}
+//!   }]
+//! }
+//! ```
+//!
+//! [`Scenario::from_transcript`] turns a recorded job transcript into a
+//! scenario that expects the recorded tool calls, answered with the
+//! recorded results.
+
+use std::path::Path;
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+
+use crate::agent::agent_loop::{MAX_TOOL_ITERATIONS, TOOL_NUDGE};
+use crate::agent::transcript::{RecordedToolResults, TranscriptEntry};
+use crate::config::SafetyConfig;
+use crate::error::LlmError;
+use crate::llm::{
+    ChatMessage, LlmProvider, Reasoning, ReasoningContext, RespondResult, Role, ToolCall,
+    ToolDefinition,
+};
+use crate::safety::SafetyLayer;
+
+/// Errors loading or running a scenario.
+#[derive(Debug, thiserror::Error)]
+pub enum ScenarioError {
+    #[error("Invalid scenario: {0}")]
+    Invalid(String),
+
+    #[error("Failed to read scenario {path}: {reason}")]
+    Io { path: String, reason: String },
+
+    #[error("LLM error in scenario: {0}")]
+    Llm(#[from] LlmError),
+}
+
+/// A scripted conversation and what the agent should do in it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Scenario {
+    pub name: String,
+    /// Workspace prompt (identity files) the agent would have loaded.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub system_prompt: Option<String>,
+    /// Tools offered to the model; all the runner's tools if unset.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tools: Option<Vec<String>>,
+    /// Definitions for tools the runner doesn't provide (MCP, WASM, ...).
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tool_definitions: Vec<ToolDefinition>,
+    /// Canned tool outputs.
+    #[serde(default)]
+    pub mocks: Vec<ToolMock>,
+    pub turns: Vec<ScenarioTurn>,
+}
+
+/// Canned output for calls to a tool.
+///
+/// Mocks are used in order: a call takes the first unused mock for its
+/// tool whose `args` (if given) equal the call's arguments. Once all are
+/// used, matching mocks are reused.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ToolMock {
+    pub tool: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub args: Option<serde_json::Value>,
+    pub output: String,
+}
+
+/// One user message and the expectations for the agent's handling of it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScenarioTurn {
+    pub user: String,
+    #[serde(default)]
+    pub expect: Expectations,
+}
+
+/// Assertions on a turn. Unset fields aren't checked.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Expectations {
+    /// Exact sequence of tools called.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tool_calls: Option<Vec<String>>,
+    /// Tools that must be called at least once.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub tools_used: Vec<String>,
+    /// Tools that must not be called.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub tools_not_used: Vec<String>,
+    /// Substrings the answer must contain (case-insensitive).
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub answer_contains: Vec<String>,
+    /// Substrings the answer must not contain (case-insensitive).
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub answer_excludes: Vec<String>,
+    /// Regex the answer must match.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub answer_matches: Option<String>,
+}
+
+impl Expectations {
+    /// Failed assertions for `outcome`, as readable messages.
+    pub fn check(&self, outcome: &TurnOutcome) -> Vec<String> {
+        let mut failures = Vec::new();
+        let called = outcome.tool_names();
+
+        if let Some(ref expected) = self.tool_calls
+            && called != *expected
+        {
+            failures.push(format!(
+                "expected tool calls [{}], got [{}]",
+                expected.join(", "),
+                called.join(", ")
+            ));
+        }
+        for tool in &self.tools_used {
+            if !called.contains(tool) {
+                failures.push(format!("expected a call to {}", tool));
+            }
+        }
+        for tool in &self.tools_not_used {
+            if called.contains(tool) {
+                failures.push(format!("{} should not have been called", tool));
+            }
+        }
+
+        let answer = outcome.answer.as_deref().unwrap_or_default();
+        let lower = answer.to_lowercase();
+        for needle in &self.answer_contains {
+            if !lower.contains(&needle.to_lowercase()) {
+                failures.push(format!("answer should contain \"{}\"", needle));
+            }
+        }
+        for needle in &self.answer_excludes {
+            if lower.contains(&needle.to_lowercase()) {
+                failures.push(format!("answer should not contain \"{}\"", needle));
+            }
+        }
+        if let Some(ref pattern) = self.answer_matches {
+            match regex::Regex::new(pattern) {
+                Ok(re) if re.is_match(answer) => {}
+                Ok(_) => failures.push(format!("answer should match /{}/", pattern)),
+                Err(e) => failures.push(format!("invalid answer_matches /{}/: {}", pattern, e)),
+            }
+        }
+        failures
+    }
+}
+
+impl Scenario {
+    /// Parse and validate a scenario from JSON.
+    pub fn from_json(json: &str) -> Result<Self, ScenarioError> {
+        let scenario: Self =
+            serde_json::from_str(json).map_err(|e| ScenarioError::Invalid(e.to_string()))?;
+        scenario.validate()?;
+        Ok(scenario)
+    }
+
+    /// Read a scenario file.
+    pub fn load(path: &Path) -> Result<Self, ScenarioError> {
+        let json = std::fs::read_to_string(path).map_err(|e| ScenarioError::Io {
+            path: path.display().to_string(),
+            reason: e.to_string(),
+        })?;
+        Self::from_json(&json).map_err(|e| match e {
+            ScenarioError::Invalid(reason) => {
+                ScenarioError::Invalid(format!("{}: {}", path.display(), reason))
+            }
+            other => other,
+        })
+    }
+
+    fn validate(&self) -> Result<(), ScenarioError> {
+        if self.turns.is_empty() {
+            return Err(ScenarioError::Invalid(format!(
+                "'{}' has no turns",
+                self.name
+            )));
+        }
+        for turn in &self.turns {
+            if let Some(ref pattern) = turn.expect.answer_matches {
+                regex::Regex::new(pattern).map_err(|e| {
+                    ScenarioError::Invalid(format!("answer_matches /{}/: {}", pattern, e))
+                })?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Build a scenario from a recorded job transcript.
+    ///
+    /// Each user message becomes a turn expecting the tool calls the model
+    /// made before the next one; the recorded tool results become mocks
+    /// bound to their exact arguments, and the recorded tool definitions
+    /// fill in tools the runner lacks. The wording of answers isn't
+    /// asserted, since it changes from run to run.
+    pub fn from_transcript(
+        name: impl Into<String>,
+        transcript: &[TranscriptEntry],
+    ) -> Result<Self, ScenarioError> {
+        let last = transcript
+            .last()
+            .ok_or_else(|| ScenarioError::Invalid("transcript is empty".to_string()))?;
+
+        let mut turns: Vec<ScenarioTurn> = Vec::new();
+        let mut calls: Vec<Vec<String>> = Vec::new();
+        for message in &last.request.messages {
+            match message.role {
+                Role::User if message.content != TOOL_NUDGE => {
+                    turns.push(ScenarioTurn {
+                        user: message.content.clone(),
+                        expect: Expectations::default(),
+                    });
+                    calls.push(Vec::new());
+                }
+                Role::Assistant => {
+                    if let Some(current) = calls.last_mut() {
+                        current.extend(message.tool_calls.iter().flatten().map(|c| c.name.clone()));
+                    }
+                }
+                _ => {}
+            }
+        }
+        if let Some(current) = calls.last_mut() {
+            current.extend(last.response.tool_calls.iter().map(|c| c.name.clone()));
+        }
+        for (turn, calls) in turns.iter_mut().zip(calls) {
+            turn.expect.tool_calls = Some(calls);
+        }
+
+        let mocks = RecordedToolResults::collect(transcript)
+            .iter()
+            .map(|(call, output)| ToolMock {
+                tool: call.name.clone(),
+                args: Some(call.arguments.clone()),
+                output: output.to_string(),
+            })
+            .collect();
+
+        let scenario = Self {
+            name: name.into(),
+            system_prompt: None,
+            tools: None,
+            tool_definitions: last.request.tools.clone(),
+            mocks,
+            turns,
+        };
+        scenario.validate()?;
+        Ok(scenario)
+    }
+}
+
+/// What the agent did in one turn.
+#[derive(Debug, Clone, Serialize)]
+pub struct TurnOutcome {
+    pub user: String,
+    pub tool_calls: Vec<ToolCall>,
+    /// Final text answer; `None` if the turn hit the iteration limit.
+    pub answer: Option<String>,
+    /// Tools called without a matching mock.
+    pub unmocked: Vec<String>,
+}
+
+impl TurnOutcome {
+    fn tool_names(&self) -> Vec<String> {
+        self.tool_calls.iter().map(|c| c.name.clone()).collect()
+    }
+}
+
+/// A turn's outcome and its failed assertions.
+#[derive(Debug, Clone, Serialize)]
+pub struct TurnReport {
+    pub outcome: TurnOutcome,
+    pub failures: Vec<String>,
+}
+
+/// Result of running a scenario.
+#[derive(Debug, Clone, Serialize)]
+pub struct ScenarioReport {
+    pub name: String,
+    pub model: String,
+    pub turns: Vec<TurnReport>,
+}
+
+impl ScenarioReport {
+    pub fn passed(&self) -> bool {
+        self.turns.iter().all(|t| t.failures.is_empty())
+    }
+}
+
+/// Plays scenarios against a model with the chat loop's prompt and rules.
+pub struct ScenarioRunner {
+    llm: Arc<dyn LlmProvider>,
+    safety: Arc<SafetyLayer>,
+    tools: Vec<ToolDefinition>,
+}
+
+impl ScenarioRunner {
+    /// Run against `llm`, offering `tools` (usually
+    /// [`ToolRegistry::tool_definitions`](crate::tools::ToolRegistry::tool_definitions)).
+    pub fn new(llm: Arc<dyn LlmProvider>, tools: Vec<ToolDefinition>) -> Self {
+        Self {
+            llm,
+            safety: Arc::new(SafetyLayer::new(&SafetyConfig {
+                max_output_length: 100_000,
+                injection_check_enabled: true,
+            })),
+            tools,
+        }
+    }
+
+    /// Sanitize mocked tool output with this safety layer.
+    pub fn with_safety(mut self, safety: Arc<SafetyLayer>) -> Self {
+        self.safety = safety;
+        self
+    }
+
+    /// Definitions offered to the model for `scenario`.
+    fn tools_for(&self, scenario: &Scenario) -> Vec<ToolDefinition> {
+        let mut tools: Vec<ToolDefinition> = self.tools.clone();
+        for def in &scenario.tool_definitions {
+            if !tools.iter().any(|t| t.name == def.name) {
+                tools.push(def.clone());
+            }
+        }
+        if let Some(ref names) = scenario.tools {
+            tools.retain(|t| names.contains(&t.name));
+        }
+        tools
+    }
+
+    /// Play every turn of `scenario` and check its expectations.
+    pub async fn run(&self, scenario: &Scenario) -> Result<ScenarioReport, ScenarioError> {
+        let mut reasoning = Reasoning::new(Arc::clone(&self.llm), Arc::clone(&self.safety));
+        if let Some(ref prompt) = scenario.system_prompt {
+            reasoning = reasoning.with_system_prompt(prompt.clone());
+        }
+        let tools = self.tools_for(scenario);
+        let mut mocks = Mocks::new(&scenario.mocks);
+
+        // Like a thread, later turns see earlier ones as user/answer pairs.
+        let mut history: Vec<ChatMessage> = Vec::new();
+        let mut turns = Vec::with_capacity(scenario.turns.len());
+        for turn in &scenario.turns {
+            let outcome = self
+                .run_turn(&reasoning, &tools, &mut mocks, &history, &turn.user)
+                .await?;
+            history.push(ChatMessage::user(&turn.user));
+            if let Some(ref answer) = outcome.answer {
+                history.push(ChatMessage::assistant(answer));
+            }
+
+            let mut failures = turn.expect.check(&outcome);
+            for tool in &outcome.unmocked {
+                failures.push(format!("no mock for call to {}", tool));
+            }
+            if outcome.answer.is_none() {
+                failures.push(format!(
+                    "no answer after {} iterations",
+                    MAX_TOOL_ITERATIONS
+                ));
+            }
+            turns.push(TurnReport { outcome, failures });
+        }
+
+        Ok(ScenarioReport {
+            name: scenario.name.clone(),
+            model: self.llm.active_model_name(),
+            turns,
+        })
+    }
+
+    async fn run_turn(
+        &self,
+        reasoning: &Reasoning,
+        tools: &[ToolDefinition],
+        mocks: &mut Mocks<'_>,
+        history: &[ChatMessage],
+        user: &str,
+    ) -> Result<TurnOutcome, ScenarioError> {
+        let mut messages = history.to_vec();
+        messages.push(ChatMessage::user(user));
+        let mut outcome = TurnOutcome {
+            user: user.to_string(),
+            tool_calls: Vec::new(),
+            answer: None,
+            unmocked: Vec::new(),
+        };
+
+        for iteration in 1..=MAX_TOOL_ITERATIONS {
+            let context = ReasoningContext::new()
+                .with_messages(messages.clone())
+                .with_tools(tools.to_vec());
+            match reasoning.respond_with_tools(&context).await?.result {
+                RespondResult::Text(text) => {
+                    // Same nudge as the chat loop.
+                    if outcome.tool_calls.is_empty() && iteration < 3 {
+                        messages.push(ChatMessage::assistant(&text));
+                        messages.push(ChatMessage::user(TOOL_NUDGE));
+                        continue;
+                    }
+                    outcome.answer = Some(text);
+                    break;
+                }
+                RespondResult::ToolCalls {
+                    tool_calls,
+                    content,
+                } => {
+                    messages.push(ChatMessage::assistant_with_tool_calls(
+                        content,
+                        tool_calls.clone(),
+                    ));
+                    for call in tool_calls {
+                        let content = match mocks.take(&call) {
+                            Some(output) => {
+                                let sanitized =
+                                    self.safety.sanitize_tool_output(&call.name, output);
+                                self.safety.wrap_for_llm(
+                                    &call.name,
+                                    &sanitized.content,
+                                    sanitized.was_modified,
+                                )
+                            }
+                            None => {
+                                outcome.unmocked.push(call.name.clone());
+                                format!("Error: tool '{}' is unavailable", call.name)
+                            }
+                        };
+                        messages.push(ChatMessage::tool_result(&call.id, &call.name, content));
+                        outcome.tool_calls.push(call);
+                    }
+                }
+            }
+        }
+        Ok(outcome)
+    }
+}
+
+/// Mock outputs and which have been used.
+struct Mocks<'a> {
+    mocks: Vec<(&'a ToolMock, bool)>,
+}
+
+impl<'a> Mocks<'a> {
+    fn new(mocks: &'a [ToolMock]) -> Self {
+        Self {
+            mocks: mocks.iter().map(|m| (m, false)).collect(),
+        }
+    }
+
+    fn take(&mut self, call: &ToolCall) -> Option<&'a str> {
+        let matches = |m: &ToolMock| {
+            m.tool == call.name && m.args.as_ref().is_none_or(|args| *args == call.arguments)
+        };
+        if let Some((mock, used)) = self.mocks.iter_mut().find(|(m, used)| !used && matches(m)) {
+            *used = true;
+            return Some(mock.output.as_str());
+        }
+        self.mocks
+            .iter()
+            .find(|(m, _)| matches(m))
+            .map(|(m, _)| m.output.as_str())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent::transcript::{TranscriptRequest, TranscriptResponse};
+    use crate::llm::{FinishReason, MockLlmProvider, MockResponse};
+
+    /// A mock provider that answers with `responses` in order.
+    fn scripted(responses: Vec<MockResponse>) -> MockLlmProvider {
+        let llm = MockLlmProvider::new().with_model("scripted");
+        for response in responses {
+            llm.push(response);
+        }
+        llm
+    }
+
+    fn call(id: &str, name: &str, arguments: serde_json::Value) -> ToolCall {
+        ToolCall {
+            id: id.to_string(),
+            name: name.to_string(),
+            arguments,
+        }
+    }
+
+    fn response(content: Option<&str>, tool_calls: Vec<ToolCall>) -> MockResponse {
+        match content {
+            Some(text) if tool_calls.is_empty() => MockResponse::Text(text.to_string()),
+            content => MockResponse::ToolCalls {
+                content: content.map(String::from),
+                calls: tool_calls,
+            },
+        }
+    }
+
+    fn tool(name: &str) -> ToolDefinition {
+        ToolDefinition {
+            name: name.to_string(),
+            description: format!("The {} tool", name),
+            parameters: serde_json::json!({"type": "object"}),
+        }
+    }
+
+    const SCENARIO: &str = r#"{
+        "name": "dentist",
+        "mocks": [{"tool": "memory_search", "output": "Dentist: Tuesday 3pm"}],
+        "turns": [
+            {
+                "user": "When is my dentist appointment?",
+                "expect": {
+                    "tool_calls": ["memory_search"],
+                    "answer_contains": ["tuesday"],
+                    "answer_matches": "\\d+pm"
+                }
+            },
+            {
+                "user": "Thanks!",
+                "expect": {"tools_not_used": ["shell"], "answer_excludes": ["error"]}
+            }
+        ]
+    }"#;
+
+    #[tokio::test]
+    async fn test_scenario_passes() {
+        let scenario = Scenario::from_json(SCENARIO).unwrap();
+        let llm = Arc::new(scripted(vec![
+            response(
+                None,
+                vec![call(
+                    "c1",
+                    "memory_search",
+                    serde_json::json!({"query": "dentist"}),
+                )],
+            ),
+            response(Some("It's on Tuesday at 3pm."), vec![]),
+            // Second turn: a text answer is nudged twice before it's accepted.
+            response(Some("You're welcome!"), vec![]),
+            response(Some("You're welcome!"), vec![]),
+            response(Some("You're welcome!"), vec![]),
+        ]));
+        let runner = ScenarioRunner::new(llm.clone(), vec![tool("memory_search"), tool("shell")]);
+
+        let report = runner.run(&scenario).await.unwrap();
+        assert!(report.passed(), "{:?}", report.turns);
+        assert_eq!(report.model, "scripted");
+
+        let requests = llm.requests();
+        // The mock reached the model as a tool result.
+        let result = requests[1].messages.last().unwrap();
+        assert_eq!(result.role, Role::Tool);
+        assert!(result.content.contains("Tuesday 3pm"));
+        // Later turns carry earlier ones as user/answer pairs.
+        let second_turn = &requests[2].messages;
+        assert!(
+            second_turn
+                .iter()
+                .any(|m| m.content == "It's on Tuesday at 3pm.")
+        );
+        assert!(second_turn.iter().all(|m| m.role != Role::Tool));
+    }
+
+    #[tokio::test]
+    async fn test_scenario_reports_failures() {
+        let mut scenario = Scenario::from_json(SCENARIO).unwrap();
+        scenario.turns.truncate(1);
+        let llm = Arc::new(scripted(vec![
+            response(None, vec![call("c1", "shell", serde_json::json!({}))]),
+            response(Some("No idea."), vec![]),
+        ]));
+        let runner = ScenarioRunner::new(llm, vec![tool("memory_search"), tool("shell")]);
+
+        let report = runner.run(&scenario).await.unwrap();
+        assert!(!report.passed());
+        let failures = &report.turns[0].failures;
+        assert!(failures.iter().any(|f| f.contains("expected tool calls")));
+        assert!(failures.iter().any(|f| f.contains("\"tuesday\"")));
+        assert!(
+            failures
+                .iter()
+                .any(|f| f.contains("no mock for call to shell"))
+        );
+    }
+
+    #[test]
+    fn test_invalid_scenarios() {
+        assert!(Scenario::from_json(r#"{"name": "empty", "turns": []}"#).is_err());
+        assert!(
+            Scenario::from_json(
+                r#"{"name": "bad", "turns": [{"user": "hi", "expect": {"answer_matches": "("}}]}"#
+            )
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn test_mocks_match_args_then_reuse() {
+        let mocks = vec![
+            ToolMock {
+                tool: "read_file".to_string(),
+                args: Some(serde_json::json!({"path": "a"})),
+                output: "A".to_string(),
+            },
+            ToolMock {
+                tool: "read_file".to_string(),
+                args: None,
+                output: "any".to_string(),
+            },
+        ];
+        let mut mocks = Mocks::new(&mocks);
+        let b = call("1", "read_file", serde_json::json!({"path": "b"}));
+        let a = call("2", "read_file", serde_json::json!({"path": "a"}));
+        assert_eq!(mocks.take(&b), Some("any"));
+        assert_eq!(mocks.take(&a), Some("A"));
+        assert_eq!(mocks.take(&a), Some("A"));
+        assert_eq!(mocks.take(&call("3", "shell", serde_json::json!({}))), None);
+    }
+
+    #[test]
+    fn test_from_transcript() {
+        let read = call("c1", "read_file", serde_json::json!({"path": "notes.md"}));
+        let messages = vec![
+            ChatMessage::system("system"),
+            ChatMessage::user("Summarize my notes"),
+            ChatMessage::assistant_with_tool_calls(None, vec![read.clone()]),
+            ChatMessage::tool_result("c1", "read_file", "Buy milk"),
+            ChatMessage::assistant("Buy milk."),
+            ChatMessage::user(TOOL_NUDGE),
+            ChatMessage::user("And the other one?"),
+        ];
+        let transcript = vec![TranscriptEntry {
+            seq: 0,
+            model: "recorded".to_string(),
+            request: TranscriptRequest {
+                messages,
+                tools: vec![tool("read_file")],
+                max_tokens: None,
+                temperature: None,
+                tool_choice: None,
+            },
+            response: TranscriptResponse {
+                content: None,
+                tool_calls: vec![call("c2", "list_dir", serde_json::json!({}))],
+                input_tokens: 1,
+                output_tokens: 1,
+                finish_reason: FinishReason::ToolUse,
+            },
+            created_at: chrono::Utc::now(),
+        }];
+
+        let scenario = Scenario::from_transcript("notes", &transcript).unwrap();
+        assert_eq!(scenario.turns.len(), 2);
+        assert_eq!(
+            scenario.turns[0].expect.tool_calls,
+            Some(vec!["read_file".to_string()])
+        );
+        assert_eq!(
+            scenario.turns[1].expect.tool_calls,
+            Some(vec!["list_dir".to_string()])
+        );
+        assert_eq!(scenario.mocks.len(), 1);
+        assert_eq!(scenario.mocks[0].output, "Buy milk");
+        assert_eq!(scenario.tool_definitions[0].name, "read_file");
+
+        // The JSON form round-trips.
+        let json = serde_json::to_string(&scenario).unwrap();
+        assert_eq!(Scenario::from_json(&json).unwrap().turns.len(), 2);
+    }
+}
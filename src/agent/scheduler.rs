@@ -8,15 +8,21 @@ use tokio::sync::{RwLock, mpsc, oneshot};
 use tokio::task::JoinHandle;
 use uuid::Uuid;
 
+use crate::agent::episode::EpisodeRecorder;
 use crate::agent::task::{Task, TaskContext, TaskOutput};
+use crate::agent::transcript::RecordingProvider;
 use crate::agent::worker::{Worker, WorkerDeps};
+use crate::audit::{AuditEvent, AuditLog};
 use crate::config::AgentConfig;
 use crate::context::{ContextManager, JobContext, JobState};
 use crate::db::Database;
 use crate::error::{Error, JobError};
-use crate::llm::LlmProvider;
+use crate::llm::{LlmProvider, ModelProviders};
+use crate::metrics::Metrics;
 use crate::safety::SafetyLayer;
 use crate::tools::ToolRegistry;
+use crate::webhooks::Webhooks;
+use crate::workspace::{Sensitivity, Workspace};
 
 /// Message to send to a worker.
 #[derive(Debug)]
@@ -49,6 +55,13 @@ pub struct Scheduler {
     safety: Arc<SafetyLayer>,
     tools: Arc<ToolRegistry>,
     store: Option<Arc<dyn Database>>,
+    audit: Option<Arc<AuditLog>>,
+    episodes: Option<Arc<EpisodeRecorder>>,
+    webhooks: Option<Arc<Webhooks>>,
+    /// Documents the egress guard keeps jobs from sending out.
+    workspace: Option<Arc<Workspace>>,
+    /// Providers for jobs that ask for their own model.
+    models: Option<Arc<ModelProviders>>,
     /// Running jobs (main LLM-driven jobs).
     jobs: Arc<RwLock<HashMap<Uuid, ScheduledJob>>>,
     /// Running sub-tasks (tool executions, background tasks).
@@ -72,11 +85,47 @@ impl Scheduler {
             safety,
             tools,
             store,
+            audit: None,
+            episodes: None,
+            webhooks: None,
+            workspace: None,
+            models: None,
             jobs: Arc::new(RwLock::new(HashMap::new())),
             subtasks: Arc::new(RwLock::new(HashMap::new())),
         }
     }
 
+    /// Record tool executions from jobs and sub-tasks in the audit log.
+    pub fn with_audit(mut self, audit: Arc<AuditLog>) -> Self {
+        self.audit = Some(audit);
+        self
+    }
+
+    /// Summarize finished jobs into the daily log.
+    pub fn with_episodes(mut self, episodes: Arc<EpisodeRecorder>) -> Self {
+        self.episodes = Some(episodes);
+        self
+    }
+
+    /// Send a `job.completed` webhook when each job finishes.
+    pub fn with_webhooks(mut self, webhooks: Arc<Webhooks>) -> Self {
+        self.webhooks = Some(webhooks);
+        self
+    }
+
+    /// Fail job tool calls that would send out data from the workspace's
+    /// internal or private documents.
+    pub fn with_workspace(mut self, workspace: Arc<Workspace>) -> Self {
+        self.workspace = Some(workspace);
+        self
+    }
+
+    /// Run jobs that name a model on that model instead of the default.
+    pub fn with_models(mut self, models: Arc<ModelProviders>) -> Self {
+        self.models = Some(models);
+        self
+    }
+
     /// Schedule a job for execution.
     pub async fn schedule(&self, job_id: Uuid) -> Result<(), JobError> {
         // Hold write lock for the entire check-insert sequence to prevent
@@ -111,13 +160,27 @@ impl Scheduler {
             // Create worker channel
             let (tx, rx) = mpsc::channel(16);
 
+            let llm = self.llm_for(job_id).await;
+
+            // Record the job's LLM calls so it can be replayed later
+            let llm: Arc<dyn LlmProvider> = match self.store {
+                Some(ref store) if self.config.record_transcripts => {
+                    Arc::new(RecordingProvider::new(llm, job_id, store.clone()))
+                }
+                _ => llm,
+            };
+
             // Create worker with shared dependencies
             let deps = WorkerDeps {
                 context_manager: self.context_manager.clone(),
-                llm: self.llm.clone(),
+                llm,
                 safety: self.safety.clone(),
                 tools: self.tools.clone(),
                 store: self.store.clone(),
+                audit: self.audit.clone(),
+                episodes: self.episodes.clone(),
+                webhooks: self.webhooks.clone(),
+                workspace: self.workspace.clone(),
                 timeout: self.config.job_timeout,
                 use_planning: self.config.use_planning,
             };
@@ -158,10 +221,35 @@ impl Scheduler {
             }
         });
 
+        Metrics::global().job_started();
         tracing::info!("Scheduled job {} for execution", job_id);
         Ok(())
     }
 
+    /// The provider for a job: its own model if it names one, otherwise
+    /// the default.
+    async fn llm_for(&self, job_id: Uuid) -> Arc<dyn LlmProvider> {
+        let model = match self.context_manager.get_context(job_id).await {
+            Ok(ctx) => ctx.model,
+            Err(_) => None,
+        };
+        match (model, self.models.as_ref()) {
+            (Some(model), Some(models)) => match models.get(&model) {
+                Ok(llm) => llm,
+                Err(e) => {
+                    tracing::warn!(
+                        "Job {} asked for model {}, using the default: {}",
+                        job_id,
+                        model,
+                        e
+                    );
+                    self.llm.clone()
+                }
+            },
+            _ => self.llm.clone(),
+        }
+    }
+
     /// Schedule a sub-task from within a worker.
     ///
     /// Sub-tasks are lightweight tasks that don't go through the full job lifecycle.
@@ -193,12 +281,16 @@ impl Scheduler {
                 let tools = self.tools.clone();
                 let context_manager = self.context_manager.clone();
                 let safety = self.safety.clone();
+                let audit = self.audit.clone();
+                let workspace = self.workspace.clone();
 
                 tokio::spawn(async move {
                     let result = Self::execute_tool_task(
                         tools,
                         context_manager,
                         safety,
+                        audit,
+                        workspace,
                         tool_parent_id,
                         &tool_name,
                         params,
@@ -323,10 +415,18 @@ impl Scheduler {
     }
 
     /// Execute a single tool as a subtask.
-    async fn execute_tool_task(
+    #[allow(clippy::too_many_arguments)]
+    #[tracing::instrument(
+        name = "tool.execute",
+        skip_all,
+        fields(tool = %tool_name, job_id = %job_id)
+    )]
+    pub(crate) async fn execute_tool_task(
         tools: Arc<ToolRegistry>,
         context_manager: Arc<ContextManager>,
         safety: Arc<SafetyLayer>,
+        audit: Option<Arc<AuditLog>>,
+        workspace: Option<Arc<Workspace>>,
         job_id: Uuid,
         tool_name: &str,
         params: serde_json::Value,
@@ -350,13 +450,25 @@ impl Scheduler {
             .into());
         }
 
-        if tool.requires_approval() {
+        if !tools.is_allowed(&job_ctx.user_id, tool_name) {
+            return Err(crate::error::ToolError::PermissionDenied {
+                name: tool_name.to_string(),
+                user_id: job_ctx.user_id.clone(),
+            }
+            .into());
+        }
+
+        if tool.requires_approval_for(&params) {
             return Err(crate::error::ToolError::AuthRequired {
                 name: tool_name.to_string(),
             }
             .into());
         }
 
+        if tool.is_external_action() {
+            check_job_egress(workspace.as_deref(), tool_name, &params).await?;
+        }
+
         // Validate tool parameters
         let validation = safety.validator().validate_tool_params(&params);
         if !validation.is_valid {
@@ -375,21 +487,42 @@ impl Scheduler {
 
         // Execute with per-tool timeout
         let tool_timeout = tool.execution_timeout();
-        let result =
-            tokio::time::timeout(tool_timeout, async { tool.execute(params, &job_ctx).await })
-                .await
-                .map_err(|_| {
-                    Error::Tool(crate::error::ToolError::Timeout {
-                        name: tool_name.to_string(),
-                        timeout: tool_timeout,
-                    })
-                })?
-                .map_err(|e| {
-                    Error::Tool(crate::error::ToolError::ExecutionFailed {
-                        name: tool_name.to_string(),
-                        reason: e.to_string(),
-                    })
-                })?;
+        let result = tokio::time::timeout(tool_timeout, async {
+            tool.execute(params.clone(), &job_ctx).await
+        })
+        .await;
+        Metrics::global().record_tool(tool_name, start.elapsed(), matches!(result, Ok(Ok(_))));
+
+        if let Some(audit) = audit {
+            let error = match &result {
+                Ok(Ok(_)) => None,
+                Ok(Err(e)) => Some(e.to_string()),
+                Err(_) => Some("timed out".to_string()),
+            };
+            audit
+                .record(AuditEvent::tool_execution(
+                    &job_ctx.user_id,
+                    tool_name,
+                    &params,
+                    error.as_deref(),
+                    start.elapsed(),
+                ))
+                .await;
+        }
+
+        let result = result
+            .map_err(|_| {
+                Error::Tool(crate::error::ToolError::Timeout {
+                    name: tool_name.to_string(),
+                    timeout: tool_timeout,
+                })
+            })?
+            .map_err(|e| {
+                Error::Tool(crate::error::ToolError::ExecutionFailed {
+                    name: tool_name.to_string(),
+                    reason: e.to_string(),
+                })
+            })?;
 
         Ok(TaskOutput::new(result.result, start.elapsed()))
     }
@@ -500,6 +633,66 @@ impl Scheduler {
         }
     }
 
+    /// Wait until every running job has finished or `deadline` passes.
+    /// Returns whether all jobs finished.
+    pub async fn drain(&self, deadline: tokio::time::Instant) -> bool {
+        loop {
+            self.cleanup_finished().await;
+            if self.running_count().await == 0 {
+                return true;
+            }
+            if tokio::time::Instant::now() >= deadline {
+                return false;
+            }
+            tokio::time::sleep_until(
+                deadline.min(tokio::time::Instant::now() + Duration::from_millis(100)),
+            )
+            .await;
+        }
+    }
+
+    /// Stop every running job without cancelling it: each is marked stuck
+    /// with `reason` and saved, so self-repair can pick it up again.
+    /// Returns the checkpointed job IDs.
+    pub async fn checkpoint_all(&self, reason: &str) -> Vec<Uuid> {
+        let scheduled: Vec<(Uuid, ScheduledJob)> = self.jobs.write().await.drain().collect();
+        let mut checkpointed = Vec::with_capacity(scheduled.len());
+
+        for (job_id, job) in scheduled {
+            let _ = job.tx.send(WorkerMessage::Stop).await;
+            job.handle.abort();
+
+            let ctx = match self
+                .context_manager
+                .update_context(job_id, |ctx| {
+                    let _ = ctx.transition_to(JobState::Stuck, Some(reason.to_string()));
+                    ctx.clone()
+                })
+                .await
+            {
+                Ok(ctx) => ctx,
+                Err(e) => {
+                    tracing::warn!("Failed to checkpoint job {}: {}", job_id, e);
+                    continue;
+                }
+            };
+            if let Some(ref store) = self.store
+                && let Err(e) = store.save_job(&ctx).await
+            {
+                tracing::warn!("Failed to persist checkpoint for job {}: {}", job_id, e);
+            }
+            tracing::info!("Checkpointed job {} ({})", job_id, ctx.state);
+            checkpointed.push(job_id);
+        }
+
+        let mut subtasks = self.subtasks.write().await;
+        for (_, scheduled) in subtasks.drain() {
+            scheduled.handle.abort();
+        }
+
+        checkpointed
+    }
+
     /// Stop all jobs.
     pub async fn stop_all(&self) {
         let job_ids: Vec<Uuid> = self.jobs.read().await.keys().cloned().collect();
@@ -526,6 +719,32 @@ impl Scheduler {
     }
 }
 
+/// Fail an external action in a job if its arguments carry data from the
+/// user's internal or private documents. Jobs run unattended, so there is
+/// no one to approve it as the chat loop would ask.
+pub(crate) async fn check_job_egress(
+    workspace: Option<&Workspace>,
+    tool_name: &str,
+    params: &serde_json::Value,
+) -> Result<(), Error> {
+    let context = super::agent_loop::egress_context(workspace, Sensitivity::Internal).await;
+    let context: Vec<&str> = context.iter().map(String::as_str).collect();
+    let payload = crate::safety::egress_payload_text(params);
+    let findings = crate::safety::check_egress(&payload, &context);
+    if findings.is_empty() {
+        return Ok(());
+    }
+    Err(crate::error::ToolError::PrivateEgress {
+        name: tool_name.to_string(),
+        reasons: findings
+            .iter()
+            .map(|f| f.to_string())
+            .collect::<Vec<_>>()
+            .join(", "),
+    }
+    .into())
+}
+
 #[cfg(test)]
 mod tests {
     #[test]
@@ -16,6 +16,7 @@ use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
+use crate::context::ScratchBuffer;
 use crate::llm::ChatMessage;
 
 /// A session containing one or more threads.
@@ -177,6 +178,9 @@ pub struct Thread {
     /// metadata so we can resume chaining across restarts.
     #[serde(default)]
     pub last_response_id: Option<String>,
+    /// Scratch buffer shared by this thread's tool calls (not persisted).
+    #[serde(skip)]
+    pub scratch: ScratchBuffer,
 }
 
 impl Thread {
@@ -194,6 +198,7 @@ impl Thread {
             pending_approval: None,
             pending_auth: None,
             last_response_id: None,
+            scratch: ScratchBuffer::default(),
         }
     }
 
@@ -211,6 +216,7 @@ impl Thread {
             pending_approval: None,
             pending_auth: None,
             last_response_id: None,
+            scratch: ScratchBuffer::default(),
         }
     }
 
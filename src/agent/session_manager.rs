@@ -1,7 +1,9 @@
 //! Session manager for multi-user, multi-thread conversation handling.
 //!
 //! Maps external channel thread IDs to internal UUIDs and manages undo state
-//! for each thread.
+//! for each thread. A channel thread (a Telegram chat, a Discord thread, the
+//! REPL) can hold several conversations: `/new` starts another one and
+//! `/thread` switches between them, each with its own history.
 
 use std::collections::HashMap;
 use std::sync::Arc;
@@ -24,6 +26,8 @@ struct ThreadKey {
 pub struct SessionManager {
     sessions: RwLock<HashMap<String, Arc<Mutex<Session>>>>,
     thread_map: RwLock<HashMap<ThreadKey, Uuid>>,
+    /// Every thread each key has used, oldest first.
+    branches: RwLock<HashMap<ThreadKey, Vec<Uuid>>>,
     undo_managers: RwLock<HashMap<Uuid, Arc<Mutex<UndoManager>>>>,
 }
 
@@ -33,6 +37,7 @@ impl SessionManager {
         Self {
             sessions: RwLock::new(HashMap::new()),
             thread_map: RwLock::new(HashMap::new()),
+            branches: RwLock::new(HashMap::new()),
             undo_managers: RwLock::new(HashMap::new()),
         }
     }
@@ -94,20 +99,125 @@ impl SessionManager {
             let thread = sess.create_thread();
             thread.id
         };
+        self.bind(key, thread_id).await;
 
-        // Store mapping
+        (session, thread_id)
+    }
+
+    /// Route `key` to `thread_id`, remembering it among the key's threads.
+    async fn bind(&self, key: ThreadKey, thread_id: Uuid) {
         {
-            let mut thread_map = self.thread_map.write().await;
-            thread_map.insert(key, thread_id);
+            let mut branches = self.branches.write().await;
+            let threads = branches.entry(key.clone()).or_default();
+            if !threads.contains(&thread_id) {
+                threads.push(thread_id);
+            }
         }
+        self.thread_map.write().await.insert(key, thread_id);
+        self.undo_managers
+            .write()
+            .await
+            .entry(thread_id)
+            .or_insert_with(|| Arc::new(Mutex::new(UndoManager::new())));
+    }
 
-        // Create undo manager for thread
-        {
-            let mut undo_managers = self.undo_managers.write().await;
-            undo_managers.insert(thread_id, Arc::new(Mutex::new(UndoManager::new())));
+    /// Start a fresh thread for a channel thread and route its messages
+    /// there. The previous thread is kept and can be switched back to.
+    pub async fn start_thread(
+        &self,
+        user_id: &str,
+        channel: &str,
+        external_thread_id: Option<&str>,
+    ) -> Uuid {
+        let session = self.get_or_create_session(user_id).await;
+        let thread_id = session.lock().await.create_thread().id;
+        let key = ThreadKey {
+            user_id: user_id.to_string(),
+            channel: channel.to_string(),
+            external_thread_id: external_thread_id.map(String::from),
+        };
+        self.bind(key, thread_id).await;
+        thread_id
+    }
+
+    /// The threads a channel thread has used (oldest first) and the one it
+    /// currently routes to.
+    pub async fn list_threads(
+        &self,
+        user_id: &str,
+        channel: &str,
+        external_thread_id: Option<&str>,
+    ) -> (Vec<Uuid>, Option<Uuid>) {
+        let key = ThreadKey {
+            user_id: user_id.to_string(),
+            channel: channel.to_string(),
+            external_thread_id: external_thread_id.map(String::from),
+        };
+        let current = self.thread_map.read().await.get(&key).copied();
+        let threads = self
+            .branches
+            .read()
+            .await
+            .get(&key)
+            .cloned()
+            .unwrap_or_default();
+        let Some(session) = self.sessions.read().await.get(user_id).cloned() else {
+            return (Vec::new(), None);
+        };
+        let sess = session.lock().await;
+        let threads = threads
+            .into_iter()
+            .filter(|id| sess.threads.contains_key(id))
+            .collect();
+        (threads, current)
+    }
+
+    /// Route a channel thread to `thread_id`, which must be one of its own
+    /// threads: a group chat can't be switched into a DM's conversation.
+    pub async fn switch_thread(
+        &self,
+        user_id: &str,
+        channel: &str,
+        external_thread_id: Option<&str>,
+        thread_id: Uuid,
+    ) -> bool {
+        let (threads, _) = self
+            .list_threads(user_id, channel, external_thread_id)
+            .await;
+        if !threads.contains(&thread_id) {
+            return false;
         }
+        if let Some(session) = self.sessions.read().await.get(user_id) {
+            session.lock().await.switch_thread(thread_id);
+        }
+        let key = ThreadKey {
+            user_id: user_id.to_string(),
+            channel: channel.to_string(),
+            external_thread_id: external_thread_id.map(String::from),
+        };
+        self.bind(key, thread_id).await;
+        true
+    }
 
-        (session, thread_id)
+    /// Whether `resolve_thread` would find an existing thread for this key.
+    pub async fn has_thread(
+        &self,
+        user_id: &str,
+        channel: &str,
+        external_thread_id: Option<&str>,
+    ) -> bool {
+        let key = ThreadKey {
+            user_id: user_id.to_string(),
+            channel: channel.to_string(),
+            external_thread_id: external_thread_id.map(String::from),
+        };
+        let Some(thread_id) = self.thread_map.read().await.get(&key).copied() else {
+            return false;
+        };
+        let Some(session) = self.sessions.read().await.get(user_id).cloned() else {
+            return false;
+        };
+        session.lock().await.threads.contains_key(&thread_id)
     }
 
     /// Register a hydrated thread so subsequent `resolve_thread` calls find it.
@@ -119,24 +229,34 @@ impl SessionManager {
         channel: &str,
         thread_id: Uuid,
         session: Arc<Mutex<Session>>,
+    ) {
+        let external_thread_id = thread_id.to_string();
+        self.register_thread_as(
+            user_id,
+            channel,
+            Some(&external_thread_id),
+            thread_id,
+            session,
+        )
+        .await;
+    }
+
+    /// Register a hydrated thread under the channel's own thread ID (or
+    /// none), for channels whose thread IDs aren't our UUIDs.
+    pub async fn register_thread_as(
+        &self,
+        user_id: &str,
+        channel: &str,
+        external_thread_id: Option<&str>,
+        thread_id: Uuid,
+        session: Arc<Mutex<Session>>,
     ) {
         let key = ThreadKey {
             user_id: user_id.to_string(),
             channel: channel.to_string(),
-            external_thread_id: Some(thread_id.to_string()),
+            external_thread_id: external_thread_id.map(String::from),
         };
-
-        {
-            let mut thread_map = self.thread_map.write().await;
-            thread_map.insert(key, thread_id);
-        }
-
-        {
-            let mut undo_managers = self.undo_managers.write().await;
-            undo_managers
-                .entry(thread_id)
-                .or_insert_with(|| Arc::new(Mutex::new(UndoManager::new())));
-        }
+        self.bind(key, thread_id).await;
 
         // Ensure the session is tracked
         {
@@ -171,6 +291,15 @@ impl SessionManager {
     ///
     /// Returns the number of sessions pruned.
     pub async fn prune_stale_sessions(&self, max_idle: std::time::Duration) -> usize {
+        self.take_stale_sessions(max_idle).await.len()
+    }
+
+    /// Remove sessions that have been idle for longer than the given duration
+    /// and hand them back, so their threads can be wrapped up.
+    pub async fn take_stale_sessions(
+        &self,
+        max_idle: std::time::Duration,
+    ) -> Vec<Arc<Mutex<Session>>> {
         let cutoff = chrono::Utc::now() - chrono::TimeDelta::seconds(max_idle.as_secs() as i64);
 
         // Find stale session user_ids
@@ -191,7 +320,7 @@ impl SessionManager {
         };
 
         if stale_users.is_empty() {
-            return 0;
+            return Vec::new();
         }
 
         // Collect thread IDs from stale sessions for cleanup
@@ -208,13 +337,12 @@ impl SessionManager {
         }
 
         // Remove sessions
-        let count = {
+        let removed: Vec<Arc<Mutex<Session>>> = {
             let mut sessions = self.sessions.write().await;
-            let before = sessions.len();
-            for user_id in &stale_users {
-                sessions.remove(user_id);
-            }
-            before - sessions.len()
+            stale_users
+                .iter()
+                .filter_map(|user_id| sessions.remove(user_id))
+                .collect()
         };
 
         // Clean up thread mappings that point to stale sessions
@@ -222,6 +350,10 @@ impl SessionManager {
             let mut thread_map = self.thread_map.write().await;
             thread_map.retain(|key, _| !stale_users.contains(&key.user_id));
         }
+        {
+            let mut branches = self.branches.write().await;
+            branches.retain(|key, _| !stale_users.contains(&key.user_id));
+        }
 
         // Clean up undo managers for stale threads
         {
@@ -231,15 +363,15 @@ impl SessionManager {
             }
         }
 
-        if count > 0 {
+        if !removed.is_empty() {
             tracing::info!(
                 "Pruned {} stale session(s) (idle > {}s)",
-                count,
+                removed.len(),
                 max_idle.as_secs()
             );
         }
 
-        count
+        removed
     }
 }
 
@@ -364,6 +496,79 @@ mod tests {
         assert!(sess.threads.contains_key(&thread_id));
     }
 
+    #[tokio::test]
+    async fn test_register_thread_as_channel_key() {
+        use crate::agent::session::{Session, Thread};
+
+        let manager = SessionManager::new();
+        let thread_id = Uuid::new_v4();
+        assert!(!manager.has_thread("user-tg", "telegram", Some("42")).await);
+
+        let session = Arc::new(Mutex::new(Session::new("user-tg")));
+        {
+            let mut sess = session.lock().await;
+            let thread = Thread::with_id(thread_id, sess.id);
+            sess.threads.insert(thread_id, thread);
+        }
+        manager
+            .register_thread_as(
+                "user-tg",
+                "telegram",
+                Some("42"),
+                thread_id,
+                Arc::clone(&session),
+            )
+            .await;
+
+        assert!(manager.has_thread("user-tg", "telegram", Some("42")).await);
+        assert!(!manager.has_thread("user-tg", "telegram", None).await);
+        let (_, resolved) = manager
+            .resolve_thread("user-tg", "telegram", Some("42"))
+            .await;
+        assert_eq!(resolved, thread_id);
+    }
+
+    #[tokio::test]
+    async fn test_start_and_switch_threads_per_channel_thread() {
+        let manager = SessionManager::new();
+        let (_, first) = manager
+            .resolve_thread("user-b", "telegram", Some("1"))
+            .await;
+        let (_, other_chat) = manager
+            .resolve_thread("user-b", "telegram", Some("2"))
+            .await;
+
+        let second = manager.start_thread("user-b", "telegram", Some("1")).await;
+        let (_, resolved) = manager
+            .resolve_thread("user-b", "telegram", Some("1"))
+            .await;
+        assert_eq!(resolved, second);
+
+        let (threads, current) = manager.list_threads("user-b", "telegram", Some("1")).await;
+        assert_eq!(threads, vec![first, second]);
+        assert_eq!(current, Some(second));
+
+        // Another chat's thread can't be switched into
+        assert!(
+            !manager
+                .switch_thread("user-b", "telegram", Some("1"), other_chat)
+                .await
+        );
+        assert!(
+            manager
+                .switch_thread("user-b", "telegram", Some("1"), first)
+                .await
+        );
+        let (_, resolved) = manager
+            .resolve_thread("user-b", "telegram", Some("1"))
+            .await;
+        assert_eq!(resolved, first);
+        let (_, resolved) = manager
+            .resolve_thread("user-b", "telegram", Some("2"))
+            .await;
+        assert_eq!(resolved, other_chat);
+    }
+
     #[tokio::test]
     async fn test_resolve_thread_with_explicit_external_id() {
         let manager = SessionManager::new();
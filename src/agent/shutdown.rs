@@ -0,0 +1,224 @@
+//! Graceful shutdown.
+//!
+//! On SIGTERM or Ctrl+C the agent drains instead of dropping work mid-step:
+//!
+//! 1. stop reading messages and refuse new jobs
+//! 2. let the current turn (LLM calls and tool calls) and running jobs
+//!    finish, up to the drain deadline
+//! 3. checkpoint jobs still running: they are marked stuck and saved, so
+//!    self-repair can resume them
+//! 4. run the registered flush hooks (e.g. the knowledge graph indexing
+//!    queue)
+//! 5. close channel connections
+//!
+//! A second signal during the drain skips straight to step 3.
+
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+
+use futures::future::BoxFuture;
+use tokio::time::Instant;
+use tokio_util::sync::CancellationToken;
+
+/// Coordinates a graceful shutdown between the agent loop and the
+/// background components that have work to flush.
+pub struct ShutdownCoordinator {
+    drain_timeout: Duration,
+    /// Cancelled by the first shutdown signal.
+    drain: CancellationToken,
+    /// Cancelled by a second signal: stop waiting for work to finish.
+    force: CancellationToken,
+    deadline: OnceLock<Instant>,
+    hooks: Mutex<Vec<(String, BoxFuture<'static, ()>)>>,
+}
+
+impl ShutdownCoordinator {
+    pub fn new(drain_timeout: Duration) -> Self {
+        Self {
+            drain_timeout,
+            drain: CancellationToken::new(),
+            force: CancellationToken::new(),
+            deadline: OnceLock::new(),
+            hooks: Mutex::new(Vec::new()),
+        }
+    }
+
+    pub fn drain_timeout(&self) -> Duration {
+        self.drain_timeout
+    }
+
+    /// Start draining. The first call sets the deadline; a second call
+    /// forces the drain to end now.
+    pub fn trigger(&self) {
+        if self.drain.is_cancelled() {
+            self.force.cancel();
+            return;
+        }
+        let _ = self.deadline.set(Instant::now() + self.drain_timeout);
+        self.drain.cancel();
+    }
+
+    /// Whether shutdown has started.
+    pub fn is_draining(&self) -> bool {
+        self.drain.is_cancelled()
+    }
+
+    /// Resolves once shutdown starts.
+    pub async fn draining(&self) {
+        self.drain.cancelled().await;
+    }
+
+    /// When waiting for in-flight work ends. Only set once draining.
+    pub fn deadline(&self) -> Option<Instant> {
+        self.deadline.get().copied()
+    }
+
+    /// Resolves once shutdown has started and in-flight work has run out
+    /// of time, either because the drain deadline passed or a second
+    /// signal arrived.
+    pub async fn deadline_elapsed(&self) {
+        self.drain.cancelled().await;
+        let deadline = self.deadline().unwrap_or_else(Instant::now);
+        tokio::select! {
+            _ = tokio::time::sleep_until(deadline) => {}
+            _ = self.force.cancelled() => {}
+        }
+    }
+
+    /// Register work to run once in-flight work has drained, such as
+    /// flushing a queue. Hooks run in registration order, each bounded by
+    /// the drain timeout.
+    pub fn on_drain<F>(&self, name: impl Into<String>, hook: F)
+    where
+        F: Future<Output = ()> + Send + 'static,
+    {
+        self.hooks
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .push((name.into(), Box::pin(hook)));
+    }
+
+    /// Run the registered drain hooks.
+    pub async fn run_hooks(&self) {
+        let hooks = std::mem::take(&mut *self.hooks.lock().unwrap_or_else(|e| e.into_inner()));
+        for (name, hook) in hooks {
+            tracing::info!("Flushing {}...", name);
+            if tokio::time::timeout(self.drain_timeout, hook)
+                .await
+                .is_err()
+            {
+                tracing::warn!("Flushing {} timed out", name);
+            }
+        }
+    }
+
+    /// Trigger on SIGTERM or Ctrl+C, for as long as the process runs.
+    pub fn listen_for_signals(self: &std::sync::Arc<Self>) -> tokio::task::JoinHandle<()> {
+        let this = std::sync::Arc::clone(self);
+        tokio::spawn(async move {
+            #[cfg(unix)]
+            let mut sigterm =
+                match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+                    Ok(s) => Some(s),
+                    Err(e) => {
+                        tracing::warn!("Failed to listen for SIGTERM: {}", e);
+                        None
+                    }
+                };
+
+            loop {
+                #[cfg(unix)]
+                let terminate = async {
+                    match sigterm.as_mut() {
+                        Some(s) => {
+                            s.recv().await;
+                        }
+                        None => std::future::pending().await,
+                    }
+                };
+                #[cfg(not(unix))]
+                let terminate = std::future::pending::<()>();
+
+                tokio::select! {
+                    result = tokio::signal::ctrl_c() => {
+                        if let Err(e) = result {
+                            tracing::warn!("Failed to listen for Ctrl+C: {}", e);
+                            return;
+                        }
+                        tracing::info!("Ctrl+C received, shutting down...");
+                    }
+                    _ = terminate => {
+                        tracing::info!("SIGTERM received, shutting down...");
+                    }
+                }
+                if this.is_draining() {
+                    tracing::warn!("Second shutdown signal, no longer waiting for work to finish");
+                }
+                this.trigger();
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_deadline_elapses_after_drain_timeout() {
+        let shutdown = ShutdownCoordinator::new(Duration::from_millis(50));
+        assert!(!shutdown.is_draining());
+        assert!(shutdown.deadline().is_none());
+
+        let start = Instant::now();
+        shutdown.trigger();
+        assert!(shutdown.is_draining());
+        shutdown.deadline_elapsed().await;
+        assert!(start.elapsed() >= Duration::from_millis(50));
+    }
+
+    #[tokio::test]
+    async fn test_second_trigger_forces() {
+        let shutdown = Arc::new(ShutdownCoordinator::new(Duration::from_secs(30)));
+        shutdown.trigger();
+
+        let waiter = {
+            let shutdown = Arc::clone(&shutdown);
+            tokio::spawn(async move { shutdown.deadline_elapsed().await })
+        };
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        assert!(!waiter.is_finished());
+
+        shutdown.trigger();
+        tokio::time::timeout(Duration::from_secs(5), waiter)
+            .await
+            .expect("forced drain should end the wait")
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_hooks_run_once_in_order_and_time_out() {
+        let shutdown = ShutdownCoordinator::new(Duration::from_millis(50));
+        let order = Arc::new(Mutex::new(Vec::new()));
+        let runs = Arc::new(AtomicUsize::new(0));
+
+        for name in ["first", "second"] {
+            let order = Arc::clone(&order);
+            let runs = Arc::clone(&runs);
+            shutdown.on_drain(name, async move {
+                order.lock().unwrap().push(name);
+                runs.fetch_add(1, Ordering::SeqCst);
+            });
+        }
+        shutdown.on_drain("hung", std::future::pending());
+
+        shutdown.run_hooks().await;
+        assert_eq!(*order.lock().unwrap(), vec!["first", "second"]);
+
+        shutdown.run_hooks().await;
+        assert_eq!(runs.load(Ordering::SeqCst), 2);
+    }
+}
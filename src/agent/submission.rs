@@ -43,6 +43,9 @@ impl SubmissionParser {
         if lower == "/thread new" || lower == "/new" {
             return Submission::NewThread;
         }
+        if lower == "/threads" || lower == "/thread list" {
+            return Submission::ListThreads;
+        }
         // System commands (bypass thread-state checks)
         if lower == "/help" || lower == "/?" {
             return Submission::SystemCommand {
@@ -90,14 +93,17 @@ impl SubmissionParser {
             return Submission::Quit;
         }
 
-        // /thread <uuid> - switch thread
+        // /thread <uuid> or /thread <n> (number from /threads) - switch thread
         if let Some(rest) = lower.strip_prefix("/thread ") {
             let rest = rest.trim();
-            if rest != "new"
-                && let Ok(id) = Uuid::parse_str(rest)
-            {
+            if let Ok(id) = Uuid::parse_str(rest) {
                 return Submission::SwitchThread { thread_id: id };
             }
+            if let Ok(number) = rest.parse::<usize>()
+                && number > 0
+            {
+                return Submission::SwitchThreadNumber { number };
+            }
         }
 
         // /resume <uuid> - resume from checkpoint
@@ -200,9 +206,18 @@ pub enum Submission {
         thread_id: Uuid,
     },
 
+    /// Switch to a thread by its number in the `/threads` listing.
+    SwitchThreadNumber {
+        /// 1-based position in the listing.
+        number: usize,
+    },
+
     /// Create a new thread.
     NewThread,
 
+    /// List the threads of the current channel thread.
+    ListThreads,
+
     /// Trigger a manual heartbeat check.
     Heartbeat,
 
@@ -286,6 +301,7 @@ impl Submission {
                 | Self::Redo
                 | Self::Clear
                 | Self::NewThread
+                | Self::ListThreads
                 | Self::Heartbeat
                 | Self::Summarize
                 | Self::Suggest
@@ -432,6 +448,26 @@ mod tests {
         let uuid = Uuid::new_v4();
         let submission = SubmissionParser::parse(&format!("/thread {}", uuid));
         assert!(matches!(submission, Submission::SwitchThread { thread_id } if thread_id == uuid));
+
+        let submission = SubmissionParser::parse("/thread 2");
+        assert!(matches!(
+            submission,
+            Submission::SwitchThreadNumber { number: 2 }
+        ));
+        let submission = SubmissionParser::parse("/thread 0");
+        assert!(matches!(submission, Submission::UserInput { .. }));
+    }
+
+    #[test]
+    fn test_parser_list_threads() {
+        assert!(matches!(
+            SubmissionParser::parse("/threads"),
+            Submission::ListThreads
+        ));
+        assert!(matches!(
+            SubmissionParser::parse("/thread list"),
+            Submission::ListThreads
+        ));
     }
 
     #[test]
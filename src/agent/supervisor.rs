@@ -0,0 +1,777 @@
+//! Supervisor agent pattern.
+//!
+//! A supervisor answers a request by splitting it into sub-tasks, sending
+//! each to a named worker agent over the [`AgentBus`], waiting for the
+//! replies, and writing the final answer from them:
+//!
+//! 1. **Plan**: the LLM picks workers from the bus and writes one task each.
+//! 2. **Dispatch**: sub-tasks run concurrently; each is tracked on the bus.
+//! 3. **Synthesize**: the LLM combines the replies (and any failures) into
+//!    one answer.
+//!
+//! Safeguards keep a supervisor from running away: a cap on sub-tasks per
+//! request, an optional cost budget shared with the workers through a
+//! [`SpendMeter`] (a worker stops at the first LLM step that exhausts it,
+//! and no further sub-tasks are dispatched), the bus's per-message timeout
+//! and delegation depth, and workers never being given the delegation tool
+//! themselves.
+//!
+//! Registered agents join the bus as [`RegisteredWorker`]s, which run a
+//! sub-task in-process with the agent's identity documents, toolset, and
+//! (when it has one) its own model. Worker tool calls still go through the
+//! shared tool registry, so they can't exceed what the supervisor itself
+//! may use.
+
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+
+use async_trait::async_trait;
+use futures::future::join_all;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::agent::bus::{AgentBus, AgentEndpoint, AgentMessage, AgentReply};
+use crate::agent::registry::AgentDefinition;
+use crate::agent::scheduler::Scheduler;
+use crate::audit::AuditLog;
+use crate::context::ContextManager;
+use crate::error::LlmError;
+use crate::llm::{
+    ChatMessage, CompletionRequest, LlmProvider, Reasoning, ReasoningContext, RespondResult,
+    extract_json,
+};
+use crate::safety::{SafetyLayer, tool_matches};
+use crate::tools::ToolRegistry;
+use crate::workspace::{PromptContext, SessionKind, Workspace};
+
+/// Name of the tool that hands a request to the supervisor. Workers never
+/// see it, so delegation can't recurse through them.
+pub const SUPERVISE_TOOL_NAME: &str = "supervise";
+
+/// Errors from a supervised run.
+#[derive(Debug, thiserror::Error)]
+pub enum SupervisorError {
+    #[error("No worker agents are available")]
+    NoWorkers,
+
+    #[error("Supervisor plan was not valid JSON: {0}")]
+    InvalidPlan(String),
+
+    #[error("Cost budget exceeded: spent ${spent}, budget ${budget}")]
+    BudgetExceeded { spent: Decimal, budget: Decimal },
+
+    #[error(transparent)]
+    Llm(#[from] LlmError),
+}
+
+/// Loop and cost safeguards for a supervisor.
+#[derive(Debug, Clone)]
+pub struct SupervisorLimits {
+    /// Sub-tasks dispatched per request; extra planned ones are dropped.
+    pub max_subtasks: usize,
+    /// Total LLM cost (USD) a request may incur, workers included.
+    pub budget: Option<Decimal>,
+}
+
+impl Default for SupervisorLimits {
+    fn default() -> Self {
+        Self {
+            max_subtasks: 6,
+            budget: None,
+        }
+    }
+}
+
+/// LLM spend of one supervised request, shared with the workers it
+/// dispatches to so each stops as soon as the budget is gone.
+///
+/// Spend is kept per sub-task message (the supervisor's own under
+/// [`Uuid::nil`]), so a reply's reported cost can be reconciled with what
+/// its worker already charged.
+#[derive(Debug)]
+pub struct SpendMeter {
+    budget: Option<Decimal>,
+    spent: Mutex<HashMap<Uuid, Decimal>>,
+}
+
+impl SpendMeter {
+    pub fn new(budget: Option<Decimal>) -> Self {
+        Self {
+            budget,
+            spent: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<Uuid, Decimal>> {
+        self.spent.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Total spent so far.
+    pub fn spent(&self) -> Decimal {
+        self.lock().values().sum()
+    }
+
+    /// Fail if the budget is exceeded.
+    pub fn check(&self) -> Result<(), SupervisorError> {
+        let spent = self.spent();
+        match self.budget {
+            Some(budget) if spent > budget => {
+                Err(SupervisorError::BudgetExceeded { spent, budget })
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Add `cost` to the spend of `message`.
+    pub fn record(&self, message: Uuid, cost: Decimal) {
+        *self.lock().entry(message).or_default() += cost;
+    }
+
+    /// [`record`](Self::record), then [`check`](Self::check).
+    pub fn charge(&self, message: Uuid, cost: Decimal) -> Result<(), SupervisorError> {
+        self.record(message, cost);
+        self.check()
+    }
+
+    /// Raise the spend of `message` to `total`, for endpoints that report
+    /// their cost only in the reply.
+    fn settle(&self, message: Uuid, total: Decimal) {
+        let mut spent = self.lock();
+        let charged = spent.entry(message).or_default();
+        *charged = (*charged).max(total);
+    }
+}
+
+/// One planned sub-task.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Deserialize)]
+struct PlannedSubtask {
+    agent: String,
+    task: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct Plan {
+    #[serde(default)]
+    subtasks: Vec<PlannedSubtask>,
+}
+
+/// What happened to one sub-task.
+#[derive(Debug, Clone, Serialize)]
+pub struct SubtaskReport {
+    pub agent: String,
+    pub task: String,
+    pub completed: bool,
+    /// The worker's reply, or why it failed.
+    pub output: String,
+}
+
+/// Result of a supervised run.
+#[derive(Debug, Clone, Serialize)]
+pub struct SupervisorOutcome {
+    pub answer: String,
+    pub subtasks: Vec<SubtaskReport>,
+    /// LLM cost of planning, workers, and synthesis.
+    pub cost: Decimal,
+}
+
+/// Decomposes requests and delegates them to worker agents on a bus.
+pub struct Supervisor {
+    name: String,
+    bus: Arc<AgentBus>,
+    llm: Arc<dyn LlmProvider>,
+    limits: SupervisorLimits,
+}
+
+impl Supervisor {
+    /// A supervisor that sends messages as `name`.
+    pub fn new(name: impl Into<String>, bus: Arc<AgentBus>, llm: Arc<dyn LlmProvider>) -> Self {
+        Self {
+            name: name.into(),
+            bus,
+            llm,
+            limits: SupervisorLimits::default(),
+        }
+    }
+
+    pub fn with_limits(mut self, limits: SupervisorLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    pub fn bus(&self) -> &Arc<AgentBus> {
+        &self.bus
+    }
+
+    /// Plan, dispatch, and synthesize an answer to `request`.
+    pub async fn run(&self, request: &str) -> Result<SupervisorOutcome, SupervisorError> {
+        let workers: Vec<(String, String)> = self
+            .bus
+            .agents()
+            .await
+            .into_iter()
+            .filter(|(name, _)| *name != self.name)
+            .collect();
+        if workers.is_empty() {
+            return Err(SupervisorError::NoWorkers);
+        }
+
+        let spend = Arc::new(SpendMeter::new(self.limits.budget));
+        let (plan, plan_cost) = self.plan(request, &workers).await?;
+        spend.charge(Uuid::nil(), plan_cost)?;
+
+        let known: HashSet<&str> = workers.iter().map(|(n, _)| n.as_str()).collect();
+        let subtasks = limit_plan(plan.subtasks, &known, self.limits.max_subtasks);
+        tracing::info!(
+            "Supervisor '{}' dispatching {} sub-task(s)",
+            self.name,
+            subtasks.len()
+        );
+
+        let replies = join_all(subtasks.iter().map(|s| self.dispatch(s, &spend))).await;
+
+        let mut reports = Vec::with_capacity(subtasks.len());
+        for (subtask, reply) in subtasks.into_iter().zip(replies) {
+            let (completed, output) = match reply {
+                Ok(reply) => (true, reply.content),
+                Err(e) => (false, e),
+            };
+            reports.push(SubtaskReport {
+                agent: subtask.agent,
+                task: subtask.task,
+                completed,
+                output,
+            });
+        }
+        spend.check()?;
+
+        let (answer, synth_cost) = self.synthesize(request, &reports).await?;
+        spend.record(Uuid::nil(), synth_cost);
+
+        Ok(SupervisorOutcome {
+            answer,
+            subtasks: reports,
+            cost: spend.spent(),
+        })
+    }
+
+    /// Send one sub-task on the request's budget, unless it is already
+    /// spent.
+    async fn dispatch(
+        &self,
+        subtask: &PlannedSubtask,
+        spend: &Arc<SpendMeter>,
+    ) -> Result<AgentReply, String> {
+        spend.check().map_err(|e| e.to_string())?;
+        let message = AgentMessage::new(&self.name, &subtask.agent, &subtask.task)
+            .with_spend(Arc::clone(spend));
+        let id = message.id;
+        let reply = self.bus.send(message).await.map_err(|e| e.to_string())?;
+        spend.settle(id, reply.cost);
+        Ok(reply)
+    }
+
+    async fn plan(
+        &self,
+        request: &str,
+        workers: &[(String, String)],
+    ) -> Result<(Plan, Decimal), SupervisorError> {
+        let roster = workers
+            .iter()
+            .map(|(name, description)| format!("- {}: {}", name, description))
+            .collect::<Vec<_>>()
+            .join("\n");
+        let prompt = format!(
+            r#"You coordinate a team of agents. Split the user's request into independent sub-tasks and assign each to the best agent.
+
+Agents:
+{}
+
+Rules:
+- At most {} sub-tasks. Each task must be self-contained: the agent sees only its task, not the request.
+- Only use the agents listed above.
+- Return an empty list if the request needs no delegation.
+
+Respond with JSON only:
+{{"subtasks": [{{"agent": "<name>", "task": "<instructions>"}}]}}"#,
+            roster, self.limits.max_subtasks
+        );
+
+        let response = self
+            .llm
+            .complete(
+                CompletionRequest::new(vec![
+                    ChatMessage::system(prompt),
+                    ChatMessage::user(request),
+                ])
+                .with_max_tokens(2048)
+                .with_temperature(0.2),
+            )
+            .await?;
+        let cost = self
+            .llm
+            .calculate_cost(response.input_tokens, response.output_tokens);
+
+        let json = extract_json(&response.content).unwrap_or(&response.content);
+        let plan: Plan =
+            serde_json::from_str(json).map_err(|e| SupervisorError::InvalidPlan(e.to_string()))?;
+        Ok((plan, cost))
+    }
+
+    async fn synthesize(
+        &self,
+        request: &str,
+        reports: &[SubtaskReport],
+    ) -> Result<(String, Decimal), SupervisorError> {
+        let results = if reports.is_empty() {
+            "(no sub-tasks were dispatched)".to_string()
+        } else {
+            reports
+                .iter()
+                .map(|r| {
+                    format!(
+                        "### {} ({})\nTask: {}\n\n{}",
+                        r.agent,
+                        if r.completed { "completed" } else { "failed" },
+                        r.task,
+                        r.output
+                    )
+                })
+                .collect::<Vec<_>>()
+                .join("\n\n")
+        };
+        let prompt = "You coordinate a team of agents. Using their results below, write the \
+                      final answer to the user's request. Say plainly if a failed sub-task \
+                      leaves part of the request unanswered.";
+
+        let response = self
+            .llm
+            .complete(
+                CompletionRequest::new(vec![
+                    ChatMessage::system(prompt),
+                    ChatMessage::user(format!(
+                        "Request:\n{}\n\n## Agent results\n\n{}",
+                        request, results
+                    )),
+                ])
+                .with_max_tokens(4096)
+                .with_temperature(0.5),
+            )
+            .await?;
+        let cost = self
+            .llm
+            .calculate_cost(response.input_tokens, response.output_tokens);
+        Ok((response.content, cost))
+    }
+}
+
+/// Drop sub-tasks for unknown agents and duplicates, then cap the count.
+fn limit_plan(
+    subtasks: Vec<PlannedSubtask>,
+    known: &HashSet<&str>,
+    max: usize,
+) -> Vec<PlannedSubtask> {
+    let mut seen = HashSet::new();
+    let mut kept = Vec::new();
+    for subtask in subtasks {
+        if !known.contains(subtask.agent.as_str()) {
+            tracing::warn!(
+                "Supervisor planned a task for unknown agent '{}'",
+                subtask.agent
+            );
+            continue;
+        }
+        if subtask.task.trim().is_empty() || !seen.insert(subtask.clone()) {
+            continue;
+        }
+        kept.push(subtask);
+    }
+    if kept.len() > max {
+        tracing::warn!(
+            "Supervisor planned {} sub-tasks; dispatching the first {}",
+            kept.len(),
+            max
+        );
+        kept.truncate(max);
+    }
+    kept
+}
+
+/// Shared dependencies for running registered agents as workers.
+#[derive(Clone)]
+pub struct WorkerRuntime {
+    pub safety: Arc<SafetyLayer>,
+    pub tools: Arc<ToolRegistry>,
+    pub context_manager: Arc<ContextManager>,
+    pub audit: Option<Arc<AuditLog>>,
+    /// User the workers' jobs and tool calls run as.
+    pub user_id: String,
+    /// LLM turns a worker may take on one sub-task.
+    pub max_iterations: usize,
+}
+
+/// Bus endpoint that runs a registered agent in-process.
+pub struct RegisteredWorker {
+    agent: AgentDefinition,
+    llm: Arc<dyn LlmProvider>,
+    workspace: Option<Arc<Workspace>>,
+    runtime: WorkerRuntime,
+    description: String,
+}
+
+impl RegisteredWorker {
+    /// `llm` should already use the agent's model override, if any.
+    pub fn new(agent: AgentDefinition, llm: Arc<dyn LlmProvider>, runtime: WorkerRuntime) -> Self {
+        let description = if agent.description.is_empty() {
+            format!("Agent '{}'", agent.name)
+        } else {
+            agent.description.clone()
+        };
+        Self {
+            agent,
+            llm,
+            workspace: None,
+            runtime,
+            description,
+        }
+    }
+
+    /// Load identity documents from the agent's workspace.
+    pub fn with_workspace(mut self, workspace: Arc<Workspace>) -> Self {
+        self.workspace = Some(workspace);
+        self
+    }
+
+    /// Whether the worker may call `tool_name`.
+    fn may_use(&self, tool_name: &str) -> bool {
+        tool_name != SUPERVISE_TOOL_NAME
+            && self.agent.tools.iter().any(|p| tool_matches(p, tool_name))
+    }
+
+    async fn run(&self, job_id: uuid::Uuid, message: &AgentMessage) -> Result<AgentReply, String> {
+        let identity = match self.workspace {
+            Some(ref ws) => ws
+                .system_prompt_with(
+                    ws.user_id(),
+                    &PromptContext::session(SessionKind::Supervisor),
+                )
+                .await
+                .unwrap_or_default(),
+            None => String::new(),
+        };
+        let reasoning = Reasoning::new(Arc::clone(&self.llm), Arc::clone(&self.runtime.safety))
+            .with_system_prompt(identity);
+
+        let mut available_tools = self
+            .runtime
+            .tools
+            .tool_definitions_for_user(&self.runtime.user_id)
+            .await;
+        available_tools.retain(|t| self.may_use(&t.name));
+
+        let mut ctx = ReasoningContext::new()
+            .with_tools(available_tools)
+            .with_message(ChatMessage::user(format!(
+                "You are the '{}' agent. The '{}' agent delegated this task to you. \
+                 Complete it and reply with the result only.\n\n{}",
+                self.agent.name, message.from, message.task
+            )));
+
+        let mut cost = Decimal::ZERO;
+        for _ in 0..self.runtime.max_iterations {
+            // Another worker on the same request may have used up the budget.
+            if let Some(ref spend) = message.spend {
+                spend.check().map_err(|e| e.to_string())?;
+            }
+            let output = reasoning
+                .respond_with_tools(&ctx)
+                .await
+                .map_err(|e| e.to_string())?;
+            let step_cost = self
+                .llm
+                .calculate_cost(output.usage.input_tokens, output.usage.output_tokens);
+            cost += step_cost;
+            if let Some(ref spend) = message.spend {
+                spend
+                    .charge(message.id, step_cost)
+                    .map_err(|e| e.to_string())?;
+            }
+
+            let (tool_calls, content) = match output.result {
+                RespondResult::Text(content) => return Ok(AgentReply { content, cost }),
+                RespondResult::ToolCalls {
+                    tool_calls,
+                    content,
+                } => (tool_calls, content),
+            };
+            ctx.messages.push(ChatMessage::assistant_with_tool_calls(
+                content,
+                tool_calls.clone(),
+            ));
+
+            for call in tool_calls {
+                let result = if self.may_use(&call.name) {
+                    Scheduler::execute_tool_task(
+                        Arc::clone(&self.runtime.tools),
+                        Arc::clone(&self.runtime.context_manager),
+                        Arc::clone(&self.runtime.safety),
+                        self.runtime.audit.clone(),
+                        self.workspace.clone(),
+                        job_id,
+                        &call.name,
+                        call.arguments.clone(),
+                    )
+                    .await
+                    .map(|out| match out.result {
+                        serde_json::Value::String(s) => s,
+                        other => other.to_string(),
+                    })
+                    .map_err(|e| e.to_string())
+                } else {
+                    Err(format!(
+                        "tool '{}' is not available to this agent",
+                        call.name
+                    ))
+                };
+
+                let content = match result {
+                    Ok(output) => {
+                        let sanitized = self
+                            .runtime
+                            .safety
+                            .sanitize_tool_output(&call.name, &output);
+                        self.runtime.safety.wrap_for_llm(
+                            &call.name,
+                            &sanitized.content,
+                            sanitized.was_modified,
+                        )
+                    }
+                    Err(e) => format!("Error: {}", e),
+                };
+                ctx.messages
+                    .push(ChatMessage::tool_result(&call.id, &call.name, content));
+            }
+        }
+
+        Err(format!(
+            "did not finish within {} steps",
+            self.runtime.max_iterations
+        ))
+    }
+}
+
+#[async_trait]
+impl AgentEndpoint for RegisteredWorker {
+    fn description(&self) -> &str {
+        &self.description
+    }
+
+    async fn handle(&self, message: &AgentMessage) -> Result<AgentReply, String> {
+        let job_id = self
+            .runtime
+            .context_manager
+            .create_job_for_user(
+                &self.runtime.user_id,
+                format!("{}: task from {}", self.agent.name, message.from),
+                &message.task,
+            )
+            .await
+            .map_err(|e| e.to_string())?;
+        let result = self.run(job_id, message).await;
+        let _ = self.runtime.context_manager.remove_job(job_id).await;
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use super::*;
+    use crate::llm::{
+        CompletionResponse, FinishReason, ToolCompletionRequest, ToolCompletionResponse,
+    };
+
+    struct ScriptedLlm {
+        responses: Mutex<Vec<String>>,
+    }
+
+    #[async_trait]
+    impl LlmProvider for ScriptedLlm {
+        fn model_name(&self) -> &str {
+            "scripted"
+        }
+
+        fn cost_per_token(&self) -> (Decimal, Decimal) {
+            (Decimal::new(1, 3), Decimal::ZERO)
+        }
+
+        async fn complete(&self, _: CompletionRequest) -> Result<CompletionResponse, LlmError> {
+            let mut responses = self.responses.lock().unwrap();
+            Ok(CompletionResponse {
+                content: responses.remove(0),
+                input_tokens: 100,
+                output_tokens: 10,
+                finish_reason: FinishReason::Stop,
+                response_id: None,
+            })
+        }
+
+        async fn complete_with_tools(
+            &self,
+            _: ToolCompletionRequest,
+        ) -> Result<ToolCompletionResponse, LlmError> {
+            unimplemented!()
+        }
+    }
+
+    struct Echo;
+
+    #[async_trait]
+    impl AgentEndpoint for Echo {
+        fn description(&self) -> &str {
+            "Repeats its task"
+        }
+
+        async fn handle(&self, message: &AgentMessage) -> Result<AgentReply, String> {
+            Ok(AgentReply {
+                content: format!("done: {}", message.task),
+                cost: Decimal::ONE,
+            })
+        }
+    }
+
+    fn scripted(responses: &[&str]) -> Arc<dyn LlmProvider> {
+        Arc::new(ScriptedLlm {
+            responses: Mutex::new(responses.iter().map(|s| s.to_string()).collect()),
+        })
+    }
+
+    #[tokio::test]
+    async fn test_run_plans_dispatches_and_synthesizes() {
+        let bus = Arc::new(AgentBus::new());
+        bus.register("research", Arc::new(Echo)).await;
+
+        let llm = scripted(&[
+            r#"Plan: {"subtasks": [
+                {"agent": "research", "task": "find sources"},
+                {"agent": "research", "task": "find sources"},
+                {"agent": "ghost", "task": "haunt"}
+            ]}"#,
+            "Final answer",
+        ]);
+        let outcome = Supervisor::new("boss", Arc::clone(&bus), llm)
+            .run("write a report")
+            .await
+            .unwrap();
+
+        assert_eq!(outcome.answer, "Final answer");
+        assert_eq!(outcome.subtasks.len(), 1);
+        assert!(outcome.subtasks[0].completed);
+        assert_eq!(outcome.subtasks[0].output, "done: find sources");
+        // Two LLM calls at $0.10 each plus the worker's $1.
+        assert_eq!(outcome.cost, Decimal::new(12, 1));
+        assert_eq!(bus.dispatches_from("boss").await.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_run_enforces_budget() {
+        let bus = Arc::new(AgentBus::new());
+        bus.register("research", Arc::new(Echo)).await;
+
+        let llm = scripted(&[r#"{"subtasks": [{"agent": "research", "task": "dig"}]}"#]);
+        let result = Supervisor::new("boss", bus, llm)
+            .with_limits(SupervisorLimits {
+                max_subtasks: 6,
+                budget: Some(Decimal::new(5, 1)),
+            })
+            .run("dig deep")
+            .await;
+        assert!(matches!(
+            result,
+            Err(SupervisorError::BudgetExceeded { .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_worker_stops_when_its_own_spend_exceeds_budget() {
+        use crate::config::SafetyConfig;
+        use crate::llm::{MockLlmProvider, MockResponse, ToolCall};
+        use crate::tools::builtin::EchoTool;
+
+        let tools = Arc::new(ToolRegistry::new());
+        tools.register(Arc::new(EchoTool)).await;
+        let runtime = WorkerRuntime {
+            safety: Arc::new(SafetyLayer::new(&SafetyConfig {
+                max_output_length: 100_000,
+                injection_check_enabled: true,
+            })),
+            tools,
+            context_manager: Arc::new(ContextManager::new(4)),
+            audit: None,
+            user_id: "default".to_string(),
+            max_iterations: 10,
+        };
+        // Every step calls a tool and costs well over the whole budget.
+        let worker_llm = Arc::new(
+            MockLlmProvider::new()
+                .with_cost(Decimal::ONE, Decimal::ZERO)
+                .with_fallback(MockResponse::ToolCalls {
+                    content: None,
+                    calls: vec![ToolCall {
+                        id: "call_1".to_string(),
+                        name: "echo".to_string(),
+                        arguments: serde_json::json!({"message": "again"}),
+                    }],
+                }),
+        );
+        let mut agent = AgentDefinition::new("research");
+        agent.tools = vec!["echo".to_string()];
+        let bus = Arc::new(AgentBus::new());
+        bus.register(
+            "research",
+            Arc::new(RegisteredWorker::new(
+                agent,
+                Arc::clone(&worker_llm) as Arc<dyn LlmProvider>,
+                runtime,
+            )),
+        )
+        .await;
+
+        let llm = scripted(&[r#"{"subtasks": [{"agent": "research", "task": "dig"}]}"#]);
+        let result = Supervisor::new("boss", bus, llm)
+            .with_limits(SupervisorLimits {
+                max_subtasks: 6,
+                budget: Some(Decimal::new(5, 1)),
+            })
+            .run("dig deep")
+            .await;
+
+        assert!(matches!(
+            result,
+            Err(SupervisorError::BudgetExceeded { .. })
+        ));
+        // The worker stopped after its first step instead of using all ten.
+        assert_eq!(worker_llm.request_count(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_run_without_workers() {
+        let bus = Arc::new(AgentBus::new());
+        bus.register("boss", Arc::new(Echo)).await;
+        let result = Supervisor::new("boss", bus, scripted(&[])).run("hi").await;
+        assert!(matches!(result, Err(SupervisorError::NoWorkers)));
+    }
+
+    #[test]
+    fn test_limit_plan_caps_subtasks() {
+        let known: HashSet<&str> = ["a"].into_iter().collect();
+        let planned = (0..5)
+            .map(|i| PlannedSubtask {
+                agent: "a".to_string(),
+                task: format!("task {}", i),
+            })
+            .collect();
+        assert_eq!(limit_plan(planned, &known, 3).len(), 3);
+    }
+}
@@ -0,0 +1,470 @@
+//! Job templates: reusable definitions for recurring jobs.
+//!
+//! A template is a workspace document at `templates/<name>.md`. Its body is
+//! the job prompt, with `{{variable}}` placeholders filled in at launch; its
+//! frontmatter sets defaults and the job's limits:
+//!
+//! ```text
+//! ---
+//! description: Summarize a repository's week
+//! variables:
+//!   - repo
+//!   - "since: 7 days ago"
+//! tools: [github, memory_*]
+//! model: gpt-4o-mini
+//! max_tokens: 40000
+//! ---
+//! Summarize what happened in {{repo}} since {{since}} and write the
+//! summary to reports/{{repo}}.md.
+//! ```
+//!
+//! - `variables`: `name` or `name: default`. Placeholders in the body that
+//!   aren't listed are required.
+//! - `tools`: tools the job may call (`prefix*` globs allowed); all when
+//!   omitted.
+//! - `model`: model to run the job on instead of the configured one.
+//! - `max_tokens`: token budget; the job fails once it is spent.
+//!
+//! Templates are launched by the `run_template` tool, by routines with a
+//! `template` action (cron, event, or webhook triggered), and by
+//! `ironclaw template run`.
+
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use uuid::Uuid;
+
+use crate::agent::Scheduler;
+use crate::context::ContextManager;
+use crate::db::Database;
+use crate::error::{JobError, WorkspaceError};
+use crate::workspace::Workspace;
+use crate::workspace::frontmatter::{parse_frontmatter, strip_frontmatter};
+
+/// Workspace directory holding job templates.
+pub const TEMPLATES_DIR: &str = "templates";
+
+/// Errors loading or launching a job template.
+#[derive(Debug, thiserror::Error)]
+pub enum TemplateError {
+    #[error("No job template named {0}")]
+    NotFound(String),
+
+    #[error("Invalid job template {path}: {reason}")]
+    Invalid { path: String, reason: String },
+
+    #[error("Template {template} needs a value for '{name}'")]
+    MissingVariable { template: String, name: String },
+
+    #[error("Template {template} has no variable '{name}'")]
+    UnknownVariable { template: String, name: String },
+
+    #[error(transparent)]
+    Job(#[from] JobError),
+
+    #[error(transparent)]
+    Workspace(#[from] WorkspaceError),
+}
+
+/// A parsed job template.
+#[derive(Debug, Clone, PartialEq)]
+pub struct JobTemplate {
+    pub name: String,
+    pub description: String,
+    /// Prompt with `{{variable}}` placeholders.
+    pub prompt: String,
+    /// Default values, by variable name.
+    pub defaults: BTreeMap<String, String>,
+    /// Tools the job may call; empty means all.
+    pub tools: Vec<String>,
+    pub model: Option<String>,
+    /// Token budget (0 = unlimited).
+    pub max_tokens: u64,
+}
+
+impl JobTemplate {
+    /// Parse a template document. `name` comes from the file name.
+    pub fn parse(name: &str, content: &str) -> Result<Self, TemplateError> {
+        let invalid = |reason: String| TemplateError::Invalid {
+            path: template_path(name),
+            reason,
+        };
+        if !is_identifier(name) {
+            return Err(invalid(
+                "name must be letters, digits, '_' or '-'".to_string(),
+            ));
+        }
+
+        let fields = parse_frontmatter(content);
+        let field = |key: &str| {
+            fields
+                .get(key)
+                .and_then(|v| v.as_str())
+                .map(str::trim)
+                .filter(|v| !v.is_empty())
+        };
+        let list = |key: &str| -> Vec<String> {
+            match fields.get(key) {
+                Some(serde_json::Value::Array(items)) => items
+                    .iter()
+                    .filter_map(|v| v.as_str())
+                    .map(|v| v.trim().to_string())
+                    .filter(|v| !v.is_empty())
+                    .collect(),
+                Some(serde_json::Value::String(v)) if !v.trim().is_empty() => {
+                    vec![v.trim().to_string()]
+                }
+                _ => Vec::new(),
+            }
+        };
+
+        let prompt = strip_frontmatter(content).trim().to_string();
+        if prompt.is_empty() {
+            return Err(invalid("prompt is empty".to_string()));
+        }
+
+        let mut defaults = BTreeMap::new();
+        for entry in list("variables") {
+            if let Some((variable, default)) = entry.split_once(':') {
+                let variable = variable.trim();
+                if !is_identifier(variable) {
+                    return Err(invalid(format!("invalid variable {:?}", entry)));
+                }
+                defaults.insert(variable.to_string(), default.trim().to_string());
+            } else if !is_identifier(&entry) {
+                return Err(invalid(format!("invalid variable {:?}", entry)));
+            }
+        }
+
+        let max_tokens = match field("max_tokens") {
+            Some(v) => v
+                .parse()
+                .map_err(|_| invalid(format!("max_tokens {:?} is not a number", v)))?,
+            None => 0,
+        };
+
+        let description = match field("description") {
+            Some(description) => description.to_string(),
+            None => prompt
+                .lines()
+                .map(|l| l.trim_start_matches('#').trim())
+                .find(|l| !l.is_empty())
+                .unwrap_or_default()
+                .to_string(),
+        };
+
+        Ok(Self {
+            name: name.to_string(),
+            description,
+            prompt,
+            defaults,
+            tools: list("tools"),
+            model: field("model").map(str::to_string),
+            max_tokens,
+        })
+    }
+
+    /// Names of the prompt's placeholders, in order of first use.
+    pub fn variables(&self) -> Vec<String> {
+        let mut variables: Vec<String> = Vec::new();
+        for (_, name) in placeholders(&self.prompt) {
+            if !variables.iter().any(|v| v == name) {
+                variables.push(name.to_string());
+            }
+        }
+        variables
+    }
+
+    /// Placeholders without a default, which every launch must supply.
+    pub fn required_variables(&self) -> Vec<String> {
+        self.variables()
+            .into_iter()
+            .filter(|v| !self.defaults.contains_key(v))
+            .collect()
+    }
+
+    /// The prompt with each placeholder replaced by its value or default.
+    ///
+    /// Values are inserted as-is and never expanded again.
+    pub fn render(&self, values: &BTreeMap<String, String>) -> Result<String, TemplateError> {
+        let variables = self.variables();
+        if let Some(name) = values.keys().find(|k| !variables.contains(k)) {
+            return Err(TemplateError::UnknownVariable {
+                template: self.name.clone(),
+                name: name.clone(),
+            });
+        }
+
+        let mut rendered = String::with_capacity(self.prompt.len());
+        let mut last = 0;
+        for (range, name) in placeholders(&self.prompt) {
+            let value = values
+                .get(name)
+                .or_else(|| self.defaults.get(name))
+                .ok_or_else(|| TemplateError::MissingVariable {
+                    template: self.name.clone(),
+                    name: name.to_string(),
+                })?;
+            rendered.push_str(&self.prompt[last..range.start]);
+            rendered.push_str(value);
+            last = range.end;
+        }
+        rendered.push_str(&self.prompt[last..]);
+        Ok(rendered)
+    }
+}
+
+/// `{{name}}` placeholders in `text`, with their byte ranges.
+fn placeholders(text: &str) -> Vec<(std::ops::Range<usize>, &str)> {
+    let mut found = Vec::new();
+    let mut offset = 0;
+    while let Some(start) = text[offset..].find("{{") {
+        let start = offset + start;
+        let Some(len) = text[start + 2..].find("}}") else {
+            break;
+        };
+        let end = start + 2 + len + 2;
+        let name = text[start + 2..end - 2].trim();
+        if is_identifier(name) {
+            found.push((start..end, name));
+            offset = end;
+        } else {
+            offset = start + 2;
+        }
+    }
+    found
+}
+
+fn is_identifier(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+}
+
+/// Workspace path of the template called `name`.
+pub fn template_path(name: &str) -> String {
+    format!("{}/{}.md", TEMPLATES_DIR, name)
+}
+
+/// The job templates in a workspace.
+#[derive(Clone)]
+pub struct JobTemplates {
+    workspace: Arc<Workspace>,
+}
+
+impl JobTemplates {
+    pub fn new(workspace: Arc<Workspace>) -> Self {
+        Self { workspace }
+    }
+
+    /// All valid templates, sorted by name. Invalid ones are logged and
+    /// skipped.
+    pub async fn list(&self) -> Result<Vec<JobTemplate>, TemplateError> {
+        let mut templates = Vec::new();
+        for path in self.workspace.list_all().await? {
+            let Some(name) = path
+                .strip_prefix(TEMPLATES_DIR)
+                .and_then(|p| p.strip_prefix('/'))
+                .and_then(|p| p.strip_suffix(".md"))
+                .filter(|name| !name.contains('/'))
+            else {
+                continue;
+            };
+            let doc = self.workspace.read(&path).await?;
+            match JobTemplate::parse(name, &doc.content) {
+                Ok(template) => templates.push(template),
+                Err(e) => tracing::warn!("Skipping job template: {}", e),
+            }
+        }
+        templates.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(templates)
+    }
+
+    /// The template called `name`.
+    pub async fn get(&self, name: &str) -> Result<JobTemplate, TemplateError> {
+        if !is_identifier(name) {
+            return Err(TemplateError::NotFound(name.to_string()));
+        }
+        match self.workspace.read(&template_path(name)).await {
+            Ok(doc) => JobTemplate::parse(name, &doc.content),
+            Err(WorkspaceError::DocumentNotFound { .. }) => {
+                Err(TemplateError::NotFound(name.to_string()))
+            }
+            Err(e) => Err(e.into()),
+        }
+    }
+}
+
+/// Starts jobs from templates on the scheduler.
+pub struct TemplateLauncher {
+    templates: JobTemplates,
+    context_manager: Arc<ContextManager>,
+    scheduler: Arc<Scheduler>,
+    store: Option<Arc<dyn Database>>,
+}
+
+impl TemplateLauncher {
+    pub fn new(
+        templates: JobTemplates,
+        context_manager: Arc<ContextManager>,
+        scheduler: Arc<Scheduler>,
+        store: Option<Arc<dyn Database>>,
+    ) -> Self {
+        Self {
+            templates,
+            context_manager,
+            scheduler,
+            store,
+        }
+    }
+
+    pub fn templates(&self) -> &JobTemplates {
+        &self.templates
+    }
+
+    /// Render template `name` with `values` and schedule it as a job for
+    /// `user_id`. Returns the job ID.
+    pub async fn launch(
+        &self,
+        user_id: &str,
+        name: &str,
+        values: &BTreeMap<String, String>,
+    ) -> Result<Uuid, TemplateError> {
+        let template = self.templates.get(name).await?;
+        let prompt = template.render(values)?;
+
+        let job_id = self
+            .context_manager
+            .create_job_for_user(user_id, &template.name, &prompt)
+            .await?;
+        self.context_manager
+            .update_context(job_id, |ctx| {
+                ctx.category = Some("template".to_string());
+                ctx.max_tokens = template.max_tokens;
+                ctx.allowed_tools = template.tools.clone();
+                ctx.model = template.model.clone();
+                ctx.metadata = serde_json::json!({
+                    "template": template.name,
+                    "variables": values,
+                });
+            })
+            .await?;
+
+        // Persist new job to database (fire-and-forget)
+        if let Some(ref store) = self.store
+            && let Ok(ctx) = self.context_manager.get_context(job_id).await
+        {
+            let store = Arc::clone(store);
+            tokio::spawn(async move {
+                if let Err(e) = store.save_job(&ctx).await {
+                    tracing::warn!("Failed to persist new job {}: {}", job_id, e);
+                }
+            });
+        }
+
+        self.scheduler.schedule(job_id).await?;
+        tracing::info!(template = %template.name, "Launched job {} from template", job_id);
+        Ok(job_id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WEEKLY: &str = "---\n\
+        description: Summarize a repository's week\n\
+        variables:\n  - repo\n  - \"since: 7 days ago\"\n\
+        tools: [github, memory_*]\n\
+        model: gpt-4o-mini\n\
+        max_tokens: 40000\n\
+        ---\n\
+        Summarize {{repo}} since {{ since }}. Write it to reports/{{repo}}.md.\n";
+
+    fn values(pairs: &[(&str, &str)]) -> BTreeMap<String, String> {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_parse() {
+        let template = JobTemplate::parse("weekly", WEEKLY).unwrap();
+        assert_eq!(template.description, "Summarize a repository's week");
+        assert_eq!(template.tools, vec!["github", "memory_*"]);
+        assert_eq!(template.model.as_deref(), Some("gpt-4o-mini"));
+        assert_eq!(template.max_tokens, 40000);
+        assert_eq!(template.variables(), vec!["repo", "since"]);
+        assert_eq!(template.required_variables(), vec!["repo"]);
+
+        assert!(JobTemplate::parse("weekly", "---\nmodel: x\n---\n").is_err());
+        assert!(JobTemplate::parse("../etc", "Do it").is_err());
+        assert!(JobTemplate::parse("weekly", "---\nmax_tokens: lots\n---\nDo it").is_err());
+    }
+
+    #[test]
+    fn test_render() {
+        let template = JobTemplate::parse("weekly", WEEKLY).unwrap();
+        assert_eq!(
+            template.render(&values(&[("repo", "ironclaw")])).unwrap(),
+            "Summarize ironclaw since 7 days ago. Write it to reports/ironclaw.md."
+        );
+        assert_eq!(
+            template
+                .render(&values(&[("repo", "{{since}}"), ("since", "monday")]))
+                .unwrap(),
+            "Summarize {{since}} since monday. Write it to reports/{{since}}.md."
+        );
+        assert!(matches!(
+            template.render(&BTreeMap::new()),
+            Err(TemplateError::MissingVariable { name, .. }) if name == "repo"
+        ));
+        assert!(matches!(
+            template.render(&values(&[("repo", "x"), ("branch", "main")])),
+            Err(TemplateError::UnknownVariable { name, .. }) if name == "branch"
+        ));
+    }
+
+    #[test]
+    fn test_placeholders_ignore_non_identifiers() {
+        let template = JobTemplate::parse("json", "Reply with {{\"a\": 1}} for {{who}}").unwrap();
+        assert_eq!(template.variables(), vec!["who"]);
+        assert_eq!(
+            template.render(&values(&[("who", "me")])).unwrap(),
+            "Reply with {{\"a\": 1}} for me"
+        );
+    }
+
+    #[cfg(feature = "libsql")]
+    #[tokio::test]
+    async fn test_workspace_templates() {
+        use crate::db::libsql_backend::test_db;
+
+        let (db, _dir) = test_db().await;
+        let workspace = Arc::new(Workspace::new_with_db("default", db));
+        workspace
+            .write("templates/weekly.md", WEEKLY)
+            .await
+            .unwrap();
+        workspace
+            .write("templates/broken.md", "---\nmodel: x\n---\n")
+            .await
+            .unwrap();
+        workspace
+            .write("notes/other.md", "Not a template")
+            .await
+            .unwrap();
+
+        let templates = JobTemplates::new(workspace);
+        let listed = templates.list().await.unwrap();
+        assert_eq!(listed.len(), 1);
+        assert_eq!(listed[0].name, "weekly");
+        assert_eq!(templates.get("weekly").await.unwrap(), listed[0]);
+        assert!(matches!(
+            templates.get("missing").await,
+            Err(TemplateError::NotFound(_))
+        ));
+    }
+}
@@ -0,0 +1,121 @@
+//! Repair turns for malformed tool calls.
+//!
+//! Models sometimes emit tool calls with unparseable JSON arguments, with
+//! arguments that don't match the tool's schema, or for tools that don't
+//! exist. Instead of executing those (and failing the job on the resulting
+//! error), the worker feeds the validation error back as the tool result and
+//! lets the model retry, up to `MAX_REPAIR_ATTEMPTS` consecutive times.
+
+use crate::tools::{ToolRegistry, validate_params};
+
+/// Maximum consecutive turns containing invalid tool calls before giving up.
+pub const MAX_REPAIR_ATTEMPTS: u32 = 3;
+
+/// Validate a tool call before execution.
+///
+/// Returns `None` if the call can be executed, or the repair prompt to send
+/// back to the model in place of the tool result.
+pub async fn check_call(
+    tools: &ToolRegistry,
+    tool_name: &str,
+    arguments: &serde_json::Value,
+) -> Option<String> {
+    let Some(tool) = tools.get(tool_name).await else {
+        let mut available = tools.list().await;
+        available.sort();
+        return Some(unknown_tool_prompt(tool_name, &available));
+    };
+
+    let schema = tool.parameters_schema();
+    let problems = validate_params(&schema, arguments);
+    if problems.is_empty() {
+        return None;
+    }
+    Some(invalid_arguments_prompt(
+        tool_name, arguments, &schema, &problems,
+    ))
+}
+
+fn unknown_tool_prompt(tool_name: &str, available: &[String]) -> String {
+    format!(
+        "Error: tool '{}' does not exist. Available tools: {}. \
+         Call one of these tools instead, or respond without a tool call.",
+        tool_name,
+        available.join(", ")
+    )
+}
+
+fn invalid_arguments_prompt(
+    tool_name: &str,
+    arguments: &serde_json::Value,
+    schema: &serde_json::Value,
+    problems: &[String],
+) -> String {
+    // Unparseable JSON is carried through as the raw string.
+    let received = match arguments {
+        serde_json::Value::String(raw) => format!("unparseable JSON: {}", raw),
+        other => other.to_string(),
+    };
+    let schema = serde_json::to_string_pretty(schema).unwrap_or_else(|_| schema.to_string());
+    format!(
+        "Error: invalid arguments for tool '{}': {}.\n\
+         Received {}\n\
+         Expected parameters (JSON Schema):\n{}\n\
+         Call '{}' again with corrected arguments as a JSON object.",
+        tool_name,
+        problems.join("; "),
+        received,
+        schema,
+        tool_name
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::tools::builtin::EchoTool;
+
+    async fn registry() -> ToolRegistry {
+        let registry = ToolRegistry::new();
+        registry.register(Arc::new(EchoTool)).await;
+        registry
+    }
+
+    #[tokio::test]
+    async fn test_valid_call_passes() {
+        let tools = registry().await;
+        let args = serde_json::json!({"message": "hi"});
+        assert!(check_call(&tools, "echo", &args).await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_unknown_tool_lists_available() {
+        let tools = registry().await;
+        let prompt = check_call(&tools, "ecko", &serde_json::json!({}))
+            .await
+            .unwrap();
+        assert!(prompt.contains("'ecko' does not exist"));
+        assert!(prompt.contains("Available tools: echo"));
+    }
+
+    #[tokio::test]
+    async fn test_malformed_json_includes_schema() {
+        let tools = registry().await;
+        let args = crate::llm::parse_tool_arguments(r#"{"message": "hi"#);
+        let prompt = check_call(&tools, "echo", &args).await.unwrap();
+        assert!(prompt.contains("arguments must be a JSON object"));
+        assert!(prompt.contains(r#"unparseable JSON: {"message": "hi"#));
+        assert!(prompt.contains("\"required\""));
+    }
+
+    #[tokio::test]
+    async fn test_missing_required_parameter() {
+        let tools = registry().await;
+        let prompt = check_call(&tools, "echo", &serde_json::json!({}))
+            .await
+            .unwrap();
+        assert!(prompt.contains("missing required parameter 'message'"));
+    }
+}
@@ -0,0 +1,605 @@
+//! Job transcript recording and replay.
+//!
+//! Every LLM round-trip a job makes is persisted as a [`TranscriptEntry`]:
+//! the full request (messages, tool definitions, sampling params) and the
+//! model's response (text, tool calls, token usage). Tool calls and their
+//! results are captured implicitly, since each request carries the results
+//! of the previous step's calls.
+//!
+//! [`replay`] re-runs a recorded transcript against any provider. The first
+//! request's messages seed the conversation; tool calls are answered from
+//! the recorded results (or from per-tool mocks) instead of executing
+//! anything, so replays are side-effect free. The report marks the first
+//! step where the new run's tool calls stop matching the recording, which
+//! is what a prompt or tool-description change regression test looks at.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicI32, Ordering};
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::db::Database;
+use crate::error::LlmError;
+use crate::llm::{
+    ChatMessage, CompletionRequest, CompletionResponse, FinishReason, LlmProvider, ModelMetadata,
+    Role, ToolCall, ToolCompletionRequest, ToolCompletionResponse, ToolDefinition,
+};
+
+/// Default cap on replayed LLM calls.
+pub const DEFAULT_MAX_REPLAY_STEPS: usize = 50;
+
+/// The request half of a recorded LLM call.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TranscriptRequest {
+    pub messages: Vec<ChatMessage>,
+    /// Empty for plain completions.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tools: Vec<ToolDefinition>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_tokens: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub temperature: Option<f32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tool_choice: Option<String>,
+}
+
+impl TranscriptRequest {
+    fn from_completion(request: &CompletionRequest) -> Self {
+        Self {
+            messages: request.messages.clone(),
+            tools: Vec::new(),
+            max_tokens: request.max_tokens,
+            temperature: request.temperature,
+            tool_choice: None,
+        }
+    }
+
+    fn from_tool_completion(request: &ToolCompletionRequest) -> Self {
+        Self {
+            messages: request.messages.clone(),
+            tools: request.tools.clone(),
+            max_tokens: request.max_tokens,
+            temperature: request.temperature,
+            tool_choice: request.tool_choice.clone(),
+        }
+    }
+}
+
+/// The response half of a recorded LLM call.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TranscriptResponse {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tool_calls: Vec<ToolCall>,
+    pub input_tokens: u32,
+    pub output_tokens: u32,
+    pub finish_reason: FinishReason,
+}
+
+impl From<&CompletionResponse> for TranscriptResponse {
+    fn from(response: &CompletionResponse) -> Self {
+        Self {
+            content: Some(response.content.clone()),
+            tool_calls: Vec::new(),
+            input_tokens: response.input_tokens,
+            output_tokens: response.output_tokens,
+            finish_reason: response.finish_reason,
+        }
+    }
+}
+
+impl From<&ToolCompletionResponse> for TranscriptResponse {
+    fn from(response: &ToolCompletionResponse) -> Self {
+        Self {
+            content: response.content.clone(),
+            tool_calls: response.tool_calls.clone(),
+            input_tokens: response.input_tokens,
+            output_tokens: response.output_tokens,
+            finish_reason: response.finish_reason,
+        }
+    }
+}
+
+/// One recorded LLM call within a job.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TranscriptEntry {
+    /// Position within the job's transcript, starting at 0.
+    pub seq: i32,
+    /// Model that served the request.
+    pub model: String,
+    pub request: TranscriptRequest,
+    pub response: TranscriptResponse,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Records every successful completion of a job to its transcript.
+///
+/// Failed requests are not recorded; they never became part of the
+/// conversation.
+pub struct RecordingProvider {
+    inner: Arc<dyn LlmProvider>,
+    job_id: Uuid,
+    store: Arc<dyn Database>,
+    next_seq: AtomicI32,
+}
+
+impl RecordingProvider {
+    pub fn new(inner: Arc<dyn LlmProvider>, job_id: Uuid, store: Arc<dyn Database>) -> Self {
+        Self {
+            inner,
+            job_id,
+            store,
+            next_seq: AtomicI32::new(0),
+        }
+    }
+
+    async fn record(&self, request: TranscriptRequest, response: TranscriptResponse) {
+        let entry = TranscriptEntry {
+            seq: self.next_seq.fetch_add(1, Ordering::SeqCst),
+            model: self.inner.active_model_name(),
+            request,
+            response,
+            created_at: Utc::now(),
+        };
+        if let Err(e) = self
+            .store
+            .append_transcript_entry(self.job_id, &entry)
+            .await
+        {
+            tracing::warn!(job_id = %self.job_id, "Failed to record transcript entry: {}", e);
+        }
+    }
+}
+
+#[async_trait]
+impl LlmProvider for RecordingProvider {
+    fn model_name(&self) -> &str {
+        self.inner.model_name()
+    }
+
+    fn cost_per_token(&self) -> (Decimal, Decimal) {
+        self.inner.cost_per_token()
+    }
+
+    async fn complete(&self, request: CompletionRequest) -> Result<CompletionResponse, LlmError> {
+        let recorded = TranscriptRequest::from_completion(&request);
+        let response = self.inner.complete(request).await?;
+        self.record(recorded, (&response).into()).await;
+        Ok(response)
+    }
+
+    async fn complete_with_tools(
+        &self,
+        request: ToolCompletionRequest,
+    ) -> Result<ToolCompletionResponse, LlmError> {
+        let recorded = TranscriptRequest::from_tool_completion(&request);
+        let response = self.inner.complete_with_tools(request).await?;
+        self.record(recorded, (&response).into()).await;
+        Ok(response)
+    }
+
+    async fn list_models(&self) -> Result<Vec<String>, LlmError> {
+        self.inner.list_models().await
+    }
+
+    async fn model_metadata(&self) -> Result<ModelMetadata, LlmError> {
+        self.inner.model_metadata().await
+    }
+
+    fn active_model_name(&self) -> String {
+        self.inner.active_model_name()
+    }
+
+    fn set_model(&self, model: &str) -> Result<(), LlmError> {
+        self.inner.set_model(model)
+    }
+
+    fn seed_response_chain(&self, thread_id: &str, response_id: String) {
+        self.inner.seed_response_chain(thread_id, response_id)
+    }
+
+    fn get_response_chain_id(&self, thread_id: &str) -> Option<String> {
+        self.inner.get_response_chain_id(thread_id)
+    }
+
+    fn supports_vision(&self) -> bool {
+        self.inner.supports_vision()
+    }
+
+    fn calculate_cost(&self, input_tokens: u32, output_tokens: u32) -> Decimal {
+        self.inner.calculate_cost(input_tokens, output_tokens)
+    }
+}
+
+/// Error from [`replay`].
+#[derive(Debug, thiserror::Error)]
+pub enum ReplayError {
+    #[error("Transcript is empty")]
+    Empty,
+
+    #[error("LLM error during replay: {0}")]
+    Llm(#[from] LlmError),
+}
+
+/// How a replay answers tool calls.
+#[derive(Debug, Clone)]
+pub struct ReplayOptions {
+    /// Stop after this many LLM calls.
+    pub max_steps: usize,
+    /// Fixed output per tool name. Takes precedence over recorded results.
+    pub tool_mocks: HashMap<String, String>,
+}
+
+impl Default for ReplayOptions {
+    fn default() -> Self {
+        Self {
+            max_steps: DEFAULT_MAX_REPLAY_STEPS,
+            tool_mocks: HashMap::new(),
+        }
+    }
+}
+
+impl ReplayOptions {
+    /// Answer every call to `tool` with `output`.
+    pub fn with_tool_mock(mut self, tool: impl Into<String>, output: impl Into<String>) -> Self {
+        self.tool_mocks.insert(tool.into(), output.into());
+        self
+    }
+}
+
+/// One replayed LLM call alongside the recording at the same position.
+#[derive(Debug, Clone, Serialize)]
+pub struct ReplayStep {
+    pub response: TranscriptResponse,
+    pub recorded: Option<TranscriptResponse>,
+}
+
+impl ReplayStep {
+    /// Whether the model made the same tool calls (name and arguments) as
+    /// the recording. Two text-only responses match regardless of wording.
+    pub fn matches_recorded(&self) -> bool {
+        let Some(ref recorded) = self.recorded else {
+            return false;
+        };
+        self.response.tool_calls.len() == recorded.tool_calls.len()
+            && self
+                .response
+                .tool_calls
+                .iter()
+                .zip(&recorded.tool_calls)
+                .all(|(a, b)| a.name == b.name && a.arguments == b.arguments)
+    }
+}
+
+/// Outcome of a replay.
+#[derive(Debug, Clone, Serialize)]
+pub struct ReplayReport {
+    pub model: String,
+    pub steps: Vec<ReplayStep>,
+    /// Index of the first step that doesn't match the recording.
+    pub diverged_at: Option<usize>,
+    /// False when the replay stopped at `max_steps` with tool calls pending.
+    pub completed: bool,
+}
+
+/// Re-run a recorded transcript against `llm`.
+///
+/// The conversation is seeded with the first recorded request's messages
+/// and continues until the model answers without tool calls or
+/// `options.max_steps` is reached. Each step reuses the tool definitions and
+/// sampling params recorded at the same position (or the last recorded
+/// step, once the replay runs longer than the original).
+pub async fn replay(
+    transcript: &[TranscriptEntry],
+    llm: &dyn LlmProvider,
+    options: &ReplayOptions,
+) -> Result<ReplayReport, ReplayError> {
+    let first = transcript.first().ok_or(ReplayError::Empty)?;
+    let mut recorded_results = RecordedToolResults::collect(transcript);
+    let mut messages = first.request.messages.clone();
+    let mut steps = Vec::new();
+    let mut completed = false;
+
+    while steps.len() < options.max_steps {
+        let params = &transcript
+            .get(steps.len())
+            .or(transcript.last())
+            .unwrap_or(first)
+            .request;
+
+        let response = if params.tools.is_empty() {
+            let mut request = CompletionRequest::new(messages.clone());
+            request.max_tokens = params.max_tokens;
+            request.temperature = params.temperature;
+            TranscriptResponse::from(&llm.complete(request).await?)
+        } else {
+            let mut request = ToolCompletionRequest::new(messages.clone(), params.tools.clone());
+            request.max_tokens = params.max_tokens;
+            request.temperature = params.temperature;
+            request.tool_choice = params.tool_choice.clone();
+            TranscriptResponse::from(&llm.complete_with_tools(request).await?)
+        };
+
+        let tool_calls = response.tool_calls.clone();
+        let content = response.content.clone();
+        steps.push(ReplayStep {
+            response,
+            recorded: transcript
+                .get(steps.len())
+                .map(|entry| entry.response.clone()),
+        });
+
+        if tool_calls.is_empty() {
+            completed = true;
+            break;
+        }
+
+        messages.push(ChatMessage::assistant_with_tool_calls(
+            content,
+            tool_calls.clone(),
+        ));
+        for call in tool_calls {
+            let output = match options.tool_mocks.get(&call.name) {
+                Some(output) => output.clone(),
+                None => recorded_results.take(&call).unwrap_or_else(|| {
+                    format!(
+                        "Error: no recorded result for {} with these arguments",
+                        call.name
+                    )
+                }),
+            };
+            messages.push(ChatMessage::tool_result(call.id, call.name, output));
+        }
+    }
+
+    let diverged_at = steps.iter().position(|step| !step.matches_recorded());
+    Ok(ReplayReport {
+        model: llm.active_model_name(),
+        steps,
+        diverged_at,
+        completed,
+    })
+}
+
+/// Tool results seen in a transcript, keyed by the call that produced them.
+pub(crate) struct RecordedToolResults {
+    results: Vec<(ToolCall, String, bool)>,
+}
+
+impl RecordedToolResults {
+    pub(crate) fn collect(transcript: &[TranscriptEntry]) -> Self {
+        let mut calls: HashMap<&str, &ToolCall> = HashMap::new();
+        let mut results = Vec::new();
+        let mut seen = std::collections::HashSet::new();
+
+        for entry in transcript {
+            for message in &entry.request.messages {
+                for call in message.tool_calls.iter().flatten() {
+                    calls.insert(&call.id, call);
+                }
+                if message.role == Role::Tool
+                    && let Some(ref id) = message.tool_call_id
+                    && let Some(call) = calls.get(id.as_str())
+                    && seen.insert(id.clone())
+                {
+                    results.push(((*call).clone(), message.content.clone(), false));
+                }
+            }
+        }
+
+        Self { results }
+    }
+
+    /// Recorded calls and their results, in the order they were made.
+    pub(crate) fn iter(&self) -> impl Iterator<Item = (&ToolCall, &str)> {
+        self.results
+            .iter()
+            .map(|(call, output, _)| (call, output.as_str()))
+    }
+
+    /// Result for the first unused recorded call with the same name and
+    /// arguments; falls back to a used one for repeated identical calls.
+    fn take(&mut self, call: &ToolCall) -> Option<String> {
+        let same = |c: &ToolCall| c.name == call.name && c.arguments == call.arguments;
+        if let Some((_, output, used)) = self
+            .results
+            .iter_mut()
+            .find(|(c, _, used)| !used && same(c))
+        {
+            *used = true;
+            return Some(output.clone());
+        }
+        self.results
+            .iter()
+            .find(|(c, _, _)| same(c))
+            .map(|(_, output, _)| output.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::llm::{MockLlmProvider, MockResponse};
+
+    /// A mock provider that answers with `responses` in order.
+    fn scripted(responses: Vec<MockResponse>) -> MockLlmProvider {
+        let llm = MockLlmProvider::new();
+        for response in responses {
+            llm.push(response);
+        }
+        llm
+    }
+
+    fn call(id: &str, name: &str, arguments: serde_json::Value) -> ToolCall {
+        ToolCall {
+            id: id.to_string(),
+            name: name.to_string(),
+            arguments,
+        }
+    }
+
+    fn response(content: Option<&str>, tool_calls: Vec<ToolCall>) -> TranscriptResponse {
+        TranscriptResponse {
+            content: content.map(String::from),
+            finish_reason: if tool_calls.is_empty() {
+                FinishReason::Stop
+            } else {
+                FinishReason::ToolUse
+            },
+            tool_calls,
+            input_tokens: 10,
+            output_tokens: 5,
+        }
+    }
+
+    fn tool_response(r: TranscriptResponse) -> MockResponse {
+        match r.content {
+            Some(text) if r.tool_calls.is_empty() => MockResponse::Text(text),
+            content => MockResponse::ToolCalls {
+                content,
+                calls: r.tool_calls,
+            },
+        }
+    }
+
+    fn entry(
+        seq: i32,
+        messages: Vec<ChatMessage>,
+        response: TranscriptResponse,
+    ) -> TranscriptEntry {
+        TranscriptEntry {
+            seq,
+            model: "recorded-model".to_string(),
+            request: TranscriptRequest {
+                messages,
+                tools: vec![ToolDefinition {
+                    name: "read_file".to_string(),
+                    description: "Read a file".to_string(),
+                    parameters: serde_json::json!({"type": "object"}),
+                }],
+                max_tokens: Some(1024),
+                temperature: Some(0.2),
+                tool_choice: None,
+            },
+            response,
+            created_at: Utc::now(),
+        }
+    }
+
+    /// Two steps: read a file, then answer.
+    fn sample_transcript() -> Vec<TranscriptEntry> {
+        let read = call("c1", "read_file", serde_json::json!({"path": "a.txt"}));
+        let seed = vec![
+            ChatMessage::system("You are a worker."),
+            ChatMessage::user("Summarize a.txt"),
+        ];
+        let mut second = seed.clone();
+        second.push(ChatMessage::assistant_with_tool_calls(
+            None,
+            vec![read.clone()],
+        ));
+        second.push(ChatMessage::tool_result("c1", "read_file", "hello world"));
+
+        vec![
+            entry(0, seed, response(None, vec![read])),
+            entry(1, second, response(Some("It says hello."), vec![])),
+        ]
+    }
+
+    #[test]
+    fn test_entry_json_round_trip() {
+        let transcript = sample_transcript();
+        let json = serde_json::to_value(&transcript).unwrap();
+        assert_eq!(json[0]["response"]["finish_reason"], "tool_use");
+        let back: Vec<TranscriptEntry> = serde_json::from_value(json.clone()).unwrap();
+        assert_eq!(serde_json::to_value(&back).unwrap(), json);
+    }
+
+    #[tokio::test]
+    async fn test_replay_feeds_recorded_tool_results() {
+        let transcript = sample_transcript();
+        let llm = scripted(vec![
+            tool_response(response(
+                None,
+                vec![call(
+                    "new1",
+                    "read_file",
+                    serde_json::json!({"path": "a.txt"}),
+                )],
+            )),
+            tool_response(response(Some("Hello!"), vec![])),
+        ]);
+
+        let report = replay(&transcript, &llm, &ReplayOptions::default())
+            .await
+            .unwrap();
+
+        assert!(report.completed);
+        assert_eq!(report.steps.len(), 2);
+        assert_eq!(report.diverged_at, None);
+
+        let requests = llm.requests();
+        assert_eq!(requests[0].temperature, Some(0.2));
+        let last = requests[1].messages.last().unwrap();
+        assert_eq!(last.role, Role::Tool);
+        assert_eq!(last.tool_call_id.as_deref(), Some("new1"));
+        assert_eq!(last.content, "hello world");
+    }
+
+    #[tokio::test]
+    async fn test_replay_uses_mocks_and_reports_divergence() {
+        let transcript = sample_transcript();
+        let llm = scripted(vec![
+            tool_response(response(
+                None,
+                vec![call("x", "read_file", serde_json::json!({"path": "b.txt"}))],
+            )),
+            tool_response(response(Some("done"), vec![])),
+        ]);
+
+        let options = ReplayOptions::default().with_tool_mock("read_file", "mocked");
+        let report = replay(&transcript, &llm, &options).await.unwrap();
+
+        assert_eq!(report.diverged_at, Some(0));
+        let requests = llm.requests();
+        assert_eq!(requests[1].messages.last().unwrap().content, "mocked");
+    }
+
+    #[tokio::test]
+    async fn test_replay_stops_at_max_steps() {
+        let transcript = sample_transcript();
+        let looping = || {
+            tool_response(response(
+                None,
+                vec![call("x", "read_file", serde_json::json!({"path": "a.txt"}))],
+            ))
+        };
+        let llm = scripted(vec![looping(), looping(), looping()]);
+
+        let options = ReplayOptions {
+            max_steps: 3,
+            ..Default::default()
+        };
+        let report = replay(&transcript, &llm, &options).await.unwrap();
+
+        assert!(!report.completed);
+        assert_eq!(report.steps.len(), 3);
+        // Step 1 made a tool call where the recording answered.
+        assert_eq!(report.diverged_at, Some(1));
+    }
+
+    #[tokio::test]
+    async fn test_replay_empty_transcript() {
+        let llm = scripted(vec![]);
+        let err = replay(&[], &llm, &ReplayOptions::default())
+            .await
+            .unwrap_err();
+        assert!(matches!(err, ReplayError::Empty));
+    }
+}
@@ -7,16 +7,23 @@ use futures::future::join_all;
 use tokio::sync::mpsc;
 use uuid::Uuid;
 
-use crate::agent::scheduler::WorkerMessage;
+use crate::agent::episode::EpisodeRecorder;
+use crate::agent::scheduler::{WorkerMessage, check_job_egress};
 use crate::agent::task::TaskOutput;
+use crate::agent::tool_repair;
+use crate::audit::{AuditEvent, AuditLog};
 use crate::context::{ContextManager, JobState};
 use crate::db::Database;
 use crate::error::Error;
 use crate::llm::{
-    ActionPlan, ChatMessage, LlmProvider, Reasoning, ReasoningContext, RespondResult, ToolSelection,
+    ActionPlan, ChatMessage, LlmProvider, Reasoning, ReasoningContext, RespondResult,
+    ToolDefinition, ToolSelection,
 };
+use crate::metrics::Metrics;
 use crate::safety::SafetyLayer;
 use crate::tools::ToolRegistry;
+use crate::webhooks::{WebhookEvent, Webhooks};
+use crate::workspace::Workspace;
 
 /// Shared dependencies for worker execution.
 ///
@@ -29,6 +36,10 @@ pub struct WorkerDeps {
     pub safety: Arc<SafetyLayer>,
     pub tools: Arc<ToolRegistry>,
     pub store: Option<Arc<dyn Database>>,
+    pub audit: Option<Arc<AuditLog>>,
+    pub episodes: Option<Arc<EpisodeRecorder>>,
+    pub webhooks: Option<Arc<Webhooks>>,
+    pub workspace: Option<Arc<Workspace>>,
     pub timeout: Duration,
     pub use_planning: bool,
 }
@@ -79,6 +90,16 @@ impl Worker {
         self.deps.use_planning
     }
 
+    /// Tool definitions the job may use: the user's tools, narrowed to the
+    /// job's toolset if it has one.
+    async fn available_tools(&self, user_id: &str) -> Vec<ToolDefinition> {
+        let mut definitions = self.tools().tool_definitions_for_user(user_id).await;
+        if let Ok(ctx) = self.context_manager().get_context(self.job_id).await {
+            definitions.retain(|def| ctx.allows_tool(&def.name));
+        }
+        definitions
+    }
+
     /// Fire-and-forget persistence of job status.
     fn persist_status(&self, status: JobState, reason: Option<String>) {
         if let Some(store) = self.store() {
@@ -96,6 +117,11 @@ impl Worker {
     }
 
     /// Run the worker until the job is complete or stopped.
+    #[tracing::instrument(
+        name = "job.run",
+        skip_all,
+        fields(job_id = %self.job_id, user_id = tracing::field::Empty)
+    )]
     pub async fn run(self, mut rx: mpsc::Receiver<WorkerMessage>) -> Result<(), Error> {
         tracing::info!("Worker starting for job {}", self.job_id);
 
@@ -152,6 +178,36 @@ Report when the job is complete or if you encounter issues you cannot resolve."#
             }
         }
 
+        if let Some(ref episodes) = self.deps.episodes {
+            let job_ctx = self.context_manager().get_context(self.job_id).await?;
+            episodes.record_job(&job_ctx, &reason_ctx.messages);
+        }
+
+        if let Some(ref webhooks) = self.deps.webhooks {
+            let job_ctx = self.context_manager().get_context(self.job_id).await?;
+            let status = match job_ctx.state {
+                JobState::Completed => Some("completed"),
+                JobState::Failed => Some("failed"),
+                _ => None,
+            };
+            if let Some(status) = status {
+                let reason = job_ctx.transitions.last().and_then(|t| t.reason.as_deref());
+                webhooks.notify(WebhookEvent::job_completed(
+                    self.job_id,
+                    &job_ctx.user_id,
+                    &job_ctx.title,
+                    status,
+                    reason,
+                ));
+            }
+        }
+
+        // A stuck job may be repaired and resumed; keep its files until then.
+        let state = self.context_manager().get_context(self.job_id).await?.state;
+        if state != JobState::Stuck {
+            self.context_manager().release_temp_dir(self.job_id).await;
+        }
+
         Ok(())
     }
 
@@ -163,9 +219,16 @@ Report when the job is complete or if you encounter issues you cannot resolve."#
     ) -> Result<(), Error> {
         let max_iterations = 50;
         let mut iteration = 0;
+        let mut repair_attempts = 0;
+        let user_id = self
+            .context_manager()
+            .get_context(self.job_id)
+            .await?
+            .user_id;
+        tracing::Span::current().record("user_id", user_id.as_str());
 
         // Initial tool definitions for planning (will be refreshed in loop)
-        reason_ctx.available_tools = self.tools().tool_definitions().await;
+        reason_ctx.available_tools = self.available_tools(&user_id).await;
 
         // Generate plan if planning is enabled
         let plan = if self.use_planning() {
@@ -241,14 +304,42 @@ Report when the job is complete or if you encounter issues you cannot resolve."#
             }
 
             // Refresh tool definitions so newly built tools become visible
-            reason_ctx.available_tools = self.tools().tool_definitions().await;
+            reason_ctx.available_tools = self.available_tools(&user_id).await;
 
             // Select next tool(s) to use
             let selections = reasoning.select_tools(reason_ctx).await?;
+            let attempts_before = repair_attempts;
+            let mut executed = false;
+
+            // Invalid calls get a repair prompt instead of being executed.
+            // If every selected call was invalid, go straight to the repair turn.
+            let had_selections = !selections.is_empty();
+            let Some(selections) = self
+                .repair_invalid_calls(reason_ctx, selections, &mut repair_attempts)
+                .await?
+            else {
+                return Ok(());
+            };
+            if had_selections && selections.is_empty() {
+                continue;
+            }
 
             if selections.is_empty() {
                 // No tools from select_tools, ask LLM directly (may still return tool calls)
                 let respond_output = reasoning.respond_with_tools(reason_ctx).await?;
+                if let Some(limits) = self.context_manager().user_limits() {
+                    limits.record_tokens(&user_id, u64::from(respond_output.usage.total()));
+                }
+                let charged = self
+                    .context_manager()
+                    .update_context(self.job_id, |ctx| {
+                        ctx.add_tokens(u64::from(respond_output.usage.total()))
+                    })
+                    .await?;
+                if let Err(reason) = charged {
+                    self.mark_failed(&reason).await?;
+                    return Ok(());
+                }
 
                 match respond_output.result {
                     RespondResult::Text(response) => {
@@ -290,20 +381,33 @@ Report when the job is complete or if you encounter issues you cannot resolve."#
                                 tool_calls.clone(),
                             ));
 
-                        for tc in tool_calls {
-                            let result = self.execute_tool(&tc.name, &tc.arguments).await;
-
-                            // Create synthetic selection for process_tool_result
-                            let selection = ToolSelection {
-                                tool_name: tc.name.clone(),
-                                parameters: tc.arguments.clone(),
+                        // Create synthetic selections for process_tool_result
+                        let selections = tool_calls
+                            .into_iter()
+                            .map(|tc| ToolSelection {
+                                tool_name: tc.name,
+                                parameters: tc.arguments,
                                 reasoning: String::new(),
                                 alternatives: vec![],
-                                tool_call_id: tc.id.clone(),
-                            };
+                                tool_call_id: tc.id,
+                            })
+                            .collect();
+
+                        let Some(selections) = self
+                            .repair_invalid_calls(reason_ctx, selections, &mut repair_attempts)
+                            .await?
+                        else {
+                            return Ok(());
+                        };
+
+                        for selection in selections {
+                            let result = self
+                                .execute_tool(&selection.tool_name, &selection.parameters)
+                                .await;
 
                             self.process_tool_result(reason_ctx, &selection, result)
                                 .await?;
+                            executed = true;
                         }
                     }
                 }
@@ -323,6 +427,7 @@ Report when the job is complete or if you encounter issues you cannot resolve."#
 
                 self.process_tool_result(reason_ctx, selection, result)
                     .await?;
+                executed = true;
             } else {
                 // Multiple tools: execute in parallel
                 tracing::debug!(
@@ -338,6 +443,14 @@ Report when the job is complete or if you encounter issues you cannot resolve."#
                     self.process_tool_result(reason_ctx, selection, result.result)
                         .await?;
                 }
+                executed = true;
+            }
+
+            // The model recovered: a turn without invalid calls actually ran
+            // tools. Text-only turns don't count, so a model alternating
+            // between chatter and broken calls still hits the limit.
+            if executed && repair_attempts == attempts_before {
+                repair_attempts = 0;
             }
 
             // Small delay between iterations
@@ -345,6 +458,60 @@ Report when the job is complete or if you encounter issues you cannot resolve."#
         }
     }
 
+    /// Validate tool calls before execution, answering invalid ones with a
+    /// repair prompt (validation error plus the tool's schema).
+    ///
+    /// Returns the calls that are safe to execute. `repair_attempts` counts
+    /// turns with invalid calls since the last clean turn that executed
+    /// tools (the caller resets it); once it exceeds `MAX_REPAIR_ATTEMPTS`
+    /// the job is marked failed and `None` is returned.
+    async fn repair_invalid_calls(
+        &self,
+        reason_ctx: &mut ReasoningContext,
+        selections: Vec<ToolSelection>,
+        repair_attempts: &mut u32,
+    ) -> Result<Option<Vec<ToolSelection>>, Error> {
+        let mut valid = Vec::with_capacity(selections.len());
+        let mut invalid = 0;
+
+        for selection in selections {
+            match tool_repair::check_call(self.tools(), &selection.tool_name, &selection.parameters)
+                .await
+            {
+                None => valid.push(selection),
+                Some(prompt) => {
+                    tracing::warn!(
+                        "Job {} emitted invalid call to tool {}, requesting repair",
+                        self.job_id,
+                        selection.tool_name
+                    );
+                    invalid += 1;
+                    reason_ctx.messages.push(ChatMessage::tool_result(
+                        &selection.tool_call_id,
+                        &selection.tool_name,
+                        prompt,
+                    ));
+                }
+            }
+        }
+
+        if invalid == 0 {
+            return Ok(Some(valid));
+        }
+
+        *repair_attempts += 1;
+        if *repair_attempts > tool_repair::MAX_REPAIR_ATTEMPTS {
+            self.mark_failed(&format!(
+                "Model produced invalid tool calls {} turns in a row",
+                *repair_attempts
+            ))
+            .await?;
+            return Ok(None);
+        }
+
+        Ok(Some(valid))
+    }
+
     /// Execute multiple tools in parallel.
     async fn execute_tools_parallel(&self, selections: &[ToolSelection]) -> Vec<ToolExecResult> {
         let futures: Vec<_> = selections
@@ -357,6 +524,8 @@ Report when the job is complete or if you encounter issues you cannot resolve."#
                 let safety = self.safety().clone();
                 let job_id = self.job_id;
                 let store = self.deps.store.clone();
+                let audit = self.deps.audit.clone();
+                let workspace = self.deps.workspace.clone();
 
                 async move {
                     let result = Self::execute_tool_inner(
@@ -364,6 +533,8 @@ Report when the job is complete or if you encounter issues you cannot resolve."#
                         context_manager,
                         safety,
                         store,
+                        audit,
+                        workspace,
                         job_id,
                         &tool_name,
                         &params,
@@ -378,11 +549,19 @@ Report when the job is complete or if you encounter issues you cannot resolve."#
     }
 
     /// Inner tool execution logic that can be called from both single and parallel paths.
+    #[allow(clippy::too_many_arguments)]
+    #[tracing::instrument(
+        name = "tool.execute",
+        skip_all,
+        fields(tool = %tool_name, job_id = %job_id)
+    )]
     async fn execute_tool_inner(
         tools: Arc<ToolRegistry>,
         context_manager: Arc<ContextManager>,
         safety: Arc<SafetyLayer>,
         store: Option<Arc<dyn Database>>,
+        audit: Option<Arc<AuditLog>>,
+        workspace: Option<Arc<Workspace>>,
         job_id: Uuid,
         tool_name: &str,
         params: &serde_json::Value,
@@ -395,7 +574,7 @@ Report when the job is complete or if you encounter issues you cannot resolve."#
             })?;
 
         // Tools requiring approval are blocked in autonomous jobs
-        if tool.requires_approval() {
+        if tool.requires_approval_for(params) {
             return Err(crate::error::ToolError::AuthRequired {
                 name: tool_name.to_string(),
             }
@@ -412,6 +591,24 @@ Report when the job is complete or if you encounter issues you cannot resolve."#
             .into());
         }
 
+        if !tools.is_allowed(&job_ctx.user_id, tool_name) {
+            return Err(crate::error::ToolError::PermissionDenied {
+                name: tool_name.to_string(),
+                user_id: job_ctx.user_id.clone(),
+            }
+            .into());
+        }
+        if !job_ctx.allows_tool(tool_name) {
+            return Err(crate::error::ToolError::Disabled {
+                name: tool_name.to_string(),
+                reason: "not in this job's toolset".to_string(),
+            }
+            .into());
+        }
+        if tool.is_external_action() {
+            check_job_egress(workspace.as_deref(), tool_name, params).await?;
+        }
+
         // Validate tool parameters
         let validation = safety.validator().validate_tool_params(params);
         if !validation.is_valid {
@@ -435,11 +632,17 @@ Report when the job is complete or if you encounter issues you cannot resolve."#
             "Tool call started"
         );
 
-        // Execute with per-tool timeout and timing
+        // Execute with per-tool timeout and timing; cancelling the job
+        // abandons the call.
         let tool_timeout = tool.execution_timeout();
         let start = std::time::Instant::now();
         let result = tokio::time::timeout(tool_timeout, async {
-            tool.execute(params.clone(), &job_ctx).await
+            tokio::select! {
+                result = tool.execute(params.clone(), &job_ctx) => result,
+                _ = job_ctx.cancel.cancelled() => Err(crate::tools::ToolError::ExecutionFailed(
+                    "Job was cancelled".to_string(),
+                )),
+            }
         })
         .await;
         let elapsed = start.elapsed();
@@ -472,6 +675,24 @@ Report when the job is complete or if you encounter issues you cannot resolve."#
                 );
             }
         }
+        Metrics::global().record_tool(tool_name, elapsed, matches!(result, Ok(Ok(_))));
+
+        if let Some(audit) = audit {
+            let error = match &result {
+                Ok(Ok(_)) => None,
+                Ok(Err(e)) => Some(e.to_string()),
+                Err(_) => Some("timed out".to_string()),
+            };
+            audit
+                .record(AuditEvent::tool_execution(
+                    &job_ctx.user_id,
+                    tool_name,
+                    params,
+                    error.as_deref(),
+                    elapsed,
+                ))
+                .await;
+        }
 
         // Record action in memory and get the ActionRecord for persistence
         let action = match &result {
@@ -706,6 +927,8 @@ Report when the job is complete or if you encounter issues you cannot resolve."#
             self.context_manager().clone(),
             self.safety().clone(),
             self.deps.store.clone(),
+            self.deps.audit.clone(),
+            self.deps.workspace.clone(),
             self.job_id,
             tool_name,
             params,
@@ -731,6 +954,7 @@ Report when the job is complete or if you encounter issues you cannot resolve."#
             JobState::Completed,
             Some("Job completed successfully".to_string()),
         );
+        Metrics::global().job_completed();
         Ok(())
     }
 
@@ -746,6 +970,7 @@ Report when the job is complete or if you encounter issues you cannot resolve."#
             })?;
 
         self.persist_status(JobState::Failed, Some(reason.to_string()));
+        Metrics::global().job_failed();
         Ok(())
     }
 
@@ -847,6 +1072,108 @@ mod tests {
         ));
     }
 
+    #[cfg(feature = "libsql")]
+    #[tokio::test]
+    async fn test_job_external_action_refuses_private_data() {
+        use std::sync::Arc;
+        use std::sync::atomic::{AtomicBool, Ordering};
+        use std::time::Duration;
+
+        use super::Worker;
+        use crate::config::SafetyConfig;
+        use crate::context::{ContextManager, JobContext};
+        use crate::db::libsql_backend::test_db;
+        use crate::error::{Error, ToolError};
+        use crate::safety::SafetyLayer;
+        use crate::tools::{Tool, ToolOutput, ToolRegistry};
+        use crate::workspace::{Workspace, paths};
+
+        struct PostTool {
+            ran: Arc<AtomicBool>,
+        }
+
+        #[async_trait::async_trait]
+        impl Tool for PostTool {
+            fn name(&self) -> &str {
+                "post"
+            }
+            fn description(&self) -> &str {
+                "Posts a message somewhere public"
+            }
+            fn parameters_schema(&self) -> serde_json::Value {
+                serde_json::json!({"type": "object"})
+            }
+            async fn execute(
+                &self,
+                _params: serde_json::Value,
+                _ctx: &JobContext,
+            ) -> Result<ToolOutput, crate::tools::ToolError> {
+                self.ran.store(true, Ordering::SeqCst);
+                Ok(ToolOutput::text("posted", Duration::from_millis(1)))
+            }
+            fn is_external_action(&self) -> bool {
+                true
+            }
+        }
+
+        let (db, _dir) = test_db().await;
+        let workspace = Arc::new(Workspace::new_with_db("default", db));
+        workspace
+            .write(paths::MEMORY, "Reach me privately at jo@example.com")
+            .await
+            .unwrap();
+
+        let ran = Arc::new(AtomicBool::new(false));
+        let tools = Arc::new(ToolRegistry::new());
+        tools
+            .register(Arc::new(PostTool {
+                ran: Arc::clone(&ran),
+            }))
+            .await;
+        let context_manager = Arc::new(ContextManager::new(1));
+        let job_id = context_manager
+            .create_job_for_user("default", "post", "post an update")
+            .await
+            .unwrap();
+        let safety = Arc::new(SafetyLayer::new(&SafetyConfig {
+            max_output_length: 100_000,
+            injection_check_enabled: true,
+        }));
+
+        let run = |params: serde_json::Value| {
+            let tools = Arc::clone(&tools);
+            let context_manager = Arc::clone(&context_manager);
+            let safety = Arc::clone(&safety);
+            let workspace = Arc::clone(&workspace);
+            async move {
+                Worker::execute_tool_inner(
+                    tools,
+                    context_manager,
+                    safety,
+                    None,
+                    None,
+                    Some(workspace),
+                    job_id,
+                    "post",
+                    &params,
+                )
+                .await
+            }
+        };
+
+        let result = run(serde_json::json!({"text": "Write to jo@example.com"})).await;
+        assert!(matches!(
+            result,
+            Err(Error::Tool(ToolError::PrivateEgress { .. }))
+        ));
+        assert!(!ran.load(Ordering::SeqCst));
+
+        run(serde_json::json!({"text": "Release notes are out"}))
+            .await
+            .unwrap();
+        assert!(ran.load(Ordering::SeqCst));
+    }
+
     #[test]
     fn test_completion_tool_output_injection() {
         // A malicious tool output echoed by the LLM should not trigger
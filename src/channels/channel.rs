@@ -8,6 +8,7 @@ use futures::Stream;
 use uuid::Uuid;
 
 use crate::error::ChannelError;
+use crate::llm::ImageContent;
 
 /// A message received from an external channel.
 #[derive(Debug, Clone)]
@@ -28,6 +29,9 @@ pub struct IncomingMessage {
     pub received_at: DateTime<Utc>,
     /// Channel-specific metadata.
     pub metadata: serde_json::Value,
+    /// Image attachments sent with the message. Only the web gateway fills
+    /// this in today; other channels leave it empty.
+    pub attachments: Vec<ImageContent>,
 }
 
 impl IncomingMessage {
@@ -46,6 +50,7 @@ impl IncomingMessage {
             thread_id: None,
             received_at: Utc::now(),
             metadata: serde_json::Value::Null,
+            attachments: Vec::new(),
         }
     }
 
@@ -66,6 +71,12 @@ impl IncomingMessage {
         self.user_name = Some(name.into());
         self
     }
+
+    /// Set image attachments.
+    pub fn with_attachments(mut self, attachments: Vec<ImageContent>) -> Self {
+        self.attachments = attachments;
+        self
+    }
 }
 
 /// Stream of incoming messages.
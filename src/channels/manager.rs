@@ -1,17 +1,48 @@
 //! Channel manager for coordinating multiple input channels.
+//!
+//! Outgoing messages pass through the manager, which redacts secrets from
+//! them and applies the moderation policy (see
+//! [`crate::safety::moderation`]) before they reach a channel. Replies in
+//! shared conversations are also checked against the user's private
+//! documents (see [`crate::safety::check_context_egress`]) and withheld if
+//! they would reveal them.
 
 use std::collections::HashMap;
-use std::sync::Arc;
+use std::sync::{Arc, Weak};
 
+use chrono::{DateTime, Utc};
 use futures::stream;
-use tokio::sync::RwLock;
+use serde::Serialize;
+use tokio::sync::{Mutex, RwLock};
+use uuid::Uuid;
 
+use crate::audit::{AGENT_ACTOR, AuditEvent, AuditLog};
 use crate::channels::{Channel, IncomingMessage, MessageStream, OutgoingResponse, StatusUpdate};
+use crate::context::SessionScope;
 use crate::error::ChannelError;
+use crate::safety::{Moderation, SecretRedactor, check_context_egress};
+use crate::workspace::{Sensitivity, Workspace};
+
+type ChannelMap = HashMap<String, Box<dyn Channel>>;
+
+type HeldMap = HashMap<Uuid, HeldResponse>;
+
+/// Responses held for approval at once; the oldest is dropped beyond this.
+const MAX_HELD: usize = 200;
+
+/// Sent instead of a shared-conversation reply that would leak private data.
+const PRIVATE_EGRESS_NOTICE: &str =
+    "I can't answer that here without revealing private information. Ask me in a direct chat.";
 
 /// Manages multiple input channels and merges their message streams.
 pub struct ChannelManager {
-    channels: Arc<RwLock<HashMap<String, Box<dyn Channel>>>>,
+    channels: Arc<RwLock<ChannelMap>>,
+    audit: Option<Arc<AuditLog>>,
+    moderation: Option<Arc<Moderation>>,
+    held: Arc<Mutex<HeldMap>>,
+    /// Workspace whose private documents replies in shared conversations
+    /// are checked against.
+    egress: Option<Arc<Workspace>>,
 }
 
 impl ChannelManager {
@@ -19,6 +50,165 @@ impl ChannelManager {
     pub fn new() -> Self {
         Self {
             channels: Arc::new(RwLock::new(HashMap::new())),
+            audit: None,
+            moderation: None,
+            held: Arc::new(Mutex::new(HashMap::new())),
+            egress: None,
+        }
+    }
+
+    /// Record every outgoing message in the audit log.
+    pub fn with_audit(mut self, audit: Arc<AuditLog>) -> Self {
+        self.audit = Some(audit);
+        self
+    }
+
+    /// Moderate outgoing messages on the channels the policy covers.
+    pub fn with_moderation(mut self, moderation: Arc<Moderation>) -> Self {
+        self.moderation = Some(moderation);
+        self
+    }
+
+    /// Withhold replies in shared conversations that would reveal this
+    /// workspace's private documents.
+    pub fn with_egress_guard(mut self, workspace: Arc<Workspace>) -> Self {
+        self.egress = Some(workspace);
+        self
+    }
+
+    /// A handle for reviewing responses held by moderation.
+    pub fn moderation_queue(&self) -> ModerationQueue {
+        ModerationQueue {
+            channels: Arc::downgrade(&self.channels),
+            held: Arc::clone(&self.held),
+            audit: self.audit.clone(),
+        }
+    }
+
+    /// Whether messages on `channel` are moderated.
+    fn is_moderated(&self, channel: &str) -> bool {
+        self.moderation
+            .as_ref()
+            .is_some_and(|m| m.applies_to(channel))
+    }
+
+    /// Apply the moderation policy to `response` on `channel`. Returns the
+    /// response to deliver, or None if it is held for approval.
+    async fn moderate(
+        &self,
+        channel: &str,
+        user_id: &str,
+        reply_to: Option<&IncomingMessage>,
+        mut response: OutgoingResponse,
+    ) -> Option<OutgoingResponse> {
+        let Some(ref moderation) = self.moderation else {
+            return Some(response);
+        };
+        if !moderation.applies_to(channel) {
+            return Some(response);
+        }
+        let Some(intervention) = moderation.review(&response.content).await else {
+            return Some(response);
+        };
+
+        let outcome = intervention.action.outcome();
+        tracing::info!(
+            channel,
+            categories = ?intervention.categories,
+            "Moderation {} a response",
+            outcome
+        );
+        if let Some(ref audit) = self.audit {
+            audit
+                .record(AuditEvent::moderation(
+                    AGENT_ACTOR,
+                    channel,
+                    user_id,
+                    outcome,
+                    &intervention.categories,
+                    &response.content,
+                ))
+                .await;
+        }
+
+        match intervention.replacement {
+            Some(content) => {
+                response.content = content;
+                Some(response)
+            }
+            None => {
+                let held = HeldResponse {
+                    id: Uuid::new_v4(),
+                    channel: channel.to_string(),
+                    user_id: user_id.to_string(),
+                    content: response.content.clone(),
+                    categories: intervention.categories,
+                    held_at: Utc::now(),
+                    reply_to: reply_to.cloned(),
+                    response,
+                };
+                let mut queue = self.held.lock().await;
+                if queue.len() >= MAX_HELD
+                    && let Some(oldest) = queue.values().min_by_key(|h| h.held_at).map(|h| h.id)
+                {
+                    tracing::warn!(
+                        "Moderation queue is full, dropping held response {}",
+                        oldest
+                    );
+                    queue.remove(&oldest);
+                }
+                queue.insert(held.id, held);
+                None
+            }
+        }
+    }
+
+    /// Whether replies to a conversation with this metadata are checked
+    /// for private data.
+    fn guards_egress(&self, metadata: &serde_json::Value) -> bool {
+        self.egress.is_some() && SessionScope::from_metadata(metadata) == SessionScope::Shared
+    }
+
+    /// Replace a reply to a shared conversation with a notice if it carries
+    /// data from a private document.
+    async fn guard_egress(
+        &self,
+        msg: &IncomingMessage,
+        mut response: OutgoingResponse,
+    ) -> OutgoingResponse {
+        let Some(ref workspace) = self.egress else {
+            return response;
+        };
+        if !self.guards_egress(&msg.metadata) {
+            return response;
+        }
+        let private = match workspace.egress_documents(Sensitivity::Private).await {
+            Ok(docs) => docs.into_iter().map(|d| d.content).collect::<Vec<_>>(),
+            Err(e) => {
+                tracing::warn!("Could not load documents for the egress guard: {}", e);
+                Vec::new()
+            }
+        };
+        let private: Vec<&str> = private.iter().map(String::as_str).collect();
+        let findings = check_context_egress(&response.content, &private);
+        if !findings.is_empty() {
+            tracing::warn!(
+                channel = %msg.channel,
+                findings = findings.len(),
+                "Withholding a shared-conversation reply that would leak private data"
+            );
+            response.content = PRIVATE_EGRESS_NOTICE.to_string();
+        }
+        response
+    }
+
+    async fn audit_outgoing(&self, channel: &str, user_id: &str, content: &str, delivered: bool) {
+        if let Some(ref audit) = self.audit {
+            audit
+                .record(AuditEvent::message_out(
+                    user_id, channel, content, delivered,
+                ))
+                .await;
         }
     }
 
@@ -66,32 +256,53 @@ impl ChannelManager {
     }
 
     /// Send a response to a specific channel.
+    ///
+    /// Returns Ok without sending if moderation holds the response.
     pub async fn respond(
         &self,
         msg: &IncomingMessage,
-        response: OutgoingResponse,
+        mut response: OutgoingResponse,
     ) -> Result<(), ChannelError> {
-        let channels = self.channels.read().await;
-        if let Some(channel) = channels.get(&msg.channel) {
-            channel.respond(msg, response).await
-        } else {
-            Err(ChannelError::SendFailed {
-                name: msg.channel.clone(),
-                reason: "Channel not found".to_string(),
-            })
-        }
+        response.content = SecretRedactor::global().redact(&response.content);
+        let response = self.guard_egress(msg, response).await;
+        let Some(response) = self
+            .moderate(&msg.channel, &msg.user_id, Some(msg), response)
+            .await
+        else {
+            return Ok(());
+        };
+        let content = response.content.clone();
+        let result = deliver(
+            &self.channels,
+            &msg.channel,
+            &msg.user_id,
+            Some(msg),
+            response,
+        )
+        .await;
+        self.audit_outgoing(&msg.channel, &msg.user_id, &content, result.is_ok())
+            .await;
+        result
     }
 
     /// Send a status update to a specific channel.
     ///
     /// The metadata contains channel-specific routing info (e.g., Telegram chat_id)
     /// needed to deliver the status to the correct destination.
+    ///
+    /// Streamed text isn't sent on moderated channels or to guarded shared
+    /// conversations, where only the final response is checked.
     pub async fn send_status(
         &self,
         channel_name: &str,
         status: StatusUpdate,
         metadata: &serde_json::Value,
     ) -> Result<(), ChannelError> {
+        if matches!(status, StatusUpdate::StreamChunk(_))
+            && (self.is_moderated(channel_name) || self.guards_egress(metadata))
+        {
+            return Ok(());
+        }
         let channels = self.channels.read().await;
         if let Some(channel) = channels.get(channel_name) {
             channel.send_status(status, metadata).await
@@ -108,17 +319,17 @@ impl ChannelManager {
         &self,
         channel_name: &str,
         user_id: &str,
-        response: OutgoingResponse,
+        mut response: OutgoingResponse,
     ) -> Result<(), ChannelError> {
-        let channels = self.channels.read().await;
-        if let Some(channel) = channels.get(channel_name) {
-            channel.broadcast(user_id, response).await
-        } else {
-            Err(ChannelError::SendFailed {
-                name: channel_name.to_string(),
-                reason: "Channel not found".to_string(),
-            })
-        }
+        response.content = SecretRedactor::global().redact(&response.content);
+        let Some(response) = self.moderate(channel_name, user_id, None, response).await else {
+            return Ok(());
+        };
+        let content = response.content.clone();
+        let result = deliver(&self.channels, channel_name, user_id, None, response).await;
+        self.audit_outgoing(channel_name, user_id, &content, result.is_ok())
+            .await;
+        result
     }
 
     /// Broadcast a message to all channels.
@@ -127,13 +338,21 @@ impl ChannelManager {
     pub async fn broadcast_all(
         &self,
         user_id: &str,
-        response: OutgoingResponse,
+        mut response: OutgoingResponse,
     ) -> Vec<(String, Result<(), ChannelError>)> {
+        response.content = SecretRedactor::global().redact(&response.content);
         let channels = self.channels.read().await;
         let mut results = Vec::new();
 
         for (name, channel) in channels.iter() {
-            let result = channel.broadcast(user_id, response.clone()).await;
+            let Some(response) = self.moderate(name, user_id, None, response.clone()).await else {
+                results.push((name.clone(), Ok(())));
+                continue;
+            };
+            let content = response.content.clone();
+            let result = channel.broadcast(user_id, response).await;
+            self.audit_outgoing(name, user_id, &content, result.is_ok())
+                .await;
             results.push((name.clone(), result));
         }
 
@@ -142,14 +361,15 @@ impl ChannelManager {
 
     /// Check health of all channels.
     pub async fn health_check_all(&self) -> HashMap<String, Result<(), ChannelError>> {
-        let channels = self.channels.read().await;
-        let mut results = HashMap::new();
+        check_channels(&self.channels).await
+    }
 
-        for (name, channel) in channels.iter() {
-            results.insert(name.clone(), channel.health_check().await);
+    /// A handle for checking channel health after the manager has moved
+    /// into the agent.
+    pub fn health_probe(&self) -> ChannelHealthProbe {
+        ChannelHealthProbe {
+            channels: Arc::downgrade(&self.channels),
         }
-
-        results
     }
 
     /// Shutdown all channels.
@@ -169,6 +389,171 @@ impl ChannelManager {
     }
 }
 
+/// Send `response` on `channel_name`: as a reply to `reply_to`, or as a
+/// broadcast to `user_id`.
+async fn deliver(
+    channels: &RwLock<ChannelMap>,
+    channel_name: &str,
+    user_id: &str,
+    reply_to: Option<&IncomingMessage>,
+    response: OutgoingResponse,
+) -> Result<(), ChannelError> {
+    let channels = channels.read().await;
+    let Some(channel) = channels.get(channel_name) else {
+        return Err(ChannelError::SendFailed {
+            name: channel_name.to_string(),
+            reason: "Channel not found".to_string(),
+        });
+    };
+    match reply_to {
+        Some(msg) => channel.respond(msg, response).await,
+        None => channel.broadcast(user_id, response).await,
+    }
+}
+
+/// A response held by moderation until someone approves or rejects it.
+#[derive(Debug, Clone, Serialize)]
+pub struct HeldResponse {
+    pub id: Uuid,
+    pub channel: String,
+    /// Who the response is for.
+    pub user_id: String,
+    pub content: String,
+    /// What moderation flagged it for.
+    pub categories: Vec<String>,
+    pub held_at: DateTime<Utc>,
+    /// The message it answers; None for a broadcast.
+    #[serde(skip)]
+    reply_to: Option<IncomingMessage>,
+    #[serde(skip)]
+    response: OutgoingResponse,
+}
+
+/// Reviews responses held by moderation, without owning the channels.
+///
+/// The web gateway keeps one behind `/api/moderation`.
+#[derive(Clone)]
+pub struct ModerationQueue {
+    channels: Weak<RwLock<ChannelMap>>,
+    held: Arc<Mutex<HeldMap>>,
+    audit: Option<Arc<AuditLog>>,
+}
+
+impl ModerationQueue {
+    /// Held responses, oldest first.
+    pub async fn list(&self) -> Vec<HeldResponse> {
+        let mut held: Vec<HeldResponse> = self.held.lock().await.values().cloned().collect();
+        held.sort_by_key(|h| h.held_at);
+        held
+    }
+
+    /// Send held response `id` on behalf of `actor`. Returns false if no
+    /// such response is held; one that fails to send stays held.
+    pub async fn approve(&self, id: Uuid, actor: &str) -> Result<bool, ChannelError> {
+        let Some(held) = self.held.lock().await.remove(&id) else {
+            return Ok(false);
+        };
+        let Some(channels) = self.channels.upgrade() else {
+            self.held.lock().await.insert(id, held);
+            return Err(ChannelError::SendFailed {
+                name: "moderation".to_string(),
+                reason: "Channels have shut down".to_string(),
+            });
+        };
+
+        let result = deliver(
+            &channels,
+            &held.channel,
+            &held.user_id,
+            held.reply_to.as_ref(),
+            held.response.clone(),
+        )
+        .await;
+        if let Some(ref audit) = self.audit {
+            if result.is_ok() {
+                audit
+                    .record(AuditEvent::moderation(
+                        actor,
+                        &held.channel,
+                        &held.user_id,
+                        "approved",
+                        &held.categories,
+                        &held.content,
+                    ))
+                    .await;
+            }
+            audit
+                .record(AuditEvent::message_out(
+                    &held.user_id,
+                    &held.channel,
+                    &held.content,
+                    result.is_ok(),
+                ))
+                .await;
+        }
+        match result {
+            Ok(()) => Ok(true),
+            Err(e) => {
+                self.held.lock().await.insert(id, held);
+                Err(e)
+            }
+        }
+    }
+
+    /// Discard held response `id` on behalf of `actor`. Returns false if no
+    /// such response is held.
+    pub async fn reject(&self, id: Uuid, actor: &str) -> bool {
+        let Some(held) = self.held.lock().await.remove(&id) else {
+            return false;
+        };
+        if let Some(ref audit) = self.audit {
+            audit
+                .record(AuditEvent::moderation(
+                    actor,
+                    &held.channel,
+                    &held.user_id,
+                    "rejected",
+                    &held.categories,
+                    &held.content,
+                ))
+                .await;
+        }
+        true
+    }
+}
+
+/// Checks channel health without owning the channels.
+///
+/// Holds a weak reference, so a channel that keeps a probe (the web gateway
+/// does, for its readiness endpoint) doesn't keep the manager alive.
+#[derive(Clone)]
+pub struct ChannelHealthProbe {
+    channels: Weak<RwLock<ChannelMap>>,
+}
+
+impl ChannelHealthProbe {
+    /// Check health of all channels. Empty once the manager is dropped.
+    pub async fn check_all(&self) -> HashMap<String, Result<(), ChannelError>> {
+        match self.channels.upgrade() {
+            Some(channels) => check_channels(&channels).await,
+            None => HashMap::new(),
+        }
+    }
+}
+
+async fn check_channels(
+    channels: &RwLock<ChannelMap>,
+) -> HashMap<String, Result<(), ChannelError>> {
+    let channels = channels.read().await;
+    let mut results = HashMap::new();
+
+    for (name, channel) in channels.iter() {
+        results.insert(name.clone(), channel.health_check().await);
+    }
+
+    results
+}
+
 impl Default for ChannelManager {
     fn default() -> Self {
         Self::new()
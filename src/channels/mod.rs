@@ -31,13 +31,15 @@ mod channel;
 mod http;
 mod manager;
 mod repl;
+mod routine_hooks;
 pub mod wasm;
 pub mod web;
 mod webhook_server;
 
 pub use channel::{Channel, IncomingMessage, MessageStream, OutgoingResponse, StatusUpdate};
 pub use http::HttpChannel;
-pub use manager::ChannelManager;
+pub use manager::{ChannelHealthProbe, ChannelManager, HeldResponse, ModerationQueue};
 pub use repl::ReplChannel;
+pub use routine_hooks::RoutineHooks;
 pub use web::GatewayChannel;
 pub use webhook_server::{WebhookServer, WebhookServerConfig};
@@ -14,6 +14,7 @@
 //! - `/clear` - Clear the conversation
 //! - `/compact` - Compact the context
 //! - `/new` - Start a new thread
+//! - `/threads` - List threads; `/thread <n>` switches to one
 //! - `yes`/`no`/`always` - Respond to tool approval prompts
 
 use std::borrow::Cow;
@@ -67,6 +68,7 @@ const SLASH_COMMANDS: &[&str] = &[
     "/summarize",
     "/suggest",
     "/thread",
+    "/threads",
     "/resume",
 ];
 
@@ -237,6 +239,8 @@ fn print_help() {
     println!("  {c}/clear{r}             {d}clear conversation{r}");
     println!("  {c}/compact{r}           {d}compact context window{r}");
     println!("  {c}/new{r}               {d}new conversation thread{r}");
+    println!("  {c}/threads{r}           {d}list threads{r}");
+    println!("  {c}/thread{r} <n>        {d}switch to thread n{r}");
     println!("  {c}/interrupt{r}         {d}stop current operation{r}");
     println!();
     println!("  {h}Approval responses{r}");
@@ -0,0 +1,141 @@
+//! Inbound webhooks that fire routines.
+//!
+//! `POST /hooks/routine/{path}` fires the enabled webhook routine whose
+//! trigger path (or ID) is `{path}`, with the request body as its input, so
+//! a CI failure, form submission, or monitoring alert can wake the agent.
+//! Requests must carry the routine's secret, either as a signature of the
+//! body (`X-Hub-Signature-256` or `X-Ironclaw-Signature`:
+//! `sha256=<hex HMAC-SHA256>`) or as `Authorization: Bearer <secret>`.
+//!
+//! The routes are served by the [`WebhookServer`](super::WebhookServer)
+//! alongside the HTTP channel. The routine engine only exists once the agent
+//! is running, so it is handed over later through [`RoutineHooks::set_engine`];
+//! until then requests get 503.
+
+use std::sync::Arc;
+
+use axum::{
+    Json, Router,
+    body::Bytes,
+    extract::{DefaultBodyLimit, Path, State},
+    http::{HeaderMap, StatusCode},
+    routing::post,
+};
+use tokio::sync::RwLock;
+
+use crate::agent::routine::WebhookAuth;
+use crate::agent::routine_engine::{RoutineEngine, WebhookFireError};
+
+/// Maximum request body size (64 KB).
+const MAX_BODY_BYTES: usize = 64 * 1024;
+
+/// Route fragment for routine webhooks, and the engine it fires into.
+#[derive(Clone, Default)]
+pub struct RoutineHooks {
+    engine: Arc<RwLock<Option<Arc<RoutineEngine>>>>,
+}
+
+impl RoutineHooks {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Start firing routines on `engine`.
+    pub async fn set_engine(&self, engine: Arc<RoutineEngine>) {
+        *self.engine.write().await = Some(engine);
+    }
+
+    /// The axum routes, with state applied.
+    pub fn routes(&self) -> Router {
+        Router::new()
+            .route("/hooks/routine/{*path}", post(hook_handler))
+            .layer(DefaultBodyLimit::max(MAX_BODY_BYTES))
+            .with_state(self.clone())
+    }
+}
+
+/// The credentials a request carries, preferring a signature.
+fn auth_from_headers(headers: &HeaderMap) -> Option<WebhookAuth<'_>> {
+    let header = |name: &str| headers.get(name).and_then(|v| v.to_str().ok());
+    if let Some(signature) =
+        header("x-hub-signature-256").or_else(|| header("x-ironclaw-signature"))
+    {
+        return Some(WebhookAuth::Signature(signature));
+    }
+    header("authorization")
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .map(|token| WebhookAuth::Token(token.trim()))
+}
+
+async fn hook_handler(
+    State(hooks): State<RoutineHooks>,
+    Path(path): Path<String>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> (StatusCode, Json<serde_json::Value>) {
+    let Some(engine) = hooks.engine.read().await.clone() else {
+        return (
+            StatusCode::SERVICE_UNAVAILABLE,
+            Json(serde_json::json!({ "error": "Routines not started" })),
+        );
+    };
+
+    match engine
+        .fire_webhook(&path, &body, auth_from_headers(&headers))
+        .await
+    {
+        Ok(run_id) => (
+            StatusCode::ACCEPTED,
+            Json(serde_json::json!({ "status": "accepted", "run_id": run_id })),
+        ),
+        Err(e) => {
+            let status = match e {
+                WebhookFireError::NotFound => StatusCode::NOT_FOUND,
+                WebhookFireError::Unauthorized => StatusCode::UNAUTHORIZED,
+                WebhookFireError::Busy(_) => StatusCode::TOO_MANY_REQUESTS,
+                WebhookFireError::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            };
+            (status, Json(serde_json::json!({ "error": e.to_string() })))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::http::HeaderValue;
+
+    use super::*;
+
+    #[test]
+    fn test_auth_from_headers() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(auth_from_headers(&headers), None);
+
+        headers.insert("authorization", HeaderValue::from_static("Bearer s3cret"));
+        assert_eq!(
+            auth_from_headers(&headers),
+            Some(WebhookAuth::Token("s3cret"))
+        );
+
+        headers.insert(
+            "x-hub-signature-256",
+            HeaderValue::from_static("sha256=abc"),
+        );
+        assert_eq!(
+            auth_from_headers(&headers),
+            Some(WebhookAuth::Signature("sha256=abc"))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_unavailable_before_engine_is_set() {
+        let (status, _) = hook_handler(
+            State(RoutineHooks::new()),
+            Path("ci-failed".to_string()),
+            HeaderMap::new(),
+            Bytes::from_static(b"{}"),
+        )
+        .await;
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+    }
+}
@@ -0,0 +1,576 @@
+//! Scoped API keys and short-lived sessions for the web gateway.
+//!
+//! The gateway's master token (`GATEWAY_AUTH_TOKEN`) grants everything. For
+//! anything exposed beyond localhost, hand out API keys instead: each key has
+//! a set of scopes, an optional expiry, and its own rate limit, and can be
+//! rotated or revoked without touching the master token.
+//!
+//! Keys look like `icw_<id>_<secret>`. Only a SHA-256 hash of the secret is
+//! stored (in the gateway owner's settings, key `gateway_api_keys`), so the
+//! full key is shown exactly once, at creation or rotation.
+//!
+//! Any valid credential can be exchanged for a session token
+//! (`POST /api/auth/session`) that carries the same scopes but expires after
+//! [`SESSION_TTL`]. Browsers should use sessions for the `?token=` query
+//! parameter that SSE and WebSocket connections need, so long-lived keys
+//! don't end up in URLs.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use subtle::ConstantTimeEq;
+
+use crate::channels::web::server::RateLimiter;
+use crate::db::Database;
+use crate::error::DatabaseError;
+
+/// Settings key the hashed keys are stored under.
+pub const API_KEYS_SETTING_KEY: &str = "gateway_api_keys";
+
+/// Requests per minute for keys created without an explicit limit.
+pub const DEFAULT_RATE_LIMIT_PER_MINUTE: u64 = 120;
+
+/// Lifetime of a session token.
+pub const SESSION_TTL: Duration = Duration::from_secs(12 * 60 * 60);
+
+const KEY_PREFIX: &str = "icw_";
+const SESSION_PREFIX: &str = "ics_";
+const ID_LEN: usize = 12;
+const SECRET_LEN: usize = 32;
+
+/// Errors from managing API keys.
+#[derive(Debug, thiserror::Error)]
+pub enum ApiKeyError {
+    #[error("API key not found: {0}")]
+    NotFound(String),
+
+    #[error("Invalid API key request: {0}")]
+    Invalid(String),
+
+    #[error("Failed to serialize API keys: {0}")]
+    Serialization(String),
+
+    #[error(transparent)]
+    Database(#[from] DatabaseError),
+}
+
+/// Why a presented credential was rejected.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AuthFailure {
+    /// Unknown, malformed, or revoked credential.
+    Invalid,
+    /// The key or session has expired.
+    Expired,
+    /// The key's rate limit is exhausted for the current window.
+    RateLimited,
+}
+
+/// Area of the HTTP API a credential may use.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Scope {
+    /// Chat, WebSocket, and the OpenAI-compatible API.
+    Chat,
+    /// Workspace memory browsing and editing.
+    Memory,
+    /// Sandbox jobs, routines, and project files.
+    Jobs,
+    /// Settings, extensions, permissions, audit log, logs, keys, and the
+    /// gateway control plane. Implies every other scope.
+    Admin,
+}
+
+impl Scope {
+    /// Every scope, as granted to the master token.
+    pub const ALL: [Scope; 4] = [Scope::Chat, Scope::Memory, Scope::Jobs, Scope::Admin];
+
+    /// The scope a request path needs, or `None` if any valid credential
+    /// will do.
+    pub fn for_path(path: &str) -> Option<Scope> {
+        let first = |prefixes: &[&str]| prefixes.iter().any(|p| path.starts_with(p));
+        if path == "/api/auth/session" {
+            None
+        } else if first(&["/api/chat/", "/v1/"]) {
+            Some(Scope::Chat)
+        } else if first(&["/api/memory/"]) {
+            Some(Scope::Memory)
+        } else if first(&["/api/jobs", "/api/routines", "/projects/"]) {
+            Some(Scope::Jobs)
+        } else {
+            // Anything else (including routes added later) needs admin.
+            Some(Scope::Admin)
+        }
+    }
+}
+
+/// Who made an authenticated request. Inserted into request extensions by
+/// the auth middleware.
+#[derive(Debug, Clone)]
+pub struct Principal {
+    /// The API key used, or `None` for the master token.
+    pub key_id: Option<String>,
+    pub scopes: Vec<Scope>,
+}
+
+impl Principal {
+    /// The holder of the master token.
+    pub fn owner() -> Self {
+        Self {
+            key_id: None,
+            scopes: Scope::ALL.to_vec(),
+        }
+    }
+
+    /// Whether this principal may use `scope`.
+    pub fn allows(&self, scope: Scope) -> bool {
+        self.scopes.contains(&Scope::Admin) || self.scopes.contains(&scope)
+    }
+}
+
+/// Public view of an API key (never includes the secret).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ApiKey {
+    pub id: String,
+    pub name: String,
+    pub scopes: Vec<Scope>,
+    pub created_at: DateTime<Utc>,
+    #[serde(default)]
+    pub expires_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub rotated_at: Option<DateTime<Utc>>,
+    pub rate_limit_per_minute: u64,
+}
+
+impl ApiKey {
+    /// Whether the key has expired as of `now`.
+    pub fn is_expired(&self, now: DateTime<Utc>) -> bool {
+        self.expires_at.is_some_and(|at| at <= now)
+    }
+}
+
+/// Parameters for a new key.
+#[derive(Debug, Clone, Default)]
+pub struct NewApiKey {
+    pub name: String,
+    pub scopes: Vec<Scope>,
+    pub expires_at: Option<DateTime<Utc>>,
+    /// Requests per minute; `None` uses [`DEFAULT_RATE_LIMIT_PER_MINUTE`].
+    pub rate_limit_per_minute: Option<u64>,
+}
+
+/// Persisted form of a key: the public fields plus the secret hash.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct StoredKey {
+    #[serde(flatten)]
+    key: ApiKey,
+    secret_hash: String,
+}
+
+struct Session {
+    principal: Principal,
+    expires_at: DateTime<Utc>,
+}
+
+/// Live API key and session registry shared by the auth middleware and the
+/// key management API.
+pub struct ApiKeys {
+    keys: RwLock<HashMap<String, StoredKey>>,
+    /// Per-key limiters, created on first use.
+    limiters: Mutex<HashMap<String, Arc<RateLimiter>>>,
+    /// Sessions by hash of their token.
+    sessions: Mutex<HashMap<String, Session>>,
+    /// Serializes create/rotate/revoke so persisted state matches memory.
+    write_lock: tokio::sync::Mutex<()>,
+    /// Where keys are persisted: database and the gateway owner's user ID.
+    store: Option<(Arc<dyn Database>, String)>,
+}
+
+impl ApiKeys {
+    /// In-memory registry with no keys (not persisted).
+    pub fn new() -> Self {
+        Self {
+            keys: RwLock::new(HashMap::new()),
+            limiters: Mutex::new(HashMap::new()),
+            sessions: Mutex::new(HashMap::new()),
+            write_lock: tokio::sync::Mutex::new(()),
+            store: None,
+        }
+    }
+
+    /// Load keys from the owner's settings.
+    pub async fn load(
+        store: Arc<dyn Database>,
+        owner_id: impl Into<String>,
+    ) -> Result<Self, ApiKeyError> {
+        let owner_id = owner_id.into();
+        let stored: Vec<StoredKey> =
+            match store.get_setting(&owner_id, API_KEYS_SETTING_KEY).await? {
+                Some(value) => serde_json::from_value(value)
+                    .map_err(|e| ApiKeyError::Serialization(e.to_string()))?,
+                None => Vec::new(),
+            };
+        let mut keys = Self::new();
+        keys.keys = RwLock::new(stored.into_iter().map(|s| (s.key.id.clone(), s)).collect());
+        keys.store = Some((store, owner_id));
+        Ok(keys)
+    }
+
+    /// All keys, oldest first.
+    pub fn list(&self) -> Vec<ApiKey> {
+        let mut keys: Vec<ApiKey> = self.read(|k| k.values().map(|s| s.key.clone()).collect());
+        keys.sort_by_key(|k| k.created_at);
+        keys
+    }
+
+    /// Create a key. Returns its public view and the full key string, which
+    /// cannot be recovered later.
+    pub async fn create(&self, new: NewApiKey) -> Result<(ApiKey, String), ApiKeyError> {
+        let name = new.name.trim();
+        if name.is_empty() {
+            return Err(ApiKeyError::Invalid("name must not be empty".to_string()));
+        }
+        if new.scopes.is_empty() {
+            return Err(ApiKeyError::Invalid(
+                "at least one scope is required".to_string(),
+            ));
+        }
+        if new.rate_limit_per_minute == Some(0) {
+            return Err(ApiKeyError::Invalid(
+                "rate_limit_per_minute must be positive".to_string(),
+            ));
+        }
+        let now = Utc::now();
+        if new.expires_at.is_some_and(|at| at <= now) {
+            return Err(ApiKeyError::Invalid(
+                "expires_at must be in the future".to_string(),
+            ));
+        }
+
+        let mut scopes = new.scopes;
+        scopes.sort_by_key(|s| *s as u8);
+        scopes.dedup();
+
+        let id = random_string(ID_LEN);
+        let secret = random_string(SECRET_LEN);
+        let key = ApiKey {
+            id: id.clone(),
+            name: name.to_string(),
+            scopes,
+            created_at: now,
+            expires_at: new.expires_at,
+            rotated_at: None,
+            rate_limit_per_minute: new
+                .rate_limit_per_minute
+                .unwrap_or(DEFAULT_RATE_LIMIT_PER_MINUTE),
+        };
+
+        let _guard = self.write_lock.lock().await;
+        let mut keys = self.read(|k| k.clone());
+        keys.insert(
+            id.clone(),
+            StoredKey {
+                key: key.clone(),
+                secret_hash: hash(&secret),
+            },
+        );
+        self.persist_and_swap(keys).await?;
+
+        Ok((key, format!("{}{}_{}", KEY_PREFIX, id, secret)))
+    }
+
+    /// Replace a key's secret. The old secret and any sessions created from
+    /// it stop working immediately.
+    pub async fn rotate(&self, id: &str) -> Result<(ApiKey, String), ApiKeyError> {
+        let _guard = self.write_lock.lock().await;
+        let mut keys = self.read(|k| k.clone());
+        let stored = keys
+            .get_mut(id)
+            .ok_or_else(|| ApiKeyError::NotFound(id.to_string()))?;
+        let secret = random_string(SECRET_LEN);
+        stored.secret_hash = hash(&secret);
+        stored.key.rotated_at = Some(Utc::now());
+        let key = stored.key.clone();
+        self.persist_and_swap(keys).await?;
+        self.drop_sessions_for(id);
+
+        Ok((key, format!("{}{}_{}", KEY_PREFIX, id, secret)))
+    }
+
+    /// Delete a key and end its sessions. Returns `false` if it didn't exist.
+    pub async fn revoke(&self, id: &str) -> Result<bool, ApiKeyError> {
+        let _guard = self.write_lock.lock().await;
+        let mut keys = self.read(|k| k.clone());
+        if keys.remove(id).is_none() {
+            return Ok(false);
+        }
+        self.persist_and_swap(keys).await?;
+        self.drop_sessions_for(id);
+        if let Ok(mut limiters) = self.limiters.lock() {
+            limiters.remove(id);
+        }
+        Ok(true)
+    }
+
+    /// Check an API key or session token and consume one request from its
+    /// rate limit.
+    pub fn authenticate(&self, token: &str) -> Result<Principal, AuthFailure> {
+        let now = Utc::now();
+        let principal = if token.starts_with(SESSION_PREFIX) {
+            self.session_principal(token, now)?
+        } else {
+            self.key_principal(token, now)?
+        };
+
+        if let Some(ref key_id) = principal.key_id
+            && !self.limiter_for(key_id).check()
+        {
+            return Err(AuthFailure::RateLimited);
+        }
+        Ok(principal)
+    }
+
+    /// Start a session with the same scopes as `principal`. Sessions from an
+    /// API key never outlive the key.
+    pub fn create_session(&self, principal: &Principal) -> (String, DateTime<Utc>) {
+        let now = Utc::now();
+        let mut expires_at = now
+            + chrono::Duration::from_std(SESSION_TTL)
+                .unwrap_or_else(|_| chrono::Duration::hours(12));
+        if let Some(ref key_id) = principal.key_id
+            && let Some(key_expiry) = self.read(|k| k.get(key_id).and_then(|s| s.key.expires_at))
+        {
+            expires_at = expires_at.min(key_expiry);
+        }
+
+        let token = format!("{}{}", SESSION_PREFIX, random_string(SECRET_LEN + ID_LEN));
+        if let Ok(mut sessions) = self.sessions.lock() {
+            sessions.retain(|_, s| s.expires_at > now);
+            sessions.insert(
+                hash(&token),
+                Session {
+                    principal: principal.clone(),
+                    expires_at,
+                },
+            );
+        }
+        (token, expires_at)
+    }
+
+    fn key_principal(&self, token: &str, now: DateTime<Utc>) -> Result<Principal, AuthFailure> {
+        let (id, secret) = token
+            .strip_prefix(KEY_PREFIX)
+            .and_then(|rest| rest.split_once('_'))
+            .ok_or(AuthFailure::Invalid)?;
+        let presented = hash(secret);
+        self.read(|keys| {
+            let stored = keys.get(id).ok_or(AuthFailure::Invalid)?;
+            if !bool::from(presented.as_bytes().ct_eq(stored.secret_hash.as_bytes())) {
+                return Err(AuthFailure::Invalid);
+            }
+            if stored.key.is_expired(now) {
+                return Err(AuthFailure::Expired);
+            }
+            Ok(Principal {
+                key_id: Some(stored.key.id.clone()),
+                scopes: stored.key.scopes.clone(),
+            })
+        })
+    }
+
+    fn session_principal(&self, token: &str, now: DateTime<Utc>) -> Result<Principal, AuthFailure> {
+        let mut sessions = self.sessions.lock().map_err(|_| AuthFailure::Invalid)?;
+        let token_hash = hash(token);
+        let session = sessions.get(&token_hash).ok_or(AuthFailure::Invalid)?;
+        if session.expires_at <= now {
+            sessions.remove(&token_hash);
+            return Err(AuthFailure::Expired);
+        }
+        Ok(session.principal.clone())
+    }
+
+    fn limiter_for(&self, key_id: &str) -> Arc<RateLimiter> {
+        let per_minute = self
+            .read(|k| k.get(key_id).map(|s| s.key.rate_limit_per_minute))
+            .unwrap_or(DEFAULT_RATE_LIMIT_PER_MINUTE);
+        match self.limiters.lock() {
+            Ok(mut limiters) => Arc::clone(
+                limiters
+                    .entry(key_id.to_string())
+                    .or_insert_with(|| Arc::new(RateLimiter::new(per_minute, 60))),
+            ),
+            Err(_) => Arc::new(RateLimiter::new(per_minute, 60)),
+        }
+    }
+
+    fn drop_sessions_for(&self, key_id: &str) {
+        if let Ok(mut sessions) = self.sessions.lock() {
+            sessions.retain(|_, s| s.principal.key_id.as_deref() != Some(key_id));
+        }
+    }
+
+    async fn persist_and_swap(&self, keys: HashMap<String, StoredKey>) -> Result<(), ApiKeyError> {
+        if let Some((store, owner_id)) = &self.store {
+            let stored: Vec<&StoredKey> = keys.values().collect();
+            let value = serde_json::to_value(&stored)
+                .map_err(|e| ApiKeyError::Serialization(e.to_string()))?;
+            store
+                .set_setting(owner_id, API_KEYS_SETTING_KEY, &value)
+                .await?;
+        }
+        if let Ok(mut current) = self.keys.write() {
+            *current = keys;
+        }
+        Ok(())
+    }
+
+    fn read<T>(&self, f: impl FnOnce(&HashMap<String, StoredKey>) -> T) -> T {
+        match self.keys.read() {
+            Ok(keys) => f(&keys),
+            Err(poisoned) => f(&poisoned.into_inner()),
+        }
+    }
+}
+
+impl Default for ApiKeys {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn hash(secret: &str) -> String {
+    format!("{:x}", Sha256::digest(secret.as_bytes()))
+}
+
+fn random_string(len: usize) -> String {
+    use rand::Rng;
+    rand::thread_rng()
+        .sample_iter(&rand::distributions::Alphanumeric)
+        .take(len)
+        .map(char::from)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn new_key(scopes: Vec<Scope>) -> NewApiKey {
+        NewApiKey {
+            name: "ci".to_string(),
+            scopes,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_scope_for_path() {
+        assert_eq!(Scope::for_path("/api/chat/send"), Some(Scope::Chat));
+        assert_eq!(Scope::for_path("/v1/chat/completions"), Some(Scope::Chat));
+        assert_eq!(Scope::for_path("/api/memory/read"), Some(Scope::Memory));
+        assert_eq!(Scope::for_path("/api/jobs/abc/cancel"), Some(Scope::Jobs));
+        assert_eq!(
+            Scope::for_path("/projects/p1/index.html"),
+            Some(Scope::Jobs)
+        );
+        assert_eq!(Scope::for_path("/api/settings/foo"), Some(Scope::Admin));
+        assert_eq!(Scope::for_path("/api/keys"), Some(Scope::Admin));
+        assert_eq!(Scope::for_path("/api/auth/session"), None);
+    }
+
+    #[tokio::test]
+    async fn test_create_and_authenticate() {
+        let keys = ApiKeys::new();
+        let (key, token) = keys.create(new_key(vec![Scope::Chat])).await.unwrap();
+        assert!(token.starts_with("icw_"));
+
+        let principal = keys.authenticate(&token).unwrap();
+        assert_eq!(principal.key_id.as_deref(), Some(key.id.as_str()));
+        assert!(principal.allows(Scope::Chat));
+        assert!(!principal.allows(Scope::Memory));
+
+        let forged = format!("{}x", token);
+        assert_eq!(
+            keys.authenticate(&forged).unwrap_err(),
+            AuthFailure::Invalid
+        );
+        assert_eq!(
+            keys.authenticate("garbage").unwrap_err(),
+            AuthFailure::Invalid
+        );
+    }
+
+    #[tokio::test]
+    async fn test_create_rejects_bad_requests() {
+        let keys = ApiKeys::new();
+        assert!(keys.create(new_key(vec![])).await.is_err());
+        let mut past = new_key(vec![Scope::Chat]);
+        past.expires_at = Some(Utc::now() - chrono::Duration::minutes(1));
+        assert!(keys.create(past).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_rotate_invalidates_old_secret_and_sessions() {
+        let keys = ApiKeys::new();
+        let (key, old) = keys.create(new_key(vec![Scope::Admin])).await.unwrap();
+        let principal = keys.authenticate(&old).unwrap();
+        let (session, _) = keys.create_session(&principal);
+        assert!(keys.authenticate(&session).is_ok());
+
+        let (rotated, new) = keys.rotate(&key.id).await.unwrap();
+        assert!(rotated.rotated_at.is_some());
+        assert_eq!(keys.authenticate(&old).unwrap_err(), AuthFailure::Invalid);
+        assert_eq!(
+            keys.authenticate(&session).unwrap_err(),
+            AuthFailure::Invalid
+        );
+        assert!(keys.authenticate(&new).is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_revoke_and_rate_limit() {
+        let keys = ApiKeys::new();
+        let mut limited = new_key(vec![Scope::Chat]);
+        limited.rate_limit_per_minute = Some(2);
+        let (key, token) = keys.create(limited).await.unwrap();
+
+        assert!(keys.authenticate(&token).is_ok());
+        assert!(keys.authenticate(&token).is_ok());
+        assert_eq!(
+            keys.authenticate(&token).unwrap_err(),
+            AuthFailure::RateLimited
+        );
+
+        assert!(keys.revoke(&key.id).await.unwrap());
+        assert!(!keys.revoke(&key.id).await.unwrap());
+        assert_eq!(keys.authenticate(&token).unwrap_err(), AuthFailure::Invalid);
+    }
+
+    #[test]
+    fn test_expired_key_rejected() {
+        let keys = ApiKeys::new();
+        let stored = StoredKey {
+            key: ApiKey {
+                id: "expired00000".to_string(),
+                name: "old".to_string(),
+                scopes: vec![Scope::Chat],
+                created_at: Utc::now() - chrono::Duration::days(2),
+                expires_at: Some(Utc::now() - chrono::Duration::days(1)),
+                rotated_at: None,
+                rate_limit_per_minute: 10,
+            },
+            secret_hash: hash("secret"),
+        };
+        keys.keys
+            .write()
+            .unwrap()
+            .insert(stored.key.id.clone(), stored);
+        assert_eq!(
+            keys.authenticate("icw_expired00000_secret").unwrap_err(),
+            AuthFailure::Expired
+        );
+    }
+}
@@ -1,4 +1,10 @@
 //! Bearer token authentication middleware for the web gateway.
+//!
+//! Accepts the master token, scoped API keys, and session tokens (see
+//! [`super::api_keys`]). The resolved [`Principal`] is checked against the
+//! scope the route needs and then attached to the request extensions.
+
+use std::sync::Arc;
 
 use axum::{
     extract::{Request, State},
@@ -8,43 +14,101 @@ use axum::{
 };
 use subtle::ConstantTimeEq;
 
+use crate::channels::web::api_keys::{ApiKeys, AuthFailure, Principal, Scope};
+
 /// Shared auth state injected via axum middleware state.
 #[derive(Clone)]
 pub struct AuthState {
+    /// Master token, granting every scope.
     pub token: String,
+    /// API keys and sessions, if key management is enabled.
+    pub api_keys: Option<Arc<ApiKeys>>,
+}
+
+impl AuthState {
+    fn resolve(&self, token: &str) -> Result<Principal, AuthFailure> {
+        // Constant-time comparison against the master token
+        if bool::from(token.as_bytes().ct_eq(self.token.as_bytes())) {
+            return Ok(Principal::owner());
+        }
+        match self.api_keys {
+            Some(ref keys) => keys.authenticate(token),
+            None => Err(AuthFailure::Invalid),
+        }
+    }
+
+    /// Resolve the first candidate token that authenticates, trying each in
+    /// turn. If none does, reports the first failure more specific than
+    /// [`AuthFailure::Invalid`] (an expired or rate-limited key).
+    fn resolve_first<'a>(
+        &self,
+        candidates: impl IntoIterator<Item = &'a str>,
+    ) -> Result<Principal, AuthFailure> {
+        let mut failure = AuthFailure::Invalid;
+        for token in candidates {
+            match self.resolve(token) {
+                Ok(principal) => return Ok(principal),
+                Err(AuthFailure::Invalid) => {}
+                Err(e) if failure == AuthFailure::Invalid => failure = e,
+                Err(_) => {}
+            }
+        }
+        Err(failure)
+    }
 }
 
 /// Auth middleware that validates bearer token from header or query param.
 ///
 /// SSE connections can't set headers from `EventSource`, so we also accept
-/// `?token=xxx` as a query parameter.
+/// `?token=xxx` as a query parameter. The header is tried first, then each
+/// query token, so a stale header doesn't shadow a valid query token.
 pub async fn auth_middleware(
     State(auth): State<AuthState>,
     headers: HeaderMap,
-    request: Request,
+    mut request: Request,
     next: Next,
 ) -> Response {
-    // Try Authorization header first (constant-time comparison)
-    if let Some(auth_header) = headers.get("authorization")
-        && let Ok(value) = auth_header.to_str()
-        && let Some(token) = value.strip_prefix("Bearer ")
-        && bool::from(token.as_bytes().ct_eq(auth.token.as_bytes()))
-    {
-        return next.run(request).await;
-    }
+    let header_token = headers
+        .get("authorization")
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "));
+    let query_tokens = request
+        .uri()
+        .query()
+        .into_iter()
+        .flat_map(|query| query.split('&'))
+        .filter_map(|pair| pair.strip_prefix("token="));
 
-    // Fall back to query parameter for SSE EventSource (constant-time comparison)
-    if let Some(query) = request.uri().query() {
-        for pair in query.split('&') {
-            if let Some(token) = pair.strip_prefix("token=")
-                && bool::from(token.as_bytes().ct_eq(auth.token.as_bytes()))
-            {
-                return next.run(request).await;
-            }
+    let resolved = auth.resolve_first(header_token.into_iter().chain(query_tokens));
+    let principal = match resolved {
+        Ok(principal) => principal,
+        Err(AuthFailure::Invalid) => {
+            return (StatusCode::UNAUTHORIZED, "Invalid or missing auth token").into_response();
+        }
+        Err(AuthFailure::Expired) => {
+            return (StatusCode::UNAUTHORIZED, "Auth token has expired").into_response();
         }
+        Err(AuthFailure::RateLimited) => {
+            return (
+                StatusCode::TOO_MANY_REQUESTS,
+                "Rate limit exceeded for this API key",
+            )
+                .into_response();
+        }
+    };
+
+    if let Some(scope) = Scope::for_path(request.uri().path())
+        && !principal.allows(scope)
+    {
+        return (
+            StatusCode::FORBIDDEN,
+            "API key is missing the required scope",
+        )
+            .into_response();
     }
 
-    (StatusCode::UNAUTHORIZED, "Invalid or missing auth token").into_response()
+    request.extensions_mut().insert(principal);
+    next.run(request).await
 }
 
 #[cfg(test)]
@@ -55,8 +119,44 @@ mod tests {
     fn test_auth_state_clone() {
         let state = AuthState {
             token: "test-token".to_string(),
+            api_keys: None,
         };
         let cloned = state.clone();
         assert_eq!(cloned.token, "test-token");
     }
+
+    #[tokio::test]
+    async fn test_resolve_master_token_and_keys() {
+        let keys = Arc::new(ApiKeys::new());
+        let (_, key) = keys
+            .create(crate::channels::web::api_keys::NewApiKey {
+                name: "reader".to_string(),
+                scopes: vec![Scope::Memory],
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+        let state = AuthState {
+            token: "master".to_string(),
+            api_keys: Some(keys),
+        };
+
+        assert!(state.resolve("master").unwrap().allows(Scope::Admin));
+        let principal = state.resolve(&key).unwrap();
+        assert!(principal.allows(Scope::Memory));
+        assert!(!principal.allows(Scope::Chat));
+        assert_eq!(state.resolve("nope").unwrap_err(), AuthFailure::Invalid);
+
+        // An invalid header token falls back to the query token.
+        let principal = state.resolve_first(["stale", key.as_str()]).unwrap();
+        assert!(principal.allows(Scope::Memory));
+        assert_eq!(
+            state.resolve_first(["stale", "nope"]).unwrap_err(),
+            AuthFailure::Invalid
+        );
+        assert_eq!(
+            state.resolve_first(std::iter::empty()).unwrap_err(),
+            AuthFailure::Invalid
+        );
+    }
 }
@@ -24,7 +24,7 @@ use tokio::sync::broadcast;
 use tracing::field::{Field, Visit};
 use tracing_subscriber::Layer;
 
-use crate::safety::LeakDetector;
+use crate::safety::{LeakDetector, SecretRedactor};
 
 /// Maximum number of recent log entries kept for late-joining SSE subscribers.
 const HISTORY_CAP: usize = 500;
@@ -66,9 +66,10 @@ impl LogBroadcaster {
         // Scrub secrets from the message before it reaches any subscriber.
         // This is defense-in-depth: even if code elsewhere accidentally logs
         // a secret, it won't be broadcast to SSE clients.
+        let redacted = SecretRedactor::global().redact(&entry.message);
         entry.message = self
             .leak_detector
-            .scan_and_clean(&entry.message)
+            .scan_and_clean(&redacted)
             .unwrap_or_else(|_| "[log message redacted: contained blocked secret]".to_string());
 
         // Stash in ring buffer (for late joiners)
@@ -14,6 +14,7 @@
 //!         ◄── GET  / ───────────────── Static HTML/CSS/JS
 //! ```
 
+pub mod api_keys;
 pub mod auth;
 pub mod log_layer;
 pub mod openai_compat;
@@ -30,15 +31,19 @@ use tokio::sync::mpsc;
 use tokio_stream::wrappers::ReceiverStream;
 
 use crate::agent::SessionManager;
-use crate::channels::{Channel, IncomingMessage, MessageStream, OutgoingResponse, StatusUpdate};
+use crate::channels::{
+    Channel, IncomingMessage, MessageStream, ModerationQueue, OutgoingResponse, StatusUpdate,
+};
 use crate::config::GatewayConfig;
 use crate::db::Database;
 use crate::error::ChannelError;
 use crate::extensions::ExtensionManager;
+use crate::health::HealthChecker;
 use crate::orchestrator::job_manager::ContainerJobManager;
 use crate::tools::ToolRegistry;
 use crate::workspace::Workspace;
 
+use self::api_keys::ApiKeys;
 use self::log_layer::LogBroadcaster;
 
 use self::server::GatewayState;
@@ -83,7 +88,13 @@ impl GatewayChannel {
             shutdown_tx: tokio::sync::RwLock::new(None),
             ws_tracker: Some(Arc::new(ws::WsConnectionTracker::new())),
             llm_provider: None,
-            chat_rate_limiter: server::RateLimiter::new(30, 60),
+            chat_rate_limiter: Arc::new(server::RateLimiter::new(
+                config.chat_rate_limit_per_minute,
+                60,
+            )),
+            api_keys: None,
+            health: None,
+            moderation: None,
         });
 
         Self {
@@ -110,7 +121,10 @@ impl GatewayChannel {
             shutdown_tx: tokio::sync::RwLock::new(None),
             ws_tracker: self.state.ws_tracker.clone(),
             llm_provider: self.state.llm_provider.clone(),
-            chat_rate_limiter: server::RateLimiter::new(30, 60),
+            chat_rate_limiter: Arc::clone(&self.state.chat_rate_limiter),
+            api_keys: self.state.api_keys.clone(),
+            health: self.state.health.clone(),
+            moderation: self.state.moderation.clone(),
         };
         mutate(&mut new_state);
         self.state = Arc::new(new_state);
@@ -180,6 +194,24 @@ impl GatewayChannel {
         self
     }
 
+    /// Inject the API key registry so scoped keys and sessions are accepted.
+    pub fn with_api_keys(mut self, keys: Arc<ApiKeys>) -> Self {
+        self.rebuild_state(|s| s.api_keys = Some(keys));
+        self
+    }
+
+    /// Inject the health checker behind `/api/health` and `/api/ready`.
+    pub fn with_health(mut self, health: Arc<HealthChecker>) -> Self {
+        self.rebuild_state(|s| s.health = Some(health));
+        self
+    }
+
+    /// Inject the queue of responses held by moderation for `/api/moderation`.
+    pub fn with_moderation(mut self, queue: ModerationQueue) -> Self {
+        self.rebuild_state(|s| s.moderation = Some(queue));
+        self
+    }
+
     /// Get the auth token (for printing to console on startup).
     pub fn auth_token(&self) -> &str {
         &self.auth_token
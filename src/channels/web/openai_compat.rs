@@ -245,6 +245,7 @@ pub fn convert_messages(messages: &[OpenAiMessage]) -> Result<Vec<ChatMessage>,
                     tool_call_id: None,
                     name: m.name.clone(),
                     tool_calls: None,
+                    images: Vec::new(),
                 }),
             }
         })
@@ -557,11 +558,11 @@ pub async fn chat_completions_handler(
 
 /// Handle streaming responses.
 ///
-/// The current `LlmProvider` returns complete responses (no streaming method).
-/// We execute the LLM call first, then simulate chunked delivery by splitting
-/// the response into word-boundary chunks. This ensures LLM failures return
-/// proper HTTP errors instead of SSE error events. True token streaming can be
-/// added later by extending `LlmProvider` with a `complete_stream()` method.
+/// Requests without tools are streamed token by token when the provider
+/// supports it (see [`stream_natively`]). Otherwise we execute the LLM call
+/// first, then simulate chunked delivery by splitting the response into
+/// word-boundary chunks. This ensures LLM failures return proper HTTP errors
+/// instead of SSE error events.
 async fn handle_streaming(
     llm: Arc<dyn crate::llm::LlmProvider>,
     req: OpenAiChatRequest,
@@ -612,6 +613,9 @@ async fn handle_streaming(
         if let Some(ref stop_val) = req.stop {
             comp_req.stop_sequences = parse_stop(stop_val);
         }
+        if llm.supports_streaming() {
+            return stream_natively(llm, comp_req, id, created, model_name).await;
+        }
         LlmResult::Simple(llm.complete(comp_req).await.map_err(map_llm_error)?)
     };
 
@@ -706,6 +710,95 @@ async fn handle_streaming(
     Ok(response)
 }
 
+/// Stream a completion as the provider produces it.
+///
+/// The response is committed to SSE only once the first text arrives, so a
+/// request that fails up front still gets a proper HTTP error. A failure
+/// after that is sent as an error event before `[DONE]`.
+async fn stream_natively(
+    llm: Arc<dyn crate::llm::LlmProvider>,
+    req: CompletionRequest,
+    id: String,
+    created: u64,
+    model_name: String,
+) -> Result<Response, (StatusCode, Json<OpenAiErrorResponse>)> {
+    let (delta_tx, mut delta_rx) = tokio::sync::mpsc::unbounded_channel();
+    let mut call = tokio::spawn(async move { llm.complete_stream(req, delta_tx).await });
+    let joined = |result: Result<_, tokio::task::JoinError>| {
+        result.unwrap_or_else(|e| {
+            Err(crate::error::LlmError::RequestFailed {
+                provider: "gateway".to_string(),
+                reason: format!("streaming task failed: {}", e),
+            })
+        })
+    };
+
+    let first = delta_rx.recv().await;
+    // No text at all: the call is over, so report how it ended directly.
+    let finished = match first {
+        Some(_) => None,
+        None => Some(joined((&mut call).await).map_err(map_llm_error)?),
+    };
+
+    let (tx, rx) = tokio::sync::mpsc::channel::<Result<Event, std::convert::Infallible>>(64);
+
+    tokio::spawn(async move {
+        let role_chunk = OpenAiChatChunk {
+            id: id.clone(),
+            object: "chat.completion.chunk",
+            created,
+            model: model_name.clone(),
+            choices: vec![OpenAiChunkChoice {
+                index: 0,
+                delta: OpenAiDelta {
+                    role: Some("assistant".to_string()),
+                    content: None,
+                    tool_calls: None,
+                },
+                finish_reason: None,
+            }],
+        };
+        let data = serde_json::to_string(&role_chunk).unwrap_or_default();
+        let _ = tx.send(Ok(Event::default().data(data))).await;
+
+        let result = match finished {
+            Some(resp) => Ok(resp),
+            None => {
+                let mut delta = first;
+                while let Some(text) = delta {
+                    // Keep draining when the client is gone so the call
+                    // can finish.
+                    send_content_chunk(&tx, &id, created, &model_name, text).await;
+                    delta = delta_rx.recv().await;
+                }
+                joined(call.await)
+            }
+        };
+
+        match result {
+            Ok(resp) => {
+                send_finish_chunk(&tx, &id, created, &model_name, resp.finish_reason).await;
+            }
+            Err(e) => {
+                tracing::warn!(error = %e, "LLM stream failed after it started");
+                let (_, Json(body)) = map_llm_error(e);
+                let data = serde_json::to_string(&body).unwrap_or_default();
+                let _ = tx.send(Ok(Event::default().data(data))).await;
+            }
+        }
+
+        let _ = tx.send(Ok(Event::default().data("[DONE]"))).await;
+    });
+
+    let stream = tokio_stream::wrappers::ReceiverStream::new(rx);
+    let sse = Sse::new(stream).keep_alive(KeepAlive::new().text(""));
+    let mut response = sse.into_response();
+    response
+        .headers_mut()
+        .insert("x-ironclaw-streaming", HeaderValue::from_static("native"));
+    Ok(response)
+}
+
 /// Split content into word-boundary chunks and send as SSE events.
 async fn stream_content_chunks(
     tx: &tokio::sync::mpsc::Sender<Result<Event, std::convert::Infallible>>,
@@ -718,51 +811,45 @@ async fn stream_content_chunks(
     let mut buf = String::new();
     for word in content.split_inclusive(char::is_whitespace) {
         buf.push_str(word);
-        if buf.len() >= 20 {
-            let chunk = OpenAiChatChunk {
-                id: id.to_string(),
-                object: "chat.completion.chunk",
-                created,
-                model: model.to_string(),
-                choices: vec![OpenAiChunkChoice {
-                    index: 0,
-                    delta: OpenAiDelta {
-                        role: None,
-                        content: Some(buf.clone()),
-                        tool_calls: None,
-                    },
-                    finish_reason: None,
-                }],
-            };
-            let data = serde_json::to_string(&chunk).unwrap_or_default();
-            if tx.send(Ok(Event::default().data(data))).await.is_err() {
-                return;
-            }
-            buf.clear();
+        if buf.len() >= 20
+            && !send_content_chunk(tx, id, created, model, std::mem::take(&mut buf)).await
+        {
+            return;
         }
     }
     // Flush remaining
     if !buf.is_empty() {
-        let chunk = OpenAiChatChunk {
-            id: id.to_string(),
-            object: "chat.completion.chunk",
-            created,
-            model: model.to_string(),
-            choices: vec![OpenAiChunkChoice {
-                index: 0,
-                delta: OpenAiDelta {
-                    role: None,
-                    content: Some(buf),
-                    tool_calls: None,
-                },
-                finish_reason: None,
-            }],
-        };
-        let data = serde_json::to_string(&chunk).unwrap_or_default();
-        let _ = tx.send(Ok(Event::default().data(data))).await;
+        send_content_chunk(tx, id, created, model, buf).await;
     }
 }
 
+/// Send `content` as one SSE chunk. Returns false if the client is gone.
+async fn send_content_chunk(
+    tx: &tokio::sync::mpsc::Sender<Result<Event, std::convert::Infallible>>,
+    id: &str,
+    created: u64,
+    model: &str,
+    content: String,
+) -> bool {
+    let chunk = OpenAiChatChunk {
+        id: id.to_string(),
+        object: "chat.completion.chunk",
+        created,
+        model: model.to_string(),
+        choices: vec![OpenAiChunkChoice {
+            index: 0,
+            delta: OpenAiDelta {
+                role: None,
+                content: Some(content),
+                tool_calls: None,
+            },
+            finish_reason: None,
+        }],
+    };
+    let data = serde_json::to_string(&chunk).unwrap_or_default();
+    tx.send(Ok(Event::default().data(data))).await.is_ok()
+}
+
 async fn send_finish_chunk(
     tx: &tokio::sync::mpsc::Sender<Result<Event, std::convert::Infallible>>,
     id: &str,
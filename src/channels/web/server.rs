@@ -8,7 +8,7 @@ use std::sync::Arc;
 use std::sync::atomic::{AtomicU64, Ordering};
 
 use axum::{
-    Json, Router,
+    Extension, Json, Router,
     extract::{DefaultBodyLimit, Path, Query, State, WebSocketUpgrade},
     http::{StatusCode, header},
     middleware,
@@ -25,16 +25,24 @@ use tower_http::cors::{AllowHeaders, CorsLayer};
 use uuid::Uuid;
 
 use crate::agent::SessionManager;
-use crate::channels::IncomingMessage;
+use crate::agent::registry::{AgentDefinition, AgentRegistry, AgentRegistryError, AgentUpdate};
+use crate::audit::{AuditEventKind, AuditQuery};
+use crate::channels::web::api_keys::{
+    API_KEYS_SETTING_KEY, ApiKeyError, ApiKeys, NewApiKey, Principal,
+};
 use crate::channels::web::auth::{AuthState, auth_middleware};
 use crate::channels::web::log_layer::LogBroadcaster;
 use crate::channels::web::sse::SseManager;
 use crate::channels::web::types::*;
+use crate::channels::{IncomingMessage, ModerationQueue};
 use crate::db::Database;
+use crate::error::WorkspaceError;
 use crate::extensions::ExtensionManager;
+use crate::health::HealthChecker;
 use crate::orchestrator::job_manager::ContainerJobManager;
+use crate::safety::{PERMISSIONS_SETTING_KEY, PermissionError, PermissionPolicy};
 use crate::tools::ToolRegistry;
-use crate::workspace::Workspace;
+use crate::workspace::{PersonaStatus, Workspace};
 
 /// Shared prompt queue: maps job IDs to pending follow-up prompts for Claude Code bridges.
 pub type PromptQueue = Arc<
@@ -55,8 +63,8 @@ pub struct RateLimiter {
     remaining: AtomicU64,
     /// Epoch second when the current window started.
     window_start: AtomicU64,
-    /// Maximum requests per window.
-    max_requests: u64,
+    /// Maximum requests per window (adjustable at runtime).
+    max_requests: AtomicU64,
     /// Window duration in seconds.
     window_secs: u64,
 }
@@ -71,11 +79,23 @@ impl RateLimiter {
                     .unwrap_or_default()
                     .as_secs(),
             ),
-            max_requests,
+            max_requests: AtomicU64::new(max_requests),
             window_secs,
         }
     }
 
+    /// Change the per-window limit. Takes full effect from the next window;
+    /// lowering it also caps what is left of the current one.
+    pub fn set_max_requests(&self, max_requests: u64) {
+        self.max_requests.store(max_requests, Ordering::Relaxed);
+        self.remaining.fetch_min(max_requests, Ordering::Relaxed);
+    }
+
+    /// Current per-window limit.
+    pub fn max_requests(&self) -> u64 {
+        self.max_requests.load(Ordering::Relaxed)
+    }
+
     /// Try to consume one request. Returns `true` if allowed, `false` if rate limited.
     pub fn check(&self) -> bool {
         let now = std::time::SystemTime::now()
@@ -88,7 +108,7 @@ impl RateLimiter {
             // Window expired, reset
             self.window_start.store(now, Ordering::Relaxed);
             self.remaining
-                .store(self.max_requests - 1, Ordering::Relaxed);
+                .store(self.max_requests().saturating_sub(1), Ordering::Relaxed);
             return true;
         }
 
@@ -139,8 +159,15 @@ pub struct GatewayState {
     pub ws_tracker: Option<Arc<crate::channels::web::ws::WsConnectionTracker>>,
     /// LLM provider for OpenAI-compatible API proxy.
     pub llm_provider: Option<Arc<dyn crate::llm::LlmProvider>>,
-    /// Rate limiter for chat endpoints (30 messages per 60 seconds).
-    pub chat_rate_limiter: RateLimiter,
+    /// Rate limiter for chat endpoints (`GATEWAY_CHAT_RATE_LIMIT` messages
+    /// per 60 seconds, 30 by default). Shared so the limit can be reloaded.
+    pub chat_rate_limiter: Arc<RateLimiter>,
+    /// Scoped API keys and sessions accepted alongside the master token.
+    pub api_keys: Option<Arc<ApiKeys>>,
+    /// Dependency health checks for the health and readiness endpoints.
+    pub health: Option<Arc<HealthChecker>>,
+    /// Responses held by moderation, for review.
+    pub moderation: Option<ModerationQueue>,
 }
 
 /// Start the gateway HTTP server.
@@ -166,10 +193,15 @@ pub async fn start_server(
             })?;
 
     // Public routes (no auth)
-    let public = Router::new().route("/api/health", get(health_handler));
+    let public = Router::new()
+        .route("/api/health", get(health_handler))
+        .route("/api/ready", get(ready_handler));
 
     // Protected routes (require auth)
-    let auth_state = AuthState { token: auth_token };
+    let auth_state = AuthState {
+        token: auth_token,
+        api_keys: state.api_keys.clone(),
+    };
     let protected = Router::new()
         // Chat
         .route("/api/chat/send", post(chat_send_handler))
@@ -187,6 +219,11 @@ pub async fn start_server(
         .route("/api/memory/read", get(memory_read_handler))
         .route("/api/memory/write", post(memory_write_handler))
         .route("/api/memory/search", post(memory_search_handler))
+        .route("/api/memory/personas", get(personas_handler))
+        .route(
+            "/api/memory/personas",
+            axum::routing::put(persona_set_handler),
+        )
         // Jobs
         .route("/api/jobs", get(jobs_list_handler))
         .route("/api/jobs/summary", get(jobs_summary_handler))
@@ -195,6 +232,7 @@ pub async fn start_server(
         .route("/api/jobs/{id}/restart", post(jobs_restart_handler))
         .route("/api/jobs/{id}/prompt", post(jobs_prompt_handler))
         .route("/api/jobs/{id}/events", get(jobs_events_handler))
+        .route("/api/jobs/{id}/transcript", get(jobs_transcript_handler))
         .route("/api/jobs/{id}/files/list", get(job_files_list_handler))
         .route("/api/jobs/{id}/files/read", get(job_files_read_handler))
         // Logs
@@ -222,6 +260,26 @@ pub async fn start_server(
             axum::routing::delete(routines_delete_handler),
         )
         .route("/api/routines/{id}/runs", get(routines_runs_handler))
+        // Agents
+        .route("/api/agents", get(agents_list_handler))
+        .route("/api/agents", post(agents_create_handler))
+        .route("/api/agents/{id}", get(agents_detail_handler))
+        .route(
+            "/api/agents/{id}",
+            axum::routing::patch(agents_update_handler),
+        )
+        .route(
+            "/api/agents/{id}",
+            axum::routing::delete(agents_delete_handler),
+        )
+        .route("/api/agents/{id}/enable", post(agents_enable_handler))
+        .route("/api/agents/{id}/disable", post(agents_disable_handler))
+        .route("/api/agents/{id}/fork", post(agents_fork_handler))
+        .route("/api/agents/{id}/personas", get(agents_personas_handler))
+        .route(
+            "/api/agents/{id}/personas",
+            axum::routing::put(agents_persona_set_handler),
+        )
         // Settings
         .route("/api/settings", get(settings_list_handler))
         .route("/api/settings/export", get(settings_export_handler))
@@ -235,6 +293,33 @@ pub async fn start_server(
             "/api/settings/{key}",
             axum::routing::delete(settings_delete_handler),
         )
+        // Permissions
+        .route("/api/permissions", get(permissions_get_handler))
+        .route(
+            "/api/permissions",
+            axum::routing::put(permissions_set_handler),
+        )
+        // Audit log
+        .route("/api/audit", get(audit_log_handler))
+        // Responses held by moderation
+        .route("/api/moderation", get(moderation_list_handler))
+        .route(
+            "/api/moderation/{id}/approve",
+            post(moderation_approve_handler),
+        )
+        .route(
+            "/api/moderation/{id}/reject",
+            post(moderation_reject_handler),
+        )
+        // API keys and sessions
+        .route("/api/auth/session", post(auth_session_handler))
+        .route("/api/keys", get(api_keys_list_handler))
+        .route("/api/keys", post(api_keys_create_handler))
+        .route("/api/keys/{id}/rotate", post(api_keys_rotate_handler))
+        .route(
+            "/api/keys/{id}",
+            axum::routing::delete(api_keys_revoke_handler),
+        )
         // Gateway control plane
         .route("/api/gateway/status", get(gateway_status_handler))
         // OpenAI-compatible API
@@ -336,15 +421,75 @@ async fn js_handler() -> impl IntoResponse {
 
 // --- Health ---
 
-async fn health_handler() -> Json<HealthResponse> {
-    Json(HealthResponse {
-        status: "healthy",
+async fn health_report(state: &GatewayState) -> HealthResponse {
+    let report = match state.health {
+        Some(ref health) => health.health().await,
+        None => HealthChecker::new().health().await,
+    };
+    HealthResponse {
+        status: report.status.as_str(),
         channel: "gateway",
-    })
+        ready: report.ready,
+        // Failure details are logged, not exposed on this unauthenticated route.
+        components: report
+            .components
+            .into_iter()
+            .map(|c| ComponentHealthResponse {
+                name: c.name,
+                healthy: c.healthy,
+                required: c.required,
+                latency_ms: c.latency_ms,
+            })
+            .collect(),
+    }
+}
+
+async fn health_handler(State(state): State<Arc<GatewayState>>) -> Json<HealthResponse> {
+    Json(health_report(&state).await)
+}
+
+/// 200 when every required component is up, 503 otherwise.
+async fn ready_handler(
+    State(state): State<Arc<GatewayState>>,
+) -> (StatusCode, Json<HealthResponse>) {
+    let report = health_report(&state).await;
+    let code = if report.ready {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+    (code, Json(report))
 }
 
 // --- Chat handlers ---
 
+/// Resolve a chat image entry: either an inline `data:image/...;base64,` URI
+/// or a workspace path whose document holds such a URI.
+async fn resolve_image(
+    state: &GatewayState,
+    entry: &str,
+) -> Result<crate::llm::ImageContent, (StatusCode, String)> {
+    if entry.starts_with("data:") {
+        return crate::llm::ImageContent::from_data_uri(entry).ok_or((
+            StatusCode::BAD_REQUEST,
+            "images must be data:image/...;base64 URIs or workspace paths".to_string(),
+        ));
+    }
+
+    let workspace = state.workspace.as_ref().ok_or((
+        StatusCode::SERVICE_UNAVAILABLE,
+        "Workspace not available".to_string(),
+    ))?;
+    let doc = workspace
+        .read(entry)
+        .await
+        .map_err(|e| (StatusCode::NOT_FOUND, e.to_string()))?;
+    crate::llm::ImageContent::from_data_uri(&doc.content).ok_or((
+        StatusCode::BAD_REQUEST,
+        format!("Workspace document '{}' is not an image", entry),
+    ))
+}
+
 async fn chat_send_handler(
     State(state): State<Arc<GatewayState>>,
     Json(req): Json<SendMessageRequest>,
@@ -358,6 +503,14 @@ async fn chat_send_handler(
 
     let mut msg = IncomingMessage::new("gateway", &state.user_id, &req.content);
 
+    if !req.images.is_empty() {
+        let mut images = Vec::with_capacity(req.images.len());
+        for entry in &req.images {
+            images.push(resolve_image(&state, entry).await?);
+        }
+        msg = msg.with_attachments(images);
+    }
+
     if let Some(ref thread_id) = req.thread_id {
         msg = msg.with_thread(thread_id);
         msg = msg.with_metadata(serde_json::json!({"thread_id": thread_id}));
@@ -1052,6 +1205,53 @@ async fn memory_search_handler(
     Ok(Json(MemorySearchResponse { results: hits }))
 }
 
+async fn personas_handler(
+    State(state): State<Arc<GatewayState>>,
+) -> Result<Json<PersonaStatus>, (StatusCode, String)> {
+    let workspace = state.workspace.as_ref().ok_or((
+        StatusCode::SERVICE_UNAVAILABLE,
+        "Workspace not available".to_string(),
+    ))?;
+    persona_status(workspace).await
+}
+
+async fn persona_set_handler(
+    State(state): State<Arc<GatewayState>>,
+    Json(req): Json<PersonaSetRequest>,
+) -> Result<Json<PersonaStatus>, (StatusCode, String)> {
+    let workspace = state.workspace.as_ref().ok_or((
+        StatusCode::SERVICE_UNAVAILABLE,
+        "Workspace not available".to_string(),
+    ))?;
+    set_persona(workspace, req).await
+}
+
+async fn persona_status(
+    workspace: &Workspace,
+) -> Result<Json<PersonaStatus>, (StatusCode, String)> {
+    workspace
+        .persona_status()
+        .await
+        .map(Json)
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))
+}
+
+async fn set_persona(
+    workspace: &Workspace,
+    req: PersonaSetRequest,
+) -> Result<Json<PersonaStatus>, (StatusCode, String)> {
+    workspace
+        .set_persona(req.channel.as_deref(), req.persona.as_deref())
+        .await
+        .map_err(|e| match e {
+            WorkspaceError::PersonaNotFound { .. } | WorkspaceError::InvalidPath { .. } => {
+                (StatusCode::BAD_REQUEST, e.to_string())
+            }
+            e => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+        })?;
+    persona_status(workspace).await
+}
+
 // --- Jobs handlers ---
 
 async fn jobs_list_handler(
@@ -1413,6 +1613,36 @@ async fn jobs_events_handler(
     })))
 }
 
+async fn jobs_transcript_handler(
+    State(state): State<Arc<GatewayState>>,
+    Path(id): Path<String>,
+) -> Result<Json<serde_json::Value>, (StatusCode, String)> {
+    let store = state.store.as_ref().ok_or((
+        StatusCode::NOT_IMPLEMENTED,
+        "Database not available".to_string(),
+    ))?;
+
+    let job_id: uuid::Uuid = id
+        .parse()
+        .map_err(|_| (StatusCode::BAD_REQUEST, "Invalid job ID".to_string()))?;
+
+    // Verify user owns this job.
+    match store.get_job(job_id).await {
+        Ok(Some(job)) if job.user_id == state.user_id => {}
+        _ => return Err((StatusCode::NOT_FOUND, "Job not found".to_string())),
+    }
+
+    let entries = store
+        .get_job_transcript(job_id)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    Ok(Json(serde_json::json!({
+        "job_id": job_id.to_string(),
+        "entries": entries,
+    })))
+}
+
 // --- Project file handlers for sandbox jobs ---
 
 #[derive(Deserialize)]
@@ -1927,6 +2157,14 @@ async fn routines_trigger_handler(
         crate::agent::routine::RoutineAction::FullJob {
             title, description, ..
         } => format!("{}: {}", title, description),
+        crate::agent::routine::RoutineAction::Template {
+            template,
+            variables,
+        } => format!(
+            "Run the '{}' job template with variables {}",
+            template,
+            serde_json::json!(variables)
+        ),
     };
 
     let content = format!("[routine:{}] {}", routine.name, prompt);
@@ -2077,6 +2315,7 @@ fn routine_to_info(r: &crate::agent::routine::Routine) -> RoutineInfo {
     let action_type = match &r.action {
         crate::agent::routine::RoutineAction::Lightweight { .. } => "lightweight",
         crate::agent::routine::RoutineAction::FullJob { .. } => "full_job",
+        crate::agent::routine::RoutineAction::Template { .. } => "template",
     };
 
     let status = if !r.enabled {
@@ -2103,6 +2342,143 @@ fn routine_to_info(r: &crate::agent::routine::Routine) -> RoutineInfo {
     }
 }
 
+// --- Agent registry handlers ---
+
+fn agent_registry(state: &GatewayState) -> Result<AgentRegistry, (StatusCode, String)> {
+    let store = state.store.as_ref().ok_or((
+        StatusCode::SERVICE_UNAVAILABLE,
+        "Database not available".to_string(),
+    ))?;
+    Ok(AgentRegistry::new(Arc::clone(store)))
+}
+
+fn agent_error(e: AgentRegistryError) -> (StatusCode, String) {
+    let status = match e {
+        AgentRegistryError::NotFound(_) => StatusCode::NOT_FOUND,
+        AgentRegistryError::AlreadyExists(_) => StatusCode::CONFLICT,
+        AgentRegistryError::Invalid(_) => StatusCode::BAD_REQUEST,
+        _ => StatusCode::INTERNAL_SERVER_ERROR,
+    };
+    (status, e.to_string())
+}
+
+async fn agents_list_handler(
+    State(state): State<Arc<GatewayState>>,
+) -> Result<Json<AgentListResponse>, (StatusCode, String)> {
+    let agents = agent_registry(&state)?.list().await.map_err(agent_error)?;
+    Ok(Json(AgentListResponse { agents }))
+}
+
+async fn agents_create_handler(
+    State(state): State<Arc<GatewayState>>,
+    Json(req): Json<AgentCreateRequest>,
+) -> Result<(StatusCode, Json<AgentDefinition>), (StatusCode, String)> {
+    let mut agent = AgentDefinition::new(req.name);
+    agent.description = req.description;
+    agent.model = req.model;
+    if let Some(tools) = req.tools {
+        agent.tools = tools;
+    }
+    agent.channels = req.channels;
+    agent.workspace_seed = req.workspace_seed;
+
+    let agent = agent_registry(&state)?
+        .create(agent)
+        .await
+        .map_err(agent_error)?;
+    Ok((StatusCode::CREATED, Json(agent)))
+}
+
+async fn agents_detail_handler(
+    State(state): State<Arc<GatewayState>>,
+    Path(id): Path<String>,
+) -> Result<Json<AgentDefinition>, (StatusCode, String)> {
+    let agent = agent_registry(&state)?
+        .get(&id)
+        .await
+        .map_err(agent_error)?;
+    Ok(Json(agent))
+}
+
+async fn agents_update_handler(
+    State(state): State<Arc<GatewayState>>,
+    Path(id): Path<String>,
+    Json(update): Json<AgentUpdate>,
+) -> Result<Json<AgentDefinition>, (StatusCode, String)> {
+    let agent = agent_registry(&state)?
+        .configure(&id, update)
+        .await
+        .map_err(agent_error)?;
+    Ok(Json(agent))
+}
+
+async fn agents_delete_handler(
+    State(state): State<Arc<GatewayState>>,
+    Path(id): Path<String>,
+) -> Result<Json<serde_json::Value>, (StatusCode, String)> {
+    let agent = agent_registry(&state)?
+        .remove(&id)
+        .await
+        .map_err(agent_error)?;
+    Ok(Json(serde_json::json!({
+        "status": "deleted",
+        "agent_id": agent.id,
+    })))
+}
+
+async fn agents_enable_handler(
+    State(state): State<Arc<GatewayState>>,
+    Path(id): Path<String>,
+) -> Result<Json<AgentDefinition>, (StatusCode, String)> {
+    let agent = agent_registry(&state)?
+        .set_enabled(&id, true)
+        .await
+        .map_err(agent_error)?;
+    Ok(Json(agent))
+}
+
+async fn agents_disable_handler(
+    State(state): State<Arc<GatewayState>>,
+    Path(id): Path<String>,
+) -> Result<Json<AgentDefinition>, (StatusCode, String)> {
+    let agent = agent_registry(&state)?
+        .set_enabled(&id, false)
+        .await
+        .map_err(agent_error)?;
+    Ok(Json(agent))
+}
+
+async fn agents_fork_handler(
+    State(state): State<Arc<GatewayState>>,
+    Path(id): Path<String>,
+    Json(req): Json<AgentForkRequest>,
+) -> Result<(StatusCode, Json<AgentDefinition>), (StatusCode, String)> {
+    let agent = agent_registry(&state)?
+        .fork(&id, &req.name, req.options)
+        .await
+        .map_err(agent_error)?;
+    Ok((StatusCode::CREATED, Json(agent)))
+}
+
+async fn agents_personas_handler(
+    State(state): State<Arc<GatewayState>>,
+    Path(id): Path<String>,
+) -> Result<Json<PersonaStatus>, (StatusCode, String)> {
+    let registry = agent_registry(&state)?;
+    let agent = registry.get(&id).await.map_err(agent_error)?;
+    persona_status(&registry.workspace(&agent)).await
+}
+
+async fn agents_persona_set_handler(
+    State(state): State<Arc<GatewayState>>,
+    Path(id): Path<String>,
+    Json(req): Json<PersonaSetRequest>,
+) -> Result<Json<PersonaStatus>, (StatusCode, String)> {
+    let registry = agent_registry(&state)?;
+    let agent = registry.get(&id).await.map_err(agent_error)?;
+    set_persona(&registry.workspace(&agent), req).await
+}
+
 // --- Settings handlers ---
 
 async fn settings_list_handler(
@@ -2119,6 +2495,7 @@ async fn settings_list_handler(
 
     let settings = rows
         .into_iter()
+        .filter(|r| r.key != API_KEYS_SETTING_KEY)
         .map(|r| SettingResponse {
             key: r.key,
             value: r.value,
@@ -2133,6 +2510,9 @@ async fn settings_get_handler(
     State(state): State<Arc<GatewayState>>,
     Path(key): Path<String>,
 ) -> Result<Json<SettingResponse>, StatusCode> {
+    if key == API_KEYS_SETTING_KEY {
+        return Err(StatusCode::FORBIDDEN);
+    }
     let store = state
         .store
         .as_ref()
@@ -2158,6 +2538,16 @@ async fn settings_set_handler(
     Path(key): Path<String>,
     Json(body): Json<SettingWriteRequest>,
 ) -> Result<StatusCode, StatusCode> {
+    // API keys are managed through /api/keys, which keeps the live keys in
+    // step with what is stored; a write here would go unnoticed until restart.
+    if key == API_KEYS_SETTING_KEY {
+        return Err(StatusCode::FORBIDDEN);
+    }
+    // The permission policy is validated and applied live, not just stored.
+    if key == PERMISSIONS_SETTING_KEY {
+        return apply_permissions(&state, body.value).await;
+    }
+
     let store = state
         .store
         .as_ref()
@@ -2177,6 +2567,15 @@ async fn settings_delete_handler(
     State(state): State<Arc<GatewayState>>,
     Path(key): Path<String>,
 ) -> Result<StatusCode, StatusCode> {
+    if key == API_KEYS_SETTING_KEY {
+        return Err(StatusCode::FORBIDDEN);
+    }
+    if key == PERMISSIONS_SETTING_KEY {
+        let empty = serde_json::to_value(PermissionPolicy::default())
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+        return apply_permissions(&state, empty).await;
+    }
+
     let store = state
         .store
         .as_ref()
@@ -2199,18 +2598,24 @@ async fn settings_export_handler(
         .store
         .as_ref()
         .ok_or(StatusCode::SERVICE_UNAVAILABLE)?;
-    let settings = store.get_all_settings(&state.user_id).await.map_err(|e| {
+    let mut settings = store.get_all_settings(&state.user_id).await.map_err(|e| {
         tracing::error!("Failed to export settings: {}", e);
         StatusCode::INTERNAL_SERVER_ERROR
     })?;
+    settings.remove(API_KEYS_SETTING_KEY);
 
     Ok(Json(SettingsExportResponse { settings }))
 }
 
 async fn settings_import_handler(
     State(state): State<Arc<GatewayState>>,
-    Json(body): Json<SettingsImportRequest>,
+    Json(mut body): Json<SettingsImportRequest>,
 ) -> Result<StatusCode, StatusCode> {
+    body.settings.remove(API_KEYS_SETTING_KEY);
+    if let Some(policy) = body.settings.get(PERMISSIONS_SETTING_KEY) {
+        apply_permissions(&state, policy.clone()).await?;
+    }
+
     let store = state
         .store
         .as_ref()
@@ -2226,6 +2631,275 @@ async fn settings_import_handler(
     Ok(StatusCode::NO_CONTENT)
 }
 
+// --- Permissions handlers ---
+
+async fn permissions_get_handler(
+    State(state): State<Arc<GatewayState>>,
+) -> Result<Json<PermissionPolicy>, StatusCode> {
+    let permissions = state
+        .tool_registry
+        .as_ref()
+        .and_then(|r| r.permissions())
+        .ok_or(StatusCode::SERVICE_UNAVAILABLE)?;
+    Ok(Json(permissions.policy()))
+}
+
+async fn permissions_set_handler(
+    State(state): State<Arc<GatewayState>>,
+    Json(body): Json<serde_json::Value>,
+) -> Result<StatusCode, StatusCode> {
+    apply_permissions(&state, body).await
+}
+
+/// Validate, persist, and activate a permission policy.
+async fn apply_permissions(
+    state: &GatewayState,
+    value: serde_json::Value,
+) -> Result<StatusCode, StatusCode> {
+    let permissions = state
+        .tool_registry
+        .as_ref()
+        .and_then(|r| r.permissions())
+        .ok_or(StatusCode::SERVICE_UNAVAILABLE)?;
+    let policy: PermissionPolicy =
+        serde_json::from_value(value).map_err(|_| StatusCode::BAD_REQUEST)?;
+    permissions.set_policy(policy).await.map_err(|e| match e {
+        PermissionError::InvalidPolicy(_) => StatusCode::BAD_REQUEST,
+        PermissionError::Database(e) => {
+            tracing::error!("Failed to save permissions: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        }
+    })?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+// --- Audit log handlers ---
+
+#[derive(Deserialize)]
+struct AuditLogQuery {
+    kind: Option<String>,
+    actor: Option<String>,
+    since: Option<String>,
+    until: Option<String>,
+    before_id: Option<i64>,
+    limit: Option<i64>,
+}
+
+fn parse_audit_timestamp(
+    value: Option<&str>,
+    name: &str,
+) -> Result<Option<chrono::DateTime<chrono::Utc>>, (StatusCode, String)> {
+    value
+        .map(|s| {
+            chrono::DateTime::parse_from_rfc3339(s)
+                .map(|dt| dt.with_timezone(&chrono::Utc))
+                .map_err(|_| {
+                    (
+                        StatusCode::BAD_REQUEST,
+                        format!("Invalid '{}' timestamp", name),
+                    )
+                })
+        })
+        .transpose()
+}
+
+async fn audit_log_handler(
+    State(state): State<Arc<GatewayState>>,
+    Query(query): Query<AuditLogQuery>,
+) -> Result<Json<AuditLogResponse>, (StatusCode, String)> {
+    let store = state.store.as_ref().ok_or((
+        StatusCode::SERVICE_UNAVAILABLE,
+        "Database not available".to_string(),
+    ))?;
+
+    let kind = query
+        .kind
+        .as_deref()
+        .map(|k| {
+            k.parse::<AuditEventKind>()
+                .map_err(|e| (StatusCode::BAD_REQUEST, e))
+        })
+        .transpose()?;
+    let filter = AuditQuery {
+        kind,
+        actor: query.actor,
+        since: parse_audit_timestamp(query.since.as_deref(), "since")?,
+        until: parse_audit_timestamp(query.until.as_deref(), "until")?,
+        before_id: query.before_id,
+        limit: query.limit,
+    };
+
+    let records = store.query_audit_log(&filter).await.map_err(|e| {
+        tracing::error!("Failed to query audit log: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Failed to query audit log".to_string(),
+        )
+    })?;
+
+    let next_before_id = if records.len() as i64 == filter.effective_limit() {
+        records.last().map(|r| r.id)
+    } else {
+        None
+    };
+    let entries = records
+        .into_iter()
+        .map(|r| AuditEntryResponse {
+            id: r.id,
+            kind: r.kind.to_string(),
+            actor: r.actor,
+            channel: r.channel,
+            target: r.target,
+            outcome: r.outcome,
+            details: r.details,
+            created_at: r.created_at.to_rfc3339(),
+        })
+        .collect();
+
+    Ok(Json(AuditLogResponse {
+        entries,
+        next_before_id,
+    }))
+}
+
+// --- API key and session handlers ---
+
+async fn auth_session_handler(
+    State(state): State<Arc<GatewayState>>,
+    Extension(principal): Extension<Principal>,
+) -> Result<Json<SessionResponse>, StatusCode> {
+    let keys = state
+        .api_keys
+        .as_ref()
+        .ok_or(StatusCode::SERVICE_UNAVAILABLE)?;
+    let (token, expires_at) = keys.create_session(&principal);
+    Ok(Json(SessionResponse {
+        token,
+        expires_at: expires_at.to_rfc3339(),
+        scopes: principal.scopes,
+    }))
+}
+
+async fn api_keys_list_handler(
+    State(state): State<Arc<GatewayState>>,
+) -> Result<Json<ApiKeysListResponse>, StatusCode> {
+    let keys = state
+        .api_keys
+        .as_ref()
+        .ok_or(StatusCode::SERVICE_UNAVAILABLE)?;
+    Ok(Json(ApiKeysListResponse { keys: keys.list() }))
+}
+
+async fn api_keys_create_handler(
+    State(state): State<Arc<GatewayState>>,
+    Json(body): Json<CreateApiKeyRequest>,
+) -> Result<Json<ApiKeySecretResponse>, (StatusCode, String)> {
+    let keys = state.api_keys.as_ref().ok_or((
+        StatusCode::SERVICE_UNAVAILABLE,
+        "API keys not available".to_string(),
+    ))?;
+    let expires_at = body
+        .expires_in_days
+        .map(|days| chrono::Utc::now() + chrono::Duration::days(days));
+    let (key, token) = keys
+        .create(NewApiKey {
+            name: body.name,
+            scopes: body.scopes,
+            expires_at,
+            rate_limit_per_minute: body.rate_limit_per_minute,
+        })
+        .await
+        .map_err(api_key_error)?;
+    tracing::info!("Created API key '{}' ({})", key.name, key.id);
+    Ok(Json(ApiKeySecretResponse { key, token }))
+}
+
+async fn api_keys_rotate_handler(
+    State(state): State<Arc<GatewayState>>,
+    Path(id): Path<String>,
+) -> Result<Json<ApiKeySecretResponse>, (StatusCode, String)> {
+    let keys = state.api_keys.as_ref().ok_or((
+        StatusCode::SERVICE_UNAVAILABLE,
+        "API keys not available".to_string(),
+    ))?;
+    let (key, token) = keys.rotate(&id).await.map_err(api_key_error)?;
+    tracing::info!("Rotated API key '{}' ({})", key.name, key.id);
+    Ok(Json(ApiKeySecretResponse { key, token }))
+}
+
+async fn api_keys_revoke_handler(
+    State(state): State<Arc<GatewayState>>,
+    Path(id): Path<String>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    let keys = state.api_keys.as_ref().ok_or((
+        StatusCode::SERVICE_UNAVAILABLE,
+        "API keys not available".to_string(),
+    ))?;
+    if keys.revoke(&id).await.map_err(api_key_error)? {
+        tracing::info!("Revoked API key {}", id);
+        Ok(StatusCode::NO_CONTENT)
+    } else {
+        Err((StatusCode::NOT_FOUND, format!("API key not found: {}", id)))
+    }
+}
+
+fn api_key_error(e: ApiKeyError) -> (StatusCode, String) {
+    match e {
+        ApiKeyError::NotFound(_) => (StatusCode::NOT_FOUND, e.to_string()),
+        ApiKeyError::Invalid(_) => (StatusCode::BAD_REQUEST, e.to_string()),
+        ApiKeyError::Serialization(_) | ApiKeyError::Database(_) => {
+            tracing::error!("API key update failed: {}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Failed to save API keys".to_string(),
+            )
+        }
+    }
+}
+
+// --- Moderation handlers ---
+
+fn moderation_queue(state: &GatewayState) -> Result<&ModerationQueue, (StatusCode, String)> {
+    state.moderation.as_ref().ok_or((
+        StatusCode::SERVICE_UNAVAILABLE,
+        "Moderation not enabled".to_string(),
+    ))
+}
+
+async fn moderation_list_handler(
+    State(state): State<Arc<GatewayState>>,
+) -> Result<Json<ModerationListResponse>, (StatusCode, String)> {
+    let held = moderation_queue(&state)?.list().await;
+    Ok(Json(ModerationListResponse { held }))
+}
+
+async fn moderation_approve_handler(
+    State(state): State<Arc<GatewayState>>,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    let queue = moderation_queue(&state)?;
+    match queue.approve(id, &state.user_id).await {
+        Ok(true) => Ok(StatusCode::NO_CONTENT),
+        Ok(false) => Err((StatusCode::NOT_FOUND, format!("No held response {}", id))),
+        Err(e) => {
+            tracing::error!("Failed to send approved response {}: {}", id, e);
+            Err((StatusCode::BAD_GATEWAY, e.to_string()))
+        }
+    }
+}
+
+async fn moderation_reject_handler(
+    State(state): State<Arc<GatewayState>>,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    if moderation_queue(&state)?.reject(id, &state.user_id).await {
+        Ok(StatusCode::NO_CONTENT)
+    } else {
+        Err((StatusCode::NOT_FOUND, format!("No held response {}", id)))
+    }
+}
+
 // --- Gateway control plane handlers ---
 
 async fn gateway_status_handler(
@@ -2256,6 +2930,86 @@ struct GatewayStatusResponse {
 mod tests {
     use super::*;
 
+    #[cfg(feature = "libsql")]
+    #[tokio::test]
+    async fn test_settings_api_cannot_touch_api_keys() {
+        use crate::channels::web::api_keys::Scope;
+        use crate::channels::web::sse::SseManager;
+        use crate::db::libsql_backend::test_db;
+
+        let (db, _dir) = test_db().await;
+        let keys = Arc::new(ApiKeys::load(Arc::clone(&db), "test").await.unwrap());
+        let (key, token) = keys
+            .create(NewApiKey {
+                name: "ci".to_string(),
+                scopes: vec![Scope::Chat],
+                expires_at: None,
+                rate_limit_per_minute: None,
+            })
+            .await
+            .unwrap();
+        let before_revoke = db
+            .get_setting("test", API_KEYS_SETTING_KEY)
+            .await
+            .unwrap()
+            .unwrap();
+        keys.revoke(&key.id).await.unwrap();
+
+        let state = Arc::new(GatewayState {
+            msg_tx: tokio::sync::RwLock::new(None),
+            sse: SseManager::new(),
+            workspace: None,
+            session_manager: None,
+            log_broadcaster: None,
+            extension_manager: None,
+            tool_registry: None,
+            store: Some(Arc::clone(&db)),
+            job_manager: None,
+            prompt_queue: None,
+            user_id: "test".to_string(),
+            shutdown_tx: tokio::sync::RwLock::new(None),
+            ws_tracker: None,
+            llm_provider: None,
+            chat_rate_limiter: Arc::new(RateLimiter::new(30, 60)),
+            api_keys: Some(Arc::clone(&keys)),
+            health: None,
+            moderation: None,
+        });
+
+        // Writing the pre-revocation keys back must not bring the key back.
+        let set = settings_set_handler(
+            State(Arc::clone(&state)),
+            Path(API_KEYS_SETTING_KEY.to_string()),
+            Json(SettingWriteRequest {
+                value: before_revoke,
+            }),
+        )
+        .await;
+        assert_eq!(set, Err(StatusCode::FORBIDDEN));
+        let deleted = settings_delete_handler(
+            State(Arc::clone(&state)),
+            Path(API_KEYS_SETTING_KEY.to_string()),
+        )
+        .await;
+        assert_eq!(deleted, Err(StatusCode::FORBIDDEN));
+
+        let reloaded = ApiKeys::load(Arc::clone(&db), "test").await.unwrap();
+        assert!(reloaded.authenticate(&token).is_err());
+        assert!(keys.authenticate(&token).is_err());
+
+        let Json(listed) = settings_list_handler(State(Arc::clone(&state)))
+            .await
+            .unwrap();
+        assert!(
+            listed
+                .settings
+                .iter()
+                .all(|s| s.key != API_KEYS_SETTING_KEY)
+        );
+        let Json(exported) = settings_export_handler(State(state)).await.unwrap();
+        assert!(!exported.settings.contains_key(API_KEYS_SETTING_KEY));
+    }
+
     #[test]
     fn test_build_turns_from_db_messages_complete() {
         let now = chrono::Utc::now();
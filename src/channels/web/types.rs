@@ -1,14 +1,23 @@
 //! Request and response DTOs for the web gateway API.
 
+use std::collections::BTreeMap;
+
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
+use crate::agent::registry::{AgentDefinition, ForkOptions};
+use crate::channels::web::api_keys::{ApiKey, Scope};
+
 // --- Chat ---
 
 #[derive(Debug, Deserialize)]
 pub struct SendMessageRequest {
     pub content: String,
     pub thread_id: Option<String>,
+    /// Attached images: `data:image/...;base64,` URIs or workspace paths
+    /// of documents holding one.
+    #[serde(default)]
+    pub images: Vec<String>,
 }
 
 #[derive(Debug, Serialize)]
@@ -568,6 +577,46 @@ pub struct RoutineRunInfo {
     pub tokens_used: Option<i32>,
 }
 
+// --- Agents ---
+
+#[derive(Debug, Serialize)]
+pub struct AgentListResponse {
+    pub agents: Vec<AgentDefinition>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AgentCreateRequest {
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default)]
+    pub model: Option<String>,
+    /// Defaults to every tool.
+    #[serde(default)]
+    pub tools: Option<Vec<String>>,
+    #[serde(default)]
+    pub channels: Vec<String>,
+    #[serde(default)]
+    pub workspace_seed: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AgentForkRequest {
+    pub name: String,
+    #[serde(flatten)]
+    pub options: ForkOptions,
+}
+
+/// Switch the persona of one channel, or the default one when `channel` is
+/// absent. A missing `persona` goes back to the default.
+#[derive(Debug, Deserialize)]
+pub struct PersonaSetRequest {
+    #[serde(default)]
+    pub channel: Option<String>,
+    #[serde(default)]
+    pub persona: Option<String>,
+}
+
 // --- Settings ---
 
 #[derive(Debug, Serialize)]
@@ -597,12 +646,81 @@ pub struct SettingsExportResponse {
     pub settings: std::collections::HashMap<String, serde_json::Value>,
 }
 
+// --- Audit ---
+
+#[derive(Debug, Serialize)]
+pub struct AuditEntryResponse {
+    pub id: i64,
+    pub kind: String,
+    pub actor: String,
+    pub channel: Option<String>,
+    pub target: Option<String>,
+    pub outcome: Option<String>,
+    pub details: serde_json::Value,
+    pub created_at: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AuditLogResponse {
+    pub entries: Vec<AuditEntryResponse>,
+    /// Pass as `before_id` to fetch the next (older) page.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next_before_id: Option<i64>,
+}
+
+// --- Moderation ---
+
+#[derive(Debug, Serialize)]
+pub struct ModerationListResponse {
+    /// Responses awaiting approval, oldest first.
+    pub held: Vec<crate::channels::HeldResponse>,
+}
+
+// --- API Keys ---
+
+#[derive(Debug, Deserialize)]
+pub struct CreateApiKeyRequest {
+    pub name: String,
+    pub scopes: Vec<Scope>,
+    pub expires_in_days: Option<i64>,
+    pub rate_limit_per_minute: Option<u64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ApiKeysListResponse {
+    pub keys: Vec<ApiKey>,
+}
+
+/// Returned on create and rotate: the only time the full key is visible.
+#[derive(Debug, Serialize)]
+pub struct ApiKeySecretResponse {
+    pub key: ApiKey,
+    pub token: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SessionResponse {
+    pub token: String,
+    pub expires_at: String,
+    pub scopes: Vec<Scope>,
+}
+
 // --- Health ---
 
 #[derive(Debug, Serialize)]
 pub struct HealthResponse {
     pub status: &'static str,
     pub channel: &'static str,
+    pub ready: bool,
+    pub components: Vec<ComponentHealthResponse>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ComponentHealthResponse {
+    pub name: String,
+    pub healthy: bool,
+    pub required: bool,
+    pub latency_ms: u64,
 }
 
 #[cfg(test)]
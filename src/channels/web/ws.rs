@@ -486,7 +486,10 @@ mod tests {
             shutdown_tx: tokio::sync::RwLock::new(None),
             ws_tracker: Some(Arc::new(WsConnectionTracker::new())),
             llm_provider: None,
-            chat_rate_limiter: crate::channels::web::server::RateLimiter::new(30, 60),
+            chat_rate_limiter: Arc::new(crate::channels::web::server::RateLimiter::new(30, 60)),
+            api_keys: None,
+            health: None,
+            moderation: None,
         }
     }
 }
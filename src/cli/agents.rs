@@ -0,0 +1,374 @@
+//! Agent registry CLI commands.
+//!
+//! Commands for creating, configuring, enabling, and removing registered
+//! agents, and for searching all of their workspaces at once. Start one
+//! with `ironclaw run --agent <name>`.
+
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use clap::Subcommand;
+
+use crate::agent::registry::{
+    AgentDefinition, AgentRegistry, AgentUpdate, ForkOptions, ResultSource,
+};
+use crate::db::Database;
+use crate::workspace::{EmbeddingProvider, SearchConfig};
+
+#[derive(Subcommand, Debug, Clone)]
+pub enum AgentsCommand {
+    /// List registered agents
+    List {
+        /// Print agents as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Register a new agent
+    Create {
+        /// Agent name (lowercase letters, digits, '-' and '_')
+        name: String,
+
+        /// What the agent is for
+        #[arg(long)]
+        description: Option<String>,
+
+        /// Model to use instead of the configured one
+        #[arg(long)]
+        model: Option<String>,
+
+        /// Tool the agent may use (repeatable; `*` and `prefix*` allowed).
+        /// Defaults to every tool.
+        #[arg(long = "tool")]
+        tools: Vec<String>,
+
+        /// Channel the agent serves (repeatable: cli, http, gateway, or a
+        /// WASM channel name). Defaults to every configured channel.
+        #[arg(long = "channel")]
+        channels: Vec<String>,
+
+        /// Seed a workspace document from a local file (PATH=FILE, repeatable)
+        #[arg(long = "seed", value_parser = parse_seed)]
+        seeds: Vec<(String, std::path::PathBuf)>,
+    },
+
+    /// Show an agent's configuration
+    Show {
+        /// Agent name or ID
+        agent: String,
+    },
+
+    /// Change an agent's configuration
+    Configure {
+        /// Agent name or ID
+        agent: String,
+
+        /// New description
+        #[arg(long)]
+        description: Option<String>,
+
+        /// Model override (empty string to use the configured model)
+        #[arg(long)]
+        model: Option<String>,
+
+        /// Replace the toolset (repeatable)
+        #[arg(long = "tool")]
+        tools: Vec<String>,
+
+        /// Replace the channel list (repeatable)
+        #[arg(long = "channel")]
+        channels: Vec<String>,
+
+        /// Serve every configured channel again
+        #[arg(long, conflicts_with = "channels")]
+        all_channels: bool,
+    },
+
+    /// Register a copy of an agent with a copy of its identity documents
+    Fork {
+        /// Agent to copy (name, ID, or "default" for the main agent)
+        agent: String,
+
+        /// Name of the new agent
+        name: String,
+
+        /// Don't copy identity documents (IDENTITY.md, SOUL.md, context/, ...)
+        #[arg(long)]
+        no_identity: bool,
+
+        /// Also copy long-term memory (MEMORY.md)
+        #[arg(long)]
+        memory: bool,
+    },
+
+    /// Allow an agent to start
+    Enable {
+        /// Agent name or ID
+        agent: String,
+    },
+
+    /// Stop an agent from starting
+    Disable {
+        /// Agent name or ID
+        agent: String,
+    },
+
+    /// Remove an agent (its workspace documents are kept)
+    Remove {
+        /// Agent name or ID
+        agent: String,
+    },
+
+    /// Search every agent's workspace and shared namespace
+    Search {
+        /// Search query
+        query: String,
+
+        /// Maximum number of results
+        #[arg(short, long, default_value = "10")]
+        limit: usize,
+
+        /// Print results as JSON
+        #[arg(long)]
+        json: bool,
+    },
+}
+
+/// Run an agents command.
+pub async fn run_agents_command(
+    cmd: AgentsCommand,
+    db: Arc<dyn Database>,
+    embeddings: Option<Arc<dyn EmbeddingProvider>>,
+) -> anyhow::Result<()> {
+    let mut registry = AgentRegistry::new(db);
+    if let Some(provider) = embeddings {
+        registry = registry.with_embeddings(provider);
+    }
+
+    match cmd {
+        AgentsCommand::List { json } => {
+            let agents = registry.list().await?;
+            if json {
+                println!("{}", serde_json::to_string_pretty(&agents)?);
+            } else if agents.is_empty() {
+                println!("No agents registered.");
+                println!();
+                println!("Create one with: ironclaw agents create <name>");
+            } else {
+                for agent in &agents {
+                    let status = if agent.enabled { "enabled" } else { "disabled" };
+                    println!("  {:<20} {:<9} {}", agent.name, status, agent.description);
+                }
+            }
+        }
+
+        AgentsCommand::Create {
+            name,
+            description,
+            model,
+            tools,
+            channels,
+            seeds,
+        } => {
+            let mut agent = AgentDefinition::new(name);
+            agent.description = description.unwrap_or_default();
+            agent.model = model;
+            if !tools.is_empty() {
+                agent.tools = tools;
+            }
+            agent.channels = channels;
+            agent.workspace_seed = read_seeds(seeds)?;
+
+            let agent = registry.create(agent).await?;
+            println!("✓ Created agent '{}' ({})", agent.name, agent.id);
+            println!();
+            println!("Start it with: ironclaw run --agent {}", agent.name);
+        }
+
+        AgentsCommand::Show { agent } => {
+            print_agent(&registry.get(&agent).await?);
+        }
+
+        AgentsCommand::Configure {
+            agent,
+            description,
+            model,
+            tools,
+            channels,
+            all_channels,
+        } => {
+            let update = AgentUpdate {
+                description,
+                model,
+                tools: Some(tools).filter(|t| !t.is_empty()),
+                channels: if all_channels {
+                    Some(Vec::new())
+                } else {
+                    Some(channels).filter(|c| !c.is_empty())
+                },
+                enabled: None,
+            };
+            let agent = registry.configure(&agent, update).await?;
+            println!("✓ Updated agent '{}'", agent.name);
+            println!();
+            print_agent(&agent);
+        }
+
+        AgentsCommand::Fork {
+            agent,
+            name,
+            no_identity,
+            memory,
+        } => {
+            let options = ForkOptions {
+                copy_identity: !no_identity,
+                copy_memory: memory,
+            };
+            let fork = registry.fork(&agent, &name, options).await?;
+            println!("✓ Forked '{}' into '{}' ({})", agent, fork.name, fork.id);
+            println!();
+            println!("Start it with: ironclaw run --agent {}", fork.name);
+        }
+
+        AgentsCommand::Enable { agent } => {
+            let agent = registry.set_enabled(&agent, true).await?;
+            println!("✓ Enabled agent '{}'", agent.name);
+        }
+
+        AgentsCommand::Disable { agent } => {
+            let agent = registry.set_enabled(&agent, false).await?;
+            println!("✓ Disabled agent '{}'", agent.name);
+        }
+
+        AgentsCommand::Remove { agent } => {
+            let agent = registry.remove(&agent).await?;
+            println!("✓ Removed agent '{}'", agent.name);
+        }
+
+        AgentsCommand::Search { query, limit, json } => {
+            let config = SearchConfig::default().with_limit(limit.min(100));
+            let results = registry.search(&query, config).await?;
+            if json {
+                let results: Vec<serde_json::Value> = results
+                    .iter()
+                    .map(|r| {
+                        serde_json::json!({
+                            "source": r.source,
+                            "path": r.path,
+                            "document_id": r.result.document_id,
+                            "score": r.result.score,
+                            "content": r.result.content,
+                        })
+                    })
+                    .collect();
+                println!("{}", serde_json::to_string_pretty(&results)?);
+            } else if results.is_empty() {
+                println!("No results found for: {}", query);
+            } else {
+                for (i, r) in results.iter().enumerate() {
+                    let source = match &r.source {
+                        ResultSource::Agent { name, .. } => name.clone(),
+                        ResultSource::Namespace { name, members } => {
+                            format!("{} (shared with {})", name, members.join(", "))
+                        }
+                    };
+                    println!(
+                        "{:>2}. [{}] {}  {:.3}",
+                        i + 1,
+                        source,
+                        r.path,
+                        r.result.score
+                    );
+                    let preview: String = r
+                        .result
+                        .content
+                        .split_whitespace()
+                        .collect::<Vec<_>>()
+                        .join(" ")
+                        .chars()
+                        .take(120)
+                        .collect();
+                    println!("    {}", preview);
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn print_agent(agent: &AgentDefinition) {
+    println!("Agent: {}", agent.name);
+    println!("  ID:          {}", agent.id);
+    println!(
+        "  Status:      {}",
+        if agent.enabled { "enabled" } else { "disabled" }
+    );
+    if !agent.description.is_empty() {
+        println!("  Description: {}", agent.description);
+    }
+    println!(
+        "  Model:       {}",
+        agent.model.as_deref().unwrap_or("(configured model)")
+    );
+    println!("  Tools:       {}", agent.tools.join(", "));
+    if agent.channels.is_empty() {
+        println!("  Channels:    (all configured)");
+    } else {
+        println!("  Channels:    {}", agent.channels.join(", "));
+    }
+    if !agent.workspace_seed.is_empty() {
+        let paths: Vec<&str> = agent.workspace_seed.keys().map(String::as_str).collect();
+        println!("  Seeded:      {}", paths.join(", "));
+    }
+}
+
+fn parse_seed(arg: &str) -> Result<(String, std::path::PathBuf), String> {
+    match arg.split_once('=') {
+        Some((path, file)) if !path.trim().is_empty() && !file.trim().is_empty() => Ok((
+            path.trim().to_string(),
+            std::path::PathBuf::from(file.trim()),
+        )),
+        _ => Err(format!("expected PATH=FILE, got '{}'", arg)),
+    }
+}
+
+fn read_seeds(
+    seeds: Vec<(String, std::path::PathBuf)>,
+) -> anyhow::Result<BTreeMap<String, String>> {
+    let mut docs = BTreeMap::new();
+    for (path, file) in seeds {
+        let content = std::fs::read_to_string(&file)
+            .map_err(|e| anyhow::anyhow!("Failed to read {}: {}", file.display(), e))?;
+        docs.insert(path, content);
+    }
+    Ok(docs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_seed() {
+        let (path, file) = parse_seed("IDENTITY.md=./identity.md").unwrap();
+        assert_eq!(path, "IDENTITY.md");
+        assert_eq!(file, std::path::PathBuf::from("./identity.md"));
+
+        assert!(parse_seed("IDENTITY.md").is_err());
+        assert!(parse_seed("=file.md").is_err());
+    }
+
+    #[test]
+    fn test_command_structure() {
+        use clap::CommandFactory;
+
+        #[derive(clap::Parser)]
+        struct TestCli {
+            #[command(subcommand)]
+            cmd: AgentsCommand,
+        }
+
+        TestCli::command().debug_assert();
+    }
+}
@@ -41,6 +41,9 @@ pub enum ConfigCommand {
 
     /// Show the settings storage info
     Path,
+
+    /// Validate the config file and show the settings it provides
+    Check,
 }
 
 /// Run a config command.
@@ -48,6 +51,11 @@ pub enum ConfigCommand {
 /// Connects to the database to read/write settings. Falls back to disk
 /// if the database is not available.
 pub async fn run_config_command(cmd: ConfigCommand) -> anyhow::Result<()> {
+    // Checking the file must not depend on it being valid
+    if let ConfigCommand::Check = cmd {
+        return check_config_file();
+    }
+
     // Try to connect to the DB for settings access
     let db: Option<Arc<dyn crate::db::Database>> = match connect_db().await {
         Ok(d) => Some(d),
@@ -67,6 +75,7 @@ pub async fn run_config_command(cmd: ConfigCommand) -> anyhow::Result<()> {
         ConfigCommand::Set { path, value } => set_setting(db_ref, &path, &value).await,
         ConfigCommand::Reset { path } => reset_setting(db_ref, &path).await,
         ConfigCommand::Path => show_path(db_ref.is_some()),
+        ConfigCommand::Check => unreachable!("handled above"),
     }
 }
 
@@ -199,6 +208,47 @@ fn show_path(has_db: bool) -> anyhow::Result<()> {
         "Env config:         {}",
         crate::bootstrap::ironclaw_env_path().display()
     );
+    println!(
+        "Config file:        {}",
+        std::env::var(crate::config_file::CONFIG_PATH_ENV)
+            .map(std::path::PathBuf::from)
+            .unwrap_or_else(|_| crate::config_file::default_config_path())
+            .display()
+    );
+
+    Ok(())
+}
+
+/// Validate the config file and list the env settings it provides.
+fn check_config_file() -> anyhow::Result<()> {
+    let Some(file) = crate::config_file::load()? else {
+        println!(
+            "No config file at {} (set {} to use another path)",
+            crate::config_file::default_config_path().display(),
+            crate::config_file::CONFIG_PATH_ENV
+        );
+        return Ok(());
+    };
+
+    println!("{} is valid.", file.path.display());
+    if file.vars.is_empty() {
+        println!("It sets nothing; all settings keep their defaults.");
+        return Ok(());
+    }
+    println!();
+    for (name, value) in &file.vars {
+        let value = if crate::config_file::is_secret(name) {
+            "********"
+        } else {
+            value.as_str()
+        };
+        let note = if std::env::var(name).is_ok_and(|v| !v.is_empty()) {
+            "  (overridden by env var)"
+        } else {
+            ""
+        };
+        println!("  {:<28} {}{}", name, value, note);
+    }
 
     Ok(())
 }
@@ -0,0 +1,602 @@
+//! Diagnostics CLI command.
+//!
+//! Where `status` reports what is configured, `doctor` checks that the
+//! pieces actually work together: configuration loads, the database schema
+//! is current, stored embeddings match the configured embedding model, the
+//! memory index has no gaps, workspace documents lint clean, provider
+//! credentials are accepted, and channel listeners can bind. Every problem
+//! comes with a suggested fix.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::config::{Config, DatabaseBackend, LlmBackend};
+use crate::db::{DbDiagnostics, SCHEMA_VERSION};
+use crate::llm::{SessionConfig, create_llm_provider, create_session_manager};
+use crate::workspace::{
+    EmbeddingProvider, LintDiagnostic, LintSeverity, NearAiEmbeddings, OpenAiEmbeddings, Workspace,
+};
+
+/// Timeout for each network probe (LLM, embeddings).
+const PROBE_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Status {
+    Ok,
+    Warn,
+    Fail,
+    Skip,
+}
+
+impl Status {
+    fn label(&self) -> &'static str {
+        match self {
+            Self::Ok => "ok",
+            Self::Warn => "warn",
+            Self::Fail => "fail",
+            Self::Skip => "skip",
+        }
+    }
+}
+
+/// Outcome of one check.
+#[derive(Debug, Clone)]
+struct Check {
+    name: &'static str,
+    status: Status,
+    detail: String,
+    fix: Option<String>,
+}
+
+impl Check {
+    fn ok(name: &'static str, detail: impl Into<String>) -> Self {
+        Self {
+            name,
+            status: Status::Ok,
+            detail: detail.into(),
+            fix: None,
+        }
+    }
+
+    fn warn(name: &'static str, detail: impl Into<String>, fix: impl Into<String>) -> Self {
+        Self {
+            name,
+            status: Status::Warn,
+            detail: detail.into(),
+            fix: Some(fix.into()),
+        }
+    }
+
+    fn fail(name: &'static str, detail: impl Into<String>, fix: impl Into<String>) -> Self {
+        Self {
+            name,
+            status: Status::Fail,
+            detail: detail.into(),
+            fix: Some(fix.into()),
+        }
+    }
+
+    fn skip(name: &'static str, detail: impl Into<String>) -> Self {
+        Self {
+            name,
+            status: Status::Skip,
+            detail: detail.into(),
+            fix: None,
+        }
+    }
+
+    fn print(&self) {
+        println!(
+            "  [{:<4}] {:<12} {}",
+            self.status.label(),
+            self.name,
+            self.detail
+        );
+        if let Some(ref fix) = self.fix {
+            println!("         {:<12} fix: {}", "", fix);
+        }
+    }
+}
+
+/// Run every diagnostic check and print the results. Fails if any check
+/// failed, so the command can gate scripts.
+pub async fn run_doctor_command() -> anyhow::Result<()> {
+    println!("IronClaw Doctor");
+    println!("===============\n");
+
+    let checks = collect_checks().await;
+    for check in &checks {
+        check.print();
+    }
+
+    let failed = checks.iter().filter(|c| c.status == Status::Fail).count();
+    let warned = checks.iter().filter(|c| c.status == Status::Warn).count();
+    println!();
+    if failed > 0 {
+        anyhow::bail!("{} check(s) failed, {} warning(s)", failed, warned);
+    }
+    if warned > 0 {
+        println!("No failures, {} warning(s).", warned);
+    } else {
+        println!("All checks passed.");
+    }
+    Ok(())
+}
+
+async fn collect_checks() -> Vec<Check> {
+    let mut checks = Vec::new();
+
+    let config = match Config::from_env().await {
+        Ok(config) => {
+            checks.push(Check::ok("config", "loaded"));
+            config
+        }
+        Err(e) => {
+            checks.push(Check::fail(
+                "config",
+                e.to_string(),
+                "fix the value in ~/.ironclaw/.env or the environment, or run `ironclaw onboard`",
+            ));
+            // Nothing else can be checked without a configuration.
+            return checks;
+        }
+    };
+
+    let session = create_session_manager(SessionConfig {
+        auth_base_url: config.llm.nearai.auth_base_url.clone(),
+        session_path: config.llm.nearai.session_path.clone(),
+    })
+    .await;
+
+    // Opened without migrating so a stale schema is reported, not fixed.
+    let db = match crate::db::open_from_config(&config.database).await {
+        Ok(db) => match db.ping().await {
+            Ok(()) => {
+                checks.push(Check::ok("database", backend_name(&config)));
+                Some(db)
+            }
+            Err(e) => {
+                checks.push(Check::fail(
+                    "database",
+                    e.to_string(),
+                    database_fix(&config),
+                ));
+                None
+            }
+        },
+        Err(e) => {
+            checks.push(Check::fail(
+                "database",
+                e.to_string(),
+                database_fix(&config),
+            ));
+            None
+        }
+    };
+
+    let embeddings = embedding_provider(&config, Arc::clone(&session));
+    let diagnostics = match db {
+        Some(ref db) => match db.diagnostics().await {
+            Ok(d) => Some(d),
+            Err(e) => {
+                checks.push(Check::fail(
+                    "schema",
+                    format!("could not inspect database: {}", e),
+                    "run `ironclaw run` once to apply migrations",
+                ));
+                None
+            }
+        },
+        None => None,
+    };
+
+    match diagnostics {
+        Some(ref d) => {
+            checks.push(check_schema(d.schema_version));
+            checks.push(check_dimension(
+                d.embedding_dimension,
+                embeddings
+                    .as_deref()
+                    .map(|e| (e.model_name(), e.dimension())),
+            ));
+            checks.push(check_missing_embeddings(d, embeddings.is_some()));
+            checks.push(check_orphans(d));
+        }
+        None => {
+            for name in ["schema", "dimension", "embeddings", "orphans"] {
+                checks.push(Check::skip(name, "database unavailable"));
+            }
+        }
+    }
+
+    checks.push(match db {
+        Some(ref db) if diagnostics.is_some() => {
+            match Workspace::new_with_db("default", Arc::clone(db))
+                .lint()
+                .await
+            {
+                Ok(found) => check_lint(&found),
+                Err(e) => Check::warn(
+                    "workspace",
+                    format!("could not lint documents: {}", e),
+                    "run `ironclaw memory lint` to retry",
+                ),
+            }
+        }
+        _ => Check::skip("workspace", "database unavailable"),
+    });
+
+    checks.push(match embeddings {
+        Some(ref emb) => probe_embeddings(emb.as_ref()).await,
+        None if config.embeddings.enabled => Check::fail(
+            "embed-api",
+            "embeddings enabled but no credentials",
+            "set OPENAI_API_KEY, or EMBEDDING_PROVIDER=nearai",
+        ),
+        None => Check::skip("embed-api", "embeddings disabled"),
+    });
+
+    checks.push(probe_llm(&config, session).await);
+    checks.extend(check_channels(&config).await);
+
+    checks
+}
+
+fn backend_name(config: &Config) -> String {
+    match config.database.backend {
+        DatabaseBackend::Postgres => "connected (PostgreSQL)".to_string(),
+        DatabaseBackend::LibSql => "connected (libSQL)".to_string(),
+    }
+}
+
+fn database_fix(config: &Config) -> &'static str {
+    match config.database.backend {
+        DatabaseBackend::Postgres => {
+            "check DATABASE_URL and that PostgreSQL is running with the pgvector extension"
+        }
+        DatabaseBackend::LibSql => "check LIBSQL_PATH (and LIBSQL_URL/LIBSQL_AUTH_TOKEN for Turso)",
+    }
+}
+
+/// Build the configured embedding provider, if embeddings are enabled and
+/// have credentials.
+fn embedding_provider(
+    config: &Config,
+    session: Arc<crate::llm::SessionManager>,
+) -> Option<Arc<dyn EmbeddingProvider>> {
+    if !config.embeddings.enabled {
+        return None;
+    }
+    match config.embeddings.provider.as_str() {
+        "nearai" => Some(Arc::new(
+            NearAiEmbeddings::new(&config.llm.nearai.base_url, session)
+                .with_model(&config.embeddings.model, 1536),
+        )),
+        _ => {
+            let api_key = config.embeddings.openai_api_key()?;
+            let dim = match config.embeddings.model.as_str() {
+                "text-embedding-3-large" => 3072,
+                _ => 1536,
+            };
+            Some(Arc::new(OpenAiEmbeddings::with_model(
+                api_key,
+                &config.embeddings.model,
+                dim,
+            )))
+        }
+    }
+}
+
+fn check_schema(version: Option<i64>) -> Check {
+    match version {
+        None => Check::fail(
+            "schema",
+            "no migrations applied",
+            "run `ironclaw run` once; migrations are applied on startup",
+        ),
+        Some(v) if v < SCHEMA_VERSION => Check::fail(
+            "schema",
+            format!("version {} (expected {})", v, SCHEMA_VERSION),
+            "run `ironclaw run` once to apply pending migrations",
+        ),
+        Some(v) if v > SCHEMA_VERSION => Check::warn(
+            "schema",
+            format!(
+                "version {} is newer than this binary ({})",
+                v, SCHEMA_VERSION
+            ),
+            "upgrade ironclaw to match the database",
+        ),
+        Some(v) => Check::ok("schema", format!("version {}", v)),
+    }
+}
+
+/// Compare the stored vector column width against the configured model.
+fn check_dimension(column: Option<usize>, provider: Option<(&str, usize)>) -> Check {
+    match (column, provider) {
+        (_, None) => Check::skip("dimension", "embeddings disabled"),
+        (None, Some(_)) => Check::warn(
+            "dimension",
+            "could not determine the embedding column size",
+            "run `ironclaw run` once to create the memory tables",
+        ),
+        (Some(col), Some((model, dim))) if col != dim => Check::fail(
+            "dimension",
+            format!(
+                "{} produces {}-d vectors but the index stores {}-d",
+                model, dim, col
+            ),
+            format!(
+                "set EMBEDDING_MODEL to a {}-dimension model, or recreate memory_chunks.embedding as {}-d and re-embed",
+                col, dim
+            ),
+        ),
+        (Some(col), Some((model, _))) => Check::ok("dimension", format!("{}-d ({})", col, model)),
+    }
+}
+
+fn check_missing_embeddings(d: &DbDiagnostics, embeddings_enabled: bool) -> Check {
+    if d.chunks_missing_embeddings == 0 {
+        return Check::ok(
+            "embeddings",
+            format!("all {} chunks embedded", d.chunk_count),
+        );
+    }
+    let detail = format!(
+        "{} of {} chunks have no embedding; vector search can't find them",
+        d.chunks_missing_embeddings, d.chunk_count
+    );
+    if embeddings_enabled {
+        Check::warn(
+            "embeddings",
+            detail,
+            "start the agent (`ironclaw run`); missing embeddings are backfilled on startup",
+        )
+    } else {
+        Check::warn(
+            "embeddings",
+            detail,
+            "set EMBEDDING_ENABLED=true and start the agent to backfill them",
+        )
+    }
+}
+
+fn check_orphans(d: &DbDiagnostics) -> Check {
+    if d.orphaned_chunks == 0 {
+        return Check::ok("orphans", "no orphaned chunks");
+    }
+    Check::warn(
+        "orphans",
+        format!("{} chunks reference deleted documents", d.orphaned_chunks),
+        "DELETE FROM memory_chunks WHERE document_id NOT IN (SELECT id FROM memory_documents)",
+    )
+}
+
+/// Summarize workspace lint findings. Content problems never fail the
+/// doctor: the agent keeps working, just not as the files intend.
+fn check_lint(found: &[LintDiagnostic]) -> Check {
+    let Some(first) = found.first() else {
+        return Check::ok("workspace", "no content problems");
+    };
+    let errors = found
+        .iter()
+        .filter(|d| d.severity == LintSeverity::Error)
+        .count();
+    Check::warn(
+        "workspace",
+        format!(
+            "{} error(s), {} warning(s), e.g. {}",
+            errors,
+            found.len() - errors,
+            first
+        ),
+        "run `ironclaw memory lint` for the full list",
+    )
+}
+
+async fn probe_embeddings(emb: &dyn EmbeddingProvider) -> Check {
+    match tokio::time::timeout(PROBE_TIMEOUT, emb.embed("ironclaw doctor")).await {
+        Ok(Ok(v)) if v.len() == emb.dimension() => {
+            Check::ok("embed-api", format!("{} responded", emb.model_name()))
+        }
+        Ok(Ok(v)) => Check::fail(
+            "embed-api",
+            format!(
+                "{} returned {}-d vectors, expected {}",
+                emb.model_name(),
+                v.len(),
+                emb.dimension()
+            ),
+            "check EMBEDDING_MODEL",
+        ),
+        Ok(Err(e)) => Check::fail(
+            "embed-api",
+            e.to_string(),
+            "check OPENAI_API_KEY (or the NEAR AI session) and EMBEDDING_MODEL",
+        ),
+        Err(_) => Check::fail(
+            "embed-api",
+            format!("timed out after {}s", PROBE_TIMEOUT.as_secs()),
+            "check network access to the embedding provider",
+        ),
+    }
+}
+
+async fn probe_llm(config: &Config, session: Arc<crate::llm::SessionManager>) -> Check {
+    let llm = match create_llm_provider(&config.llm, session) {
+        Ok(llm) => llm,
+        Err(e) => {
+            return Check::fail("llm", e.to_string(), llm_fix(config));
+        }
+    };
+    match tokio::time::timeout(PROBE_TIMEOUT, llm.list_models()).await {
+        Ok(Ok(_)) => Check::ok(
+            "llm",
+            format!(
+                "{} accepted credentials ({})",
+                config.llm.backend,
+                llm.model_name()
+            ),
+        ),
+        Ok(Err(e)) => Check::fail("llm", e.to_string(), llm_fix(config)),
+        Err(_) => Check::fail(
+            "llm",
+            format!("timed out after {}s", PROBE_TIMEOUT.as_secs()),
+            "check network access to the LLM provider",
+        ),
+    }
+}
+
+fn llm_fix(config: &Config) -> &'static str {
+    match config.llm.backend {
+        LlmBackend::NearAi => "run `ironclaw onboard` to log in, or set NEARAI_API_KEY",
+        LlmBackend::OpenAi => "check OPENAI_API_KEY",
+        LlmBackend::Anthropic => "check ANTHROPIC_API_KEY",
+        LlmBackend::Ollama => "check that Ollama is running at OLLAMA_BASE_URL",
+        LlmBackend::OpenAiCompatible => "check LLM_BASE_URL and LLM_API_KEY",
+        LlmBackend::Groq => "check GROQ_API_KEY",
+        LlmBackend::Together => "check TOGETHER_API_KEY",
+    }
+}
+
+async fn check_channels(config: &Config) -> Vec<Check> {
+    let mut checks = Vec::new();
+    if let Some(ref gw) = config.channels.gateway {
+        checks.push(check_listener("gateway", &gw.host, gw.port, "GATEWAY_PORT").await);
+    }
+    if let Some(ref http) = config.channels.http {
+        checks.push(check_listener("http", &http.host, http.port, "HTTP_PORT").await);
+    }
+
+    if config.channels.wasm_channels_enabled {
+        let dir = &config.channels.wasm_channels_dir;
+        let installed = std::fs::read_dir(dir)
+            .map(|entries| {
+                entries
+                    .filter_map(|e| e.ok())
+                    .filter(|e| e.path().extension().is_some_and(|ext| ext == "wasm"))
+                    .count()
+            })
+            .unwrap_or(0);
+        checks.push(if installed > 0 {
+            Check::ok(
+                "wasm",
+                format!("{} channel(s) in {}", installed, dir.display()),
+            )
+        } else {
+            Check::skip("wasm", format!("no channels in {}", dir.display()))
+        });
+    }
+
+    if checks.is_empty() {
+        checks.push(Check::skip("channels", "only the CLI channel is enabled"));
+    }
+    checks
+}
+
+/// A configured listener is healthy if its address can be bound now, or if
+/// something (presumably a running agent) is already answering on it.
+async fn check_listener(name: &'static str, host: &str, port: u16, env_var: &str) -> Check {
+    let addr = format!("{}:{}", host, port);
+    match tokio::net::TcpListener::bind(&addr).await {
+        Ok(_) => Check::ok(name, format!("{} is available", addr)),
+        Err(e) if e.kind() == std::io::ErrorKind::AddrInUse => {
+            let connect_host = if host == "0.0.0.0" { "127.0.0.1" } else { host };
+            let answering = tokio::time::timeout(
+                Duration::from_secs(2),
+                tokio::net::TcpStream::connect((connect_host, port)),
+            )
+            .await
+            .is_ok_and(|r| r.is_ok());
+            Check::warn(
+                name,
+                format!(
+                    "{} is in use{}",
+                    addr,
+                    if answering {
+                        " (an agent may already be running)"
+                    } else {
+                        ""
+                    }
+                ),
+                format!("stop the other process or set {} to a free port", env_var),
+            )
+        }
+        Err(e) => Check::fail(
+            name,
+            format!("cannot bind {}: {}", addr, e),
+            format!("set {} to a port this user may bind", env_var),
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_schema() {
+        assert_eq!(check_schema(None).status, Status::Fail);
+        assert_eq!(check_schema(Some(SCHEMA_VERSION - 1)).status, Status::Fail);
+        assert_eq!(check_schema(Some(SCHEMA_VERSION)).status, Status::Ok);
+        assert_eq!(check_schema(Some(SCHEMA_VERSION + 1)).status, Status::Warn);
+    }
+
+    #[test]
+    fn test_check_dimension() {
+        let large = Some(("text-embedding-3-large", 3072));
+        assert_eq!(check_dimension(Some(1536), large).status, Status::Fail);
+        assert_eq!(check_dimension(Some(3072), large).status, Status::Ok);
+        assert_eq!(check_dimension(Some(1536), None).status, Status::Skip);
+        assert_eq!(check_dimension(None, large).status, Status::Warn);
+    }
+
+    #[test]
+    fn test_index_checks() {
+        let clean = DbDiagnostics {
+            chunk_count: 10,
+            ..Default::default()
+        };
+        assert_eq!(check_missing_embeddings(&clean, true).status, Status::Ok);
+        assert_eq!(check_orphans(&clean).status, Status::Ok);
+
+        let gaps = DbDiagnostics {
+            chunk_count: 10,
+            chunks_missing_embeddings: 3,
+            orphaned_chunks: 2,
+            ..Default::default()
+        };
+        let missing = check_missing_embeddings(&gaps, false);
+        assert_eq!(missing.status, Status::Warn);
+        assert!(missing.fix.unwrap().contains("EMBEDDING_ENABLED"));
+        assert_eq!(check_orphans(&gaps).status, Status::Warn);
+    }
+
+    #[test]
+    fn test_check_lint() {
+        assert_eq!(check_lint(&[]).status, Status::Ok);
+
+        let found = crate::workspace::lint::lint_document(
+            "notes.md",
+            "---\ntitle: x\n[[nowhere]]",
+            &[],
+            2_000,
+        );
+        let check = check_lint(&found);
+        assert_eq!(check.status, Status::Warn);
+        assert!(check.detail.starts_with("1 error(s), 1 warning(s)"));
+    }
+
+    #[tokio::test]
+    async fn test_check_listener_detects_port_in_use() {
+        let held = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = held.local_addr().unwrap().port();
+        let check = check_listener("gateway", "127.0.0.1", port, "GATEWAY_PORT").await;
+        assert_eq!(check.status, Status::Warn);
+        assert!(check.detail.contains("already be running"));
+
+        drop(held);
+        let check = check_listener("gateway", "127.0.0.1", port, "GATEWAY_PORT").await;
+        assert_eq!(check.status, Status::Ok);
+    }
+}
@@ -0,0 +1,234 @@
+//! Retrieval evaluation CLI command.
+//!
+//! Runs the workspace's eval set (`eval/retrieval.md`) through search with
+//! the given knobs, prints recall@k and MRR, and lists the cases that got
+//! worse since the saved baseline. Knobs mirror `ironclaw search`.
+
+use std::sync::Arc;
+
+use clap::Args;
+
+use crate::db::Database;
+use crate::llm::LlmProvider;
+use crate::workspace::eval::{
+    BASELINE_PATH, CASES_PATH, CaseChange, Comparison, EvalReport, RelevanceGrader, RetrievalEval,
+    load_baseline, load_cases, save_baseline,
+};
+use crate::workspace::{EmbeddingProvider, SearchConfig, Workspace};
+
+#[derive(Args, Debug, Clone)]
+pub struct EvalArgs {
+    /// Cutoff for recall@k, and results graded per query
+    #[arg(short, default_value = "5")]
+    pub k: usize,
+
+    /// Results fetched per query (ranks beyond this count as misses)
+    #[arg(short, long, default_value = "20")]
+    pub limit: usize,
+
+    /// RRF constant (default: 60)
+    #[arg(long)]
+    pub rrf_k: Option<u32>,
+
+    /// Candidates fetched from each method before fusion (default: 50)
+    #[arg(long)]
+    pub pre_fusion_limit: Option<usize>,
+
+    /// Full-text search only
+    #[arg(long, conflicts_with = "vector_only")]
+    pub fts_only: bool,
+
+    /// Vector search only
+    #[arg(long)]
+    pub vector_only: bool,
+
+    /// Have the configured LLM grade cases without expected documents
+    #[arg(long)]
+    pub grade: bool,
+
+    /// Save this run as the baseline for later comparisons
+    #[arg(long)]
+    pub save: bool,
+
+    /// Exit with an error if any case regressed from the baseline
+    #[arg(long)]
+    pub check: bool,
+
+    /// Workspace user to evaluate
+    #[arg(short, long, default_value = "default")]
+    pub user: String,
+
+    /// Print the report as JSON
+    #[arg(long)]
+    pub json: bool,
+}
+
+impl EvalArgs {
+    fn config(&self) -> SearchConfig {
+        let mut config = SearchConfig::default().with_limit(self.limit.clamp(1, 100));
+        if let Some(k) = self.rrf_k {
+            config = config.with_rrf_k(k);
+        }
+        if let Some(n) = self.pre_fusion_limit {
+            config.pre_fusion_limit = n;
+        }
+        if self.fts_only {
+            config = config.fts_only();
+        }
+        if self.vector_only {
+            config = config.vector_only();
+        }
+        config
+    }
+}
+
+/// Run the retrieval eval set and compare against the baseline.
+pub async fn run_eval_command(
+    args: EvalArgs,
+    db: Arc<dyn Database>,
+    embeddings: Option<Arc<dyn EmbeddingProvider>>,
+    llm: Option<Arc<dyn LlmProvider>>,
+) -> anyhow::Result<()> {
+    let config = args.config();
+    if config.use_vector && embeddings.is_none() {
+        if !config.use_fts {
+            anyhow::bail!("Vector search needs an embedding provider (EMBEDDING_ENABLED=true)");
+        }
+        eprintln!("Note: no embedding provider configured; evaluating full-text search only.\n");
+    }
+
+    let mut workspace = Workspace::new_with_db(&args.user, db);
+    if let Some(emb) = embeddings {
+        workspace = workspace.with_embeddings(emb);
+    }
+
+    let cases = load_cases(&workspace).await?;
+    let mut eval = RetrievalEval::new(&workspace, config).with_k(args.k);
+    if let Some(llm) = llm {
+        eval = eval.with_grader(RelevanceGrader::new(llm));
+    }
+    let report = eval.run(&cases).await?;
+    let baseline = load_baseline(&workspace).await?;
+    let comparison = baseline.as_ref().map(|b| report.compare(b));
+
+    if args.json {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&serde_json::json!({
+                "report": report,
+                "comparison": comparison,
+            }))?
+        );
+    } else {
+        print_report(&report);
+        match (&baseline, &comparison) {
+            (Some(baseline), Some(comparison)) => print_comparison(baseline, comparison, report.k),
+            _ => println!("\nNo baseline yet; rerun with --save to record one."),
+        }
+    }
+
+    if args.save {
+        save_baseline(&workspace, &report).await?;
+        if !args.json {
+            println!("\nSaved as baseline ({}).", BASELINE_PATH);
+        }
+    }
+    if args.check
+        && let Some(comparison) = &comparison
+        && !comparison.regressions.is_empty()
+    {
+        anyhow::bail!(
+            "{} case(s) regressed from the baseline",
+            comparison.regressions.len()
+        );
+    }
+    Ok(())
+}
+
+fn print_report(report: &EvalReport) {
+    println!(
+        "{} case(s) from {} (k={})\n",
+        report.cases.len(),
+        CASES_PATH,
+        report.k
+    );
+    for case in &report.cases {
+        let marker = if case.recall > 0.0 { "+" } else { "-" };
+        let graded = if case.is_graded() { " (graded)" } else { "" };
+        println!(
+            "{} {:>5}  recall {:.2}  {}{}",
+            marker,
+            rank_label(case.first_relevant),
+            case.recall,
+            case.query,
+            graded
+        );
+        if case.recall < 1.0 && !case.is_graded() {
+            let missing: Vec<&str> = case
+                .expected
+                .iter()
+                .filter(|p| !case.relevant.contains(p))
+                .map(String::as_str)
+                .collect();
+            if !missing.is_empty() {
+                println!("           not found: {}", missing.join(", "));
+            }
+        }
+    }
+    println!(
+        "\nrecall@{}: {:.3}   MRR: {:.3}",
+        report.k, report.recall_at_k, report.mrr
+    );
+    if !report.cost.is_zero() {
+        println!("grading cost: ${}", report.cost.round_dp(4));
+    }
+}
+
+fn print_comparison(baseline: &EvalReport, comparison: &Comparison, current_k: usize) {
+    println!(
+        "\nvs baseline from {}: recall@k {:+.3}, MRR {:+.3}",
+        baseline.run_at.format("%Y-%m-%d %H:%M"),
+        comparison.recall_delta,
+        comparison.mrr_delta
+    );
+    if baseline.k != current_k {
+        println!("Note: the baseline used k={}", baseline.k);
+    }
+    if comparison.new_cases > 0 {
+        println!("{} case(s) not in the baseline", comparison.new_cases);
+    }
+    print_changes("Regressions", &comparison.regressions);
+    print_changes("Improvements", &comparison.improvements);
+}
+
+fn print_changes(title: &str, changes: &[CaseChange]) {
+    if changes.is_empty() {
+        return;
+    }
+    println!("\n{}:", title);
+    for change in changes {
+        println!(
+            "  {} -> {}  recall {:.2} -> {:.2}  {}",
+            rank_label(change.rank_before),
+            rank_label(change.rank_after),
+            change.recall_before,
+            change.recall_after,
+            change.query
+        );
+    }
+}
+
+fn rank_label(rank: Option<usize>) -> String {
+    rank.map_or_else(|| "miss".to_string(), |r| format!("#{}", r))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rank_label() {
+        assert_eq!(rank_label(Some(3)), "#3");
+        assert_eq!(rank_label(None), "miss");
+    }
+}
@@ -6,22 +6,38 @@ use std::io::Read;
 use std::sync::Arc;
 
 use clap::Subcommand;
+use futures::TryStreamExt;
 
-use crate::workspace::{EmbeddingProvider, SearchConfig, Workspace};
+use crate::workspace::frontmatter::stored_frontmatter;
+use crate::workspace::graph::{GraphFact, KnowledgeGraph};
+use crate::workspace::{
+    DecayPolicy, EmbeddingProvider, FieldFilter, ImportReport, LintSeverity, NotionImport,
+    ObsidianImport, PinTarget, ReindexOutcome, SearchConfig, SparseEncoder, StaleReason, Workspace,
+};
 
 /// Run a memory command using the Database trait (works with any backend).
 pub async fn run_memory_command_with_db(
     cmd: MemoryCommand,
     db: std::sync::Arc<dyn crate::db::Database>,
     embeddings: Option<Arc<dyn EmbeddingProvider>>,
+    sparse: Option<Arc<dyn SparseEncoder>>,
 ) -> anyhow::Result<()> {
-    let mut workspace = Workspace::new_with_db("default", db);
+    let mut workspace = Workspace::new_with_db("default", Arc::clone(&db));
     if let Some(emb) = embeddings {
         workspace = workspace.with_embeddings(emb);
     }
+    if let Some(encoder) = sparse {
+        workspace = workspace.with_sparse_encoder(encoder);
+    }
 
     match cmd {
-        MemoryCommand::Search { query, limit } => search(&workspace, &query, limit).await,
+        MemoryCommand::Search {
+            query,
+            limit,
+            filters,
+        } => search(&workspace, &query, limit, &filters).await,
+        MemoryCommand::Find { filters } => find(&workspace, &filters).await,
+        MemoryCommand::Similar { path, limit } => similar(&workspace, &path, limit).await,
         MemoryCommand::Read { path } => read(&workspace, &path).await,
         MemoryCommand::Write {
             path,
@@ -30,6 +46,45 @@ pub async fn run_memory_command_with_db(
         } => write(&workspace, &path, content, append).await,
         MemoryCommand::Tree { path, depth } => tree(&workspace, &path, depth).await,
         MemoryCommand::Status => status(&workspace).await,
+        MemoryCommand::Usage => usage(&workspace).await,
+        MemoryCommand::Lint => lint(&workspace).await,
+        MemoryCommand::Fsck { dry_run } => fsck(&workspace, dry_run).await,
+        MemoryCommand::Backfill { max_chunks } => backfill(&workspace, max_chunks).await,
+        MemoryCommand::Reindex { concurrency, force } => {
+            reindex(&workspace, concurrency, force).await
+        }
+        MemoryCommand::ImportObsidian {
+            vault,
+            into,
+            attachments,
+        } => import_obsidian(&workspace, &vault, into, attachments).await,
+        MemoryCommand::ImportNotion {
+            export,
+            into,
+            attachments,
+        } => import_notion(&workspace, &export, into, attachments).await,
+        MemoryCommand::Export { output } => export_archive(&workspace, &output).await,
+        MemoryCommand::Import { archive } => import_archive(&workspace, &archive).await,
+        MemoryCommand::Archive {
+            idle_days,
+            max_entries,
+            dry_run,
+        } => archive(&workspace, idle_days, max_entries, dry_run).await,
+        MemoryCommand::Pin {
+            fact,
+            path,
+            section,
+        } => pin(&workspace, fact, path, section).await,
+        MemoryCommand::Unpin { id } => unpin(&workspace, id).await,
+        MemoryCommand::Pins => list_pins(&workspace).await,
+        MemoryCommand::Graph {
+            entity,
+            connect_to,
+            max_hops,
+        } => {
+            let graph = KnowledgeGraph::new(db, "default");
+            graph_query(&graph, &entity, connect_to.as_deref(), max_hops).await
+        }
     }
 }
 
@@ -43,6 +98,27 @@ pub enum MemoryCommand {
         /// Maximum number of results
         #[arg(short, long, default_value = "5")]
         limit: usize,
+
+        /// Only search documents whose frontmatter matches (repeatable)
+        #[arg(long = "where", value_name = "FIELD=VALUE")]
+        filters: Vec<String>,
+    },
+
+    /// List documents by frontmatter fields (e.g. `status=active tags=work`)
+    Find {
+        /// FIELD=VALUE to match, or FIELD to require the field
+        #[arg(required = true, value_name = "FIELD[=VALUE]")]
+        filters: Vec<String>,
+    },
+
+    /// List documents related to one, by embedding similarity
+    Similar {
+        /// Document path
+        path: String,
+
+        /// Maximum number of documents
+        #[arg(short, long, default_value = "5")]
+        limit: usize,
     },
 
     /// Read a file from the workspace
@@ -77,6 +153,136 @@ pub enum MemoryCommand {
 
     /// Show workspace status (document count, index health)
     Status,
+
+    /// Show bytes, documents, and chunks stored per directory
+    Usage,
+
+    /// Check documents for broken frontmatter, links, and profile fields
+    Lint,
+
+    /// Check index and path consistency (e.g. after a crash or migration)
+    /// and repair what is wrong
+    Fsck {
+        /// Only report problems
+        #[arg(long)]
+        dry_run: bool,
+    },
+
+    /// Embed chunks that have no embedding yet (e.g. after enabling a provider)
+    Backfill {
+        /// Stop after this many chunks (rerun to continue)
+        #[arg(long)]
+        max_chunks: Option<usize>,
+    },
+
+    /// Re-chunk and re-embed every document (e.g. after changing the chunker
+    /// or embedding model). Safe to interrupt: rerunning resumes.
+    Reindex {
+        /// Documents to index in parallel
+        #[arg(short, long, default_value = "4")]
+        concurrency: usize,
+
+        /// Reindex documents already indexed with the current setup
+        #[arg(long)]
+        force: bool,
+    },
+
+    /// Import an Obsidian vault, keeping its folders and converting its links
+    ImportObsidian {
+        /// Path to the vault
+        vault: std::path::PathBuf,
+
+        /// Workspace directory to import into (default: the vault's name)
+        #[arg(long)]
+        into: Option<String>,
+
+        /// Directory to copy images and other binary attachments to
+        /// (skipped if not given)
+        #[arg(long)]
+        attachments: Option<std::path::PathBuf>,
+    },
+
+    /// Import a Notion export (or any folder or ZIP of Markdown files),
+    /// turning database rows into documents with frontmatter
+    ImportNotion {
+        /// Path to the export directory or .zip file
+        export: std::path::PathBuf,
+
+        /// Workspace directory to import into (default: the export's name)
+        #[arg(long)]
+        into: Option<String>,
+
+        /// Directory to copy images and other binary attachments to
+        /// (skipped if not given)
+        #[arg(long)]
+        attachments: Option<std::path::PathBuf>,
+    },
+
+    /// Back up the workspace to a .tar.gz archive
+    Export {
+        /// Archive file to write
+        output: std::path::PathBuf,
+    },
+
+    /// Restore documents from an archive written by `memory export`,
+    /// re-indexing them (also moves a workspace between backends)
+    Import {
+        /// Archive file to read
+        archive: std::path::PathBuf,
+    },
+
+    /// Move stale MEMORY.md entries to memory/archive/ (still searchable)
+    Archive {
+        /// Archive entries not written or retrieved for this many days (0 = never)
+        #[arg(long, default_value = "180")]
+        idle_days: u64,
+
+        /// Keep at most this many entries, archiving the least recently used (0 = no limit)
+        #[arg(long, default_value = "0")]
+        max_entries: usize,
+
+        /// Only list the entries that would be archived
+        #[arg(long)]
+        dry_run: bool,
+    },
+
+    /// Pin a fact, document, or section into every system prompt
+    Pin {
+        /// Fact to pin (omit when pinning a document with --path)
+        #[arg(conflicts_with = "path")]
+        fact: Option<String>,
+
+        /// Workspace document to pin
+        #[arg(long)]
+        path: Option<String>,
+
+        /// Pin only this section of --path
+        #[arg(long, requires = "path")]
+        section: Option<String>,
+    },
+
+    /// Remove a pin
+    Unpin {
+        /// Pin ID (from `memory pins`)
+        id: uuid::Uuid,
+    },
+
+    /// List pinned memories
+    Pins,
+
+    /// Query the knowledge graph extracted from workspace documents
+    Graph {
+        /// Entity to look up
+        entity: String,
+
+        /// Show the facts connecting ENTITY to this entity instead
+        #[arg(long)]
+        connect_to: Option<String>,
+
+        /// Relations to follow (default: 3 when connecting, 1 otherwise)
+        #[arg(long)]
+        max_hops: Option<usize>,
+    },
 }
 
 /// Run a memory command (PostgreSQL backend).
@@ -92,7 +298,13 @@ pub async fn run_memory_command(
     }
 
     match cmd {
-        MemoryCommand::Search { query, limit } => search(&workspace, &query, limit).await,
+        MemoryCommand::Search {
+            query,
+            limit,
+            filters,
+        } => search(&workspace, &query, limit, &filters).await,
+        MemoryCommand::Find { filters } => find(&workspace, &filters).await,
+        MemoryCommand::Similar { path, limit } => similar(&workspace, &path, limit).await,
         MemoryCommand::Read { path } => read(&workspace, &path).await,
         MemoryCommand::Write {
             path,
@@ -101,11 +313,131 @@ pub async fn run_memory_command(
         } => write(&workspace, &path, content, append).await,
         MemoryCommand::Tree { path, depth } => tree(&workspace, &path, depth).await,
         MemoryCommand::Status => status(&workspace).await,
+        MemoryCommand::Usage => usage(&workspace).await,
+        MemoryCommand::Lint => lint(&workspace).await,
+        MemoryCommand::Fsck { dry_run } => fsck(&workspace, dry_run).await,
+        MemoryCommand::Backfill { max_chunks } => backfill(&workspace, max_chunks).await,
+        MemoryCommand::Reindex { concurrency, force } => {
+            reindex(&workspace, concurrency, force).await
+        }
+        MemoryCommand::ImportObsidian {
+            vault,
+            into,
+            attachments,
+        } => import_obsidian(&workspace, &vault, into, attachments).await,
+        MemoryCommand::ImportNotion {
+            export,
+            into,
+            attachments,
+        } => import_notion(&workspace, &export, into, attachments).await,
+        MemoryCommand::Export { output } => export_archive(&workspace, &output).await,
+        MemoryCommand::Import { archive } => import_archive(&workspace, &archive).await,
+        MemoryCommand::Archive {
+            idle_days,
+            max_entries,
+            dry_run,
+        } => archive(&workspace, idle_days, max_entries, dry_run).await,
+        MemoryCommand::Pin {
+            fact,
+            path,
+            section,
+        } => pin(&workspace, fact, path, section).await,
+        MemoryCommand::Unpin { id } => unpin(&workspace, id).await,
+        MemoryCommand::Pins => list_pins(&workspace).await,
+        MemoryCommand::Graph { .. } => {
+            anyhow::bail!("Graph queries need the Database backend")
+        }
+    }
+}
+
+fn parse_filters(filters: &[String]) -> anyhow::Result<Vec<FieldFilter>> {
+    filters
+        .iter()
+        .map(|f| FieldFilter::parse(f).ok_or_else(|| anyhow::anyhow!("Invalid filter: {:?}", f)))
+        .collect()
+}
+
+async fn similar(workspace: &Workspace, path: &str, limit: usize) -> anyhow::Result<()> {
+    let similar = workspace.similar(path, limit.min(50)).await?;
+    if similar.is_empty() {
+        println!(
+            "No similar documents for {} (it may not be embedded yet; try `memory backfill`)",
+            path
+        );
+        return Ok(());
+    }
+
+    println!("Documents similar to {}:\n", path);
+    for doc in &similar {
+        println!(
+            "  [{}] {:.3}  {}",
+            score_indicator(doc.similarity),
+            doc.similarity,
+            doc.path
+        );
+    }
+    Ok(())
+}
+
+async fn lint(workspace: &Workspace) -> anyhow::Result<()> {
+    let diagnostics = workspace.lint().await?;
+    if diagnostics.is_empty() {
+        println!("No problems found.");
+        return Ok(());
+    }
+
+    for diagnostic in &diagnostics {
+        println!("{}", diagnostic);
     }
+    let errors = diagnostics
+        .iter()
+        .filter(|d| d.severity == LintSeverity::Error)
+        .count();
+    println!();
+    if errors > 0 {
+        anyhow::bail!(
+            "{} error(s), {} warning(s)",
+            errors,
+            diagnostics.len() - errors
+        );
+    }
+    println!("{} warning(s).", diagnostics.len());
+    Ok(())
 }
 
-async fn search(workspace: &Workspace, query: &str, limit: usize) -> anyhow::Result<()> {
-    let config = SearchConfig::default().with_limit(limit.min(50));
+async fn fsck(workspace: &Workspace, dry_run: bool) -> anyhow::Result<()> {
+    let report = workspace.fsck(dry_run).await?;
+    for issue in &report.issues {
+        println!("{}: {}", issue.path, issue.message);
+    }
+    if report.is_clean() {
+        println!("Checked {} documents, no problems found.", report.documents);
+    } else if dry_run {
+        println!();
+        println!(
+            "Checked {} documents, {} problem(s). Run without --dry-run to repair.",
+            report.documents,
+            report.issues.len()
+        );
+    } else {
+        println!();
+        println!(
+            "Checked {} documents, repaired {} problem(s).",
+            report.documents,
+            report.repaired()
+        );
+    }
+    Ok(())
+}
+
+async fn search(
+    workspace: &Workspace,
+    query: &str,
+    limit: usize,
+    filters: &[String],
+) -> anyhow::Result<()> {
+    let mut config = SearchConfig::default().with_limit(limit.min(50));
+    config.filters = parse_filters(filters)?;
     let results = workspace.search_with_config(query, config).await?;
 
     if results.is_empty() {
@@ -130,6 +462,28 @@ async fn search(workspace: &Workspace, query: &str, limit: usize) -> anyhow::Res
     Ok(())
 }
 
+async fn find(workspace: &Workspace, filters: &[String]) -> anyhow::Result<()> {
+    let docs = workspace
+        .find_by_frontmatter(&parse_filters(filters)?)
+        .await?;
+    if docs.is_empty() {
+        println!("No documents match.");
+        return Ok(());
+    }
+
+    for doc in &docs {
+        let fields = stored_frontmatter(doc);
+        let title = fields.get("title").and_then(|v| v.as_str()).unwrap_or("");
+        if title.is_empty() {
+            println!("{}", doc.path);
+        } else {
+            println!("{}  ({})", doc.path, title);
+        }
+    }
+    println!("\n{} document(s)", docs.len());
+    Ok(())
+}
+
 async fn read(workspace: &Workspace, path: &str) -> anyhow::Result<()> {
     match workspace.read(path).await {
         Ok(doc) => {
@@ -170,6 +524,130 @@ async fn write(
     Ok(())
 }
 
+async fn archive(
+    workspace: &Workspace,
+    idle_days: u64,
+    max_entries: usize,
+    dry_run: bool,
+) -> anyhow::Result<()> {
+    let policy = DecayPolicy {
+        max_idle: (idle_days > 0).then(|| chrono::Duration::days(idle_days as i64)),
+        max_entries: (max_entries > 0).then_some(max_entries),
+    };
+
+    let stale = if dry_run {
+        workspace.stale_memory(&policy).await?
+    } else {
+        workspace.archive_stale_memory(&policy).await?.archived
+    };
+    if stale.is_empty() {
+        println!("No stale entries in MEMORY.md.");
+        return Ok(());
+    }
+
+    for entry in &stale {
+        let reason = match entry.reason {
+            StaleReason::Idle => "idle",
+            StaleReason::OverLimit => "over limit",
+        };
+        println!(
+            "- [{}, last used {}] {}",
+            reason,
+            entry.last_used.format("%Y-%m-%d"),
+            truncate_content(&entry.content, 80).replace('\n', " ")
+        );
+    }
+    println!();
+    if dry_run {
+        println!("{} entries would be archived.", stale.len());
+    } else {
+        println!("✓ Archived {} entries to memory/archive/", stale.len());
+    }
+    Ok(())
+}
+
+async fn pin(
+    workspace: &Workspace,
+    fact: Option<String>,
+    path: Option<String>,
+    section: Option<String>,
+) -> anyhow::Result<()> {
+    let target = match (fact, path, section) {
+        (Some(text), _, _) => PinTarget::Fact { text },
+        (None, Some(path), Some(heading)) => PinTarget::Section { path, heading },
+        (None, Some(path), None) => PinTarget::Document { path },
+        (None, None, _) => anyhow::bail!("Give a fact to pin, or --path"),
+    };
+    let pin = workspace.pin(target).await?;
+    println!("✓ Pinned ({})", pin.id);
+    Ok(())
+}
+
+async fn unpin(workspace: &Workspace, id: uuid::Uuid) -> anyhow::Result<()> {
+    if workspace.unpin(id).await? {
+        println!("✓ Unpinned {}", id);
+    } else {
+        anyhow::bail!("No pin with ID {}", id);
+    }
+    Ok(())
+}
+
+async fn list_pins(workspace: &Workspace) -> anyhow::Result<()> {
+    let pins = workspace.pins().await?;
+    if pins.is_empty() {
+        println!("Nothing pinned.");
+        return Ok(());
+    }
+    for pin in &pins {
+        let what = match &pin.target {
+            PinTarget::Fact { text } => format!("fact: {}", truncate_content(text, 80)),
+            PinTarget::Document { path } => format!("document: {}", path),
+            PinTarget::Section { path, heading } => format!("section: {} › {}", path, heading),
+        };
+        println!("{}  {}", pin.id, what);
+    }
+    Ok(())
+}
+
+async fn graph_query(
+    graph: &KnowledgeGraph,
+    entity: &str,
+    connect_to: Option<&str>,
+    max_hops: Option<usize>,
+) -> anyhow::Result<()> {
+    let print_fact = |fact: &GraphFact| match &fact.document {
+        Some(path) => println!("   {}  ({})", fact, path),
+        None => println!("   {}", fact),
+    };
+
+    match connect_to {
+        Some(other) => {
+            let paths = graph.connect(entity, other, max_hops.unwrap_or(3)).await?;
+            if paths.is_empty() {
+                println!(
+                    "No connection found between \"{}\" and \"{}\".",
+                    entity, other
+                );
+                return Ok(());
+            }
+            for (i, path) in paths.iter().enumerate() {
+                println!("{}. {} hop(s)", i + 1, path.facts.len());
+                path.facts.iter().for_each(print_fact);
+                println!();
+            }
+        }
+        None => {
+            let facts = graph.neighborhood(entity, max_hops.unwrap_or(1)).await?;
+            if facts.is_empty() {
+                println!("Nothing known about \"{}\".", entity);
+                return Ok(());
+            }
+            facts.iter().for_each(print_fact);
+        }
+    }
+    Ok(())
+}
+
 async fn tree(workspace: &Workspace, path: &str, max_depth: usize) -> anyhow::Result<()> {
     let root = if path.is_empty() { "." } else { path };
     println!("{}/", root);
@@ -215,12 +693,13 @@ async fn print_tree(
 }
 
 async fn status(workspace: &Workspace) -> anyhow::Result<()> {
-    let all_paths = workspace.list_all().await?;
-    let file_count = all_paths.len();
-
-    // Count directories by collecting unique parent paths
+    // Count files and directories (unique parent paths) without holding
+    // every path at once
+    let mut file_count = 0;
     let mut dirs: std::collections::HashSet<String> = std::collections::HashSet::new();
-    for path in &all_paths {
+    let mut paths = std::pin::pin!(workspace.paths());
+    while let Some(path) = paths.try_next().await? {
+        file_count += 1;
         if let Some(parent) = path.rsplit_once('/') {
             dirs.insert(parent.0.to_string());
         }
@@ -250,6 +729,142 @@ async fn status(workspace: &Workspace) -> anyhow::Result<()> {
     Ok(())
 }
 
+async fn usage(workspace: &Workspace) -> anyhow::Result<()> {
+    let usage = workspace.usage().await?;
+    println!("{:>12}  {:>6}  {:>7}  DIRECTORY", "BYTES", "DOCS", "CHUNKS");
+    for dir in &usage.directories {
+        println!(
+            "{:>12}  {:>6}  {:>7}  {}/",
+            dir.bytes, dir.documents, dir.chunks, dir.path
+        );
+    }
+    println!(
+        "{:>12}  {:>6}  {:>7}  (total)",
+        usage.bytes, usage.documents, usage.chunks
+    );
+    Ok(())
+}
+
+async fn backfill(workspace: &Workspace, max_chunks: Option<usize>) -> anyhow::Result<()> {
+    if !workspace.has_embeddings() {
+        anyhow::bail!("No embedding provider configured");
+    }
+    let progress = workspace
+        .backfill_embeddings_with(max_chunks, |p| {
+            println!("  embedded {} chunk(s), {} failed", p.embedded, p.failed);
+        })
+        .await?;
+    println!(
+        "\nEmbedded {} chunk(s), {} failed.",
+        progress.embedded, progress.failed
+    );
+    Ok(())
+}
+
+async fn reindex(workspace: &Workspace, concurrency: usize, force: bool) -> anyhow::Result<()> {
+    let report = workspace
+        .reindex_all_with(concurrency, force, |p| {
+            let marker = match p.outcome {
+                ReindexOutcome::Reindexed => "+",
+                ReindexOutcome::Skipped => "=",
+                ReindexOutcome::Failed => "!",
+            };
+            println!("[{}/{}] {} {}", p.completed, p.total, marker, p.path);
+        })
+        .await?;
+
+    println!(
+        "\nReindexed {} document(s), {} already up to date, {} failed.",
+        report.reindexed, report.skipped, report.failed
+    );
+    if report.failed > 0 {
+        anyhow::bail!(
+            "{} document(s) failed to reindex; rerun to retry",
+            report.failed
+        );
+    }
+    Ok(())
+}
+
+async fn import_obsidian(
+    workspace: &Workspace,
+    vault: &std::path::Path,
+    into: Option<String>,
+    attachments: Option<std::path::PathBuf>,
+) -> anyhow::Result<()> {
+    let options = ObsidianImport {
+        prefix: import_prefix(vault, into)?,
+        attachments_dir: attachments,
+    };
+    let report = workspace.import_obsidian(vault, &options).await?;
+    print_import_report(&report, &options.prefix);
+    Ok(())
+}
+
+async fn import_notion(
+    workspace: &Workspace,
+    export: &std::path::Path,
+    into: Option<String>,
+    attachments: Option<std::path::PathBuf>,
+) -> anyhow::Result<()> {
+    let options = NotionImport {
+        prefix: import_prefix(export, into)?,
+        attachments_dir: attachments,
+    };
+    let report = workspace.import_notion(export, &options).await?;
+    print_import_report(&report, &options.prefix);
+    Ok(())
+}
+
+async fn export_archive(workspace: &Workspace, output: &std::path::Path) -> anyhow::Result<()> {
+    let file = std::io::BufWriter::new(std::fs::File::create(output)?);
+    let count = workspace.export_archive(file).await?;
+    println!("Exported {} documents to {}", count, output.display());
+    Ok(())
+}
+
+async fn import_archive(workspace: &Workspace, archive: &std::path::Path) -> anyhow::Result<()> {
+    let file = std::io::BufReader::new(std::fs::File::open(archive)?);
+    let count = workspace.import_archive(file).await?;
+    println!("Imported {} documents from {}", count, archive.display());
+    Ok(())
+}
+
+/// Workspace directory to import into: `into`, or the name of `source`.
+fn import_prefix(source: &std::path::Path, into: Option<String>) -> anyhow::Result<String> {
+    if let Some(prefix) = into {
+        return Ok(prefix);
+    }
+    Ok(source
+        .canonicalize()?
+        .file_stem()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_default())
+}
+
+fn print_import_report(report: &ImportReport, prefix: &str) {
+    println!(
+        "Imported {} note(s) and {} attachment(s) into {}/",
+        report.notes, report.attachments, prefix
+    );
+    if report.databases > 0 {
+        println!("Imported {} database(s).", report.databases);
+    }
+    println!(
+        "Converted {} link(s); {} pointed outside the export.",
+        report.links_converted, report.unresolved_links
+    );
+    if !report.skipped.is_empty() {
+        println!(
+            "Skipped {} binary attachment(s); pass --attachments DIR to copy them:",
+            report.skipped.len()
+        );
+        for path in &report.skipped {
+            println!("  {}", path);
+        }
+    }
+}
+
 fn truncate_content(s: &str, max_len: usize) -> String {
     if s.len() <= max_len {
         s.to_string()
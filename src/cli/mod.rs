@@ -2,29 +2,54 @@
 //!
 //! Provides subcommands for:
 //! - Running the agent (`run`)
+//! - Chatting with the agent in the terminal only (`repl`)
+//! - Managing registered agents (`agents create`, `agents list`, `agents configure`, `agents fork`)
 //! - Interactive onboarding wizard (`onboard`)
 //! - Managing configuration (`config list`, `config get`, `config set`)
 //! - Managing WASM tools (`tool install`, `tool list`, `tool remove`)
+//! - Managing skill packages (`skill install`, `skill update`, `skill remove`, `skill list`)
 //! - Managing MCP servers (`mcp add`, `mcp auth`, `mcp list`, `mcp test`)
 //! - Querying workspace memory (`memory search`, `memory read`, `memory write`)
+//! - Inspecting search ranking (`search`)
+//! - Evaluating retrieval quality against an eval set (`eval`)
 //! - Checking system health (`status`)
+//! - Diagnosing configuration and data problems (`doctor`)
+//! - Replaying recorded job transcripts (`replay`)
+//! - Running scenario regression tests (`scenario run`, `scenario record`)
+//! - Listing, rendering, and running job templates (`template list`, `template run`)
 
+mod agents;
 mod config;
+mod doctor;
+mod eval;
 mod mcp;
 pub mod memory;
 pub mod oauth_defaults;
 mod pairing;
+mod replay;
+mod scenario;
+mod search;
+mod skill;
 pub mod status;
+mod template;
 mod tool;
 
+pub use agents::{AgentsCommand, run_agents_command};
 pub use config::{ConfigCommand, run_config_command};
+pub use doctor::run_doctor_command;
+pub use eval::{EvalArgs, run_eval_command};
 pub use mcp::{McpCommand, run_mcp_command};
 pub use memory::MemoryCommand;
 #[cfg(feature = "postgres")]
 pub use memory::run_memory_command;
 pub use memory::run_memory_command_with_db;
 pub use pairing::{PairingCommand, run_pairing_command, run_pairing_command_with_store};
+pub use replay::{ReplayArgs, run_replay_command};
+pub use scenario::{ScenarioCommand, record_scenario, run_scenarios};
+pub use search::{SearchArgs, run_search_command};
+pub use skill::{SkillCommand, run_skill_command};
 pub use status::run_status_command;
+pub use template::{TemplateCommand, run_template_command};
 pub use tool::{ToolCommand, run_tool_command};
 
 use clap::{Parser, Subcommand};
@@ -58,6 +83,10 @@ pub struct Cli {
     /// Skip first-run onboarding check
     #[arg(long, global = true)]
     pub no_onboard: bool,
+
+    /// Start a registered agent (name or ID) instead of the main agent
+    #[arg(long, global = true)]
+    pub agent: Option<String>,
 }
 
 #[derive(Subcommand, Debug)]
@@ -65,6 +94,9 @@ pub enum Command {
     /// Run the agent (default if no subcommand given)
     Run,
 
+    /// Chat with the agent in this terminal, with no other channels started
+    Repl,
+
     /// Interactive onboarding wizard
     Onboard {
         /// Skip authentication (use existing session)
@@ -76,6 +108,10 @@ pub enum Command {
         channels_only: bool,
     },
 
+    /// Manage registered agents
+    #[command(subcommand)]
+    Agents(AgentsCommand),
+
     /// Manage configuration settings
     #[command(subcommand)]
     Config(ConfigCommand),
@@ -84,6 +120,10 @@ pub enum Command {
     #[command(subcommand)]
     Tool(ToolCommand),
 
+    /// Install, update, and remove skills
+    #[command(subcommand)]
+    Skill(SkillCommand),
+
     /// Manage MCP servers (hosted tool providers)
     #[command(subcommand)]
     Mcp(McpCommand),
@@ -92,6 +132,12 @@ pub enum Command {
     #[command(subcommand)]
     Memory(MemoryCommand),
 
+    /// Search workspace memory and show how each result was scored
+    Search(SearchArgs),
+
+    /// Score search against the workspace's eval set (recall@k, MRR)
+    Eval(EvalArgs),
+
     /// DM pairing (approve inbound requests from unknown senders)
     #[command(subcommand)]
     Pairing(PairingCommand),
@@ -99,6 +145,20 @@ pub enum Command {
     /// Show system health and diagnostics
     Status,
 
+    /// Check configuration, database, and providers, and suggest fixes
+    Doctor,
+
+    /// Re-run a recorded job transcript against the configured model
+    Replay(ReplayArgs),
+
+    /// Run scripted conversations against the model and check what it does
+    #[command(subcommand)]
+    Scenario(ScenarioCommand),
+
+    /// List, render, and run job templates
+    #[command(subcommand)]
+    Template(TemplateCommand),
+
     /// Run as a sandboxed worker inside a Docker container (internal use).
     /// This is invoked automatically by the orchestrator, not by users directly.
     Worker {
@@ -0,0 +1,126 @@
+//! Job transcript replay CLI command.
+//!
+//! Re-runs a job's recorded LLM transcript against the configured (or
+//! overridden) model without executing any tools, and reports where the new
+//! run departs from the recording.
+
+use std::sync::Arc;
+
+use clap::Args;
+use uuid::Uuid;
+
+use crate::agent::transcript::{DEFAULT_MAX_REPLAY_STEPS, ReplayOptions, ReplayReport, replay};
+use crate::db::Database;
+use crate::llm::LlmProvider;
+
+#[derive(Args, Debug, Clone)]
+pub struct ReplayArgs {
+    /// Job whose transcript to replay
+    pub job_id: Uuid,
+
+    /// Model to replay against (defaults to the configured model)
+    #[arg(long)]
+    pub model: Option<String>,
+
+    /// Answer every call to a tool with a fixed output (repeatable)
+    #[arg(long = "mock-tool", value_name = "NAME=OUTPUT", value_parser = parse_tool_mock)]
+    pub mock_tools: Vec<(String, String)>,
+
+    /// Maximum LLM calls before giving up
+    #[arg(long, default_value_t = DEFAULT_MAX_REPLAY_STEPS)]
+    pub max_steps: usize,
+
+    /// Print the full report as JSON
+    #[arg(long)]
+    pub json: bool,
+}
+
+fn parse_tool_mock(s: &str) -> Result<(String, String), String> {
+    s.split_once('=')
+        .filter(|(name, _)| !name.is_empty())
+        .map(|(name, output)| (name.to_string(), output.to_string()))
+        .ok_or_else(|| format!("expected NAME=OUTPUT, got '{}'", s))
+}
+
+/// Replay a recorded job transcript.
+pub async fn run_replay_command(
+    args: ReplayArgs,
+    db: Arc<dyn Database>,
+    llm: Arc<dyn LlmProvider>,
+) -> anyhow::Result<()> {
+    let transcript = db.get_job_transcript(args.job_id).await?;
+    if transcript.is_empty() {
+        anyhow::bail!("No transcript recorded for job {}", args.job_id);
+    }
+
+    let options = ReplayOptions {
+        max_steps: args.max_steps,
+        tool_mocks: args.mock_tools.into_iter().collect(),
+    };
+    let report = replay(&transcript, llm.as_ref(), &options).await?;
+
+    if args.json {
+        println!("{}", serde_json::to_string_pretty(&report)?);
+    } else {
+        print_report(&transcript[0].model, &report);
+    }
+    Ok(())
+}
+
+fn print_report(recorded_model: &str, report: &ReplayReport) {
+    println!("Recorded with: {}", recorded_model);
+    println!("Replayed with: {}", report.model);
+    println!();
+
+    for (i, step) in report.steps.iter().enumerate() {
+        let marker = if step.matches_recorded() { " " } else { "!" };
+        let calls: Vec<&str> = step
+            .response
+            .tool_calls
+            .iter()
+            .map(|c| c.name.as_str())
+            .collect();
+        if calls.is_empty() {
+            let text = step.response.content.as_deref().unwrap_or("");
+            println!(
+                "{} [{}] reply: {}",
+                marker,
+                i,
+                crate::agent::truncate_for_preview(text, 100)
+            );
+        } else {
+            println!("{} [{}] tools: {}", marker, i, calls.join(", "));
+        }
+    }
+
+    println!();
+    match report.diverged_at {
+        Some(step) => println!("Diverged from recording at step {}", step),
+        None => println!("Matched recording ({} steps)", report.steps.len()),
+    }
+    if !report.completed {
+        println!(
+            "Stopped after {} steps with tool calls pending",
+            report.steps.len()
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_tool_mock() {
+        assert_eq!(
+            parse_tool_mock("shell=ok=1"),
+            Ok(("shell".to_string(), "ok=1".to_string()))
+        );
+        assert_eq!(
+            parse_tool_mock("http="),
+            Ok(("http".to_string(), String::new()))
+        );
+        assert!(parse_tool_mock("=x").is_err());
+        assert!(parse_tool_mock("shell").is_err());
+    }
+}
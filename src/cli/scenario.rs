@@ -0,0 +1,197 @@
+//! Scenario regression test CLI commands.
+//!
+//! `scenario run` plays scenario files against the configured model and
+//! exits with an error if any assertion fails, so prompt and tool changes
+//! can be checked in CI. `scenario record` turns a recorded job transcript
+//! into a scenario file to start from.
+
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use clap::Subcommand;
+use uuid::Uuid;
+
+use crate::agent::scenario::{Scenario, ScenarioReport, ScenarioRunner};
+use crate::db::Database;
+use crate::llm::LlmProvider;
+use crate::tools::ToolRegistry;
+
+#[derive(Subcommand, Debug, Clone)]
+pub enum ScenarioCommand {
+    /// Run scenario files (or directories of them) against the model
+    Run {
+        /// Scenario files or directories of `.json` files
+        #[arg(required = true)]
+        paths: Vec<PathBuf>,
+
+        /// Model to run against (defaults to the configured model)
+        #[arg(long)]
+        model: Option<String>,
+
+        /// Print the reports as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Write a scenario from a job's recorded transcript
+    Record {
+        /// Job whose transcript to convert
+        job_id: Uuid,
+
+        /// Scenario name (default: the job ID)
+        #[arg(long)]
+        name: Option<String>,
+
+        /// File to write (default: stdout)
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+}
+
+/// Run scenarios, failing if any assertion fails.
+pub async fn run_scenarios(
+    paths: &[PathBuf],
+    llm: Arc<dyn LlmProvider>,
+    json: bool,
+) -> anyhow::Result<()> {
+    let mut scenarios = Vec::new();
+    for path in scenario_files(paths)? {
+        scenarios.push(Scenario::load(&path)?);
+    }
+    if scenarios.is_empty() {
+        anyhow::bail!("No scenario files found");
+    }
+
+    let runner = ScenarioRunner::new(llm, chat_tool_definitions().await);
+    let mut reports = Vec::with_capacity(scenarios.len());
+    for scenario in &scenarios {
+        let report = runner.run(scenario).await?;
+        if !json {
+            print_report(&report);
+        }
+        reports.push(report);
+    }
+
+    let failed = reports.iter().filter(|r| !r.passed()).count();
+    if json {
+        println!("{}", serde_json::to_string_pretty(&reports)?);
+    } else {
+        println!("\n{} passed, {} failed", reports.len() - failed, failed);
+    }
+    if failed > 0 {
+        anyhow::bail!("{} scenario(s) failed", failed);
+    }
+    Ok(())
+}
+
+/// Write a scenario built from a job's transcript.
+pub async fn record_scenario(
+    db: Arc<dyn Database>,
+    job_id: Uuid,
+    name: Option<String>,
+    output: Option<PathBuf>,
+) -> anyhow::Result<()> {
+    let transcript = db.get_job_transcript(job_id).await?;
+    if transcript.is_empty() {
+        anyhow::bail!("No transcript recorded for job {}", job_id);
+    }
+    let scenario =
+        Scenario::from_transcript(name.unwrap_or_else(|| job_id.to_string()), &transcript)?;
+    let json = serde_json::to_string_pretty(&scenario)?;
+    match output {
+        Some(path) => {
+            std::fs::write(&path, json + "\n")?;
+            println!(
+                "Wrote {} turn(s) and {} mock(s) to {}",
+                scenario.turns.len(),
+                scenario.mocks.len(),
+                path.display()
+            );
+        }
+        None => println!("{}", json),
+    }
+    Ok(())
+}
+
+/// Scenario files named by `paths`, expanding directories to their
+/// `.json` files in name order.
+fn scenario_files(paths: &[PathBuf]) -> anyhow::Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    for path in paths {
+        if path.is_dir() {
+            let mut entries: Vec<PathBuf> = std::fs::read_dir(path)?
+                .filter_map(|e| e.ok().map(|e| e.path()))
+                .filter(|p| is_json(p))
+                .collect();
+            entries.sort();
+            files.extend(entries);
+        } else {
+            files.push(path.clone());
+        }
+    }
+    Ok(files)
+}
+
+fn is_json(path: &Path) -> bool {
+    path.is_file() && path.extension().is_some_and(|ext| ext == "json")
+}
+
+/// Definitions of the tools chat offers by default. Memory tools are
+/// registered over an in-memory workspace; scenarios never execute them.
+async fn chat_tool_definitions() -> Vec<crate::llm::ToolDefinition> {
+    let registry = ToolRegistry::new();
+    registry.register_builtin_tools();
+    #[cfg(feature = "libsql")]
+    {
+        use crate::db::libsql_backend::LibSqlBackend;
+        use crate::workspace::Workspace;
+
+        if let Ok(backend) = LibSqlBackend::new_memory().await {
+            let db: Arc<dyn Database> = Arc::new(backend);
+            registry.register_memory_tools(Arc::new(Workspace::new_with_db("default", db)), None);
+        }
+    }
+    registry.tool_definitions().await
+}
+
+fn print_report(report: &ScenarioReport) {
+    let status = if report.passed() { "PASS" } else { "FAIL" };
+    println!("{}  {} ({})", status, report.name, report.model);
+    for (i, turn) in report.turns.iter().enumerate() {
+        let calls: Vec<&str> = turn
+            .outcome
+            .tool_calls
+            .iter()
+            .map(|c| c.name.as_str())
+            .collect();
+        println!(
+            "  {}. {}  [{}]",
+            i + 1,
+            crate::agent::truncate_for_preview(&turn.outcome.user, 60),
+            calls.join(", ")
+        );
+        for failure in &turn.failures {
+            println!("     - {}", failure);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scenario_files_expands_directories() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("b.json"), "{}").unwrap();
+        std::fs::write(dir.path().join("a.json"), "{}").unwrap();
+        std::fs::write(dir.path().join("notes.txt"), "").unwrap();
+        let single = PathBuf::from("single.json");
+
+        let files = scenario_files(&[dir.path().to_path_buf(), single.clone()]).unwrap();
+        assert_eq!(
+            files,
+            vec![dir.path().join("a.json"), dir.path().join("b.json"), single]
+        );
+    }
+}
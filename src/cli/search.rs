@@ -0,0 +1,390 @@
+//! Workspace search CLI command.
+//!
+//! Runs a hybrid search and shows how each result was scored: its rank and
+//! raw score from full-text search, its rank and cosine similarity from
+//! vector search, its rank and dot product from sparse search (with a
+//! sparse encoder configured), and the fused RRF score. Knobs mirror [`SearchConfig`] so
+//! the output can be used to tune it.
+//!
+//! With `--documents`, results are grouped per document and scored with
+//! `--aggregate` (`max` or `sum`), showing each document's best snippets.
+
+use std::collections::HashMap;
+use std::collections::hash_map::Entry;
+use std::sync::Arc;
+
+use clap::Args;
+use serde::Serialize;
+use uuid::Uuid;
+
+use crate::db::Database;
+use crate::workspace::{
+    ChunkAggregation, DocumentResult, EmbeddingProvider, SearchConfig, SearchResult, SparseEncoder,
+    Workspace,
+};
+
+/// Characters of context shown around the first query-term match.
+const SNIPPET_WIDTH: usize = 160;
+
+/// Snippets shown per document with `--documents`.
+const DOCUMENT_SNIPPETS: usize = 2;
+
+#[derive(Args, Debug, Clone)]
+pub struct SearchArgs {
+    /// Search query
+    pub query: String,
+
+    /// Maximum number of results
+    #[arg(short, long, default_value = "10")]
+    pub limit: usize,
+
+    /// RRF constant (default: 60). Higher values flatten the rank curve.
+    #[arg(long)]
+    pub rrf_k: Option<u32>,
+
+    /// Candidates fetched from each method before fusion (default: 50)
+    #[arg(long)]
+    pub pre_fusion_limit: Option<usize>,
+
+    /// Drop results whose normalized fused score is below this (0.0-1.0)
+    #[arg(long)]
+    pub min_score: Option<f32>,
+
+    /// Full-text search only
+    #[arg(long, conflicts_with = "vector_only")]
+    pub fts_only: bool,
+
+    /// Vector search only
+    #[arg(long)]
+    pub vector_only: bool,
+
+    /// One result per document instead of per chunk
+    #[arg(long)]
+    pub documents: bool,
+
+    /// How chunk scores combine with --documents: max or sum
+    #[arg(long, default_value = "max", requires = "documents")]
+    pub aggregate: String,
+
+    /// Workspace user to search
+    #[arg(short, long, default_value = "default")]
+    pub user: String,
+
+    /// Print results as JSON
+    #[arg(long)]
+    pub json: bool,
+}
+
+impl SearchArgs {
+    fn config(&self) -> SearchConfig {
+        let mut config = SearchConfig::default().with_limit(self.limit.min(100));
+        if let Some(k) = self.rrf_k {
+            config = config.with_rrf_k(k);
+        }
+        if let Some(n) = self.pre_fusion_limit {
+            config.pre_fusion_limit = n;
+        }
+        if let Some(min) = self.min_score {
+            config = config.with_min_score(min);
+        }
+        if self.fts_only {
+            config = config.fts_only();
+        }
+        if self.vector_only {
+            config = config.vector_only();
+        }
+        config
+    }
+}
+
+/// One result as printed with `--json`.
+#[derive(Debug, Serialize)]
+struct ScoredResult {
+    path: String,
+    document_id: Uuid,
+    chunk_id: Uuid,
+    score: f32,
+    fts_rank: Option<u32>,
+    fts_score: Option<f32>,
+    vector_rank: Option<u32>,
+    vector_score: Option<f32>,
+    sparse_rank: Option<u32>,
+    sparse_score: Option<f32>,
+    snippet: String,
+}
+
+/// One document as printed with `--documents --json`.
+#[derive(Debug, Serialize)]
+struct ScoredDocument {
+    path: String,
+    document_id: Uuid,
+    score: f32,
+    matching_chunks: usize,
+    snippets: Vec<String>,
+}
+
+/// Search workspace memory and print each result's score breakdown.
+pub async fn run_search_command(
+    args: SearchArgs,
+    db: Arc<dyn Database>,
+    embeddings: Option<Arc<dyn EmbeddingProvider>>,
+    sparse: Option<Arc<dyn SparseEncoder>>,
+) -> anyhow::Result<()> {
+    let config = args.config();
+    if config.use_vector && !config.use_fts && embeddings.is_none() {
+        anyhow::bail!(
+            "Vector search needs an embedding provider (EMBEDDING_ENABLED=true or EMBEDDING_FALLBACK=true)"
+        );
+    }
+
+    let mut workspace = Workspace::new_with_db(&args.user, Arc::clone(&db));
+    if let Some(emb) = embeddings {
+        workspace = workspace.with_embeddings(emb);
+    }
+    if let Some(encoder) = sparse {
+        workspace = workspace.with_sparse_encoder(encoder);
+    }
+    let modes = workspace.search_modes(&config);
+    if !args.json
+        && let Some(note) = modes.note()
+    {
+        eprintln!("Note: {}\n", note);
+    }
+    if args.documents {
+        let Some(aggregation) = ChunkAggregation::parse(&args.aggregate) else {
+            anyhow::bail!("Unknown --aggregate '{}': use max or sum", args.aggregate);
+        };
+        let documents = workspace
+            .search_documents(&args.query, config, aggregation)
+            .await?;
+        return print_documents(&args, db.as_ref(), documents).await;
+    }
+    let results = workspace
+        .search_with_config(&args.query, config.clone())
+        .await?;
+
+    let mut paths: HashMap<Uuid, String> = HashMap::new();
+    let mut scored = Vec::with_capacity(results.len());
+    for result in results {
+        if let Entry::Vacant(slot) = paths.entry(result.document_id) {
+            let path = match db.get_document_by_id(result.document_id).await {
+                Ok(doc) => doc.path,
+                Err(_) => result.document_id.to_string(),
+            };
+            slot.insert(path);
+        }
+        scored.push(to_scored(&result, &paths[&result.document_id], &args.query));
+    }
+
+    if args.json {
+        println!("{}", serde_json::to_string_pretty(&scored)?);
+        return Ok(());
+    }
+
+    if scored.is_empty() {
+        println!("No results found for: {}", args.query);
+        return Ok(());
+    }
+
+    println!(
+        "{} result(s) for \"{}\" (rrf_k={}, pre_fusion_limit={}, modes: {})\n",
+        scored.len(),
+        args.query,
+        config.rrf_k,
+        config.pre_fusion_limit,
+        modes.label(),
+    );
+
+    for (i, r) in scored.iter().enumerate() {
+        println!("{:>2}. {}  rrf {:.3}", i + 1, r.path, r.score);
+        println!(
+            "    fts:    {}",
+            method_breakdown(r.fts_rank, r.fts_score, "score")
+        );
+        println!(
+            "    vector: {}",
+            method_breakdown(r.vector_rank, r.vector_score, "similarity")
+        );
+        println!(
+            "    sparse: {}",
+            method_breakdown(r.sparse_rank, r.sparse_score, "score")
+        );
+        println!("    {}", r.snippet);
+        println!();
+    }
+
+    Ok(())
+}
+
+async fn print_documents(
+    args: &SearchArgs,
+    db: &dyn Database,
+    documents: Vec<DocumentResult>,
+) -> anyhow::Result<()> {
+    let mut scored = Vec::with_capacity(documents.len());
+    for doc in documents {
+        let path = match db.get_document_by_id(doc.document_id).await {
+            Ok(d) => d.path,
+            Err(_) => doc.document_id.to_string(),
+        };
+        scored.push(ScoredDocument {
+            path,
+            document_id: doc.document_id,
+            score: doc.score,
+            matching_chunks: doc.chunks.len(),
+            snippets: doc
+                .chunks
+                .iter()
+                .take(DOCUMENT_SNIPPETS)
+                .map(|c| snippet(&c.content, &args.query, SNIPPET_WIDTH))
+                .collect(),
+        });
+    }
+
+    if args.json {
+        println!("{}", serde_json::to_string_pretty(&scored)?);
+        return Ok(());
+    }
+    if scored.is_empty() {
+        println!("No documents found for: {}", args.query);
+        return Ok(());
+    }
+
+    println!(
+        "{} document(s) for \"{}\" (aggregate: {})\n",
+        scored.len(),
+        args.query,
+        args.aggregate,
+    );
+    for (i, d) in scored.iter().enumerate() {
+        println!(
+            "{:>2}. {}  score {:.3}  ({} matching chunk(s))",
+            i + 1,
+            d.path,
+            d.score,
+            d.matching_chunks
+        );
+        for s in &d.snippets {
+            println!("    {}", s);
+        }
+        println!();
+    }
+    Ok(())
+}
+
+fn to_scored(result: &SearchResult, path: &str, query: &str) -> ScoredResult {
+    ScoredResult {
+        path: path.to_string(),
+        document_id: result.document_id,
+        chunk_id: result.chunk_id,
+        score: result.score,
+        fts_rank: result.fts_rank,
+        fts_score: result.fts_score,
+        vector_rank: result.vector_rank,
+        vector_score: result.vector_score,
+        sparse_rank: result.sparse_rank,
+        sparse_score: result.sparse_score,
+        snippet: snippet(&result.content, query, SNIPPET_WIDTH),
+    }
+}
+
+fn method_breakdown(rank: Option<u32>, score: Option<f32>, label: &str) -> String {
+    match (rank, score) {
+        (Some(rank), Some(score)) => format!("#{:<3} {} {:.4}", rank, label, score),
+        (Some(rank), None) => format!("#{}", rank),
+        _ => "-".to_string(),
+    }
+}
+
+/// A single-line window of `content` around the first query term it
+/// contains, or its start when no term matches.
+fn snippet(content: &str, query: &str, width: usize) -> String {
+    let flat: String = content.split_whitespace().collect::<Vec<_>>().join(" ");
+    let lower = flat.to_lowercase();
+
+    // Byte offsets are only reused when lowercasing kept the length.
+    let hit = if lower.len() == flat.len() {
+        query
+            .split_whitespace()
+            .filter(|t| t.chars().count() > 1)
+            .filter_map(|t| lower.find(&t.to_lowercase()))
+            .min()
+    } else {
+        None
+    };
+
+    let chars: Vec<char> = flat.chars().collect();
+    let center = hit.map(|byte| flat[..byte].chars().count()).unwrap_or(0);
+    let start = center.saturating_sub(width / 3);
+    let end = (start + width).min(chars.len());
+    let start = end.saturating_sub(width).min(start);
+
+    let mut out = String::new();
+    if start > 0 {
+        out.push_str("...");
+    }
+    out.extend(&chars[start..end]);
+    if end < chars.len() {
+        out.push_str("...");
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_snippet_centers_on_match() {
+        let content = format!("{} needle {}", "a ".repeat(100), "b ".repeat(100));
+        let s = snippet(&content, "Needle", 40);
+        assert!(s.starts_with("..."));
+        assert!(s.ends_with("..."));
+        assert!(s.contains("needle"));
+    }
+
+    #[test]
+    fn test_snippet_without_match_uses_start() {
+        let s = snippet("first line\n\nsecond   line", "absent", 100);
+        assert_eq!(s, "first line second line");
+    }
+
+    #[test]
+    fn test_snippet_multibyte() {
+        let content = format!("{}ünïcödé match", "é".repeat(200));
+        let s = snippet(&content, "match", 20);
+        assert!(s.contains("match"));
+    }
+
+    #[test]
+    fn test_config_from_args() {
+        let args = SearchArgs {
+            query: "q".to_string(),
+            limit: 5,
+            rrf_k: Some(20),
+            pre_fusion_limit: None,
+            min_score: None,
+            fts_only: true,
+            vector_only: false,
+            documents: false,
+            aggregate: "max".to_string(),
+            user: "default".to_string(),
+            json: false,
+        };
+        let config = args.config();
+        assert_eq!(config.limit, 5);
+        assert_eq!(config.rrf_k, 20);
+        assert_eq!(config.pre_fusion_limit, 50);
+        assert!(config.use_fts);
+        assert!(!config.use_vector);
+    }
+
+    #[test]
+    fn test_method_breakdown() {
+        assert_eq!(
+            method_breakdown(Some(2), Some(0.5), "similarity"),
+            "#2   similarity 0.5000"
+        );
+        assert_eq!(method_breakdown(None, None, "score"), "-");
+    }
+}
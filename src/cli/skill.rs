@@ -0,0 +1,182 @@
+//! Skill management CLI commands.
+//!
+//! Commands for installing, updating, listing, and removing skill packages
+//! (see [`crate::skills::install`]).
+
+use clap::Subcommand;
+
+use crate::config::Config;
+use crate::skills::install::{self, InstallOptions, InstalledSkill, PackageSource};
+
+#[derive(Subcommand, Debug, Clone)]
+pub enum SkillCommand {
+    /// Install a skill from a git URL, a local directory, or the registry
+    Install {
+        /// Git URL, package directory, or registry name
+        source: String,
+
+        /// Git branch, tag, or commit to install
+        #[arg(long = "ref")]
+        reference: Option<String>,
+
+        /// Package directory inside the repository
+        #[arg(long)]
+        path: Option<String>,
+
+        /// Replace the skill if it is already installed
+        #[arg(short, long)]
+        force: bool,
+
+        /// Install even if the package is unsigned or its signer isn't trusted
+        #[arg(long)]
+        allow_unsigned: bool,
+    },
+
+    /// Reinstall skills from the source they were installed from
+    Update {
+        /// Skill to update
+        #[arg(required_unless_present = "all")]
+        name: Option<String>,
+
+        /// Update every installed package
+        #[arg(long, conflicts_with = "name")]
+        all: bool,
+
+        /// Install even if the package is unsigned or its signer isn't trusted
+        #[arg(long)]
+        allow_unsigned: bool,
+    },
+
+    /// Remove an installed skill and the WASM tool it brought
+    Remove {
+        /// Skill to remove
+        name: String,
+    },
+
+    /// List installed skills
+    List,
+}
+
+/// Run a skill command.
+pub async fn run_skill_command(cmd: SkillCommand) -> anyhow::Result<()> {
+    let config = Config::from_env().await?;
+    let mut options = InstallOptions {
+        skills_dir: config.skills.install_dir(),
+        tools_dir: config.wasm.tools_dir.clone(),
+        trusted_keys: config.skills.trusted_keys.clone(),
+        allow_unsigned: false,
+        force: false,
+    };
+
+    match cmd {
+        SkillCommand::Install {
+            source,
+            reference,
+            path,
+            force,
+            allow_unsigned,
+        } => {
+            let package = match PackageSource::parse(&source, reference, path) {
+                Some(package) => package,
+                None => {
+                    let url = config.skills.registry_url.as_deref().ok_or_else(|| {
+                        anyhow::anyhow!(
+                            "'{}' is not a git URL or directory, and SKILLS_REGISTRY_URL is not set",
+                            source
+                        )
+                    })?;
+                    install::resolve_registry(url, &source).await?
+                }
+            };
+            options.force = force;
+            options.allow_unsigned = allow_unsigned;
+            println!("Installing from {}...", package);
+            let installed = install::install(&package, &options)?;
+            print_installed("Installed", &installed);
+        }
+        SkillCommand::Update {
+            name,
+            all,
+            allow_unsigned,
+        } => {
+            options.allow_unsigned = allow_unsigned;
+            let names: Vec<String> = if all {
+                install::list_installed(&options.skills_dir)?
+                    .into_iter()
+                    .filter(|s| s.record.is_some())
+                    .map(|s| s.skill.name)
+                    .collect()
+            } else {
+                name.into_iter().collect()
+            };
+            if names.is_empty() {
+                println!("No installed packages to update");
+            }
+            for name in names {
+                let installed = install::update(&name, &options)?;
+                print_installed("Updated", &installed);
+            }
+        }
+        SkillCommand::Remove { name } => {
+            let removed = install::remove(&name, &options)?;
+            println!("Removed {}", removed.skill.name);
+            if let Some(tool) = removed.record.and_then(|r| r.wasm_tool) {
+                println!("  Removed WASM tool {}", tool);
+            }
+        }
+        SkillCommand::List => {
+            let skills = install::list_installed(&options.skills_dir)?;
+            if skills.is_empty() {
+                println!("No skills installed in {}", options.skills_dir.display());
+                return Ok(());
+            }
+            println!("Installed skills in {}:", options.skills_dir.display());
+            println!();
+            for installed in &skills {
+                println!(
+                    "  {}: {}",
+                    installed.skill.name, installed.skill.description
+                );
+                match &installed.record {
+                    Some(record) => println!(
+                        "    {} {}({})",
+                        record.source,
+                        record
+                            .version
+                            .as_ref()
+                            .map(|v| format!("v{} ", v))
+                            .unwrap_or_default(),
+                        if record.signed_by.is_some() {
+                            "signed"
+                        } else {
+                            "unsigned"
+                        }
+                    ),
+                    None => println!("    added by hand"),
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+fn print_installed(verb: &str, installed: &InstalledSkill) {
+    println!("{} {}", verb, installed.skill.name);
+    let Some(record) = &installed.record else {
+        return;
+    };
+    if let Some(version) = &record.version {
+        println!("  Version: {}", version);
+    }
+    if let Some(commit) = &record.commit {
+        println!("  Commit: {}", commit);
+    }
+    match &record.signed_by {
+        Some(key) => println!("  Signed by: {}", key),
+        None => println!("  Signature: not verified"),
+    }
+    if let Some(tool) = &record.wasm_tool {
+        println!("  WASM tool: {}", tool);
+    }
+    println!("Restart the agent to load it.");
+}
@@ -0,0 +1,185 @@
+//! Job template CLI commands.
+//!
+//! Lists, shows, and renders the job templates in the workspace
+//! (`templates/<name>.md`), and runs one as a job in this process.
+
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use clap::Subcommand;
+
+use crate::agent::Scheduler;
+use crate::agent::template::{JobTemplates, TemplateLauncher};
+use crate::config::Config;
+use crate::context::ContextManager;
+use crate::db::Database;
+use crate::llm::{ModelProviders, SessionConfig, create_llm_provider, create_session_manager};
+use crate::safety::SafetyLayer;
+use crate::tools::ToolRegistry;
+use crate::workspace::Workspace;
+
+#[derive(Subcommand, Debug, Clone)]
+pub enum TemplateCommand {
+    /// List job templates
+    List,
+
+    /// Show a template's settings, variables, and prompt
+    Show {
+        /// Template name
+        name: String,
+    },
+
+    /// Print a template's prompt with its variables filled in
+    Render {
+        /// Template name
+        name: String,
+
+        /// Variable value (repeatable)
+        #[arg(long = "var", value_name = "NAME=VALUE", value_parser = parse_var)]
+        vars: Vec<(String, String)>,
+    },
+
+    /// Run a template as a job in this process and wait for it to finish.
+    /// The job gets the built-in and memory tools, plus local tools when
+    /// ALLOW_LOCAL_TOOLS is set.
+    Run {
+        /// Template name
+        name: String,
+
+        /// Variable value (repeatable)
+        #[arg(long = "var", value_name = "NAME=VALUE", value_parser = parse_var)]
+        vars: Vec<(String, String)>,
+    },
+}
+
+fn parse_var(s: &str) -> Result<(String, String), String> {
+    s.split_once('=')
+        .filter(|(name, _)| !name.trim().is_empty())
+        .map(|(name, value)| (name.trim().to_string(), value.to_string()))
+        .ok_or_else(|| format!("expected NAME=VALUE, got '{}'", s))
+}
+
+/// Run a template command.
+pub async fn run_template_command(
+    cmd: TemplateCommand,
+    db: Arc<dyn Database>,
+    config: &Config,
+) -> anyhow::Result<()> {
+    let workspace = Arc::new(Workspace::new_with_db("default", Arc::clone(&db)));
+    let templates = JobTemplates::new(Arc::clone(&workspace));
+
+    match cmd {
+        TemplateCommand::List => {
+            let listed = templates.list().await?;
+            if listed.is_empty() {
+                println!("No job templates. Add one at templates/<name>.md in the workspace.");
+            }
+            for template in listed {
+                println!("{:<24} {}", template.name, template.description);
+            }
+        }
+        TemplateCommand::Show { name } => {
+            let template = templates.get(&name).await?;
+            println!("Name:        {}", template.name);
+            println!("Description: {}", template.description);
+            println!(
+                "Model:       {}",
+                template.model.as_deref().unwrap_or("(configured)")
+            );
+            println!(
+                "Tools:       {}",
+                if template.tools.is_empty() {
+                    "all".to_string()
+                } else {
+                    template.tools.join(", ")
+                }
+            );
+            if template.max_tokens > 0 {
+                println!("Budget:      {} tokens", template.max_tokens);
+            }
+            println!("Variables:");
+            for variable in template.variables() {
+                match template.defaults.get(&variable) {
+                    Some(default) => println!("  {} (default: {})", variable, default),
+                    None => println!("  {} (required)", variable),
+                }
+            }
+            println!("\n{}", template.prompt);
+        }
+        TemplateCommand::Render { name, vars } => {
+            let template = templates.get(&name).await?;
+            let values: BTreeMap<String, String> = vars.into_iter().collect();
+            println!("{}", template.render(&values)?);
+        }
+        TemplateCommand::Run { name, vars } => {
+            let values: BTreeMap<String, String> = vars.into_iter().collect();
+            run(templates, workspace, db, config, &name, &values).await?;
+        }
+    }
+    Ok(())
+}
+
+async fn run(
+    templates: JobTemplates,
+    workspace: Arc<Workspace>,
+    db: Arc<dyn Database>,
+    config: &Config,
+    name: &str,
+    values: &BTreeMap<String, String>,
+) -> anyhow::Result<()> {
+    // Fail on a bad name or missing variable before connecting to the LLM
+    templates.get(name).await?.render(values)?;
+
+    let session = create_session_manager(SessionConfig {
+        auth_base_url: config.llm.nearai.auth_base_url.clone(),
+        session_path: config.llm.nearai.session_path.clone(),
+    })
+    .await;
+    let llm = create_llm_provider(&config.llm, Arc::clone(&session))?;
+    let models = Arc::new(ModelProviders::new(config.llm.clone(), session));
+
+    let tools = Arc::new(ToolRegistry::new());
+    tools.register_builtin_tools();
+    tools.register_memory_tools(Arc::clone(&workspace), None);
+    if config.agent.allow_local_tools {
+        tools.register_dev_tools();
+    }
+
+    let context_manager = Arc::new(ContextManager::new(1));
+    let scheduler = Arc::new(
+        Scheduler::new(
+            config.agent.clone(),
+            Arc::clone(&context_manager),
+            llm,
+            Arc::new(SafetyLayer::new(&config.safety)),
+            tools,
+            Some(Arc::clone(&db)),
+        )
+        .with_models(models)
+        .with_workspace(workspace),
+    );
+    let launcher = TemplateLauncher::new(
+        templates,
+        Arc::clone(&context_manager),
+        Arc::clone(&scheduler),
+        Some(db),
+    );
+
+    let job_id = launcher.launch("default", name, values).await?;
+    println!("Started job {} from template '{}'", job_id, name);
+
+    while scheduler.is_running(job_id).await {
+        tokio::time::sleep(Duration::from_secs(1)).await;
+    }
+
+    let ctx = context_manager.get_context(job_id).await?;
+    println!("Job {} finished: {}", job_id, ctx.state);
+    if let Some(reason) = ctx.transitions.last().and_then(|t| t.reason.as_deref()) {
+        println!("  {}", reason);
+    }
+    if ctx.total_tokens_used > 0 {
+        println!("  Tokens used: {}", ctx.total_tokens_used);
+    }
+    Ok(())
+}
@@ -1,19 +1,27 @@
 //! Configuration for SiloClaw.
 //!
-//! Settings are loaded with priority: env var > database > default.
-//! `DATABASE_URL` lives in `~/.ironclaw/.env` (loaded via dotenvy early
-//! in startup). Everything else comes from env vars, the DB settings
+//! Settings are loaded with priority: env var > config file > database >
+//! default. `DATABASE_URL` lives in `~/.ironclaw/.env` (loaded via dotenvy
+//! early in startup). Everything else comes from env vars, the optional
+//! `~/.ironclaw/config.toml` (see [`crate::config_file`]), the DB settings
 //! table, or auto-detection.
 
 use std::collections::HashMap;
+use std::net::SocketAddr;
 use std::path::PathBuf;
 use std::sync::OnceLock;
 use std::time::Duration;
 
 use secrecy::{ExposeSecret, SecretString};
+use uuid::Uuid;
 
+use crate::agent::citations::CitationStyle;
+use crate::agent::registry::{AgentDefinition, BUILTIN_CHANNELS};
 use crate::error::ConfigError;
+use crate::safety::ModerationAction;
 use crate::settings::Settings;
+use crate::webhooks::WebhookEventKind;
+use crate::workspace::language::{Language, non_english_share};
 
 /// Thread-safe overlay for injected env vars (secrets loaded from DB).
 ///
@@ -22,6 +30,10 @@ use crate::settings::Settings;
 /// real env vars first, then falls back to this overlay.
 static INJECTED_VARS: OnceLock<HashMap<String, String>> = OnceLock::new();
 
+/// Env vars set by the config file, checked by `optional_env()` after real
+/// env vars and before injected secrets.
+static FILE_VARS: OnceLock<HashMap<String, String>> = OnceLock::new();
+
 /// Main configuration for the agent.
 #[derive(Debug, Clone)]
 pub struct Config {
@@ -32,11 +44,37 @@ pub struct Config {
     pub channels: ChannelsConfig,
     pub agent: AgentConfig,
     pub safety: SafetyConfig,
+    pub moderation: ModerationConfig,
     pub wasm: WasmConfig,
     pub secrets: SecretsConfig,
     pub builder: BuilderModeConfig,
     pub heartbeat: HeartbeatConfig,
     pub routines: RoutineConfig,
+    pub audit: AuditConfig,
+    pub webhooks: WebhookConfig,
+    pub supervisor: SupervisorConfig,
+    pub memory_decay: MemoryDecayConfig,
+    pub system_prompt: SystemPromptConfig,
+    pub workspace_seed: WorkspaceSeedConfig,
+    pub workspace_cache: WorkspaceCacheConfig,
+    pub workspace_index: WorkspaceIndexConfig,
+    pub workspace_paths: WorkspacePathConfig,
+    pub workspace_quota: WorkspaceQuotaConfig,
+    pub workspace_trash: WorkspaceTrashConfig,
+    pub workspace_mirror: WorkspaceMirrorConfig,
+    pub workspace_git: WorkspaceGitConfig,
+    pub skills: SkillsConfig,
+    pub daily_rollup: DailyRollupConfig,
+    pub document_summaries: DocumentSummaryConfig,
+    pub knowledge_graph: KnowledgeGraphConfig,
+    pub usage_reports: UsageReportConfig,
+    pub memory_topics: MemoryTopicsConfig,
+    pub memory_conflicts: MemoryConflictConfig,
+    pub leases: LeaseConfig,
+    pub telemetry: TelemetryConfig,
+    pub metrics: MetricsConfig,
+    pub github: GitHubConfig,
+    pub home_assistant: HomeAssistantConfig,
     pub sandbox: SandboxModeConfig,
     pub claude_code: ClaudeCodeConfig,
 }
@@ -52,6 +90,7 @@ impl Config {
     ) -> Result<Self, ConfigError> {
         let _ = dotenvy::dotenv();
         crate::bootstrap::load_ironclaw_env();
+        load_config_file()?;
 
         // Load all settings from DB into a Settings struct
         let db_settings = match store.get_all_settings(user_id).await {
@@ -76,12 +115,13 @@ impl Config {
     pub async fn from_env() -> Result<Self, ConfigError> {
         let _ = dotenvy::dotenv();
         crate::bootstrap::load_ironclaw_env();
+        load_config_file()?;
         let settings = Settings::load();
         Self::build(&settings).await
     }
 
-    /// Build config from settings (shared by from_env and from_db).
-    async fn build(settings: &Settings) -> Result<Self, ConfigError> {
+    /// Build config from settings (shared by from_env, from_db, and reload).
+    pub(crate) async fn build(settings: &Settings) -> Result<Self, ConfigError> {
         Ok(Self {
             database: DatabaseConfig::resolve()?,
             llm: LlmConfig::resolve(settings)?,
@@ -90,15 +130,81 @@ impl Config {
             channels: ChannelsConfig::resolve(settings)?,
             agent: AgentConfig::resolve(settings)?,
             safety: SafetyConfig::resolve()?,
+            moderation: ModerationConfig::resolve()?,
             wasm: WasmConfig::resolve()?,
             secrets: SecretsConfig::resolve().await?,
             builder: BuilderModeConfig::resolve()?,
             heartbeat: HeartbeatConfig::resolve(settings)?,
             routines: RoutineConfig::resolve()?,
+            audit: AuditConfig::resolve()?,
+            webhooks: WebhookConfig::resolve()?,
+            supervisor: SupervisorConfig::resolve()?,
+            memory_decay: MemoryDecayConfig::resolve()?,
+            system_prompt: SystemPromptConfig::resolve()?,
+            workspace_seed: WorkspaceSeedConfig::resolve(settings)?,
+            workspace_cache: WorkspaceCacheConfig::resolve()?,
+            workspace_index: WorkspaceIndexConfig::resolve()?,
+            workspace_paths: WorkspacePathConfig::resolve()?,
+            workspace_quota: WorkspaceQuotaConfig::resolve()?,
+            workspace_trash: WorkspaceTrashConfig::resolve()?,
+            workspace_mirror: WorkspaceMirrorConfig::resolve()?,
+            workspace_git: WorkspaceGitConfig::resolve()?,
+            skills: SkillsConfig::resolve()?,
+            daily_rollup: DailyRollupConfig::resolve()?,
+            document_summaries: DocumentSummaryConfig::resolve()?,
+            knowledge_graph: KnowledgeGraphConfig::resolve()?,
+            usage_reports: UsageReportConfig::resolve()?,
+            memory_topics: MemoryTopicsConfig::resolve()?,
+            memory_conflicts: MemoryConflictConfig::resolve()?,
+            leases: LeaseConfig::resolve()?,
+            telemetry: TelemetryConfig::resolve()?,
+            metrics: MetricsConfig::resolve()?,
+            github: GitHubConfig::resolve()?,
+            home_assistant: HomeAssistantConfig::resolve()?,
             sandbox: SandboxModeConfig::resolve()?,
             claude_code: ClaudeCodeConfig::resolve()?,
         })
     }
+
+    /// Limit the agent to a local terminal session: the CLI channel only,
+    /// with no HTTP/gateway listeners, WASM channels, or heartbeat.
+    pub fn restrict_to_repl(&mut self) {
+        self.channels.cli.enabled = true;
+        self.channels.http = None;
+        self.channels.gateway = None;
+        self.channels.wasm_channels_enabled = false;
+        self.heartbeat.enabled = false;
+    }
+
+    /// Apply a registered agent's overrides: its name, model, toolset, and
+    /// (when it lists any) the channels it serves.
+    pub fn apply_agent(&mut self, agent: &AgentDefinition) {
+        self.agent.name = agent.name.clone();
+        if let Some(ref model) = agent.model {
+            self.llm.set_model(model);
+        }
+        self.agent.toolset = agent.tools.clone();
+        self.agent.agent_id = Some(agent.id);
+
+        if agent.channels.is_empty() {
+            return;
+        }
+        self.channels.cli.enabled = agent.serves_channel("cli");
+        if !agent.serves_channel("http") {
+            self.channels.http = None;
+        }
+        if !agent.serves_channel("gateway") {
+            self.channels.gateway = None;
+        }
+        let wasm: Vec<String> = agent
+            .channels
+            .iter()
+            .filter(|c| !BUILTIN_CHANNELS.contains(&c.as_str()))
+            .cloned()
+            .collect();
+        self.channels.wasm_channels_enabled &= !wasm.is_empty();
+        self.channels.wasm_channel_filter = Some(wasm);
+    }
 }
 
 /// Tunnel configuration for exposing the agent to the internet.
@@ -258,6 +364,31 @@ pub fn default_libsql_path() -> PathBuf {
         .join("ironclaw.db")
 }
 
+/// Lease settings for running several instances against one database.
+#[derive(Debug, Clone)]
+pub struct LeaseConfig {
+    /// This instance's lease owner ID (`INSTANCE_ID`; defaults to the host
+    /// name plus a random suffix, so every process is distinct).
+    pub instance_id: String,
+    /// Slack added to each tick lease on top of its interval
+    /// (`LEASE_TTL_SECS`). A crashed instance's heartbeat and cron ticks
+    /// are taken over after about this long.
+    pub ttl: Duration,
+}
+
+impl LeaseConfig {
+    fn resolve() -> Result<Self, ConfigError> {
+        let instance_id = optional_env("INSTANCE_ID")?.unwrap_or_else(|| {
+            let host = std::env::var("HOSTNAME").unwrap_or_else(|_| "ironclaw".to_string());
+            format!("{}-{}", host, &Uuid::new_v4().simple().to_string()[..8])
+        });
+        Ok(Self {
+            instance_id,
+            ttl: Duration::from_secs(parse_optional_env("LEASE_TTL_SECS", 60)?.max(1)),
+        })
+    }
+}
+
 /// Which LLM backend to use.
 ///
 /// Defaults to `NearAi` to keep SiloClaw close to the NEAR ecosystem.
@@ -273,8 +404,12 @@ pub enum LlmBackend {
     Anthropic,
     /// Local Ollama instance
     Ollama,
-    /// Any OpenAI-compatible endpoint (e.g. vLLM, LiteLLM, Together)
+    /// Any OpenAI-compatible endpoint (e.g. vLLM, LiteLLM)
     OpenAiCompatible,
+    /// Groq (fast inference, OpenAI-compatible)
+    Groq,
+    /// Together AI (fast inference, OpenAI-compatible)
+    Together,
 }
 
 impl std::str::FromStr for LlmBackend {
@@ -287,8 +422,10 @@ impl std::str::FromStr for LlmBackend {
             "anthropic" | "claude" => Ok(Self::Anthropic),
             "ollama" => Ok(Self::Ollama),
             "openai_compatible" | "openai-compatible" | "compatible" => Ok(Self::OpenAiCompatible),
+            "groq" => Ok(Self::Groq),
+            "together" | "together_ai" | "togetherai" => Ok(Self::Together),
             _ => Err(format!(
-                "invalid LLM backend '{}', expected one of: nearai, openai, anthropic, ollama, openai_compatible",
+                "invalid LLM backend '{}', expected one of: nearai, openai, anthropic, ollama, openai_compatible, groq, together",
                 s
             )),
         }
@@ -303,6 +440,8 @@ impl std::fmt::Display for LlmBackend {
             Self::Anthropic => write!(f, "anthropic"),
             Self::Ollama => write!(f, "ollama"),
             Self::OpenAiCompatible => write!(f, "openai_compatible"),
+            Self::Groq => write!(f, "groq"),
+            Self::Together => write!(f, "together"),
         }
     }
 }
@@ -336,6 +475,20 @@ pub struct OpenAiCompatibleConfig {
     pub model: String,
 }
 
+/// Configuration for Groq.
+#[derive(Debug, Clone)]
+pub struct GroqConfig {
+    pub api_key: SecretString,
+    pub model: String,
+}
+
+/// Configuration for Together AI.
+#[derive(Debug, Clone)]
+pub struct TogetherConfig {
+    pub api_key: SecretString,
+    pub model: String,
+}
+
 /// LLM provider configuration.
 ///
 /// NEAR AI remains the default backend. Users can switch to other providers
@@ -354,6 +507,13 @@ pub struct LlmConfig {
     pub ollama: Option<OllamaConfig>,
     /// OpenAI-compatible config (populated when backend=openai_compatible)
     pub openai_compatible: Option<OpenAiCompatibleConfig>,
+    /// Groq config (populated when GROQ_API_KEY is set)
+    pub groq: Option<GroqConfig>,
+    /// Together AI config (populated when TOGETHER_API_KEY is set)
+    pub together: Option<TogetherConfig>,
+    /// Backend for latency-sensitive calls such as heartbeat triage, in
+    /// place of the cheap model (`FAST_LLM_BACKEND`: groq or together).
+    pub fast_backend: Option<LlmBackend>,
 }
 
 /// API mode for NEAR AI.
@@ -413,6 +573,59 @@ pub struct NearAiConfig {
 }
 
 impl LlmConfig {
+    /// Model used by the active backend.
+    pub fn model(&self) -> &str {
+        match self.backend {
+            LlmBackend::NearAi => &self.nearai.model,
+            LlmBackend::OpenAi => self.openai.as_ref().map_or("", |c| &c.model),
+            LlmBackend::Anthropic => self.anthropic.as_ref().map_or("", |c| &c.model),
+            LlmBackend::Ollama => self.ollama.as_ref().map_or("", |c| &c.model),
+            LlmBackend::OpenAiCompatible => {
+                self.openai_compatible.as_ref().map_or("", |c| &c.model)
+            }
+            LlmBackend::Groq => self.groq.as_ref().map_or("", |c| &c.model),
+            LlmBackend::Together => self.together.as_ref().map_or("", |c| &c.model),
+        }
+    }
+
+    /// Point the active backend at a different model.
+    pub fn set_model(&mut self, model: &str) {
+        let model = model.to_string();
+        match self.backend {
+            LlmBackend::NearAi => self.nearai.model = model,
+            LlmBackend::OpenAi => {
+                if let Some(ref mut c) = self.openai {
+                    c.model = model;
+                }
+            }
+            LlmBackend::Anthropic => {
+                if let Some(ref mut c) = self.anthropic {
+                    c.model = model;
+                }
+            }
+            LlmBackend::Ollama => {
+                if let Some(ref mut c) = self.ollama {
+                    c.model = model;
+                }
+            }
+            LlmBackend::OpenAiCompatible => {
+                if let Some(ref mut c) = self.openai_compatible {
+                    c.model = model;
+                }
+            }
+            LlmBackend::Groq => {
+                if let Some(ref mut c) = self.groq {
+                    c.model = model;
+                }
+            }
+            LlmBackend::Together => {
+                if let Some(ref mut c) = self.together {
+                    c.model = model;
+                }
+            }
+        }
+    }
+
     fn resolve(settings: &Settings) -> Result<Self, ConfigError> {
         // Determine backend: env var > settings > default (NearAi)
         let backend: LlmBackend = if let Some(b) = optional_env("LLM_BACKEND")? {
@@ -527,6 +740,52 @@ impl LlmConfig {
             None
         };
 
+        let fast_backend = match optional_env("FAST_LLM_BACKEND")? {
+            Some(b) => match b.parse() {
+                Ok(backend @ (LlmBackend::Groq | LlmBackend::Together)) => Some(backend),
+                _ => {
+                    return Err(ConfigError::InvalidValue {
+                        key: "FAST_LLM_BACKEND".to_string(),
+                        message: format!("expected 'groq' or 'together', got '{}'", b),
+                    });
+                }
+            },
+            None => None,
+        };
+        let uses = |b: LlmBackend| backend == b || fast_backend == Some(b);
+
+        let groq = match optional_env("GROQ_API_KEY")? {
+            Some(api_key) => Some(GroqConfig {
+                api_key: SecretString::from(api_key),
+                model: optional_env("GROQ_MODEL")?
+                    .unwrap_or_else(|| "llama-3.1-8b-instant".to_string()),
+            }),
+            None if uses(LlmBackend::Groq) => {
+                return Err(ConfigError::MissingRequired {
+                    key: "GROQ_API_KEY".to_string(),
+                    hint: "Set GROQ_API_KEY when LLM_BACKEND or FAST_LLM_BACKEND is groq"
+                        .to_string(),
+                });
+            }
+            None => None,
+        };
+
+        let together = match optional_env("TOGETHER_API_KEY")? {
+            Some(api_key) => Some(TogetherConfig {
+                api_key: SecretString::from(api_key),
+                model: optional_env("TOGETHER_MODEL")?
+                    .unwrap_or_else(|| "meta-llama/Llama-3.3-70B-Instruct-Turbo".to_string()),
+            }),
+            None if uses(LlmBackend::Together) => {
+                return Err(ConfigError::MissingRequired {
+                    key: "TOGETHER_API_KEY".to_string(),
+                    hint: "Set TOGETHER_API_KEY when LLM_BACKEND or FAST_LLM_BACKEND is together"
+                        .to_string(),
+                });
+            }
+            None => None,
+        };
+
         Ok(Self {
             backend,
             nearai,
@@ -534,6 +793,9 @@ impl LlmConfig {
             anthropic,
             ollama,
             openai_compatible,
+            groq,
+            together,
+            fast_backend,
         })
     }
 }
@@ -549,6 +811,19 @@ pub struct EmbeddingsConfig {
     pub openai_api_key: Option<SecretString>,
     /// Model to use for embeddings.
     pub model: String,
+    /// Use local hash embeddings when no provider is available
+    /// (`EMBEDDING_FALLBACK`), so semantic search still contributes.
+    pub fallback: bool,
+    /// Model used instead of `model` when most of the workspace is not in
+    /// English (`EMBEDDING_MULTILINGUAL_MODEL`). Must produce embeddings of
+    /// the same size.
+    pub multilingual_model: Option<String>,
+    /// text-embeddings-inference server with a sparse model (SPLADE) for
+    /// learned sparse search alongside keyword and vector search
+    /// (`SPARSE_EMBEDDING_URL`).
+    pub sparse_url: Option<String>,
+    /// Name of the model `sparse_url` serves (`SPARSE_EMBEDDING_MODEL`).
+    pub sparse_model: String,
 }
 
 impl Default for EmbeddingsConfig {
@@ -558,6 +833,10 @@ impl Default for EmbeddingsConfig {
             provider: "openai".to_string(),
             openai_api_key: None,
             model: "text-embedding-3-small".to_string(),
+            fallback: false,
+            multilingual_model: None,
+            sparse_url: None,
+            sparse_model: "splade".to_string(),
         }
     }
 }
@@ -586,9 +865,28 @@ impl EmbeddingsConfig {
             provider,
             openai_api_key,
             model,
+            fallback: parse_optional_env("EMBEDDING_FALLBACK", false)?,
+            multilingual_model: optional_env("EMBEDDING_MULTILINGUAL_MODEL")?,
+            sparse_url: optional_env("SPARSE_EMBEDDING_URL")?,
+            sparse_model: optional_env("SPARSE_EMBEDDING_MODEL")?
+                .unwrap_or_else(|| "splade".to_string()),
         })
     }
 
+    /// Switch to the multilingual model if one is configured and most of
+    /// the indexed chunks (language and count) are not in English. Returns
+    /// whether the model changed.
+    pub fn route_by_language(&mut self, counts: &[(Language, u64)]) -> bool {
+        let Some(model) = &self.multilingual_model else {
+            return false;
+        };
+        if *model == self.model || non_english_share(counts) <= 0.5 {
+            return false;
+        }
+        self.model = model.clone();
+        true
+    }
+
     /// Get the OpenAI API key if configured.
     pub fn openai_api_key(&self) -> Option<&str> {
         self.openai_api_key.as_ref().map(|s| s.expose_secret())
@@ -615,6 +913,8 @@ pub struct ChannelsConfig {
     pub wasm_channels_enabled: bool,
     /// Telegram owner user ID. When set, the bot only responds to this user.
     pub telegram_owner_id: Option<i64>,
+    /// WASM channels to load; `None` loads every installed channel.
+    pub wasm_channel_filter: Option<Vec<String>>,
 }
 
 #[derive(Debug, Clone)]
@@ -638,6 +938,8 @@ pub struct GatewayConfig {
     /// Bearer token for authentication. Random hex generated at startup if unset.
     pub auth_token: Option<String>,
     pub user_id: String,
+    /// Chat messages accepted per minute across all clients.
+    pub chat_rate_limit_per_minute: u64,
 }
 
 impl ChannelsConfig {
@@ -676,11 +978,29 @@ impl ChannelsConfig {
                     .unwrap_or(3000),
                 auth_token: optional_env("GATEWAY_AUTH_TOKEN")?,
                 user_id: optional_env("GATEWAY_USER_ID")?.unwrap_or_else(|| "default".to_string()),
+                chat_rate_limit_per_minute: optional_env("GATEWAY_CHAT_RATE_LIMIT")?
+                    .map(|s| s.parse())
+                    .transpose()
+                    .map_err(|e| ConfigError::InvalidValue {
+                        key: "GATEWAY_CHAT_RATE_LIMIT".to_string(),
+                        message: format!("must be a positive integer: {e}"),
+                    })?
+                    .or(settings.channels.gateway_chat_rate_limit)
+                    .unwrap_or(30),
             })
         } else {
             None
         };
 
+        if let Some(ref gw) = gateway
+            && gw.chat_rate_limit_per_minute == 0
+        {
+            return Err(ConfigError::InvalidValue {
+                key: "GATEWAY_CHAT_RATE_LIMIT".to_string(),
+                message: "must be a positive integer".to_string(),
+            });
+        }
+
         let cli_enabled = optional_env("CLI_ENABLED")?
             .map(|s| s.to_lowercase() != "false" && s != "0")
             .unwrap_or(true);
@@ -710,6 +1030,7 @@ impl ChannelsConfig {
                     message: format!("must be an integer: {e}"),
                 })?
                 .or(settings.channels.telegram_owner_id),
+            wasm_channel_filter: None,
         })
     }
 }
@@ -735,8 +1056,37 @@ pub struct AgentConfig {
     pub use_planning: bool,
     /// Session idle timeout. Sessions inactive longer than this are pruned.
     pub session_idle_timeout: Duration,
+    /// How long stored conversations are kept after their last message
+    /// (`SESSION_RETENTION_DAYS`; `None` = forever).
+    pub session_retention: Option<Duration>,
     /// Allow chat to use filesystem/shell tools directly (bypass sandbox).
     pub allow_local_tools: bool,
+    /// Persist each job's LLM transcript for replay (requires a database).
+    pub record_transcripts: bool,
+    /// Summarize finished conversations and jobs into the daily log
+    /// (requires a workspace).
+    pub episode_summaries: bool,
+    /// Tools hidden from the LLM and refused if called.
+    pub disabled_tools: Vec<String>,
+    /// How often to poll settings for hot-reloadable changes (`None` = off).
+    pub config_reload_interval: Option<Duration>,
+    /// Tool name patterns the agent may use (`*` for all). Set from the
+    /// registered agent started with `--agent`.
+    pub toolset: Vec<String>,
+    /// Where per-job temp directories are created (`AGENT_JOB_TEMP_DIR`).
+    pub job_temp_root: PathBuf,
+    /// ID of the registered agent started with `--agent`, if any.
+    pub agent_id: Option<Uuid>,
+    /// Locale given to tools for formatting (`AGENT_LOCALE`, falling back
+    /// to the workspace seed locale).
+    pub locale: Option<String>,
+    /// Per-user rate limits and token budgets.
+    pub user_limits: UserLimitsConfig,
+    /// How long shutdown waits for the current turn and running jobs to
+    /// finish before checkpointing them (`SHUTDOWN_DRAIN_TIMEOUT_SECS`).
+    pub shutdown_drain_timeout: Duration,
+    /// How memory the response relies on is cited (`AGENT_CITATIONS`).
+    pub citation_style: CitationStyle,
 }
 
 impl AgentConfig {
@@ -807,6 +1157,9 @@ impl AgentConfig {
                     })?
                     .unwrap_or(settings.agent.session_idle_timeout_secs),
             ),
+            session_retention: Some(parse_optional_env::<u64>("SESSION_RETENTION_DAYS", 0)?)
+                .filter(|days| *days > 0)
+                .map(|days| Duration::from_secs(days * 86_400)),
             allow_local_tools: optional_env("ALLOW_LOCAL_TOOLS")?
                 .map(|s| s.parse())
                 .transpose()
@@ -815,8 +1168,84 @@ impl AgentConfig {
                     message: format!("must be 'true' or 'false': {e}"),
                 })?
                 .unwrap_or(false),
+            record_transcripts: optional_env("AGENT_RECORD_TRANSCRIPTS")?
+                .map(|s| s.parse())
+                .transpose()
+                .map_err(|e| ConfigError::InvalidValue {
+                    key: "AGENT_RECORD_TRANSCRIPTS".to_string(),
+                    message: format!("must be 'true' or 'false': {e}"),
+                })?
+                .unwrap_or(true),
+            episode_summaries: optional_env("AGENT_EPISODE_SUMMARIES")?
+                .map(|s| s.parse())
+                .transpose()
+                .map_err(|e| ConfigError::InvalidValue {
+                    key: "AGENT_EPISODE_SUMMARIES".to_string(),
+                    message: format!("must be 'true' or 'false': {e}"),
+                })?
+                .unwrap_or(true),
+            disabled_tools: optional_env("AGENT_DISABLED_TOOLS")?
+                .map(|s| {
+                    s.split(',')
+                        .map(|t| t.trim().to_string())
+                        .filter(|t| !t.is_empty())
+                        .collect()
+                })
+                .unwrap_or_else(|| settings.agent.disabled_tools.clone()),
+            config_reload_interval: Some(
+                optional_env("CONFIG_RELOAD_INTERVAL_SECS")?
+                    .map(|s| s.parse())
+                    .transpose()
+                    .map_err(|e| ConfigError::InvalidValue {
+                        key: "CONFIG_RELOAD_INTERVAL_SECS".to_string(),
+                        message: format!("must be a non-negative integer: {e}"),
+                    })?
+                    .unwrap_or(5),
+            )
+            .filter(|secs| *secs > 0)
+            .map(Duration::from_secs),
+            toolset: vec!["*".to_string()],
+            job_temp_root: optional_env("AGENT_JOB_TEMP_DIR")?
+                .map(PathBuf::from)
+                .unwrap_or_else(|| std::env::temp_dir().join("ironclaw-jobs")),
+            agent_id: None,
+            locale: optional_env("AGENT_LOCALE")?
+                .or_else(|| settings.workspace_seed.locale.clone()),
+            user_limits: UserLimitsConfig::resolve()?,
+            shutdown_drain_timeout: Duration::from_secs(parse_optional_env(
+                "SHUTDOWN_DRAIN_TIMEOUT_SECS",
+                30,
+            )?),
+            citation_style: parse_optional_env("AGENT_CITATIONS", CitationStyle::default())?,
+        })
+    }
+}
+
+/// Per-user inbound limits, so one user can't run up the bill for a
+/// public-facing bot. 0 means unlimited.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct UserLimitsConfig {
+    /// Messages a user may send per minute (`USER_MESSAGES_PER_MINUTE`).
+    pub messages_per_minute: u32,
+    /// Jobs a user may start per hour (`USER_JOBS_PER_HOUR`).
+    pub jobs_per_hour: u32,
+    /// LLM tokens a user may consume per UTC day (`USER_DAILY_TOKEN_BUDGET`).
+    pub daily_token_budget: u64,
+}
+
+impl UserLimitsConfig {
+    fn resolve() -> Result<Self, ConfigError> {
+        Ok(Self {
+            messages_per_minute: parse_optional_env("USER_MESSAGES_PER_MINUTE", 0)?,
+            jobs_per_hour: parse_optional_env("USER_JOBS_PER_HOUR", 0)?,
+            daily_token_budget: parse_optional_env("USER_DAILY_TOKEN_BUDGET", 0)?,
         })
     }
+
+    /// Whether any limit is set.
+    pub fn is_enabled(&self) -> bool {
+        self.messages_per_minute > 0 || self.jobs_per_hour > 0 || self.daily_token_budget > 0
+    }
 }
 
 /// Safety configuration.
@@ -842,6 +1271,139 @@ impl SafetyConfig {
     }
 }
 
+/// Moderation of outgoing messages on public-facing channels; see
+/// [`crate::safety::moderation`].
+#[derive(Clone)]
+pub struct ModerationConfig {
+    /// Channels whose outgoing messages are moderated (`*` = all). Empty
+    /// disables moderation.
+    pub channels: Vec<String>,
+    /// What happens to a flagged message.
+    pub action: ModerationAction,
+    /// Regular expressions that flag a message: `MODERATION_BLOCKED_TERMS`
+    /// as whole words, then the lines of `MODERATION_RULES_FILE`.
+    pub rules: Vec<String>,
+    /// API key for the OpenAI moderation API (`MODERATION_PROVIDER=openai`).
+    pub openai_api_key: Option<SecretString>,
+    /// Sent in place of a blocked message.
+    pub block_message: String,
+}
+
+impl Default for ModerationConfig {
+    fn default() -> Self {
+        Self {
+            channels: Vec::new(),
+            action: ModerationAction::Block,
+            rules: Vec::new(),
+            openai_api_key: None,
+            block_message: "I can't share that response here.".to_string(),
+        }
+    }
+}
+
+impl std::fmt::Debug for ModerationConfig {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ModerationConfig")
+            .field("channels", &self.channels)
+            .field("action", &self.action)
+            .field("rules", &self.rules.len())
+            .field("openai", &self.openai_api_key.is_some())
+            .finish()
+    }
+}
+
+impl ModerationConfig {
+    fn resolve() -> Result<Self, ConfigError> {
+        let split = |list: String| -> Vec<String> {
+            list.split(',')
+                .map(|s| s.trim().to_string())
+                .filter(|s| !s.is_empty())
+                .collect()
+        };
+        let defaults = Self::default();
+        let channels = optional_env("MODERATION_CHANNELS")?
+            .map(split)
+            .unwrap_or_default();
+        let action = match optional_env("MODERATION_ACTION")? {
+            Some(s) => s.parse().map_err(|message| ConfigError::InvalidValue {
+                key: "MODERATION_ACTION".to_string(),
+                message,
+            })?,
+            None => defaults.action,
+        };
+
+        let mut rules: Vec<String> = optional_env("MODERATION_BLOCKED_TERMS")?
+            .map(split)
+            .unwrap_or_default()
+            .iter()
+            .map(|term| format!(r"\b{}\b", regex::escape(term)))
+            .collect();
+        if let Some(path) = optional_env("MODERATION_RULES_FILE")? {
+            let text = std::fs::read_to_string(&path).map_err(|e| ConfigError::InvalidValue {
+                key: "MODERATION_RULES_FILE".to_string(),
+                message: format!("cannot read '{}': {}", path, e),
+            })?;
+            for line in text.lines().map(str::trim) {
+                if line.is_empty() || line.starts_with('#') {
+                    continue;
+                }
+                if let Err(e) = regex::Regex::new(line) {
+                    return Err(ConfigError::InvalidValue {
+                        key: "MODERATION_RULES_FILE".to_string(),
+                        message: format!("invalid pattern '{}': {}", line, e),
+                    });
+                }
+                rules.push(line.to_string());
+            }
+        }
+
+        let openai_api_key = match optional_env("MODERATION_PROVIDER")?.as_deref() {
+            None | Some("none") => None,
+            Some("openai") => Some(
+                optional_env("MODERATION_OPENAI_API_KEY")?
+                    .or(optional_env("OPENAI_API_KEY")?)
+                    .map(SecretString::from)
+                    .ok_or_else(|| ConfigError::MissingRequired {
+                        key: "OPENAI_API_KEY".to_string(),
+                        hint: "required when MODERATION_PROVIDER=openai".to_string(),
+                    })?,
+            ),
+            Some(other) => {
+                return Err(ConfigError::InvalidValue {
+                    key: "MODERATION_PROVIDER".to_string(),
+                    message: format!(
+                        "invalid moderation provider '{}', expected one of: none, openai",
+                        other
+                    ),
+                });
+            }
+        };
+
+        if !channels.is_empty() && rules.is_empty() && openai_api_key.is_none() {
+            return Err(ConfigError::MissingRequired {
+                key: "MODERATION_BLOCKED_TERMS".to_string(),
+                hint: "MODERATION_CHANNELS needs MODERATION_BLOCKED_TERMS, \
+                       MODERATION_RULES_FILE, or MODERATION_PROVIDER"
+                    .to_string(),
+            });
+        }
+
+        Ok(Self {
+            channels,
+            action,
+            rules,
+            openai_api_key,
+            block_message: optional_env("MODERATION_BLOCK_MESSAGE")?
+                .unwrap_or(defaults.block_message),
+        })
+    }
+
+    /// Whether any channel is moderated.
+    pub fn enabled(&self) -> bool {
+        !self.channels.is_empty()
+    }
+}
+
 /// WASM sandbox configuration.
 #[derive(Debug, Clone)]
 pub struct WasmConfig {
@@ -870,6 +1432,9 @@ pub struct SecretsConfig {
     pub enabled: bool,
     /// Source of the master key.
     pub source: crate::settings::KeySource,
+    /// Hosts and commands each secret may be injected into
+    /// (`SECRETS_ALLOWED_HOSTS`, `SECRETS_ALLOWED_COMMANDS`).
+    pub bindings: crate::secrets::inject::SecretBindings,
 }
 
 impl std::fmt::Debug for SecretsConfig {
@@ -878,6 +1443,7 @@ impl std::fmt::Debug for SecretsConfig {
             .field("master_key", &self.master_key.is_some())
             .field("enabled", &self.enabled)
             .field("source", &self.source)
+            .field("bindings", &self.bindings)
             .finish()
     }
 }
@@ -888,15 +1454,25 @@ impl std::fmt::Debug for SecretsConfig {
 /// (e.g. `Config::from_env()` then `Config::from_db()`). Thread-safe alternative
 /// to caching in a process env var.
 impl SecretsConfig {
-    /// Auto-detect secrets master key from env var, then OS keychain.
+    /// Auto-detect secrets master key from env var, key file, then OS keychain.
     ///
-    /// Sequential probe: SECRETS_MASTER_KEY env var first, then OS keychain.
-    /// No saved "source" needed; just try each source in order.
+    /// Sequential probe: SECRETS_MASTER_KEY env var first, then the file named
+    /// by SECRETS_MASTER_KEY_FILE, then OS keychain. No saved "source" needed;
+    /// just try each source in order.
     async fn resolve() -> Result<Self, ConfigError> {
         use crate::settings::KeySource;
 
         let (master_key, source) = if let Some(env_key) = optional_env("SECRETS_MASTER_KEY")? {
             (Some(SecretString::from(env_key)), KeySource::Env)
+        } else if let Some(path) = optional_env("SECRETS_MASTER_KEY_FILE")? {
+            let key = std::fs::read_to_string(&path).map_err(|e| ConfigError::InvalidValue {
+                key: "SECRETS_MASTER_KEY_FILE".to_string(),
+                message: format!("failed to read {}: {}", path, e),
+            })?;
+            (
+                Some(SecretString::from(key.trim().to_string())),
+                KeySource::File,
+            )
         } else {
             // Probe the OS keychain; if a key is stored, use it
             match crate::secrets::keychain::get_master_key().await {
@@ -919,10 +1495,20 @@ impl SecretsConfig {
             });
         }
 
+        let bindings = crate::secrets::inject::SecretBindings::parse(
+            &optional_env("SECRETS_ALLOWED_HOSTS")?.unwrap_or_default(),
+            &optional_env("SECRETS_ALLOWED_COMMANDS")?.unwrap_or_default(),
+        )
+        .map_err(|message| ConfigError::InvalidValue {
+            key: "SECRETS_ALLOWED_HOSTS/SECRETS_ALLOWED_COMMANDS".to_string(),
+            message,
+        })?;
+
         Ok(Self {
             master_key,
             enabled,
             source,
+            bindings,
         })
     }
 
@@ -946,6 +1532,67 @@ impl Default for WasmConfig {
     }
 }
 
+/// Skills configuration (see [`crate::skills`]).
+#[derive(Debug, Clone)]
+pub struct SkillsConfig {
+    pub enabled: bool,
+    /// Directories searched for skills on disk, in order; later directories
+    /// override earlier ones. Workspace skills override all of them.
+    /// `ironclaw skill install` installs into the first.
+    pub dirs: Vec<PathBuf>,
+    /// Base64 Ed25519 public keys trusted to sign skill packages.
+    pub trusted_keys: Vec<String>,
+    /// Registry index for installing skills by name.
+    pub registry_url: Option<String>,
+}
+
+impl SkillsConfig {
+    /// Directory `ironclaw skill install` installs into.
+    pub fn install_dir(&self) -> PathBuf {
+        self.dirs
+            .first()
+            .cloned()
+            .unwrap_or_else(default_skills_dir)
+    }
+
+    fn resolve() -> Result<Self, ConfigError> {
+        let dirs = match optional_env("SKILLS_DIRS")? {
+            Some(list) => std::env::split_paths(&list)
+                .filter(|p| !p.as_os_str().is_empty())
+                .collect(),
+            None => vec![default_skills_dir()],
+        };
+        Ok(Self {
+            enabled: optional_env("SKILLS_ENABLED")?
+                .map(|s| s.parse())
+                .transpose()
+                .map_err(|e| ConfigError::InvalidValue {
+                    key: "SKILLS_ENABLED".to_string(),
+                    message: format!("must be 'true' or 'false': {e}"),
+                })?
+                .unwrap_or(true),
+            dirs,
+            trusted_keys: optional_env("SKILLS_TRUSTED_KEYS")?
+                .map(|keys| {
+                    keys.split(',')
+                        .map(|k| k.trim().to_string())
+                        .filter(|k| !k.is_empty())
+                        .collect()
+                })
+                .unwrap_or_default(),
+            registry_url: optional_env("SKILLS_REGISTRY_URL")?,
+        })
+    }
+}
+
+/// Get the default skills directory (~/.ironclaw/skills/).
+fn default_skills_dir() -> PathBuf {
+    dirs::home_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join(".ironclaw")
+        .join("skills")
+}
+
 /// Get the default tools directory (~/.ironclaw/tools/).
 fn default_tools_dir() -> PathBuf {
     dirs::home_dir()
@@ -1086,6 +1733,8 @@ pub struct HeartbeatConfig {
     pub notify_channel: Option<String>,
     /// User ID to notify on heartbeat findings.
     pub notify_user: Option<String>,
+    /// Probes run before the LLM; see [`HeartbeatPrecheckConfig`].
+    pub prechecks: HeartbeatPrecheckConfig,
 }
 
 impl Default for HeartbeatConfig {
@@ -1095,13 +1744,14 @@ impl Default for HeartbeatConfig {
             interval_secs: 1800, // 30 minutes
             notify_channel: None,
             notify_user: None,
+            prechecks: HeartbeatPrecheckConfig::default(),
         }
     }
 }
 
 impl HeartbeatConfig {
     fn resolve(settings: &Settings) -> Result<Self, ConfigError> {
-        Ok(Self {
+        let config = Self {
             enabled: optional_env("HEARTBEAT_ENABLED")?
                 .map(|s| s.parse())
                 .transpose()
@@ -1122,8 +1772,105 @@ impl HeartbeatConfig {
                 .or_else(|| settings.heartbeat.notify_channel.clone()),
             notify_user: optional_env("HEARTBEAT_NOTIFY_USER")?
                 .or_else(|| settings.heartbeat.notify_user.clone()),
+            prechecks: HeartbeatPrecheckConfig::resolve()?,
+        };
+        if config.interval_secs == 0 {
+            return Err(ConfigError::InvalidValue {
+                key: "HEARTBEAT_INTERVAL_SECS".to_string(),
+                message: "must be a positive integer".to_string(),
+            });
+        }
+        Ok(config)
+    }
+}
+
+/// Deterministic probes run before each heartbeat. When any are configured,
+/// the LLM is only called if one of them reports something new.
+#[derive(Debug, Clone)]
+pub struct HeartbeatPrecheckConfig {
+    /// Mailbox whose unread messages are watched (`HEARTBEAT_IMAP_*`).
+    pub imap: Option<ImapProbeConfig>,
+    /// iCalendar (.ics) URL whose upcoming events are watched.
+    pub calendar_url: Option<String>,
+    /// How far ahead an event counts as upcoming.
+    pub calendar_lookahead: Duration,
+    /// RSS or Atom feeds whose new items are watched.
+    pub feed_urls: Vec<String>,
+}
+
+impl Default for HeartbeatPrecheckConfig {
+    fn default() -> Self {
+        Self {
+            imap: None,
+            calendar_url: None,
+            calendar_lookahead: Duration::from_secs(60 * 60),
+            feed_urls: Vec::new(),
+        }
+    }
+}
+
+impl HeartbeatPrecheckConfig {
+    fn resolve() -> Result<Self, ConfigError> {
+        let imap = match optional_env("HEARTBEAT_IMAP_HOST")? {
+            Some(host) => {
+                let username = optional_env("HEARTBEAT_IMAP_USER")?.ok_or_else(|| {
+                    ConfigError::MissingRequired {
+                        key: "HEARTBEAT_IMAP_USER".to_string(),
+                        hint: "required when HEARTBEAT_IMAP_HOST is set".to_string(),
+                    }
+                })?;
+                let password = optional_env("HEARTBEAT_IMAP_PASSWORD")?.ok_or_else(|| {
+                    ConfigError::MissingRequired {
+                        key: "HEARTBEAT_IMAP_PASSWORD".to_string(),
+                        hint: "required when HEARTBEAT_IMAP_HOST is set".to_string(),
+                    }
+                })?;
+                Some(ImapProbeConfig {
+                    host,
+                    port: parse_optional_env("HEARTBEAT_IMAP_PORT", 993)?,
+                    username,
+                    password: SecretString::from(password),
+                    mailbox: optional_env("HEARTBEAT_IMAP_MAILBOX")?
+                        .unwrap_or_else(|| "INBOX".to_string()),
+                })
+            }
+            None => None,
+        };
+        let feed_urls = optional_env("HEARTBEAT_FEED_URLS")?
+            .map(|s| {
+                s.split(',')
+                    .map(|u| u.trim().to_string())
+                    .filter(|u| !u.is_empty())
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        Ok(Self {
+            imap,
+            calendar_url: optional_env("HEARTBEAT_CALENDAR_URL")?,
+            calendar_lookahead: Duration::from_secs(
+                parse_optional_env("HEARTBEAT_CALENDAR_LOOKAHEAD_MINS", 60u64)? * 60,
+            ),
+            feed_urls,
         })
     }
+
+    /// Whether any probe is configured.
+    pub fn is_enabled(&self) -> bool {
+        self.imap.is_some() || self.calendar_url.is_some() || !self.feed_urls.is_empty()
+    }
+}
+
+/// IMAP account checked for unread mail by the heartbeat pre-checks.
+#[derive(Debug, Clone)]
+pub struct ImapProbeConfig {
+    pub host: String,
+    /// IMAP over TLS port (default 993).
+    pub port: u16,
+    pub username: String,
+    pub password: SecretString,
+    /// Mailbox to watch (default `INBOX`).
+    pub mailbox: String,
 }
 
 /// Routines configuration.
@@ -1172,68 +1919,968 @@ impl RoutineConfig {
     }
 }
 
-/// Docker sandbox configuration.
+/// Audit log configuration.
 #[derive(Debug, Clone)]
-pub struct SandboxModeConfig {
-    /// Whether the Docker sandbox is enabled.
+pub struct AuditConfig {
+    /// Whether actions are recorded in the audit log.
     pub enabled: bool,
-    /// Sandbox policy: "readonly", "workspace_write", or "full_access".
-    pub policy: String,
-    /// Command timeout in seconds.
-    pub timeout_secs: u64,
-    /// Memory limit in megabytes.
-    pub memory_limit_mb: u64,
-    /// CPU shares (relative weight).
-    pub cpu_shares: u32,
-    /// Docker image for the sandbox.
-    pub image: String,
-    /// Whether to auto-pull the image if not found.
-    pub auto_pull_image: bool,
-    /// Additional domains to allow through the network proxy.
-    pub extra_allowed_domains: Vec<String>,
+    /// Days to keep entries before pruning (0 = keep forever).
+    pub retention_days: u64,
 }
 
-impl Default for SandboxModeConfig {
+impl Default for AuditConfig {
     fn default() -> Self {
         Self {
             enabled: true,
-            policy: "readonly".to_string(),
-            timeout_secs: 120,
-            memory_limit_mb: 2048,
-            cpu_shares: 1024,
-            image: "ghcr.io/nearai/sandbox:latest".to_string(),
-            auto_pull_image: true,
-            extra_allowed_domains: Vec::new(),
+            retention_days: 90,
         }
     }
 }
 
-impl SandboxModeConfig {
+impl AuditConfig {
     fn resolve() -> Result<Self, ConfigError> {
-        let extra_domains = optional_env("SANDBOX_EXTRA_DOMAINS")?
-            .map(|s| s.split(',').map(|d| d.trim().to_string()).collect())
-            .unwrap_or_default();
-
         Ok(Self {
-            enabled: optional_env("SANDBOX_ENABLED")?
+            enabled: optional_env("AUDIT_LOG_ENABLED")?
                 .map(|s| s.parse())
                 .transpose()
                 .map_err(|e| ConfigError::InvalidValue {
-                    key: "SANDBOX_ENABLED".to_string(),
+                    key: "AUDIT_LOG_ENABLED".to_string(),
                     message: format!("must be 'true' or 'false': {e}"),
                 })?
                 .unwrap_or(true),
-            policy: optional_env("SANDBOX_POLICY")?.unwrap_or_else(|| "readonly".to_string()),
-            timeout_secs: parse_optional_env("SANDBOX_TIMEOUT_SECS", 120)?,
-            memory_limit_mb: parse_optional_env("SANDBOX_MEMORY_LIMIT_MB", 2048)?,
-            cpu_shares: parse_optional_env("SANDBOX_CPU_SHARES", 1024)?,
-            image: optional_env("SANDBOX_IMAGE")?
-                .unwrap_or_else(|| "ghcr.io/nearai/sandbox:latest".to_string()),
-            auto_pull_image: optional_env("SANDBOX_AUTO_PULL")?
-                .map(|s| s.parse())
-                .transpose()
-                .map_err(|e| ConfigError::InvalidValue {
-                    key: "SANDBOX_AUTO_PULL".to_string(),
+            retention_days: parse_optional_env("AUDIT_RETENTION_DAYS", 90)?,
+        })
+    }
+
+    /// How long entries are kept, or `None` to keep them forever.
+    pub fn retention(&self) -> Option<Duration> {
+        (self.retention_days > 0).then(|| Duration::from_secs(self.retention_days * 86_400))
+    }
+}
+
+/// Outbound webhook configuration.
+///
+/// Events are POSTed to every URL in `WEBHOOK_URLS`, signed with
+/// `WEBHOOK_SECRET`; see [`crate::webhooks`].
+#[derive(Clone)]
+pub struct WebhookConfig {
+    /// URLs every event is sent to.
+    pub urls: Vec<String>,
+    /// HMAC key for the `X-Ironclaw-Signature` header.
+    pub secret: Option<SecretString>,
+    /// Events to send (`WEBHOOK_EVENTS`); all of them when empty.
+    pub events: Vec<WebhookEventKind>,
+    /// Attempts per delivery before giving up.
+    pub max_attempts: u32,
+}
+
+impl Default for WebhookConfig {
+    fn default() -> Self {
+        Self {
+            urls: Vec::new(),
+            secret: None,
+            events: Vec::new(),
+            max_attempts: 5,
+        }
+    }
+}
+
+impl std::fmt::Debug for WebhookConfig {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("WebhookConfig")
+            .field("urls", &self.urls)
+            .field("secret", &self.secret.is_some())
+            .field("events", &self.events)
+            .field("max_attempts", &self.max_attempts)
+            .finish()
+    }
+}
+
+impl WebhookConfig {
+    fn resolve() -> Result<Self, ConfigError> {
+        let split = |list: String| -> Vec<String> {
+            list.split(',')
+                .map(|s| s.trim().to_string())
+                .filter(|s| !s.is_empty())
+                .collect()
+        };
+        let urls = optional_env("WEBHOOK_URLS")?.map(split).unwrap_or_default();
+        for url in &urls {
+            if !(url.starts_with("http://") || url.starts_with("https://")) {
+                return Err(ConfigError::InvalidValue {
+                    key: "WEBHOOK_URLS".to_string(),
+                    message: format!("'{}' is not an http:// or https:// URL", url),
+                });
+            }
+        }
+        let secret = optional_env("WEBHOOK_SECRET")?.map(SecretString::from);
+        if !urls.is_empty() && secret.is_none() {
+            return Err(ConfigError::MissingRequired {
+                key: "WEBHOOK_SECRET".to_string(),
+                hint: "required when WEBHOOK_URLS is set".to_string(),
+            });
+        }
+        let events = optional_env("WEBHOOK_EVENTS")?
+            .map(split)
+            .unwrap_or_default()
+            .into_iter()
+            .map(|e| e.parse())
+            .collect::<Result<Vec<WebhookEventKind>, String>>()
+            .map_err(|message| ConfigError::InvalidValue {
+                key: "WEBHOOK_EVENTS".to_string(),
+                message,
+            })?;
+
+        Ok(Self {
+            urls,
+            secret,
+            events,
+            max_attempts: parse_optional_env("WEBHOOK_MAX_ATTEMPTS", 5)?,
+        })
+    }
+
+    /// Whether any webhook URL is configured.
+    pub fn enabled(&self) -> bool {
+        !self.urls.is_empty()
+    }
+}
+
+/// Supervisor agent configuration.
+///
+/// When enabled, the running agent gets a `supervise` tool that splits a
+/// request across the registered agents and combines their answers.
+#[derive(Debug, Clone)]
+pub struct SupervisorConfig {
+    pub enabled: bool,
+    /// Sub-tasks dispatched per request.
+    pub max_subtasks: usize,
+    /// LLM cost limit (USD) per supervised request. `None` = unlimited.
+    pub budget_usd: Option<rust_decimal::Decimal>,
+    /// How long one worker may spend on a sub-task.
+    pub subtask_timeout: Duration,
+    /// LLM turns a worker may take on one sub-task.
+    pub worker_max_iterations: usize,
+}
+
+impl Default for SupervisorConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            max_subtasks: 6,
+            budget_usd: None,
+            subtask_timeout: Duration::from_secs(300),
+            worker_max_iterations: 10,
+        }
+    }
+}
+
+impl SupervisorConfig {
+    fn resolve() -> Result<Self, ConfigError> {
+        let defaults = Self::default();
+        Ok(Self {
+            enabled: parse_optional_env("SUPERVISOR_ENABLED", defaults.enabled)?,
+            max_subtasks: parse_optional_env("SUPERVISOR_MAX_SUBTASKS", defaults.max_subtasks)?,
+            budget_usd: optional_env("SUPERVISOR_BUDGET_USD")?
+                .map(|s| s.parse())
+                .transpose()
+                .map_err(|e| ConfigError::InvalidValue {
+                    key: "SUPERVISOR_BUDGET_USD".to_string(),
+                    message: format!("must be a decimal amount: {e}"),
+                })?,
+            subtask_timeout: Duration::from_secs(parse_optional_env(
+                "SUPERVISOR_SUBTASK_TIMEOUT_SECS",
+                defaults.subtask_timeout.as_secs(),
+            )?),
+            worker_max_iterations: parse_optional_env(
+                "SUPERVISOR_WORKER_MAX_ITERATIONS",
+                defaults.worker_max_iterations,
+            )?,
+        })
+    }
+}
+
+/// Memory decay configuration.
+///
+/// When enabled, MEMORY.md entries nobody has written or searched up for
+/// `idle_days` are periodically moved under `memory/archive/`.
+#[derive(Debug, Clone)]
+pub struct MemoryDecayConfig {
+    pub enabled: bool,
+    /// Days an entry may go unused before it is archived (0 = never).
+    pub idle_days: u64,
+    /// Most entries MEMORY.md keeps (0 = no limit).
+    pub max_entries: usize,
+    /// How often the archival pass runs.
+    pub interval: Duration,
+}
+
+impl Default for MemoryDecayConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            idle_days: 180,
+            max_entries: 0,
+            interval: Duration::from_secs(86_400),
+        }
+    }
+}
+
+impl MemoryDecayConfig {
+    fn resolve() -> Result<Self, ConfigError> {
+        let defaults = Self::default();
+        Ok(Self {
+            enabled: parse_optional_env("MEMORY_DECAY_ENABLED", defaults.enabled)?,
+            idle_days: parse_optional_env("MEMORY_DECAY_IDLE_DAYS", defaults.idle_days)?,
+            max_entries: parse_optional_env("MEMORY_DECAY_MAX_ENTRIES", defaults.max_entries)?,
+            interval: Duration::from_secs(parse_optional_env(
+                "MEMORY_DECAY_INTERVAL_SECS",
+                defaults.interval.as_secs(),
+            )?),
+        })
+    }
+
+    /// The archival policy these settings describe.
+    pub fn policy(&self) -> crate::workspace::DecayPolicy {
+        crate::workspace::DecayPolicy {
+            max_idle: (self.idle_days > 0).then(|| chrono::Duration::days(self.idle_days as i64)),
+            max_entries: (self.max_entries > 0).then_some(self.max_entries),
+        }
+    }
+}
+
+/// System prompt assembly configuration.
+#[derive(Debug, Clone, Default)]
+pub struct SystemPromptConfig {
+    /// Token budget for each section of the workspace system prompt.
+    pub budget: crate::workspace::PromptBudget,
+}
+
+impl SystemPromptConfig {
+    fn resolve() -> Result<Self, ConfigError> {
+        let defaults = crate::workspace::PromptBudget::default();
+        Ok(Self {
+            budget: crate::workspace::PromptBudget {
+                identity_file: parse_optional_env(
+                    "SYSTEM_PROMPT_IDENTITY_FILE_TOKENS",
+                    defaults.identity_file,
+                )?,
+                pinned: parse_optional_env("SYSTEM_PROMPT_PINNED_TOKENS", defaults.pinned)?,
+                conditional: parse_optional_env(
+                    "SYSTEM_PROMPT_CONDITIONAL_TOKENS",
+                    defaults.conditional,
+                )?,
+                today: parse_optional_env("SYSTEM_PROMPT_TODAY_TOKENS", defaults.today)?,
+                yesterday: parse_optional_env(
+                    "SYSTEM_PROMPT_YESTERDAY_TOKENS",
+                    defaults.yesterday,
+                )?,
+                rollup: parse_optional_env("SYSTEM_PROMPT_ROLLUP_TOKENS", defaults.rollup)?,
+            },
+        })
+    }
+}
+
+/// Workspace seed template configuration.
+///
+/// Env vars override `workspace_seed.*` settings; inline templates come from
+/// settings only.
+#[derive(Debug, Clone, Default)]
+pub struct WorkspaceSeedConfig {
+    pub seed: crate::workspace::SeedConfig,
+}
+
+impl WorkspaceSeedConfig {
+    fn resolve(settings: &Settings) -> Result<Self, ConfigError> {
+        let seed = &settings.workspace_seed;
+        let files = match optional_env("WORKSPACE_SEED_FILES")? {
+            Some(list) => Some(
+                list.split(',')
+                    .map(|p| p.trim().to_string())
+                    .filter(|p| !p.is_empty())
+                    .collect(),
+            ),
+            None => seed.files.clone(),
+        };
+        Ok(Self {
+            seed: crate::workspace::SeedConfig {
+                template_dir: optional_env("WORKSPACE_SEED_DIR")?
+                    .map(PathBuf::from)
+                    .or_else(|| seed.template_dir.clone()),
+                locale: optional_env("WORKSPACE_SEED_LOCALE")?.or_else(|| seed.locale.clone()),
+                files,
+                templates: seed
+                    .templates
+                    .iter()
+                    .map(|t| (t.path.clone(), t.content.clone()))
+                    .collect(),
+                agent_name: optional_env("WORKSPACE_SEED_AGENT_NAME")?
+                    .or_else(|| seed.agent_name.clone()),
+            },
+        })
+    }
+}
+
+/// Workspace document cache configuration.
+///
+/// Identity files and MEMORY.md are read on every prompt; the cache keeps
+/// them in memory until a write through any workspace in this process
+/// invalidates them, or the TTL passes (for writers in other processes).
+#[derive(Debug, Clone)]
+pub struct WorkspaceCacheConfig {
+    /// Maximum cached documents (0 disables the cache).
+    pub capacity: usize,
+    /// How long an entry may be served without re-reading the database.
+    pub ttl: Duration,
+}
+
+impl Default for WorkspaceCacheConfig {
+    fn default() -> Self {
+        Self {
+            capacity: 64,
+            ttl: Duration::from_secs(300),
+        }
+    }
+}
+
+impl WorkspaceCacheConfig {
+    fn resolve() -> Result<Self, ConfigError> {
+        let defaults = Self::default();
+        Ok(Self {
+            capacity: parse_optional_env("WORKSPACE_CACHE_CAPACITY", defaults.capacity)?,
+            ttl: Duration::from_secs(parse_optional_env(
+                "WORKSPACE_CACHE_TTL_SECS",
+                defaults.ttl.as_secs(),
+            )?),
+        })
+    }
+}
+
+/// Directory index configuration.
+///
+/// When enabled, every workspace directory keeps a README.md listing its
+/// children and their summaries, refreshed as documents change.
+#[derive(Debug, Clone, Default)]
+pub struct WorkspaceIndexConfig {
+    pub auto_readme: bool,
+}
+
+impl WorkspaceIndexConfig {
+    fn resolve() -> Result<Self, ConfigError> {
+        Ok(Self {
+            auto_readme: parse_optional_env("WORKSPACE_AUTO_README", false)?,
+        })
+    }
+}
+
+/// Workspace path matching configuration.
+///
+/// Paths are always Unicode-normalized; this additionally makes them match
+/// regardless of case, as on macOS and Windows filesystems.
+#[derive(Debug, Clone, Default)]
+pub struct WorkspacePathConfig {
+    pub case_insensitive: bool,
+}
+
+impl WorkspacePathConfig {
+    fn resolve() -> Result<Self, ConfigError> {
+        Ok(Self {
+            case_insensitive: parse_optional_env("WORKSPACE_CASE_INSENSITIVE_PATHS", false)?,
+        })
+    }
+}
+
+/// Workspace size limits.
+///
+/// Writes that would grow a document or the workspace past its limit fail,
+/// which stops a runaway append loop before it swamps the system prompt.
+#[derive(Debug, Clone, Default)]
+pub struct WorkspaceQuotaConfig {
+    pub quota: crate::workspace::WorkspaceQuota,
+}
+
+impl WorkspaceQuotaConfig {
+    fn resolve() -> Result<Self, ConfigError> {
+        let defaults = crate::workspace::WorkspaceQuota::default();
+        Ok(Self {
+            quota: crate::workspace::WorkspaceQuota {
+                max_document_bytes: parse_optional_env(
+                    "WORKSPACE_MAX_DOCUMENT_BYTES",
+                    defaults.max_document_bytes,
+                )?,
+                max_workspace_bytes: parse_optional_env(
+                    "WORKSPACE_MAX_TOTAL_BYTES",
+                    defaults.max_workspace_bytes,
+                )?,
+            },
+        })
+    }
+}
+
+/// How long deleted workspace documents stay in the trash.
+#[derive(Debug, Clone)]
+pub struct WorkspaceTrashConfig {
+    /// Days a document stays in the trash before it is purged (0 = keep it
+    /// until deleted from the trash by hand).
+    pub retention_days: u64,
+    /// How often the trash is purged.
+    pub interval: Duration,
+}
+
+impl Default for WorkspaceTrashConfig {
+    fn default() -> Self {
+        Self {
+            retention_days: 30,
+            interval: Duration::from_secs(86_400),
+        }
+    }
+}
+
+impl WorkspaceTrashConfig {
+    fn resolve() -> Result<Self, ConfigError> {
+        let defaults = Self::default();
+        Ok(Self {
+            retention_days: parse_optional_env(
+                "WORKSPACE_TRASH_RETENTION_DAYS",
+                defaults.retention_days,
+            )?,
+            interval: Duration::from_secs(parse_optional_env(
+                "WORKSPACE_TRASH_PURGE_INTERVAL_SECS",
+                defaults.interval.as_secs(),
+            )?),
+        })
+    }
+
+    /// The retention window, if documents are purged at all.
+    pub fn retention(&self) -> Option<chrono::Duration> {
+        (self.retention_days > 0).then(|| chrono::Duration::days(self.retention_days as i64))
+    }
+}
+
+/// Two-way mirror of the workspace to a directory on disk.
+#[derive(Debug, Clone, Default)]
+pub struct WorkspaceMirrorConfig {
+    /// Directory documents are mirrored to (not mirrored if unset).
+    pub dir: Option<PathBuf>,
+}
+
+impl WorkspaceMirrorConfig {
+    fn resolve() -> Result<Self, ConfigError> {
+        Ok(Self {
+            dir: optional_env("WORKSPACE_MIRROR_DIR")?.map(PathBuf::from),
+        })
+    }
+}
+
+/// Committing workspace content to a git repository.
+#[derive(Debug, Clone)]
+pub struct WorkspaceGitConfig {
+    /// Bare repository documents are committed to (git is off if unset).
+    pub dir: Option<PathBuf>,
+    /// Remote name or URL the repository is pushed to, if any.
+    pub remote: Option<String>,
+    /// How often to push.
+    pub push_interval: Duration,
+}
+
+impl Default for WorkspaceGitConfig {
+    fn default() -> Self {
+        Self {
+            dir: None,
+            remote: None,
+            push_interval: Duration::from_secs(900),
+        }
+    }
+}
+
+impl WorkspaceGitConfig {
+    fn resolve() -> Result<Self, ConfigError> {
+        let defaults = Self::default();
+        Ok(Self {
+            dir: optional_env("WORKSPACE_GIT_DIR")?.map(PathBuf::from),
+            remote: optional_env("WORKSPACE_GIT_REMOTE")?,
+            push_interval: Duration::from_secs(parse_optional_env(
+                "WORKSPACE_GIT_PUSH_INTERVAL_SECS",
+                defaults.push_interval.as_secs(),
+            )?),
+        })
+    }
+}
+
+/// Daily log rollup configuration.
+///
+/// When enabled, finished weeks and months of daily logs are periodically
+/// summarized into `daily/weekly/` and `daily/monthly/`.
+#[derive(Debug, Clone)]
+pub struct DailyRollupConfig {
+    pub enabled: bool,
+    /// How often to check for periods needing a rollup.
+    pub interval: Duration,
+}
+
+impl Default for DailyRollupConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            interval: Duration::from_secs(21_600),
+        }
+    }
+}
+
+impl DailyRollupConfig {
+    fn resolve() -> Result<Self, ConfigError> {
+        let defaults = Self::default();
+        Ok(Self {
+            enabled: parse_optional_env("DAILY_ROLLUP_ENABLED", defaults.enabled)?,
+            interval: Duration::from_secs(parse_optional_env(
+                "DAILY_ROLLUP_INTERVAL_SECS",
+                defaults.interval.as_secs(),
+            )?),
+        })
+    }
+}
+
+/// Long-document summary configuration.
+///
+/// When enabled, documents over the threshold are summarized by the LLM as
+/// they are written, and the summary stands in for them in the system prompt.
+#[derive(Debug, Clone)]
+pub struct DocumentSummaryConfig {
+    pub enabled: bool,
+    /// Estimated tokens above which a document is summarized.
+    pub threshold_tokens: usize,
+}
+
+impl Default for DocumentSummaryConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            threshold_tokens: crate::workspace::summary::DEFAULT_THRESHOLD_TOKENS,
+        }
+    }
+}
+
+impl DocumentSummaryConfig {
+    fn resolve() -> Result<Self, ConfigError> {
+        let defaults = Self::default();
+        Ok(Self {
+            enabled: parse_optional_env("DOCUMENT_SUMMARIES_ENABLED", defaults.enabled)?,
+            threshold_tokens: parse_optional_env(
+                "DOCUMENT_SUMMARY_THRESHOLD_TOKENS",
+                defaults.threshold_tokens,
+            )?,
+        })
+    }
+}
+
+/// Weekly usage report configuration.
+///
+/// When enabled, a report of each finished week's jobs, tokens, tools,
+/// heartbeat activity, and errors is written to `reports/usage-YYYY-WW.md`.
+#[derive(Debug, Clone)]
+pub struct UsageReportConfig {
+    pub enabled: bool,
+    /// How often to check whether last week's report is missing.
+    pub interval: Duration,
+}
+
+impl Default for UsageReportConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            interval: Duration::from_secs(21_600),
+        }
+    }
+}
+
+impl UsageReportConfig {
+    fn resolve() -> Result<Self, ConfigError> {
+        let defaults = Self::default();
+        Ok(Self {
+            enabled: parse_optional_env("USAGE_REPORTS_ENABLED", defaults.enabled)?,
+            interval: Duration::from_secs(parse_optional_env(
+                "USAGE_REPORTS_INTERVAL_SECS",
+                defaults.interval.as_secs(),
+            )?),
+        })
+    }
+}
+
+/// Topic index configuration.
+///
+/// When enabled, chunk embeddings are periodically clustered into topics,
+/// named by the LLM, and written to `memory/topics.md`.
+#[derive(Debug, Clone)]
+pub struct MemoryTopicsConfig {
+    pub enabled: bool,
+    /// How often to check whether documents changed since the last index.
+    pub interval: Duration,
+    /// Most topics in the index.
+    pub max_topics: usize,
+}
+
+impl Default for MemoryTopicsConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            interval: Duration::from_secs(86_400),
+            max_topics: 12,
+        }
+    }
+}
+
+impl MemoryTopicsConfig {
+    fn resolve() -> Result<Self, ConfigError> {
+        let defaults = Self::default();
+        Ok(Self {
+            enabled: parse_optional_env("MEMORY_TOPICS_ENABLED", defaults.enabled)?,
+            interval: Duration::from_secs(parse_optional_env(
+                "MEMORY_TOPICS_INTERVAL_SECS",
+                defaults.interval.as_secs(),
+            )?),
+            max_topics: parse_optional_env("MEMORY_TOPICS_MAX", defaults.max_topics)?,
+        })
+    }
+}
+
+/// MEMORY.md contradiction check configuration.
+///
+/// When enabled, entries appended to MEMORY.md are compared with the most
+/// related existing entries, and `memory_write` reports those the new entry
+/// may contradict so the agent can reconcile them.
+#[derive(Debug, Clone)]
+pub struct MemoryConflictConfig {
+    pub enabled: bool,
+    /// Have the (cheap) LLM confirm which related entries conflict, rather
+    /// than flagging every closely related entry.
+    pub use_llm: bool,
+}
+
+impl Default for MemoryConflictConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            use_llm: true,
+        }
+    }
+}
+
+impl MemoryConflictConfig {
+    fn resolve() -> Result<Self, ConfigError> {
+        let defaults = Self::default();
+        Ok(Self {
+            enabled: parse_optional_env("MEMORY_CONFLICT_CHECK", defaults.enabled)?,
+            use_llm: parse_optional_env("MEMORY_CONFLICT_LLM", defaults.use_llm)?,
+        })
+    }
+}
+
+/// Knowledge graph configuration.
+///
+/// When enabled, workspace documents are periodically run through an LLM to
+/// extract entities and relations, and the agent gets a `memory_graph` tool.
+#[derive(Debug, Clone)]
+pub struct KnowledgeGraphConfig {
+    pub enabled: bool,
+    /// How often changed documents are re-indexed.
+    pub index_interval: Duration,
+}
+
+impl Default for KnowledgeGraphConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            index_interval: Duration::from_secs(3600),
+        }
+    }
+}
+
+impl KnowledgeGraphConfig {
+    fn resolve() -> Result<Self, ConfigError> {
+        let defaults = Self::default();
+        Ok(Self {
+            enabled: parse_optional_env("KNOWLEDGE_GRAPH_ENABLED", defaults.enabled)?,
+            index_interval: Duration::from_secs(parse_optional_env(
+                "KNOWLEDGE_GRAPH_INDEX_INTERVAL_SECS",
+                defaults.index_interval.as_secs(),
+            )?),
+        })
+    }
+}
+
+/// OpenTelemetry trace export configuration.
+///
+/// Uses the standard `OTEL_*` variable names so existing collector setups
+/// work unchanged. Export is off unless an OTLP endpoint is set.
+#[derive(Debug, Clone)]
+pub struct TelemetryConfig {
+    /// OTLP/HTTP traces endpoint (e.g. `http://localhost:4318/v1/traces`).
+    pub otlp_endpoint: Option<String>,
+    /// `service.name` resource attribute.
+    pub service_name: String,
+    /// Fraction of root traces to sample (0.0 - 1.0).
+    pub sample_ratio: f64,
+}
+
+impl Default for TelemetryConfig {
+    fn default() -> Self {
+        Self {
+            otlp_endpoint: None,
+            service_name: "ironclaw".to_string(),
+            sample_ratio: 1.0,
+        }
+    }
+}
+
+impl TelemetryConfig {
+    fn resolve() -> Result<Self, ConfigError> {
+        // The signal-specific variable is a full URL; the generic one is a
+        // base URL that gets the `/v1/traces` path appended.
+        let otlp_endpoint = match optional_env("OTEL_EXPORTER_OTLP_TRACES_ENDPOINT")? {
+            Some(url) => Some(url),
+            None => optional_env("OTEL_EXPORTER_OTLP_ENDPOINT")?
+                .map(|base| format!("{}/v1/traces", base.trim_end_matches('/'))),
+        };
+
+        let sample_ratio: f64 = parse_optional_env("OTEL_TRACES_SAMPLER_ARG", 1.0)?;
+        if !(0.0..=1.0).contains(&sample_ratio) {
+            return Err(ConfigError::InvalidValue {
+                key: "OTEL_TRACES_SAMPLER_ARG".to_string(),
+                message: "must be between 0.0 and 1.0".to_string(),
+            });
+        }
+
+        Ok(Self {
+            otlp_endpoint,
+            service_name: optional_env("OTEL_SERVICE_NAME")?
+                .unwrap_or_else(|| "ironclaw".to_string()),
+            sample_ratio,
+        })
+    }
+
+    /// Whether spans should be exported.
+    pub fn enabled(&self) -> bool {
+        self.otlp_endpoint.is_some()
+    }
+}
+
+/// Prometheus `/metrics` listener configuration.
+#[derive(Debug, Clone, Default)]
+pub struct MetricsConfig {
+    /// Address to serve `/metrics` on. `None` disables the listener.
+    pub listen: Option<SocketAddr>,
+}
+
+impl MetricsConfig {
+    fn resolve() -> Result<Self, ConfigError> {
+        let Some(port) = optional_env("METRICS_PORT")? else {
+            return Ok(Self::default());
+        };
+        let host = optional_env("METRICS_HOST")?.unwrap_or_else(|| "127.0.0.1".to_string());
+        let listen =
+            format!("{}:{}", host, port)
+                .parse()
+                .map_err(|e| ConfigError::InvalidValue {
+                    key: "METRICS_PORT".to_string(),
+                    message: format!("invalid listen address {}:{}: {e}", host, port),
+                })?;
+        Ok(Self {
+            listen: Some(listen),
+        })
+    }
+}
+
+/// GitHub tool configuration.
+///
+/// The `github` tool is only registered when at least one repository is
+/// allowed. Entries are `owner/repo` or `owner/*`; a `:read` suffix allows
+/// reading but not commenting.
+#[derive(Clone)]
+pub struct GitHubConfig {
+    /// Repositories the tool may touch (`GITHUB_REPOS`).
+    pub repos: Vec<String>,
+    /// API token. Without one the tool uses the `github_token` secret.
+    pub token: Option<SecretString>,
+    /// REST API base URL (differs for GitHub Enterprise).
+    pub api_url: String,
+}
+
+impl Default for GitHubConfig {
+    fn default() -> Self {
+        Self {
+            repos: Vec::new(),
+            token: None,
+            api_url: "https://api.github.com".to_string(),
+        }
+    }
+}
+
+impl std::fmt::Debug for GitHubConfig {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("GitHubConfig")
+            .field("repos", &self.repos)
+            .field("token", &self.token.is_some())
+            .field("api_url", &self.api_url)
+            .finish()
+    }
+}
+
+impl GitHubConfig {
+    fn resolve() -> Result<Self, ConfigError> {
+        let defaults = Self::default();
+        let repos: Vec<String> = optional_env("GITHUB_REPOS")?
+            .map(|list| {
+                list.split(',')
+                    .map(|r| r.trim().to_string())
+                    .filter(|r| !r.is_empty())
+                    .collect()
+            })
+            .unwrap_or_default();
+        for repo in &repos {
+            let name = repo.strip_suffix(":read").unwrap_or(repo);
+            let valid = name.split_once('/').is_some_and(|(owner, repo)| {
+                !owner.is_empty() && !repo.is_empty() && !repo.contains('/')
+            });
+            if !valid {
+                return Err(ConfigError::InvalidValue {
+                    key: "GITHUB_REPOS".to_string(),
+                    message: format!("'{}' is not owner/repo, owner/*, or owner/repo:read", repo),
+                });
+            }
+        }
+        Ok(Self {
+            repos,
+            token: optional_env("GITHUB_TOKEN")?.map(SecretString::from),
+            api_url: optional_env("GITHUB_API_URL")?
+                .map(|url| url.trim_end_matches('/').to_string())
+                .unwrap_or(defaults.api_url),
+        })
+    }
+
+    /// Whether the `github` tool should be registered.
+    pub fn enabled(&self) -> bool {
+        !self.repos.is_empty()
+    }
+}
+
+/// Home Assistant tool configuration.
+///
+/// The `home_assistant` tool is registered when a URL and at least one
+/// allowed entity are set. Entries are a domain (`light`, `light.*`), an
+/// entity ID (`switch.kettle`), or `*` for everything.
+#[derive(Clone, Default)]
+pub struct HomeAssistantConfig {
+    /// Base URL of the instance, e.g. `http://homeassistant.local:8123`.
+    pub url: Option<String>,
+    /// Long-lived access token. Without one the tool uses the
+    /// `home_assistant_token` secret.
+    pub token: Option<SecretString>,
+    /// Entities and domains the tool may read or control.
+    pub entities: Vec<String>,
+}
+
+impl std::fmt::Debug for HomeAssistantConfig {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("HomeAssistantConfig")
+            .field("url", &self.url)
+            .field("token", &self.token.is_some())
+            .field("entities", &self.entities)
+            .finish()
+    }
+}
+
+impl HomeAssistantConfig {
+    fn resolve() -> Result<Self, ConfigError> {
+        let url = optional_env("HOME_ASSISTANT_URL")?;
+        if let Some(ref url) = url
+            && !(url.starts_with("http://") || url.starts_with("https://"))
+        {
+            return Err(ConfigError::InvalidValue {
+                key: "HOME_ASSISTANT_URL".to_string(),
+                message: "must be an http:// or https:// URL".to_string(),
+            });
+        }
+        Ok(Self {
+            url: url.map(|url| url.trim_end_matches('/').to_string()),
+            token: optional_env("HOME_ASSISTANT_TOKEN")?.map(SecretString::from),
+            entities: optional_env("HOME_ASSISTANT_ENTITIES")?
+                .map(|list| {
+                    list.split(',')
+                        .map(|e| e.trim().to_lowercase())
+                        .filter(|e| !e.is_empty())
+                        .collect()
+                })
+                .unwrap_or_default(),
+        })
+    }
+
+    /// Whether the `home_assistant` tool should be registered.
+    pub fn enabled(&self) -> bool {
+        self.url.is_some() && !self.entities.is_empty()
+    }
+}
+
+/// Docker sandbox configuration.
+#[derive(Debug, Clone)]
+pub struct SandboxModeConfig {
+    /// Whether the Docker sandbox is enabled.
+    pub enabled: bool,
+    /// Sandbox policy: "readonly", "workspace_write", or "full_access".
+    pub policy: String,
+    /// Command timeout in seconds.
+    pub timeout_secs: u64,
+    /// Memory limit in megabytes.
+    pub memory_limit_mb: u64,
+    /// CPU shares (relative weight).
+    pub cpu_shares: u32,
+    /// Docker image for the sandbox.
+    pub image: String,
+    /// Whether to auto-pull the image if not found.
+    pub auto_pull_image: bool,
+    /// Additional domains to allow through the network proxy.
+    pub extra_allowed_domains: Vec<String>,
+}
+
+impl Default for SandboxModeConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            policy: "readonly".to_string(),
+            timeout_secs: 120,
+            memory_limit_mb: 2048,
+            cpu_shares: 1024,
+            image: "ghcr.io/nearai/sandbox:latest".to_string(),
+            auto_pull_image: true,
+            extra_allowed_domains: Vec::new(),
+        }
+    }
+}
+
+impl SandboxModeConfig {
+    fn resolve() -> Result<Self, ConfigError> {
+        let extra_domains = optional_env("SANDBOX_EXTRA_DOMAINS")?
+            .map(|s| s.split(',').map(|d| d.trim().to_string()).collect())
+            .unwrap_or_default();
+
+        Ok(Self {
+            enabled: optional_env("SANDBOX_ENABLED")?
+                .map(|s| s.parse())
+                .transpose()
+                .map_err(|e| ConfigError::InvalidValue {
+                    key: "SANDBOX_ENABLED".to_string(),
+                    message: format!("must be 'true' or 'false': {e}"),
+                })?
+                .unwrap_or(true),
+            policy: optional_env("SANDBOX_POLICY")?.unwrap_or_else(|| "readonly".to_string()),
+            timeout_secs: parse_optional_env("SANDBOX_TIMEOUT_SECS", 120)?,
+            memory_limit_mb: parse_optional_env("SANDBOX_MEMORY_LIMIT_MB", 2048)?,
+            cpu_shares: parse_optional_env("SANDBOX_CPU_SHARES", 1024)?,
+            image: optional_env("SANDBOX_IMAGE")?
+                .unwrap_or_else(|| "ghcr.io/nearai/sandbox:latest".to_string()),
+            auto_pull_image: optional_env("SANDBOX_AUTO_PULL")?
+                .map(|s| s.parse())
+                .transpose()
+                .map_err(|e| ConfigError::InvalidValue {
+                    key: "SANDBOX_AUTO_PULL".to_string(),
                     message: format!("must be 'true' or 'false': {e}"),
                 })?
                 .unwrap_or(true),
@@ -1395,6 +3042,7 @@ pub async fn inject_llm_keys_from_secrets(
         }
         match secrets.get_decrypted(user_id, secret_name).await {
             Ok(decrypted) => {
+                crate::safety::SecretRedactor::global().register(decrypted.expose());
                 injected.insert(env_var.to_string(), decrypted.expose().to_string());
                 tracing::debug!("Loaded secret '{}' for env var '{}'", secret_name, env_var);
             }
@@ -1407,6 +3055,26 @@ pub async fn inject_llm_keys_from_secrets(
     let _ = INJECTED_VARS.set(injected);
 }
 
+/// Load the config file into the overlay `optional_env()` reads. Only the
+/// first load takes effect, like the injected secrets.
+fn load_config_file() -> Result<(), ConfigError> {
+    if FILE_VARS.get().is_some() {
+        return Ok(());
+    }
+    let vars = match crate::config_file::load()? {
+        Some(file) => {
+            tracing::debug!("Loaded config file {}", file.path.display());
+            file.vars
+                .into_iter()
+                .map(|(k, v)| (k.to_string(), v))
+                .collect()
+        }
+        None => HashMap::new(),
+    };
+    let _ = FILE_VARS.set(vars);
+    Ok(())
+}
+
 // Helper functions
 
 fn optional_env(key: &str) -> Result<Option<String>, ConfigError> {
@@ -1422,6 +3090,11 @@ fn optional_env(key: &str) -> Result<Option<String>, ConfigError> {
         }
     }
 
+    // Then the config file
+    if let Some(val) = FILE_VARS.get().and_then(|map| map.get(key)) {
+        return Ok(Some(val.clone()));
+    }
+
     // Fall back to thread-safe overlay (secrets injected from DB)
     if let Some(val) = INJECTED_VARS.get().and_then(|map| map.get(key)) {
         return Ok(Some(val.clone()));
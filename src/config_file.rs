@@ -0,0 +1,696 @@
+//! Declarative configuration file.
+//!
+//! The settings most deployments touch (LLM provider, embeddings, tools,
+//! channels, budgets, heartbeat, storage) can be kept in one TOML file
+//! instead of a page of env vars:
+//!
+//! ```toml
+//! [llm]
+//! backend = "anthropic"
+//! model = "claude-sonnet-4-20250514"
+//! api_key = "${ANTHROPIC_API_KEY}"
+//!
+//! [channels.gateway]
+//! port = 3000
+//! auth_token = "${GATEWAY_TOKEN:-change-me}"
+//!
+//! [budgets]
+//! daily_token_budget = 2000000
+//!
+//! [storage]
+//! backend = "libsql"
+//! path = "/var/lib/ironclaw/ironclaw.db"
+//! ```
+//!
+//! The file is `~/.ironclaw/config.toml`, or the path in `IRONCLAW_CONFIG`.
+//! String values may reference env vars as `${NAME}` or `${NAME:-default}`
+//! (`$$` is a literal `$`). Unknown keys, wrong types, and invalid values are
+//! reported together, each with the key it concerns, and stop startup.
+//!
+//! Every key stands for the env var named in its doc comment, and resolves
+//! with the same precedence: env var > config file > database settings >
+//! built-in default (see [`crate::config`]). Keys left out keep their
+//! defaults.
+
+use std::path::PathBuf;
+
+use serde::Deserialize;
+
+use crate::config::{DatabaseBackend, LlmBackend};
+use crate::error::ConfigError;
+
+/// Env var naming the config file to load instead of the default.
+pub const CONFIG_PATH_ENV: &str = "IRONCLAW_CONFIG";
+
+/// Default location of the config file.
+pub fn default_config_path() -> PathBuf {
+    dirs::home_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join(".ironclaw")
+        .join("config.toml")
+}
+
+/// The config file's schema.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ConfigFile {
+    pub llm: LlmSection,
+    pub embeddings: EmbeddingsSection,
+    pub tools: ToolsSection,
+    pub channels: ChannelsSection,
+    pub budgets: BudgetsSection,
+    pub heartbeat: HeartbeatSection,
+    pub storage: StorageSection,
+}
+
+/// `[llm]`: the chat model provider.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LlmSection {
+    /// `LLM_BACKEND`: nearai, openai, anthropic, ollama, or openai_compatible.
+    pub backend: Option<String>,
+    /// The backend's model (`NEARAI_MODEL`, `OPENAI_MODEL`, ...).
+    pub model: Option<String>,
+    /// The backend's API key (`NEARAI_API_KEY`, `OPENAI_API_KEY`, ...).
+    pub api_key: Option<String>,
+    /// The backend's endpoint (`NEARAI_BASE_URL`, `OLLAMA_BASE_URL`,
+    /// `LLM_BASE_URL`).
+    pub base_url: Option<String>,
+}
+
+/// `[embeddings]`: the embedding provider for semantic search.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct EmbeddingsSection {
+    /// `EMBEDDING_ENABLED`.
+    pub enabled: Option<bool>,
+    /// `EMBEDDING_PROVIDER`: nearai or openai.
+    pub provider: Option<String>,
+    /// `EMBEDDING_MODEL`.
+    pub model: Option<String>,
+}
+
+/// `[tools]`.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ToolsSection {
+    /// `AGENT_DISABLED_TOOLS`: tools hidden from the LLM.
+    pub disabled: Option<Vec<String>>,
+    /// `ALLOW_LOCAL_TOOLS`: filesystem and shell tools outside the sandbox.
+    pub allow_local: Option<bool>,
+}
+
+/// `[channels]`.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ChannelsSection {
+    pub cli: CliSection,
+    pub http: HttpSection,
+    pub gateway: GatewaySection,
+}
+
+/// `[channels.cli]`.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CliSection {
+    /// `CLI_ENABLED`.
+    pub enabled: Option<bool>,
+}
+
+/// `[channels.http]`: the webhook listener, started when a host or port is
+/// set.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct HttpSection {
+    /// `HTTP_HOST`.
+    pub host: Option<String>,
+    /// `HTTP_PORT`.
+    pub port: Option<u16>,
+    /// `HTTP_WEBHOOK_SECRET`.
+    pub webhook_secret: Option<String>,
+}
+
+/// `[channels.gateway]`: the web UI and API.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct GatewaySection {
+    /// `GATEWAY_ENABLED`.
+    pub enabled: Option<bool>,
+    /// `GATEWAY_HOST`.
+    pub host: Option<String>,
+    /// `GATEWAY_PORT`.
+    pub port: Option<u16>,
+    /// `GATEWAY_AUTH_TOKEN`.
+    pub auth_token: Option<String>,
+    /// `GATEWAY_USER_ID`.
+    pub user_id: Option<String>,
+}
+
+/// `[budgets]`: concurrency, timeouts, and per-user limits.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct BudgetsSection {
+    /// `AGENT_MAX_PARALLEL_JOBS`.
+    pub max_parallel_jobs: Option<u32>,
+    /// `AGENT_JOB_TIMEOUT_SECS`.
+    pub job_timeout_secs: Option<u64>,
+    /// `USER_MESSAGES_PER_MINUTE` (0 = unlimited).
+    pub messages_per_minute: Option<u32>,
+    /// `USER_JOBS_PER_HOUR` (0 = unlimited).
+    pub jobs_per_hour: Option<u32>,
+    /// `USER_DAILY_TOKEN_BUDGET` (0 = unlimited).
+    pub daily_token_budget: Option<u64>,
+}
+
+/// `[heartbeat]`.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct HeartbeatSection {
+    /// `HEARTBEAT_ENABLED`.
+    pub enabled: Option<bool>,
+    /// `HEARTBEAT_INTERVAL_SECS`.
+    pub interval_secs: Option<u64>,
+    /// `HEARTBEAT_NOTIFY_CHANNEL`.
+    pub notify_channel: Option<String>,
+    /// `HEARTBEAT_NOTIFY_USER`.
+    pub notify_user: Option<String>,
+}
+
+/// `[storage]`: the database.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct StorageSection {
+    /// `DATABASE_BACKEND`: postgres or libsql.
+    pub backend: Option<String>,
+    /// `DATABASE_URL` for PostgreSQL, `LIBSQL_URL` (Turso) for libSQL.
+    pub url: Option<String>,
+    /// `LIBSQL_PATH`: the local libSQL database file.
+    pub path: Option<String>,
+    /// `LIBSQL_AUTH_TOKEN`.
+    pub auth_token: Option<String>,
+    /// `DATABASE_POOL_SIZE`.
+    pub pool_size: Option<u32>,
+}
+
+/// A loaded config file and the env vars it sets.
+#[derive(Debug, Clone)]
+pub struct LoadedConfigFile {
+    pub path: PathBuf,
+    pub vars: Vec<(&'static str, String)>,
+}
+
+/// Load the config file, if there is one. A file named by
+/// [`CONFIG_PATH_ENV`] must exist; the default one is optional.
+pub fn load() -> Result<Option<LoadedConfigFile>, ConfigError> {
+    let (path, required) = match std::env::var(CONFIG_PATH_ENV) {
+        Ok(path) if !path.is_empty() => (PathBuf::from(path), true),
+        _ => (default_config_path(), false),
+    };
+    if !required && !path.exists() {
+        return Ok(None);
+    }
+    let text = std::fs::read_to_string(&path).map_err(|e| ConfigError::InvalidFile {
+        path: path.clone(),
+        errors: vec![format!("cannot read: {}", e)],
+    })?;
+    let vars = load_str(&text, |name| std::env::var(name).ok()).map_err(|errors| {
+        ConfigError::InvalidFile {
+            path: path.clone(),
+            errors,
+        }
+    })?;
+    Ok(Some(LoadedConfigFile { path, vars }))
+}
+
+/// Parse and validate config file `text`, resolving `${NAME}` references
+/// with `env`, into the env vars it sets.
+pub fn load_str(
+    text: &str,
+    env: impl Fn(&str) -> Option<String>,
+) -> Result<Vec<(&'static str, String)>, Vec<String>> {
+    let file: ConfigFile = toml::from_str(text).map_err(|e| vec![e.to_string()])?;
+    file.resolve(env)
+}
+
+impl ConfigFile {
+    /// Interpolate and validate the file's values into the env vars they
+    /// stand for, or every problem found.
+    pub fn resolve(
+        &self,
+        env: impl Fn(&str) -> Option<String>,
+    ) -> Result<Vec<(&'static str, String)>, Vec<String>> {
+        let mut out = Resolver {
+            env: &env,
+            vars: Vec::new(),
+            errors: Vec::new(),
+        };
+        self.resolve_llm(&mut out);
+        self.resolve_embeddings(&mut out);
+        self.resolve_tools(&mut out);
+        self.resolve_channels(&mut out);
+        self.resolve_budgets(&mut out);
+        self.resolve_heartbeat(&mut out);
+        self.resolve_storage(&mut out);
+        if out.errors.is_empty() {
+            Ok(out.vars)
+        } else {
+            Err(out.errors)
+        }
+    }
+
+    fn resolve_llm(&self, out: &mut Resolver<'_>) {
+        let llm = &self.llm;
+        let backend = out
+            .string("llm.backend", &llm.backend)
+            .and_then(|b| match b.parse::<LlmBackend>() {
+                Ok(backend) => {
+                    out.set("LLM_BACKEND", backend.to_string());
+                    Some(backend)
+                }
+                Err(e) => {
+                    out.error("llm.backend", e);
+                    None
+                }
+            })
+            .unwrap_or_default();
+
+        let (model_var, key_var, url_var) = match backend {
+            LlmBackend::NearAi => (
+                "NEARAI_MODEL",
+                Some("NEARAI_API_KEY"),
+                Some("NEARAI_BASE_URL"),
+            ),
+            LlmBackend::OpenAi => ("OPENAI_MODEL", Some("OPENAI_API_KEY"), None),
+            LlmBackend::Anthropic => ("ANTHROPIC_MODEL", Some("ANTHROPIC_API_KEY"), None),
+            LlmBackend::Ollama => ("OLLAMA_MODEL", None, Some("OLLAMA_BASE_URL")),
+            LlmBackend::OpenAiCompatible => {
+                ("LLM_MODEL", Some("LLM_API_KEY"), Some("LLM_BASE_URL"))
+            }
+            LlmBackend::Groq => ("GROQ_MODEL", Some("GROQ_API_KEY"), None),
+            LlmBackend::Together => ("TOGETHER_MODEL", Some("TOGETHER_API_KEY"), None),
+        };
+        if let Some(model) = out.string("llm.model", &llm.model) {
+            out.set(model_var, model);
+        }
+        if let Some(key) = out.string("llm.api_key", &llm.api_key) {
+            match key_var {
+                Some(var) => out.set(var, key),
+                None => out.error("llm.api_key", format!("not used by backend '{}'", backend)),
+            }
+        }
+        if let Some(url) = out.string("llm.base_url", &llm.base_url) {
+            match url_var {
+                Some(var) => {
+                    if out.url("llm.base_url", &url) {
+                        out.set(var, url);
+                    }
+                }
+                None => out.error(
+                    "llm.base_url",
+                    format!("not supported by backend '{}'", backend),
+                ),
+            }
+        }
+    }
+
+    fn resolve_embeddings(&self, out: &mut Resolver<'_>) {
+        let embeddings = &self.embeddings;
+        out.set_opt("EMBEDDING_ENABLED", embeddings.enabled);
+        if let Some(provider) = out.string("embeddings.provider", &embeddings.provider) {
+            match provider.as_str() {
+                "nearai" | "openai" => out.set("EMBEDDING_PROVIDER", provider),
+                _ => out.error(
+                    "embeddings.provider",
+                    format!("unknown provider '{}', expected nearai or openai", provider),
+                ),
+            }
+        }
+        if let Some(model) = out.string("embeddings.model", &embeddings.model) {
+            out.set("EMBEDDING_MODEL", model);
+        }
+    }
+
+    fn resolve_tools(&self, out: &mut Resolver<'_>) {
+        if let Some(disabled) = &self.tools.disabled {
+            let mut names = Vec::with_capacity(disabled.len());
+            for name in disabled {
+                let Some(name) = out.string("tools.disabled", &Some(name.clone())) else {
+                    continue;
+                };
+                let name = name.trim().to_string();
+                if name.is_empty() || name.contains(',') {
+                    out.error("tools.disabled", format!("invalid tool name '{}'", name));
+                } else {
+                    names.push(name);
+                }
+            }
+            out.set("AGENT_DISABLED_TOOLS", names.join(","));
+        }
+        out.set_opt("ALLOW_LOCAL_TOOLS", self.tools.allow_local);
+    }
+
+    fn resolve_channels(&self, out: &mut Resolver<'_>) {
+        let channels = &self.channels;
+        out.set_opt("CLI_ENABLED", channels.cli.enabled);
+
+        if let Some(host) = out.string("channels.http.host", &channels.http.host) {
+            out.set("HTTP_HOST", host);
+        }
+        if let Some(port) = out.port("channels.http.port", channels.http.port) {
+            out.set("HTTP_PORT", port);
+        }
+        if let Some(secret) = out.string(
+            "channels.http.webhook_secret",
+            &channels.http.webhook_secret,
+        ) {
+            out.set("HTTP_WEBHOOK_SECRET", secret);
+        }
+
+        out.set_opt("GATEWAY_ENABLED", channels.gateway.enabled);
+        if let Some(host) = out.string("channels.gateway.host", &channels.gateway.host) {
+            out.set("GATEWAY_HOST", host);
+        }
+        if let Some(port) = out.port("channels.gateway.port", channels.gateway.port) {
+            out.set("GATEWAY_PORT", port);
+        }
+        if let Some(token) = out.string("channels.gateway.auth_token", &channels.gateway.auth_token)
+        {
+            if token.is_empty() {
+                out.error("channels.gateway.auth_token", "must not be empty");
+            } else {
+                out.set("GATEWAY_AUTH_TOKEN", token);
+            }
+        }
+        if let Some(user) = out.string("channels.gateway.user_id", &channels.gateway.user_id) {
+            out.set("GATEWAY_USER_ID", user);
+        }
+    }
+
+    fn resolve_budgets(&self, out: &mut Resolver<'_>) {
+        let budgets = &self.budgets;
+        if let Some(jobs) = out.positive("budgets.max_parallel_jobs", budgets.max_parallel_jobs) {
+            out.set("AGENT_MAX_PARALLEL_JOBS", jobs);
+        }
+        if let Some(secs) = out.positive("budgets.job_timeout_secs", budgets.job_timeout_secs) {
+            out.set("AGENT_JOB_TIMEOUT_SECS", secs);
+        }
+        out.set_opt("USER_MESSAGES_PER_MINUTE", budgets.messages_per_minute);
+        out.set_opt("USER_JOBS_PER_HOUR", budgets.jobs_per_hour);
+        out.set_opt("USER_DAILY_TOKEN_BUDGET", budgets.daily_token_budget);
+    }
+
+    fn resolve_heartbeat(&self, out: &mut Resolver<'_>) {
+        let heartbeat = &self.heartbeat;
+        out.set_opt("HEARTBEAT_ENABLED", heartbeat.enabled);
+        if let Some(secs) = out.positive("heartbeat.interval_secs", heartbeat.interval_secs) {
+            out.set("HEARTBEAT_INTERVAL_SECS", secs);
+        }
+        if let Some(channel) = out.string("heartbeat.notify_channel", &heartbeat.notify_channel) {
+            out.set("HEARTBEAT_NOTIFY_CHANNEL", channel);
+        }
+        if let Some(user) = out.string("heartbeat.notify_user", &heartbeat.notify_user) {
+            out.set("HEARTBEAT_NOTIFY_USER", user);
+        }
+    }
+
+    fn resolve_storage(&self, out: &mut Resolver<'_>) {
+        let storage = &self.storage;
+        let backend = out
+            .string("storage.backend", &storage.backend)
+            .and_then(|b| match b.parse::<DatabaseBackend>() {
+                Ok(backend) => {
+                    out.set("DATABASE_BACKEND", b);
+                    Some(backend)
+                }
+                Err(e) => {
+                    out.error("storage.backend", e);
+                    None
+                }
+            })
+            .unwrap_or_default();
+
+        let libsql = matches!(backend, DatabaseBackend::LibSql);
+        if let Some(url) = out.string("storage.url", &storage.url) {
+            out.set(if libsql { "LIBSQL_URL" } else { "DATABASE_URL" }, url);
+        }
+        for (key, value, var) in [
+            ("storage.path", &storage.path, "LIBSQL_PATH"),
+            (
+                "storage.auth_token",
+                &storage.auth_token,
+                "LIBSQL_AUTH_TOKEN",
+            ),
+        ] {
+            let Some(value) = out.string(key, value) else {
+                continue;
+            };
+            if libsql {
+                out.set(var, value);
+            } else {
+                out.error(key, "only applies to backend 'libsql'");
+            }
+        }
+        if let Some(size) = out.positive("storage.pool_size", storage.pool_size) {
+            out.set("DATABASE_POOL_SIZE", size);
+        }
+    }
+}
+
+/// Collects the env vars and errors of a file being resolved.
+struct Resolver<'a> {
+    env: &'a dyn Fn(&str) -> Option<String>,
+    vars: Vec<(&'static str, String)>,
+    errors: Vec<String>,
+}
+
+impl Resolver<'_> {
+    fn set(&mut self, var: &'static str, value: impl ToString) {
+        self.vars.push((var, value.to_string()));
+    }
+
+    fn set_opt(&mut self, var: &'static str, value: Option<impl ToString>) {
+        if let Some(value) = value {
+            self.set(var, value);
+        }
+    }
+
+    fn error(&mut self, key: &str, message: impl std::fmt::Display) {
+        self.errors.push(format!("{}: {}", key, message));
+    }
+
+    /// The string at `key` with env var references resolved.
+    fn string(&mut self, key: &str, value: &Option<String>) -> Option<String> {
+        let value = value.as_deref()?;
+        match interpolate(value, self.env) {
+            Ok(value) => Some(value),
+            Err(e) => {
+                self.error(key, e);
+                None
+            }
+        }
+    }
+
+    fn port(&mut self, key: &str, port: Option<u16>) -> Option<u16> {
+        match port? {
+            0 => {
+                self.error(key, "must be a port number from 1 to 65535");
+                None
+            }
+            port => Some(port),
+        }
+    }
+
+    fn positive<T: Default + PartialEq>(&mut self, key: &str, value: Option<T>) -> Option<T> {
+        let value = value?;
+        if value == T::default() {
+            self.error(key, "must be greater than 0");
+            return None;
+        }
+        Some(value)
+    }
+
+    fn url(&mut self, key: &str, url: &str) -> bool {
+        let ok = url.starts_with("http://") || url.starts_with("https://");
+        if !ok {
+            self.error(key, format!("'{}' is not an http(s) URL", url));
+        }
+        ok
+    }
+}
+
+/// Replace `${NAME}` and `${NAME:-default}` in `value` with env vars from
+/// `env`, and `$$` with `$`.
+fn interpolate(value: &str, env: &dyn Fn(&str) -> Option<String>) -> Result<String, String> {
+    let mut out = String::with_capacity(value.len());
+    let mut rest = value;
+    while let Some(i) = rest.find('$') {
+        out.push_str(&rest[..i]);
+        rest = &rest[i..];
+        if let Some(after) = rest.strip_prefix("$$") {
+            out.push('$');
+            rest = after;
+        } else if let Some(after) = rest.strip_prefix("${") {
+            let end = after
+                .find('}')
+                .ok_or_else(|| format!("unterminated '${{' in '{}'", value))?;
+            let reference = &after[..end];
+            let (name, default) = match reference.split_once(":-") {
+                Some((name, default)) => (name, Some(default)),
+                None => (reference, None),
+            };
+            if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+                return Err(format!("invalid env var reference '${{{}}}'", reference));
+            }
+            match env(name).filter(|v| !v.is_empty()) {
+                Some(v) => out.push_str(&v),
+                None => match default {
+                    Some(default) => out.push_str(default),
+                    None => return Err(format!("env var {} is not set", name)),
+                },
+            }
+            rest = &after[end + 1..];
+        } else {
+            out.push('$');
+            rest = &rest[1..];
+        }
+    }
+    out.push_str(rest);
+    Ok(out)
+}
+
+/// Whether the env var `name` holds a secret, for display.
+pub fn is_secret(name: &str) -> bool {
+    name == "DATABASE_URL"
+        || name == "LIBSQL_URL"
+        || ["_KEY", "_TOKEN", "_SECRET", "_PASSWORD"]
+            .iter()
+            .any(|suffix| name.ends_with(suffix))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+
+    fn env(vars: &[(&str, &str)]) -> impl Fn(&str) -> Option<String> {
+        let vars: HashMap<String, String> = vars
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        move |name| vars.get(name).cloned()
+    }
+
+    fn get<'a>(vars: &'a [(&'static str, String)], name: &str) -> Option<&'a str> {
+        vars.iter()
+            .find(|(k, _)| *k == name)
+            .map(|(_, v)| v.as_str())
+    }
+
+    #[test]
+    fn test_maps_sections_to_env_vars() {
+        let text = r#"
+            [llm]
+            backend = "anthropic"
+            model = "claude-sonnet-4-20250514"
+            api_key = "${ANTHROPIC_KEY}"
+
+            [tools]
+            disabled = ["shell", "http"]
+
+            [channels.gateway]
+            port = 4000
+            auth_token = "${GATEWAY_TOKEN:-change-me}"
+
+            [budgets]
+            daily_token_budget = 2000000
+
+            [heartbeat]
+            interval_secs = 900
+
+            [storage]
+            backend = "libsql"
+            path = "/data/ironclaw.db"
+        "#;
+        let vars = load_str(text, env(&[("ANTHROPIC_KEY", "sk-ant")])).unwrap();
+        assert_eq!(get(&vars, "LLM_BACKEND"), Some("anthropic"));
+        assert_eq!(
+            get(&vars, "ANTHROPIC_MODEL"),
+            Some("claude-sonnet-4-20250514")
+        );
+        assert_eq!(get(&vars, "ANTHROPIC_API_KEY"), Some("sk-ant"));
+        assert_eq!(get(&vars, "AGENT_DISABLED_TOOLS"), Some("shell,http"));
+        assert_eq!(get(&vars, "GATEWAY_PORT"), Some("4000"));
+        assert_eq!(get(&vars, "GATEWAY_AUTH_TOKEN"), Some("change-me"));
+        assert_eq!(get(&vars, "USER_DAILY_TOKEN_BUDGET"), Some("2000000"));
+        assert_eq!(get(&vars, "HEARTBEAT_INTERVAL_SECS"), Some("900"));
+        assert_eq!(get(&vars, "DATABASE_BACKEND"), Some("libsql"));
+        assert_eq!(get(&vars, "LIBSQL_PATH"), Some("/data/ironclaw.db"));
+        // Keys left out set nothing.
+        assert_eq!(get(&vars, "HEARTBEAT_ENABLED"), None);
+        assert!(load_str("", env(&[])).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_reports_every_problem_with_its_key() {
+        let text = r#"
+            [llm]
+            backend = "ollama"
+            api_key = "${MISSING}"
+            base_url = "localhost:11434"
+
+            [heartbeat]
+            interval_secs = 0
+
+            [storage]
+            auth_token = "secret"
+        "#;
+        let errors = load_str(text, env(&[])).unwrap_err();
+        assert_eq!(
+            errors,
+            vec![
+                "llm.api_key: env var MISSING is not set",
+                "llm.base_url: 'localhost:11434' is not an http(s) URL",
+                "heartbeat.interval_secs: must be greater than 0",
+                "storage.auth_token: only applies to backend 'libsql'",
+            ]
+        );
+
+        let errors = load_str("[llm]\nbackend = \"gpt\"", env(&[])).unwrap_err();
+        assert!(errors[0].starts_with("llm.backend: invalid LLM backend 'gpt'"));
+    }
+
+    #[test]
+    fn test_unknown_keys_and_wrong_types_are_rejected() {
+        let errors = load_str("[llm]\nbakend = \"openai\"", env(&[])).unwrap_err();
+        assert!(
+            errors[0].contains("unknown field `bakend`"),
+            "{}",
+            errors[0]
+        );
+
+        let errors = load_str("[channels.gateway]\nport = \"3000\"", env(&[])).unwrap_err();
+        assert!(errors[0].contains("port"), "{}", errors[0]);
+    }
+
+    #[test]
+    fn test_interpolate() {
+        let env = env(&[("HOST", "db.local"), ("EMPTY", "")]);
+        assert_eq!(
+            interpolate("postgres://${HOST}:5432/x", &env).unwrap(),
+            "postgres://db.local:5432/x"
+        );
+        assert_eq!(interpolate("${EMPTY:-fallback}", &env).unwrap(), "fallback");
+        assert_eq!(interpolate("$$HOME and $5", &env).unwrap(), "$HOME and $5");
+        assert!(interpolate("${HOST", &env).is_err());
+        assert!(interpolate("${NOT SET}", &env).is_err());
+    }
+
+    #[test]
+    fn test_is_secret() {
+        assert!(is_secret("ANTHROPIC_API_KEY"));
+        assert!(is_secret("GATEWAY_AUTH_TOKEN"));
+        assert!(is_secret("DATABASE_URL"));
+        assert!(!is_secret("GATEWAY_PORT"));
+    }
+}
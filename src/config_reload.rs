@@ -0,0 +1,516 @@
+//! Config hot-reload.
+//!
+//! Environment variables are fixed for the life of the process, so the
+//! reloadable layer is the settings store: the database settings table
+//! (edited with `ironclaw config set` or the web settings API), or
+//! `~/.ironclaw/settings.json` when running without a database.
+//! [`ConfigReloader`] polls it and applies the changes that are safe while
+//! channels stay connected:
+//!
+//! | Setting | Applied to |
+//! |---------|------------|
+//! | `selected_model` | LLM provider (`set_model`) |
+//! | `agent.disabled_tools` | tool registry |
+//! | `heartbeat.interval_secs` | heartbeat runner |
+//! | `channels.gateway_chat_rate_limit` | web gateway chat limiter |
+//!
+//! A changed snapshot is rebuilt into a full [`Config`] first, so the usual
+//! validation applies; an invalid snapshot is rejected and the running values
+//! stay in place. The only fallible step (switching the model) runs before
+//! anything else is touched, so a change is applied completely or not at all.
+//! Other changed settings are logged as needing a restart.
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+
+use tokio::sync::watch;
+
+use crate::agent::registry::AgentDefinition;
+use crate::channels::web::server::RateLimiter;
+use crate::config::Config;
+use crate::db::Database;
+use crate::llm::LlmProvider;
+use crate::settings::Settings;
+use crate::tools::ToolRegistry;
+
+/// Settings keys whose changes [`ConfigReloader`] applies at runtime.
+pub const RELOADABLE_KEYS: &[&str] = &[
+    "selected_model",
+    "agent.disabled_tools",
+    "heartbeat.interval_secs",
+    "channels.gateway_chat_rate_limit",
+];
+
+/// Errors from a reload attempt. The running configuration is unchanged.
+#[derive(Debug, thiserror::Error)]
+pub enum ReloadError {
+    #[error("Failed to read settings: {0}")]
+    Source(String),
+
+    #[error("Invalid configuration: {0}")]
+    Invalid(String),
+
+    #[error("Failed to apply {setting}: {reason}")]
+    Apply {
+        setting: &'static str,
+        reason: String,
+    },
+}
+
+/// Where reloadable settings are read from.
+pub enum SettingsSource {
+    /// The owner's rows in the settings table.
+    Database {
+        store: Arc<dyn Database>,
+        user_id: String,
+    },
+    /// A `settings.json` file.
+    File(PathBuf),
+}
+
+impl SettingsSource {
+    async fn load(&self) -> Result<Settings, ReloadError> {
+        match self {
+            Self::Database { store, user_id } => store
+                .get_all_settings(user_id)
+                .await
+                .map(|map| Settings::from_db_map(&map))
+                .map_err(|e| ReloadError::Source(e.to_string())),
+            Self::File(path) => {
+                // Unlike `Settings::load_from`, a file that doesn't parse is
+                // an error rather than a silent reset to defaults.
+                let data = std::fs::read_to_string(path)
+                    .map_err(|e| ReloadError::Source(format!("{}: {}", path.display(), e)))?;
+                serde_json::from_str(&data)
+                    .map_err(|e| ReloadError::Invalid(format!("{}: {}", path.display(), e)))
+            }
+        }
+    }
+}
+
+/// The runtime-adjustable subset of [`Config`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReloadableConfig {
+    pub model: String,
+    /// Sorted so reordering the list isn't a change.
+    pub disabled_tools: Vec<String>,
+    pub heartbeat_interval: Duration,
+    /// `None` when the gateway is disabled.
+    pub chat_rate_limit: Option<u64>,
+}
+
+impl ReloadableConfig {
+    pub fn from_config(config: &Config) -> Self {
+        let mut disabled_tools = config.agent.disabled_tools.clone();
+        disabled_tools.sort();
+        disabled_tools.dedup();
+        Self {
+            model: config.llm.model().to_string(),
+            disabled_tools,
+            heartbeat_interval: Duration::from_secs(config.heartbeat.interval_secs),
+            chat_rate_limit: config
+                .channels
+                .gateway
+                .as_ref()
+                .map(|gw| gw.chat_rate_limit_per_minute),
+        }
+    }
+}
+
+/// Result of a successful reload check.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ReloadOutcome {
+    /// Runtime values that were updated.
+    pub applied: Vec<&'static str>,
+    /// Changed settings that only take effect after a restart.
+    pub restart_required: Vec<String>,
+}
+
+/// Polls the settings store and applies safe changes to the running agent.
+pub struct ConfigReloader {
+    source: SettingsSource,
+    current: ReloadableConfig,
+    llm: Option<Arc<dyn LlmProvider>>,
+    tools: Option<Arc<ToolRegistry>>,
+    heartbeat_interval: Option<watch::Sender<Duration>>,
+    chat_rate_limiter: Option<Arc<RateLimiter>>,
+    /// Registered agent whose overrides are reapplied to each snapshot.
+    agent: Option<AgentDefinition>,
+    /// Flattened settings of the snapshot currently in effect.
+    last_settings: Option<HashMap<String, serde_json::Value>>,
+    /// Last snapshot that failed, so it is reported once rather than on
+    /// every poll.
+    rejected: Option<HashMap<String, serde_json::Value>>,
+}
+
+impl ConfigReloader {
+    /// Create a reloader for an agent running with `current`.
+    pub fn new(source: SettingsSource, current: ReloadableConfig) -> Self {
+        Self {
+            source,
+            current,
+            llm: None,
+            tools: None,
+            heartbeat_interval: None,
+            chat_rate_limiter: None,
+            agent: None,
+            last_settings: None,
+            rejected: None,
+        }
+    }
+
+    pub fn with_llm(mut self, llm: Arc<dyn LlmProvider>) -> Self {
+        self.llm = Some(llm);
+        self
+    }
+
+    pub fn with_tools(mut self, tools: Arc<ToolRegistry>) -> Self {
+        self.tools = Some(tools);
+        self
+    }
+
+    /// Publish heartbeat interval changes on `tx`.
+    pub fn with_heartbeat_interval(mut self, tx: watch::Sender<Duration>) -> Self {
+        self.heartbeat_interval = Some(tx);
+        self
+    }
+
+    pub fn with_chat_rate_limiter(mut self, limiter: Arc<RateLimiter>) -> Self {
+        self.chat_rate_limiter = Some(limiter);
+        self
+    }
+
+    /// Keep a registered agent's model override in effect across reloads.
+    pub fn with_agent(mut self, agent: AgentDefinition) -> Self {
+        self.agent = Some(agent);
+        self
+    }
+
+    /// Runtime values currently in effect.
+    pub fn current(&self) -> &ReloadableConfig {
+        &self.current
+    }
+
+    /// Read the settings store once and apply what changed since the last
+    /// check. The first check only records a baseline.
+    pub async fn check(&mut self) -> Result<ReloadOutcome, ReloadError> {
+        let settings = self.source.load().await?;
+        let snapshot = settings.to_db_map();
+
+        match self.last_settings {
+            None => {
+                self.last_settings = Some(snapshot);
+                return Ok(ReloadOutcome::default());
+            }
+            Some(ref previous) if *previous == snapshot => {
+                return Ok(ReloadOutcome::default());
+            }
+            Some(_) if self.rejected.as_ref() == Some(&snapshot) => {
+                return Ok(ReloadOutcome::default());
+            }
+            Some(_) => {}
+        }
+
+        let agent = self.agent.as_ref();
+        let next = Config::build(&settings)
+            .await
+            .map(|mut config| {
+                if let Some(agent) = agent {
+                    config.apply_agent(agent);
+                }
+                ReloadableConfig::from_config(&config)
+            })
+            .map_err(|e| ReloadError::Invalid(e.to_string()));
+        self.update(snapshot, next)
+    }
+
+    /// Move to a new settings snapshot whose validated runtime values are
+    /// `next`, or record it as rejected.
+    fn update(
+        &mut self,
+        snapshot: HashMap<String, serde_json::Value>,
+        next: Result<ReloadableConfig, ReloadError>,
+    ) -> Result<ReloadOutcome, ReloadError> {
+        match next.and_then(|next| self.apply(next)) {
+            Ok(applied) => {
+                let restart_required = self
+                    .last_settings
+                    .as_ref()
+                    .map(|previous| changed_keys(previous, &snapshot))
+                    .unwrap_or_default()
+                    .into_iter()
+                    .filter(|key| !RELOADABLE_KEYS.contains(&key.as_str()))
+                    .collect();
+                self.last_settings = Some(snapshot);
+                self.rejected = None;
+                Ok(ReloadOutcome {
+                    applied,
+                    restart_required,
+                })
+            }
+            Err(e) => {
+                self.rejected = Some(snapshot);
+                Err(e)
+            }
+        }
+    }
+
+    fn apply(&mut self, next: ReloadableConfig) -> Result<Vec<&'static str>, ReloadError> {
+        let mut applied = Vec::new();
+
+        // Fallible, so first: nothing has changed yet if it fails.
+        if next.model != self.current.model
+            && let Some(ref llm) = self.llm
+        {
+            llm.set_model(&next.model).map_err(|e| ReloadError::Apply {
+                setting: "model",
+                reason: e.to_string(),
+            })?;
+            applied.push("model");
+        }
+
+        if next.disabled_tools != self.current.disabled_tools
+            && let Some(ref tools) = self.tools
+        {
+            tools.set_disabled_tools(next.disabled_tools.iter().cloned());
+            applied.push("disabled_tools");
+        }
+
+        if next.heartbeat_interval != self.current.heartbeat_interval
+            && let Some(ref tx) = self.heartbeat_interval
+        {
+            tx.send_replace(next.heartbeat_interval);
+            applied.push("heartbeat_interval");
+        }
+
+        if next.chat_rate_limit != self.current.chat_rate_limit
+            && let (Some(limiter), Some(limit)) = (&self.chat_rate_limiter, next.chat_rate_limit)
+        {
+            limiter.set_max_requests(limit);
+            applied.push("chat_rate_limit");
+        }
+
+        self.current = next;
+        Ok(applied)
+    }
+
+    /// Poll every `interval` until the task is dropped.
+    pub fn spawn(mut self, interval: Duration) -> tokio::task::JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            loop {
+                ticker.tick().await;
+                match self.check().await {
+                    Ok(outcome) => {
+                        if !outcome.applied.is_empty() {
+                            tracing::info!("Config reloaded: {}", outcome.applied.join(", "));
+                        }
+                        if !outcome.restart_required.is_empty() {
+                            tracing::warn!(
+                                "Changed settings take effect after a restart: {}",
+                                outcome.restart_required.join(", ")
+                            );
+                        }
+                    }
+                    Err(ReloadError::Source(e)) => {
+                        tracing::debug!("Config reload skipped: {}", e);
+                    }
+                    Err(e) => {
+                        tracing::error!("Config reload rejected, keeping current config: {}", e);
+                    }
+                }
+            }
+        })
+    }
+}
+
+fn changed_keys(
+    old: &HashMap<String, serde_json::Value>,
+    new: &HashMap<String, serde_json::Value>,
+) -> Vec<String> {
+    let mut keys: Vec<String> = old
+        .keys()
+        .chain(new.keys())
+        .filter(|key| old.get(*key) != new.get(*key))
+        .cloned()
+        .collect();
+    keys.sort();
+    keys.dedup();
+    keys
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::LlmError;
+    use crate::llm::{
+        CompletionRequest, CompletionResponse, ToolCompletionRequest, ToolCompletionResponse,
+    };
+
+    fn base() -> ReloadableConfig {
+        ReloadableConfig {
+            model: "model-a".to_string(),
+            disabled_tools: Vec::new(),
+            heartbeat_interval: Duration::from_secs(1800),
+            chat_rate_limit: Some(30),
+        }
+    }
+
+    /// A reloader that has already seen the default settings.
+    fn reloader(source: SettingsSource) -> ConfigReloader {
+        let mut reloader = ConfigReloader::new(source, base());
+        reloader.last_settings = Some(Settings::default().to_db_map());
+        reloader
+    }
+
+    fn no_file() -> SettingsSource {
+        SettingsSource::File(PathBuf::from("/nonexistent/settings.json"))
+    }
+
+    fn snapshot(edit: impl FnOnce(&mut Settings)) -> HashMap<String, serde_json::Value> {
+        let mut settings = Settings::default();
+        edit(&mut settings);
+        settings.to_db_map()
+    }
+
+    #[test]
+    fn test_applies_safe_changes() {
+        let tools = Arc::new(ToolRegistry::new());
+        let limiter = Arc::new(RateLimiter::new(30, 60));
+        let (tx, rx) = watch::channel(Duration::from_secs(1800));
+        let mut reloader = reloader(no_file())
+            .with_tools(Arc::clone(&tools))
+            .with_heartbeat_interval(tx)
+            .with_chat_rate_limiter(Arc::clone(&limiter));
+
+        let next = ReloadableConfig {
+            disabled_tools: vec!["shell".to_string()],
+            heartbeat_interval: Duration::from_secs(60),
+            chat_rate_limit: Some(5),
+            ..base()
+        };
+        let settings = snapshot(|s| {
+            s.agent.disabled_tools = vec!["shell".to_string()];
+            s.heartbeat.interval_secs = 60;
+            s.channels.gateway_chat_rate_limit = Some(5);
+            s.agent.max_parallel_jobs = 9;
+        });
+
+        let outcome = reloader.update(settings, Ok(next.clone())).unwrap();
+        assert_eq!(
+            outcome.applied,
+            vec!["disabled_tools", "heartbeat_interval", "chat_rate_limit"]
+        );
+        assert_eq!(outcome.restart_required, vec!["agent.max_parallel_jobs"]);
+        assert!(tools.is_disabled("shell"));
+        assert_eq!(*rx.borrow(), Duration::from_secs(60));
+        assert_eq!(limiter.max_requests(), 5);
+        assert_eq!(*reloader.current(), next);
+    }
+
+    #[test]
+    fn test_invalid_config_keeps_current_values() {
+        let tools = Arc::new(ToolRegistry::new());
+        let mut reloader = reloader(no_file()).with_tools(Arc::clone(&tools));
+        let settings = snapshot(|s| s.heartbeat.interval_secs = 0);
+
+        let err = reloader
+            .update(
+                settings.clone(),
+                Err(ReloadError::Invalid("bad interval".to_string())),
+            )
+            .unwrap_err();
+        assert!(matches!(err, ReloadError::Invalid(_)));
+        assert_eq!(*reloader.current(), base());
+        assert_eq!(reloader.rejected, Some(settings));
+        assert_eq!(
+            reloader.last_settings,
+            Some(Settings::default().to_db_map())
+        );
+    }
+
+    #[tokio::test]
+    async fn test_unparseable_file_is_rejected() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("settings.json");
+        std::fs::write(&path, "{ not json").unwrap();
+
+        let mut reloader = reloader(SettingsSource::File(path));
+        assert!(matches!(
+            reloader.check().await,
+            Err(ReloadError::Invalid(_))
+        ));
+        assert_eq!(*reloader.current(), base());
+    }
+
+    #[tokio::test]
+    async fn test_first_check_records_baseline() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("settings.json");
+        let mut settings = Settings::default();
+        settings.heartbeat.interval_secs = 60;
+        std::fs::write(&path, serde_json::to_string(&settings).unwrap()).unwrap();
+
+        let mut reloader = ConfigReloader::new(SettingsSource::File(path), base());
+        assert_eq!(reloader.check().await.unwrap(), ReloadOutcome::default());
+        // Unchanged on the next poll, so nothing to rebuild or apply.
+        assert_eq!(reloader.check().await.unwrap(), ReloadOutcome::default());
+        assert_eq!(*reloader.current(), base());
+    }
+
+    /// Provider that refuses runtime model switches.
+    struct FixedModel;
+
+    #[async_trait::async_trait]
+    impl LlmProvider for FixedModel {
+        fn model_name(&self) -> &str {
+            "model-a"
+        }
+
+        fn cost_per_token(&self) -> (rust_decimal::Decimal, rust_decimal::Decimal) {
+            (rust_decimal::Decimal::ZERO, rust_decimal::Decimal::ZERO)
+        }
+
+        async fn complete(&self, _req: CompletionRequest) -> Result<CompletionResponse, LlmError> {
+            unreachable!()
+        }
+
+        async fn complete_with_tools(
+            &self,
+            _req: ToolCompletionRequest,
+        ) -> Result<ToolCompletionResponse, LlmError> {
+            unreachable!()
+        }
+    }
+
+    #[test]
+    fn test_failed_model_switch_applies_nothing() {
+        let tools = Arc::new(ToolRegistry::new());
+        let mut reloader = reloader(no_file())
+            .with_llm(Arc::new(FixedModel))
+            .with_tools(Arc::clone(&tools));
+
+        let next = ReloadableConfig {
+            model: "model-b".to_string(),
+            disabled_tools: vec!["shell".to_string()],
+            ..base()
+        };
+        let settings = snapshot(|s| {
+            s.selected_model = Some("model-b".to_string());
+            s.agent.disabled_tools = vec!["shell".to_string()];
+        });
+
+        let err = reloader.update(settings, Ok(next)).unwrap_err();
+        assert!(matches!(
+            err,
+            ReloadError::Apply {
+                setting: "model",
+                ..
+            }
+        ));
+        assert!(!tools.is_disabled("shell"));
+        assert_eq!(*reloader.current(), base());
+    }
+}
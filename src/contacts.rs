@@ -0,0 +1,517 @@
+//! Contact book.
+//!
+//! The people the agent knows about: their names, how the user relates to
+//! them, their email addresses, and the handles (sender IDs) they have on
+//! each channel. A request like "message Anna about dinner" is only acted on
+//! once [`ContactBook::resolve`] has narrowed "Anna" down to one contact and
+//! one handle; anything less specific is an error listing the candidates,
+//! so the agent asks instead of guessing.
+//!
+//! Handles are linked automatically as messages arrive: a sender on an
+//! external channel becomes a contact the first time they write, and a
+//! known sender's contact records when they were last seen. A handle never
+//! moves between contacts on its own; a new sender whose display name
+//! matches an existing contact gets no contact until linked explicitly,
+//! since anyone can pick a display name.
+
+use std::sync::Arc;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::db::Database;
+use crate::error::DatabaseError;
+
+/// Channels whose senders are the user themselves rather than other people.
+const LOCAL_CHANNELS: &[&str] = &["repl", "gateway", "http", "cli"];
+
+/// Errors from contact book operations.
+#[derive(Debug, thiserror::Error)]
+pub enum ContactError {
+    #[error(transparent)]
+    Database(#[from] DatabaseError),
+
+    #[error("No contact matches '{0}'")]
+    NotFound(String),
+
+    #[error("'{query}' matches several contacts: {}", .candidates.join(", "))]
+    Ambiguous {
+        query: String,
+        candidates: Vec<String>,
+    },
+
+    #[error("{name} has no handle on {channel}")]
+    NoHandle { name: String, channel: String },
+
+    #[error("{name} can be reached on several channels ({}); pick one", .channels.join(", "))]
+    ChannelRequired { name: String, channels: Vec<String> },
+
+    #[error("{channel} handle '{handle}' already belongs to {owner}")]
+    HandleTaken {
+        channel: String,
+        handle: String,
+        owner: String,
+    },
+
+    #[error("Invalid contact: {0}")]
+    Invalid(String),
+}
+
+/// A sender ID on one channel.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ContactHandle {
+    pub channel: String,
+    pub handle: String,
+}
+
+impl ContactHandle {
+    pub fn new(channel: impl Into<String>, handle: impl Into<String>) -> Self {
+        Self {
+            channel: channel.into(),
+            handle: handle.into(),
+        }
+    }
+}
+
+/// A person in the contact book.
+#[derive(Debug, Clone, Serialize)]
+pub struct Contact {
+    pub id: Uuid,
+    pub user_id: String,
+    pub name: String,
+    /// How the user knows them ("sister", "dentist", ...); empty if unknown.
+    pub relationship: String,
+    pub emails: Vec<String>,
+    pub handles: Vec<ContactHandle>,
+    pub notes: String,
+    /// When they last sent a message on a linked handle.
+    pub last_seen_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl Contact {
+    pub fn new(user_id: impl Into<String>, name: impl Into<String>) -> Self {
+        let now = Utc::now();
+        Self {
+            id: Uuid::new_v4(),
+            user_id: user_id.into(),
+            name: name.into(),
+            relationship: String::new(),
+            emails: Vec::new(),
+            handles: Vec::new(),
+            notes: String::new(),
+            last_seen_at: None,
+            created_at: now,
+            updated_at: now,
+        }
+    }
+
+    /// Whether `handle` on `channel` is linked to this contact.
+    pub fn has_handle(&self, channel: &str, handle: &str) -> bool {
+        self.handles
+            .iter()
+            .any(|h| h.channel == channel && h.handle == handle)
+    }
+
+    /// Link `handle` on `channel`; does nothing if already linked.
+    pub fn link(&mut self, channel: &str, handle: &str) {
+        if !self.has_handle(channel, handle) {
+            self.handles.push(ContactHandle::new(channel, handle));
+        }
+    }
+
+    /// Unlink `handle` on `channel`. Returns whether it was linked.
+    pub fn unlink(&mut self, channel: &str, handle: &str) -> bool {
+        let before = self.handles.len();
+        self.handles
+            .retain(|h| !(h.channel == channel && h.handle == handle));
+        self.handles.len() != before
+    }
+
+    /// How well `query` names this contact, if at all.
+    fn matches(&self, query: &str) -> Option<Match> {
+        let key = name_key(query);
+        if key.is_empty() {
+            return None;
+        }
+        let name = name_key(&self.name);
+        if name == key
+            || self
+                .emails
+                .iter()
+                .any(|e| e.eq_ignore_ascii_case(query.trim()))
+            || self.handles.iter().any(|h| h.handle == query.trim())
+        {
+            return Some(Match::Exact);
+        }
+        if name.split(' ').any(|part| part == key) {
+            return Some(Match::Word);
+        }
+        if name.contains(&key) || self.relationship.to_lowercase() == key {
+            return Some(Match::Partial);
+        }
+        None
+    }
+}
+
+/// Strength of a query match; stronger matches hide weaker ones.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Match {
+    Partial,
+    Word,
+    Exact,
+}
+
+/// A resolved message recipient.
+#[derive(Debug, Clone, Serialize)]
+pub struct Recipient {
+    pub contact: Contact,
+    pub channel: String,
+    pub handle: String,
+}
+
+/// Contacts of one user.
+pub struct ContactBook {
+    db: Arc<dyn Database>,
+    user_id: String,
+}
+
+impl ContactBook {
+    pub fn new(db: Arc<dyn Database>, user_id: impl Into<String>) -> Self {
+        Self {
+            db,
+            user_id: user_id.into(),
+        }
+    }
+
+    /// Owner of the contacts.
+    pub fn user_id(&self) -> &str {
+        &self.user_id
+    }
+
+    /// All contacts, ordered by name.
+    pub async fn list(&self) -> Result<Vec<Contact>, ContactError> {
+        Ok(self.db.list_contacts(&self.user_id).await?)
+    }
+
+    /// A contact by ID.
+    pub async fn get(&self, id: Uuid) -> Result<Option<Contact>, ContactError> {
+        Ok(self
+            .db
+            .get_contact(id)
+            .await?
+            .filter(|c| c.user_id == self.user_id))
+    }
+
+    /// Contacts matching `query` by name, relationship, email, or handle,
+    /// keeping only the best kind of match.
+    pub async fn find(&self, query: &str) -> Result<Vec<Contact>, ContactError> {
+        let mut matched: Vec<(Match, Contact)> = self
+            .list()
+            .await?
+            .into_iter()
+            .filter_map(|c| c.matches(query).map(|m| (m, c)))
+            .collect();
+        let best = matched.iter().map(|(m, _)| *m).max();
+        matched.retain(|(m, _)| Some(*m) == best);
+        Ok(matched.into_iter().map(|(_, c)| c).collect())
+    }
+
+    /// The one contact `query` names, by ID or as in [`find`](Self::find).
+    pub async fn find_one(&self, query: &str) -> Result<Contact, ContactError> {
+        if let Ok(id) = query.trim().parse::<Uuid>() {
+            return self
+                .get(id)
+                .await?
+                .ok_or_else(|| ContactError::NotFound(query.to_string()));
+        }
+        let mut found = self.find(query).await?;
+        match found.len() {
+            0 => Err(ContactError::NotFound(query.to_string())),
+            1 => Ok(found.remove(0)),
+            _ => Err(ContactError::Ambiguous {
+                query: query.to_string(),
+                candidates: found.iter().map(describe).collect(),
+            }),
+        }
+    }
+
+    /// Save a new contact.
+    pub async fn create(&self, mut contact: Contact) -> Result<Contact, ContactError> {
+        contact.user_id = self.user_id.clone();
+        self.validate(&contact).await?;
+        self.db.create_contact(&contact).await?;
+        Ok(contact)
+    }
+
+    /// Save changes to an existing contact.
+    pub async fn update(&self, mut contact: Contact) -> Result<Contact, ContactError> {
+        if self.get(contact.id).await?.is_none() {
+            return Err(ContactError::NotFound(contact.id.to_string()));
+        }
+        self.validate(&contact).await?;
+        contact.updated_at = Utc::now();
+        self.db.update_contact(&contact).await?;
+        Ok(contact)
+    }
+
+    /// Delete a contact. Returns whether it existed.
+    pub async fn delete(&self, id: Uuid) -> Result<bool, ContactError> {
+        if self.get(id).await?.is_none() {
+            return Ok(false);
+        }
+        Ok(self.db.delete_contact(id).await?)
+    }
+
+    /// The contact and handle a message to `query` should go to, on
+    /// `channel` or else on the contact's only channel.
+    pub async fn resolve(
+        &self,
+        query: &str,
+        channel: Option<&str>,
+    ) -> Result<Recipient, ContactError> {
+        let contact = self.find_one(query).await?;
+        let handles: Vec<&ContactHandle> = contact
+            .handles
+            .iter()
+            .filter(|h| channel.is_none_or(|c| h.channel == c))
+            .collect();
+        let (channel, handle) = match handles.as_slice() {
+            [only] => (only.channel.clone(), only.handle.clone()),
+            [] => {
+                return Err(ContactError::NoHandle {
+                    name: contact.name,
+                    channel: channel.unwrap_or("any channel").to_string(),
+                });
+            }
+            several => {
+                let mut channels: Vec<String> = several.iter().map(|h| h.channel.clone()).collect();
+                channels.dedup();
+                if channels.len() > 1 {
+                    return Err(ContactError::ChannelRequired {
+                        name: contact.name,
+                        channels,
+                    });
+                }
+                // Several handles on one channel: the first linked wins.
+                (several[0].channel.clone(), several[0].handle.clone())
+            }
+        };
+        Ok(Recipient {
+            contact,
+            channel,
+            handle,
+        })
+    }
+
+    /// Link an incoming message's sender to the contact book.
+    ///
+    /// A known handle marks its contact as seen; an unknown one becomes a
+    /// new contact named after the sender, unless that name is already
+    /// taken. Returns the sender's contact, if any.
+    pub async fn observe(
+        &self,
+        channel: &str,
+        handle: &str,
+        display_name: Option<&str>,
+    ) -> Result<Option<Contact>, ContactError> {
+        if LOCAL_CHANNELS.contains(&channel) || handle.is_empty() {
+            return Ok(None);
+        }
+        let contacts = self.list().await?;
+        if let Some(mut contact) = contacts
+            .iter()
+            .find(|c| c.has_handle(channel, handle))
+            .cloned()
+        {
+            contact.last_seen_at = Some(Utc::now());
+            self.db.update_contact(&contact).await?;
+            return Ok(Some(contact));
+        }
+
+        let name = display_name
+            .map(str::trim)
+            .filter(|n| !n.is_empty())
+            .unwrap_or(handle);
+        if contacts.iter().any(|c| name_key(&c.name) == name_key(name)) {
+            tracing::debug!(
+                "Not linking {} sender '{}': a contact named '{}' already exists",
+                channel,
+                handle,
+                name
+            );
+            return Ok(None);
+        }
+        let mut contact = Contact::new(&self.user_id, name);
+        contact.link(channel, handle);
+        contact.last_seen_at = Some(Utc::now());
+        self.db.create_contact(&contact).await?;
+        tracing::info!("Added contact '{}' from {}", contact.name, channel);
+        Ok(Some(contact))
+    }
+
+    /// Check `contact` before saving: it needs a name, and its handles
+    /// can't belong to anyone else.
+    async fn validate(&self, contact: &Contact) -> Result<(), ContactError> {
+        if contact.name.trim().is_empty() {
+            return Err(ContactError::Invalid("name is empty".to_string()));
+        }
+        if let Some(h) = contact
+            .handles
+            .iter()
+            .find(|h| h.channel.trim().is_empty() || h.handle.trim().is_empty())
+        {
+            return Err(ContactError::Invalid(format!(
+                "incomplete handle '{}' on '{}'",
+                h.handle, h.channel
+            )));
+        }
+        for other in self.list().await? {
+            if other.id == contact.id {
+                continue;
+            }
+            if let Some(h) = contact
+                .handles
+                .iter()
+                .find(|h| other.has_handle(&h.channel, &h.handle))
+            {
+                return Err(ContactError::HandleTaken {
+                    channel: h.channel.clone(),
+                    handle: h.handle.clone(),
+                    owner: other.name,
+                });
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Key names are compared by: lowercased with whitespace collapsed.
+fn name_key(name: &str) -> String {
+    name.split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase()
+}
+
+/// A contact as shown among ambiguous matches.
+fn describe(contact: &Contact) -> String {
+    if contact.relationship.is_empty() {
+        contact.name.clone()
+    } else {
+        format!("{} ({})", contact.name, contact.relationship)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_matches_prefer_exact_names() {
+        let mut anna = Contact::new("default", "Anna  Schmidt");
+        anna.relationship = "sister".to_string();
+        anna.emails.push("anna@example.com".to_string());
+        anna.link("telegram", "4242");
+
+        assert_eq!(anna.matches("anna schmidt"), Some(Match::Exact));
+        assert_eq!(anna.matches("ANNA@example.com"), Some(Match::Exact));
+        assert_eq!(anna.matches("4242"), Some(Match::Exact));
+        assert_eq!(anna.matches("Anna"), Some(Match::Word));
+        assert_eq!(anna.matches("Sister"), Some(Match::Partial));
+        assert_eq!(anna.matches("Bob"), None);
+        assert_eq!(anna.matches("  "), None);
+
+        assert!(anna.unlink("telegram", "4242"));
+        assert!(!anna.unlink("telegram", "4242"));
+    }
+
+    #[cfg(feature = "libsql")]
+    #[tokio::test]
+    async fn test_contacts_resolve_and_link_senders() {
+        use crate::db::libsql_backend::test_db;
+
+        let (db, _dir) = test_db().await;
+        let book = ContactBook::new(db, "default");
+
+        // A new sender becomes a contact; messages from them mark it seen.
+        let anna = book
+            .observe("telegram", "4242", Some("Anna Schmidt"))
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(anna.has_handle("telegram", "4242"));
+        assert!(
+            book.observe("repl", "default", None)
+                .await
+                .unwrap()
+                .is_none()
+        );
+        let seen = book
+            .observe("telegram", "4242", None)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(seen.id, anna.id);
+
+        // A display name alone doesn't link to an existing contact.
+        assert!(
+            book.observe("slack", "U99", Some("anna schmidt"))
+                .await
+                .unwrap()
+                .is_none()
+        );
+
+        let recipient = book.resolve("Anna", None).await.unwrap();
+        assert_eq!(
+            (recipient.channel.as_str(), recipient.handle.as_str()),
+            ("telegram", "4242")
+        );
+        assert!(matches!(
+            book.resolve("Anna", Some("slack")).await,
+            Err(ContactError::NoHandle { .. })
+        ));
+
+        let mut anna_k = Contact::new("ignored", "Anna Kowalski");
+        anna_k.link("slack", "U01");
+        let anna_k = book.create(anna_k).await.unwrap();
+        assert_eq!(anna_k.user_id, "default");
+        assert!(matches!(
+            book.resolve("anna", None).await,
+            Err(ContactError::Ambiguous { ref candidates, .. }) if candidates.len() == 2
+        ));
+        assert_eq!(
+            book.resolve("Anna Kowalski", None).await.unwrap().handle,
+            "U01"
+        );
+
+        // A handle belongs to one contact.
+        let mut taken = anna_k.clone();
+        taken.link("telegram", "4242");
+        assert!(matches!(
+            book.update(taken).await,
+            Err(ContactError::HandleTaken { .. })
+        ));
+
+        let mut anna = book.get(anna.id).await.unwrap().unwrap();
+        anna.link("slack", "U02");
+        let anna = book.update(anna).await.unwrap();
+        assert!(matches!(
+            book.resolve("Anna Schmidt", None).await,
+            Err(ContactError::ChannelRequired { .. })
+        ));
+        assert_eq!(
+            book.resolve(&anna.id.to_string(), Some("slack"))
+                .await
+                .unwrap()
+                .handle,
+            "U02"
+        );
+
+        assert!(book.delete(anna.id).await.unwrap());
+        assert!(!book.delete(anna.id).await.unwrap());
+        assert_eq!(book.list().await.unwrap().len(), 1);
+    }
+}
@@ -1,10 +1,14 @@
 //! Context manager for handling multiple job contexts.
 
 use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
 
 use tokio::sync::RwLock;
 use uuid::Uuid;
 
+use crate::agent::UserLimiter;
 use crate::context::{JobContext, Memory};
 use crate::error::JobError;
 
@@ -16,6 +20,12 @@ pub struct ContextManager {
     memories: RwLock<HashMap<Uuid, Memory>>,
     /// Maximum concurrent jobs.
     max_jobs: usize,
+    /// Directory holding each job's temp directory, if jobs get one.
+    temp_root: Option<PathBuf>,
+    /// Per-user limits checked before a job is created.
+    user_limits: Option<Arc<UserLimiter>>,
+    /// Cleared when the agent starts shutting down.
+    accepting: AtomicBool,
 }
 
 impl ContextManager {
@@ -25,9 +35,58 @@ impl ContextManager {
             contexts: RwLock::new(HashMap::new()),
             memories: RwLock::new(HashMap::new()),
             max_jobs,
+            temp_root: None,
+            user_limits: None,
+            accepting: AtomicBool::new(true),
         }
     }
 
+    /// Give each job a private temp directory under `root`.
+    ///
+    /// Directories left behind by a previous run (the process exited before
+    /// its jobs finished) are removed.
+    pub fn with_temp_root(mut self, root: PathBuf) -> Self {
+        if let Ok(entries) = std::fs::read_dir(&root) {
+            for entry in entries.flatten() {
+                let is_job_dir = entry
+                    .file_name()
+                    .to_str()
+                    .is_some_and(|name| Uuid::parse_str(name).is_ok());
+                if is_job_dir && let Err(e) = std::fs::remove_dir_all(entry.path()) {
+                    tracing::warn!(
+                        "Failed to remove stale job temp dir {}: {}",
+                        entry.path().display(),
+                        e
+                    );
+                }
+            }
+        }
+        self.temp_root = Some(root);
+        self
+    }
+
+    /// Enforce per-user limits on job creation. The limiter is shared with
+    /// the agent, which checks messages and tokens against it.
+    pub fn with_user_limits(mut self, limits: Arc<UserLimiter>) -> Self {
+        self.user_limits = Some(limits);
+        self
+    }
+
+    /// The per-user limiter, if limits are enforced.
+    pub fn user_limits(&self) -> Option<&Arc<UserLimiter>> {
+        self.user_limits.as_ref()
+    }
+
+    /// Refuse new jobs from now on. Existing jobs are unaffected.
+    pub fn stop_accepting(&self) {
+        self.accepting.store(false, Ordering::SeqCst);
+    }
+
+    /// Whether new jobs are still accepted.
+    pub fn is_accepting(&self) -> bool {
+        self.accepting.load(Ordering::SeqCst)
+    }
+
     /// Create a new job context.
     pub async fn create_job(
         &self,
@@ -45,6 +104,11 @@ impl ContextManager {
         title: impl Into<String>,
         description: impl Into<String>,
     ) -> Result<Uuid, JobError> {
+        let user_id = user_id.into();
+        if !self.is_accepting() {
+            return Err(JobError::ShuttingDown);
+        }
+
         // Hold write lock for the entire check-insert to prevent TOCTOU races
         // where two concurrent calls both pass the active_count check.
         let mut contexts = self.contexts.write().await;
@@ -53,6 +117,9 @@ impl ContextManager {
         if active_count >= self.max_jobs {
             return Err(JobError::MaxJobsExceeded { max: self.max_jobs });
         }
+        if let Some(ref limits) = self.user_limits {
+            limits.check_job(&user_id)?;
+        }
 
         let context = JobContext::with_user(user_id, title, description);
         let job_id = context.job_id;
@@ -62,6 +129,17 @@ impl ContextManager {
         let memory = Memory::new(job_id);
         self.memories.write().await.insert(job_id, memory);
 
+        if let Some(ref root) = self.temp_root {
+            match create_temp_dir(&root.join(job_id.to_string())).await {
+                Ok(dir) => {
+                    if let Some(context) = self.contexts.write().await.get_mut(&job_id) {
+                        context.temp_dir = Some(dir);
+                    }
+                }
+                Err(e) => tracing::warn!("Failed to create temp dir for job {}: {}", job_id, e),
+            }
+        }
+
         Ok(job_id)
     }
 
@@ -157,14 +235,30 @@ impl ContextManager {
             .count()
     }
 
+    /// Delete a job's temp directory, e.g. once it has finished.
+    pub async fn release_temp_dir(&self, job_id: Uuid) {
+        let dir = self
+            .contexts
+            .write()
+            .await
+            .get_mut(&job_id)
+            .and_then(|c| c.temp_dir.take());
+        if let Some(dir) = dir {
+            remove_temp_dir(&dir).await;
+        }
+    }
+
     /// Remove a completed job (cleanup).
     pub async fn remove_job(&self, job_id: Uuid) -> Result<(JobContext, Memory), JobError> {
-        let context = self
+        let mut context = self
             .contexts
             .write()
             .await
             .remove(&job_id)
             .ok_or(JobError::NotFound { id: job_id })?;
+        if let Some(dir) = context.temp_dir.take() {
+            remove_temp_dir(&dir).await;
+        }
 
         let memory = self
             .memories
@@ -259,6 +353,25 @@ pub struct ContextSummary {
     pub cancelled: usize,
 }
 
+/// Create `dir`, readable only by us.
+async fn create_temp_dir(dir: &Path) -> std::io::Result<PathBuf> {
+    tokio::fs::create_dir_all(dir).await?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        tokio::fs::set_permissions(dir, std::fs::Permissions::from_mode(0o700)).await?;
+    }
+    Ok(dir.to_path_buf())
+}
+
+async fn remove_temp_dir(dir: &Path) {
+    if let Err(e) = tokio::fs::remove_dir_all(dir).await
+        && e.kind() != std::io::ErrorKind::NotFound
+    {
+        tracing::warn!("Failed to remove job temp dir {}: {}", dir.display(), e);
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -272,6 +385,34 @@ mod tests {
         assert_eq!(context.title, "Test");
     }
 
+    #[tokio::test]
+    async fn test_job_temp_dirs() {
+        let root = tempfile::tempdir().unwrap();
+        let stale = root.path().join(Uuid::new_v4().to_string());
+        std::fs::create_dir(&stale).unwrap();
+        let unrelated = root.path().join("keep-me");
+        std::fs::create_dir(&unrelated).unwrap();
+
+        let manager = ContextManager::new(5).with_temp_root(root.path().to_path_buf());
+        assert!(!stale.exists());
+        assert!(unrelated.exists());
+
+        let a = manager.create_job("A", "Desc").await.unwrap();
+        let b = manager.create_job("B", "Desc").await.unwrap();
+        let dir_a = manager.get_context(a).await.unwrap().temp_dir.unwrap();
+        let dir_b = manager.get_context(b).await.unwrap().temp_dir.unwrap();
+        assert_ne!(dir_a, dir_b);
+        assert!(dir_a.is_dir());
+        std::fs::write(dir_a.join("out.txt"), "x").unwrap();
+
+        manager.release_temp_dir(a).await;
+        assert!(!dir_a.exists());
+        assert!(manager.get_context(a).await.unwrap().temp_dir.is_none());
+
+        manager.remove_job(b).await.unwrap();
+        assert!(!dir_b.exists());
+    }
+
     #[tokio::test]
     async fn test_create_job_for_user_sets_user_id() {
         let manager = ContextManager::new(5);
@@ -307,6 +448,42 @@ mod tests {
         assert!(matches!(result, Err(JobError::MaxJobsExceeded { max: 2 })));
     }
 
+    #[tokio::test]
+    async fn test_user_jobs_per_hour_limit() {
+        let limits = Arc::new(UserLimiter::new(crate::config::UserLimitsConfig {
+            jobs_per_hour: 1,
+            ..Default::default()
+        }));
+        let manager = ContextManager::new(5).with_user_limits(limits);
+
+        manager
+            .create_job_for_user("alice", "Job 1", "Desc")
+            .await
+            .unwrap();
+        let result = manager.create_job_for_user("alice", "Job 2", "Desc").await;
+        assert!(matches!(result, Err(JobError::RateLimited(_))));
+
+        // Other users are unaffected
+        manager
+            .create_job_for_user("bob", "Job 1", "Desc")
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_stop_accepting_refuses_new_jobs() {
+        let manager = ContextManager::new(5);
+        let existing = manager.create_job("Job 1", "Desc").await.unwrap();
+
+        manager.stop_accepting();
+        assert!(!manager.is_accepting());
+        let result = manager.create_job("Job 2", "Desc").await;
+        assert!(matches!(result, Err(JobError::ShuttingDown)));
+
+        // Jobs created before shutdown are still there
+        assert!(manager.get_context(existing).await.is_ok());
+    }
+
     #[tokio::test]
     async fn test_update_context() {
         let manager = ContextManager::new(5);
@@ -5,11 +5,14 @@
 //! - Action history
 //! - State machine
 //! - Resource tracking
+//! - Scratch buffer for passing data between tool calls
 
 mod manager;
 mod memory;
+mod scratch;
 mod state;
 
 pub use manager::ContextManager;
 pub use memory::{ActionRecord, ConversationMemory, Memory};
-pub use state::{JobContext, JobState, StateTransition};
+pub use scratch::{ScratchBuffer, ScratchEntryInfo, ScratchError};
+pub use state::{JobContext, JobState, SessionScope, StateTransition};
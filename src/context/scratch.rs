@@ -0,0 +1,203 @@
+//! Per-job scratch buffer.
+//!
+//! Tools stash large intermediate results (a fetched page, a diff, an API
+//! response) under a key, and later tool calls in the same job pick them up
+//! by key, so the content never has to pass through the LLM context. The
+//! buffer lives on [`JobContext`](super::JobContext); clones of a context
+//! share it. In chat it belongs to the thread, so it survives across turns
+//! and approvals.
+
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+
+use chrono::{DateTime, Utc};
+
+/// Largest single entry, in bytes.
+pub const MAX_ENTRY_BYTES: usize = 5 * 1024 * 1024;
+
+/// Largest total size of a buffer, in bytes.
+pub const MAX_TOTAL_BYTES: usize = 20 * 1024 * 1024;
+
+/// Most entries in a buffer.
+pub const MAX_ENTRIES: usize = 64;
+
+/// Errors from stashing.
+#[derive(Debug, thiserror::Error)]
+pub enum ScratchError {
+    #[error("invalid scratch key '{0}': use letters, digits, '-', '_', '.' (max 64)")]
+    InvalidKey(String),
+
+    #[error("'{key}' is {size} bytes; scratch entries are limited to {max}")]
+    TooLarge {
+        key: String,
+        size: usize,
+        max: usize,
+    },
+
+    #[error("scratch buffer is full ({entries} entries, {bytes} bytes); unstash something first")]
+    Full { entries: usize, bytes: usize },
+}
+
+/// A stashed value.
+#[derive(Debug, Clone)]
+struct Entry {
+    value: String,
+    stashed_at: DateTime<Utc>,
+}
+
+/// Listing of a stashed value, without its content.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct ScratchEntryInfo {
+    pub key: String,
+    pub bytes: usize,
+    pub lines: usize,
+    pub stashed_at: DateTime<Utc>,
+}
+
+/// Shared key-value buffer for one job. Cloning shares the contents.
+#[derive(Clone, Default)]
+pub struct ScratchBuffer {
+    entries: Arc<Mutex<BTreeMap<String, Entry>>>,
+}
+
+impl std::fmt::Debug for ScratchBuffer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ScratchBuffer")
+            .field(
+                "keys",
+                &self.list().iter().map(|e| &e.key).collect::<Vec<_>>(),
+            )
+            .finish()
+    }
+}
+
+impl ScratchBuffer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, BTreeMap<String, Entry>> {
+        // A panic while holding the lock can't leave the map half-updated.
+        self.entries.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Store `value` under `key`, replacing any previous value.
+    pub fn stash(&self, key: &str, value: impl Into<String>) -> Result<(), ScratchError> {
+        if !is_valid_key(key) {
+            return Err(ScratchError::InvalidKey(key.to_string()));
+        }
+        let value = value.into();
+        if value.len() > MAX_ENTRY_BYTES {
+            return Err(ScratchError::TooLarge {
+                key: key.to_string(),
+                size: value.len(),
+                max: MAX_ENTRY_BYTES,
+            });
+        }
+
+        let mut entries = self.lock();
+        let replaced = entries.get(key).map_or(0, |e| e.value.len());
+        let bytes: usize = entries.values().map(|e| e.value.len()).sum::<usize>() - replaced;
+        let count = entries.len() - usize::from(entries.contains_key(key));
+        if count + 1 > MAX_ENTRIES || bytes + value.len() > MAX_TOTAL_BYTES {
+            return Err(ScratchError::Full {
+                entries: entries.len(),
+                bytes,
+            });
+        }
+        entries.insert(
+            key.to_string(),
+            Entry {
+                value,
+                stashed_at: Utc::now(),
+            },
+        );
+        Ok(())
+    }
+
+    /// Remove and return the value under `key`.
+    pub fn unstash(&self, key: &str) -> Option<String> {
+        self.lock().remove(key).map(|e| e.value)
+    }
+
+    /// The value under `key`, leaving it in place.
+    pub fn get(&self, key: &str) -> Option<String> {
+        self.lock().get(key).map(|e| e.value.clone())
+    }
+
+    /// Whether something is stashed under `key`.
+    pub fn contains(&self, key: &str) -> bool {
+        self.lock().contains_key(key)
+    }
+
+    /// Stashed entries, by key.
+    pub fn list(&self) -> Vec<ScratchEntryInfo> {
+        self.lock()
+            .iter()
+            .map(|(key, e)| ScratchEntryInfo {
+                key: key.clone(),
+                bytes: e.value.len(),
+                lines: e.value.lines().count(),
+                stashed_at: e.stashed_at,
+            })
+            .collect()
+    }
+
+    /// Drop everything.
+    pub fn clear(&self) {
+        self.lock().clear();
+    }
+}
+
+fn is_valid_key(key: &str) -> bool {
+    !key.is_empty()
+        && key.len() <= 64
+        && key
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stash_and_unstash() {
+        let scratch = ScratchBuffer::new();
+        let shared = scratch.clone();
+        scratch.stash("page", "# Title\nbody").unwrap();
+        assert!(shared.contains("page"));
+        assert_eq!(shared.get("page").as_deref(), Some("# Title\nbody"));
+
+        let list = scratch.list();
+        assert_eq!(list.len(), 1);
+        assert_eq!(list[0].lines, 2);
+
+        scratch.stash("page", "replaced").unwrap();
+        assert_eq!(shared.unstash("page").as_deref(), Some("replaced"));
+        assert!(scratch.unstash("page").is_none());
+
+        assert!(matches!(
+            scratch.stash("no spaces", "x"),
+            Err(ScratchError::InvalidKey(_))
+        ));
+    }
+
+    #[test]
+    fn test_limits() {
+        let scratch = ScratchBuffer::new();
+        assert!(matches!(
+            scratch.stash("big", "x".repeat(MAX_ENTRY_BYTES + 1)),
+            Err(ScratchError::TooLarge { .. })
+        ));
+        for i in 0..MAX_ENTRIES {
+            scratch.stash(&format!("k{}", i), "v").unwrap();
+        }
+        assert!(matches!(
+            scratch.stash("one-more", "v"),
+            Err(ScratchError::Full { .. })
+        ));
+        // Replacing an entry doesn't count as a new one.
+        scratch.stash("k0", "new").unwrap();
+    }
+}
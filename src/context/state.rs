@@ -1,12 +1,16 @@
 //! Job state machine.
 
+use std::path::PathBuf;
 use std::time::Duration;
 
 use chrono::{DateTime, Utc};
 use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
+use tokio_util::sync::CancellationToken;
 use uuid::Uuid;
 
+use crate::context::ScratchBuffer;
+
 /// State of a job.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
@@ -90,6 +94,34 @@ pub struct StateTransition {
     pub reason: Option<String>,
 }
 
+/// Who can see the conversation a job runs for.
+///
+/// A main session is a direct chat with the user; a shared one (group chat,
+/// channel with other people present) must not surface the user's private
+/// memory. Background jobs and routines run as main.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SessionScope {
+    #[default]
+    Main,
+    Shared,
+}
+
+impl SessionScope {
+    /// Scope of a chat message, from the channel's metadata. Channels flag
+    /// group chats with `is_private: false`, `is_group: true`, or a
+    /// `chat_type` other than "private"/"direct"/"dm".
+    pub fn from_metadata(metadata: &serde_json::Value) -> Self {
+        let shared = metadata.get("is_private").and_then(|v| v.as_bool()) == Some(false)
+            || metadata.get("is_group").and_then(|v| v.as_bool()) == Some(true)
+            || metadata
+                .get("chat_type")
+                .and_then(|v| v.as_str())
+                .is_some_and(|t| !matches!(t, "private" | "direct" | "dm"));
+        if shared { Self::Shared } else { Self::Main }
+    }
+}
+
 /// Context for a running job.
 #[derive(Debug, Clone, Serialize)]
 pub struct JobContext {
@@ -135,6 +167,36 @@ pub struct JobContext {
     pub transitions: Vec<StateTransition>,
     /// Metadata.
     pub metadata: serde_json::Value,
+    /// Values tools pass to each other without going through the LLM.
+    #[serde(skip)]
+    pub scratch: ScratchBuffer,
+    /// Private temp directory, removed when the job finishes. Shell and
+    /// file tools work here unless told otherwise.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub temp_dir: Option<PathBuf>,
+    /// Agent definition the job runs under, if not the default agent.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub agent_id: Option<Uuid>,
+    /// Tools the job may call (`prefix*` globs allowed); empty means all.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub allowed_tools: Vec<String>,
+    /// Model to use instead of the configured one.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    /// Channel the job was started from ("telegram", "web", ...).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub channel: Option<String>,
+    /// Whether the conversation is private to the user.
+    pub scope: SessionScope,
+    /// User's IANA timezone (e.g. "Europe/Berlin"), if known.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timezone: Option<String>,
+    /// User's locale (e.g. "de-DE"), if known.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub locale: Option<String>,
+    /// Cancelled when the job is cancelled; long-running tools can watch it.
+    #[serde(skip)]
+    pub cancel: CancellationToken,
 }
 
 impl JobContext {
@@ -171,9 +233,63 @@ impl JobContext {
             repair_attempts: 0,
             transitions: Vec::new(),
             metadata: serde_json::Value::Null,
+            scratch: ScratchBuffer::default(),
+            temp_dir: None,
+            agent_id: None,
+            allowed_tools: Vec::new(),
+            model: None,
+            channel: None,
+            scope: SessionScope::Main,
+            timezone: None,
+            locale: None,
+            cancel: CancellationToken::new(),
         }
     }
 
+    /// Set the agent the job runs under.
+    pub fn with_agent(mut self, agent_id: Option<Uuid>) -> Self {
+        self.agent_id = agent_id;
+        self
+    }
+
+    /// Whether the job may call `tool_name`.
+    pub fn allows_tool(&self, tool_name: &str) -> bool {
+        self.allowed_tools.is_empty()
+            || self
+                .allowed_tools
+                .iter()
+                .any(|p| crate::safety::tool_matches(p, tool_name))
+    }
+
+    /// Set the originating channel and its session scope.
+    pub fn with_channel(mut self, channel: impl Into<String>, scope: SessionScope) -> Self {
+        self.channel = Some(channel.into());
+        self.scope = scope;
+        self
+    }
+
+    /// Set the user's timezone.
+    pub fn with_timezone(mut self, timezone: impl Into<String>) -> Self {
+        self.timezone = Some(timezone.into());
+        self
+    }
+
+    /// Set the user's locale.
+    pub fn with_locale(mut self, locale: impl Into<String>) -> Self {
+        self.locale = Some(locale.into());
+        self
+    }
+
+    /// Whether other people can see this conversation.
+    pub fn is_shared(&self) -> bool {
+        self.scope == SessionScope::Shared
+    }
+
+    /// Whether the job has been cancelled.
+    pub fn is_cancelled(&self) -> bool {
+        self.cancel.is_cancelled()
+    }
+
     /// Transition to a new state.
     pub fn transition_to(
         &mut self,
@@ -215,6 +331,9 @@ impl JobContext {
             }
             _ => {}
         }
+        if new_state == JobState::Cancelled {
+            self.cancel.cancel();
+        }
 
         Ok(())
     }
@@ -338,6 +457,17 @@ mod tests {
         assert_eq!(ctx.total_tokens_used, 1100); // tokens still recorded
     }
 
+    #[test]
+    fn test_allows_tool() {
+        let mut ctx = JobContext::default();
+        assert!(ctx.allows_tool("shell"));
+
+        ctx.allowed_tools = vec!["memory_*".to_string(), "http".to_string()];
+        assert!(ctx.allows_tool("memory_search"));
+        assert!(ctx.allows_tool("http"));
+        assert!(!ctx.allows_tool("shell"));
+    }
+
     #[test]
     fn test_add_tokens_unlimited() {
         let mut ctx = JobContext::new("Test", "No budget");
@@ -373,4 +503,36 @@ mod tests {
         assert_eq!(ctx.state, JobState::InProgress);
         assert_eq!(ctx.repair_attempts, 1);
     }
+
+    #[test]
+    fn test_cancel_signals_clones() {
+        let mut ctx = JobContext::new("Test", "Cancel test");
+        let tool_view = ctx.clone();
+        ctx.transition_to(JobState::InProgress, None).unwrap();
+        assert!(!tool_view.is_cancelled());
+        ctx.transition_to(JobState::Cancelled, None).unwrap();
+        assert!(tool_view.is_cancelled());
+    }
+
+    #[test]
+    fn test_session_scope_from_metadata() {
+        let scope = |v| SessionScope::from_metadata(&v);
+        assert_eq!(scope(serde_json::Value::Null), SessionScope::Main);
+        assert_eq!(
+            scope(serde_json::json!({ "chat_id": 1, "is_private": true })),
+            SessionScope::Main
+        );
+        assert_eq!(
+            scope(serde_json::json!({ "chat_id": 1, "is_private": false })),
+            SessionScope::Shared
+        );
+        assert_eq!(
+            scope(serde_json::json!({ "chat_type": "supergroup" })),
+            SessionScope::Shared
+        );
+
+        let ctx = JobContext::default().with_channel("telegram", SessionScope::Shared);
+        assert!(ctx.is_shared());
+        assert_eq!(ctx.channel.as_deref(), Some("telegram"));
+    }
 }
@@ -0,0 +1,140 @@
+//! Leases for running several instances against one database.
+//!
+//! Without coordination every instance fires its own heartbeat and routine
+//! cron ticker, so periodic work happens twice. A lease names a periodic
+//! task and the instance that owns it: [`LeaseManager::hold`] takes or
+//! extends the lease on each tick, so only the holder's ticks fire. An
+//! instance that dies stops extending its leases; once they expire another
+//! instance takes the ticks over.
+//!
+//! Only the heartbeat and cron ticks are coordinated. Jobs are not: each
+//! instance runs the jobs it schedules itself.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+
+use crate::db::Database;
+use crate::error::DatabaseError;
+
+/// A stored lease.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LeaseRecord {
+    pub name: String,
+    /// Instance ID of the holder.
+    pub owner: String,
+    pub acquired_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+}
+
+impl LeaseRecord {
+    pub fn is_expired_at(&self, now: DateTime<Utc>) -> bool {
+        self.expires_at <= now
+    }
+}
+
+/// Takes leases on behalf of one instance.
+pub struct LeaseManager {
+    db: Arc<dyn Database>,
+    owner: String,
+    ttl: Duration,
+}
+
+impl LeaseManager {
+    /// `owner` identifies this instance; `ttl` is the slack a tick lease
+    /// gets on top of its task's interval.
+    pub fn new(db: Arc<dyn Database>, owner: impl Into<String>, ttl: Duration) -> Self {
+        Self {
+            db,
+            owner: owner.into(),
+            ttl,
+        }
+    }
+
+    pub fn owner(&self) -> &str {
+        &self.owner
+    }
+
+    pub fn ttl(&self) -> Duration {
+        self.ttl
+    }
+
+    /// Take or extend `name` for `ttl`, for periodic work that checks the
+    /// lease on every tick. Returns whether this instance holds it.
+    pub async fn hold(&self, name: &str, ttl: Duration) -> Result<bool, DatabaseError> {
+        self.db.try_acquire_lease(name, &self.owner, ttl).await
+    }
+}
+
+#[cfg(all(test, feature = "libsql"))]
+mod tests {
+    use super::*;
+    use crate::db::libsql_backend::test_db;
+
+    #[tokio::test]
+    async fn test_acquire_renew_release() {
+        let (db, _dir) = test_db().await;
+        let ttl = Duration::from_secs(60);
+
+        assert!(db.try_acquire_lease("cron", "a", ttl).await.unwrap());
+        // Held by a, so b can't take it; a re-acquiring extends it.
+        assert!(!db.try_acquire_lease("cron", "b", ttl).await.unwrap());
+        assert!(db.try_acquire_lease("cron", "a", ttl).await.unwrap());
+        assert!(db.renew_lease("cron", "a", ttl).await.unwrap());
+        assert!(!db.renew_lease("cron", "b", ttl).await.unwrap());
+
+        let lease = db.get_lease("cron").await.unwrap().unwrap();
+        assert_eq!(lease.owner, "a");
+        assert!(!lease.is_expired_at(Utc::now()));
+
+        // Releasing someone else's lease does nothing.
+        db.release_lease("cron", "b").await.unwrap();
+        assert!(db.get_lease("cron").await.unwrap().is_some());
+        db.release_lease("cron", "a").await.unwrap();
+        assert!(db.get_lease("cron").await.unwrap().is_none());
+        assert!(db.try_acquire_lease("cron", "b", ttl).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_expired_lease_is_taken_over() {
+        let (db, _dir) = test_db().await;
+
+        assert!(
+            db.try_acquire_lease("heartbeat", "a", Duration::from_millis(20))
+                .await
+                .unwrap()
+        );
+        tokio::time::sleep(Duration::from_millis(50)).await;
+
+        assert!(
+            db.try_acquire_lease("heartbeat", "b", Duration::from_secs(60))
+                .await
+                .unwrap()
+        );
+        assert_eq!(db.get_lease("heartbeat").await.unwrap().unwrap().owner, "b");
+        // The old owner can no longer renew.
+        assert!(
+            !db.renew_lease("heartbeat", "a", Duration::from_secs(60))
+                .await
+                .unwrap()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_hold_fires_ticks_on_one_instance() {
+        let (db, _dir) = test_db().await;
+        let a = LeaseManager::new(Arc::clone(&db), "a", Duration::from_secs(60));
+        let b = LeaseManager::new(Arc::clone(&db), "b", Duration::from_secs(60));
+
+        assert!(a.hold("cron", Duration::from_millis(40)).await.unwrap());
+        assert!(!b.hold("cron", Duration::from_millis(40)).await.unwrap());
+        // a keeps ticking while its lease is live.
+        assert!(a.hold("cron", Duration::from_millis(40)).await.unwrap());
+
+        // Once a stops ticking, b takes over.
+        tokio::time::sleep(Duration::from_millis(80)).await;
+        assert!(b.hold("cron", Duration::from_millis(40)).await.unwrap());
+        assert!(!a.hold("cron", Duration::from_millis(40)).await.unwrap());
+    }
+}
@@ -9,6 +9,7 @@
 use std::collections::HashMap;
 use std::path::Path;
 use std::sync::Arc;
+use std::time::Duration;
 
 use async_trait::async_trait;
 use chrono::{DateTime, NaiveDateTime, Utc};
@@ -17,23 +18,49 @@ use rust_decimal::Decimal;
 use uuid::Uuid;
 
 use crate::agent::BrokenTool;
+use crate::agent::registry::AgentDefinition;
 use crate::agent::routine::{
     NotifyConfig, Routine, RoutineAction, RoutineGuardrails, RoutineRun, RunStatus, Trigger,
 };
+use crate::agent::transcript::TranscriptEntry;
+use crate::audit::{AuditEvent, AuditQuery, AuditRecord};
+use crate::contacts::Contact;
 use crate::context::{ActionRecord, JobContext, JobState};
-use crate::db::Database;
+use crate::db::lease::LeaseRecord;
+use crate::db::{Database, DbDiagnostics, SCHEMA_VERSION};
 use crate::error::{DatabaseError, WorkspaceError};
 use crate::history::{
-    ConversationMessage, ConversationSummary, JobEventRecord, LlmCallRecord, SandboxJobRecord,
-    SandboxJobSummary, SettingRow,
+    ConversationMessage, ConversationSummary, JobEventRecord, JobUsageRecord, LlmCallRecord,
+    SandboxJobRecord, SandboxJobSummary, SettingRow,
 };
+use crate::workspace::frontmatter::{DocumentMetadata, FieldFilter};
+use crate::workspace::glob::PathGlob;
+use crate::workspace::graph::{GraphEdge, GraphEntity, entity_key};
+use crate::workspace::importance::ChunkSignals;
+use crate::workspace::language::Language;
+use crate::workspace::links::Backlink;
+use crate::workspace::pins::{MemoryPin, PinTarget};
 use crate::workspace::{
-    MemoryChunk, MemoryDocument, RankedResult, SearchConfig, SearchResult, WorkspaceEntry,
-    reciprocal_rank_fusion,
+    DirectoryRecord, DocumentSize, DocumentVersion, MemoryChunk, MemoryDocument, NewChunk,
+    RankedResult, SearchConfig, SearchResult, SparseVector, VersionInfo, WorkspaceEntry,
+    reciprocal_rank_fusion_with_sparse,
 };
 
 use crate::db::libsql_migrations;
 
+/// Explicit column list for agents table (matches positional access in `row_to_agent_libsql`).
+const AGENT_COLUMNS: &str = "\
+    id, name, description, enabled, model, \
+    tools, channels, workspace_seed, created_at, updated_at";
+
+/// Explicit column list for contacts table (matches positional access in `row_to_contact_libsql`).
+const CONTACT_COLUMNS: &str = "\
+    id, user_id, name, relationship, emails, handles, notes, \
+    last_seen_at, created_at, updated_at";
+
+/// Explicit column list for graph_entities table (matches positional access in `row_to_graph_entity_libsql`).
+const GRAPH_ENTITY_COLUMNS: &str = "id, user_id, agent_id, name, kind, created_at";
+
 /// Explicit column list for routines table (matches positional access in `row_to_routine_libsql`).
 const ROUTINE_COLUMNS: &str = "\
     id, name, description, user_id, enabled, \
@@ -123,6 +150,18 @@ impl LibSqlBackend {
     }
 }
 
+/// A migrated database in a fresh temporary directory, for tests. Keep the
+/// directory alive for as long as the database is in use.
+#[cfg(test)]
+pub(crate) async fn test_db() -> (Arc<dyn Database>, tempfile::TempDir) {
+    let dir = tempfile::tempdir().unwrap();
+    let backend = LibSqlBackend::new_local(&dir.path().join("test.db"))
+        .await
+        .unwrap();
+    backend.run_migrations().await.unwrap();
+    (Arc::new(backend), dir)
+}
+
 // ==================== Helper functions ====================
 
 /// Parse an ISO-8601 timestamp string from SQLite into DateTime<Utc>.
@@ -209,6 +248,17 @@ fn get_i64(row: &libsql::Row, idx: i32) -> i64 {
     row.get::<i64>(idx).unwrap_or(0)
 }
 
+/// Dimension of the `embedding F32_BLOB(N)` column in a `CREATE TABLE` statement.
+fn parse_blob_dimension(create_sql: &str) -> Option<usize> {
+    let rest = &create_sql[create_sql.find("embedding F32_BLOB(")? + "embedding F32_BLOB(".len()..];
+    rest[..rest.find(')')?].trim().parse().ok()
+}
+
+/// Extract an f64 column, defaulting to 0.0.
+fn get_f64(row: &libsql::Row, idx: i32) -> f64 {
+    row.get::<f64>(idx).unwrap_or(0.0)
+}
+
 /// Extract an optional bool from an integer column.
 fn get_opt_bool(row: &libsql::Row, idx: i32) -> Option<bool> {
     row.get::<i64>(idx).ok().map(|v| v != 0)
@@ -275,16 +325,111 @@ fn get_opt_ts(row: &libsql::Row, idx: i32) -> Option<DateTime<Utc>> {
 
 #[async_trait]
 impl Database for LibSqlBackend {
+    #[tracing::instrument(name = "db.run_migrations", skip_all)]
     async fn run_migrations(&self) -> Result<(), DatabaseError> {
         let conn = self.connect()?;
         conn.execute_batch(libsql_migrations::SCHEMA)
             .await
             .map_err(|e| DatabaseError::Migration(format!("libSQL migration failed: {}", e)))?;
+        // Older databases keep their tables; add the columns since introduced.
+        let migration_error =
+            |e: libsql::Error| DatabaseError::Migration(format!("libSQL migration failed: {}", e));
+        for (table, column, definition) in libsql_migrations::ADDED_COLUMNS {
+            let exists = {
+                let mut rows = conn
+                    .query(
+                        "SELECT COUNT(*) FROM pragma_table_info(?1) WHERE name = ?2",
+                        params![*table, *column],
+                    )
+                    .await
+                    .map_err(migration_error)?;
+                rows.next()
+                    .await
+                    .map_err(migration_error)?
+                    .is_some_and(|row| get_i64(&row, 0) > 0)
+            };
+            if !exists {
+                conn.execute(
+                    &format!("ALTER TABLE {} ADD COLUMN {} {}", table, column, definition),
+                    (),
+                )
+                .await
+                .map_err(migration_error)?;
+            }
+        }
+        // The consolidated schema has no history table; record its version
+        // so `ironclaw doctor` can tell a stale database apart.
+        conn.execute(&format!("PRAGMA user_version = {}", SCHEMA_VERSION), ())
+            .await
+            .map_err(|e| DatabaseError::Migration(format!("libSQL migration failed: {}", e)))?;
+        Ok(())
+    }
+
+    #[tracing::instrument(name = "db.ping", skip_all)]
+    async fn ping(&self) -> Result<(), DatabaseError> {
+        let conn = self.connect()?;
+        conn.query("SELECT 1", ())
+            .await
+            .map_err(|e| DatabaseError::Query(e.to_string()))?;
         Ok(())
     }
 
+    #[tracing::instrument(name = "db.diagnostics", skip_all)]
+    async fn diagnostics(&self) -> Result<DbDiagnostics, DatabaseError> {
+        let conn = self.connect()?;
+        let query_row = |sql: &'static str| {
+            let conn = conn.clone();
+            async move {
+                let mut rows = conn
+                    .query(sql, ())
+                    .await
+                    .map_err(|e| DatabaseError::Query(e.to_string()))?;
+                rows.next()
+                    .await
+                    .map_err(|e| DatabaseError::Query(e.to_string()))
+            }
+        };
+
+        let schema_version = query_row("PRAGMA user_version")
+            .await?
+            .map(|row| get_i64(&row, 0))
+            .filter(|v| *v > 0);
+        let Some(chunks_sql) = query_row(
+            "SELECT sql FROM sqlite_master WHERE type = 'table' AND name = 'memory_chunks'",
+        )
+        .await?
+        .map(|row| get_text(&row, 0)) else {
+            // Schema not created yet.
+            return Ok(DbDiagnostics {
+                schema_version,
+                ..Default::default()
+            });
+        };
+        let embedding_dimension = parse_blob_dimension(&chunks_sql);
+        let counts = query_row(
+            r#"
+            SELECT
+                COUNT(*),
+                COALESCE(SUM(c.embedding IS NULL), 0),
+                COALESCE(SUM(d.id IS NULL), 0)
+            FROM memory_chunks c
+            LEFT JOIN memory_documents d ON d.id = c.document_id
+            "#,
+        )
+        .await?;
+
+        Ok(DbDiagnostics {
+            schema_version,
+            embedding_dimension,
+            chunk_count: counts.as_ref().map(|r| get_i64(r, 0)).unwrap_or(0),
+            chunks_missing_embeddings: counts.as_ref().map(|r| get_i64(r, 1)).unwrap_or(0),
+            orphaned_chunks: counts.as_ref().map(|r| get_i64(r, 2)).unwrap_or(0),
+        })
+    }
+
     // ==================== Conversations ====================
 
+    #[tracing::instrument(name = "db.create_conversation", skip_all)]
     async fn create_conversation(
         &self,
         channel: &str,
@@ -302,6 +447,7 @@ impl Database for LibSqlBackend {
         Ok(id)
     }
 
+    #[tracing::instrument(name = "db.touch_conversation", skip_all)]
     async fn touch_conversation(&self, id: Uuid) -> Result<(), DatabaseError> {
         let conn = self.connect()?;
         let now = fmt_ts(&Utc::now());
@@ -314,6 +460,7 @@ impl Database for LibSqlBackend {
         Ok(())
     }
 
+    #[tracing::instrument(name = "db.add_conversation_message", skip_all)]
     async fn add_conversation_message(
         &self,
         conversation_id: Uuid,
@@ -332,6 +479,7 @@ impl Database for LibSqlBackend {
         Ok(id)
     }
 
+    #[tracing::instrument(name = "db.ensure_conversation", skip_all)]
     async fn ensure_conversation(
         &self,
         id: Uuid,
@@ -354,6 +502,7 @@ impl Database for LibSqlBackend {
         Ok(())
     }
 
+    #[tracing::instrument(name = "db.list_conversations_with_preview", skip_all)]
     async fn list_conversations_with_preview(
         &self,
         user_id: &str,
@@ -413,6 +562,7 @@ impl Database for LibSqlBackend {
         Ok(results)
     }
 
+    #[tracing::instrument(name = "db.get_or_create_assistant_conversation", skip_all)]
     async fn get_or_create_assistant_conversation(
         &self,
         user_id: &str,
@@ -456,6 +606,7 @@ impl Database for LibSqlBackend {
         Ok(id)
     }
 
+    #[tracing::instrument(name = "db.create_conversation_with_metadata", skip_all)]
     async fn create_conversation_with_metadata(
         &self,
         channel: &str,
@@ -473,6 +624,7 @@ impl Database for LibSqlBackend {
         Ok(id)
     }
 
+    #[tracing::instrument(name = "db.list_conversation_messages_paginated", skip_all)]
     async fn list_conversation_messages_paginated(
         &self,
         conversation_id: Uuid,
@@ -530,6 +682,7 @@ impl Database for LibSqlBackend {
         Ok((all, has_more))
     }
 
+    #[tracing::instrument(name = "db.update_conversation_metadata_field", skip_all)]
     async fn update_conversation_metadata_field(
         &self,
         id: Uuid,
@@ -548,6 +701,7 @@ impl Database for LibSqlBackend {
         Ok(())
     }
 
+    #[tracing::instrument(name = "db.get_conversation_metadata", skip_all)]
     async fn get_conversation_metadata(
         &self,
         id: Uuid,
@@ -571,6 +725,7 @@ impl Database for LibSqlBackend {
         }
     }
 
+    #[tracing::instrument(name = "db.list_conversation_messages", skip_all)]
     async fn list_conversation_messages(
         &self,
         conversation_id: Uuid,
@@ -605,6 +760,7 @@ impl Database for LibSqlBackend {
         Ok(messages)
     }
 
+    #[tracing::instrument(name = "db.conversation_belongs_to_user", skip_all)]
     async fn conversation_belongs_to_user(
         &self,
         conversation_id: Uuid,
@@ -625,8 +781,65 @@ impl Database for LibSqlBackend {
         Ok(found.is_some())
     }
 
+    #[tracing::instrument(name = "db.find_conversation", skip_all)]
+    async fn find_conversation(
+        &self,
+        user_id: &str,
+        channel: &str,
+        thread_id: Option<&str>,
+    ) -> Result<Option<Uuid>, DatabaseError> {
+        let conn = self.connect()?;
+        let mut rows = conn
+            .query(
+                r#"
+                SELECT id FROM conversations
+                WHERE user_id = ?1 AND channel = ?2 AND thread_id IS ?3
+                ORDER BY datetime(last_activity) DESC
+                LIMIT 1
+                "#,
+                params![user_id, channel, opt_text(thread_id)],
+            )
+            .await
+            .map_err(|e| DatabaseError::Query(e.to_string()))?;
+        let row = rows
+            .next()
+            .await
+            .map_err(|e| DatabaseError::Query(e.to_string()))?;
+        Ok(row.and_then(|r| get_text(&r, 0).parse().ok()))
+    }
+
+    #[tracing::instrument(name = "db.prune_conversations", skip_all)]
+    async fn prune_conversations(&self, cutoff: DateTime<Utc>) -> Result<u64, DatabaseError> {
+        let conn = self.connect()?;
+        let cutoff = fmt_ts(&cutoff);
+        // Foreign keys aren't enforced here, so clear dependents by hand;
+        // each step is safe to repeat if a later one fails. Timestamps mix
+        // SQLite's default format and RFC 3339, so compare via datetime().
+        for sql in [
+            "DELETE FROM conversation_messages WHERE conversation_id IN \
+             (SELECT id FROM conversations WHERE datetime(last_activity) < datetime(?1))",
+            "UPDATE agent_jobs SET conversation_id = NULL WHERE conversation_id IN \
+             (SELECT id FROM conversations WHERE datetime(last_activity) < datetime(?1))",
+            "UPDATE llm_calls SET conversation_id = NULL WHERE conversation_id IN \
+             (SELECT id FROM conversations WHERE datetime(last_activity) < datetime(?1))",
+        ] {
+            conn.execute(sql, params![cutoff.as_str()])
+                .await
+                .map_err(|e| DatabaseError::Query(e.to_string()))?;
+        }
+        let count = conn
+            .execute(
+                "DELETE FROM conversations WHERE datetime(last_activity) < datetime(?1)",
+                params![cutoff.as_str()],
+            )
+            .await
+            .map_err(|e| DatabaseError::Query(e.to_string()))?;
+        Ok(count)
+    }
+
     // ==================== Jobs ====================
 
+    #[tracing::instrument(name = "db.save_job", skip_all)]
     async fn save_job(&self, ctx: &JobContext) -> Result<(), DatabaseError> {
         let conn = self.connect()?;
         let status = ctx.state.to_string();
@@ -638,8 +851,8 @@ impl Database for LibSqlBackend {
                 INSERT INTO agent_jobs (
                     id, conversation_id, title, description, category, status, source,
                     budget_amount, budget_token, bid_amount, estimated_cost, estimated_time_secs,
-                    actual_cost, repair_attempts, created_at, started_at, completed_at
-                ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17)
+                    actual_cost, repair_attempts, created_at, started_at, completed_at, user_id
+                ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18)
                 ON CONFLICT (id) DO UPDATE SET
                     title = excluded.title,
                     description = excluded.description,
@@ -670,6 +883,7 @@ impl Database for LibSqlBackend {
                     fmt_ts(&ctx.created_at),
                     fmt_opt_ts(&ctx.started_at),
                     fmt_opt_ts(&ctx.completed_at),
+                    ctx.user_id.as_str(),
                 ],
             )
             .await
@@ -677,6 +891,7 @@ impl Database for LibSqlBackend {
         Ok(())
     }
 
+    #[tracing::instrument(name = "db.get_job", skip_all)]
     async fn get_job(&self, id: Uuid) -> Result<Option<JobContext>, DatabaseError> {
         let conn = self.connect()?;
         let mut rows = conn
@@ -725,12 +940,14 @@ impl Database for LibSqlBackend {
                     completed_at: get_opt_ts(&row, 16),
                     transitions: Vec::new(),
                     metadata: serde_json::Value::Null,
+                    ..Default::default()
                 }))
             }
             None => Ok(None),
         }
     }
 
+    #[tracing::instrument(name = "db.update_job_status", skip_all)]
     async fn update_job_status(
         &self,
         id: Uuid,
@@ -747,6 +964,7 @@ impl Database for LibSqlBackend {
         Ok(())
     }
 
+    #[tracing::instrument(name = "db.mark_job_stuck", skip_all)]
     async fn mark_job_stuck(&self, id: Uuid) -> Result<(), DatabaseError> {
         let conn = self.connect()?;
         let now = fmt_ts(&Utc::now());
@@ -759,6 +977,7 @@ impl Database for LibSqlBackend {
         Ok(())
     }
 
+    #[tracing::instrument(name = "db.get_stuck_jobs", skip_all)]
     async fn get_stuck_jobs(&self) -> Result<Vec<Uuid>, DatabaseError> {
         let conn = self.connect()?;
         let mut rows = conn
@@ -781,8 +1000,54 @@ impl Database for LibSqlBackend {
         Ok(ids)
     }
 
+    #[tracing::instrument(name = "db.list_job_usage", skip_all)]
+    async fn list_job_usage(
+        &self,
+        user_id: &str,
+        since: DateTime<Utc>,
+        until: DateTime<Utc>,
+    ) -> Result<Vec<JobUsageRecord>, DatabaseError> {
+        let conn = self.connect()?;
+        let mut rows = conn
+            .query(
+                r#"
+                SELECT j.id, j.title, j.status, j.actual_cost, j.failure_reason, j.created_at,
+                       COALESCE(SUM(json_extract(t.response, '$.input_tokens')), 0),
+                       COALESCE(SUM(json_extract(t.response, '$.output_tokens')), 0)
+                FROM agent_jobs j
+                LEFT JOIN job_transcript_entries t ON t.job_id = j.id
+                WHERE j.user_id = ?1 AND j.created_at >= ?2 AND j.created_at < ?3
+                GROUP BY j.id
+                ORDER BY j.created_at ASC
+                "#,
+                params![user_id, fmt_ts(&since), fmt_ts(&until)],
+            )
+            .await
+            .map_err(|e| DatabaseError::Query(e.to_string()))?;
+
+        let mut jobs = Vec::new();
+        while let Some(row) = rows
+            .next()
+            .await
+            .map_err(|e| DatabaseError::Query(e.to_string()))?
+        {
+            jobs.push(JobUsageRecord {
+                id: get_text(&row, 0).parse().unwrap_or_default(),
+                title: get_text(&row, 1),
+                status: get_text(&row, 2),
+                actual_cost: get_decimal(&row, 3),
+                failure_reason: get_opt_text(&row, 4),
+                created_at: get_ts(&row, 5),
+                input_tokens: get_i64(&row, 6).max(0) as u64,
+                output_tokens: get_i64(&row, 7).max(0) as u64,
+            });
+        }
+        Ok(jobs)
+    }
+
     // ==================== Actions ====================
 
+    #[tracing::instrument(name = "db.save_action", skip_all)]
     async fn save_action(&self, job_id: Uuid, action: &ActionRecord) -> Result<(), DatabaseError> {
         let conn = self.connect()?;
         let duration_ms = action.duration.as_millis() as i64;
@@ -817,6 +1082,7 @@ impl Database for LibSqlBackend {
         Ok(())
     }
 
+    #[tracing::instrument(name = "db.get_job_actions", skip_all)]
     async fn get_job_actions(&self, job_id: Uuid) -> Result<Vec<ActionRecord>, DatabaseError> {
         let conn = self.connect()?;
         let mut rows = conn
@@ -859,6 +1125,7 @@ impl Database for LibSqlBackend {
 
     // ==================== LLM Calls ====================
 
+    #[tracing::instrument(name = "db.record_llm_call", skip_all)]
     async fn record_llm_call(&self, record: &LlmCallRecord<'_>) -> Result<Uuid, DatabaseError> {
         let conn = self.connect()?;
         let id = Uuid::new_v4();
@@ -886,6 +1153,7 @@ impl Database for LibSqlBackend {
 
     // ==================== Estimation Snapshots ====================
 
+    #[tracing::instrument(name = "db.save_estimation_snapshot", skip_all)]
     async fn save_estimation_snapshot(
         &self,
         job_id: Uuid,
@@ -920,6 +1188,7 @@ impl Database for LibSqlBackend {
         Ok(id)
     }
 
+    #[tracing::instrument(name = "db.update_estimation_actuals", skip_all)]
     async fn update_estimation_actuals(
         &self,
         id: Uuid,
@@ -944,6 +1213,7 @@ impl Database for LibSqlBackend {
 
     // ==================== Sandbox Jobs ====================
 
+    #[tracing::instrument(name = "db.save_sandbox_job", skip_all)]
     async fn save_sandbox_job(&self, job: &SandboxJobRecord) -> Result<(), DatabaseError> {
         let conn = self.connect()?;
         conn.execute(
@@ -977,6 +1247,7 @@ impl Database for LibSqlBackend {
         Ok(())
     }
 
+    #[tracing::instrument(name = "db.get_sandbox_job", skip_all)]
     async fn get_sandbox_job(&self, id: Uuid) -> Result<Option<SandboxJobRecord>, DatabaseError> {
         let conn = self.connect()?;
         let mut rows = conn
@@ -1012,6 +1283,7 @@ impl Database for LibSqlBackend {
         }
     }
 
+    #[tracing::instrument(name = "db.list_sandbox_jobs", skip_all)]
     async fn list_sandbox_jobs(&self) -> Result<Vec<SandboxJobRecord>, DatabaseError> {
         let conn = self.connect()?;
         let mut rows = conn
@@ -1049,6 +1321,7 @@ impl Database for LibSqlBackend {
         Ok(jobs)
     }
 
+    #[tracing::instrument(name = "db.update_sandbox_job_status", skip_all)]
     async fn update_sandbox_job_status(
         &self,
         id: Uuid,
@@ -1083,6 +1356,7 @@ impl Database for LibSqlBackend {
         Ok(())
     }
 
+    #[tracing::instrument(name = "db.cleanup_stale_sandbox_jobs", skip_all)]
     async fn cleanup_stale_sandbox_jobs(&self) -> Result<u64, DatabaseError> {
         let conn = self.connect()?;
         let now = fmt_ts(&Utc::now());
@@ -1105,6 +1379,7 @@ impl Database for LibSqlBackend {
         Ok(count)
     }
 
+    #[tracing::instrument(name = "db.sandbox_job_summary", skip_all)]
     async fn sandbox_job_summary(&self) -> Result<SandboxJobSummary, DatabaseError> {
         let conn = self.connect()?;
         let mut rows = conn
@@ -1136,6 +1411,7 @@ impl Database for LibSqlBackend {
         Ok(summary)
     }
 
+    #[tracing::instrument(name = "db.list_sandbox_jobs_for_user", skip_all)]
     async fn list_sandbox_jobs_for_user(
         &self,
         user_id: &str,
@@ -1176,6 +1452,7 @@ impl Database for LibSqlBackend {
         Ok(jobs)
     }
 
+    #[tracing::instrument(name = "db.sandbox_job_summary_for_user", skip_all)]
     async fn sandbox_job_summary_for_user(
         &self,
         user_id: &str,
@@ -1210,6 +1487,7 @@ impl Database for LibSqlBackend {
         Ok(summary)
     }
 
+    #[tracing::instrument(name = "db.sandbox_job_belongs_to_user", skip_all)]
     async fn sandbox_job_belongs_to_user(
         &self,
         job_id: Uuid,
@@ -1230,6 +1508,7 @@ impl Database for LibSqlBackend {
         Ok(found.is_some())
     }
 
+    #[tracing::instrument(name = "db.update_sandbox_job_mode", skip_all)]
     async fn update_sandbox_job_mode(&self, id: Uuid, mode: &str) -> Result<(), DatabaseError> {
         let conn = self.connect()?;
         conn.execute(
@@ -1241,6 +1520,7 @@ impl Database for LibSqlBackend {
         Ok(())
     }
 
+    #[tracing::instrument(name = "db.get_sandbox_job_mode", skip_all)]
     async fn get_sandbox_job_mode(&self, id: Uuid) -> Result<Option<String>, DatabaseError> {
         let conn = self.connect()?;
         let mut rows = conn
@@ -1263,6 +1543,7 @@ impl Database for LibSqlBackend {
 
     // ==================== Job Events ====================
 
+    #[tracing::instrument(name = "db.save_job_event", skip_all)]
     async fn save_job_event(
         &self,
         job_id: Uuid,
@@ -1279,6 +1560,7 @@ impl Database for LibSqlBackend {
         Ok(())
     }
 
+    #[tracing::instrument(name = "db.list_job_events", skip_all)]
     async fn list_job_events(&self, job_id: Uuid) -> Result<Vec<JobEventRecord>, DatabaseError> {
         let conn = self.connect()?;
         let mut rows = conn
@@ -1309,96 +1591,95 @@ impl Database for LibSqlBackend {
         Ok(events)
     }
 
-    // ==================== Routines ====================
+    // ==================== Job Transcripts ====================
 
-    async fn create_routine(&self, routine: &Routine) -> Result<(), DatabaseError> {
+    #[tracing::instrument(name = "db.append_transcript_entry", skip_all)]
+    async fn append_transcript_entry(
+        &self,
+        job_id: Uuid,
+        entry: &TranscriptEntry,
+    ) -> Result<(), DatabaseError> {
+        let request = serde_json::to_string(&entry.request)
+            .map_err(|e| DatabaseError::Serialization(e.to_string()))?;
+        let response = serde_json::to_string(&entry.response)
+            .map_err(|e| DatabaseError::Serialization(e.to_string()))?;
         let conn = self.connect()?;
-        let trigger_type = routine.trigger.type_tag();
-        let trigger_config = routine.trigger.to_config_json();
-        let action_type = routine.action.type_tag();
-        let action_config = routine.action.to_config_json();
-        let cooldown_secs = routine.guardrails.cooldown.as_secs() as i64;
-        let max_concurrent = routine.guardrails.max_concurrent as i64;
-        let dedup_window_secs = routine.guardrails.dedup_window.map(|d| d.as_secs() as i64);
-
         conn.execute(
-                r#"
-                INSERT INTO routines (
-                    id, name, description, user_id, enabled,
-                    trigger_type, trigger_config, action_type, action_config,
-                    cooldown_secs, max_concurrent, dedup_window_secs,
-                    notify_channel, notify_user, notify_on_success, notify_on_failure, notify_on_attention,
-                    state, next_fire_at, created_at, updated_at
-                ) VALUES (
-                    ?1, ?2, ?3, ?4, ?5,
-                    ?6, ?7, ?8, ?9,
-                    ?10, ?11, ?12,
-                    ?13, ?14, ?15, ?16, ?17,
-                    ?18, ?19, ?20, ?21
-                )
-                "#,
-                params![
-                    routine.id.to_string(),
-                    routine.name.as_str(),
-                    routine.description.as_str(),
-                    routine.user_id.as_str(),
-                    routine.enabled as i64,
-                    trigger_type,
-                    trigger_config.to_string(),
-                    action_type,
-                    action_config.to_string(),
-                    cooldown_secs,
-                    max_concurrent,
-                    dedup_window_secs,
-                    opt_text(routine.notify.channel.as_deref()),
-                    routine.notify.user.as_str(),
-                    routine.notify.on_success as i64,
-                    routine.notify.on_failure as i64,
-                    routine.notify.on_attention as i64,
-                    routine.state.to_string(),
-                    fmt_opt_ts(&routine.next_fire_at),
-                    fmt_ts(&routine.created_at),
-                    fmt_ts(&routine.updated_at),
-                ],
-            )
-            .await
-            .map_err(|e| DatabaseError::Query(e.to_string()))?;
+            r#"
+            INSERT INTO job_transcript_entries (job_id, seq, model, request, response, created_at)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+            "#,
+            params![
+                job_id.to_string(),
+                entry.seq,
+                entry.model.as_str(),
+                request,
+                response,
+                fmt_ts(&entry.created_at),
+            ],
+        )
+        .await
+        .map_err(|e| DatabaseError::Query(e.to_string()))?;
         Ok(())
     }
 
-    async fn get_routine(&self, id: Uuid) -> Result<Option<Routine>, DatabaseError> {
+    #[tracing::instrument(name = "db.get_job_transcript", skip_all)]
+    async fn get_job_transcript(
+        &self,
+        job_id: Uuid,
+    ) -> Result<Vec<TranscriptEntry>, DatabaseError> {
         let conn = self.connect()?;
         let mut rows = conn
             .query(
-                &format!("SELECT {} FROM routines WHERE id = ?1", ROUTINE_COLUMNS),
-                params![id.to_string()],
+                r#"
+                SELECT seq, model, request, response, created_at
+                FROM job_transcript_entries WHERE job_id = ?1 ORDER BY seq ASC
+                "#,
+                params![job_id.to_string()],
             )
             .await
             .map_err(|e| DatabaseError::Query(e.to_string()))?;
 
-        match rows
+        let mut entries = Vec::new();
+        while let Some(row) = rows
             .next()
             .await
             .map_err(|e| DatabaseError::Query(e.to_string()))?
         {
-            Some(row) => Ok(Some(row_to_routine_libsql(&row)?)),
-            None => Ok(None),
+            entries.push(TranscriptEntry {
+                seq: get_i64(&row, 0) as i32,
+                model: get_text(&row, 1),
+                request: serde_json::from_value(get_json(&row, 2))
+                    .map_err(|e| DatabaseError::Serialization(e.to_string()))?,
+                response: serde_json::from_value(get_json(&row, 3))
+                    .map_err(|e| DatabaseError::Serialization(e.to_string()))?,
+                created_at: get_ts(&row, 4),
+            });
         }
+        Ok(entries)
     }
 
-    async fn get_routine_by_name(
-        &self,
-        user_id: &str,
-        name: &str,
-    ) -> Result<Option<Routine>, DatabaseError> {
+    // ==================== Audit Log ====================
+
+    #[tracing::instrument(name = "db.append_audit_event", skip_all)]
+    async fn append_audit_event(&self, event: &AuditEvent) -> Result<i64, DatabaseError> {
         let conn = self.connect()?;
         let mut rows = conn
             .query(
-                &format!(
-                    "SELECT {} FROM routines WHERE user_id = ?1 AND name = ?2",
-                    ROUTINE_COLUMNS
-                ),
-                params![user_id, name],
+                r#"
+                INSERT INTO audit_log (kind, actor, channel, target, outcome, details, created_at)
+                VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+                RETURNING id
+                "#,
+                params![
+                    event.kind.as_str(),
+                    event.actor.as_str(),
+                    opt_text(event.channel.as_deref()),
+                    opt_text(event.target.as_deref()),
+                    opt_text(event.outcome.as_deref()),
+                    event.details.to_string(),
+                    fmt_ts(&Utc::now()),
+                ],
             )
             .await
             .map_err(|e| DatabaseError::Query(e.to_string()))?;
@@ -1408,162 +1689,198 @@ impl Database for LibSqlBackend {
             .await
             .map_err(|e| DatabaseError::Query(e.to_string()))?
         {
-            Some(row) => Ok(Some(row_to_routine_libsql(&row)?)),
-            None => Ok(None),
+            Some(row) => Ok(get_i64(&row, 0)),
+            None => Err(DatabaseError::Query(
+                "audit_log insert returned no id".to_string(),
+            )),
         }
     }
 
-    async fn list_routines(&self, user_id: &str) -> Result<Vec<Routine>, DatabaseError> {
+    #[tracing::instrument(name = "db.query_audit_log", skip_all)]
+    async fn query_audit_log(&self, query: &AuditQuery) -> Result<Vec<AuditRecord>, DatabaseError> {
         let conn = self.connect()?;
         let mut rows = conn
             .query(
-                &format!(
-                    "SELECT {} FROM routines WHERE user_id = ?1 ORDER BY name",
-                    ROUTINE_COLUMNS
-                ),
-                params![user_id],
+                r#"
+                SELECT id, kind, actor, channel, target, outcome, details, created_at
+                FROM audit_log
+                WHERE (?1 IS NULL OR kind = ?1)
+                  AND (?2 IS NULL OR actor = ?2)
+                  AND (?3 IS NULL OR created_at >= ?3)
+                  AND (?4 IS NULL OR created_at < ?4)
+                  AND (?5 IS NULL OR id < ?5)
+                ORDER BY id DESC
+                LIMIT ?6
+                "#,
+                params![
+                    opt_text(query.kind.map(|k| k.as_str())),
+                    opt_text(query.actor.as_deref()),
+                    fmt_opt_ts(&query.since),
+                    fmt_opt_ts(&query.until),
+                    match query.before_id {
+                        Some(id) => libsql::Value::Integer(id),
+                        None => libsql::Value::Null,
+                    },
+                    query.effective_limit(),
+                ],
             )
             .await
             .map_err(|e| DatabaseError::Query(e.to_string()))?;
 
-        let mut routines = Vec::new();
+        let mut records = Vec::new();
         while let Some(row) = rows
             .next()
             .await
             .map_err(|e| DatabaseError::Query(e.to_string()))?
         {
-            routines.push(row_to_routine_libsql(&row)?);
+            records.push(AuditRecord {
+                id: get_i64(&row, 0),
+                kind: get_text(&row, 1)
+                    .parse()
+                    .map_err(DatabaseError::Serialization)?,
+                actor: get_text(&row, 2),
+                channel: get_opt_text(&row, 3),
+                target: get_opt_text(&row, 4),
+                outcome: get_opt_text(&row, 5),
+                details: get_json(&row, 6),
+                created_at: get_ts(&row, 7),
+            });
         }
-        Ok(routines)
+        Ok(records)
     }
 
-    async fn list_event_routines(&self) -> Result<Vec<Routine>, DatabaseError> {
+    #[tracing::instrument(name = "db.prune_audit_log", skip_all)]
+    async fn prune_audit_log(&self, cutoff: DateTime<Utc>) -> Result<u64, DatabaseError> {
+        let conn = self.connect()?;
+        let count = conn
+            .execute(
+                "DELETE FROM audit_log WHERE created_at < ?1",
+                params![fmt_ts(&cutoff)],
+            )
+            .await
+            .map_err(|e| DatabaseError::Query(e.to_string()))?;
+        Ok(count)
+    }
+
+    // ==================== Agents ====================
+
+    #[tracing::instrument(name = "db.create_agent", skip_all)]
+    async fn create_agent(&self, agent: &AgentDefinition) -> Result<(), DatabaseError> {
+        let (tools, channels, seed) = agent_json_columns(agent)?;
+        let conn = self.connect()?;
+        conn.execute(
+            r#"
+                INSERT INTO agents (
+                    id, name, description, enabled, model,
+                    tools, channels, workspace_seed, created_at, updated_at
+                ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)
+                "#,
+            params![
+                agent.id.to_string(),
+                agent.name.as_str(),
+                agent.description.as_str(),
+                agent.enabled as i64,
+                opt_text(agent.model.as_deref()),
+                tools,
+                channels,
+                seed,
+                fmt_ts(&agent.created_at),
+                fmt_ts(&agent.updated_at),
+            ],
+        )
+        .await
+        .map_err(|e| DatabaseError::Query(e.to_string()))?;
+        Ok(())
+    }
+
+    #[tracing::instrument(name = "db.get_agent", skip_all)]
+    async fn get_agent(&self, id: Uuid) -> Result<Option<AgentDefinition>, DatabaseError> {
         let conn = self.connect()?;
         let mut rows = conn
             .query(
-                &format!(
-                    "SELECT {} FROM routines WHERE enabled = 1 AND trigger_type = 'event'",
-                    ROUTINE_COLUMNS
-                ),
-                (),
+                &format!("SELECT {} FROM agents WHERE id = ?1", AGENT_COLUMNS),
+                params![id.to_string()],
             )
             .await
             .map_err(|e| DatabaseError::Query(e.to_string()))?;
 
-        let mut routines = Vec::new();
-        while let Some(row) = rows
+        match rows
             .next()
             .await
             .map_err(|e| DatabaseError::Query(e.to_string()))?
         {
-            routines.push(row_to_routine_libsql(&row)?);
+            Some(row) => Ok(Some(row_to_agent_libsql(&row)?)),
+            None => Ok(None),
         }
-        Ok(routines)
     }
 
-    async fn list_due_cron_routines(&self) -> Result<Vec<Routine>, DatabaseError> {
+    #[tracing::instrument(name = "db.get_agent_by_name", skip_all)]
+    async fn get_agent_by_name(
+        &self,
+        name: &str,
+    ) -> Result<Option<AgentDefinition>, DatabaseError> {
         let conn = self.connect()?;
-        let now = fmt_ts(&Utc::now());
         let mut rows = conn
             .query(
-                &format!(
-                    "SELECT {} FROM routines WHERE enabled = 1 AND trigger_type = 'cron' AND next_fire_at IS NOT NULL AND next_fire_at <= ?1",
-                    ROUTINE_COLUMNS
-                ),
-                params![now],
+                &format!("SELECT {} FROM agents WHERE name = ?1", AGENT_COLUMNS),
+                params![name],
             )
             .await
             .map_err(|e| DatabaseError::Query(e.to_string()))?;
 
-        let mut routines = Vec::new();
-        while let Some(row) = rows
+        match rows
             .next()
             .await
             .map_err(|e| DatabaseError::Query(e.to_string()))?
         {
-            routines.push(row_to_routine_libsql(&row)?);
+            Some(row) => Ok(Some(row_to_agent_libsql(&row)?)),
+            None => Ok(None),
         }
-        Ok(routines)
     }
 
-    async fn update_routine(&self, routine: &Routine) -> Result<(), DatabaseError> {
+    #[tracing::instrument(name = "db.list_agents", skip_all)]
+    async fn list_agents(&self) -> Result<Vec<AgentDefinition>, DatabaseError> {
         let conn = self.connect()?;
-        let trigger_type = routine.trigger.type_tag();
-        let trigger_config = routine.trigger.to_config_json();
-        let action_type = routine.action.type_tag();
-        let action_config = routine.action.to_config_json();
-        let cooldown_secs = routine.guardrails.cooldown.as_secs() as i64;
-        let max_concurrent = routine.guardrails.max_concurrent as i64;
-        let dedup_window_secs = routine.guardrails.dedup_window.map(|d| d.as_secs() as i64);
-        let now = fmt_ts(&Utc::now());
+        let mut rows = conn
+            .query(
+                &format!("SELECT {} FROM agents ORDER BY name", AGENT_COLUMNS),
+                (),
+            )
+            .await
+            .map_err(|e| DatabaseError::Query(e.to_string()))?;
 
-        conn.execute(
-            r#"
-                UPDATE routines SET
-                    name = ?2, description = ?3, enabled = ?4,
-                    trigger_type = ?5, trigger_config = ?6,
-                    action_type = ?7, action_config = ?8,
-                    cooldown_secs = ?9, max_concurrent = ?10, dedup_window_secs = ?11,
-                    notify_channel = ?12, notify_user = ?13,
-                    notify_on_success = ?14, notify_on_failure = ?15, notify_on_attention = ?16,
-                    state = ?17, next_fire_at = ?18,
-                    updated_at = ?19
-                WHERE id = ?1
-                "#,
-            params![
-                routine.id.to_string(),
-                routine.name.as_str(),
-                routine.description.as_str(),
-                routine.enabled as i64,
-                trigger_type,
-                trigger_config.to_string(),
-                action_type,
-                action_config.to_string(),
-                cooldown_secs,
-                max_concurrent,
-                dedup_window_secs,
-                opt_text(routine.notify.channel.as_deref()),
-                routine.notify.user.as_str(),
-                routine.notify.on_success as i64,
-                routine.notify.on_failure as i64,
-                routine.notify.on_attention as i64,
-                routine.state.to_string(),
-                fmt_opt_ts(&routine.next_fire_at),
-                now,
-            ],
-        )
-        .await
-        .map_err(|e| DatabaseError::Query(e.to_string()))?;
-        Ok(())
+        let mut agents = Vec::new();
+        while let Some(row) = rows
+            .next()
+            .await
+            .map_err(|e| DatabaseError::Query(e.to_string()))?
+        {
+            agents.push(row_to_agent_libsql(&row)?);
+        }
+        Ok(agents)
     }
 
-    async fn update_routine_runtime(
-        &self,
-        id: Uuid,
-        last_run_at: DateTime<Utc>,
-        next_fire_at: Option<DateTime<Utc>>,
-        run_count: u64,
-        consecutive_failures: u32,
-        state: &serde_json::Value,
-    ) -> Result<(), DatabaseError> {
+    #[tracing::instrument(name = "db.update_agent", skip_all)]
+    async fn update_agent(&self, agent: &AgentDefinition) -> Result<(), DatabaseError> {
+        let (tools, channels, seed) = agent_json_columns(agent)?;
         let conn = self.connect()?;
-        let now = fmt_ts(&Utc::now());
         conn.execute(
             r#"
-                UPDATE routines SET
-                    last_run_at = ?2, next_fire_at = ?3,
-                    run_count = ?4, consecutive_failures = ?5,
-                    state = ?6, updated_at = ?7
+                UPDATE agents SET
+                    name = ?2, description = ?3, enabled = ?4, model = ?5,
+                    tools = ?6, channels = ?7, workspace_seed = ?8, updated_at = ?9
                 WHERE id = ?1
                 "#,
             params![
-                id.to_string(),
-                fmt_ts(&last_run_at),
-                fmt_opt_ts(&next_fire_at),
-                run_count as i64,
-                consecutive_failures as i64,
-                state.to_string(),
-                now,
+                agent.id.to_string(),
+                agent.name.as_str(),
+                agent.description.as_str(),
+                agent.enabled as i64,
+                opt_text(agent.model.as_deref()),
+                tools,
+                channels,
+                seed,
+                fmt_ts(&agent.updated_at),
             ],
         )
         .await
@@ -1571,66 +1888,40 @@ impl Database for LibSqlBackend {
         Ok(())
     }
 
-    async fn delete_routine(&self, id: Uuid) -> Result<bool, DatabaseError> {
+    #[tracing::instrument(name = "db.delete_agent", skip_all)]
+    async fn delete_agent(&self, id: Uuid) -> Result<bool, DatabaseError> {
         let conn = self.connect()?;
         let count = conn
-            .execute(
-                "DELETE FROM routines WHERE id = ?1",
-                params![id.to_string()],
-            )
+            .execute("DELETE FROM agents WHERE id = ?1", params![id.to_string()])
             .await
             .map_err(|e| DatabaseError::Query(e.to_string()))?;
         Ok(count > 0)
     }
 
-    // ==================== Routine Runs ====================
-
-    async fn create_routine_run(&self, run: &RoutineRun) -> Result<(), DatabaseError> {
-        let conn = self.connect()?;
-        conn.execute(
-            r#"
-                INSERT INTO routine_runs (
-                    id, routine_id, trigger_type, trigger_detail,
-                    started_at, status, job_id
-                ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
-                "#,
-            params![
-                run.id.to_string(),
-                run.routine_id.to_string(),
-                run.trigger_type.as_str(),
-                opt_text(run.trigger_detail.as_deref()),
-                fmt_ts(&run.started_at),
-                run.status.to_string(),
-                opt_text_owned(run.job_id.map(|id| id.to_string())),
-            ],
-        )
-        .await
-        .map_err(|e| DatabaseError::Query(e.to_string()))?;
-        Ok(())
-    }
+    // ==================== Contacts ====================
 
-    async fn complete_routine_run(
-        &self,
-        id: Uuid,
-        status: RunStatus,
-        result_summary: Option<&str>,
-        tokens_used: Option<i32>,
-    ) -> Result<(), DatabaseError> {
+    #[tracing::instrument(name = "db.create_contact", skip_all)]
+    async fn create_contact(&self, contact: &Contact) -> Result<(), DatabaseError> {
+        let (emails, handles) = contact_json_columns(contact)?;
         let conn = self.connect()?;
-        let now = fmt_ts(&Utc::now());
         conn.execute(
             r#"
-                UPDATE routine_runs SET
-                    completed_at = ?5, status = ?2,
-                    result_summary = ?3, tokens_used = ?4
-                WHERE id = ?1
+                INSERT INTO contacts (
+                    id, user_id, name, relationship, emails, handles, notes,
+                    last_seen_at, created_at, updated_at
+                ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)
                 "#,
             params![
-                id.to_string(),
-                status.to_string(),
-                opt_text(result_summary),
-                tokens_used.map(|t| t as i64),
-                now,
+                contact.id.to_string(),
+                contact.user_id.as_str(),
+                contact.name.as_str(),
+                contact.relationship.as_str(),
+                emails,
+                handles,
+                contact.notes.as_str(),
+                fmt_opt_ts(&contact.last_seen_at),
+                fmt_ts(&contact.created_at),
+                fmt_ts(&contact.updated_at),
             ],
         )
         .await
@@ -1638,448 +1929,2202 @@ impl Database for LibSqlBackend {
         Ok(())
     }
 
-    async fn list_routine_runs(
-        &self,
-        routine_id: Uuid,
-        limit: i64,
-    ) -> Result<Vec<RoutineRun>, DatabaseError> {
+    #[tracing::instrument(name = "db.get_contact", skip_all)]
+    async fn get_contact(&self, id: Uuid) -> Result<Option<Contact>, DatabaseError> {
         let conn = self.connect()?;
         let mut rows = conn
             .query(
-                &format!(
-                    "SELECT {} FROM routine_runs WHERE routine_id = ?1 ORDER BY started_at DESC LIMIT ?2",
-                    ROUTINE_RUN_COLUMNS
-                ),
-                params![routine_id.to_string(), limit],
+                &format!("SELECT {} FROM contacts WHERE id = ?1", CONTACT_COLUMNS),
+                params![id.to_string()],
             )
             .await
             .map_err(|e| DatabaseError::Query(e.to_string()))?;
 
-        let mut runs = Vec::new();
-        while let Some(row) = rows
+        match rows
             .next()
             .await
             .map_err(|e| DatabaseError::Query(e.to_string()))?
         {
-            runs.push(row_to_routine_run_libsql(&row)?);
+            Some(row) => Ok(Some(row_to_contact_libsql(&row)?)),
+            None => Ok(None),
         }
-        Ok(runs)
     }
 
-    async fn count_running_routine_runs(&self, routine_id: Uuid) -> Result<i64, DatabaseError> {
+    #[tracing::instrument(name = "db.list_contacts", skip_all)]
+    async fn list_contacts(&self, user_id: &str) -> Result<Vec<Contact>, DatabaseError> {
         let conn = self.connect()?;
         let mut rows = conn
             .query(
-                "SELECT COUNT(*) as cnt FROM routine_runs WHERE routine_id = ?1 AND status = 'running'",
-                params![routine_id.to_string()],
+                &format!(
+                    "SELECT {} FROM contacts WHERE user_id = ?1 ORDER BY lower(name)",
+                    CONTACT_COLUMNS
+                ),
+                params![user_id],
             )
             .await
             .map_err(|e| DatabaseError::Query(e.to_string()))?;
 
-        match rows
+        let mut contacts = Vec::new();
+        while let Some(row) = rows
             .next()
             .await
             .map_err(|e| DatabaseError::Query(e.to_string()))?
         {
-            Some(row) => Ok(get_i64(&row, 0)),
-            None => Ok(0),
+            contacts.push(row_to_contact_libsql(&row)?);
         }
+        Ok(contacts)
     }
 
-    // ==================== Tool Failures ====================
-
-    async fn record_tool_failure(
-        &self,
-        tool_name: &str,
-        error_message: &str,
-    ) -> Result<(), DatabaseError> {
+    #[tracing::instrument(name = "db.update_contact", skip_all)]
+    async fn update_contact(&self, contact: &Contact) -> Result<(), DatabaseError> {
+        let (emails, handles) = contact_json_columns(contact)?;
         let conn = self.connect()?;
-        let now = fmt_ts(&Utc::now());
         conn.execute(
             r#"
-                INSERT INTO tool_failures (id, tool_name, error_message, error_count, last_failure)
-                VALUES (?1, ?2, ?3, 1, ?4)
-                ON CONFLICT (tool_name) DO UPDATE SET
-                    error_message = ?3,
-                    error_count = tool_failures.error_count + 1,
-                    last_failure = ?4
+                UPDATE contacts SET
+                    name = ?2, relationship = ?3, emails = ?4, handles = ?5, notes = ?6,
+                    last_seen_at = ?7, updated_at = ?8
+                WHERE id = ?1
                 "#,
-            params![Uuid::new_v4().to_string(), tool_name, error_message, now],
+            params![
+                contact.id.to_string(),
+                contact.name.as_str(),
+                contact.relationship.as_str(),
+                emails,
+                handles,
+                contact.notes.as_str(),
+                fmt_opt_ts(&contact.last_seen_at),
+                fmt_ts(&contact.updated_at),
+            ],
         )
         .await
         .map_err(|e| DatabaseError::Query(e.to_string()))?;
         Ok(())
     }
 
-    async fn get_broken_tools(&self, threshold: i32) -> Result<Vec<BrokenTool>, DatabaseError> {
+    #[tracing::instrument(name = "db.delete_contact", skip_all)]
+    async fn delete_contact(&self, id: Uuid) -> Result<bool, DatabaseError> {
         let conn = self.connect()?;
-        let mut rows = conn
-            .query(
+        let count = conn
+            .execute(
+                "DELETE FROM contacts WHERE id = ?1",
+                params![id.to_string()],
+            )
+            .await
+            .map_err(|e| DatabaseError::Query(e.to_string()))?;
+        Ok(count > 0)
+    }
+
+    // ==================== Leases ====================
+
+    #[tracing::instrument(name = "db.try_acquire_lease", skip_all)]
+    async fn try_acquire_lease(
+        &self,
+        name: &str,
+        owner: &str,
+        ttl: Duration,
+    ) -> Result<bool, DatabaseError> {
+        let now = Utc::now();
+        let expires_at = now + chrono::Duration::from_std(ttl).unwrap_or(chrono::Duration::MAX);
+        let conn = self.connect()?;
+        let count = conn
+            .execute(
                 r#"
-                SELECT tool_name, error_message, error_count, first_failure, last_failure,
-                       last_build_result, repair_attempts
-                FROM tool_failures
-                WHERE error_count >= ?1 AND repaired_at IS NULL
-                ORDER BY error_count DESC
+                INSERT INTO leases (name, owner, acquired_at, expires_at)
+                VALUES (?1, ?2, ?3, ?4)
+                ON CONFLICT (name) DO UPDATE SET
+                    owner = excluded.owner,
+                    acquired_at = CASE
+                        WHEN leases.owner = excluded.owner THEN leases.acquired_at
+                        ELSE excluded.acquired_at
+                    END,
+                    expires_at = excluded.expires_at
+                WHERE leases.owner = excluded.owner OR leases.expires_at <= ?3
                 "#,
-                params![threshold as i64],
+                params![name, owner, fmt_ts(&now), fmt_ts(&expires_at)],
             )
             .await
             .map_err(|e| DatabaseError::Query(e.to_string()))?;
+        Ok(count > 0)
+    }
 
-        let mut tools = Vec::new();
-        while let Some(row) = rows
-            .next()
+    #[tracing::instrument(name = "db.renew_lease", skip_all)]
+    async fn renew_lease(
+        &self,
+        name: &str,
+        owner: &str,
+        ttl: Duration,
+    ) -> Result<bool, DatabaseError> {
+        let expires_at =
+            Utc::now() + chrono::Duration::from_std(ttl).unwrap_or(chrono::Duration::MAX);
+        let conn = self.connect()?;
+        let count = conn
+            .execute(
+                "UPDATE leases SET expires_at = ?3 WHERE name = ?1 AND owner = ?2",
+                params![name, owner, fmt_ts(&expires_at)],
+            )
             .await
-            .map_err(|e| DatabaseError::Query(e.to_string()))?
-        {
-            tools.push(BrokenTool {
-                name: get_text(&row, 0),
-                last_error: get_opt_text(&row, 1),
-                failure_count: get_i64(&row, 2) as u32,
-                first_failure: get_ts(&row, 3),
-                last_failure: get_ts(&row, 4),
-                last_build_result: get_opt_text(&row, 5)
-                    .and_then(|s| serde_json::from_str(&s).ok()),
-                repair_attempts: get_i64(&row, 6) as u32,
-            });
-        }
-        Ok(tools)
+            .map_err(|e| DatabaseError::Query(e.to_string()))?;
+        Ok(count > 0)
     }
 
-    async fn mark_tool_repaired(&self, tool_name: &str) -> Result<(), DatabaseError> {
+    #[tracing::instrument(name = "db.release_lease", skip_all)]
+    async fn release_lease(&self, name: &str, owner: &str) -> Result<(), DatabaseError> {
         let conn = self.connect()?;
-        let now = fmt_ts(&Utc::now());
         conn.execute(
-            "UPDATE tool_failures SET repaired_at = ?2, error_count = 0 WHERE tool_name = ?1",
-            params![tool_name, now],
+            "DELETE FROM leases WHERE name = ?1 AND owner = ?2",
+            params![name, owner],
         )
         .await
         .map_err(|e| DatabaseError::Query(e.to_string()))?;
         Ok(())
     }
 
-    async fn increment_repair_attempts(&self, tool_name: &str) -> Result<(), DatabaseError> {
+    #[tracing::instrument(name = "db.get_lease", skip_all)]
+    async fn get_lease(&self, name: &str) -> Result<Option<LeaseRecord>, DatabaseError> {
         let conn = self.connect()?;
-        conn.execute(
-            "UPDATE tool_failures SET repair_attempts = repair_attempts + 1 WHERE tool_name = ?1",
-            params![tool_name],
-        )
-        .await
-        .map_err(|e| DatabaseError::Query(e.to_string()))?;
-        Ok(())
+        let mut rows = conn
+            .query(
+                "SELECT name, owner, acquired_at, expires_at FROM leases WHERE name = ?1",
+                params![name],
+            )
+            .await
+            .map_err(|e| DatabaseError::Query(e.to_string()))?;
+        let row = rows
+            .next()
+            .await
+            .map_err(|e| DatabaseError::Query(e.to_string()))?;
+        Ok(row.map(|r| LeaseRecord {
+            name: get_text(&r, 0),
+            owner: get_text(&r, 1),
+            acquired_at: get_ts(&r, 2),
+            expires_at: get_ts(&r, 3),
+        }))
     }
 
-    // ==================== Settings ====================
+    // ==================== Knowledge Graph ====================
 
-    async fn get_setting(
+    #[tracing::instrument(name = "db.upsert_graph_entity", skip_all)]
+    async fn upsert_graph_entity(
         &self,
         user_id: &str,
-        key: &str,
-    ) -> Result<Option<serde_json::Value>, DatabaseError> {
+        agent_id: Option<Uuid>,
+        name: &str,
+        kind: &str,
+    ) -> Result<GraphEntity, DatabaseError> {
+        let key = entity_key(name);
+        let agent_id_str = agent_id.map(|id| id.to_string());
         let conn = self.connect()?;
         let mut rows = conn
             .query(
-                "SELECT value FROM settings WHERE user_id = ?1 AND key = ?2",
-                params![user_id, key],
+                &format!(
+                    "SELECT {} FROM graph_entities \
+                     WHERE user_id = ?1 AND agent_id IS ?2 AND name_key = ?3",
+                    GRAPH_ENTITY_COLUMNS
+                ),
+                params![user_id, agent_id_str.as_deref(), key.as_str()],
             )
             .await
             .map_err(|e| DatabaseError::Query(e.to_string()))?;
-
-        match rows
+        if let Some(row) = rows
             .next()
             .await
             .map_err(|e| DatabaseError::Query(e.to_string()))?
         {
-            Some(row) => Ok(Some(get_json(&row, 0))),
-            None => Ok(None),
+            let entity = row_to_graph_entity_libsql(&row);
+            if entity.kind.is_empty() && !kind.is_empty() {
+                conn.execute(
+                    "UPDATE graph_entities SET kind = ?2 WHERE id = ?1",
+                    params![entity.id.to_string(), kind],
+                )
+                .await
+                .map_err(|e| DatabaseError::Query(e.to_string()))?;
+                return Ok(GraphEntity {
+                    kind: kind.to_string(),
+                    ..entity
+                });
+            }
+            return Ok(entity);
         }
+
+        let entity = GraphEntity {
+            id: Uuid::new_v4(),
+            user_id: user_id.to_string(),
+            agent_id,
+            name: name.to_string(),
+            kind: kind.to_string(),
+            created_at: Utc::now(),
+        };
+        conn.execute(
+            r#"
+                INSERT INTO graph_entities (id, user_id, agent_id, name, name_key, kind, created_at)
+                VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+                "#,
+            params![
+                entity.id.to_string(),
+                user_id,
+                agent_id_str.as_deref(),
+                name,
+                key.as_str(),
+                kind,
+                fmt_ts(&entity.created_at),
+            ],
+        )
+        .await
+        .map_err(|e| DatabaseError::Query(e.to_string()))?;
+        Ok(entity)
     }
 
-    async fn get_setting_full(
+    #[tracing::instrument(name = "db.find_graph_entities", skip_all)]
+    async fn find_graph_entities(
         &self,
         user_id: &str,
+        agent_id: Option<Uuid>,
         key: &str,
-    ) -> Result<Option<SettingRow>, DatabaseError> {
+        limit: usize,
+    ) -> Result<Vec<GraphEntity>, DatabaseError> {
+        let agent_id_str = agent_id.map(|id| id.to_string());
         let conn = self.connect()?;
         let mut rows = conn
             .query(
-                "SELECT key, value, updated_at FROM settings WHERE user_id = ?1 AND key = ?2",
-                params![user_id, key],
+                &format!(
+                    "SELECT {} FROM graph_entities \
+                     WHERE user_id = ?1 AND agent_id IS ?2 AND instr(name_key, ?3) > 0 \
+                     ORDER BY (name_key = ?3) DESC, length(name_key), name_key \
+                     LIMIT ?4",
+                    GRAPH_ENTITY_COLUMNS
+                ),
+                params![user_id, agent_id_str.as_deref(), key, limit as i64],
             )
             .await
             .map_err(|e| DatabaseError::Query(e.to_string()))?;
 
-        match rows
+        let mut entities = Vec::new();
+        while let Some(row) = rows
             .next()
             .await
             .map_err(|e| DatabaseError::Query(e.to_string()))?
         {
-            Some(row) => Ok(Some(SettingRow {
-                key: get_text(&row, 0),
-                value: get_json(&row, 1),
-                updated_at: get_ts(&row, 2),
-            })),
-            None => Ok(None),
+            entities.push(row_to_graph_entity_libsql(&row));
         }
+        Ok(entities)
     }
 
-    async fn set_setting(
-        &self,
-        user_id: &str,
-        key: &str,
-        value: &serde_json::Value,
-    ) -> Result<(), DatabaseError> {
+    #[tracing::instrument(name = "db.get_graph_entities", skip_all)]
+    async fn get_graph_entities(&self, ids: &[Uuid]) -> Result<Vec<GraphEntity>, DatabaseError> {
+        let conn = self.connect()?;
+        let mut rows = conn
+            .query(
+                &format!(
+                    "SELECT {} FROM graph_entities \
+                     WHERE id IN (SELECT value FROM json_each(?1))",
+                    GRAPH_ENTITY_COLUMNS
+                ),
+                params![uuid_json_array(ids)],
+            )
+            .await
+            .map_err(|e| DatabaseError::Query(e.to_string()))?;
+
+        let mut entities = Vec::new();
+        while let Some(row) = rows
+            .next()
+            .await
+            .map_err(|e| DatabaseError::Query(e.to_string()))?
+        {
+            entities.push(row_to_graph_entity_libsql(&row));
+        }
+        Ok(entities)
+    }
+
+    #[tracing::instrument(name = "db.insert_graph_edge", skip_all)]
+    async fn insert_graph_edge(&self, edge: &GraphEdge) -> Result<(), DatabaseError> {
         let conn = self.connect()?;
-        let now = fmt_ts(&Utc::now());
         conn.execute(
             r#"
-                INSERT INTO settings (user_id, key, value, updated_at)
-                VALUES (?1, ?2, ?3, ?4)
-                ON CONFLICT (user_id, key) DO UPDATE SET
-                    value = excluded.value,
-                    updated_at = ?4
+                INSERT INTO graph_edges (id, source_id, relation, target_id, document_id, created_at)
+                VALUES (?1, ?2, ?3, ?4, ?5, ?6)
                 "#,
-            params![user_id, key, value.to_string(), now],
+            params![
+                edge.id.to_string(),
+                edge.source_id.to_string(),
+                edge.relation.as_str(),
+                edge.target_id.to_string(),
+                opt_text_owned(edge.document_id.map(|id| id.to_string())),
+                fmt_ts(&edge.created_at),
+            ],
         )
         .await
         .map_err(|e| DatabaseError::Query(e.to_string()))?;
         Ok(())
     }
 
-    async fn delete_setting(&self, user_id: &str, key: &str) -> Result<bool, DatabaseError> {
+    #[tracing::instrument(name = "db.graph_edges_touching", skip_all)]
+    async fn graph_edges_touching(
+        &self,
+        entity_ids: &[Uuid],
+    ) -> Result<Vec<GraphEdge>, DatabaseError> {
+        let conn = self.connect()?;
+        let mut rows = conn
+            .query(
+                r#"
+                SELECT id, source_id, relation, target_id, document_id, created_at
+                FROM graph_edges
+                WHERE source_id IN (SELECT value FROM json_each(?1))
+                   OR target_id IN (SELECT value FROM json_each(?1))
+                ORDER BY created_at
+                "#,
+                params![uuid_json_array(entity_ids)],
+            )
+            .await
+            .map_err(|e| DatabaseError::Query(e.to_string()))?;
+
+        let mut edges = Vec::new();
+        while let Some(row) = rows
+            .next()
+            .await
+            .map_err(|e| DatabaseError::Query(e.to_string()))?
+        {
+            edges.push(GraphEdge {
+                id: get_text(&row, 0).parse().unwrap_or_default(),
+                source_id: get_text(&row, 1).parse().unwrap_or_default(),
+                relation: get_text(&row, 2),
+                target_id: get_text(&row, 3).parse().unwrap_or_default(),
+                document_id: get_opt_text(&row, 4).and_then(|s| s.parse().ok()),
+                created_at: get_ts(&row, 5),
+            });
+        }
+        Ok(edges)
+    }
+
+    #[tracing::instrument(name = "db.delete_graph_edges_for_document", skip_all)]
+    async fn delete_graph_edges_for_document(
+        &self,
+        document_id: Uuid,
+    ) -> Result<u64, DatabaseError> {
         let conn = self.connect()?;
         let count = conn
             .execute(
-                "DELETE FROM settings WHERE user_id = ?1 AND key = ?2",
-                params![user_id, key],
+                "DELETE FROM graph_edges WHERE document_id = ?1",
+                params![document_id.to_string()],
             )
             .await
             .map_err(|e| DatabaseError::Query(e.to_string()))?;
-        Ok(count > 0)
+        Ok(count)
     }
 
-    async fn list_settings(&self, user_id: &str) -> Result<Vec<SettingRow>, DatabaseError> {
+    // ==================== Routines ====================
+
+    #[tracing::instrument(name = "db.create_routine", skip_all)]
+    async fn create_routine(&self, routine: &Routine) -> Result<(), DatabaseError> {
+        let conn = self.connect()?;
+        let trigger_type = routine.trigger.type_tag();
+        let trigger_config = routine.trigger.to_config_json();
+        let action_type = routine.action.type_tag();
+        let action_config = routine.action.to_config_json();
+        let cooldown_secs = routine.guardrails.cooldown.as_secs() as i64;
+        let max_concurrent = routine.guardrails.max_concurrent as i64;
+        let dedup_window_secs = routine.guardrails.dedup_window.map(|d| d.as_secs() as i64);
+
+        conn.execute(
+                r#"
+                INSERT INTO routines (
+                    id, name, description, user_id, enabled,
+                    trigger_type, trigger_config, action_type, action_config,
+                    cooldown_secs, max_concurrent, dedup_window_secs,
+                    notify_channel, notify_user, notify_on_success, notify_on_failure, notify_on_attention,
+                    state, next_fire_at, created_at, updated_at
+                ) VALUES (
+                    ?1, ?2, ?3, ?4, ?5,
+                    ?6, ?7, ?8, ?9,
+                    ?10, ?11, ?12,
+                    ?13, ?14, ?15, ?16, ?17,
+                    ?18, ?19, ?20, ?21
+                )
+                "#,
+                params![
+                    routine.id.to_string(),
+                    routine.name.as_str(),
+                    routine.description.as_str(),
+                    routine.user_id.as_str(),
+                    routine.enabled as i64,
+                    trigger_type,
+                    trigger_config.to_string(),
+                    action_type,
+                    action_config.to_string(),
+                    cooldown_secs,
+                    max_concurrent,
+                    dedup_window_secs,
+                    opt_text(routine.notify.channel.as_deref()),
+                    routine.notify.user.as_str(),
+                    routine.notify.on_success as i64,
+                    routine.notify.on_failure as i64,
+                    routine.notify.on_attention as i64,
+                    routine.state.to_string(),
+                    fmt_opt_ts(&routine.next_fire_at),
+                    fmt_ts(&routine.created_at),
+                    fmt_ts(&routine.updated_at),
+                ],
+            )
+            .await
+            .map_err(|e| DatabaseError::Query(e.to_string()))?;
+        Ok(())
+    }
+
+    #[tracing::instrument(name = "db.get_routine", skip_all)]
+    async fn get_routine(&self, id: Uuid) -> Result<Option<Routine>, DatabaseError> {
         let conn = self.connect()?;
         let mut rows = conn
             .query(
-                "SELECT key, value, updated_at FROM settings WHERE user_id = ?1 ORDER BY key",
-                params![user_id],
+                &format!("SELECT {} FROM routines WHERE id = ?1", ROUTINE_COLUMNS),
+                params![id.to_string()],
             )
             .await
             .map_err(|e| DatabaseError::Query(e.to_string()))?;
 
-        let mut settings = Vec::new();
-        while let Some(row) = rows
+        match rows
             .next()
             .await
             .map_err(|e| DatabaseError::Query(e.to_string()))?
         {
-            settings.push(SettingRow {
-                key: get_text(&row, 0),
-                value: get_json(&row, 1),
-                updated_at: get_ts(&row, 2),
-            });
+            Some(row) => Ok(Some(row_to_routine_libsql(&row)?)),
+            None => Ok(None),
         }
-        Ok(settings)
     }
 
-    async fn get_all_settings(
+    #[tracing::instrument(name = "db.get_routine_by_name", skip_all)]
+    async fn get_routine_by_name(
         &self,
         user_id: &str,
-    ) -> Result<HashMap<String, serde_json::Value>, DatabaseError> {
+        name: &str,
+    ) -> Result<Option<Routine>, DatabaseError> {
         let conn = self.connect()?;
         let mut rows = conn
             .query(
-                "SELECT key, value FROM settings WHERE user_id = ?1",
-                params![user_id],
+                &format!(
+                    "SELECT {} FROM routines WHERE user_id = ?1 AND name = ?2",
+                    ROUTINE_COLUMNS
+                ),
+                params![user_id, name],
             )
             .await
             .map_err(|e| DatabaseError::Query(e.to_string()))?;
 
-        let mut map = HashMap::new();
-        while let Some(row) = rows
+        match rows
             .next()
             .await
             .map_err(|e| DatabaseError::Query(e.to_string()))?
         {
-            map.insert(get_text(&row, 0), get_json(&row, 1));
+            Some(row) => Ok(Some(row_to_routine_libsql(&row)?)),
+            None => Ok(None),
         }
-        Ok(map)
     }
 
-    async fn set_all_settings(
-        &self,
-        user_id: &str,
-        settings: &HashMap<String, serde_json::Value>,
-    ) -> Result<(), DatabaseError> {
+    #[tracing::instrument(name = "db.list_routines", skip_all)]
+    async fn list_routines(&self, user_id: &str) -> Result<Vec<Routine>, DatabaseError> {
         let conn = self.connect()?;
-        let now = fmt_ts(&Utc::now());
-        conn.execute("BEGIN", ())
+        let mut rows = conn
+            .query(
+                &format!(
+                    "SELECT {} FROM routines WHERE user_id = ?1 ORDER BY name",
+                    ROUTINE_COLUMNS
+                ),
+                params![user_id],
+            )
             .await
             .map_err(|e| DatabaseError::Query(e.to_string()))?;
 
-        for (key, value) in settings {
-            if let Err(e) = conn
-                .execute(
-                    r#"
-                    INSERT INTO settings (user_id, key, value, updated_at)
-                    VALUES (?1, ?2, ?3, ?4)
-                    ON CONFLICT (user_id, key) DO UPDATE SET
-                        value = excluded.value,
-                        updated_at = ?4
-                    "#,
-                    params![user_id, key.as_str(), value.to_string(), now.as_str()],
-                )
-                .await
-            {
-                let _ = conn.execute("ROLLBACK", ()).await;
-                return Err(DatabaseError::Query(e.to_string()));
-            }
-        }
-
-        conn.execute("COMMIT", ())
+        let mut routines = Vec::new();
+        while let Some(row) = rows
+            .next()
             .await
-            .map_err(|e| DatabaseError::Query(e.to_string()))?;
-        Ok(())
+            .map_err(|e| DatabaseError::Query(e.to_string()))?
+        {
+            routines.push(row_to_routine_libsql(&row)?);
+        }
+        Ok(routines)
     }
 
-    async fn has_settings(&self, user_id: &str) -> Result<bool, DatabaseError> {
+    #[tracing::instrument(name = "db.list_event_routines", skip_all)]
+    async fn list_event_routines(&self) -> Result<Vec<Routine>, DatabaseError> {
         let conn = self.connect()?;
         let mut rows = conn
             .query(
-                "SELECT COUNT(*) as cnt FROM settings WHERE user_id = ?1",
-                params![user_id],
+                &format!(
+                    "SELECT {} FROM routines WHERE enabled = 1 AND trigger_type = 'event'",
+                    ROUTINE_COLUMNS
+                ),
+                (),
             )
             .await
             .map_err(|e| DatabaseError::Query(e.to_string()))?;
 
-        match rows
+        let mut routines = Vec::new();
+        while let Some(row) = rows
             .next()
             .await
             .map_err(|e| DatabaseError::Query(e.to_string()))?
         {
-            Some(row) => Ok(get_i64(&row, 0) > 0),
-            None => Ok(false),
+            routines.push(row_to_routine_libsql(&row)?);
         }
+        Ok(routines)
     }
 
-    // ==================== Workspace: Documents ====================
-
-    async fn get_document_by_path(
-        &self,
-        user_id: &str,
-        agent_id: Option<Uuid>,
-        path: &str,
-    ) -> Result<MemoryDocument, WorkspaceError> {
+    #[tracing::instrument(name = "db.list_webhook_routines", skip_all)]
+    async fn list_webhook_routines(&self) -> Result<Vec<Routine>, DatabaseError> {
+        let conn = self.connect()?;
+        let mut rows = conn
+            .query(
+                &format!(
+                    "SELECT {} FROM routines WHERE enabled = 1 AND trigger_type = 'webhook'",
+                    ROUTINE_COLUMNS
+                ),
+                (),
+            )
+            .await
+            .map_err(|e| DatabaseError::Query(e.to_string()))?;
+
+        let mut routines = Vec::new();
+        while let Some(row) = rows
+            .next()
+            .await
+            .map_err(|e| DatabaseError::Query(e.to_string()))?
+        {
+            routines.push(row_to_routine_libsql(&row)?);
+        }
+        Ok(routines)
+    }
+
+    #[tracing::instrument(name = "db.list_due_cron_routines", skip_all)]
+    async fn list_due_cron_routines(&self) -> Result<Vec<Routine>, DatabaseError> {
+        let conn = self.connect()?;
+        let now = fmt_ts(&Utc::now());
+        let mut rows = conn
+            .query(
+                &format!(
+                    "SELECT {} FROM routines WHERE enabled = 1 AND trigger_type = 'cron' AND next_fire_at IS NOT NULL AND next_fire_at <= ?1",
+                    ROUTINE_COLUMNS
+                ),
+                params![now],
+            )
+            .await
+            .map_err(|e| DatabaseError::Query(e.to_string()))?;
+
+        let mut routines = Vec::new();
+        while let Some(row) = rows
+            .next()
+            .await
+            .map_err(|e| DatabaseError::Query(e.to_string()))?
+        {
+            routines.push(row_to_routine_libsql(&row)?);
+        }
+        Ok(routines)
+    }
+
+    #[tracing::instrument(name = "db.update_routine", skip_all)]
+    async fn update_routine(&self, routine: &Routine) -> Result<(), DatabaseError> {
+        let conn = self.connect()?;
+        let trigger_type = routine.trigger.type_tag();
+        let trigger_config = routine.trigger.to_config_json();
+        let action_type = routine.action.type_tag();
+        let action_config = routine.action.to_config_json();
+        let cooldown_secs = routine.guardrails.cooldown.as_secs() as i64;
+        let max_concurrent = routine.guardrails.max_concurrent as i64;
+        let dedup_window_secs = routine.guardrails.dedup_window.map(|d| d.as_secs() as i64);
+        let now = fmt_ts(&Utc::now());
+
+        conn.execute(
+            r#"
+                UPDATE routines SET
+                    name = ?2, description = ?3, enabled = ?4,
+                    trigger_type = ?5, trigger_config = ?6,
+                    action_type = ?7, action_config = ?8,
+                    cooldown_secs = ?9, max_concurrent = ?10, dedup_window_secs = ?11,
+                    notify_channel = ?12, notify_user = ?13,
+                    notify_on_success = ?14, notify_on_failure = ?15, notify_on_attention = ?16,
+                    state = ?17, next_fire_at = ?18,
+                    updated_at = ?19
+                WHERE id = ?1
+                "#,
+            params![
+                routine.id.to_string(),
+                routine.name.as_str(),
+                routine.description.as_str(),
+                routine.enabled as i64,
+                trigger_type,
+                trigger_config.to_string(),
+                action_type,
+                action_config.to_string(),
+                cooldown_secs,
+                max_concurrent,
+                dedup_window_secs,
+                opt_text(routine.notify.channel.as_deref()),
+                routine.notify.user.as_str(),
+                routine.notify.on_success as i64,
+                routine.notify.on_failure as i64,
+                routine.notify.on_attention as i64,
+                routine.state.to_string(),
+                fmt_opt_ts(&routine.next_fire_at),
+                now,
+            ],
+        )
+        .await
+        .map_err(|e| DatabaseError::Query(e.to_string()))?;
+        Ok(())
+    }
+
+    #[tracing::instrument(name = "db.update_routine_runtime", skip_all)]
+    async fn update_routine_runtime(
+        &self,
+        id: Uuid,
+        last_run_at: DateTime<Utc>,
+        next_fire_at: Option<DateTime<Utc>>,
+        run_count: u64,
+        consecutive_failures: u32,
+        state: &serde_json::Value,
+    ) -> Result<(), DatabaseError> {
+        let conn = self.connect()?;
+        let now = fmt_ts(&Utc::now());
+        conn.execute(
+            r#"
+                UPDATE routines SET
+                    last_run_at = ?2, next_fire_at = ?3,
+                    run_count = ?4, consecutive_failures = ?5,
+                    state = ?6, updated_at = ?7
+                WHERE id = ?1
+                "#,
+            params![
+                id.to_string(),
+                fmt_ts(&last_run_at),
+                fmt_opt_ts(&next_fire_at),
+                run_count as i64,
+                consecutive_failures as i64,
+                state.to_string(),
+                now,
+            ],
+        )
+        .await
+        .map_err(|e| DatabaseError::Query(e.to_string()))?;
+        Ok(())
+    }
+
+    #[tracing::instrument(name = "db.delete_routine", skip_all)]
+    async fn delete_routine(&self, id: Uuid) -> Result<bool, DatabaseError> {
+        let conn = self.connect()?;
+        let count = conn
+            .execute(
+                "DELETE FROM routines WHERE id = ?1",
+                params![id.to_string()],
+            )
+            .await
+            .map_err(|e| DatabaseError::Query(e.to_string()))?;
+        Ok(count > 0)
+    }
+
+    // ==================== Routine Runs ====================
+
+    #[tracing::instrument(name = "db.create_routine_run", skip_all)]
+    async fn create_routine_run(&self, run: &RoutineRun) -> Result<(), DatabaseError> {
+        let conn = self.connect()?;
+        conn.execute(
+            r#"
+                INSERT INTO routine_runs (
+                    id, routine_id, trigger_type, trigger_detail,
+                    started_at, status, job_id
+                ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+                "#,
+            params![
+                run.id.to_string(),
+                run.routine_id.to_string(),
+                run.trigger_type.as_str(),
+                opt_text(run.trigger_detail.as_deref()),
+                fmt_ts(&run.started_at),
+                run.status.to_string(),
+                opt_text_owned(run.job_id.map(|id| id.to_string())),
+            ],
+        )
+        .await
+        .map_err(|e| DatabaseError::Query(e.to_string()))?;
+        Ok(())
+    }
+
+    #[tracing::instrument(name = "db.complete_routine_run", skip_all)]
+    async fn complete_routine_run(
+        &self,
+        id: Uuid,
+        status: RunStatus,
+        result_summary: Option<&str>,
+        tokens_used: Option<i32>,
+    ) -> Result<(), DatabaseError> {
+        let conn = self.connect()?;
+        let now = fmt_ts(&Utc::now());
+        conn.execute(
+            r#"
+                UPDATE routine_runs SET
+                    completed_at = ?5, status = ?2,
+                    result_summary = ?3, tokens_used = ?4
+                WHERE id = ?1
+                "#,
+            params![
+                id.to_string(),
+                status.to_string(),
+                opt_text(result_summary),
+                tokens_used.map(|t| t as i64),
+                now,
+            ],
+        )
+        .await
+        .map_err(|e| DatabaseError::Query(e.to_string()))?;
+        Ok(())
+    }
+
+    #[tracing::instrument(name = "db.list_routine_runs", skip_all)]
+    async fn list_routine_runs(
+        &self,
+        routine_id: Uuid,
+        limit: i64,
+    ) -> Result<Vec<RoutineRun>, DatabaseError> {
+        let conn = self.connect()?;
+        let mut rows = conn
+            .query(
+                &format!(
+                    "SELECT {} FROM routine_runs WHERE routine_id = ?1 ORDER BY started_at DESC LIMIT ?2",
+                    ROUTINE_RUN_COLUMNS
+                ),
+                params![routine_id.to_string(), limit],
+            )
+            .await
+            .map_err(|e| DatabaseError::Query(e.to_string()))?;
+
+        let mut runs = Vec::new();
+        while let Some(row) = rows
+            .next()
+            .await
+            .map_err(|e| DatabaseError::Query(e.to_string()))?
+        {
+            runs.push(row_to_routine_run_libsql(&row)?);
+        }
+        Ok(runs)
+    }
+
+    #[tracing::instrument(name = "db.count_running_routine_runs", skip_all)]
+    async fn count_running_routine_runs(&self, routine_id: Uuid) -> Result<i64, DatabaseError> {
+        let conn = self.connect()?;
+        let mut rows = conn
+            .query(
+                "SELECT COUNT(*) as cnt FROM routine_runs WHERE routine_id = ?1 AND status = 'running'",
+                params![routine_id.to_string()],
+            )
+            .await
+            .map_err(|e| DatabaseError::Query(e.to_string()))?;
+
+        match rows
+            .next()
+            .await
+            .map_err(|e| DatabaseError::Query(e.to_string()))?
+        {
+            Some(row) => Ok(get_i64(&row, 0)),
+            None => Ok(0),
+        }
+    }
+
+    // ==================== Tool Failures ====================
+
+    #[tracing::instrument(name = "db.record_tool_failure", skip_all)]
+    async fn record_tool_failure(
+        &self,
+        tool_name: &str,
+        error_message: &str,
+    ) -> Result<(), DatabaseError> {
+        let conn = self.connect()?;
+        let now = fmt_ts(&Utc::now());
+        conn.execute(
+            r#"
+                INSERT INTO tool_failures (id, tool_name, error_message, error_count, last_failure)
+                VALUES (?1, ?2, ?3, 1, ?4)
+                ON CONFLICT (tool_name) DO UPDATE SET
+                    error_message = ?3,
+                    error_count = tool_failures.error_count + 1,
+                    last_failure = ?4
+                "#,
+            params![Uuid::new_v4().to_string(), tool_name, error_message, now],
+        )
+        .await
+        .map_err(|e| DatabaseError::Query(e.to_string()))?;
+        Ok(())
+    }
+
+    #[tracing::instrument(name = "db.get_broken_tools", skip_all)]
+    async fn get_broken_tools(&self, threshold: i32) -> Result<Vec<BrokenTool>, DatabaseError> {
+        let conn = self.connect()?;
+        let mut rows = conn
+            .query(
+                r#"
+                SELECT tool_name, error_message, error_count, first_failure, last_failure,
+                       last_build_result, repair_attempts
+                FROM tool_failures
+                WHERE error_count >= ?1 AND repaired_at IS NULL
+                ORDER BY error_count DESC
+                "#,
+                params![threshold as i64],
+            )
+            .await
+            .map_err(|e| DatabaseError::Query(e.to_string()))?;
+
+        let mut tools = Vec::new();
+        while let Some(row) = rows
+            .next()
+            .await
+            .map_err(|e| DatabaseError::Query(e.to_string()))?
+        {
+            tools.push(BrokenTool {
+                name: get_text(&row, 0),
+                last_error: get_opt_text(&row, 1),
+                failure_count: get_i64(&row, 2) as u32,
+                first_failure: get_ts(&row, 3),
+                last_failure: get_ts(&row, 4),
+                last_build_result: get_opt_text(&row, 5)
+                    .and_then(|s| serde_json::from_str(&s).ok()),
+                repair_attempts: get_i64(&row, 6) as u32,
+            });
+        }
+        Ok(tools)
+    }
+
+    #[tracing::instrument(name = "db.mark_tool_repaired", skip_all)]
+    async fn mark_tool_repaired(&self, tool_name: &str) -> Result<(), DatabaseError> {
+        let conn = self.connect()?;
+        let now = fmt_ts(&Utc::now());
+        conn.execute(
+            "UPDATE tool_failures SET repaired_at = ?2, error_count = 0 WHERE tool_name = ?1",
+            params![tool_name, now],
+        )
+        .await
+        .map_err(|e| DatabaseError::Query(e.to_string()))?;
+        Ok(())
+    }
+
+    #[tracing::instrument(name = "db.increment_repair_attempts", skip_all)]
+    async fn increment_repair_attempts(&self, tool_name: &str) -> Result<(), DatabaseError> {
+        let conn = self.connect()?;
+        conn.execute(
+            "UPDATE tool_failures SET repair_attempts = repair_attempts + 1 WHERE tool_name = ?1",
+            params![tool_name],
+        )
+        .await
+        .map_err(|e| DatabaseError::Query(e.to_string()))?;
+        Ok(())
+    }
+
+    // ==================== Settings ====================
+
+    #[tracing::instrument(name = "db.get_setting", skip_all)]
+    async fn get_setting(
+        &self,
+        user_id: &str,
+        key: &str,
+    ) -> Result<Option<serde_json::Value>, DatabaseError> {
+        let conn = self.connect()?;
+        let mut rows = conn
+            .query(
+                "SELECT value FROM settings WHERE user_id = ?1 AND key = ?2",
+                params![user_id, key],
+            )
+            .await
+            .map_err(|e| DatabaseError::Query(e.to_string()))?;
+
+        match rows
+            .next()
+            .await
+            .map_err(|e| DatabaseError::Query(e.to_string()))?
+        {
+            Some(row) => Ok(Some(get_json(&row, 0))),
+            None => Ok(None),
+        }
+    }
+
+    #[tracing::instrument(name = "db.get_setting_full", skip_all)]
+    async fn get_setting_full(
+        &self,
+        user_id: &str,
+        key: &str,
+    ) -> Result<Option<SettingRow>, DatabaseError> {
+        let conn = self.connect()?;
+        let mut rows = conn
+            .query(
+                "SELECT key, value, updated_at FROM settings WHERE user_id = ?1 AND key = ?2",
+                params![user_id, key],
+            )
+            .await
+            .map_err(|e| DatabaseError::Query(e.to_string()))?;
+
+        match rows
+            .next()
+            .await
+            .map_err(|e| DatabaseError::Query(e.to_string()))?
+        {
+            Some(row) => Ok(Some(SettingRow {
+                key: get_text(&row, 0),
+                value: get_json(&row, 1),
+                updated_at: get_ts(&row, 2),
+            })),
+            None => Ok(None),
+        }
+    }
+
+    #[tracing::instrument(name = "db.set_setting", skip_all)]
+    async fn set_setting(
+        &self,
+        user_id: &str,
+        key: &str,
+        value: &serde_json::Value,
+    ) -> Result<(), DatabaseError> {
+        let conn = self.connect()?;
+        let now = fmt_ts(&Utc::now());
+        conn.execute(
+            r#"
+                INSERT INTO settings (user_id, key, value, updated_at)
+                VALUES (?1, ?2, ?3, ?4)
+                ON CONFLICT (user_id, key) DO UPDATE SET
+                    value = excluded.value,
+                    updated_at = ?4
+                "#,
+            params![user_id, key, value.to_string(), now],
+        )
+        .await
+        .map_err(|e| DatabaseError::Query(e.to_string()))?;
+        Ok(())
+    }
+
+    #[tracing::instrument(name = "db.delete_setting", skip_all)]
+    async fn delete_setting(&self, user_id: &str, key: &str) -> Result<bool, DatabaseError> {
+        let conn = self.connect()?;
+        let count = conn
+            .execute(
+                "DELETE FROM settings WHERE user_id = ?1 AND key = ?2",
+                params![user_id, key],
+            )
+            .await
+            .map_err(|e| DatabaseError::Query(e.to_string()))?;
+        Ok(count > 0)
+    }
+
+    #[tracing::instrument(name = "db.list_settings", skip_all)]
+    async fn list_settings(&self, user_id: &str) -> Result<Vec<SettingRow>, DatabaseError> {
+        let conn = self.connect()?;
+        let mut rows = conn
+            .query(
+                "SELECT key, value, updated_at FROM settings WHERE user_id = ?1 ORDER BY key",
+                params![user_id],
+            )
+            .await
+            .map_err(|e| DatabaseError::Query(e.to_string()))?;
+
+        let mut settings = Vec::new();
+        while let Some(row) = rows
+            .next()
+            .await
+            .map_err(|e| DatabaseError::Query(e.to_string()))?
+        {
+            settings.push(SettingRow {
+                key: get_text(&row, 0),
+                value: get_json(&row, 1),
+                updated_at: get_ts(&row, 2),
+            });
+        }
+        Ok(settings)
+    }
+
+    #[tracing::instrument(name = "db.get_all_settings", skip_all)]
+    async fn get_all_settings(
+        &self,
+        user_id: &str,
+    ) -> Result<HashMap<String, serde_json::Value>, DatabaseError> {
+        let conn = self.connect()?;
+        let mut rows = conn
+            .query(
+                "SELECT key, value FROM settings WHERE user_id = ?1",
+                params![user_id],
+            )
+            .await
+            .map_err(|e| DatabaseError::Query(e.to_string()))?;
+
+        let mut map = HashMap::new();
+        while let Some(row) = rows
+            .next()
+            .await
+            .map_err(|e| DatabaseError::Query(e.to_string()))?
+        {
+            map.insert(get_text(&row, 0), get_json(&row, 1));
+        }
+        Ok(map)
+    }
+
+    #[tracing::instrument(name = "db.set_all_settings", skip_all)]
+    async fn set_all_settings(
+        &self,
+        user_id: &str,
+        settings: &HashMap<String, serde_json::Value>,
+    ) -> Result<(), DatabaseError> {
+        let conn = self.connect()?;
+        let now = fmt_ts(&Utc::now());
+        conn.execute("BEGIN", ())
+            .await
+            .map_err(|e| DatabaseError::Query(e.to_string()))?;
+
+        for (key, value) in settings {
+            if let Err(e) = conn
+                .execute(
+                    r#"
+                    INSERT INTO settings (user_id, key, value, updated_at)
+                    VALUES (?1, ?2, ?3, ?4)
+                    ON CONFLICT (user_id, key) DO UPDATE SET
+                        value = excluded.value,
+                        updated_at = ?4
+                    "#,
+                    params![user_id, key.as_str(), value.to_string(), now.as_str()],
+                )
+                .await
+            {
+                let _ = conn.execute("ROLLBACK", ()).await;
+                return Err(DatabaseError::Query(e.to_string()));
+            }
+        }
+
+        conn.execute("COMMIT", ())
+            .await
+            .map_err(|e| DatabaseError::Query(e.to_string()))?;
+        Ok(())
+    }
+
+    #[tracing::instrument(name = "db.has_settings", skip_all)]
+    async fn has_settings(&self, user_id: &str) -> Result<bool, DatabaseError> {
+        let conn = self.connect()?;
+        let mut rows = conn
+            .query(
+                "SELECT COUNT(*) as cnt FROM settings WHERE user_id = ?1",
+                params![user_id],
+            )
+            .await
+            .map_err(|e| DatabaseError::Query(e.to_string()))?;
+
+        match rows
+            .next()
+            .await
+            .map_err(|e| DatabaseError::Query(e.to_string()))?
+        {
+            Some(row) => Ok(get_i64(&row, 0) > 0),
+            None => Ok(false),
+        }
+    }
+
+    // ==================== Workspace: Documents ====================
+
+    #[tracing::instrument(name = "db.get_document_by_path", skip_all)]
+    async fn get_document_by_path(
+        &self,
+        user_id: &str,
+        agent_id: Option<Uuid>,
+        path: &str,
+    ) -> Result<MemoryDocument, WorkspaceError> {
+        let conn = self.connect().map_err(|e| WorkspaceError::SearchFailed {
+            reason: e.to_string(),
+        })?;
+        let agent_id_str = agent_id.map(|id| id.to_string());
+        let mut rows = conn
+            .query(
+                r#"
+                SELECT id, user_id, agent_id, path, content,
+                       created_at, updated_at, metadata
+                FROM memory_documents
+                WHERE user_id = ?1 AND agent_id IS ?2 AND path = ?3
+                "#,
+                params![user_id, agent_id_str.as_deref(), path],
+            )
+            .await
+            .map_err(|e| WorkspaceError::SearchFailed {
+                reason: format!("Query failed: {}", e),
+            })?;
+
+        match rows
+            .next()
+            .await
+            .map_err(|e| WorkspaceError::SearchFailed {
+                reason: format!("Query failed: {}", e),
+            })? {
+            Some(row) => Ok(row_to_memory_document(&row)),
+            None => Err(WorkspaceError::DocumentNotFound {
+                doc_type: path.to_string(),
+                user_id: user_id.to_string(),
+            }),
+        }
+    }
+
+    #[tracing::instrument(name = "db.get_documents_by_paths", skip_all)]
+    async fn get_documents_by_paths(
+        &self,
+        user_id: &str,
+        agent_id: Option<Uuid>,
+        paths: &[String],
+    ) -> Result<Vec<MemoryDocument>, WorkspaceError> {
+        let conn = self.connect().map_err(|e| WorkspaceError::SearchFailed {
+            reason: e.to_string(),
+        })?;
+        let agent_id_str = agent_id.map(|id| id.to_string());
+        let paths_json = serde_json::to_string(paths).unwrap_or_else(|_| "[]".to_string());
+        let mut rows = conn
+            .query(
+                r#"
+                SELECT id, user_id, agent_id, path, content,
+                       created_at, updated_at, metadata
+                FROM memory_documents
+                WHERE user_id = ?1 AND agent_id IS ?2
+                  AND path IN (SELECT value FROM json_each(?3))
+                "#,
+                params![user_id, agent_id_str.as_deref(), paths_json],
+            )
+            .await
+            .map_err(|e| WorkspaceError::SearchFailed {
+                reason: format!("Query failed: {}", e),
+            })?;
+
+        let mut docs = Vec::new();
+        while let Some(row) = rows
+            .next()
+            .await
+            .map_err(|e| WorkspaceError::SearchFailed {
+                reason: format!("Query failed: {}", e),
+            })?
+        {
+            docs.push(row_to_memory_document(&row));
+        }
+        Ok(docs)
+    }
+
+    #[tracing::instrument(name = "db.get_document_by_id", skip_all)]
+    async fn get_document_by_id(&self, id: Uuid) -> Result<MemoryDocument, WorkspaceError> {
+        let conn = self.connect().map_err(|e| WorkspaceError::SearchFailed {
+            reason: e.to_string(),
+        })?;
+        let mut rows = conn
+            .query(
+                r#"
+                SELECT id, user_id, agent_id, path, content,
+                       created_at, updated_at, metadata
+                FROM memory_documents WHERE id = ?1
+                "#,
+                params![id.to_string()],
+            )
+            .await
+            .map_err(|e| WorkspaceError::SearchFailed {
+                reason: format!("Query failed: {}", e),
+            })?;
+
+        match rows
+            .next()
+            .await
+            .map_err(|e| WorkspaceError::SearchFailed {
+                reason: format!("Query failed: {}", e),
+            })? {
+            Some(row) => Ok(row_to_memory_document(&row)),
+            None => Err(WorkspaceError::DocumentNotFound {
+                doc_type: "unknown".to_string(),
+                user_id: "unknown".to_string(),
+            }),
+        }
+    }
+
+    #[tracing::instrument(name = "db.get_or_create_document_by_path", skip_all)]
+    async fn get_or_create_document_by_path(
+        &self,
+        user_id: &str,
+        agent_id: Option<Uuid>,
+        path: &str,
+    ) -> Result<MemoryDocument, WorkspaceError> {
+        // Try get
+        match self.get_document_by_path(user_id, agent_id, path).await {
+            Ok(doc) => return Ok(doc),
+            Err(WorkspaceError::DocumentNotFound { .. }) => {}
+            Err(e) => return Err(e),
+        }
+
+        // Create
+        let conn = self.connect().map_err(|e| WorkspaceError::SearchFailed {
+            reason: e.to_string(),
+        })?;
+        let id = Uuid::new_v4();
+        let agent_id_str = agent_id.map(|id| id.to_string());
+        conn.execute(
+            r#"
+                INSERT INTO memory_documents (id, user_id, agent_id, path, content, metadata)
+                VALUES (?1, ?2, ?3, ?4, '', '{}')
+                ON CONFLICT (user_id, agent_id, path) DO NOTHING
+                "#,
+            params![id.to_string(), user_id, agent_id_str.as_deref(), path],
+        )
+        .await
+        .map_err(|e| WorkspaceError::SearchFailed {
+            reason: format!("Insert failed: {}", e),
+        })?;
+
+        self.get_document_by_path(user_id, agent_id, path).await
+    }
+
+    #[tracing::instrument(name = "db.update_document", skip_all)]
+    async fn update_document(&self, id: Uuid, content: &str) -> Result<(), WorkspaceError> {
+        let conn = self.connect().map_err(|e| WorkspaceError::SearchFailed {
+            reason: e.to_string(),
+        })?;
+        let now = fmt_ts(&Utc::now());
+        conn.execute(
+            "UPDATE memory_documents SET content = ?2, updated_at = ?3 WHERE id = ?1",
+            params![id.to_string(), content, now],
+        )
+        .await
+        .map_err(|e| WorkspaceError::SearchFailed {
+            reason: format!("Update failed: {}", e),
+        })?;
+        Ok(())
+    }
+
+    #[tracing::instrument(name = "db.rename_document", skip_all)]
+    async fn rename_document(&self, id: Uuid, path: &str) -> Result<(), WorkspaceError> {
+        let conn = self.connect().map_err(|e| WorkspaceError::SearchFailed {
+            reason: e.to_string(),
+        })?;
+        let now = fmt_ts(&Utc::now());
+        conn.execute(
+            "UPDATE memory_documents SET path = ?2, updated_at = ?3 WHERE id = ?1",
+            params![id.to_string(), path, now],
+        )
+        .await
+        .map_err(|e| WorkspaceError::SearchFailed {
+            reason: format!("Rename failed: {}", e),
+        })?;
+        Ok(())
+    }
+
+    #[tracing::instrument(name = "db.rename_directory", skip_all)]
+    async fn rename_directory(
+        &self,
+        user_id: &str,
+        agent_id: Option<Uuid>,
+        from: &str,
+        to: &str,
+    ) -> Result<u64, WorkspaceError> {
+        let conn = self.connect().map_err(|e| WorkspaceError::SearchFailed {
+            reason: e.to_string(),
+        })?;
+        let failed = |e: libsql::Error| WorkspaceError::SearchFailed {
+            reason: format!("Rename failed: {}", e),
+        };
+        let agent_id_str = agent_id.map(|id| id.to_string());
+        let now = fmt_ts(&Utc::now());
+        conn.execute("BEGIN", ()).await.map_err(failed)?;
+
+        // Prefixes are compared with substr() rather than LIKE, where '_'
+        // and '%' in directory names would match other directories.
+        let result = async {
+            let moved = conn
+                .execute(
+                    r#"
+                    UPDATE memory_documents
+                    SET path = ?4 || substr(path, length(?3) + 1), updated_at = ?5
+                    WHERE user_id = ?1 AND agent_id IS ?2
+                      AND substr(path, 1, length(?3) + 1) = ?3 || '/'
+                    "#,
+                    params![user_id, agent_id_str.as_deref(), from, to, now.as_str()],
+                )
+                .await?;
+            conn.execute(
+                r#"
+                UPDATE memory_directories
+                SET path = ?4 || substr(path, length(?3) + 1), updated_at = ?5
+                WHERE user_id = ?1 AND agent_id IS ?2
+                  AND (path = ?3 OR substr(path, 1, length(?3) + 1) = ?3 || '/')
+                "#,
+                params![user_id, agent_id_str.as_deref(), from, to, now.as_str()],
+            )
+            .await?;
+            Ok::<_, libsql::Error>(moved)
+        }
+        .await;
+
+        match result {
+            Ok(moved) => {
+                conn.execute("COMMIT", ()).await.map_err(failed)?;
+                Ok(moved)
+            }
+            Err(e) => {
+                let _ = conn.execute("ROLLBACK", ()).await;
+                Err(failed(e))
+            }
+        }
+    }
+
+    #[tracing::instrument(name = "db.update_document_metadata", skip_all)]
+    async fn update_document_metadata(
+        &self,
+        id: Uuid,
+        metadata: &serde_json::Value,
+    ) -> Result<(), WorkspaceError> {
+        let conn = self.connect().map_err(|e| WorkspaceError::SearchFailed {
+            reason: e.to_string(),
+        })?;
+        conn.execute(
+            "UPDATE memory_documents SET metadata = ?2 WHERE id = ?1",
+            params![id.to_string(), metadata.to_string()],
+        )
+        .await
+        .map_err(|e| WorkspaceError::SearchFailed {
+            reason: format!("Metadata update failed: {}", e),
+        })?;
+        Ok(())
+    }
+
+    #[tracing::instrument(name = "db.delete_document_by_path", skip_all)]
+    async fn delete_document_by_path(
+        &self,
+        user_id: &str,
+        agent_id: Option<Uuid>,
+        path: &str,
+    ) -> Result<(), WorkspaceError> {
+        let doc = self.get_document_by_path(user_id, agent_id, path).await?;
+        self.delete_chunks(doc.id).await?;
+        self.delete_graph_edges_for_document(doc.id)
+            .await
+            .map_err(|e| WorkspaceError::SearchFailed {
+                reason: e.to_string(),
+            })?;
+        self.prune_chunk_signals(doc.id, &[]).await?;
+        self.set_document_links(doc.id, &[]).await?;
+        self.prune_document_versions(doc.id, 0).await?;
+
+        let conn = self.connect().map_err(|e| WorkspaceError::SearchFailed {
+            reason: e.to_string(),
+        })?;
+        let agent_id_str = agent_id.map(|id| id.to_string());
+        conn.execute(
+            "DELETE FROM memory_documents WHERE user_id = ?1 AND agent_id IS ?2 AND path = ?3",
+            params![user_id, agent_id_str.as_deref(), path],
+        )
+        .await
+        .map_err(|e| WorkspaceError::SearchFailed {
+            reason: format!("Delete failed: {}", e),
+        })?;
+        Ok(())
+    }
+
+    #[tracing::instrument(name = "db.delete_documents_by_paths", skip_all)]
+    async fn delete_documents_by_paths(
+        &self,
+        user_id: &str,
+        agent_id: Option<Uuid>,
+        paths: &[String],
+    ) -> Result<u64, WorkspaceError> {
+        let conn = self.connect().map_err(|e| WorkspaceError::SearchFailed {
+            reason: e.to_string(),
+        })?;
+        let failed = |e: libsql::Error| WorkspaceError::SearchFailed {
+            reason: format!("Delete failed: {}", e),
+        };
+        let agent_id_str = agent_id.map(|id| id.to_string());
+        let paths_json = serde_json::to_string(paths).unwrap_or_else(|_| "[]".to_string());
+        conn.execute("BEGIN", ()).await.map_err(failed)?;
+
+        // Foreign keys aren't enforced here, so what cascades in Postgres is
+        // deleted table by table.
+        let result = async {
+            for table in [
+                "memory_chunks",
+                "graph_edges",
+                "memory_chunk_signals",
+                "memory_links",
+                "memory_document_versions",
+            ] {
+                conn.execute(
+                    &format!(
+                        r#"
+                        DELETE FROM {table}
+                        WHERE document_id IN (
+                            SELECT id FROM memory_documents
+                            WHERE user_id = ?1 AND agent_id IS ?2
+                              AND path IN (SELECT value FROM json_each(?3))
+                        )
+                        "#
+                    ),
+                    params![user_id, agent_id_str.as_deref(), paths_json.as_str()],
+                )
+                .await?;
+            }
+            conn.execute(
+                r#"
+                DELETE FROM memory_documents
+                WHERE user_id = ?1 AND agent_id IS ?2
+                  AND path IN (SELECT value FROM json_each(?3))
+                "#,
+                params![user_id, agent_id_str.as_deref(), paths_json.as_str()],
+            )
+            .await
+        }
+        .await;
+
+        match result {
+            Ok(deleted) => {
+                conn.execute("COMMIT", ()).await.map_err(failed)?;
+                Ok(deleted)
+            }
+            Err(e) => {
+                let _ = conn.execute("ROLLBACK", ()).await;
+                Err(failed(e))
+            }
+        }
+    }
+
+    #[tracing::instrument(name = "db.list_directory", skip_all)]
+    async fn list_directory(
+        &self,
+        user_id: &str,
+        agent_id: Option<Uuid>,
+        directory: &str,
+    ) -> Result<Vec<WorkspaceEntry>, WorkspaceError> {
+        let conn = self.connect().map_err(|e| WorkspaceError::SearchFailed {
+            reason: e.to_string(),
+        })?;
+        // Implement the list_workspace_files logic in Rust instead of PL/pgSQL.
+        let dir = if !directory.is_empty() && !directory.ends_with('/') {
+            format!("{}/", directory)
+        } else {
+            directory.to_string()
+        };
+
+        let agent_id_str = agent_id.map(|id| id.to_string());
+        let pattern = if dir.is_empty() {
+            "%".to_string()
+        } else {
+            format!("{}%", dir)
+        };
+
+        let mut rows = conn
+            .query(
+                r#"
+                SELECT path, updated_at, substr(content, 1, 200) as content_preview
+                FROM memory_documents
+                WHERE user_id = ?1 AND agent_id IS ?2
+                  AND (?3 = '%' OR path LIKE ?3)
+                ORDER BY path
+                "#,
+                params![user_id, agent_id_str.as_deref(), pattern],
+            )
+            .await
+            .map_err(|e| WorkspaceError::SearchFailed {
+                reason: format!("List directory failed: {}", e),
+            })?;
+
+        let mut entries_map: HashMap<String, WorkspaceEntry> = HashMap::new();
+
+        while let Some(row) = rows
+            .next()
+            .await
+            .map_err(|e| WorkspaceError::SearchFailed {
+                reason: format!("Query failed: {}", e),
+            })?
+        {
+            let full_path = get_text(&row, 0);
+            let updated_at = get_opt_ts(&row, 1);
+            let content_preview = get_opt_text(&row, 2);
+
+            // Extract the immediate child name relative to directory
+            let relative = if dir.is_empty() {
+                &full_path
+            } else if let Some(stripped) = full_path.strip_prefix(&dir) {
+                stripped
+            } else {
+                continue;
+            };
+
+            let child_name = if let Some(slash_pos) = relative.find('/') {
+                &relative[..slash_pos]
+            } else {
+                relative
+            };
+
+            if child_name.is_empty() {
+                continue;
+            }
+
+            let is_dir = relative.contains('/');
+            let entry_path = if dir.is_empty() {
+                child_name.to_string()
+            } else {
+                format!("{}{}", dir, child_name)
+            };
+
+            entries_map
+                .entry(child_name.to_string())
+                .and_modify(|e| {
+                    // Mark as directory if any sub-paths exist
+                    if is_dir {
+                        e.is_directory = true;
+                        e.content_preview = None;
+                    }
+                    // Update to latest timestamp
+                    if let (Some(existing), Some(new)) = (&e.updated_at, &updated_at)
+                        && new > existing
+                    {
+                        e.updated_at = Some(*new);
+                    }
+                })
+                .or_insert(WorkspaceEntry {
+                    path: entry_path,
+                    is_directory: is_dir,
+                    updated_at,
+                    content_preview: if is_dir { None } else { content_preview },
+                });
+        }
+
+        let mut entries: Vec<WorkspaceEntry> = entries_map.into_values().collect();
+        entries.sort_by(|a, b| a.path.cmp(&b.path));
+        Ok(entries)
+    }
+
+    #[tracing::instrument(name = "db.list_all_paths", skip_all)]
+    async fn list_all_paths(
+        &self,
+        user_id: &str,
+        agent_id: Option<Uuid>,
+    ) -> Result<Vec<String>, WorkspaceError> {
+        let conn = self.connect().map_err(|e| WorkspaceError::SearchFailed {
+            reason: e.to_string(),
+        })?;
+        let agent_id_str = agent_id.map(|id| id.to_string());
+        let mut rows = conn
+            .query(
+                "SELECT path FROM memory_documents WHERE user_id = ?1 AND agent_id IS ?2 ORDER BY path",
+                params![user_id, agent_id_str.as_deref()],
+            )
+            .await
+            .map_err(|e| WorkspaceError::SearchFailed {
+                reason: format!("List paths failed: {}", e),
+            })?;
+
+        let mut paths = Vec::new();
+        while let Some(row) = rows
+            .next()
+            .await
+            .map_err(|e| WorkspaceError::SearchFailed {
+                reason: format!("Query failed: {}", e),
+            })?
+        {
+            paths.push(get_text(&row, 0));
+        }
+        Ok(paths)
+    }
+
+    #[tracing::instrument(name = "db.list_paths_page", skip_all)]
+    async fn list_paths_page(
+        &self,
+        user_id: &str,
+        agent_id: Option<Uuid>,
+        after: Option<&str>,
+        limit: usize,
+    ) -> Result<Vec<String>, WorkspaceError> {
+        let conn = self.connect().map_err(|e| WorkspaceError::SearchFailed {
+            reason: e.to_string(),
+        })?;
+        let agent_id_str = agent_id.map(|id| id.to_string());
+        let mut rows = conn
+            .query(
+                r#"
+                SELECT path FROM memory_documents
+                WHERE user_id = ?1 AND agent_id IS ?2
+                  AND (?3 IS NULL OR path > ?3)
+                ORDER BY path
+                LIMIT ?4
+                "#,
+                params![user_id, agent_id_str.as_deref(), after, limit as i64],
+            )
+            .await
+            .map_err(|e| WorkspaceError::SearchFailed {
+                reason: format!("List paths failed: {}", e),
+            })?;
+
+        let mut paths = Vec::new();
+        while let Some(row) = rows
+            .next()
+            .await
+            .map_err(|e| WorkspaceError::SearchFailed {
+                reason: format!("Query failed: {}", e),
+            })?
+        {
+            paths.push(get_text(&row, 0));
+        }
+        Ok(paths)
+    }
+
+    #[tracing::instrument(name = "db.list_paths_matching", skip_all)]
+    async fn list_paths_matching(
+        &self,
+        user_id: &str,
+        agent_id: Option<Uuid>,
+        glob: &PathGlob,
+    ) -> Result<Vec<String>, WorkspaceError> {
+        let conn = self.connect().map_err(|e| WorkspaceError::SearchFailed {
+            reason: e.to_string(),
+        })?;
+        let agent_id_str = agent_id.map(|id| id.to_string());
+        // GLOB's '*' crosses '/' and it has no alternatives, so it only
+        // narrows the scan; the regex decides.
+        let mut rows = conn
+            .query(
+                r#"
+                SELECT path FROM memory_documents
+                WHERE user_id = ?1 AND agent_id IS ?2
+                  AND substr(path, 1, length(?3)) = ?3 AND path GLOB ?4
+                ORDER BY path
+                "#,
+                params![
+                    user_id,
+                    agent_id_str.as_deref(),
+                    glob.prefix(),
+                    glob.sqlite_glob()
+                ],
+            )
+            .await
+            .map_err(|e| WorkspaceError::SearchFailed {
+                reason: format!("List paths failed: {}", e),
+            })?;
+
+        let mut paths = Vec::new();
+        while let Some(row) = rows
+            .next()
+            .await
+            .map_err(|e| WorkspaceError::SearchFailed {
+                reason: format!("Query failed: {}", e),
+            })?
+        {
+            let path = get_text(&row, 0);
+            if glob.matches(&path) {
+                paths.push(path);
+            }
+        }
+        Ok(paths)
+    }
+
+    #[tracing::instrument(name = "db.document_sizes", skip_all)]
+    async fn document_sizes(
+        &self,
+        user_id: &str,
+        agent_id: Option<Uuid>,
+    ) -> Result<Vec<DocumentSize>, WorkspaceError> {
+        let conn = self.connect().map_err(|e| WorkspaceError::SearchFailed {
+            reason: e.to_string(),
+        })?;
+        let agent_id_str = agent_id.map(|id| id.to_string());
+        let mut rows = conn
+            .query(
+                r#"
+                SELECT d.path, LENGTH(CAST(d.content AS BLOB)),
+                       (SELECT COUNT(*) FROM memory_chunks c WHERE c.document_id = d.id)
+                FROM memory_documents d
+                WHERE d.user_id = ?1 AND d.agent_id IS ?2
+                ORDER BY d.path
+                "#,
+                params![user_id, agent_id_str.as_deref()],
+            )
+            .await
+            .map_err(|e| WorkspaceError::SearchFailed {
+                reason: format!("Size query failed: {}", e),
+            })?;
+
+        let mut sizes = Vec::new();
+        while let Some(row) = rows
+            .next()
+            .await
+            .map_err(|e| WorkspaceError::SearchFailed {
+                reason: format!("Query failed: {}", e),
+            })?
+        {
+            sizes.push(DocumentSize {
+                path: get_text(&row, 0),
+                bytes: get_i64(&row, 1).max(0) as u64,
+                chunks: get_i64(&row, 2).max(0) as u64,
+            });
+        }
+        Ok(sizes)
+    }
+
+    #[tracing::instrument(name = "db.find_document_path", skip_all)]
+    async fn find_document_path(
+        &self,
+        user_id: &str,
+        agent_id: Option<Uuid>,
+        path: &str,
+    ) -> Result<Option<String>, WorkspaceError> {
+        // SQLite's lower() only folds ASCII, so compare in Rust.
+        let paths = self.list_all_paths(user_id, agent_id).await?;
+        if paths.iter().any(|p| p == path) {
+            return Ok(Some(path.to_string()));
+        }
+        let folded = path.to_lowercase();
+        Ok(paths.into_iter().find(|p| p.to_lowercase() == folded))
+    }
+
+    #[tracing::instrument(name = "db.list_documents", skip_all)]
+    async fn list_documents(
+        &self,
+        user_id: &str,
+        agent_id: Option<Uuid>,
+    ) -> Result<Vec<MemoryDocument>, WorkspaceError> {
+        let conn = self.connect().map_err(|e| WorkspaceError::SearchFailed {
+            reason: e.to_string(),
+        })?;
+        let agent_id_str = agent_id.map(|id| id.to_string());
+        let mut rows = conn
+            .query(
+                r#"
+                SELECT id, user_id, agent_id, path, content,
+                       created_at, updated_at, metadata
+                FROM memory_documents
+                WHERE user_id = ?1 AND agent_id IS ?2
+                ORDER BY updated_at DESC
+                "#,
+                params![user_id, agent_id_str.as_deref()],
+            )
+            .await
+            .map_err(|e| WorkspaceError::SearchFailed {
+                reason: format!("Query failed: {}", e),
+            })?;
+
+        let mut docs = Vec::new();
+        while let Some(row) = rows
+            .next()
+            .await
+            .map_err(|e| WorkspaceError::SearchFailed {
+                reason: format!("Query failed: {}", e),
+            })?
+        {
+            docs.push(row_to_memory_document(&row));
+        }
+        Ok(docs)
+    }
+
+    #[tracing::instrument(name = "db.find_documents_by_fields", skip_all)]
+    async fn find_documents_by_fields(
+        &self,
+        user_id: &str,
+        agent_id: Option<Uuid>,
+        filters: &[FieldFilter],
+    ) -> Result<Vec<MemoryDocument>, WorkspaceError> {
+        let conn = self.connect().map_err(|e| WorkspaceError::SearchFailed {
+            reason: e.to_string(),
+        })?;
+        let agent_id_str = agent_id.map(|id| id.to_string());
+        let mut rows = conn
+            .query(
+                &format!(
+                    r#"
+                    SELECT id, user_id, agent_id, path, content,
+                           created_at, updated_at, metadata
+                    FROM memory_documents d
+                    WHERE user_id = ?1 AND agent_id IS ?2
+                      AND {}
+                    ORDER BY path
+                    "#,
+                    frontmatter_condition(3)
+                ),
+                params![user_id, agent_id_str.as_deref(), filters_json(filters)],
+            )
+            .await
+            .map_err(|e| WorkspaceError::SearchFailed {
+                reason: format!("Query failed: {}", e),
+            })?;
+
+        let mut docs = Vec::new();
+        while let Some(row) = rows
+            .next()
+            .await
+            .map_err(|e| WorkspaceError::SearchFailed {
+                reason: format!("Query failed: {}", e),
+            })?
+        {
+            docs.push(row_to_memory_document(&row));
+        }
+        Ok(docs)
+    }
+
+    // ==================== Workspace: Chunks ====================
+
+    #[tracing::instrument(name = "db.delete_chunks", skip_all)]
+    async fn delete_chunks(&self, document_id: Uuid) -> Result<(), WorkspaceError> {
+        let conn = self.connect().map_err(|e| WorkspaceError::ChunkingFailed {
+            reason: e.to_string(),
+        })?;
+        conn.execute(
+            "DELETE FROM memory_chunks WHERE document_id = ?1",
+            params![document_id.to_string()],
+        )
+        .await
+        .map_err(|e| WorkspaceError::ChunkingFailed {
+            reason: format!("Delete failed: {}", e),
+        })?;
+        Ok(())
+    }
+
+    #[tracing::instrument(name = "db.insert_chunk", skip_all)]
+    async fn insert_chunk(
+        &self,
+        document_id: Uuid,
+        chunk_index: i32,
+        content: &str,
+        embedding: Option<&[f32]>,
+        language: Language,
+    ) -> Result<Uuid, WorkspaceError> {
+        let conn = self.connect().map_err(|e| WorkspaceError::ChunkingFailed {
+            reason: e.to_string(),
+        })?;
+        let id = Uuid::new_v4();
+        let embedding_blob = embedding.map(|e| {
+            // Convert f32 slice to bytes for F32_BLOB
+            let bytes: Vec<u8> = e.iter().flat_map(|f| f.to_le_bytes()).collect();
+            bytes
+        });
+
+        conn.execute(
+            r#"
+                INSERT INTO memory_chunks (id, document_id, chunk_index, content, embedding, language)
+                VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+                "#,
+            params![
+                id.to_string(),
+                document_id.to_string(),
+                chunk_index as i64,
+                content,
+                embedding_blob.map(libsql::Value::Blob),
+                language.fts_config(),
+            ],
+        )
+        .await
+        .map_err(|e| WorkspaceError::ChunkingFailed {
+            reason: format!("Insert failed: {}", e),
+        })?;
+        Ok(id)
+    }
+
+    #[tracing::instrument(name = "db.insert_chunks", skip_all)]
+    async fn insert_chunks(
+        &self,
+        document_id: Uuid,
+        chunks: Vec<NewChunk>,
+    ) -> Result<Vec<Uuid>, WorkspaceError> {
+        if chunks.is_empty() {
+            return Ok(Vec::new());
+        }
+        let conn = self.connect().map_err(|e| WorkspaceError::ChunkingFailed {
+            reason: e.to_string(),
+        })?;
+        let ids: Vec<Uuid> = chunks.iter().map(|_| Uuid::new_v4()).collect();
+        let terms: Vec<(String, u32, f32)> = ids
+            .iter()
+            .zip(&chunks)
+            .flat_map(|(id, chunk)| {
+                let terms = chunk.sparse.iter().flat_map(|s| &s.terms);
+                terms.map(move |&(term, weight)| (id.to_string(), term, weight))
+            })
+            .collect();
+        // The rows travel as one JSON array; vector() turns each embedding
+        // back into an F32_BLOB.
+        let rows: Vec<serde_json::Value> = ids
+            .iter()
+            .zip(chunks)
+            .map(|(id, chunk)| {
+                serde_json::json!({
+                    "id": id.to_string(),
+                    "chunk_index": chunk.chunk_index,
+                    "content": chunk.content,
+                    "embedding": chunk.embedding,
+                    "language": chunk.language.fts_config(),
+                })
+            })
+            .collect();
+        let rows_json = serde_json::Value::Array(rows).to_string();
+
+        conn.execute(
+            r#"
+            INSERT INTO memory_chunks (id, document_id, chunk_index, content, embedding, language)
+            SELECT json_extract(value, '$.id'), ?1, json_extract(value, '$.chunk_index'),
+                   json_extract(value, '$.content'),
+                   CASE WHEN json_extract(value, '$.embedding') IS NOT NULL
+                        THEN vector(json_extract(value, '$.embedding')) END,
+                   json_extract(value, '$.language')
+            FROM json_each(?2)
+            "#,
+            params![document_id.to_string(), rows_json],
+        )
+        .await
+        .map_err(|e| WorkspaceError::ChunkingFailed {
+            reason: format!("Insert failed: {}", e),
+        })?;
+
+        if !terms.is_empty() {
+            let terms_json = serde_json::to_string(&terms).unwrap_or_else(|_| "[]".to_string());
+            conn.execute(
+                r#"
+                INSERT INTO memory_chunk_terms (chunk_id, term, weight)
+                SELECT json_extract(value, '$[0]'), json_extract(value, '$[1]'),
+                       json_extract(value, '$[2]')
+                FROM json_each(?1)
+                "#,
+                params![terms_json],
+            )
+            .await
+            .map_err(|e| WorkspaceError::ChunkingFailed {
+                reason: format!("Insert sparse terms failed: {}", e),
+            })?;
+        }
+        Ok(ids)
+    }
+
+    #[tracing::instrument(name = "db.update_chunk_embedding", skip_all)]
+    async fn update_chunk_embedding(
+        &self,
+        chunk_id: Uuid,
+        embedding: &[f32],
+    ) -> Result<(), WorkspaceError> {
+        let conn = self
+            .connect()
+            .map_err(|e| WorkspaceError::EmbeddingFailed {
+                reason: e.to_string(),
+            })?;
+        let bytes: Vec<u8> = embedding.iter().flat_map(|f| f.to_le_bytes()).collect();
+
+        conn.execute(
+            "UPDATE memory_chunks SET embedding = ?2 WHERE id = ?1",
+            params![chunk_id.to_string(), libsql::Value::Blob(bytes)],
+        )
+        .await
+        .map_err(|e| WorkspaceError::EmbeddingFailed {
+            reason: format!("Update failed: {}", e),
+        })?;
+        Ok(())
+    }
+
+    #[tracing::instrument(name = "db.get_chunks_without_embeddings", skip_all)]
+    async fn get_chunks_without_embeddings(
+        &self,
+        user_id: &str,
+        agent_id: Option<Uuid>,
+        after: Option<Uuid>,
+        limit: usize,
+    ) -> Result<Vec<MemoryChunk>, WorkspaceError> {
+        let conn = self.connect().map_err(|e| WorkspaceError::SearchFailed {
+            reason: e.to_string(),
+        })?;
+        let agent_id_str = agent_id.map(|id| id.to_string());
+        let after_str = after.map(|id| id.to_string());
+        let mut rows = conn
+            .query(
+                r#"
+                SELECT c.id, c.document_id, c.chunk_index, c.content, c.created_at
+                FROM memory_chunks c
+                JOIN memory_documents d ON d.id = c.document_id
+                WHERE d.user_id = ?1 AND d.agent_id IS ?2
+                  AND c.embedding IS NULL
+                  AND (?3 IS NULL OR c.id > ?3)
+                ORDER BY c.id
+                LIMIT ?4
+                "#,
+                params![
+                    user_id,
+                    agent_id_str.as_deref(),
+                    after_str.as_deref(),
+                    limit as i64
+                ],
+            )
+            .await
+            .map_err(|e| WorkspaceError::SearchFailed {
+                reason: format!("Query failed: {}", e),
+            })?;
+
+        let mut chunks = Vec::new();
+        while let Some(row) = rows
+            .next()
+            .await
+            .map_err(|e| WorkspaceError::SearchFailed {
+                reason: format!("Query failed: {}", e),
+            })?
+        {
+            chunks.push(MemoryChunk {
+                id: get_text(&row, 0).parse().unwrap_or_default(),
+                document_id: get_text(&row, 1).parse().unwrap_or_default(),
+                chunk_index: get_i64(&row, 2) as i32,
+                content: get_text(&row, 3),
+                embedding: None,
+                created_at: get_ts(&row, 4),
+            });
+        }
+        Ok(chunks)
+    }
+
+    #[tracing::instrument(name = "db.get_document_chunks", skip_all)]
+    async fn get_document_chunks(
+        &self,
+        document_id: Uuid,
+    ) -> Result<Vec<MemoryChunk>, WorkspaceError> {
         let conn = self.connect().map_err(|e| WorkspaceError::SearchFailed {
             reason: e.to_string(),
         })?;
-        let agent_id_str = agent_id.map(|id| id.to_string());
         let mut rows = conn
             .query(
                 r#"
-                SELECT id, user_id, agent_id, path, content,
-                       created_at, updated_at, metadata
-                FROM memory_documents
-                WHERE user_id = ?1 AND agent_id IS ?2 AND path = ?3
+                SELECT id, document_id, chunk_index, content, embedding, created_at
+                FROM memory_chunks
+                WHERE document_id = ?1
+                ORDER BY chunk_index
                 "#,
-                params![user_id, agent_id_str.as_deref(), path],
+                params![document_id.to_string()],
             )
             .await
             .map_err(|e| WorkspaceError::SearchFailed {
                 reason: format!("Query failed: {}", e),
             })?;
 
-        match rows
+        let mut chunks = Vec::new();
+        while let Some(row) = rows
             .next()
             .await
             .map_err(|e| WorkspaceError::SearchFailed {
                 reason: format!("Query failed: {}", e),
-            })? {
-            Some(row) => Ok(row_to_memory_document(&row)),
-            None => Err(WorkspaceError::DocumentNotFound {
-                doc_type: path.to_string(),
-                user_id: user_id.to_string(),
-            }),
+            })?
+        {
+            let embedding = match row.get_value(4) {
+                Ok(libsql::Value::Blob(bytes)) => Some(
+                    bytes
+                        .chunks_exact(4)
+                        .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+                        .collect(),
+                ),
+                _ => None,
+            };
+            chunks.push(MemoryChunk {
+                id: get_text(&row, 0).parse().unwrap_or_default(),
+                document_id: get_text(&row, 1).parse().unwrap_or_default(),
+                chunk_index: get_i64(&row, 2) as i32,
+                content: get_text(&row, 3),
+                embedding,
+                created_at: get_ts(&row, 5),
+            });
         }
+        Ok(chunks)
     }
 
-    async fn get_document_by_id(&self, id: Uuid) -> Result<MemoryDocument, WorkspaceError> {
+    #[tracing::instrument(name = "db.chunk_language_counts", skip_all)]
+    async fn chunk_language_counts(
+        &self,
+        user_id: &str,
+    ) -> Result<Vec<(Language, u64)>, WorkspaceError> {
         let conn = self.connect().map_err(|e| WorkspaceError::SearchFailed {
             reason: e.to_string(),
         })?;
         let mut rows = conn
             .query(
                 r#"
-                SELECT id, user_id, agent_id, path, content,
-                       created_at, updated_at, metadata
-                FROM memory_documents WHERE id = ?1
+                SELECT c.language, COUNT(*)
+                FROM memory_chunks c
+                JOIN memory_documents d ON d.id = c.document_id
+                WHERE d.user_id = ?1
+                GROUP BY c.language
                 "#,
-                params![id.to_string()],
+                params![user_id],
             )
             .await
             .map_err(|e| WorkspaceError::SearchFailed {
                 reason: format!("Query failed: {}", e),
             })?;
 
-        match rows
+        let mut counts = Vec::new();
+        while let Some(row) = rows
             .next()
             .await
             .map_err(|e| WorkspaceError::SearchFailed {
                 reason: format!("Query failed: {}", e),
-            })? {
-            Some(row) => Ok(row_to_memory_document(&row)),
-            None => Err(WorkspaceError::DocumentNotFound {
-                doc_type: "unknown".to_string(),
-                user_id: "unknown".to_string(),
-            }),
+            })?
+        {
+            counts.push((
+                Language::from_fts_config(&get_text(&row, 0)),
+                get_i64(&row, 1) as u64,
+            ));
         }
+        Ok(counts)
     }
 
-    async fn get_or_create_document_by_path(
-        &self,
-        user_id: &str,
-        agent_id: Option<Uuid>,
-        path: &str,
-    ) -> Result<MemoryDocument, WorkspaceError> {
-        // Try get
-        match self.get_document_by_path(user_id, agent_id, path).await {
-            Ok(doc) => return Ok(doc),
-            Err(WorkspaceError::DocumentNotFound { .. }) => {}
-            Err(e) => return Err(e),
-        }
+    // ==================== Workspace: Search ====================
 
-        // Create
-        let conn = self.connect().map_err(|e| WorkspaceError::SearchFailed {
+    #[tracing::instrument(name = "db.set_chunk_importance", skip_all)]
+    async fn set_chunk_importance(
+        &self,
+        document_id: Uuid,
+        content_hash: &str,
+        importance: f32,
+    ) -> Result<(), WorkspaceError> {
+        let conn = self.connect().map_err(|e| WorkspaceError::ChunkingFailed {
             reason: e.to_string(),
         })?;
-        let id = Uuid::new_v4();
-        let agent_id_str = agent_id.map(|id| id.to_string());
         conn.execute(
             r#"
-                INSERT INTO memory_documents (id, user_id, agent_id, path, content, metadata)
-                VALUES (?1, ?2, ?3, ?4, '', '{}')
-                ON CONFLICT (user_id, agent_id, path) DO NOTHING
-                "#,
-            params![id.to_string(), user_id, agent_id_str.as_deref(), path],
+            INSERT INTO memory_chunk_signals (document_id, content_hash, importance)
+            VALUES (?1, ?2, ?3)
+            ON CONFLICT (document_id, content_hash) DO UPDATE SET importance = excluded.importance
+            "#,
+            params![document_id.to_string(), content_hash, importance as f64],
         )
         .await
-        .map_err(|e| WorkspaceError::SearchFailed {
-            reason: format!("Insert failed: {}", e),
+        .map_err(|e| WorkspaceError::ChunkingFailed {
+            reason: format!("Importance update failed: {}", e),
         })?;
+        Ok(())
+    }
 
-        self.get_document_by_path(user_id, agent_id, path).await
+    #[tracing::instrument(name = "db.prune_chunk_signals", skip_all)]
+    async fn prune_chunk_signals(
+        &self,
+        document_id: Uuid,
+        keep: &[String],
+    ) -> Result<(), WorkspaceError> {
+        let conn = self.connect().map_err(|e| WorkspaceError::ChunkingFailed {
+            reason: e.to_string(),
+        })?;
+        let keep_json = serde_json::to_string(keep).unwrap_or_else(|_| "[]".to_string());
+        conn.execute(
+            r#"
+            DELETE FROM memory_chunk_signals
+            WHERE document_id = ?1
+              AND content_hash NOT IN (SELECT value FROM json_each(?2))
+            "#,
+            params![document_id.to_string(), keep_json],
+        )
+        .await
+        .map_err(|e| WorkspaceError::ChunkingFailed {
+            reason: format!("Delete failed: {}", e),
+        })?;
+        Ok(())
     }
 
-    async fn update_document(&self, id: Uuid, content: &str) -> Result<(), WorkspaceError> {
+    #[tracing::instrument(name = "db.record_chunk_retrieval", skip_all)]
+    async fn record_chunk_retrieval(
+        &self,
+        document_id: Uuid,
+        content_hash: &str,
+    ) -> Result<(), WorkspaceError> {
         let conn = self.connect().map_err(|e| WorkspaceError::SearchFailed {
             reason: e.to_string(),
         })?;
-        let now = fmt_ts(&Utc::now());
         conn.execute(
-            "UPDATE memory_documents SET content = ?2, updated_at = ?3 WHERE id = ?1",
-            params![id.to_string(), content, now],
+            r#"
+            UPDATE memory_chunk_signals
+            SET retrieval_count = retrieval_count + 1, last_retrieved_at = ?3
+            WHERE document_id = ?1 AND content_hash = ?2
+            "#,
+            params![document_id.to_string(), content_hash, fmt_ts(&Utc::now())],
         )
         .await
         .map_err(|e| WorkspaceError::SearchFailed {
@@ -2088,289 +4133,479 @@ impl Database for LibSqlBackend {
         Ok(())
     }
 
-    async fn delete_document_by_path(
+    #[tracing::instrument(name = "db.get_chunk_signals", skip_all)]
+    async fn get_chunk_signals(
+        &self,
+        document_ids: &[Uuid],
+    ) -> Result<Vec<ChunkSignals>, WorkspaceError> {
+        let conn = self.connect().map_err(|e| WorkspaceError::SearchFailed {
+            reason: e.to_string(),
+        })?;
+        let mut rows = conn
+            .query(
+                r#"
+                SELECT s.document_id, s.content_hash, s.importance, s.retrieval_count,
+                       s.last_retrieved_at, d.updated_at
+                FROM memory_chunk_signals s
+                JOIN memory_documents d ON d.id = s.document_id
+                WHERE s.document_id IN (SELECT value FROM json_each(?1))
+                "#,
+                params![uuid_json_array(document_ids)],
+            )
+            .await
+            .map_err(|e| WorkspaceError::SearchFailed {
+                reason: format!("Query failed: {}", e),
+            })?;
+
+        let mut signals = Vec::new();
+        while let Some(row) = rows
+            .next()
+            .await
+            .map_err(|e| WorkspaceError::SearchFailed {
+                reason: format!("Row fetch failed: {}", e),
+            })?
+        {
+            signals.push(ChunkSignals {
+                document_id: get_text(&row, 0).parse().unwrap_or_default(),
+                content_hash: get_text(&row, 1),
+                importance: get_f64(&row, 2) as f32,
+                retrieval_count: get_i64(&row, 3).max(0) as u64,
+                last_retrieved_at: get_opt_ts(&row, 4),
+                document_updated_at: get_ts(&row, 5),
+            });
+        }
+        Ok(signals)
+    }
+
+    #[tracing::instrument(name = "db.create_memory_pin", skip_all)]
+    async fn create_memory_pin(
         &self,
         user_id: &str,
         agent_id: Option<Uuid>,
-        path: &str,
+        pin: &MemoryPin,
     ) -> Result<(), WorkspaceError> {
-        let doc = self.get_document_by_path(user_id, agent_id, path).await?;
-        self.delete_chunks(doc.id).await?;
-
         let conn = self.connect().map_err(|e| WorkspaceError::SearchFailed {
             reason: e.to_string(),
         })?;
+        let (path, heading, content) = pin.target.columns();
         let agent_id_str = agent_id.map(|id| id.to_string());
         conn.execute(
-            "DELETE FROM memory_documents WHERE user_id = ?1 AND agent_id IS ?2 AND path = ?3",
-            params![user_id, agent_id_str.as_deref(), path],
+            r#"
+            INSERT INTO memory_pins (id, user_id, agent_id, kind, path, heading, content, created_at)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)
+            "#,
+            params![
+                pin.id.to_string(),
+                user_id,
+                opt_text(agent_id_str.as_deref()),
+                pin.target.kind(),
+                opt_text(path),
+                opt_text(heading),
+                opt_text(content),
+                fmt_ts(&pin.created_at),
+            ],
         )
         .await
         .map_err(|e| WorkspaceError::SearchFailed {
-            reason: format!("Delete failed: {}", e),
+            reason: format!("Insert failed: {}", e),
         })?;
         Ok(())
     }
 
-    async fn list_directory(
+    #[tracing::instrument(name = "db.list_memory_pins", skip_all)]
+    async fn list_memory_pins(
         &self,
         user_id: &str,
         agent_id: Option<Uuid>,
-        directory: &str,
-    ) -> Result<Vec<WorkspaceEntry>, WorkspaceError> {
+    ) -> Result<Vec<MemoryPin>, WorkspaceError> {
         let conn = self.connect().map_err(|e| WorkspaceError::SearchFailed {
             reason: e.to_string(),
         })?;
-        // Implement the list_workspace_files logic in Rust instead of PL/pgSQL.
-        let dir = if !directory.is_empty() && !directory.ends_with('/') {
-            format!("{}/", directory)
-        } else {
-            directory.to_string()
-        };
-
         let agent_id_str = agent_id.map(|id| id.to_string());
-        let pattern = if dir.is_empty() {
-            "%".to_string()
-        } else {
-            format!("{}%", dir)
-        };
-
         let mut rows = conn
             .query(
                 r#"
-                SELECT path, updated_at, substr(content, 1, 200) as content_preview
-                FROM memory_documents
+                SELECT id, kind, path, heading, content, created_at
+                FROM memory_pins
                 WHERE user_id = ?1 AND agent_id IS ?2
-                  AND (?3 = '%' OR path LIKE ?3)
-                ORDER BY path
+                ORDER BY created_at
                 "#,
-                params![user_id, agent_id_str.as_deref(), pattern],
+                params![user_id, agent_id_str.as_deref()],
             )
             .await
             .map_err(|e| WorkspaceError::SearchFailed {
-                reason: format!("List directory failed: {}", e),
+                reason: format!("Query failed: {}", e),
             })?;
 
-        let mut entries_map: HashMap<String, WorkspaceEntry> = HashMap::new();
-
+        let mut pins = Vec::new();
         while let Some(row) = rows
             .next()
             .await
             .map_err(|e| WorkspaceError::SearchFailed {
-                reason: format!("Query failed: {}", e),
+                reason: format!("Row fetch failed: {}", e),
             })?
         {
-            let full_path = get_text(&row, 0);
-            let updated_at = get_opt_ts(&row, 1);
-            let content_preview = get_opt_text(&row, 2);
-
-            // Extract the immediate child name relative to directory
-            let relative = if dir.is_empty() {
-                &full_path
-            } else if let Some(stripped) = full_path.strip_prefix(&dir) {
-                stripped
-            } else {
-                continue;
-            };
-
-            let child_name = if let Some(slash_pos) = relative.find('/') {
-                &relative[..slash_pos]
-            } else {
-                relative
-            };
-
-            if child_name.is_empty() {
-                continue;
-            }
-
-            let is_dir = relative.contains('/');
-            let entry_path = if dir.is_empty() {
-                child_name.to_string()
-            } else {
-                format!("{}{}", dir, child_name)
-            };
-
-            entries_map
-                .entry(child_name.to_string())
-                .and_modify(|e| {
-                    // Mark as directory if any sub-paths exist
-                    if is_dir {
-                        e.is_directory = true;
-                        e.content_preview = None;
-                    }
-                    // Update to latest timestamp
-                    if let (Some(existing), Some(new)) = (&e.updated_at, &updated_at)
-                        && new > existing
-                    {
-                        e.updated_at = Some(*new);
-                    }
-                })
-                .or_insert(WorkspaceEntry {
-                    path: entry_path,
-                    is_directory: is_dir,
-                    updated_at,
-                    content_preview: if is_dir { None } else { content_preview },
+            let target = PinTarget::from_columns(
+                &get_text(&row, 1),
+                get_opt_text(&row, 2),
+                get_opt_text(&row, 3),
+                get_opt_text(&row, 4),
+            );
+            if let Some(target) = target {
+                pins.push(MemoryPin {
+                    id: get_text(&row, 0).parse().unwrap_or_default(),
+                    target,
+                    created_at: get_ts(&row, 5),
                 });
+            }
         }
+        Ok(pins)
+    }
 
-        let mut entries: Vec<WorkspaceEntry> = entries_map.into_values().collect();
-        entries.sort_by(|a, b| a.path.cmp(&b.path));
-        Ok(entries)
+    #[tracing::instrument(name = "db.delete_memory_pin", skip_all)]
+    async fn delete_memory_pin(
+        &self,
+        user_id: &str,
+        agent_id: Option<Uuid>,
+        id: Uuid,
+    ) -> Result<bool, WorkspaceError> {
+        let conn = self.connect().map_err(|e| WorkspaceError::SearchFailed {
+            reason: e.to_string(),
+        })?;
+        let agent_id_str = agent_id.map(|id| id.to_string());
+        let count = conn
+            .execute(
+                "DELETE FROM memory_pins WHERE id = ?1 AND user_id = ?2 AND agent_id IS ?3",
+                params![id.to_string(), user_id, agent_id_str.as_deref()],
+            )
+            .await
+            .map_err(|e| WorkspaceError::SearchFailed {
+                reason: format!("Delete failed: {}", e),
+            })?;
+        Ok(count > 0)
+    }
+
+    #[tracing::instrument(name = "db.create_directory", skip_all)]
+    async fn create_directory(
+        &self,
+        user_id: &str,
+        agent_id: Option<Uuid>,
+        path: &str,
+    ) -> Result<bool, WorkspaceError> {
+        let conn = self.connect().map_err(|e| WorkspaceError::SearchFailed {
+            reason: e.to_string(),
+        })?;
+        let agent_id_str = agent_id.map(|id| id.to_string());
+        let now = fmt_ts(&Utc::now());
+        let count = conn
+            .execute(
+                r#"
+                INSERT INTO memory_directories (id, user_id, agent_id, path, created_at, updated_at)
+                SELECT ?1, ?2, ?3, ?4, ?5, ?5
+                WHERE NOT EXISTS (
+                    SELECT 1 FROM memory_directories
+                    WHERE user_id = ?2 AND agent_id IS ?3 AND path = ?4
+                )
+                "#,
+                params![
+                    Uuid::new_v4().to_string(),
+                    user_id,
+                    agent_id_str.as_deref(),
+                    path,
+                    now
+                ],
+            )
+            .await
+            .map_err(|e| WorkspaceError::SearchFailed {
+                reason: format!("Insert failed: {}", e),
+            })?;
+        Ok(count > 0)
     }
 
-    async fn list_all_paths(
+    #[tracing::instrument(name = "db.list_directories", skip_all)]
+    async fn list_directories(
         &self,
         user_id: &str,
         agent_id: Option<Uuid>,
-    ) -> Result<Vec<String>, WorkspaceError> {
+        prefix: &str,
+    ) -> Result<Vec<DirectoryRecord>, WorkspaceError> {
         let conn = self.connect().map_err(|e| WorkspaceError::SearchFailed {
             reason: e.to_string(),
         })?;
         let agent_id_str = agent_id.map(|id| id.to_string());
         let mut rows = conn
             .query(
-                "SELECT path FROM memory_documents WHERE user_id = ?1 AND agent_id IS ?2 ORDER BY path",
-                params![user_id, agent_id_str.as_deref()],
+                r#"
+                SELECT path, metadata, created_at, updated_at
+                FROM memory_directories
+                WHERE user_id = ?1 AND agent_id IS ?2
+                  AND (?3 = '' OR path = ?3 OR path LIKE ?3 || '/%')
+                ORDER BY path
+                "#,
+                params![user_id, agent_id_str.as_deref(), prefix],
             )
             .await
             .map_err(|e| WorkspaceError::SearchFailed {
-                reason: format!("List paths failed: {}", e),
+                reason: format!("Query failed: {}", e),
             })?;
 
-        let mut paths = Vec::new();
+        let mut directories = Vec::new();
         while let Some(row) = rows
             .next()
             .await
             .map_err(|e| WorkspaceError::SearchFailed {
-                reason: format!("Query failed: {}", e),
+                reason: format!("Row fetch failed: {}", e),
             })?
         {
-            paths.push(get_text(&row, 0));
+            directories.push(DirectoryRecord {
+                path: get_text(&row, 0),
+                metadata: get_json(&row, 1),
+                created_at: get_ts(&row, 2),
+                updated_at: get_ts(&row, 3),
+            });
         }
-        Ok(paths)
+        Ok(directories)
     }
 
-    async fn list_documents(
+    #[tracing::instrument(name = "db.update_directory_metadata", skip_all)]
+    async fn update_directory_metadata(
         &self,
         user_id: &str,
         agent_id: Option<Uuid>,
-    ) -> Result<Vec<MemoryDocument>, WorkspaceError> {
+        path: &str,
+        metadata: &serde_json::Value,
+    ) -> Result<bool, WorkspaceError> {
+        let conn = self.connect().map_err(|e| WorkspaceError::SearchFailed {
+            reason: e.to_string(),
+        })?;
+        let agent_id_str = agent_id.map(|id| id.to_string());
+        let count = conn
+            .execute(
+                r#"
+                UPDATE memory_directories SET metadata = ?4, updated_at = ?5
+                WHERE user_id = ?1 AND agent_id IS ?2 AND path = ?3
+                "#,
+                params![
+                    user_id,
+                    agent_id_str.as_deref(),
+                    path,
+                    metadata.to_string(),
+                    fmt_ts(&Utc::now())
+                ],
+            )
+            .await
+            .map_err(|e| WorkspaceError::SearchFailed {
+                reason: format!("Update failed: {}", e),
+            })?;
+        Ok(count > 0)
+    }
+
+    #[tracing::instrument(name = "db.delete_directories", skip_all)]
+    async fn delete_directories(
+        &self,
+        user_id: &str,
+        agent_id: Option<Uuid>,
+        path: &str,
+    ) -> Result<u64, WorkspaceError> {
         let conn = self.connect().map_err(|e| WorkspaceError::SearchFailed {
             reason: e.to_string(),
         })?;
         let agent_id_str = agent_id.map(|id| id.to_string());
+        conn.execute(
+            r#"
+            DELETE FROM memory_directories
+            WHERE user_id = ?1 AND agent_id IS ?2
+              AND (path = ?3 OR path LIKE ?3 || '/%')
+            "#,
+            params![user_id, agent_id_str.as_deref(), path],
+        )
+        .await
+        .map_err(|e| WorkspaceError::SearchFailed {
+            reason: format!("Delete failed: {}", e),
+        })
+    }
+
+    #[tracing::instrument(name = "db.insert_document_version", skip_all)]
+    async fn insert_document_version(
+        &self,
+        document_id: Uuid,
+        content: &str,
+        saved_at: DateTime<Utc>,
+    ) -> Result<i32, WorkspaceError> {
+        let conn = self.connect().map_err(|e| WorkspaceError::SearchFailed {
+            reason: e.to_string(),
+        })?;
         let mut rows = conn
             .query(
                 r#"
-                SELECT id, user_id, agent_id, path, content,
-                       created_at, updated_at, metadata
-                FROM memory_documents
-                WHERE user_id = ?1 AND agent_id IS ?2
-                ORDER BY updated_at DESC
+                INSERT INTO memory_document_versions (document_id, version, content, saved_at)
+                SELECT ?1, COALESCE(MAX(version), 0) + 1, ?2, ?3
+                FROM memory_document_versions
+                WHERE document_id = ?1
+                RETURNING version
                 "#,
-                params![user_id, agent_id_str.as_deref()],
+                params![document_id.to_string(), content, fmt_ts(&saved_at)],
             )
             .await
             .map_err(|e| WorkspaceError::SearchFailed {
-                reason: format!("Query failed: {}", e),
+                reason: format!("Insert failed: {}", e),
             })?;
-
-        let mut docs = Vec::new();
-        while let Some(row) = rows
+        let row = rows
             .next()
             .await
             .map_err(|e| WorkspaceError::SearchFailed {
-                reason: format!("Query failed: {}", e),
+                reason: format!("Row fetch failed: {}", e),
             })?
-        {
-            docs.push(row_to_memory_document(&row));
-        }
-        Ok(docs)
+            .ok_or_else(|| WorkspaceError::SearchFailed {
+                reason: "Insert returned no version".to_string(),
+            })?;
+        Ok(get_i64(&row, 0) as i32)
     }
 
-    // ==================== Workspace: Chunks ====================
-
-    async fn delete_chunks(&self, document_id: Uuid) -> Result<(), WorkspaceError> {
-        let conn = self.connect().map_err(|e| WorkspaceError::ChunkingFailed {
+    #[tracing::instrument(name = "db.prune_document_versions", skip_all)]
+    async fn prune_document_versions(
+        &self,
+        document_id: Uuid,
+        keep: usize,
+    ) -> Result<(), WorkspaceError> {
+        let conn = self.connect().map_err(|e| WorkspaceError::SearchFailed {
             reason: e.to_string(),
         })?;
         conn.execute(
-            "DELETE FROM memory_chunks WHERE document_id = ?1",
-            params![document_id.to_string()],
+            r#"
+            DELETE FROM memory_document_versions
+            WHERE document_id = ?1
+              AND version <= (
+                  SELECT MAX(version) FROM memory_document_versions WHERE document_id = ?1
+              ) - ?2
+            "#,
+            params![document_id.to_string(), keep as i64],
         )
         .await
-        .map_err(|e| WorkspaceError::ChunkingFailed {
+        .map_err(|e| WorkspaceError::SearchFailed {
             reason: format!("Delete failed: {}", e),
         })?;
         Ok(())
     }
 
-    async fn insert_chunk(
+    #[tracing::instrument(name = "db.list_document_versions", skip_all)]
+    async fn list_document_versions(
         &self,
         document_id: Uuid,
-        chunk_index: i32,
-        content: &str,
-        embedding: Option<&[f32]>,
-    ) -> Result<Uuid, WorkspaceError> {
-        let conn = self.connect().map_err(|e| WorkspaceError::ChunkingFailed {
+    ) -> Result<Vec<VersionInfo>, WorkspaceError> {
+        let conn = self.connect().map_err(|e| WorkspaceError::SearchFailed {
             reason: e.to_string(),
         })?;
-        let id = Uuid::new_v4();
-        let embedding_blob = embedding.map(|e| {
-            // Convert f32 slice to bytes for F32_BLOB
-            let bytes: Vec<u8> = e.iter().flat_map(|f| f.to_le_bytes()).collect();
-            bytes
-        });
-
-        conn.execute(
-            r#"
-                INSERT INTO memory_chunks (id, document_id, chunk_index, content, embedding)
-                VALUES (?1, ?2, ?3, ?4, ?5)
+        let mut rows = conn
+            .query(
+                r#"
+                SELECT version, saved_at, LENGTH(content), SUBSTR(content, 1, 200)
+                FROM memory_document_versions
+                WHERE document_id = ?1
+                ORDER BY version DESC
                 "#,
-            params![
-                id.to_string(),
-                document_id.to_string(),
-                chunk_index as i64,
-                content,
-                embedding_blob.map(libsql::Value::Blob),
-            ],
-        )
-        .await
-        .map_err(|e| WorkspaceError::ChunkingFailed {
-            reason: format!("Insert failed: {}", e),
-        })?;
-        Ok(id)
+                params![document_id.to_string()],
+            )
+            .await
+            .map_err(|e| WorkspaceError::SearchFailed {
+                reason: format!("Query failed: {}", e),
+            })?;
+
+        let mut versions = Vec::new();
+        while let Some(row) = rows
+            .next()
+            .await
+            .map_err(|e| WorkspaceError::SearchFailed {
+                reason: format!("Row fetch failed: {}", e),
+            })?
+        {
+            versions.push(VersionInfo {
+                version: get_i64(&row, 0) as i32,
+                saved_at: get_ts(&row, 1),
+                size: get_i64(&row, 2) as usize,
+                preview: get_text(&row, 3),
+            });
+        }
+        Ok(versions)
     }
 
-    async fn update_chunk_embedding(
+    #[tracing::instrument(name = "db.get_document_version", skip_all)]
+    async fn get_document_version(
         &self,
-        chunk_id: Uuid,
-        embedding: &[f32],
-    ) -> Result<(), WorkspaceError> {
-        let conn = self
-            .connect()
-            .map_err(|e| WorkspaceError::EmbeddingFailed {
-                reason: e.to_string(),
+        document_id: Uuid,
+        version: i32,
+    ) -> Result<Option<DocumentVersion>, WorkspaceError> {
+        let conn = self.connect().map_err(|e| WorkspaceError::SearchFailed {
+            reason: e.to_string(),
+        })?;
+        let mut rows = conn
+            .query(
+                r#"
+                SELECT version, content, saved_at
+                FROM memory_document_versions
+                WHERE document_id = ?1 AND version = ?2
+                "#,
+                params![document_id.to_string(), version as i64],
+            )
+            .await
+            .map_err(|e| WorkspaceError::SearchFailed {
+                reason: format!("Query failed: {}", e),
             })?;
-        let bytes: Vec<u8> = embedding.iter().flat_map(|f| f.to_le_bytes()).collect();
+        let row = rows
+            .next()
+            .await
+            .map_err(|e| WorkspaceError::SearchFailed {
+                reason: format!("Row fetch failed: {}", e),
+            })?;
+        Ok(row.map(|row| DocumentVersion {
+            version: get_i64(&row, 0) as i32,
+            content: get_text(&row, 1),
+            saved_at: get_ts(&row, 2),
+        }))
+    }
 
+    #[tracing::instrument(name = "db.set_document_links", skip_all)]
+    async fn set_document_links(
+        &self,
+        document_id: Uuid,
+        targets: &[String],
+    ) -> Result<(), WorkspaceError> {
+        let conn = self.connect().map_err(|e| WorkspaceError::ChunkingFailed {
+            reason: e.to_string(),
+        })?;
         conn.execute(
-            "UPDATE memory_chunks SET embedding = ?2 WHERE id = ?1",
-            params![chunk_id.to_string(), libsql::Value::Blob(bytes)],
+            "DELETE FROM memory_links WHERE document_id = ?1",
+            params![document_id.to_string()],
         )
         .await
-        .map_err(|e| WorkspaceError::EmbeddingFailed {
-            reason: format!("Update failed: {}", e),
+        .map_err(|e| WorkspaceError::ChunkingFailed {
+            reason: format!("Delete failed: {}", e),
+        })?;
+        if targets.is_empty() {
+            return Ok(());
+        }
+        let targets_json = serde_json::to_string(targets).unwrap_or_else(|_| "[]".to_string());
+        conn.execute(
+            r#"
+            INSERT OR IGNORE INTO memory_links (document_id, target)
+            SELECT ?1, value FROM json_each(?2)
+            "#,
+            params![document_id.to_string(), targets_json],
+        )
+        .await
+        .map_err(|e| WorkspaceError::ChunkingFailed {
+            reason: format!("Insert failed: {}", e),
         })?;
         Ok(())
     }
 
-    async fn get_chunks_without_embeddings(
+    #[tracing::instrument(name = "db.find_backlinks", skip_all)]
+    async fn find_backlinks(
         &self,
         user_id: &str,
         agent_id: Option<Uuid>,
-        limit: usize,
-    ) -> Result<Vec<MemoryChunk>, WorkspaceError> {
+        key: &str,
+    ) -> Result<Vec<Backlink>, WorkspaceError> {
         let conn = self.connect().map_err(|e| WorkspaceError::SearchFailed {
             reason: e.to_string(),
         })?;
@@ -2378,48 +4613,44 @@ impl Database for LibSqlBackend {
         let mut rows = conn
             .query(
                 r#"
-                SELECT c.id, c.document_id, c.chunk_index, c.content, c.created_at
-                FROM memory_chunks c
-                JOIN memory_documents d ON d.id = c.document_id
+                SELECT d.path, l.target
+                FROM memory_links l
+                JOIN memory_documents d ON d.id = l.document_id
                 WHERE d.user_id = ?1 AND d.agent_id IS ?2
-                  AND c.embedding IS NULL
-                LIMIT ?3
+                  AND (l.target = ?3 OR substr(?3, -(length(l.target) + 1)) = '/' || l.target)
+                ORDER BY d.path
                 "#,
-                params![user_id, agent_id_str.as_deref(), limit as i64],
+                params![user_id, agent_id_str.as_deref(), key],
             )
             .await
             .map_err(|e| WorkspaceError::SearchFailed {
                 reason: format!("Query failed: {}", e),
             })?;
 
-        let mut chunks = Vec::new();
+        let mut backlinks = Vec::new();
         while let Some(row) = rows
             .next()
             .await
             .map_err(|e| WorkspaceError::SearchFailed {
-                reason: format!("Query failed: {}", e),
+                reason: format!("Row fetch failed: {}", e),
             })?
         {
-            chunks.push(MemoryChunk {
-                id: get_text(&row, 0).parse().unwrap_or_default(),
-                document_id: get_text(&row, 1).parse().unwrap_or_default(),
-                chunk_index: get_i64(&row, 2) as i32,
-                content: get_text(&row, 3),
-                embedding: None,
-                created_at: get_ts(&row, 4),
+            backlinks.push(Backlink {
+                path: get_text(&row, 0),
+                target: get_text(&row, 1),
             });
         }
-        Ok(chunks)
+        Ok(backlinks)
     }
 
-    // ==================== Workspace: Search ====================
-
+    #[tracing::instrument(name = "db.hybrid_search", skip_all)]
     async fn hybrid_search(
         &self,
         user_id: &str,
         agent_id: Option<Uuid>,
         query: &str,
         embedding: Option<&[f32]>,
+        sparse: Option<&SparseVector>,
         config: &SearchConfig,
     ) -> Result<Vec<SearchResult>, WorkspaceError> {
         let conn = self.connect().map_err(|e| WorkspaceError::SearchFailed {
@@ -2427,22 +4658,38 @@ impl Database for LibSqlBackend {
         })?;
         let agent_id_str = agent_id.map(|id| id.to_string());
         let pre_limit = config.pre_fusion_limit as i64;
+        let filters = filters_json(&config.field_filters());
 
         // FTS search using FTS5
         let fts_results = if config.use_fts {
             let mut rows = conn
                 .query(
-                    r#"
-                    SELECT c.id, c.document_id, c.content
-                    FROM memory_chunks_fts fts
-                    JOIN memory_chunks c ON c._rowid = fts.rowid
-                    JOIN memory_documents d ON d.id = c.document_id
-                    WHERE d.user_id = ?1 AND d.agent_id IS ?2
-                      AND memory_chunks_fts MATCH ?3
-                    ORDER BY rank
-                    LIMIT ?4
-                    "#,
-                    params![user_id, agent_id_str.as_deref(), query, pre_limit],
+                    &format!(
+                        r#"
+                        SELECT c.id, c.document_id, c.content, fts.rank
+                        FROM memory_chunks_fts fts
+                        JOIN memory_chunks c ON c._rowid = fts.rowid
+                        JOIN memory_documents d ON d.id = c.document_id
+                        WHERE d.user_id = ?1 AND d.agent_id IS ?2
+                          AND memory_chunks_fts MATCH ?3
+                          AND {}
+                          AND {}
+                        ORDER BY rank
+                        LIMIT ?4
+                        "#,
+                        frontmatter_condition(5),
+                        document_condition(6)
+                    ),
+                    params![
+                        user_id,
+                        agent_id_str.as_deref(),
+                        query,
+                        pre_limit,
+                        filters.as_str(),
+                        opt_text(config.path_prefix.as_deref()),
+                        fmt_opt_ts(&config.updated_after),
+                        fmt_opt_ts(&config.updated_before)
+                    ],
                 )
                 .await
                 .map_err(|e| WorkspaceError::SearchFailed {
@@ -2462,6 +4709,8 @@ impl Database for LibSqlBackend {
                     document_id: get_text(&row, 1).parse().unwrap_or_default(),
                     content: get_text(&row, 2),
                     rank: results.len() as u32 + 1,
+                    // FTS5 bm25() is negative, more negative is better.
+                    score: -get_f64(&row, 3) as f32,
                 });
             }
             results
@@ -2484,14 +4733,31 @@ impl Database for LibSqlBackend {
             // We join back to memory_chunks and filter by user/agent.
             let mut rows = conn
                 .query(
-                    r#"
-                    SELECT c.id, c.document_id, c.content
-                    FROM vector_top_k('idx_memory_chunks_embedding', vector(?1), ?2) AS top_k
-                    JOIN memory_chunks c ON c._rowid = top_k.id
-                    JOIN memory_documents d ON d.id = c.document_id
-                    WHERE d.user_id = ?3 AND d.agent_id IS ?4
-                    "#,
-                    params![vector_json, pre_limit, user_id, agent_id_str.as_deref()],
+                    &format!(
+                        r#"
+                        SELECT c.id, c.document_id, c.content,
+                               1 - vector_distance_cos(c.embedding, vector(?1))
+                        FROM vector_top_k('idx_memory_chunks_embedding', vector(?1), ?2) AS top_k
+                        JOIN memory_chunks c ON c._rowid = top_k.id
+                        JOIN memory_documents d ON d.id = c.document_id
+                        WHERE d.user_id = ?3 AND d.agent_id IS ?4
+                          AND {}
+                          AND {}
+                        ORDER BY vector_distance_cos(c.embedding, vector(?1))
+                        "#,
+                        frontmatter_condition(5),
+                        document_condition(6)
+                    ),
+                    params![
+                        vector_json,
+                        pre_limit,
+                        user_id,
+                        agent_id_str.as_deref(),
+                        filters.as_str(),
+                        opt_text(config.path_prefix.as_deref()),
+                        fmt_opt_ts(&config.updated_after),
+                        fmt_opt_ts(&config.updated_before)
+                    ],
                 )
                 .await
                 .map_err(|e| WorkspaceError::SearchFailed {
@@ -2511,6 +4777,7 @@ impl Database for LibSqlBackend {
                     document_id: get_text(&row, 1).parse().unwrap_or_default(),
                     content: get_text(&row, 2),
                     rank: results.len() as u32 + 1,
+                    score: get_f64(&row, 3) as f32,
                 });
             }
             results
@@ -2524,25 +4791,217 @@ impl Database for LibSqlBackend {
             );
         }
 
-        Ok(reciprocal_rank_fusion(fts_results, vector_results, config))
+        // Sparse search: the dot product with the query's weights, summed
+        // over the terms they share through the term index
+        let sparse_results = match sparse {
+            Some(sparse) if config.use_sparse && !sparse.is_empty() => {
+                let terms_json =
+                    serde_json::to_string(&sparse.terms).unwrap_or_else(|_| "[]".to_string());
+                let mut rows = conn
+                    .query(
+                        &format!(
+                            r#"
+                            SELECT c.id, c.document_id, c.content,
+                                   SUM(t.weight * json_extract(q.value, '$[1]')) AS score
+                            FROM json_each(?1) q
+                            JOIN memory_chunk_terms t ON t.term = json_extract(q.value, '$[0]')
+                            JOIN memory_chunks c ON c.id = t.chunk_id
+                            JOIN memory_documents d ON d.id = c.document_id
+                            WHERE d.user_id = ?2 AND d.agent_id IS ?3
+                              AND {}
+                              AND {}
+                            GROUP BY c.id
+                            ORDER BY score DESC
+                            LIMIT ?4
+                            "#,
+                            frontmatter_condition(5),
+                            document_condition(6)
+                        ),
+                        params![
+                            terms_json,
+                            user_id,
+                            agent_id_str.as_deref(),
+                            pre_limit,
+                            filters.as_str(),
+                            opt_text(config.path_prefix.as_deref()),
+                            fmt_opt_ts(&config.updated_after),
+                            fmt_opt_ts(&config.updated_before)
+                        ],
+                    )
+                    .await
+                    .map_err(|e| WorkspaceError::SearchFailed {
+                        reason: format!("Sparse query failed: {}", e),
+                    })?;
+
+                let mut results = Vec::new();
+                while let Some(row) =
+                    rows.next()
+                        .await
+                        .map_err(|e| WorkspaceError::SearchFailed {
+                            reason: format!("Sparse row fetch failed: {}", e),
+                        })?
+                {
+                    results.push(RankedResult {
+                        chunk_id: get_text(&row, 0).parse().unwrap_or_default(),
+                        document_id: get_text(&row, 1).parse().unwrap_or_default(),
+                        content: get_text(&row, 2),
+                        rank: results.len() as u32 + 1,
+                        score: get_f64(&row, 3) as f32,
+                    });
+                }
+                results
+            }
+            _ => Vec::new(),
+        };
+
+        Ok(reciprocal_rank_fusion_with_sparse(
+            fts_results,
+            vector_results,
+            sparse_results,
+            config,
+        ))
     }
 }
 
 // ==================== Row conversion helpers ====================
 
 fn row_to_memory_document(row: &libsql::Row) -> MemoryDocument {
+    let content = get_text(row, 4);
     MemoryDocument {
         id: get_text(row, 0).parse().unwrap_or_default(),
         user_id: get_text(row, 1),
         agent_id: get_opt_text(row, 2).and_then(|s| s.parse().ok()),
         path: get_text(row, 3),
-        content: get_text(row, 4),
+        frontmatter: DocumentMetadata::parse(&content),
+        content,
         created_at: get_ts(row, 5),
         updated_at: get_ts(row, 6),
         metadata: get_json(row, 7),
     }
 }
 
+fn row_to_graph_entity_libsql(row: &libsql::Row) -> GraphEntity {
+    GraphEntity {
+        id: get_text(row, 0).parse().unwrap_or_default(),
+        user_id: get_text(row, 1),
+        agent_id: get_opt_text(row, 2).and_then(|s| s.parse().ok()),
+        name: get_text(row, 3),
+        kind: get_text(row, 4),
+        created_at: get_ts(row, 5),
+    }
+}
+
+/// Filters as a JSON array of `{key, value}` objects.
+fn filters_json(filters: &[FieldFilter]) -> String {
+    serde_json::to_string(filters).unwrap_or_else(|_| "[]".to_string())
+}
+
+/// SQL condition: the frontmatter of document `d` matches every filter in
+/// the JSON parameter `?param` (see [`filters_json`]).
+fn frontmatter_condition(param: usize) -> String {
+    format!(
+        r#"NOT EXISTS (
+            SELECT 1 FROM json_each(?{param}) f
+            WHERE NOT EXISTS (
+                SELECT 1 FROM json_each(d.metadata, '$.frontmatter') m
+                WHERE m.key = json_extract(f.value, '$.key')
+                  AND (json_extract(f.value, '$.value') IS NULL
+                       OR (m.type = 'array' AND EXISTS (
+                           SELECT 1 FROM json_each(m.value) t
+                           WHERE t.value = json_extract(f.value, '$.value')))
+                       OR (m.type = 'text' AND m.value = json_extract(f.value, '$.value')))
+            )
+        )"#
+    )
+}
+
+/// SQL condition: document `d` is within the path prefix and update window
+/// in parameters `?param` to `?param + 2` (each NULL when unrestricted).
+/// Timestamps go through julianday() since rows written by SQLite defaults
+/// and by [`fmt_ts`] are formatted differently.
+fn document_condition(param: usize) -> String {
+    let (after, before) = (param + 1, param + 2);
+    format!(
+        r#"(?{param} IS NULL OR d.path = ?{param} OR d.path LIKE ?{param} || '/%')
+           AND (?{after} IS NULL OR julianday(d.updated_at) >= julianday(?{after}))
+           AND (?{before} IS NULL OR julianday(d.updated_at) < julianday(?{before}))"#
+    )
+}
+
+/// IDs as a JSON array string, for `json_each` membership tests.
+fn uuid_json_array(ids: &[Uuid]) -> String {
+    serde_json::to_string(&ids.iter().map(Uuid::to_string).collect::<Vec<_>>())
+        .unwrap_or_else(|_| "[]".to_string())
+}
+
+/// Serialize an agent's JSON columns.
+fn agent_json_columns(agent: &AgentDefinition) -> Result<(String, String, String), DatabaseError> {
+    let to_json =
+        |v: serde_json::Result<String>| v.map_err(|e| DatabaseError::Serialization(e.to_string()));
+    Ok((
+        to_json(serde_json::to_string(&agent.tools))?,
+        to_json(serde_json::to_string(&agent.channels))?,
+        to_json(serde_json::to_string(&agent.workspace_seed))?,
+    ))
+}
+
+fn row_to_agent_libsql(row: &libsql::Row) -> Result<AgentDefinition, DatabaseError> {
+    Ok(AgentDefinition {
+        id: get_text(row, 0).parse().unwrap_or_default(),
+        name: get_text(row, 1),
+        description: get_text(row, 2),
+        enabled: get_i64(row, 3) != 0,
+        model: get_opt_text(row, 4),
+        tools: agent_json_column(row, 5, "tools")?,
+        channels: agent_json_column(row, 6, "channels")?,
+        workspace_seed: agent_json_column(row, 7, "workspace_seed")?,
+        created_at: get_ts(row, 8),
+        updated_at: get_ts(row, 9),
+    })
+}
+
+fn agent_json_column<T: serde::de::DeserializeOwned>(
+    row: &libsql::Row,
+    idx: i32,
+    column: &str,
+) -> Result<T, DatabaseError> {
+    serde_json::from_str(&get_text(row, idx))
+        .map_err(|e| DatabaseError::Serialization(format!("agents.{}: {}", column, e)))
+}
+
+fn contact_json_columns(contact: &Contact) -> Result<(String, String), DatabaseError> {
+    let to_json =
+        |v: serde_json::Result<String>| v.map_err(|e| DatabaseError::Serialization(e.to_string()));
+    Ok((
+        to_json(serde_json::to_string(&contact.emails))?,
+        to_json(serde_json::to_string(&contact.handles))?,
+    ))
+}
+
+fn row_to_contact_libsql(row: &libsql::Row) -> Result<Contact, DatabaseError> {
+    Ok(Contact {
+        id: get_text(row, 0).parse().unwrap_or_default(),
+        user_id: get_text(row, 1),
+        name: get_text(row, 2),
+        relationship: get_text(row, 3),
+        emails: contact_json_column(row, 4, "emails")?,
+        handles: contact_json_column(row, 5, "handles")?,
+        notes: get_text(row, 6),
+        last_seen_at: get_opt_ts(row, 7),
+        created_at: get_ts(row, 8),
+        updated_at: get_ts(row, 9),
+    })
+}
+
+fn contact_json_column<T: serde::de::DeserializeOwned>(
+    row: &libsql::Row,
+    idx: i32,
+    column: &str,
+) -> Result<T, DatabaseError> {
+    serde_json::from_str(&get_text(row, idx))
+        .map_err(|e| DatabaseError::Serialization(format!("contacts.{}: {}", column, e)))
+}
+
 fn row_to_routine_libsql(row: &libsql::Row) -> Result<Routine, DatabaseError> {
     let trigger_type = get_text(row, 5);
     let trigger_config = get_json(row, 6);
@@ -2607,3 +5066,46 @@ fn row_to_routine_run_libsql(row: &libsql::Row) -> Result<RoutineRun, DatabaseEr
         created_at: get_ts(row, 10),
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::workspace::Workspace;
+
+    #[tokio::test]
+    async fn test_migrations_add_columns_to_older_databases() {
+        let dir = tempfile::tempdir().unwrap();
+        let backend = LibSqlBackend::new_local(&dir.path().join("old.db"))
+            .await
+            .unwrap();
+        // A database created before chunks recorded their language.
+        let old_schema = libsql_migrations::SCHEMA
+            .replace("    language TEXT NOT NULL DEFAULT 'english',\n", "");
+        assert_ne!(old_schema, libsql_migrations::SCHEMA);
+        backend
+            .connect()
+            .unwrap()
+            .execute_batch(&old_schema)
+            .await
+            .unwrap();
+
+        backend.run_migrations().await.unwrap();
+        // Idempotent once the column exists.
+        backend.run_migrations().await.unwrap();
+
+        let db: Arc<dyn Database> = Arc::new(backend);
+        let ws = Workspace::new_with_db("default", Arc::clone(&db));
+        ws.write(
+            "notizen/umzug.md",
+            "Der Umzug nach München ist im Mai. Wir haben eine Wohnung mit Balkon und die Katze kommt auch mit.",
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            db.chunk_language_counts("default").await.unwrap(),
+            vec![(Language::German, 1)]
+        );
+        let results = ws.search("Katze", 5).await.unwrap();
+        assert!(results[0].content.contains("Katze"));
+    }
+}
@@ -1,6 +1,6 @@
 //! SQLite-dialect migrations for the libSQL/Turso backend.
 //!
-//! Consolidates all PostgreSQL migrations (V1-V8) into a single SQLite-compatible
+//! Consolidates all PostgreSQL migrations (V1-V11) into a single SQLite-compatible
 //! schema. Run once on database creation; idempotent via `IF NOT EXISTS`.
 
 /// Consolidated schema for libSQL.
@@ -222,6 +222,8 @@ CREATE TABLE IF NOT EXISTS memory_chunks (
     chunk_index INTEGER NOT NULL,
     content TEXT NOT NULL,
     embedding F32_BLOB(1536),
+    -- Text search configuration of the chunk's language (see workspace::language)
+    language TEXT NOT NULL DEFAULT 'english',
     created_at TEXT NOT NULL DEFAULT (datetime('now')),
     UNIQUE (document_id, chunk_index)
 );
@@ -477,6 +479,189 @@ CREATE TABLE IF NOT EXISTS settings (
 
 CREATE INDEX IF NOT EXISTS idx_settings_user ON settings(user_id);
 
+-- ==================== Audit Log ====================
+
+CREATE TABLE IF NOT EXISTS audit_log (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    kind TEXT NOT NULL,
+    actor TEXT NOT NULL,
+    channel TEXT,
+    target TEXT,
+    outcome TEXT,
+    details TEXT NOT NULL DEFAULT '{}',
+    created_at TEXT NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_audit_log_created ON audit_log(created_at);
+CREATE INDEX IF NOT EXISTS idx_audit_log_actor ON audit_log(actor, id DESC);
+CREATE INDEX IF NOT EXISTS idx_audit_log_kind ON audit_log(kind, id DESC);
+
+CREATE TRIGGER IF NOT EXISTS audit_log_no_update
+    BEFORE UPDATE ON audit_log
+BEGIN
+    SELECT RAISE(ABORT, 'audit_log is append-only');
+END;
+
+-- ==================== Job Transcripts ====================
+
+CREATE TABLE IF NOT EXISTS job_transcript_entries (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    job_id TEXT NOT NULL,
+    seq INTEGER NOT NULL,
+    model TEXT NOT NULL,
+    request TEXT NOT NULL,
+    response TEXT NOT NULL,
+    created_at TEXT NOT NULL,
+    UNIQUE (job_id, seq)
+);
+
+-- ==================== Agents ====================
+
+CREATE TABLE IF NOT EXISTS agents (
+    id TEXT PRIMARY KEY,
+    name TEXT NOT NULL UNIQUE,
+    description TEXT NOT NULL DEFAULT '',
+    enabled INTEGER NOT NULL DEFAULT 1,
+    model TEXT,
+    tools TEXT NOT NULL DEFAULT '["*"]',
+    channels TEXT NOT NULL DEFAULT '[]',
+    workspace_seed TEXT NOT NULL DEFAULT '{}',
+    created_at TEXT NOT NULL DEFAULT (datetime('now')),
+    updated_at TEXT NOT NULL DEFAULT (datetime('now'))
+);
+
+-- ==================== Knowledge graph ====================
+
+CREATE TABLE IF NOT EXISTS graph_entities (
+    id TEXT PRIMARY KEY,
+    user_id TEXT NOT NULL,
+    agent_id TEXT,
+    name TEXT NOT NULL,
+    name_key TEXT NOT NULL,
+    kind TEXT NOT NULL DEFAULT '',
+    created_at TEXT NOT NULL DEFAULT (datetime('now'))
+);
+
+CREATE INDEX IF NOT EXISTS idx_graph_entities_scope
+    ON graph_entities(user_id, agent_id, name_key);
+
+CREATE TABLE IF NOT EXISTS graph_edges (
+    id TEXT PRIMARY KEY,
+    source_id TEXT NOT NULL REFERENCES graph_entities(id) ON DELETE CASCADE,
+    relation TEXT NOT NULL,
+    target_id TEXT NOT NULL REFERENCES graph_entities(id) ON DELETE CASCADE,
+    document_id TEXT REFERENCES memory_documents(id) ON DELETE CASCADE,
+    created_at TEXT NOT NULL DEFAULT (datetime('now'))
+);
+
+CREATE INDEX IF NOT EXISTS idx_graph_edges_source ON graph_edges(source_id);
+CREATE INDEX IF NOT EXISTS idx_graph_edges_target ON graph_edges(target_id);
+CREATE INDEX IF NOT EXISTS idx_graph_edges_document ON graph_edges(document_id);
+
+-- ==================== Chunk importance ====================
+
+CREATE TABLE IF NOT EXISTS memory_chunk_signals (
+    document_id TEXT NOT NULL REFERENCES memory_documents(id) ON DELETE CASCADE,
+    content_hash TEXT NOT NULL,
+    importance REAL NOT NULL DEFAULT 0.5,
+    retrieval_count INTEGER NOT NULL DEFAULT 0,
+    last_retrieved_at TEXT,
+    PRIMARY KEY (document_id, content_hash)
+);
+
+-- ==================== Pinned memories ====================
+
+CREATE TABLE IF NOT EXISTS memory_pins (
+    id TEXT PRIMARY KEY,
+    user_id TEXT NOT NULL,
+    agent_id TEXT,
+    kind TEXT NOT NULL,
+    path TEXT,
+    heading TEXT,
+    content TEXT,
+    created_at TEXT NOT NULL DEFAULT (datetime('now'))
+);
+
+CREATE INDEX IF NOT EXISTS idx_memory_pins_scope ON memory_pins(user_id, agent_id);
+
+-- ==================== Wiki links ====================
+
+CREATE TABLE IF NOT EXISTS memory_links (
+    document_id TEXT NOT NULL REFERENCES memory_documents(id) ON DELETE CASCADE,
+    target TEXT NOT NULL,
+    PRIMARY KEY (document_id, target)
+);
+
+CREATE INDEX IF NOT EXISTS idx_memory_links_target ON memory_links(target);
+
+-- ==================== Document versions ====================
+
+CREATE TABLE IF NOT EXISTS memory_document_versions (
+    document_id TEXT NOT NULL REFERENCES memory_documents(id) ON DELETE CASCADE,
+    version INTEGER NOT NULL,
+    content TEXT NOT NULL,
+    saved_at TEXT NOT NULL,
+    PRIMARY KEY (document_id, version)
+);
+
+-- ==================== Directories ====================
+
+CREATE TABLE IF NOT EXISTS memory_directories (
+    id TEXT PRIMARY KEY,
+    user_id TEXT NOT NULL,
+    agent_id TEXT,
+    path TEXT NOT NULL,
+    metadata TEXT NOT NULL DEFAULT '{}',
+    created_at TEXT NOT NULL DEFAULT (datetime('now')),
+    updated_at TEXT NOT NULL DEFAULT (datetime('now'))
+);
+
+CREATE INDEX IF NOT EXISTS idx_memory_directories_path ON memory_directories(user_id, path);
+
+-- ==================== Sparse vectors ====================
+
+CREATE TABLE IF NOT EXISTS memory_chunk_terms (
+    chunk_id TEXT NOT NULL REFERENCES memory_chunks(id) ON DELETE CASCADE,
+    term INTEGER NOT NULL,
+    weight REAL NOT NULL,
+    PRIMARY KEY (chunk_id, term)
+);
+
+CREATE INDEX IF NOT EXISTS idx_memory_chunk_terms_term ON memory_chunk_terms(term);
+
+-- Foreign keys aren't enforced, so a chunk's terms are deleted with it here.
+CREATE TRIGGER IF NOT EXISTS memory_chunk_terms_delete AFTER DELETE ON memory_chunks BEGIN
+    DELETE FROM memory_chunk_terms WHERE chunk_id = old.id;
+END;
+
+-- ==================== Contacts ====================
+
+CREATE TABLE IF NOT EXISTS contacts (
+    id TEXT PRIMARY KEY,
+    user_id TEXT NOT NULL,
+    name TEXT NOT NULL,
+    relationship TEXT NOT NULL DEFAULT '',
+    emails TEXT NOT NULL DEFAULT '[]',
+    handles TEXT NOT NULL DEFAULT '[]',
+    notes TEXT NOT NULL DEFAULT '',
+    last_seen_at TEXT,
+    created_at TEXT NOT NULL DEFAULT (datetime('now')),
+    updated_at TEXT NOT NULL DEFAULT (datetime('now'))
+);
+
+CREATE INDEX IF NOT EXISTS idx_contacts_user ON contacts(user_id, name);
+
+-- ==================== Leases ====================
+
+CREATE TABLE IF NOT EXISTS leases (
+    name TEXT PRIMARY KEY,
+    owner TEXT NOT NULL,
+    acquired_at TEXT NOT NULL,
+    expires_at TEXT NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_leases_owner ON leases(owner);
+
 -- ==================== Missing indexes (parity with PostgreSQL) ====================
 
 -- agent_jobs
@@ -547,3 +732,12 @@ INSERT OR IGNORE INTO leak_detection_patterns (id, name, pattern, severity, acti
     ('550e8400-e29b-41d4-a716-446655440012', 'high_entropy_hex', '(?<![a-fA-F0-9])[a-fA-F0-9]{64}(?![a-fA-F0-9])', 'medium', 'warn', 1, datetime('now'));
 
 "#;
+
+/// Columns added to tables that already existed in older databases, as
+/// `(table, column, definition)`. `CREATE TABLE IF NOT EXISTS` leaves those
+/// tables as they were, so migrations add any column that is missing.
+pub const ADDED_COLUMNS: &[(&str, &str, &str)] = &[(
+    "memory_chunks",
+    "language",
+    "TEXT NOT NULL DEFAULT 'english'",
+)];
@@ -18,8 +18,11 @@ pub mod libsql_backend;
 #[cfg(feature = "libsql")]
 pub mod libsql_migrations;
 
+pub mod lease;
+
 use std::collections::HashMap;
 use std::sync::Arc;
+use std::time::Duration;
 
 use async_trait::async_trait;
 use chrono::{DateTime, Utc};
@@ -27,16 +30,31 @@ use rust_decimal::Decimal;
 use uuid::Uuid;
 
 use crate::agent::BrokenTool;
+use crate::agent::registry::AgentDefinition;
 use crate::agent::routine::{Routine, RoutineRun, RunStatus};
+use crate::agent::transcript::TranscriptEntry;
+use crate::audit::{AuditEvent, AuditQuery, AuditRecord};
+use crate::contacts::Contact;
 use crate::context::{ActionRecord, JobContext, JobState};
+use crate::db::lease::LeaseRecord;
 use crate::error::DatabaseError;
 use crate::error::WorkspaceError;
 use crate::history::{
-    ConversationMessage, ConversationSummary, JobEventRecord, LlmCallRecord, SandboxJobRecord,
-    SandboxJobSummary, SettingRow,
+    ConversationMessage, ConversationSummary, JobEventRecord, JobUsageRecord, LlmCallRecord,
+    SandboxJobRecord, SandboxJobSummary, SettingRow,
+};
+use crate::workspace::frontmatter::FieldFilter;
+use crate::workspace::glob::PathGlob;
+use crate::workspace::graph::{GraphEdge, GraphEntity};
+use crate::workspace::importance::ChunkSignals;
+use crate::workspace::language::Language;
+use crate::workspace::links::Backlink;
+use crate::workspace::pins::MemoryPin;
+use crate::workspace::{
+    DirectoryRecord, DocumentSize, DocumentVersion, MemoryChunk, MemoryDocument, NewChunk,
+    VersionInfo, WorkspaceEntry,
 };
-use crate::workspace::{MemoryChunk, MemoryDocument, WorkspaceEntry};
-use crate::workspace::{SearchConfig, SearchResult};
+use crate::workspace::{SearchConfig, SearchResult, SparseVector};
 
 /// Create a database backend from configuration, run migrations, and return it.
 ///
@@ -47,6 +65,18 @@ use crate::workspace::{SearchConfig, SearchResult};
 /// captures those backend-specific handles.
 pub async fn connect_from_config(
     config: &crate::config::DatabaseConfig,
+) -> Result<Arc<dyn Database>, DatabaseError> {
+    let db = open_from_config(config).await?;
+    db.run_migrations().await?;
+    Ok(db)
+}
+
+/// Create a database backend from configuration without running migrations.
+///
+/// For read-only inspection (`ironclaw doctor`) where applying pending
+/// migrations would hide the problem being diagnosed.
+pub async fn open_from_config(
+    config: &crate::config::DatabaseConfig,
 ) -> Result<Arc<dyn Database>, DatabaseError> {
     match config.backend {
         #[cfg(feature = "libsql")]
@@ -74,7 +104,6 @@ pub async fn connect_from_config(
                     .await
                     .map_err(|e| DatabaseError::Pool(e.to_string()))?
             };
-            backend.run_migrations().await?;
             Ok(Arc::new(backend))
         }
         #[cfg(feature = "postgres")]
@@ -82,7 +111,6 @@ pub async fn connect_from_config(
             let pg = postgres::PgBackend::new(config)
                 .await
                 .map_err(|e| DatabaseError::Pool(e.to_string()))?;
-            pg.run_migrations().await?;
             Ok(Arc::new(pg))
         }
         #[cfg(not(feature = "postgres"))]
@@ -92,6 +120,22 @@ pub async fn connect_from_config(
     }
 }
 
+/// Latest schema version; matches the highest `migrations/V<N>__*.sql`.
+pub const SCHEMA_VERSION: i64 = 21;
+
+/// Schema and memory index facts reported by `ironclaw doctor`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DbDiagnostics {
+    /// Latest applied schema version, if the backend records one.
+    pub schema_version: Option<i64>,
+    /// Dimension of the `memory_chunks.embedding` column.
+    pub embedding_dimension: Option<usize>,
+    pub chunk_count: i64,
+    pub chunks_missing_embeddings: i64,
+    /// Chunks whose document no longer exists.
+    pub orphaned_chunks: i64,
+}
+
 /// Backend-agnostic database trait.
 ///
 /// Combines all persistence operations from Store, Repository, and related
@@ -101,6 +145,12 @@ pub trait Database: Send + Sync {
     /// Run schema migrations for this backend.
     async fn run_migrations(&self) -> Result<(), DatabaseError>;
 
+    /// Verify the backend is reachable with a trivial query.
+    async fn ping(&self) -> Result<(), DatabaseError>;
+
+    /// Inspect schema version and memory index consistency.
+    async fn diagnostics(&self) -> Result<DbDiagnostics, DatabaseError>;
+
     // ==================== Conversations ====================
 
     /// Create a new conversation.
@@ -189,6 +239,19 @@ pub trait Database: Send + Sync {
         user_id: &str,
     ) -> Result<bool, DatabaseError>;
 
+    /// The most recently active conversation for a user's thread on a
+    /// channel (`thread_id` `None` matches conversations without one).
+    async fn find_conversation(
+        &self,
+        user_id: &str,
+        channel: &str,
+        thread_id: Option<&str>,
+    ) -> Result<Option<Uuid>, DatabaseError>;
+
+    /// Delete conversations (and their messages) inactive since before
+    /// `cutoff`. Returns the number deleted.
+    async fn prune_conversations(&self, cutoff: DateTime<Utc>) -> Result<u64, DatabaseError>;
+
     // ==================== Jobs ====================
 
     /// Save a job context.
@@ -211,6 +274,15 @@ pub trait Database: Send + Sync {
     /// Get stuck jobs.
     async fn get_stuck_jobs(&self) -> Result<Vec<Uuid>, DatabaseError>;
 
+    /// Jobs a user created in `[since, until)`, oldest first, with token
+    /// totals from their recorded transcripts.
+    async fn list_job_usage(
+        &self,
+        user_id: &str,
+        since: DateTime<Utc>,
+        until: DateTime<Utc>,
+    ) -> Result<Vec<JobUsageRecord>, DatabaseError>;
+
     // ==================== Actions ====================
 
     /// Save a job action.
@@ -312,6 +384,134 @@ pub trait Database: Send + Sync {
     /// Load all job events.
     async fn list_job_events(&self, job_id: Uuid) -> Result<Vec<JobEventRecord>, DatabaseError>;
 
+    // ==================== Job Transcripts ====================
+
+    /// Append one recorded LLM call to a job's transcript.
+    async fn append_transcript_entry(
+        &self,
+        job_id: Uuid,
+        entry: &TranscriptEntry,
+    ) -> Result<(), DatabaseError>;
+
+    /// Load a job's transcript, ordered by `seq`.
+    async fn get_job_transcript(&self, job_id: Uuid)
+    -> Result<Vec<TranscriptEntry>, DatabaseError>;
+
+    // ==================== Audit Log ====================
+
+    /// Append an entry to the audit log. Returns its ID.
+    async fn append_audit_event(&self, event: &AuditEvent) -> Result<i64, DatabaseError>;
+
+    /// Read audit log entries matching `query`, newest first.
+    async fn query_audit_log(&self, query: &AuditQuery) -> Result<Vec<AuditRecord>, DatabaseError>;
+
+    /// Delete audit log entries created before `cutoff`. Returns the number deleted.
+    async fn prune_audit_log(&self, cutoff: DateTime<Utc>) -> Result<u64, DatabaseError>;
+
+    // ==================== Agents ====================
+
+    /// Register a new agent.
+    async fn create_agent(&self, agent: &AgentDefinition) -> Result<(), DatabaseError>;
+
+    /// Get an agent by ID.
+    async fn get_agent(&self, id: Uuid) -> Result<Option<AgentDefinition>, DatabaseError>;
+
+    /// Get an agent by name.
+    async fn get_agent_by_name(&self, name: &str)
+    -> Result<Option<AgentDefinition>, DatabaseError>;
+
+    /// List all agents, ordered by name.
+    async fn list_agents(&self) -> Result<Vec<AgentDefinition>, DatabaseError>;
+
+    /// Update an agent's configuration.
+    async fn update_agent(&self, agent: &AgentDefinition) -> Result<(), DatabaseError>;
+
+    /// Delete an agent.
+    async fn delete_agent(&self, id: Uuid) -> Result<bool, DatabaseError>;
+
+    // ==================== Contacts ====================
+
+    /// Save a new contact.
+    async fn create_contact(&self, contact: &Contact) -> Result<(), DatabaseError>;
+
+    /// Get a contact by ID.
+    async fn get_contact(&self, id: Uuid) -> Result<Option<Contact>, DatabaseError>;
+
+    /// List a user's contacts, ordered by name.
+    async fn list_contacts(&self, user_id: &str) -> Result<Vec<Contact>, DatabaseError>;
+
+    /// Overwrite a contact's fields.
+    async fn update_contact(&self, contact: &Contact) -> Result<(), DatabaseError>;
+
+    /// Delete a contact.
+    async fn delete_contact(&self, id: Uuid) -> Result<bool, DatabaseError>;
+
+    // ==================== Leases ====================
+
+    /// Take the lease `name` for `owner` until `ttl` from now. Succeeds if
+    /// the lease is free, expired, or already held by `owner` (which
+    /// extends it). Returns whether `owner` now holds it.
+    async fn try_acquire_lease(
+        &self,
+        name: &str,
+        owner: &str,
+        ttl: Duration,
+    ) -> Result<bool, DatabaseError>;
+
+    /// Extend a lease `owner` still holds to `ttl` from now. Returns false
+    /// if another instance has taken it over.
+    async fn renew_lease(
+        &self,
+        name: &str,
+        owner: &str,
+        ttl: Duration,
+    ) -> Result<bool, DatabaseError>;
+
+    /// Give up a lease if `owner` holds it.
+    async fn release_lease(&self, name: &str, owner: &str) -> Result<(), DatabaseError>;
+
+    /// The current holder of a lease, expired or not.
+    async fn get_lease(&self, name: &str) -> Result<Option<LeaseRecord>, DatabaseError>;
+
+    // ==================== Knowledge Graph ====================
+
+    /// Get the entity whose name matches `name` (case-insensitive) in this
+    /// scope, creating it with `kind` if there is none.
+    async fn upsert_graph_entity(
+        &self,
+        user_id: &str,
+        agent_id: Option<Uuid>,
+        name: &str,
+        kind: &str,
+    ) -> Result<GraphEntity, DatabaseError>;
+
+    /// Entities whose key contains `key`, exact match first, then shortest.
+    async fn find_graph_entities(
+        &self,
+        user_id: &str,
+        agent_id: Option<Uuid>,
+        key: &str,
+        limit: usize,
+    ) -> Result<Vec<GraphEntity>, DatabaseError>;
+
+    /// Get entities by ID.
+    async fn get_graph_entities(&self, ids: &[Uuid]) -> Result<Vec<GraphEntity>, DatabaseError>;
+
+    /// Record a relation between two entities.
+    async fn insert_graph_edge(&self, edge: &GraphEdge) -> Result<(), DatabaseError>;
+
+    /// Edges with either end in `entity_ids`.
+    async fn graph_edges_touching(
+        &self,
+        entity_ids: &[Uuid],
+    ) -> Result<Vec<GraphEdge>, DatabaseError>;
+
+    /// Delete the edges extracted from a document. Returns how many.
+    async fn delete_graph_edges_for_document(
+        &self,
+        document_id: Uuid,
+    ) -> Result<u64, DatabaseError>;
+
     // ==================== Routines ====================
 
     /// Create a new routine.
@@ -333,6 +533,9 @@ pub trait Database: Send + Sync {
     /// List all enabled event routines.
     async fn list_event_routines(&self) -> Result<Vec<Routine>, DatabaseError>;
 
+    /// List all enabled webhook routines.
+    async fn list_webhook_routines(&self) -> Result<Vec<Routine>, DatabaseError>;
+
     /// List due cron routines.
     async fn list_due_cron_routines(&self) -> Result<Vec<Routine>, DatabaseError>;
 
@@ -451,6 +654,24 @@ pub trait Database: Send + Sync {
         path: &str,
     ) -> Result<MemoryDocument, WorkspaceError>;
 
+    /// Stored path of the document whose path equals `path` ignoring case,
+    /// preferring an exact match.
+    async fn find_document_path(
+        &self,
+        user_id: &str,
+        agent_id: Option<Uuid>,
+        path: &str,
+    ) -> Result<Option<String>, WorkspaceError>;
+
+    /// Get the documents at `paths` in one query, in no particular order.
+    /// Paths without a document are skipped.
+    async fn get_documents_by_paths(
+        &self,
+        user_id: &str,
+        agent_id: Option<Uuid>,
+        paths: &[String],
+    ) -> Result<Vec<MemoryDocument>, WorkspaceError>;
+
     /// Get a document by ID.
     async fn get_document_by_id(&self, id: Uuid) -> Result<MemoryDocument, WorkspaceError>;
 
@@ -465,6 +686,27 @@ pub trait Database: Send + Sync {
     /// Update a document's content.
     async fn update_document(&self, id: Uuid, content: &str) -> Result<(), WorkspaceError>;
 
+    /// Move a document to `path` within its scope.
+    async fn rename_document(&self, id: Uuid, path: &str) -> Result<(), WorkspaceError>;
+
+    /// Move every document below directory `from` to the same place below
+    /// `to`, together with the directory records at and below `from`, in
+    /// one transaction. Returns how many documents were moved.
+    async fn rename_directory(
+        &self,
+        user_id: &str,
+        agent_id: Option<Uuid>,
+        from: &str,
+        to: &str,
+    ) -> Result<u64, WorkspaceError>;
+
+    /// Replace a document's metadata without touching its content.
+    async fn update_document_metadata(
+        &self,
+        id: Uuid,
+        metadata: &serde_json::Value,
+    ) -> Result<(), WorkspaceError>;
+
     /// Delete a document by path.
     async fn delete_document_by_path(
         &self,
@@ -473,6 +715,16 @@ pub trait Database: Send + Sync {
         path: &str,
     ) -> Result<(), WorkspaceError>;
 
+    /// Delete the documents at `paths` with their chunks in one
+    /// transaction, returning how many were deleted. Paths without a
+    /// document are skipped.
+    async fn delete_documents_by_paths(
+        &self,
+        user_id: &str,
+        agent_id: Option<Uuid>,
+        paths: &[String],
+    ) -> Result<u64, WorkspaceError>;
+
     /// List files and directories in a directory path.
     async fn list_directory(
         &self,
@@ -488,6 +740,33 @@ pub trait Database: Send + Sync {
         agent_id: Option<Uuid>,
     ) -> Result<Vec<String>, WorkspaceError>;
 
+    /// Up to `limit` file paths in the workspace, ordered by path and
+    /// starting after `after`, for listing a large workspace a page at a
+    /// time.
+    async fn list_paths_page(
+        &self,
+        user_id: &str,
+        agent_id: Option<Uuid>,
+        after: Option<&str>,
+        limit: usize,
+    ) -> Result<Vec<String>, WorkspaceError>;
+
+    /// Paths of the documents matching `glob`, ordered by path.
+    async fn list_paths_matching(
+        &self,
+        user_id: &str,
+        agent_id: Option<Uuid>,
+        glob: &PathGlob,
+    ) -> Result<Vec<String>, WorkspaceError>;
+
+    /// Content size and chunk count of every document in the workspace,
+    /// ordered by path.
+    async fn document_sizes(
+        &self,
+        user_id: &str,
+        agent_id: Option<Uuid>,
+    ) -> Result<Vec<DocumentSize>, WorkspaceError>;
+
     /// List all documents for a user.
     async fn list_documents(
         &self,
@@ -495,20 +774,37 @@ pub trait Database: Send + Sync {
         agent_id: Option<Uuid>,
     ) -> Result<Vec<MemoryDocument>, WorkspaceError>;
 
+    /// List documents whose stored frontmatter matches every filter.
+    async fn find_documents_by_fields(
+        &self,
+        user_id: &str,
+        agent_id: Option<Uuid>,
+        filters: &[FieldFilter],
+    ) -> Result<Vec<MemoryDocument>, WorkspaceError>;
+
     // ==================== Workspace: Chunks ====================
 
     /// Delete all chunks for a document.
     async fn delete_chunks(&self, document_id: Uuid) -> Result<(), WorkspaceError>;
 
-    /// Insert a chunk.
+    /// Insert a chunk written in `language`.
     async fn insert_chunk(
         &self,
         document_id: Uuid,
         chunk_index: i32,
         content: &str,
         embedding: Option<&[f32]>,
+        language: Language,
     ) -> Result<Uuid, WorkspaceError>;
 
+    /// Insert a document's chunks in one round trip, returning their IDs in
+    /// the order given.
+    async fn insert_chunks(
+        &self,
+        document_id: Uuid,
+        chunks: Vec<NewChunk>,
+    ) -> Result<Vec<Uuid>, WorkspaceError>;
+
     /// Update a chunk's embedding.
     async fn update_chunk_embedding(
         &self,
@@ -516,23 +812,203 @@ pub trait Database: Send + Sync {
         embedding: &[f32],
     ) -> Result<(), WorkspaceError>;
 
-    /// Get chunks without embeddings for backfilling.
+    /// Get chunks without embeddings for backfilling, ordered by ID and
+    /// starting after `after` (for paging past chunks that failed to embed).
     async fn get_chunks_without_embeddings(
         &self,
         user_id: &str,
         agent_id: Option<Uuid>,
+        after: Option<Uuid>,
         limit: usize,
     ) -> Result<Vec<MemoryChunk>, WorkspaceError>;
 
+    /// A document's chunks with their embeddings, in document order.
+    async fn get_document_chunks(
+        &self,
+        document_id: Uuid,
+    ) -> Result<Vec<MemoryChunk>, WorkspaceError>;
+
+    /// Number of a user's chunks in each language, across all agents.
+    async fn chunk_language_counts(
+        &self,
+        user_id: &str,
+    ) -> Result<Vec<(Language, u64)>, WorkspaceError>;
+
+    // ==================== Workspace: Chunk Importance ====================
+
+    /// Set the static importance of a chunk, creating its signals if needed.
+    async fn set_chunk_importance(
+        &self,
+        document_id: Uuid,
+        content_hash: &str,
+        importance: f32,
+    ) -> Result<(), WorkspaceError>;
+
+    /// Drop signals for a document's chunks whose hash is not in `keep`.
+    async fn prune_chunk_signals(
+        &self,
+        document_id: Uuid,
+        keep: &[String],
+    ) -> Result<(), WorkspaceError>;
+
+    /// Count one search retrieval of a chunk.
+    async fn record_chunk_retrieval(
+        &self,
+        document_id: Uuid,
+        content_hash: &str,
+    ) -> Result<(), WorkspaceError>;
+
+    /// Importance signals for all chunks of the given documents.
+    async fn get_chunk_signals(
+        &self,
+        document_ids: &[Uuid],
+    ) -> Result<Vec<ChunkSignals>, WorkspaceError>;
+
+    // ==================== Workspace: Pins ====================
+
+    /// Store a new pin.
+    async fn create_memory_pin(
+        &self,
+        user_id: &str,
+        agent_id: Option<Uuid>,
+        pin: &MemoryPin,
+    ) -> Result<(), WorkspaceError>;
+
+    /// List pins, oldest first.
+    async fn list_memory_pins(
+        &self,
+        user_id: &str,
+        agent_id: Option<Uuid>,
+    ) -> Result<Vec<MemoryPin>, WorkspaceError>;
+
+    /// Delete a pin. Returns false if it did not exist.
+    async fn delete_memory_pin(
+        &self,
+        user_id: &str,
+        agent_id: Option<Uuid>,
+        id: Uuid,
+    ) -> Result<bool, WorkspaceError>;
+
+    // ==================== Workspace: Directories ====================
+
+    /// Record a directory. Returns false if it was already recorded.
+    async fn create_directory(
+        &self,
+        user_id: &str,
+        agent_id: Option<Uuid>,
+        path: &str,
+    ) -> Result<bool, WorkspaceError>;
+
+    /// Recorded directories at `prefix` or below it (all of them for an
+    /// empty prefix), ordered by path.
+    async fn list_directories(
+        &self,
+        user_id: &str,
+        agent_id: Option<Uuid>,
+        prefix: &str,
+    ) -> Result<Vec<DirectoryRecord>, WorkspaceError>;
+
+    /// Replace a recorded directory's metadata. Returns false if it isn't
+    /// recorded.
+    async fn update_directory_metadata(
+        &self,
+        user_id: &str,
+        agent_id: Option<Uuid>,
+        path: &str,
+        metadata: &serde_json::Value,
+    ) -> Result<bool, WorkspaceError>;
+
+    /// Forget the directory at `path` and every directory below it.
+    /// Returns how many were removed.
+    async fn delete_directories(
+        &self,
+        user_id: &str,
+        agent_id: Option<Uuid>,
+        path: &str,
+    ) -> Result<u64, WorkspaceError>;
+
+    // ==================== Workspace: Versions ====================
+
+    /// Store `content`, written at `saved_at`, as the next revision of a
+    /// document. Returns its version number.
+    async fn insert_document_version(
+        &self,
+        document_id: Uuid,
+        content: &str,
+        saved_at: DateTime<Utc>,
+    ) -> Result<i32, WorkspaceError>;
+
+    /// Delete all but the newest `keep` revisions of a document.
+    async fn prune_document_versions(
+        &self,
+        document_id: Uuid,
+        keep: usize,
+    ) -> Result<(), WorkspaceError>;
+
+    /// A document's revisions, newest first.
+    async fn list_document_versions(
+        &self,
+        document_id: Uuid,
+    ) -> Result<Vec<VersionInfo>, WorkspaceError>;
+
+    /// One revision of a document, if it is stored.
+    async fn get_document_version(
+        &self,
+        document_id: Uuid,
+        version: i32,
+    ) -> Result<Option<DocumentVersion>, WorkspaceError>;
+
+    // ==================== Workspace: Links ====================
+
+    /// Replace the `[[wiki link]]` targets stored for a document.
+    async fn set_document_links(
+        &self,
+        document_id: Uuid,
+        targets: &[String],
+    ) -> Result<(), WorkspaceError>;
+
+    /// Links in the scope whose target equals `key` or is a `/`-bounded
+    /// suffix of it, with the path of the linking document.
+    async fn find_backlinks(
+        &self,
+        user_id: &str,
+        agent_id: Option<Uuid>,
+        key: &str,
+    ) -> Result<Vec<Backlink>, WorkspaceError>;
+
     // ==================== Workspace: Search ====================
 
-    /// Perform hybrid search combining FTS and vector similarity.
+    /// Perform hybrid search combining FTS, vector similarity, and (given
+    /// the query's sparse vector) learned sparse matching.
     async fn hybrid_search(
         &self,
         user_id: &str,
         agent_id: Option<Uuid>,
         query: &str,
         embedding: Option<&[f32]>,
+        sparse: Option<&SparseVector>,
         config: &SearchConfig,
     ) -> Result<Vec<SearchResult>, WorkspaceError>;
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_schema_version_matches_migrations() {
+        let dir = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("migrations");
+        let latest = std::fs::read_dir(dir)
+            .unwrap()
+            .filter_map(|e| {
+                let name = e.ok()?.file_name().into_string().ok()?;
+                name.strip_prefix('V')?
+                    .split("__")
+                    .next()?
+                    .parse::<i64>()
+                    .ok()
+            })
+            .max();
+        assert_eq!(latest, Some(SCHEMA_VERSION));
+    }
+}
@@ -4,6 +4,7 @@
 //! implementations, avoiding SQL duplication.
 
 use std::collections::HashMap;
+use std::time::Duration;
 
 use async_trait::async_trait;
 use chrono::{DateTime, Utc};
@@ -12,17 +13,30 @@ use rust_decimal::Decimal;
 use uuid::Uuid;
 
 use crate::agent::BrokenTool;
+use crate::agent::registry::AgentDefinition;
 use crate::agent::routine::{Routine, RoutineRun, RunStatus};
+use crate::agent::transcript::TranscriptEntry;
+use crate::audit::{AuditEvent, AuditQuery, AuditRecord};
 use crate::config::DatabaseConfig;
+use crate::contacts::Contact;
 use crate::context::{ActionRecord, JobContext, JobState};
-use crate::db::Database;
+use crate::db::lease::LeaseRecord;
+use crate::db::{Database, DbDiagnostics};
 use crate::error::{DatabaseError, WorkspaceError};
 use crate::history::{
-    ConversationMessage, ConversationSummary, JobEventRecord, LlmCallRecord, SandboxJobRecord,
-    SandboxJobSummary, SettingRow, Store,
+    ConversationMessage, ConversationSummary, JobEventRecord, JobUsageRecord, LlmCallRecord,
+    SandboxJobRecord, SandboxJobSummary, SettingRow, Store,
 };
+use crate::workspace::frontmatter::FieldFilter;
+use crate::workspace::glob::PathGlob;
+use crate::workspace::graph::{GraphEdge, GraphEntity};
+use crate::workspace::importance::ChunkSignals;
+use crate::workspace::language::Language;
+use crate::workspace::links::Backlink;
+use crate::workspace::pins::MemoryPin;
 use crate::workspace::{
-    MemoryChunk, MemoryDocument, Repository, SearchConfig, SearchResult, WorkspaceEntry,
+    DirectoryRecord, DocumentSize, DocumentVersion, MemoryChunk, MemoryDocument, NewChunk,
+    Repository, SearchConfig, SearchResult, SparseVector, VersionInfo, WorkspaceEntry,
 };
 
 /// PostgreSQL database backend.
@@ -53,12 +67,24 @@ impl PgBackend {
 
 #[async_trait]
 impl Database for PgBackend {
+    #[tracing::instrument(name = "db.run_migrations", skip_all)]
     async fn run_migrations(&self) -> Result<(), DatabaseError> {
         self.store.run_migrations().await
     }
 
+    #[tracing::instrument(name = "db.ping", skip_all)]
+    async fn ping(&self) -> Result<(), DatabaseError> {
+        self.store.ping().await
+    }
+
+    #[tracing::instrument(name = "db.diagnostics", skip_all)]
+    async fn diagnostics(&self) -> Result<DbDiagnostics, DatabaseError> {
+        self.store.diagnostics().await
+    }
+
     // ==================== Conversations ====================
 
+    #[tracing::instrument(name = "db.create_conversation", skip_all)]
     async fn create_conversation(
         &self,
         channel: &str,
@@ -70,10 +96,12 @@ impl Database for PgBackend {
             .await
     }
 
+    #[tracing::instrument(name = "db.touch_conversation", skip_all)]
     async fn touch_conversation(&self, id: Uuid) -> Result<(), DatabaseError> {
         self.store.touch_conversation(id).await
     }
 
+    #[tracing::instrument(name = "db.add_conversation_message", skip_all)]
     async fn add_conversation_message(
         &self,
         conversation_id: Uuid,
@@ -85,6 +113,7 @@ impl Database for PgBackend {
             .await
     }
 
+    #[tracing::instrument(name = "db.ensure_conversation", skip_all)]
     async fn ensure_conversation(
         &self,
         id: Uuid,
@@ -97,6 +126,7 @@ impl Database for PgBackend {
             .await
     }
 
+    #[tracing::instrument(name = "db.list_conversations_with_preview", skip_all)]
     async fn list_conversations_with_preview(
         &self,
         user_id: &str,
@@ -108,6 +138,7 @@ impl Database for PgBackend {
             .await
     }
 
+    #[tracing::instrument(name = "db.get_or_create_assistant_conversation", skip_all)]
     async fn get_or_create_assistant_conversation(
         &self,
         user_id: &str,
@@ -118,6 +149,7 @@ impl Database for PgBackend {
             .await
     }
 
+    #[tracing::instrument(name = "db.create_conversation_with_metadata", skip_all)]
     async fn create_conversation_with_metadata(
         &self,
         channel: &str,
@@ -129,6 +161,7 @@ impl Database for PgBackend {
             .await
     }
 
+    #[tracing::instrument(name = "db.list_conversation_messages_paginated", skip_all)]
     async fn list_conversation_messages_paginated(
         &self,
         conversation_id: Uuid,
@@ -140,6 +173,7 @@ impl Database for PgBackend {
             .await
     }
 
+    #[tracing::instrument(name = "db.update_conversation_metadata_field", skip_all)]
     async fn update_conversation_metadata_field(
         &self,
         id: Uuid,
@@ -151,6 +185,7 @@ impl Database for PgBackend {
             .await
     }
 
+    #[tracing::instrument(name = "db.get_conversation_metadata", skip_all)]
     async fn get_conversation_metadata(
         &self,
         id: Uuid,
@@ -158,6 +193,7 @@ impl Database for PgBackend {
         self.store.get_conversation_metadata(id).await
     }
 
+    #[tracing::instrument(name = "db.list_conversation_messages", skip_all)]
     async fn list_conversation_messages(
         &self,
         conversation_id: Uuid,
@@ -165,6 +201,7 @@ impl Database for PgBackend {
         self.store.list_conversation_messages(conversation_id).await
     }
 
+    #[tracing::instrument(name = "db.conversation_belongs_to_user", skip_all)]
     async fn conversation_belongs_to_user(
         &self,
         conversation_id: Uuid,
@@ -175,16 +212,36 @@ impl Database for PgBackend {
             .await
     }
 
+    #[tracing::instrument(name = "db.find_conversation", skip_all)]
+    async fn find_conversation(
+        &self,
+        user_id: &str,
+        channel: &str,
+        thread_id: Option<&str>,
+    ) -> Result<Option<Uuid>, DatabaseError> {
+        self.store
+            .find_conversation(user_id, channel, thread_id)
+            .await
+    }
+
+    #[tracing::instrument(name = "db.prune_conversations", skip_all)]
+    async fn prune_conversations(&self, cutoff: DateTime<Utc>) -> Result<u64, DatabaseError> {
+        self.store.prune_conversations(cutoff).await
+    }
+
     // ==================== Jobs ====================
 
+    #[tracing::instrument(name = "db.save_job", skip_all)]
     async fn save_job(&self, ctx: &JobContext) -> Result<(), DatabaseError> {
         self.store.save_job(ctx).await
     }
 
+    #[tracing::instrument(name = "db.get_job", skip_all)]
     async fn get_job(&self, id: Uuid) -> Result<Option<JobContext>, DatabaseError> {
         self.store.get_job(id).await
     }
 
+    #[tracing::instrument(name = "db.update_job_status", skip_all)]
     async fn update_job_status(
         &self,
         id: Uuid,
@@ -196,32 +253,47 @@ impl Database for PgBackend {
             .await
     }
 
+    #[tracing::instrument(name = "db.mark_job_stuck", skip_all)]
     async fn mark_job_stuck(&self, id: Uuid) -> Result<(), DatabaseError> {
         self.store.mark_job_stuck(id).await
     }
 
+    #[tracing::instrument(name = "db.get_stuck_jobs", skip_all)]
     async fn get_stuck_jobs(&self) -> Result<Vec<Uuid>, DatabaseError> {
         self.store.get_stuck_jobs().await
     }
 
+    async fn list_job_usage(
+        &self,
+        user_id: &str,
+        since: DateTime<Utc>,
+        until: DateTime<Utc>,
+    ) -> Result<Vec<JobUsageRecord>, DatabaseError> {
+        self.store.list_job_usage(user_id, since, until).await
+    }
+
     // ==================== Actions ====================
 
+    #[tracing::instrument(name = "db.save_action", skip_all)]
     async fn save_action(&self, job_id: Uuid, action: &ActionRecord) -> Result<(), DatabaseError> {
         self.store.save_action(job_id, action).await
     }
 
+    #[tracing::instrument(name = "db.get_job_actions", skip_all)]
     async fn get_job_actions(&self, job_id: Uuid) -> Result<Vec<ActionRecord>, DatabaseError> {
         self.store.get_job_actions(job_id).await
     }
 
     // ==================== LLM Calls ====================
 
+    #[tracing::instrument(name = "db.record_llm_call", skip_all)]
     async fn record_llm_call(&self, record: &LlmCallRecord<'_>) -> Result<Uuid, DatabaseError> {
         self.store.record_llm_call(record).await
     }
 
     // ==================== Estimation Snapshots ====================
 
+    #[tracing::instrument(name = "db.save_estimation_snapshot", skip_all)]
     async fn save_estimation_snapshot(
         &self,
         job_id: Uuid,
@@ -243,6 +315,7 @@ impl Database for PgBackend {
             .await
     }
 
+    #[tracing::instrument(name = "db.update_estimation_actuals", skip_all)]
     async fn update_estimation_actuals(
         &self,
         id: Uuid,
@@ -257,18 +330,22 @@ impl Database for PgBackend {
 
     // ==================== Sandbox Jobs ====================
 
+    #[tracing::instrument(name = "db.save_sandbox_job", skip_all)]
     async fn save_sandbox_job(&self, job: &SandboxJobRecord) -> Result<(), DatabaseError> {
         self.store.save_sandbox_job(job).await
     }
 
+    #[tracing::instrument(name = "db.get_sandbox_job", skip_all)]
     async fn get_sandbox_job(&self, id: Uuid) -> Result<Option<SandboxJobRecord>, DatabaseError> {
         self.store.get_sandbox_job(id).await
     }
 
+    #[tracing::instrument(name = "db.list_sandbox_jobs", skip_all)]
     async fn list_sandbox_jobs(&self) -> Result<Vec<SandboxJobRecord>, DatabaseError> {
         self.store.list_sandbox_jobs().await
     }
 
+    #[tracing::instrument(name = "db.update_sandbox_job_status", skip_all)]
     async fn update_sandbox_job_status(
         &self,
         id: Uuid,
@@ -283,14 +360,17 @@ impl Database for PgBackend {
             .await
     }
 
+    #[tracing::instrument(name = "db.cleanup_stale_sandbox_jobs", skip_all)]
     async fn cleanup_stale_sandbox_jobs(&self) -> Result<u64, DatabaseError> {
         self.store.cleanup_stale_sandbox_jobs().await
     }
 
+    #[tracing::instrument(name = "db.sandbox_job_summary", skip_all)]
     async fn sandbox_job_summary(&self) -> Result<SandboxJobSummary, DatabaseError> {
         self.store.sandbox_job_summary().await
     }
 
+    #[tracing::instrument(name = "db.list_sandbox_jobs_for_user", skip_all)]
     async fn list_sandbox_jobs_for_user(
         &self,
         user_id: &str,
@@ -298,6 +378,7 @@ impl Database for PgBackend {
         self.store.list_sandbox_jobs_for_user(user_id).await
     }
 
+    #[tracing::instrument(name = "db.sandbox_job_summary_for_user", skip_all)]
     async fn sandbox_job_summary_for_user(
         &self,
         user_id: &str,
@@ -305,6 +386,7 @@ impl Database for PgBackend {
         self.store.sandbox_job_summary_for_user(user_id).await
     }
 
+    #[tracing::instrument(name = "db.sandbox_job_belongs_to_user", skip_all)]
     async fn sandbox_job_belongs_to_user(
         &self,
         job_id: Uuid,
@@ -315,16 +397,19 @@ impl Database for PgBackend {
             .await
     }
 
+    #[tracing::instrument(name = "db.update_sandbox_job_mode", skip_all)]
     async fn update_sandbox_job_mode(&self, id: Uuid, mode: &str) -> Result<(), DatabaseError> {
         self.store.update_sandbox_job_mode(id, mode).await
     }
 
+    #[tracing::instrument(name = "db.get_sandbox_job_mode", skip_all)]
     async fn get_sandbox_job_mode(&self, id: Uuid) -> Result<Option<String>, DatabaseError> {
         self.store.get_sandbox_job_mode(id).await
     }
 
     // ==================== Job Events ====================
 
+    #[tracing::instrument(name = "db.save_job_event", skip_all)]
     async fn save_job_event(
         &self,
         job_id: Uuid,
@@ -334,20 +419,210 @@ impl Database for PgBackend {
         self.store.save_job_event(job_id, event_type, data).await
     }
 
+    #[tracing::instrument(name = "db.list_job_events", skip_all)]
     async fn list_job_events(&self, job_id: Uuid) -> Result<Vec<JobEventRecord>, DatabaseError> {
         self.store.list_job_events(job_id).await
     }
 
+    // ==================== Job Transcripts ====================
+
+    #[tracing::instrument(name = "db.append_transcript_entry", skip_all)]
+    async fn append_transcript_entry(
+        &self,
+        job_id: Uuid,
+        entry: &TranscriptEntry,
+    ) -> Result<(), DatabaseError> {
+        self.store.append_transcript_entry(job_id, entry).await
+    }
+
+    #[tracing::instrument(name = "db.get_job_transcript", skip_all)]
+    async fn get_job_transcript(
+        &self,
+        job_id: Uuid,
+    ) -> Result<Vec<TranscriptEntry>, DatabaseError> {
+        self.store.get_job_transcript(job_id).await
+    }
+
+    // ==================== Audit Log ====================
+
+    #[tracing::instrument(name = "db.append_audit_event", skip_all)]
+    async fn append_audit_event(&self, event: &AuditEvent) -> Result<i64, DatabaseError> {
+        self.store.append_audit_event(event).await
+    }
+
+    #[tracing::instrument(name = "db.query_audit_log", skip_all)]
+    async fn query_audit_log(&self, query: &AuditQuery) -> Result<Vec<AuditRecord>, DatabaseError> {
+        self.store.query_audit_log(query).await
+    }
+
+    #[tracing::instrument(name = "db.prune_audit_log", skip_all)]
+    async fn prune_audit_log(&self, cutoff: DateTime<Utc>) -> Result<u64, DatabaseError> {
+        self.store.prune_audit_log(cutoff).await
+    }
+
+    // ==================== Agents ====================
+
+    #[tracing::instrument(name = "db.create_agent", skip_all)]
+    async fn create_agent(&self, agent: &AgentDefinition) -> Result<(), DatabaseError> {
+        self.store.create_agent(agent).await
+    }
+
+    #[tracing::instrument(name = "db.get_agent", skip_all)]
+    async fn get_agent(&self, id: Uuid) -> Result<Option<AgentDefinition>, DatabaseError> {
+        self.store.get_agent(id).await
+    }
+
+    #[tracing::instrument(name = "db.get_agent_by_name", skip_all)]
+    async fn get_agent_by_name(
+        &self,
+        name: &str,
+    ) -> Result<Option<AgentDefinition>, DatabaseError> {
+        self.store.get_agent_by_name(name).await
+    }
+
+    #[tracing::instrument(name = "db.list_agents", skip_all)]
+    async fn list_agents(&self) -> Result<Vec<AgentDefinition>, DatabaseError> {
+        self.store.list_agents().await
+    }
+
+    #[tracing::instrument(name = "db.update_agent", skip_all)]
+    async fn update_agent(&self, agent: &AgentDefinition) -> Result<(), DatabaseError> {
+        self.store.update_agent(agent).await
+    }
+
+    #[tracing::instrument(name = "db.delete_agent", skip_all)]
+    async fn delete_agent(&self, id: Uuid) -> Result<bool, DatabaseError> {
+        self.store.delete_agent(id).await
+    }
+
+    // ==================== Contacts ====================
+
+    #[tracing::instrument(name = "db.create_contact", skip_all)]
+    async fn create_contact(&self, contact: &Contact) -> Result<(), DatabaseError> {
+        self.store.create_contact(contact).await
+    }
+
+    #[tracing::instrument(name = "db.get_contact", skip_all)]
+    async fn get_contact(&self, id: Uuid) -> Result<Option<Contact>, DatabaseError> {
+        self.store.get_contact(id).await
+    }
+
+    #[tracing::instrument(name = "db.list_contacts", skip_all)]
+    async fn list_contacts(&self, user_id: &str) -> Result<Vec<Contact>, DatabaseError> {
+        self.store.list_contacts(user_id).await
+    }
+
+    #[tracing::instrument(name = "db.update_contact", skip_all)]
+    async fn update_contact(&self, contact: &Contact) -> Result<(), DatabaseError> {
+        self.store.update_contact(contact).await
+    }
+
+    #[tracing::instrument(name = "db.delete_contact", skip_all)]
+    async fn delete_contact(&self, id: Uuid) -> Result<bool, DatabaseError> {
+        self.store.delete_contact(id).await
+    }
+
+    // ==================== Leases ====================
+
+    #[tracing::instrument(name = "db.try_acquire_lease", skip_all)]
+    async fn try_acquire_lease(
+        &self,
+        name: &str,
+        owner: &str,
+        ttl: Duration,
+    ) -> Result<bool, DatabaseError> {
+        self.store.try_acquire_lease(name, owner, ttl).await
+    }
+
+    #[tracing::instrument(name = "db.renew_lease", skip_all)]
+    async fn renew_lease(
+        &self,
+        name: &str,
+        owner: &str,
+        ttl: Duration,
+    ) -> Result<bool, DatabaseError> {
+        self.store.renew_lease(name, owner, ttl).await
+    }
+
+    #[tracing::instrument(name = "db.release_lease", skip_all)]
+    async fn release_lease(&self, name: &str, owner: &str) -> Result<(), DatabaseError> {
+        self.store.release_lease(name, owner).await
+    }
+
+    #[tracing::instrument(name = "db.get_lease", skip_all)]
+    async fn get_lease(&self, name: &str) -> Result<Option<LeaseRecord>, DatabaseError> {
+        self.store.get_lease(name).await
+    }
+
+    // ==================== Knowledge Graph ====================
+
+    #[tracing::instrument(name = "db.upsert_graph_entity", skip_all)]
+    async fn upsert_graph_entity(
+        &self,
+        user_id: &str,
+        agent_id: Option<Uuid>,
+        name: &str,
+        kind: &str,
+    ) -> Result<GraphEntity, DatabaseError> {
+        self.store
+            .upsert_graph_entity(user_id, agent_id, name, kind)
+            .await
+    }
+
+    #[tracing::instrument(name = "db.find_graph_entities", skip_all)]
+    async fn find_graph_entities(
+        &self,
+        user_id: &str,
+        agent_id: Option<Uuid>,
+        key: &str,
+        limit: usize,
+    ) -> Result<Vec<GraphEntity>, DatabaseError> {
+        self.store
+            .find_graph_entities(user_id, agent_id, key, limit)
+            .await
+    }
+
+    #[tracing::instrument(name = "db.get_graph_entities", skip_all)]
+    async fn get_graph_entities(&self, ids: &[Uuid]) -> Result<Vec<GraphEntity>, DatabaseError> {
+        self.store.get_graph_entities(ids).await
+    }
+
+    #[tracing::instrument(name = "db.insert_graph_edge", skip_all)]
+    async fn insert_graph_edge(&self, edge: &GraphEdge) -> Result<(), DatabaseError> {
+        self.store.insert_graph_edge(edge).await
+    }
+
+    #[tracing::instrument(name = "db.graph_edges_touching", skip_all)]
+    async fn graph_edges_touching(
+        &self,
+        entity_ids: &[Uuid],
+    ) -> Result<Vec<GraphEdge>, DatabaseError> {
+        self.store.graph_edges_touching(entity_ids).await
+    }
+
+    #[tracing::instrument(name = "db.delete_graph_edges_for_document", skip_all)]
+    async fn delete_graph_edges_for_document(
+        &self,
+        document_id: Uuid,
+    ) -> Result<u64, DatabaseError> {
+        self.store
+            .delete_graph_edges_for_document(document_id)
+            .await
+    }
+
     // ==================== Routines ====================
 
+    #[tracing::instrument(name = "db.create_routine", skip_all)]
     async fn create_routine(&self, routine: &Routine) -> Result<(), DatabaseError> {
         self.store.create_routine(routine).await
     }
 
+    #[tracing::instrument(name = "db.get_routine", skip_all)]
     async fn get_routine(&self, id: Uuid) -> Result<Option<Routine>, DatabaseError> {
         self.store.get_routine(id).await
     }
 
+    #[tracing::instrument(name = "db.get_routine_by_name", skip_all)]
     async fn get_routine_by_name(
         &self,
         user_id: &str,
@@ -356,22 +631,32 @@ impl Database for PgBackend {
         self.store.get_routine_by_name(user_id, name).await
     }
 
+    #[tracing::instrument(name = "db.list_routines", skip_all)]
     async fn list_routines(&self, user_id: &str) -> Result<Vec<Routine>, DatabaseError> {
         self.store.list_routines(user_id).await
     }
 
+    #[tracing::instrument(name = "db.list_event_routines", skip_all)]
     async fn list_event_routines(&self) -> Result<Vec<Routine>, DatabaseError> {
         self.store.list_event_routines().await
     }
 
+    #[tracing::instrument(name = "db.list_webhook_routines", skip_all)]
+    async fn list_webhook_routines(&self) -> Result<Vec<Routine>, DatabaseError> {
+        self.store.list_webhook_routines().await
+    }
+
+    #[tracing::instrument(name = "db.list_due_cron_routines", skip_all)]
     async fn list_due_cron_routines(&self) -> Result<Vec<Routine>, DatabaseError> {
         self.store.list_due_cron_routines().await
     }
 
+    #[tracing::instrument(name = "db.update_routine", skip_all)]
     async fn update_routine(&self, routine: &Routine) -> Result<(), DatabaseError> {
         self.store.update_routine(routine).await
     }
 
+    #[tracing::instrument(name = "db.update_routine_runtime", skip_all)]
     async fn update_routine_runtime(
         &self,
         id: Uuid,
@@ -393,16 +678,19 @@ impl Database for PgBackend {
             .await
     }
 
+    #[tracing::instrument(name = "db.delete_routine", skip_all)]
     async fn delete_routine(&self, id: Uuid) -> Result<bool, DatabaseError> {
         self.store.delete_routine(id).await
     }
 
     // ==================== Routine Runs ====================
 
+    #[tracing::instrument(name = "db.create_routine_run", skip_all)]
     async fn create_routine_run(&self, run: &RoutineRun) -> Result<(), DatabaseError> {
         self.store.create_routine_run(run).await
     }
 
+    #[tracing::instrument(name = "db.complete_routine_run", skip_all)]
     async fn complete_routine_run(
         &self,
         id: Uuid,
@@ -415,6 +703,7 @@ impl Database for PgBackend {
             .await
     }
 
+    #[tracing::instrument(name = "db.list_routine_runs", skip_all)]
     async fn list_routine_runs(
         &self,
         routine_id: Uuid,
@@ -423,12 +712,14 @@ impl Database for PgBackend {
         self.store.list_routine_runs(routine_id, limit).await
     }
 
+    #[tracing::instrument(name = "db.count_running_routine_runs", skip_all)]
     async fn count_running_routine_runs(&self, routine_id: Uuid) -> Result<i64, DatabaseError> {
         self.store.count_running_routine_runs(routine_id).await
     }
 
     // ==================== Tool Failures ====================
 
+    #[tracing::instrument(name = "db.record_tool_failure", skip_all)]
     async fn record_tool_failure(
         &self,
         tool_name: &str,
@@ -439,20 +730,24 @@ impl Database for PgBackend {
             .await
     }
 
+    #[tracing::instrument(name = "db.get_broken_tools", skip_all)]
     async fn get_broken_tools(&self, threshold: i32) -> Result<Vec<BrokenTool>, DatabaseError> {
         self.store.get_broken_tools(threshold).await
     }
 
+    #[tracing::instrument(name = "db.mark_tool_repaired", skip_all)]
     async fn mark_tool_repaired(&self, tool_name: &str) -> Result<(), DatabaseError> {
         self.store.mark_tool_repaired(tool_name).await
     }
 
+    #[tracing::instrument(name = "db.increment_repair_attempts", skip_all)]
     async fn increment_repair_attempts(&self, tool_name: &str) -> Result<(), DatabaseError> {
         self.store.increment_repair_attempts(tool_name).await
     }
 
     // ==================== Settings ====================
 
+    #[tracing::instrument(name = "db.get_setting", skip_all)]
     async fn get_setting(
         &self,
         user_id: &str,
@@ -461,6 +756,7 @@ impl Database for PgBackend {
         self.store.get_setting(user_id, key).await
     }
 
+    #[tracing::instrument(name = "db.get_setting_full", skip_all)]
     async fn get_setting_full(
         &self,
         user_id: &str,
@@ -469,6 +765,7 @@ impl Database for PgBackend {
         self.store.get_setting_full(user_id, key).await
     }
 
+    #[tracing::instrument(name = "db.set_setting", skip_all)]
     async fn set_setting(
         &self,
         user_id: &str,
@@ -478,14 +775,17 @@ impl Database for PgBackend {
         self.store.set_setting(user_id, key, value).await
     }
 
+    #[tracing::instrument(name = "db.delete_setting", skip_all)]
     async fn delete_setting(&self, user_id: &str, key: &str) -> Result<bool, DatabaseError> {
         self.store.delete_setting(user_id, key).await
     }
 
+    #[tracing::instrument(name = "db.list_settings", skip_all)]
     async fn list_settings(&self, user_id: &str) -> Result<Vec<SettingRow>, DatabaseError> {
         self.store.list_settings(user_id).await
     }
 
+    #[tracing::instrument(name = "db.get_all_settings", skip_all)]
     async fn get_all_settings(
         &self,
         user_id: &str,
@@ -493,6 +793,7 @@ impl Database for PgBackend {
         self.store.get_all_settings(user_id).await
     }
 
+    #[tracing::instrument(name = "db.set_all_settings", skip_all)]
     async fn set_all_settings(
         &self,
         user_id: &str,
@@ -501,12 +802,14 @@ impl Database for PgBackend {
         self.store.set_all_settings(user_id, settings).await
     }
 
+    #[tracing::instrument(name = "db.has_settings", skip_all)]
     async fn has_settings(&self, user_id: &str) -> Result<bool, DatabaseError> {
         self.store.has_settings(user_id).await
     }
 
     // ==================== Workspace: Documents ====================
 
+    #[tracing::instrument(name = "db.get_document_by_path", skip_all)]
     async fn get_document_by_path(
         &self,
         user_id: &str,
@@ -518,10 +821,24 @@ impl Database for PgBackend {
             .await
     }
 
+    #[tracing::instrument(name = "db.get_documents_by_paths", skip_all)]
+    async fn get_documents_by_paths(
+        &self,
+        user_id: &str,
+        agent_id: Option<Uuid>,
+        paths: &[String],
+    ) -> Result<Vec<MemoryDocument>, WorkspaceError> {
+        self.repo
+            .get_documents_by_paths(user_id, agent_id, paths)
+            .await
+    }
+
+    #[tracing::instrument(name = "db.get_document_by_id", skip_all)]
     async fn get_document_by_id(&self, id: Uuid) -> Result<MemoryDocument, WorkspaceError> {
         self.repo.get_document_by_id(id).await
     }
 
+    #[tracing::instrument(name = "db.get_or_create_document_by_path", skip_all)]
     async fn get_or_create_document_by_path(
         &self,
         user_id: &str,
@@ -533,10 +850,39 @@ impl Database for PgBackend {
             .await
     }
 
+    #[tracing::instrument(name = "db.update_document", skip_all)]
     async fn update_document(&self, id: Uuid, content: &str) -> Result<(), WorkspaceError> {
         self.repo.update_document(id, content).await
     }
 
+    #[tracing::instrument(name = "db.rename_document", skip_all)]
+    async fn rename_document(&self, id: Uuid, path: &str) -> Result<(), WorkspaceError> {
+        self.repo.rename_document(id, path).await
+    }
+
+    #[tracing::instrument(name = "db.rename_directory", skip_all)]
+    async fn rename_directory(
+        &self,
+        user_id: &str,
+        agent_id: Option<Uuid>,
+        from: &str,
+        to: &str,
+    ) -> Result<u64, WorkspaceError> {
+        self.repo
+            .rename_directory(user_id, agent_id, from, to)
+            .await
+    }
+
+    #[tracing::instrument(name = "db.update_document_metadata", skip_all)]
+    async fn update_document_metadata(
+        &self,
+        id: Uuid,
+        metadata: &serde_json::Value,
+    ) -> Result<(), WorkspaceError> {
+        self.repo.update_document_metadata(id, metadata).await
+    }
+
+    #[tracing::instrument(name = "db.delete_document_by_path", skip_all)]
     async fn delete_document_by_path(
         &self,
         user_id: &str,
@@ -548,6 +894,19 @@ impl Database for PgBackend {
             .await
     }
 
+    #[tracing::instrument(name = "db.delete_documents_by_paths", skip_all)]
+    async fn delete_documents_by_paths(
+        &self,
+        user_id: &str,
+        agent_id: Option<Uuid>,
+        paths: &[String],
+    ) -> Result<u64, WorkspaceError> {
+        self.repo
+            .delete_documents_by_paths(user_id, agent_id, paths)
+            .await
+    }
+
+    #[tracing::instrument(name = "db.list_directory", skip_all)]
     async fn list_directory(
         &self,
         user_id: &str,
@@ -557,6 +916,17 @@ impl Database for PgBackend {
         self.repo.list_directory(user_id, agent_id, directory).await
     }
 
+    #[tracing::instrument(name = "db.find_document_path", skip_all)]
+    async fn find_document_path(
+        &self,
+        user_id: &str,
+        agent_id: Option<Uuid>,
+        path: &str,
+    ) -> Result<Option<String>, WorkspaceError> {
+        self.repo.find_document_path(user_id, agent_id, path).await
+    }
+
+    #[tracing::instrument(name = "db.list_all_paths", skip_all)]
     async fn list_all_paths(
         &self,
         user_id: &str,
@@ -565,6 +935,39 @@ impl Database for PgBackend {
         self.repo.list_all_paths(user_id, agent_id).await
     }
 
+    #[tracing::instrument(name = "db.list_paths_page", skip_all)]
+    async fn list_paths_page(
+        &self,
+        user_id: &str,
+        agent_id: Option<Uuid>,
+        after: Option<&str>,
+        limit: usize,
+    ) -> Result<Vec<String>, WorkspaceError> {
+        self.repo
+            .list_paths_page(user_id, agent_id, after, limit)
+            .await
+    }
+
+    #[tracing::instrument(name = "db.list_paths_matching", skip_all)]
+    async fn list_paths_matching(
+        &self,
+        user_id: &str,
+        agent_id: Option<Uuid>,
+        glob: &PathGlob,
+    ) -> Result<Vec<String>, WorkspaceError> {
+        self.repo.list_paths_matching(user_id, agent_id, glob).await
+    }
+
+    #[tracing::instrument(name = "db.document_sizes", skip_all)]
+    async fn document_sizes(
+        &self,
+        user_id: &str,
+        agent_id: Option<Uuid>,
+    ) -> Result<Vec<DocumentSize>, WorkspaceError> {
+        self.repo.document_sizes(user_id, agent_id).await
+    }
+
+    #[tracing::instrument(name = "db.list_documents", skip_all)]
     async fn list_documents(
         &self,
         user_id: &str,
@@ -573,24 +976,49 @@ impl Database for PgBackend {
         self.repo.list_documents(user_id, agent_id).await
     }
 
+    #[tracing::instrument(name = "db.find_documents_by_fields", skip_all)]
+    async fn find_documents_by_fields(
+        &self,
+        user_id: &str,
+        agent_id: Option<Uuid>,
+        filters: &[FieldFilter],
+    ) -> Result<Vec<MemoryDocument>, WorkspaceError> {
+        self.repo
+            .find_documents_by_fields(user_id, agent_id, filters)
+            .await
+    }
+
     // ==================== Workspace: Chunks ====================
 
+    #[tracing::instrument(name = "db.delete_chunks", skip_all)]
     async fn delete_chunks(&self, document_id: Uuid) -> Result<(), WorkspaceError> {
         self.repo.delete_chunks(document_id).await
     }
 
+    #[tracing::instrument(name = "db.insert_chunk", skip_all)]
     async fn insert_chunk(
         &self,
         document_id: Uuid,
         chunk_index: i32,
         content: &str,
         embedding: Option<&[f32]>,
+        language: Language,
     ) -> Result<Uuid, WorkspaceError> {
         self.repo
-            .insert_chunk(document_id, chunk_index, content, embedding)
+            .insert_chunk(document_id, chunk_index, content, embedding, language)
             .await
     }
 
+    #[tracing::instrument(name = "db.insert_chunks", skip_all)]
+    async fn insert_chunks(
+        &self,
+        document_id: Uuid,
+        chunks: Vec<NewChunk>,
+    ) -> Result<Vec<Uuid>, WorkspaceError> {
+        self.repo.insert_chunks(document_id, chunks).await
+    }
+
+    #[tracing::instrument(name = "db.update_chunk_embedding", skip_all)]
     async fn update_chunk_embedding(
         &self,
         chunk_id: Uuid,
@@ -599,29 +1027,226 @@ impl Database for PgBackend {
         self.repo.update_chunk_embedding(chunk_id, embedding).await
     }
 
+    #[tracing::instrument(name = "db.get_chunks_without_embeddings", skip_all)]
     async fn get_chunks_without_embeddings(
         &self,
         user_id: &str,
         agent_id: Option<Uuid>,
+        after: Option<Uuid>,
         limit: usize,
     ) -> Result<Vec<MemoryChunk>, WorkspaceError> {
         self.repo
-            .get_chunks_without_embeddings(user_id, agent_id, limit)
+            .get_chunks_without_embeddings(user_id, agent_id, after, limit)
+            .await
+    }
+
+    #[tracing::instrument(name = "db.get_document_chunks", skip_all)]
+    async fn get_document_chunks(
+        &self,
+        document_id: Uuid,
+    ) -> Result<Vec<MemoryChunk>, WorkspaceError> {
+        self.repo.get_document_chunks(document_id).await
+    }
+
+    #[tracing::instrument(name = "db.chunk_language_counts", skip_all)]
+    async fn chunk_language_counts(
+        &self,
+        user_id: &str,
+    ) -> Result<Vec<(Language, u64)>, WorkspaceError> {
+        self.repo.chunk_language_counts(user_id).await
+    }
+
+    // ==================== Workspace: Chunk Importance ====================
+
+    #[tracing::instrument(name = "db.set_chunk_importance", skip_all)]
+    async fn set_chunk_importance(
+        &self,
+        document_id: Uuid,
+        content_hash: &str,
+        importance: f32,
+    ) -> Result<(), WorkspaceError> {
+        self.repo
+            .set_chunk_importance(document_id, content_hash, importance)
+            .await
+    }
+
+    #[tracing::instrument(name = "db.prune_chunk_signals", skip_all)]
+    async fn prune_chunk_signals(
+        &self,
+        document_id: Uuid,
+        keep: &[String],
+    ) -> Result<(), WorkspaceError> {
+        self.repo.prune_chunk_signals(document_id, keep).await
+    }
+
+    #[tracing::instrument(name = "db.record_chunk_retrieval", skip_all)]
+    async fn record_chunk_retrieval(
+        &self,
+        document_id: Uuid,
+        content_hash: &str,
+    ) -> Result<(), WorkspaceError> {
+        self.repo
+            .record_chunk_retrieval(document_id, content_hash)
             .await
     }
 
+    #[tracing::instrument(name = "db.get_chunk_signals", skip_all)]
+    async fn get_chunk_signals(
+        &self,
+        document_ids: &[Uuid],
+    ) -> Result<Vec<ChunkSignals>, WorkspaceError> {
+        self.repo.get_chunk_signals(document_ids).await
+    }
+
+    // ==================== Workspace: Pins ====================
+
+    #[tracing::instrument(name = "db.create_memory_pin", skip_all)]
+    async fn create_memory_pin(
+        &self,
+        user_id: &str,
+        agent_id: Option<Uuid>,
+        pin: &MemoryPin,
+    ) -> Result<(), WorkspaceError> {
+        self.repo.create_memory_pin(user_id, agent_id, pin).await
+    }
+
+    #[tracing::instrument(name = "db.list_memory_pins", skip_all)]
+    async fn list_memory_pins(
+        &self,
+        user_id: &str,
+        agent_id: Option<Uuid>,
+    ) -> Result<Vec<MemoryPin>, WorkspaceError> {
+        self.repo.list_memory_pins(user_id, agent_id).await
+    }
+
+    #[tracing::instrument(name = "db.delete_memory_pin", skip_all)]
+    async fn delete_memory_pin(
+        &self,
+        user_id: &str,
+        agent_id: Option<Uuid>,
+        id: Uuid,
+    ) -> Result<bool, WorkspaceError> {
+        self.repo.delete_memory_pin(user_id, agent_id, id).await
+    }
+
+    // ==================== Workspace: Directories ====================
+
+    #[tracing::instrument(name = "db.create_directory", skip_all)]
+    async fn create_directory(
+        &self,
+        user_id: &str,
+        agent_id: Option<Uuid>,
+        path: &str,
+    ) -> Result<bool, WorkspaceError> {
+        self.repo.create_directory(user_id, agent_id, path).await
+    }
+
+    #[tracing::instrument(name = "db.list_directories", skip_all)]
+    async fn list_directories(
+        &self,
+        user_id: &str,
+        agent_id: Option<Uuid>,
+        prefix: &str,
+    ) -> Result<Vec<DirectoryRecord>, WorkspaceError> {
+        self.repo.list_directories(user_id, agent_id, prefix).await
+    }
+
+    #[tracing::instrument(name = "db.update_directory_metadata", skip_all)]
+    async fn update_directory_metadata(
+        &self,
+        user_id: &str,
+        agent_id: Option<Uuid>,
+        path: &str,
+        metadata: &serde_json::Value,
+    ) -> Result<bool, WorkspaceError> {
+        self.repo
+            .update_directory_metadata(user_id, agent_id, path, metadata)
+            .await
+    }
+
+    #[tracing::instrument(name = "db.delete_directories", skip_all)]
+    async fn delete_directories(
+        &self,
+        user_id: &str,
+        agent_id: Option<Uuid>,
+        path: &str,
+    ) -> Result<u64, WorkspaceError> {
+        self.repo.delete_directories(user_id, agent_id, path).await
+    }
+
+    // ==================== Workspace: Versions ====================
+
+    #[tracing::instrument(name = "db.insert_document_version", skip_all)]
+    async fn insert_document_version(
+        &self,
+        document_id: Uuid,
+        content: &str,
+        saved_at: DateTime<Utc>,
+    ) -> Result<i32, WorkspaceError> {
+        self.repo
+            .insert_document_version(document_id, content, saved_at)
+            .await
+    }
+
+    #[tracing::instrument(name = "db.prune_document_versions", skip_all)]
+    async fn prune_document_versions(
+        &self,
+        document_id: Uuid,
+        keep: usize,
+    ) -> Result<(), WorkspaceError> {
+        self.repo.prune_document_versions(document_id, keep).await
+    }
+
+    #[tracing::instrument(name = "db.list_document_versions", skip_all)]
+    async fn list_document_versions(
+        &self,
+        document_id: Uuid,
+    ) -> Result<Vec<VersionInfo>, WorkspaceError> {
+        self.repo.list_document_versions(document_id).await
+    }
+
+    #[tracing::instrument(name = "db.get_document_version", skip_all)]
+    async fn get_document_version(
+        &self,
+        document_id: Uuid,
+        version: i32,
+    ) -> Result<Option<DocumentVersion>, WorkspaceError> {
+        self.repo.get_document_version(document_id, version).await
+    }
+
     // ==================== Workspace: Search ====================
 
+    #[tracing::instrument(name = "db.set_document_links", skip_all)]
+    async fn set_document_links(
+        &self,
+        document_id: Uuid,
+        targets: &[String],
+    ) -> Result<(), WorkspaceError> {
+        self.repo.set_document_links(document_id, targets).await
+    }
+
+    #[tracing::instrument(name = "db.find_backlinks", skip_all)]
+    async fn find_backlinks(
+        &self,
+        user_id: &str,
+        agent_id: Option<Uuid>,
+        key: &str,
+    ) -> Result<Vec<Backlink>, WorkspaceError> {
+        self.repo.find_backlinks(user_id, agent_id, key).await
+    }
+
+    #[tracing::instrument(name = "db.hybrid_search", skip_all)]
     async fn hybrid_search(
         &self,
         user_id: &str,
         agent_id: Option<Uuid>,
         query: &str,
         embedding: Option<&[f32]>,
+        sparse: Option<&SparseVector>,
         config: &SearchConfig,
     ) -> Result<Vec<SearchResult>, WorkspaceError> {
         self.repo
-            .hybrid_search(user_id, agent_id, query, embedding, config)
+            .hybrid_search(user_id, agent_id, query, embedding, sparse, config)
             .await
     }
 }
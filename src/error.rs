@@ -1,4 +1,9 @@
 //! Error types for SiloClaw.
+//!
+//! Every error has an [`ErrorCategory`] (transient, config, permission,
+//! budget, input, internal). Errors shown to users on a channel go through
+//! [`Error::user_message`], which renders the category, what happened, and
+//! what to do about it instead of the raw error chain.
 
 use std::time::Duration;
 
@@ -62,6 +67,12 @@ pub enum ConfigError {
     #[error("Failed to parse configuration: {0}")]
     ParseError(String),
 
+    #[error("Invalid config file {}:\n  - {}", .path.display(), .errors.join("\n  - "))]
+    InvalidFile {
+        path: std::path::PathBuf,
+        errors: Vec<String>,
+    },
+
     #[error("IO error: {0}")]
     Io(#[from] std::io::Error),
 }
@@ -138,7 +149,7 @@ pub enum LlmError {
     #[error("Provider {provider} request failed: {reason}")]
     RequestFailed { provider: String, reason: String },
 
-    #[error("Provider {provider} rate limited, retry after {retry_after:?}")]
+    #[error("Provider {provider} rate limited{}", retry_suffix(retry_after))]
     RateLimited {
         provider: String,
         retry_after: Option<Duration>,
@@ -196,6 +207,12 @@ pub enum ToolError {
     #[error("Tool {name} requires authentication")]
     AuthRequired { name: String },
 
+    #[error("Tool {name} is not permitted for user {user_id}")]
+    PermissionDenied { name: String, user_id: String },
+
+    #[error("Tool {name} would leak private data: {reasons}")]
+    PrivateEgress { name: String, reasons: String },
+
     #[error("Tool builder failed: {0}")]
     BuilderFailed(String),
 }
@@ -241,6 +258,12 @@ pub enum JobError {
     #[error("Maximum parallel jobs ({max}) exceeded")]
     MaxJobsExceeded { max: usize },
 
+    #[error("{0}")]
+    RateLimited(#[from] crate::agent::limits::LimitExceeded),
+
+    #[error("Not accepting new jobs: shutting down")]
+    ShuttingDown,
+
     #[error("Job {id} context error: {reason}")]
     ContextError { id: Uuid, reason: String },
 }
@@ -319,6 +342,52 @@ pub enum WorkspaceError {
 
     #[error("Heartbeat error: {reason}")]
     HeartbeatError { reason: String },
+
+    #[error("Access denied: {user_id} may not access {path}")]
+    AccessDenied { path: String, user_id: String },
+
+    #[error("Read-only: {path} is in shared namespace '{namespace}'")]
+    ReadOnly { path: String, namespace: String },
+
+    #[error("No section '{heading}' in {path}")]
+    SectionNotFound { path: String, heading: String },
+
+    #[error("Import failed at {path}: {reason}")]
+    ImportFailed { path: String, reason: String },
+
+    #[error("Export failed: {reason}")]
+    ExportFailed { reason: String },
+
+    #[error("Git storage failed: {reason}")]
+    GitFailed { reason: String },
+
+    #[error("Invalid path '{path}': {reason}")]
+    InvalidPath { path: String, reason: String },
+
+    #[error("Invalid tag '{tag}': use letters, digits, '-', '_', '/', or '.'")]
+    InvalidTag { tag: String },
+
+    #[error("Directory not empty: {path}")]
+    DirectoryNotEmpty { path: String },
+
+    #[error("No persona named '{name}'")]
+    PersonaNotFound { name: String },
+
+    #[error("No version {version} of {path}")]
+    VersionNotFound { path: String, version: i32 },
+
+    #[error("No revision of {path} from as early as {at} is kept")]
+    HistoryTooShort { path: String, at: String },
+
+    #[error("{path} would be {bytes} bytes, over the {limit}-byte document limit")]
+    DocumentTooLarge {
+        path: String,
+        bytes: u64,
+        limit: u64,
+    },
+
+    #[error("Workspace would hold {bytes} bytes, over its {limit}-byte quota")]
+    QuotaExceeded { bytes: u64, limit: u64 },
 }
 
 /// Orchestrator errors (internal API, container management).
@@ -368,5 +437,418 @@ pub enum WorkerError {
     MissingToken,
 }
 
+/// ", retry after 30s" for a known wait, nothing otherwise.
+fn retry_suffix(retry_after: &Option<Duration>) -> String {
+    retry_after
+        .map(|wait| format!(", retry after {}s", wait.as_secs()))
+        .unwrap_or_default()
+}
+
+// ==================== Categories and user-facing rendering ====================
+
+/// Broad kind of failure, deciding how an error is explained to the user.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorCategory {
+    /// Likely to succeed if retried: timeouts, rate limits, network and
+    /// provider hiccups.
+    Transient,
+    /// Something is misconfigured or missing: credentials, models, setup.
+    Config,
+    /// Refused by policy, permissions, or a safety check.
+    Permission,
+    /// Over a limit: rate limits per user, job slots, context length.
+    Budget,
+    /// The request itself can't be carried out as asked.
+    Input,
+    /// A bug or unexpected failure on the agent's side.
+    Internal,
+}
+
+impl ErrorCategory {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Transient => "transient",
+            Self::Config => "config",
+            Self::Permission => "permission",
+            Self::Budget => "budget",
+            Self::Input => "input",
+            Self::Internal => "internal",
+        }
+    }
+
+    /// Whether retrying the same request may succeed.
+    pub fn is_retryable(&self) -> bool {
+        matches!(self, Self::Transient)
+    }
+
+    /// Opening of the message shown to the user.
+    fn headline(&self) -> &'static str {
+        match self {
+            Self::Transient => "Temporary problem",
+            Self::Config => "Configuration problem",
+            Self::Permission => "Not allowed",
+            Self::Budget => "Limit reached",
+            Self::Input => "Couldn't do that",
+            Self::Internal => "Something went wrong",
+        }
+    }
+
+    /// What to do next, when the error has nothing more specific.
+    fn default_hint(&self) -> Option<&'static str> {
+        match self {
+            Self::Transient => Some("Please try again in a moment."),
+            Self::Config => Some("`ironclaw doctor` can help find what's missing."),
+            Self::Internal => Some("If this keeps happening, the logs have the details."),
+            Self::Permission | Self::Budget | Self::Input => None,
+        }
+    }
+}
+
+impl std::fmt::Display for ErrorCategory {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// An error as shown to a user on a channel: its category, what happened,
+/// and what to do about it. `Display` renders the message.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+pub struct UserFacingError {
+    pub category: ErrorCategory,
+    /// What happened, from the error's own message.
+    pub detail: String,
+    /// What to do about it, if anything specific.
+    pub hint: Option<String>,
+}
+
+impl UserFacingError {
+    pub fn new(category: ErrorCategory, detail: impl Into<String>) -> Self {
+        Self {
+            category,
+            detail: detail.into(),
+            hint: None,
+        }
+    }
+
+    pub fn with_hint(mut self, hint: impl Into<String>) -> Self {
+        self.hint = Some(hint.into());
+        self
+    }
+}
+
+impl std::fmt::Display for UserFacingError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.category.headline(), self.detail)?;
+        let hint = self.hint.as_deref().or(self.category.default_hint());
+        if let Some(hint) = hint {
+            write!(f, "\n{}", hint)?;
+        }
+        Ok(())
+    }
+}
+
+impl Error {
+    pub fn category(&self) -> ErrorCategory {
+        match self {
+            Self::Config(_) => ErrorCategory::Config,
+            Self::Database(e) => e.category(),
+            Self::Channel(e) => e.category(),
+            Self::Llm(e) => e.category(),
+            Self::Tool(e) => e.category(),
+            Self::Safety(e) => e.category(),
+            Self::Job(e) => e.category(),
+            Self::Workspace(e) => e.category(),
+            Self::Orchestrator(e) => e.category(),
+            Self::Worker(e) => e.category(),
+            Self::Estimation(_) | Self::Evaluation(_) | Self::Repair(_) => ErrorCategory::Internal,
+        }
+    }
+
+    /// The error as shown to a user, without the wrapper's prefix.
+    pub fn to_user_facing(&self) -> UserFacingError {
+        let (detail, hint) = match self {
+            Self::Config(e) => (e.to_string(), None),
+            Self::Database(e) => (e.to_string(), None),
+            Self::Channel(e) => (e.to_string(), None),
+            Self::Llm(e) => (e.to_string(), e.hint()),
+            Self::Tool(e) => (e.to_string(), e.hint()),
+            Self::Safety(e) => (e.to_string(), None),
+            Self::Job(e) => (e.to_string(), e.hint()),
+            Self::Workspace(e) => (e.to_string(), None),
+            Self::Orchestrator(e) => (e.to_string(), None),
+            Self::Worker(e) => (e.to_string(), None),
+            Self::Estimation(e) => (e.to_string(), None),
+            Self::Evaluation(e) => (e.to_string(), None),
+            Self::Repair(e) => (e.to_string(), None),
+        };
+        UserFacingError {
+            category: self.category(),
+            detail,
+            hint,
+        }
+    }
+
+    /// Message for the user explaining the error.
+    pub fn user_message(&self) -> String {
+        self.to_user_facing().to_string()
+    }
+}
+
+impl DatabaseError {
+    pub fn category(&self) -> ErrorCategory {
+        match self {
+            Self::NotFound { .. } | Self::Constraint(_) => ErrorCategory::Input,
+            Self::Migration(_) => ErrorCategory::Config,
+            Self::Query(_) | Self::Serialization(_) => ErrorCategory::Internal,
+            Self::Pool(_) => ErrorCategory::Transient,
+            #[cfg(feature = "postgres")]
+            Self::Postgres(_) | Self::PoolRuntime(_) => ErrorCategory::Transient,
+            #[cfg(feature = "postgres")]
+            Self::PoolBuild(_) => ErrorCategory::Config,
+            #[cfg(feature = "libsql")]
+            Self::LibSql(_) => ErrorCategory::Transient,
+        }
+    }
+}
+
+impl ChannelError {
+    pub fn category(&self) -> ErrorCategory {
+        match self {
+            Self::StartupFailed { .. } | Self::AuthFailed { .. } => ErrorCategory::Config,
+            Self::InvalidMessage(_) => ErrorCategory::Input,
+            Self::Disconnected { .. }
+            | Self::SendFailed { .. }
+            | Self::RateLimited { .. }
+            | Self::Http(_)
+            | Self::HealthCheckFailed { .. } => ErrorCategory::Transient,
+        }
+    }
+}
+
+impl LlmError {
+    pub fn category(&self) -> ErrorCategory {
+        match self {
+            Self::RequestFailed { .. }
+            | Self::RateLimited { .. }
+            | Self::InvalidResponse { .. }
+            | Self::Http(_)
+            | Self::Json(_) => ErrorCategory::Transient,
+            Self::ContextLengthExceeded { .. } => ErrorCategory::Budget,
+            Self::ModelNotAvailable { .. }
+            | Self::AuthFailed { .. }
+            | Self::SessionExpired { .. }
+            | Self::SessionRenewalFailed { .. } => ErrorCategory::Config,
+            Self::Io(_) => ErrorCategory::Internal,
+        }
+    }
+
+    /// What the user can do about the error.
+    pub fn hint(&self) -> Option<String> {
+        match self {
+            Self::RateLimited {
+                retry_after: Some(wait),
+                ..
+            } => Some(format!(
+                "Please try again in about {} seconds.",
+                wait.as_secs().max(1)
+            )),
+            Self::ContextLengthExceeded { .. } => Some(
+                "Use /compact to shorten this conversation, or /new to start a fresh thread."
+                    .to_string(),
+            ),
+            Self::ModelNotAvailable { .. } => Some("Pick another model with /model.".to_string()),
+            Self::AuthFailed { .. }
+            | Self::SessionExpired { .. }
+            | Self::SessionRenewalFailed { .. } => Some(
+                "Check the provider's API key, or run `ironclaw onboard` to sign in again."
+                    .to_string(),
+            ),
+            _ => None,
+        }
+    }
+}
+
+impl ToolError {
+    pub fn category(&self) -> ErrorCategory {
+        match self {
+            Self::NotFound { .. } | Self::InvalidParameters { .. } => ErrorCategory::Input,
+            Self::Timeout { .. } => ErrorCategory::Transient,
+            Self::Disabled { .. } | Self::AuthRequired { .. } => ErrorCategory::Config,
+            Self::PermissionDenied { .. } | Self::PrivateEgress { .. } => ErrorCategory::Permission,
+            Self::ExecutionFailed { .. } | Self::Sandbox { .. } | Self::BuilderFailed(_) => {
+                ErrorCategory::Internal
+            }
+        }
+    }
+
+    /// What the user can do about the error.
+    pub fn hint(&self) -> Option<String> {
+        match self {
+            Self::AuthRequired { name } => Some(format!(
+                "Set up its credentials with `ironclaw tool auth {}`.",
+                name
+            )),
+            _ => None,
+        }
+    }
+}
+
+impl SafetyError {
+    pub fn category(&self) -> ErrorCategory {
+        match self {
+            Self::OutputTooLarge { .. } => ErrorCategory::Budget,
+            Self::ValidationFailed { .. } => ErrorCategory::Input,
+            Self::InjectionDetected { .. }
+            | Self::BlockedContent { .. }
+            | Self::PolicyViolation { .. } => ErrorCategory::Permission,
+        }
+    }
+}
+
+impl JobError {
+    pub fn category(&self) -> ErrorCategory {
+        match self {
+            Self::NotFound { .. } | Self::InvalidTransition { .. } => ErrorCategory::Input,
+            Self::MaxJobsExceeded { .. } | Self::RateLimited(_) => ErrorCategory::Budget,
+            Self::Stuck { .. } | Self::ShuttingDown => ErrorCategory::Transient,
+            Self::Failed { .. } | Self::ContextError { .. } => ErrorCategory::Internal,
+        }
+    }
+
+    /// What the user can do about the error.
+    pub fn hint(&self) -> Option<String> {
+        match self {
+            Self::MaxJobsExceeded { .. } => {
+                Some("Wait for a running job to finish, or cancel one.".to_string())
+            }
+            Self::ShuttingDown => {
+                Some("The agent is restarting; try again once it is back.".to_string())
+            }
+            _ => None,
+        }
+    }
+}
+
+impl WorkspaceError {
+    pub fn category(&self) -> ErrorCategory {
+        match self {
+            Self::DocumentNotFound { .. }
+            | Self::SectionNotFound { .. }
+            | Self::InvalidDocType { .. }
+            | Self::InvalidPath { .. }
+            | Self::InvalidTag { .. }
+            | Self::DirectoryNotEmpty { .. }
+            | Self::ImportFailed { .. }
+            | Self::PersonaNotFound { .. }
+            | Self::VersionNotFound { .. }
+            | Self::HistoryTooShort { .. }
+            | Self::DocumentTooLarge { .. } => ErrorCategory::Input,
+            Self::AccessDenied { .. } | Self::ReadOnly { .. } => ErrorCategory::Permission,
+            Self::QuotaExceeded { .. } => ErrorCategory::Budget,
+            Self::SearchFailed { .. } | Self::EmbeddingFailed { .. } => ErrorCategory::Transient,
+            Self::NotInitialized { .. } => ErrorCategory::Config,
+            Self::ChunkingFailed { .. }
+            | Self::HeartbeatError { .. }
+            | Self::ExportFailed { .. }
+            | Self::GitFailed { .. } => ErrorCategory::Internal,
+        }
+    }
+}
+
+impl OrchestratorError {
+    pub fn category(&self) -> ErrorCategory {
+        match self {
+            Self::AuthFailed { .. } | Self::Docker { .. } => ErrorCategory::Config,
+            Self::ContainerTimeout { .. } => ErrorCategory::Transient,
+            Self::ContainerCreationFailed { .. }
+            | Self::ContainerNotFound { .. }
+            | Self::InvalidContainerState { .. }
+            | Self::ApiError { .. } => ErrorCategory::Internal,
+        }
+    }
+}
+
+impl WorkerError {
+    pub fn category(&self) -> ErrorCategory {
+        match self {
+            Self::ConnectionFailed { .. } | Self::LlmProxyFailed { .. } => ErrorCategory::Transient,
+            Self::MissingToken => ErrorCategory::Config,
+            Self::SecretResolveFailed { .. } => ErrorCategory::Config,
+            Self::OrchestratorRejected { .. } | Self::ExecutionFailed { .. } => {
+                ErrorCategory::Internal
+            }
+        }
+    }
+}
+
 /// Result type alias for the agent.
 pub type Result<T> = std::result::Result<T, Error>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_categories() {
+        let rate_limited = Error::from(LlmError::RateLimited {
+            provider: "openai".to_string(),
+            retry_after: None,
+        });
+        assert_eq!(rate_limited.category(), ErrorCategory::Transient);
+        assert!(rate_limited.category().is_retryable());
+
+        let auth = Error::from(LlmError::AuthFailed {
+            provider: "openai".to_string(),
+        });
+        assert_eq!(auth.category(), ErrorCategory::Config);
+
+        let denied = Error::from(WorkspaceError::AccessDenied {
+            path: "secrets.md".to_string(),
+            user_id: "bob".to_string(),
+        });
+        assert_eq!(denied.category(), ErrorCategory::Permission);
+
+        let full = Error::from(JobError::MaxJobsExceeded { max: 3 });
+        assert_eq!(full.category(), ErrorCategory::Budget);
+    }
+
+    #[test]
+    fn test_user_message_has_headline_detail_and_hint() {
+        let error = Error::from(LlmError::RateLimited {
+            provider: "openai".to_string(),
+            retry_after: Some(Duration::from_secs(30)),
+        });
+        let message = error.user_message();
+        assert!(message.starts_with("Temporary problem: Provider openai rate limited"));
+        assert!(message.ends_with("Please try again in about 30 seconds."));
+        // The wrapper's "LLM error:" prefix is not shown.
+        assert!(!message.contains("LLM error"));
+
+        let error = Error::from(LlmError::ContextLengthExceeded {
+            used: 200_000,
+            limit: 128_000,
+        });
+        assert!(error.user_message().contains("/compact"));
+    }
+
+    #[test]
+    fn test_default_hints() {
+        let error = Error::from(ToolError::PermissionDenied {
+            name: "shell".to_string(),
+            user_id: "bob".to_string(),
+        });
+        assert_eq!(
+            error.user_message(),
+            "Not allowed: Tool shell is not permitted for user bob"
+        );
+
+        let internal = UserFacingError::new(ErrorCategory::Internal, "boom");
+        assert_eq!(
+            internal.to_string(),
+            "Something went wrong: boom\nIf this keeps happening, the logs have the details."
+        );
+        let hinted = UserFacingError::new(ErrorCategory::Transient, "timeout").with_hint("Wait.");
+        assert_eq!(hinted.to_string(), "Temporary problem: timeout\nWait.");
+    }
+}
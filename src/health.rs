@@ -0,0 +1,321 @@
+//! Health and readiness checks.
+//!
+//! [`HealthChecker`] probes each dependency the agent needs and reports a
+//! status per component:
+//!
+//! | Component | Probe | Required for readiness |
+//! |-----------|-------|------------------------|
+//! | `database` | `SELECT 1` | yes |
+//! | `llm` | list models (exercises provider auth) | yes |
+//! | `embeddings` | embed a short string | no |
+//! | `channel:<name>` | [`Channel::health_check`] | no |
+//!
+//! A failed optional component degrades health but doesn't make the agent
+//! unready. Results are cached briefly so frequent orchestrator probes don't
+//! turn into a stream of embedding requests.
+//!
+//! [`Channel::health_check`]: crate::channels::Channel::health_check
+
+use std::fmt::Display;
+use std::future::Future;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+use tokio::sync::Mutex;
+
+use crate::channels::ChannelHealthProbe;
+use crate::db::Database;
+use crate::llm::LlmProvider;
+use crate::workspace::EmbeddingProvider;
+
+/// Per-component probe timeout.
+pub const CHECK_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// How long a report is reused before dependencies are probed again.
+const CACHE_TTL: Duration = Duration::from_secs(10);
+
+/// Overall health.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HealthStatus {
+    /// Every component is up.
+    Healthy,
+    /// Required components are up; an optional one is not.
+    Degraded,
+    /// A required component is down.
+    Unhealthy,
+}
+
+impl HealthStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Healthy => "healthy",
+            Self::Degraded => "degraded",
+            Self::Unhealthy => "unhealthy",
+        }
+    }
+}
+
+/// Result of probing one component.
+#[derive(Debug, Clone, Serialize)]
+pub struct ComponentHealth {
+    pub name: String,
+    pub healthy: bool,
+    /// Whether readiness depends on this component.
+    pub required: bool,
+    pub latency_ms: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Health of every configured component.
+#[derive(Debug, Clone, Serialize)]
+pub struct HealthReport {
+    pub status: HealthStatus,
+    /// True when every required component is healthy.
+    pub ready: bool,
+    pub components: Vec<ComponentHealth>,
+}
+
+impl HealthReport {
+    fn from_components(components: Vec<ComponentHealth>) -> Self {
+        let ready = components.iter().all(|c| c.healthy || !c.required);
+        let status = if !ready {
+            HealthStatus::Unhealthy
+        } else if components.iter().any(|c| !c.healthy) {
+            HealthStatus::Degraded
+        } else {
+            HealthStatus::Healthy
+        };
+        Self {
+            status,
+            ready,
+            components,
+        }
+    }
+}
+
+/// Probes the agent's dependencies. Components that aren't configured are
+/// left out of the report.
+pub struct HealthChecker {
+    db: Option<Arc<dyn Database>>,
+    llm: Option<Arc<dyn LlmProvider>>,
+    embeddings: Option<Arc<dyn EmbeddingProvider>>,
+    channels: Option<ChannelHealthProbe>,
+    cache: Mutex<Option<(Instant, HealthReport)>>,
+}
+
+impl HealthChecker {
+    pub fn new() -> Self {
+        Self {
+            db: None,
+            llm: None,
+            embeddings: None,
+            channels: None,
+            cache: Mutex::new(None),
+        }
+    }
+
+    pub fn with_database(mut self, db: Arc<dyn Database>) -> Self {
+        self.db = Some(db);
+        self
+    }
+
+    pub fn with_llm(mut self, llm: Arc<dyn LlmProvider>) -> Self {
+        self.llm = Some(llm);
+        self
+    }
+
+    pub fn with_embeddings(mut self, embeddings: Arc<dyn EmbeddingProvider>) -> Self {
+        self.embeddings = Some(embeddings);
+        self
+    }
+
+    pub fn with_channels(mut self, channels: ChannelHealthProbe) -> Self {
+        self.channels = Some(channels);
+        self
+    }
+
+    /// Probe every component (or reuse a report from the last few seconds).
+    pub async fn health(&self) -> HealthReport {
+        // Held across the probes so concurrent callers share one round.
+        let mut cache = self.cache.lock().await;
+        if let Some((at, ref report)) = *cache
+            && at.elapsed() < CACHE_TTL
+        {
+            return report.clone();
+        }
+
+        let report = HealthReport::from_components(self.check_all().await);
+        for c in report.components.iter().filter(|c| !c.healthy) {
+            tracing::warn!(
+                component = %c.name,
+                error = c.error.as_deref().unwrap_or(""),
+                "Health check failed"
+            );
+        }
+        *cache = Some((Instant::now(), report.clone()));
+        report
+    }
+
+    /// Whether every required component is healthy.
+    pub async fn ready(&self) -> bool {
+        self.health().await.ready
+    }
+
+    async fn check_all(&self) -> Vec<ComponentHealth> {
+        let db = async {
+            match self.db {
+                Some(ref db) => Some(probe("database", true, db.ping()).await),
+                None => None,
+            }
+        };
+        let llm = async {
+            match self.llm {
+                Some(ref llm) => {
+                    Some(probe("llm", true, async { llm.list_models().await.map(|_| ()) }).await)
+                }
+                None => None,
+            }
+        };
+        let embeddings = async {
+            match self.embeddings {
+                Some(ref emb) => Some(
+                    probe("embeddings", false, async {
+                        emb.embed("health check").await.map(|_| ())
+                    })
+                    .await,
+                ),
+                None => None,
+            }
+        };
+        let channels = async {
+            match self.channels {
+                Some(ref channels) => check_channels(channels).await,
+                None => Vec::new(),
+            }
+        };
+
+        let (db, llm, embeddings, channels) = tokio::join!(db, llm, embeddings, channels);
+        [db, llm, embeddings]
+            .into_iter()
+            .flatten()
+            .chain(channels)
+            .collect()
+    }
+}
+
+impl Default for HealthChecker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+async fn probe<E: Display>(
+    name: &str,
+    required: bool,
+    check: impl Future<Output = Result<(), E>>,
+) -> ComponentHealth {
+    let start = Instant::now();
+    let error = match tokio::time::timeout(CHECK_TIMEOUT, check).await {
+        Ok(Ok(())) => None,
+        Ok(Err(e)) => Some(e.to_string()),
+        Err(_) => Some(format!("timed out after {}s", CHECK_TIMEOUT.as_secs())),
+    };
+    ComponentHealth {
+        name: name.to_string(),
+        healthy: error.is_none(),
+        required,
+        latency_ms: start.elapsed().as_millis() as u64,
+        error,
+    }
+}
+
+async fn check_channels(channels: &ChannelHealthProbe) -> Vec<ComponentHealth> {
+    let start = Instant::now();
+    let results = match tokio::time::timeout(CHECK_TIMEOUT, channels.check_all()).await {
+        Ok(results) => results,
+        Err(_) => {
+            return vec![ComponentHealth {
+                name: "channels".to_string(),
+                healthy: false,
+                required: false,
+                latency_ms: start.elapsed().as_millis() as u64,
+                error: Some(format!("timed out after {}s", CHECK_TIMEOUT.as_secs())),
+            }];
+        }
+    };
+    let latency_ms = start.elapsed().as_millis() as u64;
+
+    let mut components: Vec<ComponentHealth> = results
+        .into_iter()
+        .map(|(name, result)| ComponentHealth {
+            name: format!("channel:{}", name),
+            healthy: result.is_ok(),
+            required: false,
+            latency_ms,
+            error: result.err().map(|e| e.to_string()),
+        })
+        .collect();
+    components.sort_by(|a, b| a.name.cmp(&b.name));
+    components
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn component(name: &str, healthy: bool, required: bool) -> ComponentHealth {
+        ComponentHealth {
+            name: name.to_string(),
+            healthy,
+            required,
+            latency_ms: 0,
+            error: (!healthy).then(|| "down".to_string()),
+        }
+    }
+
+    #[test]
+    fn test_report_status() {
+        let all_up = HealthReport::from_components(vec![
+            component("database", true, true),
+            component("embeddings", true, false),
+        ]);
+        assert_eq!(all_up.status, HealthStatus::Healthy);
+        assert!(all_up.ready);
+
+        let optional_down = HealthReport::from_components(vec![
+            component("database", true, true),
+            component("embeddings", false, false),
+        ]);
+        assert_eq!(optional_down.status, HealthStatus::Degraded);
+        assert!(optional_down.ready);
+
+        let required_down = HealthReport::from_components(vec![
+            component("database", false, true),
+            component("embeddings", true, false),
+        ]);
+        assert_eq!(required_down.status, HealthStatus::Unhealthy);
+        assert!(!required_down.ready);
+    }
+
+    #[tokio::test]
+    async fn test_probe_reports_errors_and_timeouts() {
+        let ok = probe("a", true, async { Ok::<(), String>(()) }).await;
+        assert!(ok.healthy);
+        assert!(ok.error.is_none());
+
+        let err = probe("b", true, async { Err::<(), _>("connection refused") }).await;
+        assert!(!err.healthy);
+        assert_eq!(err.error.as_deref(), Some("connection refused"));
+    }
+
+    #[tokio::test]
+    async fn test_unconfigured_checker_is_ready() {
+        let report = HealthChecker::new().health().await;
+        assert!(report.ready);
+        assert_eq!(report.status, HealthStatus::Healthy);
+        assert!(report.components.is_empty());
+    }
+}
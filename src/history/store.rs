@@ -28,6 +28,20 @@ pub struct LlmCallRecord<'a> {
     pub purpose: Option<&'a str>,
 }
 
+/// A job's outcome and LLM usage, for usage reports.
+#[derive(Debug, Clone)]
+pub struct JobUsageRecord {
+    pub id: Uuid,
+    pub title: String,
+    pub status: String,
+    pub actual_cost: Decimal,
+    /// Tokens from the job's recorded transcript (0 if not recorded).
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    pub failure_reason: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
 /// Database store for the agent.
 #[cfg(feature = "postgres")]
 pub struct Store {
@@ -78,6 +92,68 @@ impl Store {
         Ok(self.pool.get().await?)
     }
 
+    /// Check that a connection can be acquired and used.
+    pub async fn ping(&self) -> Result<(), DatabaseError> {
+        let conn = self.conn().await?;
+        conn.execute("SELECT 1", &[]).await?;
+        Ok(())
+    }
+
+    /// Inspect schema version and memory index consistency.
+    pub async fn diagnostics(&self) -> Result<crate::db::DbDiagnostics, DatabaseError> {
+        let conn = self.conn().await?;
+        let schema_version: Option<i64> = match conn
+            .query_one(
+                "SELECT MAX(version)::BIGINT AS version FROM refinery_schema_history",
+                &[],
+            )
+            .await
+        {
+            Ok(row) => row.get("version"),
+            // No history table: migrations have never run.
+            Err(_) => None,
+        };
+        // `None` when memory_chunks doesn't exist yet.
+        let dimension: Option<i32> = conn
+            .query_opt(
+                r#"
+                SELECT atttypmod FROM pg_attribute
+                WHERE attrelid = 'memory_chunks'::regclass AND attname = 'embedding'
+                "#,
+                &[],
+            )
+            .await
+            .ok()
+            .flatten()
+            .map(|row| row.get(0));
+        let Some(dimension) = dimension else {
+            return Ok(crate::db::DbDiagnostics {
+                schema_version,
+                ..Default::default()
+            });
+        };
+        let counts = conn
+            .query_one(
+                r#"
+                SELECT
+                    COUNT(*) AS total,
+                    COUNT(*) FILTER (WHERE c.embedding IS NULL) AS missing,
+                    COUNT(*) FILTER (WHERE d.id IS NULL) AS orphaned
+                FROM memory_chunks c
+                LEFT JOIN memory_documents d ON d.id = c.document_id
+                "#,
+                &[],
+            )
+            .await?;
+        Ok(crate::db::DbDiagnostics {
+            schema_version,
+            embedding_dimension: (dimension > 0).then_some(dimension as usize),
+            chunk_count: counts.get("total"),
+            chunks_missing_embeddings: counts.get("missing"),
+            orphaned_chunks: counts.get("orphaned"),
+        })
+    }
+
     /// Get a clone of the database pool.
     ///
     /// Useful for sharing the pool with other components like Workspace.
@@ -153,8 +229,8 @@ impl Store {
             INSERT INTO agent_jobs (
                 id, conversation_id, title, description, category, status, source,
                 budget_amount, budget_token, bid_amount, estimated_cost, estimated_time_secs,
-                actual_cost, repair_attempts, created_at, started_at, completed_at
-            ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17)
+                actual_cost, repair_attempts, created_at, started_at, completed_at, user_id
+            ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17, $18)
             ON CONFLICT (id) DO UPDATE SET
                 title = EXCLUDED.title,
                 description = EXCLUDED.description,
@@ -185,6 +261,7 @@ impl Store {
                 &ctx.created_at,
                 &ctx.started_at,
                 &ctx.completed_at,
+                &ctx.user_id,
             ],
         )
         .await?;
@@ -239,6 +316,7 @@ impl Store {
                     metadata: serde_json::Value::Null,
                     total_tokens_used: 0,
                     max_tokens: 0,
+                    ..Default::default()
                 }))
             }
             None => Ok(None),
@@ -288,6 +366,47 @@ impl Store {
         Ok(rows.iter().map(|r| r.get("id")).collect())
     }
 
+    /// Jobs a user created in `[since, until)`, oldest first, with token
+    /// totals from their transcripts.
+    pub async fn list_job_usage(
+        &self,
+        user_id: &str,
+        since: DateTime<Utc>,
+        until: DateTime<Utc>,
+    ) -> Result<Vec<JobUsageRecord>, DatabaseError> {
+        let conn = self.conn().await?;
+        let rows = conn
+            .query(
+                r#"
+                SELECT j.id, j.title, j.status, j.actual_cost, j.failure_reason, j.created_at,
+                       COALESCE(SUM((t.response->>'input_tokens')::BIGINT), 0)::BIGINT AS input_tokens,
+                       COALESCE(SUM((t.response->>'output_tokens')::BIGINT), 0)::BIGINT AS output_tokens
+                FROM agent_jobs j
+                LEFT JOIN job_transcript_entries t ON t.job_id = j.id
+                WHERE j.user_id = $1 AND j.created_at >= $2 AND j.created_at < $3
+                GROUP BY j.id
+                ORDER BY j.created_at ASC
+                "#,
+                &[&user_id, &since, &until],
+            )
+            .await?;
+        Ok(rows
+            .iter()
+            .map(|r| JobUsageRecord {
+                id: r.get("id"),
+                title: r.get("title"),
+                status: r.get("status"),
+                actual_cost: r
+                    .get::<_, Option<Decimal>>("actual_cost")
+                    .unwrap_or_default(),
+                input_tokens: r.get::<_, i64>("input_tokens").max(0) as u64,
+                output_tokens: r.get::<_, i64>("output_tokens").max(0) as u64,
+                failure_reason: r.get("failure_reason"),
+                created_at: r.get("created_at"),
+            })
+            .collect())
+    }
+
     // ==================== Actions ====================
 
     /// Save a job action.
@@ -945,6 +1064,18 @@ impl Store {
         rows.iter().map(row_to_routine).collect()
     }
 
+    /// List all enabled routines with webhook triggers (for inbound hooks).
+    pub async fn list_webhook_routines(&self) -> Result<Vec<Routine>, DatabaseError> {
+        let conn = self.conn().await?;
+        let rows = conn
+            .query(
+                "SELECT * FROM routines WHERE enabled AND trigger_type = 'webhook'",
+                &[],
+            )
+            .await?;
+        rows.iter().map(row_to_routine).collect()
+    }
+
     /// List all enabled cron routines whose next_fire_at <= now.
     pub async fn list_due_cron_routines(&self) -> Result<Vec<Routine>, DatabaseError> {
         let conn = self.conn().await?;
@@ -1384,6 +1515,53 @@ impl Store {
         Ok(row.is_some())
     }
 
+    /// The most recently active conversation for a user's thread on a channel.
+    pub async fn find_conversation(
+        &self,
+        user_id: &str,
+        channel: &str,
+        thread_id: Option<&str>,
+    ) -> Result<Option<Uuid>, DatabaseError> {
+        let conn = self.conn().await?;
+        let row = conn
+            .query_opt(
+                r#"
+                SELECT id FROM conversations
+                WHERE user_id = $1 AND channel = $2 AND thread_id IS NOT DISTINCT FROM $3
+                ORDER BY last_activity DESC
+                LIMIT 1
+                "#,
+                &[&user_id, &channel, &thread_id],
+            )
+            .await?;
+        Ok(row.map(|r| r.get("id")))
+    }
+
+    /// Delete conversations inactive since before `cutoff`. Jobs and LLM
+    /// call records that pointed at them are kept, unlinked.
+    pub async fn prune_conversations(&self, cutoff: DateTime<Utc>) -> Result<u64, DatabaseError> {
+        let mut conn = self.conn().await?;
+        let tx = conn.transaction().await?;
+        for table in ["agent_jobs", "llm_calls"] {
+            tx.execute(
+                &format!(
+                    "UPDATE {table} SET conversation_id = NULL WHERE conversation_id IN \
+                     (SELECT id FROM conversations WHERE last_activity < $1)"
+                ),
+                &[&cutoff],
+            )
+            .await?;
+        }
+        let count = tx
+            .execute(
+                "DELETE FROM conversations WHERE last_activity < $1",
+                &[&cutoff],
+            )
+            .await?;
+        tx.commit().await?;
+        Ok(count)
+    }
+
     /// Load messages for a conversation with cursor-based pagination.
     ///
     /// Returns `(messages_oldest_first, has_more)`.
@@ -1770,3 +1948,685 @@ impl Store {
         Ok(count > 0)
     }
 }
+
+// ==================== Job Transcripts ====================
+
+#[cfg(feature = "postgres")]
+use crate::agent::transcript::TranscriptEntry;
+
+#[cfg(feature = "postgres")]
+impl Store {
+    /// Append one recorded LLM call to a job's transcript.
+    pub async fn append_transcript_entry(
+        &self,
+        job_id: Uuid,
+        entry: &TranscriptEntry,
+    ) -> Result<(), DatabaseError> {
+        let request = serde_json::to_value(&entry.request)
+            .map_err(|e| DatabaseError::Serialization(e.to_string()))?;
+        let response = serde_json::to_value(&entry.response)
+            .map_err(|e| DatabaseError::Serialization(e.to_string()))?;
+        let conn = self.conn().await?;
+        conn.execute(
+            r#"
+            INSERT INTO job_transcript_entries (job_id, seq, model, request, response, created_at)
+            VALUES ($1, $2, $3, $4, $5, $6)
+            "#,
+            &[
+                &job_id,
+                &entry.seq,
+                &entry.model,
+                &request,
+                &response,
+                &entry.created_at,
+            ],
+        )
+        .await?;
+        Ok(())
+    }
+
+    /// Load a job's transcript, ordered by `seq`.
+    pub async fn get_job_transcript(
+        &self,
+        job_id: Uuid,
+    ) -> Result<Vec<TranscriptEntry>, DatabaseError> {
+        let conn = self.conn().await?;
+        let rows = conn
+            .query(
+                r#"
+                SELECT seq, model, request, response, created_at
+                FROM job_transcript_entries
+                WHERE job_id = $1
+                ORDER BY seq ASC
+                "#,
+                &[&job_id],
+            )
+            .await?;
+        rows.iter()
+            .map(|r| {
+                Ok(TranscriptEntry {
+                    seq: r.get("seq"),
+                    model: r.get("model"),
+                    request: serde_json::from_value(r.get("request"))
+                        .map_err(|e| DatabaseError::Serialization(e.to_string()))?,
+                    response: serde_json::from_value(r.get("response"))
+                        .map_err(|e| DatabaseError::Serialization(e.to_string()))?,
+                    created_at: r.get("created_at"),
+                })
+            })
+            .collect()
+    }
+}
+
+// ==================== Audit Log ====================
+
+#[cfg(feature = "postgres")]
+use crate::audit::{AuditEvent, AuditQuery, AuditRecord};
+
+#[cfg(feature = "postgres")]
+impl Store {
+    /// Append an entry to the audit log.
+    pub async fn append_audit_event(&self, event: &AuditEvent) -> Result<i64, DatabaseError> {
+        let conn = self.conn().await?;
+        let row = conn
+            .query_one(
+                r#"
+                INSERT INTO audit_log (kind, actor, channel, target, outcome, details)
+                VALUES ($1, $2, $3, $4, $5, $6)
+                RETURNING id
+                "#,
+                &[
+                    &event.kind.as_str(),
+                    &event.actor,
+                    &event.channel,
+                    &event.target,
+                    &event.outcome,
+                    &event.details,
+                ],
+            )
+            .await?;
+        Ok(row.get("id"))
+    }
+
+    /// Read audit log entries matching `query`, newest first.
+    pub async fn query_audit_log(
+        &self,
+        query: &AuditQuery,
+    ) -> Result<Vec<AuditRecord>, DatabaseError> {
+        let conn = self.conn().await?;
+        let kind = query.kind.map(|k| k.as_str());
+        let rows = conn
+            .query(
+                r#"
+                SELECT id, kind, actor, channel, target, outcome, details, created_at
+                FROM audit_log
+                WHERE ($1::TEXT IS NULL OR kind = $1)
+                  AND ($2::TEXT IS NULL OR actor = $2)
+                  AND ($3::TIMESTAMPTZ IS NULL OR created_at >= $3)
+                  AND ($4::TIMESTAMPTZ IS NULL OR created_at < $4)
+                  AND ($5::BIGINT IS NULL OR id < $5)
+                ORDER BY id DESC
+                LIMIT $6
+                "#,
+                &[
+                    &kind,
+                    &query.actor,
+                    &query.since,
+                    &query.until,
+                    &query.before_id,
+                    &query.effective_limit(),
+                ],
+            )
+            .await?;
+        rows.iter()
+            .map(|r| {
+                let kind: String = r.get("kind");
+                Ok(AuditRecord {
+                    id: r.get("id"),
+                    kind: kind.parse().map_err(DatabaseError::Serialization)?,
+                    actor: r.get("actor"),
+                    channel: r.get("channel"),
+                    target: r.get("target"),
+                    outcome: r.get("outcome"),
+                    details: r.get("details"),
+                    created_at: r.get("created_at"),
+                })
+            })
+            .collect()
+    }
+
+    /// Delete audit log entries created before `cutoff`.
+    pub async fn prune_audit_log(&self, cutoff: DateTime<Utc>) -> Result<u64, DatabaseError> {
+        let conn = self.conn().await?;
+        let count = conn
+            .execute("DELETE FROM audit_log WHERE created_at < $1", &[&cutoff])
+            .await?;
+        Ok(count)
+    }
+}
+
+// ==================== Agents ====================
+
+#[cfg(feature = "postgres")]
+use crate::agent::registry::AgentDefinition;
+
+#[cfg(feature = "postgres")]
+impl Store {
+    /// Register a new agent.
+    pub async fn create_agent(&self, agent: &AgentDefinition) -> Result<(), DatabaseError> {
+        let (tools, channels, seed) = agent_json_columns(agent)?;
+        let conn = self.conn().await?;
+        conn.execute(
+            r#"
+            INSERT INTO agents (
+                id, name, description, enabled, model,
+                tools, channels, workspace_seed, created_at, updated_at
+            ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
+            "#,
+            &[
+                &agent.id,
+                &agent.name,
+                &agent.description,
+                &agent.enabled,
+                &agent.model,
+                &tools,
+                &channels,
+                &seed,
+                &agent.created_at,
+                &agent.updated_at,
+            ],
+        )
+        .await?;
+        Ok(())
+    }
+
+    /// Get an agent by ID.
+    pub async fn get_agent(&self, id: Uuid) -> Result<Option<AgentDefinition>, DatabaseError> {
+        let conn = self.conn().await?;
+        let row = conn
+            .query_opt("SELECT * FROM agents WHERE id = $1", &[&id])
+            .await?;
+        row.map(|r| row_to_agent(&r)).transpose()
+    }
+
+    /// Get an agent by name.
+    pub async fn get_agent_by_name(
+        &self,
+        name: &str,
+    ) -> Result<Option<AgentDefinition>, DatabaseError> {
+        let conn = self.conn().await?;
+        let row = conn
+            .query_opt("SELECT * FROM agents WHERE name = $1", &[&name])
+            .await?;
+        row.map(|r| row_to_agent(&r)).transpose()
+    }
+
+    /// List all agents, ordered by name.
+    pub async fn list_agents(&self) -> Result<Vec<AgentDefinition>, DatabaseError> {
+        let conn = self.conn().await?;
+        let rows = conn
+            .query("SELECT * FROM agents ORDER BY name", &[])
+            .await?;
+        rows.iter().map(row_to_agent).collect()
+    }
+
+    /// Update an agent's configuration.
+    pub async fn update_agent(&self, agent: &AgentDefinition) -> Result<(), DatabaseError> {
+        let (tools, channels, seed) = agent_json_columns(agent)?;
+        let conn = self.conn().await?;
+        conn.execute(
+            r#"
+            UPDATE agents SET
+                name = $2, description = $3, enabled = $4, model = $5,
+                tools = $6, channels = $7, workspace_seed = $8, updated_at = $9
+            WHERE id = $1
+            "#,
+            &[
+                &agent.id,
+                &agent.name,
+                &agent.description,
+                &agent.enabled,
+                &agent.model,
+                &tools,
+                &channels,
+                &seed,
+                &agent.updated_at,
+            ],
+        )
+        .await?;
+        Ok(())
+    }
+
+    /// Delete an agent.
+    pub async fn delete_agent(&self, id: Uuid) -> Result<bool, DatabaseError> {
+        let conn = self.conn().await?;
+        let count = conn
+            .execute("DELETE FROM agents WHERE id = $1", &[&id])
+            .await?;
+        Ok(count > 0)
+    }
+}
+
+#[cfg(feature = "postgres")]
+fn agent_json_columns(
+    agent: &AgentDefinition,
+) -> Result<(serde_json::Value, serde_json::Value, serde_json::Value), DatabaseError> {
+    let to_json = |v: serde_json::Result<serde_json::Value>| {
+        v.map_err(|e| DatabaseError::Serialization(e.to_string()))
+    };
+    Ok((
+        to_json(serde_json::to_value(&agent.tools))?,
+        to_json(serde_json::to_value(&agent.channels))?,
+        to_json(serde_json::to_value(&agent.workspace_seed))?,
+    ))
+}
+
+#[cfg(feature = "postgres")]
+fn row_to_agent(row: &tokio_postgres::Row) -> Result<AgentDefinition, DatabaseError> {
+    Ok(AgentDefinition {
+        id: row.get("id"),
+        name: row.get("name"),
+        description: row.get("description"),
+        enabled: row.get("enabled"),
+        model: row.get("model"),
+        tools: agent_json_column(row, "tools")?,
+        channels: agent_json_column(row, "channels")?,
+        workspace_seed: agent_json_column(row, "workspace_seed")?,
+        created_at: row.get("created_at"),
+        updated_at: row.get("updated_at"),
+    })
+}
+
+#[cfg(feature = "postgres")]
+fn agent_json_column<T: serde::de::DeserializeOwned>(
+    row: &tokio_postgres::Row,
+    column: &str,
+) -> Result<T, DatabaseError> {
+    serde_json::from_value(row.get(column))
+        .map_err(|e| DatabaseError::Serialization(format!("agents.{}: {}", column, e)))
+}
+
+// ==================== Contacts ====================
+
+#[cfg(feature = "postgres")]
+use crate::contacts::Contact;
+
+#[cfg(feature = "postgres")]
+impl Store {
+    /// Save a new contact.
+    pub async fn create_contact(&self, contact: &Contact) -> Result<(), DatabaseError> {
+        let (emails, handles) = contact_json_columns(contact)?;
+        let conn = self.conn().await?;
+        conn.execute(
+            r#"
+            INSERT INTO contacts (
+                id, user_id, name, relationship, emails, handles, notes,
+                last_seen_at, created_at, updated_at
+            ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
+            "#,
+            &[
+                &contact.id,
+                &contact.user_id,
+                &contact.name,
+                &contact.relationship,
+                &emails,
+                &handles,
+                &contact.notes,
+                &contact.last_seen_at,
+                &contact.created_at,
+                &contact.updated_at,
+            ],
+        )
+        .await?;
+        Ok(())
+    }
+
+    /// Get a contact by ID.
+    pub async fn get_contact(&self, id: Uuid) -> Result<Option<Contact>, DatabaseError> {
+        let conn = self.conn().await?;
+        let row = conn
+            .query_opt("SELECT * FROM contacts WHERE id = $1", &[&id])
+            .await?;
+        row.map(|r| row_to_contact(&r)).transpose()
+    }
+
+    /// List a user's contacts, ordered by name.
+    pub async fn list_contacts(&self, user_id: &str) -> Result<Vec<Contact>, DatabaseError> {
+        let conn = self.conn().await?;
+        let rows = conn
+            .query(
+                "SELECT * FROM contacts WHERE user_id = $1 ORDER BY lower(name)",
+                &[&user_id],
+            )
+            .await?;
+        rows.iter().map(row_to_contact).collect()
+    }
+
+    /// Overwrite a contact's fields.
+    pub async fn update_contact(&self, contact: &Contact) -> Result<(), DatabaseError> {
+        let (emails, handles) = contact_json_columns(contact)?;
+        let conn = self.conn().await?;
+        conn.execute(
+            r#"
+            UPDATE contacts SET
+                name = $2, relationship = $3, emails = $4, handles = $5, notes = $6,
+                last_seen_at = $7, updated_at = $8
+            WHERE id = $1
+            "#,
+            &[
+                &contact.id,
+                &contact.name,
+                &contact.relationship,
+                &emails,
+                &handles,
+                &contact.notes,
+                &contact.last_seen_at,
+                &contact.updated_at,
+            ],
+        )
+        .await?;
+        Ok(())
+    }
+
+    /// Delete a contact.
+    pub async fn delete_contact(&self, id: Uuid) -> Result<bool, DatabaseError> {
+        let conn = self.conn().await?;
+        let count = conn
+            .execute("DELETE FROM contacts WHERE id = $1", &[&id])
+            .await?;
+        Ok(count > 0)
+    }
+}
+
+#[cfg(feature = "postgres")]
+fn contact_json_columns(
+    contact: &Contact,
+) -> Result<(serde_json::Value, serde_json::Value), DatabaseError> {
+    let to_json = |v: serde_json::Result<serde_json::Value>| {
+        v.map_err(|e| DatabaseError::Serialization(e.to_string()))
+    };
+    Ok((
+        to_json(serde_json::to_value(&contact.emails))?,
+        to_json(serde_json::to_value(&contact.handles))?,
+    ))
+}
+
+#[cfg(feature = "postgres")]
+fn row_to_contact(row: &tokio_postgres::Row) -> Result<Contact, DatabaseError> {
+    Ok(Contact {
+        id: row.get("id"),
+        user_id: row.get("user_id"),
+        name: row.get("name"),
+        relationship: row.get("relationship"),
+        emails: contact_json_column(row, "emails")?,
+        handles: contact_json_column(row, "handles")?,
+        notes: row.get("notes"),
+        last_seen_at: row.get("last_seen_at"),
+        created_at: row.get("created_at"),
+        updated_at: row.get("updated_at"),
+    })
+}
+
+#[cfg(feature = "postgres")]
+fn contact_json_column<T: serde::de::DeserializeOwned>(
+    row: &tokio_postgres::Row,
+    column: &str,
+) -> Result<T, DatabaseError> {
+    serde_json::from_value(row.get(column))
+        .map_err(|e| DatabaseError::Serialization(format!("contacts.{}: {}", column, e)))
+}
+
+// ==================== Leases ====================
+
+#[cfg(feature = "postgres")]
+use crate::db::lease::LeaseRecord;
+
+/// Lease expiry is computed from the database clock, so instances with
+/// skewed clocks still agree on when a lease has expired.
+#[cfg(feature = "postgres")]
+impl Store {
+    /// Take a lease that is free, expired, or already held by `owner`.
+    pub async fn try_acquire_lease(
+        &self,
+        name: &str,
+        owner: &str,
+        ttl: std::time::Duration,
+    ) -> Result<bool, DatabaseError> {
+        let conn = self.conn().await?;
+        let count = conn
+            .execute(
+                r#"
+                INSERT INTO leases (name, owner, acquired_at, expires_at)
+                VALUES ($1, $2, NOW(), NOW() + make_interval(secs => $3))
+                ON CONFLICT (name) DO UPDATE SET
+                    owner = EXCLUDED.owner,
+                    acquired_at = CASE
+                        WHEN leases.owner = EXCLUDED.owner THEN leases.acquired_at
+                        ELSE EXCLUDED.acquired_at
+                    END,
+                    expires_at = EXCLUDED.expires_at
+                WHERE leases.owner = EXCLUDED.owner OR leases.expires_at <= NOW()
+                "#,
+                &[&name, &owner, &ttl.as_secs_f64()],
+            )
+            .await?;
+        Ok(count > 0)
+    }
+
+    /// Extend a lease `owner` still holds.
+    pub async fn renew_lease(
+        &self,
+        name: &str,
+        owner: &str,
+        ttl: std::time::Duration,
+    ) -> Result<bool, DatabaseError> {
+        let conn = self.conn().await?;
+        let count = conn
+            .execute(
+                r#"
+                UPDATE leases SET expires_at = NOW() + make_interval(secs => $3)
+                WHERE name = $1 AND owner = $2
+                "#,
+                &[&name, &owner, &ttl.as_secs_f64()],
+            )
+            .await?;
+        Ok(count > 0)
+    }
+
+    /// Give up a lease if `owner` holds it.
+    pub async fn release_lease(&self, name: &str, owner: &str) -> Result<(), DatabaseError> {
+        let conn = self.conn().await?;
+        conn.execute(
+            "DELETE FROM leases WHERE name = $1 AND owner = $2",
+            &[&name, &owner],
+        )
+        .await?;
+        Ok(())
+    }
+
+    /// The current holder of a lease.
+    pub async fn get_lease(&self, name: &str) -> Result<Option<LeaseRecord>, DatabaseError> {
+        let conn = self.conn().await?;
+        let row = conn
+            .query_opt(
+                "SELECT name, owner, acquired_at, expires_at FROM leases WHERE name = $1",
+                &[&name],
+            )
+            .await?;
+        Ok(row.map(|r| LeaseRecord {
+            name: r.get("name"),
+            owner: r.get("owner"),
+            acquired_at: r.get("acquired_at"),
+            expires_at: r.get("expires_at"),
+        }))
+    }
+}
+
+// ==================== Knowledge Graph ====================
+
+#[cfg(feature = "postgres")]
+use crate::workspace::graph::{GraphEdge, GraphEntity, entity_key};
+
+#[cfg(feature = "postgres")]
+impl Store {
+    /// Get or create the entity named `name` in this scope.
+    pub async fn upsert_graph_entity(
+        &self,
+        user_id: &str,
+        agent_id: Option<Uuid>,
+        name: &str,
+        kind: &str,
+    ) -> Result<GraphEntity, DatabaseError> {
+        let key = entity_key(name);
+        let conn = self.conn().await?;
+        let existing = conn
+            .query_opt(
+                r#"
+                SELECT * FROM graph_entities
+                WHERE user_id = $1 AND agent_id IS NOT DISTINCT FROM $2 AND name_key = $3
+                "#,
+                &[&user_id, &agent_id, &key],
+            )
+            .await?;
+        if let Some(row) = existing {
+            let entity = row_to_graph_entity(&row);
+            if entity.kind.is_empty() && !kind.is_empty() {
+                conn.execute(
+                    "UPDATE graph_entities SET kind = $2 WHERE id = $1",
+                    &[&entity.id, &kind],
+                )
+                .await?;
+                return Ok(GraphEntity {
+                    kind: kind.to_string(),
+                    ..entity
+                });
+            }
+            return Ok(entity);
+        }
+
+        let row = conn
+            .query_one(
+                r#"
+                INSERT INTO graph_entities (id, user_id, agent_id, name, name_key, kind)
+                VALUES ($1, $2, $3, $4, $5, $6)
+                RETURNING *
+                "#,
+                &[&Uuid::new_v4(), &user_id, &agent_id, &name, &key, &kind],
+            )
+            .await?;
+        Ok(row_to_graph_entity(&row))
+    }
+
+    /// Entities whose key contains `key`.
+    pub async fn find_graph_entities(
+        &self,
+        user_id: &str,
+        agent_id: Option<Uuid>,
+        key: &str,
+        limit: usize,
+    ) -> Result<Vec<GraphEntity>, DatabaseError> {
+        let conn = self.conn().await?;
+        let rows = conn
+            .query(
+                r#"
+                SELECT * FROM graph_entities
+                WHERE user_id = $1 AND agent_id IS NOT DISTINCT FROM $2
+                  AND strpos(name_key, $3) > 0
+                ORDER BY (name_key = $3) DESC, length(name_key), name_key
+                LIMIT $4
+                "#,
+                &[&user_id, &agent_id, &key, &(limit as i64)],
+            )
+            .await?;
+        Ok(rows.iter().map(row_to_graph_entity).collect())
+    }
+
+    /// Get entities by ID.
+    pub async fn get_graph_entities(
+        &self,
+        ids: &[Uuid],
+    ) -> Result<Vec<GraphEntity>, DatabaseError> {
+        let conn = self.conn().await?;
+        let rows = conn
+            .query("SELECT * FROM graph_entities WHERE id = ANY($1)", &[&ids])
+            .await?;
+        Ok(rows.iter().map(row_to_graph_entity).collect())
+    }
+
+    /// Record a relation.
+    pub async fn insert_graph_edge(&self, edge: &GraphEdge) -> Result<(), DatabaseError> {
+        let conn = self.conn().await?;
+        conn.execute(
+            r#"
+            INSERT INTO graph_edges (id, source_id, relation, target_id, document_id, created_at)
+            VALUES ($1, $2, $3, $4, $5, $6)
+            "#,
+            &[
+                &edge.id,
+                &edge.source_id,
+                &edge.relation,
+                &edge.target_id,
+                &edge.document_id,
+                &edge.created_at,
+            ],
+        )
+        .await?;
+        Ok(())
+    }
+
+    /// Edges with either end in `entity_ids`.
+    pub async fn graph_edges_touching(
+        &self,
+        entity_ids: &[Uuid],
+    ) -> Result<Vec<GraphEdge>, DatabaseError> {
+        let conn = self.conn().await?;
+        let rows = conn
+            .query(
+                r#"
+                SELECT * FROM graph_edges
+                WHERE source_id = ANY($1) OR target_id = ANY($1)
+                ORDER BY created_at
+                "#,
+                &[&entity_ids],
+            )
+            .await?;
+        Ok(rows
+            .iter()
+            .map(|row| GraphEdge {
+                id: row.get("id"),
+                source_id: row.get("source_id"),
+                relation: row.get("relation"),
+                target_id: row.get("target_id"),
+                document_id: row.get("document_id"),
+                created_at: row.get("created_at"),
+            })
+            .collect())
+    }
+
+    /// Delete the edges extracted from a document.
+    pub async fn delete_graph_edges_for_document(
+        &self,
+        document_id: Uuid,
+    ) -> Result<u64, DatabaseError> {
+        let conn = self.conn().await?;
+        let count = conn
+            .execute(
+                "DELETE FROM graph_edges WHERE document_id = $1",
+                &[&document_id],
+            )
+            .await?;
+        Ok(count)
+    }
+}
+
+#[cfg(feature = "postgres")]
+fn row_to_graph_entity(row: &tokio_postgres::Row) -> GraphEntity {
+    GraphEntity {
+        id: row.get("id"),
+        user_id: row.get("user_id"),
+        agent_id: row.get("agent_id"),
+        name: row.get("name"),
+        kind: row.get("kind"),
+        created_at: row.get("created_at"),
+    }
+}
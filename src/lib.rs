@@ -39,18 +39,24 @@
 //! - **Continuous learning** - Improve estimates from historical data
 
 pub mod agent;
+pub mod audit;
 pub mod bootstrap;
 pub mod channels;
 pub mod cli;
 pub mod config;
+pub mod config_file;
+pub mod config_reload;
+pub mod contacts;
 pub mod context;
 pub mod db;
 pub mod error;
 pub mod estimation;
 pub mod evaluation;
 pub mod extensions;
+pub mod health;
 pub mod history;
 pub mod llm;
+pub mod metrics;
 pub mod orchestrator;
 pub mod pairing;
 pub mod safety;
@@ -58,9 +64,12 @@ pub mod sandbox;
 pub mod secrets;
 pub mod settings;
 pub mod setup;
+pub mod skills;
+pub mod telemetry;
 pub mod tools;
 pub mod tracing_fmt;
 pub mod util;
+pub mod webhooks;
 pub mod worker;
 pub mod workspace;
 
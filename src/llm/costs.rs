@@ -41,6 +41,13 @@ pub fn model_cost(model_id: &str) -> Option<(Decimal, Decimal)> {
         }
         "claude-3-haiku-20240307" => Some((dec!(0.00000025), dec!(0.00000125))),
 
+        // Groq and Together AI -- hosted open models, which the local model
+        // heuristic below would otherwise price at zero
+        "llama-3.1-8b-instant" => Some((dec!(0.00000005), dec!(0.00000008))),
+        "llama-3.3-70b-versatile" => Some((dec!(0.00000059), dec!(0.00000079))),
+        "Meta-Llama-3.1-8B-Instruct-Turbo" => Some((dec!(0.00000018), dec!(0.00000018))),
+        "Llama-3.3-70B-Instruct-Turbo" => Some((dec!(0.00000088), dec!(0.00000088))),
+
         // Ollama / local models -- free
         _ if is_local_model(id) => Some((Decimal::ZERO, Decimal::ZERO)),
 
@@ -104,6 +111,14 @@ mod tests {
         assert_eq!(output, Decimal::ZERO);
     }
 
+    #[test]
+    fn test_hosted_llama_not_free() {
+        let (input, _) = model_cost("llama-3.1-8b-instant").unwrap();
+        assert!(input > Decimal::ZERO);
+        let (input, _) = model_cost("meta-llama/Llama-3.3-70B-Instruct-Turbo").unwrap();
+        assert!(input > Decimal::ZERO);
+    }
+
     #[test]
     fn test_unknown_model_returns_none() {
         assert!(model_cost("some-totally-unknown-model-xyz").is_none());
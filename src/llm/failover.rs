@@ -9,6 +9,7 @@ use std::sync::atomic::{AtomicUsize, Ordering};
 
 use async_trait::async_trait;
 use rust_decimal::Decimal;
+use tokio::sync::mpsc;
 
 use crate::error::LlmError;
 use crate::llm::provider::{
@@ -128,6 +129,63 @@ impl LlmProvider for FailoverProvider {
         .await
     }
 
+    /// Fails over like [`Self::complete`] until a provider has sent text.
+    /// After that its error is returned, since the next provider would
+    /// start the text over.
+    async fn complete_stream(
+        &self,
+        request: CompletionRequest,
+        deltas: mpsc::UnboundedSender<String>,
+    ) -> Result<CompletionResponse, LlmError> {
+        let mut last_error: Option<LlmError> = None;
+
+        for (i, provider) in self.providers.iter().enumerate() {
+            let (tx, mut rx) = mpsc::unbounded_channel();
+            let call = provider.complete_stream(request.clone(), tx);
+            tokio::pin!(call);
+            let mut streamed = false;
+            let result = loop {
+                tokio::select! {
+                    Some(delta) = rx.recv() => {
+                        streamed = true;
+                        let _ = deltas.send(delta);
+                    }
+                    result = &mut call => break result,
+                }
+            };
+            while let Ok(delta) = rx.try_recv() {
+                streamed = true;
+                let _ = deltas.send(delta);
+            }
+
+            match result {
+                Ok(response) => {
+                    self.last_used.store(i, Ordering::Relaxed);
+                    return Ok(response);
+                }
+                Err(err) if streamed || !is_retryable(&err) => return Err(err),
+                Err(err) => {
+                    if i + 1 < self.providers.len() {
+                        tracing::warn!(
+                            provider = %provider.model_name(),
+                            error = %err,
+                            next_provider = %self.providers[i + 1].model_name(),
+                            "Provider failed with retryable error, trying next provider"
+                        );
+                    }
+                    last_error = Some(err);
+                }
+            }
+        }
+
+        Err(last_error.expect("providers list is non-empty"))
+    }
+
+    /// Only report streaming if every provider in the chain streams.
+    fn supports_streaming(&self) -> bool {
+        self.providers.iter().all(|p| p.supports_streaming())
+    }
+
     async fn complete_with_tools(
         &self,
         request: ToolCompletionRequest,
@@ -139,6 +197,12 @@ impl LlmProvider for FailoverProvider {
         .await
     }
 
+    /// Only report vision support if every provider in the chain has it,
+    /// since a failover may route the images to any of them.
+    fn supports_vision(&self) -> bool {
+        self.providers.iter().all(|p| p.supports_vision())
+    }
+
     async fn list_models(&self) -> Result<Vec<String>, LlmError> {
         let mut all_models = Vec::new();
 
@@ -394,6 +458,70 @@ mod tests {
         assert_eq!(response.content.as_deref(), Some("tools fallback"));
     }
 
+    /// Streams some text, then fails with a retryable error.
+    struct BrokenStream;
+
+    #[async_trait]
+    impl LlmProvider for BrokenStream {
+        fn model_name(&self) -> &str {
+            "broken"
+        }
+
+        fn cost_per_token(&self) -> (Decimal, Decimal) {
+            (Decimal::ZERO, Decimal::ZERO)
+        }
+
+        async fn complete(
+            &self,
+            _request: CompletionRequest,
+        ) -> Result<CompletionResponse, LlmError> {
+            unreachable!("only streamed")
+        }
+
+        async fn complete_stream(
+            &self,
+            _request: CompletionRequest,
+            deltas: mpsc::UnboundedSender<String>,
+        ) -> Result<CompletionResponse, LlmError> {
+            let _ = deltas.send("Hel".to_string());
+            Err(LlmError::RequestFailed {
+                provider: "broken".to_string(),
+                reason: "connection reset".to_string(),
+            })
+        }
+
+        async fn complete_with_tools(
+            &self,
+            _request: ToolCompletionRequest,
+        ) -> Result<ToolCompletionResponse, LlmError> {
+            unreachable!("only streamed")
+        }
+    }
+
+    // Test: streaming fails over until text has been sent, then stops.
+    #[tokio::test]
+    async fn complete_stream_fails_over_only_before_text() {
+        let primary = Arc::new(MockProvider::failing_retryable("primary"));
+        let fallback = Arc::new(MockProvider::succeeding("fallback", "fallback response"));
+        let failover = FailoverProvider::new(vec![primary, fallback]).unwrap();
+
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let response = failover.complete_stream(make_request(), tx).await.unwrap();
+        assert_eq!(response.content, "fallback response");
+        assert_eq!(rx.recv().await.as_deref(), Some("fallback response"));
+
+        let fallback = Arc::new(MockProvider::succeeding("fallback", "fallback response"));
+        let failover = FailoverProvider::new(vec![Arc::new(BrokenStream), fallback]).unwrap();
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let err = failover
+            .complete_stream(make_request(), tx)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, LlmError::RequestFailed { provider, .. } if provider == "broken"));
+        assert_eq!(rx.recv().await.as_deref(), Some("Hel"));
+        assert_eq!(rx.recv().await, None);
+    }
+
     // Test: model_name and cost_per_token reflect the last-used provider.
     #[tokio::test]
     async fn model_name_and_cost_track_last_used_provider() {
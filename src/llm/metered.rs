@@ -0,0 +1,111 @@
+//! Metrics-recording LLM provider wrapper.
+//!
+//! Wraps any LlmProvider and records request outcomes and token usage in
+//! the Prometheus registry. Transparent to callers --- same LlmProvider trait.
+
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use rust_decimal::Decimal;
+use tokio::sync::mpsc;
+
+use crate::error::LlmError;
+use crate::llm::provider::{
+    CompletionRequest, CompletionResponse, LlmProvider, ModelMetadata, ToolCompletionRequest,
+    ToolCompletionResponse,
+};
+use crate::metrics::Metrics;
+
+/// Records every completion in [`Metrics`] before returning it.
+pub struct MeteredProvider {
+    inner: Arc<dyn LlmProvider>,
+}
+
+impl MeteredProvider {
+    pub fn new(inner: Arc<dyn LlmProvider>) -> Self {
+        Self { inner }
+    }
+
+    fn observe<T>(&self, result: &Result<T, LlmError>, usage: impl FnOnce(&T) -> (u32, u32)) {
+        let model = self.inner.active_model_name();
+        match result {
+            Ok(response) => {
+                let (input, output) = usage(response);
+                Metrics::global().record_llm_usage(&model, input, output);
+            }
+            Err(_) => Metrics::global().record_llm_error(&model),
+        }
+    }
+}
+
+#[async_trait]
+impl LlmProvider for MeteredProvider {
+    fn model_name(&self) -> &str {
+        self.inner.model_name()
+    }
+
+    fn cost_per_token(&self) -> (Decimal, Decimal) {
+        self.inner.cost_per_token()
+    }
+
+    async fn complete(&self, request: CompletionRequest) -> Result<CompletionResponse, LlmError> {
+        let result = self.inner.complete(request).await;
+        self.observe(&result, |r| (r.input_tokens, r.output_tokens));
+        result
+    }
+
+    async fn complete_stream(
+        &self,
+        request: CompletionRequest,
+        deltas: mpsc::UnboundedSender<String>,
+    ) -> Result<CompletionResponse, LlmError> {
+        let result = self.inner.complete_stream(request, deltas).await;
+        self.observe(&result, |r| (r.input_tokens, r.output_tokens));
+        result
+    }
+
+    fn supports_streaming(&self) -> bool {
+        self.inner.supports_streaming()
+    }
+
+    async fn complete_with_tools(
+        &self,
+        request: ToolCompletionRequest,
+    ) -> Result<ToolCompletionResponse, LlmError> {
+        let result = self.inner.complete_with_tools(request).await;
+        self.observe(&result, |r| (r.input_tokens, r.output_tokens));
+        result
+    }
+
+    async fn list_models(&self) -> Result<Vec<String>, LlmError> {
+        self.inner.list_models().await
+    }
+
+    async fn model_metadata(&self) -> Result<ModelMetadata, LlmError> {
+        self.inner.model_metadata().await
+    }
+
+    fn active_model_name(&self) -> String {
+        self.inner.active_model_name()
+    }
+
+    fn set_model(&self, model: &str) -> Result<(), LlmError> {
+        self.inner.set_model(model)
+    }
+
+    fn seed_response_chain(&self, thread_id: &str, response_id: String) {
+        self.inner.seed_response_chain(thread_id, response_id)
+    }
+
+    fn get_response_chain_id(&self, thread_id: &str) -> Option<String> {
+        self.inner.get_response_chain_id(thread_id)
+    }
+
+    fn supports_vision(&self) -> bool {
+        self.inner.supports_vision()
+    }
+
+    fn calculate_cost(&self, input_tokens: u32, output_tokens: u32) -> Decimal {
+        self.inner.calculate_cost(input_tokens, output_tokens)
+    }
+}
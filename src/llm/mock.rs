@@ -0,0 +1,427 @@
+//! Scriptable LLM provider for tests.
+//!
+//! [`MockLlmProvider`] answers from a queue of canned responses (text,
+//! tool calls, or errors) instead of calling a model, and keeps every
+//! request it receives so a test can assert on the prompts afterwards, or
+//! inline with a request hook. It never touches the network, so agent
+//! integrations can be tested deterministically:
+//!
+//! ```
+//! use ironclaw::llm::{ChatMessage, CompletionRequest, LlmProvider, MockLlmProvider};
+//!
+//! # tokio_test::block_on(async {
+//! let llm = MockLlmProvider::new().with_text("It's on Tuesday.");
+//! let response = llm
+//!     .complete(CompletionRequest::new(vec![ChatMessage::user("When?")]))
+//!     .await
+//!     .unwrap();
+//! assert_eq!(response.content, "It's on Tuesday.");
+//! assert_eq!(llm.last_request().unwrap().last_user_message(), Some("When?"));
+//! # });
+//! ```
+
+use std::collections::VecDeque;
+use std::sync::Mutex;
+
+use async_trait::async_trait;
+use rust_decimal::Decimal;
+
+use crate::error::LlmError;
+use crate::llm::{
+    ChatMessage, CompletionRequest, CompletionResponse, FinishReason, LlmProvider, Role, ToolCall,
+    ToolCompletionRequest, ToolCompletionResponse, ToolDefinition,
+};
+
+/// Provider name used in errors.
+const PROVIDER: &str = "mock";
+
+/// A canned response.
+#[derive(Debug, Clone)]
+pub enum MockResponse {
+    /// A text answer.
+    Text(String),
+    /// Tool calls, with optional text alongside.
+    ToolCalls {
+        content: Option<String>,
+        calls: Vec<ToolCall>,
+    },
+    /// A failed request (`LlmError::RequestFailed` with this reason).
+    Error(String),
+}
+
+/// A request the provider received.
+#[derive(Debug, Clone)]
+pub struct MockRequest {
+    pub messages: Vec<ChatMessage>,
+    /// Tools offered; empty for plain completions.
+    pub tools: Vec<ToolDefinition>,
+    pub max_tokens: Option<u32>,
+    pub temperature: Option<f32>,
+    pub tool_choice: Option<String>,
+}
+
+impl MockRequest {
+    /// Content of the first system message.
+    pub fn system_prompt(&self) -> Option<&str> {
+        self.messages
+            .iter()
+            .find(|m| m.role == Role::System)
+            .map(|m| m.content.as_str())
+    }
+
+    /// Content of the last user message.
+    pub fn last_user_message(&self) -> Option<&str> {
+        self.messages
+            .iter()
+            .rev()
+            .find(|m| m.role == Role::User)
+            .map(|m| m.content.as_str())
+    }
+
+    /// Whether any message contains `text`.
+    pub fn contains(&self, text: &str) -> bool {
+        self.messages.iter().any(|m| m.content.contains(text))
+    }
+
+    /// Whether a tool named `name` was offered.
+    pub fn offers_tool(&self, name: &str) -> bool {
+        self.tools.iter().any(|t| t.name == name)
+    }
+}
+
+type RequestHook = Box<dyn Fn(&MockRequest) + Send + Sync>;
+
+/// An LLM provider that replays canned responses in order.
+///
+/// When the queue runs out, the fallback response is used if one was set;
+/// otherwise the request fails.
+pub struct MockLlmProvider {
+    model: String,
+    cost: (Decimal, Decimal),
+    responses: Mutex<VecDeque<MockResponse>>,
+    fallback: Option<MockResponse>,
+    requests: Mutex<Vec<MockRequest>>,
+    hooks: Vec<RequestHook>,
+    next_call_id: Mutex<usize>,
+}
+
+impl Default for MockLlmProvider {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl std::fmt::Debug for MockLlmProvider {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("MockLlmProvider")
+            .field("model", &self.model)
+            .field("queued", &self.lock_responses().len())
+            .field("requests", &self.request_count())
+            .finish()
+    }
+}
+
+impl MockLlmProvider {
+    /// A provider named "mock" with nothing queued and zero cost.
+    pub fn new() -> Self {
+        Self {
+            model: "mock".to_string(),
+            cost: (Decimal::ZERO, Decimal::ZERO),
+            responses: Mutex::new(VecDeque::new()),
+            fallback: None,
+            requests: Mutex::new(Vec::new()),
+            hooks: Vec::new(),
+            next_call_id: Mutex::new(0),
+        }
+    }
+
+    /// Report `model` as the model name.
+    pub fn with_model(mut self, model: impl Into<String>) -> Self {
+        self.model = model.into();
+        self
+    }
+
+    /// Charge this much per input and output token.
+    pub fn with_cost(mut self, input: Decimal, output: Decimal) -> Self {
+        self.cost = (input, output);
+        self
+    }
+
+    /// Queue a response.
+    pub fn with_response(self, response: MockResponse) -> Self {
+        self.push(response);
+        self
+    }
+
+    /// Queue a text answer.
+    pub fn with_text(self, text: impl Into<String>) -> Self {
+        self.with_response(MockResponse::Text(text.into()))
+    }
+
+    /// Queue a single tool call, with a generated call ID.
+    pub fn with_tool_call(self, name: impl Into<String>, arguments: serde_json::Value) -> Self {
+        let call = ToolCall {
+            id: self.next_call_id(),
+            name: name.into(),
+            arguments,
+        };
+        self.with_tool_calls(None, vec![call])
+    }
+
+    /// Queue tool calls, with optional text alongside.
+    pub fn with_tool_calls(self, content: Option<String>, calls: Vec<ToolCall>) -> Self {
+        self.with_response(MockResponse::ToolCalls { content, calls })
+    }
+
+    /// Queue a failed request.
+    pub fn with_error(self, reason: impl Into<String>) -> Self {
+        self.with_response(MockResponse::Error(reason.into()))
+    }
+
+    /// Answer with `response` once the queue is empty.
+    pub fn with_fallback(mut self, response: MockResponse) -> Self {
+        self.fallback = Some(response);
+        self
+    }
+
+    /// Call `hook` with every request as it arrives. Panicking in the hook
+    /// fails the test at the call that broke the expectation.
+    pub fn with_request_hook(
+        mut self,
+        hook: impl Fn(&MockRequest) + Send + Sync + 'static,
+    ) -> Self {
+        self.hooks.push(Box::new(hook));
+        self
+    }
+
+    /// Queue a response on a shared provider.
+    pub fn push(&self, response: MockResponse) {
+        self.lock_responses().push_back(response);
+    }
+
+    /// Responses still queued.
+    pub fn remaining(&self) -> usize {
+        self.lock_responses().len()
+    }
+
+    /// Every request received, in order.
+    pub fn requests(&self) -> Vec<MockRequest> {
+        self.lock_requests().clone()
+    }
+
+    /// The most recent request.
+    pub fn last_request(&self) -> Option<MockRequest> {
+        self.lock_requests().last().cloned()
+    }
+
+    pub fn request_count(&self) -> usize {
+        self.lock_requests().len()
+    }
+
+    fn lock_responses(&self) -> std::sync::MutexGuard<'_, VecDeque<MockResponse>> {
+        self.responses.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn lock_requests(&self) -> std::sync::MutexGuard<'_, Vec<MockRequest>> {
+        self.requests.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn next_call_id(&self) -> String {
+        let mut next = self.next_call_id.lock().unwrap_or_else(|e| e.into_inner());
+        *next += 1;
+        format!("call_{}", *next)
+    }
+
+    /// Record `request`, run the hooks, and pop the next response.
+    fn respond(&self, request: MockRequest) -> Result<(MockResponse, u32), LlmError> {
+        for hook in &self.hooks {
+            hook(&request);
+        }
+        let input_tokens = estimate_tokens(request.messages.iter().map(|m| m.content.as_str()));
+        self.lock_requests().push(request);
+
+        let response = self
+            .lock_responses()
+            .pop_front()
+            .or_else(|| self.fallback.clone())
+            .ok_or_else(|| LlmError::RequestFailed {
+                provider: PROVIDER.to_string(),
+                reason: "no more scripted responses".to_string(),
+            })?;
+        match response {
+            MockResponse::Error(reason) => Err(LlmError::RequestFailed {
+                provider: PROVIDER.to_string(),
+                reason,
+            }),
+            response => Ok((response, input_tokens)),
+        }
+    }
+}
+
+#[async_trait]
+impl LlmProvider for MockLlmProvider {
+    fn model_name(&self) -> &str {
+        &self.model
+    }
+
+    fn cost_per_token(&self) -> (Decimal, Decimal) {
+        self.cost
+    }
+
+    async fn complete(&self, request: CompletionRequest) -> Result<CompletionResponse, LlmError> {
+        let (response, input_tokens) = self.respond(MockRequest {
+            messages: request.messages,
+            tools: Vec::new(),
+            max_tokens: request.max_tokens,
+            temperature: request.temperature,
+            tool_choice: None,
+        })?;
+        match response {
+            MockResponse::Text(content) => Ok(CompletionResponse {
+                output_tokens: estimate_tokens([content.as_str()]),
+                content,
+                input_tokens,
+                finish_reason: FinishReason::Stop,
+                response_id: None,
+            }),
+            _ => Err(LlmError::InvalidResponse {
+                provider: PROVIDER.to_string(),
+                reason: "scripted tool calls for a completion without tools".to_string(),
+            }),
+        }
+    }
+
+    async fn complete_with_tools(
+        &self,
+        request: ToolCompletionRequest,
+    ) -> Result<ToolCompletionResponse, LlmError> {
+        let (response, input_tokens) = self.respond(MockRequest {
+            messages: request.messages,
+            tools: request.tools,
+            max_tokens: request.max_tokens,
+            temperature: request.temperature,
+            tool_choice: request.tool_choice,
+        })?;
+        let (content, tool_calls) = match response {
+            MockResponse::Text(text) => (Some(text), Vec::new()),
+            MockResponse::ToolCalls { content, calls } => (content, calls),
+            MockResponse::Error(_) => unreachable!("errors are returned by respond()"),
+        };
+        Ok(ToolCompletionResponse {
+            output_tokens: estimate_tokens(content.as_deref()),
+            finish_reason: if tool_calls.is_empty() {
+                FinishReason::Stop
+            } else {
+                FinishReason::ToolUse
+            },
+            content,
+            tool_calls,
+            input_tokens,
+            response_id: None,
+        })
+    }
+}
+
+/// Roughly four characters per token, so usage and cost aren't zero.
+fn estimate_tokens<'a>(texts: impl IntoIterator<Item = &'a str>) -> u32 {
+    let chars: usize = texts.into_iter().map(str::len).sum();
+    chars.div_ceil(4) as u32
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_scripted_responses_in_order() {
+        let llm = MockLlmProvider::new()
+            .with_model("test-model")
+            .with_tool_call("echo", serde_json::json!({"message": "hi"}))
+            .with_text("done")
+            .with_error("overloaded");
+
+        let tools = vec![ToolDefinition {
+            name: "echo".to_string(),
+            description: "Echo".to_string(),
+            parameters: serde_json::json!({"type": "object"}),
+        }];
+        let first = llm
+            .complete_with_tools(ToolCompletionRequest::new(
+                vec![
+                    ChatMessage::system("Be brief."),
+                    ChatMessage::user("Say hi"),
+                ],
+                tools.clone(),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(first.tool_calls[0].id, "call_1");
+        assert_eq!(first.finish_reason, FinishReason::ToolUse);
+
+        let second = llm
+            .complete_with_tools(ToolCompletionRequest::new(vec![], tools))
+            .await
+            .unwrap();
+        assert_eq!(second.content.as_deref(), Some("done"));
+        assert!(second.tool_calls.is_empty());
+
+        let err = llm
+            .complete(CompletionRequest::new(vec![]))
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("overloaded"));
+        // Exhausted without a fallback.
+        assert!(llm.complete(CompletionRequest::new(vec![])).await.is_err());
+
+        let requests = llm.requests();
+        assert_eq!(requests.len(), 4);
+        assert_eq!(requests[0].system_prompt(), Some("Be brief."));
+        assert_eq!(requests[0].last_user_message(), Some("Say hi"));
+        assert!(requests[0].offers_tool("echo"));
+        assert!(requests[2].tools.is_empty());
+        assert_eq!(llm.active_model_name(), "test-model");
+    }
+
+    #[tokio::test]
+    async fn test_fallback_and_hooks() {
+        let seen = Arc::new(AtomicUsize::new(0));
+        let counter = Arc::clone(&seen);
+        let llm = MockLlmProvider::new()
+            .with_fallback(MockResponse::Text("ok".to_string()))
+            .with_request_hook(move |request| {
+                assert!(request.contains("ping"));
+                counter.fetch_add(1, Ordering::SeqCst);
+            });
+
+        for _ in 0..3 {
+            let response = llm
+                .complete(CompletionRequest::new(vec![ChatMessage::user("ping")]))
+                .await
+                .unwrap();
+            assert_eq!(response.content, "ok");
+            assert!(response.input_tokens > 0);
+        }
+        assert_eq!(seen.load(Ordering::SeqCst), 3);
+
+        llm.push(MockResponse::Text("queued".to_string()));
+        assert_eq!(llm.remaining(), 1);
+        let response = llm
+            .complete(CompletionRequest::new(vec![ChatMessage::user("ping")]))
+            .await
+            .unwrap();
+        assert_eq!(response.content, "queued");
+    }
+
+    #[tokio::test]
+    async fn test_tool_calls_rejected_for_plain_completion() {
+        let llm = MockLlmProvider::new().with_tool_call("echo", serde_json::json!({}));
+        let err = llm
+            .complete(CompletionRequest::new(vec![]))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, LlmError::InvalidResponse { .. }));
+    }
+}
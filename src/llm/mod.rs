@@ -1,14 +1,23 @@
 //! LLM integration for the agent.
 //!
 //! Supports multiple backends:
-//! - **NEAR AI** (default): Session-based or API key auth via NEAR AI proxy
+//! - **NEAR AI** (default): Session-based or API key auth via NEAR AI proxy,
+//!   over the Responses API or Chat Completions (with streaming)
 //! - **OpenAI**: Direct API access with your own key
 //! - **Anthropic**: Direct API access with your own key
 //! - **Ollama**: Local model inference
 //! - **OpenAI-compatible**: Any endpoint that speaks the OpenAI API
+//! - **Groq** and **Together AI**: Fast inference, as the main backend or
+//!   for latency-sensitive calls (`FAST_LLM_BACKEND`), and for any job whose
+//!   model is prefixed `groq/` or `together/`
+//!
+//! [`MockLlmProvider`] replays scripted responses for tests.
 
 mod costs;
 pub mod failover;
+mod metered;
+pub mod mock;
+mod models;
 mod nearai;
 mod nearai_chat;
 mod provider;
@@ -16,14 +25,20 @@ mod reasoning;
 mod retry;
 mod rig_adapter;
 pub mod session;
+pub mod vision;
 
 pub use failover::FailoverProvider;
+pub use metered::MeteredProvider;
+pub use mock::{MockLlmProvider, MockRequest, MockResponse};
+pub use models::ModelProviders;
 pub use nearai::{ModelInfo, NearAiProvider};
 pub use nearai_chat::NearAiChatProvider;
 pub use provider::{
-    ChatMessage, CompletionRequest, CompletionResponse, FinishReason, LlmProvider, ModelMetadata,
-    Role, ToolCall, ToolCompletionRequest, ToolCompletionResponse, ToolDefinition, ToolResult,
+    ChatMessage, CompletionRequest, CompletionResponse, FinishReason, ImageContent, LlmProvider,
+    ModelMetadata, Role, ToolCall, ToolCompletionRequest, ToolCompletionResponse, ToolDefinition,
+    ToolResult, parse_tool_arguments,
 };
+pub(crate) use reasoning::extract_json;
 pub use reasoning::{
     ActionPlan, Reasoning, ReasoningContext, RespondOutput, RespondResult, TokenUsage,
     ToolSelection,
@@ -54,6 +69,8 @@ pub fn create_llm_provider(
         LlmBackend::Anthropic => create_anthropic_provider(config),
         LlmBackend::Ollama => create_ollama_provider(config),
         LlmBackend::OpenAiCompatible => create_openai_compatible_provider(config),
+        LlmBackend::Groq => create_groq_provider(config),
+        LlmBackend::Together => create_together_provider(config),
     }
 }
 
@@ -76,9 +93,13 @@ pub fn create_llm_provider_with_config(
         NearAiApiMode::ChatCompletions => {
             tracing::info!(
                 model = %config.model,
-                "Using Chat Completions API (cloud-api) with API key auth"
+                auth = if config.api_key.is_some() { "api_key" } else { "session" },
+                "Using Chat Completions API (cloud-api)"
             );
-            Ok(Arc::new(NearAiChatProvider::new(config.clone())?))
+            Ok(Arc::new(NearAiChatProvider::with_session(
+                config.clone(),
+                session,
+            )))
         }
     }
 }
@@ -184,14 +205,76 @@ fn create_openai_compatible_provider(config: &LlmConfig) -> Result<Arc<dyn LlmPr
     Ok(Arc::new(RigAdapter::new(model, &compat.model)))
 }
 
+fn create_groq_provider(config: &LlmConfig) -> Result<Arc<dyn LlmProvider>, LlmError> {
+    let gq = config.groq.as_ref().ok_or_else(|| LlmError::AuthFailed {
+        provider: "groq".to_string(),
+    })?;
+
+    use rig::providers::groq;
+
+    // Groq's own rig client rather than the OpenAI one, which covers where
+    // its API departs from OpenAI's.
+    let client: groq::Client =
+        groq::Client::new(gq.api_key.expose_secret()).map_err(|e| LlmError::RequestFailed {
+            provider: "groq".to_string(),
+            reason: format!("Failed to create Groq client: {}", e),
+        })?;
+
+    let model = client.completion_model(&gq.model);
+    tracing::info!("Using Groq (model: {})", gq.model);
+    Ok(Arc::new(RigAdapter::new(model, &gq.model)))
+}
+
+fn create_together_provider(config: &LlmConfig) -> Result<Arc<dyn LlmProvider>, LlmError> {
+    let tg = config
+        .together
+        .as_ref()
+        .ok_or_else(|| LlmError::AuthFailed {
+            provider: "together".to_string(),
+        })?;
+
+    use rig::providers::together;
+
+    // Together's own rig client rather than the OpenAI one, which covers
+    // where its API departs from OpenAI's.
+    let client: together::Client =
+        together::Client::new(tg.api_key.expose_secret()).map_err(|e| LlmError::RequestFailed {
+            provider: "together".to_string(),
+            reason: format!("Failed to create Together AI client: {}", e),
+        })?;
+
+    let model = client.completion_model(&tg.model);
+    tracing::info!("Using Together AI (model: {})", tg.model);
+    Ok(Arc::new(RigAdapter::new(model, &tg.model)))
+}
+
+/// Create the provider for latency-sensitive calls (heartbeat triage,
+/// routing) on the backend named by `FAST_LLM_BACKEND`, if set.
+pub fn create_fast_llm_provider(
+    config: &LlmConfig,
+) -> Result<Option<Arc<dyn LlmProvider>>, LlmError> {
+    let provider = match config.fast_backend {
+        Some(LlmBackend::Groq) => create_groq_provider(config)?,
+        Some(LlmBackend::Together) => create_together_provider(config)?,
+        _ => return Ok(None),
+    };
+    tracing::info!("Fast LLM provider: {}", provider.model_name());
+    Ok(Some(provider))
+}
+
 /// Create a cheap/fast LLM provider for lightweight tasks (heartbeat, routing, evaluation).
 ///
-/// Uses `NEARAI_CHEAP_MODEL` if set, otherwise falls back to the main provider.
-/// Currently only supports NEAR AI backends (Responses and ChatCompletions modes).
+/// Uses the fast backend (`FAST_LLM_BACKEND`) if set, then `NEARAI_CHEAP_MODEL`,
+/// otherwise falls back to the main provider. The cheap model is only
+/// supported on NEAR AI backends (Responses and ChatCompletions modes).
 pub fn create_cheap_llm_provider(
     config: &LlmConfig,
     session: Arc<SessionManager>,
 ) -> Result<Option<Arc<dyn LlmProvider>>, LlmError> {
+    if let Some(fast) = create_fast_llm_provider(config)? {
+        return Ok(Some(fast));
+    }
+
     let Some(ref cheap_model) = config.nearai.cheap_model else {
         return Ok(None);
     };
@@ -212,16 +295,18 @@ pub fn create_cheap_llm_provider(
 
     match cheap_config.api_mode {
         NearAiApiMode::Responses => Ok(Some(Arc::new(NearAiProvider::new(cheap_config, session)))),
-        NearAiApiMode::ChatCompletions => {
-            Ok(Some(Arc::new(NearAiChatProvider::new(cheap_config)?)))
-        }
+        NearAiApiMode::ChatCompletions => Ok(Some(Arc::new(NearAiChatProvider::with_session(
+            cheap_config,
+            session,
+        )))),
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::config::{LlmBackend, NearAiApiMode, NearAiConfig};
+    use crate::config::{GroqConfig, LlmBackend, NearAiApiMode, NearAiConfig};
+    use secrecy::SecretString;
     use std::path::PathBuf;
 
     fn test_nearai_config() -> NearAiConfig {
@@ -246,6 +331,9 @@ mod tests {
             anthropic: None,
             ollama: None,
             openai_compatible: None,
+            groq: None,
+            together: None,
+            fast_backend: None,
         }
     }
 
@@ -285,4 +373,31 @@ mod tests {
         assert!(result.is_ok());
         assert!(result.unwrap().is_none());
     }
+
+    #[test]
+    fn test_create_cheap_llm_provider_prefers_fast_backend() {
+        let mut config = test_llm_config();
+        config.nearai.cheap_model = Some("cheap-test-model".to_string());
+        config.groq = Some(GroqConfig {
+            api_key: SecretString::from("gsk-test".to_string()),
+            model: "llama-3.1-8b-instant".to_string(),
+        });
+        config.fast_backend = Some(LlmBackend::Groq);
+
+        let session = Arc::new(SessionManager::new(SessionConfig::default()));
+        let provider = create_cheap_llm_provider(&config, session).unwrap();
+
+        assert_eq!(provider.unwrap().model_name(), "llama-3.1-8b-instant");
+    }
+
+    #[test]
+    fn test_create_fast_llm_provider_requires_key() {
+        let mut config = test_llm_config();
+        config.fast_backend = Some(LlmBackend::Together);
+
+        assert!(matches!(
+            create_fast_llm_provider(&config),
+            Err(LlmError::AuthFailed { .. })
+        ));
+    }
 }
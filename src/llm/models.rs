@@ -0,0 +1,87 @@
+//! Providers for models other than the configured one.
+//!
+//! Jobs and agents can ask for a specific model. [`ModelProviders`] builds
+//! a provider for each requested model from the main LLM configuration, on
+//! first use, and reuses it afterwards. A model prefixed `groq/` or
+//! `together/` runs on that backend instead of the configured one.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use crate::config::{LlmBackend, LlmConfig};
+use crate::error::LlmError;
+use crate::llm::{LlmProvider, MeteredProvider, SessionManager, create_llm_provider};
+
+/// Lazily created providers, one per model.
+pub struct ModelProviders {
+    config: LlmConfig,
+    session: Arc<SessionManager>,
+    providers: Mutex<HashMap<String, Arc<dyn LlmProvider>>>,
+}
+
+impl ModelProviders {
+    pub fn new(config: LlmConfig, session: Arc<SessionManager>) -> Self {
+        Self {
+            config,
+            session,
+            providers: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// A provider for `model`, on the configured backend.
+    pub fn get(&self, model: &str) -> Result<Arc<dyn LlmProvider>, LlmError> {
+        let mut providers = self.providers.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(provider) = providers.get(model) {
+            return Ok(Arc::clone(provider));
+        }
+
+        let mut config = self.config.clone();
+        match routed(model) {
+            Some((backend, name)) => {
+                config.backend = backend;
+                config.set_model(name);
+            }
+            None => config.set_model(model),
+        }
+        let provider: Arc<dyn LlmProvider> = Arc::new(MeteredProvider::new(create_llm_provider(
+            &config,
+            Arc::clone(&self.session),
+        )?));
+        providers.insert(model.to_string(), Arc::clone(&provider));
+        Ok(provider)
+    }
+}
+
+/// The backend named by a `groq/` or `together/` prefix on `model`, and
+/// the model name after it.
+fn routed(model: &str) -> Option<(LlmBackend, &str)> {
+    let (prefix, name) = model.split_once('/')?;
+    let backend = match prefix {
+        "groq" => LlmBackend::Groq,
+        "together" => LlmBackend::Together,
+        _ => return None,
+    };
+    Some((backend, name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_routed() {
+        assert_eq!(
+            routed("groq/llama-3.1-8b-instant"),
+            Some((LlmBackend::Groq, "llama-3.1-8b-instant"))
+        );
+        assert_eq!(
+            routed("together/meta-llama/Llama-3.3-70B-Instruct-Turbo"),
+            Some((
+                LlmBackend::Together,
+                "meta-llama/Llama-3.3-70B-Instruct-Turbo"
+            ))
+        );
+        assert_eq!(routed("meta-llama/Llama-3.3-70B-Instruct-Turbo"), None);
+        assert_eq!(routed("gpt-4o"), None);
+    }
+}
@@ -17,7 +17,7 @@ use crate::config::NearAiConfig;
 use crate::error::LlmError;
 use crate::llm::provider::{
     ChatMessage, CompletionRequest, CompletionResponse, FinishReason, LlmProvider, Role, ToolCall,
-    ToolCompletionRequest, ToolCompletionResponse,
+    ToolCompletionRequest, ToolCompletionResponse, parse_tool_arguments,
 };
 use crate::llm::retry::{is_retryable_status, retry_backoff_delay};
 use crate::llm::session::SessionManager;
@@ -449,6 +449,11 @@ fn split_messages(
 
 #[async_trait]
 impl LlmProvider for NearAiProvider {
+    #[tracing::instrument(
+        name = "llm.complete",
+        skip_all,
+        fields(provider = "nearai", model = %self.model_name())
+    )]
     async fn complete(&self, req: CompletionRequest) -> Result<CompletionResponse, LlmError> {
         let thread_id = req.metadata.get("thread_id").cloned();
         let (instructions, input) = split_messages(req.messages, false);
@@ -563,6 +568,11 @@ impl LlmProvider for NearAiProvider {
         })
     }
 
+    #[tracing::instrument(
+        name = "llm.complete_with_tools",
+        skip_all,
+        fields(provider = "nearai", model = %self.model_name())
+    )]
     async fn complete_with_tools(
         &self,
         req: ToolCompletionRequest,
@@ -739,11 +749,12 @@ impl LlmProvider for NearAiProvider {
             } else if item.item_type == "function_call"
                 && let (Some(name), Some(call_id)) = (&item.name, &item.call_id)
             {
-                // Parse arguments JSON string into Value
+                // Parse arguments JSON string into Value. Malformed JSON is
+                // kept as a raw string so the worker can ask for a repair.
                 let arguments = item
                     .arguments
-                    .as_ref()
-                    .and_then(|s| serde_json::from_str(s).ok())
+                    .as_deref()
+                    .map(parse_tool_arguments)
                     .unwrap_or(serde_json::Value::Object(Default::default()));
 
                 tool_calls.push(ToolCall {
@@ -895,6 +906,7 @@ impl NearAiInputItem {
                     tool_call_id: None,
                     name: None,
                     tool_calls: None,
+                    images: Vec::new(),
                 }
             }
             NearAiInputItem::FunctionCallOutput {
@@ -905,6 +917,7 @@ impl NearAiInputItem {
                 tool_call_id: Some(call_id.clone()),
                 name: None,
                 tool_calls: None,
+                images: Vec::new(),
             },
         }
     }
@@ -1084,8 +1097,7 @@ fn extract_tool_calls_from_output(output: &Option<serde_json::Value>) -> Vec<Too
 
             // Arguments can be a string (JSON) or already an object
             let arguments = if let Some(args_str) = item.get("arguments").and_then(|a| a.as_str()) {
-                serde_json::from_str(args_str)
-                    .unwrap_or(serde_json::Value::Object(Default::default()))
+                parse_tool_arguments(args_str)
             } else if let Some(args_obj) = item.get("arguments") {
                 args_obj.clone()
             } else {
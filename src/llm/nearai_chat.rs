@@ -1,27 +1,38 @@
 //! NEAR AI Chat Completions API provider implementation.
 //!
 //! This provider uses the standard OpenAI-compatible chat completions API
-//! with API key authentication (for cloud-api).
+//! (cloud-api), authenticated with an API key or, without one, with the
+//! NEAR AI session shared with [`NearAiEmbeddings`], which is renewed when
+//! it expires. Plain completions can be streamed token by token.
+//!
+//! [`NearAiEmbeddings`]: crate::workspace::NearAiEmbeddings
+
+use std::sync::Arc;
 
 use async_trait::async_trait;
+use futures::StreamExt;
 use reqwest::Client;
 use rust_decimal::Decimal;
 use rust_decimal_macros::dec;
 use secrecy::ExposeSecret;
 use serde::{Deserialize, Serialize};
+use tokio::sync::mpsc;
 
 use crate::config::NearAiConfig;
 use crate::error::LlmError;
 use crate::llm::provider::{
     ChatMessage, CompletionRequest, CompletionResponse, FinishReason, LlmProvider, ModelMetadata,
-    Role, ToolCall, ToolCompletionRequest, ToolCompletionResponse,
+    Role, ToolCall, ToolCompletionRequest, ToolCompletionResponse, parse_tool_arguments,
 };
 use crate::llm::retry::{is_retryable_status, retry_backoff_delay};
+use crate::llm::session::SessionManager;
 
 /// NEAR AI Chat Completions API provider.
 pub struct NearAiChatProvider {
     client: Client,
     config: NearAiConfig,
+    /// Session used for auth when no API key is configured.
+    session: Option<Arc<SessionManager>>,
     active_model: std::sync::RwLock<String>,
 }
 
@@ -33,18 +44,29 @@ impl NearAiChatProvider {
                 provider: "nearai_chat".to_string(),
             });
         }
+        Ok(Self::build(config, None))
+    }
+
+    /// Create a provider that authenticates with the NEAR AI session unless
+    /// an API key is configured, renewing the session once when a request
+    /// is rejected as unauthorized.
+    pub fn with_session(config: NearAiConfig, session: Arc<SessionManager>) -> Self {
+        Self::build(config, Some(session))
+    }
 
+    fn build(config: NearAiConfig, session: Option<Arc<SessionManager>>) -> Self {
         let client = Client::builder()
             .timeout(std::time::Duration::from_secs(120))
             .build()
             .unwrap_or_else(|_| Client::new());
 
         let active_model = std::sync::RwLock::new(config.model.clone());
-        Ok(Self {
+        Self {
             client,
             config,
+            session,
             active_model,
-        })
+        }
     }
 
     fn api_url(&self, path: &str) -> String {
@@ -55,22 +77,59 @@ impl NearAiChatProvider {
         )
     }
 
-    fn api_key(&self) -> String {
-        self.config
-            .api_key
+    /// The session, if requests are authenticated with it.
+    fn session_auth(&self) -> Option<&Arc<SessionManager>> {
+        self.session
             .as_ref()
-            .map(|k| k.expose_secret().to_string())
-            .unwrap_or_default()
+            .filter(|_| self.config.api_key.is_none())
     }
 
-    /// Send a request to the chat completions API with retry on transient errors.
-    ///
-    /// Retries on HTTP 429, 500, 502, 503, 504 with exponential backoff.
-    /// Does not retry on client errors (400, 401, 403, 404) or parse errors.
+    /// Bearer token for requests: the API key, or the session token.
+    async fn bearer_token(&self) -> Result<String, LlmError> {
+        if let Some(key) = &self.config.api_key {
+            return Ok(key.expose_secret().to_string());
+        }
+        match &self.session {
+            Some(session) => Ok(session.get_token().await?.expose_secret().to_string()),
+            None => Err(LlmError::AuthFailed {
+                provider: "nearai_chat".to_string(),
+            }),
+        }
+    }
+
+    /// Send a request to the chat completions API and parse the response.
     async fn send_request<T: Serialize, R: for<'de> Deserialize<'de>>(
         &self,
         body: &T,
     ) -> Result<R, LlmError> {
+        let response = self.post(body).await?;
+        let response_text = response.text().await?;
+        tracing::debug!("NEAR AI Chat response body: {}", response_text);
+
+        serde_json::from_str(&response_text).map_err(|e| LlmError::InvalidResponse {
+            provider: "nearai_chat".to_string(),
+            reason: format!("JSON parse error: {}. Raw: {}", e, response_text),
+        })
+    }
+
+    /// POST to the chat completions API, renewing the session and trying
+    /// once more if it expired.
+    async fn post<T: Serialize>(&self, body: &T) -> Result<reqwest::Response, LlmError> {
+        match (self.post_inner(body).await, self.session_auth()) {
+            (Err(LlmError::SessionExpired { .. }), Some(session)) => {
+                session.handle_auth_failure().await?;
+                self.post_inner(body).await
+            }
+            (result, _) => result,
+        }
+    }
+
+    /// POST to the chat completions API with retry on transient errors,
+    /// returning the successful response with its body unread.
+    ///
+    /// Retries on HTTP 429, 500, 502, 503, 504 with exponential backoff.
+    /// Does not retry on client errors (400, 401, 403, 404).
+    async fn post_inner<T: Serialize>(&self, body: &T) -> Result<reqwest::Response, LlmError> {
         let url = self.api_url("chat/completions");
         let max_retries = self.config.max_retries;
 
@@ -87,10 +146,11 @@ impl NearAiChatProvider {
                 tracing::debug!("NEAR AI Chat request body: {}", json);
             }
 
+            let token = self.bearer_token().await?;
             let response = self
                 .client
                 .post(&url)
-                .header("Authorization", format!("Bearer {}", self.api_key()))
+                .header("Authorization", format!("Bearer {}", token))
                 .header("Content-Type", "application/json")
                 .json(body)
                 .send()
@@ -120,52 +180,51 @@ impl NearAiChatProvider {
             };
 
             let status = response.status();
-            let response_text = response.text().await.unwrap_or_default();
-
             tracing::debug!("NEAR AI Chat response status: {}", status);
-            tracing::debug!("NEAR AI Chat response body: {}", response_text);
+            if status.is_success() {
+                return Ok(response);
+            }
 
-            if !status.is_success() {
-                let status_code = status.as_u16();
+            let status_code = status.as_u16();
+            let response_text = response.text().await.unwrap_or_default();
+            tracing::debug!("NEAR AI Chat response body: {}", response_text);
 
-                // Auth errors are not retryable
-                if status_code == 401 {
-                    return Err(LlmError::AuthFailed {
+            // Auth errors are not retryable, but an expired session can be renewed
+            if status_code == 401 {
+                if self.session_auth().is_some() {
+                    return Err(LlmError::SessionExpired {
                         provider: "nearai_chat".to_string(),
                     });
                 }
+                return Err(LlmError::AuthFailed {
+                    provider: "nearai_chat".to_string(),
+                });
+            }
 
-                // Transient errors: retry with backoff
-                if is_retryable_status(status_code) && attempt < max_retries {
-                    let delay = retry_backoff_delay(attempt);
-                    tracing::warn!(
-                        "NEAR AI Chat returned HTTP {} (attempt {}/{}), retrying in {:?}",
-                        status_code,
-                        attempt + 1,
-                        max_retries + 1,
-                        delay,
-                    );
-                    tokio::time::sleep(delay).await;
-                    continue;
-                }
+            // Transient errors: retry with backoff
+            if is_retryable_status(status_code) && attempt < max_retries {
+                let delay = retry_backoff_delay(attempt);
+                tracing::warn!(
+                    "NEAR AI Chat returned HTTP {} (attempt {}/{}), retrying in {:?}",
+                    status_code,
+                    attempt + 1,
+                    max_retries + 1,
+                    delay,
+                );
+                tokio::time::sleep(delay).await;
+                continue;
+            }
 
-                // Non-retryable or exhausted retries
-                if status_code == 429 {
-                    return Err(LlmError::RateLimited {
-                        provider: "nearai_chat".to_string(),
-                        retry_after: None,
-                    });
-                }
-                return Err(LlmError::RequestFailed {
+            // Non-retryable or exhausted retries
+            if status_code == 429 {
+                return Err(LlmError::RateLimited {
                     provider: "nearai_chat".to_string(),
-                    reason: format!("HTTP {}: {}", status, response_text),
+                    retry_after: None,
                 });
             }
-
-            // Success — parse the response
-            return serde_json::from_str(&response_text).map_err(|e| LlmError::InvalidResponse {
+            return Err(LlmError::RequestFailed {
                 provider: "nearai_chat".to_string(),
-                reason: format!("JSON parse error: {}. Raw: {}", e, response_text),
+                reason: format!("HTTP {}: {}", status, response_text),
             });
         }
 
@@ -183,7 +242,10 @@ impl NearAiChatProvider {
         let response = self
             .client
             .get(&url)
-            .header("Authorization", format!("Bearer {}", self.api_key()))
+            .header(
+                "Authorization",
+                format!("Bearer {}", self.bearer_token().await?),
+            )
             .send()
             .await
             .map_err(|e| LlmError::RequestFailed {
@@ -226,6 +288,11 @@ struct ApiModelEntry {
 
 #[async_trait]
 impl LlmProvider for NearAiChatProvider {
+    #[tracing::instrument(
+        name = "llm.complete",
+        skip_all,
+        fields(provider = "nearai_chat", model = %self.model_name())
+    )]
     async fn complete(&self, req: CompletionRequest) -> Result<CompletionResponse, LlmError> {
         let messages: Vec<ChatCompletionMessage> =
             req.messages.into_iter().map(|m| m.into()).collect();
@@ -237,6 +304,8 @@ impl LlmProvider for NearAiChatProvider {
             max_tokens: req.max_tokens,
             tools: None,
             tool_choice: None,
+            stream: None,
+            stream_options: None,
         };
 
         let response: ChatCompletionResponse = self.send_request(&request).await?;
@@ -252,13 +321,7 @@ impl LlmProvider for NearAiChatProvider {
                 })?;
 
         let content = choice.message.content.unwrap_or_default();
-        let finish_reason = match choice.finish_reason.as_deref() {
-            Some("stop") => FinishReason::Stop,
-            Some("length") => FinishReason::Length,
-            Some("tool_calls") => FinishReason::ToolUse,
-            Some("content_filter") => FinishReason::ContentFilter,
-            _ => FinishReason::Unknown,
-        };
+        let finish_reason = parse_finish_reason(choice.finish_reason.as_deref());
 
         Ok(CompletionResponse {
             content,
@@ -269,6 +332,54 @@ impl LlmProvider for NearAiChatProvider {
         })
     }
 
+    #[tracing::instrument(
+        name = "llm.complete_stream",
+        skip_all,
+        fields(provider = "nearai_chat", model = %self.model_name())
+    )]
+    async fn complete_stream(
+        &self,
+        req: CompletionRequest,
+        deltas: mpsc::UnboundedSender<String>,
+    ) -> Result<CompletionResponse, LlmError> {
+        let messages: Vec<ChatCompletionMessage> =
+            req.messages.into_iter().map(|m| m.into()).collect();
+
+        let request = ChatCompletionRequest {
+            model: self.active_model_name(),
+            messages,
+            temperature: req.temperature,
+            max_tokens: req.max_tokens,
+            tools: None,
+            tool_choice: None,
+            stream: Some(true),
+            stream_options: Some(StreamOptions {
+                include_usage: true,
+            }),
+        };
+
+        let response = self.post(&request).await?;
+        let mut body = response.bytes_stream();
+        let mut stream = ChunkStream::default();
+        while !stream.done
+            && let Some(bytes) = body.next().await
+        {
+            for delta in stream.feed(&bytes?)? {
+                let _ = deltas.send(delta);
+            }
+        }
+        stream.into_response()
+    }
+
+    fn supports_streaming(&self) -> bool {
+        true
+    }
+
+    #[tracing::instrument(
+        name = "llm.complete_with_tools",
+        skip_all,
+        fields(provider = "nearai_chat", model = %self.model_name())
+    )]
     async fn complete_with_tools(
         &self,
         req: ToolCompletionRequest,
@@ -302,6 +413,8 @@ impl LlmProvider for NearAiChatProvider {
             max_tokens: req.max_tokens,
             tools: if tools.is_empty() { None } else { Some(tools) },
             tool_choice: req.tool_choice,
+            stream: None,
+            stream_options: None,
         };
 
         let response: ChatCompletionResponse = self.send_request(&request).await?;
@@ -323,8 +436,7 @@ impl LlmProvider for NearAiChatProvider {
             .unwrap_or_default()
             .into_iter()
             .map(|tc| {
-                let arguments = serde_json::from_str(&tc.function.arguments)
-                    .unwrap_or(serde_json::Value::Object(Default::default()));
+                let arguments = parse_tool_arguments(&tc.function.arguments);
                 ToolCall {
                     id: tc.id,
                     name: tc.function.name,
@@ -409,6 +521,10 @@ struct ChatCompletionRequest {
     #[serde(skip_serializing_if = "Option::is_none")]
     max_tokens: Option<u32>,
     #[serde(skip_serializing_if = "Option::is_none")]
+    stream: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    stream_options: Option<StreamOptions>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     tools: Option<Vec<ChatCompletionTool>>,
     #[serde(skip_serializing_if = "Option::is_none")]
     tool_choice: Option<String>,
@@ -577,6 +693,112 @@ struct ChatCompletionToolCallFunction {
     arguments: String,
 }
 
+fn parse_finish_reason(reason: Option<&str>) -> FinishReason {
+    match reason {
+        Some("stop") => FinishReason::Stop,
+        Some("length") => FinishReason::Length,
+        Some("tool_calls") => FinishReason::ToolUse,
+        Some("content_filter") => FinishReason::ContentFilter,
+        _ => FinishReason::Unknown,
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct StreamOptions {
+    /// Ask for a final chunk with the token usage.
+    include_usage: bool,
+}
+
+/// One server-sent event of a streamed completion.
+#[derive(Debug, Deserialize)]
+struct ChatCompletionChunk {
+    #[serde(default)]
+    choices: Vec<ChatCompletionChunkChoice>,
+    #[serde(default)]
+    usage: Option<ChatCompletionUsage>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ChatCompletionChunkChoice {
+    #[serde(default)]
+    delta: ChatCompletionDelta,
+    finish_reason: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct ChatCompletionDelta {
+    content: Option<String>,
+}
+
+/// Assembles a streamed completion from the bytes of its event stream.
+#[derive(Debug, Default)]
+struct ChunkStream {
+    /// Bytes of an incomplete line.
+    pending: Vec<u8>,
+    content: String,
+    finish_reason: Option<String>,
+    usage: Option<ChatCompletionUsage>,
+    /// Whether the `[DONE]` event arrived.
+    done: bool,
+}
+
+impl ChunkStream {
+    /// Consume `bytes`, returning the text deltas in the events they complete.
+    fn feed(&mut self, bytes: &[u8]) -> Result<Vec<String>, LlmError> {
+        self.pending.extend_from_slice(bytes);
+        let mut deltas = Vec::new();
+        while let Some(end) = self.pending.iter().position(|b| *b == b'\n') {
+            let line: Vec<u8> = self.pending.drain(..=end).collect();
+            let line = String::from_utf8_lossy(&line);
+            let Some(data) = line.trim().strip_prefix("data:") else {
+                continue;
+            };
+            let data = data.trim();
+            if data == "[DONE]" {
+                self.done = true;
+                break;
+            }
+            let chunk: ChatCompletionChunk =
+                serde_json::from_str(data).map_err(|e| LlmError::InvalidResponse {
+                    provider: "nearai_chat".to_string(),
+                    reason: format!("JSON parse error in stream: {}. Raw: {}", e, data),
+                })?;
+            if let Some(usage) = chunk.usage {
+                self.usage = Some(usage);
+            }
+            for choice in chunk.choices {
+                if let Some(reason) = choice.finish_reason {
+                    self.finish_reason = Some(reason);
+                }
+                if let Some(text) = choice.delta.content.filter(|t| !t.is_empty()) {
+                    self.content.push_str(&text);
+                    deltas.push(text);
+                }
+            }
+        }
+        Ok(deltas)
+    }
+
+    fn into_response(self) -> Result<CompletionResponse, LlmError> {
+        if !self.done && self.finish_reason.is_none() {
+            return Err(LlmError::InvalidResponse {
+                provider: "nearai_chat".to_string(),
+                reason: "stream ended before the completion finished".to_string(),
+            });
+        }
+        let (input_tokens, output_tokens) = self
+            .usage
+            .map_or((0, 0), |u| (u.prompt_tokens, u.completion_tokens));
+        Ok(CompletionResponse {
+            content: self.content,
+            finish_reason: parse_finish_reason(self.finish_reason.as_deref()),
+            input_tokens,
+            output_tokens,
+            response_id: None,
+        })
+    }
+}
+
 #[derive(Debug, Deserialize)]
 struct ChatCompletionUsage {
     prompt_tokens: u32,
@@ -777,4 +999,39 @@ mod tests {
         assert!(text.starts_with("Let me check that."));
         assert!(text.contains("[Called tool `search`"));
     }
+
+    #[test]
+    fn test_chunk_stream_assembles_split_events() {
+        let mut stream = ChunkStream::default();
+        let first = stream
+            .feed(b"data: {\"choices\":[{\"delta\":{\"role\":\"assistant\"}}]}\n\ndata: {\"choices\":[{\"delta\":{\"content\":\"Hel")
+            .unwrap();
+        assert!(first.is_empty());
+        let rest = stream
+            .feed(concat!(
+                "lo\"}}]}\n\n",
+                ": keep-alive\n",
+                "data: {\"choices\":[{\"delta\":{\"content\":\" world\"},\"finish_reason\":\"stop\"}]}\n\n",
+                "data: {\"choices\":[],\"usage\":{\"prompt_tokens\":7,\"completion_tokens\":2,\"total_tokens\":9}}\n\n",
+                "data: [DONE]\n\n",
+            ).as_bytes())
+            .unwrap();
+        assert_eq!(rest, vec!["Hello".to_string(), " world".to_string()]);
+        assert!(stream.done);
+
+        let response = stream.into_response().unwrap();
+        assert_eq!(response.content, "Hello world");
+        assert_eq!(response.finish_reason, FinishReason::Stop);
+        assert_eq!((response.input_tokens, response.output_tokens), (7, 2));
+    }
+
+    #[test]
+    fn test_chunk_stream_rejects_truncated_stream() {
+        let mut stream = ChunkStream::default();
+        stream
+            .feed(b"data: {\"choices\":[{\"delta\":{\"content\":\"Hi\"}}]}\n")
+            .unwrap();
+        assert!(stream.into_response().is_err());
+        assert!(ChunkStream::default().feed(b"data: {oops\n").is_err());
+    }
 }
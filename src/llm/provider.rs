@@ -3,6 +3,7 @@
 use async_trait::async_trait;
 use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
+use tokio::sync::mpsc;
 
 use crate::error::LlmError;
 
@@ -31,6 +32,59 @@ pub struct ChatMessage {
     /// to appear on the assistant message preceding tool result messages).
     #[serde(skip_serializing_if = "Option::is_none")]
     pub tool_calls: Option<Vec<ToolCall>>,
+    /// Image content blocks attached to a user message. Only sent to
+    /// providers that report `supports_vision()`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub images: Vec<ImageContent>,
+}
+
+/// An image content block, base64-encoded.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ImageContent {
+    /// MIME type, e.g. `image/png`.
+    pub media_type: String,
+    /// Base64-encoded image bytes (standard alphabet, padded).
+    pub data: String,
+}
+
+impl ImageContent {
+    /// Create an image block from raw bytes.
+    pub fn from_bytes(media_type: impl Into<String>, bytes: &[u8]) -> Self {
+        use base64::Engine as _;
+        Self {
+            media_type: media_type.into(),
+            data: base64::engine::general_purpose::STANDARD.encode(bytes),
+        }
+    }
+
+    /// Parse a `data:image/...;base64,...` URI.
+    ///
+    /// This is how image blobs are stored in workspace documents.
+    pub fn from_data_uri(uri: &str) -> Option<Self> {
+        let rest = uri.trim().strip_prefix("data:")?;
+        let (header, data) = rest.split_once(',')?;
+        let media_type = header.strip_suffix(";base64")?;
+        if !media_type.starts_with("image/") || data.is_empty() {
+            return None;
+        }
+        Some(Self {
+            media_type: media_type.to_string(),
+            data: data.to_string(),
+        })
+    }
+
+    /// Render as a `data:` URI (used by OpenAI-style `image_url` parts).
+    pub fn to_data_uri(&self) -> String {
+        format!("data:{};base64,{}", self.media_type, self.data)
+    }
+
+    /// Decode the base64 payload.
+    pub fn decode(&self) -> Option<Vec<u8>> {
+        use base64::Engine as _;
+        base64::engine::general_purpose::STANDARD
+            .decode(self.data.as_bytes())
+            .ok()
+    }
 }
 
 impl ChatMessage {
@@ -42,6 +96,7 @@ impl ChatMessage {
             tool_call_id: None,
             name: None,
             tool_calls: None,
+            images: Vec::new(),
         }
     }
 
@@ -53,6 +108,7 @@ impl ChatMessage {
             tool_call_id: None,
             name: None,
             tool_calls: None,
+            images: Vec::new(),
         }
     }
 
@@ -64,6 +120,7 @@ impl ChatMessage {
             tool_call_id: None,
             name: None,
             tool_calls: None,
+            images: Vec::new(),
         }
     }
 
@@ -82,6 +139,7 @@ impl ChatMessage {
             } else {
                 Some(tool_calls)
             },
+            images: Vec::new(),
         }
     }
 
@@ -97,8 +155,15 @@ impl ChatMessage {
             tool_call_id: Some(tool_call_id.into()),
             name: Some(name.into()),
             tool_calls: None,
+            images: Vec::new(),
         }
     }
+
+    /// Attach image content blocks to this message.
+    pub fn with_images(mut self, images: Vec<ImageContent>) -> Self {
+        self.images = images;
+        self
+    }
 }
 
 /// Request for a chat completion.
@@ -149,7 +214,8 @@ pub struct CompletionResponse {
 }
 
 /// Why the completion finished.
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum FinishReason {
     Stop,
     Length,
@@ -174,6 +240,18 @@ pub struct ToolCall {
     pub arguments: serde_json::Value,
 }
 
+/// Parse a tool call's raw `arguments` string.
+///
+/// Empty input means "no arguments". Malformed JSON is returned as a
+/// `Value::String` holding the raw text rather than silently replaced with
+/// `{}`, so schema validation can report it back to the model.
+pub fn parse_tool_arguments(raw: &str) -> serde_json::Value {
+    if raw.trim().is_empty() {
+        return serde_json::Value::Object(Default::default());
+    }
+    serde_json::from_str(raw).unwrap_or_else(|_| serde_json::Value::String(raw.to_string()))
+}
+
 /// Result of a tool execution to send back to the LLM.
 #[derive(Debug, Clone)]
 pub struct ToolResult {
@@ -262,6 +340,26 @@ pub trait LlmProvider: Send + Sync {
     /// Complete a chat conversation.
     async fn complete(&self, request: CompletionRequest) -> Result<CompletionResponse, LlmError>;
 
+    /// Complete a chat conversation, sending text to `deltas` as it is
+    /// generated. The response still holds the full text.
+    ///
+    /// Default sends the whole text as one delta once it is complete; see
+    /// [`Self::supports_streaming`].
+    async fn complete_stream(
+        &self,
+        request: CompletionRequest,
+        deltas: mpsc::UnboundedSender<String>,
+    ) -> Result<CompletionResponse, LlmError> {
+        let response = self.complete(request).await?;
+        let _ = deltas.send(response.content.clone());
+        Ok(response)
+    }
+
+    /// Whether [`Self::complete_stream`] delivers text as it is generated.
+    fn supports_streaming(&self) -> bool {
+        false
+    }
+
     /// Complete with tool use support.
     async fn complete_with_tools(
         &self,
@@ -313,6 +411,14 @@ pub trait LlmProvider: Send + Sync {
         None
     }
 
+    /// Whether the active model accepts image content blocks.
+    ///
+    /// Providers that return `false` never see `ChatMessage::images`; the
+    /// agent replaces them with a text description instead.
+    fn supports_vision(&self) -> bool {
+        false
+    }
+
     /// Calculate cost for a completion.
     fn calculate_cost(&self, input_tokens: u32, output_tokens: u32) -> Decimal {
         let (input_cost, output_cost) = self.cost_per_token();
@@ -535,7 +535,7 @@ pub struct SuccessEvaluation {
 }
 
 /// Extract JSON from text that might contain other content.
-fn extract_json(text: &str) -> Option<&str> {
+pub(crate) fn extract_json(text: &str) -> Option<&str> {
     // Find the first { and last } to extract JSON
     let start = text.find('{')?;
     let end = text.rfind('}')?;
@@ -10,8 +10,8 @@ use rig::completion::{
     ToolDefinition as RigToolDefinition, Usage as RigUsage,
 };
 use rig::message::{
-    Message as RigMessage, ToolChoice as RigToolChoice, ToolFunction, ToolResult as RigToolResult,
-    ToolResultContent, UserContent,
+    ImageMediaType, Message as RigMessage, MimeType, ToolChoice as RigToolChoice, ToolFunction,
+    ToolResult as RigToolResult, ToolResultContent, UserContent,
 };
 use rust_decimal::Decimal;
 use serde::Serialize;
@@ -24,6 +24,7 @@ use crate::llm::provider::{
     ToolCall as IronToolCall, ToolCompletionRequest, ToolCompletionResponse,
     ToolDefinition as IronToolDefinition,
 };
+use crate::llm::vision;
 
 /// Adapter that wraps a rig-core `CompletionModel` and implements `LlmProvider`.
 pub struct RigAdapter<M: CompletionModel> {
@@ -31,6 +32,7 @@ pub struct RigAdapter<M: CompletionModel> {
     model_name: String,
     input_cost: Decimal,
     output_cost: Decimal,
+    vision: bool,
 }
 
 impl<M: CompletionModel> RigAdapter<M> {
@@ -39,11 +41,13 @@ impl<M: CompletionModel> RigAdapter<M> {
         let name = model_name.into();
         let (input_cost, output_cost) =
             costs::model_cost(&name).unwrap_or_else(costs::default_cost);
+        let vision = vision::model_supports_vision(&name);
         Self {
             model,
             model_name: name,
             input_cost,
             output_cost,
+            vision,
         }
     }
 }
@@ -71,7 +75,25 @@ fn convert_messages(messages: &[ChatMessage]) -> (Option<String>, Vec<RigMessage
                 }
             }
             crate::llm::Role::User => {
-                history.push(RigMessage::user(&msg.content));
+                if msg.images.is_empty() {
+                    history.push(RigMessage::user(&msg.content));
+                    continue;
+                }
+                let mut contents: Vec<UserContent> = Vec::new();
+                if !msg.content.is_empty() {
+                    contents.push(UserContent::text(&msg.content));
+                }
+                for img in &msg.images {
+                    contents.push(UserContent::image_base64(
+                        img.data.clone(),
+                        ImageMediaType::from_mime_type(&img.media_type),
+                        None,
+                    ));
+                }
+                match OneOrMany::many(contents) {
+                    Ok(many) => history.push(RigMessage::User { content: many }),
+                    Err(_) => history.push(RigMessage::user(&msg.content)),
+                }
             }
             crate::llm::Role::Assistant => {
                 if let Some(ref tool_calls) = msg.tool_calls {
@@ -148,10 +170,8 @@ fn extract_response(
 
     for content in choice.iter() {
         match content {
-            AssistantContent::Text(t) => {
-                if !t.text.is_empty() {
-                    text_parts.push(t.text.clone());
-                }
+            AssistantContent::Text(t) if !t.text.is_empty() => {
+                text_parts.push(t.text.clone());
             }
             AssistantContent::ToolCall(tc) => {
                 tool_calls.push(IronToolCall {
@@ -230,6 +250,11 @@ where
         (self.input_cost, self.output_cost)
     }
 
+    #[tracing::instrument(
+        name = "llm.complete",
+        skip_all,
+        fields(provider = "rig", model = %self.model_name())
+    )]
     async fn complete(&self, request: CompletionRequest) -> Result<CompletionResponse, LlmError> {
         let (preamble, history) = convert_messages(&request.messages);
 
@@ -262,6 +287,11 @@ where
         })
     }
 
+    #[tracing::instrument(
+        name = "llm.complete_with_tools",
+        skip_all,
+        fields(provider = "rig", model = %self.model_name())
+    )]
     async fn complete_with_tools(
         &self,
         request: ToolCompletionRequest,
@@ -300,6 +330,10 @@ where
         })
     }
 
+    fn supports_vision(&self) -> bool {
+        self.vision
+    }
+
     fn active_model_name(&self) -> String {
         self.model_name.clone()
     }
@@ -380,6 +414,27 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_convert_messages_user_with_images() {
+        let img = crate::llm::ImageContent {
+            media_type: "image/png".to_string(),
+            data: "aGVsbG8=".to_string(),
+        };
+        let messages = vec![ChatMessage::user("look").with_images(vec![img])];
+        let (_, history) = convert_messages(&messages);
+        match &history[0] {
+            RigMessage::User { content } => {
+                assert_eq!(content.len(), 2);
+                assert!(
+                    content
+                        .iter()
+                        .any(|c| matches!(c, UserContent::Image(i) if i.media_type == Some(ImageMediaType::PNG)))
+                );
+            }
+            other => panic!("Expected user message, got: {:?}", other),
+        }
+    }
+
     #[test]
     fn test_convert_tools() {
         let tools = vec![IronToolDefinition {
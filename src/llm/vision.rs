@@ -0,0 +1,266 @@
+//! Vision input helpers.
+//!
+//! Images currently arrive only through the web gateway's chat endpoint,
+//! either inline or as workspace blobs (documents holding a
+//! `data:image/...;base64,` URI). WASM channels don't forward attachments
+//! yet, and the NEAR AI providers report no vision support, so in practice
+//! this needs a rig-core backend with a vision model.
+//!
+//! Before they reach the LLM images are downscaled so the longest edge fits
+//! the provider limits, and for models without vision they are replaced by
+//! a short text description so the model at least knows something was
+//! attached.
+
+use std::io::Cursor;
+
+use image::{GenericImageView, ImageFormat, imageops::FilterType};
+
+use crate::llm::provider::{ChatMessage, ImageContent, Role};
+
+/// Longest edge (in pixels) an image is downscaled to before sending.
+///
+/// Anthropic recommends <= 1568px; OpenAI tiles at 2048px then 768px, so
+/// this keeps token usage predictable on both.
+pub const MAX_IMAGE_DIMENSION: u32 = 1568;
+
+/// Maximum number of images attached to a single message.
+pub const MAX_IMAGES_PER_MESSAGE: usize = 8;
+
+/// Heuristic check whether a model name refers to a vision-capable model.
+///
+/// Used for providers that have no capability endpoint (rig-core backends).
+pub fn model_supports_vision(model: &str) -> bool {
+    let m = model.to_lowercase();
+    const VISION_MARKERS: &[&str] = &[
+        "gpt-4o",
+        "gpt-4.1",
+        "gpt-4-turbo",
+        "gpt-4-vision",
+        "gpt-5",
+        "claude-3",
+        "claude-sonnet-4",
+        "claude-opus-4",
+        "claude-haiku-4",
+        "gemini",
+        "gemma3",
+        "llava",
+        "bakllava",
+        "pixtral",
+        "minicpm-v",
+        "vision",
+        "-vl",
+    ];
+    // OpenAI reasoning models have no marker in their names, and some of
+    // them (o1-mini, o1-preview, o3-mini) take text only, so these are
+    // matched exactly, ignoring any provider prefix and date suffix.
+    const VISION_REASONING_MODELS: &[&str] = &["o1", "o1-pro", "o3", "o3-pro", "o4-mini"];
+    let name = m.rsplit_once('/').map_or(m.as_str(), |(_, name)| name);
+    let name = name.split_once("-20").map_or(name, |(base, _)| base);
+    VISION_MARKERS.iter().any(|marker| m.contains(marker))
+        || VISION_REASONING_MODELS.contains(&name)
+}
+
+/// Downscale an image so its longest edge is at most `max_dim` pixels.
+///
+/// Images that already fit are returned unchanged (no re-encoding). JPEG
+/// inputs are re-encoded as JPEG, everything else as PNG to keep alpha.
+/// Returns `None` if the payload cannot be decoded as an image.
+pub fn downscale(image: &ImageContent, max_dim: u32) -> Option<ImageContent> {
+    let bytes = image.decode()?;
+    let decoded = image::load_from_memory(&bytes).ok()?;
+    let (width, height) = decoded.dimensions();
+    if width <= max_dim && height <= max_dim {
+        return Some(image.clone());
+    }
+
+    let resized = decoded.resize(max_dim, max_dim, FilterType::Triangle);
+    let (format, media_type) = if image.media_type == "image/jpeg" {
+        (ImageFormat::Jpeg, "image/jpeg")
+    } else {
+        (ImageFormat::Png, "image/png")
+    };
+    // The JPEG encoder rejects alpha channels.
+    let resized = if format == ImageFormat::Jpeg {
+        image::DynamicImage::ImageRgb8(resized.to_rgb8())
+    } else {
+        resized
+    };
+
+    let mut out = Cursor::new(Vec::new());
+    resized.write_to(&mut out, format).ok()?;
+    Some(ImageContent::from_bytes(media_type, out.get_ref()))
+}
+
+/// Text stand-in for an image when the model cannot see it.
+pub fn describe(image: &ImageContent) -> String {
+    let dimensions = image
+        .decode()
+        .and_then(|bytes| {
+            image::ImageReader::new(Cursor::new(bytes))
+                .with_guessed_format()
+                .ok()?
+                .into_dimensions()
+                .ok()
+        })
+        .map(|(w, h)| format!(", {}x{}", w, h))
+        .unwrap_or_default();
+    format!(
+        "[Image attached ({}{}). The current model cannot view images; \
+         ask the user to describe it if its contents matter.]",
+        image.media_type, dimensions
+    )
+}
+
+/// Attach images to the last user message in `messages`.
+///
+/// With `supports_vision`, images are downscaled and attached as content
+/// blocks; undecodable images are described instead. Without it, every
+/// image is replaced by a text description appended to the message.
+pub fn attach_to_last_user(
+    messages: &mut [ChatMessage],
+    images: &[ImageContent],
+    supports_vision: bool,
+) {
+    if images.is_empty() {
+        return;
+    }
+    let Some(msg) = messages.iter_mut().rev().find(|m| m.role == Role::User) else {
+        return;
+    };
+
+    let mut notes = Vec::new();
+    for (i, img) in images.iter().enumerate() {
+        if i >= MAX_IMAGES_PER_MESSAGE {
+            notes.push(format!(
+                "[{} more image(s) omitted: at most {} images per message.]",
+                images.len() - MAX_IMAGES_PER_MESSAGE,
+                MAX_IMAGES_PER_MESSAGE
+            ));
+            break;
+        }
+        if !supports_vision {
+            notes.push(describe(img));
+            continue;
+        }
+        match downscale(img, MAX_IMAGE_DIMENSION) {
+            Some(scaled) => msg.images.push(scaled),
+            None => {
+                tracing::warn!("Dropping undecodable {} attachment", img.media_type);
+                notes.push(format!(
+                    "[Image attached ({}) could not be decoded.]",
+                    img.media_type
+                ));
+            }
+        }
+    }
+
+    for note in notes {
+        if !msg.content.is_empty() {
+            msg.content.push('\n');
+        }
+        msg.content.push_str(&note);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn png(width: u32, height: u32) -> ImageContent {
+        let img = image::DynamicImage::new_rgba8(width, height);
+        let mut out = Cursor::new(Vec::new());
+        img.write_to(&mut out, ImageFormat::Png).unwrap();
+        ImageContent::from_bytes("image/png", out.get_ref())
+    }
+
+    fn dims(image: &ImageContent) -> (u32, u32) {
+        image::load_from_memory(&image.decode().unwrap())
+            .unwrap()
+            .dimensions()
+    }
+
+    #[test]
+    fn test_model_supports_vision() {
+        assert!(model_supports_vision("gpt-4o-mini"));
+        assert!(model_supports_vision("claude-sonnet-4-20250514"));
+        assert!(model_supports_vision("llama3.2-vision"));
+        assert!(model_supports_vision("qwen2.5-vl-72b"));
+        assert!(!model_supports_vision("gpt-3.5-turbo"));
+        assert!(!model_supports_vision("llama3"));
+    }
+
+    #[test]
+    fn test_reasoning_model_vision_matched_exactly() {
+        assert!(model_supports_vision("o1"));
+        assert!(model_supports_vision("o3-2025-04-16"));
+        assert!(model_supports_vision("o4-mini"));
+        assert!(model_supports_vision("openai/o3"));
+        assert!(!model_supports_vision("o1-mini"));
+        assert!(!model_supports_vision("o1-mini-2024-09-12"));
+        assert!(!model_supports_vision("o1-preview"));
+        assert!(!model_supports_vision("o3-mini"));
+    }
+
+    #[test]
+    fn test_downscale_keeps_small_images() {
+        let img = png(64, 32);
+        assert_eq!(downscale(&img, 128).unwrap(), img);
+    }
+
+    #[test]
+    fn test_downscale_preserves_aspect_ratio() {
+        let scaled = downscale(&png(400, 200), 100).unwrap();
+        assert_eq!(scaled.media_type, "image/png");
+        assert_eq!(dims(&scaled), (100, 50));
+    }
+
+    #[test]
+    fn test_downscale_rejects_garbage() {
+        let bogus = ImageContent::from_bytes("image/png", b"not an image");
+        assert!(downscale(&bogus, 100).is_none());
+    }
+
+    #[test]
+    fn test_data_uri_roundtrip() {
+        let img = png(2, 2);
+        let parsed = ImageContent::from_data_uri(&img.to_data_uri()).unwrap();
+        assert_eq!(parsed, img);
+        assert!(ImageContent::from_data_uri("data:text/plain;base64,aGk=").is_none());
+        assert!(ImageContent::from_data_uri("# just markdown").is_none());
+    }
+
+    #[test]
+    fn test_attach_with_vision() {
+        let mut messages = vec![
+            ChatMessage::user("first"),
+            ChatMessage::assistant("ok"),
+            ChatMessage::user("what is this?"),
+        ];
+        attach_to_last_user(&mut messages, &[png(4, 4)], true);
+        assert!(messages[0].images.is_empty());
+        assert_eq!(messages[2].images.len(), 1);
+        assert_eq!(messages[2].content, "what is this?");
+    }
+
+    #[test]
+    fn test_attach_without_vision_falls_back_to_text() {
+        let mut messages = vec![ChatMessage::user("what is this?")];
+        attach_to_last_user(&mut messages, &[png(4, 3)], false);
+        assert!(messages[0].images.is_empty());
+        assert!(
+            messages[0]
+                .content
+                .starts_with("what is this?\n[Image attached")
+        );
+        assert!(messages[0].content.contains("image/png, 4x3"));
+    }
+
+    #[test]
+    fn test_attach_caps_image_count() {
+        let mut messages = vec![ChatMessage::user("")];
+        let images = vec![png(1, 1); MAX_IMAGES_PER_MESSAGE + 2];
+        attach_to_last_user(&mut messages, &images, true);
+        assert_eq!(messages[0].images.len(), MAX_IMAGES_PER_MESSAGE);
+        assert!(messages[0].content.contains("2 more image(s) omitted"));
+    }
+}
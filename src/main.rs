@@ -6,39 +6,65 @@ use clap::Parser;
 use tracing_subscriber::{EnvFilter, layer::SubscriberExt, util::SubscriberInitExt};
 
 use ironclaw::{
-    agent::{Agent, AgentDeps, SessionManager},
+    agent::{
+        Agent, AgentBus, AgentDeps, AgentRegistry, RegisteredWorker, SessionManager,
+        ShutdownCoordinator, Supervisor, SupervisorLimits, UserLimiter, WorkerRuntime,
+    },
+    audit::AuditLog,
     channels::{
-        ChannelManager, GatewayChannel, HttpChannel, ReplChannel, WebhookServer,
+        ChannelManager, GatewayChannel, HttpChannel, ReplChannel, RoutineHooks, WebhookServer,
         WebhookServerConfig,
         wasm::{
             RegisteredEndpoint, SharedWasmChannel, WasmChannelLoader, WasmChannelRouter,
             WasmChannelRuntime, WasmChannelRuntimeConfig, create_wasm_channel_router,
         },
-        web::log_layer::{LogBroadcaster, WebLogLayer},
+        web::{
+            api_keys::ApiKeys,
+            log_layer::{LogBroadcaster, WebLogLayer},
+        },
     },
     cli::{
-        Cli, Command, run_mcp_command, run_pairing_command, run_status_command, run_tool_command,
+        Cli, Command, ScenarioCommand, run_mcp_command, run_pairing_command, run_skill_command,
+        run_status_command, run_tool_command,
     },
     config::Config,
+    config_reload::{ConfigReloader, ReloadableConfig, SettingsSource},
+    contacts::ContactBook,
     context::ContextManager,
+    db::lease::LeaseManager,
     extensions::ExtensionManager,
+    health::HealthChecker,
     llm::{
-        FailoverProvider, LlmProvider, SessionConfig, create_cheap_llm_provider,
-        create_llm_provider, create_llm_provider_with_config, create_session_manager,
+        FailoverProvider, LlmProvider, MeteredProvider, ModelProviders, SessionConfig,
+        create_cheap_llm_provider, create_llm_provider, create_llm_provider_with_config,
+        create_session_manager,
     },
+    metrics::{Metrics, PoolStats},
     orchestrator::{
         ContainerJobConfig, ContainerJobManager, OrchestratorApi, TokenStore,
         api::OrchestratorState,
     },
     pairing::PairingStore,
-    safety::SafetyLayer,
+    safety::{Moderation, Permissions, SafetyLayer},
     secrets::SecretsStore,
+    skills::SkillRegistry,
     tools::{
         ToolRegistry,
+        builtin::SuperviseTool,
         mcp::{McpClient, McpSessionManager, config::load_mcp_servers_from_db, is_authenticated},
         wasm::{WasmToolLoader, WasmToolRuntime, load_dev_tools},
     },
-    workspace::{EmbeddingProvider, NearAiEmbeddings, OpenAiEmbeddings, Workspace},
+    webhooks::Webhooks,
+    workspace::{
+        DocumentSummarizer, EmbeddingProvider, GitStorage, GitSyncReport, HashEmbeddings,
+        NearAiEmbeddings, OpenAiEmbeddings, SeedTemplates, SharedNamespaces, SparseEncoder,
+        TeiSparseEncoder, Workspace, WorkspaceEvents, WorkspaceMirror,
+        conflicts::ConflictChecker,
+        graph::{GraphExtractor, KnowledgeGraph},
+        rollup::RollupSummarizer,
+        topics::TopicLabeler,
+        usage_report::UsageReporter,
+    },
 };
 
 #[cfg(feature = "libsql")]
@@ -64,6 +90,15 @@ async fn main() -> anyhow::Result<()> {
 
             return run_tool_command(tool_cmd.clone()).await;
         }
+        Some(Command::Skill(skill_cmd)) => {
+            tracing_subscriber::fmt()
+                .with_env_filter(
+                    EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("warn")),
+                )
+                .init();
+
+            return run_skill_command(skill_cmd.clone()).await;
+        }
         Some(Command::Config(config_cmd)) => {
             // Config commands need DB access for settings
             tracing_subscriber::fmt()
@@ -74,6 +109,20 @@ async fn main() -> anyhow::Result<()> {
 
             return ironclaw::cli::run_config_command(config_cmd.clone()).await;
         }
+        Some(Command::Agents(agents_cmd)) => {
+            tracing_subscriber::fmt()
+                .with_env_filter(
+                    EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("warn")),
+                )
+                .init();
+
+            let config = Config::from_env()
+                .await
+                .map_err(|e| anyhow::anyhow!("{}", e))?;
+            let (db, embeddings) = connect_memory_backend(&config).await?;
+
+            return ironclaw::cli::run_agents_command(agents_cmd.clone(), db, embeddings).await;
+        }
         Some(Command::Mcp(mcp_cmd)) => {
             // Simple logging for MCP commands
             tracing_subscriber::fmt()
@@ -96,60 +145,149 @@ async fn main() -> anyhow::Result<()> {
                 .await
                 .map_err(|e| anyhow::anyhow!("{}", e))?;
 
-            // Set up embeddings if available
-            let session = ironclaw::llm::create_session_manager(ironclaw::llm::SessionConfig {
+            let (db, embeddings) = connect_memory_backend(&config).await?;
+            return ironclaw::cli::run_memory_command_with_db(
+                mem_cmd.clone(),
+                db,
+                embeddings,
+                sparse_encoder(&config),
+            )
+            .await;
+        }
+        Some(Command::Search(args)) => {
+            tracing_subscriber::fmt()
+                .with_env_filter(
+                    EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("warn")),
+                )
+                .init();
+
+            let config = Config::from_env()
+                .await
+                .map_err(|e| anyhow::anyhow!("{}", e))?;
+            let (db, embeddings) = connect_memory_backend(&config).await?;
+
+            return ironclaw::cli::run_search_command(
+                args.clone(),
+                db,
+                embeddings,
+                sparse_encoder(&config),
+            )
+            .await;
+        }
+        Some(Command::Eval(args)) => {
+            tracing_subscriber::fmt()
+                .with_env_filter(
+                    EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("warn")),
+                )
+                .init();
+
+            let config = Config::from_env()
+                .await
+                .map_err(|e| anyhow::anyhow!("{}", e))?;
+            let (db, embeddings) = connect_memory_backend(&config).await?;
+            let llm = if args.grade {
+                let session = create_session_manager(SessionConfig {
+                    auth_base_url: config.llm.nearai.auth_base_url.clone(),
+                    session_path: config.llm.nearai.session_path.clone(),
+                })
+                .await;
+                Some(create_llm_provider(&config.llm, session)?)
+            } else {
+                None
+            };
+
+            return ironclaw::cli::run_eval_command(args.clone(), db, embeddings, llm).await;
+        }
+        Some(Command::Pairing(pairing_cmd)) => {
+            tracing_subscriber::fmt()
+                .with_env_filter(
+                    EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("warn")),
+                )
+                .init();
+
+            return run_pairing_command(pairing_cmd.clone()).map_err(|e| anyhow::anyhow!("{}", e));
+        }
+        Some(Command::Replay(args)) => {
+            tracing_subscriber::fmt()
+                .with_env_filter(
+                    EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("warn")),
+                )
+                .init();
+
+            let mut config = Config::from_env()
+                .await
+                .map_err(|e| anyhow::anyhow!("{}", e))?;
+            if let Some(ref model) = args.model {
+                config.llm.set_model(model);
+            }
+
+            let session = create_session_manager(SessionConfig {
                 auth_base_url: config.llm.nearai.auth_base_url.clone(),
                 session_path: config.llm.nearai.session_path.clone(),
             })
             .await;
+            let llm = create_llm_provider(&config.llm, session)?;
 
-            let embeddings: Option<Arc<dyn ironclaw::workspace::EmbeddingProvider>> =
-                if config.embeddings.enabled {
-                    match config.embeddings.provider.as_str() {
-                        "nearai" => Some(Arc::new(
-                            ironclaw::workspace::NearAiEmbeddings::new(
-                                &config.llm.nearai.base_url,
-                                session,
-                            )
-                            .with_model(&config.embeddings.model, 1536),
-                        )),
-                        _ => {
-                            if let Some(api_key) = config.embeddings.openai_api_key() {
-                                let dim = match config.embeddings.model.as_str() {
-                                    "text-embedding-3-large" => 3072,
-                                    _ => 1536,
-                                };
-                                Some(Arc::new(ironclaw::workspace::OpenAiEmbeddings::with_model(
-                                    api_key,
-                                    &config.embeddings.model,
-                                    dim,
-                                )))
-                            } else {
-                                None
-                            }
-                        }
-                    }
-                } else {
-                    None
-                };
-
-            // Create a Database-trait-backed workspace for the memory command
             let db: Arc<dyn ironclaw::db::Database> =
                 ironclaw::db::connect_from_config(&config.database)
                     .await
                     .map_err(|e| anyhow::anyhow!("{}", e))?;
 
-            return ironclaw::cli::run_memory_command_with_db(mem_cmd.clone(), db, embeddings)
-                .await;
+            return ironclaw::cli::run_replay_command(args.clone(), db, llm).await;
         }
-        Some(Command::Pairing(pairing_cmd)) => {
+        Some(Command::Scenario(scenario_cmd)) => {
             tracing_subscriber::fmt()
                 .with_env_filter(
                     EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("warn")),
                 )
                 .init();
 
-            return run_pairing_command(pairing_cmd.clone()).map_err(|e| anyhow::anyhow!("{}", e));
+            let mut config = Config::from_env()
+                .await
+                .map_err(|e| anyhow::anyhow!("{}", e))?;
+
+            return match scenario_cmd {
+                ScenarioCommand::Run { paths, model, json } => {
+                    if let Some(model) = model {
+                        config.llm.set_model(model);
+                    }
+                    let session = create_session_manager(SessionConfig {
+                        auth_base_url: config.llm.nearai.auth_base_url.clone(),
+                        session_path: config.llm.nearai.session_path.clone(),
+                    })
+                    .await;
+                    let llm = create_llm_provider(&config.llm, session)?;
+                    ironclaw::cli::run_scenarios(paths, llm, *json).await
+                }
+                ScenarioCommand::Record {
+                    job_id,
+                    name,
+                    output,
+                } => {
+                    let db: Arc<dyn ironclaw::db::Database> =
+                        ironclaw::db::connect_from_config(&config.database)
+                            .await
+                            .map_err(|e| anyhow::anyhow!("{}", e))?;
+                    ironclaw::cli::record_scenario(db, *job_id, name.clone(), output.clone()).await
+                }
+            };
+        }
+        Some(Command::Template(template_cmd)) => {
+            tracing_subscriber::fmt()
+                .with_env_filter(
+                    EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("warn")),
+                )
+                .init();
+
+            let config = Config::from_env()
+                .await
+                .map_err(|e| anyhow::anyhow!("{}", e))?;
+            let db: Arc<dyn ironclaw::db::Database> =
+                ironclaw::db::connect_from_config(&config.database)
+                    .await
+                    .map_err(|e| anyhow::anyhow!("{}", e))?;
+
+            return ironclaw::cli::run_template_command(template_cmd.clone(), db, &config).await;
         }
         Some(Command::Status) => {
             tracing_subscriber::fmt()
@@ -160,6 +298,15 @@ async fn main() -> anyhow::Result<()> {
 
             return run_status_command().await;
         }
+        Some(Command::Doctor) => {
+            tracing_subscriber::fmt()
+                .with_env_filter(
+                    EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("warn")),
+                )
+                .init();
+
+            return ironclaw::cli::run_doctor_command().await;
+        }
         Some(Command::Worker {
             job_id,
             orchestrator_url,
@@ -263,11 +410,14 @@ async fn main() -> anyhow::Result<()> {
             }
             return Ok(());
         }
-        None | Some(Command::Run) => {
+        None | Some(Command::Run) | Some(Command::Repl) => {
             // Continue to run agent
         }
     }
 
+    // `repl` runs the full agent but only talks to this terminal.
+    let repl_only = matches!(cli.command, Some(Command::Repl));
+
     // Load .env files early so DATABASE_URL (and any other vars) are
     // available to all subsequent env-based config resolution.
     // Standard ./.env first (higher priority), then ~/.ironclaw/.env.
@@ -299,6 +449,9 @@ async fn main() -> anyhow::Result<()> {
         }
         Err(e) => return Err(e.into()),
     };
+    if repl_only {
+        config.restrict_to_repl();
+    }
 
     // Initialize session manager and authenticate before channel setup
     let session_config = SessionConfig {
@@ -315,14 +468,26 @@ async fn main() -> anyhow::Result<()> {
         session.ensure_authenticated().await?;
     }
 
-    // Initialize tracing
-    let env_filter = EnvFilter::try_from_default_env()
-        .unwrap_or_else(|_| EnvFilter::new("ironclaw=info,tower_http=warn"));
+    // Initialize tracing. Info logs would interleave with the conversation
+    // in a REPL session, so it defaults to warnings only.
+    let default_filter = if repl_only {
+        "ironclaw=warn,tower_http=warn"
+    } else {
+        "ironclaw=info,tower_http=warn"
+    };
+    let env_filter =
+        EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new(default_filter));
 
     // Create log broadcaster before tracing init so the WebLogLayer can capture all events.
     // This gets wired to the gateway's /api/logs/events SSE endpoint later.
     let log_broadcaster = Arc::new(LogBroadcaster::new());
 
+    // Optional OTLP span export; the guard flushes pending spans on exit.
+    let (otel_layer, _telemetry_guard) = match ironclaw::telemetry::otel_layer(&config.telemetry)? {
+        Some((layer, guard)) => (Some(layer), Some(guard)),
+        None => (None, None),
+    };
+
     tracing_subscriber::registry()
         .with(env_filter)
         .with(
@@ -331,8 +496,20 @@ async fn main() -> anyhow::Result<()> {
                 .with_writer(ironclaw::tracing_fmt::TruncatingStderr::default()),
         )
         .with(WebLogLayer::new(Arc::clone(&log_broadcaster)))
+        .with(otel_layer)
         .init();
 
+    if let Some(ref endpoint) = config.telemetry.otlp_endpoint {
+        tracing::info!("Exporting traces to {}", endpoint);
+    }
+
+    if let Some(addr) = config.metrics.listen {
+        let bound = ironclaw::metrics::serve(addr)
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to bind metrics listener on {}: {}", addr, e))?;
+        tracing::info!("Prometheus metrics at http://{}/metrics", bound);
+    }
+
     // Create CLI channel
     let repl_channel = if let Some(ref msg) = cli.message {
         Some(ReplChannel::with_message(msg.clone()))
@@ -406,6 +583,16 @@ async fn main() -> anyhow::Result<()> {
                 tracing::info!("PostgreSQL database connected and migrations applied");
 
                 pg_pool = Some(pg.pool());
+                let probe_pool = pg.pool();
+                Metrics::global().set_pool_probe(move || {
+                    let status = probe_pool.status();
+                    PoolStats {
+                        max_size: status.max_size,
+                        size: status.size,
+                        available: status.available,
+                        waiting: status.waiting,
+                    }
+                });
                 Some(Arc::new(pg) as Arc<dyn ironclaw::db::Database>)
             }
             #[cfg(not(feature = "postgres"))]
@@ -417,6 +604,29 @@ async fn main() -> anyhow::Result<()> {
         }
     };
 
+    // Resolve the registered agent to start, if one was named.
+    let agent_def = match (cli.agent.as_deref(), db.as_ref()) {
+        (None, _) => None,
+        (Some(_), None) => anyhow::bail!("--agent requires a database connection"),
+        (Some(key), Some(db)) => {
+            let agent = AgentRegistry::new(Arc::clone(db))
+                .resolve_for_run(key)
+                .await
+                .map_err(|e| anyhow::anyhow!("{}", e))?;
+            tracing::info!("Starting registered agent '{}' ({})", agent.name, agent.id);
+            Some(agent)
+        }
+    };
+    let apply_overrides = |config: &mut Config| {
+        if let Some(ref agent) = agent_def {
+            config.apply_agent(agent);
+        }
+        if repl_only {
+            config.restrict_to_repl();
+        }
+    };
+    apply_overrides(&mut config);
+
     // Post-init operations using the database
     if let Some(ref db) = db {
         // One-time migration: move disk config files into the DB settings table.
@@ -428,6 +638,7 @@ async fn main() -> anyhow::Result<()> {
         match Config::from_db(db.as_ref(), "default").await {
             Ok(db_config) => {
                 config = db_config;
+                apply_overrides(&mut config);
                 tracing::info!("Configuration reloaded from database");
             }
             Err(e) => {
@@ -503,6 +714,7 @@ async fn main() -> anyhow::Result<()> {
             match Config::from_db(db_ref.as_ref(), "default").await {
                 Ok(refreshed) => {
                     config = refreshed;
+                    apply_overrides(&mut config);
                     tracing::debug!("LlmConfig re-resolved after secret injection");
                 }
                 Err(e) => {
@@ -536,9 +748,11 @@ async fn main() -> anyhow::Result<()> {
         } else {
             llm
         };
+    let llm: Arc<dyn LlmProvider> = Arc::new(MeteredProvider::new(llm));
 
     // Initialize cheap LLM provider for lightweight tasks (heartbeat, evaluation)
-    let cheap_llm = create_cheap_llm_provider(&config.llm, session.clone())?;
+    let cheap_llm = create_cheap_llm_provider(&config.llm, session.clone())?
+        .map(|cheap| Arc::new(MeteredProvider::new(cheap)) as Arc<dyn LlmProvider>);
     if let Some(ref cheap) = cheap_llm {
         tracing::info!("Cheap LLM provider initialized: {}", cheap.model_name());
     }
@@ -547,11 +761,71 @@ async fn main() -> anyhow::Result<()> {
     let safety = Arc::new(SafetyLayer::new(&config.safety));
     tracing::info!("Safety layer initialized");
 
+    // Load per-user tool and workspace permissions
+    let permissions = match db {
+        Some(ref db) => Permissions::load(Arc::clone(db), "default")
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to load permissions: {}", e))?,
+        None => Permissions::default(),
+    };
+    let permissions = Arc::new(permissions);
+
+    // Shared workspace namespaces mounted by this agent
+    let agent_id = agent_def.as_ref().map(|a| a.id);
+    let shared_mounts = match db {
+        Some(ref db) => SharedNamespaces::load(Arc::clone(db), "default")
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to load workspace namespaces: {}", e))?
+            .mounts_for(agent_id),
+        None => Vec::new(),
+    };
+    for mount in &shared_mounts {
+        tracing::info!(
+            "Mounted shared namespace '{}' at {}/ ({:?})",
+            mount.namespace,
+            mount.mount,
+            mount.mode
+        );
+    }
+
+    // Audit log for messages, tool executions, approvals, and workspace writes
+    let audit = match db {
+        Some(ref db) if config.audit.enabled => {
+            let audit = Arc::new(AuditLog::new(Arc::clone(db)));
+            if let Some(retention) = config.audit.retention() {
+                audit.spawn_retention(retention);
+            }
+            tracing::info!(
+                "Audit log enabled (retention: {} days, 0 = forever)",
+                config.audit.retention_days
+            );
+            Some(audit)
+        }
+        _ => None,
+    };
+
     // Initialize tool registry
-    let tools = Arc::new(ToolRegistry::new());
+    let tools = match secrets_store {
+        Some(ref secrets) => ToolRegistry::new()
+            .with_secrets_store(Arc::clone(secrets), config.secrets.bindings.clone()),
+        None => ToolRegistry::new(),
+    };
+    let tools = Arc::new(tools.with_permissions(Arc::clone(&permissions)));
+    tools.set_disabled_tools(config.agent.disabled_tools.iter().cloned());
+    tools.set_toolset(config.agent.toolset.iter().cloned());
     tools.register_builtin_tools();
+    if config.github.enabled() {
+        tools.register_github_tool(&config.github);
+    }
+    if config.home_assistant.enabled() {
+        tools.register_home_assistant_tool(&config.home_assistant);
+    }
     tracing::info!("Registered {} built-in tools", tools.count());
 
+    if let Some(ref db) = db {
+        route_embeddings_by_language(&mut config.embeddings, db.as_ref()).await;
+    }
+
     // Create embeddings provider if configured
     let embeddings: Option<Arc<dyn EmbeddingProvider>> = if config.embeddings.enabled {
         match config.embeddings.provider.as_str() {
@@ -590,15 +864,233 @@ async fn main() -> anyhow::Result<()> {
         tracing::info!("Embeddings disabled (set OPENAI_API_KEY or EMBEDDING_ENABLED=true)");
         None
     };
+    let embeddings = embeddings.or_else(|| {
+        let fallback = fallback_embeddings(&config);
+        if fallback.is_some() {
+            tracing::info!("Using local hash embeddings for approximate semantic search");
+        }
+        fallback
+    });
+    let sparse = sparse_encoder(&config);
+    if let Some(ref encoder) = sparse {
+        tracing::info!("Sparse search enabled (model: {})", encoder.model_name());
+    }
+
+    // Skills on disk; workspace skills are added once the workspace exists
+    let mut skills = SkillRegistry::new();
+    if config.skills.enabled {
+        for dir in &config.skills.dirs {
+            if let Err(e) = skills.load_dir(dir) {
+                tracing::warn!("Failed to load skills from {}: {}", dir.display(), e);
+            }
+        }
+    }
+
+    // Workspaces built below share one change stream so a write through the
+    // memory tools invalidates the agent's document cache.
+    let workspace_events = WorkspaceEvents::new();
+
+    // Outbound webhooks for job, approval, workspace, and budget events
+    let webhooks = Webhooks::from_config(&config.webhooks);
+    if let Some(ref webhooks) = webhooks {
+        webhooks.watch_workspace(&workspace_events);
+        tracing::info!("Webhooks enabled for {} URL(s)", config.webhooks.urls.len());
+    }
+
+    // Contacts are kept for the workspace owner, whoever the sender.
+    let contacts = db
+        .as_ref()
+        .map(|db| Arc::new(ContactBook::new(Arc::clone(db), "default")));
+    if let Some(ref contacts) = contacts {
+        tools.register_contact_tools(Arc::clone(contacts));
+    }
+
+    // Coordinates draining on SIGTERM/Ctrl+C; components register work to
+    // flush before exit
+    let shutdown = Arc::new(ShutdownCoordinator::new(
+        config.agent.shutdown_drain_timeout,
+    ));
+
+    // Long documents are summarized as they are written, by both workspaces
+    let document_summarizer = config.document_summaries.enabled.then(|| {
+        Arc::new(DocumentSummarizer::new(
+            cheap_llm.clone().unwrap_or_else(|| llm.clone()),
+        ))
+    });
+    if document_summarizer.is_some() {
+        tracing::info!(
+            "Long-document summaries enabled (over {} tokens)",
+            config.document_summaries.threshold_tokens
+        );
+    }
+
+    // Both workspaces commit to the same repository, one commit at a time
+    let workspace_git = match config.workspace_git.dir {
+        Some(ref dir) if db.is_some() => match GitStorage::open(dir).await {
+            Ok(git) => {
+                let git = Arc::new(git);
+                if let Some(ref remote) = config.workspace_git.remote {
+                    git.spawn_push(remote.clone(), config.workspace_git.push_interval);
+                }
+                tracing::info!("Workspace committed to git repository {}", dir.display());
+                Some(git)
+            }
+            Err(e) => {
+                tracing::warn!(
+                    "Failed to open workspace git repository {}: {}",
+                    dir.display(),
+                    e
+                );
+                None
+            }
+        },
+        _ => None,
+    };
 
     // Register memory tools if database is available
     if let Some(ref db) = db {
-        let mut workspace = Workspace::new_with_db("default", Arc::clone(db));
+        let mut workspace = Workspace::new_with_db("default", Arc::clone(db))
+            .with_permissions(Arc::clone(&permissions))
+            .with_shared_mounts(shared_mounts.iter().cloned())
+            .with_prompt_budget(config.system_prompt.budget)
+            .with_events(workspace_events.clone())
+            .with_document_cache(config.workspace_cache.capacity, config.workspace_cache.ttl)
+            .with_auto_readme(config.workspace_index.auto_readme)
+            .with_case_insensitive_paths(config.workspace_paths.case_insensitive)
+            .with_quota(config.workspace_quota.quota);
+        if let Some(id) = agent_id {
+            workspace = workspace.with_agent(id);
+        }
+        if let Some(ref audit) = audit {
+            workspace = workspace.with_audit(Arc::clone(audit));
+        }
+        if let Some(ref git) = workspace_git {
+            workspace = workspace.with_git(Arc::clone(git));
+        }
         if let Some(ref emb) = embeddings {
             workspace = workspace.with_embeddings(emb.clone());
         }
+        if let Some(ref encoder) = sparse {
+            workspace = workspace.with_sparse_encoder(Arc::clone(encoder));
+        }
+        if let Some(ref summarizer) = document_summarizer {
+            workspace = workspace.with_document_summaries(
+                Arc::clone(summarizer),
+                config.document_summaries.threshold_tokens,
+            );
+        }
         let workspace = Arc::new(workspace);
-        tools.register_memory_tools(workspace);
+        match workspace.sync_from_git().await {
+            Ok(report) if report != GitSyncReport::default() => tracing::info!(
+                "Synced workspace with git: {} committed, {} restored, {} trashed",
+                report.committed,
+                report.restored,
+                report.trashed
+            ),
+            Ok(_) => {}
+            Err(e) => tracing::warn!("Failed to sync the workspace with git: {}", e),
+        }
+        if config.memory_decay.enabled {
+            workspace
+                .spawn_memory_decay(config.memory_decay.policy(), config.memory_decay.interval);
+            tracing::info!(
+                "Memory decay enabled (idle: {} days, max entries: {}, 0 = no limit)",
+                config.memory_decay.idle_days,
+                config.memory_decay.max_entries
+            );
+        }
+        if let Some(retention) = config.workspace_trash.retention() {
+            workspace.spawn_trash_purge(retention, config.workspace_trash.interval);
+        }
+        if let Some(ref dir) = config.workspace_mirror.dir {
+            match WorkspaceMirror::new(Arc::clone(&workspace), dir) {
+                Ok(mirror) => {
+                    Arc::new(mirror).spawn();
+                }
+                Err(e) => {
+                    tracing::warn!("Failed to mirror the workspace to {}: {}", dir.display(), e)
+                }
+            }
+        }
+        if config.daily_rollup.enabled {
+            let summarizer =
+                RollupSummarizer::new(cheap_llm.clone().unwrap_or_else(|| llm.clone()));
+            workspace.spawn_daily_rollups(summarizer, config.daily_rollup.interval);
+            tracing::info!(
+                "Daily log rollups enabled (checking every {}s)",
+                config.daily_rollup.interval.as_secs()
+            );
+        }
+        if config.usage_reports.enabled {
+            workspace.spawn_usage_reports(
+                UsageReporter::new(Arc::clone(db)),
+                config.usage_reports.interval,
+            );
+            tracing::info!(
+                "Weekly usage reports enabled (checking every {}s)",
+                config.usage_reports.interval.as_secs()
+            );
+        }
+        if config.memory_topics.enabled {
+            if workspace.has_embeddings() {
+                let labeler = TopicLabeler::new(cheap_llm.clone().unwrap_or_else(|| llm.clone()));
+                workspace.spawn_topic_index(
+                    labeler,
+                    config.memory_topics.max_topics,
+                    config.memory_topics.interval,
+                );
+                tracing::info!(
+                    "Topic index enabled (up to {} topics, checking every {}s)",
+                    config.memory_topics.max_topics,
+                    config.memory_topics.interval.as_secs()
+                );
+            } else {
+                tracing::warn!("Topic index needs embeddings; MEMORY_TOPICS_ENABLED ignored");
+            }
+        }
+        if config.skills.enabled
+            && let Err(e) = skills.load_workspace(&workspace).await
+        {
+            tracing::warn!("Failed to load workspace skills: {}", e);
+        }
+        tools.register_weather_tool(Arc::clone(&workspace));
+        let conflicts = config.memory_conflicts.enabled.then(|| {
+            Arc::new(if config.memory_conflicts.use_llm {
+                ConflictChecker::new(cheap_llm.clone().unwrap_or_else(|| llm.clone()))
+            } else {
+                ConflictChecker::flag_only()
+            })
+        });
+        tools.register_memory_tools(Arc::clone(&workspace), conflicts);
+
+        if config.knowledge_graph.enabled {
+            let mut graph = KnowledgeGraph::new(Arc::clone(db), "default");
+            if let Some(id) = agent_id {
+                graph = graph.with_agent(id);
+            }
+            let graph = Arc::new(graph);
+            let extractor = GraphExtractor::new(cheap_llm.clone().unwrap_or_else(|| llm.clone()));
+            let indexer =
+                graph.spawn_indexer(extractor.clone(), config.knowledge_graph.index_interval);
+            // Index whatever is still queued before exiting
+            let pending = Arc::clone(&graph);
+            shutdown.on_drain("knowledge graph index", async move {
+                indexer.abort();
+                if let Err(e) = pending.index_pending(&extractor).await {
+                    tracing::warn!("Final knowledge graph indexing failed: {}", e);
+                }
+            });
+            tools.register_graph_tool(graph, workspace);
+            tracing::info!(
+                "Knowledge graph enabled (indexing every {}s)",
+                config.knowledge_graph.index_interval.as_secs()
+            );
+        }
+    }
+
+    let skills = Arc::new(skills);
+    if !skills.is_empty() {
+        tools.register_skill_tool(Arc::clone(&skills));
     }
 
     // Register builder tool if enabled.
@@ -811,6 +1303,7 @@ async fn main() -> anyhow::Result<()> {
     // When allow_local_tools is true, dev tools are also registered directly (current behavior).
     if config.agent.allow_local_tools {
         tools.register_dev_tools();
+        tools.register_skill_commands(&skills);
         tracing::info!(
             "Local tools enabled (allow_local_tools=true), dev tools registered directly"
         );
@@ -885,7 +1378,24 @@ async fn main() -> anyhow::Result<()> {
 
     // Initialize channel manager
     let mut channels = ChannelManager::new();
+    if let Some(ref audit) = audit {
+        channels = channels.with_audit(Arc::clone(audit));
+    }
+    let moderation = Moderation::from_config(&config.moderation)
+        .map_err(|e| anyhow::anyhow!("Invalid moderation rule: {}", e))?;
+    if let Some(moderation) = moderation {
+        tracing::info!(
+            channels = ?config.moderation.channels,
+            action = %config.moderation.action,
+            "Moderating outgoing messages"
+        );
+        channels = channels.with_moderation(Arc::new(moderation));
+    }
 
+    // A registered agent may not serve the terminal; single-message mode
+    // always does.
+    let repl_channel =
+        repl_channel.filter(|_| cli.message.is_some() || config.channels.cli.enabled);
     if let Some(repl) = repl_channel {
         channels.add(Box::new(repl));
         if cli.message.is_some() {
@@ -916,6 +1426,15 @@ async fn main() -> anyhow::Result<()> {
 
                         for loaded in results.loaded {
                             let channel_name = loaded.name().to_string();
+                            if let Some(ref filter) = config.channels.wasm_channel_filter
+                                && !filter.contains(&channel_name)
+                            {
+                                tracing::info!(
+                                    "Skipping WASM channel {} (not served by this agent)",
+                                    channel_name
+                                );
+                                continue;
+                            }
                             tracing::info!("Loaded WASM channel: {}", channel_name);
 
                             let secret_name = loaded.webhook_secret_name();
@@ -1078,6 +1597,17 @@ async fn main() -> anyhow::Result<()> {
         );
     }
 
+    // Webhook routines are fired through the same server as the HTTP channel
+    let routine_hooks = if webhook_server_addr.is_some() && config.routines.enabled && db.is_some()
+    {
+        let hooks = RoutineHooks::new();
+        webhook_routes.push(hooks.routes());
+        tracing::info!("Routine webhooks enabled at /hooks/routine/{{path}}");
+        Some(hooks)
+    } else {
+        None
+    };
+
     // Start the unified webhook server if any routes were registered.
     let mut webhook_server = if !webhook_routes.is_empty() {
         let addr =
@@ -1094,18 +1624,54 @@ async fn main() -> anyhow::Result<()> {
 
     // Create workspace for agent (shared with memory tools)
     let workspace = if let Some(ref db_ref) = db {
-        let mut ws = Workspace::new_with_db("default", Arc::clone(db_ref));
+        let mut ws = Workspace::new_with_db("default", Arc::clone(db_ref))
+            .with_permissions(Arc::clone(&permissions))
+            .with_shared_mounts(shared_mounts.iter().cloned())
+            .with_prompt_budget(config.system_prompt.budget)
+            .with_events(workspace_events.clone())
+            .with_document_cache(config.workspace_cache.capacity, config.workspace_cache.ttl)
+            .with_auto_readme(config.workspace_index.auto_readme)
+            .with_case_insensitive_paths(config.workspace_paths.case_insensitive)
+            .with_quota(config.workspace_quota.quota);
+        if let Some(id) = agent_id {
+            ws = ws.with_agent(id);
+        }
+        if let Some(ref audit) = audit {
+            ws = ws.with_audit(Arc::clone(audit));
+        }
+        if let Some(ref git) = workspace_git {
+            ws = ws.with_git(Arc::clone(git));
+        }
         if let Some(ref emb) = embeddings {
             ws = ws.with_embeddings(emb.clone());
         }
+        if let Some(ref encoder) = sparse {
+            ws = ws.with_sparse_encoder(Arc::clone(encoder));
+        }
+        if let Some(ref summarizer) = document_summarizer {
+            ws = ws.with_document_summaries(
+                Arc::clone(summarizer),
+                config.document_summaries.threshold_tokens,
+            );
+        }
         Some(Arc::new(ws))
     } else {
         None
     };
+    if let Some(ref ws) = workspace {
+        channels = channels.with_egress_guard(Arc::clone(ws));
+    }
 
     // Seed workspace with core identity files on first boot
     if let Some(ref ws) = workspace {
-        match ws.seed_if_empty().await {
+        let templates = match SeedTemplates::from_config(&config.workspace_seed.seed) {
+            Ok(templates) => templates,
+            Err(e) => {
+                tracing::warn!("Failed to load seed templates, using built-ins: {}", e);
+                SeedTemplates::builtin()
+            }
+        };
+        match ws.seed_with(&templates).await {
             Ok(count) if count > 0 => {
                 tracing::info!("Workspace seeded with {} core files", count);
             }
@@ -1116,21 +1682,31 @@ async fn main() -> anyhow::Result<()> {
         }
     }
 
-    // Backfill embeddings if we just enabled the provider
+    // Backfill embeddings if we just enabled the provider. This can take a
+    // while for a large workspace, so don't hold up startup.
     if let (Some(ws), Some(_)) = (&workspace, &embeddings) {
-        match ws.backfill_embeddings().await {
-            Ok(count) if count > 0 => {
-                tracing::info!("Backfilled embeddings for {} chunks", count);
-            }
-            Ok(_) => {}
-            Err(e) => {
-                tracing::warn!("Failed to backfill embeddings: {}", e);
+        let ws = Arc::clone(ws);
+        tokio::spawn(async move {
+            match ws.backfill_embeddings().await {
+                Ok(count) if count > 0 => {
+                    tracing::info!("Backfilled embeddings for {} chunks", count);
+                }
+                Ok(_) => {}
+                Err(e) => {
+                    tracing::warn!("Failed to backfill embeddings: {}", e);
+                }
             }
-        }
+        });
     }
 
     // Create context manager (shared between job tools and agent)
-    let context_manager = Arc::new(ContextManager::new(config.agent.max_parallel_jobs));
+    let mut context_manager = ContextManager::new(config.agent.max_parallel_jobs)
+        .with_temp_root(config.agent.job_temp_root.clone());
+    if config.agent.user_limits.is_enabled() {
+        context_manager = context_manager
+            .with_user_limits(Arc::new(UserLimiter::new(config.agent.user_limits.clone())));
+    }
+    let context_manager = Arc::new(context_manager);
 
     // Create session manager (shared between agent and web gateway)
     let session_manager = Arc::new(SessionManager::new());
@@ -1142,7 +1718,68 @@ async fn main() -> anyhow::Result<()> {
         db.clone(),
     );
 
+    // Supervisor: delegate requests to the other registered agents over the bus
+    if config.supervisor.enabled
+        && let Some(ref db_ref) = db
+    {
+        let registry = AgentRegistry::new(Arc::clone(db_ref));
+        let bus = Arc::new(AgentBus::new().with_timeout(config.supervisor.subtask_timeout));
+        let runtime = WorkerRuntime {
+            safety: Arc::clone(&safety),
+            tools: Arc::clone(&tools),
+            context_manager: Arc::clone(&context_manager),
+            audit: audit.clone(),
+            user_id: "default".to_string(),
+            max_iterations: config.supervisor.worker_max_iterations,
+        };
+        let workers = registry
+            .list()
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to load agents for supervisor: {}", e))?;
+        let mut worker_count = 0;
+        for agent in workers
+            .into_iter()
+            .filter(|a| a.enabled && Some(a.id) != agent_id)
+        {
+            let worker_llm: Arc<dyn LlmProvider> = match agent.model {
+                Some(ref model) => {
+                    let mut llm_config = config.llm.clone();
+                    llm_config.set_model(model);
+                    Arc::new(MeteredProvider::new(create_llm_provider(
+                        &llm_config,
+                        session.clone(),
+                    )?))
+                }
+                None => Arc::clone(&llm),
+            };
+            let workspace = Arc::new(registry.workspace(&agent));
+            let name = agent.name.clone();
+            let worker =
+                RegisteredWorker::new(agent, worker_llm, runtime.clone()).with_workspace(workspace);
+            bus.register(name, Arc::new(worker)).await;
+            worker_count += 1;
+        }
+
+        if worker_count == 0 {
+            tracing::warn!("Supervisor enabled but no other enabled agents are registered");
+        } else {
+            let supervisor = Supervisor::new(config.agent.name.clone(), bus, Arc::clone(&llm))
+                .with_limits(SupervisorLimits {
+                    max_subtasks: config.supervisor.max_subtasks,
+                    budget: config.supervisor.budget_usd,
+                });
+            // Workers run concurrently, so a run is one sub-task timeout plus
+            // planning and synthesis.
+            let timeout = config.supervisor.subtask_timeout + std::time::Duration::from_secs(180);
+            tools.register_supervisor_tool(
+                SuperviseTool::new(Arc::new(supervisor)).with_timeout(timeout),
+            );
+            tracing::info!("Supervisor enabled with {} worker agent(s)", worker_count);
+        }
+    }
+
     // Add web gateway channel if configured
+    let mut chat_rate_limiter = None;
     if let Some(ref gw_config) = config.channels.gateway {
         let mut gw = GatewayChannel::new(gw_config.clone());
         if let Some(ref ws) = workspace {
@@ -1157,6 +1794,26 @@ async fn main() -> anyhow::Result<()> {
         if let Some(ref d) = db {
             gw = gw.with_store(Arc::clone(d));
         }
+        let api_keys = match db {
+            Some(ref d) => ApiKeys::load(Arc::clone(d), gw_config.user_id.clone())
+                .await
+                .map_err(|e| anyhow::anyhow!("Failed to load gateway API keys: {}", e))?,
+            None => ApiKeys::new(),
+        };
+        gw = gw.with_api_keys(Arc::new(api_keys));
+        let mut health = HealthChecker::new()
+            .with_llm(Arc::clone(&llm))
+            .with_channels(channels.health_probe());
+        if let Some(ref d) = db {
+            health = health.with_database(Arc::clone(d));
+        }
+        if let Some(ref emb) = embeddings {
+            health = health.with_embeddings(Arc::clone(emb));
+        }
+        gw = gw.with_health(Arc::new(health));
+        if config.moderation.enabled() {
+            gw = gw.with_moderation(channels.moderation_queue());
+        }
         if let Some(ref jm) = container_job_manager {
             gw = gw.with_job_manager(Arc::clone(jm));
         }
@@ -1187,10 +1844,52 @@ async fn main() -> anyhow::Result<()> {
             gw.auth_token()
         );
 
+        chat_rate_limiter = Some(Arc::clone(&gw.state().chat_rate_limiter));
         channels.add(Box::new(gw));
     }
 
+    // First run: walk the user through setup until BOOTSTRAP.md is gone.
+    if let Some(ref ws) = workspace
+        && ws.in_bootstrap().await
+    {
+        tools.register_bootstrap_tool(Arc::clone(ws), channels.channel_names().await);
+    }
+
+    // Hot-reload safe settings (model, disabled tools, heartbeat interval,
+    // chat rate limit) without restarting channels.
+    let (heartbeat_interval_tx, heartbeat_interval_rx) = tokio::sync::watch::channel(
+        std::time::Duration::from_secs(config.heartbeat.interval_secs),
+    );
+    if let Some(interval) = config.agent.config_reload_interval {
+        let source = match db {
+            Some(ref d) => SettingsSource::Database {
+                store: Arc::clone(d),
+                user_id: "default".to_string(),
+            },
+            None => SettingsSource::File(ironclaw::settings::Settings::default_path()),
+        };
+        let mut reloader = ConfigReloader::new(source, ReloadableConfig::from_config(&config))
+            .with_llm(Arc::clone(&llm))
+            .with_tools(Arc::clone(&tools))
+            .with_heartbeat_interval(heartbeat_interval_tx);
+        if let Some(limiter) = chat_rate_limiter {
+            reloader = reloader.with_chat_rate_limiter(limiter);
+        }
+        if let Some(ref agent) = agent_def {
+            reloader = reloader.with_agent(agent.clone());
+        }
+        reloader.spawn(interval);
+    }
+
     // Create and run the agent
+    let leases = db.as_ref().map(|db| {
+        tracing::info!("Claiming work as instance {}", config.leases.instance_id);
+        Arc::new(LeaseManager::new(
+            Arc::clone(db),
+            config.leases.instance_id.clone(),
+            config.leases.ttl,
+        ))
+    });
     let deps = AgentDeps {
         store: db,
         llm,
@@ -1199,6 +1898,14 @@ async fn main() -> anyhow::Result<()> {
         tools,
         workspace,
         extension_manager,
+        audit,
+        webhooks,
+        contacts,
+        leases,
+        models: Some(Arc::new(ModelProviders::new(
+            config.llm.clone(),
+            session.clone(),
+        ))),
     };
     let agent = Agent::new(
         config.agent.clone(),
@@ -1208,7 +1915,13 @@ async fn main() -> anyhow::Result<()> {
         Some(config.routines.clone()),
         Some(context_manager),
         Some(session_manager),
-    );
+    )
+    .with_heartbeat_interval_updates(heartbeat_interval_rx)
+    .with_shutdown(shutdown);
+    let agent = match routine_hooks {
+        Some(hooks) => agent.with_routine_hooks(hooks),
+        None => agent,
+    };
 
     tracing::info!("Agent initialized, starting main loop...");
 
@@ -1224,6 +1937,100 @@ async fn main() -> anyhow::Result<()> {
     Ok(())
 }
 
+/// Database and (optional) embedding provider for the memory and search
+/// commands, which run without the agent.
+async fn connect_memory_backend(
+    config: &Config,
+) -> anyhow::Result<(
+    Arc<dyn ironclaw::db::Database>,
+    Option<Arc<dyn EmbeddingProvider>>,
+)> {
+    // Database-trait-backed, so either backend works
+    let db: Arc<dyn ironclaw::db::Database> = ironclaw::db::connect_from_config(&config.database)
+        .await
+        .map_err(|e| anyhow::anyhow!("{}", e))?;
+    let mut embeddings_config = config.embeddings.clone();
+    route_embeddings_by_language(&mut embeddings_config, db.as_ref()).await;
+
+    // Set up embeddings if available
+    let session = ironclaw::llm::create_session_manager(ironclaw::llm::SessionConfig {
+        auth_base_url: config.llm.nearai.auth_base_url.clone(),
+        session_path: config.llm.nearai.session_path.clone(),
+    })
+    .await;
+
+    let embeddings: Option<Arc<dyn ironclaw::workspace::EmbeddingProvider>> = if embeddings_config
+        .enabled
+    {
+        match embeddings_config.provider.as_str() {
+            "nearai" => Some(Arc::new(
+                ironclaw::workspace::NearAiEmbeddings::new(&config.llm.nearai.base_url, session)
+                    .with_model(&embeddings_config.model, 1536),
+            )),
+            _ => {
+                if let Some(api_key) = embeddings_config.openai_api_key() {
+                    let dim = match embeddings_config.model.as_str() {
+                        "text-embedding-3-large" => 3072,
+                        _ => 1536,
+                    };
+                    Some(Arc::new(ironclaw::workspace::OpenAiEmbeddings::with_model(
+                        api_key,
+                        &embeddings_config.model,
+                        dim,
+                    )))
+                } else {
+                    None
+                }
+            }
+        }
+    } else {
+        None
+    };
+    let embeddings = embeddings.or_else(|| fallback_embeddings(config));
+
+    Ok((db, embeddings))
+}
+
+/// Switch to `EMBEDDING_MULTILINGUAL_MODEL` if most of the workspace is not
+/// in English.
+async fn route_embeddings_by_language(
+    embeddings: &mut ironclaw::config::EmbeddingsConfig,
+    db: &dyn ironclaw::db::Database,
+) {
+    if embeddings.multilingual_model.is_none() {
+        return;
+    }
+    match db.chunk_language_counts("default").await {
+        Ok(counts) => {
+            if embeddings.route_by_language(&counts) {
+                tracing::info!(
+                    "Workspace is mostly not in English, embedding with {}",
+                    embeddings.model
+                );
+            }
+        }
+        Err(e) => tracing::warn!("Failed to count chunk languages: {}", e),
+    }
+}
+
+/// Local hash embeddings when `EMBEDDING_FALLBACK` is set, sized to the
+/// `memory_chunks.embedding` column.
+fn fallback_embeddings(config: &Config) -> Option<Arc<dyn EmbeddingProvider>> {
+    config
+        .embeddings
+        .fallback
+        .then(|| Arc::new(HashEmbeddings::new(1536)) as Arc<dyn EmbeddingProvider>)
+}
+
+/// Sparse encoder at `SPARSE_EMBEDDING_URL`, if set.
+fn sparse_encoder(config: &Config) -> Option<Arc<dyn SparseEncoder>> {
+    let url = config.embeddings.sparse_url.as_deref()?;
+    Some(Arc::new(TeiSparseEncoder::new(
+        url,
+        &config.embeddings.sparse_model,
+    )))
+}
+
 /// Check if onboarding is needed and return the reason.
 ///
 /// Returns `Some(reason)` if onboarding should be triggered, `None` otherwise.
@@ -0,0 +1,339 @@
+//! Prometheus metrics.
+//!
+//! A process-wide registry of counters, gauges, and histograms, exposed in
+//! the Prometheus text format on `GET /metrics` by a small standalone HTTP
+//! listener (see [`serve`]). It runs separately from the web gateway so a
+//! daemon without the gateway can still be scraped, and so scrapers don't
+//! need a gateway token.
+//!
+//! | Metric | Type | Labels |
+//! |--------|------|--------|
+//! | `ironclaw_jobs_started_total` | counter | |
+//! | `ironclaw_jobs_completed_total` | counter | |
+//! | `ironclaw_jobs_failed_total` | counter | |
+//! | `ironclaw_tool_duration_seconds` | histogram | `tool`, `outcome` |
+//! | `ironclaw_llm_requests_total` | counter | `model`, `outcome` |
+//! | `ironclaw_llm_tokens_total` | counter | `model`, `direction` |
+//! | `ironclaw_embedding_requests_total` | counter | `model`, `outcome` |
+//! | `ironclaw_embedding_duration_seconds` | histogram | `model` |
+//! | `ironclaw_heartbeat_runs_total` | counter | `result` |
+//! | `ironclaw_db_pool_connections` | gauge | `state` |
+//! | `ironclaw_db_pool_max_connections` | gauge | |
+
+use std::net::SocketAddr;
+use std::sync::{LazyLock, RwLock};
+use std::time::Duration;
+
+use axum::{Router, http::header, response::IntoResponse, routing::get};
+use prometheus::{
+    Encoder, HistogramOpts, HistogramVec, IntCounter, IntCounterVec, IntGauge, IntGaugeVec, Opts,
+    Registry, TextEncoder,
+};
+
+/// Bucket bounds (seconds) for tool and embedding latency.
+const LATENCY_BUCKETS: &[f64] = &[
+    0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0, 60.0, 120.0, 300.0,
+];
+
+static GLOBAL: LazyLock<Metrics> = LazyLock::new(Metrics::new);
+
+/// Point-in-time connection pool usage, reported by the database backend.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PoolStats {
+    pub max_size: usize,
+    /// Connections currently open.
+    pub size: usize,
+    /// Open connections that are idle.
+    pub available: usize,
+    /// Callers waiting for a connection.
+    pub waiting: usize,
+}
+
+type PoolProbe = Box<dyn Fn() -> PoolStats + Send + Sync>;
+
+/// All ironclaw metrics and the registry they're exported from.
+pub struct Metrics {
+    registry: Registry,
+    jobs_started: IntCounter,
+    jobs_completed: IntCounter,
+    jobs_failed: IntCounter,
+    tool_duration: HistogramVec,
+    llm_requests: IntCounterVec,
+    llm_tokens: IntCounterVec,
+    embedding_requests: IntCounterVec,
+    embedding_duration: HistogramVec,
+    heartbeat_runs: IntCounterVec,
+    db_pool_connections: IntGaugeVec,
+    db_pool_max: IntGauge,
+    /// Sampled on every scrape so pool gauges are never stale.
+    pool_probe: RwLock<Option<PoolProbe>>,
+}
+
+impl Metrics {
+    /// The process-wide metrics instance.
+    pub fn global() -> &'static Metrics {
+        &GLOBAL
+    }
+
+    fn new() -> Self {
+        let registry = Registry::new_custom(Some("ironclaw".to_string()), None)
+            .expect("valid registry prefix");
+
+        let counter = |name: &str, help: &str| {
+            let c = IntCounter::new(name, help).expect("valid metric");
+            registry
+                .register(Box::new(c.clone()))
+                .expect("unique metric");
+            c
+        };
+        let counter_vec = |name: &str, help: &str, labels: &[&str]| {
+            let c = IntCounterVec::new(Opts::new(name, help), labels).expect("valid metric");
+            registry
+                .register(Box::new(c.clone()))
+                .expect("unique metric");
+            c
+        };
+        let histogram_vec = |name: &str, help: &str, labels: &[&str]| {
+            let opts = HistogramOpts::new(name, help).buckets(LATENCY_BUCKETS.to_vec());
+            let h = HistogramVec::new(opts, labels).expect("valid metric");
+            registry
+                .register(Box::new(h.clone()))
+                .expect("unique metric");
+            h
+        };
+
+        let jobs_started = counter("jobs_started_total", "Jobs scheduled for execution");
+        let jobs_completed = counter("jobs_completed_total", "Jobs that completed");
+        let jobs_failed = counter("jobs_failed_total", "Jobs that failed");
+        let tool_duration = histogram_vec(
+            "tool_duration_seconds",
+            "Tool execution latency",
+            &["tool", "outcome"],
+        );
+        let llm_requests = counter_vec(
+            "llm_requests_total",
+            "LLM provider requests",
+            &["model", "outcome"],
+        );
+        let llm_tokens = counter_vec(
+            "llm_tokens_total",
+            "LLM tokens consumed",
+            &["model", "direction"],
+        );
+        let embedding_requests = counter_vec(
+            "embedding_requests_total",
+            "Embedding provider requests",
+            &["model", "outcome"],
+        );
+        let embedding_duration = histogram_vec(
+            "embedding_duration_seconds",
+            "Embedding request latency",
+            &["model"],
+        );
+        let heartbeat_runs = counter_vec(
+            "heartbeat_runs_total",
+            "Heartbeat checks by result",
+            &["result"],
+        );
+
+        let db_pool_connections = IntGaugeVec::new(
+            Opts::new("db_pool_connections", "Database pool connections by state"),
+            &["state"],
+        )
+        .expect("valid metric");
+        registry
+            .register(Box::new(db_pool_connections.clone()))
+            .expect("unique metric");
+        let db_pool_max = IntGauge::new("db_pool_max_connections", "Database pool capacity")
+            .expect("valid metric");
+        registry
+            .register(Box::new(db_pool_max.clone()))
+            .expect("unique metric");
+
+        Self {
+            registry,
+            jobs_started,
+            jobs_completed,
+            jobs_failed,
+            tool_duration,
+            llm_requests,
+            llm_tokens,
+            embedding_requests,
+            embedding_duration,
+            heartbeat_runs,
+            db_pool_connections,
+            db_pool_max,
+            pool_probe: RwLock::new(None),
+        }
+    }
+
+    pub fn job_started(&self) {
+        self.jobs_started.inc();
+    }
+
+    pub fn job_completed(&self) {
+        self.jobs_completed.inc();
+    }
+
+    pub fn job_failed(&self) {
+        self.jobs_failed.inc();
+    }
+
+    /// Record one tool execution.
+    pub fn record_tool(&self, tool: &str, elapsed: Duration, success: bool) {
+        self.tool_duration
+            .with_label_values(&[tool, outcome(success)])
+            .observe(elapsed.as_secs_f64());
+    }
+
+    /// Record a successful LLM request and its token usage.
+    pub fn record_llm_usage(&self, model: &str, input_tokens: u32, output_tokens: u32) {
+        self.llm_requests.with_label_values(&[model, "ok"]).inc();
+        self.llm_tokens
+            .with_label_values(&[model, "input"])
+            .inc_by(u64::from(input_tokens));
+        self.llm_tokens
+            .with_label_values(&[model, "output"])
+            .inc_by(u64::from(output_tokens));
+    }
+
+    /// Record a failed LLM request.
+    pub fn record_llm_error(&self, model: &str) {
+        self.llm_requests.with_label_values(&[model, "error"]).inc();
+    }
+
+    /// Record one embedding request.
+    pub fn record_embedding(&self, model: &str, elapsed: Duration, success: bool) {
+        self.embedding_requests
+            .with_label_values(&[model, outcome(success)])
+            .inc();
+        self.embedding_duration
+            .with_label_values(&[model])
+            .observe(elapsed.as_secs_f64());
+    }
+
+    /// Record a heartbeat check (`ok`, `needs_attention`, `skipped`, `failed`).
+    pub fn record_heartbeat(&self, result: &str) {
+        self.heartbeat_runs.with_label_values(&[result]).inc();
+    }
+
+    /// Install the function that reports database pool usage.
+    pub fn set_pool_probe(&self, probe: impl Fn() -> PoolStats + Send + Sync + 'static) {
+        if let Ok(mut slot) = self.pool_probe.write() {
+            *slot = Some(Box::new(probe));
+        }
+    }
+
+    /// Encode all metrics in the Prometheus text exposition format.
+    pub fn render(&self) -> String {
+        self.sample_pool();
+
+        let mut buf = Vec::new();
+        if let Err(e) = TextEncoder::new().encode(&self.registry.gather(), &mut buf) {
+            tracing::warn!("Failed to encode metrics: {}", e);
+        }
+        String::from_utf8(buf).unwrap_or_default()
+    }
+
+    fn sample_pool(&self) {
+        let Ok(probe) = self.pool_probe.read() else {
+            return;
+        };
+        let Some(ref probe) = *probe else {
+            return;
+        };
+        let stats = probe();
+        let gauge = |state: &str, value: usize| {
+            self.db_pool_connections
+                .with_label_values(&[state])
+                .set(value as i64);
+        };
+        gauge("open", stats.size);
+        gauge("idle", stats.available);
+        gauge("in_use", stats.size.saturating_sub(stats.available));
+        gauge("waiting", stats.waiting);
+        self.db_pool_max.set(stats.max_size as i64);
+    }
+}
+
+fn outcome(success: bool) -> &'static str {
+    if success { "ok" } else { "error" }
+}
+
+async fn metrics_handler() -> impl IntoResponse {
+    (
+        [(header::CONTENT_TYPE, "text/plain; version=0.0.4")],
+        Metrics::global().render(),
+    )
+}
+
+/// Start the `/metrics` listener. Returns the bound address.
+pub async fn serve(addr: SocketAddr) -> std::io::Result<SocketAddr> {
+    let listener = tokio::net::TcpListener::bind(addr).await?;
+    let bound = listener.local_addr()?;
+    let app = Router::new().route("/metrics", get(metrics_handler));
+
+    tokio::spawn(async move {
+        if let Err(e) = axum::serve(listener, app).await {
+            tracing::error!("Metrics server error: {}", e);
+        }
+    });
+
+    Ok(bound)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_includes_recorded_metrics() {
+        let metrics = Metrics::new();
+        metrics.job_started();
+        metrics.record_tool("echo", Duration::from_millis(12), true);
+        metrics.record_llm_usage("test-model", 100, 20);
+        metrics.record_heartbeat("skipped");
+
+        let text = metrics.render();
+        assert!(text.contains("ironclaw_jobs_started_total 1"));
+        assert!(
+            text.contains("ironclaw_tool_duration_seconds_count{outcome=\"ok\",tool=\"echo\"} 1")
+        );
+        assert!(
+            text.contains(
+                "ironclaw_llm_tokens_total{direction=\"input\",model=\"test-model\"} 100"
+            )
+        );
+        assert!(text.contains("ironclaw_heartbeat_runs_total{result=\"skipped\"} 1"));
+    }
+
+    #[test]
+    fn test_pool_probe_sampled_on_render() {
+        let metrics = Metrics::new();
+        metrics.set_pool_probe(|| PoolStats {
+            max_size: 16,
+            size: 5,
+            available: 2,
+            waiting: 1,
+        });
+
+        let text = metrics.render();
+        assert!(text.contains("ironclaw_db_pool_connections{state=\"in_use\"} 3"));
+        assert!(text.contains("ironclaw_db_pool_connections{state=\"waiting\"} 1"));
+        assert!(text.contains("ironclaw_db_pool_max_connections 16"));
+    }
+
+    #[tokio::test]
+    async fn test_serve_exposes_metrics() {
+        let addr = serve("127.0.0.1:0".parse().unwrap()).await.unwrap();
+        Metrics::global().job_completed();
+
+        let body = reqwest::get(format!("http://{}/metrics", addr))
+            .await
+            .unwrap()
+            .text()
+            .await
+            .unwrap();
+        assert!(body.contains("ironclaw_jobs_completed_total"));
+    }
+}
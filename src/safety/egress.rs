@@ -0,0 +1,267 @@
+//! Private-data egress guard for external actions.
+//!
+//! Tools that act on the outside world (HTTP calls, sending email, posting
+//! to chats) can leak what the agent knows about its user. Before such a
+//! tool runs, its arguments are compared against the user's internal and
+//! private documents (`MEMORY.md`, `USER.md`, and those labelled by
+//! [`sensitivity`](crate::workspace::sensitivity)); if they appear to carry
+//! that data the call needs explicit approval, even when the tool is
+//! otherwise auto-approved. In a shared conversation, a call carrying data
+//! from a private document ([`check_context_egress`]) is refused outright,
+//! and the channel manager withholds a reply that carries it.
+//! Jobs run unattended, so there a flagged call fails instead of asking.
+//!
+//! Detection is deliberately simple and biased towards asking:
+//!
+//! - **Identifiers** (email addresses, phone numbers) found in the
+//!   private context and repeated in the outgoing content
+//! - **Verbatim passages**: any run of `SHINGLE_WORDS` consecutive words
+//!   shared between the private context and the outgoing content
+//! - **Sensitive patterns** that need approval regardless of context:
+//!   payment card numbers (Luhn-valid) and US social security numbers
+
+use std::collections::HashSet;
+use std::sync::LazyLock;
+
+use regex::Regex;
+
+/// Number of consecutive words that count as a verbatim passage.
+const SHINGLE_WORDS: usize = 6;
+
+static EMAIL: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"[A-Za-z0-9._%+-]+@[A-Za-z0-9.-]+\.[A-Za-z]{2,}").expect("valid regex")
+});
+static PHONE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\+?\d[\d\s().-]{7,}\d").expect("valid regex"));
+static CARD: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\b(?:\d[ -]?){13,19}\b").expect("valid regex"));
+static SSN: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\b\d{3}-\d{2}-\d{4}\b").expect("valid regex"));
+
+/// Why an outgoing payload was flagged.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EgressFinding {
+    /// Short category, e.g. `"email"`, `"passage"`, `"card_number"`.
+    pub kind: &'static str,
+    /// Masked excerpt of the flagged content, safe to show in an approval prompt.
+    pub excerpt: String,
+}
+
+impl std::fmt::Display for EgressFinding {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} ({})", self.kind, self.excerpt)
+    }
+}
+
+/// Check outgoing content against the user's private context.
+///
+/// Returns one finding per distinct problem; empty means the content can
+/// leave without extra approval.
+pub fn check_egress(content: &str, private_context: &[&str]) -> Vec<EgressFinding> {
+    let mut findings = Findings::default();
+
+    // Sensitive patterns, regardless of context
+    for m in CARD.find_iter(content) {
+        if luhn_valid(m.as_str()) {
+            findings.push("card_number", m.as_str());
+        }
+    }
+    for m in SSN.find_iter(content) {
+        findings.push("ssn", m.as_str());
+    }
+
+    findings.context(content, private_context);
+    findings.list
+}
+
+/// Check outgoing content against `context` only: identifiers and verbatim
+/// passages from it, but not the context-free sensitive patterns.
+pub fn check_context_egress(content: &str, context: &[&str]) -> Vec<EgressFinding> {
+    let mut findings = Findings::default();
+    findings.context(content, context);
+    findings.list
+}
+
+/// Findings in the order found, one per distinct kind and text.
+#[derive(Default)]
+struct Findings {
+    list: Vec<EgressFinding>,
+    seen: HashSet<(&'static str, String)>,
+}
+
+impl Findings {
+    fn push(&mut self, kind: &'static str, text: &str) {
+        if self.seen.insert((kind, text.to_string())) {
+            self.list.push(EgressFinding {
+                kind,
+                excerpt: mask(text),
+            });
+        }
+    }
+
+    /// Identifiers and verbatim passages of `context` repeated in `content`.
+    fn context(&mut self, content: &str, context: &[&str]) {
+        // Identifiers from the context
+        let outgoing_digits = digits_only(content);
+        let outgoing_lower = content.to_lowercase();
+        for doc in context {
+            for m in EMAIL.find_iter(doc) {
+                if outgoing_lower.contains(&m.as_str().to_lowercase()) {
+                    self.push("email", m.as_str());
+                }
+            }
+            for m in PHONE.find_iter(doc) {
+                // Compare the national number so "+1 (415) ..." matches "415-...".
+                let digits = digits_only(m.as_str());
+                let national = &digits[digits.len().saturating_sub(10)..];
+                if national.len() >= 7 && outgoing_digits.contains(national) {
+                    self.push("phone", m.as_str());
+                }
+            }
+        }
+
+        // Verbatim passages
+        let outgoing_words = words(content);
+        if outgoing_words.len() >= SHINGLE_WORDS {
+            let outgoing_shingles: HashSet<&[String]> =
+                outgoing_words.windows(SHINGLE_WORDS).collect();
+            for doc in context {
+                let doc_words = words(doc);
+                if let Some(shared) = doc_words
+                    .windows(SHINGLE_WORDS)
+                    .find(|w| outgoing_shingles.contains(w))
+                {
+                    self.push("passage", &shared.join(" "));
+                }
+            }
+        }
+    }
+}
+
+/// Flatten tool arguments into the text that would leave the machine.
+///
+/// String leaves are taken verbatim (not JSON-escaped) so passages with
+/// newlines or quotes still match the private context.
+pub fn egress_payload_text(arguments: &serde_json::Value) -> String {
+    fn collect(value: &serde_json::Value, out: &mut Vec<String>) {
+        match value {
+            serde_json::Value::String(s) => out.push(s.clone()),
+            serde_json::Value::Array(items) => items.iter().for_each(|v| collect(v, out)),
+            serde_json::Value::Object(map) => map.values().for_each(|v| collect(v, out)),
+            serde_json::Value::Null => {}
+            other => out.push(other.to_string()),
+        }
+    }
+    let mut parts = Vec::new();
+    collect(arguments, &mut parts);
+    parts.join("\n")
+}
+
+/// Lowercased alphanumeric words.
+fn words(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .map(|w| w.to_lowercase())
+        .collect()
+}
+
+fn digits_only(text: &str) -> String {
+    text.chars().filter(|c| c.is_ascii_digit()).collect()
+}
+
+fn luhn_valid(number: &str) -> bool {
+    let digits: Vec<u32> = number.chars().filter_map(|c| c.to_digit(10)).collect();
+    if !(13..=19).contains(&digits.len()) {
+        return false;
+    }
+    let sum: u32 = digits
+        .iter()
+        .rev()
+        .enumerate()
+        .map(|(i, &d)| {
+            if i % 2 == 1 {
+                let doubled = d * 2;
+                if doubled > 9 { doubled - 9 } else { doubled }
+            } else {
+                d
+            }
+        })
+        .sum();
+    sum.is_multiple_of(10)
+}
+
+/// Keep the first few characters so the user can recognise the data.
+fn mask(text: &str) -> String {
+    let visible: String = text.chars().take(4).collect();
+    if text.chars().count() <= 4 {
+        return "*".repeat(text.chars().count());
+    }
+    format!("{}…", visible)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const USER_MD: &str = "# User\n\nName: Alice Example\nEmail: alice@example.com\n\
+                           Phone: +1 (415) 555-0134\n\
+                           Alice is recovering from knee surgery at Mercy hospital in March.";
+
+    #[test]
+    fn test_unrelated_content_passes() {
+        let findings = check_egress("Meeting moved to 3pm, see you there!", &[USER_MD]);
+        assert!(findings.is_empty());
+    }
+
+    #[test]
+    fn test_private_email_and_phone_flagged() {
+        let findings = check_egress(
+            "Contact her at ALICE@example.com or 415-555-0134",
+            &[USER_MD],
+        );
+        let kinds: Vec<_> = findings.iter().map(|f| f.kind).collect();
+        assert_eq!(kinds, vec!["email", "phone"]);
+        assert!(!findings[0].excerpt.contains("example.com"));
+    }
+
+    #[test]
+    fn test_verbatim_passage_flagged() {
+        let findings = check_egress(
+            "FYI: she is recovering from knee surgery at Mercy hospital",
+            &[USER_MD],
+        );
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].kind, "passage");
+    }
+
+    #[test]
+    fn test_payload_text_unescapes_strings() {
+        let args = serde_json::json!({
+            "to": "bob@example.org",
+            "body": {"text": "line one\n\"quoted\""},
+            "count": 2
+        });
+        let text = egress_payload_text(&args);
+        assert!(text.contains("line one\n\"quoted\""));
+        assert!(text.contains("bob@example.org"));
+        assert!(text.contains('2'));
+    }
+
+    #[test]
+    fn test_sensitive_patterns_flagged_without_context() {
+        let findings = check_egress("card 4111 1111 1111 1111, ssn 123-45-6789", &[]);
+        let kinds: Vec<_> = findings.iter().map(|f| f.kind).collect();
+        assert_eq!(kinds, vec!["card_number", "ssn"]);
+        // Not Luhn-valid: an order number, not a card
+        assert!(check_egress("order 4111 1111 1111 1112", &[]).is_empty());
+    }
+
+    #[test]
+    fn test_context_egress_ignores_patterns() {
+        let content = "Card 4111 1111 1111 1111; call 415-555-0134";
+        let findings = check_context_egress(content, &[USER_MD]);
+        let kinds: Vec<_> = findings.iter().map(|f| f.kind).collect();
+        assert_eq!(kinds, vec!["phone"]);
+        assert!(check_context_egress(content, &[]).is_empty());
+    }
+}
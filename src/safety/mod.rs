@@ -6,17 +6,33 @@
 //! - Validating inputs before processing
 //! - Enforcing safety policies
 //! - Detecting secret leakage in outputs
+//! - Redacting known secrets and credentials from transcripts and logs
+//! - Guarding external actions against leaking the user's private data
+//! - Enforcing per-user tool and workspace permissions
+//! - Moderating outgoing messages on public-facing channels
 
+mod egress;
 mod leak_detector;
+pub mod moderation;
+mod permissions;
 mod policy;
+mod redactor;
 mod sanitizer;
 mod validator;
 
+pub use egress::{EgressFinding, check_context_egress, check_egress, egress_payload_text};
 pub use leak_detector::{
     LeakAction, LeakDetectionError, LeakDetector, LeakMatch, LeakPattern, LeakScanResult,
     LeakSeverity,
 };
+pub use moderation::{Intervention, Moderation, ModerationAction, Moderator};
+pub(crate) use permissions::tool_matches;
+pub use permissions::{
+    PERMISSIONS_SETTING_KEY, PermissionError, PermissionPolicy, Permissions, Role,
+};
 pub use policy::{Policy, PolicyAction, PolicyRule, Severity};
+pub(crate) use redactor::REDACTED;
+pub use redactor::SecretRedactor;
 pub use sanitizer::{InjectionWarning, SanitizedOutput, Sanitizer};
 pub use validator::{ValidationResult, Validator};
 
@@ -66,8 +82,10 @@ impl SafetyLayer {
             };
         }
 
-        let mut content = output.to_string();
-        let mut was_modified = false;
+        // Mask known secrets and credential patterns first, so a secret we
+        // injected ourselves is redacted rather than blocking the whole output.
+        let mut content = SecretRedactor::global().redact(output);
+        let mut was_modified = content != output;
 
         // Leak detection and redaction
         match self.leak_detector.scan_and_clean(&content) {
@@ -0,0 +1,463 @@
+//! Moderation of outgoing messages on public-facing channels.
+//!
+//! Responses sent on the channels listed in `MODERATION_CHANNELS` are
+//! checked by every configured [`Moderator`] before delivery: local rules
+//! ([`RuleModerator`], from `MODERATION_BLOCKED_TERMS` and
+//! `MODERATION_RULES_FILE`) and, with `MODERATION_PROVIDER=openai`, the
+//! OpenAI moderation API ([`OpenAiModerator`]). When a message is flagged,
+//! `MODERATION_ACTION` decides what happens to it:
+//!
+//! - `block`: the message is replaced with `MODERATION_BLOCK_MESSAGE`
+//! - `rewrite`: the flagged text is replaced with `[removed]`; a message
+//!   flagged as a whole (by a provider) is blocked instead
+//! - `flag`: the message is held until it is approved or rejected through
+//!   the gateway (`/api/moderation`)
+//!
+//! A moderator that fails counts as flagging the message, so nothing goes
+//! out unchecked. The channel manager records every intervention in the
+//! audit log.
+
+use std::collections::HashMap;
+use std::ops::Range;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use regex::{Regex, RegexBuilder};
+use secrecy::{ExposeSecret, SecretString};
+use serde::Deserialize;
+
+use crate::config::ModerationConfig;
+
+/// Replacement for flagged text when rewriting.
+const REMOVED: &str = "[removed]";
+
+/// Category reported when a moderator could not check a message.
+const UNAVAILABLE: &str = "moderation_unavailable";
+
+/// What to do with a flagged message (`MODERATION_ACTION`).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ModerationAction {
+    /// Send a notice instead of the message.
+    #[default]
+    Block,
+    /// Send the message with the flagged text removed.
+    Rewrite,
+    /// Hold the message until someone approves it.
+    Flag,
+}
+
+impl ModerationAction {
+    /// Audit outcome of an intervention with this action.
+    pub fn outcome(&self) -> &'static str {
+        match self {
+            Self::Block => "blocked",
+            Self::Rewrite => "rewritten",
+            Self::Flag => "held",
+        }
+    }
+}
+
+impl std::str::FromStr for ModerationAction {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "block" => Ok(Self::Block),
+            "rewrite" => Ok(Self::Rewrite),
+            "flag" | "approve" => Ok(Self::Flag),
+            _ => Err(format!(
+                "invalid moderation action '{}', expected one of: block, rewrite, flag",
+                s
+            )),
+        }
+    }
+}
+
+impl std::fmt::Display for ModerationAction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Block => write!(f, "block"),
+            Self::Rewrite => write!(f, "rewrite"),
+            Self::Flag => write!(f, "flag"),
+        }
+    }
+}
+
+/// Why a moderator flagged a message.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ModerationFlag {
+    /// Name of the moderator.
+    pub moderator: String,
+    /// What the message was flagged for.
+    pub categories: Vec<String>,
+    /// Byte ranges of the flagged text; empty if the message is flagged as
+    /// a whole.
+    pub spans: Vec<Range<usize>>,
+}
+
+/// Error from a moderator.
+#[derive(Debug, thiserror::Error)]
+#[error("moderator {moderator} failed: {reason}")]
+pub struct ModerationError {
+    pub moderator: String,
+    pub reason: String,
+}
+
+/// A check applied to outgoing messages.
+#[async_trait]
+pub trait Moderator: Send + Sync {
+    /// Name used in logs and audit entries.
+    fn name(&self) -> &str;
+
+    /// Check `text`, returning why it is flagged, if it is.
+    async fn check(&self, text: &str) -> Result<Option<ModerationFlag>, ModerationError>;
+}
+
+/// Flags messages matching local regular expressions (case-insensitive).
+pub struct RuleModerator {
+    rules: Vec<Regex>,
+}
+
+impl RuleModerator {
+    pub fn new(patterns: &[String]) -> Result<Self, regex::Error> {
+        let rules = patterns
+            .iter()
+            .map(|p| RegexBuilder::new(p).case_insensitive(true).build())
+            .collect::<Result<_, _>>()?;
+        Ok(Self { rules })
+    }
+}
+
+#[async_trait]
+impl Moderator for RuleModerator {
+    fn name(&self) -> &str {
+        "rules"
+    }
+
+    async fn check(&self, text: &str) -> Result<Option<ModerationFlag>, ModerationError> {
+        let mut categories = Vec::new();
+        let mut spans = Vec::new();
+        for rule in &self.rules {
+            let before = spans.len();
+            spans.extend(rule.find_iter(text).map(|m| m.range()));
+            if spans.len() > before {
+                categories.push(rule.as_str().to_string());
+            }
+        }
+        Ok((!spans.is_empty()).then(|| ModerationFlag {
+            moderator: self.name().to_string(),
+            categories,
+            spans,
+        }))
+    }
+}
+
+/// Flags messages with the OpenAI moderation API.
+pub struct OpenAiModerator {
+    client: reqwest::Client,
+    api_key: SecretString,
+    url: String,
+}
+
+impl OpenAiModerator {
+    pub fn new(api_key: SecretString) -> Self {
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(30))
+            .build()
+            .unwrap_or_else(|_| reqwest::Client::new());
+        Self {
+            client,
+            api_key,
+            url: "https://api.openai.com/v1/moderations".to_string(),
+        }
+    }
+
+    fn error(&self, reason: impl std::fmt::Display) -> ModerationError {
+        ModerationError {
+            moderator: self.name().to_string(),
+            reason: reason.to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct OpenAiModerationResponse {
+    results: Vec<OpenAiModerationResult>,
+}
+
+#[derive(Debug, Deserialize)]
+struct OpenAiModerationResult {
+    flagged: bool,
+    #[serde(default)]
+    categories: HashMap<String, bool>,
+}
+
+#[async_trait]
+impl Moderator for OpenAiModerator {
+    fn name(&self) -> &str {
+        "openai"
+    }
+
+    async fn check(&self, text: &str) -> Result<Option<ModerationFlag>, ModerationError> {
+        let response = self
+            .client
+            .post(&self.url)
+            .bearer_auth(self.api_key.expose_secret())
+            .json(&serde_json::json!({
+                "model": "omni-moderation-latest",
+                "input": text,
+            }))
+            .send()
+            .await
+            .map_err(|e| self.error(e))?;
+        let status = response.status();
+        if !status.is_success() {
+            return Err(self.error(format!("HTTP {}", status)));
+        }
+        let body: OpenAiModerationResponse = response.json().await.map_err(|e| self.error(e))?;
+        if !body.results.iter().any(|r| r.flagged) {
+            return Ok(None);
+        }
+
+        let mut categories: Vec<String> = body
+            .results
+            .iter()
+            .filter(|r| r.flagged)
+            .flat_map(|r| r.categories.iter().filter(|(_, hit)| **hit))
+            .map(|(category, _)| category.clone())
+            .collect();
+        categories.sort();
+        categories.dedup();
+        Ok(Some(ModerationFlag {
+            moderator: self.name().to_string(),
+            categories,
+            spans: Vec::new(),
+        }))
+    }
+}
+
+/// What moderation did to a flagged message.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Intervention {
+    /// The action taken, which may be stricter than the configured one.
+    pub action: ModerationAction,
+    /// Everything the message was flagged for, across moderators.
+    pub categories: Vec<String>,
+    /// What to send instead, or None if the message is held.
+    pub replacement: Option<String>,
+}
+
+/// The moderation policy for outgoing messages.
+pub struct Moderation {
+    moderators: Vec<Box<dyn Moderator>>,
+    action: ModerationAction,
+    /// Moderated channels; `*` matches every channel.
+    channels: Vec<String>,
+    block_message: String,
+}
+
+impl Moderation {
+    pub fn new(
+        moderators: Vec<Box<dyn Moderator>>,
+        action: ModerationAction,
+        channels: Vec<String>,
+        block_message: impl Into<String>,
+    ) -> Self {
+        Self {
+            moderators,
+            action,
+            channels,
+            block_message: block_message.into(),
+        }
+    }
+
+    /// The policy in `config`, or None if no channel is moderated.
+    pub fn from_config(config: &ModerationConfig) -> Result<Option<Self>, regex::Error> {
+        if !config.enabled() {
+            return Ok(None);
+        }
+        let mut moderators: Vec<Box<dyn Moderator>> = Vec::new();
+        if !config.rules.is_empty() {
+            moderators.push(Box::new(RuleModerator::new(&config.rules)?));
+        }
+        if let Some(ref api_key) = config.openai_api_key {
+            moderators.push(Box::new(OpenAiModerator::new(api_key.clone())));
+        }
+        Ok(Some(Self::new(
+            moderators,
+            config.action,
+            config.channels.clone(),
+            config.block_message.clone(),
+        )))
+    }
+
+    /// Whether messages sent on `channel` are moderated.
+    pub fn applies_to(&self, channel: &str) -> bool {
+        self.channels.iter().any(|c| c == "*" || c == channel)
+    }
+
+    /// Check `content` with every moderator. None if nothing flagged it.
+    pub async fn review(&self, content: &str) -> Option<Intervention> {
+        let mut flags = Vec::new();
+        for moderator in &self.moderators {
+            match moderator.check(content).await {
+                Ok(Some(flag)) => flags.push(flag),
+                Ok(None) => {}
+                Err(e) => {
+                    tracing::warn!("{}", e);
+                    flags.push(ModerationFlag {
+                        moderator: moderator.name().to_string(),
+                        categories: vec![UNAVAILABLE.to_string()],
+                        spans: Vec::new(),
+                    });
+                }
+            }
+        }
+        if flags.is_empty() {
+            return None;
+        }
+
+        let mut categories: Vec<String> = flags
+            .iter()
+            .flat_map(|f| f.categories.iter().cloned())
+            .collect();
+        categories.sort();
+        categories.dedup();
+        let rewritable = flags.iter().all(|f| !f.spans.is_empty());
+        let (action, replacement) = match self.action {
+            ModerationAction::Flag => (ModerationAction::Flag, None),
+            ModerationAction::Rewrite if rewritable => {
+                let spans = flags.into_iter().flat_map(|f| f.spans).collect();
+                (
+                    ModerationAction::Rewrite,
+                    Some(remove_spans(content, spans)),
+                )
+            }
+            _ => (ModerationAction::Block, Some(self.block_message.clone())),
+        };
+        Some(Intervention {
+            action,
+            categories,
+            replacement,
+        })
+    }
+}
+
+/// `text` with each of `spans` (merged where they overlap) replaced by
+/// [`REMOVED`].
+fn remove_spans(text: &str, mut spans: Vec<Range<usize>>) -> String {
+    spans.sort_by_key(|s| s.start);
+    let mut out = String::with_capacity(text.len());
+    let mut pos = 0;
+    for span in spans {
+        if span.end <= pos {
+            continue;
+        }
+        if span.start >= pos {
+            out.push_str(&text[pos..span.start]);
+            out.push_str(REMOVED);
+        }
+        pos = span.end;
+    }
+    out.push_str(&text[pos..]);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Failing;
+
+    #[async_trait]
+    impl Moderator for Failing {
+        fn name(&self) -> &str {
+            "failing"
+        }
+
+        async fn check(&self, _text: &str) -> Result<Option<ModerationFlag>, ModerationError> {
+            Err(ModerationError {
+                moderator: "failing".to_string(),
+                reason: "timeout".to_string(),
+            })
+        }
+    }
+
+    fn rules(patterns: &[&str]) -> Box<dyn Moderator> {
+        let patterns: Vec<String> = patterns.iter().map(|p| p.to_string()).collect();
+        Box::new(RuleModerator::new(&patterns).unwrap())
+    }
+
+    #[test]
+    fn test_action_parsing() {
+        assert_eq!(
+            "Rewrite".parse::<ModerationAction>(),
+            Ok(ModerationAction::Rewrite)
+        );
+        assert_eq!(
+            "flag".parse::<ModerationAction>(),
+            Ok(ModerationAction::Flag)
+        );
+        assert!("delete".parse::<ModerationAction>().is_err());
+        assert_eq!(ModerationAction::Flag.to_string(), "flag");
+    }
+
+    #[tokio::test]
+    async fn test_rewrite_removes_matches() {
+        let moderation = Moderation::new(
+            vec![rules(&[r"\bdarn\b", r"secret plan"])],
+            ModerationAction::Rewrite,
+            vec!["telegram".to_string()],
+            "Withheld.",
+        );
+        assert!(moderation.applies_to("telegram"));
+        assert!(!moderation.applies_to("cli"));
+        assert_eq!(moderation.review("All good here").await, None);
+
+        let intervention = moderation
+            .review("Darn, the Secret Plan leaked. darn.")
+            .await
+            .unwrap();
+        assert_eq!(intervention.action, ModerationAction::Rewrite);
+        assert_eq!(
+            intervention.replacement.as_deref(),
+            Some("[removed], the [removed] leaked. [removed].")
+        );
+        assert_eq!(intervention.categories.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_unlocated_flags_block_and_flag_holds() {
+        // A failing moderator flags the whole message, so a rewrite
+        // becomes a block.
+        let moderation = Moderation::new(
+            vec![rules(&["darn"]), Box::new(Failing)],
+            ModerationAction::Rewrite,
+            vec!["*".to_string()],
+            "Withheld.",
+        );
+        assert!(moderation.applies_to("anything"));
+        let intervention = moderation.review("fine text").await.unwrap();
+        assert_eq!(intervention.action, ModerationAction::Block);
+        assert_eq!(intervention.replacement.as_deref(), Some("Withheld."));
+        assert_eq!(intervention.categories, vec![UNAVAILABLE.to_string()]);
+
+        let moderation = Moderation::new(
+            vec![rules(&["darn"])],
+            ModerationAction::Flag,
+            vec!["*".to_string()],
+            "Withheld.",
+        );
+        let intervention = moderation.review("darn").await.unwrap();
+        assert_eq!(intervention.action, ModerationAction::Flag);
+        assert_eq!(intervention.replacement, None);
+    }
+
+    #[test]
+    fn test_remove_overlapping_spans() {
+        assert_eq!(
+            remove_spans("abcdefgh", vec![4..6, 1..3, 2..5]),
+            "a[removed]gh"
+        );
+    }
+}
@@ -0,0 +1,362 @@
+//! Per-user and per-tool permissions.
+//!
+//! Maps users to roles, and each role to the tools it may call and the
+//! workspace paths it may touch. The policy is stored as JSON in the owner's
+//! settings (key `permissions`) and can be replaced at runtime through
+//! `PUT /api/permissions`; the running agent picks up the change immediately.
+//!
+//! ```json
+//! {
+//!   "roles": {
+//!     "guest": {
+//!       "tools": ["memory_search", "memory_read", "time"],
+//!       "deny_paths": ["MEMORY.md", "USER.md"]
+//!     }
+//!   },
+//!   "users": { "telegram-4815162342": "guest" },
+//!   "default_role": null
+//! }
+//! ```
+//!
+//! Rules:
+//! - A user without a role (and no `default_role`) is unrestricted, so an
+//!   empty policy behaves as if permissions didn't exist.
+//! - Deny lists win over allow lists.
+//! - Tool patterns match exact names, `*`, or a `prefix*` glob.
+//! - Path rules are workspace path prefixes matched on whole segments
+//!   (`daily` covers `daily/2024-01-15.md`, not `daily_notes.md`),
+//!   case-insensitively.
+//! - A user mapped to a role that doesn't exist is denied everything.
+
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+
+use serde::{Deserialize, Serialize};
+
+use crate::db::Database;
+use crate::error::DatabaseError;
+
+/// Settings key the policy is stored under.
+pub const PERMISSIONS_SETTING_KEY: &str = "permissions";
+
+/// Errors from loading or updating the permission policy.
+#[derive(Debug, thiserror::Error)]
+pub enum PermissionError {
+    #[error("Invalid permission policy: {0}")]
+    InvalidPolicy(String),
+
+    #[error(transparent)]
+    Database(#[from] DatabaseError),
+}
+
+/// What a role may do.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Role {
+    /// Tools the role may call (`*` for all, `prefix*` globs allowed).
+    #[serde(default = "allow_all")]
+    pub tools: Vec<String>,
+    /// Tools the role may never call, even if matched by `tools`.
+    #[serde(default)]
+    pub deny_tools: Vec<String>,
+    /// Workspace path prefixes the role may read and write (`*` for all).
+    #[serde(default = "allow_all")]
+    pub paths: Vec<String>,
+    /// Workspace path prefixes the role may never access.
+    #[serde(default)]
+    pub deny_paths: Vec<String>,
+}
+
+impl Default for Role {
+    fn default() -> Self {
+        Self {
+            tools: allow_all(),
+            deny_tools: Vec::new(),
+            paths: allow_all(),
+            deny_paths: Vec::new(),
+        }
+    }
+}
+
+fn allow_all() -> Vec<String> {
+    vec!["*".to_string()]
+}
+
+impl Role {
+    /// Whether this role may call `tool_name`.
+    pub fn allows_tool(&self, tool_name: &str) -> bool {
+        !self.deny_tools.iter().any(|p| tool_matches(p, tool_name))
+            && self.tools.iter().any(|p| tool_matches(p, tool_name))
+    }
+
+    /// Whether this role may access the workspace document at `path`.
+    pub fn allows_path(&self, path: &str) -> bool {
+        let path = normalize(path);
+        !self.deny_paths.iter().any(|p| path_matches(p, &path))
+            && self.paths.iter().any(|p| path_matches(p, &path))
+    }
+}
+
+/// The full permission policy: roles and who holds them.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PermissionPolicy {
+    /// Role definitions by name.
+    #[serde(default)]
+    pub roles: HashMap<String, Role>,
+    /// User ID to role name.
+    #[serde(default)]
+    pub users: HashMap<String, String>,
+    /// Role for users not listed in `users`. `None` leaves them unrestricted.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default_role: Option<String>,
+}
+
+/// Resolved role for a user.
+enum Assignment<'a> {
+    Unrestricted,
+    Role(&'a Role),
+    /// Assigned a role that isn't defined: fail closed.
+    Missing,
+}
+
+impl PermissionPolicy {
+    /// Check that every referenced role is defined.
+    pub fn validate(&self) -> Result<(), PermissionError> {
+        let referenced = self.users.values().chain(self.default_role.iter());
+        for role in referenced {
+            if !self.roles.contains_key(role) {
+                return Err(PermissionError::InvalidPolicy(format!(
+                    "role '{}' is assigned but not defined",
+                    role
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    fn assignment(&self, user_id: &str) -> Assignment<'_> {
+        let Some(role_name) = self.users.get(user_id).or(self.default_role.as_ref()) else {
+            return Assignment::Unrestricted;
+        };
+        match self.roles.get(role_name) {
+            Some(role) => Assignment::Role(role),
+            None => Assignment::Missing,
+        }
+    }
+
+    /// Whether `user_id` may call `tool_name`.
+    pub fn allows_tool(&self, user_id: &str, tool_name: &str) -> bool {
+        match self.assignment(user_id) {
+            Assignment::Unrestricted => true,
+            Assignment::Role(role) => role.allows_tool(tool_name),
+            Assignment::Missing => false,
+        }
+    }
+
+    /// Whether `user_id` may access the workspace document at `path`.
+    pub fn allows_path(&self, user_id: &str, path: &str) -> bool {
+        match self.assignment(user_id) {
+            Assignment::Unrestricted => true,
+            Assignment::Role(role) => role.allows_path(path),
+            Assignment::Missing => false,
+        }
+    }
+
+    /// Whether `user_id` has any path restrictions at all.
+    pub fn is_unrestricted(&self, user_id: &str) -> bool {
+        matches!(self.assignment(user_id), Assignment::Unrestricted)
+    }
+}
+
+/// Live permission policy shared by the tool dispatcher, the workspace,
+/// and the web API.
+pub struct Permissions {
+    policy: RwLock<PermissionPolicy>,
+    /// Where updates are persisted: database and the owner's user ID.
+    store: Option<(Arc<dyn Database>, String)>,
+}
+
+impl Permissions {
+    /// In-memory permissions with a fixed initial policy (not persisted).
+    pub fn new(policy: PermissionPolicy) -> Self {
+        Self {
+            policy: RwLock::new(policy),
+            store: None,
+        }
+    }
+
+    /// Load the policy from the owner's settings.
+    ///
+    /// A missing setting yields an empty (unrestricted) policy. A stored
+    /// policy that no longer parses is an error rather than silently
+    /// dropping restrictions.
+    pub async fn load(
+        store: Arc<dyn Database>,
+        owner_id: impl Into<String>,
+    ) -> Result<Self, PermissionError> {
+        let owner_id = owner_id.into();
+        let policy = match store
+            .get_setting(&owner_id, PERMISSIONS_SETTING_KEY)
+            .await?
+        {
+            Some(value) => serde_json::from_value(value)
+                .map_err(|e| PermissionError::InvalidPolicy(e.to_string()))?,
+            None => PermissionPolicy::default(),
+        };
+        policy.validate()?;
+        Ok(Self {
+            policy: RwLock::new(policy),
+            store: Some((store, owner_id)),
+        })
+    }
+
+    /// Snapshot of the current policy.
+    pub fn policy(&self) -> PermissionPolicy {
+        self.read(|p| p.clone())
+    }
+
+    /// Validate, persist, and activate a new policy.
+    pub async fn set_policy(&self, policy: PermissionPolicy) -> Result<(), PermissionError> {
+        policy.validate()?;
+        if let Some((store, owner_id)) = &self.store {
+            let value = serde_json::to_value(&policy)
+                .map_err(|e| PermissionError::InvalidPolicy(e.to_string()))?;
+            store
+                .set_setting(owner_id, PERMISSIONS_SETTING_KEY, &value)
+                .await?;
+        }
+        if let Ok(mut current) = self.policy.write() {
+            *current = policy;
+        }
+        Ok(())
+    }
+
+    /// Whether `user_id` may call `tool_name`.
+    pub fn allows_tool(&self, user_id: &str, tool_name: &str) -> bool {
+        self.read(|p| p.allows_tool(user_id, tool_name))
+    }
+
+    /// Whether `user_id` may access the workspace document at `path`.
+    pub fn allows_path(&self, user_id: &str, path: &str) -> bool {
+        self.read(|p| p.allows_path(user_id, path))
+    }
+
+    /// Whether `user_id` has no restrictions (lets callers skip path lookups).
+    pub fn is_unrestricted(&self, user_id: &str) -> bool {
+        self.read(|p| p.is_unrestricted(user_id))
+    }
+
+    fn read<T>(&self, f: impl FnOnce(&PermissionPolicy) -> T) -> T {
+        // A poisoned lock still holds the last policy that was fully written.
+        match self.policy.read() {
+            Ok(policy) => f(&policy),
+            Err(poisoned) => f(&poisoned.into_inner()),
+        }
+    }
+}
+
+impl Default for Permissions {
+    fn default() -> Self {
+        Self::new(PermissionPolicy::default())
+    }
+}
+
+/// Whether `tool_name` matches `pattern` (`*`, `prefix*`, or an exact name).
+pub(crate) fn tool_matches(pattern: &str, tool_name: &str) -> bool {
+    match pattern.strip_suffix('*') {
+        Some(prefix) => tool_name.starts_with(prefix),
+        None => pattern == tool_name,
+    }
+}
+
+fn path_matches(rule: &str, normalized_path: &str) -> bool {
+    let rule = normalize(rule);
+    if rule == "*" || rule.is_empty() {
+        return true;
+    }
+    normalized_path == rule
+        || normalized_path
+            .strip_prefix(&rule)
+            .is_some_and(|rest| rest.starts_with('/'))
+}
+
+fn normalize(path: &str) -> String {
+    path.trim().trim_matches('/').to_lowercase()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn guest_policy() -> PermissionPolicy {
+        serde_json::from_value(serde_json::json!({
+            "roles": {
+                "guest": {
+                    "tools": ["memory_search", "memory_read", "time"],
+                    "deny_paths": ["MEMORY.md", "private"]
+                },
+                "helper": { "deny_tools": ["shell", "memory_*"] }
+            },
+            "users": { "guest1": "guest", "helper1": "helper", "ghost": "nobody" }
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_unassigned_users_are_unrestricted() {
+        let policy = guest_policy();
+        assert!(policy.allows_tool("owner", "shell"));
+        assert!(policy.allows_path("owner", "MEMORY.md"));
+        assert!(PermissionPolicy::default().allows_tool("anyone", "shell"));
+    }
+
+    #[test]
+    fn test_tool_allow_and_deny_lists() {
+        let policy = guest_policy();
+        assert!(policy.allows_tool("guest1", "memory_search"));
+        assert!(!policy.allows_tool("guest1", "shell"));
+
+        assert!(policy.allows_tool("helper1", "http"));
+        assert!(!policy.allows_tool("helper1", "shell"));
+        assert!(!policy.allows_tool("helper1", "memory_read"));
+    }
+
+    #[test]
+    fn test_path_rules_match_whole_segments() {
+        let policy = guest_policy();
+        assert!(!policy.allows_path("guest1", "MEMORY.md"));
+        assert!(!policy.allows_path("guest1", "/memory.md"));
+        assert!(!policy.allows_path("guest1", "private/diary.md"));
+        assert!(policy.allows_path("guest1", "private_notes.md"));
+        assert!(policy.allows_path("guest1", "daily/2024-01-15.md"));
+    }
+
+    #[test]
+    fn test_missing_role_fails_closed() {
+        let policy = guest_policy();
+        assert!(!policy.allows_tool("ghost", "time"));
+        assert!(!policy.allows_path("ghost", "README.md"));
+        assert!(policy.validate().is_err());
+    }
+
+    #[test]
+    fn test_default_role_applies_to_unlisted_users() {
+        let mut policy = guest_policy();
+        policy.users.remove("ghost");
+        policy.default_role = Some("guest".to_string());
+        assert!(policy.validate().is_ok());
+        assert!(!policy.allows_tool("stranger", "shell"));
+        assert!(!policy.is_unrestricted("stranger"));
+    }
+
+    #[tokio::test]
+    async fn test_set_policy_rejects_undefined_roles() {
+        let permissions = Permissions::default();
+        assert!(permissions.set_policy(guest_policy()).await.is_err());
+        assert!(permissions.allows_tool("ghost", "shell"));
+
+        let mut policy = guest_policy();
+        policy.users.remove("ghost");
+        permissions.set_policy(policy).await.unwrap();
+        assert!(!permissions.allows_tool("guest1", "shell"));
+    }
+}
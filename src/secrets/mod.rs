@@ -6,6 +6,8 @@
 //! - PostgreSQL persistence
 //! - OS keychain integration for master key
 //! - Access control for WASM tools
+//! - `{{secret:NAME}}` placeholders resolved by built-in tools (HTTP, shell),
+//!   each secret only for the hosts or commands it is bound to
 //!
 //! # Security Model
 //!
@@ -59,6 +61,7 @@
 //! ```
 
 mod crypto;
+pub mod inject;
 pub mod keychain;
 mod store;
 mod types;
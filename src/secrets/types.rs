@@ -157,6 +157,9 @@ pub enum SecretError {
     #[error("Secret access denied for tool")]
     AccessDenied,
 
+    #[error("Secret '{name}' may not be used with {target}")]
+    NotAllowed { name: String, target: String },
+
     #[error("Keychain error: {0}")]
     KeychainError(String),
 }
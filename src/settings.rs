@@ -97,6 +97,10 @@ pub struct Settings {
     /// Builder configuration.
     #[serde(default)]
     pub builder: BuilderSettings,
+
+    /// Templates seeded into new workspaces.
+    #[serde(default)]
+    pub workspace_seed: WorkspaceSeedSettings,
 }
 
 /// Source for the secrets master key.
@@ -107,6 +111,8 @@ pub enum KeySource {
     Keychain,
     /// User provides via SECRETS_MASTER_KEY env var.
     Env,
+    /// Hex key read from the file named by SECRETS_MASTER_KEY_FILE.
+    File,
     /// Not configured (secrets features disabled).
     #[default]
     None,
@@ -189,6 +195,10 @@ pub struct ChannelSettings {
     /// Directory containing WASM channel modules.
     #[serde(default)]
     pub wasm_channels_dir: Option<PathBuf>,
+
+    /// Web gateway chat messages accepted per minute.
+    #[serde(default)]
+    pub gateway_chat_rate_limit: Option<u64>,
 }
 
 /// Heartbeat configuration.
@@ -261,6 +271,10 @@ pub struct AgentSettings {
     /// longer than this are pruned from memory.
     #[serde(default = "default_session_idle_timeout")]
     pub session_idle_timeout_secs: u64,
+
+    /// Tools hidden from the LLM and refused if called.
+    #[serde(default)]
+    pub disabled_tools: Vec<String>,
 }
 
 fn default_agent_name() -> String {
@@ -306,6 +320,7 @@ impl Default for AgentSettings {
             repair_check_interval_secs: default_repair_interval(),
             max_repair_attempts: default_max_repair_attempts(),
             session_idle_timeout_secs: default_session_idle_timeout(),
+            disabled_tools: Vec::new(),
         }
     }
 }
@@ -508,6 +523,39 @@ impl Default for BuilderSettings {
     }
 }
 
+/// Workspace seed template configuration.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct WorkspaceSeedSettings {
+    /// Directory of templates overriding or extending the built-ins.
+    #[serde(default)]
+    pub template_dir: Option<PathBuf>,
+
+    /// Locale whose templates under `<template_dir>/locales/` apply.
+    #[serde(default)]
+    pub locale: Option<String>,
+
+    /// Only seed these workspace paths (all templates when unset).
+    #[serde(default)]
+    pub files: Option<Vec<String>>,
+
+    /// Agent name substituted into the templates.
+    #[serde(default)]
+    pub agent_name: Option<String>,
+
+    /// Inline templates, applied after the template directory.
+    #[serde(default)]
+    pub templates: Vec<SeedTemplateSettings>,
+}
+
+/// One inline seed template.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SeedTemplateSettings {
+    /// Workspace path to seed.
+    pub path: String,
+    /// File content.
+    pub content: String,
+}
+
 impl Settings {
     /// Reconstruct Settings from a flat key-value map (as stored in the DB).
     ///
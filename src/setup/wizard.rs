@@ -1027,6 +1027,9 @@ impl SetupWizard {
             anthropic: None,
             ollama: None,
             openai_compatible: None,
+            groq: None,
+            together: None,
+            fast_backend: None,
         };
 
         match create_llm_provider(&config, session) {
@@ -1571,6 +1574,7 @@ impl SetupWizard {
         match self.settings.secrets_master_key_source {
             KeySource::Keychain => println!("  Security: OS keychain"),
             KeySource::Env => println!("  Security: environment variable"),
+            KeySource::File => println!("  Security: key file"),
             KeySource::None => println!("  Security: disabled"),
         }
 
@@ -0,0 +1,701 @@
+//! Installing, updating, and removing skill packages.
+//!
+//! A package is a skill directory (`SKILL.md` plus any assets) that may
+//! also carry:
+//!
+//! - `skill.json`, a manifest with the skill's name, version, and the
+//!   SHA-256 of every file in the package
+//! - `skill.sig`, a base64 Ed25519 signature of `skill.json`
+//! - a WASM tool, named by the manifest's `wasm` and `capabilities` entries,
+//!   which is installed into the WASM tools directory alongside the skill
+//!
+//! Packages come from a git repository (optionally a subdirectory of it), a
+//! local directory, or a registry index that maps skill names to git
+//! repositories.
+//!
+//! Installing checks every file against the manifest and the signature
+//! against the trusted keys. Packages without a manifest or signature are
+//! refused unless explicitly allowed. Where a skill came from is recorded in
+//! `.install.json` inside its directory, so it can be updated later.
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::skills::{SKILL_FILE, Skill, SkillError, SkillSource};
+use crate::tools::wasm::CapabilitiesFile;
+
+/// Package manifest file.
+pub const MANIFEST_FILE: &str = "skill.json";
+
+/// Detached signature of the manifest.
+pub const SIGNATURE_FILE: &str = "skill.sig";
+
+/// Where an installed skill came from, inside its directory.
+pub const INSTALL_RECORD_FILE: &str = ".install.json";
+
+/// Package manifest (`skill.json`).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SkillManifest {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+    /// SHA-256 (hex) of every file in the package, by relative path.
+    #[serde(default)]
+    pub files: BTreeMap<String, String>,
+    /// WASM tool shipped with the skill.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub wasm: Option<String>,
+    /// Capabilities file for the WASM tool.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub capabilities: Option<String>,
+}
+
+/// Where to fetch a package from.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum PackageSource {
+    Git {
+        url: String,
+        /// Branch or tag (default branch if unset).
+        #[serde(rename = "ref", default, skip_serializing_if = "Option::is_none")]
+        reference: Option<String>,
+        /// Package directory within the repository.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        subdir: Option<String>,
+    },
+    Local {
+        path: PathBuf,
+    },
+}
+
+impl PackageSource {
+    /// Interpret `spec` as a git URL or a local directory. Returns `None`
+    /// for anything else, which callers may look up in a registry.
+    pub fn parse(spec: &str, reference: Option<String>, subdir: Option<String>) -> Option<Self> {
+        let is_git = spec.contains("://") || spec.starts_with("git@") || spec.ends_with(".git");
+        if is_git {
+            return Some(Self::Git {
+                url: spec.to_string(),
+                reference,
+                subdir,
+            });
+        }
+        let path = Path::new(spec);
+        path.is_dir().then(|| Self::Local {
+            path: match subdir {
+                Some(subdir) => path.join(subdir),
+                None => path.to_path_buf(),
+            },
+        })
+    }
+}
+
+impl std::fmt::Display for PackageSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Git {
+                url,
+                reference,
+                subdir,
+            } => {
+                write!(f, "{}", url)?;
+                if let Some(reference) = reference {
+                    write!(f, "@{}", reference)?;
+                }
+                if let Some(subdir) = subdir {
+                    write!(f, " ({})", subdir)?;
+                }
+                Ok(())
+            }
+            Self::Local { path } => write!(f, "{}", path.display()),
+        }
+    }
+}
+
+/// Contents of `.install.json`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InstallRecord {
+    pub source: PackageSource,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+    /// Commit installed, for git sources.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub commit: Option<String>,
+    /// Trusted key the manifest was signed with (`None` if unsigned).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signed_by: Option<String>,
+    /// Name of the WASM tool installed with the skill.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub wasm_tool: Option<String>,
+    pub installed_at: DateTime<Utc>,
+}
+
+/// Where and how to install packages.
+#[derive(Debug, Clone)]
+pub struct InstallOptions {
+    /// Directory skills are installed into.
+    pub skills_dir: PathBuf,
+    /// WASM tools directory, for packages shipping a tool.
+    pub tools_dir: PathBuf,
+    /// Base64 Ed25519 public keys whose signatures are accepted.
+    pub trusted_keys: Vec<String>,
+    /// Install packages without a manifest or signature.
+    pub allow_unsigned: bool,
+    /// Replace an installed skill of the same name.
+    pub force: bool,
+}
+
+/// A skill installed on disk.
+#[derive(Debug, Clone)]
+pub struct InstalledSkill {
+    pub skill: Skill,
+    /// `None` for skills copied in by hand.
+    pub record: Option<InstallRecord>,
+}
+
+/// Look up `name` in the registry index at `url`.
+///
+/// The index is JSON: `{"skills": [{"name": ..., "git": ..., "ref": ...,
+/// "subdir": ...}]}`.
+pub async fn resolve_registry(url: &str, name: &str) -> Result<PackageSource, SkillError> {
+    #[derive(Deserialize)]
+    struct Index {
+        skills: Vec<Entry>,
+    }
+    #[derive(Deserialize)]
+    struct Entry {
+        name: String,
+        git: String,
+        #[serde(rename = "ref")]
+        reference: Option<String>,
+        subdir: Option<String>,
+    }
+
+    let package = |reason: String| SkillError::Package(format!("registry {}: {}", url, reason));
+    let index: Index = reqwest::get(url)
+        .await
+        .and_then(|r| r.error_for_status())
+        .map_err(|e| package(e.to_string()))?
+        .json()
+        .await
+        .map_err(|e| package(e.to_string()))?;
+    let entry = index
+        .skills
+        .into_iter()
+        .find(|e| e.name == name)
+        .ok_or_else(|| package(format!("no skill named '{}'", name)))?;
+    Ok(PackageSource::Git {
+        url: entry.git,
+        reference: entry.reference,
+        subdir: entry.subdir,
+    })
+}
+
+/// Fetch, verify, and install the package at `source`.
+pub fn install(
+    source: &PackageSource,
+    options: &InstallOptions,
+) -> Result<InstalledSkill, SkillError> {
+    match source {
+        PackageSource::Local { path } => install_from_dir(path, source, None, options),
+        PackageSource::Git {
+            url,
+            reference,
+            subdir,
+        } => {
+            let checkout = std::env::temp_dir()
+                .join(format!("ironclaw-skill-{}", uuid::Uuid::new_v4().simple()));
+            let result = clone(url, reference.as_deref(), &checkout).and_then(|commit| {
+                let dir = match subdir {
+                    Some(subdir) => checkout.join(subdir),
+                    None => checkout.clone(),
+                };
+                install_from_dir(&dir, source, Some(commit), options)
+            });
+            let _ = std::fs::remove_dir_all(&checkout);
+            result
+        }
+    }
+}
+
+/// Reinstall `name` from the source it was installed from.
+pub fn update(name: &str, options: &InstallOptions) -> Result<InstalledSkill, SkillError> {
+    let installed = installed(&options.skills_dir, name)?;
+    let record = installed.record.ok_or_else(|| {
+        SkillError::Package(format!(
+            "'{}' was not installed from a package and can't be updated",
+            name
+        ))
+    })?;
+    let options = InstallOptions {
+        force: true,
+        ..options.clone()
+    };
+    install(&record.source, &options)
+}
+
+/// Remove the installed skill `name` and any WASM tool it brought.
+pub fn remove(name: &str, options: &InstallOptions) -> Result<InstalledSkill, SkillError> {
+    let installed = installed(&options.skills_dir, name)?;
+    if let Some(tool) = installed.record.as_ref().and_then(|r| r.wasm_tool.as_ref()) {
+        for file in [
+            format!("{}.wasm", tool),
+            format!("{}.capabilities.json", tool),
+        ] {
+            let path = options.tools_dir.join(file);
+            if path.exists() {
+                std::fs::remove_file(&path).map_err(|e| io_error(&path, e))?;
+            }
+        }
+    }
+    let dir = options.skills_dir.join(&installed.skill.name);
+    std::fs::remove_dir_all(&dir).map_err(|e| io_error(&dir, e))?;
+    Ok(installed)
+}
+
+/// Skills installed in `skills_dir`, sorted by name.
+pub fn list_installed(skills_dir: &Path) -> Result<Vec<InstalledSkill>, SkillError> {
+    let entries = match std::fs::read_dir(skills_dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(io_error(skills_dir, e)),
+    };
+    let mut names: Vec<String> = entries
+        .filter_map(|e| e.ok())
+        .filter(|e| e.path().join(SKILL_FILE).is_file())
+        .map(|e| e.file_name().to_string_lossy().into_owned())
+        .collect();
+    names.sort();
+    names.iter().map(|n| installed(skills_dir, n)).collect()
+}
+
+/// The skill installed in `skills_dir/name`.
+pub fn installed(skills_dir: &Path, name: &str) -> Result<InstalledSkill, SkillError> {
+    let dir = skills_dir.join(name);
+    let file = dir.join(SKILL_FILE);
+    if !file.is_file() {
+        return Err(SkillError::Package(format!(
+            "no skill '{}' installed in {}",
+            name,
+            skills_dir.display()
+        )));
+    }
+    let content = std::fs::read_to_string(&file).map_err(|e| io_error(&file, e))?;
+    let skill = Skill::parse(&content, name, SkillSource::Disk(dir.clone()))?;
+    let record_path = dir.join(INSTALL_RECORD_FILE);
+    let record = match std::fs::read_to_string(&record_path) {
+        Ok(json) => Some(serde_json::from_str(&json).map_err(|e| {
+            SkillError::Package(format!("invalid {}: {}", record_path.display(), e))
+        })?),
+        Err(_) => None,
+    };
+    Ok(InstalledSkill { skill, record })
+}
+
+/// Verify and install the package in `dir`.
+fn install_from_dir(
+    dir: &Path,
+    source: &PackageSource,
+    commit: Option<String>,
+    options: &InstallOptions,
+) -> Result<InstalledSkill, SkillError> {
+    let skill_file = dir.join(SKILL_FILE);
+    let content = std::fs::read_to_string(&skill_file).map_err(|e| io_error(&skill_file, e))?;
+    let dir_name = dir
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let skill = Skill::parse(&content, &dir_name, SkillSource::Disk(dir.to_path_buf()))?;
+
+    let (manifest, signed_by) = verify_package(dir, &skill.name, options)?;
+
+    let target = options.skills_dir.join(&skill.name);
+    if target.exists() && !options.force {
+        return Err(SkillError::Package(format!(
+            "'{}' is already installed; use --force to replace it",
+            skill.name
+        )));
+    }
+
+    // Copy the tool first: the install record may only claim a tool that
+    // is actually in place.
+    let wasm_tool = match &manifest {
+        Some(manifest) => install_wasm(dir, manifest, &options.tools_dir)?,
+        None => None,
+    };
+
+    std::fs::create_dir_all(&options.skills_dir).map_err(|e| io_error(&options.skills_dir, e))?;
+    let staging = options.skills_dir.join(format!(
+        ".{}.installing-{}",
+        skill.name,
+        uuid::Uuid::new_v4().simple()
+    ));
+    let result = (|| -> Result<InstallRecord, SkillError> {
+        copy_dir(dir, &staging)?;
+        let record = InstallRecord {
+            source: source.clone(),
+            version: manifest.as_ref().and_then(|m| m.version.clone()),
+            commit,
+            signed_by,
+            wasm_tool,
+            installed_at: Utc::now(),
+        };
+        let json = serde_json::to_string_pretty(&record)
+            .map_err(|e| SkillError::Package(e.to_string()))?;
+        let record_path = staging.join(INSTALL_RECORD_FILE);
+        std::fs::write(&record_path, json).map_err(|e| io_error(&record_path, e))?;
+        if target.exists() {
+            std::fs::remove_dir_all(&target).map_err(|e| io_error(&target, e))?;
+        }
+        std::fs::rename(&staging, &target).map_err(|e| io_error(&target, e))?;
+        Ok(record)
+    })();
+    if result.is_err() {
+        let _ = std::fs::remove_dir_all(&staging);
+    }
+    let record = result?;
+
+    Ok(InstalledSkill {
+        skill: Skill {
+            source: SkillSource::Disk(target),
+            ..skill
+        },
+        record: Some(record),
+    })
+}
+
+/// Check the package in `dir` against its manifest and signature. Returns
+/// the manifest and the key that signed it.
+fn verify_package(
+    dir: &Path,
+    name: &str,
+    options: &InstallOptions,
+) -> Result<(Option<SkillManifest>, Option<String>), SkillError> {
+    let untrusted = |reason: String| SkillError::Untrusted {
+        name: name.to_string(),
+        reason,
+    };
+
+    let manifest_path = dir.join(MANIFEST_FILE);
+    let manifest_bytes = match std::fs::read(&manifest_path) {
+        Ok(bytes) => bytes,
+        Err(_) if options.allow_unsigned => return Ok((None, None)),
+        Err(_) => {
+            return Err(untrusted(format!(
+                "no {} (use --allow-unsigned to install anyway)",
+                MANIFEST_FILE
+            )));
+        }
+    };
+    let manifest: SkillManifest = serde_json::from_slice(&manifest_bytes)
+        .map_err(|e| untrusted(format!("invalid {}: {}", MANIFEST_FILE, e)))?;
+    if manifest.name != name {
+        return Err(untrusted(format!(
+            "manifest is for '{}', SKILL.md for '{}'",
+            manifest.name, name
+        )));
+    }
+
+    let actual = hash_files(dir)?;
+    for (path, hash) in &actual {
+        match manifest.files.get(path) {
+            Some(expected) if expected.eq_ignore_ascii_case(hash) => {}
+            Some(_) => return Err(untrusted(format!("{} doesn't match the manifest", path))),
+            None => return Err(untrusted(format!("{} is not in the manifest", path))),
+        }
+    }
+    if let Some(missing) = manifest.files.keys().find(|p| !actual.contains_key(*p)) {
+        return Err(untrusted(format!("{} is missing", missing)));
+    }
+
+    let signature = match std::fs::read_to_string(dir.join(SIGNATURE_FILE)) {
+        Ok(signature) => signature,
+        Err(_) if options.allow_unsigned => return Ok((Some(manifest), None)),
+        Err(_) => {
+            return Err(untrusted(format!(
+                "no {} (use --allow-unsigned to install anyway)",
+                SIGNATURE_FILE
+            )));
+        }
+    };
+    let signature = BASE64
+        .decode(signature.trim())
+        .map_err(|e| untrusted(format!("invalid {}: {}", SIGNATURE_FILE, e)))?;
+    let signed_by = options.trusted_keys.iter().find(|key| {
+        BASE64.decode(key.trim()).is_ok_and(|key| {
+            ring::signature::UnparsedPublicKey::new(&ring::signature::ED25519, key)
+                .verify(&manifest_bytes, &signature)
+                .is_ok()
+        })
+    });
+    match signed_by {
+        Some(key) => Ok((Some(manifest), Some(key.trim().to_string()))),
+        None if options.allow_unsigned => Ok((Some(manifest), None)),
+        None => Err(untrusted(
+            "signature doesn't match any trusted key (SKILLS_TRUSTED_KEYS)".to_string(),
+        )),
+    }
+}
+
+/// Copy the manifest's WASM tool into `tools_dir`. Returns the tool name.
+fn install_wasm(
+    dir: &Path,
+    manifest: &SkillManifest,
+    tools_dir: &Path,
+) -> Result<Option<String>, SkillError> {
+    let Some(wasm) = &manifest.wasm else {
+        return Ok(None);
+    };
+    // Only files covered by the checksums may be installed.
+    for file in std::iter::once(wasm).chain(&manifest.capabilities) {
+        if !manifest.files.contains_key(file) {
+            return Err(SkillError::Package(format!(
+                "{} is not listed in the manifest files",
+                file
+            )));
+        }
+    }
+    let tool = Path::new(wasm)
+        .file_stem()
+        .map(|s| s.to_string_lossy().into_owned())
+        .ok_or_else(|| SkillError::Package(format!("invalid wasm path {:?}", wasm)))?;
+
+    let caps = match &manifest.capabilities {
+        Some(caps) => {
+            let path = dir.join(caps);
+            let json = std::fs::read_to_string(&path).map_err(|e| io_error(&path, e))?;
+            CapabilitiesFile::from_json(&json).map_err(|e| {
+                SkillError::Package(format!("invalid capabilities file {}: {}", caps, e))
+            })?;
+            Some(json)
+        }
+        None => None,
+    };
+
+    std::fs::create_dir_all(tools_dir).map_err(|e| io_error(tools_dir, e))?;
+    let target = tools_dir.join(format!("{}.wasm", tool));
+    std::fs::copy(dir.join(wasm), &target).map_err(|e| io_error(&target, e))?;
+    if let Some(json) = caps {
+        let target = tools_dir.join(format!("{}.capabilities.json", tool));
+        std::fs::write(&target, json).map_err(|e| io_error(&target, e))?;
+    }
+    Ok(Some(tool))
+}
+
+/// Shallow-clone `url` into `dest`. Returns the checked-out commit.
+fn clone(url: &str, reference: Option<&str>, dest: &Path) -> Result<String, SkillError> {
+    let mut cmd = Command::new("git");
+    cmd.args(["clone", "--quiet", "--depth", "1"]);
+    if let Some(reference) = reference {
+        cmd.args(["--branch", reference]);
+    }
+    cmd.arg("--").arg(url).arg(dest);
+    run_git(&mut cmd)?;
+    run_git(
+        Command::new("git")
+            .arg("-C")
+            .arg(dest)
+            .args(["rev-parse", "HEAD"]),
+    )
+}
+
+fn run_git(cmd: &mut Command) -> Result<String, SkillError> {
+    let output = cmd
+        .output()
+        .map_err(|e| SkillError::Package(format!("failed to run git: {}", e)))?;
+    if !output.status.success() {
+        return Err(SkillError::Package(format!(
+            "git failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// SHA-256 of each package file by relative path, leaving out the
+/// manifest, signature, install record, and `.git`.
+pub fn hash_files(dir: &Path) -> Result<BTreeMap<String, String>, SkillError> {
+    let mut hashes = BTreeMap::new();
+    let mut pending = vec![dir.to_path_buf()];
+    while let Some(current) = pending.pop() {
+        for entry in std::fs::read_dir(&current).map_err(|e| io_error(&current, e))? {
+            let entry = entry.map_err(|e| io_error(&current, e))?;
+            let path = entry.path();
+            let relative = path
+                .strip_prefix(dir)
+                .unwrap_or(&path)
+                .components()
+                .map(|c| c.as_os_str().to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("/");
+            if [".git", MANIFEST_FILE, SIGNATURE_FILE, INSTALL_RECORD_FILE]
+                .contains(&relative.as_str())
+            {
+                continue;
+            }
+            if path.is_dir() {
+                pending.push(path);
+            } else {
+                let bytes = std::fs::read(&path).map_err(|e| io_error(&path, e))?;
+                let digest = Sha256::digest(&bytes);
+                let hex: String = digest.iter().map(|b| format!("{:02x}", b)).collect();
+                hashes.insert(relative, hex);
+            }
+        }
+    }
+    Ok(hashes)
+}
+
+fn copy_dir(from: &Path, to: &Path) -> Result<(), SkillError> {
+    std::fs::create_dir_all(to).map_err(|e| io_error(to, e))?;
+    for entry in std::fs::read_dir(from).map_err(|e| io_error(from, e))? {
+        let entry = entry.map_err(|e| io_error(from, e))?;
+        let path = entry.path();
+        if entry.file_name() == ".git" {
+            continue;
+        }
+        let target = to.join(entry.file_name());
+        if path.is_dir() {
+            copy_dir(&path, &target)?;
+        } else {
+            std::fs::copy(&path, &target).map_err(|e| io_error(&path, e))?;
+        }
+    }
+    Ok(())
+}
+
+fn io_error(path: &Path, e: std::io::Error) -> SkillError {
+    SkillError::Io {
+        path: path.display().to_string(),
+        reason: e.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ring::signature::{Ed25519KeyPair, KeyPair};
+
+    use super::*;
+
+    /// Write a package to `dir` with a manifest, signed by `key` if given.
+    fn write_package(dir: &Path, version: &str, key: Option<&Ed25519KeyPair>) {
+        std::fs::create_dir_all(dir.join("scripts")).unwrap();
+        std::fs::write(
+            dir.join(SKILL_FILE),
+            "---\nname: notes\ndescription: Take notes\n---\nUse the script.",
+        )
+        .unwrap();
+        std::fs::write(dir.join("scripts/run.sh"), "echo hi").unwrap();
+        let manifest = SkillManifest {
+            name: "notes".to_string(),
+            version: Some(version.to_string()),
+            files: hash_files(dir).unwrap(),
+            wasm: None,
+            capabilities: None,
+        };
+        let json = serde_json::to_vec_pretty(&manifest).unwrap();
+        std::fs::write(dir.join(MANIFEST_FILE), &json).unwrap();
+        match key {
+            Some(key) => std::fs::write(
+                dir.join(SIGNATURE_FILE),
+                BASE64.encode(key.sign(&json).as_ref()),
+            )
+            .unwrap(),
+            None => {
+                let _ = std::fs::remove_file(dir.join(SIGNATURE_FILE));
+            }
+        }
+    }
+
+    fn key_pair() -> Ed25519KeyPair {
+        let rng = ring::rand::SystemRandom::new();
+        let pkcs8 = Ed25519KeyPair::generate_pkcs8(&rng).unwrap();
+        Ed25519KeyPair::from_pkcs8(pkcs8.as_ref()).unwrap()
+    }
+
+    fn options(root: &Path, key: &Ed25519KeyPair) -> InstallOptions {
+        InstallOptions {
+            skills_dir: root.join("skills"),
+            tools_dir: root.join("tools"),
+            trusted_keys: vec![BASE64.encode(key.public_key().as_ref())],
+            allow_unsigned: false,
+            force: false,
+        }
+    }
+
+    #[test]
+    fn test_install_update_remove_signed_package() {
+        let root = tempfile::tempdir().unwrap();
+        let key = key_pair();
+        let package = root.path().join("src/notes");
+        write_package(&package, "1.0.0", Some(&key));
+        let options = options(root.path(), &key);
+        let source = PackageSource::parse(package.to_str().unwrap(), None, None).unwrap();
+
+        let installed = install(&source, &options).unwrap();
+        assert_eq!(installed.skill.name, "notes");
+        let record = installed.record.unwrap();
+        assert_eq!(record.version.as_deref(), Some("1.0.0"));
+        assert!(record.signed_by.is_some());
+        assert!(options.skills_dir.join("notes/scripts/run.sh").is_file());
+        assert!(install(&source, &options).is_err(), "needs --force");
+
+        write_package(&package, "1.1.0", Some(&key));
+        let updated = update("notes", &options).unwrap();
+        assert_eq!(updated.record.unwrap().version.as_deref(), Some("1.1.0"));
+        assert_eq!(list_installed(&options.skills_dir).unwrap().len(), 1);
+
+        remove("notes", &options).unwrap();
+        assert!(list_installed(&options.skills_dir).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_rejects_tampered_and_unsigned_packages() {
+        let root = tempfile::tempdir().unwrap();
+        let key = key_pair();
+        let mut options = options(root.path(), &key);
+        let package = root.path().join("src/notes");
+
+        write_package(&package, "1.0.0", Some(&key));
+        std::fs::write(package.join("scripts/run.sh"), "rm -rf ~").unwrap();
+        let source = PackageSource::Local {
+            path: package.clone(),
+        };
+        let err = install(&source, &options).unwrap_err();
+        assert!(err.to_string().contains("scripts/run.sh"), "{}", err);
+
+        // Signed by a key we don't trust.
+        write_package(&package, "1.0.0", Some(&key_pair()));
+        assert!(install(&source, &options).is_err());
+
+        write_package(&package, "1.0.0", None);
+        assert!(install(&source, &options).is_err());
+        options.allow_unsigned = true;
+        let installed = install(&source, &options).unwrap();
+        assert_eq!(installed.record.unwrap().signed_by, None);
+    }
+
+    #[test]
+    fn test_parse_source() {
+        assert_eq!(
+            PackageSource::parse("https://github.com/a/skills", Some("v1".into()), None),
+            Some(PackageSource::Git {
+                url: "https://github.com/a/skills".to_string(),
+                reference: Some("v1".to_string()),
+                subdir: None,
+            })
+        );
+        assert!(matches!(
+            PackageSource::parse("git@github.com:a/b.git", None, None),
+            Some(PackageSource::Git { .. })
+        ));
+        assert_eq!(PackageSource::parse("weather", None, None), None);
+    }
+}
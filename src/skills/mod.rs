@@ -0,0 +1,426 @@
+//! Skills: packaged guidance for using tools.
+//!
+//! A skill is a directory with a `SKILL.md` file. Its frontmatter names and
+//! describes the skill; the body tells the agent how to do the job:
+//!
+//! ```text
+//! ---
+//! name: github
+//! description: Triage issues and pull requests with the gh CLI
+//! commands:
+//!   - "issues: gh issue list --repo {repo} --limit 20"
+//!   - "pr_checks: gh pr checks {number} --repo {repo}"
+//! ---
+//! # GitHub
+//!
+//! Always pass --repo explicitly ...
+//! ```
+//!
+//! Skills are discovered in the workspace (`skills/<name>/SKILL.md`) and in
+//! skill directories on disk (`~/.ironclaw/skills/<name>/SKILL.md` by
+//! default). A workspace skill replaces a disk skill of the same name.
+//!
+//! Only names and descriptions are shown to the model up front, in the
+//! `skill` tool's description. The body is loaded on demand when the agent
+//! calls that tool, so unused skills cost no context.
+//!
+//! Each `commands` entry (`name: template`) becomes a tool of its own,
+//! `<skill>_<name>`. `{param}` placeholders become string parameters and
+//! are substituted shell-quoted, so don't quote them in the template.
+//! Commands run through the shell tool and need local tools enabled.
+//!
+//! Skills can be shared as packages; see [`install`].
+
+pub mod install;
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use crate::error::WorkspaceError;
+use crate::workspace::Workspace;
+use crate::workspace::frontmatter::{parse_frontmatter, strip_frontmatter};
+
+/// File that defines a skill inside its directory.
+pub const SKILL_FILE: &str = "SKILL.md";
+
+/// Workspace directory holding skill directories.
+pub const WORKSPACE_SKILLS_DIR: &str = "skills";
+
+/// Error loading a skill.
+#[derive(Debug, thiserror::Error)]
+pub enum SkillError {
+    #[error("Invalid skill {path}: {reason}")]
+    Invalid { path: String, reason: String },
+
+    #[error("Failed to read {path}: {reason}")]
+    Io { path: String, reason: String },
+
+    #[error("Skill package error: {0}")]
+    Package(String),
+
+    #[error("Refusing to install skill {name}: {reason}")]
+    Untrusted { name: String, reason: String },
+
+    #[error(transparent)]
+    Workspace(#[from] WorkspaceError),
+}
+
+/// Where a skill was loaded from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SkillSource {
+    /// Workspace path of the `SKILL.md`.
+    Workspace(String),
+    /// Skill directory on disk.
+    Disk(PathBuf),
+}
+
+/// A command a skill exposes as a tool.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SkillCommand {
+    pub name: String,
+    /// Shell command with `{param}` placeholders.
+    pub template: String,
+}
+
+impl SkillCommand {
+    /// Names of the template's `{param}` placeholders, in order of first use.
+    pub fn params(&self) -> Vec<String> {
+        let mut params: Vec<String> = Vec::new();
+        let mut rest = self.template.as_str();
+        while let Some(start) = rest.find('{') {
+            let after = &rest[start + 1..];
+            let Some(end) = after.find('}') else {
+                break;
+            };
+            let name = &after[..end];
+            if is_identifier(name) && !params.iter().any(|p| p == name) {
+                params.push(name.to_string());
+            }
+            rest = &after[end + 1..];
+        }
+        params
+    }
+
+    /// The command with each placeholder replaced by its shell-quoted value.
+    pub fn render(&self, args: &BTreeMap<String, String>) -> Result<String, String> {
+        let mut command = self.template.clone();
+        for param in self.params() {
+            let value = args
+                .get(&param)
+                .ok_or_else(|| format!("missing '{}' parameter", param))?;
+            command = command.replace(&format!("{{{}}}", param), &shell_quote(value));
+        }
+        Ok(command)
+    }
+}
+
+/// A loaded skill.
+#[derive(Debug, Clone)]
+pub struct Skill {
+    pub name: String,
+    pub description: String,
+    /// The `SKILL.md` body, without frontmatter.
+    pub guidance: String,
+    pub commands: Vec<SkillCommand>,
+    pub source: SkillSource,
+}
+
+impl Skill {
+    /// Parse the contents of a `SKILL.md`. `dir_name` is the name of the
+    /// skill directory, used when the frontmatter has no `name`.
+    pub fn parse(content: &str, dir_name: &str, source: SkillSource) -> Result<Self, SkillError> {
+        let invalid = |reason: String| SkillError::Invalid {
+            path: match &source {
+                SkillSource::Workspace(path) => path.clone(),
+                SkillSource::Disk(dir) => dir.join(SKILL_FILE).display().to_string(),
+            },
+            reason,
+        };
+
+        let fields = parse_frontmatter(content);
+        let field = |key: &str| {
+            fields
+                .get(key)
+                .and_then(|v| v.as_str())
+                .map(str::trim)
+                .filter(|v| !v.is_empty())
+        };
+        let name = field("name").unwrap_or(dir_name).to_string();
+        if !is_identifier(&name) {
+            return Err(invalid(format!(
+                "name {:?} must be letters, digits, '_' or '-'",
+                name
+            )));
+        }
+        let guidance = strip_frontmatter(content).trim().to_string();
+        let description = match field("description") {
+            Some(description) => description.to_string(),
+            None => guidance
+                .lines()
+                .map(|l| l.trim_start_matches('#').trim())
+                .find(|l| !l.is_empty())
+                .ok_or_else(|| invalid("no description".to_string()))?
+                .to_string(),
+        };
+
+        let mut commands = Vec::new();
+        let entries = match fields.get("commands") {
+            Some(serde_json::Value::Array(items)) => items.clone(),
+            _ => Vec::new(),
+        };
+        for entry in entries.iter().filter_map(|e| e.as_str()) {
+            let Some((command_name, template)) = entry.split_once(':') else {
+                return Err(invalid(format!(
+                    "command {:?} must be 'name: template'",
+                    entry
+                )));
+            };
+            let command_name = command_name.trim();
+            if !is_identifier(command_name) || template.trim().is_empty() {
+                return Err(invalid(format!(
+                    "command {:?} must be 'name: template'",
+                    entry
+                )));
+            }
+            commands.push(SkillCommand {
+                name: command_name.to_string(),
+                template: template.trim().to_string(),
+            });
+        }
+
+        Ok(Self {
+            name,
+            description,
+            guidance,
+            commands,
+            source,
+        })
+    }
+
+    /// Working directory for the skill's commands: the skill directory, for
+    /// skills on disk.
+    pub fn dir(&self) -> Option<&Path> {
+        match &self.source {
+            SkillSource::Disk(dir) => Some(dir),
+            SkillSource::Workspace(_) => None,
+        }
+    }
+}
+
+/// The skills available to the agent, by name.
+#[derive(Debug, Clone, Default)]
+pub struct SkillRegistry {
+    skills: BTreeMap<String, Skill>,
+}
+
+impl SkillRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add the skills in each subdirectory of `dir` that has a `SKILL.md`.
+    ///
+    /// A missing `dir` is not an error. Skills that fail to parse are
+    /// logged and skipped. Returns the number added.
+    pub fn load_dir(&mut self, dir: &Path) -> Result<usize, SkillError> {
+        let entries = match std::fs::read_dir(dir) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(0),
+            Err(e) => {
+                return Err(SkillError::Io {
+                    path: dir.display().to_string(),
+                    reason: e.to_string(),
+                });
+            }
+        };
+        let mut dirs: Vec<PathBuf> = entries
+            .filter_map(|e| e.ok())
+            .map(|e| e.path())
+            .filter(|p| p.join(SKILL_FILE).is_file())
+            .collect();
+        dirs.sort();
+
+        let mut count = 0;
+        for skill_dir in dirs {
+            let file = skill_dir.join(SKILL_FILE);
+            let content = match std::fs::read_to_string(&file) {
+                Ok(content) => content,
+                Err(e) => {
+                    tracing::warn!("Failed to read {}: {}", file.display(), e);
+                    continue;
+                }
+            };
+            let dir_name = skill_dir
+                .file_name()
+                .map(|n| n.to_string_lossy().into_owned())
+                .unwrap_or_default();
+            match Skill::parse(&content, &dir_name, SkillSource::Disk(skill_dir.clone())) {
+                Ok(skill) => {
+                    self.insert(skill);
+                    count += 1;
+                }
+                Err(e) => tracing::warn!("Skipping skill: {}", e),
+            }
+        }
+        Ok(count)
+    }
+
+    /// Add the skills under `skills/` in `workspace`, replacing disk skills
+    /// of the same name. Returns the number added.
+    pub async fn load_workspace(&mut self, workspace: &Workspace) -> Result<usize, SkillError> {
+        let mut count = 0;
+        for path in workspace.list_all().await? {
+            let Some(dir_name) = path
+                .strip_prefix(WORKSPACE_SKILLS_DIR)
+                .and_then(|p| p.strip_prefix('/'))
+                .and_then(|p| p.strip_suffix(SKILL_FILE))
+                .and_then(|p| p.strip_suffix('/'))
+                .filter(|name| !name.contains('/'))
+            else {
+                continue;
+            };
+            let doc = workspace.read(&path).await?;
+            match Skill::parse(&doc.content, dir_name, SkillSource::Workspace(path.clone())) {
+                Ok(skill) => {
+                    self.insert(skill);
+                    count += 1;
+                }
+                Err(e) => tracing::warn!("Skipping skill: {}", e),
+            }
+        }
+        Ok(count)
+    }
+
+    /// Add `skill`, replacing any skill of the same name.
+    pub fn insert(&mut self, skill: Skill) {
+        if let Some(old) = self.skills.insert(skill.name.clone(), skill) {
+            tracing::debug!("Skill {} from {:?} was overridden", old.name, old.source);
+        }
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Skill> {
+        self.skills.get(name)
+    }
+
+    /// All skills, sorted by name.
+    pub fn iter(&self) -> impl Iterator<Item = &Skill> {
+        self.skills.values()
+    }
+
+    pub fn len(&self) -> usize {
+        self.skills.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.skills.is_empty()
+    }
+}
+
+fn is_identifier(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+}
+
+/// Quote `value` as a single POSIX shell word.
+fn shell_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', r"'\''"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GITHUB: &str = "---\nname: github\ndescription: Triage issues with gh\ncommands:\n  - \"issues: gh issue list --repo {repo} --label {label} --repo {repo}\"\n---\n# GitHub\n\nAlways pass --repo.\n";
+
+    #[test]
+    fn test_parse_skill() {
+        let skill = Skill::parse(
+            GITHUB,
+            "gh",
+            SkillSource::Workspace("skills/gh/SKILL.md".to_string()),
+        )
+        .unwrap();
+        assert_eq!(skill.name, "github");
+        assert_eq!(skill.description, "Triage issues with gh");
+        assert_eq!(skill.guidance, "# GitHub\n\nAlways pass --repo.");
+        assert_eq!(skill.commands[0].name, "issues");
+        assert_eq!(skill.commands[0].params(), vec!["repo", "label"]);
+
+        // Name and description fall back to the directory and first line.
+        let skill =
+            Skill::parse("# Weather lookups\n\nUse wttr.in.", "weather", skill.source).unwrap();
+        assert_eq!(skill.name, "weather");
+        assert_eq!(skill.description, "Weather lookups");
+
+        let err = Skill::parse(
+            "---\ncommands:\n  - no template\n---\nBody",
+            "bad",
+            SkillSource::Disk(PathBuf::from("/skills/bad")),
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("/skills/bad/SKILL.md"));
+    }
+
+    #[test]
+    fn test_render_quotes_arguments() {
+        let command = SkillCommand {
+            name: "grep".to_string(),
+            template: "grep -r {pattern} {dir}".to_string(),
+        };
+        let args = BTreeMap::from([
+            ("pattern".to_string(), "it's; rm -rf /".to_string()),
+            ("dir".to_string(), "src".to_string()),
+        ]);
+        assert_eq!(
+            command.render(&args).unwrap(),
+            r"grep -r 'it'\''s; rm -rf /' 'src'"
+        );
+        assert!(command.render(&BTreeMap::new()).is_err());
+    }
+
+    #[test]
+    fn test_load_dir() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(dir.path().join("gh")).unwrap();
+        std::fs::write(dir.path().join("gh").join(SKILL_FILE), GITHUB).unwrap();
+        std::fs::create_dir_all(dir.path().join("empty")).unwrap();
+
+        let mut registry = SkillRegistry::new();
+        assert_eq!(registry.load_dir(dir.path()).unwrap(), 1);
+        let skill = registry.get("github").unwrap();
+        assert_eq!(skill.dir(), Some(dir.path().join("gh").as_path()));
+        assert_eq!(registry.load_dir(&dir.path().join("missing")).unwrap(), 0);
+    }
+
+    #[cfg(feature = "libsql")]
+    #[tokio::test]
+    async fn test_workspace_skills_override_disk() {
+        use crate::db::libsql_backend::test_db;
+
+        let (db, _dir) = test_db().await;
+        let workspace = Workspace::new_with_db("default", db);
+        workspace
+            .write(
+                "skills/gh/SKILL.md",
+                &GITHUB.replace("with gh", "(workspace)"),
+            )
+            .await
+            .unwrap();
+        workspace
+            .write("skills/gh/notes/SKILL.md", "# Nested, ignored")
+            .await
+            .unwrap();
+
+        let mut registry = SkillRegistry::new();
+        registry.insert(
+            Skill::parse(GITHUB, "gh", SkillSource::Disk(PathBuf::from("/skills/gh"))).unwrap(),
+        );
+        assert_eq!(registry.load_workspace(&workspace).await.unwrap(), 1);
+        assert_eq!(registry.len(), 1);
+        let skill = registry.get("github").unwrap();
+        assert_eq!(skill.description, "Triage issues (workspace)");
+        assert_eq!(skill.dir(), None);
+    }
+}
@@ -0,0 +1,113 @@
+//! OpenTelemetry export of tracing spans.
+//!
+//! The agent loop, job workers, tool executions, LLM provider requests, and
+//! database calls open `tracing` spans (with `user_id` / `job_id` fields where
+//! they apply). When an OTLP endpoint is configured, [`otel_layer`] forwards
+//! those spans to a collector so a multi-step job shows up as one trace:
+//!
+//! ```text
+//! agent.turn{user_id, channel}
+//!   ├── llm.complete_with_tools{model}
+//!   ├── tool.execute{tool, user_id}
+//!   │     └── db.get_document_by_path
+//!   └── llm.complete_with_tools{model}
+//! job.run{job_id, user_id}
+//!   ├── llm.complete_with_tools{model}
+//!   └── tool.execute{tool, job_id}
+//! ```
+//!
+//! Export is off by default; spans still appear as context in log lines.
+
+use opentelemetry::KeyValue;
+use opentelemetry::trace::TracerProvider as _;
+use opentelemetry_otlp::WithExportConfig;
+use opentelemetry_sdk::Resource;
+use opentelemetry_sdk::trace::{Sampler, Tracer, TracerProvider};
+use tracing::Subscriber;
+use tracing_opentelemetry::OpenTelemetryLayer;
+use tracing_subscriber::registry::LookupSpan;
+
+use crate::config::TelemetryConfig;
+use crate::error::ConfigError;
+
+/// Tracing layer that forwards spans to the OTLP exporter.
+pub type OtelLayer<S> = OpenTelemetryLayer<S, Tracer>;
+
+/// Flushes buffered spans when dropped. Keep it alive for the life of the
+/// process.
+pub struct TelemetryGuard {
+    provider: TracerProvider,
+}
+
+impl Drop for TelemetryGuard {
+    fn drop(&mut self) {
+        if let Err(e) = self.provider.shutdown() {
+            eprintln!("Failed to flush OpenTelemetry spans: {}", e);
+        }
+    }
+}
+
+/// Build the tracing layer that exports spans over OTLP/HTTP.
+///
+/// Returns `None` when no endpoint is configured. Must be called from
+/// within a multi-threaded Tokio runtime: the batch exporter runs as a task
+/// on it, and dropping the guard blocks until that task has flushed.
+pub fn otel_layer<S>(
+    config: &TelemetryConfig,
+) -> Result<Option<(OtelLayer<S>, TelemetryGuard)>, ConfigError>
+where
+    S: Subscriber + for<'span> LookupSpan<'span>,
+{
+    let Some(ref endpoint) = config.otlp_endpoint else {
+        return Ok(None);
+    };
+
+    let exporter = opentelemetry_otlp::SpanExporter::builder()
+        .with_http()
+        .with_endpoint(endpoint)
+        .build()
+        .map_err(|e| ConfigError::InvalidValue {
+            key: "OTEL_EXPORTER_OTLP_ENDPOINT".to_string(),
+            message: e.to_string(),
+        })?;
+
+    let provider = TracerProvider::builder()
+        .with_batch_exporter(exporter, opentelemetry_sdk::runtime::Tokio)
+        .with_sampler(Sampler::ParentBased(Box::new(Sampler::TraceIdRatioBased(
+            config.sample_ratio,
+        ))))
+        .with_resource(Resource::new(vec![
+            KeyValue::new("service.name", config.service_name.clone()),
+            KeyValue::new("service.version", env!("CARGO_PKG_VERSION")),
+        ]))
+        .build();
+
+    let tracer = provider.tracer("ironclaw");
+    let layer = tracing_opentelemetry::layer().with_tracer(tracer);
+    Ok(Some((layer, TelemetryGuard { provider })))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_disabled_without_endpoint() {
+        let config = TelemetryConfig::default();
+        assert!(!config.enabled());
+        let layer = otel_layer::<tracing_subscriber::Registry>(&config).unwrap();
+        assert!(layer.is_none());
+    }
+
+    // Shutting down the batch exporter blocks until its task acknowledges,
+    // which needs a second worker thread.
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_layer_built_with_endpoint() {
+        let config = TelemetryConfig {
+            otlp_endpoint: Some("http://127.0.0.1:4318/v1/traces".to_string()),
+            ..Default::default()
+        };
+        let layer = otel_layer::<tracing_subscriber::Registry>(&config).unwrap();
+        assert!(layer.is_some());
+    }
+}
@@ -0,0 +1,217 @@
+//! First-run bootstrap tool.
+//!
+//! Registered only while the workspace is in bootstrap mode (BOOTSTRAP.md
+//! exists). It is the one way the agent can fill in IDENTITY.md and
+//! USER.md, and only field by field; see [`crate::workspace::Profile`].
+
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+
+use crate::context::JobContext;
+use crate::tools::tool::{Tool, ToolError, ToolOutput};
+use crate::workspace::{Profile, Workspace};
+
+/// Tool that records first-run answers and ends bootstrap mode.
+pub struct BootstrapTool {
+    workspace: Arc<Workspace>,
+    channels: Vec<String>,
+}
+
+impl BootstrapTool {
+    /// Create a bootstrap tool; `channels` are the running channels the user
+    /// can be pointed at.
+    pub fn new(workspace: Arc<Workspace>, channels: Vec<String>) -> Self {
+        Self {
+            workspace,
+            channels,
+        }
+    }
+
+    /// How the user reaches the agent over each running channel.
+    fn channel_hints(&self) -> Vec<serde_json::Value> {
+        self.channels
+            .iter()
+            .map(|name| {
+                let how = match name.as_str() {
+                    "repl" => "Chat in this terminal.".to_string(),
+                    "gateway" => "Chat in the web gateway.".to_string(),
+                    "http" => "Send messages to the webhook API.".to_string(),
+                    other => format!(
+                        "Message the bot on {0}; unknown senders get a pairing code, approved \
+                         with `ironclaw pairing approve {0} <code>`.",
+                        other
+                    ),
+                };
+                serde_json::json!({ "channel": name, "how": how })
+            })
+            .collect()
+    }
+}
+
+/// The string fields of `params[key]`.
+fn fields(params: &serde_json::Value, key: &str) -> Result<BTreeMap<String, String>, ToolError> {
+    match params.get(key) {
+        Some(serde_json::Value::Object(map)) => map
+            .iter()
+            .map(|(k, v)| match v {
+                serde_json::Value::String(s) => Ok((k.clone(), s.clone())),
+                _ => Err(ToolError::InvalidParameters(format!(
+                    "{}.{} must be a string",
+                    key, k
+                ))),
+            })
+            .collect(),
+        Some(serde_json::Value::Null) | None => Ok(BTreeMap::new()),
+        Some(_) => Err(ToolError::InvalidParameters(format!(
+            "{} must be an object",
+            key
+        ))),
+    }
+}
+
+fn schema_for(profile: Profile) -> serde_json::Value {
+    let properties: serde_json::Map<String, serde_json::Value> = profile
+        .fields()
+        .iter()
+        .map(|(key, label)| {
+            (
+                key.to_string(),
+                serde_json::json!({ "type": "string", "description": label }),
+            )
+        })
+        .collect();
+    serde_json::json!({
+        "type": "object",
+        "properties": properties,
+        "additionalProperties": false
+    })
+}
+
+#[async_trait]
+impl Tool for BootstrapTool {
+    fn name(&self) -> &str {
+        "bootstrap"
+    }
+
+    fn description(&self) -> &str {
+        "First-run setup. Record who you are (identity) and who your human is (user) as \
+         you learn it; fields are saved to IDENTITY.md and USER.md. Call with no arguments \
+         to see what is still missing and which channels the user can link. Set \
+         'complete' to true once the conversation is done to delete BOOTSTRAP.md."
+    }
+
+    fn parameters_schema(&self) -> serde_json::Value {
+        serde_json::json!({
+            "type": "object",
+            "properties": {
+                "identity": schema_for(Profile::Identity),
+                "user": schema_for(Profile::User),
+                "complete": {
+                    "type": "boolean",
+                    "description": "Finish bootstrap and delete BOOTSTRAP.md",
+                    "default": false
+                }
+            }
+        })
+    }
+
+    async fn execute(
+        &self,
+        params: serde_json::Value,
+        _ctx: &JobContext,
+    ) -> Result<ToolOutput, ToolError> {
+        let start = std::time::Instant::now();
+
+        if !self.workspace.in_bootstrap().await {
+            return Err(ToolError::ExecutionFailed(
+                "bootstrap is already complete".to_string(),
+            ));
+        }
+
+        let failed = |e| ToolError::ExecutionFailed(format!("Bootstrap failed: {}", e));
+        for (profile, key) in [(Profile::Identity, "identity"), (Profile::User, "user")] {
+            let values = fields(&params, key)?;
+            if !values.is_empty() {
+                self.workspace
+                    .update_profile(profile, &values)
+                    .await
+                    .map_err(failed)?;
+            }
+        }
+
+        let complete = params
+            .get("complete")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false);
+        if complete {
+            self.workspace.complete_bootstrap().await.map_err(failed)?;
+        }
+
+        let status = self.workspace.bootstrap_status().await.map_err(failed)?;
+        let output = serde_json::json!({
+            "identity": status.identity,
+            "user": status.user,
+            "missing": status.missing,
+            "channels": self.channel_hints(),
+            "completed": !status.active,
+        });
+        Ok(ToolOutput::success(output, start.elapsed()))
+    }
+
+    fn requires_sanitization(&self) -> bool {
+        false // Internal memory, trusted content
+    }
+}
+
+#[cfg(all(test, feature = "libsql"))]
+mod tests {
+    use super::*;
+    use crate::db::libsql_backend::test_db;
+
+    #[tokio::test]
+    async fn test_bootstrap_tool_fills_profile_and_completes() {
+        let (db, _dir) = test_db().await;
+        let workspace = Arc::new(Workspace::new_with_db("default", db));
+        workspace.seed_if_empty().await.unwrap();
+
+        let tool = BootstrapTool::new(
+            Arc::clone(&workspace),
+            vec!["repl".to_string(), "telegram".to_string()],
+        );
+        let ctx = JobContext::default();
+        let output = tool
+            .execute(
+                serde_json::json!({
+                    "identity": { "creature": "familiar", "vibe": "dry" },
+                    "user": { "name": "Ada" }
+                }),
+                &ctx,
+            )
+            .await
+            .unwrap();
+        assert_eq!(output.result["identity"]["vibe"], "dry");
+        assert_eq!(output.result["user"]["name"], "Ada");
+        assert_eq!(output.result["completed"], false);
+        assert!(
+            output.result["channels"][1]["how"]
+                .as_str()
+                .unwrap()
+                .contains("pairing approve telegram")
+        );
+        assert!(
+            tool.execute(serde_json::json!({ "user": { "name": 3 } }), &ctx)
+                .await
+                .is_err()
+        );
+
+        let output = tool
+            .execute(serde_json::json!({ "complete": true }), &ctx)
+            .await
+            .unwrap();
+        assert_eq!(output.result["completed"], true);
+        assert!(!workspace.in_bootstrap().await);
+        assert!(tool.execute(serde_json::json!({}), &ctx).await.is_err());
+    }
+}
@@ -0,0 +1,515 @@
+//! LLM-facing tools for the contact book.
+//!
+//! Five tools let the agent keep track of people:
+//! - `contact_create` - Add a contact
+//! - `contact_list` - List or search contacts
+//! - `contact_update` - Edit a contact, link or unlink handles
+//! - `contact_delete` - Remove a contact
+//! - `contact_resolve` - Pick the one recipient a message should go to
+
+use std::sync::Arc;
+
+use async_trait::async_trait;
+
+use crate::contacts::{Contact, ContactBook, ContactError, ContactHandle};
+use crate::context::JobContext;
+use crate::tools::tool::{Tool, ToolError, ToolOutput, require_str};
+
+fn contact_failed(e: ContactError) -> ToolError {
+    match e {
+        ContactError::Database(e) => ToolError::ExecutionFailed(format!("DB error: {e}")),
+        other => ToolError::ExecutionFailed(other.to_string()),
+    }
+}
+
+/// The strings of `params[key]`, or `None` if absent.
+fn string_list(params: &serde_json::Value, key: &str) -> Result<Option<Vec<String>>, ToolError> {
+    match params.get(key) {
+        None | Some(serde_json::Value::Null) => Ok(None),
+        Some(serde_json::Value::Array(items)) => items
+            .iter()
+            .map(|v| {
+                v.as_str().map(|s| s.trim().to_string()).ok_or_else(|| {
+                    ToolError::InvalidParameters(format!("'{}' must contain strings", key))
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()
+            .map(Some),
+        Some(_) => Err(ToolError::InvalidParameters(format!(
+            "'{}' must be an array",
+            key
+        ))),
+    }
+}
+
+/// The `{channel, handle}` objects of `params[key]`.
+fn handle_list(params: &serde_json::Value, key: &str) -> Result<Vec<ContactHandle>, ToolError> {
+    match params.get(key) {
+        None | Some(serde_json::Value::Null) => Ok(Vec::new()),
+        Some(value) => serde_json::from_value(value.clone()).map_err(|e| {
+            ToolError::InvalidParameters(format!(
+                "'{}' must be a list of {{channel, handle}}: {}",
+                key, e
+            ))
+        }),
+    }
+}
+
+fn handles_schema(description: &str) -> serde_json::Value {
+    serde_json::json!({
+        "type": "array",
+        "items": {
+            "type": "object",
+            "properties": {
+                "channel": { "type": "string", "description": "Channel name (e.g. 'telegram')" },
+                "handle": { "type": "string", "description": "Sender ID on that channel" }
+            },
+            "required": ["channel", "handle"]
+        },
+        "description": description
+    })
+}
+
+fn contact_json(contact: &Contact) -> serde_json::Value {
+    serde_json::json!({
+        "id": contact.id.to_string(),
+        "name": contact.name,
+        "relationship": contact.relationship,
+        "emails": contact.emails,
+        "handles": contact.handles,
+        "notes": contact.notes,
+        "last_seen_at": contact.last_seen_at.map(|t| t.to_rfc3339()),
+    })
+}
+
+/// Set the optional text fields of `contact` present in `params`.
+fn apply_fields(contact: &mut Contact, params: &serde_json::Value) -> Result<(), ToolError> {
+    if let Some(name) = params.get("name").and_then(|v| v.as_str()) {
+        contact.name = name.trim().to_string();
+    }
+    if let Some(relationship) = params.get("relationship").and_then(|v| v.as_str()) {
+        contact.relationship = relationship.trim().to_string();
+    }
+    if let Some(notes) = params.get("notes").and_then(|v| v.as_str()) {
+        contact.notes = notes.to_string();
+    }
+    if let Some(emails) = string_list(params, "emails")? {
+        contact.emails = emails.into_iter().filter(|e| !e.is_empty()).collect();
+    }
+    Ok(())
+}
+
+// ==================== contact_create ====================
+
+pub struct ContactCreateTool {
+    book: Arc<ContactBook>,
+}
+
+impl ContactCreateTool {
+    pub fn new(book: Arc<ContactBook>) -> Self {
+        Self { book }
+    }
+}
+
+#[async_trait]
+impl Tool for ContactCreateTool {
+    fn name(&self) -> &str {
+        "contact_create"
+    }
+
+    fn description(&self) -> &str {
+        "Add a person to the contact book with their relationship to the user, email \
+         addresses, and handles (sender IDs) on messaging channels."
+    }
+
+    fn parameters_schema(&self) -> serde_json::Value {
+        serde_json::json!({
+            "type": "object",
+            "properties": {
+                "name": { "type": "string", "description": "Full name" },
+                "relationship": {
+                    "type": "string",
+                    "description": "How the user knows them (e.g. 'sister', 'dentist')"
+                },
+                "emails": {
+                    "type": "array",
+                    "items": { "type": "string" },
+                    "description": "Email addresses"
+                },
+                "handles": handles_schema("Handles on messaging channels"),
+                "notes": { "type": "string", "description": "Anything worth remembering" }
+            },
+            "required": ["name"]
+        })
+    }
+
+    async fn execute(
+        &self,
+        params: serde_json::Value,
+        _ctx: &JobContext,
+    ) -> Result<ToolOutput, ToolError> {
+        let start = std::time::Instant::now();
+
+        let name = require_str(&params, "name")?;
+        let mut contact = Contact::new(self.book.user_id(), name.trim());
+        apply_fields(&mut contact, &params)?;
+        for h in handle_list(&params, "handles")? {
+            contact.link(h.channel.trim(), h.handle.trim());
+        }
+
+        let contact = self.book.create(contact).await.map_err(contact_failed)?;
+        Ok(ToolOutput::success(contact_json(&contact), start.elapsed()))
+    }
+}
+
+// ==================== contact_list ====================
+
+pub struct ContactListTool {
+    book: Arc<ContactBook>,
+}
+
+impl ContactListTool {
+    pub fn new(book: Arc<ContactBook>) -> Self {
+        Self { book }
+    }
+}
+
+#[async_trait]
+impl Tool for ContactListTool {
+    fn name(&self) -> &str {
+        "contact_list"
+    }
+
+    fn description(&self) -> &str {
+        "List contacts, or search them by name, relationship, email, or handle."
+    }
+
+    fn parameters_schema(&self) -> serde_json::Value {
+        serde_json::json!({
+            "type": "object",
+            "properties": {
+                "query": {
+                    "type": "string",
+                    "description": "Name, relationship, email, or handle to search for (omit to list all)"
+                }
+            }
+        })
+    }
+
+    async fn execute(
+        &self,
+        params: serde_json::Value,
+        ctx: &JobContext,
+    ) -> Result<ToolOutput, ToolError> {
+        let start = std::time::Instant::now();
+
+        let contacts = match params.get("query").and_then(|v| v.as_str()) {
+            Some(query) if !query.trim().is_empty() => self.book.find(query).await,
+            _ => self.book.list().await,
+        }
+        .map_err(contact_failed)?;
+
+        // Others in a shared conversation see names and relationships only.
+        let list: Vec<serde_json::Value> = contacts
+            .iter()
+            .map(|c| {
+                let mut json = contact_json(c);
+                if ctx.is_shared()
+                    && let Some(fields) = json.as_object_mut()
+                {
+                    for field in ["emails", "handles", "notes"] {
+                        fields.remove(field);
+                    }
+                }
+                json
+            })
+            .collect();
+        let result = serde_json::json!({
+            "count": list.len(),
+            "contacts": list,
+        });
+        Ok(ToolOutput::success(result, start.elapsed()))
+    }
+}
+
+// ==================== contact_update ====================
+
+pub struct ContactUpdateTool {
+    book: Arc<ContactBook>,
+}
+
+impl ContactUpdateTool {
+    pub fn new(book: Arc<ContactBook>) -> Self {
+        Self { book }
+    }
+}
+
+#[async_trait]
+impl Tool for ContactUpdateTool {
+    fn name(&self) -> &str {
+        "contact_update"
+    }
+
+    fn description(&self) -> &str {
+        "Edit a contact. Only the given fields change; 'emails' replaces the list. Use \
+         'link' and 'unlink' to add or remove channel handles. A handle can belong to \
+         only one contact."
+    }
+
+    fn parameters_schema(&self) -> serde_json::Value {
+        serde_json::json!({
+            "type": "object",
+            "properties": {
+                "contact": {
+                    "type": "string",
+                    "description": "ID or unambiguous name of the contact"
+                },
+                "name": { "type": "string", "description": "New name" },
+                "relationship": { "type": "string", "description": "New relationship" },
+                "emails": {
+                    "type": "array",
+                    "items": { "type": "string" },
+                    "description": "Email addresses (replaces the current list)"
+                },
+                "notes": { "type": "string", "description": "New notes" },
+                "link": handles_schema("Handles to add"),
+                "unlink": handles_schema("Handles to remove")
+            },
+            "required": ["contact"]
+        })
+    }
+
+    async fn execute(
+        &self,
+        params: serde_json::Value,
+        _ctx: &JobContext,
+    ) -> Result<ToolOutput, ToolError> {
+        let start = std::time::Instant::now();
+
+        let query = require_str(&params, "contact")?;
+        let mut contact = self.book.find_one(query).await.map_err(contact_failed)?;
+        apply_fields(&mut contact, &params)?;
+        for h in handle_list(&params, "unlink")? {
+            contact.unlink(h.channel.trim(), h.handle.trim());
+        }
+        for h in handle_list(&params, "link")? {
+            contact.link(h.channel.trim(), h.handle.trim());
+        }
+
+        let contact = self.book.update(contact).await.map_err(contact_failed)?;
+        Ok(ToolOutput::success(contact_json(&contact), start.elapsed()))
+    }
+}
+
+// ==================== contact_delete ====================
+
+pub struct ContactDeleteTool {
+    book: Arc<ContactBook>,
+}
+
+impl ContactDeleteTool {
+    pub fn new(book: Arc<ContactBook>) -> Self {
+        Self { book }
+    }
+}
+
+#[async_trait]
+impl Tool for ContactDeleteTool {
+    fn name(&self) -> &str {
+        "contact_delete"
+    }
+
+    fn description(&self) -> &str {
+        "Delete a contact permanently."
+    }
+
+    fn parameters_schema(&self) -> serde_json::Value {
+        serde_json::json!({
+            "type": "object",
+            "properties": {
+                "contact": {
+                    "type": "string",
+                    "description": "ID or unambiguous name of the contact"
+                }
+            },
+            "required": ["contact"]
+        })
+    }
+
+    async fn execute(
+        &self,
+        params: serde_json::Value,
+        _ctx: &JobContext,
+    ) -> Result<ToolOutput, ToolError> {
+        let start = std::time::Instant::now();
+
+        let query = require_str(&params, "contact")?;
+        let contact = self.book.find_one(query).await.map_err(contact_failed)?;
+        let deleted = self.book.delete(contact.id).await.map_err(contact_failed)?;
+
+        let result = serde_json::json!({
+            "name": contact.name,
+            "deleted": deleted,
+        });
+        Ok(ToolOutput::success(result, start.elapsed()))
+    }
+}
+
+// ==================== contact_resolve ====================
+
+pub struct ContactResolveTool {
+    book: Arc<ContactBook>,
+}
+
+impl ContactResolveTool {
+    pub fn new(book: Arc<ContactBook>) -> Self {
+        Self { book }
+    }
+}
+
+#[async_trait]
+impl Tool for ContactResolveTool {
+    fn name(&self) -> &str {
+        "contact_resolve"
+    }
+
+    fn description(&self) -> &str {
+        "Find who to message before sending anything to a person. Returns the one contact \
+         and handle a name refers to, or an error listing the candidates when the name is \
+         ambiguous or the person can't be reached; ask the user instead of guessing."
+    }
+
+    fn parameters_schema(&self) -> serde_json::Value {
+        serde_json::json!({
+            "type": "object",
+            "properties": {
+                "recipient": {
+                    "type": "string",
+                    "description": "Who to reach: a name, relationship, email, handle, or contact ID"
+                },
+                "channel": {
+                    "type": "string",
+                    "description": "Channel to reach them on (required if they have handles on several)"
+                }
+            },
+            "required": ["recipient"]
+        })
+    }
+
+    async fn execute(
+        &self,
+        params: serde_json::Value,
+        _ctx: &JobContext,
+    ) -> Result<ToolOutput, ToolError> {
+        let start = std::time::Instant::now();
+
+        let query = require_str(&params, "recipient")?;
+        let channel = params
+            .get("channel")
+            .and_then(|v| v.as_str())
+            .filter(|c| !c.trim().is_empty());
+        let recipient = self
+            .book
+            .resolve(query, channel)
+            .await
+            .map_err(contact_failed)?;
+
+        let result = serde_json::json!({
+            "contact": contact_json(&recipient.contact),
+            "channel": recipient.channel,
+            "handle": recipient.handle,
+        });
+        Ok(ToolOutput::success(result, start.elapsed()))
+    }
+}
+
+#[cfg(all(test, feature = "libsql"))]
+mod tests {
+    use super::*;
+    use crate::context::SessionScope;
+    use crate::db::libsql_backend::test_db;
+
+    #[tokio::test]
+    async fn test_contact_tools_round_trip() {
+        let (db, _dir) = test_db().await;
+        let book = Arc::new(ContactBook::new(db, "default"));
+        let ctx = JobContext::default();
+
+        let created = ContactCreateTool::new(Arc::clone(&book))
+            .execute(
+                serde_json::json!({
+                    "name": "Anna Schmidt",
+                    "relationship": "sister",
+                    "handles": [{ "channel": "telegram", "handle": "4242" }]
+                }),
+                &ctx,
+            )
+            .await
+            .unwrap();
+        assert_eq!(created.result["handles"][0]["handle"], "4242");
+
+        let update = ContactUpdateTool::new(Arc::clone(&book));
+        update
+            .execute(
+                serde_json::json!({
+                    "contact": "anna",
+                    "emails": ["anna@example.com"],
+                    "link": [{ "channel": "slack", "handle": "U02" }]
+                }),
+                &ctx,
+            )
+            .await
+            .unwrap();
+        assert!(
+            update
+                .execute(
+                    serde_json::json!({ "contact": "anna", "link": "telegram" }),
+                    &ctx
+                )
+                .await
+                .is_err()
+        );
+
+        let resolve = ContactResolveTool::new(Arc::clone(&book));
+        let err = resolve
+            .execute(serde_json::json!({ "recipient": "my sister" }), &ctx)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("No contact"));
+        let err = resolve
+            .execute(serde_json::json!({ "recipient": "sister" }), &ctx)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("several channels"));
+        let output = resolve
+            .execute(
+                serde_json::json!({ "recipient": "sister", "channel": "slack" }),
+                &ctx,
+            )
+            .await
+            .unwrap();
+        assert_eq!(output.result["handle"], "U02");
+
+        let list = ContactListTool::new(Arc::clone(&book))
+            .execute(serde_json::json!({ "query": "anna@example.com" }), &ctx)
+            .await
+            .unwrap();
+        assert_eq!(list.result["count"], 1);
+        assert_eq!(list.result["contacts"][0]["emails"][0], "anna@example.com");
+
+        let group = JobContext::default().with_channel("telegram", SessionScope::Shared);
+        let list = ContactListTool::new(Arc::clone(&book))
+            .execute(serde_json::json!({}), &group)
+            .await
+            .unwrap();
+        assert_eq!(list.result["contacts"][0]["relationship"], "sister");
+        assert!(list.result["contacts"][0].get("emails").is_none());
+
+        let deleted = ContactDeleteTool::new(Arc::clone(&book))
+            .execute(serde_json::json!({ "contact": "Anna Schmidt" }), &ctx)
+            .await
+            .unwrap();
+        assert_eq!(deleted.result["deleted"], true);
+        assert!(book.list().await.unwrap().is_empty());
+    }
+}
@@ -148,6 +148,21 @@ fn validate_path(path_str: &str, base_dir: Option<&Path>) -> Result<PathBuf, Too
     Ok(resolved)
 }
 
+/// Resolve `path_str` for a tool call. Without a sandbox base, relative
+/// paths are taken relative to the job's temp directory, if it has one.
+fn resolve_path(
+    path_str: &str,
+    base_dir: Option<&Path>,
+    ctx: &JobContext,
+) -> Result<PathBuf, ToolError> {
+    match (base_dir, ctx.temp_dir.as_deref()) {
+        (None, Some(dir)) if Path::new(path_str).is_relative() => {
+            validate_path(&dir.join(path_str).to_string_lossy(), None)
+        }
+        _ => validate_path(path_str, base_dir),
+    }
+}
+
 /// Read file contents tool.
 #[derive(Debug, Default)]
 pub struct ReadFileTool {
@@ -201,7 +216,7 @@ impl Tool for ReadFileTool {
     async fn execute(
         &self,
         params: serde_json::Value,
-        _ctx: &JobContext,
+        ctx: &JobContext,
     ) -> Result<ToolOutput, ToolError> {
         let path_str = require_str(&params, "path")?;
 
@@ -210,7 +225,7 @@ impl Tool for ReadFileTool {
 
         let start = std::time::Instant::now();
 
-        let path = validate_path(path_str, self.base_dir.as_deref())?;
+        let path = resolve_path(path_str, self.base_dir.as_deref(), ctx)?;
 
         // Check file size
         let metadata = fs::metadata(&path)
@@ -323,7 +338,7 @@ impl Tool for WriteFileTool {
     async fn execute(
         &self,
         params: serde_json::Value,
-        _ctx: &JobContext,
+        ctx: &JobContext,
     ) -> Result<ToolOutput, ToolError> {
         let path_str = require_str(&params, "path")?;
 
@@ -349,7 +364,7 @@ impl Tool for WriteFileTool {
             )));
         }
 
-        let path = validate_path(path_str, self.base_dir.as_deref())?;
+        let path = resolve_path(path_str, self.base_dir.as_deref(), ctx)?;
 
         // Create parent directories
         if let Some(parent) = path.parent() {
@@ -437,7 +452,7 @@ impl Tool for ListDirTool {
     async fn execute(
         &self,
         params: serde_json::Value,
-        _ctx: &JobContext,
+        ctx: &JobContext,
     ) -> Result<ToolOutput, ToolError> {
         let path_str = params.get("path").and_then(|v| v.as_str()).unwrap_or(".");
 
@@ -453,7 +468,7 @@ impl Tool for ListDirTool {
 
         let start = std::time::Instant::now();
 
-        let path = validate_path(path_str, self.base_dir.as_deref())?;
+        let path = resolve_path(path_str, self.base_dir.as_deref(), ctx)?;
 
         let mut entries = Vec::new();
         list_dir_inner(&path, &path, recursive, max_depth, 0, &mut entries).await?;
@@ -639,7 +654,7 @@ impl Tool for ApplyPatchTool {
     async fn execute(
         &self,
         params: serde_json::Value,
-        _ctx: &JobContext,
+        ctx: &JobContext,
     ) -> Result<ToolOutput, ToolError> {
         let path_str = require_str(&params, "path")?;
 
@@ -654,7 +669,7 @@ impl Tool for ApplyPatchTool {
 
         let start = std::time::Instant::now();
 
-        let path = validate_path(path_str, self.base_dir.as_deref())?;
+        let path = resolve_path(path_str, self.base_dir.as_deref(), ctx)?;
 
         // Read current content
         let content = fs::read_to_string(&path)
@@ -858,6 +873,30 @@ mod tests {
         assert!(result.is_ok());
     }
 
+    #[tokio::test]
+    async fn test_relative_paths_use_job_temp_dir() {
+        let dir = TempDir::new().unwrap();
+        let ctx = JobContext {
+            temp_dir: Some(dir.path().to_path_buf()),
+            ..Default::default()
+        };
+
+        let tool = WriteFileTool::new();
+        tool.execute(
+            serde_json::json!({ "path": "notes/out.txt", "content": "hi" }),
+            &ctx,
+        )
+        .await
+        .unwrap();
+        assert!(dir.path().join("notes/out.txt").exists());
+
+        let output = ReadFileTool::new()
+            .execute(serde_json::json!({ "path": "notes/out.txt" }), &ctx)
+            .await
+            .unwrap();
+        assert!(output.result["content"].as_str().unwrap().contains("hi"));
+    }
+
     #[tokio::test]
     async fn test_list_dir() {
         let dir = TempDir::new().unwrap();
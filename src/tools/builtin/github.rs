@@ -0,0 +1,727 @@
+//! GitHub tool (REST API).
+//!
+//! Lists notifications, reads issues and pull requests, checks CI, and
+//! comments. Every call is limited to the repositories in `GITHUB_REPOS`:
+//! notifications from other repositories are dropped, and anything else
+//! naming one is refused. Commenting needs write access in the allowlist
+//! and the user's approval.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use reqwest::{Client, Method};
+use secrecy::{ExposeSecret, SecretString};
+
+use crate::config::GitHubConfig;
+use crate::context::JobContext;
+use crate::secrets::SecretsStore;
+use crate::tools::tool::{Tool, ToolError, ToolOutput, require_str};
+
+/// Secret holding the API token when `GITHUB_TOKEN` isn't set.
+const TOKEN_SECRET: &str = "github_token";
+
+/// Comments shown with an issue or pull request (the most recent).
+const MAX_COMMENTS: usize = 10;
+
+/// Longest issue, PR, or comment body returned, in bytes.
+const MAX_BODY: usize = 4_000;
+
+/// One `GITHUB_REPOS` entry.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct RepoRule {
+    owner: String,
+    /// Repository name, or `None` for all of the owner's repositories.
+    name: Option<String>,
+    writable: bool,
+}
+
+impl RepoRule {
+    fn parse(entry: &str) -> Option<Self> {
+        let (entry, writable) = match entry.strip_suffix(":read") {
+            Some(entry) => (entry, false),
+            None => (entry, true),
+        };
+        let (owner, name) = entry.split_once('/')?;
+        if owner.is_empty() || name.is_empty() || name.contains('/') {
+            return None;
+        }
+        Some(Self {
+            owner: owner.to_lowercase(),
+            name: (name != "*").then(|| name.to_lowercase()),
+            writable,
+        })
+    }
+
+    fn matches(&self, owner: &str, name: &str) -> bool {
+        self.owner == owner && self.name.as_deref().is_none_or(|n| n == name)
+    }
+}
+
+/// Access granted to a repository by the allowlist.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Access {
+    None,
+    Read,
+    Write,
+}
+
+/// Tool for working with GitHub repositories.
+pub struct GitHubTool {
+    client: Client,
+    api_url: String,
+    rules: Vec<RepoRule>,
+    token: Option<SecretString>,
+    secrets: Option<Arc<dyn SecretsStore + Send + Sync>>,
+}
+
+impl GitHubTool {
+    pub fn new(config: &GitHubConfig) -> Self {
+        let client = Client::builder()
+            .timeout(Duration::from_secs(20))
+            .user_agent("ironclaw")
+            .build()
+            .expect("Failed to create HTTP client");
+        Self {
+            client,
+            api_url: config.api_url.clone(),
+            rules: config
+                .repos
+                .iter()
+                .filter_map(|r| RepoRule::parse(r))
+                .collect(),
+            token: config.token.clone(),
+            secrets: None,
+        }
+    }
+
+    /// Read the token from this store when `GITHUB_TOKEN` isn't set.
+    pub fn with_secrets(mut self, secrets: Arc<dyn SecretsStore + Send + Sync>) -> Self {
+        self.secrets = Some(secrets);
+        self
+    }
+
+    /// The allowlist's access to `repo` (`owner/name`). A specific entry
+    /// wins over an `owner/*` one.
+    fn access(&self, repo: &str) -> Access {
+        let Some((owner, name)) = repo
+            .to_lowercase()
+            .split_once('/')
+            .map(|(o, n)| (o.to_string(), n.to_string()))
+        else {
+            return Access::None;
+        };
+        let rule = self
+            .rules
+            .iter()
+            .filter(|r| r.matches(&owner, &name))
+            .max_by_key(|r| r.name.is_some());
+        match rule {
+            Some(r) if r.writable => Access::Write,
+            Some(_) => Access::Read,
+            None => Access::None,
+        }
+    }
+
+    /// The `repo` parameter, checked against the allowlist.
+    fn repo<'a>(&self, params: &'a serde_json::Value, write: bool) -> Result<&'a str, ToolError> {
+        let repo = require_str(params, "repo")?.trim();
+        if repo.matches('/').count() != 1 || !is_path_safe(repo) {
+            return Err(ToolError::InvalidParameters(format!(
+                "'{}' is not an owner/name repository",
+                repo
+            )));
+        }
+        match self.access(repo) {
+            Access::Write => Ok(repo),
+            Access::Read if !write => Ok(repo),
+            Access::Read => Err(ToolError::NotAuthorized(format!(
+                "{} is read-only in GITHUB_REPOS",
+                repo
+            ))),
+            Access::None => Err(ToolError::NotAuthorized(format!(
+                "{} is not in GITHUB_REPOS",
+                repo
+            ))),
+        }
+    }
+
+    async fn token(&self, user_id: &str) -> Result<String, ToolError> {
+        if let Some(token) = &self.token {
+            return Ok(token.expose_secret().to_string());
+        }
+        let store = self.secrets.as_ref().ok_or_else(|| {
+            ToolError::ExecutionFailed(
+                "no GitHub token: set GITHUB_TOKEN or store a 'github_token' secret".to_string(),
+            )
+        })?;
+        let secret = store
+            .get_decrypted(user_id, TOKEN_SECRET)
+            .await
+            .map_err(|e| {
+                ToolError::ExecutionFailed(format!("no GitHub token ({}): {}", TOKEN_SECRET, e))
+            })?;
+        Ok(secret.expose().to_string())
+    }
+
+    async fn request(
+        &self,
+        ctx: &JobContext,
+        method: Method,
+        path: &str,
+        body: Option<serde_json::Value>,
+    ) -> Result<serde_json::Value, ToolError> {
+        let token = self.token(&ctx.user_id).await?;
+        let mut request = self
+            .client
+            .request(method, format!("{}{}", self.api_url, path))
+            .bearer_auth(token)
+            .header("Accept", "application/vnd.github+json")
+            .header("X-GitHub-Api-Version", "2022-11-28");
+        if let Some(body) = body {
+            request = request.json(&body);
+        }
+        let response = request.send().await.map_err(|e| {
+            if e.is_timeout() {
+                ToolError::Timeout(Duration::from_secs(20))
+            } else {
+                ToolError::ExternalService(format!("GitHub request failed: {}", e))
+            }
+        })?;
+
+        let status = response.status();
+        if !status.is_success() {
+            let message = response
+                .json::<serde_json::Value>()
+                .await
+                .ok()
+                .and_then(|v| v.get("message").and_then(|m| m.as_str()).map(String::from))
+                .unwrap_or_default();
+            return Err(ToolError::ExternalService(format!(
+                "GitHub returned {} for {}: {}",
+                status, path, message
+            )));
+        }
+        response
+            .json()
+            .await
+            .map_err(|e| ToolError::ExternalService(format!("invalid GitHub response: {}", e)))
+    }
+
+    async fn get(&self, ctx: &JobContext, path: &str) -> Result<serde_json::Value, ToolError> {
+        self.request(ctx, Method::GET, path, None).await
+    }
+
+    async fn notifications(
+        &self,
+        params: &serde_json::Value,
+        ctx: &JobContext,
+    ) -> Result<serde_json::Value, ToolError> {
+        let all = params.get("all").and_then(|v| v.as_bool()).unwrap_or(false);
+        let list = self
+            .get(ctx, &format!("/notifications?all={}&per_page=50", all))
+            .await?;
+        let notifications: Vec<serde_json::Value> = list
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter(|n| {
+                n.pointer("/repository/full_name")
+                    .and_then(|v| v.as_str())
+                    .is_some_and(|repo| self.access(repo) != Access::None)
+            })
+            .map(notification)
+            .collect();
+        Ok(serde_json::json!({
+            "count": notifications.len(),
+            "notifications": notifications,
+        }))
+    }
+
+    async fn list(
+        &self,
+        params: &serde_json::Value,
+        ctx: &JobContext,
+    ) -> Result<serde_json::Value, ToolError> {
+        let repo = self.repo(params, false)?;
+        let state = match params.get("state").and_then(|v| v.as_str()) {
+            Some(state @ ("open" | "closed" | "all")) => state,
+            Some(other) => {
+                return Err(ToolError::InvalidParameters(format!(
+                    "unknown state '{}'",
+                    other
+                )));
+            }
+            None => "open",
+        };
+        let list = self
+            .get(
+                ctx,
+                &format!("/repos/{}/issues?state={}&per_page=30", repo, state),
+            )
+            .await?;
+        let items: Vec<serde_json::Value> = list
+            .as_array()
+            .into_iter()
+            .flatten()
+            .map(|i| {
+                serde_json::json!({
+                    "number": i.get("number"),
+                    "kind": kind(i),
+                    "title": i.get("title"),
+                    "state": i.get("state"),
+                    "author": i.pointer("/user/login"),
+                    "labels": labels(i),
+                    "comments": i.get("comments"),
+                    "updated_at": i.get("updated_at"),
+                })
+            })
+            .collect();
+        Ok(serde_json::json!({ "repo": repo, "items": items }))
+    }
+
+    async fn read(
+        &self,
+        params: &serde_json::Value,
+        ctx: &JobContext,
+    ) -> Result<serde_json::Value, ToolError> {
+        let repo = self.repo(params, false)?;
+        let number = number(params)?;
+        let issue = self
+            .get(ctx, &format!("/repos/{}/issues/{}", repo, number))
+            .await?;
+        let comments = self
+            .get(
+                ctx,
+                &format!("/repos/{}/issues/{}/comments?per_page=100", repo, number),
+            )
+            .await?;
+        let comments = comments.as_array().map(Vec::as_slice).unwrap_or_default();
+        let recent: Vec<serde_json::Value> = comments
+            [comments.len().saturating_sub(MAX_COMMENTS)..]
+            .iter()
+            .map(|c| {
+                serde_json::json!({
+                    "author": c.pointer("/user/login"),
+                    "created_at": c.get("created_at"),
+                    "body": truncate(c.get("body")),
+                })
+            })
+            .collect();
+
+        let mut result = serde_json::json!({
+            "repo": repo,
+            "number": number,
+            "kind": kind(&issue),
+            "title": issue.get("title"),
+            "state": issue.get("state"),
+            "author": issue.pointer("/user/login"),
+            "labels": labels(&issue),
+            "created_at": issue.get("created_at"),
+            "updated_at": issue.get("updated_at"),
+            "url": issue.get("html_url"),
+            "body": truncate(issue.get("body")),
+            "comment_count": comments.len(),
+            "recent_comments": recent,
+        });
+        if kind(&issue) == "pull_request" {
+            let pr = self
+                .get(ctx, &format!("/repos/{}/pulls/{}", repo, number))
+                .await?;
+            result["pull_request"] = serde_json::json!({
+                "draft": pr.get("draft"),
+                "merged": pr.get("merged"),
+                "mergeable_state": pr.get("mergeable_state"),
+                "base": pr.pointer("/base/ref"),
+                "head": pr.pointer("/head/ref"),
+                "head_sha": pr.pointer("/head/sha"),
+                "changed_files": pr.get("changed_files"),
+                "additions": pr.get("additions"),
+                "deletions": pr.get("deletions"),
+            });
+        }
+        Ok(result)
+    }
+
+    async fn comment(
+        &self,
+        params: &serde_json::Value,
+        ctx: &JobContext,
+    ) -> Result<serde_json::Value, ToolError> {
+        let repo = self.repo(params, true)?;
+        let number = number(params)?;
+        let body = require_str(params, "body")?.trim();
+        if body.is_empty() {
+            return Err(ToolError::InvalidParameters(
+                "comment body is empty".to_string(),
+            ));
+        }
+        let comment = self
+            .request(
+                ctx,
+                Method::POST,
+                &format!("/repos/{}/issues/{}/comments", repo, number),
+                Some(serde_json::json!({ "body": body })),
+            )
+            .await?;
+        Ok(serde_json::json!({
+            "repo": repo,
+            "number": number,
+            "id": comment.get("id"),
+            "url": comment.get("html_url"),
+        }))
+    }
+
+    async fn ci_status(
+        &self,
+        params: &serde_json::Value,
+        ctx: &JobContext,
+    ) -> Result<serde_json::Value, ToolError> {
+        let repo = self.repo(params, false)?;
+        let git_ref = match params.get("ref").and_then(|v| v.as_str()) {
+            Some(git_ref) if is_path_safe(git_ref) => git_ref.to_string(),
+            Some(git_ref) => {
+                return Err(ToolError::InvalidParameters(format!(
+                    "invalid ref '{}'",
+                    git_ref
+                )));
+            }
+            None if params.get("number").is_some() => {
+                let pr = self
+                    .get(ctx, &format!("/repos/{}/pulls/{}", repo, number(params)?))
+                    .await?;
+                pr.pointer("/head/sha")
+                    .and_then(|v| v.as_str())
+                    .ok_or_else(|| {
+                        ToolError::ExternalService("pull request has no head commit".to_string())
+                    })?
+                    .to_string()
+            }
+            None => {
+                return Err(ToolError::InvalidParameters(
+                    "ci_status needs 'ref' or a pull request 'number'".to_string(),
+                ));
+            }
+        };
+        let checks = self
+            .get(
+                ctx,
+                &format!(
+                    "/repos/{}/commits/{}/check-runs?per_page=100",
+                    repo, git_ref
+                ),
+            )
+            .await?;
+        let statuses = self
+            .get(ctx, &format!("/repos/{}/commits/{}/status", repo, git_ref))
+            .await?;
+        let mut result = ci_summary(&checks, &statuses);
+        result["repo"] = serde_json::json!(repo);
+        result["ref"] = serde_json::json!(git_ref);
+        Ok(result)
+    }
+}
+
+/// A notification as returned to the model.
+fn notification(n: &serde_json::Value) -> serde_json::Value {
+    // Subject URLs are API URLs ending in the issue or PR number.
+    let number = n
+        .pointer("/subject/url")
+        .and_then(|v| v.as_str())
+        .and_then(|url| url.rsplit('/').next())
+        .and_then(|n| n.parse::<u64>().ok());
+    serde_json::json!({
+        "id": n.get("id"),
+        "repo": n.pointer("/repository/full_name"),
+        "type": n.pointer("/subject/type"),
+        "title": n.pointer("/subject/title"),
+        "number": number,
+        "reason": n.get("reason"),
+        "unread": n.get("unread"),
+        "updated_at": n.get("updated_at"),
+    })
+}
+
+/// Overall CI state from check runs and commit statuses: `failure` if
+/// anything failed, `pending` if anything is still running, else `success`
+/// (or `none` when nothing reported).
+fn ci_summary(checks: &serde_json::Value, statuses: &serde_json::Value) -> serde_json::Value {
+    let mut results = Vec::new();
+    for run in checks
+        .get("check_runs")
+        .and_then(|v| v.as_array())
+        .into_iter()
+        .flatten()
+    {
+        let state = match run.get("status").and_then(|v| v.as_str()) {
+            Some("completed") => match run.get("conclusion").and_then(|v| v.as_str()) {
+                Some("success" | "neutral" | "skipped") => "success",
+                _ => "failure",
+            },
+            _ => "pending",
+        };
+        results.push(serde_json::json!({
+            "name": run.get("name"),
+            "state": state,
+            "conclusion": run.get("conclusion"),
+            "url": run.get("html_url"),
+        }));
+    }
+    for status in statuses
+        .get("statuses")
+        .and_then(|v| v.as_array())
+        .into_iter()
+        .flatten()
+    {
+        let state = match status.get("state").and_then(|v| v.as_str()) {
+            Some("success") => "success",
+            Some("pending") => "pending",
+            _ => "failure",
+        };
+        results.push(serde_json::json!({
+            "name": status.get("context"),
+            "state": state,
+            "description": status.get("description"),
+            "url": status.get("target_url"),
+        }));
+    }
+
+    let count = |state: &str| results.iter().filter(|r| r["state"] == state).count();
+    let (failed, pending) = (count("failure"), count("pending"));
+    let state = if results.is_empty() {
+        "none"
+    } else if failed > 0 {
+        "failure"
+    } else if pending > 0 {
+        "pending"
+    } else {
+        "success"
+    };
+    serde_json::json!({
+        "state": state,
+        "total": results.len(),
+        "failed": failed,
+        "pending": pending,
+        "checks": results,
+    })
+}
+
+/// Whether `s` can go into an API path as is: names, refs, and SHAs only.
+fn is_path_safe(s: &str) -> bool {
+    !s.is_empty()
+        && !s.contains("..")
+        && s.chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.' | '/'))
+}
+
+fn kind(issue: &serde_json::Value) -> &'static str {
+    if issue.get("pull_request").is_some() {
+        "pull_request"
+    } else {
+        "issue"
+    }
+}
+
+fn labels(issue: &serde_json::Value) -> Vec<&str> {
+    issue
+        .get("labels")
+        .and_then(|v| v.as_array())
+        .into_iter()
+        .flatten()
+        .filter_map(|l| l.get("name").and_then(|n| n.as_str()))
+        .collect()
+}
+
+fn truncate(body: Option<&serde_json::Value>) -> String {
+    let body = body.and_then(|v| v.as_str()).unwrap_or("");
+    if body.len() <= MAX_BODY {
+        return body.to_string();
+    }
+    let mut end = MAX_BODY;
+    while !body.is_char_boundary(end) {
+        end -= 1;
+    }
+    format!("{}… (truncated)", &body[..end])
+}
+
+fn number(params: &serde_json::Value) -> Result<u64, ToolError> {
+    params
+        .get("number")
+        .and_then(|v| v.as_u64())
+        .ok_or_else(|| ToolError::InvalidParameters("missing 'number'".to_string()))
+}
+
+#[async_trait]
+impl Tool for GitHubTool {
+    fn name(&self) -> &str {
+        "github"
+    }
+
+    fn description(&self) -> &str {
+        "Work with GitHub: list notifications, list or read issues and pull requests (with \
+         recent comments), check CI status for a PR or commit, and comment (requires \
+         approval). Limited to the repositories the user has allowed."
+    }
+
+    fn parameters_schema(&self) -> serde_json::Value {
+        serde_json::json!({
+            "type": "object",
+            "properties": {
+                "action": {
+                    "type": "string",
+                    "enum": ["notifications", "list", "read", "comment", "ci_status"],
+                    "description": "What to do"
+                },
+                "repo": {
+                    "type": "string",
+                    "description": "Repository as owner/name (all actions but notifications)"
+                },
+                "number": {
+                    "type": "integer",
+                    "description": "Issue or pull request number (read, comment, ci_status)"
+                },
+                "body": {
+                    "type": "string",
+                    "description": "Comment text in markdown (comment)"
+                },
+                "ref": {
+                    "type": "string",
+                    "description": "Branch, tag, or commit SHA (ci_status, instead of number)"
+                },
+                "state": {
+                    "type": "string",
+                    "enum": ["open", "closed", "all"],
+                    "description": "Issue state (list; default open)"
+                },
+                "all": {
+                    "type": "boolean",
+                    "description": "Include read notifications (notifications; default false)"
+                }
+            },
+            "required": ["action"]
+        })
+    }
+
+    async fn execute(
+        &self,
+        params: serde_json::Value,
+        ctx: &JobContext,
+    ) -> Result<ToolOutput, ToolError> {
+        let start = std::time::Instant::now();
+        let result = match require_str(&params, "action")? {
+            "notifications" => self.notifications(&params, ctx).await?,
+            "list" => self.list(&params, ctx).await?,
+            "read" => self.read(&params, ctx).await?,
+            "comment" => self.comment(&params, ctx).await?,
+            "ci_status" => self.ci_status(&params, ctx).await?,
+            other => {
+                return Err(ToolError::InvalidParameters(format!(
+                    "unknown action '{}'",
+                    other
+                )));
+            }
+        };
+        Ok(ToolOutput::success(result, start.elapsed()))
+    }
+
+    fn estimated_duration(&self, _params: &serde_json::Value) -> Option<Duration> {
+        Some(Duration::from_secs(3))
+    }
+
+    fn requires_sanitization(&self) -> bool {
+        true // Issue and comment text is untrusted
+    }
+
+    fn requires_approval_for(&self, params: &serde_json::Value) -> bool {
+        params.get("action").and_then(|v| v.as_str()) == Some("comment")
+    }
+
+    fn is_external_action(&self) -> bool {
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tool(repos: &[&str]) -> GitHubTool {
+        GitHubTool::new(&GitHubConfig {
+            repos: repos.iter().map(|r| r.to_string()).collect(),
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn test_allowlist() {
+        let tool = tool(&["nearai/ironclaw", "acme/*:read", "acme/site"]);
+        assert_eq!(tool.access("nearai/ironclaw"), Access::Write);
+        assert_eq!(tool.access("NearAI/IronClaw"), Access::Write);
+        assert_eq!(tool.access("nearai/other"), Access::None);
+        assert_eq!(tool.access("acme/anything"), Access::Read);
+        assert_eq!(tool.access("acme/site"), Access::Write);
+        assert_eq!(tool.access("not-a-repo"), Access::None);
+
+        let params = serde_json::json!({ "repo": "acme/docs" });
+        assert!(tool.repo(&params, false).is_ok());
+        assert!(matches!(
+            tool.repo(&params, true),
+            Err(ToolError::NotAuthorized(_))
+        ));
+        let params = serde_json::json!({ "repo": "acme/x/../../other/repo" });
+        assert!(matches!(
+            tool.repo(&params, false),
+            Err(ToolError::InvalidParameters(_))
+        ));
+        let params = serde_json::json!({ "repo": "other/repo" });
+        assert!(matches!(
+            tool.repo(&params, false),
+            Err(ToolError::NotAuthorized(_))
+        ));
+
+        assert!(tool.requires_approval_for(&serde_json::json!({ "action": "comment" })));
+        assert!(!tool.requires_approval_for(&serde_json::json!({ "action": "read" })));
+    }
+
+    #[test]
+    fn test_ci_summary() {
+        let checks = serde_json::json!({ "check_runs": [
+            { "name": "test", "status": "completed", "conclusion": "success" },
+            { "name": "lint", "status": "completed", "conclusion": "skipped" },
+            { "name": "deploy", "status": "in_progress", "conclusion": null },
+        ]});
+        let statuses = serde_json::json!({ "statuses": [
+            { "context": "ci/legacy", "state": "success" },
+        ]});
+        let summary = ci_summary(&checks, &statuses);
+        assert_eq!(summary["state"], "pending");
+        assert_eq!(summary["total"], 4);
+
+        let statuses = serde_json::json!({ "statuses": [
+            { "context": "ci/legacy", "state": "error" },
+        ]});
+        let summary = ci_summary(&checks, &statuses);
+        assert_eq!(summary["state"], "failure");
+        assert_eq!(summary["failed"], 1);
+
+        let empty = serde_json::json!({});
+        assert_eq!(ci_summary(&empty, &empty)["state"], "none");
+    }
+
+    #[test]
+    fn test_notification_number_from_subject_url() {
+        let n = serde_json::json!({
+            "id": "1",
+            "reason": "review_requested",
+            "repository": { "full_name": "nearai/ironclaw" },
+            "subject": {
+                "type": "PullRequest",
+                "title": "Add github tool",
+                "url": "https://api.github.com/repos/nearai/ironclaw/pulls/42"
+            }
+        });
+        let n = notification(&n);
+        assert_eq!(n["number"], 42);
+        assert_eq!(n["type"], "PullRequest");
+    }
+}
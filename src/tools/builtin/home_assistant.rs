@@ -0,0 +1,465 @@
+//! Home Assistant tool (REST API).
+//!
+//! Reads entity states and calls services on a Home Assistant instance.
+//! Only entities matched by `HOME_ASSISTANT_ENTITIES` are visible or
+//! controllable, and service calls must name their target entities
+//! explicitly, since an untargeted call acts on every entity of the domain.
+//! Anything touching a lock or alarm panel needs the user's approval.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use reqwest::{Client, Method};
+use secrecy::{ExposeSecret, SecretString};
+
+use crate::config::HomeAssistantConfig;
+use crate::context::JobContext;
+use crate::secrets::SecretsStore;
+use crate::tools::tool::{Tool, ToolError, ToolOutput, require_str};
+
+/// Secret holding the access token when `HOME_ASSISTANT_TOKEN` isn't set.
+const TOKEN_SECRET: &str = "home_assistant_token";
+
+/// Domains whose service calls always need approval.
+const SENSITIVE_DOMAINS: &[&str] = &["lock", "alarm_control_panel"];
+
+/// Service data keys that select targets beyond `entity_id`.
+const TARGET_KEYS: &[&str] = &["entity_id", "area_id", "device_id", "floor_id", "label_id"];
+
+/// Tool for reading and controlling smart home devices.
+pub struct HomeAssistantTool {
+    client: Client,
+    url: String,
+    /// Allowlist entries: a domain, an entity ID, or `*`.
+    allowed: Vec<String>,
+    token: Option<SecretString>,
+    secrets: Option<Arc<dyn SecretsStore + Send + Sync>>,
+}
+
+impl HomeAssistantTool {
+    pub fn new(url: impl Into<String>, config: &HomeAssistantConfig) -> Self {
+        let client = Client::builder()
+            .timeout(Duration::from_secs(15))
+            .build()
+            .expect("Failed to create HTTP client");
+        Self {
+            client,
+            url: url.into(),
+            allowed: config
+                .entities
+                .iter()
+                .map(|e| e.trim_end_matches(".*").to_string())
+                .collect(),
+            token: config.token.clone(),
+            secrets: None,
+        }
+    }
+
+    /// Read the token from this store when `HOME_ASSISTANT_TOKEN` isn't set.
+    pub fn with_secrets(mut self, secrets: Arc<dyn SecretsStore + Send + Sync>) -> Self {
+        self.secrets = Some(secrets);
+        self
+    }
+
+    /// Whether the allowlist covers `entity_id`.
+    fn allows(&self, entity_id: &str) -> bool {
+        let domain = entity_id.split('.').next().unwrap_or("");
+        self.allowed
+            .iter()
+            .any(|a| a == "*" || a == entity_id || a == domain)
+    }
+
+    /// `entity_id`, checked for form and against the allowlist.
+    fn entity<'a>(&self, entity_id: &'a str) -> Result<&'a str, ToolError> {
+        let entity_id = entity_id.trim();
+        if !is_entity_id(entity_id) {
+            return Err(ToolError::InvalidParameters(format!(
+                "'{}' is not an entity ID like light.kitchen",
+                entity_id
+            )));
+        }
+        if !self.allows(entity_id) {
+            return Err(ToolError::NotAuthorized(format!(
+                "{} is not in HOME_ASSISTANT_ENTITIES",
+                entity_id
+            )));
+        }
+        Ok(entity_id)
+    }
+
+    async fn token(&self, user_id: &str) -> Result<String, ToolError> {
+        if let Some(token) = &self.token {
+            return Ok(token.expose_secret().to_string());
+        }
+        let store = self.secrets.as_ref().ok_or_else(|| {
+            ToolError::ExecutionFailed(
+                "no Home Assistant token: set HOME_ASSISTANT_TOKEN or store a \
+                 'home_assistant_token' secret"
+                    .to_string(),
+            )
+        })?;
+        let secret = store
+            .get_decrypted(user_id, TOKEN_SECRET)
+            .await
+            .map_err(|e| {
+                ToolError::ExecutionFailed(format!(
+                    "no Home Assistant token ({}): {}",
+                    TOKEN_SECRET, e
+                ))
+            })?;
+        Ok(secret.expose().to_string())
+    }
+
+    async fn request(
+        &self,
+        ctx: &JobContext,
+        method: Method,
+        path: &str,
+        body: Option<serde_json::Value>,
+    ) -> Result<serde_json::Value, ToolError> {
+        let token = self.token(&ctx.user_id).await?;
+        let mut request = self
+            .client
+            .request(method, format!("{}{}", self.url, path))
+            .bearer_auth(token);
+        if let Some(body) = body {
+            request = request.json(&body);
+        }
+        let response = request.send().await.map_err(|e| {
+            if e.is_timeout() {
+                ToolError::Timeout(Duration::from_secs(15))
+            } else {
+                ToolError::ExternalService(format!("Home Assistant request failed: {}", e))
+            }
+        })?;
+        let status = response.status();
+        if !status.is_success() {
+            let message = response.text().await.unwrap_or_default();
+            return Err(ToolError::ExternalService(format!(
+                "Home Assistant returned {} for {}: {}",
+                status,
+                path,
+                message.trim()
+            )));
+        }
+        response.json().await.map_err(|e| {
+            ToolError::ExternalService(format!("invalid Home Assistant response: {}", e))
+        })
+    }
+
+    async fn states(
+        &self,
+        params: &serde_json::Value,
+        ctx: &JobContext,
+    ) -> Result<serde_json::Value, ToolError> {
+        let domain = params.get("domain").and_then(|v| v.as_str());
+        let states = self.request(ctx, Method::GET, "/api/states", None).await?;
+        let entities: Vec<serde_json::Value> = states
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter(|s| {
+                s.get("entity_id")
+                    .and_then(|v| v.as_str())
+                    .is_some_and(|id| {
+                        self.allows(id) && domain.is_none_or(|d| id.split('.').next() == Some(d))
+                    })
+            })
+            .map(summary)
+            .collect();
+        Ok(serde_json::json!({
+            "count": entities.len(),
+            "entities": entities,
+        }))
+    }
+
+    async fn get(
+        &self,
+        params: &serde_json::Value,
+        ctx: &JobContext,
+    ) -> Result<serde_json::Value, ToolError> {
+        let entity_id = self.entity(require_str(params, "entity_id")?)?;
+        let state = self
+            .request(
+                ctx,
+                Method::GET,
+                &format!("/api/states/{}", entity_id),
+                None,
+            )
+            .await?;
+        Ok(serde_json::json!({
+            "entity_id": entity_id,
+            "state": state.get("state"),
+            "attributes": state.get("attributes"),
+            "last_changed": state.get("last_changed"),
+            "last_updated": state.get("last_updated"),
+        }))
+    }
+
+    async fn call_service(
+        &self,
+        params: &serde_json::Value,
+        ctx: &JobContext,
+    ) -> Result<serde_json::Value, ToolError> {
+        let domain = require_str(params, "domain")?.trim();
+        let service = require_str(params, "service")?.trim();
+        if !is_name(domain) || !is_name(service) {
+            return Err(ToolError::InvalidParameters(format!(
+                "invalid service '{}.{}'",
+                domain, service
+            )));
+        }
+        let targets = targets(params)?;
+        for entity_id in &targets {
+            self.entity(entity_id)?;
+        }
+
+        let mut data = match params.get("data") {
+            Some(serde_json::Value::Object(data)) => data.clone(),
+            Some(serde_json::Value::Null) | None => serde_json::Map::new(),
+            Some(_) => {
+                return Err(ToolError::InvalidParameters(
+                    "'data' must be an object".to_string(),
+                ));
+            }
+        };
+        if let Some(key) = TARGET_KEYS.iter().find(|k| data.contains_key(**k)) {
+            return Err(ToolError::InvalidParameters(format!(
+                "set targets with 'entity_id', not data.{}",
+                key
+            )));
+        }
+        data.insert("entity_id".to_string(), serde_json::json!(targets));
+
+        let changed = self
+            .request(
+                ctx,
+                Method::POST,
+                &format!("/api/services/{}/{}", domain, service),
+                Some(serde_json::Value::Object(data)),
+            )
+            .await?;
+        let changed: Vec<serde_json::Value> = changed
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter(|s| {
+                s.get("entity_id")
+                    .and_then(|v| v.as_str())
+                    .is_some_and(|id| self.allows(id))
+            })
+            .map(summary)
+            .collect();
+        Ok(serde_json::json!({
+            "service": format!("{}.{}", domain, service),
+            "entity_ids": targets,
+            "changed": changed,
+        }))
+    }
+}
+
+/// The `entity_id` parameter as a list (a string or an array of strings).
+fn targets(params: &serde_json::Value) -> Result<Vec<String>, ToolError> {
+    let targets: Vec<String> = match params.get("entity_id") {
+        Some(serde_json::Value::String(id)) => vec![id.trim().to_string()],
+        Some(serde_json::Value::Array(ids)) => ids
+            .iter()
+            .map(|v| {
+                v.as_str().map(|id| id.trim().to_string()).ok_or_else(|| {
+                    ToolError::InvalidParameters("'entity_id' must be strings".to_string())
+                })
+            })
+            .collect::<Result<_, _>>()?,
+        _ => Vec::new(),
+    };
+    if targets.is_empty() {
+        return Err(ToolError::InvalidParameters(
+            "service calls need 'entity_id' (one ID or a list)".to_string(),
+        ));
+    }
+    Ok(targets)
+}
+
+/// Compact state of an entity for listings.
+fn summary(state: &serde_json::Value) -> serde_json::Value {
+    serde_json::json!({
+        "entity_id": state.get("entity_id"),
+        "name": state.pointer("/attributes/friendly_name"),
+        "state": state.get("state"),
+        "unit": state.pointer("/attributes/unit_of_measurement"),
+        "last_changed": state.get("last_changed"),
+    })
+}
+
+/// A domain, service, or object ID: lowercase letters, digits, underscores.
+fn is_name(s: &str) -> bool {
+    !s.is_empty()
+        && s.chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_')
+}
+
+fn is_entity_id(s: &str) -> bool {
+    s.split_once('.')
+        .is_some_and(|(domain, object)| is_name(domain) && is_name(object))
+}
+
+#[async_trait]
+impl Tool for HomeAssistantTool {
+    fn name(&self) -> &str {
+        "home_assistant"
+    }
+
+    fn description(&self) -> &str {
+        "Smart home control through Home Assistant: list entity states (lights, sensors, \
+         media players, ...), read one entity in detail, or call a service such as \
+         light.turn_on or media_player.volume_set on specific entities. Only entities the \
+         user has allowed are available; lock and alarm actions require approval. Device \
+         nicknames may be noted in TOOLS.md."
+    }
+
+    fn parameters_schema(&self) -> serde_json::Value {
+        serde_json::json!({
+            "type": "object",
+            "properties": {
+                "action": {
+                    "type": "string",
+                    "enum": ["states", "get", "call_service"],
+                    "description": "What to do"
+                },
+                "domain": {
+                    "type": "string",
+                    "description": "Service domain such as 'light' (call_service), or a domain to filter by (states)"
+                },
+                "service": {
+                    "type": "string",
+                    "description": "Service name such as 'turn_on' (call_service)"
+                },
+                "entity_id": {
+                    "description": "Entity ID such as 'light.kitchen', or a list of them (get, call_service)",
+                    "oneOf": [
+                        { "type": "string" },
+                        { "type": "array", "items": { "type": "string" } }
+                    ]
+                },
+                "data": {
+                    "type": "object",
+                    "description": "Extra service data, e.g. {\"brightness_pct\": 40} (call_service)"
+                }
+            },
+            "required": ["action"]
+        })
+    }
+
+    async fn execute(
+        &self,
+        params: serde_json::Value,
+        ctx: &JobContext,
+    ) -> Result<ToolOutput, ToolError> {
+        let start = std::time::Instant::now();
+        let result = match require_str(&params, "action")? {
+            "states" => self.states(&params, ctx).await?,
+            "get" => self.get(&params, ctx).await?,
+            "call_service" => self.call_service(&params, ctx).await?,
+            other => {
+                return Err(ToolError::InvalidParameters(format!(
+                    "unknown action '{}'",
+                    other
+                )));
+            }
+        };
+        Ok(ToolOutput::success(result, start.elapsed()))
+    }
+
+    fn requires_sanitization(&self) -> bool {
+        true // Entity names and attributes come from devices
+    }
+
+    fn requires_approval_for(&self, params: &serde_json::Value) -> bool {
+        if params.get("action").and_then(|v| v.as_str()) != Some("call_service") {
+            return false;
+        }
+        let sensitive = |domain: &str| SENSITIVE_DOMAINS.contains(&domain);
+        let domain = params.get("domain").and_then(|v| v.as_str()).unwrap_or("");
+        sensitive(domain.trim())
+            || targets(params).is_ok_and(|targets| {
+                targets
+                    .iter()
+                    .any(|id| sensitive(id.split('.').next().unwrap_or("")))
+            })
+    }
+
+    fn is_external_action(&self) -> bool {
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ha(entities: &[&str]) -> HomeAssistantTool {
+        let config = HomeAssistantConfig {
+            url: Some("http://ha.local:8123".to_string()),
+            token: None,
+            entities: entities.iter().map(|e| e.to_string()).collect(),
+        };
+        HomeAssistantTool::new("http://ha.local:8123", &config)
+    }
+
+    #[test]
+    fn test_allowlist() {
+        let tool = ha(&["light", "media_player.*", "lock.front_door"]);
+        assert!(tool.allows("light.kitchen"));
+        assert!(tool.allows("media_player.living_room"));
+        assert!(tool.allows("lock.front_door"));
+        assert!(!tool.allows("lock.back_door"));
+        assert!(!tool.allows("switch.kettle"));
+
+        assert!(tool.entity("light.kitchen").is_ok());
+        assert!(matches!(
+            tool.entity("switch.kettle"),
+            Err(ToolError::NotAuthorized(_))
+        ));
+        assert!(matches!(
+            tool.entity("light/../../api/config"),
+            Err(ToolError::InvalidParameters(_))
+        ));
+        assert!(ha(&["*"]).allows("switch.kettle"));
+    }
+
+    #[test]
+    fn test_locks_and_alarms_need_approval() {
+        let tool = ha(&["*"]);
+        let call = |domain: &str, entity: serde_json::Value| {
+            serde_json::json!({
+                "action": "call_service", "domain": domain, "service": "turn_on",
+                "entity_id": entity,
+            })
+        };
+        assert!(!tool.requires_approval_for(&call("light", "light.kitchen".into())));
+        assert!(tool.requires_approval_for(&call("lock", "lock.front_door".into())));
+        assert!(tool.requires_approval_for(&call(
+            "alarm_control_panel",
+            "alarm_control_panel.home".into()
+        )));
+        // Generic services targeting a lock count too.
+        assert!(tool.requires_approval_for(&call(
+            "homeassistant",
+            serde_json::json!(["light.hall", "lock.front_door"])
+        )));
+        assert!(!tool.requires_approval_for(&serde_json::json!({
+            "action": "get", "entity_id": "lock.front_door"
+        })));
+    }
+
+    #[test]
+    fn test_service_calls_need_explicit_targets() {
+        assert!(targets(&serde_json::json!({})).is_err());
+        assert!(targets(&serde_json::json!({ "entity_id": [] })).is_err());
+        assert_eq!(
+            targets(&serde_json::json!({ "entity_id": ["light.a", "light.b"] })).unwrap(),
+            vec!["light.a", "light.b"]
+        );
+    }
+}
@@ -2,6 +2,7 @@
 
 use std::collections::HashMap;
 use std::net::{IpAddr, ToSocketAddrs};
+use std::sync::Arc;
 use std::time::Duration;
 
 use async_trait::async_trait;
@@ -9,6 +10,8 @@ use reqwest::Client;
 
 use crate::context::JobContext;
 use crate::safety::LeakDetector;
+use crate::secrets::SecretsStore;
+use crate::secrets::inject::{self, InjectTarget, ResolvedSecrets, SecretBindings};
 use crate::tools::tool::{Tool, ToolError, ToolOutput, require_str};
 
 /// Maximum response body size (5 MB). Prevents OOM from unbounded responses.
@@ -17,6 +20,10 @@ const MAX_RESPONSE_SIZE: usize = 5 * 1024 * 1024;
 /// Tool for making HTTP requests.
 pub struct HttpTool {
     client: Client,
+    /// Store used to resolve `{{secret:NAME}}` placeholders in headers.
+    secrets: Option<Arc<dyn SecretsStore + Send + Sync>>,
+    /// Hosts each secret may be sent to.
+    bindings: SecretBindings,
 }
 
 impl HttpTool {
@@ -28,11 +35,61 @@ impl HttpTool {
             .build()
             .expect("Failed to create HTTP client");
 
-        Self { client }
+        Self {
+            client,
+            secrets: None,
+            bindings: SecretBindings::default(),
+        }
+    }
+
+    /// Resolve `{{secret:NAME}}` placeholders in header values from this
+    /// store, for requests to the hosts each secret is bound to.
+    pub fn with_secrets(
+        mut self,
+        secrets: Arc<dyn SecretsStore + Send + Sync>,
+        bindings: SecretBindings,
+    ) -> Self {
+        self.secrets = Some(secrets);
+        self.bindings = bindings;
+        self
+    }
+
+    /// Swap secret placeholders in header values for the decrypted secrets.
+    async fn resolve_header_secrets(
+        &self,
+        user_id: &str,
+        host: &str,
+        headers: HashMap<String, String>,
+        secrets: &mut ResolvedSecrets,
+    ) -> Result<HashMap<String, String>, ToolError> {
+        let mut resolved = HashMap::with_capacity(headers.len());
+        for (key, value) in headers {
+            if !inject::has_placeholders(&value) {
+                resolved.insert(key, value);
+                continue;
+            }
+            let store = self.secrets.as_ref().ok_or_else(|| {
+                ToolError::ExecutionFailed(
+                    "header references a secret but no secrets store is configured".to_string(),
+                )
+            })?;
+            let value = secrets
+                .resolve(
+                    store.as_ref(),
+                    &self.bindings,
+                    InjectTarget::Host(host),
+                    user_id,
+                    &value,
+                )
+                .await
+                .map_err(|e| ToolError::InvalidParameters(format!("header '{}': {}", key, e)))?;
+            resolved.insert(key, value);
+        }
+        Ok(resolved)
     }
 }
 
-fn validate_url(url: &str) -> Result<reqwest::Url, ToolError> {
+pub(super) fn validate_url(url: &str) -> Result<reqwest::Url, ToolError> {
     let parsed = reqwest::Url::parse(url)
         .map_err(|e| ToolError::InvalidParameters(format!("invalid URL: {}", e)))?;
 
@@ -133,7 +190,7 @@ impl Tool for HttpTool {
                 "headers": {
                     "type": "object",
                     "additionalProperties": { "type": "string" },
-                    "description": "HTTP headers to include"
+                    "description": "HTTP headers to include. Reference stored credentials as {{secret:NAME}} instead of pasting their values."
                 },
                 "body": {
                     "description": "Request body (for POST/PUT/PATCH)"
@@ -141,6 +198,10 @@ impl Tool for HttpTool {
                 "timeout_secs": {
                     "type": "integer",
                     "description": "Request timeout in seconds (default: 30)"
+                },
+                "stash_as": {
+                    "type": "string",
+                    "description": "Put the response body in the scratch buffer under this key instead of returning it"
                 }
             },
             "required": ["method", "url"]
@@ -150,7 +211,7 @@ impl Tool for HttpTool {
     async fn execute(
         &self,
         params: serde_json::Value,
-        _ctx: &JobContext,
+        ctx: &JobContext,
     ) -> Result<ToolOutput, ToolError> {
         let start = std::time::Instant::now();
 
@@ -184,11 +245,6 @@ impl Tool for HttpTool {
             }
         };
 
-        // Add headers
-        for (key, value) in headers {
-            request = request.header(&key, &value);
-        }
-
         // Add body if present
         let body_bytes = if let Some(body) = params.get("body") {
             let bytes = serde_json::to_vec(body)
@@ -205,6 +261,21 @@ impl Tool for HttpTool {
             .scan_http_request(parsed_url.as_str(), &headers_vec, body_bytes.as_deref())
             .map_err(|e| ToolError::NotAuthorized(format!("{}", e)))?;
 
+        // Add headers. Secrets are resolved only after leak detection so the
+        // scan sees placeholders rather than flagging our own credentials.
+        let mut secrets = ResolvedSecrets::default();
+        for (key, value) in self
+            .resolve_header_secrets(
+                &ctx.user_id,
+                parsed_url.host_str().unwrap_or_default(),
+                headers,
+                &mut secrets,
+            )
+            .await?
+        {
+            request = request.header(&key, &value);
+        }
+
         // Execute request
         let response = request.send().await.map_err(|e| {
             if e.is_timeout() {
@@ -227,7 +298,7 @@ impl Tool for HttpTool {
         let headers: HashMap<String, String> = response
             .headers()
             .iter()
-            .filter_map(|(k, v)| v.to_str().ok().map(|v| (k.to_string(), v.to_string())))
+            .filter_map(|(k, v)| v.to_str().ok().map(|v| (k.to_string(), secrets.mask(v))))
             .collect();
 
         // Get response body with size cap to prevent OOM
@@ -243,7 +314,22 @@ impl Tool for HttpTool {
             )));
         }
 
-        let body_text = String::from_utf8_lossy(&body_bytes).into_owned();
+        // An echoing endpoint would hand the resolved secrets back.
+        let body_text = secrets.mask(&String::from_utf8_lossy(&body_bytes));
+
+        if let Some(key) = params.get("stash_as").and_then(|v| v.as_str()) {
+            let bytes = body_text.len();
+            ctx.scratch
+                .stash(key, body_text)
+                .map_err(|e| ToolError::ExecutionFailed(e.to_string()))?;
+            let result = serde_json::json!({
+                "status": status,
+                "headers": headers,
+                "stashed_as": key,
+                "bytes": bytes,
+            });
+            return Ok(ToolOutput::success(result, start.elapsed()));
+        }
 
         // Try to parse as JSON, fall back to string
         let body: serde_json::Value = serde_json::from_str(&body_text)
@@ -269,6 +355,10 @@ impl Tool for HttpTool {
     fn requires_approval(&self) -> bool {
         true // HTTP requests go to external services, require user approval
     }
+
+    fn is_external_action(&self) -> bool {
+        true
+    }
 }
 
 #[cfg(test)]
@@ -11,14 +11,41 @@
 //!
 //! Use `memory_write` to persist important facts that should be remembered
 //! across sessions.
+//!
+//! Use `memory_edit` to rewrite a document, one of its sections, or its
+//! path; it returns a diff of the change.
+//!
+//! Use `memory_mkdir` to lay out directories before writing into them.
+//!
+//! Use `memory_lint` to find broken frontmatter, dangling links, and other
+//! mistakes in workspace files.
+//!
+//! Use `memory_pin` for the few things that must be in every prompt, whether
+//! or not a search would surface them.
+//!
+//! Use `persona` to switch between the persona variants of SOUL.md and
+//! IDENTITY.md for a channel.
 
+use std::collections::HashMap;
 use std::sync::Arc;
 
 use async_trait::async_trait;
+use chrono::{DateTime, NaiveDate, NaiveTime, Utc};
+use uuid::Uuid;
 
 use crate::context::JobContext;
+use crate::error::WorkspaceError;
 use crate::tools::tool::{Tool, ToolError, ToolOutput, require_str};
-use crate::workspace::{Workspace, paths};
+use crate::workspace::conflicts::{ConflictChecker, MemoryConflict};
+use crate::workspace::diff::unified_diff;
+use crate::workspace::frontmatter::stored_frontmatter;
+use crate::workspace::graph::{GraphFact, KnowledgeGraph};
+use crate::workspace::provenance::citation_ref;
+use crate::workspace::tags::{TAGS_KEY, normalize_tag};
+use crate::workspace::{
+    ChunkAggregation, DocumentSummary, FieldFilter, MemoryDocument, PinTarget, Provenance,
+    SearchConfig, SearchModes, Workspace, paths,
+};
 
 /// Identity files that the LLM must not overwrite via tool calls.
 /// These are loaded into the system prompt and could be used for prompt
@@ -26,6 +53,22 @@ use crate::workspace::{Workspace, paths};
 const PROTECTED_IDENTITY_FILES: &[&str] =
     &[paths::IDENTITY, paths::SOUL, paths::AGENTS, paths::USER];
 
+/// Check that the job may touch `path`: the user's workspace permissions,
+/// and in shared sessions (group chats) the user's private memory is off
+/// limits entirely.
+fn check_access(workspace: &Workspace, ctx: &JobContext, path: &str) -> Result<(), ToolError> {
+    workspace
+        .check_access(&ctx.user_id, path)
+        .map_err(|e| ToolError::NotAuthorized(e.to_string()))?;
+    if ctx.is_shared() && paths::is_private(path) {
+        return Err(ToolError::NotAuthorized(format!(
+            "{} is private to the user and not available in a shared conversation",
+            path
+        )));
+    }
+    Ok(())
+}
+
 /// Tool for searching workspace memory.
 ///
 /// Performs hybrid search (FTS + semantic) across all memory documents.
@@ -40,6 +83,67 @@ impl MemorySearchTool {
     pub fn new(workspace: Arc<Workspace>) -> Self {
         Self { workspace }
     }
+
+    /// `granularity: documents`: one result per document with its best
+    /// snippets.
+    async fn search_documents(
+        &self,
+        ctx: &JobContext,
+        query: &str,
+        config: SearchConfig,
+        aggregation: ChunkAggregation,
+        modes: SearchModes,
+        start: std::time::Instant,
+    ) -> Result<ToolOutput, ToolError> {
+        let documents = self
+            .workspace
+            .search_documents_as(&ctx.user_id, query, config, aggregation)
+            .await
+            .map_err(|e| ToolError::ExecutionFailed(format!("Search failed: {}", e)))?;
+
+        let today = Utc::now().date_naive();
+        let mut results = Vec::with_capacity(documents.len());
+        for found in &documents {
+            let Ok(doc) = self.workspace.document_by_id(found.document_id).await else {
+                continue;
+            };
+            if ctx.is_shared() && doc.is_private() {
+                continue;
+            }
+            let mut result = serde_json::json!({
+                "path": doc.path,
+                "score": found.score,
+                "document_id": found.document_id.to_string(),
+                "matching_chunks": found.chunks.len(),
+                "snippets": found
+                    .chunks
+                    .iter()
+                    .take(DOCUMENT_SNIPPETS)
+                    .map(|c| snippet(&c.content, query))
+                    .collect::<Vec<_>>(),
+                "matched_by": found.matched_by(),
+            });
+            if let Some(best) = found.chunks.first() {
+                result["source"] = source(&doc, best.chunk_id, &best.content, today);
+            }
+            if let Some(summary) = DocumentSummary::of(&doc) {
+                result["summary"] = serde_json::Value::from(summary.text);
+            }
+            results.push(result);
+        }
+
+        let mut output = serde_json::json!({
+            "query": query,
+            "granularity": "documents",
+            "results": results,
+            "result_count": results.len(),
+            "search_modes": modes.label(),
+        });
+        if let Some(note) = modes.note() {
+            output["search_note"] = serde_json::Value::from(note);
+        }
+        Ok(ToolOutput::success(output, start.elapsed()))
+    }
 }
 
 #[async_trait]
@@ -51,7 +155,13 @@ impl Tool for MemorySearchTool {
     fn description(&self) -> &str {
         "Search past memories, decisions, and context. MUST be called before answering \
          questions about prior work, decisions, dates, people, preferences, or todos. \
-         Returns relevant snippets with relevance scores."
+         Returns matching chunks with their paths and relevance scores. Narrow the search \
+         with 'path_prefix', 'updated_after'/'updated_before', 'tags', or 'filters' on \
+         frontmatter fields (e.g. {\"status\": \"active\"}); with an empty query, lists \
+         every matching document instead. Use mode 'exact' for names, IDs, and quoted \
+         phrases, 'semantic' for loosely worded questions. Each result has a 'source' \
+         with a 'ref'; when your answer relies on a result, cite it by writing the ref \
+         in brackets after the statement, e.g. [mem:3f2a9c]."
     }
 
     fn parameters_schema(&self) -> serde_json::Value {
@@ -68,6 +178,52 @@ impl Tool for MemorySearchTool {
                     "default": 5,
                     "minimum": 1,
                     "maximum": 20
+                },
+                "filters": {
+                    "type": "object",
+                    "description": "Frontmatter fields documents must match, e.g. {\"status\": \"active\", \"tags\": \"work\"}. A list field matches if it contains the value; null only requires the field to exist.",
+                    "additionalProperties": { "type": ["string", "null"] }
+                },
+                "path_prefix": {
+                    "type": "string",
+                    "description": "Only search this document or directory, e.g. 'projects/alpha' or 'daily'"
+                },
+                "updated_after": {
+                    "type": "string",
+                    "description": "Only search documents updated on or after this date (YYYY-MM-DD or RFC 3339)"
+                },
+                "updated_before": {
+                    "type": "string",
+                    "description": "Only search documents updated before this date (YYYY-MM-DD or RFC 3339)"
+                },
+                "tags": {
+                    "type": "array",
+                    "items": { "type": "string" },
+                    "description": "Only search documents whose frontmatter 'tags' include all of these"
+                },
+                "mode": {
+                    "type": "string",
+                    "enum": ["hybrid", "exact", "semantic"],
+                    "description": "'exact' matches keywords only, 'semantic' matches meaning only (needs embeddings), 'hybrid' combines both",
+                    "default": "hybrid"
+                },
+                "format": {
+                    "type": "string",
+                    "enum": ["chunks", "snippets"],
+                    "description": "'chunks' returns each matching chunk in full, 'snippets' a short excerpt around the match",
+                    "default": "chunks"
+                },
+                "granularity": {
+                    "type": "string",
+                    "enum": ["chunks", "documents"],
+                    "description": "'documents' returns each matching document once with its best snippets, for questions like 'which files mention X'",
+                    "default": "chunks"
+                },
+                "aggregate": {
+                    "type": "string",
+                    "enum": ["max", "sum"],
+                    "description": "With granularity 'documents': score a document by its best chunk ('max') or by all matching chunks with diminishing weight ('sum')",
+                    "default": "max"
                 }
             },
             "required": ["query"]
@@ -77,7 +233,7 @@ impl Tool for MemorySearchTool {
     async fn execute(
         &self,
         params: serde_json::Value,
-        _ctx: &JobContext,
+        ctx: &JobContext,
     ) -> Result<ToolOutput, ToolError> {
         let start = std::time::Instant::now();
 
@@ -89,22 +245,207 @@ impl Tool for MemorySearchTool {
             .unwrap_or(5)
             .min(20) as usize;
 
+        let mut filters: Vec<FieldFilter> = match params.get("filters") {
+            Some(serde_json::Value::Object(map)) => map
+                .iter()
+                .map(|(key, value)| match value {
+                    serde_json::Value::Null => FieldFilter::exists(key.as_str()),
+                    serde_json::Value::String(v) => FieldFilter::equals(key.as_str(), v.as_str()),
+                    other => FieldFilter::equals(key.as_str(), other.to_string()),
+                })
+                .collect(),
+            Some(serde_json::Value::Null) | None => Vec::new(),
+            Some(_) => {
+                return Err(ToolError::InvalidParameters(
+                    "filters must be an object of field names to values".to_string(),
+                ));
+            }
+        };
+        match params.get("tags") {
+            Some(serde_json::Value::Array(tags)) => {
+                for tag in tags {
+                    let tag = tag.as_str().ok_or_else(|| {
+                        ToolError::InvalidParameters("tags must be strings".to_string())
+                    })?;
+                    let tag = normalize_tag(tag).ok_or_else(|| {
+                        ToolError::InvalidParameters(format!("invalid tag '{}'", tag))
+                    })?;
+                    filters.push(FieldFilter::equals(TAGS_KEY, tag));
+                }
+            }
+            Some(serde_json::Value::Null) | None => {}
+            Some(_) => {
+                return Err(ToolError::InvalidParameters(
+                    "tags must be an array of strings".to_string(),
+                ));
+            }
+        }
+
+        let str_param = |name: &str| {
+            params
+                .get(name)
+                .and_then(|v| v.as_str())
+                .map(str::trim)
+                .filter(|s| !s.is_empty())
+        };
+        let path_prefix = str_param("path_prefix").map(|p| p.trim_matches('/').to_string());
+        if let Some(prefix) = &path_prefix {
+            check_access(&self.workspace, ctx, prefix)?;
+        }
+        let updated_after = str_param("updated_after")
+            .map(|v| parse_time(v, "updated_after"))
+            .transpose()?;
+        let updated_before = str_param("updated_before")
+            .map(|v| parse_time(v, "updated_before"))
+            .transpose()?;
+        let snippets = match str_param("format").unwrap_or("chunks") {
+            "chunks" => false,
+            "snippets" => true,
+            other => {
+                return Err(ToolError::InvalidParameters(format!(
+                    "unknown format '{}'",
+                    other
+                )));
+            }
+        };
+
+        let aggregation = match str_param("granularity").unwrap_or("chunks") {
+            "chunks" => None,
+            "documents" => {
+                let name = str_param("aggregate").unwrap_or("max");
+                Some(ChunkAggregation::parse(name).ok_or_else(|| {
+                    ToolError::InvalidParameters(format!("unknown aggregate '{}'", name))
+                })?)
+            }
+            other => {
+                return Err(ToolError::InvalidParameters(format!(
+                    "unknown granularity '{}'",
+                    other
+                )));
+            }
+        };
+
+        if query.trim().is_empty() {
+            if filters.is_empty()
+                && path_prefix.is_none()
+                && updated_after.is_none()
+                && updated_before.is_none()
+            {
+                return Err(ToolError::InvalidParameters(
+                    "query is empty; provide a query or filters".to_string(),
+                ));
+            }
+            let docs = self
+                .workspace
+                .find_by_frontmatter(&filters)
+                .await
+                .map_err(|e| ToolError::ExecutionFailed(format!("Search failed: {}", e)))?;
+            let docs: Vec<_> = docs
+                .into_iter()
+                .filter(|d| {
+                    path_prefix.as_deref().is_none_or(|prefix| {
+                        d.path == prefix || d.path.starts_with(&format!("{}/", prefix))
+                    })
+                })
+                .filter(|d| updated_after.is_none_or(|t| d.updated_at >= t))
+                .filter(|d| updated_before.is_none_or(|t| d.updated_at < t))
+                .filter(|d| check_access(&self.workspace, ctx, &d.path).is_ok())
+                .filter(|d| !(ctx.is_shared() && d.is_private()))
+                .collect();
+            let output = serde_json::json!({
+                "filters": filters,
+                "documents": docs.iter().map(|d| serde_json::json!({
+                    "path": d.path,
+                    "frontmatter": stored_frontmatter(d),
+                    "updated_at": d.updated_at.to_rfc3339(),
+                })).collect::<Vec<_>>(),
+                "document_count": docs.len(),
+            });
+            return Ok(ToolOutput::success(output, start.elapsed()));
+        }
+
+        let mut config = SearchConfig::default()
+            .with_limit(limit)
+            .with_updated_between(updated_after, updated_before);
+        if let Some(prefix) = &path_prefix {
+            config = config.with_path_prefix(prefix.as_str());
+        }
+        config.filters = filters;
+        config = match str_param("mode").unwrap_or("hybrid") {
+            "hybrid" => config,
+            "exact" => config.fts_only(),
+            "semantic" if self.workspace.has_embeddings() => config.vector_only(),
+            "semantic" => {
+                return Err(ToolError::InvalidParameters(
+                    "semantic mode needs an embedding provider; use 'exact' or 'hybrid'"
+                        .to_string(),
+                ));
+            }
+            other => {
+                return Err(ToolError::InvalidParameters(format!(
+                    "unknown mode '{}'",
+                    other
+                )));
+            }
+        };
+        let modes = self.workspace.search_modes(&config);
+        if let Some(aggregation) = aggregation {
+            return self
+                .search_documents(ctx, query, config, aggregation, modes, start)
+                .await;
+        }
         let results = self
             .workspace
-            .search(query, limit)
+            .search_as_with_config(&ctx.user_id, query, config)
             .await
             .map_err(|e| ToolError::ExecutionFailed(format!("Search failed: {}", e)))?;
 
-        let output = serde_json::json!({
-            "query": query,
-            "results": results.iter().map(|r| serde_json::json!({
-                "content": r.content,
+        // Each result's document, fetched once. Its summary is given only
+        // with its first result.
+        let mut documents: HashMap<Uuid, Option<MemoryDocument>> = HashMap::new();
+        let today = Utc::now().date_naive();
+        let mut matches = Vec::with_capacity(results.len());
+        for r in &results {
+            let first = !documents.contains_key(&r.document_id);
+            if first {
+                let doc = self.workspace.document_by_id(r.document_id).await.ok();
+                documents.insert(r.document_id, doc);
+            }
+            let Some(Some(doc)) = documents.get(&r.document_id) else {
+                continue;
+            };
+            if ctx.is_shared() && doc.is_private() {
+                continue;
+            }
+            let content = if snippets {
+                snippet(&r.content, query)
+            } else {
+                r.content.clone()
+            };
+            let mut result = serde_json::json!({
+                "path": doc.path,
+                "content": content,
                 "score": r.score,
                 "document_id": r.document_id.to_string(),
                 "is_hybrid_match": r.is_hybrid(),
-            })).collect::<Vec<_>>(),
+                "matched_by": r.matched_by(),
+                "source": source(doc, r.chunk_id, &r.content, today),
+            });
+            if first && let Some(summary) = DocumentSummary::of(doc) {
+                result["document_summary"] = serde_json::Value::from(summary.text);
+            }
+            matches.push(result);
+        }
+
+        let mut output = serde_json::json!({
+            "query": query,
+            "results": matches,
             "result_count": results.len(),
+            "search_modes": modes.label(),
         });
+        if let Some(note) = modes.note() {
+            output["search_note"] = serde_json::Value::from(note);
+        }
 
         Ok(ToolOutput::success(output, start.elapsed()))
     }
@@ -114,21 +455,131 @@ impl Tool for MemorySearchTool {
     }
 }
 
+/// Where the chunk with `chunk_id` and `content` sits in `doc`, with the ref
+/// the agent cites it by.
+fn source(
+    doc: &MemoryDocument,
+    chunk_id: Uuid,
+    content: &str,
+    today: NaiveDate,
+) -> serde_json::Value {
+    let provenance = Provenance::of(doc, content);
+    let mut source = serde_json::to_value(&provenance).unwrap_or_default();
+    source["ref"] = serde_json::Value::from(citation_ref(chunk_id));
+    source["citation"] = serde_json::Value::from(provenance.citation(today));
+    source
+}
+
+/// Longest excerpt returned in snippet format, in characters.
+const SNIPPET_CHARS: usize = 240;
+
+/// Snippets returned per document in document-level search.
+const DOCUMENT_SNIPPETS: usize = 2;
+
+/// A date (`YYYY-MM-DD`, midnight UTC) or RFC 3339 timestamp.
+fn parse_time(value: &str, name: &str) -> Result<DateTime<Utc>, ToolError> {
+    if let Ok(date) = NaiveDate::parse_from_str(value, "%Y-%m-%d") {
+        return Ok(date.and_time(NaiveTime::MIN).and_utc());
+    }
+    DateTime::parse_from_rfc3339(value)
+        .map(|t| t.with_timezone(&Utc))
+        .map_err(|_| {
+            ToolError::InvalidParameters(format!(
+                "{} must be a date (YYYY-MM-DD) or RFC 3339 timestamp",
+                name
+            ))
+        })
+}
+
+/// An excerpt of `content` around the first word of `query` it contains,
+/// or its beginning if none.
+fn snippet(content: &str, query: &str) -> String {
+    let chars: Vec<char> = content.chars().collect();
+    if chars.len() <= SNIPPET_CHARS {
+        return content.to_string();
+    }
+    let fold = |c: &char| c.to_lowercase().next().unwrap_or(*c);
+    let folded: Vec<char> = chars.iter().map(fold).collect();
+    let hit = query
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| word.chars().count() >= 3)
+        .find_map(|word| {
+            let word: Vec<char> = word.chars().map(|c| fold(&c)).collect();
+            folded
+                .windows(word.len())
+                .position(|w| w == word.as_slice())
+        })
+        .unwrap_or(0);
+    let begin = hit
+        .saturating_sub(SNIPPET_CHARS / 3)
+        .min(chars.len() - SNIPPET_CHARS);
+    let end = begin + SNIPPET_CHARS;
+    let mut excerpt: String = chars[begin..end].iter().collect();
+    excerpt = excerpt.trim().to_string();
+    if begin > 0 {
+        excerpt.insert(0, '…');
+    }
+    if end < chars.len() {
+        excerpt.push('…');
+    }
+    excerpt
+}
+
 /// Tool for writing to workspace memory.
 ///
 /// Use this to persist important information that should be remembered
 /// across sessions: decisions, preferences, facts, lessons learned.
 pub struct MemoryWriteTool {
     workspace: Arc<Workspace>,
+    conflicts: Option<Arc<ConflictChecker>>,
 }
 
 impl MemoryWriteTool {
     /// Create a new memory write tool.
     pub fn new(workspace: Arc<Workspace>) -> Self {
-        Self { workspace }
+        Self {
+            workspace,
+            conflicts: None,
+        }
+    }
+
+    /// Check entries appended to MEMORY.md against existing ones and report
+    /// those they may contradict.
+    pub fn with_conflict_checker(mut self, checker: Arc<ConflictChecker>) -> Self {
+        self.conflicts = Some(checker);
+        self
+    }
+
+    /// Existing MEMORY.md entries `entry` may contradict. A failed check
+    /// doesn't fail the write.
+    async fn memory_conflicts(&self, entry: &str) -> Vec<MemoryConflict> {
+        let Some(checker) = &self.conflicts else {
+            return Vec::new();
+        };
+        match checker.check(&self.workspace, entry).await {
+            Ok(conflicts) => conflicts,
+            Err(e) => {
+                tracing::warn!("MEMORY.md conflict check failed: {}", e);
+                Vec::new()
+            }
+        }
     }
 }
 
+/// Attach possible conflicts to a write result, asking the agent to settle
+/// them.
+fn report_conflicts(output: &mut serde_json::Value, conflicts: Vec<MemoryConflict>) {
+    if conflicts.is_empty() {
+        return;
+    }
+    output["possible_conflicts"] = serde_json::json!(conflicts);
+    output["reconcile"] = serde_json::Value::from(
+        "MEMORY.md already has entries that may contradict this one. If the new entry \
+         replaces one, remove or update the old entry with memory_edit; if both hold, \
+         leave them.",
+    );
+}
+
 #[async_trait]
 impl Tool for MemoryWriteTool {
     fn name(&self) -> &str {
@@ -140,7 +591,8 @@ impl Tool for MemoryWriteTool {
          Use for important facts, decisions, preferences, or lessons learned that should \
          be remembered across sessions. Targets: 'memory' for curated long-term facts, \
          'daily_log' for timestamped session notes, 'heartbeat' for the periodic \
-         checklist (HEARTBEAT.md), or provide a custom path for arbitrary file creation."
+         checklist (HEARTBEAT.md), or provide a custom path for arbitrary file creation. \
+         Link related notes with [[path]] or [[name]]."
     }
 
     fn parameters_schema(&self) -> serde_json::Value {
@@ -149,7 +601,7 @@ impl Tool for MemoryWriteTool {
             "properties": {
                 "content": {
                     "type": "string",
-                    "description": "The content to write to memory. Be concise but include relevant context."
+                    "description": "The content to write to memory. Be concise but include relevant context. Required unless from_stash is set."
                 },
                 "target": {
                     "type": "string",
@@ -160,20 +612,36 @@ impl Tool for MemoryWriteTool {
                     "type": "boolean",
                     "description": "If true, append to existing content. If false, replace entirely.",
                     "default": true
+                },
+                "section": {
+                    "type": "string",
+                    "description": "Append under this markdown heading (e.g. 'Preferences'), creating it if missing. Use with target 'memory' to keep MEMORY.md organized by topic."
+                },
+                "from_stash": {
+                    "type": "string",
+                    "description": "Write the scratch buffer entry with this key instead of 'content' (e.g. a page stashed by read_url)"
                 }
-            },
-            "required": ["content"]
+            }
         })
     }
 
     async fn execute(
         &self,
         params: serde_json::Value,
-        _ctx: &JobContext,
+        ctx: &JobContext,
     ) -> Result<ToolOutput, ToolError> {
         let start = std::time::Instant::now();
 
-        let content = require_str(&params, "content")?;
+        let stashed = match params.get("from_stash").and_then(|v| v.as_str()) {
+            Some(key) => Some(ctx.scratch.get(key).ok_or_else(|| {
+                ToolError::InvalidParameters(format!("nothing stashed under '{}'", key))
+            })?),
+            None => None,
+        };
+        let content = match stashed.as_deref() {
+            Some(content) => content,
+            None => require_str(&params, "content")?,
+        };
 
         if content.trim().is_empty() {
             return Err(ToolError::InvalidParameters(
@@ -196,10 +664,60 @@ impl Tool for MemoryWriteTool {
             )));
         }
 
+        let target_path = match target {
+            "memory" => paths::MEMORY.to_string(),
+            "daily_log" => format!("daily/{}.md", chrono::Utc::now().format("%Y-%m-%d")),
+            "heartbeat" => paths::HEARTBEAT.to_string(),
+            path => path.to_string(),
+        };
+        check_access(&self.workspace, ctx, &target_path)?;
+
         let append = params
             .get("append")
             .and_then(|v| v.as_bool())
             .unwrap_or(true);
+        let before = content_of(&self.workspace, &target_path).await;
+        let conflicts = if append && target_path.trim_start_matches('/') == paths::MEMORY {
+            self.memory_conflicts(content).await
+        } else {
+            Vec::new()
+        };
+
+        // Section appends go through the same path for every target.
+        if let Some(section) = params
+            .get("section")
+            .and_then(|v| v.as_str())
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+        {
+            if !append {
+                return Err(ToolError::InvalidParameters(
+                    "section requires append to be true".to_string(),
+                ));
+            }
+            if is_identity_file(&target_path) {
+                return Err(ToolError::NotAuthorized(format!(
+                    "writing to '{}' is not allowed (identity file protected from tool access)",
+                    target_path
+                )));
+            }
+            self.workspace
+                .append_to_section(&target_path, section, content)
+                .await
+                .map_err(|e| ToolError::ExecutionFailed(format!("Write failed: {}", e)))?;
+
+            let after = content_of(&self.workspace, &target_path).await;
+            let mut output = serde_json::json!({
+                "status": "written",
+                "path": target_path,
+                "append": true,
+                "section": section,
+                "content_length": content.len(),
+                "diff": unified_diff(&before, &after),
+            });
+            report_conflicts(&mut output, conflicts);
+            return Ok(ToolOutput::success(output, start.elapsed()));
+        }
 
         let path = match target {
             "memory" => {
@@ -241,11 +759,7 @@ impl Tool for MemoryWriteTool {
                 // Protect identity files from LLM overwrites (prompt injection defense).
                 // These files are injected into the system prompt, so poisoning them
                 // would let an attacker rewrite the agent's core instructions.
-                let normalized = path.trim_start_matches('/');
-                if PROTECTED_IDENTITY_FILES
-                    .iter()
-                    .any(|p| normalized.eq_ignore_ascii_case(p))
-                {
+                if is_identity_file(path) {
                     return Err(ToolError::NotAuthorized(format!(
                         "writing to '{}' is not allowed (identity file protected from tool access)",
                         path
@@ -267,16 +781,204 @@ impl Tool for MemoryWriteTool {
             }
         };
 
-        let output = serde_json::json!({
+        let after = content_of(&self.workspace, &path).await;
+        let mut output = serde_json::json!({
             "status": "written",
             "path": path,
             "append": append,
             "content_length": content.len(),
+            "diff": unified_diff(&before, &after),
         });
+        report_conflicts(&mut output, conflicts);
+
+        Ok(ToolOutput::success(output, start.elapsed()))
+    }
+
+    fn requires_sanitization(&self) -> bool {
+        false // Internal tool
+    }
+}
+
+/// Current content of `path`, or empty if it doesn't exist yet.
+async fn content_of(workspace: &Workspace, path: &str) -> String {
+    workspace
+        .read(path)
+        .await
+        .map(|doc| doc.content)
+        .unwrap_or_default()
+}
+
+/// Whether `path` is an identity file loaded into every system prompt,
+/// including persona variants and the active persona choice.
+fn is_identity_file(path: &str) -> bool {
+    let path = path.trim().trim_start_matches('/');
+    PROTECTED_IDENTITY_FILES
+        .iter()
+        .any(|p| path.eq_ignore_ascii_case(p))
+        || path
+            .get(..paths::PERSONAS_DIR.len())
+            .is_some_and(|prefix| prefix.eq_ignore_ascii_case(paths::PERSONAS_DIR))
+}
+
+/// Tool for editing workspace documents in place.
+///
+/// Complements `memory_write` with whole-document replacement, section
+/// rewrites, and renames. Every edit returns a diff of what changed.
+/// Identity files can be edited, but only with the user's approval.
+pub struct MemoryEditTool {
+    workspace: Arc<Workspace>,
+}
+
+impl MemoryEditTool {
+    /// Create a new memory edit tool.
+    pub fn new(workspace: Arc<Workspace>) -> Self {
+        Self { workspace }
+    }
+}
+
+#[async_trait]
+impl Tool for MemoryEditTool {
+    fn name(&self) -> &str {
+        "memory_edit"
+    }
+
+    fn description(&self) -> &str {
+        "Edit a workspace document in place: 'write' replaces it, 'append' adds to the \
+         end, 'edit_section' rewrites the body of one markdown section (e.g. fix an \
+         outdated 'Preferences' section in MEMORY.md instead of appending a \
+         contradiction), 'rename' moves it, or a whole directory, to 'new_path' without \
+         re-indexing, 'copy' duplicates it or a directory there (e.g. start a project \
+         from 'templates/project'). Returns a diff of the change. \
+         Editing identity files (SOUL.md, AGENTS.md, IDENTITY.md, USER.md) asks the \
+         user for approval."
+    }
+
+    fn parameters_schema(&self) -> serde_json::Value {
+        serde_json::json!({
+            "type": "object",
+            "properties": {
+                "action": {
+                    "type": "string",
+                    "enum": ["write", "append", "edit_section", "rename", "copy"],
+                    "description": "What to do with the document"
+                },
+                "path": {
+                    "type": "string",
+                    "description": "Document to edit (e.g. 'MEMORY.md', 'projects/alpha/notes.md')"
+                },
+                "content": {
+                    "type": "string",
+                    "description": "New content (for write and append) or new section body (for edit_section)"
+                },
+                "section": {
+                    "type": "string",
+                    "description": "Heading of the section to rewrite (for edit_section); created if missing"
+                },
+                "new_path": {
+                    "type": "string",
+                    "description": "Where to move or copy the document or directory (for rename and copy)"
+                }
+            },
+            "required": ["action", "path"]
+        })
+    }
+
+    async fn execute(
+        &self,
+        params: serde_json::Value,
+        ctx: &JobContext,
+    ) -> Result<ToolOutput, ToolError> {
+        let start = std::time::Instant::now();
+
+        let action = require_str(&params, "action")?;
+        let path = require_str(&params, "path")?;
+        check_access(&self.workspace, ctx, path)?;
+        let failed = |e| ToolError::ExecutionFailed(format!("Edit failed: {}", e));
+
+        if action == "rename" || action == "copy" {
+            let new_path = require_str(&params, "new_path")?;
+            check_access(&self.workspace, ctx, new_path)?;
+            let is_directory = !self.workspace.exists(path).await.map_err(failed)?
+                && self.workspace.is_directory(path).await.map_err(failed)?;
+            let mut output = serde_json::json!({
+                "status": if action == "rename" { "renamed" } else { "copied" },
+                "path": path,
+                "new_path": new_path,
+            });
+            match (action, is_directory) {
+                ("rename", true) => {
+                    let moved = self
+                        .workspace
+                        .rename_dir(path, new_path)
+                        .await
+                        .map_err(failed)?;
+                    output["documents_moved"] = moved.into();
+                }
+                ("rename", false) => {
+                    self.workspace
+                        .rename(path, new_path)
+                        .await
+                        .map_err(failed)?;
+                }
+                (_, true) => {
+                    let copied = self
+                        .workspace
+                        .copy_dir(path, new_path)
+                        .await
+                        .map_err(failed)?;
+                    output["documents_copied"] = copied.into();
+                }
+                (_, false) => {
+                    self.workspace.copy(path, new_path).await.map_err(failed)?;
+                }
+            }
+            return Ok(ToolOutput::success(output, start.elapsed()));
+        }
 
+        let content = require_str(&params, "content")?;
+        let before = content_of(&self.workspace, path).await;
+        match action {
+            "write" => {
+                self.workspace.write(path, content).await.map_err(failed)?;
+            }
+            "append" => {
+                self.workspace.append(path, content).await.map_err(failed)?;
+            }
+            "edit_section" => {
+                let section = require_str(&params, "section")?;
+                self.workspace
+                    .replace_section(path, section, content)
+                    .await
+                    .map_err(failed)?;
+            }
+            other => {
+                return Err(ToolError::InvalidParameters(format!(
+                    "unknown action '{}'",
+                    other
+                )));
+            }
+        }
+        let after = content_of(&self.workspace, path).await;
+        let diff = unified_diff(&before, &after);
+
+        let output = serde_json::json!({
+            "status": if diff.is_empty() { "unchanged" } else { "written" },
+            "path": path,
+            "action": action,
+            "diff": diff,
+        });
         Ok(ToolOutput::success(output, start.elapsed()))
     }
 
+    fn requires_approval_for(&self, params: &serde_json::Value) -> bool {
+        ["path", "new_path"].iter().any(|key| {
+            params
+                .get(*key)
+                .and_then(|v| v.as_str())
+                .is_some_and(is_identity_file)
+        })
+    }
+
     fn requires_sanitization(&self) -> bool {
         false // Internal tool
     }
@@ -306,7 +1008,9 @@ impl Tool for MemoryReadTool {
         "Read a file from the workspace memory (database-backed storage). \
          Use this to read files shown by memory_tree. NOT for local filesystem files \
          (use read_file for those). Works with identity files, heartbeat checklist, \
-         memory, daily logs, or any custom workspace path."
+         memory, daily logs, or any custom workspace path. The result lists the \
+         document's [[wiki links]] (with the path each resolves to) and the \
+         documents linking to it, so related notes can be followed."
     }
 
     fn parameters_schema(&self) -> serde_json::Value {
@@ -316,6 +1020,10 @@ impl Tool for MemoryReadTool {
                 "path": {
                     "type": "string",
                     "description": "Path to the file (e.g., 'MEMORY.md', 'daily/2024-01-15.md', 'projects/alpha/notes.md')"
+                },
+                "as_of": {
+                    "type": "string",
+                    "description": "Read the file as it was at this time (YYYY-MM-DD or RFC 3339), e.g. to see what a note said last month"
                 }
             },
             "required": ["path"]
@@ -325,23 +1033,78 @@ impl Tool for MemoryReadTool {
     async fn execute(
         &self,
         params: serde_json::Value,
-        _ctx: &JobContext,
+        ctx: &JobContext,
     ) -> Result<ToolOutput, ToolError> {
         let start = std::time::Instant::now();
 
         let path = require_str(&params, "path")?;
 
-        let doc = self
-            .workspace
-            .read(path)
-            .await
-            .map_err(|e| ToolError::ExecutionFailed(format!("Read failed: {}", e)))?;
+        check_access(&self.workspace, ctx, path)?;
+        let as_of = params
+            .get("as_of")
+            .and_then(|v| v.as_str())
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+            .map(|v| parse_time(v, "as_of"))
+            .transpose()?;
+
+        let doc = match as_of {
+            Some(at) => self.workspace.read_asof(path, at).await,
+            None => self.workspace.read(path).await,
+        }
+        .map_err(|e| ToolError::ExecutionFailed(format!("Read failed: {}", e)))?;
+        if ctx.is_shared() && doc.is_private() {
+            return Err(ToolError::NotAuthorized(format!(
+                "{} is tagged private and not available in a shared conversation",
+                doc.path
+            )));
+        }
+        // Links and backlinks are only known for the current content.
+        if let Some(at) = as_of {
+            let output = serde_json::json!({
+                "path": doc.path,
+                "content": doc.content,
+                "word_count": doc.word_count(),
+                "updated_at": doc.updated_at.to_rfc3339(),
+                "as_of": at.to_rfc3339(),
+            });
+            return Ok(ToolOutput::success(output, start.elapsed()));
+        }
+
+        // Link navigation is best-effort; a failed lookup never fails the read.
+        let links: Vec<_> = match self.workspace.links(&doc.path).await {
+            Ok(links) => links
+                .into_iter()
+                .filter(|l| {
+                    l.path
+                        .as_deref()
+                        .is_none_or(|p| check_access(&self.workspace, ctx, p).is_ok())
+                })
+                .collect(),
+            Err(e) => {
+                tracing::debug!("Failed to resolve links of {}: {}", doc.path, e);
+                Vec::new()
+            }
+        };
+        let backlinks: Vec<String> = match self.workspace.backlinks(&doc.path).await {
+            Ok(backlinks) => backlinks
+                .into_iter()
+                .map(|b| b.path)
+                .filter(|p| check_access(&self.workspace, ctx, p).is_ok())
+                .collect(),
+            Err(e) => {
+                tracing::debug!("Failed to load backlinks of {}: {}", doc.path, e);
+                Vec::new()
+            }
+        };
 
         let output = serde_json::json!({
             "path": doc.path,
             "content": doc.content,
             "word_count": doc.word_count(),
             "updated_at": doc.updated_at.to_rfc3339(),
+            "links": links,
+            "backlinks": backlinks,
         });
 
         Ok(ToolOutput::success(output, start.elapsed()))
@@ -370,6 +1133,7 @@ impl MemoryTreeTool {
     /// Returns a compact format where directories end with `/` and may have children.
     async fn build_tree(
         &self,
+        ctx: &JobContext,
         path: &str,
         current_depth: usize,
         max_depth: usize,
@@ -386,6 +1150,11 @@ impl MemoryTreeTool {
 
         let mut result = Vec::new();
         for entry in entries {
+            // Hide entries the caller isn't permitted to access
+            if check_access(&self.workspace, ctx, &entry.path).is_err() {
+                continue;
+            }
+
             // Directories end with `/`, files don't
             let display_path = if entry.is_directory {
                 format!("{}/", entry.name())
@@ -395,7 +1164,8 @@ impl MemoryTreeTool {
 
             if entry.is_directory && current_depth < max_depth {
                 let children =
-                    Box::pin(self.build_tree(&entry.path, current_depth + 1, max_depth)).await?;
+                    Box::pin(self.build_tree(ctx, &entry.path, current_depth + 1, max_depth))
+                        .await?;
                 if children.is_empty() {
                     result.push(serde_json::Value::String(display_path));
                 } else {
@@ -418,6 +1188,8 @@ impl Tool for MemoryTreeTool {
 
     fn description(&self) -> &str {
         "View the workspace memory structure as a tree (database-backed storage). \
+         With a glob pattern (e.g. 'projects/**/README.md', 'daily/2024-*.md'), \
+         lists the matching file paths instead. \
          Use memory_read to read files shown here, NOT read_file. \
          The workspace is separate from the local filesystem."
     }
@@ -437,6 +1209,10 @@ impl Tool for MemoryTreeTool {
                     "default": 1,
                     "minimum": 1,
                     "maximum": 10
+                },
+                "pattern": {
+                    "type": "string",
+                    "description": "Glob of file paths to list flat instead of a tree: '*' within a directory, '**' across directories, '?', '[a-z]', '{a,b}'"
                 }
             }
         })
@@ -445,10 +1221,31 @@ impl Tool for MemoryTreeTool {
     async fn execute(
         &self,
         params: serde_json::Value,
-        _ctx: &JobContext,
+        ctx: &JobContext,
     ) -> Result<ToolOutput, ToolError> {
         let start = std::time::Instant::now();
 
+        if let Some(pattern) = params.get("pattern").and_then(|v| v.as_str()) {
+            let paths: Vec<serde_json::Value> = self
+                .workspace
+                .glob(pattern)
+                .await
+                .map_err(|e| match e {
+                    WorkspaceError::InvalidPath { .. } => {
+                        ToolError::InvalidParameters(e.to_string())
+                    }
+                    e => ToolError::ExecutionFailed(format!("Glob failed: {}", e)),
+                })?
+                .into_iter()
+                .filter(|p| check_access(&self.workspace, ctx, p).is_ok())
+                .map(serde_json::Value::String)
+                .collect();
+            return Ok(ToolOutput::success(
+                serde_json::Value::Array(paths),
+                start.elapsed(),
+            ));
+        }
+
         let path = params.get("path").and_then(|v| v.as_str()).unwrap_or("");
 
         let depth = params
@@ -457,7 +1254,7 @@ impl Tool for MemoryTreeTool {
             .unwrap_or(1)
             .clamp(1, 10) as usize;
 
-        let tree = self.build_tree(path, 1, depth).await?;
+        let tree = self.build_tree(ctx, path, 1, depth).await?;
 
         // Compact output: just the tree array
         Ok(ToolOutput::success(
@@ -471,32 +1268,616 @@ impl Tool for MemoryTreeTool {
     }
 }
 
-#[cfg(all(test, feature = "postgres"))]
-mod tests {
-    use super::*;
+/// Tool for creating workspace directories.
+///
+/// Directories otherwise only exist while they hold documents; one made
+/// here stays (and shows up in `memory_tree`) even while empty.
+pub struct MemoryMkdirTool {
+    workspace: Arc<Workspace>,
+}
 
-    fn make_test_workspace() -> Arc<Workspace> {
-        Arc::new(Workspace::new(
-            "test_user",
-            deadpool_postgres::Pool::builder(deadpool_postgres::Manager::new(
-                tokio_postgres::Config::new(),
-                tokio_postgres::NoTls,
-            ))
-            .build()
-            .unwrap(),
-        ))
+impl MemoryMkdirTool {
+    /// Create a new memory mkdir tool.
+    pub fn new(workspace: Arc<Workspace>) -> Self {
+        Self { workspace }
     }
+}
 
-    #[test]
-    fn test_memory_search_schema() {
-        let workspace = make_test_workspace();
-        let tool = MemorySearchTool::new(workspace);
+#[async_trait]
+impl Tool for MemoryMkdirTool {
+    fn name(&self) -> &str {
+        "memory_mkdir"
+    }
 
-        assert_eq!(tool.name(), "memory_search");
+    fn description(&self) -> &str {
+        "Create a directory in workspace memory, including any missing parents. \
+         Use it to lay out structure before there is anything to put in it; \
+         memory_write creates directories on its own when writing a file."
+    }
+
+    fn parameters_schema(&self) -> serde_json::Value {
+        serde_json::json!({
+            "type": "object",
+            "properties": {
+                "path": {
+                    "type": "string",
+                    "description": "Directory to create (e.g. 'projects/alpha')"
+                },
+                "description": {
+                    "type": "string",
+                    "description": "What the directory is for, stored with it"
+                }
+            },
+            "required": ["path"]
+        })
+    }
+
+    async fn execute(
+        &self,
+        params: serde_json::Value,
+        ctx: &JobContext,
+    ) -> Result<ToolOutput, ToolError> {
+        let start = std::time::Instant::now();
+
+        let path = require_str(&params, "path")?;
+        check_access(&self.workspace, ctx, path)?;
+
+        let failed = |e| ToolError::ExecutionFailed(format!("mkdir failed: {}", e));
+        let created = self.workspace.mkdir(path).await.map_err(failed)?;
+        if let Some(description) = params.get("description").and_then(|v| v.as_str()) {
+            self.workspace
+                .set_directory_metadata(path, &serde_json::json!({ "description": description }))
+                .await
+                .map_err(failed)?;
+        }
+
+        Ok(ToolOutput::success(
+            serde_json::json!({
+                "path": path.trim_matches('/'),
+                "status": if created { "created" } else { "exists" },
+            }),
+            start.elapsed(),
+        ))
+    }
+
+    fn requires_sanitization(&self) -> bool {
+        false // Internal tool
+    }
+}
+
+/// Tool for checking workspace documents for problems.
+///
+/// Reports broken frontmatter, dangling wiki links, an unnamed identity,
+/// oversized files, and invalid HEARTBEAT.md schedules, so the agent can
+/// fix what it wrote.
+pub struct MemoryLintTool {
+    workspace: Arc<Workspace>,
+}
+
+impl MemoryLintTool {
+    /// Create a new memory lint tool.
+    pub fn new(workspace: Arc<Workspace>) -> Self {
+        Self { workspace }
+    }
+}
+
+#[async_trait]
+impl Tool for MemoryLintTool {
+    fn name(&self) -> &str {
+        "memory_lint"
+    }
+
+    fn description(&self) -> &str {
+        "Check workspace memory for problems: frontmatter that doesn't parse, \
+         [[wiki links]] to documents that don't exist, a missing name in IDENTITY.md, \
+         oversized files, and invalid HEARTBEAT.md schedules. Each problem comes \
+         with its path and line; fix them with memory_edit."
+    }
+
+    fn parameters_schema(&self) -> serde_json::Value {
+        serde_json::json!({
+            "type": "object",
+            "properties": {
+                "path": {
+                    "type": "string",
+                    "description": "Only report problems in this document or directory"
+                }
+            }
+        })
+    }
+
+    async fn execute(
+        &self,
+        params: serde_json::Value,
+        ctx: &JobContext,
+    ) -> Result<ToolOutput, ToolError> {
+        let start = std::time::Instant::now();
+
+        let prefix = params
+            .get("path")
+            .and_then(|v| v.as_str())
+            .map(|p| p.trim_matches('/'))
+            .filter(|p| !p.is_empty());
+        let diagnostics: Vec<_> = self
+            .workspace
+            .lint()
+            .await
+            .map_err(|e| ToolError::ExecutionFailed(format!("Lint failed: {}", e)))?
+            .into_iter()
+            .filter(|d| {
+                prefix.is_none_or(|p| {
+                    d.path == p
+                        || d.path
+                            .strip_prefix(p)
+                            .is_some_and(|rest| rest.starts_with('/'))
+                })
+            })
+            .filter(|d| check_access(&self.workspace, ctx, &d.path).is_ok())
+            .collect();
+
+        Ok(ToolOutput::success(
+            serde_json::json!({
+                "problems": diagnostics,
+                "problem_count": diagnostics.len(),
+            }),
+            start.elapsed(),
+        ))
+    }
+
+    fn requires_sanitization(&self) -> bool {
+        false // Internal memory
+    }
+}
+
+/// Tool for multi-hop recall over the knowledge graph.
+///
+/// Answers "how are these connected?" and "what do I know about this?"
+/// from relations extracted out of workspace documents.
+pub struct MemoryGraphTool {
+    graph: Arc<KnowledgeGraph>,
+    workspace: Arc<Workspace>,
+}
+
+impl MemoryGraphTool {
+    /// Create a new memory graph tool. Facts are only shown if the job may
+    /// read the `workspace` document they came from.
+    pub fn new(graph: Arc<KnowledgeGraph>, workspace: Arc<Workspace>) -> Self {
+        Self { graph, workspace }
+    }
+
+    /// Whether the job may see `fact`: the same rules as reading its source
+    /// document, so private documents stay out of shared conversations.
+    async fn may_see(
+        &self,
+        ctx: &JobContext,
+        fact: &GraphFact,
+        visible: &mut HashMap<String, bool>,
+    ) -> bool {
+        let Some(ref path) = fact.document else {
+            return true;
+        };
+        if let Some(&allowed) = visible.get(path) {
+            return allowed;
+        }
+        let allowed = if check_access(&self.workspace, ctx, path).is_err() {
+            false
+        } else if ctx.is_shared() {
+            matches!(self.workspace.read(path).await, Ok(doc) if !doc.is_private())
+        } else {
+            true
+        };
+        visible.insert(path.clone(), allowed);
+        allowed
+    }
+}
+
+#[async_trait]
+impl Tool for MemoryGraphTool {
+    fn name(&self) -> &str {
+        "memory_graph"
+    }
+
+    fn description(&self) -> &str {
+        "Query the knowledge graph built from workspace memory. Give one entity to list \
+         what is known about it, or two to find the chain of facts connecting them \
+         (e.g. a person and a project). Complements memory_search for questions that \
+         span several notes."
+    }
+
+    fn parameters_schema(&self) -> serde_json::Value {
+        serde_json::json!({
+            "type": "object",
+            "properties": {
+                "entity": {
+                    "type": "string",
+                    "description": "Name of a person, project, place, or other entity"
+                },
+                "connect_to": {
+                    "type": "string",
+                    "description": "Second entity; returns the facts linking the two"
+                },
+                "max_hops": {
+                    "type": "integer",
+                    "description": "Relations to follow (default: 3 when connecting, 1 otherwise)",
+                    "minimum": 1,
+                    "maximum": 4
+                }
+            },
+            "required": ["entity"]
+        })
+    }
+
+    async fn execute(
+        &self,
+        params: serde_json::Value,
+        ctx: &JobContext,
+    ) -> Result<ToolOutput, ToolError> {
+        let start = std::time::Instant::now();
+
+        let entity = require_str(&params, "entity")?;
+        let connect_to = params.get("connect_to").and_then(|v| v.as_str());
+        let max_hops = params.get("max_hops").and_then(|v| v.as_u64());
+
+        let mut visible = HashMap::new();
+        let output = match connect_to {
+            Some(other) => {
+                let hops = max_hops.unwrap_or(3).clamp(1, 4) as usize;
+                let found = self.graph.connect(entity, other, hops).await.map_err(|e| {
+                    ToolError::ExecutionFailed(format!("Graph query failed: {}", e))
+                })?;
+                // A chain with a hidden link would still reveal it, so drop
+                // the whole path.
+                let mut paths = Vec::with_capacity(found.len());
+                'paths: for path in found {
+                    for fact in &path.facts {
+                        if !self.may_see(ctx, fact, &mut visible).await {
+                            continue 'paths;
+                        }
+                    }
+                    paths.push(path);
+                }
+                serde_json::json!({
+                    "from": entity,
+                    "to": other,
+                    "paths": paths,
+                    "path_count": paths.len(),
+                })
+            }
+            None => {
+                let hops = max_hops.unwrap_or(1).clamp(1, 4) as usize;
+                let found = self.graph.neighborhood(entity, hops).await.map_err(|e| {
+                    ToolError::ExecutionFailed(format!("Graph query failed: {}", e))
+                })?;
+                let mut facts = Vec::with_capacity(found.len());
+                for fact in found {
+                    if self.may_see(ctx, &fact, &mut visible).await {
+                        facts.push(fact);
+                    }
+                }
+                serde_json::json!({
+                    "entity": entity,
+                    "facts": facts,
+                    "fact_count": facts.len(),
+                })
+            }
+        };
+
+        Ok(ToolOutput::success(output, start.elapsed()))
+    }
+
+    fn requires_sanitization(&self) -> bool {
+        false // Internal memory, trusted content
+    }
+}
+
+/// Tool for pinning memories into every system prompt.
+///
+/// Pinned documents, sections, and facts are always in context, within a
+/// dedicated token budget. Pinning requires approval because pinned content
+/// reaches every future conversation.
+pub struct MemoryPinTool {
+    workspace: Arc<Workspace>,
+}
+
+impl MemoryPinTool {
+    /// Create a new memory pin tool.
+    pub fn new(workspace: Arc<Workspace>) -> Self {
+        Self { workspace }
+    }
+}
+
+#[async_trait]
+impl Tool for MemoryPinTool {
+    fn name(&self) -> &str {
+        "memory_pin"
+    }
+
+    fn description(&self) -> &str {
+        "Pin a memory so it is included in every prompt, independent of search. Use \
+         sparingly, for rules that must never be forgotten (e.g. 'never email anyone \
+         before asking'). Pin a standalone fact, a whole workspace document, or one \
+         section of a document. Also lists and removes pins."
+    }
+
+    fn parameters_schema(&self) -> serde_json::Value {
+        serde_json::json!({
+            "type": "object",
+            "properties": {
+                "action": {
+                    "type": "string",
+                    "enum": ["pin", "unpin", "list"],
+                    "description": "What to do",
+                    "default": "pin"
+                },
+                "fact": {
+                    "type": "string",
+                    "description": "A standalone fact or rule to pin"
+                },
+                "path": {
+                    "type": "string",
+                    "description": "Workspace document to pin (e.g. 'MEMORY.md')"
+                },
+                "section": {
+                    "type": "string",
+                    "description": "Heading of the section of 'path' to pin instead of the whole document"
+                },
+                "id": {
+                    "type": "string",
+                    "description": "Pin ID to remove (for action 'unpin')"
+                }
+            }
+        })
+    }
+
+    async fn execute(
+        &self,
+        params: serde_json::Value,
+        ctx: &JobContext,
+    ) -> Result<ToolOutput, ToolError> {
+        let start = std::time::Instant::now();
+
+        let action = params
+            .get("action")
+            .and_then(|v| v.as_str())
+            .unwrap_or("pin");
+        let str_param = |name: &str| {
+            params
+                .get(name)
+                .and_then(|v| v.as_str())
+                .map(str::trim)
+                .filter(|s| !s.is_empty())
+        };
+
+        let output = match action {
+            "list" => {
+                let pins = self.workspace.pins().await.map_err(|e| {
+                    ToolError::ExecutionFailed(format!("Listing pins failed: {}", e))
+                })?;
+                serde_json::json!({ "pins": pins, "count": pins.len() })
+            }
+            "unpin" => {
+                let id = require_str(&params, "id")?
+                    .parse()
+                    .map_err(|_| ToolError::InvalidParameters("id must be a UUID".to_string()))?;
+                let removed = self
+                    .workspace
+                    .unpin(id)
+                    .await
+                    .map_err(|e| ToolError::ExecutionFailed(format!("Unpin failed: {}", e)))?;
+                serde_json::json!({ "id": id.to_string(), "removed": removed })
+            }
+            "pin" => {
+                let target = match (str_param("fact"), str_param("path"), str_param("section")) {
+                    (Some(text), None, None) => PinTarget::Fact {
+                        text: text.to_string(),
+                    },
+                    (None, Some(path), section) => {
+                        check_access(&self.workspace, ctx, path)?;
+                        match section {
+                            Some(heading) => PinTarget::Section {
+                                path: path.to_string(),
+                                heading: heading.to_string(),
+                            },
+                            None => PinTarget::Document {
+                                path: path.to_string(),
+                            },
+                        }
+                    }
+                    _ => {
+                        return Err(ToolError::InvalidParameters(
+                            "provide either 'fact' or 'path' (optionally with 'section')"
+                                .to_string(),
+                        ));
+                    }
+                };
+                let pin = self
+                    .workspace
+                    .pin(target)
+                    .await
+                    .map_err(|e| ToolError::ExecutionFailed(format!("Pin failed: {}", e)))?;
+                serde_json::json!({ "status": "pinned", "pin": pin })
+            }
+            other => {
+                return Err(ToolError::InvalidParameters(format!(
+                    "unknown action '{}'",
+                    other
+                )));
+            }
+        };
+
+        Ok(ToolOutput::success(output, start.elapsed()))
+    }
+
+    fn requires_approval(&self) -> bool {
+        true
+    }
+
+    fn requires_sanitization(&self) -> bool {
+        false // Internal tool
+    }
+}
+
+/// The channel a `persona` call switches: `None` for the default persona,
+/// otherwise the given channel or the conversation's own.
+fn persona_channel<'a>(
+    params: &'a serde_json::Value,
+    ctx: &'a JobContext,
+) -> Result<Option<&'a str>, ToolError> {
+    if params
+        .get("all_channels")
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false)
+    {
+        return Ok(None);
+    }
+    match params.get("channel").and_then(|v| v.as_str()) {
+        Some(channel) => Ok(Some(channel.trim())),
+        None => ctx.channel.as_deref().map(Some).ok_or_else(|| {
+            ToolError::InvalidParameters(
+                "no channel in this context; pass 'channel' or 'all_channels'".to_string(),
+            )
+        }),
+    }
+}
+
+/// Tool for switching the active persona.
+///
+/// Personas are alternative SOUL.md and IDENTITY.md files under
+/// `personas/<name>/`; see [`crate::workspace::persona`].
+pub struct PersonaTool {
+    workspace: Arc<Workspace>,
+}
+
+impl PersonaTool {
+    /// Create a new persona tool.
+    pub fn new(workspace: Arc<Workspace>) -> Self {
+        Self { workspace }
+    }
+}
+
+#[async_trait]
+impl Tool for PersonaTool {
+    fn name(&self) -> &str {
+        "persona"
+    }
+
+    fn description(&self) -> &str {
+        "Switch which persona (the SOUL.md and IDENTITY.md variants under personas/<name>/) \
+         you use. By default the switch applies to the channel of this conversation; set \
+         'all_channels' to change the default instead. Takes effect from the next message. \
+         Use action 'list' to see the personas and where each is active."
+    }
+
+    fn parameters_schema(&self) -> serde_json::Value {
+        serde_json::json!({
+            "type": "object",
+            "properties": {
+                "action": {
+                    "type": "string",
+                    "enum": ["set", "clear", "list"],
+                    "description": "Switch to a persona, go back to the default, or list personas",
+                    "default": "list"
+                },
+                "persona": {
+                    "type": "string",
+                    "description": "Persona name (for action 'set')"
+                },
+                "channel": {
+                    "type": "string",
+                    "description": "Channel to switch (defaults to this conversation's channel)"
+                },
+                "all_channels": {
+                    "type": "boolean",
+                    "description": "Change the default persona rather than one channel's",
+                    "default": false
+                }
+            }
+        })
+    }
+
+    async fn execute(
+        &self,
+        params: serde_json::Value,
+        ctx: &JobContext,
+    ) -> Result<ToolOutput, ToolError> {
+        let start = std::time::Instant::now();
+
+        let action = params
+            .get("action")
+            .and_then(|v| v.as_str())
+            .unwrap_or("list");
+
+        let failed = |e: WorkspaceError| match e {
+            WorkspaceError::PersonaNotFound { .. } | WorkspaceError::InvalidPath { .. } => {
+                ToolError::InvalidParameters(e.to_string())
+            }
+            e => ToolError::ExecutionFailed(format!("Persona switch failed: {}", e)),
+        };
+        match action {
+            "list" => {}
+            "set" => {
+                let persona = require_str(&params, "persona")?.trim();
+                self.workspace
+                    .set_persona(persona_channel(&params, ctx)?, Some(persona))
+                    .await
+                    .map_err(failed)?;
+            }
+            "clear" => {
+                self.workspace
+                    .set_persona(persona_channel(&params, ctx)?, None)
+                    .await
+                    .map_err(failed)?;
+            }
+            other => {
+                return Err(ToolError::InvalidParameters(format!(
+                    "unknown action '{}'",
+                    other
+                )));
+            }
+        }
+
+        let status = self.workspace.persona_status().await.map_err(failed)?;
+        let output = serde_json::json!({
+            "active": status.for_channel(ctx.channel.as_deref()),
+            "personas": status.personas,
+            "default": status.default,
+            "channels": status.channels,
+        });
+        Ok(ToolOutput::success(output, start.elapsed()))
+    }
+
+    fn requires_sanitization(&self) -> bool {
+        false // Internal tool
+    }
+}
+
+#[cfg(all(test, feature = "postgres"))]
+mod tests {
+    use super::*;
+
+    fn make_test_workspace() -> Arc<Workspace> {
+        Arc::new(Workspace::new(
+            "test_user",
+            deadpool_postgres::Pool::builder(deadpool_postgres::Manager::new(
+                tokio_postgres::Config::new(),
+                tokio_postgres::NoTls,
+            ))
+            .build()
+            .unwrap(),
+        ))
+    }
+
+    #[test]
+    fn test_memory_search_schema() {
+        let workspace = make_test_workspace();
+        let tool = MemorySearchTool::new(workspace);
+
+        assert_eq!(tool.name(), "memory_search");
         assert!(!tool.requires_sanitization());
 
         let schema = tool.parameters_schema();
         assert!(schema["properties"]["query"].is_object());
+        assert_eq!(schema["properties"]["granularity"]["default"], "chunks");
         assert!(
             schema["required"]
                 .as_array()
@@ -547,4 +1928,69 @@ mod tests {
         assert!(schema["properties"]["depth"].is_object());
         assert_eq!(schema["properties"]["depth"]["default"], 1);
     }
+
+    #[test]
+    fn test_memory_lint_schema() {
+        let workspace = make_test_workspace();
+        let tool = MemoryLintTool::new(workspace);
+
+        assert_eq!(tool.name(), "memory_lint");
+
+        let schema = tool.parameters_schema();
+        assert!(schema["properties"]["path"].is_object());
+        assert!(schema.get("required").is_none());
+    }
+
+    #[test]
+    fn test_memory_pin_schema() {
+        let workspace = make_test_workspace();
+        let tool = MemoryPinTool::new(workspace);
+
+        assert_eq!(tool.name(), "memory_pin");
+        assert!(tool.requires_approval());
+
+        let schema = tool.parameters_schema();
+        assert!(schema["properties"]["fact"].is_object());
+        assert!(schema["properties"]["section"].is_object());
+        assert_eq!(schema["properties"]["action"]["default"], "pin");
+    }
+
+    #[test]
+    fn test_memory_search_helpers() {
+        let content = format!(
+            "{} The launch moved to May. {}",
+            "a".repeat(300),
+            "b".repeat(300)
+        );
+        let excerpt = snippet(&content, "when is the LAUNCH?");
+        assert!(excerpt.starts_with('…') && excerpt.ends_with('…'));
+        assert!(excerpt.contains("The launch moved to May."));
+        assert_eq!(snippet("short", "launch"), "short");
+
+        assert_eq!(
+            parse_time("2024-05-01", "updated_after")
+                .unwrap()
+                .to_rfc3339(),
+            "2024-05-01T00:00:00+00:00"
+        );
+        assert!(parse_time("2024-05-01T10:00:00+02:00", "updated_after").is_ok());
+        assert!(parse_time("last week", "updated_after").is_err());
+    }
+
+    #[test]
+    fn test_memory_edit_approval_follows_path() {
+        let workspace = make_test_workspace();
+        let tool = MemoryEditTool::new(workspace);
+
+        assert_eq!(tool.name(), "memory_edit");
+        assert!(!tool.requires_approval_for(&serde_json::json!({
+            "action": "write", "path": "notes.md", "content": "x"
+        })));
+        assert!(tool.requires_approval_for(&serde_json::json!({
+            "action": "edit_section", "path": "/soul.md", "section": "Vibe", "content": "x"
+        })));
+        assert!(tool.requires_approval_for(&serde_json::json!({
+            "action": "rename", "path": "draft.md", "new_path": "AGENTS.md"
+        })));
+    }
 }
@@ -1,27 +1,52 @@
 //! Built-in tools that come with the agent.
 
+mod bootstrap;
+mod contacts;
 mod echo;
 pub mod extension_tools;
 mod file;
+mod github;
+mod home_assistant;
 mod http;
 mod job;
 mod json;
 mod memory;
+mod read_url;
 pub mod routine;
+mod scratch;
 pub(crate) mod shell;
+mod skill;
+mod supervise;
+mod template;
 mod time;
+mod weather;
 
+pub use bootstrap::BootstrapTool;
+pub use contacts::{
+    ContactCreateTool, ContactDeleteTool, ContactListTool, ContactResolveTool, ContactUpdateTool,
+};
 pub use echo::EchoTool;
 pub use extension_tools::{
     ToolActivateTool, ToolAuthTool, ToolInstallTool, ToolListTool, ToolRemoveTool, ToolSearchTool,
 };
 pub use file::{ApplyPatchTool, ListDirTool, ReadFileTool, WriteFileTool};
+pub use github::GitHubTool;
+pub use home_assistant::HomeAssistantTool;
 pub use http::HttpTool;
 pub use job::{CancelJobTool, CreateJobTool, JobStatusTool, ListJobsTool};
 pub use json::JsonTool;
-pub use memory::{MemoryReadTool, MemorySearchTool, MemoryTreeTool, MemoryWriteTool};
+pub use memory::{
+    MemoryEditTool, MemoryGraphTool, MemoryLintTool, MemoryMkdirTool, MemoryPinTool,
+    MemoryReadTool, MemorySearchTool, MemoryTreeTool, MemoryWriteTool, PersonaTool,
+};
+pub use read_url::ReadUrlTool;
 pub use routine::{
     RoutineCreateTool, RoutineDeleteTool, RoutineHistoryTool, RoutineListTool, RoutineUpdateTool,
 };
+pub use scratch::ScratchTool;
 pub use shell::ShellTool;
+pub use skill::{SkillCommandTool, SkillTool};
+pub use supervise::SuperviseTool;
+pub use template::RunTemplateTool;
 pub use time::TimeTool;
+pub use weather::WeatherTool;
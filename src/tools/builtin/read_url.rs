@@ -0,0 +1,1105 @@
+//! Web page reader.
+//!
+//! `read_url` fetches a page and returns its main text as markdown, without
+//! a browser. The page is parsed leniently, boilerplate (navigation,
+//! sidebars, footers, scripts) is dropped, and the element holding most of
+//! the paragraph text is kept, in the spirit of Readability. Links are made
+//! absolute and every chunk cites the page it came from.
+//!
+//! Long pages are returned in chunks; each response carries the cursor of
+//! the next one. Pages are cached briefly so that reading on doesn't refetch.
+
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use async_trait::async_trait;
+use reqwest::Client;
+use tokio::sync::Mutex;
+use url::Url;
+
+use crate::context::JobContext;
+use crate::safety::LeakDetector;
+use crate::tools::builtin::http::validate_url;
+use crate::tools::tool::{Tool, ToolError, ToolOutput, require_str};
+
+/// Largest page downloaded (5 MB).
+const MAX_PAGE_BYTES: usize = 5 * 1024 * 1024;
+
+/// Redirects followed, each checked like the original URL.
+const MAX_REDIRECTS: usize = 5;
+
+/// Chunk size bounds and default, in bytes of markdown.
+const DEFAULT_CHUNK: usize = 8_000;
+const MIN_CHUNK: usize = 1_000;
+const MAX_CHUNK: usize = 50_000;
+
+/// How long a fetched page is kept for continuation reads.
+const CACHE_TTL: Duration = Duration::from_secs(10 * 60);
+
+/// Pages kept in the cache.
+const CACHE_PAGES: usize = 16;
+
+/// Elements that never hold article text.
+const SKIPPED_TAGS: &[&str] = &[
+    "script", "style", "noscript", "svg", "template", "nav", "header", "footer", "aside", "form",
+    "button", "iframe", "select", "input", "textarea", "canvas", "object", "embed",
+];
+
+/// Elements without content or closing tag.
+const VOID_TAGS: &[&str] = &[
+    "area", "base", "br", "col", "embed", "hr", "img", "input", "link", "meta", "param", "source",
+    "track", "wbr",
+];
+
+/// Elements that end an open paragraph.
+const CLOSES_P: &[&str] = &[
+    "p",
+    "div",
+    "ul",
+    "ol",
+    "pre",
+    "blockquote",
+    "table",
+    "h1",
+    "h2",
+    "h3",
+    "h4",
+    "h5",
+    "h6",
+    "hr",
+    "section",
+    "article",
+    "figure",
+    "dl",
+];
+
+/// Class or ID fragments marking boilerplate containers.
+const BOILERPLATE_HINTS: &[&str] = &[
+    "sidebar",
+    "comment",
+    "footer",
+    "navbar",
+    "menu",
+    "share",
+    "social",
+    "advert",
+    "promo",
+    "cookie",
+    "banner",
+    "related",
+    "subscribe",
+    "newsletter",
+    "breadcrumb",
+    "popup",
+];
+
+/// A fetched page as markdown.
+#[derive(Debug, Clone)]
+struct Page {
+    title: String,
+    markdown: String,
+}
+
+/// Tool that reads a web page as markdown.
+pub struct ReadUrlTool {
+    client: Client,
+    cache: Mutex<HashMap<String, (Instant, Page)>>,
+}
+
+impl ReadUrlTool {
+    pub fn new() -> Self {
+        let client = Client::builder()
+            .timeout(Duration::from_secs(20))
+            .redirect(reqwest::redirect::Policy::none())
+            .user_agent("Mozilla/5.0 (compatible; ironclaw-read-url)")
+            .build()
+            .expect("Failed to create HTTP client");
+        Self {
+            client,
+            cache: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Fetch `url`, following redirects that pass the same checks.
+    async fn fetch(&self, url: &str) -> Result<Page, ToolError> {
+        let mut url = validate_url(url)?;
+        for _ in 0..=MAX_REDIRECTS {
+            LeakDetector::new()
+                .scan_http_request(url.as_str(), &[], None)
+                .map_err(|e| ToolError::NotAuthorized(e.to_string()))?;
+            let response = self.client.get(url.clone()).send().await.map_err(|e| {
+                if e.is_timeout() {
+                    ToolError::Timeout(Duration::from_secs(20))
+                } else {
+                    ToolError::ExternalService(e.to_string())
+                }
+            })?;
+
+            let status = response.status();
+            if status.is_redirection() {
+                let location = response
+                    .headers()
+                    .get(reqwest::header::LOCATION)
+                    .and_then(|v| v.to_str().ok())
+                    .ok_or_else(|| {
+                        ToolError::ExternalService(format!("HTTP {} without Location", status))
+                    })?;
+                let next = url
+                    .join(location)
+                    .map_err(|e| ToolError::ExternalService(format!("bad redirect: {}", e)))?;
+                url = validate_url(next.as_str())?;
+                continue;
+            }
+            if !status.is_success() {
+                return Err(ToolError::ExternalService(format!(
+                    "{} returned HTTP {}",
+                    url, status
+                )));
+            }
+            if response
+                .content_length()
+                .is_some_and(|len| len as usize > MAX_PAGE_BYTES)
+            {
+                return Err(ToolError::ExecutionFailed(format!(
+                    "page too large (max {} bytes)",
+                    MAX_PAGE_BYTES
+                )));
+            }
+
+            let content_type = response
+                .headers()
+                .get(reqwest::header::CONTENT_TYPE)
+                .and_then(|v| v.to_str().ok())
+                .unwrap_or("")
+                .to_lowercase();
+            let body = response
+                .bytes()
+                .await
+                .map_err(|e| ToolError::ExternalService(format!("failed to read page: {}", e)))?;
+            if body.len() > MAX_PAGE_BYTES {
+                return Err(ToolError::ExecutionFailed(format!(
+                    "page too large (max {} bytes)",
+                    MAX_PAGE_BYTES
+                )));
+            }
+            let text = String::from_utf8_lossy(&body);
+
+            return if content_type.is_empty() || content_type.contains("html") {
+                Ok(extract(&text, &url))
+            } else if content_type.starts_with("text/") || content_type.contains("json") {
+                Ok(Page {
+                    title: url.to_string(),
+                    markdown: text.trim().to_string(),
+                })
+            } else {
+                Err(ToolError::ExecutionFailed(format!(
+                    "can't read {} content; use the http tool",
+                    content_type
+                )))
+            };
+        }
+        Err(ToolError::ExternalService(format!(
+            "more than {} redirects",
+            MAX_REDIRECTS
+        )))
+    }
+
+    /// The page at `url`, from the cache while fresh.
+    async fn page(&self, url: &str) -> Result<(Page, bool), ToolError> {
+        if let Some((fetched, page)) = self.cache.lock().await.get(url)
+            && fetched.elapsed() < CACHE_TTL
+        {
+            return Ok((page.clone(), true));
+        }
+        let page = self.fetch(url).await?;
+        let mut cache = self.cache.lock().await;
+        cache.retain(|_, (fetched, _)| fetched.elapsed() < CACHE_TTL);
+        if cache.len() >= CACHE_PAGES
+            && let Some(oldest) = cache
+                .iter()
+                .min_by_key(|(_, (fetched, _))| *fetched)
+                .map(|(url, _)| url.clone())
+        {
+            cache.remove(&oldest);
+        }
+        cache.insert(url.to_string(), (Instant::now(), page.clone()));
+        Ok((page, false))
+    }
+}
+
+impl Default for ReadUrlTool {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl Tool for ReadUrlTool {
+    fn name(&self) -> &str {
+        "read_url"
+    }
+
+    fn description(&self) -> &str {
+        "Read a web page as markdown: fetches it, keeps the main text (dropping navigation, \
+         ads, and footers), and cites the source. Long pages come in chunks; pass the \
+         returned 'next_cursor' to continue. Prefer this over a browser for reading."
+    }
+
+    fn parameters_schema(&self) -> serde_json::Value {
+        serde_json::json!({
+            "type": "object",
+            "properties": {
+                "url": {
+                    "type": "string",
+                    "description": "HTTPS URL of the page"
+                },
+                "cursor": {
+                    "type": "integer",
+                    "description": "Where to continue reading (the 'next_cursor' of the previous chunk)"
+                },
+                "max_chars": {
+                    "type": "integer",
+                    "description": "Chunk size (default 8000, 1000-50000)"
+                },
+                "stash_as": {
+                    "type": "string",
+                    "description": "Put the whole page in the scratch buffer under this key instead of returning a chunk"
+                }
+            },
+            "required": ["url"]
+        })
+    }
+
+    async fn execute(
+        &self,
+        params: serde_json::Value,
+        ctx: &JobContext,
+    ) -> Result<ToolOutput, ToolError> {
+        let start = std::time::Instant::now();
+
+        let url = require_str(&params, "url")?.trim();
+        let cursor = params.get("cursor").and_then(|v| v.as_u64()).unwrap_or(0) as usize;
+        let max_chars = params
+            .get("max_chars")
+            .and_then(|v| v.as_u64())
+            .map_or(DEFAULT_CHUNK, |v| v as usize)
+            .clamp(MIN_CHUNK, MAX_CHUNK);
+
+        let (page, cached) = self.page(url).await?;
+        if let Some(key) = params.get("stash_as").and_then(|v| v.as_str()) {
+            ctx.scratch
+                .stash(key, format!("Source: <{}>\n\n{}", url, page.markdown))
+                .map_err(|e| ToolError::ExecutionFailed(e.to_string()))?;
+            let result = serde_json::json!({
+                "url": url,
+                "title": page.title,
+                "stashed_as": key,
+                "total_chars": page.markdown.len(),
+                "cached": cached,
+            });
+            return Ok(ToolOutput::success(result, start.elapsed()));
+        }
+        if cursor > page.markdown.len() || !page.markdown.is_char_boundary(cursor) {
+            return Err(ToolError::InvalidParameters(format!(
+                "cursor {} is not a position in this page",
+                cursor
+            )));
+        }
+        let (chunk, next) = chunk_at(&page.markdown, cursor, max_chars);
+
+        let result = serde_json::json!({
+            "url": url,
+            "title": page.title,
+            "content": format!("Source: <{}>\n\n{}", url, chunk),
+            "cursor": cursor,
+            "next_cursor": next,
+            "total_chars": page.markdown.len(),
+            "cached": cached,
+        });
+        Ok(ToolOutput::success(result, start.elapsed()))
+    }
+
+    fn estimated_duration(&self, _params: &serde_json::Value) -> Option<Duration> {
+        Some(Duration::from_secs(5))
+    }
+
+    fn requires_sanitization(&self) -> bool {
+        true // Web content
+    }
+
+    fn is_external_action(&self) -> bool {
+        true
+    }
+}
+
+/// The chunk of `text` starting at `cursor`, ending at a paragraph or line
+/// break where possible, and where the next one starts (if any).
+fn chunk_at(text: &str, cursor: usize, max: usize) -> (&str, Option<usize>) {
+    let rest = &text[cursor..];
+    if rest.len() <= max {
+        return (rest, None);
+    }
+    let mut limit = max;
+    while !rest.is_char_boundary(limit) {
+        limit -= 1;
+    }
+    let window = &rest[..limit];
+    let end = window
+        .rfind("\n\n")
+        .filter(|&i| i >= max / 2)
+        .or_else(|| window.rfind('\n').filter(|&i| i >= max / 2))
+        .unwrap_or(limit);
+    let next = cursor + end + rest[end..].len() - rest[end..].trim_start().len();
+    (rest[..end].trim_end(), Some(next))
+}
+
+// ==================== HTML parsing ====================
+
+/// An element of the parsed page.
+#[derive(Debug, Default)]
+struct Element {
+    tag: String,
+    attrs: Vec<(String, String)>,
+    children: Vec<NodeRef>,
+}
+
+#[derive(Debug)]
+enum NodeRef {
+    Element(Element),
+    Text(String),
+}
+
+impl Element {
+    fn attr(&self, name: &str) -> Option<&str> {
+        self.attrs
+            .iter()
+            .find(|(k, _)| k == name)
+            .map(|(_, v)| v.as_str())
+    }
+
+    fn elements(&self) -> impl Iterator<Item = &Element> {
+        self.children.iter().filter_map(|c| match c {
+            NodeRef::Element(e) => Some(e),
+            NodeRef::Text(_) => None,
+        })
+    }
+
+    /// All text below this element.
+    fn text(&self) -> String {
+        let mut out = String::new();
+        self.collect_text(&mut out);
+        out
+    }
+
+    fn collect_text(&self, out: &mut String) {
+        for child in &self.children {
+            match child {
+                NodeRef::Text(t) => out.push_str(t),
+                NodeRef::Element(e) => e.collect_text(out),
+            }
+        }
+    }
+
+    /// First element (depth first, including self) satisfying `pred`.
+    fn find(&self, pred: &dyn Fn(&Element) -> bool) -> Option<&Element> {
+        if pred(self) {
+            return Some(self);
+        }
+        self.elements().find_map(|e| e.find(pred))
+    }
+
+    /// Whether this element is page furniture rather than content.
+    fn is_boilerplate(&self) -> bool {
+        if SKIPPED_TAGS.contains(&self.tag.as_str()) {
+            return true;
+        }
+        if matches!(self.attr("aria-hidden"), Some("true")) || self.attr("hidden").is_some() {
+            return true;
+        }
+        if matches!(
+            self.attr("role"),
+            Some("navigation" | "banner" | "complementary")
+        ) {
+            return true;
+        }
+        if matches!(self.tag.as_str(), "article" | "main" | "body") {
+            return false;
+        }
+        let names = format!(
+            "{} {}",
+            self.attr("class").unwrap_or(""),
+            self.attr("id").unwrap_or("")
+        )
+        .to_lowercase();
+        BOILERPLATE_HINTS.iter().any(|hint| names.contains(hint))
+    }
+}
+
+/// Parse `html` leniently into a tree under a synthetic root, dropping
+/// comments, scripts, styles, and boilerplate.
+fn parse(html: &str) -> Element {
+    let mut stack = vec![Element {
+        tag: "#root".to_string(),
+        ..Default::default()
+    }];
+    let mut pos = 0;
+    let bytes = html.as_bytes();
+
+    while pos < html.len() {
+        let Some(lt) = html[pos..].find('<').map(|i| pos + i) else {
+            push_text(&mut stack, &html[pos..]);
+            break;
+        };
+        if lt > pos {
+            push_text(&mut stack, &html[pos..lt]);
+        }
+        pos = lt;
+        let rest = &html[pos..];
+
+        if rest.starts_with("<!--") {
+            pos = rest.find("-->").map_or(html.len(), |i| pos + i + 3);
+            continue;
+        }
+        if rest.starts_with("<!") || rest.starts_with("<?") {
+            pos = rest.find('>').map_or(html.len(), |i| pos + i + 1);
+            continue;
+        }
+        if let Some(close) = rest.strip_prefix("</") {
+            let name = tag_name(close);
+            pos = rest.find('>').map_or(html.len(), |i| pos + i + 1);
+            close_element(&mut stack, &name);
+            continue;
+        }
+        if !bytes.get(pos + 1).is_some_and(|b| b.is_ascii_alphabetic()) {
+            push_text(&mut stack, "<");
+            pos += 1;
+            continue;
+        }
+
+        let (element, self_closing, len) = open_tag(rest);
+        pos += len;
+        if matches!(
+            element.tag.as_str(),
+            "script" | "style" | "textarea" | "title"
+        ) {
+            // Raw text up to the closing tag.
+            let end_tag = format!("</{}", element.tag);
+            let end = find_ci(&html[pos..], &end_tag).map_or(html.len(), |i| pos + i);
+            let mut element = element;
+            if element.tag == "title" || element.tag == "textarea" {
+                element
+                    .children
+                    .push(NodeRef::Text(decode_entities(&html[pos..end])));
+            }
+            pos = html[end..].find('>').map_or(html.len(), |i| end + i + 1);
+            append(&mut stack, element);
+            continue;
+        }
+        if element.tag == "li" {
+            close_open(&mut stack, "li", &["ul", "ol"]);
+        }
+        if CLOSES_P.contains(&element.tag.as_str()) {
+            close_open(
+                &mut stack,
+                "p",
+                &["div", "section", "article", "td", "li", "blockquote"],
+            );
+        }
+        if self_closing || VOID_TAGS.contains(&element.tag.as_str()) {
+            append(&mut stack, element);
+        } else {
+            stack.push(element);
+        }
+    }
+
+    while stack.len() > 1 {
+        let element = stack.pop().unwrap_or_default();
+        append(&mut stack, element);
+    }
+    stack.pop().unwrap_or_default()
+}
+
+fn push_text(stack: &mut [Element], text: &str) {
+    if let Some(top) = stack.last_mut() {
+        top.children.push(NodeRef::Text(decode_entities(text)));
+    }
+}
+
+/// Add a finished element to the element below it, unless it's boilerplate.
+fn append(stack: &mut [Element], element: Element) {
+    if element.is_boilerplate() {
+        return;
+    }
+    if let Some(top) = stack.last_mut() {
+        top.children.push(NodeRef::Element(element));
+    }
+}
+
+/// Close the innermost open `name`, and anything opened inside it.
+fn close_element(stack: &mut Vec<Element>, name: &str) {
+    let Some(depth) = stack.iter().rposition(|e| e.tag == name) else {
+        return;
+    };
+    if depth == 0 {
+        return;
+    }
+    while stack.len() > depth {
+        let element = stack.pop().unwrap_or_default();
+        append(stack, element);
+    }
+}
+
+/// Implicitly close an open `name` (e.g. a `<li>` when the next starts),
+/// unless one of `scopes` was opened inside it.
+fn close_open(stack: &mut Vec<Element>, name: &str, scopes: &[&str]) {
+    for element in stack.iter().rev() {
+        if element.tag == name {
+            close_element(stack, name);
+            return;
+        }
+        if scopes.contains(&element.tag.as_str()) {
+            return;
+        }
+    }
+}
+
+/// Lowercased tag name at the start of `s`.
+fn tag_name(s: &str) -> String {
+    s.chars()
+        .take_while(|c| c.is_ascii_alphanumeric() || *c == '-')
+        .collect::<String>()
+        .to_ascii_lowercase()
+}
+
+/// Parse the open tag at the start of `s` (which begins with `<`): the
+/// element, whether it was self-closing, and the tag's length.
+fn open_tag(s: &str) -> (Element, bool, usize) {
+    let name = tag_name(&s[1..]);
+    let mut element = Element {
+        tag: name.clone(),
+        ..Default::default()
+    };
+    let mut i = 1 + name.len();
+    let bytes = s.as_bytes();
+    let mut self_closing = false;
+
+    while i < s.len() {
+        match bytes[i] {
+            b'>' => return (element, self_closing, i + 1),
+            b'/' => {
+                self_closing = true;
+                i += 1;
+            }
+            b if b.is_ascii_whitespace() => i += 1,
+            _ => {
+                let start = i;
+                while i < s.len()
+                    && !bytes[i].is_ascii_whitespace()
+                    && !matches!(bytes[i], b'=' | b'>' | b'/')
+                {
+                    i += 1;
+                }
+                let key = s[start..i].to_ascii_lowercase();
+                let mut value = String::new();
+                if bytes.get(i) == Some(&b'=') {
+                    i += 1;
+                    match bytes.get(i) {
+                        Some(&q @ (b'"' | b'\'')) => {
+                            let end = s[i + 1..].find(q as char).map_or(s.len(), |e| i + 1 + e);
+                            value = decode_entities(&s[i + 1..end]);
+                            i = (end + 1).min(s.len());
+                        }
+                        _ => {
+                            let start = i;
+                            while i < s.len() && !bytes[i].is_ascii_whitespace() && bytes[i] != b'>'
+                            {
+                                i += 1;
+                            }
+                            value = decode_entities(&s[start..i]);
+                        }
+                    }
+                }
+                self_closing = false;
+                if !key.is_empty() {
+                    element.attrs.push((key, value));
+                }
+            }
+        }
+    }
+    (element, self_closing, s.len())
+}
+
+/// Byte offset of `needle` in `haystack`, ignoring ASCII case.
+fn find_ci(haystack: &str, needle: &str) -> Option<usize> {
+    let needle = needle.as_bytes();
+    haystack
+        .as_bytes()
+        .windows(needle.len())
+        .position(|w| w.eq_ignore_ascii_case(needle))
+}
+
+/// Replace character references with the characters they stand for.
+fn decode_entities(s: &str) -> String {
+    if !s.contains('&') {
+        return s.to_string();
+    }
+    let mut out = String::with_capacity(s.len());
+    let mut rest = s;
+    while let Some(amp) = rest.find('&') {
+        out.push_str(&rest[..amp]);
+        rest = &rest[amp..];
+        let decoded = rest[1..].find(';').filter(|&i| i <= 10).and_then(|i| {
+            let name = &rest[1..1 + i];
+            let c = match name {
+                "amp" => Some('&'),
+                "lt" => Some('<'),
+                "gt" => Some('>'),
+                "quot" => Some('"'),
+                "apos" => Some('\''),
+                "nbsp" => Some(' '),
+                "ndash" => Some('–'),
+                "mdash" => Some('—'),
+                "hellip" => Some('…'),
+                "lsquo" => Some('‘'),
+                "rsquo" => Some('’'),
+                "ldquo" => Some('“'),
+                "rdquo" => Some('”'),
+                "copy" => Some('©'),
+                _ => name
+                    .strip_prefix("#x")
+                    .or_else(|| name.strip_prefix("#X"))
+                    .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+                    .or_else(|| name.strip_prefix('#').and_then(|d| d.parse().ok()))
+                    .and_then(char::from_u32),
+            };
+            c.map(|c| (c, i + 2))
+        });
+        match decoded {
+            Some((c, len)) => {
+                out.push(c);
+                rest = &rest[len..];
+            }
+            None => {
+                out.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+// ==================== Extraction ====================
+
+/// The title and main content of `html` as markdown.
+fn extract(html: &str, base: &Url) -> Page {
+    let root = parse(html);
+    let title = root
+        .find(&|e| e.tag == "meta" && e.attr("property") == Some("og:title"))
+        .and_then(|e| e.attr("content"))
+        .map(str::to_string)
+        .or_else(|| root.find(&|e| e.tag == "title").map(Element::text))
+        .or_else(|| root.find(&|e| e.tag == "h1").map(Element::text))
+        .map(|t| collapse_whitespace(&t))
+        .unwrap_or_else(|| base.to_string());
+
+    let content = main_content(&root);
+    let mut markdown = Markdown::new(base);
+    markdown.children(content);
+    Page {
+        title,
+        markdown: markdown.finish(),
+    }
+}
+
+/// The element holding the page's main text: the largest `<article>`, else
+/// `<main>`, else the element whose paragraphs hold the most text.
+fn main_content(root: &Element) -> &Element {
+    let mut articles = Vec::new();
+    collect(root, &|e| e.tag == "article", &mut articles);
+    if let Some(article) = articles.into_iter().max_by_key(|e| e.text().len()) {
+        return article;
+    }
+    if let Some(main) = root.find(&|e| e.tag == "main" || e.attr("role") == Some("main")) {
+        return main;
+    }
+
+    // Readability-style scoring: each paragraph counts fully towards its
+    // parent and half towards its grandparent.
+    let mut best: (f64, Option<&Element>) = (0.0, None);
+    score(root, &mut best);
+    best.1
+        .or_else(|| root.find(&|e| e.tag == "body"))
+        .unwrap_or(root)
+}
+
+fn collect<'a>(e: &'a Element, pred: &dyn Fn(&Element) -> bool, out: &mut Vec<&'a Element>) {
+    if pred(e) {
+        out.push(e);
+    }
+    for child in e.elements() {
+        collect(child, pred, out);
+    }
+}
+
+/// Paragraph text directly inside `e`, in bytes plus a bonus per comma.
+fn paragraph_weight(e: &Element) -> f64 {
+    e.elements()
+        .filter(|c| matches!(c.tag.as_str(), "p" | "pre" | "blockquote"))
+        .map(|p| {
+            let text = p.text();
+            let len = text.trim().len();
+            if len < 25 {
+                0.0
+            } else {
+                len as f64 + text.matches(',').count() as f64 * 10.0
+            }
+        })
+        .sum()
+}
+
+fn score<'a>(e: &'a Element, best: &mut (f64, Option<&'a Element>)) {
+    let own = paragraph_weight(e) + e.elements().map(paragraph_weight).sum::<f64>() / 2.0;
+    if own > best.0 {
+        *best = (own, Some(e));
+    }
+    for child in e.elements() {
+        score(child, best);
+    }
+}
+
+fn collapse_whitespace(s: &str) -> String {
+    s.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Markdown writer for an element tree.
+struct Markdown<'a> {
+    base: &'a Url,
+    out: String,
+    /// Whitespace was seen since the last text written.
+    space: bool,
+}
+
+impl<'a> Markdown<'a> {
+    fn new(base: &'a Url) -> Self {
+        Self {
+            base,
+            out: String::new(),
+            space: false,
+        }
+    }
+
+    fn finish(self) -> String {
+        let mut text = String::with_capacity(self.out.len());
+        let mut blank = 0;
+        for line in self.out.lines() {
+            let line = line.trim_end();
+            if line.is_empty() {
+                blank += 1;
+                if blank > 1 {
+                    continue;
+                }
+            } else {
+                blank = 0;
+            }
+            text.push_str(line);
+            text.push('\n');
+        }
+        text.trim().to_string()
+    }
+
+    /// Markdown of `e`'s children on their own.
+    fn render(&self, e: &Element) -> String {
+        let mut inner = Markdown::new(self.base);
+        inner.children(e);
+        inner.finish()
+    }
+
+    /// Markdown of `e`'s children as one line.
+    fn inline(&self, e: &Element) -> String {
+        collapse_whitespace(&self.render(e))
+    }
+
+    fn at_line_start(&self) -> bool {
+        self.out.is_empty() || self.out.ends_with('\n')
+    }
+
+    /// End the current block with a blank line.
+    fn block(&mut self) {
+        let trimmed = self.out.trim_end_matches(' ').len();
+        self.out.truncate(trimmed);
+        if !self.out.is_empty() && !self.out.ends_with("\n\n") {
+            self.out.push_str(if self.out.ends_with('\n') {
+                "\n"
+            } else {
+                "\n\n"
+            });
+        }
+        self.space = false;
+    }
+
+    fn text(&mut self, text: &str) {
+        for c in text.chars() {
+            if c.is_whitespace() {
+                self.space = true;
+                continue;
+            }
+            if self.space && !self.at_line_start() {
+                self.out.push(' ');
+            }
+            self.space = false;
+            self.out.push(c);
+        }
+    }
+
+    /// Write inline markup, keeping the whitespace before it.
+    fn inline_markup(&mut self, markup: &str) {
+        if markup.is_empty() {
+            return;
+        }
+        if self.space && !self.at_line_start() {
+            self.out.push(' ');
+        }
+        self.space = false;
+        self.out.push_str(markup);
+    }
+
+    fn children(&mut self, e: &Element) {
+        for child in &e.children {
+            match child {
+                NodeRef::Text(t) => self.text(t),
+                NodeRef::Element(e) => self.element(e),
+            }
+        }
+    }
+
+    fn element(&mut self, e: &Element) {
+        match e.tag.as_str() {
+            "h1" | "h2" | "h3" | "h4" | "h5" | "h6" => {
+                let level = e.tag[1..].parse().unwrap_or(1);
+                let text = self.inline(e);
+                self.block();
+                if !text.is_empty() {
+                    self.out.push_str(&"#".repeat(level));
+                    self.out.push(' ');
+                    self.out.push_str(&text);
+                }
+                self.block();
+            }
+            "br" => {
+                self.out.push('\n');
+                self.space = false;
+            }
+            "hr" => {
+                self.block();
+                self.out.push_str("---");
+                self.block();
+            }
+            "ul" | "ol" => {
+                self.block();
+                let mut n = e
+                    .attr("start")
+                    .and_then(|s| s.parse::<usize>().ok())
+                    .unwrap_or(1);
+                for item in e.elements() {
+                    let body = self.render(item);
+                    if body.is_empty() {
+                        continue;
+                    }
+                    let marker = if e.tag == "ol" {
+                        let marker = format!("{}. ", n);
+                        n += 1;
+                        marker
+                    } else {
+                        "- ".to_string()
+                    };
+                    self.out.push_str(&indent(&body, &marker));
+                    self.out.push('\n');
+                }
+                self.block();
+            }
+            "blockquote" => {
+                let body = self.render(e);
+                self.block();
+                for line in body.lines() {
+                    self.out.push_str(if line.is_empty() { ">" } else { "> " });
+                    self.out.push_str(line);
+                    self.out.push('\n');
+                }
+                self.block();
+            }
+            "pre" => {
+                let code = e.text();
+                self.block();
+                self.out.push_str("```\n");
+                self.out.push_str(code.trim_matches('\n'));
+                self.out.push_str("\n```");
+                self.block();
+            }
+            "code" | "kbd" | "samp" => {
+                let code = collapse_whitespace(&e.text());
+                if !code.is_empty() {
+                    self.inline_markup(&format!("`{}`", code));
+                }
+            }
+            "strong" | "b" => {
+                let text = self.inline(e);
+                if !text.is_empty() {
+                    self.inline_markup(&format!("**{}**", text));
+                }
+            }
+            "em" | "i" => {
+                let text = self.inline(e);
+                if !text.is_empty() {
+                    self.inline_markup(&format!("_{}_", text));
+                }
+            }
+            "a" => {
+                let text = self.inline(e);
+                let href = e
+                    .attr("href")
+                    .filter(|h| !h.starts_with('#') && !h.starts_with("javascript:"))
+                    .and_then(|h| self.base.join(h).ok());
+                match href {
+                    Some(href) if !text.is_empty() => {
+                        self.inline_markup(&format!("[{}]({})", text, href))
+                    }
+                    _ => self.inline_markup(&text),
+                }
+            }
+            "img" | "head" | "title" => {}
+            "table" => {
+                self.block();
+                self.table(e);
+                self.block();
+            }
+            "p" | "div" | "section" | "article" | "main" | "figure" | "figcaption" | "dl"
+            | "dt" | "dd" | "address" | "details" | "summary" | "li" | "body" | "html" => {
+                self.block();
+                self.children(e);
+                self.block();
+            }
+            _ => self.children(e),
+        }
+    }
+
+    fn table(&mut self, table: &Element) {
+        let mut rows = Vec::new();
+        collect(table, &|e| e.tag == "tr", &mut rows);
+        for (i, row) in rows.iter().enumerate() {
+            let cells: Vec<String> = row
+                .elements()
+                .filter(|c| c.tag == "td" || c.tag == "th")
+                .map(|c| self.inline(c).replace('|', "\\|"))
+                .collect();
+            if cells.is_empty() {
+                continue;
+            }
+            self.out.push_str(&format!("| {} |\n", cells.join(" | ")));
+            if i == 0 {
+                self.out
+                    .push_str(&format!("|{}\n", " --- |".repeat(cells.len())));
+            }
+        }
+    }
+}
+
+/// `body` with `marker` before its first line and the rest indented to match.
+fn indent(body: &str, marker: &str) -> String {
+    let pad = " ".repeat(marker.len());
+    body.lines()
+        .enumerate()
+        .map(|(i, line)| {
+            if i == 0 {
+                format!("{}{}", marker, line)
+            } else if line.is_empty() {
+                String::new()
+            } else {
+                format!("{}{}", pad, line)
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PAGE: &str = r#"<!DOCTYPE html>
+<html><head><title>Ignored &amp; replaced</title>
+<meta property="og:title" content="Rust &quot;2024&quot;">
+<script>var x = "<p>not text</p>";</script><style>p { color: red }</style></head>
+<body>
+<nav><a href="/">Home</a> <a href="/blog">Blog</a></nav>
+<div class="sidebar"><p>Subscribe to our newsletter, it is great, really great.</p></div>
+<article>
+  <h1>The   2024 edition</h1>
+  <p>Rust 2024 ships <b>let chains</b>, and <a href="/docs/let-chains#intro">docs</a> explain them.
+  <p>Second paragraph<br>with a break &mdash; and an entity &#8217;.
+  <ul><li>One<li>Two <code>x &lt; y</code></ul>
+  <pre>fn main() {
+    println!("hi");
+}</pre>
+  <blockquote><p>Quoted.</p></blockquote>
+  <table><tr><th>Name</th><th>Year</th></tr><tr><td>Rust</td><td>2015</td></tr></table>
+  <!-- comment <p>hidden</p> -->
+</article>
+<footer><p>Copyright, all rights reserved, forever and ever.</p></footer>
+</body></html>"#;
+
+    #[test]
+    fn test_extracts_main_content_as_markdown() {
+        let base = Url::parse("https://example.com/blog/post").unwrap();
+        let page = extract(PAGE, &base);
+        assert_eq!(page.title, "Rust \"2024\"");
+        assert_eq!(
+            page.markdown,
+            "# The 2024 edition\n\n\
+             Rust 2024 ships **let chains**, and [docs](https://example.com/docs/let-chains#intro) \
+             explain them.\n\n\
+             Second paragraph\nwith a break — and an entity ’.\n\n\
+             - One\n- Two `x < y`\n\n\
+             ```\nfn main() {\n    println!(\"hi\");\n}\n```\n\n\
+             > Quoted.\n\n\
+             | Name | Year |\n| --- | --- |\n| Rust | 2015 |"
+        );
+    }
+
+    #[test]
+    fn test_scores_paragraphs_without_article() {
+        let html = "<body><div id=\"menu\"><p>Home, About, Contact, and many more links here.</p></div>\
+                    <div><div class=\"post\"><p>The first long paragraph of the post, with commas, clauses, and words.</p>\
+                    <p>Another paragraph of the post that is also reasonably long.</p></div></div></body>";
+        let base = Url::parse("https://example.com/").unwrap();
+        let page = extract(html, &base);
+        assert!(page.markdown.starts_with("The first long paragraph"));
+        assert!(!page.markdown.contains("Home, About"));
+        assert_eq!(page.title, "https://example.com/");
+    }
+
+    #[test]
+    fn test_chunks_continue_at_paragraphs() {
+        let text = format!(
+            "{}\n\n{}\n\n{}",
+            "a".repeat(600),
+            "b".repeat(600),
+            "c".repeat(300)
+        );
+        let (first, next) = chunk_at(&text, 0, 1000);
+        assert_eq!(first, "a".repeat(600));
+        let next = next.unwrap();
+        let (second, next) = chunk_at(&text, next, 1000);
+        assert_eq!(
+            second,
+            format!("{}\n\n{}", "b".repeat(600), "c".repeat(300))
+        );
+        assert_eq!(next, None);
+
+        // Without breaks, chunks are cut at the limit on a char boundary.
+        let text = "é".repeat(800);
+        let (first, next) = chunk_at(&text, 0, 1001);
+        assert_eq!(first.len(), 1000);
+        assert_eq!(next, Some(1000));
+    }
+}
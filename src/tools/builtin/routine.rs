@@ -78,7 +78,7 @@ impl Tool for RoutineCreateTool {
                 },
                 "prompt": {
                     "type": "string",
-                    "description": "The prompt/instructions for the routine"
+                    "description": "The prompt/instructions for the routine (not used by 'template' actions)"
                 },
                 "context_paths": {
                     "type": "array",
@@ -87,15 +87,24 @@ impl Tool for RoutineCreateTool {
                 },
                 "action_type": {
                     "type": "string",
-                    "enum": ["lightweight", "full_job"],
-                    "description": "Execution mode: 'lightweight' (single LLM call, default) or 'full_job' (multi-turn with tools)"
+                    "enum": ["lightweight", "full_job", "template"],
+                    "description": "Execution mode: 'lightweight' (single LLM call, default), 'full_job' (multi-turn with tools), or 'template' (start a job from a job template)"
+                },
+                "template": {
+                    "type": "string",
+                    "description": "Job template to run (for 'template' actions)"
+                },
+                "variables": {
+                    "type": "object",
+                    "additionalProperties": { "type": "string" },
+                    "description": "Template variable values (for 'template' actions)"
                 },
                 "cooldown_secs": {
                     "type": "integer",
                     "description": "Minimum seconds between fires (default: 300)"
                 }
             },
-            "required": ["name", "trigger_type", "prompt"]
+            "required": ["name", "trigger_type"]
         })
     }
 
@@ -115,8 +124,6 @@ impl Tool for RoutineCreateTool {
 
         let trigger_type = require_str(&params, "trigger_type")?;
 
-        let prompt = require_str(&params, "prompt")?;
-
         // Build trigger
         let trigger = match trigger_type {
             "cron" => {
@@ -188,15 +195,27 @@ impl Tool for RoutineCreateTool {
 
         let action = match action_type {
             "lightweight" => RoutineAction::Lightweight {
-                prompt: prompt.to_string(),
+                prompt: require_str(&params, "prompt")?.to_string(),
                 context_paths,
                 max_tokens: 4096,
             },
             "full_job" => RoutineAction::FullJob {
                 title: name.to_string(),
-                description: prompt.to_string(),
+                description: require_str(&params, "prompt")?.to_string(),
                 max_iterations: 10,
             },
+            "template" => RoutineAction::Template {
+                template: require_str(&params, "template")?.to_string(),
+                variables: params
+                    .get("variables")
+                    .and_then(|v| v.as_object())
+                    .map(|obj| {
+                        obj.iter()
+                            .filter_map(|(k, v)| v.as_str().map(|v| (k.clone(), v.to_string())))
+                            .collect()
+                    })
+                    .unwrap_or_default(),
+            },
             other => {
                 return Err(ToolError::InvalidParameters(format!(
                     "unknown action_type: {other}"
@@ -421,6 +440,11 @@ impl Tool for RoutineUpdateTool {
             match &mut routine.action {
                 RoutineAction::Lightweight { prompt: p, .. } => *p = prompt.to_string(),
                 RoutineAction::FullJob { description: d, .. } => *d = prompt.to_string(),
+                RoutineAction::Template { .. } => {
+                    return Err(ToolError::InvalidParameters(
+                        "template routines take their prompt from the template".to_string(),
+                    ));
+                }
             }
         }
 
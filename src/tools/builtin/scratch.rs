@@ -0,0 +1,176 @@
+//! Scratch buffer tool.
+//!
+//! Gives the model access to the job's [`ScratchBuffer`]: see what other
+//! tools stashed, read part of an entry, stash text of its own, or drop an
+//! entry once it's used. Tools that accept `stash_as` / `from_stash` move
+//! content in and out of the buffer without it entering the conversation.
+
+use std::time::Duration;
+
+use async_trait::async_trait;
+
+use crate::context::JobContext;
+use crate::tools::tool::{Tool, ToolError, ToolOutput, require_str};
+
+/// Default and largest slice returned by `read`, in bytes.
+const DEFAULT_READ: usize = 8_000;
+const MAX_READ: usize = 50_000;
+
+/// Tool for the per-job scratch buffer.
+pub struct ScratchTool;
+
+#[async_trait]
+impl Tool for ScratchTool {
+    fn name(&self) -> &str {
+        "scratch"
+    }
+
+    fn description(&self) -> &str {
+        "Scratch buffer for passing large intermediate results between tool calls without \
+         copying them into the conversation. Tools with a 'stash_as' parameter (read_url, \
+         http) store their output here and tools with 'from_stash' (memory_write) read it. \
+         Actions: 'list' entries, 'read' part of one, 'stash' text under a key, 'unstash' \
+         to remove an entry."
+    }
+
+    fn parameters_schema(&self) -> serde_json::Value {
+        serde_json::json!({
+            "type": "object",
+            "properties": {
+                "action": {
+                    "type": "string",
+                    "enum": ["list", "read", "stash", "unstash"],
+                    "description": "What to do"
+                },
+                "key": {
+                    "type": "string",
+                    "description": "Entry key (read, stash, unstash)"
+                },
+                "value": {
+                    "type": "string",
+                    "description": "Text to store (stash)"
+                },
+                "offset": {
+                    "type": "integer",
+                    "description": "Byte offset to start reading at (read; default 0)"
+                },
+                "max_chars": {
+                    "type": "integer",
+                    "description": "Most bytes to return (read; default 8000, max 50000)"
+                }
+            },
+            "required": ["action"]
+        })
+    }
+
+    async fn execute(
+        &self,
+        params: serde_json::Value,
+        ctx: &JobContext,
+    ) -> Result<ToolOutput, ToolError> {
+        let start = std::time::Instant::now();
+        let scratch = &ctx.scratch;
+
+        let result = match require_str(&params, "action")? {
+            "list" => serde_json::json!({ "entries": scratch.list() }),
+            "read" => {
+                let key = require_str(&params, "key")?;
+                let value = scratch.get(key).ok_or_else(|| {
+                    ToolError::InvalidParameters(format!("nothing stashed under '{}'", key))
+                })?;
+                let offset = params.get("offset").and_then(|v| v.as_u64()).unwrap_or(0) as usize;
+                let max = params
+                    .get("max_chars")
+                    .and_then(|v| v.as_u64())
+                    .map_or(DEFAULT_READ, |v| v as usize)
+                    .clamp(1, MAX_READ);
+                if offset > value.len() || !value.is_char_boundary(offset) {
+                    return Err(ToolError::InvalidParameters(format!(
+                        "offset {} is not a position in '{}'",
+                        offset, key
+                    )));
+                }
+                let mut end = (offset + max).min(value.len());
+                while !value.is_char_boundary(end) {
+                    end -= 1;
+                }
+                serde_json::json!({
+                    "key": key,
+                    "content": &value[offset..end],
+                    "offset": offset,
+                    "next_offset": (end < value.len()).then_some(end),
+                    "total_bytes": value.len(),
+                })
+            }
+            "stash" => {
+                let key = require_str(&params, "key")?;
+                let value = require_str(&params, "value")?;
+                scratch
+                    .stash(key, value)
+                    .map_err(|e| ToolError::InvalidParameters(e.to_string()))?;
+                serde_json::json!({ "stashed": key, "bytes": value.len() })
+            }
+            "unstash" => {
+                let key = require_str(&params, "key")?;
+                let value = scratch.unstash(key).ok_or_else(|| {
+                    ToolError::InvalidParameters(format!("nothing stashed under '{}'", key))
+                })?;
+                serde_json::json!({ "removed": key, "bytes": value.len() })
+            }
+            other => {
+                return Err(ToolError::InvalidParameters(format!(
+                    "unknown action '{}'",
+                    other
+                )));
+            }
+        };
+        Ok(ToolOutput::success(result, start.elapsed()))
+    }
+
+    fn estimated_duration(&self, _params: &serde_json::Value) -> Option<Duration> {
+        Some(Duration::from_millis(1))
+    }
+
+    fn requires_sanitization(&self) -> bool {
+        true // Entries hold raw tool output (web pages, API responses)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_scratch_round_trip() {
+        let ctx = JobContext::default();
+        // Another tool stashes a page.
+        ctx.scratch.stash("page", "é".repeat(10)).unwrap();
+
+        let tool = ScratchTool;
+        let output = tool
+            .execute(serde_json::json!({ "action": "list" }), &ctx)
+            .await
+            .unwrap();
+        assert_eq!(output.result["entries"][0]["key"], "page");
+
+        let output = tool
+            .execute(
+                serde_json::json!({ "action": "read", "key": "page", "max_chars": 5 }),
+                &ctx,
+            )
+            .await
+            .unwrap();
+        assert_eq!(output.result["content"], "éé");
+        assert_eq!(output.result["next_offset"], 4);
+
+        // Clones of the context see the same buffer.
+        let clone = ctx.clone();
+        tool.execute(
+            serde_json::json!({ "action": "unstash", "key": "page" }),
+            &clone,
+        )
+        .await
+        .unwrap();
+        assert!(!ctx.scratch.contains("page"));
+    }
+}
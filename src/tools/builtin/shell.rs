@@ -6,6 +6,7 @@
 //! - Timeout enforcement
 //! - Output capture and truncation
 //! - Blocked command patterns for safety
+//! - Environment variables resolved from `{{secret:NAME}}` placeholders
 //!
 //! # Execution Modes
 //!
@@ -18,7 +19,7 @@
 //! - Commands run directly on host with basic protections
 //! - Blocked command patterns are still enforced
 
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::path::{Path, PathBuf};
 use std::process::Stdio;
 use std::sync::{Arc, LazyLock};
@@ -30,6 +31,8 @@ use tokio::process::Command;
 
 use crate::context::JobContext;
 use crate::sandbox::{SandboxManager, SandboxPolicy};
+use crate::secrets::SecretsStore;
+use crate::secrets::inject::{self, InjectTarget, ResolvedSecrets, SecretBindings};
 use crate::tools::tool::{Tool, ToolDomain, ToolError, ToolOutput, require_str};
 
 /// Maximum output size before truncation (64KB).
@@ -138,6 +141,10 @@ pub struct ShellTool {
     sandbox: Option<Arc<SandboxManager>>,
     /// Sandbox policy to use when sandbox is available.
     sandbox_policy: SandboxPolicy,
+    /// Store used to resolve `{{secret:NAME}}` placeholders in `env` values.
+    secrets: Option<Arc<dyn SecretsStore + Send + Sync>>,
+    /// Commands each secret may be passed to.
+    bindings: SecretBindings,
 }
 
 impl std::fmt::Debug for ShellTool {
@@ -148,6 +155,7 @@ impl std::fmt::Debug for ShellTool {
             .field("allow_dangerous", &self.allow_dangerous)
             .field("sandbox", &self.sandbox.is_some())
             .field("sandbox_policy", &self.sandbox_policy)
+            .field("secrets", &self.secrets.is_some())
             .finish()
     }
 }
@@ -161,6 +169,8 @@ impl ShellTool {
             allow_dangerous: false,
             sandbox: None,
             sandbox_policy: SandboxPolicy::ReadOnly,
+            secrets: None,
+            bindings: SecretBindings::default(),
         }
     }
 
@@ -188,6 +198,65 @@ impl ShellTool {
         self
     }
 
+    /// Resolve `{{secret:NAME}}` placeholders in `env` values from this
+    /// store, for the commands each secret is bound to.
+    pub fn with_secrets(
+        mut self,
+        secrets: Arc<dyn SecretsStore + Send + Sync>,
+        bindings: SecretBindings,
+    ) -> Self {
+        self.secrets = Some(secrets);
+        self.bindings = bindings;
+        self
+    }
+
+    /// Build the command environment from the `env` parameter, swapping
+    /// secret placeholders for their decrypted values.
+    async fn resolve_env(
+        &self,
+        user_id: &str,
+        command: &str,
+        env: Option<&serde_json::Value>,
+        secrets: &mut ResolvedSecrets,
+    ) -> Result<HashMap<String, String>, ToolError> {
+        let Some(env) = env else {
+            return Ok(HashMap::new());
+        };
+        let env = env.as_object().ok_or_else(|| {
+            ToolError::InvalidParameters("'env' must be an object of strings".to_string())
+        })?;
+
+        let mut resolved = HashMap::with_capacity(env.len());
+        for (key, value) in env {
+            let value = value.as_str().ok_or_else(|| {
+                ToolError::InvalidParameters(format!("env var '{}' must be a string", key))
+            })?;
+            let value = if inject::has_placeholders(value) {
+                let store = self.secrets.as_ref().ok_or_else(|| {
+                    ToolError::ExecutionFailed(
+                        "env references a secret but no secrets store is configured".to_string(),
+                    )
+                })?;
+                secrets
+                    .resolve(
+                        store.as_ref(),
+                        &self.bindings,
+                        InjectTarget::Command(command),
+                        user_id,
+                        value,
+                    )
+                    .await
+                    .map_err(|e| {
+                        ToolError::InvalidParameters(format!("env var '{}': {}", key, e))
+                    })?
+            } else {
+                value.to_string()
+            };
+            resolved.insert(key.clone(), value);
+        }
+        Ok(resolved)
+    }
+
     /// Check if a command is blocked.
     fn is_blocked(&self, cmd: &str) -> Option<&'static str> {
         let normalized = cmd.to_lowercase();
@@ -216,16 +285,12 @@ impl ShellTool {
         cmd: &str,
         workdir: &Path,
         timeout: Duration,
+        env: HashMap<String, String>,
     ) -> Result<(String, i64), ToolError> {
         // Override sandbox config timeout if needed
         let result = tokio::time::timeout(timeout, async {
             sandbox
-                .execute_with_policy(
-                    cmd,
-                    workdir,
-                    self.sandbox_policy,
-                    std::collections::HashMap::new(),
-                )
+                .execute_with_policy(cmd, workdir, self.sandbox_policy, env)
                 .await
         })
         .await;
@@ -246,6 +311,7 @@ impl ShellTool {
         cmd: &str,
         workdir: &PathBuf,
         timeout: Duration,
+        env: HashMap<String, String>,
     ) -> Result<(String, i32), ToolError> {
         // Build command
         let mut command = if cfg!(target_os = "windows") {
@@ -260,6 +326,7 @@ impl ShellTool {
 
         command
             .current_dir(workdir)
+            .envs(env)
             .stdin(Stdio::null())
             .stdout(Stdio::piped())
             .stderr(Stdio::piped());
@@ -322,6 +389,7 @@ impl ShellTool {
         cmd: &str,
         workdir: Option<&str>,
         timeout: Option<u64>,
+        env: HashMap<String, String>,
     ) -> Result<(String, i64), ToolError> {
         // Check for blocked commands
         if let Some(reason) = self.is_blocked(cmd) {
@@ -347,12 +415,14 @@ impl ShellTool {
             && (sandbox.is_initialized() || sandbox.config().enabled)
         {
             return self
-                .execute_sandboxed(sandbox, cmd, &cwd, timeout_duration)
+                .execute_sandboxed(sandbox, cmd, &cwd, timeout_duration, env)
                 .await;
         }
 
         // Only execute directly when no sandbox was configured at all.
-        let (output, code) = self.execute_direct(cmd, &cwd, timeout_duration).await?;
+        let (output, code) = self
+            .execute_direct(cmd, &cwd, timeout_duration, env)
+            .await?;
         Ok((output, code as i64))
     }
 }
@@ -385,11 +455,16 @@ impl Tool for ShellTool {
                 },
                 "workdir": {
                     "type": "string",
-                    "description": "Working directory for the command (optional)"
+                    "description": "Working directory for the command (optional; defaults to the job's temp directory)"
                 },
                 "timeout": {
                     "type": "integer",
                     "description": "Timeout in seconds (optional, default 120)"
+                },
+                "env": {
+                    "type": "object",
+                    "additionalProperties": { "type": "string" },
+                    "description": "Extra environment variables (optional). Reference stored credentials as {{secret:NAME}} instead of pasting their values."
                 }
             },
             "required": ["command"]
@@ -399,21 +474,33 @@ impl Tool for ShellTool {
     async fn execute(
         &self,
         params: serde_json::Value,
-        _ctx: &JobContext,
+        ctx: &JobContext,
     ) -> Result<ToolOutput, ToolError> {
         let command = require_str(&params, "command")?;
 
-        let workdir = params.get("workdir").and_then(|v| v.as_str());
+        // Without an explicit or configured directory, jobs run in their
+        // own temp directory.
+        let workdir = params.get("workdir").and_then(|v| v.as_str()).or_else(|| {
+            if self.working_dir.is_some() {
+                None
+            } else {
+                ctx.temp_dir.as_deref().and_then(|d| d.to_str())
+            }
+        });
         let timeout = params.get("timeout").and_then(|v| v.as_u64());
+        let mut secrets = ResolvedSecrets::default();
+        let env = self
+            .resolve_env(&ctx.user_id, command, params.get("env"), &mut secrets)
+            .await?;
 
         let start = std::time::Instant::now();
-        let (output, exit_code) = self.execute_command(command, workdir, timeout).await?;
+        let (output, exit_code) = self.execute_command(command, workdir, timeout, env).await?;
         let duration = start.elapsed();
 
         let sandboxed = self.sandbox.is_some();
 
         let result = serde_json::json!({
-            "output": output,
+            "output": secrets.mask(&output),
             "exit_code": exit_code,
             "success": exit_code == 0,
             "sandboxed": sandboxed
@@ -480,6 +567,66 @@ mod tests {
         assert_eq!(result.result.get("exit_code").unwrap().as_i64().unwrap(), 0);
     }
 
+    #[tokio::test]
+    async fn test_env_resolves_secret_placeholders() {
+        use secrecy::SecretString;
+
+        use crate::secrets::{
+            CreateSecretParams, InMemorySecretsStore, SecretsCrypto, SecretsStore,
+        };
+
+        let key = "0123456789abcdef0123456789abcdef";
+        let crypto = Arc::new(SecretsCrypto::new(SecretString::from(key.to_string())).unwrap());
+        let store = Arc::new(InMemorySecretsStore::new(crypto));
+        let ctx = JobContext::default();
+        store
+            .create(&ctx.user_id, CreateSecretParams::new("token", "s3cr3t"))
+            .await
+            .unwrap();
+
+        let bindings = SecretBindings::parse("", "token=echo").unwrap();
+        let tool = ShellTool::new().with_secrets(store, bindings);
+        let result = tool
+            .execute(
+                serde_json::json!({
+                    "command": "echo $API_TOKEN",
+                    "env": {"API_TOKEN": "{{secret:token}}"}
+                }),
+                &ctx,
+            )
+            .await
+            .unwrap();
+        let output = result.result.get("output").unwrap().as_str().unwrap();
+        assert!(!output.contains("s3cr3t"));
+        assert!(output.contains("[REDACTED]"));
+
+        // The secret isn't bound to other programs.
+        let err = tool
+            .execute(
+                serde_json::json!({
+                    "command": "printenv API_TOKEN",
+                    "env": {"API_TOKEN": "{{secret:token}}"}
+                }),
+                &ctx,
+            )
+            .await
+            .unwrap_err();
+        assert!(matches!(err, ToolError::InvalidParameters(_)));
+
+        // Without a store, placeholders are rejected rather than passed through.
+        let err = ShellTool::new()
+            .execute(
+                serde_json::json!({
+                    "command": "echo $API_TOKEN",
+                    "env": {"API_TOKEN": "{{secret:token}}"}
+                }),
+                &ctx,
+            )
+            .await
+            .unwrap_err();
+        assert!(matches!(err, ToolError::ExecutionFailed(_)));
+    }
+
     #[test]
     fn test_blocked_commands() {
         let tool = ShellTool::new();
@@ -0,0 +1,256 @@
+//! Tools exposing skills (see [`crate::skills`]).
+//!
+//! - `skill` loads a skill's guidance into the conversation on demand
+//! - `<skill>_<command>` runs a command a skill declares, via the shell tool
+
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+
+use crate::context::JobContext;
+use crate::skills::{SkillCommand, SkillRegistry};
+use crate::tools::builtin::ShellTool;
+use crate::tools::tool::{Tool, ToolDomain, ToolError, ToolOutput, require_str};
+
+/// Tool that returns a skill's full guidance.
+pub struct SkillTool {
+    skills: Arc<SkillRegistry>,
+    description: String,
+}
+
+impl SkillTool {
+    /// Create the tool. Its description lists every skill in `skills`.
+    pub fn new(skills: Arc<SkillRegistry>) -> Self {
+        let mut description = String::from(
+            "Load the instructions for a skill before doing a task it covers. \
+             Skills explain how to use tools for specific jobs. Available skills:",
+        );
+        for skill in skills.iter() {
+            description.push_str(&format!("\n- {}: {}", skill.name, skill.description));
+        }
+        Self {
+            skills,
+            description,
+        }
+    }
+}
+
+#[async_trait]
+impl Tool for SkillTool {
+    fn name(&self) -> &str {
+        "skill"
+    }
+
+    fn description(&self) -> &str {
+        &self.description
+    }
+
+    fn parameters_schema(&self) -> serde_json::Value {
+        let names: Vec<&str> = self.skills.iter().map(|s| s.name.as_str()).collect();
+        serde_json::json!({
+            "type": "object",
+            "properties": {
+                "name": {
+                    "type": "string",
+                    "enum": names,
+                    "description": "Skill to load"
+                }
+            },
+            "required": ["name"]
+        })
+    }
+
+    async fn execute(
+        &self,
+        params: serde_json::Value,
+        _ctx: &JobContext,
+    ) -> Result<ToolOutput, ToolError> {
+        let start = std::time::Instant::now();
+        let name = require_str(&params, "name")?;
+        let skill = self
+            .skills
+            .get(name)
+            .ok_or_else(|| ToolError::InvalidParameters(format!("unknown skill '{}'", name)))?;
+
+        let commands: Vec<serde_json::Value> = skill
+            .commands
+            .iter()
+            .map(|c| {
+                serde_json::json!({
+                    "tool": command_tool_name(&skill.name, c),
+                    "command": c.template,
+                })
+            })
+            .collect();
+        Ok(ToolOutput::success(
+            serde_json::json!({
+                "name": skill.name,
+                "guidance": skill.guidance,
+                "commands": commands,
+            }),
+            start.elapsed(),
+        ))
+    }
+
+    fn requires_sanitization(&self) -> bool {
+        false // Skills are installed by the operator or written to the workspace
+    }
+}
+
+/// Tool running one command declared by a skill.
+pub struct SkillCommandTool {
+    name: String,
+    description: String,
+    command: SkillCommand,
+    workdir: Option<String>,
+    shell: Arc<ShellTool>,
+}
+
+impl SkillCommandTool {
+    /// Create tools for every command declared in `skills`.
+    pub fn for_registry(skills: &SkillRegistry, shell: Arc<ShellTool>) -> Vec<Self> {
+        skills
+            .iter()
+            .flat_map(|skill| {
+                let shell = Arc::clone(&shell);
+                skill.commands.iter().map(move |command| Self {
+                    name: command_tool_name(&skill.name, command),
+                    description: format!(
+                        "Run `{}` (from the {} skill: {}). Load the skill first for usage notes.",
+                        command.template, skill.name, skill.description
+                    ),
+                    command: command.clone(),
+                    workdir: skill.dir().map(|d| d.display().to_string()),
+                    shell: Arc::clone(&shell),
+                })
+            })
+            .collect()
+    }
+}
+
+#[async_trait]
+impl Tool for SkillCommandTool {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn description(&self) -> &str {
+        &self.description
+    }
+
+    fn parameters_schema(&self) -> serde_json::Value {
+        let params = self.command.params();
+        let properties: serde_json::Map<String, serde_json::Value> = params
+            .iter()
+            .map(|p| (p.clone(), serde_json::json!({ "type": "string" })))
+            .collect();
+        serde_json::json!({
+            "type": "object",
+            "properties": properties,
+            "required": params,
+        })
+    }
+
+    async fn execute(
+        &self,
+        params: serde_json::Value,
+        ctx: &JobContext,
+    ) -> Result<ToolOutput, ToolError> {
+        let mut args = BTreeMap::new();
+        for param in self.command.params() {
+            let value = match params.get(&param) {
+                Some(serde_json::Value::String(s)) => s.clone(),
+                Some(serde_json::Value::Number(n)) => n.to_string(),
+                Some(serde_json::Value::Bool(b)) => b.to_string(),
+                _ => {
+                    return Err(ToolError::InvalidParameters(format!(
+                        "missing '{}' parameter",
+                        param
+                    )));
+                }
+            };
+            args.insert(param, value);
+        }
+        let command = self
+            .command
+            .render(&args)
+            .map_err(ToolError::InvalidParameters)?;
+
+        let mut shell_params = serde_json::json!({ "command": command });
+        if let Some(dir) = &self.workdir {
+            shell_params["workdir"] = serde_json::Value::String(dir.clone());
+        }
+        self.shell.execute(shell_params, ctx).await
+    }
+
+    fn requires_approval(&self) -> bool {
+        true // Runs a shell command, same as the shell tool
+    }
+
+    fn domain(&self) -> ToolDomain {
+        ToolDomain::Container
+    }
+}
+
+/// Tool name for `command` of skill `skill`.
+fn command_tool_name(skill: &str, command: &SkillCommand) -> String {
+    format!("{}_{}", skill, command.name).replace('-', "_")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::skills::{Skill, SkillSource};
+
+    fn registry() -> SkillRegistry {
+        let mut skills = SkillRegistry::new();
+        skills.insert(
+            Skill::parse(
+                "---\ndescription: Say things\ncommands:\n  - \"say: echo {text}\"\n---\nUse say.",
+                "echo-kit",
+                SkillSource::Workspace("skills/echo-kit/SKILL.md".to_string()),
+            )
+            .unwrap(),
+        );
+        skills
+    }
+
+    #[tokio::test]
+    async fn test_skill_tool_loads_guidance() {
+        let tool = SkillTool::new(Arc::new(registry()));
+        assert!(tool.description().contains("- echo-kit: Say things"));
+
+        let ctx = JobContext::default();
+        let output = tool
+            .execute(serde_json::json!({ "name": "echo-kit" }), &ctx)
+            .await
+            .unwrap();
+        assert_eq!(output.result["guidance"], "Use say.");
+        assert_eq!(output.result["commands"][0]["tool"], "echo_kit_say");
+        assert!(
+            tool.execute(serde_json::json!({ "name": "nope" }), &ctx)
+                .await
+                .is_err()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_command_tool_runs_through_shell() {
+        let tools = SkillCommandTool::for_registry(&registry(), Arc::new(ShellTool::new()));
+        assert_eq!(tools.len(), 1);
+        let tool = &tools[0];
+        assert_eq!(tool.name(), "echo_kit_say");
+        assert_eq!(tool.parameters_schema()["required"][0], "text");
+
+        let ctx = JobContext::default();
+        let output = tool
+            .execute(serde_json::json!({ "text": "hi; echo injected" }), &ctx)
+            .await
+            .unwrap();
+        assert_eq!(
+            output.result["output"].as_str().unwrap().trim(),
+            "hi; echo injected"
+        );
+    }
+}
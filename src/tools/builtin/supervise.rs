@@ -0,0 +1,84 @@
+//! Supervisor delegation tool.
+//!
+//! Hands a request to the [`Supervisor`], which splits it across the
+//! registered worker agents and returns their combined answer.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+
+use crate::agent::supervisor::{SUPERVISE_TOOL_NAME, Supervisor};
+use crate::context::JobContext;
+use crate::tools::tool::{Tool, ToolError, ToolOutput, require_str};
+
+/// Tool for delegating a request to worker agents.
+pub struct SuperviseTool {
+    supervisor: Arc<Supervisor>,
+    timeout: Duration,
+}
+
+impl SuperviseTool {
+    pub fn new(supervisor: Arc<Supervisor>) -> Self {
+        Self {
+            supervisor,
+            timeout: Duration::from_secs(900),
+        }
+    }
+
+    /// How long a whole supervised run (plan, workers, synthesis) may take.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+}
+
+#[async_trait]
+impl Tool for SuperviseTool {
+    fn name(&self) -> &str {
+        SUPERVISE_TOOL_NAME
+    }
+
+    fn description(&self) -> &str {
+        "Delegate a multi-part request to the team of registered agents. The request is split \
+         into sub-tasks, each handled by the best-suited agent, and their results are combined \
+         into one answer. Use for requests that need several specialties."
+    }
+
+    fn parameters_schema(&self) -> serde_json::Value {
+        serde_json::json!({
+            "type": "object",
+            "properties": {
+                "request": {
+                    "type": "string",
+                    "description": "The full request, with all context the agents need"
+                }
+            },
+            "required": ["request"]
+        })
+    }
+
+    async fn execute(
+        &self,
+        params: serde_json::Value,
+        _ctx: &JobContext,
+    ) -> Result<ToolOutput, ToolError> {
+        let start = std::time::Instant::now();
+        let request = require_str(&params, "request")?;
+
+        let outcome = self
+            .supervisor
+            .run(request)
+            .await
+            .map_err(|e| ToolError::ExecutionFailed(e.to_string()))?;
+
+        let cost = outcome.cost;
+        let result =
+            serde_json::to_value(outcome).map_err(|e| ToolError::ExecutionFailed(e.to_string()))?;
+        Ok(ToolOutput::success(result, start.elapsed()).with_cost(cost))
+    }
+
+    fn execution_timeout(&self) -> Duration {
+        self.timeout
+    }
+}
@@ -0,0 +1,114 @@
+//! Job template tool.
+//!
+//! Starts a job from a job template in the workspace (see
+//! [`crate::agent::template`]), or lists the templates when called without
+//! a name.
+
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+
+use crate::agent::template::{TemplateError, TemplateLauncher};
+use crate::context::JobContext;
+use crate::tools::tool::{Tool, ToolError, ToolOutput};
+
+/// Tool for launching jobs from templates.
+pub struct RunTemplateTool {
+    launcher: Arc<TemplateLauncher>,
+}
+
+impl RunTemplateTool {
+    pub fn new(launcher: Arc<TemplateLauncher>) -> Self {
+        Self { launcher }
+    }
+}
+
+#[async_trait]
+impl Tool for RunTemplateTool {
+    fn name(&self) -> &str {
+        "run_template"
+    }
+
+    fn description(&self) -> &str {
+        "Start a background job from a saved job template (templates/<name>.md in memory). \
+         Call without a name to list the templates and the variables each one takes."
+    }
+
+    fn parameters_schema(&self) -> serde_json::Value {
+        serde_json::json!({
+            "type": "object",
+            "properties": {
+                "name": {
+                    "type": "string",
+                    "description": "Template to run. Omit to list templates."
+                },
+                "variables": {
+                    "type": "object",
+                    "additionalProperties": { "type": "string" },
+                    "description": "Values for the template's variables"
+                }
+            }
+        })
+    }
+
+    async fn execute(
+        &self,
+        params: serde_json::Value,
+        ctx: &JobContext,
+    ) -> Result<ToolOutput, ToolError> {
+        let start = std::time::Instant::now();
+
+        let Some(name) = params.get("name").and_then(|v| v.as_str()) else {
+            let templates = self
+                .launcher
+                .templates()
+                .list()
+                .await
+                .map_err(|e| ToolError::ExecutionFailed(e.to_string()))?;
+            let listed: Vec<serde_json::Value> = templates
+                .iter()
+                .map(|t| {
+                    serde_json::json!({
+                        "name": t.name,
+                        "description": t.description,
+                        "required_variables": t.required_variables(),
+                        "defaults": t.defaults,
+                    })
+                })
+                .collect();
+            return Ok(ToolOutput::success(
+                serde_json::json!({ "templates": listed }),
+                start.elapsed(),
+            ));
+        };
+
+        let mut variables = BTreeMap::new();
+        if let Some(obj) = params.get("variables").and_then(|v| v.as_object()) {
+            for (key, value) in obj {
+                let value = match value {
+                    serde_json::Value::String(s) => s.clone(),
+                    other => other.to_string(),
+                };
+                variables.insert(key.clone(), value);
+            }
+        }
+
+        match self.launcher.launch(&ctx.user_id, name, &variables).await {
+            Ok(job_id) => Ok(ToolOutput::success(
+                serde_json::json!({
+                    "job_id": job_id.to_string(),
+                    "template": name,
+                    "status": "scheduled",
+                }),
+                start.elapsed(),
+            )),
+            Err(
+                e @ (TemplateError::NotFound(_)
+                | TemplateError::MissingVariable { .. }
+                | TemplateError::UnknownVariable { .. }),
+            ) => Err(ToolError::InvalidParameters(e.to_string())),
+            Err(e) => Err(ToolError::ExecutionFailed(e.to_string())),
+        }
+    }
+}
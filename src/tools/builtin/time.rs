@@ -48,7 +48,7 @@ impl Tool for TimeTool {
     async fn execute(
         &self,
         params: serde_json::Value,
-        _ctx: &JobContext,
+        ctx: &JobContext,
     ) -> Result<ToolOutput, ToolError> {
         let start = std::time::Instant::now();
 
@@ -60,7 +60,8 @@ impl Tool for TimeTool {
                 serde_json::json!({
                     "iso": now.to_rfc3339(),
                     "unix": now.timestamp(),
-                    "unix_millis": now.timestamp_millis()
+                    "unix_millis": now.timestamp_millis(),
+                    "user_timezone": ctx.timezone,
                 })
             }
             "parse" => {
@@ -0,0 +1,380 @@
+//! Weather tool backed by Open-Meteo (no API key needed).
+//!
+//! Without an explicit `location` the tool uses the Location field of
+//! USER.md, so heartbeat checks can ask for "the weather" without knowing
+//! where the user is. Reports are cached per place for [`CACHE_TTL`];
+//! heartbeats rotating through their checklist several times an hour get
+//! the cached report instead of refetching.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use async_trait::async_trait;
+use reqwest::Client;
+use tokio::sync::Mutex;
+
+use crate::context::JobContext;
+use crate::tools::tool::{Tool, ToolError, ToolOutput};
+use crate::workspace::{Profile, Workspace};
+
+const GEOCODING_URL: &str = "https://geocoding-api.open-meteo.com/v1/search";
+const FORECAST_URL: &str = "https://api.open-meteo.com/v1/forecast";
+
+/// How long a report is reused before fetching again.
+const CACHE_TTL: Duration = Duration::from_secs(30 * 60);
+
+/// Longest forecast available, in days.
+const MAX_DAYS: u64 = 7;
+
+/// Tool reporting current weather and a daily forecast.
+pub struct WeatherTool {
+    client: Client,
+    /// Workspace whose USER.md supplies the default location.
+    workspace: Option<Arc<Workspace>>,
+    /// Reports by (lowercased location, days), with when they were fetched.
+    cache: Mutex<HashMap<(String, u64), (Instant, serde_json::Value)>>,
+}
+
+impl WeatherTool {
+    pub fn new() -> Self {
+        let client = Client::builder()
+            .timeout(Duration::from_secs(15))
+            .build()
+            .expect("Failed to create HTTP client");
+        Self {
+            client,
+            workspace: None,
+            cache: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Default to the location stored in this workspace's USER.md.
+    pub fn with_workspace(mut self, workspace: Arc<Workspace>) -> Self {
+        self.workspace = Some(workspace);
+        self
+    }
+
+    /// The user's stored location, if any.
+    async fn stored_location(&self) -> Option<String> {
+        let workspace = self.workspace.as_ref()?;
+        let mut profile = workspace.profile(Profile::User).await.ok()?;
+        profile.remove("location")
+    }
+
+    async fn get_json(
+        &self,
+        url: &str,
+        query: &[(&str, String)],
+    ) -> Result<serde_json::Value, ToolError> {
+        let response = self
+            .client
+            .get(url)
+            .query(query)
+            .send()
+            .await
+            .map_err(|e| ToolError::ExternalService(format!("weather request failed: {}", e)))?;
+        if !response.status().is_success() {
+            return Err(ToolError::ExternalService(format!(
+                "weather service returned {}",
+                response.status()
+            )));
+        }
+        response
+            .json()
+            .await
+            .map_err(|e| ToolError::ExternalService(format!("invalid weather response: {}", e)))
+    }
+
+    /// Fetch a fresh report for `location`.
+    async fn fetch(&self, location: &str, days: u64) -> Result<serde_json::Value, ToolError> {
+        let places = self
+            .get_json(
+                GEOCODING_URL,
+                &[
+                    ("name", location.to_string()),
+                    ("count", "1".to_string()),
+                    ("format", "json".to_string()),
+                ],
+            )
+            .await?;
+        let place = places
+            .get("results")
+            .and_then(|r| r.get(0))
+            .ok_or_else(|| {
+                ToolError::ExecutionFailed(format!("unknown location '{}'", location))
+            })?;
+        let (Some(latitude), Some(longitude)) = (
+            place.get("latitude").and_then(|v| v.as_f64()),
+            place.get("longitude").and_then(|v| v.as_f64()),
+        ) else {
+            return Err(ToolError::ExternalService(
+                "geocoding result has no coordinates".to_string(),
+            ));
+        };
+
+        let forecast = self
+            .get_json(
+                FORECAST_URL,
+                &[
+                    ("latitude", latitude.to_string()),
+                    ("longitude", longitude.to_string()),
+                    (
+                        "current",
+                        "temperature_2m,apparent_temperature,relative_humidity_2m,\
+                         precipitation,weather_code,wind_speed_10m"
+                            .to_string(),
+                    ),
+                    (
+                        "daily",
+                        "weather_code,temperature_2m_max,temperature_2m_min,\
+                         precipitation_probability_max"
+                            .to_string(),
+                    ),
+                    ("timezone", "auto".to_string()),
+                    ("forecast_days", days.to_string()),
+                ],
+            )
+            .await?;
+        Ok(report(place, &forecast))
+    }
+}
+
+impl Default for WeatherTool {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The report returned to the model, from a geocoding `place` and an
+/// Open-Meteo `forecast`.
+fn report(place: &serde_json::Value, forecast: &serde_json::Value) -> serde_json::Value {
+    let name = [place.get("name"), place.get("admin1"), place.get("country")]
+        .into_iter()
+        .flatten()
+        .filter_map(|v| v.as_str())
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    let current = forecast.get("current").map(|c| {
+        serde_json::json!({
+            "time": c.get("time"),
+            "conditions": c.get("weather_code").and_then(|v| v.as_u64()).map(describe_code),
+            "temperature_c": c.get("temperature_2m"),
+            "feels_like_c": c.get("apparent_temperature"),
+            "humidity_pct": c.get("relative_humidity_2m"),
+            "precipitation_mm": c.get("precipitation"),
+            "wind_kmh": c.get("wind_speed_10m"),
+        })
+    });
+
+    let daily = forecast.get("daily");
+    let column = |key: &str, i: usize| {
+        daily
+            .and_then(|d| d.get(key))
+            .and_then(|v| v.get(i))
+            .cloned()
+            .unwrap_or(serde_json::Value::Null)
+    };
+    let day_count = daily
+        .and_then(|d| d.get("time"))
+        .and_then(|t| t.as_array())
+        .map_or(0, |t| t.len());
+    let days: Vec<serde_json::Value> = (0..day_count)
+        .map(|i| {
+            serde_json::json!({
+                "date": column("time", i),
+                "conditions": column("weather_code", i).as_u64().map(describe_code),
+                "min_c": column("temperature_2m_min", i),
+                "max_c": column("temperature_2m_max", i),
+                "precipitation_chance_pct": column("precipitation_probability_max", i),
+            })
+        })
+        .collect();
+
+    serde_json::json!({
+        "location": name,
+        "timezone": forecast.get("timezone"),
+        "current": current,
+        "daily": days,
+    })
+}
+
+/// Plain-words description of a WMO weather code.
+fn describe_code(code: u64) -> &'static str {
+    match code {
+        0 => "clear sky",
+        1 => "mainly clear",
+        2 => "partly cloudy",
+        3 => "overcast",
+        45 | 48 => "fog",
+        51 | 53 | 55 => "drizzle",
+        56 | 57 => "freezing drizzle",
+        61 => "light rain",
+        63 => "rain",
+        65 => "heavy rain",
+        66 | 67 => "freezing rain",
+        71 => "light snow",
+        73 => "snow",
+        75 => "heavy snow",
+        77 => "snow grains",
+        80..=82 => "rain showers",
+        85 | 86 => "snow showers",
+        95 => "thunderstorm",
+        96 | 99 => "thunderstorm with hail",
+        _ => "unknown",
+    }
+}
+
+#[async_trait]
+impl Tool for WeatherTool {
+    fn name(&self) -> &str {
+        "weather"
+    }
+
+    fn description(&self) -> &str {
+        "Current weather and a daily forecast for a place. Without 'location' it uses the \
+         Location field of USER.md. Results are cached for 30 minutes, so repeated checks \
+         are cheap."
+    }
+
+    fn parameters_schema(&self) -> serde_json::Value {
+        serde_json::json!({
+            "type": "object",
+            "properties": {
+                "location": {
+                    "type": "string",
+                    "description": "City or place name (default: the user's location from USER.md)"
+                },
+                "days": {
+                    "type": "integer",
+                    "description": "Days of forecast, 1-7 (default 1)",
+                    "minimum": 1,
+                    "maximum": MAX_DAYS
+                }
+            }
+        })
+    }
+
+    async fn execute(
+        &self,
+        params: serde_json::Value,
+        _ctx: &JobContext,
+    ) -> Result<ToolOutput, ToolError> {
+        let start = std::time::Instant::now();
+
+        let location = match params
+            .get("location")
+            .and_then(|v| v.as_str())
+            .map(str::trim)
+            .filter(|l| !l.is_empty())
+        {
+            Some(location) => location.to_string(),
+            None => self.stored_location().await.ok_or_else(|| {
+                ToolError::InvalidParameters(
+                    "no 'location' given and USER.md has no Location; ask the user where they \
+                     are and record it"
+                        .to_string(),
+                )
+            })?,
+        };
+        let days = params
+            .get("days")
+            .and_then(|v| v.as_u64())
+            .unwrap_or(1)
+            .clamp(1, MAX_DAYS);
+
+        let key = (location.to_lowercase(), days);
+        if let Some((fetched, cached)) = self.cache.lock().await.get(&key)
+            && fetched.elapsed() < CACHE_TTL
+        {
+            let mut result = cached.clone();
+            result["cached"] = serde_json::Value::Bool(true);
+            return Ok(ToolOutput::success(result, start.elapsed()));
+        }
+
+        let result = self.fetch(&location, days).await?;
+        let mut cache = self.cache.lock().await;
+        cache.retain(|_, (fetched, _)| fetched.elapsed() < CACHE_TTL);
+        cache.insert(key, (Instant::now(), result.clone()));
+        Ok(ToolOutput::success(result, start.elapsed()))
+    }
+
+    fn requires_sanitization(&self) -> bool {
+        true // External API content
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_report_from_open_meteo() {
+        let place = serde_json::json!({
+            "name": "Lisbon", "admin1": "Lisbon", "country": "Portugal",
+            "latitude": 38.72, "longitude": -9.13
+        });
+        let forecast = serde_json::json!({
+            "timezone": "Europe/Lisbon",
+            "current": {
+                "time": "2026-10-15T09:00", "temperature_2m": 19.4,
+                "apparent_temperature": 18.9, "relative_humidity_2m": 71,
+                "precipitation": 0.0, "weather_code": 2, "wind_speed_10m": 12.3
+            },
+            "daily": {
+                "time": ["2026-10-15", "2026-10-16"],
+                "weather_code": [2, 63],
+                "temperature_2m_max": [23.1, 20.0],
+                "temperature_2m_min": [15.2, 14.8],
+                "precipitation_probability_max": [5, 80]
+            }
+        });
+
+        let report = report(&place, &forecast);
+        assert_eq!(report["location"], "Lisbon, Lisbon, Portugal");
+        assert_eq!(report["current"]["conditions"], "partly cloudy");
+        assert_eq!(report["current"]["temperature_c"], 19.4);
+        assert_eq!(report["daily"].as_array().unwrap().len(), 2);
+        assert_eq!(report["daily"][1]["conditions"], "rain");
+        assert_eq!(report["daily"][1]["precipitation_chance_pct"], 80);
+    }
+
+    #[cfg(feature = "libsql")]
+    #[tokio::test]
+    async fn test_location_defaults_to_user_profile() {
+        use std::collections::BTreeMap;
+
+        use crate::db::libsql_backend::test_db;
+
+        let (db, _dir) = test_db().await;
+        let workspace = Arc::new(Workspace::new_with_db("default", db));
+        let tool = WeatherTool::new().with_workspace(Arc::clone(&workspace));
+
+        let err = tool
+            .execute(serde_json::json!({}), &JobContext::default())
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("USER.md has no Location"));
+
+        let values = BTreeMap::from([("location".to_string(), "Lisbon".to_string())]);
+        workspace
+            .update_profile(Profile::User, &values)
+            .await
+            .unwrap();
+        assert_eq!(tool.stored_location().await.as_deref(), Some("Lisbon"));
+
+        // A cached report is served without a request.
+        let cached = serde_json::json!({ "location": "Lisbon, Portugal" });
+        tool.cache
+            .lock()
+            .await
+            .insert(("lisbon".to_string(), 1), (Instant::now(), cached));
+        let output = tool
+            .execute(serde_json::json!({}), &JobContext::default())
+            .await
+            .unwrap();
+        assert_eq!(output.result["location"], "Lisbon, Portugal");
+        assert_eq!(output.result["cached"], true);
+    }
+}
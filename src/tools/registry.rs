@@ -5,19 +5,26 @@ use std::sync::Arc;
 
 use tokio::sync::RwLock;
 
+use crate::config::{GitHubConfig, HomeAssistantConfig};
+use crate::contacts::ContactBook;
 use crate::context::ContextManager;
 use crate::db::Database;
 use crate::extensions::ExtensionManager;
 use crate::llm::{LlmProvider, ToolDefinition};
 use crate::orchestrator::job_manager::ContainerJobManager;
-use crate::safety::SafetyLayer;
+use crate::safety::{Permissions, SafetyLayer, tool_matches};
 use crate::secrets::SecretsStore;
+use crate::secrets::inject::SecretBindings;
+use crate::skills::SkillRegistry;
 use crate::tools::builder::{BuildSoftwareTool, BuilderConfig, LlmSoftwareBuilder};
 use crate::tools::builtin::{
-    ApplyPatchTool, CancelJobTool, CreateJobTool, EchoTool, HttpTool, JobStatusTool, JsonTool,
-    ListDirTool, ListJobsTool, MemoryReadTool, MemorySearchTool, MemoryTreeTool, MemoryWriteTool,
-    ReadFileTool, ShellTool, TimeTool, ToolActivateTool, ToolAuthTool, ToolInstallTool,
-    ToolListTool, ToolRemoveTool, ToolSearchTool, WriteFileTool,
+    ApplyPatchTool, BootstrapTool, CancelJobTool, CreateJobTool, EchoTool, GitHubTool,
+    HomeAssistantTool, HttpTool, JobStatusTool, JsonTool, ListDirTool, ListJobsTool,
+    MemoryEditTool, MemoryGraphTool, MemoryLintTool, MemoryMkdirTool, MemoryPinTool,
+    MemoryReadTool, MemorySearchTool, MemoryTreeTool, MemoryWriteTool, PersonaTool, ReadFileTool,
+    ReadUrlTool, ScratchTool, ShellTool, SkillCommandTool, SkillTool, SuperviseTool, TimeTool,
+    ToolActivateTool, ToolAuthTool, ToolInstallTool, ToolListTool, ToolRemoveTool, ToolSearchTool,
+    WeatherTool, WriteFileTool,
 };
 use crate::tools::tool::{Tool, ToolDomain};
 use crate::tools::wasm::{
@@ -25,6 +32,8 @@ use crate::tools::wasm::{
     WasmToolStore, WasmToolWrapper,
 };
 use crate::workspace::Workspace;
+use crate::workspace::conflicts::ConflictChecker;
+use crate::workspace::graph::KnowledgeGraph;
 
 /// Names of built-in tools that cannot be shadowed by dynamic registrations.
 /// This prevents a dynamically built or installed tool from replacing a
@@ -34,6 +43,10 @@ const PROTECTED_TOOL_NAMES: &[&str] = &[
     "time",
     "json",
     "http",
+    "read_url",
+    "scratch",
+    "github",
+    "home_assistant",
     "shell",
     "read_file",
     "write_file",
@@ -41,8 +54,11 @@ const PROTECTED_TOOL_NAMES: &[&str] = &[
     "apply_patch",
     "memory_search",
     "memory_write",
+    "memory_edit",
     "memory_read",
     "memory_tree",
+    "memory_mkdir",
+    "memory_lint",
     "create_job",
     "list_jobs",
     "job_status",
@@ -59,6 +75,14 @@ const PROTECTED_TOOL_NAMES: &[&str] = &[
     "routine_update",
     "routine_delete",
     "routine_history",
+    "contact_create",
+    "contact_list",
+    "contact_update",
+    "contact_delete",
+    "contact_resolve",
+    "skill",
+    "bootstrap",
+    "weather",
 ];
 
 /// Registry of available tools.
@@ -66,6 +90,16 @@ pub struct ToolRegistry {
     tools: RwLock<HashMap<String, Arc<dyn Tool>>>,
     /// Tracks which names were registered as built-in (protected from shadowing).
     builtin_names: RwLock<std::collections::HashSet<String>>,
+    /// Secrets store handed to built-in tools that resolve `{{secret:NAME}}`.
+    secrets_store: Option<Arc<dyn SecretsStore + Send + Sync>>,
+    /// Hosts and commands each secret may be injected into.
+    secret_bindings: SecretBindings,
+    /// Per-user tool permissions checked by the dispatchers.
+    permissions: Option<Arc<Permissions>>,
+    /// Tools switched off for everyone (`AGENT_DISABLED_TOOLS`); reloadable.
+    disabled: std::sync::RwLock<std::collections::HashSet<String>>,
+    /// Tool name patterns the running agent may use (`*` for all).
+    toolset: std::sync::RwLock<Vec<String>>,
 }
 
 impl ToolRegistry {
@@ -74,9 +108,75 @@ impl ToolRegistry {
         Self {
             tools: RwLock::new(HashMap::new()),
             builtin_names: RwLock::new(std::collections::HashSet::new()),
+            secrets_store: None,
+            secret_bindings: SecretBindings::default(),
+            permissions: None,
+            disabled: std::sync::RwLock::new(std::collections::HashSet::new()),
+            toolset: std::sync::RwLock::new(vec!["*".to_string()]),
+        }
+    }
+
+    /// Let built-in tools (http, shell) inject secrets from this store,
+    /// each only where `bindings` allows.
+    ///
+    /// Call before `register_builtin_tools()` / `register_dev_tools()`.
+    pub fn with_secrets_store(
+        mut self,
+        store: Arc<dyn SecretsStore + Send + Sync>,
+        bindings: SecretBindings,
+    ) -> Self {
+        self.secrets_store = Some(store);
+        self.secret_bindings = bindings;
+        self
+    }
+
+    /// Restrict which tools each user may call.
+    pub fn with_permissions(mut self, permissions: Arc<Permissions>) -> Self {
+        self.permissions = Some(permissions);
+        self
+    }
+
+    /// The permissions this registry enforces, if any.
+    pub fn permissions(&self) -> Option<&Arc<Permissions>> {
+        self.permissions.as_ref()
+    }
+
+    /// Replace the set of disabled tools.
+    pub fn set_disabled_tools(&self, names: impl IntoIterator<Item = String>) {
+        if let Ok(mut disabled) = self.disabled.write() {
+            *disabled = names.into_iter().collect();
         }
     }
 
+    /// Replace the running agent's toolset (`*`, `prefix*`, or exact names).
+    pub fn set_toolset(&self, patterns: impl IntoIterator<Item = String>) {
+        if let Ok(mut toolset) = self.toolset.write() {
+            *toolset = patterns.into_iter().collect();
+        }
+    }
+
+    /// Whether `tool_name` is switched off for everyone or outside the
+    /// agent's toolset.
+    pub fn is_disabled(&self, tool_name: &str) -> bool {
+        self.disabled
+            .read()
+            .is_ok_and(|disabled| disabled.contains(tool_name))
+            || self
+                .toolset
+                .read()
+                .is_ok_and(|toolset| !toolset.iter().any(|p| tool_matches(p, tool_name)))
+    }
+
+    /// Whether `user_id` may call `tool_name`: the tool isn't disabled and
+    /// the permissions (if any) allow it.
+    pub fn is_allowed(&self, user_id: &str, tool_name: &str) -> bool {
+        !self.is_disabled(tool_name)
+            && self
+                .permissions
+                .as_ref()
+                .is_none_or(|p| p.allows_tool(user_id, tool_name))
+    }
+
     /// Register a tool. Rejects dynamic tools that try to shadow a built-in name.
     pub async fn register(&self, tool: Arc<dyn Tool>) {
         let name = tool.name().to_string();
@@ -150,6 +250,13 @@ impl ToolRegistry {
             .collect()
     }
 
+    /// Get tool definitions for the tools `user_id` may call.
+    pub async fn tool_definitions_for_user(&self, user_id: &str) -> Vec<ToolDefinition> {
+        let mut definitions = self.tool_definitions().await;
+        definitions.retain(|def| self.is_allowed(user_id, &def.name));
+        definitions
+    }
+
     /// Get tool definitions for specific tools.
     pub async fn tool_definitions_for(&self, names: &[&str]) -> Vec<ToolDefinition> {
         let tools = self.tools.read().await;
@@ -169,7 +276,15 @@ impl ToolRegistry {
         self.register_sync(Arc::new(EchoTool));
         self.register_sync(Arc::new(TimeTool));
         self.register_sync(Arc::new(JsonTool));
-        self.register_sync(Arc::new(HttpTool::new()));
+        let http = match self.secrets_store {
+            Some(ref store) => {
+                HttpTool::new().with_secrets(Arc::clone(store), self.secret_bindings.clone())
+            }
+            None => HttpTool::new(),
+        };
+        self.register_sync(Arc::new(http));
+        self.register_sync(Arc::new(ReadUrlTool::new()));
+        self.register_sync(Arc::new(ScratchTool));
 
         tracing::info!("Registered {} built-in tools", self.count());
     }
@@ -213,7 +328,13 @@ impl ToolRegistry {
     /// capabilities needed for the software builder. Call this after
     /// `register_builtin_tools()` to enable code generation features.
     pub fn register_dev_tools(&self) {
-        self.register_sync(Arc::new(ShellTool::new()));
+        let shell = match self.secrets_store {
+            Some(ref store) => {
+                ShellTool::new().with_secrets(Arc::clone(store), self.secret_bindings.clone())
+            }
+            None => ShellTool::new(),
+        };
+        self.register_sync(Arc::new(shell));
         self.register_sync(Arc::new(ReadFileTool::new()));
         self.register_sync(Arc::new(WriteFileTool::new()));
         self.register_sync(Arc::new(ListDirTool::new()));
@@ -225,14 +346,113 @@ impl ToolRegistry {
     /// Register memory tools with a workspace.
     ///
     /// Memory tools require a workspace for persistence. Call this after
-    /// `register_builtin_tools()` if you have a workspace available.
-    pub fn register_memory_tools(&self, workspace: Arc<Workspace>) {
+    /// `register_builtin_tools()` if you have a workspace available. With
+    /// `conflicts`, `memory_write` reports MEMORY.md entries a new entry may
+    /// contradict.
+    pub fn register_memory_tools(
+        &self,
+        workspace: Arc<Workspace>,
+        conflicts: Option<Arc<ConflictChecker>>,
+    ) {
+        let mut write = MemoryWriteTool::new(Arc::clone(&workspace));
+        if let Some(checker) = conflicts {
+            write = write.with_conflict_checker(checker);
+        }
         self.register_sync(Arc::new(MemorySearchTool::new(Arc::clone(&workspace))));
-        self.register_sync(Arc::new(MemoryWriteTool::new(Arc::clone(&workspace))));
+        self.register_sync(Arc::new(write));
+        self.register_sync(Arc::new(MemoryEditTool::new(Arc::clone(&workspace))));
         self.register_sync(Arc::new(MemoryReadTool::new(Arc::clone(&workspace))));
-        self.register_sync(Arc::new(MemoryTreeTool::new(workspace)));
+        self.register_sync(Arc::new(MemoryTreeTool::new(Arc::clone(&workspace))));
+        self.register_sync(Arc::new(MemoryMkdirTool::new(Arc::clone(&workspace))));
+        self.register_sync(Arc::new(MemoryLintTool::new(Arc::clone(&workspace))));
+        self.register_sync(Arc::new(MemoryPinTool::new(Arc::clone(&workspace))));
+        self.register_sync(Arc::new(PersonaTool::new(workspace)));
+
+        tracing::info!("Registered 9 memory tools");
+    }
+
+    /// Register the `weather` tool, defaulting to the location in the
+    /// workspace's USER.md.
+    pub fn register_weather_tool(&self, workspace: Arc<Workspace>) {
+        self.register_sync(Arc::new(WeatherTool::new().with_workspace(workspace)));
+        tracing::info!("Registered weather tool");
+    }
+
+    /// Register the `github` tool for the repositories in `config`.
+    pub fn register_github_tool(&self, config: &GitHubConfig) {
+        let tool = match self.secrets_store {
+            Some(ref store) => GitHubTool::new(config).with_secrets(Arc::clone(store)),
+            None => GitHubTool::new(config),
+        };
+        self.register_sync(Arc::new(tool));
+        tracing::info!("Registered github tool for {} repo(s)", config.repos.len());
+    }
+
+    /// Register the `home_assistant` tool if `config` names an instance.
+    pub fn register_home_assistant_tool(&self, config: &HomeAssistantConfig) {
+        let Some(url) = config.url.as_deref() else {
+            return;
+        };
+        let tool = match self.secrets_store {
+            Some(ref store) => HomeAssistantTool::new(url, config).with_secrets(Arc::clone(store)),
+            None => HomeAssistantTool::new(url, config),
+        };
+        self.register_sync(Arc::new(tool));
+        tracing::info!("Registered home_assistant tool for {}", url);
+    }
 
-        tracing::info!("Registered 4 memory tools");
+    /// Register the first-run `bootstrap` tool.
+    ///
+    /// Only call this while the workspace is in bootstrap mode;
+    /// `channels` are the running channels the user can link.
+    pub fn register_bootstrap_tool(&self, workspace: Arc<Workspace>, channels: Vec<String>) {
+        self.register_sync(Arc::new(BootstrapTool::new(workspace, channels)));
+        tracing::info!("Registered bootstrap tool");
+    }
+
+    /// Register the `skill` tool listing the skills in `skills`.
+    pub fn register_skill_tool(&self, skills: Arc<SkillRegistry>) {
+        let count = skills.len();
+        self.register_sync(Arc::new(SkillTool::new(skills)));
+        tracing::info!("Registered skill tool with {} skills", count);
+    }
+
+    /// Register a tool for each command the skills declare.
+    ///
+    /// Commands run through the shell tool, so only call this where local
+    /// tools are allowed.
+    pub fn register_skill_commands(&self, skills: &SkillRegistry) {
+        let shell = match self.secrets_store {
+            Some(ref store) => {
+                ShellTool::new().with_secrets(Arc::clone(store), self.secret_bindings.clone())
+            }
+            None => ShellTool::new(),
+        };
+        let tools = SkillCommandTool::for_registry(skills, Arc::new(shell));
+        let mut count = 0;
+        for tool in tools {
+            let taken = PROTECTED_TOOL_NAMES.contains(&tool.name())
+                || self
+                    .tools
+                    .try_read()
+                    .is_ok_and(|tools| tools.contains_key(tool.name()));
+            if taken {
+                tracing::warn!(
+                    "Skill command {} clashes with an existing tool",
+                    tool.name()
+                );
+                continue;
+            }
+            self.register_sync(Arc::new(tool));
+            count += 1;
+        }
+        tracing::info!("Registered {} skill command tools", count);
+    }
+
+    /// Register the knowledge graph query tool.
+    pub fn register_graph_tool(&self, graph: Arc<KnowledgeGraph>, workspace: Arc<Workspace>) {
+        self.register_sync(Arc::new(MemoryGraphTool::new(graph, workspace)));
+        tracing::info!("Registered memory_graph tool");
     }
 
     /// Register job management tools.
@@ -258,6 +478,12 @@ impl ToolRegistry {
         tracing::info!("Registered 4 job management tools");
     }
 
+    /// Register the supervisor delegation tool.
+    pub fn register_supervisor_tool(&self, tool: SuperviseTool) {
+        self.register_sync(Arc::new(tool));
+        tracing::info!("Registered supervisor tool");
+    }
+
     /// Register extension management tools (search, install, auth, activate, list, remove).
     ///
     /// These allow the LLM to manage MCP servers and WASM tools through conversation.
@@ -301,6 +527,30 @@ impl ToolRegistry {
         tracing::info!("Registered 5 routine management tools");
     }
 
+    /// Register the tool that starts jobs from job templates.
+    pub fn register_template_tool(&self, launcher: Arc<crate::agent::TemplateLauncher>) {
+        use crate::tools::builtin::RunTemplateTool;
+        self.register_sync(Arc::new(RunTemplateTool::new(launcher)));
+        tracing::info!("Registered run_template tool");
+    }
+
+    /// Register contact book tools.
+    ///
+    /// These allow the LLM to create, list, update, and delete contacts, and
+    /// to resolve a person to the one handle a message should go to.
+    pub fn register_contact_tools(&self, book: Arc<ContactBook>) {
+        use crate::tools::builtin::{
+            ContactCreateTool, ContactDeleteTool, ContactListTool, ContactResolveTool,
+            ContactUpdateTool,
+        };
+        self.register_sync(Arc::new(ContactCreateTool::new(Arc::clone(&book))));
+        self.register_sync(Arc::new(ContactListTool::new(Arc::clone(&book))));
+        self.register_sync(Arc::new(ContactUpdateTool::new(Arc::clone(&book))));
+        self.register_sync(Arc::new(ContactDeleteTool::new(Arc::clone(&book))));
+        self.register_sync(Arc::new(ContactResolveTool::new(book)));
+        tracing::info!("Registered 5 contact book tools");
+    }
+
     /// Register the software builder tool.
     ///
     /// The builder tool allows the agent to create new software including WASM tools,
@@ -525,6 +775,54 @@ mod tests {
         assert_eq!(defs[0].name, "echo");
     }
 
+    #[tokio::test]
+    async fn test_tool_definitions_respect_permissions() {
+        let policy = serde_json::from_value(serde_json::json!({
+            "roles": { "guest": { "tools": ["time"] } },
+            "users": { "guest1": "guest" }
+        }))
+        .unwrap();
+        let registry = ToolRegistry::new().with_permissions(Arc::new(Permissions::new(policy)));
+        registry.register(Arc::new(EchoTool)).await;
+
+        assert!(registry.is_allowed("owner", "echo"));
+        assert!(!registry.is_allowed("guest1", "echo"));
+        assert_eq!(registry.tool_definitions_for_user("owner").await.len(), 1);
+        assert!(
+            registry
+                .tool_definitions_for_user("guest1")
+                .await
+                .is_empty()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_disabled_tools_are_hidden_and_refused() {
+        let registry = ToolRegistry::new();
+        registry.register(Arc::new(EchoTool)).await;
+
+        registry.set_disabled_tools(["echo".to_string()]);
+        assert!(!registry.is_allowed("owner", "echo"));
+        assert!(registry.tool_definitions_for_user("owner").await.is_empty());
+
+        registry.set_disabled_tools([]);
+        assert!(registry.is_allowed("owner", "echo"));
+    }
+
+    #[tokio::test]
+    async fn test_toolset_limits_tools() {
+        let registry = ToolRegistry::new();
+        registry.register(Arc::new(EchoTool)).await;
+
+        registry.set_toolset(["memory_*".to_string()]);
+        assert!(registry.is_disabled("echo"));
+        assert!(!registry.is_disabled("memory_search"));
+        assert!(registry.tool_definitions_for_user("owner").await.is_empty());
+
+        registry.set_toolset(["ec*".to_string()]);
+        assert!(registry.is_allowed("owner", "echo"));
+    }
+
     #[tokio::test]
     async fn test_builtin_tool_cannot_be_shadowed() {
         let registry = ToolRegistry::new();
@@ -172,6 +172,26 @@ pub trait Tool: Send + Sync {
         false
     }
 
+    /// Whether this particular call requires approval.
+    ///
+    /// Defaults to [`Self::requires_approval`]. Tools that are only risky
+    /// with some arguments (e.g. writing to an identity file) override this
+    /// instead.
+    fn requires_approval_for(&self, _params: &serde_json::Value) -> bool {
+        self.requires_approval()
+    }
+
+    /// Whether this tool acts on the outside world (sends email, posts
+    /// messages, calls external APIs).
+    ///
+    /// Arguments to external actions are checked against the user's internal
+    /// and private documents before execution; calls that appear to carry
+    /// their data require approval even if the tool is auto-approved, and
+    /// are refused in shared conversations if the data is private.
+    fn is_external_action(&self) -> bool {
+        false
+    }
+
     /// Maximum time this tool is allowed to run before the caller kills it.
     /// Override for long-running tools like sandbox execution.
     /// Default: 60 seconds.
@@ -221,6 +241,93 @@ pub fn require_param<'a>(
         .ok_or_else(|| ToolError::InvalidParameters(format!("missing '{}' parameter", name)))
 }
 
+/// Check parameters against a tool's JSON Schema.
+///
+/// This is a shallow check covering what models typically get wrong: the
+/// top-level value must be an object, required keys must be present, and
+/// declared property types and enums must match. Returns one message per
+/// problem; an empty vector means the parameters look valid.
+pub fn validate_params(schema: &serde_json::Value, params: &serde_json::Value) -> Vec<String> {
+    let mut problems = Vec::new();
+
+    let Some(obj) = params.as_object() else {
+        problems.push(format!(
+            "arguments must be a JSON object, got {}",
+            json_type_name(params)
+        ));
+        return problems;
+    };
+
+    if let Some(required) = schema.get("required").and_then(|r| r.as_array()) {
+        for key in required.iter().filter_map(|k| k.as_str()) {
+            if !obj.contains_key(key) {
+                problems.push(format!("missing required parameter '{}'", key));
+            }
+        }
+    }
+
+    let Some(properties) = schema.get("properties").and_then(|p| p.as_object()) else {
+        return problems;
+    };
+    for (key, value) in obj {
+        let Some(prop) = properties.get(key) else {
+            continue;
+        };
+        if let Some(expected) = prop.get("type")
+            && !type_matches(expected, value)
+        {
+            problems.push(format!(
+                "parameter '{}' must be of type {}, got {}",
+                key,
+                expected,
+                json_type_name(value)
+            ));
+            continue;
+        }
+        if let Some(allowed) = prop.get("enum").and_then(|e| e.as_array())
+            && !allowed.contains(value)
+        {
+            problems.push(format!(
+                "parameter '{}' must be one of {}, got {}",
+                key,
+                serde_json::Value::Array(allowed.clone()),
+                value
+            ));
+        }
+    }
+
+    problems
+}
+
+fn type_matches(expected: &serde_json::Value, value: &serde_json::Value) -> bool {
+    match expected {
+        serde_json::Value::String(t) => match t.as_str() {
+            "string" => value.is_string(),
+            "number" => value.is_number(),
+            "integer" => value.is_i64() || value.is_u64(),
+            "boolean" => value.is_boolean(),
+            "array" => value.is_array(),
+            "object" => value.is_object(),
+            "null" => value.is_null(),
+            // Unknown type keyword: don't second-guess the tool.
+            _ => true,
+        },
+        serde_json::Value::Array(types) => types.iter().any(|t| type_matches(t, value)),
+        _ => true,
+    }
+}
+
+fn json_type_name(value: &serde_json::Value) -> &'static str {
+    match value {
+        serde_json::Value::Null => "null",
+        serde_json::Value::Bool(_) => "boolean",
+        serde_json::Value::Number(_) => "number",
+        serde_json::Value::String(_) => "string",
+        serde_json::Value::Array(_) => "array",
+        serde_json::Value::Object(_) => "object",
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -330,4 +437,45 @@ mod tests {
         let err = require_param(&params, "data").unwrap_err();
         assert!(err.to_string().contains("missing 'data'"));
     }
+
+    #[test]
+    fn test_validate_params_valid() {
+        let schema = EchoTool.parameters_schema();
+        let params = serde_json::json!({"message": "hi"});
+        assert!(validate_params(&schema, &params).is_empty());
+    }
+
+    #[test]
+    fn test_validate_params_missing_and_wrong_type() {
+        let schema = EchoTool.parameters_schema();
+        let problems = validate_params(&schema, &serde_json::json!({}));
+        assert_eq!(problems, vec!["missing required parameter 'message'"]);
+
+        let problems = validate_params(&schema, &serde_json::json!({"message": 1}));
+        assert_eq!(problems.len(), 1);
+        assert!(problems[0].contains("must be of type \"string\", got number"));
+    }
+
+    #[test]
+    fn test_validate_params_not_an_object() {
+        let schema = EchoTool.parameters_schema();
+        let problems = validate_params(&schema, &serde_json::json!("{\"message\": "));
+        assert_eq!(
+            problems,
+            vec!["arguments must be a JSON object, got string"]
+        );
+    }
+
+    #[test]
+    fn test_validate_params_enum() {
+        let schema = serde_json::json!({
+            "type": "object",
+            "properties": {"mode": {"type": "string", "enum": ["a", "b"]}}
+        });
+        assert!(validate_params(&schema, &serde_json::json!({"mode": "a"})).is_empty());
+        assert_eq!(
+            validate_params(&schema, &serde_json::json!({"mode": "c"})).len(),
+            1
+        );
+    }
 }
@@ -174,6 +174,7 @@ pub(crate) fn inject_credential(
     location: &CredentialLocation,
     secret: &DecryptedSecret,
 ) {
+    crate::safety::SecretRedactor::global().register(secret.expose());
     match location {
         CredentialLocation::AuthorizationBearer => {
             result.headers.insert(
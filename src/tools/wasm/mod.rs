@@ -104,6 +104,7 @@ pub use capabilities::{
 
 // Security components (V2)
 pub use allowlist::{AllowlistResult, AllowlistValidator, DenyReason};
+pub(crate) use credential_injector::host_matches_pattern;
 pub use credential_injector::{CredentialInjector, InjectedCredentials, InjectionError};
 pub use rate_limiter::{LimitType, RateLimitError, RateLimitResult, RateLimiter};
 
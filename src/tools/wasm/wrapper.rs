@@ -724,6 +724,11 @@ impl Tool for WasmToolWrapper {
         true
     }
 
+    fn is_external_action(&self) -> bool {
+        // Any tool allowed to make HTTP requests can send data out
+        self.capabilities.http.is_some()
+    }
+
     fn estimated_duration(&self, _params: &serde_json::Value) -> Option<Duration> {
         // Use the timeout as a conservative estimate
         Some(self.prepared.limits.timeout)
@@ -4,7 +4,8 @@
 //! Rather than truncating at every call site (fragile, easy to miss), we
 //! handle it at the writer level: the fmt layer gets a `TruncatingStderr`
 //! that caps each event before flushing, while the web gateway `WebLogLayer`
-//! still sees the full, untruncated content.
+//! still sees the full, untruncated content. Both paths mask secrets via
+//! `SecretRedactor` before output.
 //!
 //! ```text
 //! tracing::debug!("body: {huge_json}")
@@ -23,6 +24,8 @@ use std::io::{self, Write};
 
 use tracing_subscriber::fmt::MakeWriter;
 
+use crate::safety::SecretRedactor;
+
 /// Maximum bytes per tracing event written to the terminal.
 const TERMINAL_MAX_EVENT_BYTES: usize = 500;
 
@@ -106,6 +109,13 @@ impl Drop for EventBuffer {
             return;
         }
 
+        // Mask secrets before anything reaches the terminal.
+        let text = String::from_utf8_lossy(&self.buf);
+        let redacted = SecretRedactor::global().redact(&text);
+        if redacted != text {
+            self.buf = redacted.into_bytes();
+        }
+
         let output = if self.buf.len() <= self.max_bytes {
             &self.buf[..]
         } else {
@@ -0,0 +1,536 @@
+//! Outbound webhook notifications.
+//!
+//! POSTs a signed JSON event to every configured URL when something
+//! happens that an outside automation (n8n, Zapier, a CI bot) may want to
+//! react to:
+//!
+//! - **`job.completed`**: a job finished, successfully or not
+//! - **`approval.requested`**: a tool call is waiting for the user's approval
+//! - **`workspace.changed`**: a workspace document was written or deleted
+//! - **`budget.exceeded`**: a user ran out of their daily token budget
+//!
+//! Each delivery is a `POST` with a body like
+//!
+//! ```json
+//! {"id": "…", "event": "job.completed", "timestamp": "2026-01-01T00:00:00Z", "data": {…}}
+//! ```
+//!
+//! and these headers:
+//!
+//! - `X-Ironclaw-Event`: the event name
+//! - `X-Ironclaw-Delivery`: the event ID, the same on every retry
+//! - `X-Ironclaw-Timestamp`: Unix seconds when the delivery was signed
+//! - `X-Ironclaw-Signature`: `sha256=` followed by the hex HMAC-SHA256 of
+//!   `{timestamp}.{body}`, keyed with `WEBHOOK_SECRET`
+//!
+//! Receivers should recompute the signature and reject stale timestamps.
+//! Deliveries that fail with a network error, a 429, or a 5xx are retried
+//! with exponential backoff; other 4xx responses are not. Sending happens in
+//! the background and never holds up the action that caused the event.
+
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::Utc;
+use ring::hmac;
+use secrecy::{ExposeSecret, SecretString};
+use serde::Serialize;
+use uuid::Uuid;
+
+use crate::audit::redact_json;
+use crate::config::WebhookConfig;
+use crate::safety::SecretRedactor;
+use crate::workspace::{WorkspaceEvent, WorkspaceEvents};
+
+/// Longest a single delivery attempt may take.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Delay before the first retry.
+const INITIAL_BACKOFF: Duration = Duration::from_secs(1);
+
+/// What an outbound webhook reports.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+pub enum WebhookEventKind {
+    #[serde(rename = "job.completed")]
+    JobCompleted,
+    #[serde(rename = "approval.requested")]
+    ApprovalRequested,
+    #[serde(rename = "workspace.changed")]
+    WorkspaceChanged,
+    #[serde(rename = "budget.exceeded")]
+    BudgetExceeded,
+}
+
+impl WebhookEventKind {
+    pub const ALL: [Self; 4] = [
+        Self::JobCompleted,
+        Self::ApprovalRequested,
+        Self::WorkspaceChanged,
+        Self::BudgetExceeded,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::JobCompleted => "job.completed",
+            Self::ApprovalRequested => "approval.requested",
+            Self::WorkspaceChanged => "workspace.changed",
+            Self::BudgetExceeded => "budget.exceeded",
+        }
+    }
+}
+
+impl std::fmt::Display for WebhookEventKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for WebhookEventKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|kind| kind.as_str() == s)
+            .ok_or_else(|| format!("unknown webhook event '{}'", s))
+    }
+}
+
+/// An event to be sent to the configured webhooks.
+#[derive(Debug, Clone, PartialEq)]
+pub struct WebhookEvent {
+    pub kind: WebhookEventKind,
+    /// Event-specific fields, sent as `data`.
+    pub data: serde_json::Value,
+}
+
+impl WebhookEvent {
+    pub fn new(kind: WebhookEventKind, data: serde_json::Value) -> Self {
+        Self { kind, data }
+    }
+
+    /// A job reached a final state: `completed` or `failed`.
+    pub fn job_completed(
+        job_id: Uuid,
+        user_id: &str,
+        title: &str,
+        status: &str,
+        reason: Option<&str>,
+    ) -> Self {
+        Self::new(
+            WebhookEventKind::JobCompleted,
+            serde_json::json!({
+                "job_id": job_id,
+                "user_id": user_id,
+                "title": title,
+                "status": status,
+                "reason": reason,
+            }),
+        )
+    }
+
+    /// A tool call in `user_id`'s conversation on `channel` needs approval.
+    pub fn approval_requested(
+        user_id: &str,
+        channel: &str,
+        request_id: Uuid,
+        tool_name: &str,
+        description: &str,
+        parameters: &serde_json::Value,
+    ) -> Self {
+        Self::new(
+            WebhookEventKind::ApprovalRequested,
+            serde_json::json!({
+                "user_id": user_id,
+                "channel": channel,
+                "request_id": request_id,
+                "tool_name": tool_name,
+                "description": description,
+                "parameters": parameters,
+            }),
+        )
+    }
+
+    /// A workspace document was written (`changed`) or `deleted`.
+    pub fn workspace_changed(
+        user_id: &str,
+        agent_id: Option<Uuid>,
+        path: &str,
+        operation: &str,
+    ) -> Self {
+        Self::new(
+            WebhookEventKind::WorkspaceChanged,
+            serde_json::json!({
+                "user_id": user_id,
+                "agent_id": agent_id,
+                "path": path,
+                "operation": operation,
+            }),
+        )
+    }
+
+    /// `user_id` used up their daily token budget.
+    pub fn budget_exceeded(user_id: &str, budget: u64) -> Self {
+        Self::new(
+            WebhookEventKind::BudgetExceeded,
+            serde_json::json!({
+                "user_id": user_id,
+                "daily_token_budget": budget,
+            }),
+        )
+    }
+}
+
+/// Body of a delivery.
+#[derive(Debug, Serialize)]
+struct Payload<'a> {
+    id: Uuid,
+    event: WebhookEventKind,
+    timestamp: chrono::DateTime<Utc>,
+    data: &'a serde_json::Value,
+}
+
+/// Sends events to the configured webhook URLs.
+pub struct Webhooks {
+    client: reqwest::Client,
+    urls: Vec<String>,
+    /// Events sent; all of them when empty.
+    events: Vec<WebhookEventKind>,
+    key: hmac::Key,
+    max_attempts: u32,
+    /// Delay before the first retry; doubled for each one after.
+    backoff: Duration,
+}
+
+impl std::fmt::Debug for Webhooks {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Webhooks")
+            .field("urls", &self.urls)
+            .field("events", &self.events)
+            .field("max_attempts", &self.max_attempts)
+            .finish()
+    }
+}
+
+impl Webhooks {
+    pub fn new(urls: Vec<String>, secret: &SecretString) -> Self {
+        Self {
+            client: reqwest::Client::builder()
+                .timeout(REQUEST_TIMEOUT)
+                .build()
+                .unwrap_or_default(),
+            urls,
+            events: Vec::new(),
+            key: hmac::Key::new(hmac::HMAC_SHA256, secret.expose_secret().as_bytes()),
+            max_attempts: 5,
+            backoff: INITIAL_BACKOFF,
+        }
+    }
+
+    /// The webhooks `config` asks for, or `None` if it lists no URLs.
+    pub fn from_config(config: &WebhookConfig) -> Option<Arc<Self>> {
+        let secret = config.secret.as_ref()?;
+        if config.urls.is_empty() {
+            return None;
+        }
+        Some(Arc::new(
+            Self::new(config.urls.clone(), secret)
+                .with_events(config.events.clone())
+                .with_max_attempts(config.max_attempts),
+        ))
+    }
+
+    /// Only send these events. An empty list sends every event.
+    pub fn with_events(mut self, events: Vec<WebhookEventKind>) -> Self {
+        self.events = events;
+        self
+    }
+
+    /// Give up on a delivery after this many attempts (at least one).
+    pub fn with_max_attempts(mut self, max_attempts: u32) -> Self {
+        self.max_attempts = max_attempts.max(1);
+        self
+    }
+
+    /// Whether `kind` is sent at all.
+    pub fn wants(&self, kind: WebhookEventKind) -> bool {
+        self.events.is_empty() || self.events.contains(&kind)
+    }
+
+    /// Send `event` to every URL in the background. Secrets in its data are
+    /// masked first.
+    pub fn notify(self: &Arc<Self>, event: WebhookEvent) {
+        if !self.wants(event.kind) {
+            return;
+        }
+        let mut data = event.data;
+        redact_json(&mut data, SecretRedactor::global());
+        let payload = Payload {
+            id: Uuid::new_v4(),
+            event: event.kind,
+            timestamp: Utc::now(),
+            data: &data,
+        };
+        let body = match serde_json::to_string(&payload) {
+            Ok(body) => body,
+            Err(e) => {
+                tracing::warn!("Failed to serialize {} webhook: {}", event.kind, e);
+                return;
+            }
+        };
+        for url in &self.urls {
+            let webhooks = Arc::clone(self);
+            let url = url.clone();
+            let body = body.clone();
+            let id = payload.id;
+            let kind = event.kind;
+            tokio::spawn(async move {
+                webhooks.deliver(&url, kind, id, &body).await;
+            });
+        }
+    }
+
+    /// Post one event to `url`, retrying transient failures. Returns whether
+    /// it was accepted.
+    async fn deliver(&self, url: &str, kind: WebhookEventKind, id: Uuid, body: &str) -> bool {
+        let mut backoff = self.backoff;
+        for attempt in 1..=self.max_attempts {
+            let timestamp = Utc::now().timestamp();
+            let result = self
+                .client
+                .post(url)
+                .header("Content-Type", "application/json")
+                .header("X-Ironclaw-Event", kind.as_str())
+                .header("X-Ironclaw-Delivery", id.to_string())
+                .header("X-Ironclaw-Timestamp", timestamp.to_string())
+                .header("X-Ironclaw-Signature", self.sign(timestamp, body))
+                .body(body.to_string())
+                .send()
+                .await;
+            let retryable = match result {
+                Ok(response) if response.status().is_success() => {
+                    tracing::debug!("Delivered {} webhook {} to {}", kind, id, url);
+                    return true;
+                }
+                Ok(response) => {
+                    let status = response.status();
+                    tracing::warn!(
+                        "Webhook {} to {} rejected with {} (attempt {}/{})",
+                        id,
+                        url,
+                        status,
+                        attempt,
+                        self.max_attempts
+                    );
+                    status.is_server_error() || status == reqwest::StatusCode::TOO_MANY_REQUESTS
+                }
+                Err(e) => {
+                    tracing::warn!(
+                        "Webhook {} to {} failed: {} (attempt {}/{})",
+                        id,
+                        url,
+                        e,
+                        attempt,
+                        self.max_attempts
+                    );
+                    true
+                }
+            };
+            if !retryable || attempt == self.max_attempts {
+                break;
+            }
+            tokio::time::sleep(backoff).await;
+            backoff *= 2;
+        }
+        tracing::error!("Giving up on {} webhook {} to {}", kind, id, url);
+        false
+    }
+
+    /// The `X-Ironclaw-Signature` header value for `body` sent at `timestamp`.
+    fn sign(&self, timestamp: i64, body: &str) -> String {
+        let tag = hmac::sign(&self.key, format!("{}.{}", timestamp, body).as_bytes());
+        let hex: String = tag.as_ref().iter().map(|b| format!("{:02x}", b)).collect();
+        format!("sha256={}", hex)
+    }
+
+    /// Send a `workspace.changed` event for every document written or
+    /// deleted through workspaces publishing to `events`.
+    pub fn watch_workspace(
+        self: &Arc<Self>,
+        events: &WorkspaceEvents,
+    ) -> tokio::task::JoinHandle<()> {
+        let webhooks = Arc::clone(self);
+        let mut rx = events.subscribe();
+        tokio::spawn(async move {
+            loop {
+                let event = match rx.recv().await {
+                    Ok(event) => event,
+                    Err(tokio::sync::broadcast::error::RecvError::Lagged(n)) => {
+                        tracing::warn!("Webhooks missed {} workspace changes", n);
+                        continue;
+                    }
+                    Err(tokio::sync::broadcast::error::RecvError::Closed) => break,
+                };
+                match event {
+                    WorkspaceEvent::Changed {
+                        user_id,
+                        agent_id,
+                        path,
+                    } => webhooks.notify(WebhookEvent::workspace_changed(
+                        &user_id, agent_id, &path, "changed",
+                    )),
+                    WorkspaceEvent::Deleted {
+                        user_id,
+                        agent_id,
+                        path,
+                    } => webhooks.notify(WebhookEvent::workspace_changed(
+                        &user_id, agent_id, &path, "deleted",
+                    )),
+                    WorkspaceEvent::MetadataChanged { .. } => {}
+                }
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use axum::Router;
+    use axum::extract::State;
+    use axum::http::{HeaderMap, StatusCode};
+    use axum::routing::post;
+
+    use super::*;
+
+    #[test]
+    fn test_kind_round_trip() {
+        for kind in WebhookEventKind::ALL {
+            assert_eq!(kind.as_str().parse::<WebhookEventKind>(), Ok(kind));
+            assert_eq!(
+                serde_json::to_value(kind).unwrap(),
+                serde_json::json!(kind.as_str())
+            );
+        }
+        assert!("job.started".parse::<WebhookEventKind>().is_err());
+    }
+
+    #[test]
+    fn test_signature() {
+        // Same as `printf '1700000000.{"event":"job.completed"}' |
+        // openssl dgst -sha256 -hmac secret`.
+        let webhooks = Webhooks::new(vec![], &SecretString::from("secret"));
+        assert_eq!(
+            webhooks.sign(1_700_000_000, r#"{"event":"job.completed"}"#),
+            "sha256=e33f34cc0b46f4e752fe75a10d7177366fd795c052ed09dfa63608265c13be69"
+        );
+    }
+
+    #[test]
+    fn test_event_filter() {
+        let webhooks = Webhooks::new(vec![], &SecretString::from("s"));
+        assert!(webhooks.wants(WebhookEventKind::WorkspaceChanged));
+        let webhooks = webhooks.with_events(vec![WebhookEventKind::JobCompleted]);
+        assert!(webhooks.wants(WebhookEventKind::JobCompleted));
+        assert!(!webhooks.wants(WebhookEventKind::WorkspaceChanged));
+    }
+
+    /// Answers with each status in turn (200 once they run out) and keeps
+    /// the requests it gets.
+    #[derive(Clone, Default)]
+    struct Receiver {
+        statuses: Arc<Mutex<Vec<StatusCode>>>,
+        requests: Arc<Mutex<Vec<(HeaderMap, String)>>>,
+    }
+
+    async fn receive(
+        State(receiver): State<Receiver>,
+        headers: HeaderMap,
+        body: String,
+    ) -> StatusCode {
+        receiver.requests.lock().unwrap().push((headers, body));
+        let mut statuses = receiver.statuses.lock().unwrap();
+        if statuses.is_empty() {
+            StatusCode::OK
+        } else {
+            statuses.remove(0)
+        }
+    }
+
+    async fn serve(receiver: Receiver) -> String {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let app = Router::new()
+            .route("/hook", post(receive))
+            .with_state(receiver);
+        tokio::spawn(async move {
+            axum::serve(listener, app).await.unwrap();
+        });
+        format!("http://{}/hook", addr)
+    }
+
+    #[tokio::test]
+    async fn test_delivery_retries_transient_failures() {
+        let receiver = Receiver::default();
+        *receiver.statuses.lock().unwrap() =
+            vec![StatusCode::SERVICE_UNAVAILABLE, StatusCode::BAD_GATEWAY];
+        let url = serve(receiver.clone()).await;
+
+        let mut webhooks = Webhooks::new(vec![url.clone()], &SecretString::from("secret"));
+        webhooks.backoff = Duration::from_millis(1);
+        let id = Uuid::new_v4();
+        let body = r#"{"event":"job.completed"}"#;
+        assert!(
+            webhooks
+                .deliver(&url, WebhookEventKind::JobCompleted, id, body)
+                .await
+        );
+
+        let requests = receiver.requests.lock().unwrap();
+        assert_eq!(requests.len(), 3);
+        for (headers, received) in requests.iter() {
+            assert_eq!(received, body);
+            assert_eq!(headers["x-ironclaw-event"], "job.completed");
+            assert_eq!(headers["x-ironclaw-delivery"], id.to_string().as_str());
+            let timestamp: i64 = headers["x-ironclaw-timestamp"]
+                .to_str()
+                .unwrap()
+                .parse()
+                .unwrap();
+            assert_eq!(
+                headers["x-ironclaw-signature"],
+                webhooks.sign(timestamp, body).as_str()
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_delivery_gives_up_on_client_errors() {
+        let receiver = Receiver::default();
+        *receiver.statuses.lock().unwrap() = vec![StatusCode::BAD_REQUEST];
+        let url = serve(receiver.clone()).await;
+
+        let webhooks = Webhooks::new(vec![url.clone()], &SecretString::from("secret"));
+        assert!(
+            !webhooks
+                .deliver(&url, WebhookEventKind::BudgetExceeded, Uuid::new_v4(), "{}")
+                .await
+        );
+        assert_eq!(receiver.requests.lock().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_event_data() {
+        let event =
+            WebhookEvent::job_completed(Uuid::nil(), "u1", "Report", "failed", Some("boom"));
+        assert_eq!(event.kind, WebhookEventKind::JobCompleted);
+        assert_eq!(event.data["status"], "failed");
+        assert_eq!(event.data["reason"], "boom");
+
+        let event = WebhookEvent::workspace_changed("u1", None, "MEMORY.md", "deleted");
+        assert_eq!(event.data["path"], "MEMORY.md");
+        assert!(event.data["agent_id"].is_null());
+    }
+}
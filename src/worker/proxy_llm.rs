@@ -41,6 +41,11 @@ impl LlmProvider for ProxyLlmProvider {
         (Decimal::ZERO, Decimal::ZERO)
     }
 
+    #[tracing::instrument(
+        name = "llm.complete",
+        skip_all,
+        fields(provider = "proxy", model = %self.model_name())
+    )]
     async fn complete(&self, request: CompletionRequest) -> Result<CompletionResponse, LlmError> {
         self.client
             .llm_complete(&request)
@@ -51,6 +56,11 @@ impl LlmProvider for ProxyLlmProvider {
             })
     }
 
+    #[tracing::instrument(
+        name = "llm.complete_with_tools",
+        skip_all,
+        fields(provider = "proxy", model = %self.model_name())
+    )]
     async fn complete_with_tools(
         &self,
         request: ToolCompletionRequest,
@@ -0,0 +1,228 @@
+//! Backing up a workspace to a `.tar.gz` archive and restoring it.
+//!
+//! [`Workspace::export_archive`] writes each document in the workspace's
+//! own namespace as `documents/<path>`, followed by `metadata/<path>.json`
+//! holding its metadata and timestamps, after a `manifest.json` recording
+//! the archive format. Shared mounts and the trash are left out. Documents
+//! are plain files, so an archive can be unpacked and read with `tar`.
+//!
+//! [`Workspace::import_archive`] writes the documents back and indexes them
+//! with the current chunker and embedding model, so an archive made on the
+//! libSQL backend restores into Postgres and the other way round. A
+//! document already at the same path is overwritten, its old content kept
+//! as a revision. Version history isn't archived, and restored documents
+//! get fresh timestamps.
+
+mod tar;
+
+use std::io::{Read, Write};
+use std::pin::pin;
+
+use chrono::{DateTime, Utc};
+use flate2::Compression;
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use futures::TryStreamExt;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::error::WorkspaceError;
+use crate::workspace::reindex::INDEX_KEY;
+use crate::workspace::{Workspace, trash};
+
+use self::tar::{TarReader, TarWriter};
+
+/// Version of the archive layout, recorded in the manifest.
+pub const ARCHIVE_FORMAT: u32 = 1;
+
+const MANIFEST: &str = "manifest.json";
+const DOCUMENTS_DIR: &str = "documents/";
+const METADATA_DIR: &str = "metadata/";
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Manifest {
+    format: u32,
+    exported_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct ArchivedMetadata {
+    created_at: DateTime<Utc>,
+    updated_at: DateTime<Utc>,
+    metadata: Value,
+}
+
+fn export_error(e: impl std::fmt::Display) -> WorkspaceError {
+    WorkspaceError::ExportFailed {
+        reason: e.to_string(),
+    }
+}
+
+fn archive_error(path: &str, e: impl std::fmt::Display) -> WorkspaceError {
+    WorkspaceError::ImportFailed {
+        path: path.to_string(),
+        reason: e.to_string(),
+    }
+}
+
+impl Workspace {
+    /// Write every document in this workspace's namespace to `writer` as a
+    /// gzipped tarball. Returns the number of documents written.
+    pub async fn export_archive<W: Write>(&self, writer: W) -> Result<usize, WorkspaceError> {
+        let mut tar = TarWriter::new(GzEncoder::new(writer, Compression::default()));
+        let now = Utc::now();
+        let manifest = serde_json::to_vec_pretty(&Manifest {
+            format: ARCHIVE_FORMAT,
+            exported_at: now,
+        })
+        .map_err(export_error)?;
+        tar.append(MANIFEST, &manifest, now.timestamp())
+            .map_err(export_error)?;
+
+        let mut exported = 0;
+        let mut paths = pin!(self.scope_paths(self.agent_id));
+        while let Some(path) = paths.try_next().await? {
+            if trash::is_trashed(&path) {
+                continue;
+            }
+            let doc = self
+                .storage
+                .get_document_by_path(&self.user_id, self.agent_id, &path)
+                .await?;
+            let mtime = doc.updated_at.timestamp();
+            let metadata = serde_json::to_vec_pretty(&ArchivedMetadata {
+                created_at: doc.created_at,
+                updated_at: doc.updated_at,
+                metadata: doc.metadata,
+            })
+            .map_err(export_error)?;
+            tar.append(
+                &format!("{}{}", DOCUMENTS_DIR, path),
+                doc.content.as_bytes(),
+                mtime,
+            )
+            .map_err(export_error)?;
+            tar.append(&format!("{}{}.json", METADATA_DIR, path), &metadata, mtime)
+                .map_err(export_error)?;
+            exported += 1;
+        }
+
+        let mut writer = tar
+            .finish()
+            .and_then(GzEncoder::finish)
+            .map_err(export_error)?;
+        writer.flush().map_err(export_error)?;
+        Ok(exported)
+    }
+
+    /// Restore the documents in an archive written by
+    /// [`export_archive`](Self::export_archive), re-indexing each one.
+    /// Returns the number of documents imported.
+    pub async fn import_archive<R: Read>(&self, reader: R) -> Result<usize, WorkspaceError> {
+        let mut tar = TarReader::new(GzDecoder::new(reader));
+        let mut imported = 0;
+        while let Some(file) = tar.next_file().map_err(|e| archive_error("archive", e))? {
+            // Archives repacked with `tar -C dir .` have a leading "./".
+            let name = file.path.trim_start_matches("./");
+            if name == MANIFEST {
+                let manifest: Manifest =
+                    serde_json::from_slice(&file.data).map_err(|e| archive_error(name, e))?;
+                if manifest.format > ARCHIVE_FORMAT {
+                    return Err(archive_error(
+                        name,
+                        format!(
+                            "archive format {} is newer than this version reads",
+                            manifest.format
+                        ),
+                    ));
+                }
+            } else if let Some(path) = name.strip_prefix(DOCUMENTS_DIR) {
+                if path.is_empty() || trash::is_trashed(path) {
+                    continue;
+                }
+                let content =
+                    String::from_utf8(file.data).map_err(|_| archive_error(name, "not UTF-8"))?;
+                self.write(path, &content).await?;
+                imported += 1;
+            } else if let Some(path) = name
+                .strip_prefix(METADATA_DIR)
+                .and_then(|p| p.strip_suffix(".json"))
+            {
+                let archived: ArchivedMetadata =
+                    serde_json::from_slice(&file.data).map_err(|e| archive_error(name, e))?;
+                self.restore_metadata(path, archived.metadata).await?;
+            }
+        }
+        Ok(imported)
+    }
+
+    /// Merge archived metadata into the imported document at `path`.
+    /// What indexing derived from the content wins, and the archived index
+    /// fingerprint is dropped since the document was just indexed afresh.
+    async fn restore_metadata(&self, path: &str, archived: Value) -> Result<(), WorkspaceError> {
+        let Value::Object(mut metadata) = archived else {
+            return Ok(());
+        };
+        let doc = match self.read(path).await {
+            Ok(doc) => doc,
+            Err(WorkspaceError::DocumentNotFound { .. }) => return Ok(()),
+            Err(e) => return Err(e),
+        };
+        metadata.remove(INDEX_KEY);
+        if let Value::Object(current) = doc.metadata {
+            metadata.extend(current);
+        }
+        self.update_metadata(doc.id, &Value::Object(metadata)).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[cfg(feature = "libsql")]
+    #[tokio::test]
+    async fn test_export_and_import_archive() {
+        use serde_json::json;
+
+        use crate::db::libsql_backend::test_db;
+        use crate::workspace::Workspace;
+
+        let (source_db, _source_dir) = test_db().await;
+        let source = Workspace::new_with_db("default", source_db);
+        source
+            .write("MEMORY.md", "- Prefers green tea")
+            .await
+            .unwrap();
+        let long = format!("projects/{}/notes.md", "deep".repeat(30));
+        source.write(&long, "Launch on Friday").await.unwrap();
+        let doc = source.read("MEMORY.md").await.unwrap();
+        let mut metadata = doc.metadata.as_object().cloned().unwrap_or_default();
+        metadata.insert("pinned".to_string(), json!(true));
+        source
+            .update_metadata(doc.id, &metadata.into())
+            .await
+            .unwrap();
+        source.write("old.md", "x").await.unwrap();
+        source.delete("old.md").await.unwrap();
+
+        let mut archive = Vec::new();
+        assert_eq!(source.export_archive(&mut archive).await.unwrap(), 2);
+
+        let (target_db, _target_dir) = test_db().await;
+        let target = Workspace::new_with_db("default", target_db);
+        assert_eq!(target.import_archive(archive.as_slice()).await.unwrap(), 2);
+        assert_eq!(
+            target.list_all().await.unwrap(),
+            source.list_all().await.unwrap()
+        );
+        let restored = target.read(&long).await.unwrap();
+        assert_eq!(restored.content, "Launch on Friday");
+        let memory = target.read("MEMORY.md").await.unwrap();
+        assert_eq!(memory.metadata["pinned"], json!(true));
+        assert!(!target.search("green tea", 5).await.unwrap().is_empty());
+
+        assert!(matches!(
+            target.import_archive(&b"not an archive"[..]).await,
+            Err(crate::error::WorkspaceError::ImportFailed { .. })
+        ));
+    }
+}
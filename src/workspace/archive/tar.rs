@@ -0,0 +1,270 @@
+//! Just enough of the tar format to write and read workspace archives.
+//!
+//! Writes POSIX ustar file entries, preceded by a PAX header when a path is
+//! too long for the ustar name field. Reads the same plus GNU long names
+//! and the ustar prefix field, so an archive repacked with `tar` still
+//! imports. Directories, links, and other special entries are skipped.
+
+use std::io::{self, Read, Write};
+
+const BLOCK: usize = 512;
+
+/// Longest path the ustar name field holds.
+const NAME_LEN: usize = 100;
+
+const REGULAR: u8 = b'0';
+/// Regular file, as written by pre-POSIX tars.
+const REGULAR_OLD: u8 = 0;
+const PAX_HEADER: u8 = b'x';
+const GNU_LONG_NAME: u8 = b'L';
+
+/// Writes regular files to a tar stream.
+pub(super) struct TarWriter<W: Write> {
+    inner: W,
+}
+
+impl<W: Write> TarWriter<W> {
+    pub(super) fn new(inner: W) -> Self {
+        Self { inner }
+    }
+
+    /// Add a file at `path` holding `data`, last modified at `mtime` (Unix
+    /// seconds).
+    pub(super) fn append(&mut self, path: &str, data: &[u8], mtime: i64) -> io::Result<()> {
+        if path.len() > NAME_LEN {
+            let record = pax_record("path", path);
+            self.write_entry(PAX_HEADER, "././@PaxHeader", record.as_bytes(), mtime)?;
+        }
+        let mut end = path.len().min(NAME_LEN);
+        while !path.is_char_boundary(end) {
+            end -= 1;
+        }
+        self.write_entry(REGULAR, &path[..end], data, mtime)
+    }
+
+    fn write_entry(&mut self, kind: u8, name: &str, data: &[u8], mtime: i64) -> io::Result<()> {
+        let mut header = [0u8; BLOCK];
+        header[..name.len()].copy_from_slice(name.as_bytes());
+        octal(&mut header[100..108], 0o644);
+        octal(&mut header[108..116], 0);
+        octal(&mut header[116..124], 0);
+        octal(&mut header[124..136], data.len() as u64);
+        octal(&mut header[136..148], mtime.max(0) as u64);
+        header[156] = kind;
+        header[257..263].copy_from_slice(b"ustar\0");
+        header[263..265].copy_from_slice(b"00");
+        // The checksum is taken with its own field filled with spaces.
+        header[148..156].fill(b' ');
+        let sum = checksum(&header);
+        octal(&mut header[148..155], sum);
+
+        self.inner.write_all(&header)?;
+        self.inner.write_all(data)?;
+        self.inner.write_all(&[0; BLOCK][..padding(data.len())])
+    }
+
+    /// Write the end-of-archive marker and hand back the underlying writer.
+    pub(super) fn finish(mut self) -> io::Result<W> {
+        self.inner.write_all(&[0; 2 * BLOCK])?;
+        Ok(self.inner)
+    }
+}
+
+/// A file read from a tar stream.
+pub(super) struct TarFile {
+    pub path: String,
+    pub data: Vec<u8>,
+}
+
+/// Reads regular files from a tar stream.
+pub(super) struct TarReader<R: Read> {
+    inner: R,
+}
+
+impl<R: Read> TarReader<R> {
+    pub(super) fn new(inner: R) -> Self {
+        Self { inner }
+    }
+
+    /// The next regular file, or `None` at the end of the archive.
+    pub(super) fn next_file(&mut self) -> io::Result<Option<TarFile>> {
+        let mut long_name = None;
+        loop {
+            let mut header = [0u8; BLOCK];
+            if !self.read_header(&mut header)? || header.iter().all(|&b| b == 0) {
+                return Ok(None);
+            }
+            let mut stored = header;
+            stored[148..156].fill(b' ');
+            if parse_octal(&header[148..156])? != checksum(&stored) {
+                return Err(invalid("bad header checksum"));
+            }
+
+            let size = parse_octal(&header[124..136])?;
+            let mut data = Vec::new();
+            (&mut self.inner).take(size).read_to_end(&mut data)?;
+            if (data.len() as u64) < size {
+                return Err(io::ErrorKind::UnexpectedEof.into());
+            }
+            let mut pad = [0u8; BLOCK];
+            self.inner.read_exact(&mut pad[..padding(data.len())])?;
+
+            match header[156] {
+                PAX_HEADER => long_name = pax_path(&data)?.or(long_name),
+                GNU_LONG_NAME => long_name = Some(text(&data)?),
+                REGULAR | REGULAR_OLD => {
+                    let path = match long_name {
+                        Some(path) => path,
+                        None => ustar_path(&header)?,
+                    };
+                    return Ok(Some(TarFile { path, data }));
+                }
+                _ => long_name = None,
+            }
+        }
+    }
+
+    /// Fill `header`, returning false if the stream ended cleanly first.
+    fn read_header(&mut self, header: &mut [u8; BLOCK]) -> io::Result<bool> {
+        let mut filled = 0;
+        while filled < BLOCK {
+            match self.inner.read(&mut header[filled..]) {
+                Ok(0) if filled == 0 => return Ok(false),
+                Ok(0) => return Err(io::ErrorKind::UnexpectedEof.into()),
+                Ok(n) => filled += n,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(true)
+    }
+}
+
+fn invalid(reason: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, reason.to_string())
+}
+
+/// Zero bytes after `len` bytes of data, up to the next block.
+fn padding(len: usize) -> usize {
+    (BLOCK - len % BLOCK) % BLOCK
+}
+
+fn checksum(header: &[u8; BLOCK]) -> u64 {
+    header.iter().map(|&b| u64::from(b)).sum()
+}
+
+/// Write `value` as zero-padded octal filling `field` but for a final NUL.
+fn octal(field: &mut [u8], value: u64) {
+    let digits = format!("{:0width$o}", value, width = field.len() - 1);
+    field[..digits.len()].copy_from_slice(digits.as_bytes());
+    field[digits.len()] = 0;
+}
+
+fn parse_octal(field: &[u8]) -> io::Result<u64> {
+    if field.first().is_some_and(|&b| b & 0x80 != 0) {
+        return Err(invalid("binary size fields are not supported"));
+    }
+    let digits = text(field)?;
+    let digits = digits.trim_matches(|c: char| c == ' ' || c == '\0');
+    if digits.is_empty() {
+        return Ok(0);
+    }
+    u64::from_str_radix(digits, 8).map_err(|_| invalid("bad number in header"))
+}
+
+/// A NUL-terminated UTF-8 string.
+fn text(bytes: &[u8]) -> io::Result<String> {
+    let end = bytes.iter().position(|&b| b == 0).unwrap_or(bytes.len());
+    String::from_utf8(bytes[..end].to_vec()).map_err(|_| invalid("path is not UTF-8"))
+}
+
+/// The path in a ustar header: the name, after the prefix if there is one.
+fn ustar_path(header: &[u8; BLOCK]) -> io::Result<String> {
+    let name = text(&header[..NAME_LEN])?;
+    if &header[257..262] != b"ustar" {
+        return Ok(name);
+    }
+    let prefix = text(&header[345..500])?;
+    Ok(if prefix.is_empty() {
+        name
+    } else {
+        format!("{}/{}", prefix, name)
+    })
+}
+
+/// One `<length> <key>=<value>\n` PAX record, the length counting itself.
+fn pax_record(key: &str, value: &str) -> String {
+    let body = format!(" {}={}\n", key, value);
+    let mut len = body.len();
+    while len.to_string().len() + body.len() != len {
+        len = len.to_string().len() + body.len();
+    }
+    format!("{}{}", len, body)
+}
+
+/// The `path` record of a PAX header, if it has one.
+fn pax_path(data: &[u8]) -> io::Result<Option<String>> {
+    let records = std::str::from_utf8(data).map_err(|_| invalid("PAX header is not UTF-8"))?;
+    let mut rest = records;
+    while let Some((len, _)) = rest.split_once(' ') {
+        let len: usize = len.parse().map_err(|_| invalid("bad PAX record"))?;
+        let record = rest.get(..len).ok_or_else(|| invalid("bad PAX record"))?;
+        rest = &rest[len..];
+        let Some((_, field)) = record.trim_end_matches('\n').split_once(' ') else {
+            continue;
+        };
+        if let Some(path) = field.strip_prefix("path=") {
+            return Ok(Some(path.to_string()));
+        }
+    }
+    Ok(None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        let long = format!("documents/{}/notes.md", "a".repeat(120));
+        let mut writer = TarWriter::new(Vec::new());
+        writer.append("manifest.json", b"{}", 0).unwrap();
+        writer
+            .append(&long, "héllo".as_bytes(), 1_700_000_000)
+            .unwrap();
+        writer.append("empty.md", b"", 0).unwrap();
+        let archive = writer.finish().unwrap();
+        assert_eq!(archive.len() % BLOCK, 0);
+
+        let mut reader = TarReader::new(archive.as_slice());
+        let files: Vec<(String, Vec<u8>)> = std::iter::from_fn(|| reader.next_file().unwrap())
+            .map(|f| (f.path, f.data))
+            .collect();
+        assert_eq!(
+            files,
+            vec![
+                ("manifest.json".to_string(), b"{}".to_vec()),
+                (long, "héllo".as_bytes().to_vec()),
+                ("empty.md".to_string(), Vec::new()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_pax_record_length_counts_itself() {
+        let record = pax_record("path", &"x".repeat(93));
+        assert_eq!(record.len(), 103);
+        assert!(record.starts_with("103 path="));
+        assert_eq!(pax_path(record.as_bytes()).unwrap(), Some("x".repeat(93)));
+    }
+
+    #[test]
+    fn test_corrupt_header_rejected() {
+        let mut writer = TarWriter::new(Vec::new());
+        writer.append("a.md", b"x", 0).unwrap();
+        let mut archive = writer.finish().unwrap();
+        archive[0] = b'b';
+        let err = TarReader::new(archive.as_slice()).next_file().unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+}
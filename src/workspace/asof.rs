@@ -0,0 +1,304 @@
+//! Reading and searching the workspace as it was at an earlier time.
+//!
+//! Version history stores the content each update replaces together with
+//! when that content was written, so a document's content at any time is
+//! either its current content or the newest revision saved by then.
+//! [`Workspace::read_asof`] reads one document that way, and
+//! [`Workspace::search_asof`] searches every document, so the agent can say
+//! what its notes said last month and maintenance jobs can compare memory
+//! across time.
+//!
+//! Only documents unchanged since the time searched are still described by
+//! the search index. The past content of the others is chunked and scored
+//! on the fly (by embedding similarity, or by the query words it contains
+//! without embeddings), along with the index hits so all results are ranked
+//! alike. Every document changed since then is loaded, so searching far
+//! back in a busy workspace is slow.
+//!
+//! Only the newest [`MAX_VERSIONS`] revisions of a document are kept, which
+//! bounds how far back a frequently edited document can be read. Deleted
+//! documents are left out: the trash keeps their content but not when it
+//! was written.
+
+use std::collections::HashSet;
+
+use chrono::{DateTime, SubsecRound, Utc};
+use serde::Serialize;
+
+use crate::error::WorkspaceError;
+use crate::workspace::conflicts::{cosine, terms};
+use crate::workspace::history::MAX_VERSIONS;
+use crate::workspace::{
+    DocumentMetadata, MemoryDocument, PATH_PAGE_SIZE, SearchConfig, Workspace, chunk_document,
+    embed_batch_recorded, frontmatter, trash,
+};
+
+/// A chunk of a document as it read at the time searched.
+#[derive(Debug, Clone, Serialize)]
+pub struct AsOfResult {
+    /// Workspace path of the document.
+    pub path: String,
+    pub content: String,
+    /// When the document's content at the time searched was written.
+    pub saved_at: DateTime<Utc>,
+    /// Cosine similarity to the query, or the share of the query's words
+    /// the chunk contains without embeddings.
+    pub score: f32,
+}
+
+impl Workspace {
+    /// The document at `path` as it was at `at`: its content then, with
+    /// `updated_at` set to when that content was written. Fails with
+    /// [`WorkspaceError::DocumentNotFound`] if it didn't exist yet or was
+    /// empty, and [`WorkspaceError::HistoryTooShort`] if the revision has
+    /// been pruned.
+    pub async fn read_asof(
+        &self,
+        path: &str,
+        at: DateTime<Utc>,
+    ) -> Result<MemoryDocument, WorkspaceError> {
+        let doc = self.read(path).await?;
+        match self.document_asof(&doc, at).await? {
+            Some(past) => Ok(past),
+            None => Err(WorkspaceError::DocumentNotFound {
+                doc_type: doc.path,
+                user_id: self.user_id.clone(),
+            }),
+        }
+    }
+
+    /// Search the workspace and its mounts as they were at `at`, returning
+    /// up to `limit` chunks, best first. Documents whose revision from then
+    /// has been pruned are skipped.
+    pub async fn search_asof(
+        &self,
+        query: &str,
+        at: DateTime<Utc>,
+        limit: usize,
+    ) -> Result<Vec<AsOfResult>, WorkspaceError> {
+        // libSQL stores milliseconds; rounded the same way, a document
+        // updated within the millisecond of `at` is on one side of it only.
+        let at = at.trunc_subsecs(3);
+        let config = SearchConfig::default()
+            .with_limit(limit)
+            .with_updated_between(None, Some(at));
+        let mut candidates = Vec::new();
+        for hit in self.search_with_config(query, config).await? {
+            let doc = self.document_by_id(hit.document_id).await?;
+            candidates.push(AsOfResult {
+                path: doc.path,
+                content: hit.content,
+                saved_at: doc.updated_at,
+                score: 0.0,
+            });
+        }
+
+        let scopes =
+            std::iter::once(self.agent_id).chain(self.mounts.iter().map(|m| Some(m.scope())));
+        for agent_id in scopes {
+            let mut after: Option<String> = None;
+            loop {
+                let page = self
+                    .storage
+                    .list_paths_page(&self.user_id, agent_id, after.as_deref(), PATH_PAGE_SIZE)
+                    .await?;
+                let changed = self
+                    .storage
+                    .get_documents_by_paths(&self.user_id, agent_id, &page)
+                    .await?
+                    .into_iter()
+                    .filter(|d| d.updated_at >= at && !trash::is_trashed(&d.path));
+                for doc in changed {
+                    let doc = self.mounted(doc);
+                    let past = match self.document_asof(&doc, at).await {
+                        Ok(Some(past)) => past,
+                        Ok(None) => continue,
+                        Err(WorkspaceError::HistoryTooShort { .. }) => {
+                            tracing::debug!("No revision of {} as of {} is kept", doc.path, at);
+                            continue;
+                        }
+                        Err(e) => return Err(e),
+                    };
+                    let chunks = chunk_document(
+                        frontmatter::strip_frontmatter(&past.content),
+                        self.chunk_config.clone(),
+                    );
+                    candidates.extend(chunks.into_iter().map(|content| AsOfResult {
+                        path: past.path.clone(),
+                        content,
+                        saved_at: past.updated_at,
+                        score: 0.0,
+                    }));
+                }
+                if page.len() < PATH_PAGE_SIZE {
+                    break;
+                }
+                after = page.last().cloned();
+            }
+        }
+
+        self.score_asof(query, &mut candidates).await?;
+        candidates.retain(|c| c.score > 0.0);
+        candidates.sort_by(|a, b| b.score.total_cmp(&a.score));
+        candidates.truncate(limit);
+        Ok(candidates)
+    }
+
+    /// `doc` as it was at `at`, or None if it didn't exist yet or was empty.
+    async fn document_asof(
+        &self,
+        doc: &MemoryDocument,
+        at: DateTime<Utc>,
+    ) -> Result<Option<MemoryDocument>, WorkspaceError> {
+        if doc.created_at > at {
+            return Ok(None);
+        }
+        if doc.updated_at <= at {
+            return Ok((!doc.content.is_empty()).then(|| doc.clone()));
+        }
+
+        let versions = self.storage.list_document_versions(doc.id).await?;
+        let Some(version) = versions.iter().find(|v| v.saved_at <= at) else {
+            // Empty content isn't stored as a revision, so unless older
+            // revisions were pruned the document was still empty.
+            if versions.len() < MAX_VERSIONS {
+                return Ok(None);
+            }
+            return Err(WorkspaceError::HistoryTooShort {
+                path: doc.path.clone(),
+                at: at.to_rfc3339(),
+            });
+        };
+        let past = self
+            .storage
+            .get_document_version(doc.id, version.version)
+            .await?
+            .ok_or_else(|| WorkspaceError::HistoryTooShort {
+                path: doc.path.clone(),
+                at: at.to_rfc3339(),
+            })?;
+        Ok(Some(MemoryDocument {
+            frontmatter: DocumentMetadata::parse(&past.content),
+            content: past.content,
+            updated_at: past.saved_at,
+            ..doc.clone()
+        }))
+    }
+
+    /// Score `candidates` against `query` on one scale.
+    async fn score_asof(
+        &self,
+        query: &str,
+        candidates: &mut [AsOfResult],
+    ) -> Result<(), WorkspaceError> {
+        if candidates.is_empty() {
+            return Ok(());
+        }
+        let Some(provider) = &self.embeddings else {
+            let words = terms(query);
+            for candidate in candidates {
+                candidate.score = coverage(&words, &terms(&candidate.content));
+            }
+            return Ok(());
+        };
+
+        let mut texts = Vec::with_capacity(candidates.len() + 1);
+        texts.push(query.to_string());
+        texts.extend(candidates.iter().map(|c| c.content.clone()));
+        let vectors = embed_batch_recorded(provider.as_ref(), &texts)
+            .await
+            .map_err(|e| WorkspaceError::EmbeddingFailed {
+                reason: e.to_string(),
+            })?;
+        if let Some((query, rest)) = vectors.split_first() {
+            for (candidate, vector) in candidates.iter_mut().zip(rest) {
+                candidate.score = cosine(query, vector);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Share of the words in `query` that `text` contains.
+fn coverage(query: &HashSet<String>, text: &HashSet<String>) -> f32 {
+    if query.is_empty() {
+        return 0.0;
+    }
+    query.intersection(text).count() as f32 / query.len() as f32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_coverage() {
+        let query = terms("rocket launch date");
+        assert_eq!(
+            coverage(&query, &terms("The rocket launch slipped")),
+            2.0 / 3.0
+        );
+        assert_eq!(coverage(&query, &terms("Buy milk")), 0.0);
+        assert_eq!(coverage(&HashSet::new(), &terms("rocket")), 0.0);
+    }
+
+    #[cfg(feature = "libsql")]
+    #[tokio::test]
+    async fn test_read_and_search_asof() {
+        use std::time::Duration;
+
+        use crate::db::libsql_backend::test_db;
+
+        let (db, _dir) = test_db().await;
+        let ws = Workspace::new_with_db("default", db);
+        let pause = || tokio::time::sleep(Duration::from_millis(20));
+
+        let before = Utc::now();
+        pause().await;
+        ws.write("notes/plan.md", "Launch the rocket on Friday")
+            .await
+            .unwrap();
+        ws.write("notes/colors.md", "The rocket is painted red")
+            .await
+            .unwrap();
+        pause().await;
+        let then = Utc::now();
+        pause().await;
+        ws.write("notes/plan.md", "Launch the rocket on Monday")
+            .await
+            .unwrap();
+        ws.write("notes/fuel.md", "Order rocket fuel")
+            .await
+            .unwrap();
+
+        let past = ws.read_asof("notes/plan.md", then).await.unwrap();
+        assert_eq!(past.content, "Launch the rocket on Friday");
+        assert!(past.updated_at <= then);
+        let now = ws.read_asof("notes/plan.md", Utc::now()).await.unwrap();
+        assert_eq!(now.content, "Launch the rocket on Monday");
+        for (path, at) in [("notes/plan.md", before), ("notes/fuel.md", then)] {
+            assert!(matches!(
+                ws.read_asof(path, at).await,
+                Err(WorkspaceError::DocumentNotFound { .. })
+            ));
+        }
+
+        let results = ws.search_asof("rocket", then, 10).await.unwrap();
+        let mut paths: Vec<&str> = results.iter().map(|r| r.path.as_str()).collect();
+        paths.sort();
+        assert_eq!(paths, vec!["notes/colors.md", "notes/plan.md"]);
+        let plan = results.iter().find(|r| r.path == "notes/plan.md").unwrap();
+        assert_eq!(plan.content, "Launch the rocket on Friday");
+        assert!(ws.search_asof("Monday", then, 10).await.unwrap().is_empty());
+        assert_eq!(
+            ws.search_asof("fuel", Utc::now(), 10).await.unwrap().len(),
+            1
+        );
+        assert!(
+            ws.search_asof("rocket", before, 10)
+                .await
+                .unwrap()
+                .is_empty()
+        );
+    }
+}
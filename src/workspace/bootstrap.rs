@@ -0,0 +1,352 @@
+//! First-run bootstrap.
+//!
+//! A new workspace is seeded with BOOTSTRAP.md, the ritual in which the
+//! agent and its user get to know each other. While that file exists the
+//! workspace is in bootstrap mode:
+//!
+//! - the system prompt carries the ritual and lists the profile fields
+//!   still to be filled in
+//! - the `bootstrap` tool records the answers as structured fields in
+//!   IDENTITY.md and USER.md (which `memory_write` refuses to touch) and
+//!   deletes BOOTSTRAP.md once the ritual is done
+//!
+//! Fields are the `- **Label:** value` lines of the seeded templates.
+//! Values are kept to one short line, since both files go into every
+//! system prompt.
+
+use std::collections::BTreeMap;
+
+use serde::Serialize;
+
+use crate::error::WorkspaceError;
+use crate::workspace::frontmatter::strip_frontmatter;
+use crate::workspace::{Workspace, paths, prompt_budget};
+
+/// Longest value stored in a profile field, in characters.
+const MAX_VALUE_CHARS: usize = 200;
+
+/// A profile document filled in during bootstrap.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Profile {
+    /// The agent's IDENTITY.md.
+    Identity,
+    /// The user's USER.md.
+    User,
+}
+
+impl Profile {
+    /// Path of the profile document.
+    pub fn path(self) -> &'static str {
+        match self {
+            Self::Identity => paths::IDENTITY,
+            Self::User => paths::USER,
+        }
+    }
+
+    /// `(key, label)` of each field, in document order.
+    pub fn fields(self) -> &'static [(&'static str, &'static str)] {
+        match self {
+            Self::Identity => &[
+                ("name", "Name"),
+                ("creature", "Creature"),
+                ("vibe", "Vibe"),
+                ("emoji", "Emoji"),
+                ("avatar", "Avatar"),
+            ],
+            Self::User => &[
+                ("name", "Name"),
+                ("call_them", "What to call them"),
+                ("pronouns", "Pronouns"),
+                ("timezone", "Timezone"),
+                ("location", "Location"),
+                ("notes", "Notes"),
+            ],
+        }
+    }
+
+    /// Keys of the fields bootstrap should fill in.
+    fn required(self) -> &'static [&'static str] {
+        match self {
+            Self::Identity => &["name", "creature", "vibe", "emoji"],
+            Self::User => &["name", "call_them", "timezone"],
+        }
+    }
+
+    fn prefix(self) -> &'static str {
+        match self {
+            Self::Identity => "identity",
+            Self::User => "user",
+        }
+    }
+}
+
+/// Where bootstrap stands.
+#[derive(Debug, Clone, Serialize)]
+pub struct BootstrapStatus {
+    /// Whether BOOTSTRAP.md still exists.
+    pub active: bool,
+    /// Filled-in IDENTITY.md fields by key.
+    pub identity: BTreeMap<String, String>,
+    /// Filled-in USER.md fields by key.
+    pub user: BTreeMap<String, String>,
+    /// Required fields still empty, as `identity.<key>` or `user.<key>`.
+    pub missing: Vec<String>,
+}
+
+impl Workspace {
+    /// Whether the workspace is in bootstrap mode (BOOTSTRAP.md exists).
+    pub async fn in_bootstrap(&self) -> bool {
+        self.exists(paths::BOOTSTRAP).await.unwrap_or(false)
+    }
+
+    /// The profile fields filled in so far and those still missing.
+    pub async fn bootstrap_status(&self) -> Result<BootstrapStatus, WorkspaceError> {
+        let identity = self.profile(Profile::Identity).await?;
+        let user = self.profile(Profile::User).await?;
+        let mut missing = Vec::new();
+        for (profile, values) in [(Profile::Identity, &identity), (Profile::User, &user)] {
+            for key in profile.required() {
+                if !values.contains_key(*key) {
+                    missing.push(format!("{}.{}", profile.prefix(), key));
+                }
+            }
+        }
+        Ok(BootstrapStatus {
+            active: self.in_bootstrap().await,
+            identity,
+            user,
+            missing,
+        })
+    }
+
+    /// The filled-in fields of `profile`.
+    pub async fn profile(
+        &self,
+        profile: Profile,
+    ) -> Result<BTreeMap<String, String>, WorkspaceError> {
+        match self.read(profile.path()).await {
+            Ok(doc) => Ok(read_fields(&doc.content, profile)),
+            Err(WorkspaceError::DocumentNotFound { .. }) => Ok(BTreeMap::new()),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Set fields of `profile` from `values` (keyed as in
+    /// [`Profile::fields`]; other keys are ignored).
+    pub async fn update_profile(
+        &self,
+        profile: Profile,
+        values: &BTreeMap<String, String>,
+    ) -> Result<(), WorkspaceError> {
+        let content = match self.read(profile.path()).await {
+            Ok(doc) => doc.content,
+            Err(WorkspaceError::DocumentNotFound { .. }) => String::new(),
+            Err(e) => return Err(e),
+        };
+        let updated = write_fields(&content, profile, values);
+        if updated != content {
+            self.write(profile.path(), &updated).await?;
+        }
+        Ok(())
+    }
+
+    /// End bootstrap mode by deleting BOOTSTRAP.md.
+    pub async fn complete_bootstrap(&self) -> Result<(), WorkspaceError> {
+        self.delete(paths::BOOTSTRAP).await
+    }
+
+    /// The "Bootstrap Mode" system prompt section, while in bootstrap mode.
+    pub(super) async fn bootstrap_context(&self, user_id: &str) -> Option<String> {
+        self.check_access(user_id, paths::BOOTSTRAP).ok()?;
+        let doc = self.read(paths::BOOTSTRAP).await.ok()?;
+        let ritual = prompt_budget::fit_head(
+            strip_frontmatter(&doc.content),
+            self.prompt_budget.identity_file,
+            paths::BOOTSTRAP,
+        );
+        let mut section = format!("## Bootstrap Mode\n\n{}", ritual);
+        if let Ok(status) = self.bootstrap_status().await
+            && !status.missing.is_empty()
+        {
+            section.push_str(&format!(
+                "\n\nStill to fill in: {}.",
+                status.missing.join(", ")
+            ));
+        }
+        section.push_str(
+            "\n\nRecord answers with the `bootstrap` tool (IDENTITY.md and USER.md can't be \
+             written any other way), and call it with `complete: true` when the ritual is done.",
+        );
+        Some(section)
+    }
+}
+
+/// The filled-in `- **Label:** value` fields of `content`.
+fn read_fields(content: &str, profile: Profile) -> BTreeMap<String, String> {
+    let mut values = BTreeMap::new();
+    for line in content.lines() {
+        for (key, label) in profile.fields() {
+            if let Some(value) = field_value(line, label)
+                && !is_placeholder(value)
+            {
+                values.insert(key.to_string(), value.to_string());
+            }
+        }
+    }
+    values
+}
+
+/// The value of a `- **label:** value` line.
+fn field_value<'a>(line: &'a str, label: &str) -> Option<&'a str> {
+    let rest = line.trim_start().strip_prefix("- **")?;
+    let rest = rest.strip_prefix(label)?.strip_prefix(":**")?;
+    Some(rest.trim())
+}
+
+/// Template hint such as `_(optional)_` rather than a value.
+fn is_placeholder(value: &str) -> bool {
+    value.is_empty() || (value.starts_with("_(") && value.ends_with(")_"))
+}
+
+/// Template hint indented under a field line.
+fn is_hint(line: &str) -> bool {
+    line.starts_with([' ', '\t']) && is_placeholder(line.trim())
+}
+
+/// `content` with the fields of `profile` in `values` set. Fields missing
+/// from the document are added after the last field line.
+fn write_fields(content: &str, profile: Profile, values: &BTreeMap<String, String>) -> String {
+    let mut lines: Vec<String> = content.lines().map(str::to_string).collect();
+    for (key, label) in profile.fields() {
+        let Some(value) = values.get(*key).map(|v| clean_value(v)) else {
+            continue;
+        };
+        if value.is_empty() {
+            continue;
+        }
+        let line = format!("- **{}:** {}", label, value);
+        match lines.iter().position(|l| field_value(l, label).is_some()) {
+            Some(i) => {
+                lines[i] = line;
+                // Drop the hint lines indented under the field.
+                while lines.get(i + 1).is_some_and(|l| is_hint(l)) {
+                    lines.remove(i + 1);
+                }
+            }
+            None => {
+                let last = lines.iter().rposition(|l| {
+                    profile
+                        .fields()
+                        .iter()
+                        .any(|(_, label)| field_value(l, label).is_some())
+                });
+                match last {
+                    Some(mut i) => {
+                        while lines.get(i + 1).is_some_and(|l| is_hint(l)) {
+                            i += 1;
+                        }
+                        lines.insert(i + 1, line);
+                    }
+                    None => {
+                        if lines.last().is_some_and(|l| !l.trim().is_empty()) {
+                            lines.push(String::new());
+                        }
+                        lines.push(line);
+                    }
+                }
+            }
+        }
+    }
+    let mut updated = lines.join("\n");
+    if content.ends_with('\n') || content.is_empty() {
+        updated.push('\n');
+    }
+    updated
+}
+
+/// `value` on one line, within [`MAX_VALUE_CHARS`].
+fn clean_value(value: &str) -> String {
+    value
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .chars()
+        .take(MAX_VALUE_CHARS)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const IDENTITY: &str = "# IDENTITY.md\n\n\
+                            - **Name:** SiloClaw\n\
+                            - **Creature:**\n  _(AI? robot? familiar?)_\n\
+                            - **Vibe:**\n  _(sharp? warm?)_\n\
+                            - **Emoji:**\n  _(pick one)_\n\n---\n\nNotes.\n";
+
+    #[test]
+    fn test_fields_are_read_and_written_in_place() {
+        let fields = read_fields(IDENTITY, Profile::Identity);
+        assert_eq!(fields.len(), 1);
+        assert_eq!(fields["name"], "SiloClaw");
+
+        let values = BTreeMap::from([
+            (
+                "creature".to_string(),
+                "a ghost\nin the machine".to_string(),
+            ),
+            ("avatar".to_string(), "avatars/me.png".to_string()),
+            ("unknown".to_string(), "ignored".to_string()),
+        ]);
+        let updated = write_fields(IDENTITY, Profile::Identity, &values);
+        assert_eq!(
+            updated,
+            "# IDENTITY.md\n\n\
+             - **Name:** SiloClaw\n\
+             - **Creature:** a ghost in the machine\n\
+             - **Vibe:**\n  _(sharp? warm?)_\n\
+             - **Emoji:**\n  _(pick one)_\n\
+             - **Avatar:** avatars/me.png\n\n---\n\nNotes.\n"
+        );
+        let fields = read_fields(&updated, Profile::Identity);
+        assert_eq!(fields["creature"], "a ghost in the machine");
+        assert!(!fields.contains_key("vibe"));
+    }
+
+    #[cfg(feature = "libsql")]
+    #[tokio::test]
+    async fn test_bootstrap_mode() {
+        use crate::db::libsql_backend::test_db;
+
+        let (db, _dir) = test_db().await;
+        let ws = Workspace::new_with_db("default", db);
+        ws.seed_if_empty().await.unwrap();
+
+        assert!(ws.in_bootstrap().await);
+        let status = ws.bootstrap_status().await.unwrap();
+        assert!(status.missing.contains(&"user.timezone".to_string()));
+        assert!(!status.missing.contains(&"identity.name".to_string()));
+        let prompt = ws.system_prompt().await.unwrap();
+        assert!(prompt.contains("## Bootstrap Mode"));
+        assert!(prompt.contains("user.timezone"));
+
+        let values = BTreeMap::from([
+            ("name".to_string(), "Ada".to_string()),
+            ("timezone".to_string(), "Europe/London".to_string()),
+        ]);
+        ws.update_profile(Profile::User, &values).await.unwrap();
+        let user = ws.read(paths::USER).await.unwrap();
+        assert!(user.content.contains("- **Timezone:** Europe/London\n"));
+        assert!(user.content.contains("- **Pronouns:** _(optional)_\n"));
+
+        ws.complete_bootstrap().await.unwrap();
+        assert!(!ws.in_bootstrap().await);
+        assert!(
+            !ws.system_prompt()
+                .await
+                .unwrap()
+                .contains("## Bootstrap Mode")
+        );
+    }
+}
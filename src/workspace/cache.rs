@@ -0,0 +1,343 @@
+//! Workspace change events and the read-through document cache.
+//!
+//! Every mutation made through a [`Workspace`](super::Workspace) is
+//! published as a [`WorkspaceEvent`] on its [`WorkspaceEvents`] stream.
+//! Workspaces that share a database should share one stream (see
+//! [`Workspace::with_events`](super::Workspace::with_events)) so a write
+//! through one is seen by the others.
+//!
+//! [`DocumentCache`] keeps recently read documents (and paths known not to
+//! exist) in memory, so the identity files and MEMORY.md read on every
+//! prompt don't hit the database each turn. It drops entries as change
+//! events arrive. Writers outside this process (another agent process, the
+//! CLI) don't publish events, so entries also expire after a TTL.
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use tokio::sync::broadcast;
+use uuid::Uuid;
+
+use crate::workspace::MemoryDocument;
+
+/// Events buffered per subscriber before it is considered lagged.
+const EVENT_CAPACITY: usize = 256;
+
+/// A change to a workspace document.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WorkspaceEvent {
+    /// A document was created or its content changed.
+    Changed {
+        user_id: String,
+        agent_id: Option<Uuid>,
+        path: String,
+    },
+    /// A document was deleted.
+    Deleted {
+        user_id: String,
+        agent_id: Option<Uuid>,
+        path: String,
+    },
+    /// Only a document's metadata changed.
+    MetadataChanged { document_id: Uuid },
+}
+
+/// Broadcast stream of [`WorkspaceEvent`]s.
+#[derive(Debug, Clone)]
+pub struct WorkspaceEvents {
+    tx: broadcast::Sender<WorkspaceEvent>,
+}
+
+impl WorkspaceEvents {
+    pub fn new() -> Self {
+        let (tx, _) = broadcast::channel(EVENT_CAPACITY);
+        Self { tx }
+    }
+
+    /// Publish an event. Succeeds even with no subscribers.
+    pub fn publish(&self, event: WorkspaceEvent) {
+        let _ = self.tx.send(event);
+    }
+
+    /// Receive events published from now on.
+    pub fn subscribe(&self) -> broadcast::Receiver<WorkspaceEvent> {
+        self.tx.subscribe()
+    }
+}
+
+impl Default for WorkspaceEvents {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Scope and path a document is stored under.
+type CacheKey = (String, Option<Uuid>, String);
+
+struct Entry {
+    /// `None` records that the document doesn't exist.
+    doc: Option<MemoryDocument>,
+    cached_at: Instant,
+    last_used: Instant,
+}
+
+struct State {
+    entries: HashMap<CacheKey, Entry>,
+    events: broadcast::Receiver<WorkspaceEvent>,
+    /// Bumped whenever events are applied, so a read that started before a
+    /// change can't store what it fetched.
+    generation: u64,
+}
+
+/// Result of a cache lookup.
+pub(super) enum Lookup {
+    /// Cached document, or `None` if it is known not to exist.
+    Hit(Option<MemoryDocument>),
+    /// Not cached; pass the generation to [`DocumentCache::insert`].
+    Miss(u64),
+}
+
+/// Read-through cache of workspace documents, invalidated by
+/// [`WorkspaceEvent`]s.
+pub struct DocumentCache {
+    state: Mutex<State>,
+    capacity: usize,
+    ttl: Duration,
+}
+
+impl DocumentCache {
+    /// Cache up to `capacity` documents for at most `ttl`, dropping them as
+    /// `events` reports changes.
+    pub fn new(capacity: usize, ttl: Duration, events: &WorkspaceEvents) -> Self {
+        Self {
+            state: Mutex::new(State {
+                entries: HashMap::new(),
+                events: events.subscribe(),
+                generation: 0,
+            }),
+            capacity,
+            ttl,
+        }
+    }
+
+    /// Follow a different event stream, dropping everything cached.
+    pub(super) fn resubscribe(&self, events: &WorkspaceEvents) {
+        let mut state = self.lock();
+        state.events = events.subscribe();
+        state.entries.clear();
+        state.generation += 1;
+    }
+
+    pub(super) fn get(&self, user_id: &str, agent_id: Option<Uuid>, path: &str) -> Lookup {
+        let mut state = self.lock();
+        Self::apply_events(&mut state);
+        let key = (user_id.to_string(), agent_id, path.to_string());
+        let now = Instant::now();
+        match state.entries.get_mut(&key) {
+            Some(entry) if now.duration_since(entry.cached_at) < self.ttl => {
+                entry.last_used = now;
+                Lookup::Hit(entry.doc.clone())
+            }
+            Some(_) => {
+                state.entries.remove(&key);
+                Lookup::Miss(state.generation)
+            }
+            None => Lookup::Miss(state.generation),
+        }
+    }
+
+    /// Store the result of a read that missed at `generation`.
+    pub(super) fn insert(
+        &self,
+        user_id: &str,
+        agent_id: Option<Uuid>,
+        path: &str,
+        doc: Option<MemoryDocument>,
+        generation: u64,
+    ) {
+        let mut state = self.lock();
+        Self::apply_events(&mut state);
+        if state.generation != generation || self.capacity == 0 {
+            return;
+        }
+        if state.entries.len() >= self.capacity
+            && let Some(oldest) = state
+                .entries
+                .iter()
+                .min_by_key(|(_, e)| e.last_used)
+                .map(|(k, _)| k.clone())
+        {
+            state.entries.remove(&oldest);
+        }
+        let now = Instant::now();
+        state.entries.insert(
+            (user_id.to_string(), agent_id, path.to_string()),
+            Entry {
+                doc,
+                cached_at: now,
+                last_used: now,
+            },
+        );
+    }
+
+    /// Number of cached entries.
+    pub fn len(&self) -> usize {
+        let mut state = self.lock();
+        Self::apply_events(&mut state);
+        state.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, State> {
+        // Every update leaves the state consistent, so a panic elsewhere
+        // while holding the lock doesn't invalidate it.
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn apply_events(state: &mut State) {
+        loop {
+            match state.events.try_recv() {
+                Ok(event) => {
+                    state.generation += 1;
+                    match event {
+                        WorkspaceEvent::Changed {
+                            user_id,
+                            agent_id,
+                            path,
+                        }
+                        | WorkspaceEvent::Deleted {
+                            user_id,
+                            agent_id,
+                            path,
+                        } => {
+                            state.entries.remove(&(user_id, agent_id, path));
+                        }
+                        WorkspaceEvent::MetadataChanged { document_id } => {
+                            state
+                                .entries
+                                .retain(|_, e| e.doc.as_ref().is_none_or(|d| d.id != document_id));
+                        }
+                    }
+                }
+                Err(broadcast::error::TryRecvError::Lagged(_)) => {
+                    // Missed events: nothing cached can be trusted.
+                    state.entries.clear();
+                    state.generation += 1;
+                }
+                Err(_) => break,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn doc(path: &str) -> MemoryDocument {
+        MemoryDocument::new("u", None, path)
+    }
+
+    fn changed(path: &str) -> WorkspaceEvent {
+        WorkspaceEvent::Changed {
+            user_id: "u".to_string(),
+            agent_id: None,
+            path: path.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_events_invalidate_entries() {
+        let events = WorkspaceEvents::new();
+        let cache = DocumentCache::new(8, Duration::from_secs(60), &events);
+
+        let Lookup::Miss(generation) = cache.get("u", None, "SOUL.md") else {
+            panic!("empty cache hit");
+        };
+        cache.insert("u", None, "SOUL.md", Some(doc("SOUL.md")), generation);
+        cache.insert("u", None, "USER.md", None, generation);
+        assert!(matches!(
+            cache.get("u", None, "SOUL.md"),
+            Lookup::Hit(Some(_))
+        ));
+        assert!(matches!(cache.get("u", None, "USER.md"), Lookup::Hit(None)));
+
+        events.publish(changed("USER.md"));
+        assert!(matches!(cache.get("u", None, "USER.md"), Lookup::Miss(_)));
+        assert!(matches!(
+            cache.get("u", None, "SOUL.md"),
+            Lookup::Hit(Some(_))
+        ));
+
+        let Lookup::Hit(Some(soul)) = cache.get("u", None, "SOUL.md") else {
+            panic!("expected hit");
+        };
+        events.publish(WorkspaceEvent::MetadataChanged {
+            document_id: soul.id,
+        });
+        assert!(cache.is_empty());
+    }
+
+    #[test]
+    fn test_stale_reads_are_not_stored() {
+        let events = WorkspaceEvents::new();
+        let cache = DocumentCache::new(8, Duration::from_secs(60), &events);
+
+        let Lookup::Miss(generation) = cache.get("u", None, "MEMORY.md") else {
+            panic!("empty cache hit");
+        };
+        // A write lands while the read is in flight.
+        events.publish(changed("MEMORY.md"));
+        cache.insert("u", None, "MEMORY.md", Some(doc("MEMORY.md")), generation);
+        assert!(cache.is_empty());
+    }
+
+    #[test]
+    fn test_capacity_and_ttl() {
+        let events = WorkspaceEvents::new();
+        let cache = DocumentCache::new(2, Duration::from_secs(60), &events);
+        for path in ["a.md", "b.md", "c.md"] {
+            let Lookup::Miss(generation) = cache.get("u", None, path) else {
+                panic!("unexpected hit");
+            };
+            cache.insert("u", None, path, Some(doc(path)), generation);
+        }
+        assert_eq!(cache.len(), 2);
+        assert!(matches!(cache.get("u", None, "a.md"), Lookup::Miss(_)));
+
+        let cache = DocumentCache::new(2, Duration::ZERO, &events);
+        cache.insert("u", None, "a.md", Some(doc("a.md")), 0);
+        assert!(matches!(cache.get("u", None, "a.md"), Lookup::Miss(_)));
+    }
+
+    #[cfg(feature = "libsql")]
+    #[tokio::test]
+    async fn test_cached_reads_see_writes_from_shared_workspaces() {
+        use std::sync::Arc;
+
+        use crate::db::libsql_backend::test_db;
+        use crate::workspace::Workspace;
+
+        let (db, _dir) = test_db().await;
+        let events = WorkspaceEvents::new();
+        let agent = Workspace::new_with_db("default", Arc::clone(&db))
+            .with_events(events.clone())
+            .with_document_cache(8, Duration::from_secs(60));
+        let tools = Workspace::new_with_db("default", db).with_events(events);
+
+        assert!(agent.read("MEMORY.md").await.is_err());
+        tools.write("MEMORY.md", "first").await.unwrap();
+        assert_eq!(agent.read("MEMORY.md").await.unwrap().content, "first");
+
+        tools.append("MEMORY.md", "second").await.unwrap();
+        let doc = agent.read("MEMORY.md").await.unwrap();
+        assert!(doc.content.contains("second"));
+
+        tools.delete("MEMORY.md").await.unwrap();
+        assert!(agent.read("MEMORY.md").await.is_err());
+    }
+}
@@ -0,0 +1,92 @@
+//! Canonical document paths.
+//!
+//! Every path a document operation is given is normalized (slashes trimmed
+//! and collapsed, Unicode NFC) and checked: `.`/`..` segments and control
+//! characters are rejected rather than stored as literal path text.
+//!
+//! With [`Workspace::with_case_insensitive_paths`], an existing document
+//! is found whatever the case of the path it is addressed by, so writing
+//! `projects/alpha.md` updates `Projects/Alpha.md` instead of creating a
+//! second document.
+
+use crate::error::WorkspaceError;
+use crate::workspace::{Workspace, normalize_path};
+
+impl Workspace {
+    /// The normalized, checked form of `path`; with case-insensitive paths,
+    /// the spelling of the existing document it refers to.
+    pub(super) async fn canonical(&self, path: &str) -> Result<String, WorkspaceError> {
+        let path = normalize_path(path);
+        check_path(&path)?;
+        if !self.case_insensitive_paths {
+            return Ok(path);
+        }
+        let target = self.resolve(&path);
+        let found = self
+            .storage
+            .find_document_path(&self.user_id, target.agent_id, &target.path)
+            .await?;
+        Ok(match (found, target.mount) {
+            (Some(inner), Some(mount)) => mount.join(&inner),
+            (Some(inner), None) => inner,
+            (None, _) => path,
+        })
+    }
+}
+
+/// Reject paths that would mean something else on a filesystem.
+pub(super) fn check_path(path: &str) -> Result<(), WorkspaceError> {
+    let invalid = |reason: &str| WorkspaceError::InvalidPath {
+        path: path.to_string(),
+        reason: reason.to_string(),
+    };
+    if path
+        .split('/')
+        .any(|segment| segment == "." || segment == "..")
+    {
+        return Err(invalid("'.' and '..' segments are not allowed"));
+    }
+    if path.chars().any(char::is_control) {
+        return Err(invalid("control characters are not allowed"));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_path() {
+        assert!(check_path("projects/alpha.md").is_ok());
+        assert!(check_path("notes/v1..v2.md").is_ok());
+        assert!(check_path("../secrets.md").is_err());
+        assert!(check_path("a/./b.md").is_err());
+        assert!(check_path("a\nb.md").is_err());
+        assert!(check_path("a\u{7f}.md").is_err());
+    }
+
+    #[cfg(feature = "libsql")]
+    #[tokio::test]
+    async fn test_case_insensitive_paths() {
+        use std::sync::Arc;
+
+        use crate::db::libsql_backend::test_db;
+
+        let (db, _dir) = test_db().await;
+
+        let ws = Workspace::new_with_db("default", Arc::clone(&db));
+        ws.write("Projects/Alpha.md", "one").await.unwrap();
+        assert!(!ws.exists("projects/alpha.md").await.unwrap());
+        assert!(ws.write("../escape.md", "x").await.is_err());
+
+        let ws = Workspace::new_with_db("default", db).with_case_insensitive_paths(true);
+        ws.write("projects/ALPHA.md", "two").await.unwrap();
+        let doc = ws.read("PROJECTS/alpha.md").await.unwrap();
+        assert_eq!(doc.path, "Projects/Alpha.md");
+        assert_eq!(doc.content, "two");
+        ws.write("Projects/Zürich.md", "z").await.unwrap();
+        assert!(ws.exists("projects/zu\u{308}RICH.md").await.unwrap());
+        assert_eq!(ws.list_all().await.unwrap().len(), 2);
+    }
+}
@@ -3,6 +3,15 @@
 //! Documents are split into overlapping chunks for better search recall.
 //! The overlap ensures context is preserved across chunk boundaries.
 
+/// Version of the chunking algorithm. Bump it whenever [`chunk_document`]
+/// starts splitting the same input differently, or documents are chunked
+/// from different text, so
+/// [`Workspace::reindex_all`](crate::workspace::Workspace::reindex_all)
+/// knows existing chunks are stale.
+///
+/// 2: frontmatter is no longer chunked.
+pub const CHUNKER_VERSION: u32 = 2;
+
 /// Configuration for document chunking.
 #[derive(Debug, Clone)]
 pub struct ChunkConfig {
@@ -0,0 +1,295 @@
+//! Contradiction checks for new MEMORY.md entries.
+//!
+//! Facts change: the user moves, switches jobs, or changes their mind. If
+//! the new fact is appended while the old one stays, MEMORY.md ends up
+//! stating both. Before an entry is appended, the existing entries most
+//! related to it are found (by embedding similarity, or shared words
+//! without embeddings) and, when an LLM is available, it picks out the ones
+//! the new entry actually contradicts. The caller reports them so the agent
+//! can update or remove the stale entry.
+
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::{LlmError, WorkspaceError};
+use crate::llm::{ChatMessage, CompletionRequest, LlmProvider, extract_json};
+use crate::workspace::decay::memory_entries;
+use crate::workspace::{Workspace, embed_batch_recorded};
+
+/// Related entries considered per check.
+const CANDIDATES: usize = 5;
+
+/// Least cosine similarity for an entry to count as related.
+const MIN_SIMILARITY: f32 = 0.45;
+
+/// Least share of words in common (Jaccard) without embeddings.
+const MIN_TERM_OVERLAP: f32 = 0.2;
+
+/// Errors from checking for conflicts.
+#[derive(Debug, thiserror::Error)]
+pub enum ConflictError {
+    #[error(transparent)]
+    Workspace(#[from] WorkspaceError),
+
+    #[error(transparent)]
+    Llm(#[from] LlmError),
+}
+
+/// An existing MEMORY.md entry a new one may contradict.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct MemoryConflict {
+    pub entry: String,
+    /// How related the entries are (cosine similarity, or word overlap
+    /// without embeddings).
+    pub similarity: f32,
+    /// Why the LLM judged them contradictory. `None` when no LLM checked
+    /// and the entry is only flagged as related.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Verdict {
+    #[serde(default)]
+    conflicts: Vec<VerdictEntry>,
+}
+
+#[derive(Debug, Deserialize)]
+struct VerdictEntry {
+    entry: usize,
+    #[serde(default)]
+    reason: String,
+}
+
+/// Finds MEMORY.md entries a new entry contradicts.
+pub struct ConflictChecker {
+    llm: Option<Arc<dyn LlmProvider>>,
+}
+
+impl ConflictChecker {
+    /// Ask `llm` which related entries actually conflict.
+    pub fn new(llm: Arc<dyn LlmProvider>) -> Self {
+        Self { llm: Some(llm) }
+    }
+
+    /// Flag every closely related entry, without an LLM.
+    pub fn flag_only() -> Self {
+        Self { llm: None }
+    }
+
+    /// Entries of MEMORY.md that `entry` may contradict.
+    pub async fn check(
+        &self,
+        workspace: &Workspace,
+        entry: &str,
+    ) -> Result<Vec<MemoryConflict>, ConflictError> {
+        let related = workspace.related_memory_entries(entry, CANDIDATES).await?;
+        let Some(llm) = &self.llm else {
+            return Ok(related);
+        };
+        if related.is_empty() {
+            return Ok(related);
+        }
+
+        let prompt = r#"The user's long-term memory is about to get a new entry. Decide which existing entries it contradicts: they state something the new entry says is no longer true (a changed address, job, preference, plan, or fact). Entries that merely relate to it or add detail do not conflict.
+
+Respond with JSON only:
+{"conflicts": [{"entry": <number>, "reason": "<what changed, in a few words>"}]}"#;
+        let existing = related
+            .iter()
+            .enumerate()
+            .map(|(i, c)| format!("{}. {}", i + 1, c.entry))
+            .collect::<Vec<_>>()
+            .join("\n");
+        let response = llm
+            .complete(
+                CompletionRequest::new(vec![
+                    ChatMessage::system(prompt),
+                    ChatMessage::user(format!(
+                        "New entry:\n{}\n\nExisting entries:\n{}",
+                        entry, existing
+                    )),
+                ])
+                .with_max_tokens(512)
+                .with_temperature(0.0),
+            )
+            .await?;
+
+        let json = extract_json(&response.content).unwrap_or(&response.content);
+        let verdict: Verdict = match serde_json::from_str(json) {
+            Ok(v) => v,
+            Err(e) => {
+                tracing::debug!("Unparseable conflict verdict ({}): {}", e, json);
+                return Ok(Vec::new());
+            }
+        };
+        let mut seen = HashSet::new();
+        Ok(verdict
+            .conflicts
+            .into_iter()
+            .filter(|v| seen.insert(v.entry))
+            .filter_map(|v| {
+                let mut conflict = related.get(v.entry.checked_sub(1)?)?.clone();
+                let reason = v.reason.trim();
+                conflict.reason = Some(if reason.is_empty() {
+                    "contradicts the new entry".to_string()
+                } else {
+                    reason.to_string()
+                });
+                Some(conflict)
+            })
+            .collect())
+    }
+}
+
+impl Workspace {
+    /// Up to `limit` MEMORY.md entries most related to `entry`, most related
+    /// first. Uses embeddings when configured and shared words otherwise;
+    /// entries below a minimum relatedness, and `entry` itself, are left out.
+    pub async fn related_memory_entries(
+        &self,
+        entry: &str,
+        limit: usize,
+    ) -> Result<Vec<MemoryConflict>, WorkspaceError> {
+        let doc = self.memory().await?;
+        let new_key = normalize(entry);
+        let entries: Vec<&str> = memory_entries(&doc.content)
+            .into_iter()
+            .filter(|e| normalize(e) != new_key)
+            .collect();
+        if entries.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let mut scored: Vec<(usize, f32)> = match &self.embeddings {
+            Some(provider) => {
+                let mut texts = Vec::with_capacity(entries.len() + 1);
+                texts.push(entry.to_string());
+                texts.extend(entries.iter().map(|e| e.to_string()));
+                let vectors = embed_batch_recorded(provider.as_ref(), &texts)
+                    .await
+                    .map_err(|e| WorkspaceError::EmbeddingFailed {
+                        reason: e.to_string(),
+                    })?;
+                let Some((query, rest)) = vectors.split_first() else {
+                    return Ok(Vec::new());
+                };
+                rest.iter()
+                    .enumerate()
+                    .map(|(i, v)| (i, cosine(query, v)))
+                    .filter(|(_, s)| *s >= MIN_SIMILARITY)
+                    .collect()
+            }
+            None => {
+                let query = terms(entry);
+                entries
+                    .iter()
+                    .enumerate()
+                    .map(|(i, e)| (i, overlap(&query, &terms(e))))
+                    .filter(|(_, s)| *s >= MIN_TERM_OVERLAP)
+                    .collect()
+            }
+        };
+        scored.sort_by(|a, b| b.1.total_cmp(&a.1));
+        scored.truncate(limit);
+        Ok(scored
+            .into_iter()
+            .map(|(i, similarity)| MemoryConflict {
+                entry: entries[i].to_string(),
+                similarity,
+                reason: None,
+            })
+            .collect())
+    }
+}
+
+fn normalize(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+pub(super) fn cosine(a: &[f32], b: &[f32]) -> f32 {
+    let dot: f32 = a.iter().zip(b).map(|(x, y)| x * y).sum();
+    let norm = |v: &[f32]| v.iter().map(|x| x * x).sum::<f32>().sqrt();
+    let denom = norm(a) * norm(b);
+    if denom > 0.0 { dot / denom } else { 0.0 }
+}
+
+/// Lowercased words of three or more characters.
+pub(super) fn terms(text: &str) -> HashSet<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|w| w.chars().count() >= 3)
+        .map(str::to_lowercase)
+        .collect()
+}
+
+/// Jaccard overlap of two word sets.
+fn overlap(a: &HashSet<String>, b: &HashSet<String>) -> f32 {
+    let union = a.union(b).count();
+    if union == 0 {
+        return 0.0;
+    }
+    a.intersection(b).count() as f32 / union as f32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_overlap_and_cosine() {
+        let a = terms("Lives in Berlin, Germany");
+        let b = terms("Lives in Munich, Germany");
+        assert!((overlap(&a, &b) - 0.5).abs() < 1e-6);
+        assert_eq!(overlap(&a, &terms("")), 0.0);
+        assert!((cosine(&[1.0, 0.0], &[2.0, 0.0]) - 1.0).abs() < 1e-6);
+        assert_eq!(cosine(&[0.0, 0.0], &[1.0, 0.0]), 0.0);
+    }
+
+    #[cfg(feature = "libsql")]
+    #[tokio::test]
+    async fn test_conflicts_flagged_and_confirmed() {
+        use crate::db::libsql_backend::test_db;
+        use crate::llm::MockLlmProvider;
+
+        let (db, _dir) = test_db().await;
+        let ws = Workspace::new_with_db("default", db);
+        ws.append_memory("The user lives in Berlin, Germany")
+            .await
+            .unwrap();
+        ws.append_memory("The user lives with a cat named Miso")
+            .await
+            .unwrap();
+        ws.append_memory("Prefers window seats on flights")
+            .await
+            .unwrap();
+
+        let new = "The user lives in Munich, Germany";
+        let flagged = ConflictChecker::flag_only().check(&ws, new).await.unwrap();
+        assert_eq!(flagged[0].entry, "The user lives in Berlin, Germany");
+        assert!(flagged.iter().all(|c| c.reason.is_none()));
+        assert!(flagged.iter().all(|c| !c.entry.contains("window")));
+
+        // Entry 42 doesn't exist and is dropped.
+        let llm =
+            Arc::new(MockLlmProvider::new().with_text(
+                r#"Sure: {"conflicts": [{"entry": 1, "reason": "moved"}, {"entry": 42}]}"#,
+            ));
+        let checker = ConflictChecker::new(llm.clone());
+        let confirmed = checker.check(&ws, new).await.unwrap();
+        assert!(
+            llm.last_request()
+                .unwrap()
+                .contains("Existing entries:\n1. The user lives in Berlin, Germany")
+        );
+        assert_eq!(confirmed.len(), 1);
+        assert_eq!(confirmed[0].entry, "The user lives in Berlin, Germany");
+        assert_eq!(confirmed[0].reason.as_deref(), Some("moved"));
+
+        // Unrelated entries aren't sent to the LLM at all.
+        let none = checker.check(&ws, "Allergic to peanuts").await.unwrap();
+        assert!(none.is_empty());
+        assert_eq!(llm.request_count(), 1);
+    }
+}
@@ -0,0 +1,521 @@
+//! Memory decay: archiving MEMORY.md entries that have gone unused.
+//!
+//! MEMORY.md is split into entries at blank lines, the same separator
+//! [`Workspace::append_memory`] writes. The document's metadata records, per
+//! entry, when it was last written and when a search last returned it. A
+//! [`DecayPolicy`] decides which entries have gone stale, and
+//! [`Workspace::archive_stale_memory`] moves them into a monthly file under
+//! `memory/archive/`. Archived files are ordinary indexed documents, so
+//! search keeps finding the facts while MEMORY.md stays short.
+
+use std::collections::{BTreeMap, HashSet};
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::error::WorkspaceError;
+use crate::workspace::section::is_heading_block;
+use crate::workspace::{SearchResult, Workspace, paths};
+
+/// Directory archived entries are moved to.
+pub const ARCHIVE_DIR: &str = "memory/archive";
+
+/// Metadata key holding per-entry usage on MEMORY.md.
+const USAGE_KEY: &str = "entries";
+
+/// When a MEMORY.md entry was last written and last retrieved.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EntryUsage {
+    pub touched_at: DateTime<Utc>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retrieved_at: Option<DateTime<Utc>>,
+}
+
+impl EntryUsage {
+    /// The later of the last write and the last retrieval.
+    pub fn last_used(&self) -> DateTime<Utc> {
+        self.retrieved_at
+            .map_or(self.touched_at, |r| r.max(self.touched_at))
+    }
+}
+
+/// Rules for which MEMORY.md entries get archived.
+#[derive(Debug, Clone)]
+pub struct DecayPolicy {
+    /// Archive entries neither written nor retrieved for this long.
+    /// `None` disables age-based archival.
+    pub max_idle: Option<chrono::Duration>,
+    /// Keep at most this many entries; the least recently used beyond the
+    /// limit are archived. `None` = no limit.
+    pub max_entries: Option<usize>,
+}
+
+impl Default for DecayPolicy {
+    fn default() -> Self {
+        Self {
+            max_idle: Some(chrono::Duration::days(180)),
+            max_entries: None,
+        }
+    }
+}
+
+/// Why an entry was flagged.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StaleReason {
+    /// Unused for longer than the policy's idle limit.
+    Idle,
+    /// Pushed out by the policy's entry limit.
+    OverLimit,
+}
+
+/// A MEMORY.md entry the policy would archive.
+#[derive(Debug, Clone, Serialize)]
+pub struct StaleEntry {
+    pub content: String,
+    pub last_used: DateTime<Utc>,
+    pub reason: StaleReason,
+}
+
+/// Result of an archival pass.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ArchiveReport {
+    pub archived: Vec<StaleEntry>,
+    /// Entries left in MEMORY.md.
+    pub kept: usize,
+    /// Archive file the entries were appended to, if any were archived.
+    pub archive_path: Option<String>,
+}
+
+impl DecayPolicy {
+    /// Flag stale entries, in document order.
+    pub fn flag(&self, entries: &[(&str, EntryUsage)], now: DateTime<Utc>) -> Vec<StaleEntry> {
+        let mut reasons: Vec<Option<StaleReason>> = entries
+            .iter()
+            .map(|(content, usage)| {
+                // Section headings organize MEMORY.md; they never go stale.
+                if is_heading_block(content) {
+                    return None;
+                }
+                self.max_idle
+                    .filter(|idle| usage.last_used() < now - *idle)
+                    .map(|_| StaleReason::Idle)
+            })
+            .collect();
+
+        if let Some(max) = self.max_entries {
+            let mut live: Vec<usize> = (0..entries.len())
+                .filter(|&i| reasons[i].is_none() && !is_heading_block(entries[i].0))
+                .collect();
+            if live.len() > max {
+                live.sort_by_key(|&i| entries[i].1.last_used());
+                for &i in &live[..live.len() - max] {
+                    reasons[i] = Some(StaleReason::OverLimit);
+                }
+            }
+        }
+
+        entries
+            .iter()
+            .zip(reasons)
+            .filter_map(|((content, usage), reason)| {
+                Some(StaleEntry {
+                    content: content.to_string(),
+                    last_used: usage.last_used(),
+                    reason: reason?,
+                })
+            })
+            .collect()
+    }
+}
+
+/// Split MEMORY.md content into entries at blank lines.
+pub fn memory_entries(content: &str) -> Vec<&str> {
+    content
+        .split("\n\n")
+        .map(str::trim)
+        .filter(|e| !e.is_empty())
+        .collect()
+}
+
+/// Stable key for an entry, independent of surrounding whitespace.
+fn entry_key(entry: &str) -> String {
+    let normalized = normalize(entry);
+    blake3::hash(normalized.as_bytes()).to_hex()[..16].to_string()
+}
+
+/// Collapse whitespace the way the chunker does.
+fn normalize(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Read the usage map from MEMORY.md metadata.
+fn read_usage(metadata: &serde_json::Value) -> BTreeMap<String, EntryUsage> {
+    metadata
+        .get(USAGE_KEY)
+        .and_then(|v| serde_json::from_value(v.clone()).ok())
+        .unwrap_or_default()
+}
+
+/// Usage for exactly the entries in `content`: existing records are kept,
+/// new entries start at `baseline`, and records for removed entries drop.
+fn sync_usage(
+    usage: &BTreeMap<String, EntryUsage>,
+    content: &str,
+    baseline: DateTime<Utc>,
+) -> BTreeMap<String, EntryUsage> {
+    memory_entries(content)
+        .into_iter()
+        .map(|entry| {
+            let key = entry_key(entry);
+            let record = usage.get(&key).copied().unwrap_or(EntryUsage {
+                touched_at: baseline,
+                retrieved_at: None,
+            });
+            (key, record)
+        })
+        .collect()
+}
+
+/// Lowercased words of three or more characters.
+fn terms(text: &str) -> HashSet<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|w| w.chars().count() >= 3)
+        .map(str::to_lowercase)
+        .collect()
+}
+
+/// Entries of `content` a search for `query` surfaced through `chunks`.
+///
+/// A chunk can hold many entries, so only those sharing a term with the
+/// query count. If none do (a purely semantic match), every entry in the
+/// chunk counts.
+fn retrieved_entries<'a>(content: &'a str, query: &str, chunks: &[&str]) -> Vec<&'a str> {
+    let query_terms = terms(query);
+    let entries = memory_entries(content);
+    let mut hits = Vec::new();
+    for chunk in chunks {
+        let in_chunk: Vec<&str> = entries
+            .iter()
+            .copied()
+            .filter(|e| chunk.contains(&normalize(e)))
+            .collect();
+        let matching: Vec<&str> = in_chunk
+            .iter()
+            .copied()
+            .filter(|e| !terms(e).is_disjoint(&query_terms))
+            .collect();
+        hits.extend(if matching.is_empty() {
+            in_chunk
+        } else {
+            matching
+        });
+    }
+    hits
+}
+
+impl Workspace {
+    /// Bring MEMORY.md usage metadata in line with its content after a write.
+    pub(super) async fn track_memory_entries(&self, document_id: Uuid) {
+        if let Err(e) = self.sync_memory_usage(document_id).await {
+            tracing::warn!("Failed to track MEMORY.md entries: {}", e);
+        }
+    }
+
+    async fn sync_memory_usage(
+        &self,
+        document_id: Uuid,
+    ) -> Result<BTreeMap<String, EntryUsage>, WorkspaceError> {
+        let doc = self.storage.get_document_by_id(document_id).await?;
+        let usage = read_usage(&doc.metadata);
+        let synced = sync_usage(&usage, &doc.content, doc.updated_at);
+        if synced != usage {
+            self.save_usage(document_id, doc.metadata, &synced).await?;
+        }
+        Ok(synced)
+    }
+
+    async fn save_usage(
+        &self,
+        document_id: Uuid,
+        mut metadata: serde_json::Value,
+        usage: &BTreeMap<String, EntryUsage>,
+    ) -> Result<(), WorkspaceError> {
+        let value = serde_json::to_value(usage).map_err(|e| WorkspaceError::SearchFailed {
+            reason: format!("Failed to serialize memory usage: {}", e),
+        })?;
+        match metadata.as_object_mut() {
+            Some(map) => {
+                map.insert(USAGE_KEY.to_string(), value);
+            }
+            None => metadata = serde_json::json!({ USAGE_KEY: value }),
+        }
+        self.update_metadata(document_id, &metadata).await
+    }
+
+    /// Mark the MEMORY.md entries in `results` as retrieved.
+    pub(super) async fn record_memory_retrievals(&self, query: &str, results: &[SearchResult]) {
+        if results.is_empty() {
+            return;
+        }
+        let doc = match self
+            .storage
+            .get_document_by_path(&self.user_id, self.agent_id, paths::MEMORY)
+            .await
+        {
+            Ok(doc) => doc,
+            Err(_) => return,
+        };
+        let chunks: Vec<&str> = results
+            .iter()
+            .filter(|r| r.document_id == doc.id)
+            .map(|r| r.content.as_str())
+            .collect();
+        if chunks.is_empty() {
+            return;
+        }
+
+        let mut usage = sync_usage(&read_usage(&doc.metadata), &doc.content, doc.updated_at);
+        let now = Utc::now();
+        for entry in retrieved_entries(&doc.content, query, &chunks) {
+            if let Some(record) = usage.get_mut(&entry_key(entry)) {
+                record.retrieved_at = Some(now);
+            }
+        }
+        if let Err(e) = self.save_usage(doc.id, doc.metadata, &usage).await {
+            tracing::warn!("Failed to record MEMORY.md retrievals: {}", e);
+        }
+    }
+
+    /// MEMORY.md entries `policy` would archive now.
+    pub async fn stale_memory(
+        &self,
+        policy: &DecayPolicy,
+    ) -> Result<Vec<StaleEntry>, WorkspaceError> {
+        let doc = self.memory().await?;
+        let usage = self.sync_memory_usage(doc.id).await?;
+        Ok(policy.flag(&with_usage(&doc.content, &usage), Utc::now()))
+    }
+
+    /// Move the MEMORY.md entries `policy` flags into this month's file
+    /// under `memory/archive/`.
+    pub async fn archive_stale_memory(
+        &self,
+        policy: &DecayPolicy,
+    ) -> Result<ArchiveReport, WorkspaceError> {
+        let doc = self.memory().await?;
+        let usage = self.sync_memory_usage(doc.id).await?;
+        let entries = with_usage(&doc.content, &usage);
+        let now = Utc::now();
+        let stale = policy.flag(&entries, now);
+        if stale.is_empty() {
+            return Ok(ArchiveReport {
+                kept: entries.len(),
+                ..Default::default()
+            });
+        }
+
+        // Archive first so a failed rewrite can't lose entries.
+        let archive_path = format!("{}/{}.md", ARCHIVE_DIR, now.format("%Y-%m"));
+        let archived: HashSet<&str> = stale.iter().map(|s| s.content.as_str()).collect();
+        let section = format!(
+            "## Archived from MEMORY.md on {}\n\n{}\n",
+            now.format("%Y-%m-%d"),
+            stale
+                .iter()
+                .map(|s| s.content.as_str())
+                .collect::<Vec<_>>()
+                .join("\n\n")
+        );
+        self.append(&archive_path, &section).await?;
+
+        let kept: Vec<&str> = entries
+            .iter()
+            .map(|(content, _)| *content)
+            .filter(|content| !archived.contains(content))
+            .collect();
+        self.write(paths::MEMORY, &kept.join("\n\n")).await?;
+
+        Ok(ArchiveReport {
+            archived: stale,
+            kept: kept.len(),
+            archive_path: Some(archive_path),
+        })
+    }
+
+    /// Run [`Workspace::archive_stale_memory`] every `interval`.
+    pub fn spawn_memory_decay(
+        self: &Arc<Self>,
+        policy: DecayPolicy,
+        interval: Duration,
+    ) -> tokio::task::JoinHandle<()> {
+        let workspace = Arc::clone(self);
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            loop {
+                ticker.tick().await;
+                match workspace.archive_stale_memory(&policy).await {
+                    Ok(report) if report.archived.is_empty() => {}
+                    Ok(report) => tracing::info!(
+                        "Archived {} stale MEMORY.md entries to {}",
+                        report.archived.len(),
+                        report.archive_path.as_deref().unwrap_or_default()
+                    ),
+                    Err(e) => tracing::warn!("Failed to archive stale memory: {}", e),
+                }
+            }
+        })
+    }
+}
+
+/// Pair each entry of `content` with its usage record.
+fn with_usage<'a>(
+    content: &'a str,
+    usage: &BTreeMap<String, EntryUsage>,
+) -> Vec<(&'a str, EntryUsage)> {
+    memory_entries(content)
+        .into_iter()
+        .filter_map(|entry| Some((entry, *usage.get(&entry_key(entry))?)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn usage(days_ago: i64) -> EntryUsage {
+        EntryUsage {
+            touched_at: Utc::now() - chrono::Duration::days(days_ago),
+            retrieved_at: None,
+        }
+    }
+
+    #[test]
+    fn test_memory_entries_split_on_blank_lines() {
+        let content = "Prefers tea\n\n\n- likes hiking\n- owns a dog\n\n  \n\nLives in Oslo\n";
+        assert_eq!(
+            memory_entries(content),
+            vec![
+                "Prefers tea",
+                "- likes hiking\n- owns a dog",
+                "Lives in Oslo"
+            ]
+        );
+        assert_eq!(entry_key("a  b\nc"), entry_key(" a b c "));
+    }
+
+    #[test]
+    fn test_flag_idle_and_over_limit() {
+        let mut recent = usage(400);
+        recent.retrieved_at = Some(Utc::now() - chrono::Duration::days(2));
+        let entries = [
+            ("## Pets", usage(500)),
+            ("old", usage(300)),
+            ("retrieved", recent),
+            ("fresh", usage(1)),
+            ("older", usage(30)),
+        ];
+
+        let policy = DecayPolicy::default();
+        let stale = policy.flag(&entries, Utc::now());
+        assert_eq!(stale.len(), 1);
+        assert_eq!(stale[0].content, "old");
+        assert_eq!(stale[0].reason, StaleReason::Idle);
+
+        let policy = DecayPolicy {
+            max_idle: Some(chrono::Duration::days(180)),
+            max_entries: Some(2),
+        };
+        let flagged = policy.flag(&entries, Utc::now());
+        let stale: Vec<(&str, StaleReason)> = flagged
+            .iter()
+            .map(|s| (s.content.as_str(), s.reason))
+            .collect();
+        assert_eq!(
+            stale,
+            vec![
+                ("old", StaleReason::Idle),
+                ("older", StaleReason::OverLimit)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_retrieved_entries_prefers_query_matches() {
+        let content = "Favourite colour is green\n\nAllergic to peanuts";
+        let chunk = normalize(content);
+        assert_eq!(
+            retrieved_entries(content, "what colour", &[&chunk]),
+            vec!["Favourite colour is green"]
+        );
+        assert_eq!(retrieved_entries(content, "food?", &[&chunk]).len(), 2);
+    }
+
+    #[cfg(feature = "libsql")]
+    #[tokio::test]
+    async fn test_archive_moves_idle_entries_out_of_memory() {
+        use crate::db::libsql_backend::test_db;
+
+        let (db, _dir) = test_db().await;
+        let workspace = Workspace::new_with_db("default", Arc::clone(&db));
+
+        workspace
+            .append_memory("Old landlord was Mr Pemberton")
+            .await
+            .unwrap();
+        workspace
+            .append_memory("Prefers window seats")
+            .await
+            .unwrap();
+
+        // Age the first entry past the idle limit.
+        let doc = workspace.memory().await.unwrap();
+        let mut usage = read_usage(&doc.metadata);
+        assert_eq!(usage.len(), 2);
+        usage
+            .get_mut(&entry_key("Old landlord was Mr Pemberton"))
+            .unwrap()
+            .touched_at = Utc::now() - chrono::Duration::days(365);
+        workspace
+            .save_usage(doc.id, doc.metadata, &usage)
+            .await
+            .unwrap();
+
+        let policy = DecayPolicy::default();
+        let stale = workspace.stale_memory(&policy).await.unwrap();
+        assert_eq!(stale.len(), 1);
+
+        let report = workspace.archive_stale_memory(&policy).await.unwrap();
+        assert_eq!(report.kept, 1);
+        assert_eq!(report.archived[0].content, "Old landlord was Mr Pemberton");
+
+        let memory = workspace.memory().await.unwrap();
+        assert_eq!(memory.content, "Prefers window seats");
+        assert_eq!(read_usage(&memory.metadata).len(), 1);
+
+        let archive = workspace
+            .read(report.archive_path.as_deref().unwrap())
+            .await
+            .unwrap();
+        assert!(archive.content.contains("Old landlord was Mr Pemberton"));
+        let hits = workspace.search("Pemberton", 5).await.unwrap();
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].document_id, archive.id);
+
+        // A search hit keeps an otherwise idle entry.
+        let doc = workspace.memory().await.unwrap();
+        let mut usage = read_usage(&doc.metadata);
+        for record in usage.values_mut() {
+            record.touched_at = Utc::now() - chrono::Duration::days(365);
+        }
+        workspace
+            .save_usage(doc.id, doc.metadata, &usage)
+            .await
+            .unwrap();
+        workspace.search("window seats", 5).await.unwrap();
+        assert!(workspace.stale_memory(&policy).await.unwrap().is_empty());
+    }
+}
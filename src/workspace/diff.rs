@@ -0,0 +1,160 @@
+//! Line diffs of document edits.
+//!
+//! Memory tools return what an edit changed so the user (and the model)
+//! can see it, in unified diff format. Large edits are cut short rather
+//! than echoed back in full.
+
+/// Unchanged lines shown around each change.
+const CONTEXT: usize = 2;
+
+/// Longest diff returned, in lines (excluding the truncation note).
+const MAX_LINES: usize = 200;
+
+/// Above this many line pairs the changed region is shown as one
+/// replacement instead of being aligned line by line.
+const MAX_ALIGN: usize = 4_000_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Op {
+    Keep,
+    Remove,
+    Add,
+}
+
+/// Unified diff from `before` to `after`, or an empty string if they are
+/// the same.
+pub fn unified_diff(before: &str, after: &str) -> String {
+    let old: Vec<&str> = before.lines().collect();
+    let new: Vec<&str> = after.lines().collect();
+    let ops = align(&old, &new);
+    if ops.iter().all(|(op, _)| *op == Op::Keep) {
+        return String::new();
+    }
+
+    let mut out = Vec::new();
+    let mut i = 0;
+    while i < ops.len() {
+        if ops[i].0 == Op::Keep {
+            i += 1;
+            continue;
+        }
+        // A hunk runs from CONTEXT lines before this change to CONTEXT
+        // lines after the last change that close.
+        let start = i.saturating_sub(CONTEXT);
+        let mut end = i;
+        let mut kept = 0;
+        while end < ops.len() && kept <= 2 * CONTEXT {
+            if ops[end].0 == Op::Keep {
+                kept += 1;
+            } else {
+                kept = 0;
+            }
+            end += 1;
+        }
+        let end = (end - kept.saturating_sub(CONTEXT)).min(ops.len());
+
+        let (old_start, new_start) = position(&ops[..start]);
+        let (old_len, new_len) = position(&ops[start..end]);
+        out.push(format!(
+            "@@ -{},{} +{},{} @@",
+            old_start + 1,
+            old_len,
+            new_start + 1,
+            new_len
+        ));
+        for (op, line) in &ops[start..end] {
+            let sign = match op {
+                Op::Keep => ' ',
+                Op::Remove => '-',
+                Op::Add => '+',
+            };
+            out.push(format!("{}{}", sign, line));
+        }
+        i = end;
+    }
+
+    if out.len() > MAX_LINES {
+        let omitted = out.len() - MAX_LINES;
+        out.truncate(MAX_LINES);
+        out.push(format!("... ({} more lines)", omitted));
+    }
+    let mut diff = out.join("\n");
+    diff.push('\n');
+    diff
+}
+
+/// Lines of the old and new text covered by `ops`.
+fn position(ops: &[(Op, &str)]) -> (usize, usize) {
+    ops.iter().fold((0, 0), |(old, new), (op, _)| match op {
+        Op::Keep => (old + 1, new + 1),
+        Op::Remove => (old + 1, new),
+        Op::Add => (old, new + 1),
+    })
+}
+
+/// Edit script from `old` to `new` (longest common subsequence of lines).
+fn align<'a>(old: &[&'a str], new: &[&'a str]) -> Vec<(Op, &'a str)> {
+    let prefix = old.iter().zip(new).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let old_mid = &old[prefix..old.len() - suffix];
+    let new_mid = &new[prefix..new.len() - suffix];
+
+    let mut ops: Vec<(Op, &str)> = old[..prefix].iter().map(|l| (Op::Keep, *l)).collect();
+    if old_mid.len().saturating_mul(new_mid.len()) > MAX_ALIGN {
+        ops.extend(old_mid.iter().map(|l| (Op::Remove, *l)));
+        ops.extend(new_mid.iter().map(|l| (Op::Add, *l)));
+    } else {
+        // lcs[i][j]: common lines of old_mid[i..] and new_mid[j..].
+        let (n, m) = (old_mid.len(), new_mid.len());
+        let mut lcs = vec![vec![0u32; m + 1]; n + 1];
+        for i in (0..n).rev() {
+            for j in (0..m).rev() {
+                lcs[i][j] = if old_mid[i] == new_mid[j] {
+                    lcs[i + 1][j + 1] + 1
+                } else {
+                    lcs[i + 1][j].max(lcs[i][j + 1])
+                };
+            }
+        }
+        let (mut i, mut j) = (0, 0);
+        while i < n || j < m {
+            if i < n && j < m && old_mid[i] == new_mid[j] {
+                ops.push((Op::Keep, old_mid[i]));
+                i += 1;
+                j += 1;
+            } else if i < n && (j == m || lcs[i + 1][j] >= lcs[i][j + 1]) {
+                ops.push((Op::Remove, old_mid[i]));
+                i += 1;
+            } else {
+                ops.push((Op::Add, new_mid[j]));
+                j += 1;
+            }
+        }
+    }
+    ops.extend(old[old.len() - suffix..].iter().map(|l| (Op::Keep, *l)));
+    ops
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unified_diff() {
+        assert_eq!(unified_diff("a\nb\n", "a\nb\n"), "");
+        assert_eq!(unified_diff("", "new\n"), "@@ -1,0 +1,1 @@\n+new\n");
+
+        let before = "1\n2\n3\n4\n5\n6\n7\n8\n9\n10\n11\n12\n";
+        let after = "1\n2\nthree\n4\n5\n6\n7\n8\n9\n10\n11\n12\n13\n";
+        assert_eq!(
+            unified_diff(before, after),
+            "@@ -1,5 +1,5 @@\n 1\n 2\n-3\n+three\n 4\n 5\n\
+             @@ -11,2 +11,3 @@\n 11\n 12\n+13\n"
+        );
+    }
+}
@@ -0,0 +1,596 @@
+//! Explicit directories.
+//!
+//! Directories are normally implied by the paths of the documents in them:
+//! writing `projects/alpha/plan.md` makes `projects/` and `projects/alpha/`
+//! appear in listings. A directory record makes one exist on its own, so
+//! that:
+//!
+//! - `mkdir` creates a directory that lists as empty until something is
+//!   written into it
+//! - deleting the last document of a directory leaves the directory behind
+//!   instead of making it vanish
+//! - a directory can carry metadata (e.g. a `description`)
+//!
+//! Records are only needed for directories that would otherwise not exist;
+//! path-based writes keep working without them. `rmdir` removes documents
+//! and records together, `rename_dir` moves them together, and `copy_dir`
+//! duplicates them.
+
+use crate::error::WorkspaceError;
+use crate::workspace::canonical::check_path;
+use crate::workspace::{
+    DirectoryRecord, Workspace, WorkspaceEntry, WorkspaceEvent, normalize_directory,
+};
+
+impl Workspace {
+    /// Create a directory and any missing parents, like `mkdir -p`.
+    ///
+    /// Returns `false` if the directory already existed.
+    pub async fn mkdir(&self, path: &str) -> Result<bool, WorkspaceError> {
+        let dir = normalize_directory(path);
+        check_path(&dir)?;
+        if dir.is_empty() || self.is_directory(&dir).await? {
+            return Ok(false);
+        }
+        if self.exists(&dir).await? {
+            return Err(WorkspaceError::InvalidPath {
+                path: dir,
+                reason: "a document exists at this path".to_string(),
+            });
+        }
+        let target = self.resolve_writable(&dir)?;
+        let mut parent = target.path.as_str();
+        loop {
+            self.storage
+                .create_directory(&self.user_id, target.agent_id, parent)
+                .await?;
+            match parent.rsplit_once('/') {
+                Some((up, _)) => parent = up,
+                None => break,
+            }
+        }
+        self.audit_mutation("mkdir", &dir).await;
+        self.refresh_readmes(&dir, "", None).await;
+        Ok(true)
+    }
+
+    /// Whether `path` is a directory, either recorded or implied by the
+    /// documents below it. The root always is.
+    pub async fn is_directory(&self, path: &str) -> Result<bool, WorkspaceError> {
+        let dir = normalize_directory(path);
+        if dir.is_empty() || self.mounts.iter().any(|m| m.mount == dir) {
+            return Ok(true);
+        }
+        let target = self.resolve(&dir);
+        let recorded = self
+            .storage
+            .list_directories(&self.user_id, target.agent_id, &target.path)
+            .await?;
+        if !recorded.is_empty() {
+            return Ok(true);
+        }
+        let children = self
+            .storage
+            .list_directory(&self.user_id, target.agent_id, &target.path)
+            .await?;
+        Ok(!children.is_empty())
+    }
+
+    /// The record of `path`, if the directory was created explicitly.
+    pub async fn directory(&self, path: &str) -> Result<Option<DirectoryRecord>, WorkspaceError> {
+        let dir = normalize_directory(path);
+        let target = self.resolve(&dir);
+        let recorded = self
+            .storage
+            .list_directories(&self.user_id, target.agent_id, &target.path)
+            .await?;
+        Ok(recorded
+            .into_iter()
+            .find(|r| r.path == target.path)
+            .map(|mut r| {
+                r.path = dir;
+                r
+            }))
+    }
+
+    /// Replace the metadata of an existing directory, recording it if it
+    /// was only implied by its documents.
+    pub async fn set_directory_metadata(
+        &self,
+        path: &str,
+        metadata: &serde_json::Value,
+    ) -> Result<(), WorkspaceError> {
+        let dir = normalize_directory(path);
+        if dir.is_empty() || !self.is_directory(&dir).await? {
+            return Err(WorkspaceError::DocumentNotFound {
+                doc_type: dir,
+                user_id: self.user_id.clone(),
+            });
+        }
+        let target = self.resolve_writable(&dir)?;
+        self.storage
+            .create_directory(&self.user_id, target.agent_id, &target.path)
+            .await?;
+        self.storage
+            .update_directory_metadata(&self.user_id, target.agent_id, &target.path, metadata)
+            .await?;
+        self.audit_mutation("mkdir", &dir).await;
+        Ok(())
+    }
+
+    /// Remove a directory. Unless `recursive`, it must be empty.
+    ///
+    /// Returns the number of documents deleted.
+    pub async fn rmdir(&self, path: &str, recursive: bool) -> Result<usize, WorkspaceError> {
+        let dir = normalize_directory(path);
+        if dir.is_empty() {
+            return Err(WorkspaceError::InvalidPath {
+                path: "/".to_string(),
+                reason: "the root directory can't be removed".to_string(),
+            });
+        }
+        let target = self.resolve_writable(&dir)?;
+        let prefix = format!("{}/", target.path);
+        let documents: Vec<String> = self
+            .storage
+            .list_all_paths(&self.user_id, target.agent_id)
+            .await?
+            .into_iter()
+            .filter(|p| p.starts_with(&prefix))
+            .collect();
+        let recorded = self
+            .storage
+            .list_directories(&self.user_id, target.agent_id, &target.path)
+            .await?;
+        if documents.is_empty() && recorded.is_empty() {
+            return Err(WorkspaceError::DocumentNotFound {
+                doc_type: dir,
+                user_id: self.user_id.clone(),
+            });
+        }
+        if !recursive && (!documents.is_empty() || recorded.iter().any(|r| r.path != target.path)) {
+            return Err(WorkspaceError::DirectoryNotEmpty { path: dir });
+        }
+
+        // Deleted through storage rather than delete(), which would keep
+        // each emptied directory and refresh indexes about to be removed.
+        self.storage
+            .delete_documents_by_paths(&self.user_id, target.agent_id, &documents)
+            .await?;
+        for inner in &documents {
+            self.events.publish(WorkspaceEvent::Deleted {
+                user_id: self.user_id.clone(),
+                agent_id: target.agent_id,
+                path: inner.clone(),
+            });
+            let path = match target.mount {
+                Some(mount) => mount.join(inner),
+                None => inner.clone(),
+            };
+            self.audit_mutation("delete", &path).await;
+        }
+        self.storage
+            .delete_directories(&self.user_id, target.agent_id, &target.path)
+            .await?;
+        self.audit_mutation("rmdir", &dir).await;
+        self.keep_parent(&dir).await;
+        self.refresh_readmes(&dir, "", None).await;
+        Ok(documents.len())
+    }
+
+    /// Move directory `from` and everything below it to `to`, like `mv`
+    /// (`projects/alpha` to `archive/alpha`). Documents keep their IDs,
+    /// history, and embeddings, and move in one transaction.
+    ///
+    /// Fails if something already exists at `to`, if `to` is inside `from`,
+    /// or if it is in a different namespace. Returns the number of documents
+    /// moved.
+    pub async fn rename_dir(&self, from: &str, to: &str) -> Result<usize, WorkspaceError> {
+        let from = normalize_directory(from);
+        let to = normalize_directory(to);
+        check_path(&from)?;
+        check_path(&to)?;
+        for dir in [&from, &to] {
+            if dir.is_empty() || self.mounts.iter().any(|m| m.mount == *dir) {
+                return Err(WorkspaceError::InvalidPath {
+                    path: if dir.is_empty() {
+                        "/".to_string()
+                    } else {
+                        dir.clone()
+                    },
+                    reason: "the root and mount points can't be moved".to_string(),
+                });
+            }
+        }
+        if to == from || to.starts_with(&format!("{}/", from)) {
+            return Err(WorkspaceError::InvalidPath {
+                path: to,
+                reason: "a directory can't be moved into itself".to_string(),
+            });
+        }
+        let source = self.resolve_writable(&from)?;
+        let dest = self.resolve_writable(&to)?;
+        if source.agent_id != dest.agent_id {
+            return Err(WorkspaceError::InvalidPath {
+                path: to,
+                reason: "directories can't be moved between namespaces".to_string(),
+            });
+        }
+        if !self.is_directory(&from).await? {
+            return Err(WorkspaceError::DocumentNotFound {
+                doc_type: from,
+                user_id: self.user_id.clone(),
+            });
+        }
+        if self.exists(&to).await? || self.is_directory(&to).await? {
+            return Err(WorkspaceError::InvalidPath {
+                path: to,
+                reason: "something already exists at this path".to_string(),
+            });
+        }
+
+        let prefix = format!("{}/", source.path);
+        let documents: Vec<String> = self
+            .storage
+            .list_all_paths(&self.user_id, source.agent_id)
+            .await?
+            .into_iter()
+            .filter(|p| p.starts_with(&prefix))
+            .collect();
+        self.storage
+            .rename_directory(&self.user_id, source.agent_id, &source.path, &dest.path)
+            .await?;
+
+        for inner in &documents {
+            self.events.publish(WorkspaceEvent::Deleted {
+                user_id: self.user_id.clone(),
+                agent_id: source.agent_id,
+                path: inner.clone(),
+            });
+            let moved = format!("{}/{}", dest.path, &inner[prefix.len()..]);
+            let result = async {
+                let doc = self
+                    .storage
+                    .get_document_by_path(&self.user_id, dest.agent_id, &moved)
+                    .await?;
+                self.index_moved(doc.id).await
+            }
+            .await;
+            if let Err(e) = result {
+                tracing::warn!("Failed to update moved document '{}': {}", moved, e);
+            }
+        }
+        self.audit_mutation("rename", &format!("{} -> {}", from, to))
+            .await;
+        self.keep_parent(&from).await;
+        self.refresh_readmes(&from, "", None).await;
+        self.refresh_readmes(&to, "", None).await;
+        Ok(documents.len())
+    }
+
+    /// Copy directory `from` and everything below it to `to`, e.g. to start
+    /// a project from `templates/project`. Chunks and embeddings are cloned
+    /// from the originals; see [`Workspace::copy`].
+    ///
+    /// `from` may be in a read-only namespace. Fails if something already
+    /// exists at `to` or if `to` is inside `from`. Returns the number of
+    /// documents copied.
+    pub async fn copy_dir(&self, from: &str, to: &str) -> Result<usize, WorkspaceError> {
+        let from = normalize_directory(from);
+        let to = normalize_directory(to);
+        check_path(&from)?;
+        check_path(&to)?;
+        if to.is_empty() {
+            return Err(WorkspaceError::InvalidPath {
+                path: "/".to_string(),
+                reason: "a directory can't be copied onto the root".to_string(),
+            });
+        }
+        if from.is_empty() || to == from || to.starts_with(&format!("{}/", from)) {
+            return Err(WorkspaceError::InvalidPath {
+                path: to,
+                reason: "a directory can't be copied into itself".to_string(),
+            });
+        }
+        let source = self.resolve(&from);
+        let dest = self.resolve_writable(&to)?;
+        if !self.is_directory(&from).await? {
+            return Err(WorkspaceError::DocumentNotFound {
+                doc_type: from,
+                user_id: self.user_id.clone(),
+            });
+        }
+        if self.exists(&to).await? || self.is_directory(&to).await? {
+            return Err(WorkspaceError::InvalidPath {
+                path: to,
+                reason: "something already exists at this path".to_string(),
+            });
+        }
+
+        let prefix = format!("{}/", source.path);
+        let documents: Vec<String> = self
+            .storage
+            .list_all_paths(&self.user_id, source.agent_id)
+            .await?
+            .into_iter()
+            .filter(|p| p.starts_with(&prefix))
+            .collect();
+        for inner in &documents {
+            let original = self
+                .storage
+                .get_document_by_path(&self.user_id, source.agent_id, inner)
+                .await?;
+            let copy = format!("{}/{}", dest.path, &inner[prefix.len()..]);
+            self.clone_document(&original, dest.agent_id, &copy).await?;
+        }
+
+        // Recorded directories, so empty ones and their metadata come along.
+        let recorded = self
+            .storage
+            .list_directories(&self.user_id, source.agent_id, &source.path)
+            .await?;
+        for record in recorded {
+            let copy = format!("{}{}", dest.path, &record.path[source.path.len()..]);
+            self.storage
+                .create_directory(&self.user_id, dest.agent_id, &copy)
+                .await?;
+            if record.metadata.as_object().is_some_and(|m| !m.is_empty()) {
+                self.storage
+                    .update_directory_metadata(
+                        &self.user_id,
+                        dest.agent_id,
+                        &copy,
+                        &record.metadata,
+                    )
+                    .await?;
+            }
+        }
+
+        self.audit_mutation("copy", &format!("{} -> {}", from, to))
+            .await;
+        self.refresh_readmes(&to, "", None).await;
+        Ok(documents.len())
+    }
+
+    /// Record the directory containing `path` if removing `path` left it
+    /// empty, so that it doesn't disappear with its last document.
+    pub(super) async fn keep_parent(&self, path: &str) {
+        let Some((parent, _)) = path.rsplit_once('/') else {
+            return;
+        };
+        if self.mounts.iter().any(|m| m.mount == parent) {
+            return;
+        }
+        let target = self.resolve(parent);
+        if target.path.is_empty() {
+            return;
+        }
+        let result = async {
+            let children = self
+                .storage
+                .list_directory(&self.user_id, target.agent_id, &target.path)
+                .await?;
+            if children.is_empty() {
+                self.storage
+                    .create_directory(&self.user_id, target.agent_id, &target.path)
+                    .await?;
+            }
+            Ok::<_, WorkspaceError>(())
+        }
+        .await;
+        if let Err(e) = result {
+            tracing::warn!("Failed to keep directory '{}': {}", parent, e);
+        }
+    }
+}
+
+/// Add the immediate children of `dir` among `recorded` to `entries` as
+/// directories, unless documents already imply them.
+pub(super) fn merge_recorded(
+    entries: &mut Vec<WorkspaceEntry>,
+    dir: &str,
+    recorded: &[DirectoryRecord],
+) {
+    for record in recorded {
+        let relative = if dir.is_empty() {
+            Some(record.path.as_str())
+        } else {
+            record
+                .path
+                .strip_prefix(dir)
+                .and_then(|rest| rest.strip_prefix('/'))
+        };
+        let Some(child) = relative.and_then(|r| r.split('/').next()) else {
+            continue;
+        };
+        let path = if dir.is_empty() {
+            child.to_string()
+        } else {
+            format!("{}/{}", dir, child)
+        };
+        let updated_at = (path == record.path).then_some(record.updated_at);
+        match entries.iter_mut().find(|e| e.path == path) {
+            Some(entry) => {
+                if entry.updated_at.is_none() {
+                    entry.updated_at = updated_at;
+                }
+            }
+            None => entries.push(WorkspaceEntry {
+                path,
+                is_directory: true,
+                updated_at,
+                content_preview: None,
+            }),
+        }
+    }
+    entries.sort_by(|a, b| a.path.cmp(&b.path));
+}
+
+#[cfg(all(test, feature = "libsql"))]
+mod tests {
+    use crate::db::libsql_backend::test_db;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_directories_exist_without_documents() {
+        let (db, _dir) = test_db().await;
+        let ws = Workspace::new_with_db("default", db);
+
+        assert!(ws.mkdir("/projects/alpha/").await.unwrap());
+        assert!(!ws.mkdir("projects").await.unwrap());
+        let root = ws.list("").await.unwrap();
+        assert_eq!(root.len(), 1);
+        assert!(root[0].is_directory && root[0].path == "projects");
+        let projects = ws.list("projects").await.unwrap();
+        assert_eq!(projects[0].path, "projects/alpha");
+        assert!(ws.list("projects/alpha").await.unwrap().is_empty());
+        assert!(ws.is_directory("projects/alpha").await.unwrap());
+        assert!(!ws.is_directory("projects/beta").await.unwrap());
+
+        // The directory outlives its last document.
+        ws.write("notes/todo.md", "- ship").await.unwrap();
+        assert!(ws.mkdir("notes/todo.md").await.is_err());
+        ws.delete("notes/todo.md").await.unwrap();
+        assert!(ws.is_directory("notes").await.unwrap());
+
+        ws.set_directory_metadata(
+            "projects/alpha",
+            &serde_json::json!({ "description": "The alpha launch" }),
+        )
+        .await
+        .unwrap();
+        let record = ws.directory("projects/alpha").await.unwrap().unwrap();
+        assert_eq!(record.metadata["description"], "The alpha launch");
+        assert!(
+            ws.set_directory_metadata("nowhere", &serde_json::json!({}))
+                .await
+                .is_err()
+        );
+
+        ws.write("projects/alpha/plan.md", "# Plan").await.unwrap();
+        assert!(matches!(
+            ws.rmdir("projects", false).await,
+            Err(WorkspaceError::DirectoryNotEmpty { .. })
+        ));
+        assert_eq!(ws.rmdir("projects", true).await.unwrap(), 1);
+        assert!(!ws.is_directory("projects").await.unwrap());
+        assert!(!ws.exists("projects/alpha/plan.md").await.unwrap());
+        assert_eq!(ws.rmdir("notes", false).await.unwrap(), 0);
+        assert!(ws.list("").await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_rename_dir_moves_the_subtree() {
+        let (db, _dir) = test_db().await;
+        let ws = Workspace::new_with_db("default", db);
+
+        let plan = ws
+            .write("projects/alpha/plan.md", "# Plan\n\nLaunch in March")
+            .await
+            .unwrap();
+        ws.write("projects/alpha/notes/call.md", "Call notes")
+            .await
+            .unwrap();
+        ws.write("projects/alpha_b/keep.md", "Not inside alpha")
+            .await
+            .unwrap();
+        ws.mkdir("projects/alpha/empty").await.unwrap();
+
+        assert!(
+            ws.rename_dir("projects/alpha", "projects/alpha/x")
+                .await
+                .is_err()
+        );
+        assert!(
+            ws.rename_dir("projects/alpha", "projects/alpha_b")
+                .await
+                .is_err()
+        );
+        assert!(
+            ws.rename_dir("projects/nothing", "archive/nothing")
+                .await
+                .is_err()
+        );
+
+        assert_eq!(
+            ws.rename_dir("projects/alpha/", "archive/alpha")
+                .await
+                .unwrap(),
+            2
+        );
+        let moved = ws.read("archive/alpha/plan.md").await.unwrap();
+        assert_eq!(moved.id, plan.id);
+        assert!(ws.exists("archive/alpha/notes/call.md").await.unwrap());
+        assert!(ws.is_directory("archive/alpha/empty").await.unwrap());
+        assert!(!ws.is_directory("projects/alpha").await.unwrap());
+        // A sibling sharing the prefix stays put, and the parent remains.
+        assert!(ws.exists("projects/alpha_b/keep.md").await.unwrap());
+        assert!(ws.is_directory("projects").await.unwrap());
+
+        let hits = ws.search("March", 5).await.unwrap();
+        assert_eq!(hits[0].document_id, plan.id);
+    }
+
+    #[tokio::test]
+    async fn test_copy_dir_duplicates_the_subtree() {
+        let (db, _dir) = test_db().await;
+        let ws = Workspace::new_with_db("default", db);
+
+        let template = ws
+            .write(
+                "templates/project/README.md",
+                "# Project\n\nGoals and owners",
+            )
+            .await
+            .unwrap();
+        ws.write("templates/project/notes/log.md", "Decisions")
+            .await
+            .unwrap();
+        ws.mkdir("templates/project/assets").await.unwrap();
+        ws.set_directory_metadata(
+            "templates/project",
+            &serde_json::json!({ "description": "Project template" }),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(
+            ws.copy_dir("templates/project", "projects/new-thing")
+                .await
+                .unwrap(),
+            2
+        );
+        let copy = ws.read("projects/new-thing/README.md").await.unwrap();
+        assert_ne!(copy.id, template.id);
+        assert_eq!(copy.content, template.content);
+        assert!(ws.exists("projects/new-thing/notes/log.md").await.unwrap());
+        assert!(ws.is_directory("projects/new-thing/assets").await.unwrap());
+        let record = ws.directory("projects/new-thing").await.unwrap().unwrap();
+        assert_eq!(record.metadata["description"], "Project template");
+        // The original is untouched, and a copy can't overwrite.
+        assert!(ws.exists("templates/project/README.md").await.unwrap());
+        assert!(
+            ws.copy_dir("templates/project", "projects/new-thing")
+                .await
+                .is_err()
+        );
+
+        // Both are searchable, the copy without being re-chunked.
+        let chunks = ws.storage.get_document_chunks(copy.id).await.unwrap();
+        assert_eq!(chunks.len(), 1);
+        let hits = ws.search("owners", 5).await.unwrap();
+        assert_eq!(hits.len(), 2);
+
+        let single = ws
+            .copy("templates/project/notes/log.md", "log.md")
+            .await
+            .unwrap();
+        assert_eq!(single.content, "Decisions");
+        assert!(
+            ws.copy("log.md", "templates/project/notes/log.md")
+                .await
+                .is_err()
+        );
+    }
+}
@@ -4,6 +4,10 @@ use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
+use crate::workspace::frontmatter::DocumentMetadata;
+use crate::workspace::language::Language;
+use crate::workspace::sparse::SparseVector;
+
 /// Well-known document paths.
 ///
 /// These are conventional paths that have special meaning in the workspace.
@@ -33,6 +37,18 @@ pub mod paths {
     pub const DAILY_DIR: &str = "daily/";
     /// Context directory (for identity-related docs).
     pub const CONTEXT_DIR: &str = "context/";
+    /// Persona directory (per-persona SOUL.md and IDENTITY.md variants).
+    pub const PERSONAS_DIR: &str = "personas/";
+
+    /// Whether `path` holds the user's private memory (MEMORY.md, USER.md,
+    /// daily logs), which stays out of shared conversations.
+    pub fn is_private(path: &str) -> bool {
+        let path = path.trim_start_matches('/');
+        path == MEMORY
+            || path == USER
+            || path == DAILY_DIR.trim_end_matches('/')
+            || path.starts_with(DAILY_DIR)
+    }
 }
 
 /// A memory document stored in the database.
@@ -54,6 +70,9 @@ pub struct MemoryDocument {
     pub updated_at: DateTime<Utc>,
     /// Flexible metadata.
     pub metadata: serde_json::Value,
+    /// Parsed frontmatter of `content`.
+    #[serde(default)]
+    pub frontmatter: DocumentMetadata,
 }
 
 impl MemoryDocument {
@@ -73,6 +92,7 @@ impl MemoryDocument {
             created_at: now,
             updated_at: now,
             metadata: serde_json::Value::Object(serde_json::Map::new()),
+            frontmatter: DocumentMetadata::default(),
         }
     }
 
@@ -111,7 +131,7 @@ impl MemoryDocument {
 pub struct WorkspaceEntry {
     /// Path relative to listing directory.
     pub path: String,
-    /// True if this is a directory (has children).
+    /// True if this is a directory (has children or was created explicitly).
     pub is_directory: bool,
     /// Last update timestamp (latest among children for directories).
     pub updated_at: Option<DateTime<Utc>>,
@@ -126,6 +146,64 @@ impl WorkspaceEntry {
     }
 }
 
+/// A directory created explicitly (or kept after its last document was
+/// deleted), rather than implied by the paths of its documents.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DirectoryRecord {
+    /// Normalized path, without slashes at either end.
+    pub path: String,
+    /// Free-form metadata (e.g. a `description`).
+    pub metadata: serde_json::Value,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// A prior revision of a document's content.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DocumentVersion {
+    /// Revision number, counting from 1 per document.
+    pub version: i32,
+    pub content: String,
+    /// When this content was written.
+    pub saved_at: DateTime<Utc>,
+}
+
+/// A revision in a document's history, without its content.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VersionInfo {
+    pub version: i32,
+    pub saved_at: DateTime<Utc>,
+    /// Length of the content in characters.
+    pub size: usize,
+    /// Preview of the content (first ~200 chars).
+    pub preview: String,
+}
+
+/// Storage used by one document.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DocumentSize {
+    pub path: String,
+    /// Length of the content in bytes.
+    pub bytes: u64,
+    /// Number of indexed chunks.
+    pub chunks: u64,
+}
+
+/// A chunk to insert with [`Database::insert_chunks`](crate::db::Database::insert_chunks).
+#[derive(Debug, Clone)]
+pub struct NewChunk {
+    /// Position in the document (0-based).
+    pub chunk_index: i32,
+    /// Chunk text content.
+    pub content: String,
+    /// Embedding vector (if generated).
+    pub embedding: Option<Vec<f32>>,
+    /// Learned sparse vector (if a sparse encoder is set).
+    pub sparse: Option<SparseVector>,
+    /// Language the document is written in.
+    pub language: Language,
+}
+
 /// A chunk of a memory document for search indexing.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MemoryChunk {
@@ -214,6 +292,17 @@ mod tests {
         assert!(!custom.is_identity_document());
     }
 
+    #[test]
+    fn test_private_paths() {
+        assert!(paths::is_private(paths::MEMORY));
+        assert!(paths::is_private("USER.md"));
+        assert!(paths::is_private("daily"));
+        assert!(paths::is_private("daily/2024-01-15.md"));
+        assert!(!paths::is_private("dailies.md"));
+        assert!(!paths::is_private(paths::SOUL));
+        assert!(!paths::is_private("projects/notes.md"));
+    }
+
     #[test]
     fn test_workspace_entry_name() {
         let entry = WorkspaceEntry {
@@ -2,6 +2,10 @@
 //!
 //! Embeddings convert text into dense vectors that capture semantic meaning.
 //! Similar concepts have similar vectors, enabling semantic search.
+//!
+//! [`HashEmbeddings`] is a local fallback for when no provider is
+//! configured: it needs no model or network, and matches similar wording
+//! rather than similar meaning.
 
 use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
@@ -45,6 +49,12 @@ pub trait EmbeddingProvider: Send + Sync {
     /// Maximum input length in characters.
     fn max_input_length(&self) -> usize;
 
+    /// Whether the vectors only approximate meaning (e.g. the local hash
+    /// fallback), so semantic recall is weaker than with a real model.
+    fn is_approximate(&self) -> bool {
+        false
+    }
+
     /// Generate an embedding for a single text.
     async fn embed(&self, text: &str) -> Result<Vec<f32>, EmbeddingError>;
 
@@ -354,6 +364,102 @@ impl EmbeddingProvider for NearAiEmbeddings {
     }
 }
 
+/// Model name recorded for [`HashEmbeddings`] vectors.
+pub const HASH_EMBEDDING_MODEL: &str = "local-hash-v1";
+
+/// Weight of a character trigram relative to a whole word.
+const TRIGRAM_WEIGHT: f32 = 0.5;
+
+/// Local embeddings from hashed word and character-trigram features.
+///
+/// Each word and each trigram of it is hashed into one of `dimension`
+/// buckets with a random sign, and the result is normalized. Texts that
+/// share words, or word stems and spelling variants through their
+/// trigrams, end up close; synonyms do not. No model, network, or API key
+/// is involved, and the hash is stable across builds so stored vectors stay
+/// comparable.
+pub struct HashEmbeddings {
+    dimension: usize,
+}
+
+impl HashEmbeddings {
+    pub fn new(dimension: usize) -> Self {
+        Self {
+            dimension: dimension.max(1),
+        }
+    }
+
+    fn add_feature(&self, embedding: &mut [f32], feature: &[u8], weight: f32) {
+        let hash = fnv1a(feature);
+        let bucket = (hash % self.dimension as u64) as usize;
+        let sign = if hash >> 63 == 0 { 1.0 } else { -1.0 };
+        embedding[bucket] += sign * weight;
+    }
+}
+
+#[async_trait]
+impl EmbeddingProvider for HashEmbeddings {
+    fn dimension(&self) -> usize {
+        self.dimension
+    }
+
+    fn model_name(&self) -> &str {
+        HASH_EMBEDDING_MODEL
+    }
+
+    fn max_input_length(&self) -> usize {
+        1_000_000
+    }
+
+    fn is_approximate(&self) -> bool {
+        true
+    }
+
+    async fn embed(&self, text: &str) -> Result<Vec<f32>, EmbeddingError> {
+        let mut embedding = vec![0.0; self.dimension];
+        let lower = text.to_lowercase();
+        for word in lower.split(|c: char| !c.is_alphanumeric()) {
+            if word.is_empty() {
+                continue;
+            }
+            self.add_feature(&mut embedding, format!("w:{}", word).as_bytes(), 1.0);
+
+            let padded: Vec<char> = format!(" {} ", word).chars().collect();
+            for trigram in padded.windows(3) {
+                let trigram: String = trigram.iter().collect();
+                self.add_feature(
+                    &mut embedding,
+                    format!("t:{}", trigram).as_bytes(),
+                    TRIGRAM_WEIGHT,
+                );
+            }
+        }
+
+        let magnitude: f32 = embedding.iter().map(|x| x * x).sum::<f32>().sqrt();
+        if magnitude > 0.0 {
+            for x in &mut embedding {
+                *x /= magnitude;
+            }
+        } else {
+            // Vector indexes can't compare a zero vector; give text without
+            // words a fixed unit vector instead.
+            embedding[0] = 1.0;
+        }
+        Ok(embedding)
+    }
+}
+
+/// 64-bit FNV-1a, chosen over `DefaultHasher` because its output is
+/// guaranteed not to change between Rust releases.
+fn fnv1a(bytes: &[u8]) -> u64 {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for byte in bytes {
+        hash ^= u64::from(*byte);
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    hash
+}
+
 /// A mock embedding provider for testing.
 ///
 /// Generates deterministic embeddings based on text hash.
@@ -453,6 +559,50 @@ mod tests {
         assert_ne!(embeddings[0], embeddings[1]);
     }
 
+    fn cosine(a: &[f32], b: &[f32]) -> f32 {
+        a.iter().zip(b).map(|(x, y)| x * y).sum()
+    }
+
+    #[tokio::test]
+    async fn test_hash_embeddings_normalized_and_stable() {
+        let provider = HashEmbeddings::new(256);
+        assert!(provider.is_approximate());
+        assert_eq!(provider.model_name(), HASH_EMBEDDING_MODEL);
+
+        let emb = provider.embed("Quarterly planning notes").await.unwrap();
+        assert_eq!(emb.len(), 256);
+        let magnitude: f32 = emb.iter().map(|x| x * x).sum::<f32>().sqrt();
+        assert!((magnitude - 1.0).abs() < 0.001);
+        assert_eq!(
+            emb,
+            provider.embed("quarterly  PLANNING notes!").await.unwrap()
+        );
+
+        // Stored vectors must stay comparable across builds
+        assert_eq!(fnv1a(b"w:planning"), 0x9f45_35e9_5ead_41fd);
+    }
+
+    #[tokio::test]
+    async fn test_hash_embeddings_similarity() {
+        let provider = HashEmbeddings::new(1536);
+        let query = provider.embed("deploying the database").await.unwrap();
+        let related = provider
+            .embed("Notes on database deployment and rollback")
+            .await
+            .unwrap();
+        let unrelated = provider.embed("Grocery list: apples, bread").await.unwrap();
+
+        assert!(cosine(&query, &related) > cosine(&query, &unrelated) + 0.2);
+    }
+
+    #[tokio::test]
+    async fn test_hash_embeddings_empty_text() {
+        let provider = HashEmbeddings::new(8);
+        let emb = provider.embed("  ... ").await.unwrap();
+        assert_eq!(emb[0], 1.0);
+        assert!(emb[1..].iter().all(|x| *x == 0.0));
+    }
+
     #[test]
     fn test_openai_embeddings_config() {
         let provider = OpenAiEmbeddings::new("test-key");
@@ -0,0 +1,588 @@
+//! Retrieval evaluation.
+//!
+//! The eval set is a workspace document ([`CASES_PATH`]) listing queries and
+//! the documents search should find for them, one per list item:
+//!
+//! ```text
+//! - when is the dentist appointment -> daily/2024-03-02.md
+//! - what did Alice say about the launch -> people/alice.md, projects/launch.md
+//! - ideas for the garden
+//! ```
+//!
+//! Each query runs through [`Workspace::search_with_config`] and its results
+//! are ranked by document. A case scores recall@k (the share of its expected
+//! documents in the top k) and the reciprocal rank of the first relevant
+//! document; the report averages both over all cases (MRR). Cases without
+//! `->` have no known answer: a [`RelevanceGrader`] asks an LLM which of the
+//! top k results are relevant, and recall@k becomes whether any was.
+//!
+//! A report can be saved as the baseline ([`BASELINE_PATH`]); later runs are
+//! compared against it case by case, so a chunker or ranking change that
+//! helps on average but breaks specific queries shows up. Documents under
+//! [`EVAL_DIR`] are left out of the rankings, since the eval set itself
+//! contains every query word for word.
+
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::error::{LlmError, WorkspaceError};
+use crate::llm::{ChatMessage, CompletionRequest, LlmProvider, extract_json};
+use crate::workspace::{SearchConfig, Workspace};
+
+/// Directory holding the eval set and baseline; excluded from rankings.
+pub const EVAL_DIR: &str = "eval/";
+
+/// Workspace document listing the eval cases.
+pub const CASES_PATH: &str = "eval/retrieval.md";
+
+/// Workspace document holding the saved baseline report.
+pub const BASELINE_PATH: &str = "eval/baseline.json";
+
+/// Characters of each result shown to the grader.
+const MAX_GRADED_CHARS: usize = 1_500;
+
+/// Changes in a case's scores smaller than this are noise.
+const EPSILON: f64 = 1e-9;
+
+/// Errors from running an evaluation.
+#[derive(Debug, thiserror::Error)]
+pub enum EvalError {
+    #[error(transparent)]
+    Workspace(#[from] WorkspaceError),
+
+    #[error(transparent)]
+    Llm(#[from] LlmError),
+
+    #[error("No eval cases in {CASES_PATH}; add lines like `- <query> -> <path>`")]
+    NoCases,
+
+    #[error("{count} case(s) have no expected documents and no grader was given")]
+    GraderRequired { count: usize },
+
+    #[error("Invalid grading: {0}")]
+    InvalidGrading(String),
+
+    #[error("Invalid baseline in {BASELINE_PATH}: {0}")]
+    InvalidBaseline(String),
+}
+
+/// A query and the documents it should retrieve.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EvalCase {
+    pub query: String,
+    /// Expected document paths; empty when the LLM grades the results.
+    pub expected: Vec<String>,
+}
+
+impl EvalCase {
+    /// Whether the case needs a grader.
+    pub fn is_graded(&self) -> bool {
+        self.expected.is_empty()
+    }
+}
+
+/// Parse the list items of an eval set document into cases.
+///
+/// Lines that aren't list items (headings, prose) are ignored.
+pub fn parse_cases(content: &str) -> Vec<EvalCase> {
+    content
+        .lines()
+        .filter_map(|line| {
+            let item = line
+                .trim_start()
+                .strip_prefix("- ")
+                .or_else(|| line.trim_start().strip_prefix("* "))?;
+            let (query, expected) = match item.split_once("->") {
+                Some((query, paths)) => (
+                    query,
+                    paths
+                        .split(',')
+                        .map(|p| p.trim().trim_matches('`').to_string())
+                        .filter(|p| !p.is_empty())
+                        .collect(),
+                ),
+                None => (item, Vec::new()),
+            };
+            let query = query.trim();
+            (!query.is_empty()).then(|| EvalCase {
+                query: query.to_string(),
+                expected,
+            })
+        })
+        .collect()
+}
+
+/// How one case scored.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CaseResult {
+    pub query: String,
+    /// Expected paths (empty for graded cases).
+    pub expected: Vec<String>,
+    /// Retrieved document paths, best first.
+    pub retrieved: Vec<String>,
+    /// Retrieved paths that count as relevant.
+    pub relevant: Vec<String>,
+    /// 1-based rank of the first relevant document.
+    pub first_relevant: Option<usize>,
+    /// Recall@k, or for graded cases whether any top-k result was relevant.
+    pub recall: f64,
+}
+
+impl CaseResult {
+    pub fn is_graded(&self) -> bool {
+        self.expected.is_empty()
+    }
+
+    pub fn reciprocal_rank(&self) -> f64 {
+        self.first_relevant.map_or(0.0, |rank| 1.0 / rank as f64)
+    }
+}
+
+/// Results of a run over the whole eval set.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EvalReport {
+    /// Cutoff for recall@k and grading.
+    pub k: usize,
+    pub cases: Vec<CaseResult>,
+    /// Mean recall@k over all cases.
+    pub recall_at_k: f64,
+    /// Mean reciprocal rank over all cases.
+    pub mrr: f64,
+    /// LLM cost of grading.
+    #[serde(default)]
+    pub cost: Decimal,
+    pub run_at: DateTime<Utc>,
+}
+
+impl EvalReport {
+    fn new(k: usize, cases: Vec<CaseResult>, cost: Decimal) -> Self {
+        let n = cases.len().max(1) as f64;
+        Self {
+            k,
+            recall_at_k: cases.iter().map(|c| c.recall).sum::<f64>() / n,
+            mrr: cases.iter().map(CaseResult::reciprocal_rank).sum::<f64>() / n,
+            cases,
+            cost,
+            run_at: Utc::now(),
+        }
+    }
+
+    /// Compare against an earlier report, matching cases by query.
+    pub fn compare(&self, baseline: &EvalReport) -> Comparison {
+        let before: HashMap<&str, &CaseResult> = baseline
+            .cases
+            .iter()
+            .map(|c| (c.query.as_str(), c))
+            .collect();
+        let mut comparison = Comparison {
+            recall_delta: self.recall_at_k - baseline.recall_at_k,
+            mrr_delta: self.mrr - baseline.mrr,
+            regressions: Vec::new(),
+            improvements: Vec::new(),
+            new_cases: 0,
+        };
+        for case in &self.cases {
+            let Some(old) = before.get(case.query.as_str()) else {
+                comparison.new_cases += 1;
+                continue;
+            };
+            let change = CaseChange {
+                query: case.query.clone(),
+                rank_before: old.first_relevant,
+                rank_after: case.first_relevant,
+                recall_before: old.recall,
+                recall_after: case.recall,
+            };
+            let recall = case.recall - old.recall;
+            let rr = case.reciprocal_rank() - old.reciprocal_rank();
+            if recall < -EPSILON || (recall.abs() <= EPSILON && rr < -EPSILON) {
+                comparison.regressions.push(change);
+            } else if recall > EPSILON || rr > EPSILON {
+                comparison.improvements.push(change);
+            }
+        }
+        comparison
+    }
+}
+
+/// How a case moved between two reports.
+#[derive(Debug, Clone, Serialize)]
+pub struct CaseChange {
+    pub query: String,
+    pub rank_before: Option<usize>,
+    pub rank_after: Option<usize>,
+    pub recall_before: f64,
+    pub recall_after: f64,
+}
+
+/// Differences between a report and its baseline.
+#[derive(Debug, Clone, Serialize)]
+pub struct Comparison {
+    pub recall_delta: f64,
+    pub mrr_delta: f64,
+    /// Cases that lost recall, or kept it and fell in rank.
+    pub regressions: Vec<CaseChange>,
+    pub improvements: Vec<CaseChange>,
+    /// Cases not in the baseline.
+    pub new_cases: usize,
+}
+
+/// Judges which search results answer a query, with an LLM.
+pub struct RelevanceGrader {
+    llm: Arc<dyn LlmProvider>,
+}
+
+impl RelevanceGrader {
+    pub fn new(llm: Arc<dyn LlmProvider>) -> Self {
+        Self { llm }
+    }
+
+    /// Indexes into `results` (path and content) of the relevant ones,
+    /// and the LLM cost.
+    pub async fn grade(
+        &self,
+        query: &str,
+        results: &[(String, String)],
+    ) -> Result<(Vec<usize>, Decimal), EvalError> {
+        if results.is_empty() {
+            return Ok((Vec::new(), Decimal::ZERO));
+        }
+        let prompt = r#"Judge search results for a personal notes search engine.
+
+A result is relevant if it contains information that helps answer the query. Mentioning the same words is not enough.
+
+Respond with JSON only, listing the numbers of the relevant results:
+{"relevant": [<number>, ...]}"#;
+        let listing = results
+            .iter()
+            .enumerate()
+            .map(|(i, (path, content))| {
+                format!(
+                    "[{}] {}\n{}",
+                    i + 1,
+                    path,
+                    truncate(content, MAX_GRADED_CHARS)
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n\n");
+
+        let response = self
+            .llm
+            .complete(
+                CompletionRequest::new(vec![
+                    ChatMessage::system(prompt),
+                    ChatMessage::user(format!("Query: {}\n\n{}", query, listing)),
+                ])
+                .with_max_tokens(256)
+                .with_temperature(0.0),
+            )
+            .await?;
+        let cost = self
+            .llm
+            .calculate_cost(response.input_tokens, response.output_tokens);
+        Ok((parse_grades(&response.content, results.len())?, cost))
+    }
+}
+
+/// Parse the grader's answer into 0-based indexes below `count`.
+fn parse_grades(content: &str, count: usize) -> Result<Vec<usize>, EvalError> {
+    #[derive(Deserialize)]
+    struct Grades {
+        relevant: Vec<usize>,
+    }
+
+    let json = extract_json(content).unwrap_or(content);
+    let grades: Grades = serde_json::from_str(json)
+        .map_err(|e| EvalError::InvalidGrading(format!("{}: {}", e, json)))?;
+    let mut indexes: Vec<usize> = grades
+        .relevant
+        .into_iter()
+        .filter(|n| (1..=count).contains(n))
+        .map(|n| n - 1)
+        .collect();
+    indexes.sort_unstable();
+    indexes.dedup();
+    Ok(indexes)
+}
+
+/// Runs eval cases against a workspace's search.
+pub struct RetrievalEval<'a> {
+    workspace: &'a Workspace,
+    config: SearchConfig,
+    k: usize,
+    grader: Option<RelevanceGrader>,
+}
+
+impl<'a> RetrievalEval<'a> {
+    /// Evaluate `workspace` with the given search configuration. Its
+    /// `limit` bounds how deep ranks are measured for MRR.
+    pub fn new(workspace: &'a Workspace, config: SearchConfig) -> Self {
+        Self {
+            workspace,
+            config,
+            k: 5,
+            grader: None,
+        }
+    }
+
+    /// Cutoff for recall@k and for how many results are graded (default 5).
+    pub fn with_k(mut self, k: usize) -> Self {
+        self.k = k.max(1);
+        self
+    }
+
+    /// Grade cases that have no expected documents.
+    pub fn with_grader(mut self, grader: RelevanceGrader) -> Self {
+        self.grader = Some(grader);
+        self
+    }
+
+    /// Run every case and score it.
+    pub async fn run(&self, cases: &[EvalCase]) -> Result<EvalReport, EvalError> {
+        if cases.is_empty() {
+            return Err(EvalError::NoCases);
+        }
+        let ungraded = cases.iter().filter(|c| c.is_graded()).count();
+        if ungraded > 0 && self.grader.is_none() {
+            return Err(EvalError::GraderRequired { count: ungraded });
+        }
+
+        let mut paths: HashMap<Uuid, String> = HashMap::new();
+        let mut results = Vec::with_capacity(cases.len());
+        let mut cost = Decimal::ZERO;
+        for case in cases {
+            let (result, case_cost) = self.run_case(case, &mut paths).await?;
+            results.push(result);
+            cost += case_cost;
+        }
+        Ok(EvalReport::new(self.k, results, cost))
+    }
+
+    async fn run_case(
+        &self,
+        case: &EvalCase,
+        paths: &mut HashMap<Uuid, String>,
+    ) -> Result<(CaseResult, Decimal), EvalError> {
+        let depth = self.config.limit.max(self.k);
+        let hits = self
+            .workspace
+            .search_with_config(&case.query, self.config.clone().with_limit(depth))
+            .await?;
+
+        // Rank by document: a document's rank is that of its best chunk.
+        let mut retrieved: Vec<(String, String)> = Vec::new();
+        let mut seen = HashSet::new();
+        for hit in hits {
+            if !seen.insert(hit.document_id) {
+                continue;
+            }
+            let path = match paths.get(&hit.document_id) {
+                Some(path) => path.clone(),
+                None => {
+                    let path = self.workspace.document_path(hit.document_id).await?;
+                    paths.insert(hit.document_id, path.clone());
+                    path
+                }
+            };
+            if !path.starts_with(EVAL_DIR) {
+                retrieved.push((path, hit.content));
+            }
+        }
+
+        let k = self.k.min(retrieved.len());
+        let mut cost = Decimal::ZERO;
+        let relevant: Vec<usize> = match &self.grader {
+            Some(grader) if case.is_graded() => {
+                let (relevant, grading_cost) = grader.grade(&case.query, &retrieved[..k]).await?;
+                cost = grading_cost;
+                relevant
+            }
+            _ => retrieved
+                .iter()
+                .enumerate()
+                .filter(|(_, (path, _))| case.expected.contains(path))
+                .map(|(i, _)| i)
+                .collect(),
+        };
+
+        let in_top_k = relevant.iter().filter(|&&i| i < self.k).count();
+        let recall = if case.is_graded() {
+            if in_top_k > 0 { 1.0 } else { 0.0 }
+        } else {
+            in_top_k as f64 / case.expected.len() as f64
+        };
+        let result = CaseResult {
+            query: case.query.clone(),
+            expected: case.expected.clone(),
+            first_relevant: relevant.first().map(|i| i + 1),
+            relevant: relevant.iter().map(|&i| retrieved[i].0.clone()).collect(),
+            retrieved: retrieved.into_iter().map(|(path, _)| path).collect(),
+            recall,
+        };
+        Ok((result, cost))
+    }
+}
+
+/// Read the eval set from [`CASES_PATH`]; empty if the document is missing.
+pub async fn load_cases(workspace: &Workspace) -> Result<Vec<EvalCase>, WorkspaceError> {
+    match workspace.read(CASES_PATH).await {
+        Ok(doc) => Ok(parse_cases(&doc.content)),
+        Err(WorkspaceError::DocumentNotFound { .. }) => Ok(Vec::new()),
+        Err(e) => Err(e),
+    }
+}
+
+/// Read the saved baseline, if any.
+pub async fn load_baseline(workspace: &Workspace) -> Result<Option<EvalReport>, EvalError> {
+    match workspace.read(BASELINE_PATH).await {
+        Ok(doc) => serde_json::from_str(&doc.content)
+            .map(Some)
+            .map_err(|e| EvalError::InvalidBaseline(e.to_string())),
+        Err(WorkspaceError::DocumentNotFound { .. }) => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Save `report` as the baseline for later runs.
+pub async fn save_baseline(workspace: &Workspace, report: &EvalReport) -> Result<(), EvalError> {
+    let json = serde_json::to_string_pretty(report)
+        .map_err(|e| EvalError::InvalidBaseline(e.to_string()))?;
+    workspace.write(BASELINE_PATH, &json).await?;
+    Ok(())
+}
+
+fn truncate(text: &str, max: usize) -> &str {
+    match text.char_indices().nth(max) {
+        Some((cut, _)) => &text[..cut],
+        None => text,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_cases() {
+        let cases = parse_cases(
+            "# Retrieval eval\n\nSome prose.\n\n\
+             - when is the dentist -> daily/2024-03-02.md\n\
+             * launch notes -> `people/alice.md`, projects/launch.md\n\
+             - ideas for the garden\n\
+             -   \n",
+        );
+        assert_eq!(cases.len(), 3);
+        assert_eq!(cases[0].query, "when is the dentist");
+        assert_eq!(cases[0].expected, vec!["daily/2024-03-02.md"]);
+        assert_eq!(
+            cases[1].expected,
+            vec!["people/alice.md", "projects/launch.md"]
+        );
+        assert!(cases[2].is_graded());
+    }
+
+    #[test]
+    fn test_parse_grades() {
+        let grades = parse_grades("Sure:\n```json\n{\"relevant\": [3, 1, 1, 9]}\n```", 3).unwrap();
+        assert_eq!(grades, vec![0, 2]);
+        assert!(parse_grades("none of them", 3).is_err());
+    }
+
+    fn case(query: &str, first_relevant: Option<usize>, recall: f64) -> CaseResult {
+        CaseResult {
+            query: query.to_string(),
+            expected: vec!["a.md".to_string()],
+            retrieved: Vec::new(),
+            relevant: Vec::new(),
+            first_relevant,
+            recall,
+        }
+    }
+
+    #[test]
+    fn test_compare_reports() {
+        let baseline = EvalReport::new(
+            5,
+            vec![
+                case("a", Some(1), 1.0),
+                case("b", Some(4), 1.0),
+                case("c", None, 0.0),
+            ],
+            Decimal::ZERO,
+        );
+        let report = EvalReport::new(
+            5,
+            vec![
+                case("a", Some(2), 1.0),
+                case("b", Some(1), 1.0),
+                case("c", None, 0.0),
+                case("d", Some(1), 1.0),
+            ],
+            Decimal::ZERO,
+        );
+        assert!((baseline.mrr - (1.0 + 0.25) / 3.0).abs() < 1e-9);
+
+        let comparison = report.compare(&baseline);
+        assert_eq!(comparison.regressions.len(), 1);
+        assert_eq!(comparison.regressions[0].query, "a");
+        assert_eq!(comparison.improvements.len(), 1);
+        assert_eq!(comparison.improvements[0].query, "b");
+        assert_eq!(comparison.new_cases, 1);
+    }
+
+    #[cfg(feature = "libsql")]
+    #[tokio::test]
+    async fn test_run_against_workspace() {
+        use crate::db::libsql_backend::test_db;
+
+        let (db, _dir) = test_db().await;
+        let workspace = Workspace::new_with_db("default", db);
+        workspace
+            .write("health/dentist.md", "Dentist appointment with Dr. Molar.")
+            .await
+            .unwrap();
+        workspace
+            .write("garden.md", "Plant tomatoes along the fence.")
+            .await
+            .unwrap();
+        workspace
+            .write(
+                CASES_PATH,
+                "- dentist appointment -> health/dentist.md\n\
+                 - tomatoes fence -> health/dentist.md\n",
+            )
+            .await
+            .unwrap();
+
+        let cases = load_cases(&workspace).await.unwrap();
+        let eval = RetrievalEval::new(&workspace, SearchConfig::default().fts_only()).with_k(3);
+        let report = eval.run(&cases).await.unwrap();
+        assert_eq!(report.cases[0].first_relevant, Some(1));
+        assert_eq!(report.cases[0].recall, 1.0);
+        // The eval set itself never shows up in the rankings.
+        assert!(report.cases[0].retrieved.iter().all(|p| p != CASES_PATH));
+        assert_eq!(report.cases[1].retrieved, vec!["garden.md"]);
+        assert_eq!(report.cases[1].recall, 0.0);
+        assert!((report.mrr - 0.5).abs() < 1e-9);
+
+        save_baseline(&workspace, &report).await.unwrap();
+        let baseline = load_baseline(&workspace).await.unwrap().unwrap();
+        assert!(report.compare(&baseline).regressions.is_empty());
+
+        // Graded cases need a grader.
+        let graded = vec![EvalCase {
+            query: "garden".to_string(),
+            expected: Vec::new(),
+        }];
+        assert!(matches!(
+            eval.run(&graded).await,
+            Err(EvalError::GraderRequired { count: 1 })
+        ));
+    }
+}
@@ -0,0 +1,593 @@
+//! Document frontmatter and filtering on its fields.
+//!
+//! A document may start with a YAML frontmatter block:
+//!
+//! ```text
+//! ---
+//! title: "Project Alpha"
+//! status: active
+//! tags: [work, q3]
+//! ---
+//! ```
+//!
+//! Only the flat subset of YAML that notes actually use is understood:
+//! `key: value` scalars and lists written inline (`[a, b]`) or as `- item`
+//! lines. Values are kept as strings; nested maps are skipped.
+//!
+//! Documents carry their frontmatter as a [`DocumentMetadata`], which
+//! [`Workspace::set_metadata`] writes back as a new block. The block is
+//! left out of chunks, so it isn't embedded or matched by search.
+//!
+//! The parsed fields are stored in the document's metadata under
+//! [`FRONTMATTER_KEY`] whenever it is indexed, so [`FieldFilter`]s can be
+//! evaluated by the storage layer for listing and search.
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::error::WorkspaceError;
+use crate::workspace::{MemoryDocument, Workspace, trash};
+
+/// Metadata key holding a document's parsed frontmatter.
+pub const FRONTMATTER_KEY: &str = "frontmatter";
+
+/// A condition on one frontmatter field.
+///
+/// With a value, the field must equal it or, for a list field such as
+/// `tags`, contain it. Without one, the field only has to be present.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FieldFilter {
+    pub key: String,
+    pub value: Option<String>,
+}
+
+impl FieldFilter {
+    /// Field `key` equals (or, for a list, contains) `value`.
+    pub fn equals(key: impl Into<String>, value: impl Into<String>) -> Self {
+        Self {
+            key: key.into(),
+            value: Some(value.into()),
+        }
+    }
+
+    /// Field `key` is present.
+    pub fn exists(key: impl Into<String>) -> Self {
+        Self {
+            key: key.into(),
+            value: None,
+        }
+    }
+
+    /// Parse `key=value`, `key: value`, or a bare `key`.
+    pub fn parse(text: &str) -> Option<Self> {
+        let (key, value) = match text.split_once(['=', ':']) {
+            Some((key, value)) => (key.trim(), Some(unquote(value))),
+            None => (text.trim(), None),
+        };
+        if key.is_empty() {
+            return None;
+        }
+        Some(match value {
+            Some(value) if !value.is_empty() => Self::equals(key, value),
+            _ => Self::exists(key),
+        })
+    }
+
+    /// Whether parsed frontmatter `fields` satisfy the filter.
+    pub fn matches(&self, fields: &Map<String, Value>) -> bool {
+        let Some(field) = fields.get(&self.key) else {
+            return false;
+        };
+        let Some(value) = &self.value else {
+            return true;
+        };
+        match field {
+            Value::Array(items) => items.iter().any(|i| i.as_str() == Some(value)),
+            other => other.as_str() == Some(value),
+        }
+    }
+}
+
+/// Split a document into its frontmatter (without the `---` fences) and
+/// the body after it.
+pub fn split_frontmatter(content: &str) -> Option<(&str, &str)> {
+    let rest = content.strip_prefix("---")?;
+    let rest = rest
+        .strip_prefix('\n')
+        .or_else(|| rest.strip_prefix("\r\n"))?;
+    let end = rest
+        .match_indices("---")
+        .find(|(i, _)| *i == 0 || rest[..*i].ends_with('\n'))?
+        .0;
+    let body = rest[end + 3..].trim_start_matches(['\r', '\n']);
+    Some((&rest[..end], body))
+}
+
+/// Document content without its frontmatter.
+pub fn strip_frontmatter(content: &str) -> &str {
+    split_frontmatter(content).map_or(content, |(_, body)| body)
+}
+
+/// The frontmatter fields of `content` (empty if it has none).
+pub fn parse_frontmatter(content: &str) -> Map<String, Value> {
+    let mut fields = Map::new();
+    let Some((block, _)) = split_frontmatter(content) else {
+        return fields;
+    };
+
+    // Key of a `key:` line with no inline value, collecting `- item` lines.
+    let mut list_key: Option<String> = None;
+    for line in block.lines() {
+        let trimmed = line.trim();
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            continue;
+        }
+        if let Some(key) = &list_key
+            && let Some(item) = trimmed
+                .strip_prefix("- ")
+                .or((trimmed == "-").then_some(""))
+        {
+            let items = fields
+                .entry(key.clone())
+                .or_insert_with(|| Value::Array(Vec::new()));
+            if !items.is_array() {
+                *items = Value::Array(Vec::new());
+            }
+            if let Value::Array(items) = items {
+                items.push(Value::String(unquote(item)));
+            }
+            continue;
+        }
+        if line.starts_with([' ', '\t']) {
+            // Nested mapping or continuation line.
+            continue;
+        }
+        list_key = None;
+        let Some((key, value)) = line.split_once(':') else {
+            continue;
+        };
+        let key = key.trim();
+        if key.is_empty() {
+            continue;
+        }
+        let value = value.trim();
+        let parsed = if value.is_empty() {
+            list_key = Some(key.to_string());
+            Value::String(String::new())
+        } else if let Some(inner) = value.strip_prefix('[').and_then(|v| v.strip_suffix(']')) {
+            Value::Array(
+                inner
+                    .split(',')
+                    .map(unquote)
+                    .filter(|v| !v.is_empty())
+                    .map(Value::String)
+                    .collect(),
+            )
+        } else {
+            Value::String(unquote(value))
+        };
+        fields.insert(key.to_string(), parsed);
+    }
+    fields
+}
+
+fn unquote(value: &str) -> String {
+    let value = value.trim();
+    let quoted = value.len() >= 2
+        && ((value.starts_with('"') && value.ends_with('"'))
+            || (value.starts_with('\'') && value.ends_with('\'')));
+    if quoted {
+        value[1..value.len() - 1].to_string()
+    } else {
+        value.to_string()
+    }
+}
+
+/// A document's frontmatter as structured fields.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct DocumentMetadata {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    /// One-line description, used in generated READMEs.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub summary: Option<String>,
+    /// Rules for loading the document into the system prompt (see
+    /// [`ReadWhen`](crate::workspace::ReadWhen)).
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub read_when: Vec<String>,
+    /// Every other field, as a string or a list of strings.
+    #[serde(flatten)]
+    pub fields: Map<String, Value>,
+}
+
+impl DocumentMetadata {
+    /// The frontmatter of `content` (empty if it has none).
+    pub fn parse(content: &str) -> Self {
+        Self::from_fields(parse_frontmatter(content))
+    }
+
+    /// Sort parsed frontmatter `fields` into the known keys and the rest.
+    pub fn from_fields(mut fields: Map<String, Value>) -> Self {
+        let mut text = |key: &str| match fields.get(key) {
+            Some(Value::String(value)) => {
+                let value = value.clone();
+                fields.remove(key);
+                (!value.is_empty()).then_some(value)
+            }
+            _ => None,
+        };
+        let title = text("title");
+        let summary = text("summary");
+        let read_when = match fields.remove("read_when") {
+            Some(Value::Array(items)) => items
+                .iter()
+                .filter_map(|i| i.as_str().map(str::to_string))
+                .collect(),
+            Some(Value::String(rule)) if !rule.is_empty() => vec![rule],
+            _ => Vec::new(),
+        };
+        Self {
+            title,
+            summary,
+            read_when,
+            fields,
+        }
+    }
+
+    /// Whether there are no fields at all.
+    pub fn is_empty(&self) -> bool {
+        self.title.is_none()
+            && self.summary.is_none()
+            && self.read_when.is_empty()
+            && self.fields.is_empty()
+    }
+
+    /// The frontmatter block, fences included, or an empty string if there
+    /// are no fields. Lists are written one `- item` per line.
+    pub fn render(&self) -> String {
+        if self.is_empty() {
+            return String::new();
+        }
+        let mut out = String::from("---\n");
+        if let Some(title) = &self.title {
+            out.push_str(&format!("title: {}\n", quote(title)));
+        }
+        if let Some(summary) = &self.summary {
+            out.push_str(&format!("summary: {}\n", quote(summary)));
+        }
+        for (key, value) in &self.fields {
+            match value {
+                Value::Array(items) if items.is_empty() => out.push_str(&format!("{}: []\n", key)),
+                Value::Array(items) => {
+                    out.push_str(&format!("{}:\n", key));
+                    for item in items {
+                        out.push_str(&format!("  - {}\n", quote(&scalar(item))));
+                    }
+                }
+                other => out.push_str(&format!("{}: {}\n", key, quote(&scalar(other)))),
+            }
+        }
+        if !self.read_when.is_empty() {
+            out.push_str("read_when:\n");
+            for rule in &self.read_when {
+                out.push_str(&format!("  - {}\n", quote(rule)));
+            }
+        }
+        out.push_str("---\n");
+        out
+    }
+
+    /// `content` with its frontmatter replaced by this one (or removed, if
+    /// this one is empty).
+    pub fn apply_to(&self, content: &str) -> String {
+        let body = strip_frontmatter(content);
+        let block = self.render();
+        if block.is_empty() || body.is_empty() {
+            format!("{}{}", block, body)
+        } else {
+            format!("{}\n{}", block, body)
+        }
+    }
+}
+
+/// A field value as the string it is written as.
+fn scalar(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        Value::Null => String::new(),
+        other => other.to_string(),
+    }
+}
+
+/// `value` as written in frontmatter, quoted if it would otherwise be read
+/// differently. Values are never unescaped, so quoting only needs to keep
+/// the outer characters intact.
+fn quote(value: &str) -> String {
+    let value = value.replace(['\r', '\n'], " ");
+    let plain = !value.is_empty()
+        && value.trim() == value
+        && !value.starts_with([
+            '"', '\'', '[', '{', '-', '#', '&', '*', '!', '|', '>', '%', '@', '`',
+        ])
+        && !value.contains(": ")
+        && !value.contains(" #");
+    if plain {
+        value
+    } else {
+        format!("\"{}\"", value)
+    }
+}
+
+/// The frontmatter stored in a document's metadata.
+pub fn stored_frontmatter(doc: &MemoryDocument) -> Map<String, Value> {
+    doc.metadata
+        .get(FRONTMATTER_KEY)
+        .and_then(Value::as_object)
+        .cloned()
+        .unwrap_or_default()
+}
+
+impl Workspace {
+    /// Documents whose frontmatter matches every filter, sorted by path.
+    ///
+    /// Includes documents in mounted namespaces, but not in the trash.
+    pub async fn find_by_frontmatter(
+        &self,
+        filters: &[FieldFilter],
+    ) -> Result<Vec<MemoryDocument>, WorkspaceError> {
+        let mut docs = self
+            .storage
+            .find_documents_by_fields(&self.user_id, self.agent_id, filters)
+            .await?;
+        for mount in &self.mounts {
+            docs.extend(
+                self.storage
+                    .find_documents_by_fields(&self.user_id, Some(mount.scope()), filters)
+                    .await?,
+            );
+        }
+        let mut docs: Vec<MemoryDocument> = docs
+            .into_iter()
+            .filter(|d| !trash::is_trashed(&d.path))
+            .map(|d| self.mounted(d))
+            .collect();
+        docs.sort_by(|a, b| a.path.cmp(&b.path));
+        Ok(docs)
+    }
+
+    /// The frontmatter of the document at `path`.
+    pub async fn metadata(&self, path: &str) -> Result<DocumentMetadata, WorkspaceError> {
+        Ok(self.read(path).await?.frontmatter)
+    }
+
+    /// Replace the frontmatter of the document at `path` with `meta`,
+    /// keeping its body. The block is rewritten from the parsed fields, so
+    /// comments and nested maps in it are not kept.
+    pub async fn set_metadata(
+        &self,
+        path: &str,
+        meta: &DocumentMetadata,
+    ) -> Result<MemoryDocument, WorkspaceError> {
+        let doc = self.read(path).await?;
+        self.write(path, &meta.apply_to(&doc.content)).await
+    }
+
+    /// Store the parsed frontmatter of `doc` in its metadata if it changed.
+    pub(super) async fn sync_frontmatter(
+        &self,
+        doc: &MemoryDocument,
+    ) -> Result<(), WorkspaceError> {
+        let fields = parse_frontmatter(&doc.content);
+        let stored = doc.metadata.get(FRONTMATTER_KEY);
+        let unchanged = match stored {
+            Some(Value::Object(map)) => *map == fields,
+            Some(_) => false,
+            None => fields.is_empty(),
+        };
+        if unchanged {
+            return Ok(());
+        }
+
+        let mut metadata = doc.metadata.clone();
+        if !metadata.is_object() {
+            metadata = Value::Object(Map::new());
+        }
+        if let Some(map) = metadata.as_object_mut() {
+            if fields.is_empty() {
+                map.remove(FRONTMATTER_KEY);
+            } else {
+                map.insert(FRONTMATTER_KEY.to_string(), Value::Object(fields));
+            }
+        }
+        self.update_metadata(doc.id, &metadata).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DOC: &str = "---\ntitle: \"Project Alpha\"\nstatus: active\ntags: [work, 'q3']\nread_when:\n  - Planning\n  - \"keyword: alpha\"\nowner:\n  name: Ann\n---\n\n# Alpha\n";
+
+    #[test]
+    fn test_parse_frontmatter() {
+        let fields = parse_frontmatter(DOC);
+        assert_eq!(fields["title"], "Project Alpha");
+        assert_eq!(fields["status"], "active");
+        assert_eq!(fields["tags"], serde_json::json!(["work", "q3"]));
+        assert_eq!(
+            fields["read_when"],
+            serde_json::json!(["Planning", "keyword: alpha"])
+        );
+        // Nested maps are skipped; the key itself is present but empty.
+        assert_eq!(fields["owner"], "");
+        assert!(parse_frontmatter("# No frontmatter").is_empty());
+    }
+
+    #[test]
+    fn test_filters() {
+        let fields = parse_frontmatter(DOC);
+        assert!(FieldFilter::equals("status", "active").matches(&fields));
+        assert!(FieldFilter::equals("tags", "q3").matches(&fields));
+        assert!(!FieldFilter::equals("tags", "q4").matches(&fields));
+        assert!(FieldFilter::exists("title").matches(&fields));
+        assert!(!FieldFilter::exists("summary").matches(&fields));
+
+        assert_eq!(
+            FieldFilter::parse("status=active"),
+            Some(FieldFilter::equals("status", "active"))
+        );
+        assert_eq!(
+            FieldFilter::parse("title: \"Project Alpha\""),
+            Some(FieldFilter::equals("title", "Project Alpha"))
+        );
+        assert_eq!(
+            FieldFilter::parse("tags"),
+            Some(FieldFilter::exists("tags"))
+        );
+        assert_eq!(FieldFilter::parse("=x"), None);
+    }
+
+    #[test]
+    fn test_document_metadata() {
+        let meta = DocumentMetadata::parse(DOC);
+        assert_eq!(meta.title.as_deref(), Some("Project Alpha"));
+        assert_eq!(meta.summary, None);
+        assert_eq!(meta.read_when, vec!["Planning", "keyword: alpha"]);
+        assert_eq!(meta.fields["tags"], serde_json::json!(["work", "q3"]));
+        assert!(!meta.fields.contains_key("title"));
+        assert!(DocumentMetadata::parse("# No frontmatter").is_empty());
+
+        // Rendering and parsing again gives the same fields.
+        let content = meta.apply_to(DOC);
+        assert!(content.ends_with("---\n\n# Alpha\n"));
+        assert!(content.contains("  - \"keyword: alpha\"\n"));
+        assert_eq!(DocumentMetadata::parse(&content), meta);
+
+        assert_eq!(DocumentMetadata::default().apply_to(DOC), "# Alpha\n");
+        let added = DocumentMetadata {
+            summary: Some("- a list-looking summary".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(
+            added.apply_to("Body"),
+            "---\nsummary: \"- a list-looking summary\"\n---\n\nBody"
+        );
+    }
+
+    #[test]
+    fn test_strip() {
+        assert_eq!(strip_frontmatter(DOC), "# Alpha\n");
+        assert_eq!(strip_frontmatter("Plain"), "Plain");
+    }
+
+    #[cfg(feature = "libsql")]
+    #[tokio::test]
+    async fn test_filters_apply_to_listing_and_search() {
+        use crate::db::libsql_backend::test_db;
+        use crate::workspace::SearchConfig;
+
+        let (db, _dir) = test_db().await;
+        let workspace = Workspace::new_with_db("default", db);
+
+        workspace
+            .write(
+                "projects/alpha.md",
+                &DOC.replace("# Alpha", "Alpha launch plan"),
+            )
+            .await
+            .unwrap();
+        workspace
+            .write(
+                "projects/beta.md",
+                "---\nstatus: paused\ntags:\n  - work\n---\nBeta launch plan",
+            )
+            .await
+            .unwrap();
+        workspace
+            .write("notes.md", "No frontmatter, launch plan")
+            .await
+            .unwrap();
+
+        let paths =
+            |docs: Vec<MemoryDocument>| docs.into_iter().map(|d| d.path).collect::<Vec<_>>();
+        let active = workspace
+            .find_by_frontmatter(&[FieldFilter::equals("status", "active")])
+            .await
+            .unwrap();
+        assert_eq!(paths(active), vec!["projects/alpha.md"]);
+        let work = workspace
+            .find_by_frontmatter(&[FieldFilter::equals("tags", "work")])
+            .await
+            .unwrap();
+        assert_eq!(paths(work), vec!["projects/alpha.md", "projects/beta.md"]);
+        let both = workspace
+            .find_by_frontmatter(&[
+                FieldFilter::exists("status"),
+                FieldFilter::equals("tags", "q3"),
+            ])
+            .await
+            .unwrap();
+        assert_eq!(paths(both), vec!["projects/alpha.md"]);
+
+        let config = SearchConfig::default()
+            .with_limit(10)
+            .with_filter(FieldFilter::equals("status", "paused"));
+        let results = workspace
+            .search_with_config("launch", config)
+            .await
+            .unwrap();
+        assert_eq!(results.len(), 1);
+        assert!(results[0].content.contains("Beta"));
+
+        // Removing the frontmatter clears the stored fields.
+        workspace
+            .write("projects/beta.md", "Beta launch plan")
+            .await
+            .unwrap();
+        let paused = workspace
+            .find_by_frontmatter(&[FieldFilter::exists("status")])
+            .await
+            .unwrap();
+        assert_eq!(paths(paused), vec!["projects/alpha.md"]);
+    }
+
+    #[cfg(feature = "libsql")]
+    #[tokio::test]
+    async fn test_metadata_is_structured_and_not_searched() {
+        use crate::db::libsql_backend::test_db;
+
+        let (db, _dir) = test_db().await;
+        let workspace = Workspace::new_with_db("default", db);
+
+        let doc = workspace
+            .write(
+                "guides/packing.md",
+                "---\ntitle: Zebra handbook\n---\n\nBring sunscreen.",
+            )
+            .await
+            .unwrap();
+        assert_eq!(doc.frontmatter.title.as_deref(), Some("Zebra handbook"));
+        assert!(workspace.search("zebra", 5).await.unwrap().is_empty());
+        assert_eq!(workspace.search("sunscreen", 5).await.unwrap().len(), 1);
+
+        let mut meta = workspace.metadata("guides/packing.md").await.unwrap();
+        meta.summary = Some("What to pack".to_string());
+        meta.fields
+            .insert("tags".to_string(), serde_json::json!(["travel"]));
+        let doc = workspace
+            .set_metadata("guides/packing.md", &meta)
+            .await
+            .unwrap();
+        assert_eq!(
+            doc.content,
+            "---\ntitle: Zebra handbook\nsummary: What to pack\ntags:\n  - travel\n---\n\nBring sunscreen."
+        );
+        assert_eq!(doc.frontmatter, meta);
+        let travel = workspace
+            .find_by_frontmatter(&[FieldFilter::equals("tags", "travel")])
+            .await
+            .unwrap();
+        assert_eq!(travel.len(), 1);
+    }
+}
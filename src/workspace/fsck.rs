@@ -0,0 +1,239 @@
+//! Workspace integrity checks and repair.
+//!
+//! A crash between steps of a write, or data carried over from older
+//! versions, can leave a workspace inconsistent in ways normal operation
+//! never notices: a document whose chunks were deleted but not rewritten is
+//! invisible to search, and paths stored before paths were normalized
+//! (Unicode NFC, collapsed slashes) can't be read back, or exist twice.
+//! [`Workspace::fsck`] finds these problems in the workspace and its
+//! writable mounts and, unless it is a dry run, repairs them:
+//!
+//! - a document with content but no chunks is chunked and embedded again
+//! - chunks left on an empty document are deleted
+//! - a path that isn't normalized is renamed to its normal form
+//! - of several paths that are the same once normalized, the most recently
+//!   updated document takes the normal path and the others go to the trash
+//!
+//! Trashed documents are not checked; they have no chunks by design.
+
+use std::collections::BTreeMap;
+
+use serde::Serialize;
+use uuid::Uuid;
+
+use crate::error::WorkspaceError;
+use crate::workspace::{
+    DocumentSize, SharedMount, Workspace, WorkspaceEvent, normalize_path, trash,
+};
+
+/// What is wrong with a document.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FsckKind {
+    /// Content that isn't indexed.
+    MissingChunks,
+    /// Chunks of an empty document.
+    StaleChunks,
+    /// A path stored without being normalized.
+    UnnormalizedPath,
+    /// An older copy of a document under another spelling of its path.
+    DuplicatePath,
+}
+
+/// One problem found, and whether it was repaired.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FsckIssue {
+    /// Workspace path of the document, as stored.
+    pub path: String,
+    pub kind: FsckKind,
+    pub message: String,
+    pub repaired: bool,
+}
+
+/// Result of [`Workspace::fsck`].
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct FsckReport {
+    /// Documents checked.
+    pub documents: usize,
+    pub issues: Vec<FsckIssue>,
+}
+
+impl FsckReport {
+    pub fn is_clean(&self) -> bool {
+        self.issues.is_empty()
+    }
+
+    /// Issues that were repaired.
+    pub fn repaired(&self) -> usize {
+        self.issues.iter().filter(|i| i.repaired).count()
+    }
+}
+
+impl Workspace {
+    /// Check this workspace and its writable mounts for inconsistencies,
+    /// repairing them unless `dry_run`.
+    pub async fn fsck(&self, dry_run: bool) -> Result<FsckReport, WorkspaceError> {
+        let mut report = FsckReport::default();
+        self.fsck_scope(self.agent_id, None, dry_run, &mut report)
+            .await?;
+        for mount in self.mounts.iter().filter(|m| m.is_writable()) {
+            self.fsck_scope(Some(mount.scope()), Some(mount), dry_run, &mut report)
+                .await?;
+        }
+        Ok(report)
+    }
+
+    async fn fsck_scope(
+        &self,
+        agent_id: Option<Uuid>,
+        mount: Option<&SharedMount>,
+        dry_run: bool,
+        report: &mut FsckReport,
+    ) -> Result<(), WorkspaceError> {
+        let sizes: Vec<DocumentSize> = self
+            .storage
+            .document_sizes(&self.user_id, agent_id)
+            .await?
+            .into_iter()
+            .filter(|s| !trash::is_trashed(&s.path))
+            .collect();
+        report.documents += sizes.len();
+        let shown = |path: &str| match mount {
+            Some(mount) => mount.join(path),
+            None => path.to_string(),
+        };
+        let mut issue = |path: &str, kind, message: String| {
+            report.issues.push(FsckIssue {
+                path: shown(path),
+                kind,
+                message,
+                repaired: !dry_run,
+            });
+        };
+
+        for size in &sizes {
+            let kind = match (size.bytes, size.chunks) {
+                (0, 0) | (1.., 1..) => continue,
+                (_, 0) => FsckKind::MissingChunks,
+                (0, _) => FsckKind::StaleChunks,
+            };
+            if !dry_run {
+                let doc = self
+                    .storage
+                    .get_document_by_path(&self.user_id, agent_id, &size.path)
+                    .await?;
+                match kind {
+                    FsckKind::MissingChunks => self.reindex_document(doc.id).await?,
+                    _ => self.storage.delete_chunks(doc.id).await?,
+                }
+            }
+            let message = match kind {
+                FsckKind::MissingChunks => "content is not indexed".to_string(),
+                _ => format!("empty document has {} chunks", size.chunks),
+            };
+            issue(&size.path, kind, message);
+        }
+
+        let mut spellings: BTreeMap<String, Vec<String>> = BTreeMap::new();
+        for size in sizes {
+            spellings
+                .entry(normalize_path(&size.path))
+                .or_default()
+                .push(size.path);
+        }
+        for (normal, paths) in spellings {
+            if paths.len() == 1 && paths[0] == normal {
+                continue;
+            }
+            let mut docs = self
+                .storage
+                .get_documents_by_paths(&self.user_id, agent_id, &paths)
+                .await?;
+            docs.sort_by_key(|d| d.updated_at);
+            let Some(newest) = docs.pop() else {
+                continue;
+            };
+
+            for older in &docs {
+                if !dry_run {
+                    self.trash_document(agent_id, &older.path).await?;
+                }
+                issue(
+                    &older.path,
+                    FsckKind::DuplicatePath,
+                    format!("older copy of {}", shown(&newest.path)),
+                );
+            }
+            if newest.path != normal {
+                if !dry_run {
+                    self.storage.rename_document(newest.id, &normal).await?;
+                    self.events.publish(WorkspaceEvent::Deleted {
+                        user_id: self.user_id.clone(),
+                        agent_id,
+                        path: newest.path.clone(),
+                    });
+                    self.index_moved(newest.id).await?;
+                }
+                issue(
+                    &newest.path,
+                    FsckKind::UnnormalizedPath,
+                    format!("should be {}", shown(&normal)),
+                );
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(feature = "libsql")]
+    #[tokio::test]
+    async fn test_fsck_finds_and_repairs_issues() {
+        use std::sync::Arc;
+
+        use crate::db::libsql_backend::test_db;
+
+        let (db, _dir) = test_db().await;
+        let ws = Workspace::new_with_db("default", Arc::clone(&db));
+
+        let unindexed = ws.write("notes/unindexed.md", "Orbit plan").await.unwrap();
+        db.delete_chunks(unindexed.id).await.unwrap();
+        let emptied = ws.write("notes/emptied.md", "Old text").await.unwrap();
+        db.update_document(emptied.id, "").await.unwrap();
+        // Stored before paths were normalized.
+        db.get_or_create_document_by_path("default", None, "/notes//plan.md")
+            .await
+            .unwrap();
+        for path in ["caf\u{e9}.md", "cafe\u{301}.md"] {
+            db.get_or_create_document_by_path("default", None, path)
+                .await
+                .unwrap();
+        }
+
+        let report = ws.fsck(true).await.unwrap();
+        assert_eq!(report.documents, 5);
+        let kinds: Vec<FsckKind> = report.issues.iter().map(|i| i.kind).collect();
+        for kind in [
+            FsckKind::MissingChunks,
+            FsckKind::StaleChunks,
+            FsckKind::UnnormalizedPath,
+            FsckKind::DuplicatePath,
+        ] {
+            assert!(kinds.contains(&kind), "{:?} not reported", kind);
+        }
+        assert_eq!(report.repaired(), 0);
+        assert!(ws.search("Orbit", 5).await.unwrap().is_empty());
+
+        let report = ws.fsck(false).await.unwrap();
+        assert_eq!(report.repaired(), report.issues.len());
+        assert!(ws.fsck(true).await.unwrap().is_clean());
+        assert!(!ws.search("Orbit", 5).await.unwrap().is_empty());
+        assert!(db.get_document_chunks(emptied.id).await.unwrap().is_empty());
+        assert!(ws.exists("notes/plan.md").await.unwrap());
+        assert_eq!(ws.glob("caf*").await.unwrap(), vec!["caf\u{e9}.md"]);
+        assert_eq!(ws.glob(".trash/**").await.unwrap().len(), 1);
+    }
+}
@@ -0,0 +1,637 @@
+//! Git-backed workspace storage.
+//!
+//! A workspace given a [`GitStorage`] ([`Workspace::with_git`]) commits
+//! every change to a document's content to a bare git repository before it
+//! touches the database, one commit per write, move, or delete. The
+//! repository is the record: `git log`, `git diff`, and `git push` work on
+//! it as on any other, and [`GitStorage::spawn_push`] pushes it off-site on
+//! a schedule. The database still holds the search index, metadata, and a
+//! copy of each document's content for fast reads, which
+//! [`Workspace::sync_from_git`] brings back in line with the repository,
+//! e.g. after a crash between the commit and the database write or after
+//! the repository was rolled back by hand.
+//!
+//! Documents are stored at `<user>/<scope>/<path>`, where the scope is
+//! `default` for an agent-less workspace and the agent or shared namespace
+//! ID otherwise. Directories, metadata, and documents that were created but
+//! never written aren't committed. Git is run as a subprocess, so it must
+//! be installed.
+
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::sync::Arc;
+use std::time::Duration;
+
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command;
+use tokio::sync::Mutex;
+use uuid::Uuid;
+
+use crate::db::Database;
+use crate::error::WorkspaceError;
+use crate::workspace::{SharedMount, Workspace, WorkspaceStorage, trash};
+
+/// Branch the workspace is committed to.
+pub const BRANCH: &str = "refs/heads/main";
+
+/// Scope directory of documents without an agent.
+const DEFAULT_SCOPE: &str = "default";
+
+/// Index file used to build commits, inside the repository.
+const INDEX_FILE: &str = "ironclaw-index";
+
+/// Object ID that removes a path in `git update-index --index-info`.
+const NO_OBJECT: &str = "0000000000000000000000000000000000000000";
+
+/// A bare git repository holding workspace content.
+pub struct GitStorage {
+    dir: PathBuf,
+    /// Commits are built in one index file, so they are made one at a time.
+    lock: Mutex<()>,
+}
+
+/// One change in a commit, by repository path.
+enum Change {
+    Write {
+        key: String,
+        content: String,
+    },
+    Remove {
+        key: String,
+    },
+    /// Move everything below one directory to another.
+    MoveTree {
+        from: String,
+        to: String,
+    },
+}
+
+/// Result of [`Workspace::sync_from_git`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct GitSyncReport {
+    /// Documents committed to a repository that had no commits yet.
+    pub committed: usize,
+    /// Documents whose content was restored from the repository.
+    pub restored: usize,
+    /// Documents not in the repository, moved to the trash.
+    pub trashed: usize,
+}
+
+fn git_error(reason: impl std::fmt::Display) -> WorkspaceError {
+    WorkspaceError::GitFailed {
+        reason: reason.to_string(),
+    }
+}
+
+impl GitStorage {
+    /// Open the bare repository at `dir`, creating it if needed.
+    pub async fn open(dir: impl Into<PathBuf>) -> Result<Self, WorkspaceError> {
+        let storage = Self {
+            dir: dir.into(),
+            lock: Mutex::new(()),
+        };
+        if !storage.dir.join("HEAD").exists() {
+            std::fs::create_dir_all(&storage.dir).map_err(git_error)?;
+            run(storage.git().args(["init", "--bare", "--quiet"]), None).await?;
+            run(storage.git().args(["symbolic-ref", "HEAD", BRANCH]), None).await?;
+        }
+        Ok(storage)
+    }
+
+    /// Directory of the repository.
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Repository path of the document at `path` in a scope.
+    pub fn key(user_id: &str, agent_id: Option<Uuid>, path: &str) -> String {
+        let scope = agent_id.map_or_else(|| DEFAULT_SCOPE.to_string(), |id| id.to_string());
+        format!("{}/{}/{}", user_id, scope, path)
+    }
+
+    /// The commit at the tip of [`BRANCH`], or None before the first one.
+    pub async fn head(&self) -> Result<Option<String>, WorkspaceError> {
+        let output = self
+            .git()
+            .args(["rev-parse", "--verify", "--quiet", BRANCH])
+            .output()
+            .await
+            .map_err(|e| git_error(format!("failed to run git: {}", e)))?;
+        let commit = String::from_utf8_lossy(&output.stdout).trim().to_string();
+        Ok((output.status.success() && !commit.is_empty()).then_some(commit))
+    }
+
+    /// Content of `key` in the latest commit, if it is there.
+    pub async fn read(&self, key: &str) -> Result<Option<String>, WorkspaceError> {
+        if self.head().await?.is_none() {
+            return Ok(None);
+        }
+        let output = self
+            .git()
+            .args(["cat-file", "blob", &format!("{}:{}", BRANCH, key)])
+            .output()
+            .await
+            .map_err(|e| git_error(format!("failed to run git: {}", e)))?;
+        if !output.status.success() {
+            return Ok(None);
+        }
+        String::from_utf8(output.stdout)
+            .map(Some)
+            .map_err(|_| git_error(format!("{} is not UTF-8", key)))
+    }
+
+    /// Repository paths of the files below directory `prefix` (ending in
+    /// `/`) in the latest commit.
+    pub async fn list(&self, prefix: &str) -> Result<Vec<String>, WorkspaceError> {
+        if self.head().await?.is_none() {
+            return Ok(Vec::new());
+        }
+        let listing = run(
+            self.git()
+                .args(["ls-tree", "-r", "-z", "--name-only", BRANCH, "--", prefix]),
+            None,
+        )
+        .await?;
+        Ok(listing
+            .split('\0')
+            .filter(|key| !key.is_empty())
+            .map(str::to_string)
+            .collect())
+    }
+
+    /// Push [`BRANCH`] to `remote` (a remote name or URL).
+    pub async fn push(&self, remote: &str) -> Result<(), WorkspaceError> {
+        run(
+            self.git()
+                .args(["push", "--quiet", remote, &format!("{0}:{0}", BRANCH)]),
+            None,
+        )
+        .await?;
+        Ok(())
+    }
+
+    /// Push to `remote` every `interval`, once there is something to push.
+    pub fn spawn_push(
+        self: &Arc<Self>,
+        remote: String,
+        interval: Duration,
+    ) -> tokio::task::JoinHandle<()> {
+        let storage = Arc::clone(self);
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            let mut pushed: Option<String> = None;
+            loop {
+                ticker.tick().await;
+                let head = match storage.head().await {
+                    Ok(Some(head)) if pushed.as_ref() != Some(&head) => head,
+                    Ok(_) => continue,
+                    Err(e) => {
+                        tracing::warn!("Failed to read the workspace repository: {}", e);
+                        continue;
+                    }
+                };
+                match storage.push(&remote).await {
+                    Ok(()) => pushed = Some(head),
+                    Err(e) => tracing::warn!("Failed to push the workspace to {}: {}", remote, e),
+                }
+            }
+        })
+    }
+
+    /// Commit `content` as the document `id`, then store it.
+    pub(super) async fn update_document(
+        &self,
+        db: &dyn Database,
+        id: Uuid,
+        content: &str,
+    ) -> Result<(), WorkspaceError> {
+        let doc = db.get_document_by_id(id).await?;
+        let key = Self::key(&doc.user_id, doc.agent_id, &doc.path);
+        self.commit(
+            vec![Change::Write {
+                key,
+                content: content.to_string(),
+            }],
+            &format!("Update {}", doc.path),
+        )
+        .await?;
+        db.update_document(id, content).await
+    }
+
+    pub(super) async fn rename_document(
+        &self,
+        db: &dyn Database,
+        id: Uuid,
+        path: &str,
+    ) -> Result<(), WorkspaceError> {
+        let doc = db.get_document_by_id(id).await?;
+        self.commit(
+            vec![
+                Change::Remove {
+                    key: Self::key(&doc.user_id, doc.agent_id, &doc.path),
+                },
+                Change::Write {
+                    key: Self::key(&doc.user_id, doc.agent_id, path),
+                    content: doc.content,
+                },
+            ],
+            &format!("Move {} to {}", doc.path, path),
+        )
+        .await?;
+        db.rename_document(id, path).await
+    }
+
+    pub(super) async fn rename_directory(
+        &self,
+        db: &dyn Database,
+        user_id: &str,
+        agent_id: Option<Uuid>,
+        from: &str,
+        to: &str,
+    ) -> Result<u64, WorkspaceError> {
+        self.commit(
+            vec![Change::MoveTree {
+                from: format!("{}/", Self::key(user_id, agent_id, from)),
+                to: format!("{}/", Self::key(user_id, agent_id, to)),
+            }],
+            &format!("Move {}/ to {}/", from, to),
+        )
+        .await?;
+        db.rename_directory(user_id, agent_id, from, to).await
+    }
+
+    pub(super) async fn delete_document_by_path(
+        &self,
+        db: &dyn Database,
+        user_id: &str,
+        agent_id: Option<Uuid>,
+        path: &str,
+    ) -> Result<(), WorkspaceError> {
+        self.commit(
+            vec![Change::Remove {
+                key: Self::key(user_id, agent_id, path),
+            }],
+            &format!("Delete {}", path),
+        )
+        .await?;
+        db.delete_document_by_path(user_id, agent_id, path).await
+    }
+
+    pub(super) async fn delete_documents_by_paths(
+        &self,
+        db: &dyn Database,
+        user_id: &str,
+        agent_id: Option<Uuid>,
+        paths: &[String],
+    ) -> Result<u64, WorkspaceError> {
+        let message = match paths {
+            [path] => format!("Delete {}", path),
+            _ => format!("Delete {} documents", paths.len()),
+        };
+        let removed = paths
+            .iter()
+            .map(|path| Change::Remove {
+                key: Self::key(user_id, agent_id, path),
+            })
+            .collect();
+        self.commit(removed, &message).await?;
+        db.delete_documents_by_paths(user_id, agent_id, paths).await
+    }
+
+    /// Apply `changes` on top of the latest commit and commit the result,
+    /// unless it leaves the tree as it was.
+    async fn commit(&self, changes: Vec<Change>, message: &str) -> Result<(), WorkspaceError> {
+        let _guard = self.lock.lock().await;
+        let parent = self.head().await?;
+        match &parent {
+            Some(parent) => run(self.git().args(["read-tree", parent]), None).await?,
+            None => run(self.git().args(["read-tree", "--empty"]), None).await?,
+        };
+
+        // `--index-info` records: "<mode> <object>\t<path>", NUL-terminated.
+        let mut records = String::new();
+        for change in changes {
+            match change {
+                Change::Write { key, content } => {
+                    let object = run(
+                        self.git().args(["hash-object", "-w", "--stdin"]),
+                        Some(content.as_bytes()),
+                    )
+                    .await?;
+                    records.push_str(&format!("100644 {}\t{}\0", object, key));
+                }
+                Change::Remove { key } => {
+                    records.push_str(&format!("0 {}\t{}\0", NO_OBJECT, key));
+                }
+                Change::MoveTree { from, to } => {
+                    let Some(parent) = &parent else {
+                        continue;
+                    };
+                    let entries = run(
+                        self.git()
+                            .args(["ls-tree", "-r", "-z", parent, "--", &from]),
+                        None,
+                    )
+                    .await?;
+                    for entry in entries.split('\0').filter(|e| !e.is_empty()) {
+                        // "<mode> blob <object>\t<path>"
+                        let Some((info, key)) = entry.split_once('\t') else {
+                            continue;
+                        };
+                        let Some(rest) = key.strip_prefix(&from) else {
+                            continue;
+                        };
+                        let mut fields = info.split(' ');
+                        let (Some(mode), _, Some(object)) =
+                            (fields.next(), fields.next(), fields.next())
+                        else {
+                            continue;
+                        };
+                        records.push_str(&format!("0 {}\t{}\0", NO_OBJECT, key));
+                        records.push_str(&format!("{} {}\t{}{}\0", mode, object, to, rest));
+                    }
+                }
+            }
+        }
+        if !records.is_empty() {
+            run(
+                self.git().args(["update-index", "-z", "--index-info"]),
+                Some(records.as_bytes()),
+            )
+            .await?;
+        }
+
+        let tree = run(self.git().arg("write-tree"), None).await?;
+        let mut commit = self.git();
+        commit.args(["commit-tree", &tree, "-m", message]);
+        if let Some(parent) = &parent {
+            let current = run(
+                self.git()
+                    .args(["rev-parse", &format!("{}^{{tree}}", parent)]),
+                None,
+            )
+            .await?;
+            if current == tree {
+                return Ok(());
+            }
+            commit.args(["-p", parent]);
+        }
+        let commit = run(&mut commit, None).await?;
+        let mut update = self.git();
+        update.args(["update-ref", BRANCH, &commit]);
+        // Fails if another process moved the branch meanwhile.
+        update.arg(parent.as_deref().unwrap_or(NO_OBJECT));
+        run(&mut update, None).await?;
+        Ok(())
+    }
+
+    fn git(&self) -> Command {
+        let mut cmd = Command::new("git");
+        cmd.arg("--git-dir")
+            .arg(&self.dir)
+            .env("GIT_INDEX_FILE", self.dir.join(INDEX_FILE))
+            .env("GIT_AUTHOR_NAME", "IronClaw")
+            .env("GIT_AUTHOR_EMAIL", "ironclaw@localhost")
+            .env("GIT_COMMITTER_NAME", "IronClaw")
+            .env("GIT_COMMITTER_EMAIL", "ironclaw@localhost");
+        cmd
+    }
+}
+
+/// Run `cmd`, feeding it `input`, and return its trimmed output.
+async fn run(cmd: &mut Command, input: Option<&[u8]>) -> Result<String, WorkspaceError> {
+    cmd.stdin(if input.is_some() {
+        Stdio::piped()
+    } else {
+        Stdio::null()
+    })
+    .stdout(Stdio::piped())
+    .stderr(Stdio::piped());
+    let mut child = cmd
+        .spawn()
+        .map_err(|e| git_error(format!("failed to run git: {}", e)))?;
+    if let Some(input) = input
+        && let Some(mut stdin) = child.stdin.take()
+    {
+        stdin.write_all(input).await.map_err(git_error)?;
+    }
+    let output = child.wait_with_output().await.map_err(git_error)?;
+    if !output.status.success() {
+        return Err(git_error(format!(
+            "git failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+impl Workspace {
+    /// Commit every change to document content to `git` before storing it.
+    ///
+    /// Needs a workspace built with [`Workspace::new_with_db`]; one built on
+    /// a PostgreSQL pool is left as it is.
+    pub fn with_git(mut self, git: Arc<GitStorage>) -> Self {
+        self.storage = match self.storage {
+            WorkspaceStorage::Db(db) | WorkspaceStorage::Git { db, .. } => {
+                WorkspaceStorage::Git { db, git }
+            }
+            #[cfg(feature = "postgres")]
+            storage @ WorkspaceStorage::Repo(_) => {
+                tracing::warn!("Git storage needs a Database backend; not enabled");
+                storage
+            }
+        };
+        self
+    }
+
+    /// Bring the database in line with the git repository: restore the
+    /// content of every document that differs from its latest commit, and
+    /// move documents the repository doesn't have to the trash. Covers this
+    /// workspace's namespace and its writable mounts.
+    ///
+    /// A repository without commits is instead filled from the database in
+    /// one commit, so git can be turned on for an existing workspace.
+    pub async fn sync_from_git(&self) -> Result<GitSyncReport, WorkspaceError> {
+        let WorkspaceStorage::Git { git, .. } = &self.storage else {
+            return Ok(GitSyncReport::default());
+        };
+        let mut report = GitSyncReport::default();
+        let scopes: Vec<(Option<Uuid>, Option<&SharedMount>)> =
+            std::iter::once((self.agent_id, None))
+                .chain(
+                    self.mounts
+                        .iter()
+                        .filter(|m| m.is_writable())
+                        .map(|m| (Some(m.scope()), Some(m))),
+                )
+                .collect();
+
+        if git.head().await?.is_none() {
+            let mut changes = Vec::new();
+            for &(agent_id, _) in &scopes {
+                for doc in self.storage.list_documents(&self.user_id, agent_id).await? {
+                    if doc.content.is_empty() {
+                        continue;
+                    }
+                    changes.push(Change::Write {
+                        key: GitStorage::key(&self.user_id, agent_id, &doc.path),
+                        content: doc.content,
+                    });
+                }
+            }
+            report.committed = changes.len();
+            if !changes.is_empty() {
+                git.commit(changes, "Import workspace").await?;
+            }
+            return Ok(report);
+        }
+
+        for (agent_id, mount) in scopes {
+            let shown = |path: &str| match mount {
+                Some(mount) => mount.join(path),
+                None => path.to_string(),
+            };
+            let prefix = GitStorage::key(&self.user_id, agent_id, "");
+            let mut committed = HashSet::new();
+            for key in git.list(&prefix).await? {
+                let Some(path) = key.strip_prefix(&prefix).map(str::to_string) else {
+                    continue;
+                };
+                let Some(content) = git.read(&key).await? else {
+                    continue;
+                };
+                let current = match self
+                    .storage
+                    .get_document_by_path(&self.user_id, agent_id, &path)
+                    .await
+                {
+                    Ok(doc) => Some(doc.content),
+                    Err(WorkspaceError::DocumentNotFound { .. }) => None,
+                    Err(e) => return Err(e),
+                };
+                if current.as_deref() != Some(content.as_str()) {
+                    if trash::is_trashed(&path) {
+                        // Trashed documents stay out of the index.
+                        let doc = self
+                            .storage
+                            .get_or_create_document_by_path(&self.user_id, agent_id, &path)
+                            .await?;
+                        self.storage.update_document(doc.id, &content).await?;
+                    } else {
+                        self.write(&shown(&path), &content).await?;
+                    }
+                    report.restored += 1;
+                }
+                committed.insert(path);
+            }
+
+            for doc in self.storage.list_documents(&self.user_id, agent_id).await? {
+                if doc.content.is_empty()
+                    || committed.contains(&doc.path)
+                    || trash::is_trashed(&doc.path)
+                {
+                    continue;
+                }
+                self.trash_document(agent_id, &doc.path).await?;
+                report.trashed += 1;
+            }
+        }
+        Ok(report)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_key() {
+        let agent = Uuid::nil();
+        assert_eq!(
+            GitStorage::key("default", None, "notes/plan.md"),
+            "default/default/notes/plan.md"
+        );
+        assert_eq!(
+            GitStorage::key("alice", Some(agent), "MEMORY.md"),
+            format!("alice/{}/MEMORY.md", agent)
+        );
+    }
+
+    #[cfg(feature = "libsql")]
+    async fn commits(git: &GitStorage) -> String {
+        run(git.git().args(["rev-list", "--count", BRANCH]), None)
+            .await
+            .unwrap()
+    }
+
+    #[cfg(feature = "libsql")]
+    #[tokio::test]
+    async fn test_writes_are_committed_and_synced_back() {
+        use crate::db::libsql_backend::test_db;
+
+        let (db, dir) = test_db().await;
+        let git = Arc::new(GitStorage::open(dir.path().join("ws.git")).await.unwrap());
+        let ws = Workspace::new_with_db("default", Arc::clone(&db)).with_git(Arc::clone(&git));
+        let key = |path: &str| GitStorage::key("default", None, path);
+
+        ws.write("notes/plan.md", "Launch on Friday").await.unwrap();
+        ws.write("notes/plan.md", "Launch on Monday").await.unwrap();
+        // Unchanged content makes no commit.
+        ws.write("notes/plan.md", "Launch on Monday").await.unwrap();
+        assert_eq!(commits(&git).await, "2");
+        assert_eq!(
+            git.read(&key("notes/plan.md")).await.unwrap().as_deref(),
+            Some("Launch on Monday")
+        );
+
+        ws.rename_dir("notes", "archive").await.unwrap();
+        assert_eq!(
+            git.list(&key("")).await.unwrap(),
+            vec![key("archive/plan.md")]
+        );
+        ws.delete("archive/plan.md").await.unwrap();
+        assert_eq!(
+            git.list(&key("")).await.unwrap(),
+            vec![key(".trash/archive/plan.md")]
+        );
+
+        // The repository wins over the database.
+        let doc = ws.write("MEMORY.md", "- Likes tea").await.unwrap();
+        db.update_document(doc.id, "- Likes coffee").await.unwrap();
+        let stray = db
+            .get_or_create_document_by_path("default", None, "stray.md")
+            .await
+            .unwrap();
+        db.update_document(stray.id, "Not committed").await.unwrap();
+
+        let report = ws.sync_from_git().await.unwrap();
+        assert_eq!((report.restored, report.trashed), (1, 1));
+        assert_eq!(ws.read("MEMORY.md").await.unwrap().content, "- Likes tea");
+        assert!(ws.exists(".trash/stray.md").await.unwrap());
+        assert_eq!(ws.sync_from_git().await.unwrap(), GitSyncReport::default());
+    }
+
+    #[cfg(feature = "libsql")]
+    #[tokio::test]
+    async fn test_empty_repository_is_filled_from_the_database() {
+        use crate::db::libsql_backend::test_db;
+
+        let (db, dir) = test_db().await;
+        Workspace::new_with_db("default", Arc::clone(&db))
+            .write("MEMORY.md", "- Likes tea")
+            .await
+            .unwrap();
+
+        let git = Arc::new(GitStorage::open(dir.path().join("ws.git")).await.unwrap());
+        let ws = Workspace::new_with_db("default", db).with_git(Arc::clone(&git));
+        assert_eq!(ws.sync_from_git().await.unwrap().committed, 1);
+        assert_eq!(
+            git.read(&GitStorage::key("default", None, "MEMORY.md"))
+                .await
+                .unwrap()
+                .as_deref(),
+            Some("- Likes tea")
+        );
+    }
+}
@@ -0,0 +1,273 @@
+//! Glob patterns over workspace paths.
+//!
+//! [`Workspace::glob`] lists the documents whose paths match a pattern such
+//! as `projects/**/README.md` or `daily/2024-*.md`, so callers don't have to
+//! filter [`Workspace::list_all`] themselves. The syntax:
+//!
+//! - `*` matches any run of characters within one path segment
+//! - `**` matches across segments; `**/` also matches no directory at all
+//! - `?` matches one character other than `/`
+//! - `[abc]`, `[a-z]`, `[!a-z]` match one character of (or not of) a class
+//! - `{a,b}` matches either alternative
+//!
+//! Storage narrows the scan to the pattern's literal prefix and filters the
+//! rest in SQL: Postgres with the pattern as a regular expression, libSQL
+//! with a looser `GLOB` whose results are checked against the same regex.
+
+use regex::Regex;
+
+use crate::error::WorkspaceError;
+use crate::workspace::{Workspace, trash};
+
+/// A compiled glob pattern.
+#[derive(Debug, Clone)]
+pub struct PathGlob {
+    pattern: String,
+    prefix: String,
+    sqlite: String,
+    regex: Regex,
+}
+
+impl PathGlob {
+    /// Compile `pattern`, failing with [`WorkspaceError::InvalidPath`] on an
+    /// unclosed `[` or unbalanced braces.
+    pub fn new(pattern: &str) -> Result<Self, WorkspaceError> {
+        let pattern = pattern.trim_start_matches('/');
+        let invalid = |reason: &str| WorkspaceError::InvalidPath {
+            path: pattern.to_string(),
+            reason: reason.to_string(),
+        };
+
+        let chars: Vec<char> = pattern.chars().collect();
+        let mut regex = String::from("^");
+        let mut sqlite = String::new();
+        let mut braces = 0usize;
+        let mut i = 0;
+        while i < chars.len() {
+            match chars[i] {
+                '*' if chars.get(i + 1) == Some(&'*') => {
+                    if chars.get(i + 2) == Some(&'/') {
+                        regex.push_str("(?:.*/)?");
+                        i += 1;
+                    } else {
+                        regex.push_str(".*");
+                    }
+                    sqlite.push('*');
+                    i += 1;
+                }
+                '*' => {
+                    regex.push_str("[^/]*");
+                    sqlite.push('*');
+                }
+                '?' => {
+                    regex.push_str("[^/]");
+                    if braces == 0 {
+                        sqlite.push('?');
+                    }
+                }
+                '[' => {
+                    let negated = matches!(chars.get(i + 1), Some('!' | '^'));
+                    let start = if negated { i + 2 } else { i + 1 };
+                    // A ']' right after the opening bracket is part of the class.
+                    let end = chars
+                        .iter()
+                        .skip(start + 1)
+                        .position(|&c| c == ']')
+                        .map(|p| start + 1 + p)
+                        .ok_or_else(|| invalid("unclosed '['"))?;
+                    let class: String = chars[start..end].iter().collect();
+                    let class = class.replace('\\', "\\\\");
+                    let caret = if negated { "^" } else { "" };
+                    regex.push_str(&format!("[{caret}{class}]"));
+                    if braces == 0 {
+                        sqlite.push_str(&format!("[{caret}{class}]"));
+                    }
+                    i = end;
+                }
+                // SQLite has no alternatives; a '*' stands in for them.
+                '{' => {
+                    braces += 1;
+                    regex.push_str("(?:");
+                    sqlite.push('*');
+                }
+                ',' if braces > 0 => regex.push('|'),
+                '}' if braces > 0 => {
+                    braces -= 1;
+                    regex.push(')');
+                }
+                '}' => return Err(invalid("unmatched '}'")),
+                c => {
+                    regex.push_str(&regex::escape(&c.to_string()));
+                    if braces == 0 {
+                        sqlite.push(c);
+                    }
+                }
+            }
+            i += 1;
+        }
+        if braces > 0 {
+            return Err(invalid("unclosed '{'"));
+        }
+        regex.push('$');
+
+        let prefix: String = chars
+            .iter()
+            .take_while(|c| !matches!(c, '*' | '?' | '[' | '{'))
+            .collect();
+        let regex = Regex::new(&regex).map_err(|e| invalid(&e.to_string()))?;
+        Ok(Self {
+            pattern: pattern.to_string(),
+            prefix,
+            sqlite: collapse_stars(&sqlite),
+            regex,
+        })
+    }
+
+    /// The pattern as given, without a leading `/`.
+    pub fn pattern(&self) -> &str {
+        &self.pattern
+    }
+
+    /// The literal text every matching path starts with.
+    pub fn prefix(&self) -> &str {
+        &self.prefix
+    }
+
+    /// The pattern as an anchored regular expression, in syntax shared by
+    /// the `regex` crate and Postgres.
+    pub fn regex(&self) -> &str {
+        self.regex.as_str()
+    }
+
+    /// A SQLite `GLOB` pattern matching at least every path this one does.
+    pub fn sqlite_glob(&self) -> &str {
+        &self.sqlite
+    }
+
+    /// Whether `path` matches.
+    pub fn matches(&self, path: &str) -> bool {
+        self.regex.is_match(path)
+    }
+}
+
+/// Collapse runs of `*` left by `**` and by alternatives, which SQLite
+/// would otherwise backtrack over.
+fn collapse_stars(glob: &str) -> String {
+    let mut out = String::with_capacity(glob.len());
+    for c in glob.chars() {
+        if !(c == '*' && out.ends_with('*')) {
+            out.push(c);
+        }
+    }
+    out
+}
+
+impl Workspace {
+    /// Paths of the documents matching `pattern`, including documents in
+    /// mounted namespaces, sorted. Documents in the trash are only included
+    /// when the pattern starts there (e.g. `.trash/**`).
+    pub async fn glob(&self, pattern: &str) -> Result<Vec<String>, WorkspaceError> {
+        let glob = PathGlob::new(pattern)?;
+        let mut paths = self
+            .storage
+            .list_paths_matching(&self.user_id, self.agent_id, &glob)
+            .await?;
+        for mount in &self.mounts {
+            let root = format!("{}/", mount.mount);
+            if !glob.prefix().starts_with(&root) && !root.starts_with(glob.prefix()) {
+                continue;
+            }
+            let shared = self
+                .storage
+                .list_all_paths(&self.user_id, Some(mount.scope()))
+                .await?;
+            paths.extend(
+                shared
+                    .iter()
+                    .map(|p| mount.join(p))
+                    .filter(|p| glob.matches(p)),
+            );
+        }
+        if !trash::is_trashed(glob.prefix()) {
+            paths.retain(|p| !trash::is_trashed(p));
+        }
+        paths.sort();
+        Ok(paths)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn matches(pattern: &str, path: &str) -> bool {
+        PathGlob::new(pattern).unwrap().matches(path)
+    }
+
+    #[test]
+    fn test_glob_syntax() {
+        assert!(matches("daily/2024-*.md", "daily/2024-01-15.md"));
+        assert!(!matches("daily/2024-*.md", "daily/weekly/2024-W03.md"));
+        assert!(!matches("daily/2024-*.md", "daily/2023-12-31.md"));
+
+        assert!(matches("projects/**/README.md", "projects/README.md"));
+        assert!(matches("projects/**/README.md", "projects/alpha/README.md"));
+        assert!(matches("projects/**/README.md", "projects/a/b/README.md"));
+        assert!(!matches("projects/**/README.md", "projects/alpha/NOTES.md"));
+        assert!(matches("**", "a/b/c.md"));
+        assert!(matches("projects/**", "projects/alpha/notes.md"));
+
+        assert!(matches("notes-?.md", "notes-1.md"));
+        assert!(!matches("notes-?.md", "notes-12.md"));
+        assert!(matches("daily/2024-0[1-3]-*.md", "daily/2024-02-01.md"));
+        assert!(!matches("daily/2024-0[!1-3]-*.md", "daily/2024-02-01.md"));
+        assert!(matches("{SOUL,IDENTITY}.md", "IDENTITY.md"));
+        assert!(!matches("{SOUL,IDENTITY}.md", "USER.md"));
+        assert!(matches("/MEMORY.md", "MEMORY.md"));
+        assert!(!matches("MEMORY.md", "MEMORYxmd"));
+    }
+
+    #[test]
+    fn test_glob_storage_forms() {
+        let glob = PathGlob::new("projects/**/{README,TODO}.md").unwrap();
+        assert_eq!(glob.prefix(), "projects/");
+        assert_eq!(glob.sqlite_glob(), "projects/*.md");
+        assert_eq!(glob.regex(), r"^projects/(?:.*/)?(?:README|TODO)\.md$");
+
+        assert!(matches!(
+            PathGlob::new("notes/[abc.md"),
+            Err(WorkspaceError::InvalidPath { .. })
+        ));
+        assert!(PathGlob::new("notes/{a,b.md").is_err());
+        assert!(PathGlob::new("notes/a}.md").is_err());
+    }
+
+    #[cfg(feature = "libsql")]
+    #[tokio::test]
+    async fn test_glob_lists_matching_documents() {
+        use crate::db::libsql_backend::test_db;
+
+        let (db, _dir) = test_db().await;
+        let ws = Workspace::new_with_db("default", db);
+        for path in [
+            "projects/README.md",
+            "projects/alpha/README.md",
+            "projects/alpha/notes.md",
+            "projects_old/README.md",
+            "daily/2024-01-15.md",
+            "daily/2025-01-15.md",
+        ] {
+            ws.write(path, "x").await.unwrap();
+        }
+
+        assert_eq!(
+            ws.glob("projects/**/README.md").await.unwrap(),
+            vec!["projects/README.md", "projects/alpha/README.md"]
+        );
+        assert_eq!(
+            ws.glob("daily/2024-*.md").await.unwrap(),
+            vec!["daily/2024-01-15.md"]
+        );
+        assert!(ws.glob("nothing/**").await.unwrap().is_empty());
+    }
+}
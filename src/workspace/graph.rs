@@ -0,0 +1,751 @@
+//! Knowledge graph extracted from workspace documents.
+//!
+//! An LLM reads each document and pulls out entities (people, projects,
+//! places, ...) and the relations between them. Entities are matched by
+//! name across documents, so facts from different files join into one
+//! graph. Each edge remembers the document it came from; re-indexing a
+//! changed document replaces its edges.
+//!
+//! Vector search finds passages similar to a question. The graph answers
+//! questions that hop between facts, like "what connects Alice and project
+//! alpha?", by walking edges from one entity until it reaches the other.
+//!
+//! Indexing is incremental: a document is re-read only when it changed
+//! since the `graph_indexed_at` time stored in its metadata.
+
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::db::Database;
+use crate::error::{DatabaseError, LlmError, WorkspaceError};
+use crate::llm::{ChatMessage, CompletionRequest, LlmProvider, extract_json};
+use crate::workspace::frontmatter::strip_frontmatter;
+use crate::workspace::{ChunkConfig, MemoryDocument, chunk_document, trash};
+
+/// Metadata key recording when a document was last indexed.
+pub(super) const INDEXED_AT_KEY: &str = "graph_indexed_at";
+
+/// Entities matched per name in a query.
+const MATCHES_PER_NAME: usize = 5;
+
+/// Paths returned by [`KnowledgeGraph::connect`].
+const MAX_PATHS: usize = 5;
+
+/// Facts returned by [`KnowledgeGraph::neighborhood`].
+const MAX_FACTS: usize = 50;
+
+/// Errors from indexing or querying the graph.
+#[derive(Debug, thiserror::Error)]
+pub enum GraphError {
+    #[error(transparent)]
+    Database(#[from] DatabaseError),
+
+    #[error(transparent)]
+    Workspace(#[from] WorkspaceError),
+
+    #[error(transparent)]
+    Llm(#[from] LlmError),
+
+    #[error("Invalid extraction: {0}")]
+    InvalidExtraction(String),
+}
+
+/// A named thing in the graph.
+#[derive(Debug, Clone, Serialize)]
+pub struct GraphEntity {
+    pub id: Uuid,
+    pub user_id: String,
+    pub agent_id: Option<Uuid>,
+    pub name: String,
+    /// Free-form type ("person", "project", ...); empty if unknown.
+    pub kind: String,
+    pub created_at: DateTime<Utc>,
+}
+
+/// A directed relation between two entities.
+#[derive(Debug, Clone, Serialize)]
+pub struct GraphEdge {
+    pub id: Uuid,
+    pub source_id: Uuid,
+    pub relation: String,
+    pub target_id: Uuid,
+    /// Document the relation was extracted from.
+    pub document_id: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl GraphEdge {
+    pub fn new(source_id: Uuid, relation: impl Into<String>, target_id: Uuid) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            source_id,
+            relation: relation.into(),
+            target_id,
+            document_id: None,
+            created_at: Utc::now(),
+        }
+    }
+
+    pub fn with_document(mut self, document_id: Uuid) -> Self {
+        self.document_id = Some(document_id);
+        self
+    }
+
+    /// The end of this edge opposite `entity`.
+    fn other_end(&self, entity: Uuid) -> Uuid {
+        if self.source_id == entity {
+            self.target_id
+        } else {
+            self.source_id
+        }
+    }
+}
+
+/// Key entities are matched by: lowercased with whitespace collapsed.
+pub fn entity_key(name: &str) -> String {
+    name.split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase()
+}
+
+/// An edge with entity names resolved, for display.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct GraphFact {
+    pub source: String,
+    pub relation: String,
+    pub target: String,
+    /// Path of the document the fact came from.
+    pub document: Option<String>,
+}
+
+impl std::fmt::Display for GraphFact {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} --{}--> {}", self.source, self.relation, self.target)
+    }
+}
+
+/// A chain of facts linking two entities.
+#[derive(Debug, Clone, Serialize)]
+pub struct GraphPath {
+    pub facts: Vec<GraphFact>,
+}
+
+/// Entities and relations pulled from a piece of text.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct Extraction {
+    #[serde(default)]
+    pub entities: Vec<ExtractedEntity>,
+    #[serde(default)]
+    pub relations: Vec<ExtractedRelation>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ExtractedEntity {
+    pub name: String,
+    #[serde(default, rename = "type")]
+    pub kind: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ExtractedRelation {
+    pub source: String,
+    pub relation: String,
+    pub target: String,
+}
+
+/// Extracts entities and relations from text with an LLM.
+#[derive(Clone)]
+pub struct GraphExtractor {
+    llm: Arc<dyn LlmProvider>,
+}
+
+impl GraphExtractor {
+    pub fn new(llm: Arc<dyn LlmProvider>) -> Self {
+        Self { llm }
+    }
+
+    /// Extract from `text`, returning the extraction and its LLM cost.
+    pub async fn extract(&self, text: &str) -> Result<(Extraction, Decimal), GraphError> {
+        let prompt = r#"Extract a knowledge graph from the user's notes.
+
+Rules:
+- Entities are specific people, projects, organizations, places, products, or concepts. Use the most complete name the notes give.
+- Relations are short snake_case verbs ("works_on", "lives_in", "reports_to") between two listed entities.
+- Only include facts the notes state. Skip dates, feelings, and generic words.
+
+Respond with JSON only:
+{"entities": [{"name": "<name>", "type": "<type>"}], "relations": [{"source": "<entity>", "relation": "<verb>", "target": "<entity>"}]}"#;
+
+        let response = self
+            .llm
+            .complete(
+                CompletionRequest::new(vec![ChatMessage::system(prompt), ChatMessage::user(text)])
+                    .with_max_tokens(2048)
+                    .with_temperature(0.0),
+            )
+            .await?;
+        let cost = self
+            .llm
+            .calculate_cost(response.input_tokens, response.output_tokens);
+
+        let json = extract_json(&response.content).unwrap_or(&response.content);
+        let extraction: Extraction = serde_json::from_str(json)
+            .map_err(|e| GraphError::InvalidExtraction(format!("{}: {}", e, json)))?;
+        Ok((extraction, cost))
+    }
+}
+
+/// Result of an indexing pass.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct IndexStats {
+    pub documents: usize,
+    pub edges: usize,
+    pub cost: Decimal,
+}
+
+/// A user's (and optionally an agent's) knowledge graph.
+pub struct KnowledgeGraph {
+    db: Arc<dyn Database>,
+    user_id: String,
+    agent_id: Option<Uuid>,
+}
+
+impl KnowledgeGraph {
+    pub fn new(db: Arc<dyn Database>, user_id: impl Into<String>) -> Self {
+        Self {
+            db,
+            user_id: user_id.into(),
+            agent_id: None,
+        }
+    }
+
+    /// Scope the graph to an agent's workspace.
+    pub fn with_agent(mut self, agent_id: Uuid) -> Self {
+        self.agent_id = Some(agent_id);
+        self
+    }
+
+    // ==================== Indexing ====================
+
+    /// Replace the edges extracted from `doc` with a fresh extraction.
+    pub async fn index_document(
+        &self,
+        doc: &MemoryDocument,
+        extractor: &GraphExtractor,
+    ) -> Result<IndexStats, GraphError> {
+        let mut cost = Decimal::ZERO;
+        let mut extractions = Vec::new();
+        for chunk in chunk_document(strip_frontmatter(&doc.content), ChunkConfig::default()) {
+            let (extraction, chunk_cost) = extractor.extract(&chunk).await?;
+            cost += chunk_cost;
+            extractions.push(extraction);
+        }
+
+        self.db.delete_graph_edges_for_document(doc.id).await?;
+        let mut ids: HashMap<String, Uuid> = HashMap::new();
+        let mut edges = 0;
+        for extraction in extractions {
+            for entity in &extraction.entities {
+                self.entity_id(&mut ids, &entity.name, &entity.kind).await?;
+            }
+            for relation in &extraction.relations {
+                let relation_name = relation.relation.trim();
+                if relation_name.is_empty() {
+                    continue;
+                }
+                let (Some(source), Some(target)) = (
+                    self.entity_id(&mut ids, &relation.source, "").await?,
+                    self.entity_id(&mut ids, &relation.target, "").await?,
+                ) else {
+                    continue;
+                };
+                let edge = GraphEdge::new(source, relation_name, target).with_document(doc.id);
+                self.db.insert_graph_edge(&edge).await?;
+                edges += 1;
+            }
+        }
+
+        let mut metadata = doc.metadata.clone();
+        if let Some(map) = metadata.as_object_mut() {
+            map.insert(
+                INDEXED_AT_KEY.to_string(),
+                serde_json::json!(Utc::now().to_rfc3339()),
+            );
+        } else {
+            metadata = serde_json::json!({ INDEXED_AT_KEY: Utc::now().to_rfc3339() });
+        }
+        self.db.update_document_metadata(doc.id, &metadata).await?;
+
+        Ok(IndexStats {
+            documents: 1,
+            edges,
+            cost,
+        })
+    }
+
+    /// Look up or create the entity for `name`, caching IDs by key.
+    async fn entity_id(
+        &self,
+        ids: &mut HashMap<String, Uuid>,
+        name: &str,
+        kind: &str,
+    ) -> Result<Option<Uuid>, GraphError> {
+        let key = entity_key(name);
+        if key.is_empty() {
+            return Ok(None);
+        }
+        if let Some(id) = ids.get(&key) {
+            return Ok(Some(*id));
+        }
+        let entity = self
+            .db
+            .upsert_graph_entity(&self.user_id, self.agent_id, name.trim(), kind.trim())
+            .await?;
+        ids.insert(key, entity.id);
+        Ok(Some(entity.id))
+    }
+
+    /// Index every document that changed since it was last indexed.
+    ///
+    /// A document that fails to index is logged and retried next pass.
+    pub async fn index_pending(
+        &self,
+        extractor: &GraphExtractor,
+    ) -> Result<IndexStats, GraphError> {
+        let mut stats = IndexStats::default();
+        for doc in self.db.list_documents(&self.user_id, self.agent_id).await? {
+            if !needs_indexing(&doc) || trash::is_trashed(&doc.path) {
+                continue;
+            }
+            match self.index_document(&doc, extractor).await {
+                Ok(done) => {
+                    stats.documents += done.documents;
+                    stats.edges += done.edges;
+                    stats.cost += done.cost;
+                }
+                Err(e) => tracing::warn!("Failed to index {} into the graph: {}", doc.path, e),
+            }
+        }
+        Ok(stats)
+    }
+
+    /// Run [`KnowledgeGraph::index_pending`] every `interval`.
+    pub fn spawn_indexer(
+        self: &Arc<Self>,
+        extractor: GraphExtractor,
+        interval: Duration,
+    ) -> tokio::task::JoinHandle<()> {
+        let graph = Arc::clone(self);
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            loop {
+                ticker.tick().await;
+                match graph.index_pending(&extractor).await {
+                    Ok(stats) if stats.documents == 0 => {}
+                    Ok(stats) => tracing::info!(
+                        "Indexed {} documents into the knowledge graph ({} relations, ${})",
+                        stats.documents,
+                        stats.edges,
+                        stats.cost
+                    ),
+                    Err(e) => tracing::warn!("Knowledge graph indexing failed: {}", e),
+                }
+            }
+        })
+    }
+
+    // ==================== Queries ====================
+
+    /// Entities whose name contains `name`, best match first.
+    pub async fn find(&self, name: &str) -> Result<Vec<GraphEntity>, GraphError> {
+        let key = entity_key(name);
+        if key.is_empty() {
+            return Ok(Vec::new());
+        }
+        Ok(self
+            .db
+            .find_graph_entities(&self.user_id, self.agent_id, &key, MATCHES_PER_NAME)
+            .await?)
+    }
+
+    /// Facts within `hops` relations of the entities matching `name`.
+    pub async fn neighborhood(
+        &self,
+        name: &str,
+        hops: usize,
+    ) -> Result<Vec<GraphFact>, GraphError> {
+        let mut frontier: Vec<Uuid> = self.find(name).await?.iter().map(|e| e.id).collect();
+        let mut seen: HashSet<Uuid> = frontier.iter().copied().collect();
+        let mut edges: Vec<GraphEdge> = Vec::new();
+        let mut edge_ids = HashSet::new();
+
+        for _ in 0..hops {
+            if frontier.is_empty() || edges.len() >= MAX_FACTS {
+                break;
+            }
+            let mut next = Vec::new();
+            for edge in self.db.graph_edges_touching(&frontier).await? {
+                if !edge_ids.insert(edge.id) {
+                    continue;
+                }
+                for end in [edge.source_id, edge.target_id] {
+                    if seen.insert(end) {
+                        next.push(end);
+                    }
+                }
+                edges.push(edge);
+            }
+            frontier = next;
+        }
+        edges.truncate(MAX_FACTS);
+        self.facts(&edges).await
+    }
+
+    /// Shortest chains of relations linking an entity matching `from` to
+    /// one matching `to`, following edges in either direction.
+    pub async fn connect(
+        &self,
+        from: &str,
+        to: &str,
+        max_hops: usize,
+    ) -> Result<Vec<GraphPath>, GraphError> {
+        let starts: Vec<Uuid> = self.find(from).await?.iter().map(|e| e.id).collect();
+        let goals: HashSet<Uuid> = self.find(to).await?.iter().map(|e| e.id).collect();
+        if starts.is_empty() || goals.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        // Breadth-first from every start, remembering the edge that first
+        // reached each entity.
+        let mut parents: HashMap<Uuid, Option<GraphEdge>> =
+            starts.iter().map(|id| (*id, None)).collect();
+        let mut frontier = starts;
+        let mut reached = Vec::new();
+        for _ in 0..max_hops {
+            if frontier.is_empty() {
+                break;
+            }
+            let current: HashSet<Uuid> = frontier.iter().copied().collect();
+            let mut next = Vec::new();
+            for edge in self.db.graph_edges_touching(&frontier).await? {
+                for (here, there) in [
+                    (edge.source_id, edge.target_id),
+                    (edge.target_id, edge.source_id),
+                ] {
+                    if !current.contains(&here) || parents.contains_key(&there) {
+                        continue;
+                    }
+                    parents.insert(there, Some(edge.clone()));
+                    next.push(there);
+                    if goals.contains(&there) {
+                        reached.push(there);
+                    }
+                }
+            }
+            if !reached.is_empty() {
+                break;
+            }
+            frontier = next;
+        }
+
+        let mut paths = Vec::new();
+        for goal in reached.into_iter().take(MAX_PATHS) {
+            let mut chain = Vec::new();
+            let mut node = goal;
+            while let Some(Some(edge)) = parents.get(&node) {
+                node = edge.other_end(node);
+                chain.push(edge.clone());
+            }
+            chain.reverse();
+            paths.push(GraphPath {
+                facts: self.facts(&chain).await?,
+            });
+        }
+        Ok(paths)
+    }
+
+    /// Resolve entity names and document paths for `edges`.
+    async fn facts(&self, edges: &[GraphEdge]) -> Result<Vec<GraphFact>, GraphError> {
+        let ids: Vec<Uuid> = edges
+            .iter()
+            .flat_map(|e| [e.source_id, e.target_id])
+            .collect::<HashSet<_>>()
+            .into_iter()
+            .collect();
+        let names: HashMap<Uuid, String> = self
+            .db
+            .get_graph_entities(&ids)
+            .await?
+            .into_iter()
+            .map(|e| (e.id, e.name))
+            .collect();
+
+        let mut paths: HashMap<Uuid, Option<String>> = HashMap::new();
+        let mut facts = Vec::with_capacity(edges.len());
+        for edge in edges {
+            let document = match edge.document_id {
+                Some(id) => match paths.get(&id) {
+                    Some(path) => path.clone(),
+                    None => {
+                        let path = self.db.get_document_by_id(id).await.ok().map(|d| d.path);
+                        paths.insert(id, path.clone());
+                        path
+                    }
+                },
+                None => None,
+            };
+            let name = |id: Uuid| names.get(&id).cloned().unwrap_or_default();
+            facts.push(GraphFact {
+                source: name(edge.source_id),
+                relation: edge.relation.clone(),
+                target: name(edge.target_id),
+                document,
+            });
+        }
+        Ok(facts)
+    }
+}
+
+/// Whether `doc` changed since it was last indexed.
+fn needs_indexing(doc: &MemoryDocument) -> bool {
+    if doc.content.trim().is_empty() {
+        return false;
+    }
+    let indexed_at = doc
+        .metadata
+        .get(INDEXED_AT_KEY)
+        .and_then(|v| v.as_str())
+        .and_then(|s| DateTime::parse_from_rfc3339(s).ok());
+    indexed_at.is_none_or(|t| t < doc.updated_at)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_entity_key() {
+        assert_eq!(entity_key("  Project   Alpha "), "project alpha");
+        assert_eq!(entity_key(""), "");
+    }
+
+    #[test]
+    fn test_extraction_tolerates_missing_fields() {
+        let extraction: Extraction = serde_json::from_str(
+            r#"{"entities": [{"name": "Alice"}, {"name": "Alpha", "type": "project"}]}"#,
+        )
+        .unwrap();
+        assert_eq!(extraction.entities.len(), 2);
+        assert_eq!(extraction.entities[0].kind, "");
+        assert!(extraction.relations.is_empty());
+    }
+
+    #[cfg(feature = "libsql")]
+    #[tokio::test]
+    async fn test_index_and_connect() {
+        use async_trait::async_trait;
+
+        use crate::db::libsql_backend::test_db;
+        use crate::llm::{
+            CompletionResponse, FinishReason, ToolCompletionRequest, ToolCompletionResponse,
+        };
+        use crate::workspace::Workspace;
+
+        /// Answers with a fixed extraction per document.
+        struct FixedExtraction;
+
+        #[async_trait]
+        impl LlmProvider for FixedExtraction {
+            fn model_name(&self) -> &str {
+                "fixed"
+            }
+
+            fn cost_per_token(&self) -> (Decimal, Decimal) {
+                (Decimal::ZERO, Decimal::ZERO)
+            }
+
+            async fn complete(
+                &self,
+                request: CompletionRequest,
+            ) -> Result<CompletionResponse, LlmError> {
+                let text = request.messages.last().unwrap().content.clone();
+                let content = if text.contains("Alice") {
+                    r#"{"entities": [{"name": "Alice", "type": "person"}, {"name": "Project Alpha", "type": "project"}],
+                        "relations": [{"source": "Alice", "relation": "works_on", "target": "Project Alpha"}]}"#
+                } else {
+                    r#"```json
+                    {"relations": [{"source": "Bob", "relation": "manages", "target": "project alpha"}]}
+                    ```"#
+                };
+                Ok(CompletionResponse {
+                    content: content.to_string(),
+                    input_tokens: 10,
+                    output_tokens: 10,
+                    finish_reason: FinishReason::Stop,
+                    response_id: None,
+                })
+            }
+
+            async fn complete_with_tools(
+                &self,
+                _request: ToolCompletionRequest,
+            ) -> Result<ToolCompletionResponse, LlmError> {
+                unimplemented!()
+            }
+        }
+
+        let (db, _dir) = test_db().await;
+        let workspace = Workspace::new_with_db("default", Arc::clone(&db));
+        workspace
+            .write("people/alice.md", "Alice works on Project Alpha.")
+            .await
+            .unwrap();
+        workspace
+            .write("projects/alpha.md", "Bob manages it.")
+            .await
+            .unwrap();
+
+        let graph = KnowledgeGraph::new(Arc::clone(&db), "default");
+        let extractor = GraphExtractor::new(Arc::new(FixedExtraction));
+        let stats = graph.index_pending(&extractor).await.unwrap();
+        assert_eq!(stats.documents, 2);
+        assert_eq!(stats.edges, 2);
+
+        // Nothing changed, so nothing is re-read.
+        assert_eq!(graph.index_pending(&extractor).await.unwrap().documents, 0);
+
+        let paths = graph.connect("alice", "Bob", 3).await.unwrap();
+        assert_eq!(paths.len(), 1);
+        // The first spelling seen names the entity; document order varies.
+        let facts: Vec<String> = paths[0]
+            .facts
+            .iter()
+            .map(|f| f.to_string().to_lowercase())
+            .collect();
+        assert_eq!(
+            facts,
+            vec![
+                "alice --works_on--> project alpha",
+                "bob --manages--> project alpha"
+            ]
+        );
+        assert_eq!(
+            paths[0].facts[0].document.as_deref(),
+            Some("people/alice.md")
+        );
+        assert!(graph.connect("alice", "bob", 1).await.unwrap().is_empty());
+
+        let around = graph.neighborhood("project alpha", 1).await.unwrap();
+        assert_eq!(around.len(), 2);
+
+        // Re-indexing a changed document replaces its edges.
+        workspace
+            .write("people/alice.md", "Alice left.")
+            .await
+            .unwrap();
+        let doc = workspace.read("people/alice.md").await.unwrap();
+        graph
+            .index_document(&doc, &GraphExtractor::new(Arc::new(FixedExtraction)))
+            .await
+            .unwrap();
+        assert_eq!(
+            graph.neighborhood("project alpha", 1).await.unwrap().len(),
+            2
+        );
+    }
+
+    #[cfg(feature = "libsql")]
+    #[tokio::test]
+    async fn test_graph_tool_leaves_out_private_facts_in_shared_sessions() {
+        use crate::context::{JobContext, SessionScope};
+        use crate::db::libsql_backend::test_db;
+        use crate::llm::{MockLlmProvider, MockResponse};
+        use crate::tools::Tool;
+        use crate::tools::builtin::MemoryGraphTool;
+        use crate::workspace::Workspace;
+
+        let (db, _dir) = test_db().await;
+        let workspace = Arc::new(Workspace::new_with_db("default", Arc::clone(&db)));
+        workspace
+            .write("people/alice.md", "Alice works on Project Alpha.")
+            .await
+            .unwrap();
+        workspace
+            .write("people/bob.md", "Bob is pricing the Alpha bid.")
+            .await
+            .unwrap();
+        workspace.tag("people/bob.md", &["private"]).await.unwrap();
+
+        // Each document yields one fact about Alpha; bob.md is private.
+        let llm = MockLlmProvider::new()
+            .with_text(
+                r#"{"relations": [{"source": "Alice", "relation": "works_on", "target": "Project Alpha"}]}"#,
+            )
+            .with_fallback(MockResponse::Text(
+                r#"{"relations": [{"source": "Bob", "relation": "prices", "target": "Project Alpha"}]}"#
+                    .to_string(),
+            ));
+        let graph = Arc::new(KnowledgeGraph::new(Arc::clone(&db), "default"));
+        graph
+            .index_pending(&GraphExtractor::new(Arc::new(llm)))
+            .await
+            .unwrap();
+        let tool = MemoryGraphTool::new(graph, workspace);
+
+        let main = JobContext::default();
+        let shared = JobContext::default().with_channel("telegram", SessionScope::Shared);
+        let params = serde_json::json!({"entity": "Project Alpha"});
+
+        let output = tool.execute(params.clone(), &main).await.unwrap();
+        assert_eq!(output.result["fact_count"], 2);
+
+        let output = tool.execute(params, &shared).await.unwrap();
+        assert_eq!(output.result["fact_count"], 1);
+        let facts = output.result["facts"].to_string();
+        assert!(!facts.contains("people/bob.md"));
+
+        let connect = serde_json::json!({"entity": "Alice", "connect_to": "Bob"});
+        let output = tool.execute(connect.clone(), &main).await.unwrap();
+        assert_eq!(output.result["path_count"], 1);
+        let output = tool.execute(connect, &shared).await.unwrap();
+        assert_eq!(output.result["path_count"], 0);
+    }
+
+    #[cfg(feature = "libsql")]
+    #[tokio::test]
+    async fn test_trashed_document_leaves_the_graph_until_restored() {
+        use crate::db::libsql_backend::test_db;
+        use crate::llm::{MockLlmProvider, MockResponse};
+        use crate::workspace::Workspace;
+
+        let (db, _dir) = test_db().await;
+        let workspace = Workspace::new_with_db("default", Arc::clone(&db));
+        workspace
+            .write("people/alice.md", "Alice works on Project Alpha.")
+            .await
+            .unwrap();
+        let extractor = GraphExtractor::new(Arc::new(MockLlmProvider::new().with_fallback(
+            MockResponse::Text(
+                r#"{"relations": [{"source": "Alice", "relation": "works_on", "target": "Project Alpha"}]}"#
+                    .to_string(),
+            ),
+        )));
+        let graph = KnowledgeGraph::new(Arc::clone(&db), "default");
+        assert_eq!(graph.index_pending(&extractor).await.unwrap().edges, 1);
+
+        workspace.delete("people/alice.md").await.unwrap();
+        assert!(graph.neighborhood("alice", 1).await.unwrap().is_empty());
+        assert_eq!(graph.index_pending(&extractor).await.unwrap().documents, 0);
+
+        workspace.restore("people/alice.md").await.unwrap();
+        assert_eq!(graph.index_pending(&extractor).await.unwrap().documents, 1);
+        assert_eq!(graph.neighborhood("alice", 1).await.unwrap().len(), 1);
+    }
+}
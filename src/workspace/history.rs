@@ -0,0 +1,138 @@
+//! Document version history.
+//!
+//! Every update of a document's content first stores the content it
+//! replaces as the next numbered revision, so an overwritten MEMORY.md or
+//! identity file can be inspected and put back. Reverting writes the old
+//! content as a normal update, which makes the revert itself revertible.
+//! Only the newest [`MAX_VERSIONS`] revisions of each document are kept.
+
+use crate::error::WorkspaceError;
+use crate::workspace::{DocumentVersion, MemoryDocument, VersionInfo, Workspace};
+
+/// Revisions kept per document.
+pub const MAX_VERSIONS: usize = 50;
+
+impl Workspace {
+    /// Stored revisions of the document at `path`, newest first.
+    pub async fn history(&self, path: &str) -> Result<Vec<VersionInfo>, WorkspaceError> {
+        let doc = self.read(path).await?;
+        self.storage.list_document_versions(doc.id).await
+    }
+
+    /// Revision `version` of the document at `path`.
+    pub async fn read_version(
+        &self,
+        path: &str,
+        version: i32,
+    ) -> Result<DocumentVersion, WorkspaceError> {
+        let doc = self.read(path).await?;
+        self.storage
+            .get_document_version(doc.id, version)
+            .await?
+            .ok_or(WorkspaceError::VersionNotFound {
+                path: doc.path,
+                version,
+            })
+    }
+
+    /// Restore revision `version` of the document at `path`. The content it
+    /// replaces is stored as a revision like any other update.
+    pub async fn revert(&self, path: &str, version: i32) -> Result<MemoryDocument, WorkspaceError> {
+        let old = self.read_version(path, version).await?;
+        self.write(path, &old.content).await
+    }
+
+    /// Replace the content of `doc`, first storing its current content as a
+    /// revision unless it is empty or unchanged. Fails if the new content
+    /// would exceed the workspace quota.
+    pub(super) async fn save_content(
+        &self,
+        doc: &MemoryDocument,
+        content: &str,
+    ) -> Result<(), WorkspaceError> {
+        self.check_quota(doc, content).await?;
+        if !doc.content.is_empty() && doc.content != content {
+            self.storage
+                .insert_document_version(doc.id, &doc.content, doc.updated_at)
+                .await?;
+            self.storage
+                .prune_document_versions(doc.id, MAX_VERSIONS)
+                .await?;
+        }
+        self.storage.update_document(doc.id, content).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[cfg(feature = "libsql")]
+    #[tokio::test]
+    async fn test_history_and_revert() {
+        use crate::db::libsql_backend::test_db;
+        use crate::error::WorkspaceError;
+        use crate::workspace::{Workspace, paths};
+
+        let (db, _dir) = test_db().await;
+        let ws = Workspace::new_with_db("default", db);
+
+        ws.write(paths::MEMORY, "- Likes tea").await.unwrap();
+        assert!(ws.history(paths::MEMORY).await.unwrap().is_empty());
+
+        ws.append(paths::MEMORY, "- Lives in Lisbon").await.unwrap();
+        // Rewriting the same content stores nothing.
+        ws.write(paths::MEMORY, "- Likes tea\n- Lives in Lisbon")
+            .await
+            .unwrap();
+        ws.write(paths::MEMORY, "Oops, everything is gone")
+            .await
+            .unwrap();
+
+        let history = ws.history(paths::MEMORY).await.unwrap();
+        let versions: Vec<i32> = history.iter().map(|v| v.version).collect();
+        assert_eq!(versions, vec![2, 1]);
+        assert_eq!(history[0].preview, "- Likes tea\n- Lives in Lisbon");
+        assert_eq!(
+            ws.read_version(paths::MEMORY, 1).await.unwrap().content,
+            "- Likes tea"
+        );
+        assert!(matches!(
+            ws.read_version(paths::MEMORY, 7).await,
+            Err(WorkspaceError::VersionNotFound { version: 7, .. })
+        ));
+
+        let doc = ws.revert(paths::MEMORY, 2).await.unwrap();
+        assert_eq!(doc.content, "- Likes tea\n- Lives in Lisbon");
+        assert!(
+            ws.search("Lisbon", 5)
+                .await
+                .unwrap()
+                .iter()
+                .any(|r| r.document_id == doc.id)
+        );
+
+        // The revert is undoable.
+        let undo = ws.read_version(paths::MEMORY, 3).await.unwrap();
+        assert_eq!(undo.content, "Oops, everything is gone");
+    }
+
+    #[cfg(feature = "libsql")]
+    #[tokio::test]
+    async fn test_old_versions_are_pruned() {
+        use super::MAX_VERSIONS;
+        use crate::db::libsql_backend::test_db;
+        use crate::workspace::Workspace;
+
+        let (db, _dir) = test_db().await;
+        let ws = Workspace::new_with_db("default", db);
+
+        for i in 0..MAX_VERSIONS + 5 {
+            ws.write("notes.md", &format!("revision {}", i))
+                .await
+                .unwrap();
+        }
+        let history = ws.history("notes.md").await.unwrap();
+        assert_eq!(history.len(), MAX_VERSIONS);
+        assert_eq!(history[0].version as usize, MAX_VERSIONS + 4);
+        assert_eq!(history[MAX_VERSIONS - 1].version, 5);
+    }
+}
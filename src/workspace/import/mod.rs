@@ -0,0 +1,440 @@
+//! Importing notes exported from other apps into the workspace.
+//!
+//! - [`obsidian`]: an Obsidian vault
+//! - [`notion`]: a Notion export, or any folder or ZIP of Markdown files
+//!
+//! Both keep the export's folder structure under a workspace directory and
+//! rewrite links between notes to point at the imported documents. Links
+//! are resolved the way Obsidian does it: relative to the linking note,
+//! then the shortest path ending in the target, then case-insensitively,
+//! then by the `aliases` in a note's frontmatter. The original text is kept
+//! as display text, and links that don't resolve are left as they are.
+//! Embeds (`![[note]]`) become plain links; the workspace has no
+//! transclusion.
+//!
+//! Text attachments (CSV, JSON, canvases, ...) are imported as documents.
+//! Binary ones (images, PDFs) are copied to an attachments directory on disk
+//! when one is given, and links to them point at the copy. Importing again
+//! overwrites the documents a previous import created.
+
+pub mod notion;
+pub mod obsidian;
+mod zip;
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use serde_json::Value;
+
+use crate::error::WorkspaceError;
+use crate::workspace::frontmatter::parse_frontmatter;
+use crate::workspace::links::{link_key, resolve_link};
+use crate::workspace::{Workspace, normalize_path};
+
+/// Attachment extensions imported as documents rather than copied.
+const TEXT_EXTENSIONS: &[&str] = &[
+    "txt", "csv", "tsv", "json", "canvas", "svg", "html", "xml", "yaml", "yml", "toml", "org",
+    "tex", "bib",
+];
+
+/// What an import did.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ImportReport {
+    /// Notes written to the workspace.
+    pub notes: usize,
+    /// Attachments imported as documents or copied to disk.
+    pub attachments: usize,
+    /// Databases imported as index documents.
+    pub databases: usize,
+    /// Export paths of binary attachments that weren't copied.
+    pub skipped: Vec<String>,
+    /// Links rewritten to point at imported files.
+    pub links_converted: usize,
+    /// Links to files that aren't in the export.
+    pub unresolved_links: usize,
+}
+
+/// Where a vault file ends up.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Destination {
+    /// Workspace document at this path.
+    Document(String),
+    /// File copied to disk.
+    File(PathBuf),
+    /// Binary attachment that isn't imported.
+    Skipped,
+}
+
+/// The files of an export and where each one is imported to.
+struct VaultIndex {
+    /// Export-relative paths, `/`-separated.
+    files: Vec<String>,
+    /// `files`, lowercased.
+    lowered: Vec<String>,
+    /// Lowercased `aliases` frontmatter entries to the note declaring them.
+    aliases: HashMap<String, String>,
+    destinations: HashMap<String, Destination>,
+}
+
+impl VaultIndex {
+    /// Index `files` (export-relative paths), imported to `destinations`.
+    fn new(files: Vec<String>, destinations: HashMap<String, Destination>) -> Self {
+        Self {
+            lowered: files.iter().map(|f| f.to_lowercase()).collect(),
+            files,
+            aliases: HashMap::new(),
+            destinations,
+        }
+    }
+
+    /// Record the `aliases` declared in the frontmatter of `note`.
+    fn add_aliases(&mut self, note: &str, content: &str) {
+        let fields = parse_frontmatter(content);
+        for key in ["aliases", "alias"] {
+            let names = match fields.get(key) {
+                Some(Value::Array(items)) => items.iter().filter_map(Value::as_str).collect(),
+                Some(Value::String(name)) => vec![name.as_str()],
+                _ => Vec::new(),
+            };
+            for name in names.into_iter().filter(|n| !n.is_empty()) {
+                self.aliases
+                    .entry(name.to_lowercase())
+                    .or_insert_with(|| note.to_string());
+            }
+        }
+    }
+
+    /// The file `target` refers to from the note at `from`.
+    fn resolve(&self, target: &str, from: &str) -> Option<&str> {
+        let target = target.trim();
+        if target.is_empty() {
+            return None;
+        }
+        // Relative to the linking note first, which also prefers a note in
+        // the same folder when several share a name.
+        if let Some(joined) = join_relative(from, target) {
+            let key = link_key(&joined);
+            if let Some(file) = self.files.iter().find(|f| link_key(f) == key) {
+                return Some(file);
+            }
+        }
+        if let Some(file) = resolve_link(target, &self.files) {
+            return Some(file);
+        }
+        if let Some(lowered) = resolve_link(&target.to_lowercase(), &self.lowered)
+            && let Some(i) = self.lowered.iter().position(|f| f == lowered)
+        {
+            return Some(&self.files[i]);
+        }
+        self.aliases.get(&target.to_lowercase()).map(String::as_str)
+    }
+
+    /// Rewrite the links in the note at `from`.
+    fn convert_links(&self, content: &str, from: &str, report: &mut ImportReport) -> String {
+        let mut out = String::with_capacity(content.len());
+        let mut in_fence = false;
+        for line in content.split_inclusive('\n') {
+            if line.trim_start().starts_with("```") {
+                in_fence = !in_fence;
+                out.push_str(line);
+            } else if in_fence {
+                out.push_str(line);
+            } else {
+                self.convert_line(line, from, &mut out, report);
+            }
+        }
+        out
+    }
+
+    fn convert_line(&self, line: &str, from: &str, out: &mut String, report: &mut ImportReport) {
+        let mut rest = line;
+        while let Some(start) = rest.find('[') {
+            let embed = rest[..start].ends_with('!');
+            let prefix_end = if embed { start - 1 } else { start };
+
+            // `[[target]]`
+            if let Some(after) = rest[start..].strip_prefix("[[")
+                && let Some(end) = after.find("]]")
+            {
+                let consumed = start + 2 + end + 2;
+                match self.convert_wikilink(&after[..end], embed, from, report) {
+                    Some(link) => {
+                        out.push_str(&rest[..prefix_end]);
+                        out.push_str(&link);
+                    }
+                    None => out.push_str(&rest[..consumed]),
+                }
+                rest = &rest[consumed..];
+                continue;
+            }
+
+            // `[text](target)`
+            let after = &rest[start + 1..];
+            if let Some(close) = after.find("](")
+                && !after[..close].contains(['[', ']'])
+                && let Some(end) = after[close + 2..].find(')')
+            {
+                let text = &after[..close];
+                let target = &after[close + 2..close + 2 + end];
+                let consumed = start + 1 + close + 2 + end + 1;
+                match self.convert_markdown_link(text, target, embed, from, report) {
+                    Some(link) => {
+                        out.push_str(&rest[..prefix_end]);
+                        out.push_str(&link);
+                    }
+                    None => out.push_str(&rest[..consumed]),
+                }
+                rest = &rest[consumed..];
+                continue;
+            }
+
+            out.push_str(&rest[..start + 1]);
+            rest = &rest[start + 1..];
+        }
+        out.push_str(rest);
+    }
+
+    fn convert_wikilink(
+        &self,
+        inner: &str,
+        embed: bool,
+        from: &str,
+        report: &mut ImportReport,
+    ) -> Option<String> {
+        // Inside tables Obsidian escapes the alias separator as `\|`.
+        let (target, separator, alias) = match inner.split_once('|') {
+            Some((target, alias)) => match target.strip_suffix('\\') {
+                Some(target) => (target, "\\|", Some(alias)),
+                None => (target, "|", Some(alias)),
+            },
+            None => (inner, "|", None),
+        };
+        let (name, heading) = match target.split_once('#') {
+            Some((name, heading)) => (name, Some(heading)),
+            None => (target, None),
+        };
+        if name.trim().is_empty() {
+            return None; // A heading in the same note
+        }
+        let Some(file) = self.resolve(name, from) else {
+            report.unresolved_links += 1;
+            return None;
+        };
+        let display = alias.unwrap_or(target).trim();
+        let link = match &self.destinations[file] {
+            Destination::Document(path) => document_link(path, heading, separator, display),
+            Destination::File(path) => file_link(path, embed, display),
+            Destination::Skipped => return None,
+        };
+        report.links_converted += 1;
+        Some(link)
+    }
+
+    fn convert_markdown_link(
+        &self,
+        text: &str,
+        target: &str,
+        embed: bool,
+        from: &str,
+        report: &mut ImportReport,
+    ) -> Option<String> {
+        let target = target.trim();
+        let target = target
+            .strip_prefix('<')
+            .and_then(|t| t.strip_suffix('>'))
+            .unwrap_or(target);
+        if target.is_empty() || target.starts_with('#') || target.contains(':') {
+            return None; // Same-note heading, URL, or `mailto:`
+        }
+        let target = percent_decode(target);
+        let (name, heading) = match target.split_once('#') {
+            Some((name, heading)) => (name, Some(heading)),
+            None => (target.as_str(), None),
+        };
+        let Some(file) = self.resolve(name, from) else {
+            report.unresolved_links += 1;
+            return None;
+        };
+        let link = match &self.destinations[file] {
+            Destination::Document(path) => document_link(path, heading, "|", text.trim()),
+            Destination::File(path) => file_link(path, embed, text),
+            Destination::Skipped => return None,
+        };
+        report.links_converted += 1;
+        Some(link)
+    }
+}
+
+impl Workspace {
+    /// Import the attachment at `source` to `destination`.
+    async fn import_attachment(
+        &self,
+        source: &Path,
+        file: &str,
+        destination: &Destination,
+        report: &mut ImportReport,
+    ) -> Result<(), WorkspaceError> {
+        match destination {
+            Destination::Document(path) => {
+                self.write(path, &read_text(source)?).await?;
+            }
+            Destination::File(dest) => {
+                if let Some(parent) = dest.parent() {
+                    std::fs::create_dir_all(parent).map_err(|e| import_error(parent, e))?;
+                }
+                std::fs::copy(source, dest).map_err(|e| import_error(source, e))?;
+            }
+            Destination::Skipped => {
+                report.skipped.push(file.to_string());
+                return Ok(());
+            }
+        }
+        report.attachments += 1;
+        Ok(())
+    }
+}
+
+/// Where a note or attachment imported to `path` (relative to `prefix`)
+/// ends up.
+fn destination(path: &str, prefix: &str, attachments_dir: Option<&Path>) -> Destination {
+    if is_note(path) || is_text_attachment(path) {
+        Destination::Document(normalize_path(&format!("{}/{}", prefix, path)))
+    } else {
+        match attachments_dir {
+            Some(dir) => Destination::File(dir.join(path)),
+            None => Destination::Skipped,
+        }
+    }
+}
+
+fn is_note(file: &str) -> bool {
+    extension(file).is_some_and(|ext| ext.eq_ignore_ascii_case("md"))
+}
+
+fn is_text_attachment(file: &str) -> bool {
+    extension(file).is_some_and(|ext| {
+        TEXT_EXTENSIONS
+            .iter()
+            .any(|text| ext.eq_ignore_ascii_case(text))
+    })
+}
+
+fn extension(file: &str) -> Option<&str> {
+    let name = file.rsplit('/').next()?;
+    name.rsplit_once('.').map(|(_, ext)| ext)
+}
+
+/// Workspace link to the document at `path`.
+fn document_link(path: &str, heading: Option<&str>, separator: &str, display: &str) -> String {
+    let mut link = format!("[[{}", link_key(path));
+    // Block references (`#^id`) have no workspace equivalent.
+    if let Some(heading) = heading.map(str::trim)
+        && !heading.is_empty()
+        && !heading.starts_with('^')
+    {
+        link.push('#');
+        link.push_str(heading);
+    }
+    if !display.is_empty() {
+        link.push_str(separator);
+        link.push_str(display);
+    }
+    link.push_str("]]");
+    link
+}
+
+/// Markdown link to an attachment copied to `path`.
+fn file_link(path: &Path, embed: bool, display: &str) -> String {
+    let display = if display.is_empty() {
+        path.file_name()
+            .map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_default()
+    } else {
+        display.to_string()
+    };
+    format!(
+        "{}[{}](<{}>)",
+        if embed { "!" } else { "" },
+        display,
+        path.display()
+    )
+}
+
+/// Resolve `target` against the directory of the note at `from`.
+fn join_relative(from: &str, target: &str) -> Option<String> {
+    let mut parts: Vec<&str> = from.split('/').collect();
+    parts.pop();
+    for part in target.split('/') {
+        match part {
+            "" | "." => {}
+            ".." => {
+                parts.pop()?;
+            }
+            part => parts.push(part),
+        }
+    }
+    Some(parts.join("/"))
+}
+
+/// Decode `%XX` escapes, as Markdown links write spaces as `%20`.
+fn percent_decode(s: &str) -> String {
+    let bytes = s.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%'
+            && let Some(byte) = s
+                .get(i + 1..i + 3)
+                .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+        {
+            out.push(byte);
+            i += 3;
+        } else {
+            out.push(bytes[i]);
+            i += 1;
+        }
+    }
+    String::from_utf8(out).unwrap_or_else(|_| s.to_string())
+}
+
+/// Collect the paths of the files under `dir`, relative to it, skipping
+/// hidden files and folders.
+fn walk_files(dir: &Path, relative: &str, files: &mut Vec<String>) -> Result<(), WorkspaceError> {
+    let mut entries: Vec<_> = std::fs::read_dir(dir)
+        .map_err(|e| import_error(dir, e))?
+        .filter_map(|e| e.ok())
+        .collect();
+    entries.sort_by_key(|e| e.file_name());
+    for entry in entries {
+        let name = entry.file_name().to_string_lossy().into_owned();
+        if name.starts_with('.') {
+            continue;
+        }
+        let path = if relative.is_empty() {
+            name
+        } else {
+            format!("{}/{}", relative, name)
+        };
+        let file_type = entry
+            .file_type()
+            .map_err(|e| import_error(&entry.path(), e))?;
+        if file_type.is_dir() {
+            walk_files(&entry.path(), &path, files)?;
+        } else if file_type.is_file() {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+fn read_text(path: &Path) -> Result<String, WorkspaceError> {
+    let bytes = std::fs::read(path).map_err(|e| import_error(path, e))?;
+    Ok(String::from_utf8_lossy(&bytes).into_owned())
+}
+
+fn import_error(path: &Path, e: std::io::Error) -> WorkspaceError {
+    WorkspaceError::ImportFailed {
+        path: path.display().to_string(),
+        reason: e.to_string(),
+    }
+}
@@ -0,0 +1,570 @@
+//! Importing a Notion export, or any folder or ZIP of Markdown files (see
+//! the [parent module](super) for how links and attachments are handled).
+//!
+//! Notion's "Markdown & CSV" export names every page `Title <id>.md`, with
+//! its subpages in a `Title <id>/` folder next to it. Pages are imported at
+//! the same paths with the IDs dropped (kept only where two pages would
+//! otherwise collide).
+//!
+//! A database is exported as `Name <id>.csv` plus a `Name <id>/` folder
+//! holding one page per row. Each row page is imported with its properties
+//! as frontmatter (and a `database` field naming the database), so they can
+//! be filtered like any other frontmatter. The database itself becomes an
+//! index document listing its rows.
+//!
+//! Exports often arrive zipped, sometimes as a ZIP of ZIPs; both are
+//! unpacked before importing.
+
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+use crate::error::WorkspaceError;
+use crate::workspace::import::{
+    Destination, ImportReport, VaultIndex, destination, import_error, is_note, read_text,
+    walk_files, zip,
+};
+use crate::workspace::links::link_key;
+use crate::workspace::{Workspace, normalize_path};
+
+/// How to import an export.
+#[derive(Debug, Clone, Default)]
+pub struct NotionImport {
+    /// Workspace directory the export is imported under (empty for the root).
+    pub prefix: String,
+    /// Directory binary attachments are copied to. `None` skips them.
+    pub attachments_dir: Option<PathBuf>,
+}
+
+/// A database from its CSV export.
+struct Database {
+    /// Database name.
+    name: String,
+    /// Cleaned path of the folder holding its row pages.
+    folder: String,
+    /// Workspace path of its index document.
+    index: String,
+    columns: Vec<String>,
+    rows: Vec<Row>,
+}
+
+struct Row {
+    title: String,
+    /// Property values, one per column.
+    values: Vec<String>,
+    /// Export path of the row's page, if it has one.
+    page: Option<String>,
+}
+
+impl Workspace {
+    /// Import the export at `path`: a directory or a `.zip` archive.
+    pub async fn import_notion(
+        &self,
+        path: &Path,
+        options: &NotionImport,
+    ) -> Result<ImportReport, WorkspaceError> {
+        let is_zip = path
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("zip"));
+        if !is_zip {
+            return self.import_notion_dir(path, options).await;
+        }
+
+        let root =
+            std::env::temp_dir().join(format!("ironclaw-import-{}", uuid::Uuid::new_v4().simple()));
+        let result = match unpack(path, &root) {
+            Ok(()) => self.import_notion_dir(&root, options).await,
+            Err(e) => Err(e),
+        };
+        let _ = std::fs::remove_dir_all(&root);
+        result
+    }
+
+    async fn import_notion_dir(
+        &self,
+        root: &Path,
+        options: &NotionImport,
+    ) -> Result<ImportReport, WorkspaceError> {
+        let mut files = Vec::new();
+        walk_files(root, "", &mut files)?;
+        let cleaned = clean_paths(&files);
+        let workspace_path = |clean: &str| normalize_path(&format!("{}/{}", options.prefix, clean));
+
+        let mut databases = Vec::new();
+        for file in files.iter().filter(|f| is_csv(f)) {
+            // Newer exports write the current view and `<name>_all.csv` with
+            // every row; use the latter when both are there.
+            let all = file.replacen(".csv", "_all.csv", 1);
+            if all != *file && files.contains(&all) {
+                continue;
+            }
+            let folder = database_folder(&cleaned[file]);
+            let mut database = parse_database(&read_text(&root.join(file))?, &folder);
+            database.index = workspace_path(&format!("{}.md", folder));
+            databases.push(database);
+        }
+
+        // Match row pages to their CSV rows by title.
+        let mut rows: HashMap<String, (usize, usize)> = HashMap::new();
+        for (d, database) in databases.iter_mut().enumerate() {
+            let mut pages: Vec<&String> = files
+                .iter()
+                .filter(|f| is_note(f) && parent(&cleaned[*f]) == database.folder)
+                .collect();
+            for (r, row) in database.rows.iter_mut().enumerate() {
+                let key = title_key(&row.title);
+                if let Some(i) = pages
+                    .iter()
+                    .position(|p| title_key(stem(&cleaned[*p])) == key)
+                {
+                    let page = pages.remove(i);
+                    row.page = Some(page.clone());
+                    rows.insert(page.clone(), (d, r));
+                }
+            }
+        }
+
+        let mut destinations: HashMap<String, Destination> = files
+            .iter()
+            .map(|file| {
+                let destination = destination(
+                    &cleaned[file],
+                    &options.prefix,
+                    options.attachments_dir.as_deref(),
+                );
+                (file.clone(), destination)
+            })
+            .collect();
+        let pages: HashSet<String> = destinations
+            .values()
+            .filter_map(|d| match d {
+                Destination::Document(path) => Some(path.clone()),
+                _ => None,
+            })
+            .collect();
+        for file in files.iter().filter(|f| is_csv(f)) {
+            let folder = database_folder(&cleaned[file]);
+            destinations.insert(
+                file.clone(),
+                Destination::Document(workspace_path(&format!("{}.md", folder))),
+            );
+        }
+        let index = VaultIndex::new(files.clone(), destinations);
+
+        let mut report = ImportReport::default();
+        for file in files.iter().filter(|f| is_note(f)) {
+            let content = index.convert_links(&read_text(&root.join(file))?, file, &mut report);
+            let content = match rows.get(file) {
+                Some(&(d, r)) => {
+                    let database = &databases[d];
+                    let row = &database.rows[r];
+                    format!(
+                        "{}{}",
+                        row_frontmatter(database, row),
+                        strip_properties(&content, &database.columns)
+                    )
+                }
+                None => content,
+            };
+            if let Destination::Document(path) = &index.destinations[file] {
+                self.write(path, &content).await?;
+                report.notes += 1;
+            }
+        }
+
+        for database in &databases {
+            let mut listing = format!("# {}\n\n", database.name);
+            for row in &database.rows {
+                let path = match &row.page {
+                    Some(page) => match &index.destinations[page] {
+                        Destination::Document(path) => path.clone(),
+                        _ => continue,
+                    },
+                    None => {
+                        // Rows without a page still carry their properties.
+                        let path = workspace_path(&format!(
+                            "{}/{}.md",
+                            database.folder,
+                            file_name(&row.title)
+                        ));
+                        let content =
+                            format!("{}# {}\n", row_frontmatter(database, row), row.title.trim());
+                        self.write(&path, &content).await?;
+                        report.notes += 1;
+                        path
+                    }
+                };
+                listing.push_str(&format!("- [[{}|{}]]\n", link_key(&path), row.title.trim()));
+            }
+            // An inline database shares its name with a page; keep the page.
+            if !pages.contains(&database.index) {
+                self.write(&database.index, &listing).await?;
+            }
+            report.databases += 1;
+        }
+
+        for file in files.iter().filter(|f| !is_note(f) && !is_csv(f)) {
+            self.import_attachment(
+                &root.join(file),
+                file,
+                &index.destinations[file],
+                &mut report,
+            )
+            .await?;
+        }
+        Ok(report)
+    }
+}
+
+/// Unpack `archive` into `root`, along with any archives inside it.
+fn unpack(archive: &Path, root: &Path) -> Result<(), WorkspaceError> {
+    zip::extract(archive, root)?;
+    let mut files = Vec::new();
+    walk_files(root, "", &mut files)?;
+    for file in files.iter().filter(|f| f.to_lowercase().ends_with(".zip")) {
+        let inner = root.join(file);
+        let dir = inner.parent().unwrap_or(root);
+        zip::extract(&inner, dir)?;
+        std::fs::remove_file(&inner).map_err(|e| import_error(&inner, e))?;
+    }
+    Ok(())
+}
+
+/// Export paths without Notion's IDs, keeping the IDs of paths that would
+/// collide without them.
+fn clean_paths(files: &[String]) -> HashMap<String, String> {
+    let cleaned: Vec<String> = files
+        .iter()
+        .map(|f| f.split('/').map(strip_id).collect::<Vec<_>>().join("/"))
+        .collect();
+    let mut counts: HashMap<&str, usize> = HashMap::new();
+    for clean in &cleaned {
+        *counts.entry(clean.as_str()).or_default() += 1;
+    }
+    files
+        .iter()
+        .zip(&cleaned)
+        .map(|(file, clean)| {
+            let path = if counts[clean.as_str()] > 1 {
+                file.clone()
+            } else {
+                clean.clone()
+            };
+            (file.clone(), path)
+        })
+        .collect()
+}
+
+/// A file or folder name without the ID Notion appends to it.
+fn strip_id(name: &str) -> String {
+    if let Some(name) = without_id(name) {
+        return name.to_string();
+    }
+    if let Some((stem, ext)) = name.rsplit_once('.') {
+        let stem = stem.strip_suffix("_all").unwrap_or(stem);
+        if let Some(stem) = without_id(stem) {
+            return format!("{}.{}", stem, ext);
+        }
+    }
+    name.to_string()
+}
+
+fn without_id(name: &str) -> Option<&str> {
+    let (name, id) = name.rsplit_once(' ')?;
+    (id.len() == 32 && id.bytes().all(|b| b.is_ascii_hexdigit()) && !name.is_empty())
+        .then_some(name)
+}
+
+fn parse_database(csv: &str, folder: &str) -> Database {
+    let mut records = parse_csv(csv).into_iter();
+    let columns: Vec<String> = records
+        .next()
+        .unwrap_or_default()
+        .into_iter()
+        .map(|c| c.trim().to_string())
+        .collect();
+    let rows = records
+        .filter(|r| r.iter().any(|v| !v.trim().is_empty()))
+        .map(|values| Row {
+            title: values.first().cloned().unwrap_or_default(),
+            values,
+            page: None,
+        })
+        .collect();
+    Database {
+        name: stem(folder).to_string(),
+        folder: folder.to_string(),
+        index: String::new(),
+        columns,
+        rows,
+    }
+}
+
+/// Parse CSV as Notion writes it: comma-separated, with `"`-quoted fields
+/// that may contain commas, newlines, and `""` escapes.
+fn parse_csv(text: &str) -> Vec<Vec<String>> {
+    let text = text.trim_start_matches('\u{feff}');
+    let mut records = Vec::new();
+    let mut record = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        if quoted {
+            match c {
+                '"' if chars.peek() == Some(&'"') => {
+                    field.push('"');
+                    chars.next();
+                }
+                '"' => quoted = false,
+                c => field.push(c),
+            }
+            continue;
+        }
+        match c {
+            '"' => quoted = true,
+            ',' => record.push(std::mem::take(&mut field)),
+            '\r' => {}
+            '\n' => {
+                record.push(std::mem::take(&mut field));
+                records.push(std::mem::take(&mut record));
+            }
+            c => field.push(c),
+        }
+    }
+    if !field.is_empty() || !record.is_empty() {
+        record.push(field);
+        records.push(record);
+    }
+    records
+}
+
+/// Frontmatter holding a row's properties. The title column is left out;
+/// it is the page's heading.
+fn row_frontmatter(database: &Database, row: &Row) -> String {
+    let mut frontmatter = format!("---\ndatabase: {}\n", quote(&database.name));
+    for (column, value) in database.columns.iter().zip(&row.values).skip(1) {
+        let key = field_key(column);
+        let value = value.trim();
+        if key.is_empty() || value.is_empty() || key == "database" {
+            continue;
+        }
+        if key.ends_with("tags") {
+            // Multi-select values are exported comma-separated.
+            let items: Vec<String> = value.split(',').map(|t| quote(t.trim())).collect();
+            frontmatter.push_str(&format!("{}: [{}]\n", key, items.join(", ")));
+        } else {
+            frontmatter.push_str(&format!("{}: {}\n", key, quote(value)));
+        }
+    }
+    frontmatter.push_str("---\n\n");
+    frontmatter
+}
+
+/// Drop the `Column: value` lines Notion writes under a row page's title,
+/// now that they are in the frontmatter.
+fn strip_properties(content: &str, columns: &[String]) -> String {
+    let lines: Vec<&str> = content.lines().collect();
+    let Some(title) = lines.iter().position(|l| !l.trim().is_empty()) else {
+        return content.to_string();
+    };
+    if !lines[title].starts_with("# ") {
+        return content.to_string();
+    }
+    let is_property = |line: &str| {
+        columns.iter().any(|c| {
+            line.strip_prefix(c.as_str())
+                .is_some_and(|r| r.starts_with(':'))
+        })
+    };
+    let mut end = title + 1;
+    while end < lines.len() && lines[end].trim().is_empty() {
+        end += 1;
+    }
+    let start = end;
+    while end < lines.len() && is_property(lines[end]) {
+        end += 1;
+    }
+    if end == start {
+        return content.to_string();
+    }
+    let rest = lines[end..].join("\n");
+    let rest = rest.trim_start_matches('\n');
+    if rest.is_empty() {
+        format!("{}\n", lines[title])
+    } else {
+        format!("{}\n\n{}\n", lines[title], rest)
+    }
+}
+
+/// Frontmatter key for a column name: `Due Date` becomes `due_date`.
+fn field_key(column: &str) -> String {
+    let key: String = column
+        .trim()
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() {
+                c.to_ascii_lowercase()
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    key.split('_')
+        .filter(|p| !p.is_empty())
+        .collect::<Vec<_>>()
+        .join("_")
+}
+
+fn quote(value: &str) -> String {
+    format!("\"{}\"", value.replace('\n', " "))
+}
+
+/// Title with only letters and digits, lowercased, for matching file
+/// names against titles Notion sanitized differently.
+fn title_key(title: &str) -> String {
+    title
+        .chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+/// A row title usable as a file name.
+fn file_name(title: &str) -> String {
+    let name: String = title
+        .trim()
+        .chars()
+        .map(|c| if "/\\:*?\"<>|".contains(c) { ' ' } else { c })
+        .collect();
+    if name.trim().is_empty() {
+        "Untitled".to_string()
+    } else {
+        name.trim().to_string()
+    }
+}
+
+/// Cleaned path of the folder holding the rows of the database exported
+/// to `csv`.
+fn database_folder(csv: &str) -> String {
+    let stem = csv.strip_suffix(".csv").unwrap_or(csv);
+    stem.strip_suffix("_all").unwrap_or(stem).to_string()
+}
+
+fn is_csv(file: &str) -> bool {
+    file.to_lowercase().ends_with(".csv")
+}
+
+fn parent(path: &str) -> &str {
+    path.rsplit_once('/').map_or("", |(dir, _)| dir)
+}
+
+/// File name without directory or extension.
+fn stem(path: &str) -> &str {
+    let name = path.rsplit('/').next().unwrap_or(path);
+    name.rsplit_once('.').map_or(name, |(stem, _)| stem)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ID: &str = "0123456789abcdef0123456789abcdef";
+
+    #[test]
+    fn test_strip_ids_and_collisions() {
+        assert_eq!(strip_id(&format!("Road map {ID}.md")), "Road map.md");
+        assert_eq!(strip_id(&format!("Tasks {ID}_all.csv")), "Tasks.csv");
+        assert_eq!(strip_id(&format!("v1.2 notes {ID}")), "v1.2 notes");
+        assert_eq!(strip_id("Plain.md"), "Plain.md");
+
+        let other = "fedcba9876543210fedcba9876543210";
+        let files = vec![
+            format!("Home {ID}/Untitled {ID}.md"),
+            format!("Home {ID}/Untitled {other}.md"),
+        ];
+        let cleaned = clean_paths(&files);
+        assert_eq!(cleaned[&files[0]], files[0]);
+        assert_eq!(parent(&cleaned[&files[1]]), format!("Home {ID}"));
+    }
+
+    #[test]
+    fn test_csv_rows_become_frontmatter() {
+        let csv = "\u{feff}Name,Status,Tags,Due Date\r\n\
+                   \"Write, then ship\",Done,\"a, b\",\"October 15, 2026\"\r\n\
+                   Plan,,,\r\n";
+        let database = parse_database(csv, "Projects/Tasks");
+        assert_eq!(database.name, "Tasks");
+        assert_eq!(database.rows.len(), 2);
+        assert_eq!(
+            row_frontmatter(&database, &database.rows[0]),
+            "---\ndatabase: \"Tasks\"\nstatus: \"Done\"\ntags: [\"a\", \"b\"]\n\
+             due_date: \"October 15, 2026\"\n---\n\n"
+        );
+
+        let page = "# Write, then ship\n\nStatus: Done\nTags: a, b\n\nBody text.\n";
+        assert_eq!(
+            strip_properties(page, &database.columns),
+            "# Write, then ship\n\nBody text.\n"
+        );
+    }
+
+    #[cfg(feature = "libsql")]
+    #[tokio::test]
+    async fn test_import_zipped_export() {
+        use crate::db::libsql_backend::test_db;
+        use crate::workspace::FieldFilter;
+        use crate::workspace::import::zip::tests::build;
+
+        let other = "fedcba9876543210fedcba9876543210";
+        let home = format!("Home {ID}.md");
+        let home_content = format!("# Home\n\nSee [the tasks](Home%20{ID}/Tasks%20{other}.csv).\n");
+        let csv = format!("Home {ID}/Tasks {other}.csv");
+        let row = format!("Home {ID}/Tasks {other}/Ship it {ID}.md");
+        let inner = build(&[
+            (home.as_str(), home_content.as_bytes()),
+            (csv.as_str(), b"Name,Status\nShip it,Done\nPlan,Todo\n"),
+            (
+                row.as_str(),
+                format!("# Ship it\n\nStatus: Done\n\nBack to [Home](../../Home%20{ID}.md)")
+                    .as_bytes(),
+            ),
+        ]);
+
+        let (db, dir) = test_db().await;
+        let archive = dir.path().join("Export.zip");
+        std::fs::write(&archive, build(&[("Part-1.zip", &inner)])).unwrap();
+
+        let ws = Workspace::new_with_db("default", db);
+
+        let report = ws
+            .import_notion(
+                &archive,
+                &NotionImport {
+                    prefix: "notion".to_string(),
+                    attachments_dir: None,
+                },
+            )
+            .await
+            .unwrap();
+        assert_eq!(report.notes, 3);
+        assert_eq!(report.databases, 1);
+
+        let home = ws.read("notion/Home.md").await.unwrap();
+        assert!(home.content.contains("[[notion/Home/Tasks|the tasks]]"));
+        let row = ws.read("notion/Home/Tasks/Ship it.md").await.unwrap();
+        assert_eq!(
+            row.content,
+            "---\ndatabase: \"Tasks\"\nstatus: \"Done\"\n---\n\n# Ship it\n\n\
+             Back to [[notion/Home|Home]]\n"
+        );
+        let index = ws.read("notion/Home/Tasks.md").await.unwrap();
+        assert!(index.content.contains("- [[notion/Home/Tasks/Plan|Plan]]"));
+
+        let done = ws
+            .find_by_frontmatter(&[FieldFilter::equals("status", "Done")])
+            .await
+            .unwrap();
+        assert_eq!(done.len(), 1);
+        assert_eq!(done[0].path, "notion/Home/Tasks/Ship it.md");
+    }
+}
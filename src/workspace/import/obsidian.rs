@@ -0,0 +1,184 @@
+//! Importing an Obsidian vault (see the [parent module](super) for how
+//! links and attachments are handled).
+//!
+//! Notes keep their frontmatter, which is indexed like any document's, and
+//! `aliases` in it are used to resolve links. Hidden folders such as
+//! `.obsidian` and `.trash` are skipped.
+
+use std::path::{Path, PathBuf};
+
+use crate::error::WorkspaceError;
+use crate::workspace::Workspace;
+use crate::workspace::import::{
+    Destination, ImportReport, VaultIndex, destination, is_note, read_text, walk_files,
+};
+
+/// How to import a vault.
+#[derive(Debug, Clone, Default)]
+pub struct ObsidianImport {
+    /// Workspace directory the vault is imported under (empty for the root).
+    pub prefix: String,
+    /// Directory binary attachments are copied to. `None` skips them.
+    pub attachments_dir: Option<PathBuf>,
+}
+
+impl Workspace {
+    /// Import the Obsidian vault at `vault` (see the [module docs](self)).
+    pub async fn import_obsidian(
+        &self,
+        vault: &Path,
+        options: &ObsidianImport,
+    ) -> Result<ImportReport, WorkspaceError> {
+        let mut files = Vec::new();
+        walk_files(vault, "", &mut files)?;
+        let destinations = files
+            .iter()
+            .map(|file| {
+                let destination =
+                    destination(file, &options.prefix, options.attachments_dir.as_deref());
+                (file.clone(), destination)
+            })
+            .collect();
+        let mut index = VaultIndex::new(files, destinations);
+
+        let mut notes = Vec::new();
+        for file in index.files.iter().filter(|f| is_note(f)) {
+            notes.push((file.clone(), read_text(&vault.join(file))?));
+        }
+        for (file, content) in &notes {
+            index.add_aliases(file, content);
+        }
+
+        let mut report = ImportReport::default();
+        for (file, content) in &notes {
+            let content = index.convert_links(content, file, &mut report);
+            if let Destination::Document(path) = &index.destinations[file] {
+                self.write(path, &content).await?;
+                report.notes += 1;
+            }
+        }
+
+        for file in index.files.iter().filter(|f| !is_note(f)) {
+            self.import_attachment(
+                &vault.join(file),
+                file,
+                &index.destinations[file],
+                &mut report,
+            )
+            .await?;
+        }
+        Ok(report)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn index(attachments_dir: Option<&str>) -> VaultIndex {
+        let files = [
+            "Home.md",
+            "Projects/Alpha.md",
+            "Projects/Beta Plan.md",
+            "Archive/2020/Alpha.md",
+            "People/Bob.md",
+            "assets/diagram.png",
+            "assets/data.csv",
+        ];
+        let attachments_dir = attachments_dir.map(PathBuf::from);
+        let mut index = VaultIndex::new(
+            files.iter().map(|f| f.to_string()).collect(),
+            files
+                .iter()
+                .map(|f| {
+                    let destination = destination(f, "vault", attachments_dir.as_deref());
+                    (f.to_string(), destination)
+                })
+                .collect(),
+        );
+        index.add_aliases("People/Bob.md", "---\naliases: [Robert, Bobby]\n---\n");
+        index
+    }
+
+    #[test]
+    fn test_wikilinks_resolve_like_obsidian() {
+        let index = index(None);
+        let mut report = ImportReport::default();
+        let content = "See [[Alpha]], [[2020/alpha#Notes|old]], [[Robert]] and \
+                       [[Beta Plan#^block1]].\n\
+                       ![[Bob]] [[#Local heading]] [[Missing]] ![[diagram.png]] [[data.csv]]\n\
+                       ```\n[[Alpha]]\n```\n";
+        let converted = index.convert_links(content, "Home.md", &mut report);
+        assert_eq!(
+            converted,
+            "See [[vault/Projects/Alpha|Alpha]], [[vault/Archive/2020/Alpha#Notes|old]], \
+             [[vault/People/Bob|Robert]] and [[vault/Projects/Beta Plan|Beta Plan#^block1]].\n\
+             [[vault/People/Bob|Bob]] [[#Local heading]] [[Missing]] ![[diagram.png]] \
+             [[vault/assets/data.csv|data.csv]]\n\
+             ```\n[[Alpha]]\n```\n"
+        );
+        assert_eq!(report.links_converted, 6);
+        assert_eq!(report.unresolved_links, 1);
+    }
+
+    #[test]
+    fn test_markdown_links_and_attachments() {
+        let index = index(Some("/data/attachments"));
+        let mut report = ImportReport::default();
+        let content = "[plan](Beta%20Plan.md#Goals) ![chart](../assets/diagram.png) \
+                       [site](https://example.com) | [[Alpha\\|table alias]] |";
+        let converted = index.convert_links(content, "Projects/Alpha.md", &mut report);
+        assert_eq!(
+            converted,
+            "[[vault/Projects/Beta Plan#Goals|plan]] \
+             ![chart](</data/attachments/assets/diagram.png>) \
+             [site](https://example.com) | [[vault/Projects/Alpha\\|table alias]] |"
+        );
+        assert_eq!(report.links_converted, 3);
+    }
+
+    #[cfg(feature = "libsql")]
+    #[tokio::test]
+    async fn test_import_vault() {
+        use crate::db::libsql_backend::test_db;
+        use crate::workspace::frontmatter::stored_frontmatter;
+
+        let (db, dir) = test_db().await;
+        let vault = dir.path().join("vault");
+        for (path, content) in [
+            (
+                "Projects/Alpha.md",
+                "---\ntags: [work]\n---\n# Alpha\n\nOwner: [[Bob]]",
+            ),
+            ("People/Bob.md", "# Bob"),
+            (".obsidian/app.json", "{}"),
+            ("assets/photo.jpg", "not really a jpeg"),
+        ] {
+            let path = vault.join(path);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, content).unwrap();
+        }
+
+        let ws = Workspace::new_with_db("default", db);
+
+        let report = ws
+            .import_obsidian(
+                &vault,
+                &ObsidianImport {
+                    prefix: "notes".to_string(),
+                    attachments_dir: None,
+                },
+            )
+            .await
+            .unwrap();
+        assert_eq!(report.notes, 2);
+        assert_eq!(report.skipped, vec!["assets/photo.jpg".to_string()]);
+
+        let alpha = ws.read("notes/Projects/Alpha.md").await.unwrap();
+        assert!(alpha.content.contains("Owner: [[notes/People/Bob|Bob]]"));
+        assert_eq!(stored_frontmatter(&alpha)["tags"][0], "work");
+        let backlinks = ws.backlinks("notes/People/Bob.md").await.unwrap();
+        assert_eq!(backlinks[0].path, "notes/Projects/Alpha.md");
+        assert!(!ws.exists("notes/.obsidian/app.json").await.unwrap());
+    }
+}
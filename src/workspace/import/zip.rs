@@ -0,0 +1,250 @@
+//! Just enough of the ZIP format to unpack note exports.
+//!
+//! Reads stored and deflated entries through the central directory. ZIP64
+//! archives, encryption, and other compression methods are rejected.
+//! Entry paths that would escape the destination are rejected too.
+
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Component, Path, PathBuf};
+
+use flate2::CrcReader;
+use flate2::read::DeflateDecoder;
+
+use crate::error::WorkspaceError;
+use crate::workspace::import::import_error;
+
+const END_OF_CENTRAL_DIRECTORY: u32 = 0x0605_4b50;
+const CENTRAL_DIRECTORY_ENTRY: u32 = 0x0201_4b50;
+const LOCAL_HEADER: u32 = 0x0403_4b50;
+
+/// End of central directory record size, without the trailing comment.
+const EOCD_SIZE: usize = 22;
+
+const STORED: u16 = 0;
+const DEFLATED: u16 = 8;
+
+struct Entry {
+    name: String,
+    method: u16,
+    flags: u16,
+    crc: u32,
+    compressed_size: u64,
+    header_offset: u64,
+}
+
+/// Unpack the archive at `archive` into `dest`.
+pub(super) fn extract(archive: &Path, dest: &Path) -> Result<(), WorkspaceError> {
+    let invalid = |reason: &str| WorkspaceError::ImportFailed {
+        path: archive.display().to_string(),
+        reason: reason.to_string(),
+    };
+    let io = |e| import_error(archive, e);
+
+    let mut file = File::open(archive).map_err(io)?;
+    let len = file.metadata().map_err(io)?.len();
+
+    // The end record is followed by a comment of at most 64 KiB.
+    let tail_len = len.min((EOCD_SIZE + u16::MAX as usize) as u64);
+    let mut tail = vec![0; tail_len as usize];
+    file.seek(SeekFrom::Start(len - tail_len)).map_err(io)?;
+    file.read_exact(&mut tail).map_err(io)?;
+    let eocd = (0..=tail.len().saturating_sub(EOCD_SIZE))
+        .rev()
+        .find(|&i| u32_at(&tail, i) == END_OF_CENTRAL_DIRECTORY)
+        .ok_or_else(|| invalid("not a ZIP archive"))?;
+    let count = u16_at(&tail, eocd + 10);
+    let directory_size = u32_at(&tail, eocd + 12);
+    let directory_offset = u32_at(&tail, eocd + 16);
+    if count == u16::MAX || directory_offset == u32::MAX {
+        return Err(invalid("ZIP64 archives are not supported"));
+    }
+
+    let mut directory = vec![0; directory_size as usize];
+    file.seek(SeekFrom::Start(directory_offset.into()))
+        .map_err(io)?;
+    file.read_exact(&mut directory).map_err(io)?;
+
+    let mut entries = Vec::with_capacity(count.into());
+    let mut pos = 0;
+    for _ in 0..count {
+        if directory.len() < pos + 46 || u32_at(&directory, pos) != CENTRAL_DIRECTORY_ENTRY {
+            return Err(invalid("corrupt central directory"));
+        }
+        let name_len = usize::from(u16_at(&directory, pos + 28));
+        let extra_len = usize::from(u16_at(&directory, pos + 30));
+        let comment_len = usize::from(u16_at(&directory, pos + 32));
+        let name = directory
+            .get(pos + 46..pos + 46 + name_len)
+            .ok_or_else(|| invalid("corrupt central directory"))?;
+        entries.push(Entry {
+            name: String::from_utf8_lossy(name).replace('\\', "/"),
+            flags: u16_at(&directory, pos + 8),
+            method: u16_at(&directory, pos + 10),
+            crc: u32_at(&directory, pos + 16),
+            compressed_size: u32_at(&directory, pos + 20).into(),
+            header_offset: u32_at(&directory, pos + 42).into(),
+        });
+        pos += 46 + name_len + extra_len + comment_len;
+    }
+
+    for entry in entries {
+        // Resource forks added by macOS's archiver.
+        if entry.name.starts_with("__MACOSX/") {
+            continue;
+        }
+        let Some(relative) = safe_path(&entry.name) else {
+            return Err(invalid(&format!("unsafe entry path '{}'", entry.name)));
+        };
+        let target = dest.join(relative);
+        if entry.name.ends_with('/') {
+            std::fs::create_dir_all(&target).map_err(|e| import_error(&target, e))?;
+            continue;
+        }
+        if entry.flags & 1 != 0 {
+            return Err(invalid(&format!("'{}' is encrypted", entry.name)));
+        }
+
+        let mut header = [0; 30];
+        file.seek(SeekFrom::Start(entry.header_offset))
+            .map_err(io)?;
+        file.read_exact(&mut header).map_err(io)?;
+        if u32_at(&header, 0) != LOCAL_HEADER {
+            return Err(invalid(&format!("corrupt entry '{}'", entry.name)));
+        }
+        let skip = i64::from(u16_at(&header, 26)) + i64::from(u16_at(&header, 28));
+        file.seek(SeekFrom::Current(skip)).map_err(io)?;
+
+        if let Some(parent) = target.parent() {
+            std::fs::create_dir_all(parent).map_err(|e| import_error(parent, e))?;
+        }
+        let mut out = File::create(&target).map_err(|e| import_error(&target, e))?;
+        let data = (&mut file).take(entry.compressed_size);
+        let crc = match entry.method {
+            STORED => copy_checked(data, &mut out),
+            DEFLATED => copy_checked(DeflateDecoder::new(data), &mut out),
+            method => {
+                return Err(invalid(&format!(
+                    "'{}' uses unsupported compression method {}",
+                    entry.name, method
+                )));
+            }
+        }
+        .map_err(|e| import_error(&target, e))?;
+        if crc != entry.crc {
+            return Err(invalid(&format!("checksum mismatch in '{}'", entry.name)));
+        }
+    }
+    Ok(())
+}
+
+/// Copy `reader` to `out`, returning the CRC-32 of what was copied.
+fn copy_checked(reader: impl Read, out: &mut File) -> std::io::Result<u32> {
+    let mut reader = CrcReader::new(reader);
+    std::io::copy(&mut reader, out)?;
+    Ok(reader.crc().sum())
+}
+
+/// `name` as a relative path that stays inside the destination.
+fn safe_path(name: &str) -> Option<PathBuf> {
+    let path = Path::new(name.trim_end_matches('/'));
+    let mut safe = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::Normal(part) => safe.push(part),
+            Component::CurDir => {}
+            _ => return None,
+        }
+    }
+    (!safe.as_os_str().is_empty()).then_some(safe)
+}
+
+fn u16_at(bytes: &[u8], pos: usize) -> u16 {
+    u16::from_le_bytes([bytes[pos], bytes[pos + 1]])
+}
+
+fn u32_at(bytes: &[u8], pos: usize) -> u32 {
+    u32::from_le_bytes([bytes[pos], bytes[pos + 1], bytes[pos + 2], bytes[pos + 3]])
+}
+
+#[cfg(test)]
+pub(super) mod tests {
+    use std::io::Write;
+
+    use flate2::Compression;
+    use flate2::write::DeflateEncoder;
+
+    use super::*;
+
+    /// Build a ZIP archive of deflated `files`.
+    pub(in crate::workspace::import) fn build(files: &[(&str, &[u8])]) -> Vec<u8> {
+        let mut archive = Vec::new();
+        let mut directory = Vec::new();
+        for (name, content) in files {
+            let mut encoder = DeflateEncoder::new(Vec::new(), Compression::default());
+            encoder.write_all(content).unwrap();
+            let compressed = encoder.finish().unwrap();
+            let mut crc = flate2::Crc::new();
+            crc.update(content);
+
+            let offset = archive.len() as u32;
+            let mut fields = Vec::new();
+            fields.extend_from_slice(&20u16.to_le_bytes()); // version needed
+            fields.extend_from_slice(&0u16.to_le_bytes()); // flags
+            fields.extend_from_slice(&DEFLATED.to_le_bytes());
+            fields.extend_from_slice(&[0; 4]); // time, date
+            fields.extend_from_slice(&crc.sum().to_le_bytes());
+            fields.extend_from_slice(&(compressed.len() as u32).to_le_bytes());
+            fields.extend_from_slice(&(content.len() as u32).to_le_bytes());
+            fields.extend_from_slice(&(name.len() as u16).to_le_bytes());
+            fields.extend_from_slice(&0u16.to_le_bytes()); // extra length
+
+            archive.extend_from_slice(&LOCAL_HEADER.to_le_bytes());
+            archive.extend_from_slice(&fields);
+            archive.extend_from_slice(name.as_bytes());
+            archive.extend_from_slice(&compressed);
+
+            directory.extend_from_slice(&CENTRAL_DIRECTORY_ENTRY.to_le_bytes());
+            directory.extend_from_slice(&20u16.to_le_bytes()); // version made by
+            directory.extend_from_slice(&fields);
+            directory.extend_from_slice(&[0; 10]); // comment, disk, attributes
+            directory.extend_from_slice(&offset.to_le_bytes());
+            directory.extend_from_slice(name.as_bytes());
+        }
+        let directory_offset = archive.len() as u32;
+        archive.extend_from_slice(&directory);
+        archive.extend_from_slice(&END_OF_CENTRAL_DIRECTORY.to_le_bytes());
+        archive.extend_from_slice(&[0; 4]); // disk numbers
+        archive.extend_from_slice(&(files.len() as u16).to_le_bytes());
+        archive.extend_from_slice(&(files.len() as u16).to_le_bytes());
+        archive.extend_from_slice(&(directory.len() as u32).to_le_bytes());
+        archive.extend_from_slice(&directory_offset.to_le_bytes());
+        archive.extend_from_slice(&0u16.to_le_bytes()); // comment length
+        archive
+    }
+
+    #[test]
+    fn test_extract_round_trip_and_rejects_escapes() {
+        let dir = tempfile::tempdir().unwrap();
+        let archive = dir.path().join("export.zip");
+        std::fs::write(
+            &archive,
+            build(&[("Notes/a.md", b"# A"), ("Notes/img.png", &[0, 1, 2, 255])]),
+        )
+        .unwrap();
+        let out = dir.path().join("out");
+        extract(&archive, &out).unwrap();
+        assert_eq!(
+            std::fs::read_to_string(out.join("Notes/a.md")).unwrap(),
+            "# A"
+        );
+        assert_eq!(
+            std::fs::read(out.join("Notes/img.png")).unwrap(),
+            vec![0, 1, 2, 255]
+        );
+
+        std::fs::write(&archive, build(&[("../evil.md", b"x")])).unwrap();
+        assert!(extract(&archive, &out).is_err());
+        assert!(!dir.path().join("evil.md").exists());
+    }
+}
@@ -0,0 +1,319 @@
+//! Importance scoring for memory chunks.
+//!
+//! Search relevance alone ranks a passing remark as highly as a fact the
+//! user asked to keep. Each chunk therefore carries importance signals that
+//! are blended into the fused search score:
+//!
+//! - **Static importance**, set when the chunk is indexed: the document type
+//!   (`MEMORY.md` outranks identity files, which outrank daily log rollups,
+//!   raw daily logs, and the decay archive) plus a boost for explicit
+//!   markers like "remember this".
+//! - **Retrieval frequency**: how often the chunk has been returned by search.
+//! - **Recency**: the later of the document's last update and the chunk's
+//!   last retrieval, decaying with a 30-day half-life.
+//!
+//! Signals are keyed by document and a hash of the chunk text, so they
+//! survive re-indexing as long as the chunk's text is unchanged.
+
+use std::collections::HashMap;
+
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+use crate::error::WorkspaceError;
+use crate::workspace::decay::ARCHIVE_DIR;
+use crate::workspace::document::paths;
+use crate::workspace::rollup::{MONTHLY_DIR, WEEKLY_DIR};
+use crate::workspace::search::SearchResult;
+use crate::workspace::{SearchConfig, Workspace};
+
+/// Score used for chunks indexed before importance was tracked.
+pub const NEUTRAL_IMPORTANCE: f32 = 0.5;
+
+/// Retrievals at which the frequency signal reaches one half.
+const FREQUENCY_MIDPOINT: f32 = 5.0;
+
+/// Days for the recency signal to halve.
+const RECENCY_HALF_LIFE_DAYS: f32 = 30.0;
+
+/// Phrases that mark a chunk as something the user asked to keep.
+const EXPLICIT_MARKERS: &[&str] = &[
+    "remember this",
+    "remember that",
+    "don't forget",
+    "do not forget",
+    "never forget",
+    "important:",
+    "note to self",
+];
+
+/// Stored importance signals for one chunk.
+#[derive(Debug, Clone)]
+pub struct ChunkSignals {
+    pub document_id: Uuid,
+    pub content_hash: String,
+    /// Static importance from document type and explicit markers (0.0-1.0).
+    pub importance: f32,
+    pub retrieval_count: u64,
+    pub last_retrieved_at: Option<DateTime<Utc>>,
+    /// When the chunk's document was last updated.
+    pub document_updated_at: DateTime<Utc>,
+}
+
+impl ChunkSignals {
+    /// Combined importance at `now` (0.0-1.0).
+    pub fn score(&self, now: DateTime<Utc>) -> f32 {
+        let count = self.retrieval_count as f32;
+        let frequency = count / (count + FREQUENCY_MIDPOINT);
+
+        let last_used = self
+            .last_retrieved_at
+            .map_or(self.document_updated_at, |t| {
+                t.max(self.document_updated_at)
+            });
+        let age_days = (now - last_used).num_seconds().max(0) as f32 / 86_400.0;
+        let recency = 0.5f32.powf(age_days / RECENCY_HALF_LIFE_DAYS);
+
+        (0.5 * self.importance + 0.3 * frequency + 0.2 * recency).clamp(0.0, 1.0)
+    }
+}
+
+/// Key for a chunk's signals: a hash of its text.
+pub fn content_hash(content: &str) -> String {
+    blake3::hash(content.trim().as_bytes()).to_hex()[..16].to_string()
+}
+
+/// Static importance of a chunk of the document at `path`.
+pub fn static_importance(path: &str, content: &str) -> f32 {
+    let by_type = if path == paths::MEMORY {
+        1.0
+    } else if [paths::USER, paths::IDENTITY, paths::SOUL, paths::AGENTS].contains(&path) {
+        0.8
+    } else if path.starts_with(ARCHIVE_DIR) {
+        0.1
+    } else if path.starts_with(WEEKLY_DIR) || path.starts_with(MONTHLY_DIR) {
+        NEUTRAL_IMPORTANCE
+    } else if path.starts_with(paths::DAILY_DIR) {
+        0.3
+    } else if path.starts_with(paths::CONTEXT_DIR) {
+        0.6
+    } else {
+        NEUTRAL_IMPORTANCE
+    };
+
+    let lower = content.to_lowercase();
+    if EXPLICIT_MARKERS.iter().any(|m| lower.contains(m)) {
+        (by_type + 0.4f32).min(1.0)
+    } else {
+        by_type
+    }
+}
+
+/// Blend importance into `results` and re-sort them.
+///
+/// `weight` is the share of the final score taken by importance; chunks
+/// without stored signals get [`NEUTRAL_IMPORTANCE`].
+pub fn blend_importance(
+    results: &mut [SearchResult],
+    signals: &HashMap<(Uuid, String), ChunkSignals>,
+    weight: f32,
+    now: DateTime<Utc>,
+) {
+    for result in results.iter_mut() {
+        let importance = signals
+            .get(&(result.document_id, content_hash(&result.content)))
+            .map_or(NEUTRAL_IMPORTANCE, |s| s.score(now));
+        result.importance = Some(importance);
+        result.score = (1.0 - weight) * result.score + weight * importance;
+    }
+    results.sort_by(|a, b| b.score.total_cmp(&a.score));
+}
+
+impl Workspace {
+    /// Store the static importance of a document's chunks and drop signals
+    /// for chunks it no longer has.
+    pub(super) async fn score_chunks(
+        &self,
+        document_id: Uuid,
+        path: &str,
+        chunks: &[String],
+    ) -> Result<(), WorkspaceError> {
+        let mut hashes = Vec::with_capacity(chunks.len());
+        for chunk in chunks {
+            let hash = content_hash(chunk);
+            self.storage
+                .set_chunk_importance(document_id, &hash, static_importance(path, chunk))
+                .await?;
+            hashes.push(hash);
+        }
+        self.storage.prune_chunk_signals(document_id, &hashes).await
+    }
+
+    /// Re-rank search results by importance and count them as retrieved.
+    pub(super) async fn apply_importance(
+        &self,
+        results: &mut Vec<SearchResult>,
+        config: &SearchConfig,
+    ) {
+        if results.is_empty() {
+            return;
+        }
+        if config.importance_weight > 0.0 {
+            let mut document_ids: Vec<Uuid> = results.iter().map(|r| r.document_id).collect();
+            document_ids.sort();
+            document_ids.dedup();
+            match self.storage.get_chunk_signals(&document_ids).await {
+                Ok(rows) => {
+                    let signals = rows
+                        .into_iter()
+                        .map(|s| ((s.document_id, s.content_hash.clone()), s))
+                        .collect();
+                    blend_importance(results, &signals, config.importance_weight, Utc::now());
+                }
+                Err(e) => tracing::warn!("Failed to load chunk importance: {}", e),
+            }
+        }
+        results.truncate(config.limit);
+
+        for result in results.iter() {
+            if let Err(e) = self
+                .storage
+                .record_chunk_retrieval(result.document_id, &content_hash(&result.content))
+                .await
+            {
+                tracing::warn!("Failed to record chunk retrieval: {}", e);
+                break;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn result(content: &str, document_id: Uuid, score: f32) -> SearchResult {
+        SearchResult {
+            document_id,
+            chunk_id: Uuid::new_v4(),
+            content: content.to_string(),
+            score,
+            fts_rank: Some(1),
+            vector_rank: None,
+            sparse_rank: None,
+            fts_score: None,
+            vector_score: None,
+            sparse_score: None,
+            importance: None,
+        }
+    }
+
+    #[test]
+    fn test_static_importance_by_type_and_marker() {
+        assert_eq!(static_importance("MEMORY.md", "likes tea"), 1.0);
+        assert_eq!(static_importance("daily/2026-10-01.md", "chatted"), 0.3);
+        assert!(static_importance("daily/weekly/2026-W40.md", "chatted") > 0.3);
+        assert_eq!(static_importance("memory/archive/2026-01.md", "old"), 0.1);
+        assert_eq!(static_importance("notes/x.md", "misc"), NEUTRAL_IMPORTANCE);
+        let marked = static_importance("daily/2026-10-01.md", "Remember this: gate code 4471");
+        assert!((marked - 0.7).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_score_rises_with_use_and_falls_with_age() {
+        let now = Utc::now();
+        let fresh = ChunkSignals {
+            document_id: Uuid::new_v4(),
+            content_hash: "x".to_string(),
+            importance: 0.5,
+            retrieval_count: 0,
+            last_retrieved_at: None,
+            document_updated_at: now,
+        };
+        let stale = ChunkSignals {
+            document_updated_at: now - chrono::Duration::days(90),
+            ..fresh.clone()
+        };
+        let popular = ChunkSignals {
+            retrieval_count: 20,
+            ..stale.clone()
+        };
+        assert!(fresh.score(now) > stale.score(now));
+        assert!(popular.score(now) > stale.score(now));
+    }
+
+    #[test]
+    fn test_blend_lets_important_chunk_overtake() {
+        let now = Utc::now();
+        let memory = Uuid::new_v4();
+        let daily = Uuid::new_v4();
+        let mut results = vec![
+            result("we chatted about the weather", daily, 1.0),
+            result("allergic to penicillin", memory, 0.9),
+        ];
+        let signals: HashMap<_, _> = [
+            (daily, "we chatted about the weather", 0.3),
+            (memory, "allergic to penicillin", 1.0),
+        ]
+        .into_iter()
+        .map(|(document_id, content, importance)| {
+            let signals = ChunkSignals {
+                document_id,
+                content_hash: content_hash(content),
+                importance,
+                retrieval_count: 0,
+                last_retrieved_at: None,
+                document_updated_at: now,
+            };
+            ((document_id, signals.content_hash.clone()), signals)
+        })
+        .collect();
+
+        blend_importance(&mut results, &signals, 0.3, now);
+        assert_eq!(results[0].content, "allergic to penicillin");
+        assert!(results.iter().all(|r| r.importance.is_some()));
+    }
+
+    #[cfg(feature = "libsql")]
+    #[tokio::test]
+    async fn test_search_ranks_memory_over_daily_chatter() {
+        use std::sync::Arc;
+
+        use crate::db::libsql_backend::test_db;
+
+        let (db, _dir) = test_db().await;
+        let workspace = Workspace::new_with_db("default", Arc::clone(&db));
+
+        workspace
+            .write(
+                "daily/2026-10-01.md",
+                "Talked about penicillin in passing while discussing penicillin history and penicillin films",
+            )
+            .await
+            .unwrap();
+        workspace
+            .write(paths::MEMORY, "Allergic to penicillin")
+            .await
+            .unwrap();
+
+        let results = workspace.search("penicillin", 5).await.unwrap();
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].content, "Allergic to penicillin");
+        assert!(results[0].importance.unwrap() > results[1].importance.unwrap());
+
+        let memory = workspace.memory().await.unwrap();
+        let signals = db.get_chunk_signals(&[memory.id]).await.unwrap();
+        assert_eq!(signals.len(), 1);
+        assert_eq!(signals[0].retrieval_count, 1);
+        assert!(signals[0].last_retrieved_at.is_some());
+
+        // Rewriting the document replaces its signals.
+        workspace
+            .write(paths::MEMORY, "Allergic to amoxicillin")
+            .await
+            .unwrap();
+        let signals = db.get_chunk_signals(&[memory.id]).await.unwrap();
+        assert_eq!(signals.len(), 1);
+        assert_eq!(signals[0].retrieval_count, 0);
+    }
+}
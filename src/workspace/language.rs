@@ -0,0 +1,278 @@
+//! Language detection for indexed documents.
+//!
+//! Full-text search stems words, and stemming is language specific: the
+//! English configuration leaves "Häuser" and "Haus" unrelated and drops
+//! words it takes for English stop words. Each document's language is
+//! detected when it is indexed and stored on its chunks, so PostgreSQL can
+//! build and query each chunk's `tsvector` with the matching text search
+//! configuration. libSQL's FTS5 tokenizer is language-neutral and only
+//! records the language.
+//!
+//! Detection is deliberately cheap: the script decides Cyrillic and
+//! non-alphabetic scripts, and for Latin script the language whose common
+//! function words occur most often wins. Text that gives no clear signal
+//! (short notes, code, lists of names) counts as English, which is what
+//! every chunk was indexed as before detection existed.
+
+use std::collections::HashMap;
+
+/// Version of [`Language::detect`]. Bump it whenever detection changes, so
+/// [`Workspace::reindex_all`](crate::workspace::Workspace::reindex_all)
+/// re-detects the language of existing chunks.
+pub const DETECTOR_VERSION: u32 = 1;
+
+/// Characters of a document looked at when detecting its language.
+const SAMPLE_CHARS: usize = 4000;
+
+/// Least letters in the sample for detection to be attempted.
+const MIN_LETTERS: usize = 20;
+
+/// Least function-word hits for a Latin-script language to beat English.
+const MIN_HITS: usize = 3;
+
+/// A language with its own full-text search configuration.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Language {
+    English,
+    German,
+    French,
+    Spanish,
+    Italian,
+    Portuguese,
+    Dutch,
+    Swedish,
+    Russian,
+    /// Any other language: indexed without stemming or stop words.
+    Other,
+}
+
+impl Language {
+    pub const ALL: [Language; 10] = [
+        Language::English,
+        Language::German,
+        Language::French,
+        Language::Spanish,
+        Language::Italian,
+        Language::Portuguese,
+        Language::Dutch,
+        Language::Swedish,
+        Language::Russian,
+        Language::Other,
+    ];
+
+    /// Detect the main language of `text`.
+    pub fn detect(text: &str) -> Self {
+        let sample: String = text.chars().take(SAMPLE_CHARS).collect();
+
+        let (mut latin, mut cyrillic, mut other) = (0usize, 0usize, 0usize);
+        for c in sample.chars().filter(|c| c.is_alphabetic()) {
+            match c as u32 {
+                0x0041..=0x024F => latin += 1,
+                0x0400..=0x04FF => cyrillic += 1,
+                _ => other += 1,
+            }
+        }
+        let letters = latin + cyrillic + other;
+        if letters < MIN_LETTERS {
+            return Language::English;
+        }
+        if cyrillic * 2 > letters {
+            return Language::Russian;
+        }
+        if other * 2 > letters {
+            return Language::Other;
+        }
+
+        let mut hits: HashMap<Language, usize> = HashMap::new();
+        for word in sample
+            .split(|c: char| !c.is_alphabetic())
+            .filter(|w| !w.is_empty())
+        {
+            let word = word.to_lowercase();
+            for language in Language::ALL {
+                if language.stop_words().contains(&word.as_str()) {
+                    *hits.entry(language).or_default() += 1;
+                }
+            }
+        }
+        let english = hits.get(&Language::English).copied().unwrap_or(0);
+        // First in `ALL` order wins ties, so English keeps them.
+        let (best, count) = Language::ALL
+            .iter()
+            .map(|l| (*l, hits.get(l).copied().unwrap_or(0)))
+            .fold((Language::English, english), |best, cur| {
+                if cur.1 > best.1 { cur } else { best }
+            });
+        if count >= MIN_HITS {
+            best
+        } else {
+            Language::English
+        }
+    }
+
+    /// ISO 639-1 code, or `und` (undetermined) for [`Language::Other`].
+    pub fn code(self) -> &'static str {
+        match self {
+            Language::English => "en",
+            Language::German => "de",
+            Language::French => "fr",
+            Language::Spanish => "es",
+            Language::Italian => "it",
+            Language::Portuguese => "pt",
+            Language::Dutch => "nl",
+            Language::Swedish => "sv",
+            Language::Russian => "ru",
+            Language::Other => "und",
+        }
+    }
+
+    /// Name of the PostgreSQL text search configuration, as stored in
+    /// `memory_chunks.language`.
+    pub fn fts_config(self) -> &'static str {
+        match self {
+            Language::English => "english",
+            Language::German => "german",
+            Language::French => "french",
+            Language::Spanish => "spanish",
+            Language::Italian => "italian",
+            Language::Portuguese => "portuguese",
+            Language::Dutch => "dutch",
+            Language::Swedish => "swedish",
+            Language::Russian => "russian",
+            Language::Other => "simple",
+        }
+    }
+
+    /// The language stored as `config` in `memory_chunks.language`.
+    pub fn from_fts_config(config: &str) -> Self {
+        Language::ALL
+            .into_iter()
+            .find(|l| l.fts_config() == config)
+            .unwrap_or(Language::Other)
+    }
+
+    /// Common function words, lowercased. Only consulted for Latin script.
+    fn stop_words(self) -> &'static [&'static str] {
+        match self {
+            Language::English => &[
+                "the", "and", "is", "are", "of", "to", "with", "that", "this", "for", "was",
+                "have", "not", "you", "it", "in", "on", "be",
+            ],
+            Language::German => &[
+                "der", "die", "das", "und", "ist", "nicht", "mit", "ich", "sie", "ein", "eine",
+                "auf", "den", "zu", "auch", "wir", "für", "sich",
+            ],
+            Language::French => &[
+                "le", "la", "les", "et", "est", "une", "des", "du", "pas", "pour", "que", "qui",
+                "dans", "avec", "je", "nous", "sur", "au",
+            ],
+            Language::Spanish => &[
+                "el", "los", "las", "y", "es", "una", "por", "para", "que", "con", "del", "pero",
+                "como", "está", "muy", "se", "en", "lo",
+            ],
+            Language::Italian => &[
+                "il", "gli", "e", "è", "una", "per", "che", "con", "del", "della", "non", "sono",
+                "anche", "di", "questo", "ma", "nel", "alla",
+            ],
+            Language::Portuguese => &[
+                "o", "os", "as", "e", "é", "uma", "para", "com", "não", "que", "do", "da", "em",
+                "mas", "são", "no", "na", "um",
+            ],
+            Language::Dutch => &[
+                "de", "het", "een", "en", "is", "niet", "van", "met", "op", "dat", "ik", "zijn",
+                "voor", "ook", "maar", "wij", "er", "te",
+            ],
+            Language::Swedish => &[
+                "och", "är", "att", "det", "som", "en", "inte", "med", "för", "på", "jag", "vi",
+                "har", "av", "till", "den", "ett", "om",
+            ],
+            Language::Russian | Language::Other => &[],
+        }
+    }
+}
+
+impl std::fmt::Display for Language {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.code())
+    }
+}
+
+/// Share of chunks in `counts` (language and chunk count) that are not
+/// English.
+pub fn non_english_share(counts: &[(Language, u64)]) -> f64 {
+    let total: u64 = counts.iter().map(|(_, n)| n).sum();
+    if total == 0 {
+        return 0.0;
+    }
+    let other: u64 = counts
+        .iter()
+        .filter(|(l, _)| *l != Language::English)
+        .map(|(_, n)| n)
+        .sum();
+    other as f64 / total as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect_languages() {
+        let cases = [
+            (
+                "The user is working on a new project and wants to have it done by the end of the month.",
+                Language::English,
+            ),
+            (
+                "Der Benutzer wohnt in Berlin und arbeitet bei einer Bank. Er ist nicht verheiratet und hat eine Katze.",
+                Language::German,
+            ),
+            (
+                "L'utilisateur habite à Lyon avec sa famille. Il travaille dans une banque et il est pour le vélo.",
+                Language::French,
+            ),
+            (
+                "El usuario vive en Madrid con su familia y trabaja para una empresa de software, pero quiere viajar.",
+                Language::Spanish,
+            ),
+            (
+                "Gebruiker woont in Utrecht en werkt bij een bank. Hij is niet getrouwd en heeft een kat van drie jaar.",
+                Language::Dutch,
+            ),
+            (
+                "Пользователь живёт в Москве и работает программистом в небольшой компании.",
+                Language::Russian,
+            ),
+            (
+                "ユーザーは東京に住んでいて、ソフトウェア会社で働いています。猫を飼っています。",
+                Language::Other,
+            ),
+        ];
+        for (text, expected) in cases {
+            assert_eq!(Language::detect(text), expected, "{text}");
+        }
+    }
+
+    #[test]
+    fn test_unclear_text_counts_as_english() {
+        assert_eq!(Language::detect(""), Language::English);
+        assert_eq!(Language::detect("Berlin"), Language::English);
+        assert_eq!(
+            Language::detect("fn main() { let x = vec![1, 2, 3]; println!(\"{:?}\", x); }"),
+            Language::English
+        );
+    }
+
+    #[test]
+    fn test_fts_config_round_trip() {
+        for language in Language::ALL {
+            assert_eq!(Language::from_fts_config(language.fts_config()), language);
+        }
+        assert_eq!(Language::from_fts_config("klingon"), Language::Other);
+        assert_eq!(
+            non_english_share(&[(Language::English, 1), (Language::German, 3)]),
+            0.75
+        );
+        assert_eq!(non_english_share(&[]), 0.0);
+    }
+}
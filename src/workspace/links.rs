@@ -0,0 +1,314 @@
+//! Wiki-style links between workspace documents.
+//!
+//! Documents can link to each other the way Obsidian notes do:
+//!
+//! - `[[projects/alpha]]` - a document path, `.md` optional
+//! - `[[alpha]]` - just a name, resolved to the document with that name
+//!   (the one with the shortest path when several match)
+//! - `[[alpha#Goals]]` - a heading inside the document
+//! - `[[alpha|the Alpha project]]` - display text
+//!
+//! Links inside fenced code blocks are ignored. Each document's link targets
+//! are stored when it is indexed, which is what lets
+//! [`Workspace::backlinks`] find the documents pointing at a path. Links are
+//! resolved within the namespace of the document that contains them.
+
+use std::collections::BTreeSet;
+
+use serde::Serialize;
+
+use crate::error::WorkspaceError;
+use crate::workspace::{Workspace, normalize_path, trash};
+
+/// A `[[...]]` link as written in a document.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct WikiLink {
+    /// The linked path or name.
+    pub target: String,
+    /// Heading after `#`, if any.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub heading: Option<String>,
+    /// Display text after `|`, if any.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub alias: Option<String>,
+}
+
+/// A link together with the document it points at.
+#[derive(Debug, Clone, Serialize)]
+pub struct ResolvedLink {
+    #[serde(flatten)]
+    pub link: WikiLink,
+    /// Path of the linked document, or `None` if nothing matches yet.
+    pub path: Option<String>,
+}
+
+/// A document linking to another one.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Backlink {
+    /// Path of the linking document.
+    pub path: String,
+    /// The link target as stored (see [`link_key`]).
+    pub target: String,
+}
+
+/// Parse the `[[...]]` links of `content`, in order of appearance.
+pub fn parse_links(content: &str) -> Vec<WikiLink> {
+    let mut links = Vec::new();
+    let mut in_fence = false;
+    for line in content.lines() {
+        if line.trim_start().starts_with("```") {
+            in_fence = !in_fence;
+            continue;
+        }
+        if in_fence {
+            continue;
+        }
+        let mut rest = line;
+        while let Some(start) = rest.find("[[") {
+            let after = &rest[start + 2..];
+            let Some(end) = after.find("]]") else {
+                break;
+            };
+            if let Some(link) = parse_link(&after[..end]) {
+                links.push(link);
+            }
+            rest = &after[end + 2..];
+        }
+    }
+    links
+}
+
+fn parse_link(inner: &str) -> Option<WikiLink> {
+    let (target, alias) = match inner.split_once('|') {
+        Some((target, alias)) => (target, Some(alias.trim().to_string())),
+        None => (inner, None),
+    };
+    let (target, heading) = match target.split_once('#') {
+        Some((target, heading)) => (target, Some(heading.trim().to_string())),
+        None => (target, None),
+    };
+    let target = target.trim();
+    if target.is_empty() || target.contains('[') {
+        return None;
+    }
+    Some(WikiLink {
+        target: target.to_string(),
+        heading: heading.filter(|h| !h.is_empty()),
+        alias: alias.filter(|a| !a.is_empty()),
+    })
+}
+
+/// Normalized form of a link target or document path used for matching:
+/// the normalized path without a trailing `.md`.
+pub fn link_key(target: &str) -> String {
+    let path = normalize_path(target);
+    match path.strip_suffix(".md") {
+        Some(stem) if !stem.is_empty() => stem.to_string(),
+        _ => path,
+    }
+}
+
+/// Distinct link keys of `content`, as stored in the backlink index.
+pub fn link_keys(content: &str) -> Vec<String> {
+    parse_links(content)
+        .iter()
+        .map(|link| link_key(&link.target))
+        .filter(|key| !key.is_empty())
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .collect()
+}
+
+/// The path among `paths` that the link `target` points at.
+///
+/// An exact path match wins; otherwise the shortest path ending in
+/// `/<target>` (ties broken alphabetically).
+pub fn resolve_link<'a>(target: &str, paths: &'a [String]) -> Option<&'a str> {
+    let key = link_key(target);
+    if key.is_empty() {
+        return None;
+    }
+    let suffix = format!("/{key}");
+    let mut best: Option<&str> = None;
+    for path in paths {
+        let path_key = link_key(path);
+        if path_key == key {
+            return Some(path);
+        }
+        if path_key.ends_with(&suffix)
+            && best.is_none_or(|b| (path.len(), path.as_str()) < (b.len(), b))
+        {
+            best = Some(path);
+        }
+    }
+    best
+}
+
+impl Workspace {
+    /// The links in the document at `path`, each resolved to the document it
+    /// points at.
+    pub async fn links(&self, path: &str) -> Result<Vec<ResolvedLink>, WorkspaceError> {
+        let path = normalize_path(path);
+        let doc = self.read(&path).await?;
+        let target = self.resolve(&path);
+        let mut paths = self
+            .storage
+            .list_all_paths(&self.user_id, target.agent_id)
+            .await?;
+        paths.retain(|p| !trash::is_trashed(p));
+
+        Ok(parse_links(&doc.content)
+            .into_iter()
+            .map(|link| {
+                let resolved = resolve_link(&link.target, &paths).map(|p| match target.mount {
+                    Some(mount) => mount.join(p),
+                    None => p.to_string(),
+                });
+                ResolvedLink {
+                    link,
+                    path: resolved,
+                }
+            })
+            .collect())
+    }
+
+    /// Documents linking to `path`, sorted by path.
+    ///
+    /// Only links that resolve to `path` count: `[[README]]` is a backlink of
+    /// `projects/README.md` only if no shorter path also ends in `README`.
+    pub async fn backlinks(&self, path: &str) -> Result<Vec<Backlink>, WorkspaceError> {
+        let path = normalize_path(path);
+        let target = self.resolve(&path);
+        let candidates = self
+            .storage
+            .find_backlinks(&self.user_id, target.agent_id, &link_key(&target.path))
+            .await?;
+        if candidates.is_empty() {
+            return Ok(candidates);
+        }
+
+        let mut paths = self
+            .storage
+            .list_all_paths(&self.user_id, target.agent_id)
+            .await?;
+        paths.retain(|p| !trash::is_trashed(p));
+        // Links to a document that doesn't exist yet still count.
+        if !paths.contains(&target.path) {
+            paths.push(target.path.clone());
+        }
+        let mut backlinks: Vec<Backlink> = candidates
+            .into_iter()
+            .filter(|b| b.path != target.path)
+            .filter(|b| resolve_link(&b.target, &paths) == Some(target.path.as_str()))
+            .map(|b| Backlink {
+                path: match target.mount {
+                    Some(mount) => mount.join(&b.path),
+                    None => b.path,
+                },
+                target: b.target,
+            })
+            .collect();
+        backlinks.sort_by(|a, b| a.path.cmp(&b.path));
+        backlinks.dedup_by(|a, b| a.path == b.path);
+        Ok(backlinks)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_links() {
+        let content = "See [[projects/alpha.md#Goals|Alpha goals]] and [[bob]].\n\
+                       ```\n[[not a link]]\n```\n[[ ]] [[unclosed";
+        let links = parse_links(content);
+        assert_eq!(
+            links,
+            vec![
+                WikiLink {
+                    target: "projects/alpha.md".to_string(),
+                    heading: Some("Goals".to_string()),
+                    alias: Some("Alpha goals".to_string()),
+                },
+                WikiLink {
+                    target: "bob".to_string(),
+                    heading: None,
+                    alias: None,
+                },
+            ]
+        );
+        assert_eq!(
+            link_keys("[[Bob]] [[bob.md]] [[/people/ann]]"),
+            vec!["Bob", "bob", "people/ann"]
+        );
+    }
+
+    #[test]
+    fn test_resolve_link() {
+        let paths: Vec<String> = [
+            "README.md",
+            "people/bob.md",
+            "work/people/bob.md",
+            "notes.txt",
+        ]
+        .iter()
+        .map(|p| p.to_string())
+        .collect();
+        assert_eq!(resolve_link("README", &paths), Some("README.md"));
+        assert_eq!(resolve_link("bob", &paths), Some("people/bob.md"));
+        assert_eq!(
+            resolve_link("work/people/bob", &paths),
+            Some("work/people/bob.md")
+        );
+        assert_eq!(resolve_link("notes.txt", &paths), Some("notes.txt"));
+        assert_eq!(resolve_link("ople/bob", &paths), None);
+        assert_eq!(resolve_link("carol", &paths), None);
+    }
+
+    #[cfg(feature = "libsql")]
+    #[tokio::test]
+    async fn test_backlinks_follow_writes() {
+        use crate::db::libsql_backend::test_db;
+
+        let (db, _dir) = test_db().await;
+        let workspace = Workspace::new_with_db("default", db);
+
+        workspace
+            .write("people/bob.md", "# Bob\n\nWorks on [[projects/alpha]].")
+            .await
+            .unwrap();
+        workspace
+            .write("daily/2026-10-15.md", "Lunch with [[bob|Bob]].")
+            .await
+            .unwrap();
+        workspace
+            .write("projects/alpha.md", "Owner: [[bob]]. Next: [[beta]]")
+            .await
+            .unwrap();
+
+        let backlinks = workspace.backlinks("people/bob.md").await.unwrap();
+        let sources: Vec<&str> = backlinks.iter().map(|b| b.path.as_str()).collect();
+        assert_eq!(sources, vec!["daily/2026-10-15.md", "projects/alpha.md"]);
+
+        let links = workspace.links("projects/alpha.md").await.unwrap();
+        assert_eq!(links[0].path.as_deref(), Some("people/bob.md"));
+        assert_eq!(links[1].path, None);
+
+        // Rewriting a document replaces its links; deleting it drops them.
+        workspace
+            .write("daily/2026-10-15.md", "Lunch alone.")
+            .await
+            .unwrap();
+        workspace.delete("projects/alpha.md").await.unwrap();
+        assert!(
+            workspace
+                .backlinks("people/bob.md")
+                .await
+                .unwrap()
+                .is_empty()
+        );
+        let backlinks = workspace.backlinks("projects/alpha.md").await.unwrap();
+        assert_eq!(backlinks[0].path, "people/bob.md");
+    }
+}
@@ -0,0 +1,396 @@
+//! Workspace content linting.
+//!
+//! Most workspace files are written by hand or by the agent and parsed
+//! leniently, so mistakes go unnoticed: a frontmatter block that never
+//! closes is read as body text, a `[[link]]` to a renamed note leads
+//! nowhere, an IDENTITY.md without a name gives the agent no name, a
+//! `schedule` in HEARTBEAT.md's frontmatter that isn't a cron expression
+//! (as routines use) never matches.
+//! [`Workspace::lint`] checks every document and reports what it finds as
+//! [`LintDiagnostic`]s, for `ironclaw doctor`, `ironclaw memory lint`, and the
+//! `memory_lint` tool.
+
+use std::collections::HashSet;
+use std::str::FromStr;
+
+use serde::Serialize;
+
+use crate::agent::context_monitor::estimate_text_tokens;
+use crate::error::WorkspaceError;
+use crate::workspace::frontmatter::{parse_frontmatter, split_frontmatter};
+use crate::workspace::links::{parse_links, resolve_link};
+use crate::workspace::{Profile, Workspace, paths, trash};
+
+/// Documents larger than this are slow to index and flood search results.
+pub const MAX_DOCUMENT_BYTES: usize = 256 * 1024;
+
+/// Files loaded into every system prompt, cut to the identity file budget.
+const PROMPT_FILES: &[&str] = &[paths::AGENTS, paths::SOUL, paths::USER, paths::IDENTITY];
+
+/// How serious a diagnostic is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LintSeverity {
+    /// Content is ignored or misread.
+    Error,
+    /// Content works, but probably not as intended.
+    Warning,
+}
+
+/// What a diagnostic is about.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LintKind {
+    Frontmatter,
+    BrokenLink,
+    MissingIdentity,
+    Oversized,
+    HeartbeatSchedule,
+}
+
+/// One problem found in a document.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct LintDiagnostic {
+    pub path: String,
+    /// 1-based line the problem is on, when it is on one.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub line: Option<usize>,
+    pub kind: LintKind,
+    pub severity: LintSeverity,
+    pub message: String,
+}
+
+impl LintDiagnostic {
+    fn new(
+        path: &str,
+        line: Option<usize>,
+        kind: LintKind,
+        severity: LintSeverity,
+        message: impl Into<String>,
+    ) -> Self {
+        Self {
+            path: path.to_string(),
+            line,
+            kind,
+            severity,
+            message: message.into(),
+        }
+    }
+}
+
+impl std::fmt::Display for LintDiagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let severity = match self.severity {
+            LintSeverity::Error => "error",
+            LintSeverity::Warning => "warning",
+        };
+        match self.line {
+            Some(line) => write!(f, "{}:{}: {}: {}", self.path, line, severity, self.message),
+            None => write!(f, "{}: {}: {}", self.path, severity, self.message),
+        }
+    }
+}
+
+impl Workspace {
+    /// Check every document of the workspace, errors first, then by path.
+    pub async fn lint(&self) -> Result<Vec<LintDiagnostic>, WorkspaceError> {
+        let mut docs = self
+            .storage
+            .list_documents(&self.user_id, self.agent_id)
+            .await?;
+        docs.retain(|d| !trash::is_trashed(&d.path));
+        let paths: Vec<String> = docs.iter().map(|d| d.path.clone()).collect();
+
+        let mut diagnostics = Vec::new();
+        for doc in &docs {
+            diagnostics.extend(lint_document(
+                &doc.path,
+                &doc.content,
+                &paths,
+                self.prompt_budget.identity_file,
+            ));
+        }
+
+        // During bootstrap the profile is still being filled in.
+        if paths.iter().any(|p| p == paths::IDENTITY) && !self.in_bootstrap().await {
+            let identity = self.profile(Profile::Identity).await?;
+            if identity.get("name").is_none_or(|n| n.trim().is_empty()) {
+                diagnostics.push(LintDiagnostic::new(
+                    paths::IDENTITY,
+                    None,
+                    LintKind::MissingIdentity,
+                    LintSeverity::Error,
+                    "no `- **Name:**` set; the agent has no name",
+                ));
+            }
+        }
+
+        diagnostics
+            .sort_by(|a, b| (a.severity, &a.path, a.line).cmp(&(b.severity, &b.path, b.line)));
+        Ok(diagnostics)
+    }
+}
+
+/// Diagnostics for one document. `paths` are all document paths, for
+/// resolving links; `prompt_budget` is the token budget of a prompt file.
+pub fn lint_document(
+    path: &str,
+    content: &str,
+    paths: &[String],
+    prompt_budget: usize,
+) -> Vec<LintDiagnostic> {
+    let mut diagnostics = lint_frontmatter(path, content);
+
+    for link in parse_links(content) {
+        if resolve_link(&link.target, paths).is_none() {
+            let line = content
+                .find(&format!("[[{}", link.target))
+                .map(|i| content[..i].matches('\n').count() + 1);
+            diagnostics.push(LintDiagnostic::new(
+                path,
+                line,
+                LintKind::BrokenLink,
+                LintSeverity::Warning,
+                format!("[[{}]] doesn't match any document", link.target),
+            ));
+        }
+    }
+
+    if content.len() > MAX_DOCUMENT_BYTES {
+        diagnostics.push(LintDiagnostic::new(
+            path,
+            None,
+            LintKind::Oversized,
+            LintSeverity::Warning,
+            format!(
+                "{} KiB (limit {} KiB); split it into smaller documents",
+                content.len() / 1024,
+                MAX_DOCUMENT_BYTES / 1024
+            ),
+        ));
+    }
+    if PROMPT_FILES.contains(&path) {
+        let tokens = estimate_text_tokens(content);
+        if tokens > prompt_budget {
+            diagnostics.push(LintDiagnostic::new(
+                path,
+                None,
+                LintKind::Oversized,
+                LintSeverity::Warning,
+                format!(
+                    "~{} tokens, over the {}-token prompt budget; the end is cut from the system prompt",
+                    tokens, prompt_budget
+                ),
+            ));
+        }
+    }
+
+    if path == paths::HEARTBEAT
+        && let Some(schedule) = parse_frontmatter(content)
+            .get("schedule")
+            .and_then(|v| v.as_str())
+            .filter(|s| !s.is_empty())
+        && let Err(e) = cron::Schedule::from_str(schedule)
+    {
+        diagnostics.push(LintDiagnostic::new(
+            path,
+            frontmatter_line(content, "schedule"),
+            LintKind::HeartbeatSchedule,
+            LintSeverity::Error,
+            format!(
+                "schedule \"{}\" is not a valid cron expression: {}",
+                schedule, e
+            ),
+        ));
+    }
+
+    diagnostics
+}
+
+/// Syntax problems in the frontmatter block: one that never closes, lines
+/// that aren't `key: value` or list items, and keys given twice.
+fn lint_frontmatter(path: &str, content: &str) -> Vec<LintDiagnostic> {
+    let opens = content.starts_with("---\n") || content.starts_with("---\r\n");
+    if !opens {
+        return Vec::new();
+    }
+    let Some((block, _)) = split_frontmatter(content) else {
+        return vec![LintDiagnostic::new(
+            path,
+            Some(1),
+            LintKind::Frontmatter,
+            LintSeverity::Error,
+            "frontmatter is never closed with `---`; the whole file is read as body",
+        )];
+    };
+
+    let mut diagnostics = Vec::new();
+    let mut keys = HashSet::new();
+    for (i, line) in block.lines().enumerate() {
+        // The opening fence is line 1.
+        let line_no = Some(i + 2);
+        let trimmed = line.trim();
+        if trimmed.is_empty()
+            || trimmed.starts_with('#')
+            || trimmed.starts_with("- ")
+            || trimmed == "-"
+            || line.starts_with([' ', '\t'])
+        {
+            continue;
+        }
+        let Some((key, value)) = line.split_once(':').filter(|(k, _)| !k.trim().is_empty()) else {
+            diagnostics.push(LintDiagnostic::new(
+                path,
+                line_no,
+                LintKind::Frontmatter,
+                LintSeverity::Error,
+                format!("`{}` is not a `key: value` line", trimmed),
+            ));
+            continue;
+        };
+        let key = key.trim();
+        if !keys.insert(key.to_string()) {
+            diagnostics.push(LintDiagnostic::new(
+                path,
+                line_no,
+                LintKind::Frontmatter,
+                LintSeverity::Warning,
+                format!("`{}` is set more than once; the last value wins", key),
+            ));
+        }
+        let value = value.trim();
+        if value.starts_with('[') != value.ends_with(']') {
+            diagnostics.push(LintDiagnostic::new(
+                path,
+                line_no,
+                LintKind::Frontmatter,
+                LintSeverity::Error,
+                format!("the list for `{}` is missing a bracket", key),
+            ));
+        }
+    }
+    diagnostics
+}
+
+/// Line of frontmatter `key` in `content`.
+fn frontmatter_line(content: &str, key: &str) -> Option<usize> {
+    content
+        .lines()
+        .position(|l| {
+            l.split_once(':')
+                .is_some_and(|(k, _)| !l.starts_with([' ', '\t']) && k.trim() == key)
+        })
+        .map(|i| i + 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kinds(diagnostics: &[LintDiagnostic]) -> Vec<(LintKind, Option<usize>)> {
+        diagnostics.iter().map(|d| (d.kind, d.line)).collect()
+    }
+
+    #[test]
+    fn test_frontmatter_syntax() {
+        let ok = "---\ntitle: \"Notes\"\ntags: [a, b]\nread_when:\n  - always\n- also\n---\nBody";
+        assert!(lint_frontmatter("a.md", ok).is_empty());
+        assert!(lint_frontmatter("a.md", "No frontmatter").is_empty());
+
+        let unclosed = lint_frontmatter("a.md", "---\ntitle: x\nBody without a fence");
+        assert_eq!(kinds(&unclosed), vec![(LintKind::Frontmatter, Some(1))]);
+
+        let bad = lint_frontmatter(
+            "a.md",
+            "---\ntitle: x\njust words\ntitle: y\ntags: [a, b\n---\n",
+        );
+        assert_eq!(
+            bad.iter().map(|d| (d.line, d.severity)).collect::<Vec<_>>(),
+            vec![
+                (Some(3), LintSeverity::Error),
+                (Some(4), LintSeverity::Warning),
+                (Some(5), LintSeverity::Error),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_links_size_and_schedule() {
+        let paths = vec!["projects/alpha.md".to_string(), "HEARTBEAT.md".to_string()];
+        let content = "See [[alpha]].\nAnd [[beta|the other one]].";
+        let found = lint_document("notes.md", content, &paths, 2_000);
+        assert_eq!(kinds(&found), vec![(LintKind::BrokenLink, Some(2))]);
+        assert!(found[0].message.contains("[[beta]]"));
+
+        let big = "word ".repeat(MAX_DOCUMENT_BYTES / 4);
+        assert_eq!(
+            kinds(&lint_document("dump.md", &big, &paths, 2_000)),
+            vec![(LintKind::Oversized, None)]
+        );
+        // Prompt files are held to the prompt budget.
+        let soul = "Be kind. ".repeat(100);
+        assert!(lint_document(paths::SOUL, &soul, &paths, 2_000).is_empty());
+        assert_eq!(
+            kinds(&lint_document(paths::SOUL, &soul, &paths, 50)),
+            vec![(LintKind::Oversized, None)]
+        );
+
+        let valid = "---\nschedule: \"0 0 9 * * MON-FRI\"\n---\n- Check email";
+        assert!(lint_document(paths::HEARTBEAT, valid, &paths, 2_000).is_empty());
+        let invalid = "---\ntitle: Checks\nschedule: every now and then\n---\n- Check email";
+        assert_eq!(
+            kinds(&lint_document(paths::HEARTBEAT, invalid, &paths, 2_000)),
+            vec![(LintKind::HeartbeatSchedule, Some(3))]
+        );
+        // Only HEARTBEAT.md carries a schedule.
+        assert!(lint_document("other.md", invalid, &paths, 2_000).is_empty());
+    }
+
+    #[cfg(feature = "libsql")]
+    #[tokio::test]
+    async fn test_workspace_lint() {
+        use crate::db::libsql_backend::test_db;
+
+        let (db, _dir) = test_db().await;
+        let ws = Workspace::new_with_db("default", db);
+
+        ws.write(
+            paths::IDENTITY,
+            "# IDENTITY.md\n\n- **Name:**\n- **Vibe:** calm\n",
+        )
+        .await
+        .unwrap();
+        ws.write("notes/todo.md", "---\ntitle: Todo\nFinish [[report]]\n")
+            .await
+            .unwrap();
+
+        let found = ws.lint().await.unwrap();
+        assert_eq!(
+            found
+                .iter()
+                .map(|d| (d.path.as_str(), d.kind))
+                .collect::<Vec<_>>(),
+            vec![
+                (paths::IDENTITY, LintKind::MissingIdentity),
+                ("notes/todo.md", LintKind::Frontmatter),
+                ("notes/todo.md", LintKind::BrokenLink),
+            ]
+        );
+        assert_eq!(
+            found[1].to_string(),
+            "notes/todo.md:1: error: frontmatter is never closed with `---`; the whole file is read as body"
+        );
+
+        ws.update_profile(
+            Profile::Identity,
+            &[("name".to_string(), "Ada".to_string())].into(),
+        )
+        .await
+        .unwrap();
+        ws.write("report.md", "Quarterly report").await.unwrap();
+        ws.write("notes/todo.md", "---\ntitle: Todo\n---\nFinish [[report]]")
+            .await
+            .unwrap();
+        assert!(ws.lint().await.unwrap().is_empty());
+    }
+}
@@ -0,0 +1,506 @@
+//! Mirroring the workspace to a directory on disk.
+//!
+//! [`WorkspaceMirror`] keeps a directory in step with the workspace's own
+//! namespace, in both directions, so files such as SOUL.md and MEMORY.md
+//! can be edited in any editor. Documents written through the workspace
+//! are written out as files; the directory is scanned every
+//! [`POLL_INTERVAL`], and files created or edited on disk are written to
+//! the workspace, and so re-indexed, once a scan finds them as the one
+//! before it did. Deleting a file moves its document to the trash, and
+//! deleting a document removes its file unless the file was edited since.
+//!
+//! When the mirror starts, and whenever it falls behind the event stream,
+//! the two sides are reconciled: of two copies that differ, the one
+//! modified more recently wins, and anything on only one side is copied to
+//! the other. Hidden files (editor swap files, `.git`) and backups ending
+//! in `~` are ignored, as are the trash and shared mounts.
+//!
+//! The mirror remembers a hash of what it last synced at each path, so its
+//! own writes, seen again by the next scan or the event stream, aren't
+//! copied again.
+
+use std::collections::{HashMap, HashSet};
+use std::path::{Component, Path, PathBuf};
+use std::pin::pin;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
+
+use chrono::{DateTime, Utc};
+use futures::TryStreamExt;
+use tokio::sync::broadcast;
+use tokio::time::MissedTickBehavior;
+
+use crate::error::WorkspaceError;
+use crate::workspace::{Workspace, WorkspaceEvent, trash};
+
+/// How often the directory is scanned. A change is synced once it has held
+/// for a whole interval, so an editor's save (often a write, a rename, and
+/// a delete) is seen whole.
+pub const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// A file's modification time and size.
+type FileStamp = (SystemTime, u64);
+
+/// What [`WorkspaceMirror::reconcile`] copied.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MirrorReport {
+    /// Documents written out to disk.
+    pub exported: usize,
+    /// Files written to the workspace.
+    pub imported: usize,
+}
+
+/// Two-way sync between a workspace and a directory.
+pub struct WorkspaceMirror {
+    workspace: Arc<Workspace>,
+    root: PathBuf,
+    /// Hash of the content last synced at each workspace path.
+    synced: Mutex<HashMap<String, blake3::Hash>>,
+}
+
+impl WorkspaceMirror {
+    /// Mirror `workspace` to `root`, creating the directory if needed.
+    pub fn new(workspace: Arc<Workspace>, root: impl AsRef<Path>) -> Result<Self, WorkspaceError> {
+        let root = root.as_ref();
+        let root = std::fs::create_dir_all(root)
+            .and_then(|()| std::fs::canonicalize(root))
+            .map_err(|e| mirror_error(root, e))?;
+        Ok(Self {
+            workspace,
+            root,
+            synced: Mutex::new(HashMap::new()),
+        })
+    }
+
+    /// The mirrored directory.
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Bring the directory and the workspace in step, copying whichever
+    /// side is newer or the only one to the other.
+    pub async fn reconcile(&self) -> Result<MirrorReport, WorkspaceError> {
+        let mut report = MirrorReport::default();
+        let mut files = HashSet::new();
+        self.walk(&self.root, &mut files)?;
+
+        let ws = &self.workspace;
+        let mut paths = pin!(ws.scope_paths(ws.agent_id));
+        while let Some(path) = paths.try_next().await? {
+            if !self.mirrors(&path) {
+                continue;
+            }
+            let doc = ws
+                .storage
+                .get_document_by_path(&ws.user_id, ws.agent_id, &path)
+                .await?;
+            let file = self.file_path(&path);
+            if !files.remove(&path) {
+                self.export(&path, &doc.content)?;
+                report.exported += 1;
+                continue;
+            }
+            let Some(content) = read_text(&file)? else {
+                continue;
+            };
+            if content == doc.content {
+                self.remember(&path, &content);
+                continue;
+            }
+            let modified = std::fs::metadata(&file)
+                .and_then(|m| m.modified())
+                .map_err(|e| mirror_error(&file, e))?;
+            if DateTime::<Utc>::from(modified) > doc.updated_at {
+                self.import(&path, &content).await?;
+                report.imported += 1;
+            } else {
+                self.export(&path, &doc.content)?;
+                report.exported += 1;
+            }
+        }
+
+        for path in files {
+            if let Some(content) = read_text(&self.file_path(&path))? {
+                self.import(&path, &content).await?;
+                report.imported += 1;
+            }
+        }
+        Ok(report)
+    }
+
+    /// Reconcile, then follow changes on both sides until the task is
+    /// aborted.
+    pub fn spawn(self: &Arc<Self>) -> tokio::task::JoinHandle<()> {
+        let mirror = Arc::clone(self);
+        tokio::spawn(async move {
+            // Subscribe first so nothing written while reconciling is missed.
+            let mut events = mirror.workspace.events().subscribe();
+            match mirror.reconcile().await {
+                Ok(report) => tracing::info!(
+                    "Mirrored workspace to {} ({} exported, {} imported)",
+                    mirror.root.display(),
+                    report.exported,
+                    report.imported
+                ),
+                Err(e) => tracing::warn!("Failed to reconcile workspace mirror: {}", e),
+            }
+
+            let mut scanned = mirror.scan().unwrap_or_default();
+            let mut settled = scanned.clone();
+            let mut poll = tokio::time::interval(POLL_INTERVAL);
+            poll.set_missed_tick_behavior(MissedTickBehavior::Delay);
+            loop {
+                tokio::select! {
+                    event = events.recv() => match event {
+                        Ok(event) => mirror.on_workspace_event(event).await,
+                        Err(broadcast::error::RecvError::Lagged(_)) => {
+                            if let Err(e) = mirror.reconcile().await {
+                                tracing::warn!("Failed to reconcile workspace mirror: {}", e);
+                            }
+                        }
+                        Err(broadcast::error::RecvError::Closed) => break,
+                    },
+                    _ = poll.tick() => {
+                        // Skip a scan that failed rather than take every file
+                        // as deleted.
+                        let Some(current) = mirror.scan() else {
+                            continue;
+                        };
+                        for file in settle(&mut settled, &scanned, &current) {
+                            mirror.on_file_changed(&file).await;
+                        }
+                        scanned = current;
+                    }
+                }
+            }
+        })
+    }
+
+    /// Write a document change out to disk.
+    async fn on_workspace_event(&self, event: WorkspaceEvent) {
+        let ws = &self.workspace;
+        let (user_id, agent_id, path) = match &event {
+            WorkspaceEvent::Changed {
+                user_id,
+                agent_id,
+                path,
+            }
+            | WorkspaceEvent::Deleted {
+                user_id,
+                agent_id,
+                path,
+            } => (user_id, *agent_id, path),
+            WorkspaceEvent::MetadataChanged { .. } => return,
+        };
+        if *user_id != ws.user_id || agent_id != ws.agent_id || !self.mirrors(path) {
+            return;
+        }
+        let result = match ws
+            .storage
+            .get_document_by_path(&ws.user_id, ws.agent_id, path)
+            .await
+        {
+            Ok(doc) if !self.is_synced(path, &doc.content) => self.export(path, &doc.content),
+            Ok(_) => Ok(()),
+            Err(WorkspaceError::DocumentNotFound { .. }) => self.remove(path),
+            Err(e) => Err(e),
+        };
+        if let Err(e) = result {
+            tracing::warn!("Failed to mirror {}: {}", path, e);
+        }
+    }
+
+    /// Write a file created, edited, or deleted on disk to the workspace.
+    async fn on_file_changed(&self, file: &Path) {
+        let Some(path) = self.workspace_path(file) else {
+            return;
+        };
+        if file.is_dir() {
+            return;
+        }
+        let result = match read_text(file) {
+            Ok(Some(content)) if !self.is_synced(&path, &content) => {
+                self.import(&path, &content).await
+            }
+            Ok(_) => Ok(()),
+            Err(_) if !file.exists() && self.forget(&path) => {
+                match self.workspace.delete(&path).await {
+                    Ok(()) | Err(WorkspaceError::DocumentNotFound { .. }) => Ok(()),
+                    Err(e) => Err(e),
+                }
+            }
+            Err(e) => Err(e),
+        };
+        if let Err(e) = result {
+            tracing::warn!("Failed to sync {} from disk: {}", path, e);
+        }
+    }
+
+    async fn import(&self, path: &str, content: &str) -> Result<(), WorkspaceError> {
+        // Remember first, so the change event the write publishes is
+        // recognized as this one.
+        self.remember(path, content);
+        self.workspace.write(path, content).await?;
+        Ok(())
+    }
+
+    fn export(&self, path: &str, content: &str) -> Result<(), WorkspaceError> {
+        let file = self.file_path(path);
+        self.remember(path, content);
+        if let Some(parent) = file.parent() {
+            std::fs::create_dir_all(parent).map_err(|e| mirror_error(parent, e))?;
+        }
+        std::fs::write(&file, content).map_err(|e| mirror_error(&file, e))
+    }
+
+    /// Remove the file of a deleted document, unless it was edited since
+    /// it was last synced.
+    fn remove(&self, path: &str) -> Result<(), WorkspaceError> {
+        let file = self.file_path(path);
+        let unchanged = read_text(&file)?.is_some_and(|content| self.is_synced(path, &content));
+        if unchanged {
+            std::fs::remove_file(&file).map_err(|e| mirror_error(&file, e))?;
+            self.forget(path);
+        }
+        Ok(())
+    }
+
+    fn remember(&self, path: &str, content: &str) {
+        self.lock()
+            .insert(path.to_string(), blake3::hash(content.as_bytes()));
+    }
+
+    /// Drop what was synced at `path`, returning whether there was any.
+    fn forget(&self, path: &str) -> bool {
+        self.lock().remove(path).is_some()
+    }
+
+    fn is_synced(&self, path: &str, content: &str) -> bool {
+        self.lock().get(path) == Some(&blake3::hash(content.as_bytes()))
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<String, blake3::Hash>> {
+        self.synced.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Whether the document at `path` is mirrored.
+    fn mirrors(&self, path: &str) -> bool {
+        !trash::is_trashed(path)
+            && path.split('/').all(|segment| !is_ignored(segment))
+            && self.workspace.resolve(path).mount.is_none()
+    }
+
+    fn file_path(&self, path: &str) -> PathBuf {
+        path.split('/')
+            .fold(self.root.clone(), |file, segment| file.join(segment))
+    }
+
+    /// The workspace path of a file in the mirror, if it is mirrored.
+    fn workspace_path(&self, file: &Path) -> Option<String> {
+        let relative = file.strip_prefix(&self.root).ok()?;
+        let segments = relative
+            .components()
+            .map(|c| match c {
+                Component::Normal(segment) => segment.to_str(),
+                _ => None,
+            })
+            .collect::<Option<Vec<&str>>>()?;
+        let path = segments.join("/");
+        (!path.is_empty() && self.mirrors(&path)).then_some(path)
+    }
+
+    /// Stamp every mirrored file, or None if the directory can't be read.
+    fn scan(&self) -> Option<HashMap<PathBuf, FileStamp>> {
+        let mut paths = HashSet::new();
+        if let Err(e) = self.walk(&self.root, &mut paths) {
+            tracing::warn!("Failed to scan workspace mirror: {}", e);
+            return None;
+        }
+        let stamps = paths
+            .into_iter()
+            .filter_map(|path| {
+                let file = self.file_path(&path);
+                let meta = std::fs::metadata(&file).ok()?;
+                Some((file, (meta.modified().ok()?, meta.len())))
+            })
+            .collect();
+        Some(stamps)
+    }
+
+    /// Collect the workspace paths of the mirrored files under `dir`.
+    fn walk(&self, dir: &Path, files: &mut HashSet<String>) -> Result<(), WorkspaceError> {
+        let entries = std::fs::read_dir(dir).map_err(|e| mirror_error(dir, e))?;
+        for entry in entries {
+            let entry = entry.map_err(|e| mirror_error(dir, e))?;
+            let file = entry.path();
+            let Some(path) = self.workspace_path(&file) else {
+                continue;
+            };
+            let kind = entry.file_type().map_err(|e| mirror_error(&file, e))?;
+            if kind.is_dir() {
+                self.walk(&file, files)?;
+            } else if kind.is_file() {
+                files.insert(path);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The files whose stamp is the same in the `previous` and `current` scans
+/// but not the one last `settled`, which is updated to match.
+fn settle(
+    settled: &mut HashMap<PathBuf, FileStamp>,
+    previous: &HashMap<PathBuf, FileStamp>,
+    current: &HashMap<PathBuf, FileStamp>,
+) -> Vec<PathBuf> {
+    let files: HashSet<&PathBuf> = settled
+        .keys()
+        .chain(previous.keys())
+        .chain(current.keys())
+        .collect();
+    let changed: Vec<PathBuf> = files
+        .into_iter()
+        .filter(|file| {
+            let stamp = current.get(*file);
+            stamp == previous.get(*file) && stamp != settled.get(*file)
+        })
+        .cloned()
+        .collect();
+    for file in &changed {
+        match current.get(file) {
+            Some(stamp) => settled.insert(file.clone(), *stamp),
+            None => settled.remove(file),
+        };
+    }
+    changed
+}
+
+/// Hidden files and editor backups.
+fn is_ignored(name: &str) -> bool {
+    name.starts_with('.') || name.ends_with('~')
+}
+
+/// The content of a UTF-8 file, `None` if it holds something else.
+fn read_text(file: &Path) -> Result<Option<String>, WorkspaceError> {
+    match std::fs::read(file) {
+        Ok(bytes) => Ok(String::from_utf8(bytes).ok()),
+        Err(e) => Err(mirror_error(file, e)),
+    }
+}
+
+fn mirror_error(file: &Path, e: std::io::Error) -> WorkspaceError {
+    WorkspaceError::ImportFailed {
+        path: file.display().to_string(),
+        reason: e.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ignored_files() {
+        assert!(is_ignored(".SOUL.md.swp"));
+        assert!(is_ignored("MEMORY.md~"));
+        assert!(is_ignored(".git"));
+        assert!(!is_ignored("notes.md"));
+    }
+
+    #[test]
+    fn test_settle_waits_for_a_stable_scan() {
+        let file = PathBuf::from("/mirror/SOUL.md");
+        let saved = (SystemTime::UNIX_EPOCH, 7);
+        let edited = (SystemTime::UNIX_EPOCH + Duration::from_secs(1), 9);
+        let scan = |stamp: Option<FileStamp>| -> HashMap<PathBuf, FileStamp> {
+            stamp.map(|s| (file.clone(), s)).into_iter().collect()
+        };
+        let mut settled = scan(Some(saved));
+
+        // Unchanged, then mid-save, then saved.
+        assert!(settle(&mut settled, &scan(Some(saved)), &scan(Some(saved))).is_empty());
+        assert!(settle(&mut settled, &scan(Some(saved)), &scan(None)).is_empty());
+        assert!(settle(&mut settled, &scan(None), &scan(Some(edited))).is_empty());
+        assert_eq!(
+            settle(&mut settled, &scan(Some(edited)), &scan(Some(edited))),
+            vec![file.clone()]
+        );
+        assert!(settle(&mut settled, &scan(Some(edited)), &scan(Some(edited))).is_empty());
+
+        // Deleted.
+        assert!(settle(&mut settled, &scan(Some(edited)), &scan(None)).is_empty());
+        assert_eq!(
+            settle(&mut settled, &scan(None), &scan(None)),
+            vec![file.clone()]
+        );
+        assert!(settled.is_empty());
+    }
+
+    #[cfg(feature = "libsql")]
+    #[tokio::test]
+    async fn test_mirror_syncs_both_ways() {
+        use crate::db::libsql_backend::test_db;
+
+        let (db, dir) = test_db().await;
+        let ws = Arc::new(Workspace::new_with_db("default", db));
+        ws.write("SOUL.md", "Be kind").await.unwrap();
+        ws.write("notes/plan.md", "Launch on Friday").await.unwrap();
+
+        let root = dir.path().join("mirror");
+        std::fs::create_dir_all(&root).unwrap();
+        std::fs::write(root.join("MEMORY.md"), "- Likes tea").unwrap();
+        std::fs::write(root.join(".MEMORY.md.swp"), "junk").unwrap();
+        let mirror = WorkspaceMirror::new(Arc::clone(&ws), &root).unwrap();
+
+        let report = mirror.reconcile().await.unwrap();
+        assert_eq!(
+            report,
+            MirrorReport {
+                exported: 2,
+                imported: 1
+            }
+        );
+        let root = mirror.root().to_path_buf();
+        assert_eq!(
+            std::fs::read_to_string(root.join("notes/plan.md")).unwrap(),
+            "Launch on Friday"
+        );
+        assert_eq!(ws.read("MEMORY.md").await.unwrap().content, "- Likes tea");
+        assert!(!ws.exists(".MEMORY.md.swp").await.unwrap());
+        assert_eq!(mirror.reconcile().await.unwrap(), MirrorReport::default());
+
+        // Edited in an editor.
+        std::fs::write(root.join("SOUL.md"), "Be kind and brief").unwrap();
+        mirror.on_file_changed(&root.join("SOUL.md")).await;
+        assert_eq!(
+            ws.read("SOUL.md").await.unwrap().content,
+            "Be kind and brief"
+        );
+
+        // Written by the agent.
+        let mut events = ws.events().subscribe();
+        ws.write("notes/plan.md", "Launch on Monday").await.unwrap();
+        while let Ok(event) = events.try_recv() {
+            mirror.on_workspace_event(event).await;
+        }
+        assert_eq!(
+            std::fs::read_to_string(root.join("notes/plan.md")).unwrap(),
+            "Launch on Monday"
+        );
+
+        // Deleted on disk, then by the agent.
+        std::fs::remove_file(root.join("MEMORY.md")).unwrap();
+        mirror.on_file_changed(&root.join("MEMORY.md")).await;
+        assert!(!ws.exists("MEMORY.md").await.unwrap());
+        ws.delete("notes/plan.md").await.unwrap();
+        mirror
+            .on_workspace_event(WorkspaceEvent::Deleted {
+                user_id: "default".to_string(),
+                agent_id: None,
+                path: "notes/plan.md".to_string(),
+            })
+            .await;
+        assert!(!root.join("notes/plan.md").exists());
+    }
+}
@@ -0,0 +1,290 @@
+//! Personas: alternative SOUL.md and IDENTITY.md per conversation context.
+//!
+//! A persona is a directory under `personas/` holding its own variants of
+//! the identity files:
+//!
+//! ```text
+//! personas/work/SOUL.md        terse, professional
+//! personas/work/IDENTITY.md
+//! personas/playful/SOUL.md     (no IDENTITY.md: the root one is used)
+//! ```
+//!
+//! Which persona is active is recorded in `personas/ACTIVE.md`, per channel
+//! with an optional default:
+//!
+//! ```text
+//! ---
+//! default: work
+//! telegram: playful
+//! ---
+//! ```
+//!
+//! [`Workspace::system_prompt_with`] loads the active persona's files in
+//! place of the root ones, so a switch takes effect on the next message.
+
+use std::collections::BTreeMap;
+
+use serde::Serialize;
+
+use crate::error::WorkspaceError;
+use crate::workspace::frontmatter::parse_frontmatter;
+use crate::workspace::{MemoryDocument, Workspace, paths};
+
+/// Document recording the active persona of each channel.
+pub const ACTIVE_PERSONAS: &str = "personas/ACTIVE.md";
+
+/// Key in [`ACTIVE_PERSONAS`] for channels without their own entry.
+const DEFAULT_KEY: &str = "default";
+
+/// Identity files a persona can replace.
+const PERSONA_FILES: &[&str] = &[paths::SOUL, paths::IDENTITY];
+
+/// Personas in the workspace and which one each channel uses.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct PersonaStatus {
+    /// Persona names.
+    pub personas: Vec<String>,
+    /// Persona used where no channel entry applies.
+    pub default: Option<String>,
+    /// Persona by channel.
+    pub channels: BTreeMap<String, String>,
+}
+
+impl PersonaStatus {
+    /// The persona used on `channel`, if any.
+    pub fn for_channel(&self, channel: Option<&str>) -> Option<&str> {
+        channel
+            .and_then(|c| self.channels.get(c))
+            .or(self.default.as_ref())
+            .map(String::as_str)
+    }
+}
+
+/// Persona and channel names are plain identifiers.
+fn is_name(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+/// Path of `persona`'s variant of the identity file at `path`.
+fn persona_path(persona: &str, path: &str) -> String {
+    format!("{}{}/{}", paths::PERSONAS_DIR, persona, path)
+}
+
+/// The `(key, persona)` entries of an [`ACTIVE_PERSONAS`] document.
+fn read_assignments(content: &str) -> BTreeMap<String, String> {
+    parse_frontmatter(content)
+        .into_iter()
+        .filter_map(|(key, value)| {
+            let persona = value.as_str()?.trim().to_string();
+            (is_name(&key) && is_name(&persona)).then_some((key, persona))
+        })
+        .collect()
+}
+
+fn render_assignments(assignments: &BTreeMap<String, String>) -> String {
+    let mut out = String::from("---\n");
+    for (key, persona) in assignments {
+        out.push_str(&format!("{}: {}\n", key, persona));
+    }
+    out.push_str(
+        "---\n\nActive persona per channel (`default` applies to the rest). \
+         Persona files live in personas/<name>/.\n",
+    );
+    out
+}
+
+impl Workspace {
+    /// Names of the personas under `personas/`.
+    pub async fn personas(&self) -> Result<Vec<String>, WorkspaceError> {
+        Ok(self
+            .list(paths::PERSONAS_DIR)
+            .await?
+            .into_iter()
+            .filter(|e| e.is_directory)
+            .map(|e| e.name().to_string())
+            .filter(|name| is_name(name))
+            .collect())
+    }
+
+    /// The personas and which one is active on each channel.
+    pub async fn persona_status(&self) -> Result<PersonaStatus, WorkspaceError> {
+        let mut channels = self.persona_assignments().await?;
+        let default = channels.remove(DEFAULT_KEY);
+        Ok(PersonaStatus {
+            personas: self.personas().await?,
+            default,
+            channels,
+        })
+    }
+
+    /// The persona active on `channel` (or the default one), if any.
+    pub async fn active_persona(&self, channel: Option<&str>) -> Option<String> {
+        let mut assignments = match self.persona_assignments().await {
+            Ok(assignments) => assignments,
+            Err(e) => {
+                tracing::debug!("Could not read active personas: {}", e);
+                return None;
+            }
+        };
+        channel
+            .and_then(|c| assignments.remove(c))
+            .or_else(|| assignments.remove(DEFAULT_KEY))
+    }
+
+    async fn persona_assignments(&self) -> Result<BTreeMap<String, String>, WorkspaceError> {
+        match self.read(ACTIVE_PERSONAS).await {
+            Ok(doc) => Ok(read_assignments(&doc.content)),
+            Err(WorkspaceError::DocumentNotFound { .. }) => Ok(BTreeMap::new()),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Make `persona` the active one on `channel`, or the default when no
+    /// channel is given. `None` goes back to the root identity files (or,
+    /// for a channel, to the default persona).
+    pub async fn set_persona(
+        &self,
+        channel: Option<&str>,
+        persona: Option<&str>,
+    ) -> Result<(), WorkspaceError> {
+        let key = channel.unwrap_or(DEFAULT_KEY);
+        if !is_name(key) {
+            return Err(WorkspaceError::InvalidPath {
+                path: ACTIVE_PERSONAS.to_string(),
+                reason: format!("invalid channel name '{}'", key),
+            });
+        }
+        if let Some(name) = persona
+            && !self.personas().await?.iter().any(|p| p == name)
+        {
+            return Err(WorkspaceError::PersonaNotFound {
+                name: name.to_string(),
+            });
+        }
+
+        let mut assignments = self.persona_assignments().await?;
+        match persona {
+            Some(name) => assignments.insert(key.to_string(), name.to_string()),
+            None => assignments.remove(key),
+        };
+        self.write(ACTIVE_PERSONAS, &render_assignments(&assignments))
+            .await?;
+        Ok(())
+    }
+
+    /// The identity file at `path` as `user_id` should see it: `persona`'s
+    /// variant when it has one, the root document otherwise.
+    pub(super) async fn identity_document(
+        &self,
+        user_id: &str,
+        persona: Option<&str>,
+        path: &str,
+    ) -> Result<MemoryDocument, WorkspaceError> {
+        if let Some(persona) = persona
+            && PERSONA_FILES.contains(&path)
+        {
+            let variant = persona_path(persona, path);
+            if self.check_access(user_id, &variant).is_ok()
+                && let Ok(doc) = self.read(&variant).await
+            {
+                return Ok(doc);
+            }
+        }
+        self.check_access(user_id, path)?;
+        self.read(path).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_assignments_roundtrip() {
+        let mut assignments = BTreeMap::new();
+        assignments.insert("default".to_string(), "work".to_string());
+        assignments.insert("telegram".to_string(), "playful".to_string());
+        let content = render_assignments(&assignments);
+        assert_eq!(read_assignments(&content), assignments);
+
+        // Values that aren't persona names are ignored.
+        let content = "---\nslack: work\nrepl: \"two words\"\n---\n";
+        assert_eq!(read_assignments(content).len(), 1);
+    }
+
+    #[test]
+    fn test_for_channel_falls_back_to_default() {
+        let mut status = PersonaStatus {
+            default: Some("work".to_string()),
+            ..Default::default()
+        };
+        status
+            .channels
+            .insert("telegram".to_string(), "playful".to_string());
+        assert_eq!(status.for_channel(Some("telegram")), Some("playful"));
+        assert_eq!(status.for_channel(Some("slack")), Some("work"));
+        assert_eq!(status.for_channel(None), Some("work"));
+
+        status.default = None;
+        assert_eq!(status.for_channel(Some("slack")), None);
+    }
+
+    #[cfg(feature = "libsql")]
+    #[tokio::test]
+    async fn test_persona_switching() {
+        use crate::db::libsql_backend::test_db;
+        use crate::workspace::PromptContext;
+
+        let (db, _dir) = test_db().await;
+        let ws = Workspace::new_with_db("default", db);
+
+        ws.write(paths::SOUL, "Root soul").await.unwrap();
+        ws.write(paths::IDENTITY, "Root identity").await.unwrap();
+        ws.write("personas/work/SOUL.md", "Be terse.")
+            .await
+            .unwrap();
+        ws.write("personas/playful/SOUL.md", "Have fun.")
+            .await
+            .unwrap();
+
+        assert_eq!(ws.personas().await.unwrap(), vec!["playful", "work"]);
+        assert!(matches!(
+            ws.set_persona(None, Some("pirate")).await,
+            Err(WorkspaceError::PersonaNotFound { .. })
+        ));
+
+        ws.set_persona(None, Some("work")).await.unwrap();
+        ws.set_persona(Some("telegram"), Some("playful"))
+            .await
+            .unwrap();
+        assert_eq!(
+            ws.active_persona(Some("slack")).await.as_deref(),
+            Some("work")
+        );
+
+        let slack = ws
+            .system_prompt_with("default", &PromptContext::chat("slack", "hi"))
+            .await
+            .unwrap();
+        assert!(slack.contains("Be terse."));
+        assert!(slack.contains("Root identity"));
+        assert!(!slack.contains("Root soul"));
+
+        let telegram = ws
+            .system_prompt_with("default", &PromptContext::chat("telegram", "hi"))
+            .await
+            .unwrap();
+        assert!(telegram.contains("Have fun."));
+
+        ws.set_persona(Some("telegram"), None).await.unwrap();
+        ws.set_persona(None, None).await.unwrap();
+        let status = ws.persona_status().await.unwrap();
+        assert_eq!(status.default, None);
+        assert!(status.channels.is_empty());
+        let prompt = ws.system_prompt_for("default").await.unwrap();
+        assert!(prompt.contains("Root soul"));
+    }
+}
@@ -0,0 +1,387 @@
+//! Pinned memories.
+//!
+//! Search only surfaces a memory when the conversation happens to match it.
+//! Some things must never be forgotten ("never email anyone before asking
+//! me"), so they can be pinned: a whole document, one section of a document,
+//! or a standalone fact. Pinned content is added to every system prompt
+//! under "Pinned Memories", within its own token budget
+//! ([`PromptBudget::pinned`](super::PromptBudget::pinned)).
+//!
+//! Document and section pins are resolved when the prompt is built, so they
+//! always show the current text. Facts come first, then sections, then whole
+//! documents; pins that don't fit the budget are left out and counted.
+//! Pins of private documents are left out of shared conversations.
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use uuid::Uuid;
+
+use crate::agent::context_monitor::estimate_text_tokens;
+use crate::error::WorkspaceError;
+use crate::workspace::section::section_content;
+use crate::workspace::{PromptContext, Workspace};
+
+/// What a pin keeps in the prompt.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum PinTarget {
+    /// A whole workspace document.
+    Document { path: String },
+    /// The section of a document under a heading.
+    Section { path: String, heading: String },
+    /// A standalone fact.
+    Fact { text: String },
+}
+
+impl PinTarget {
+    /// Storage tag for the target kind.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::Document { .. } => "document",
+            Self::Section { .. } => "section",
+            Self::Fact { .. } => "fact",
+        }
+    }
+
+    /// Rebuild a target from its stored columns.
+    pub fn from_columns(
+        kind: &str,
+        path: Option<String>,
+        heading: Option<String>,
+        content: Option<String>,
+    ) -> Option<Self> {
+        match kind {
+            "document" => Some(Self::Document { path: path? }),
+            "section" => Some(Self::Section {
+                path: path?,
+                heading: heading?,
+            }),
+            "fact" => Some(Self::Fact { text: content? }),
+            _ => None,
+        }
+    }
+
+    /// Stored columns: (path, heading, content).
+    pub fn columns(&self) -> (Option<&str>, Option<&str>, Option<&str>) {
+        match self {
+            Self::Document { path } => (Some(path), None, None),
+            Self::Section { path, heading } => (Some(path), Some(heading), None),
+            Self::Fact { text } => (None, None, Some(text)),
+        }
+    }
+}
+
+/// A pinned memory.
+#[derive(Debug, Clone, Serialize)]
+pub struct MemoryPin {
+    pub id: Uuid,
+    #[serde(flatten)]
+    pub target: PinTarget,
+    pub created_at: DateTime<Utc>,
+}
+
+impl MemoryPin {
+    pub fn new(target: PinTarget) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            target,
+            created_at: Utc::now(),
+        }
+    }
+}
+
+/// Render resolved pins as a prompt section within `budget` tokens.
+///
+/// `resolved` holds each pin with its current text (`None` when its
+/// document or section no longer exists).
+fn render_pins(resolved: &[(&MemoryPin, Option<String>)], budget: usize) -> Option<String> {
+    let mut facts = Vec::new();
+    let mut blocks = Vec::new();
+    let mut used = 0;
+    let mut omitted = 0;
+
+    let order = |pin: &MemoryPin| match pin.target {
+        PinTarget::Fact { .. } => 0,
+        PinTarget::Section { .. } => 1,
+        PinTarget::Document { .. } => 2,
+    };
+    let mut sorted: Vec<&(&MemoryPin, Option<String>)> = resolved.iter().collect();
+    sorted.sort_by_key(|(pin, _)| (order(pin), pin.created_at));
+
+    for (pin, text) in sorted {
+        let Some(text) = text.as_deref().map(str::trim).filter(|t| !t.is_empty()) else {
+            continue;
+        };
+        let rendered = match &pin.target {
+            PinTarget::Fact { .. } => format!("- {text}"),
+            PinTarget::Section { path, heading } => format!("### {path} › {heading}\n\n{text}"),
+            PinTarget::Document { path } => format!("### {path}\n\n{text}"),
+        };
+        let tokens = estimate_text_tokens(&rendered);
+        if used + tokens > budget {
+            omitted += 1;
+            continue;
+        }
+        used += tokens;
+        match pin.target {
+            PinTarget::Fact { .. } => facts.push(rendered),
+            _ => blocks.push(rendered),
+        }
+    }
+
+    if omitted > 0 {
+        tracing::warn!(
+            "{} pinned memories left out of the prompt (budget {} tokens)",
+            omitted,
+            budget
+        );
+    }
+    if facts.is_empty() && blocks.is_empty() {
+        return None;
+    }
+
+    let mut parts = vec!["## Pinned Memories\n\nAlways keep these in mind.".to_string()];
+    if !facts.is_empty() {
+        parts.push(facts.join("\n"));
+    }
+    parts.extend(blocks);
+    if omitted > 0 {
+        parts.push(format!(
+            "_{omitted} pinned item(s) left out to stay within the pinned-memory budget._"
+        ));
+    }
+    Some(parts.join("\n\n"))
+}
+
+impl Workspace {
+    /// Pin a document, section, or fact into every system prompt.
+    ///
+    /// Pinning something already pinned returns the existing pin.
+    pub async fn pin(&self, target: PinTarget) -> Result<MemoryPin, WorkspaceError> {
+        let target = match target {
+            PinTarget::Document { path } => {
+                let path = super::normalize_path(&path);
+                self.read(&path).await?;
+                PinTarget::Document { path }
+            }
+            PinTarget::Section { path, heading } => {
+                let path = super::normalize_path(&path);
+                let doc = self.read(&path).await?;
+                if section_content(&doc.content, &heading).is_none() {
+                    return Err(WorkspaceError::SectionNotFound { path, heading });
+                }
+                PinTarget::Section {
+                    path,
+                    heading: heading.trim().trim_start_matches('#').trim().to_string(),
+                }
+            }
+            PinTarget::Fact { text } => PinTarget::Fact {
+                text: text.trim().to_string(),
+            },
+        };
+
+        if let Some(existing) = self.pins().await?.into_iter().find(|p| p.target == target) {
+            return Ok(existing);
+        }
+        let pin = MemoryPin::new(target);
+        self.storage
+            .create_memory_pin(&self.user_id, self.agent_id, &pin)
+            .await?;
+        Ok(pin)
+    }
+
+    /// Remove a pin. Returns false if no such pin exists.
+    pub async fn unpin(&self, id: Uuid) -> Result<bool, WorkspaceError> {
+        self.storage
+            .delete_memory_pin(&self.user_id, self.agent_id, id)
+            .await
+    }
+
+    /// All pins, oldest first.
+    pub async fn pins(&self) -> Result<Vec<MemoryPin>, WorkspaceError> {
+        self.storage
+            .list_memory_pins(&self.user_id, self.agent_id)
+            .await
+    }
+
+    /// The "Pinned Memories" prompt section for `user_id` in `ctx`, if
+    /// anything is pinned that may go into it. Facts are standalone, so
+    /// they are kept in shared conversations; pinned private documents and
+    /// their sections are not.
+    pub(super) async fn pinned_context(
+        &self,
+        user_id: &str,
+        ctx: &PromptContext<'_>,
+    ) -> Option<String> {
+        let pins = match self.pins().await {
+            Ok(pins) => pins,
+            Err(e) => {
+                tracing::warn!("Failed to load pinned memories: {}", e);
+                return None;
+            }
+        };
+        if pins.is_empty() {
+            return None;
+        }
+
+        let mut resolved = Vec::with_capacity(pins.len());
+        for pin in &pins {
+            let text = match &pin.target {
+                PinTarget::Fact { text } => Some(text.clone()),
+                PinTarget::Document { path } | PinTarget::Section { path, .. }
+                    if self.check_access(user_id, path).is_err() =>
+                {
+                    None
+                }
+                PinTarget::Document { path } => self
+                    .read(path)
+                    .await
+                    .ok()
+                    .filter(|d| self.may_prompt(user_id, ctx, d))
+                    .map(|d| d.content),
+                PinTarget::Section { path, heading } => self
+                    .read(path)
+                    .await
+                    .ok()
+                    .filter(|d| self.may_prompt(user_id, ctx, d))
+                    .and_then(|d| section_content(&d.content, heading)),
+            };
+            resolved.push((pin, text));
+        }
+        render_pins(&resolved, self.prompt_budget.pinned)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pin(target: PinTarget, age_secs: i64) -> MemoryPin {
+        MemoryPin {
+            created_at: Utc::now() - chrono::Duration::seconds(age_secs),
+            ..MemoryPin::new(target)
+        }
+    }
+
+    #[test]
+    fn test_columns_round_trip() {
+        let target = PinTarget::Section {
+            path: "MEMORY.md".to_string(),
+            heading: "Rules".to_string(),
+        };
+        let (path, heading, content) = target.columns();
+        let back = PinTarget::from_columns(
+            target.kind(),
+            path.map(String::from),
+            heading.map(String::from),
+            content.map(String::from),
+        );
+        assert_eq!(back, Some(target));
+        assert_eq!(PinTarget::from_columns("fact", None, None, None), None);
+    }
+
+    #[test]
+    fn test_render_orders_facts_first_and_respects_budget() {
+        let doc = pin(
+            PinTarget::Document {
+                path: "rules.md".to_string(),
+            },
+            30,
+        );
+        let fact = pin(
+            PinTarget::Fact {
+                text: "Never email anyone before asking".to_string(),
+            },
+            10,
+        );
+        let gone = pin(
+            PinTarget::Document {
+                path: "deleted.md".to_string(),
+            },
+            5,
+        );
+        let long = "word ".repeat(500);
+        let resolved = vec![
+            (&doc, Some("Be brief.".to_string())),
+            (&fact, Some("Never email anyone before asking".to_string())),
+            (&gone, None),
+        ];
+
+        let out = render_pins(&resolved, 100).unwrap();
+        assert!(out.starts_with("## Pinned Memories"));
+        let fact_at = out.find("- Never email").unwrap();
+        let doc_at = out.find("### rules.md").unwrap();
+        assert!(fact_at < doc_at);
+        assert!(!out.contains("left out"));
+
+        let resolved = vec![
+            (&fact, Some("Never email anyone before asking".to_string())),
+            (&doc, Some(long)),
+        ];
+        let out = render_pins(&resolved, 100).unwrap();
+        assert!(out.contains("- Never email"));
+        assert!(!out.contains("### rules.md"));
+        assert!(out.contains("1 pinned item(s) left out"));
+
+        assert!(render_pins(&[(&gone, None)], 100).is_none());
+    }
+
+    #[cfg(feature = "libsql")]
+    #[tokio::test]
+    async fn test_pins_appear_in_system_prompt() {
+        use crate::db::libsql_backend::test_db;
+        use crate::workspace::paths;
+
+        let (db, _dir) = test_db().await;
+        let workspace = Workspace::new_with_db("default", db);
+
+        workspace
+            .write(
+                paths::MEMORY,
+                "## Rules\n\nNo meetings before 10am\n\n## Trivia\n\nLikes trains",
+            )
+            .await
+            .unwrap();
+
+        let fact = workspace
+            .pin(PinTarget::Fact {
+                text: " Never email anyone before asking ".to_string(),
+            })
+            .await
+            .unwrap();
+        workspace
+            .pin(PinTarget::Section {
+                path: paths::MEMORY.to_string(),
+                heading: "## rules".to_string(),
+            })
+            .await
+            .unwrap();
+        let again = workspace
+            .pin(PinTarget::Fact {
+                text: "Never email anyone before asking".to_string(),
+            })
+            .await
+            .unwrap();
+        assert_eq!(again.id, fact.id);
+        assert!(
+            workspace
+                .pin(PinTarget::Section {
+                    path: paths::MEMORY.to_string(),
+                    heading: "Missing".to_string(),
+                })
+                .await
+                .is_err()
+        );
+        assert_eq!(workspace.pins().await.unwrap().len(), 2);
+
+        let prompt = workspace.system_prompt().await.unwrap();
+        assert!(prompt.contains("- Never email anyone before asking"));
+        assert!(prompt.contains("### MEMORY.md › rules\n\nNo meetings before 10am"));
+        assert!(!prompt.contains("Likes trains"));
+
+        assert!(workspace.unpin(fact.id).await.unwrap());
+        assert!(!workspace.unpin(fact.id).await.unwrap());
+        let prompt = workspace.system_prompt().await.unwrap();
+        assert!(!prompt.contains("Never email"));
+    }
+}
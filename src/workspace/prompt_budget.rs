@@ -0,0 +1,271 @@
+//! Token budgets for system prompt assembly.
+//!
+//! Each section of the system prompt gets its own budget so one oversized
+//! file can't crowd out the rest of the context:
+//!
+//! - **Identity files** (AGENTS.md, SOUL.md, ...) keep their beginning, where
+//!   the most important instructions usually are, and end with a marker
+//!   pointing at the full file.
+//! - **Daily logs** keep their most recent entries. Older entries that don't
+//!   fit are replaced by a one-line digest of what they covered. Days before
+//!   yesterday are represented by the latest weekly rollup (see
+//!   [`super::rollup`]), cut like an identity file.
+//! - **Pinned memories** and **`read_when` documents** have their own
+//!   budgets (see [`super::pins`] and [`super::read_when`]).
+
+use crate::agent::context_monitor::estimate_text_tokens;
+
+/// Share of a daily log's budget reserved for the digest of omitted entries.
+const DIGEST_SHARE: usize = 5;
+
+/// Longest topic shown per omitted entry in a digest.
+const DIGEST_TOPIC_CHARS: usize = 60;
+
+/// Token budgets for each section of the system prompt.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PromptBudget {
+    /// Tokens per identity file.
+    pub identity_file: usize,
+    /// Tokens for pinned memories.
+    pub pinned: usize,
+    /// Tokens for documents loaded by their `read_when` rules.
+    pub conditional: usize,
+    /// Tokens for today's daily log.
+    pub today: usize,
+    /// Tokens for yesterday's daily log.
+    pub yesterday: usize,
+    /// Tokens for the latest weekly rollup of older logs.
+    pub rollup: usize,
+}
+
+impl Default for PromptBudget {
+    fn default() -> Self {
+        Self {
+            identity_file: 2_000,
+            pinned: 1_000,
+            conditional: 1_500,
+            today: 2_000,
+            yesterday: 1_000,
+            rollup: 800,
+        }
+    }
+}
+
+/// Keep the beginning of `content` within `budget` tokens.
+///
+/// Cuts at a line boundary where possible and appends a marker naming
+/// `path` so the agent knows to read the rest.
+pub fn fit_head(content: &str, budget: usize, path: &str) -> String {
+    let total = estimate_text_tokens(content);
+    if total <= budget {
+        return content.to_string();
+    }
+
+    let mut kept = String::new();
+    let mut used = 0;
+    for line in content.lines() {
+        let tokens = estimate_text_tokens(line);
+        if used + tokens > budget {
+            break;
+        }
+        used += tokens;
+        kept.push_str(line);
+        kept.push('\n');
+    }
+    if kept.trim().is_empty() {
+        kept = take_words(content, budget);
+        used = estimate_text_tokens(&kept);
+    }
+
+    format!(
+        "{}\n\n[... about {} more tokens truncated; use memory_read(\"{}\") for the full file]",
+        kept.trim_end(),
+        total.saturating_sub(used),
+        path
+    )
+}
+
+/// Keep the most recent entries of the daily log `content` within `budget`
+/// tokens, summarizing the entries that don't fit.
+pub fn fit_recent(content: &str, budget: usize, path: &str) -> String {
+    if estimate_text_tokens(content) <= budget {
+        return content.to_string();
+    }
+
+    let starts = entry_starts(content);
+    let entries: Vec<&str> = starts
+        .iter()
+        .enumerate()
+        .map(|(i, &start)| {
+            let end = starts.get(i + 1).copied().unwrap_or(content.len());
+            content[start..end].trim_end()
+        })
+        .collect();
+
+    let reserve = budget / DIGEST_SHARE;
+    let mut used = 0;
+    let mut first_kept = entries.len();
+    for (i, entry) in entries.iter().enumerate().rev() {
+        let tokens = estimate_text_tokens(entry);
+        if used + tokens > budget - reserve {
+            break;
+        }
+        used += tokens;
+        first_kept = i;
+    }
+
+    let recent = if first_kept == entries.len() {
+        // Even the latest entry is too long on its own.
+        let last = entries.last().copied().unwrap_or(content);
+        first_kept = entries.len().saturating_sub(1);
+        fit_head(last, budget - reserve, path)
+    } else {
+        content[starts[first_kept]..].trim_end().to_string()
+    };
+
+    let digest = digest(&entries[..first_kept], reserve, path);
+    format!("{digest}\n\n{recent}")
+}
+
+/// One line describing omitted entries, newest topics first.
+fn digest(omitted: &[&str], budget: usize, path: &str) -> String {
+    let mut line = format!(
+        "[{} earlier entries omitted; use memory_read(\"{}\") for the full log",
+        omitted.len(),
+        path
+    );
+    let mut topics = Vec::new();
+    let mut used = estimate_text_tokens(&line);
+    for entry in omitted.iter().rev() {
+        let topic = entry_topic(entry);
+        let tokens = estimate_text_tokens(&topic) + 1;
+        if topic.is_empty() || used + tokens > budget {
+            continue;
+        }
+        used += tokens;
+        topics.push(topic);
+    }
+    if !topics.is_empty() {
+        line.push_str(". Covered: ");
+        line.push_str(&topics.join("; "));
+    }
+    line.push(']');
+    line
+}
+
+/// Short description of an entry: its first line without timestamp or
+/// heading markers.
+fn entry_topic(entry: &str) -> String {
+    let first = entry.lines().next().unwrap_or("").trim();
+    let first = match first.strip_prefix('[').and_then(|r| r.split_once(']')) {
+        Some((_, rest)) => rest,
+        None => first,
+    };
+    let first = first.trim_start_matches('#').trim();
+    let first = first.strip_prefix("Episode:").unwrap_or(first).trim();
+    match first.char_indices().nth(DIGEST_TOPIC_CHARS) {
+        Some((cut, _)) => format!("{}…", first[..cut].trim_end()),
+        None => first.to_string(),
+    }
+}
+
+/// Byte offsets where daily log entries begin.
+///
+/// An entry starts at a `[HH:MM:SS]` timestamp, a heading, or the first
+/// line after a blank line; other lines continue the previous entry.
+fn entry_starts(content: &str) -> Vec<usize> {
+    let mut starts = Vec::new();
+    let mut offset = 0;
+    let mut after_blank = true;
+    for line in content.split_inclusive('\n') {
+        let trimmed = line.trim();
+        if trimmed.is_empty() {
+            after_blank = true;
+        } else {
+            let timestamped =
+                trimmed.starts_with('[') && trimmed[1..].starts_with(|c: char| c.is_ascii_digit());
+            if starts.is_empty() || after_blank || timestamped || trimmed.starts_with('#') {
+                starts.push(offset);
+            }
+            after_blank = false;
+        }
+        offset += line.len();
+    }
+    if starts.is_empty() {
+        starts.push(0);
+    }
+    starts
+}
+
+/// The first words of `text` fitting in `budget` tokens.
+fn take_words(text: &str, budget: usize) -> String {
+    let mut out = String::new();
+    for word in text.split_whitespace() {
+        let candidate = if out.is_empty() {
+            word.to_string()
+        } else {
+            format!("{out} {word}")
+        };
+        if estimate_text_tokens(&candidate) > budget {
+            break;
+        }
+        out = candidate;
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fit_head_keeps_beginning() {
+        let content = (1..=100)
+            .map(|i| format!("Rule {i}: be helpful"))
+            .collect::<Vec<_>>()
+            .join("\n");
+        assert_eq!(fit_head(&content, 10_000, "AGENTS.md"), content);
+
+        let out = fit_head(&content, 50, "AGENTS.md");
+        assert!(out.starts_with("Rule 1: be helpful\n"));
+        assert!(!out.contains("Rule 100"));
+        assert!(out.ends_with("use memory_read(\"AGENTS.md\") for the full file]"));
+
+        let wall = "word ".repeat(200);
+        let out = fit_head(&wall, 13, "SOUL.md");
+        assert!(out.starts_with("word word"));
+        assert!(out.contains("truncated"));
+    }
+
+    #[test]
+    fn test_fit_recent_keeps_latest_entries_and_digests_the_rest() {
+        let mut log = String::new();
+        for hour in 8..20 {
+            log.push_str(&format!(
+                "[{hour:02}:00:00] Discussed topic number {hour} at considerable length with many words\n"
+            ));
+        }
+        log.push_str("\n## Episode: Quarterly planning (19:30 UTC)\n- Outcomes: agreed roadmap\n");
+
+        let out = fit_recent(&log, 150, "daily/2026-10-15.md");
+        assert!(out.starts_with("[5 earlier entries omitted"));
+        assert!(out.contains("daily/2026-10-15.md"));
+        assert!(out.contains("## Episode: Quarterly planning"));
+        assert!(out.contains("[13:00:00]"));
+        assert!(!out.contains("[12:00:00]"));
+        // The digest names the newest omitted topics first.
+        assert!(out.contains("Covered: Discussed topic number 12"));
+        assert!(estimate_text_tokens(&out) <= 150);
+
+        assert_eq!(fit_recent(&log, 10_000, "daily/x.md"), log);
+    }
+
+    #[test]
+    fn test_entry_starts() {
+        let log = "[09:00:00] one\ncontinued\n[10:00:00] two\n\n## Episode: x\n- When: y\n";
+        let starts = entry_starts(log);
+        assert_eq!(starts.len(), 3);
+        assert!(log[starts[1]..].starts_with("[10:00:00]"));
+        assert!(log[starts[2]..].starts_with("## Episode"));
+    }
+}
@@ -0,0 +1,189 @@
+//! Where a search result came from.
+//!
+//! Chunks are stored without positions, so provenance is worked out from
+//! the document when results are returned: the chunk is found in the
+//! current content by its leading words, which gives its line range and the
+//! heading of the section it starts in. [`Provenance::citation`] renders it
+//! for people ("MEMORY.md › Finances, updated Jan 3") and [`citation_ref`]
+//! gives the short marker the agent cites it by (see
+//! [`crate::agent::citations`]).
+
+use chrono::{DateTime, Datelike, NaiveDate, Utc};
+use serde::Serialize;
+use uuid::Uuid;
+
+use crate::workspace::MemoryDocument;
+use crate::workspace::section::parse_heading;
+
+/// Leading words of a chunk matched against the document to locate it.
+const PROBE_WORDS: usize = 12;
+
+/// Where a chunk of a document sits.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Provenance {
+    pub path: String,
+    /// Title of the section the chunk starts in.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub heading: Option<String>,
+    /// First and last line of the chunk (1-based), if it could be found in
+    /// the current content.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub lines: Option<(usize, usize)>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl Provenance {
+    /// Locate `chunk` in `doc`.
+    pub fn of(doc: &MemoryDocument, chunk: &str) -> Self {
+        let span = locate(&doc.content, chunk);
+        let lines =
+            span.map(|(start, end)| (line_of(&doc.content, start), line_of(&doc.content, end)));
+        Self {
+            path: doc.path.clone(),
+            heading: lines.and_then(|(first, _)| heading_at(&doc.content, first)),
+            lines,
+            updated_at: doc.updated_at,
+        }
+    }
+
+    /// "MEMORY.md › Finances, updated Jan 3" (with the year if it isn't
+    /// the year of `today`).
+    pub fn citation(&self, today: NaiveDate) -> String {
+        let date = self.updated_at.date_naive();
+        let updated = if date.year() == today.year() {
+            date.format("%b %-d").to_string()
+        } else {
+            date.format("%b %-d, %Y").to_string()
+        };
+        match &self.heading {
+            Some(heading) => format!("{} › {}, updated {}", self.path, heading, updated),
+            None => format!("{}, updated {}", self.path, updated),
+        }
+    }
+}
+
+/// Short marker the agent cites the chunk with `chunk_id` by, e.g.
+/// `mem:3f2a9c`.
+pub fn citation_ref(chunk_id: Uuid) -> String {
+    format!("mem:{}", &chunk_id.simple().to_string()[..6])
+}
+
+/// Words of `text` with their byte offsets.
+fn word_spans(text: &str) -> Vec<(usize, &str)> {
+    let mut spans = Vec::new();
+    let mut start = None;
+    for (i, c) in text.char_indices() {
+        if c.is_whitespace() {
+            if let Some(s) = start.take() {
+                spans.push((s, &text[s..i]));
+            }
+        } else if start.is_none() {
+            start = Some(i);
+        }
+    }
+    if let Some(s) = start {
+        spans.push((s, &text[s..]));
+    }
+    spans
+}
+
+/// Byte offsets of the first and last word of `chunk` in `content`.
+///
+/// Chunks of long documents are their words rejoined with single spaces, so
+/// they are matched word by word rather than as substrings.
+fn locate(content: &str, chunk: &str) -> Option<(usize, usize)> {
+    let words = word_spans(content);
+    let chunk_words: Vec<&str> = chunk.split_whitespace().collect();
+    let probe = chunk_words.len().min(PROBE_WORDS);
+    if probe == 0 || words.len() < probe {
+        return None;
+    }
+    let first = (0..=words.len() - probe)
+        .find(|&i| (0..probe).all(|j| words[i + j].1 == chunk_words[j]))?;
+    let last = (first + chunk_words.len() - 1).min(words.len() - 1);
+    Some((words[first].0, words[last].0))
+}
+
+/// 1-based line number of byte `offset`.
+fn line_of(content: &str, offset: usize) -> usize {
+    content[..offset].matches('\n').count() + 1
+}
+
+/// Title of the last heading at or before 1-based `line`, outside fenced
+/// code blocks.
+fn heading_at(content: &str, line: usize) -> Option<String> {
+    let mut in_fence = false;
+    let mut heading = None;
+    for text in content.lines().take(line) {
+        if text.trim_start().starts_with("```") {
+            in_fence = !in_fence;
+            continue;
+        }
+        if !in_fence && let Some((_, title)) = parse_heading(text) {
+            heading = Some(title.to_string());
+        }
+    }
+    heading
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+
+    const MEMORY: &str = "# Memory\n\n\
+        ## Travel\n\n\
+        - Prefers aisle seats\n\n\
+        ## Finances\n\n\
+        ```\n# not a heading\n```\n\
+        - Rent is due on the 1st\n\
+        - Accountant is Dana\n";
+
+    fn doc(content: &str) -> MemoryDocument {
+        let mut doc = MemoryDocument::new("user1", None, "MEMORY.md");
+        doc.content = content.to_string();
+        doc.updated_at = Utc.with_ymd_and_hms(2025, 1, 3, 9, 0, 0).unwrap();
+        doc
+    }
+
+    #[test]
+    fn test_locates_chunk_lines_and_heading() {
+        let provenance = Provenance::of(
+            &doc(MEMORY),
+            "- Rent is due on the 1st\n- Accountant is Dana",
+        );
+        assert_eq!(provenance.lines, Some((12, 13)));
+        assert_eq!(provenance.heading.as_deref(), Some("Finances"));
+
+        // Rejoined words of a long document's chunk still match.
+        let provenance = Provenance::of(&doc(MEMORY), "## Travel - Prefers aisle seats");
+        assert_eq!(provenance.lines, Some((3, 5)));
+        assert_eq!(provenance.heading.as_deref(), Some("Travel"));
+
+        let provenance = Provenance::of(&doc(MEMORY), "Not in the document");
+        assert_eq!(provenance.lines, None);
+        assert_eq!(provenance.heading, None);
+    }
+
+    #[test]
+    fn test_citation() {
+        let provenance = Provenance::of(&doc(MEMORY), "- Accountant is Dana");
+        let today = NaiveDate::from_ymd_opt(2025, 2, 1).unwrap();
+        assert_eq!(
+            provenance.citation(today),
+            "MEMORY.md › Finances, updated Jan 3"
+        );
+        let next_year = NaiveDate::from_ymd_opt(2026, 2, 1).unwrap();
+        assert_eq!(
+            provenance.citation(next_year),
+            "MEMORY.md › Finances, updated Jan 3, 2025"
+        );
+    }
+
+    #[test]
+    fn test_citation_ref() {
+        let id = Uuid::parse_str("3f2a9c10-0000-4000-8000-000000000000").unwrap();
+        assert_eq!(citation_ref(id), "mem:3f2a9c");
+    }
+}
@@ -0,0 +1,239 @@
+//! Document size quotas and usage accounting.
+//!
+//! A runaway append loop can grow a single daily log without bound, and
+//! everything built from it (chunks, embeddings, the system prompt) grows
+//! with it. [`WorkspaceQuota`] caps the size of each document and of the
+//! workspace as a whole. A write that would take a document or the
+//! workspace over its limit fails with [`WorkspaceError::DocumentTooLarge`]
+//! or [`WorkspaceError::QuotaExceeded`], leaving the document as it was.
+//! Writes that
+//! don't grow a document always succeed, so one already over a limit can
+//! still be trimmed.
+//!
+//! [`Workspace::usage`] reports the bytes, documents, and chunks stored,
+//! in total and per directory.
+
+use std::collections::BTreeMap;
+
+use serde::Serialize;
+
+use crate::error::WorkspaceError;
+use crate::workspace::{DocumentSize, MemoryDocument, Workspace};
+
+/// Size limits for workspace content, in bytes (0 = unlimited).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WorkspaceQuota {
+    /// Largest a single document may grow.
+    pub max_document_bytes: u64,
+    /// Largest the content of all documents in one scope may grow. Shared
+    /// namespaces count separately.
+    pub max_workspace_bytes: u64,
+}
+
+impl Default for WorkspaceQuota {
+    fn default() -> Self {
+        Self {
+            max_document_bytes: 1024 * 1024,
+            max_workspace_bytes: 0,
+        }
+    }
+}
+
+impl WorkspaceQuota {
+    /// No limits.
+    pub fn unlimited() -> Self {
+        Self {
+            max_document_bytes: 0,
+            max_workspace_bytes: 0,
+        }
+    }
+}
+
+/// Storage used by a directory and everything below it.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct DirectoryUsage {
+    pub path: String,
+    pub bytes: u64,
+    pub documents: u64,
+    pub chunks: u64,
+}
+
+/// Storage used by a workspace.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct WorkspaceUsage {
+    pub bytes: u64,
+    pub documents: u64,
+    pub chunks: u64,
+    /// Every directory holding documents, with its subdirectories' totals
+    /// included, sorted by path.
+    pub directories: Vec<DirectoryUsage>,
+}
+
+impl WorkspaceUsage {
+    fn from_sizes(sizes: &[DocumentSize]) -> Self {
+        let mut usage = Self::default();
+        let mut directories: BTreeMap<&str, DirectoryUsage> = BTreeMap::new();
+        for size in sizes {
+            usage.bytes += size.bytes;
+            usage.documents += 1;
+            usage.chunks += size.chunks;
+            let mut path = size.path.as_str();
+            while let Some((parent, _)) = path.rsplit_once('/') {
+                let dir = directories.entry(parent).or_default();
+                dir.bytes += size.bytes;
+                dir.documents += 1;
+                dir.chunks += size.chunks;
+                path = parent;
+            }
+        }
+        usage.directories = directories
+            .into_iter()
+            .map(|(path, dir)| DirectoryUsage {
+                path: path.to_string(),
+                ..dir
+            })
+            .collect();
+        usage
+    }
+}
+
+impl Workspace {
+    /// Set the size limits for documents and the workspace.
+    pub fn with_quota(mut self, quota: WorkspaceQuota) -> Self {
+        self.quota = quota;
+        self
+    }
+
+    /// Storage used by this workspace, including mounted namespaces.
+    pub async fn usage(&self) -> Result<WorkspaceUsage, WorkspaceError> {
+        let mut sizes = self
+            .storage
+            .document_sizes(&self.user_id, self.agent_id)
+            .await?;
+        for mount in &self.mounts {
+            let mounted = self
+                .storage
+                .document_sizes(&self.user_id, Some(mount.scope()))
+                .await?;
+            sizes.extend(mounted.into_iter().map(|s| DocumentSize {
+                path: mount.join(&s.path),
+                ..s
+            }));
+        }
+        Ok(WorkspaceUsage::from_sizes(&sizes))
+    }
+
+    /// Check that replacing the content of `doc` with `content` keeps it
+    /// and its scope within the quota.
+    pub(super) async fn check_quota(
+        &self,
+        doc: &MemoryDocument,
+        content: &str,
+    ) -> Result<(), WorkspaceError> {
+        let (old, new) = (doc.content.len() as u64, content.len() as u64);
+        if new <= old {
+            return Ok(());
+        }
+        let limit = self.quota.max_document_bytes;
+        if limit > 0 && new > limit {
+            return Err(WorkspaceError::DocumentTooLarge {
+                path: self.mounted(doc.clone()).path,
+                bytes: new,
+                limit,
+            });
+        }
+        let limit = self.quota.max_workspace_bytes;
+        if limit > 0 {
+            let used: u64 = self
+                .storage
+                .document_sizes(&doc.user_id, doc.agent_id)
+                .await?
+                .iter()
+                .map(|s| s.bytes)
+                .sum();
+            let bytes = used.saturating_sub(old) + new;
+            if bytes > limit {
+                return Err(WorkspaceError::QuotaExceeded { bytes, limit });
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_usage_per_directory() {
+        let size = |path: &str, bytes, chunks| DocumentSize {
+            path: path.to_string(),
+            bytes,
+            chunks,
+        };
+        let usage = WorkspaceUsage::from_sizes(&[
+            size("MEMORY.md", 100, 1),
+            size("daily/2025-01-02.md", 40, 1),
+            size("daily/weekly/2025-W01.md", 60, 2),
+            size("projects/alpha/notes.md", 10, 1),
+        ]);
+        assert_eq!((usage.bytes, usage.documents, usage.chunks), (210, 4, 5));
+        let dirs: Vec<(&str, u64, u64, u64)> = usage
+            .directories
+            .iter()
+            .map(|d| (d.path.as_str(), d.bytes, d.documents, d.chunks))
+            .collect();
+        assert_eq!(
+            dirs,
+            vec![
+                ("daily", 100, 2, 3),
+                ("daily/weekly", 60, 1, 2),
+                ("projects", 10, 1, 1),
+                ("projects/alpha", 10, 1, 1),
+            ]
+        );
+    }
+
+    #[cfg(feature = "libsql")]
+    #[tokio::test]
+    async fn test_quota_enforced_on_write_and_append() {
+        use crate::db::libsql_backend::test_db;
+
+        let (db, _dir) = test_db().await;
+        let ws = Workspace::new_with_db("default", db).with_quota(WorkspaceQuota {
+            max_document_bytes: 20,
+            max_workspace_bytes: 30,
+        });
+
+        ws.append("daily/log.md", "0123456789").await.unwrap();
+        let err = ws.append("daily/log.md", "0123456789").await.unwrap_err();
+        assert!(matches!(
+            err,
+            WorkspaceError::DocumentTooLarge {
+                bytes: 21,
+                limit: 20,
+                ..
+            }
+        ));
+        assert_eq!(ws.read("daily/log.md").await.unwrap().content, "0123456789");
+
+        ws.write("notes.md", "0123456789abcde").await.unwrap();
+        ws.write("notes.md", "0123456789abcdefghij").await.unwrap();
+        let err = ws.append("daily/log.md", "01234").await.unwrap_err();
+        assert!(matches!(
+            err,
+            WorkspaceError::QuotaExceeded {
+                bytes: 36,
+                limit: 30
+            }
+        ));
+        // Shrinking is always allowed.
+        ws.write("notes.md", "short").await.unwrap();
+
+        let usage = ws.usage().await.unwrap();
+        assert_eq!((usage.bytes, usage.documents), (15, 2));
+        assert_eq!(usage.directories.len(), 1);
+        assert_eq!(usage.directories[0].path, "daily");
+        assert_eq!(usage.directories[0].bytes, 10);
+    }
+}
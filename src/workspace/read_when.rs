@@ -0,0 +1,342 @@
+//! Conditional context loading from `read_when` frontmatter.
+//!
+//! A document at the workspace root or under `context/` can declare when it
+//! belongs in the system prompt:
+//!
+//! ```text
+//! ---
+//! read_when:
+//!   - "channel: telegram"
+//!   - "session: heartbeat, routine"
+//!   - "keyword: invoice, billing; channel: email"
+//! ---
+//! ```
+//!
+//! Each list item is a rule; the document is loaded when any rule matches.
+//! A rule is one or more `;`-separated conditions that must all hold:
+//!
+//! - `session: <kinds>` - the prompt is for one of these [`SessionKind`]s
+//! - `channel: <names>` - the message arrived on one of these channels
+//! - `keyword: <words>` (or `keywords:`) - the user message mentions one of
+//!   these words or phrases
+//! - `always` - every prompt
+//!
+//! Comma-separated values mean "any of". Items that aren't rules (plain
+//! prose such as "Bootstrapping a workspace manually") describe the document
+//! for humans and never match.
+//!
+//! Matching documents are added to the system prompt without their
+//! frontmatter, within [`PromptBudget::conditional`](super::PromptBudget).
+//! In a shared conversation, private documents are left out whatever their
+//! rules say (see [`MemoryDocument::is_private`](super::MemoryDocument::is_private)).
+
+use crate::agent::context_monitor::estimate_text_tokens;
+use crate::context::SessionScope;
+use crate::workspace::frontmatter::{parse_frontmatter, strip_frontmatter};
+use crate::workspace::{Workspace, paths};
+
+/// Files already in every prompt, never loaded conditionally.
+const ALWAYS_LOADED: &[&str] = &[paths::AGENTS, paths::SOUL, paths::USER, paths::IDENTITY];
+
+/// Kind of session a system prompt is built for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SessionKind {
+    /// Interactive conversation with a user.
+    Chat,
+    /// Periodic heartbeat check.
+    Heartbeat,
+    /// Scheduled or event-triggered routine.
+    Routine,
+    /// Supervisor planning a multi-step task.
+    Supervisor,
+}
+
+impl SessionKind {
+    /// Name used in `session:` conditions.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Chat => "chat",
+            Self::Heartbeat => "heartbeat",
+            Self::Routine => "routine",
+            Self::Supervisor => "supervisor",
+        }
+    }
+}
+
+/// What a system prompt is being built for, matched against `read_when`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PromptContext<'a> {
+    pub session: Option<SessionKind>,
+    pub channel: Option<&'a str>,
+    /// The user message being answered.
+    pub message: Option<&'a str>,
+    /// Who can see the conversation. Shared ones get none of the user's
+    /// private memory.
+    pub scope: SessionScope,
+}
+
+impl<'a> PromptContext<'a> {
+    /// Context for a chat message received on `channel`.
+    pub fn chat(channel: &'a str, message: &'a str) -> Self {
+        Self {
+            session: Some(SessionKind::Chat),
+            channel: Some(channel),
+            message: Some(message),
+            scope: SessionScope::Main,
+        }
+    }
+
+    /// This context with `scope`.
+    pub fn with_scope(mut self, scope: SessionScope) -> Self {
+        self.scope = scope;
+        self
+    }
+
+    /// Whether other people can see the conversation.
+    pub fn is_shared(&self) -> bool {
+        self.scope == SessionScope::Shared
+    }
+
+    /// Context for a background session with no user message.
+    pub fn session(kind: SessionKind) -> Self {
+        Self {
+            session: Some(kind),
+            ..Self::default()
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Condition {
+    Always,
+    Session(Vec<String>),
+    Channel(Vec<String>),
+    Keyword(Vec<String>),
+}
+
+impl Condition {
+    fn parse(text: &str) -> Option<Self> {
+        let text = text.trim();
+        if text.eq_ignore_ascii_case("always") {
+            return Some(Self::Always);
+        }
+        let (key, values) = text.split_once(':')?;
+        let values: Vec<String> = values
+            .split(',')
+            .map(|v| v.trim().to_lowercase())
+            .filter(|v| !v.is_empty())
+            .collect();
+        if values.is_empty() {
+            return None;
+        }
+        match key.trim().to_lowercase().as_str() {
+            "session" => Some(Self::Session(values)),
+            "channel" => Some(Self::Channel(values)),
+            "keyword" | "keywords" => Some(Self::Keyword(
+                values.iter().map(|v| normalize_words(v)).collect(),
+            )),
+            _ => None,
+        }
+    }
+
+    fn matches(&self, ctx: &PromptContext<'_>, message: &str) -> bool {
+        match self {
+            Self::Always => true,
+            Self::Session(kinds) => ctx
+                .session
+                .is_some_and(|s| kinds.iter().any(|k| k == s.as_str())),
+            Self::Channel(names) => ctx
+                .channel
+                .is_some_and(|c| names.iter().any(|n| n.eq_ignore_ascii_case(c))),
+            Self::Keyword(words) => words
+                .iter()
+                .any(|w| !w.is_empty() && message.contains(&format!(" {w} "))),
+        }
+    }
+}
+
+/// Parsed `read_when` rules of one document.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ReadWhen {
+    rules: Vec<Vec<Condition>>,
+}
+
+impl ReadWhen {
+    /// Parse the `read_when` list from a document's frontmatter.
+    pub fn parse(content: &str) -> Self {
+        let rules = read_when_items(content)
+            .iter()
+            .filter_map(|item| {
+                item.split(';')
+                    .map(Condition::parse)
+                    .collect::<Option<Vec<_>>>()
+            })
+            .collect();
+        Self { rules }
+    }
+
+    /// Whether the document declares any evaluable rule.
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// Whether any rule matches `ctx`.
+    pub fn matches(&self, ctx: &PromptContext<'_>) -> bool {
+        let message = format!(" {} ", normalize_words(ctx.message.unwrap_or("")));
+        self.rules
+            .iter()
+            .any(|rule| rule.iter().all(|c| c.matches(ctx, &message)))
+    }
+}
+
+impl Workspace {
+    /// Prompt sections for documents whose `read_when` rules match `ctx`.
+    ///
+    /// Candidates are the files at the workspace root and directly under
+    /// `context/` that may go into a prompt for `user_id` in `ctx`.
+    pub(super) async fn conditional_context(
+        &self,
+        user_id: &str,
+        ctx: &PromptContext<'_>,
+    ) -> Vec<String> {
+        let mut candidates = Vec::new();
+        for dir in ["", paths::CONTEXT_DIR] {
+            match self.list(dir).await {
+                Ok(entries) => candidates.extend(entries.into_iter().filter(|e| {
+                    !e.is_directory
+                        && e.content_preview
+                            .as_deref()
+                            .is_some_and(|p| p.starts_with("---"))
+                })),
+                Err(e) => tracing::debug!("Could not list {:?} for read_when: {}", dir, e),
+            }
+        }
+
+        let mut parts = Vec::new();
+        let mut remaining = self.prompt_budget.conditional;
+        for entry in candidates {
+            if remaining == 0 {
+                break;
+            }
+            if ALWAYS_LOADED.contains(&entry.path.as_str())
+                || self.check_access(user_id, &entry.path).is_err()
+            {
+                continue;
+            }
+            let Ok(doc) = self.read(&entry.path).await else {
+                continue;
+            };
+            if !self.may_prompt(user_id, ctx, &doc) || !ReadWhen::parse(&doc.content).matches(ctx) {
+                continue;
+            }
+            let body = strip_frontmatter(&doc.content).trim();
+            if body.is_empty() {
+                continue;
+            }
+            let budget = remaining.min(self.prompt_budget.identity_file);
+            let body = self.fit_document(&doc, body, budget);
+            remaining = remaining.saturating_sub(estimate_text_tokens(&body));
+            parts.push(format!("## Context: {}\n\n{}", doc.path, body));
+        }
+        parts
+    }
+}
+
+/// Items of the `read_when` key: either a list or an inline value.
+fn read_when_items(content: &str) -> Vec<String> {
+    match parse_frontmatter(content).remove("read_when") {
+        Some(serde_json::Value::Array(items)) => items
+            .into_iter()
+            .filter_map(|i| i.as_str().map(String::from))
+            .collect(),
+        Some(serde_json::Value::String(item)) if !item.is_empty() => vec![item],
+        _ => Vec::new(),
+    }
+}
+
+/// Lowercase words separated by single spaces.
+fn normalize_words(text: &str) -> String {
+    text.to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DOC: &str = "---\ntitle: \"Billing\"\nread_when:\n  - Handling money questions\n  - \"keyword: invoice, late fee\"\n  - \"session: heartbeat; channel: email\"\n---\n\n# Billing\n\nNet 30.\n";
+
+    #[test]
+    fn test_prose_items_are_not_rules() {
+        let rules = ReadWhen::parse(DOC);
+        assert_eq!(rules.rules.len(), 2);
+
+        let seed = "---\nread_when:\n  - Bootstrapping a workspace manually\n---\n# SOUL.md";
+        assert!(ReadWhen::parse(seed).is_empty());
+        assert!(ReadWhen::parse("# No frontmatter").is_empty());
+    }
+
+    #[test]
+    fn test_keyword_rules_match_whole_words() {
+        let rules = ReadWhen::parse(DOC);
+        assert!(rules.matches(&PromptContext::chat("cli", "Where is the Invoice?")));
+        assert!(rules.matches(&PromptContext::chat("cli", "Is there a late-fee?")));
+        assert!(!rules.matches(&PromptContext::chat("cli", "invoices are great")));
+        assert!(!rules.matches(&PromptContext::chat("cli", "hello")));
+    }
+
+    #[test]
+    fn test_conditions_in_a_rule_must_all_hold() {
+        let rules = ReadWhen::parse(DOC);
+        let heartbeat = PromptContext::session(SessionKind::Heartbeat);
+        assert!(!rules.matches(&heartbeat));
+        assert!(rules.matches(&PromptContext {
+            channel: Some("email"),
+            ..heartbeat
+        }));
+    }
+
+    #[cfg(feature = "libsql")]
+    #[tokio::test]
+    async fn test_matching_documents_join_the_system_prompt() {
+        use crate::db::libsql_backend::test_db;
+
+        let (db, _dir) = test_db().await;
+        let workspace = Workspace::new_with_db("default", db);
+
+        workspace.write("context/billing.md", DOC).await.unwrap();
+        workspace
+            .write(
+                "TOOLS.md",
+                "---\nread_when:\n  - Bootstrapping a workspace manually\n---\nCamera names",
+            )
+            .await
+            .unwrap();
+
+        let prompt = workspace
+            .system_prompt_with("default", &PromptContext::chat("cli", "Send the invoice"))
+            .await
+            .unwrap();
+        assert!(prompt.contains("## Context: context/billing.md\n\n# Billing\n\nNet 30."));
+        assert!(!prompt.contains("read_when"));
+        assert!(!prompt.contains("Camera names"));
+
+        let prompt = workspace
+            .system_prompt_with("default", &PromptContext::chat("cli", "Hello"))
+            .await
+            .unwrap();
+        assert!(!prompt.contains("Net 30"));
+    }
+
+    #[test]
+    fn test_inline_value_and_strip() {
+        let doc = "---\nread_when: always\n---\nBody";
+        assert!(ReadWhen::parse(doc).matches(&PromptContext::default()));
+        assert_eq!(strip_frontmatter(doc), "Body");
+        assert_eq!(strip_frontmatter(DOC), "# Billing\n\nNet 30.\n");
+        assert_eq!(strip_frontmatter("Plain"), "Plain");
+    }
+}
@@ -0,0 +1,287 @@
+//! Self-maintaining directory indexes.
+//!
+//! With [`Workspace::with_auto_readme`], every directory gets a README.md
+//! listing its children and their summaries, so `list()` plus one read
+//! gives the agent a map of the directory. The listing lives between
+//! marker comments; anything written outside them is left alone, so a
+//! README can still carry hand-written notes.
+//!
+//! A child's summary is its `summary` (or `description`) frontmatter
+//! field, falling back to its first heading. A subdirectory is summarized
+//! by its own README.md.
+//!
+//! Indexes are refreshed when a document is created or deleted, or when
+//! its summary changes. A change refreshes every directory above the
+//! document, since a new directory shows up in its parent's listing too.
+
+use crate::error::WorkspaceError;
+use crate::workspace::Workspace;
+use crate::workspace::frontmatter::{parse_frontmatter, strip_frontmatter};
+
+/// File name of directory indexes.
+const README: &str = "README.md";
+
+const INDEX_START: &str =
+    "<!-- index: maintained automatically; edits between these markers are replaced -->";
+const INDEX_END: &str = "<!-- /index -->";
+
+/// Longest summary shown for a child, in characters.
+const MAX_SUMMARY_CHARS: usize = 160;
+
+impl Workspace {
+    /// Refresh the indexes above `path` after its content changed from
+    /// `before` (empty for a new document) to `after` (`None` once deleted).
+    pub(super) async fn refresh_readmes(&self, path: &str, before: &str, after: Option<&str>) {
+        if !self.auto_readme {
+            return;
+        }
+        let changed =
+            before.is_empty() || after.is_none_or(|after| summary(before) != summary(after));
+        if !changed {
+            return;
+        }
+
+        // A README's own directory lists it only implicitly; start above it.
+        let mut dir = parent(path);
+        if file_name(path) == README {
+            match dir {
+                Some(d) => dir = parent(d),
+                None => return,
+            }
+        }
+        while let Some(d) = dir {
+            if let Err(e) = self.refresh_readme(d).await {
+                tracing::warn!("Failed to refresh index of '{}': {}", d, e);
+            }
+            dir = parent(d);
+        }
+    }
+
+    /// Rewrite the index in `dir`/README.md (`dir` empty for the root).
+    async fn refresh_readme(&self, dir: &str) -> Result<(), WorkspaceError> {
+        let path = if dir.is_empty() {
+            README.to_string()
+        } else {
+            format!("{}/{}", dir, README)
+        };
+        let target = self.resolve_writable(&path)?;
+
+        let mut lines = Vec::new();
+        for entry in self.list(dir).await? {
+            let name = entry.name().to_string();
+            let (link, doc_path) = if entry.is_directory {
+                (
+                    format!("[{0}/]({0}/{1})", name, README),
+                    format!("{}/{}", entry.path, README),
+                )
+            } else if name == README {
+                continue;
+            } else {
+                (format!("[{0}]({0})", name), entry.path.clone())
+            };
+            let summary = match self.read(&doc_path).await {
+                Ok(doc) => summary(&doc.content).filter(|s| s.trim_end_matches('/') != name),
+                Err(WorkspaceError::DocumentNotFound { .. }) => None,
+                Err(e) => return Err(e),
+            };
+            lines.push(match summary {
+                Some(summary) => format!("- {} — {}", link, summary),
+                None => format!("- {}", link),
+            });
+        }
+
+        let existing = match self.read(&path).await {
+            Ok(doc) => Some(doc.content),
+            Err(WorkspaceError::DocumentNotFound { .. }) => None,
+            Err(e) => return Err(e),
+        };
+        if existing.is_none() && lines.is_empty() {
+            return Ok(());
+        }
+        let title = if dir.is_empty() {
+            "Workspace"
+        } else {
+            file_name(dir)
+        };
+        let content = with_index(existing.as_deref(), title, &lines);
+        if existing.as_deref() == Some(content.as_str()) {
+            return Ok(());
+        }
+
+        // Written directly rather than through write() so that it doesn't
+        // trigger another refresh.
+        let doc = self
+            .storage
+            .get_or_create_document_by_path(&self.user_id, target.agent_id, &target.path)
+            .await?;
+        self.save_content(&doc, &content).await?;
+        self.reindex_document(doc.id).await?;
+        self.audit_mutation("write", &path).await;
+        Ok(())
+    }
+}
+
+/// `existing` README content (or a new README titled `title`) with its
+/// index replaced by `lines`.
+fn with_index(existing: Option<&str>, title: &str, lines: &[String]) -> String {
+    let mut index = format!("{}\n## Contents\n\n", INDEX_START);
+    if lines.is_empty() {
+        index.push_str("_(empty)_\n");
+    }
+    for line in lines {
+        index.push_str(line);
+        index.push('\n');
+    }
+    index.push_str(INDEX_END);
+
+    let Some(existing) = existing else {
+        return format!("# {}\n\n{}\n", title, index);
+    };
+    if let Some(start) = existing.find(INDEX_START)
+        && let Some(end) = existing[start..].find(INDEX_END)
+    {
+        let end = start + end + INDEX_END.len();
+        return format!("{}{}{}", &existing[..start], index, &existing[end..]);
+    }
+    let body = existing.trim_end();
+    if body.is_empty() {
+        format!("{}\n", index)
+    } else {
+        format!("{}\n\n{}\n", body, index)
+    }
+}
+
+/// One-line summary of a document: its `summary` or `description`
+/// frontmatter field, or else its first heading.
+fn summary(content: &str) -> Option<String> {
+    let fields = parse_frontmatter(content);
+    let text = ["summary", "description"]
+        .iter()
+        .find_map(|key| {
+            fields
+                .get(*key)
+                .and_then(|v| v.as_str())
+                .map(str::to_string)
+        })
+        .or_else(|| {
+            strip_frontmatter(content)
+                .lines()
+                .find_map(|line| line.strip_prefix('#'))
+                .map(|heading| heading.trim_start_matches('#').trim().to_string())
+        })?;
+    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    if text.is_empty() {
+        return None;
+    }
+    if text.chars().count() <= MAX_SUMMARY_CHARS {
+        return Some(text);
+    }
+    let cut: String = text.chars().take(MAX_SUMMARY_CHARS - 1).collect();
+    Some(format!("{}…", cut.trim_end()))
+}
+
+/// The directory containing `path` (`""` for the root), or `None` for the
+/// root itself.
+fn parent(path: &str) -> Option<&str> {
+    if path.is_empty() {
+        return None;
+    }
+    Some(path.rsplit_once('/').map_or("", |(dir, _)| dir))
+}
+
+fn file_name(path: &str) -> &str {
+    path.rsplit('/').next().unwrap_or(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_index_is_replaced_between_markers() {
+        let lines = vec!["- [a.md](a.md) — Alpha".to_string()];
+        let created = with_index(None, "notes", &lines);
+        assert!(created.starts_with("# notes\n\n<!-- index:"));
+        assert!(created.contains("## Contents\n\n- [a.md](a.md) — Alpha\n<!-- /index -->\n"));
+
+        let edited = format!("# Notes\n\nHand-written.\n\n{}", &created[9..]);
+        let lines = vec!["- [b.md](b.md)".to_string()];
+        let updated = with_index(Some(&edited), "notes", &lines);
+        assert!(updated.starts_with("# Notes\n\nHand-written.\n\n<!-- index:"));
+        assert!(updated.contains("- [b.md](b.md)\n<!-- /index -->\n"));
+        assert!(!updated.contains("a.md"));
+    }
+
+    #[test]
+    fn test_summary_prefers_frontmatter() {
+        assert_eq!(
+            summary("---\nsummary: \"Agent identity\"\n---\n# IDENTITY.md\n").as_deref(),
+            Some("Agent identity")
+        );
+        assert_eq!(
+            summary("Intro\n\n## Plans for Q3\n").as_deref(),
+            Some("Plans for Q3")
+        );
+        assert_eq!(summary("no heading"), None);
+        assert_eq!(parent("a/b.md"), Some("a"));
+        assert_eq!(parent("b.md"), Some(""));
+        assert_eq!(parent(""), None);
+    }
+
+    #[cfg(feature = "libsql")]
+    #[tokio::test]
+    async fn test_readmes_follow_writes_and_deletes() {
+        use crate::db::libsql_backend::test_db;
+
+        let (db, _dir) = test_db().await;
+        let ws = Workspace::new_with_db("default", db).with_auto_readme(true);
+
+        ws.write(
+            "projects/alpha/plan.md",
+            "---\nsummary: Launch plan\n---\n# Plan\n",
+        )
+        .await
+        .unwrap();
+        let alpha = ws.read("projects/alpha/README.md").await.unwrap();
+        assert!(alpha.content.starts_with("# alpha\n"));
+        assert!(
+            alpha
+                .content
+                .contains("- [plan.md](plan.md) — Launch plan\n")
+        );
+        let projects = ws.read("projects/README.md").await.unwrap();
+        assert!(projects.content.contains("- [alpha/](alpha/README.md)\n"));
+
+        // A hand-written README keeps its text and feeds its parent's index.
+        ws.write(
+            "projects/alpha/README.md",
+            &format!(
+                "---\nsummary: The alpha project\n---\n# Alpha\n\nNotes.\n\n{}",
+                alpha.content.split_once("\n\n").unwrap().1
+            ),
+        )
+        .await
+        .unwrap();
+        let projects = ws.read("projects/README.md").await.unwrap();
+        assert!(
+            projects
+                .content
+                .contains("- [alpha/](alpha/README.md) — The alpha project\n")
+        );
+
+        ws.write("projects/alpha/notes.md", "# Notes\n")
+            .await
+            .unwrap();
+        ws.delete("projects/alpha/plan.md").await.unwrap();
+        let alpha = ws.read("projects/alpha/README.md").await.unwrap();
+        assert!(alpha.content.contains("Notes.\n"));
+        assert!(alpha.content.contains("- [notes.md](notes.md) — Notes\n"));
+        assert!(!alpha.content.contains("plan.md"));
+
+        // Appends that don't change the summary leave indexes alone.
+        let before = ws.read("projects/alpha/README.md").await.unwrap();
+        ws.append("projects/alpha/notes.md", "more").await.unwrap();
+        let after = ws.read("projects/alpha/README.md").await.unwrap();
+        assert_eq!(before.updated_at, after.updated_at);
+    }
+}
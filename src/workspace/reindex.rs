@@ -0,0 +1,441 @@
+//! Re-chunking and re-embedding the whole workspace.
+//!
+//! Documents are indexed when written, with whatever chunker, chunk
+//! configuration, embedding model, and sparse encoder were in use at the
+//! time. After any of those change, [`Workspace::reindex_all`] brings every
+//! document up to date.
+//!
+//! Each document that finishes is stamped with a fingerprint of the indexing
+//! setup (stored in its metadata under [`INDEX_KEY`]). Documents already
+//! carrying the current fingerprint are skipped, so an interrupted run picks
+//! up where it stopped when started again.
+//!
+//! [`Workspace::backfill_embeddings`] is the lighter operation for when only
+//! embeddings are missing, e.g. after enabling a provider: it embeds the
+//! chunks that have none and leaves the rest alone.
+
+use futures::future;
+use futures::stream::{self, StreamExt, TryStreamExt};
+use serde::Serialize;
+use serde_json::{Map, Value};
+use uuid::Uuid;
+
+use crate::error::WorkspaceError;
+use crate::workspace::language::DETECTOR_VERSION;
+use crate::workspace::{
+    CHUNKER_VERSION, MemoryDocument, Workspace, embed_batch_recorded, embed_recorded, trash,
+};
+
+/// Chunks fetched, and embedded in one request, at a time by backfill.
+const BACKFILL_BATCH_SIZE: usize = 32;
+
+/// Metadata key holding the fingerprint of the setup a document was last
+/// reindexed with.
+pub const INDEX_KEY: &str = "index";
+
+/// What happened to one document during a reindex.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReindexOutcome {
+    Reindexed,
+    /// Already indexed with the current setup.
+    Skipped,
+    Failed,
+}
+
+/// Progress report sent after each document.
+#[derive(Debug, Clone, Serialize)]
+pub struct ReindexProgress {
+    /// Workspace path of the document just handled.
+    pub path: String,
+    pub outcome: ReindexOutcome,
+    /// Documents handled so far, including this one.
+    pub completed: usize,
+    pub total: usize,
+}
+
+/// Totals of a reindex run.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct ReindexReport {
+    pub reindexed: usize,
+    pub skipped: usize,
+    pub failed: usize,
+}
+
+impl ReindexReport {
+    pub fn total(&self) -> usize {
+        self.reindexed + self.skipped + self.failed
+    }
+}
+
+/// Running totals of an embedding backfill.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct BackfillProgress {
+    pub embedded: usize,
+    pub failed: usize,
+}
+
+impl Workspace {
+    /// Re-chunk and re-embed every document, `concurrency` at a time.
+    ///
+    /// Documents already indexed with the current setup are skipped. Failures
+    /// are logged and counted rather than aborting the run.
+    pub async fn reindex_all(&self, concurrency: usize) -> Result<ReindexReport, WorkspaceError> {
+        self.reindex_all_with(concurrency, false, |_| {}).await
+    }
+
+    /// Like [`reindex_all`](Self::reindex_all), calling `on_progress` after
+    /// each document. With `force`, documents are reindexed even if their
+    /// fingerprint is current.
+    ///
+    /// Covers this workspace's namespace and any writable shared mounts.
+    pub async fn reindex_all_with(
+        &self,
+        concurrency: usize,
+        force: bool,
+        mut on_progress: impl FnMut(&ReindexProgress),
+    ) -> Result<ReindexReport, WorkspaceError> {
+        let scopes: Vec<Option<Uuid>> = std::iter::once(self.agent_id)
+            .chain(
+                self.mounts
+                    .iter()
+                    .filter(|m| m.is_writable())
+                    .map(|m| Some(m.scope())),
+            )
+            .collect();
+        // Paths are streamed rather than collected, so a huge workspace
+        // isn't held in memory; trashed documents stay unindexed until they
+        // are restored.
+        let targets = || {
+            stream::iter(scopes.clone())
+                .flat_map(|agent_id| {
+                    self.scope_paths(agent_id)
+                        .map_ok(move |path| (agent_id, path))
+                })
+                .try_filter(|(_, path)| future::ready(!trash::is_trashed(path)))
+        };
+        let total = targets()
+            .try_fold(0, |n, _| future::ready(Ok(n + 1)))
+            .await?;
+
+        let fingerprint = self.index_fingerprint();
+        let mut report = ReindexReport::default();
+        let results = targets()
+            .map_ok(|(agent_id, path)| {
+                let fingerprint = &fingerprint;
+                async move {
+                    let outcome = match self.reindex_one(agent_id, &path, fingerprint, force).await
+                    {
+                        Ok(true) => ReindexOutcome::Reindexed,
+                        Ok(false) => ReindexOutcome::Skipped,
+                        Err(e) => {
+                            tracing::warn!("Failed to reindex {}: {}", path, e);
+                            ReindexOutcome::Failed
+                        }
+                    };
+                    let path = match self.mounts.iter().find(|m| Some(m.scope()) == agent_id) {
+                        Some(mount) => mount.join(&path),
+                        None => path,
+                    };
+                    Ok::<_, WorkspaceError>((path, outcome))
+                }
+            })
+            .try_buffer_unordered(concurrency.max(1));
+        let mut results = std::pin::pin!(results);
+
+        while let Some((path, outcome)) = results.try_next().await? {
+            match outcome {
+                ReindexOutcome::Reindexed => report.reindexed += 1,
+                ReindexOutcome::Skipped => report.skipped += 1,
+                ReindexOutcome::Failed => report.failed += 1,
+            }
+            on_progress(&ReindexProgress {
+                path,
+                outcome,
+                completed: report.total(),
+                total,
+            });
+        }
+
+        tracing::info!(
+            "Reindexed {} documents ({} up to date, {} failed)",
+            report.reindexed,
+            report.skipped,
+            report.failed
+        );
+        Ok(report)
+    }
+
+    /// Reindex one document unless it is current. Returns whether it was
+    /// reindexed.
+    async fn reindex_one(
+        &self,
+        agent_id: Option<Uuid>,
+        path: &str,
+        fingerprint: &str,
+        force: bool,
+    ) -> Result<bool, WorkspaceError> {
+        let doc = self
+            .storage
+            .get_document_by_path(&self.user_id, agent_id, path)
+            .await?;
+        if !force && indexed_with(&doc) == Some(fingerprint) {
+            return Ok(false);
+        }
+
+        self.reindex_document(doc.id).await?;
+
+        // Indexing may have rewritten the metadata (frontmatter), so stamp
+        // the fresh copy.
+        let doc = self.storage.get_document_by_id(doc.id).await?;
+        let mut metadata = match doc.metadata {
+            Value::Object(map) => map,
+            _ => Map::new(),
+        };
+        metadata.insert(
+            INDEX_KEY.to_string(),
+            Value::String(fingerprint.to_string()),
+        );
+        self.update_metadata(doc.id, &Value::Object(metadata))
+            .await?;
+        Ok(true)
+    }
+
+    /// Identifies the chunker, chunk configuration, language detection,
+    /// embedding model, and sparse encoder that indexing currently uses.
+    fn index_fingerprint(&self) -> String {
+        let config = &self.chunk_config;
+        let model = self
+            .embeddings
+            .as_ref()
+            .map_or("none", |provider| provider.model_name());
+        let mut fingerprint = format!(
+            "chunker={};size={};overlap={};min={};lang={};model={}",
+            CHUNKER_VERSION,
+            config.chunk_size,
+            config.overlap_percent,
+            config.min_chunk_size,
+            DETECTOR_VERSION,
+            model
+        );
+        // Only with an encoder, so fingerprints from before sparse search
+        // existed still match
+        if let Some(encoder) = &self.sparse {
+            fingerprint.push_str(&format!(";sparse={}", encoder.model_name()));
+        }
+        fingerprint
+    }
+}
+
+impl Workspace {
+    /// Generate embeddings for every chunk that doesn't have one yet.
+    ///
+    /// Returns the number of chunks embedded.
+    pub async fn backfill_embeddings(&self) -> Result<usize, WorkspaceError> {
+        Ok(self.backfill_embeddings_with(None, |_| {}).await?.embedded)
+    }
+
+    /// Like [`backfill_embeddings`](Self::backfill_embeddings), attempting
+    /// at most `max_chunks` chunks and calling `on_progress` after each batch.
+    ///
+    /// A chunk is done once its embedding is stored, so a run that is
+    /// interrupted or stops at its budget continues where it left off the
+    /// next time. Chunks that fail to embed are skipped for the rest of the
+    /// run and retried by the next one.
+    pub async fn backfill_embeddings_with(
+        &self,
+        max_chunks: Option<usize>,
+        mut on_progress: impl FnMut(&BackfillProgress),
+    ) -> Result<BackfillProgress, WorkspaceError> {
+        let mut progress = BackfillProgress::default();
+        let Some(ref provider) = self.embeddings else {
+            return Ok(progress);
+        };
+
+        let scopes =
+            std::iter::once(self.agent_id).chain(self.mounts.iter().map(|m| Some(m.scope())));
+        for agent_id in scopes {
+            let mut after = None;
+            loop {
+                let attempted = progress.embedded + progress.failed;
+                let limit = match max_chunks {
+                    Some(max) if attempted >= max => return Ok(progress),
+                    Some(max) => (max - attempted).min(BACKFILL_BATCH_SIZE),
+                    None => BACKFILL_BATCH_SIZE,
+                };
+                let chunks = self
+                    .storage
+                    .get_chunks_without_embeddings(&self.user_id, agent_id, after, limit)
+                    .await?;
+                let Some(last) = chunks.last() else {
+                    break;
+                };
+                after = Some(last.id);
+
+                let texts: Vec<String> = chunks.iter().map(|c| c.content.clone()).collect();
+                match embed_batch_recorded(provider.as_ref(), &texts).await {
+                    Ok(embeddings) if embeddings.len() == chunks.len() => {
+                        for (chunk, embedding) in chunks.iter().zip(embeddings) {
+                            self.storage
+                                .update_chunk_embedding(chunk.id, &embedding)
+                                .await?;
+                            progress.embedded += 1;
+                        }
+                    }
+                    result => {
+                        // Retry one by one so a single bad chunk doesn't
+                        // fail the whole batch.
+                        if let Err(e) = result {
+                            tracing::warn!("Batch embedding failed, retrying per chunk: {}", e);
+                        }
+                        for chunk in &chunks {
+                            match embed_recorded(provider.as_ref(), &chunk.content).await {
+                                Ok(embedding) => {
+                                    self.storage
+                                        .update_chunk_embedding(chunk.id, &embedding)
+                                        .await?;
+                                    progress.embedded += 1;
+                                }
+                                Err(e) => {
+                                    tracing::warn!("Failed to embed chunk {}: {}", chunk.id, e);
+                                    progress.failed += 1;
+                                }
+                            }
+                        }
+                    }
+                }
+                on_progress(&progress);
+            }
+        }
+        Ok(progress)
+    }
+}
+
+/// The fingerprint `doc` was last reindexed with.
+fn indexed_with(doc: &MemoryDocument) -> Option<&str> {
+    doc.metadata.get(INDEX_KEY).and_then(Value::as_str)
+}
+
+#[cfg(test)]
+mod tests {
+    #[cfg(feature = "libsql")]
+    #[tokio::test]
+    async fn test_reindex_all_resumes_and_applies_new_config() {
+        use std::sync::Arc;
+
+        use super::*;
+        use crate::db::Database;
+        use crate::db::libsql_backend::test_db;
+        use crate::workspace::ChunkConfig;
+
+        let (db, _dir) = test_db().await;
+        let workspace = Workspace::new_with_db("default", Arc::clone(&db));
+
+        let long: String = (0..300).map(|i| format!("word{} ", i)).collect();
+        for i in 0..5 {
+            workspace
+                .write(&format!("notes/{}.md", i), &long)
+                .await
+                .unwrap();
+        }
+        let doc = workspace.read("notes/0.md").await.unwrap();
+        // Without an embedding provider every chunk lacks an embedding.
+        let chunks = |db: Arc<dyn Database>, id| async move {
+            db.get_chunks_without_embeddings("default", None, None, 1000)
+                .await
+                .unwrap()
+                .iter()
+                .filter(|c| c.document_id == id)
+                .count()
+        };
+        assert_eq!(chunks(Arc::clone(&db), doc.id).await, 1);
+
+        let workspace = workspace.with_chunk_config(
+            ChunkConfig::default()
+                .with_chunk_size(100)
+                .with_overlap(0.0),
+        );
+        let mut seen = Vec::new();
+        let report = workspace
+            .reindex_all_with(3, false, |p| seen.push(p.completed))
+            .await
+            .unwrap();
+        assert_eq!(report.reindexed, 5);
+        assert_eq!(seen, vec![1, 2, 3, 4, 5]);
+        assert_eq!(chunks(Arc::clone(&db), doc.id).await, 3);
+
+        // A second run has nothing left to do; forcing redoes everything.
+        let report = workspace.reindex_all(2).await.unwrap();
+        assert_eq!((report.reindexed, report.skipped), (0, 5));
+        let report = workspace.reindex_all_with(2, true, |_| {}).await.unwrap();
+        assert_eq!(report.reindexed, 5);
+    }
+
+    #[cfg(feature = "libsql")]
+    #[tokio::test]
+    async fn test_backfill_pages_through_and_resumes() {
+        use std::sync::Arc;
+
+        use async_trait::async_trait;
+
+        use super::*;
+        use crate::db::libsql_backend::test_db;
+        use crate::workspace::{EmbeddingError, EmbeddingProvider, MockEmbeddings};
+
+        /// Fails on any text mentioning "bad", and on batches containing one.
+        struct Flaky(MockEmbeddings);
+
+        #[async_trait]
+        impl EmbeddingProvider for Flaky {
+            fn dimension(&self) -> usize {
+                self.0.dimension()
+            }
+            fn model_name(&self) -> &str {
+                self.0.model_name()
+            }
+            fn max_input_length(&self) -> usize {
+                self.0.max_input_length()
+            }
+            async fn embed(&self, text: &str) -> Result<Vec<f32>, EmbeddingError> {
+                if text.contains("bad") {
+                    return Err(EmbeddingError::InvalidResponse("bad".to_string()));
+                }
+                self.0.embed(text).await
+            }
+        }
+
+        let (db, _dir) = test_db().await;
+        let plain = Workspace::new_with_db("default", Arc::clone(&db));
+        for i in 0..70 {
+            plain
+                .write(&format!("notes/{}.md", i), &format!("note {}", i))
+                .await
+                .unwrap();
+        }
+        plain.write("notes/bad.md", "bad note").await.unwrap();
+
+        let workspace = Workspace::new_with_db("default", Arc::clone(&db))
+            .with_embeddings(Arc::new(Flaky(MockEmbeddings::new(1536))));
+        let first = workspace
+            .backfill_embeddings_with(Some(10), |_| {})
+            .await
+            .unwrap();
+        assert_eq!(first.embedded + first.failed, 10);
+
+        let mut batches = 0;
+        let rest = workspace
+            .backfill_embeddings_with(None, |_| batches += 1)
+            .await
+            .unwrap();
+        assert_eq!(first.embedded + rest.embedded, 70);
+        assert_eq!(rest.failed, 1);
+        assert_eq!(batches, 2);
+
+        let pending = db
+            .get_chunks_without_embeddings("default", None, None, 100)
+            .await
+            .unwrap();
+        assert_eq!(pending.len(), 1);
+        assert_eq!(pending[0].content, "bad note");
+    }
+}
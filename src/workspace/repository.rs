@@ -11,8 +11,20 @@ use uuid::Uuid;
 
 use crate::error::WorkspaceError;
 
-use crate::workspace::document::{MemoryChunk, MemoryDocument, WorkspaceEntry};
-use crate::workspace::search::{RankedResult, SearchConfig, SearchResult, reciprocal_rank_fusion};
+use crate::workspace::document::{
+    DirectoryRecord, DocumentSize, DocumentVersion, MemoryChunk, MemoryDocument, NewChunk,
+    VersionInfo, WorkspaceEntry,
+};
+use crate::workspace::frontmatter::{DocumentMetadata, FieldFilter};
+use crate::workspace::glob::PathGlob;
+use crate::workspace::importance::ChunkSignals;
+use crate::workspace::language::Language;
+use crate::workspace::links::Backlink;
+use crate::workspace::pins::{MemoryPin, PinTarget};
+use crate::workspace::search::{
+    RankedResult, SearchConfig, SearchResult, reciprocal_rank_fusion_with_sparse,
+};
+use crate::workspace::sparse::SparseVector;
 
 /// Database repository for workspace operations.
 pub struct Repository {
@@ -70,6 +82,34 @@ impl Repository {
         }
     }
 
+    /// Get the documents at `paths` in one query. Paths without a document
+    /// are skipped.
+    pub async fn get_documents_by_paths(
+        &self,
+        user_id: &str,
+        agent_id: Option<Uuid>,
+        paths: &[String],
+    ) -> Result<Vec<MemoryDocument>, WorkspaceError> {
+        let conn = self.conn().await?;
+
+        let rows = conn
+            .query(
+                r#"
+                SELECT id, user_id, agent_id, path, content,
+                       created_at, updated_at, metadata
+                FROM memory_documents
+                WHERE user_id = $1 AND agent_id IS NOT DISTINCT FROM $2 AND path = ANY($3)
+                "#,
+                &[&user_id, &agent_id, &paths],
+            )
+            .await
+            .map_err(|e| WorkspaceError::SearchFailed {
+                reason: format!("Query failed: {}", e),
+            })?;
+
+        Ok(rows.iter().map(|row| self.row_to_document(row)).collect())
+    }
+
     /// Get a document by ID.
     pub async fn get_document_by_id(&self, id: Uuid) -> Result<MemoryDocument, WorkspaceError> {
         let conn = self.conn().await?;
@@ -150,6 +190,87 @@ impl Repository {
         Ok(())
     }
 
+    /// Move a document to `path` within its scope.
+    pub async fn rename_document(&self, id: Uuid, path: &str) -> Result<(), WorkspaceError> {
+        let conn = self.conn().await?;
+
+        conn.execute(
+            "UPDATE memory_documents SET path = $2, updated_at = NOW() WHERE id = $1",
+            &[&id, &path],
+        )
+        .await
+        .map_err(|e| WorkspaceError::SearchFailed {
+            reason: format!("Rename failed: {}", e),
+        })?;
+
+        Ok(())
+    }
+
+    /// Move every document below directory `from` to the same place below
+    /// `to`, together with the directory records at and below `from`.
+    pub async fn rename_directory(
+        &self,
+        user_id: &str,
+        agent_id: Option<Uuid>,
+        from: &str,
+        to: &str,
+    ) -> Result<u64, WorkspaceError> {
+        let mut conn = self.conn().await?;
+        let failed = |e: tokio_postgres::Error| WorkspaceError::SearchFailed {
+            reason: format!("Rename failed: {}", e),
+        };
+        let tx = conn.transaction().await.map_err(failed)?;
+
+        // Prefixes are compared with left() rather than LIKE, where '_' and
+        // '%' in directory names would match other directories.
+        let moved = tx
+            .execute(
+                r#"
+                UPDATE memory_documents
+                SET path = $4 || substr(path, char_length($3) + 1), updated_at = NOW()
+                WHERE user_id = $1 AND agent_id IS NOT DISTINCT FROM $2
+                  AND left(path, char_length($3) + 1) = $3 || '/'
+                "#,
+                &[&user_id, &agent_id, &from, &to],
+            )
+            .await
+            .map_err(failed)?;
+        tx.execute(
+            r#"
+            UPDATE memory_directories
+            SET path = $4 || substr(path, char_length($3) + 1), updated_at = NOW()
+            WHERE user_id = $1 AND agent_id IS NOT DISTINCT FROM $2
+              AND (path = $3 OR left(path, char_length($3) + 1) = $3 || '/')
+            "#,
+            &[&user_id, &agent_id, &from, &to],
+        )
+        .await
+        .map_err(failed)?;
+        tx.commit().await.map_err(failed)?;
+
+        Ok(moved)
+    }
+
+    /// Replace a document's metadata without touching its content.
+    pub async fn update_document_metadata(
+        &self,
+        id: Uuid,
+        metadata: &serde_json::Value,
+    ) -> Result<(), WorkspaceError> {
+        let conn = self.conn().await?;
+
+        conn.execute(
+            "UPDATE memory_documents SET metadata = $2 WHERE id = $1",
+            &[&id, metadata],
+        )
+        .await
+        .map_err(|e| WorkspaceError::SearchFailed {
+            reason: format!("Metadata update failed: {}", e),
+        })?;
+
+        Ok(())
+    }
+
     /// Delete a document by its path.
     pub async fn delete_document_by_path(
         &self,
@@ -179,6 +300,29 @@ impl Repository {
         Ok(())
     }
 
+    /// Delete the documents at `paths` in one statement. Their chunks and
+    /// everything else indexed about them go with them by cascade.
+    pub async fn delete_documents_by_paths(
+        &self,
+        user_id: &str,
+        agent_id: Option<Uuid>,
+        paths: &[String],
+    ) -> Result<u64, WorkspaceError> {
+        let conn = self.conn().await?;
+
+        conn.execute(
+            r#"
+            DELETE FROM memory_documents
+            WHERE user_id = $1 AND agent_id IS NOT DISTINCT FROM $2 AND path = ANY($3)
+            "#,
+            &[&user_id, &agent_id, &paths],
+        )
+        .await
+        .map_err(|e| WorkspaceError::SearchFailed {
+            reason: format!("Delete failed: {}", e),
+        })
+    }
+
     /// List files and directories in a directory path.
     ///
     /// Returns immediate children (not recursive).
@@ -215,6 +359,35 @@ impl Repository {
             .collect())
     }
 
+    /// Stored path of the document whose path equals `path` ignoring case,
+    /// preferring an exact match.
+    pub async fn find_document_path(
+        &self,
+        user_id: &str,
+        agent_id: Option<Uuid>,
+        path: &str,
+    ) -> Result<Option<String>, WorkspaceError> {
+        let conn = self.conn().await?;
+
+        let row = conn
+            .query_opt(
+                r#"
+                SELECT path FROM memory_documents
+                WHERE user_id = $1 AND agent_id IS NOT DISTINCT FROM $2
+                  AND lower(path) = lower($3)
+                ORDER BY path = $3 DESC, path
+                LIMIT 1
+                "#,
+                &[&user_id, &agent_id, &path],
+            )
+            .await
+            .map_err(|e| WorkspaceError::SearchFailed {
+                reason: format!("Find path failed: {}", e),
+            })?;
+
+        Ok(row.map(|row| row.get("path")))
+    }
+
     /// List all file paths in the workspace (flat list).
     pub async fn list_all_paths(
         &self,
@@ -240,6 +413,98 @@ impl Repository {
         Ok(rows.iter().map(|row| row.get("path")).collect())
     }
 
+    /// Up to `limit` paths ordered by path, starting after `after`.
+    pub async fn list_paths_page(
+        &self,
+        user_id: &str,
+        agent_id: Option<Uuid>,
+        after: Option<&str>,
+        limit: usize,
+    ) -> Result<Vec<String>, WorkspaceError> {
+        let conn = self.conn().await?;
+
+        let rows = conn
+            .query(
+                r#"
+                SELECT path FROM memory_documents
+                WHERE user_id = $1 AND agent_id IS NOT DISTINCT FROM $2
+                  AND ($3::text IS NULL OR path > $3)
+                ORDER BY path
+                LIMIT $4
+                "#,
+                &[&user_id, &agent_id, &after, &(limit as i64)],
+            )
+            .await
+            .map_err(|e| WorkspaceError::SearchFailed {
+                reason: format!("List paths failed: {}", e),
+            })?;
+
+        Ok(rows.iter().map(|row| row.get("path")).collect())
+    }
+
+    /// Paths of the documents matching `glob`, ordered by path. The prefix
+    /// is compared with left() rather than LIKE, where '_' and '%' would
+    /// match other paths.
+    pub async fn list_paths_matching(
+        &self,
+        user_id: &str,
+        agent_id: Option<Uuid>,
+        glob: &PathGlob,
+    ) -> Result<Vec<String>, WorkspaceError> {
+        let conn = self.conn().await?;
+
+        let rows = conn
+            .query(
+                r#"
+                SELECT path FROM memory_documents
+                WHERE user_id = $1 AND agent_id IS NOT DISTINCT FROM $2
+                  AND left(path, char_length($3)) = $3 AND path ~ $4
+                ORDER BY path
+                "#,
+                &[&user_id, &agent_id, &glob.prefix(), &glob.regex()],
+            )
+            .await
+            .map_err(|e| WorkspaceError::SearchFailed {
+                reason: format!("List paths failed: {}", e),
+            })?;
+
+        Ok(rows.iter().map(|row| row.get("path")).collect())
+    }
+
+    /// Content size and chunk count of every document, ordered by path.
+    pub async fn document_sizes(
+        &self,
+        user_id: &str,
+        agent_id: Option<Uuid>,
+    ) -> Result<Vec<DocumentSize>, WorkspaceError> {
+        let conn = self.conn().await?;
+
+        let rows = conn
+            .query(
+                r#"
+                SELECT d.path, OCTET_LENGTH(d.content)::BIGINT AS bytes,
+                       (SELECT COUNT(*) FROM memory_chunks c WHERE c.document_id = d.id) AS chunks
+                FROM memory_documents d
+                WHERE d.user_id = $1 AND d.agent_id IS NOT DISTINCT FROM $2
+                ORDER BY d.path
+                "#,
+                &[&user_id, &agent_id],
+            )
+            .await
+            .map_err(|e| WorkspaceError::SearchFailed {
+                reason: format!("Size query failed: {}", e),
+            })?;
+
+        Ok(rows
+            .iter()
+            .map(|row| DocumentSize {
+                path: row.get("path"),
+                bytes: row.get::<_, i64>("bytes") as u64,
+                chunks: row.get::<_, i64>("chunks") as u64,
+            })
+            .collect())
+    }
+
     /// List all documents for a user.
     pub async fn list_documents(
         &self,
@@ -267,13 +532,47 @@ impl Repository {
         Ok(rows.iter().map(|r| self.row_to_document(r)).collect())
     }
 
+    /// List documents whose stored frontmatter matches every filter.
+    pub async fn find_documents_by_fields(
+        &self,
+        user_id: &str,
+        agent_id: Option<Uuid>,
+        filters: &[FieldFilter],
+    ) -> Result<Vec<MemoryDocument>, WorkspaceError> {
+        let conn = self.conn().await?;
+
+        let rows = conn
+            .query(
+                &format!(
+                    r#"
+                    SELECT id, user_id, agent_id, path, content,
+                           created_at, updated_at, metadata
+                    FROM memory_documents d
+                    WHERE user_id = $1 AND agent_id IS NOT DISTINCT FROM $2
+                      AND {}
+                    ORDER BY path
+                    "#,
+                    frontmatter_condition(3)
+                ),
+                &[&user_id, &agent_id, &filters_json(filters)],
+            )
+            .await
+            .map_err(|e| WorkspaceError::SearchFailed {
+                reason: format!("Query failed: {}", e),
+            })?;
+
+        Ok(rows.iter().map(|r| self.row_to_document(r)).collect())
+    }
+
     fn row_to_document(&self, row: &tokio_postgres::Row) -> MemoryDocument {
+        let content: String = row.get("content");
         MemoryDocument {
             id: row.get("id"),
             user_id: row.get("user_id"),
             agent_id: row.get("agent_id"),
             path: row.get("path"),
-            content: row.get("content"),
+            frontmatter: DocumentMetadata::parse(&content),
+            content,
             created_at: row.get("created_at"),
             updated_at: row.get("updated_at"),
             metadata: row.get("metadata"),
@@ -298,6 +597,25 @@ impl Repository {
         Ok(())
     }
 
+    /// Delete the graph edges extracted from a document.
+    pub async fn delete_graph_edges_for_document(
+        &self,
+        document_id: Uuid,
+    ) -> Result<(), WorkspaceError> {
+        let conn = self.conn().await?;
+
+        conn.execute(
+            "DELETE FROM graph_edges WHERE document_id = $1",
+            &[&document_id],
+        )
+        .await
+        .map_err(|e| WorkspaceError::SearchFailed {
+            reason: format!("Delete failed: {}", e),
+        })?;
+
+        Ok(())
+    }
+
     /// Insert a chunk.
     pub async fn insert_chunk(
         &self,
@@ -305,6 +623,7 @@ impl Repository {
         chunk_index: i32,
         content: &str,
         embedding: Option<&[f32]>,
+        language: Language,
     ) -> Result<Uuid, WorkspaceError> {
         let conn = self.conn().await?;
         let id = Uuid::new_v4();
@@ -313,10 +632,17 @@ impl Repository {
 
         conn.execute(
             r#"
-            INSERT INTO memory_chunks (id, document_id, chunk_index, content, embedding)
-            VALUES ($1, $2, $3, $4, $5)
+            INSERT INTO memory_chunks (id, document_id, chunk_index, content, embedding, language)
+            VALUES ($1, $2, $3, $4, $5, $6::text::regconfig)
             "#,
-            &[&id, &document_id, &chunk_index, &content, &embedding_vec],
+            &[
+                &id,
+                &document_id,
+                &chunk_index,
+                &content,
+                &embedding_vec,
+                &language.fts_config(),
+            ],
         )
         .await
         .map_err(|e| WorkspaceError::ChunkingFailed {
@@ -326,6 +652,71 @@ impl Repository {
         Ok(id)
     }
 
+    /// Insert a document's chunks with a single statement, unnesting one
+    /// array per column.
+    pub async fn insert_chunks(
+        &self,
+        document_id: Uuid,
+        chunks: Vec<NewChunk>,
+    ) -> Result<Vec<Uuid>, WorkspaceError> {
+        if chunks.is_empty() {
+            return Ok(Vec::new());
+        }
+        let conn = self.conn().await?;
+        let ids: Vec<Uuid> = chunks.iter().map(|_| Uuid::new_v4()).collect();
+        let indexes: Vec<i32> = chunks.iter().map(|c| c.chunk_index).collect();
+        let languages: Vec<&str> = chunks.iter().map(|c| c.language.fts_config()).collect();
+        let mut contents = Vec::with_capacity(chunks.len());
+        let mut embeddings = Vec::with_capacity(chunks.len());
+        let (mut term_chunks, mut terms, mut weights) = (Vec::new(), Vec::new(), Vec::new());
+        for (id, chunk) in ids.iter().zip(chunks) {
+            contents.push(chunk.content);
+            embeddings.push(chunk.embedding.map(Vector::from));
+            for (term, weight) in chunk.sparse.into_iter().flat_map(|s| s.terms) {
+                term_chunks.push(*id);
+                terms.push(term as i32);
+                weights.push(weight);
+            }
+        }
+
+        conn.execute(
+            r#"
+            INSERT INTO memory_chunks (id, document_id, chunk_index, content, embedding, language)
+            SELECT c.id, $1, c.chunk_index, c.content, c.embedding, c.language::regconfig
+            FROM UNNEST($2::uuid[], $3::int4[], $4::text[], $5::vector[], $6::text[])
+                AS c(id, chunk_index, content, embedding, language)
+            "#,
+            &[
+                &document_id,
+                &ids,
+                &indexes,
+                &contents,
+                &embeddings,
+                &languages,
+            ],
+        )
+        .await
+        .map_err(|e| WorkspaceError::ChunkingFailed {
+            reason: format!("Insert failed: {}", e),
+        })?;
+
+        if !terms.is_empty() {
+            conn.execute(
+                r#"
+                INSERT INTO memory_chunk_terms (chunk_id, term, weight)
+                SELECT * FROM UNNEST($1::uuid[], $2::int4[], $3::float4[])
+                "#,
+                &[&term_chunks, &terms, &weights],
+            )
+            .await
+            .map_err(|e| WorkspaceError::ChunkingFailed {
+                reason: format!("Insert sparse terms failed: {}", e),
+            })?;
+        }
+
+        Ok(ids)
+    }
+
     /// Update a chunk's embedding.
     pub async fn update_chunk_embedding(
         &self,
@@ -352,6 +743,7 @@ impl Repository {
         &self,
         user_id: &str,
         agent_id: Option<Uuid>,
+        after: Option<Uuid>,
         limit: usize,
     ) -> Result<Vec<MemoryChunk>, WorkspaceError> {
         let conn = self.conn().await?;
@@ -364,9 +756,11 @@ impl Repository {
                 JOIN memory_documents d ON d.id = c.document_id
                 WHERE d.user_id = $1 AND d.agent_id IS NOT DISTINCT FROM $2
                   AND c.embedding IS NULL
-                LIMIT $3
+                  AND ($3::uuid IS NULL OR c.id > $3)
+                ORDER BY c.id
+                LIMIT $4
                 "#,
-                &[&user_id, &agent_id, &(limit as i64)],
+                &[&user_id, &agent_id, &after, &(limit as i64)],
             )
             .await
             .map_err(|e| WorkspaceError::SearchFailed {
@@ -386,27 +780,614 @@ impl Repository {
             .collect())
     }
 
-    // ==================== Search Operations ====================
+    /// A document's chunks with their embeddings, in document order.
+    pub async fn get_document_chunks(
+        &self,
+        document_id: Uuid,
+    ) -> Result<Vec<MemoryChunk>, WorkspaceError> {
+        let conn = self.conn().await?;
 
-    /// Perform hybrid search combining FTS and vector similarity.
-    pub async fn hybrid_search(
+        let rows = conn
+            .query(
+                r#"
+                SELECT id, document_id, chunk_index, content, embedding, created_at
+                FROM memory_chunks
+                WHERE document_id = $1
+                ORDER BY chunk_index
+                "#,
+                &[&document_id],
+            )
+            .await
+            .map_err(|e| WorkspaceError::SearchFailed {
+                reason: format!("Query failed: {}", e),
+            })?;
+
+        Ok(rows
+            .iter()
+            .map(|row| MemoryChunk {
+                id: row.get("id"),
+                document_id: row.get("document_id"),
+                chunk_index: row.get("chunk_index"),
+                content: row.get("content"),
+                embedding: row
+                    .get::<_, Option<Vector>>("embedding")
+                    .map(|v| v.to_vec()),
+                created_at: row.get("created_at"),
+            })
+            .collect())
+    }
+
+    /// Number of a user's chunks in each language, across all agents.
+    pub async fn chunk_language_counts(
         &self,
         user_id: &str,
-        agent_id: Option<Uuid>,
-        query: &str,
-        embedding: Option<&[f32]>,
+    ) -> Result<Vec<(Language, u64)>, WorkspaceError> {
+        let conn = self.conn().await?;
+
+        let rows = conn
+            .query(
+                r#"
+                SELECT c.language::text AS language, COUNT(*) AS chunks
+                FROM memory_chunks c
+                JOIN memory_documents d ON d.id = c.document_id
+                WHERE d.user_id = $1
+                GROUP BY c.language
+                "#,
+                &[&user_id],
+            )
+            .await
+            .map_err(|e| WorkspaceError::SearchFailed {
+                reason: format!("Query failed: {}", e),
+            })?;
+
+        Ok(rows
+            .iter()
+            .map(|row| {
+                let language: String = row.get("language");
+                let chunks: i64 = row.get("chunks");
+                (Language::from_fts_config(&language), chunks as u64)
+            })
+            .collect())
+    }
+
+    // ==================== Chunk Importance ====================
+
+    /// Set the static importance of a chunk, creating its signals if needed.
+    pub async fn set_chunk_importance(
+        &self,
+        document_id: Uuid,
+        content_hash: &str,
+        importance: f32,
+    ) -> Result<(), WorkspaceError> {
+        let conn = self.conn().await?;
+
+        conn.execute(
+            r#"
+            INSERT INTO memory_chunk_signals (document_id, content_hash, importance)
+            VALUES ($1, $2, $3)
+            ON CONFLICT (document_id, content_hash) DO UPDATE SET importance = EXCLUDED.importance
+            "#,
+            &[&document_id, &content_hash, &importance],
+        )
+        .await
+        .map_err(|e| WorkspaceError::ChunkingFailed {
+            reason: format!("Importance update failed: {}", e),
+        })?;
+
+        Ok(())
+    }
+
+    /// Drop signals for a document's chunks whose hash is not in `keep`.
+    pub async fn prune_chunk_signals(
+        &self,
+        document_id: Uuid,
+        keep: &[String],
+    ) -> Result<(), WorkspaceError> {
+        let conn = self.conn().await?;
+
+        conn.execute(
+            "DELETE FROM memory_chunk_signals WHERE document_id = $1 AND NOT (content_hash = ANY($2))",
+            &[&document_id, &keep],
+        )
+        .await
+        .map_err(|e| WorkspaceError::ChunkingFailed {
+            reason: format!("Delete failed: {}", e),
+        })?;
+
+        Ok(())
+    }
+
+    /// Count one search retrieval of a chunk.
+    pub async fn record_chunk_retrieval(
+        &self,
+        document_id: Uuid,
+        content_hash: &str,
+    ) -> Result<(), WorkspaceError> {
+        let conn = self.conn().await?;
+
+        conn.execute(
+            r#"
+            UPDATE memory_chunk_signals
+            SET retrieval_count = retrieval_count + 1, last_retrieved_at = NOW()
+            WHERE document_id = $1 AND content_hash = $2
+            "#,
+            &[&document_id, &content_hash],
+        )
+        .await
+        .map_err(|e| WorkspaceError::SearchFailed {
+            reason: format!("Update failed: {}", e),
+        })?;
+
+        Ok(())
+    }
+
+    /// Importance signals for all chunks of the given documents.
+    pub async fn get_chunk_signals(
+        &self,
+        document_ids: &[Uuid],
+    ) -> Result<Vec<ChunkSignals>, WorkspaceError> {
+        let conn = self.conn().await?;
+
+        let rows = conn
+            .query(
+                r#"
+                SELECT s.document_id, s.content_hash, s.importance, s.retrieval_count,
+                       s.last_retrieved_at, d.updated_at
+                FROM memory_chunk_signals s
+                JOIN memory_documents d ON d.id = s.document_id
+                WHERE s.document_id = ANY($1)
+                "#,
+                &[&document_ids],
+            )
+            .await
+            .map_err(|e| WorkspaceError::SearchFailed {
+                reason: format!("Query failed: {}", e),
+            })?;
+
+        Ok(rows
+            .iter()
+            .map(|row| ChunkSignals {
+                document_id: row.get("document_id"),
+                content_hash: row.get("content_hash"),
+                importance: row.get("importance"),
+                retrieval_count: row.get::<_, i64>("retrieval_count").max(0) as u64,
+                last_retrieved_at: row.get("last_retrieved_at"),
+                document_updated_at: row.get("updated_at"),
+            })
+            .collect())
+    }
+
+    // ==================== Pins ====================
+
+    /// Store a new pin.
+    pub async fn create_memory_pin(
+        &self,
+        user_id: &str,
+        agent_id: Option<Uuid>,
+        pin: &MemoryPin,
+    ) -> Result<(), WorkspaceError> {
+        let conn = self.conn().await?;
+        let (path, heading, content) = pin.target.columns();
+
+        conn.execute(
+            r#"
+            INSERT INTO memory_pins (id, user_id, agent_id, kind, path, heading, content, created_at)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+            "#,
+            &[
+                &pin.id,
+                &user_id,
+                &agent_id,
+                &pin.target.kind(),
+                &path,
+                &heading,
+                &content,
+                &pin.created_at,
+            ],
+        )
+        .await
+        .map_err(|e| WorkspaceError::SearchFailed {
+            reason: format!("Insert failed: {}", e),
+        })?;
+
+        Ok(())
+    }
+
+    /// List pins, oldest first.
+    pub async fn list_memory_pins(
+        &self,
+        user_id: &str,
+        agent_id: Option<Uuid>,
+    ) -> Result<Vec<MemoryPin>, WorkspaceError> {
+        let conn = self.conn().await?;
+
+        let rows = conn
+            .query(
+                r#"
+                SELECT id, kind, path, heading, content, created_at
+                FROM memory_pins
+                WHERE user_id = $1 AND agent_id IS NOT DISTINCT FROM $2
+                ORDER BY created_at
+                "#,
+                &[&user_id, &agent_id],
+            )
+            .await
+            .map_err(|e| WorkspaceError::SearchFailed {
+                reason: format!("Query failed: {}", e),
+            })?;
+
+        Ok(rows
+            .iter()
+            .filter_map(|row| {
+                let kind: String = row.get("kind");
+                Some(MemoryPin {
+                    id: row.get("id"),
+                    target: PinTarget::from_columns(
+                        &kind,
+                        row.get("path"),
+                        row.get("heading"),
+                        row.get("content"),
+                    )?,
+                    created_at: row.get("created_at"),
+                })
+            })
+            .collect())
+    }
+
+    /// Delete a pin. Returns false if it did not exist.
+    pub async fn delete_memory_pin(
+        &self,
+        user_id: &str,
+        agent_id: Option<Uuid>,
+        id: Uuid,
+    ) -> Result<bool, WorkspaceError> {
+        let conn = self.conn().await?;
+
+        let count = conn
+            .execute(
+                "DELETE FROM memory_pins WHERE id = $1 AND user_id = $2 AND agent_id IS NOT DISTINCT FROM $3",
+                &[&id, &user_id, &agent_id],
+            )
+            .await
+            .map_err(|e| WorkspaceError::SearchFailed {
+                reason: format!("Delete failed: {}", e),
+            })?;
+
+        Ok(count > 0)
+    }
+
+    // ==================== Directories ====================
+
+    /// Record a directory. Returns false if it was already recorded.
+    pub async fn create_directory(
+        &self,
+        user_id: &str,
+        agent_id: Option<Uuid>,
+        path: &str,
+    ) -> Result<bool, WorkspaceError> {
+        let conn = self.conn().await?;
+
+        let count = conn
+            .execute(
+                r#"
+                INSERT INTO memory_directories (user_id, agent_id, path)
+                SELECT $1, $2, $3
+                WHERE NOT EXISTS (
+                    SELECT 1 FROM memory_directories
+                    WHERE user_id = $1 AND agent_id IS NOT DISTINCT FROM $2 AND path = $3
+                )
+                "#,
+                &[&user_id, &agent_id, &path],
+            )
+            .await
+            .map_err(|e| WorkspaceError::SearchFailed {
+                reason: format!("Insert failed: {}", e),
+            })?;
+
+        Ok(count > 0)
+    }
+
+    /// Recorded directories at `prefix` or below it, ordered by path.
+    pub async fn list_directories(
+        &self,
+        user_id: &str,
+        agent_id: Option<Uuid>,
+        prefix: &str,
+    ) -> Result<Vec<DirectoryRecord>, WorkspaceError> {
+        let conn = self.conn().await?;
+
+        let rows = conn
+            .query(
+                r#"
+                SELECT path, metadata, created_at, updated_at
+                FROM memory_directories
+                WHERE user_id = $1 AND agent_id IS NOT DISTINCT FROM $2
+                  AND ($3 = '' OR path = $3 OR path LIKE $3 || '/%')
+                ORDER BY path
+                "#,
+                &[&user_id, &agent_id, &prefix],
+            )
+            .await
+            .map_err(|e| WorkspaceError::SearchFailed {
+                reason: format!("Query failed: {}", e),
+            })?;
+
+        Ok(rows
+            .iter()
+            .map(|row| DirectoryRecord {
+                path: row.get("path"),
+                metadata: row.get("metadata"),
+                created_at: row.get("created_at"),
+                updated_at: row.get("updated_at"),
+            })
+            .collect())
+    }
+
+    /// Replace a recorded directory's metadata. Returns false if it isn't
+    /// recorded.
+    pub async fn update_directory_metadata(
+        &self,
+        user_id: &str,
+        agent_id: Option<Uuid>,
+        path: &str,
+        metadata: &serde_json::Value,
+    ) -> Result<bool, WorkspaceError> {
+        let conn = self.conn().await?;
+
+        let count = conn
+            .execute(
+                r#"
+                UPDATE memory_directories SET metadata = $4, updated_at = NOW()
+                WHERE user_id = $1 AND agent_id IS NOT DISTINCT FROM $2 AND path = $3
+                "#,
+                &[&user_id, &agent_id, &path, metadata],
+            )
+            .await
+            .map_err(|e| WorkspaceError::SearchFailed {
+                reason: format!("Update failed: {}", e),
+            })?;
+
+        Ok(count > 0)
+    }
+
+    /// Forget the directory at `path` and every directory below it.
+    pub async fn delete_directories(
+        &self,
+        user_id: &str,
+        agent_id: Option<Uuid>,
+        path: &str,
+    ) -> Result<u64, WorkspaceError> {
+        let conn = self.conn().await?;
+
+        conn.execute(
+            r#"
+            DELETE FROM memory_directories
+            WHERE user_id = $1 AND agent_id IS NOT DISTINCT FROM $2
+              AND (path = $3 OR path LIKE $3 || '/%')
+            "#,
+            &[&user_id, &agent_id, &path],
+        )
+        .await
+        .map_err(|e| WorkspaceError::SearchFailed {
+            reason: format!("Delete failed: {}", e),
+        })
+    }
+
+    // ==================== Versions ====================
+
+    /// Store `content`, written at `saved_at`, as the next revision of a
+    /// document. Returns its version number.
+    pub async fn insert_document_version(
+        &self,
+        document_id: Uuid,
+        content: &str,
+        saved_at: DateTime<Utc>,
+    ) -> Result<i32, WorkspaceError> {
+        let conn = self.conn().await?;
+
+        let row = conn
+            .query_one(
+                r#"
+                INSERT INTO memory_document_versions (document_id, version, content, saved_at)
+                SELECT $1, COALESCE(MAX(version), 0) + 1, $2::text, $3::timestamptz
+                FROM memory_document_versions
+                WHERE document_id = $1
+                RETURNING version
+                "#,
+                &[&document_id, &content, &saved_at],
+            )
+            .await
+            .map_err(|e| WorkspaceError::SearchFailed {
+                reason: format!("Insert failed: {}", e),
+            })?;
+
+        Ok(row.get("version"))
+    }
+
+    /// Delete all but the newest `keep` revisions of a document.
+    pub async fn prune_document_versions(
+        &self,
+        document_id: Uuid,
+        keep: usize,
+    ) -> Result<(), WorkspaceError> {
+        let conn = self.conn().await?;
+
+        conn.execute(
+            r#"
+            DELETE FROM memory_document_versions
+            WHERE document_id = $1
+              AND version <= (
+                  SELECT MAX(version) FROM memory_document_versions WHERE document_id = $1
+              ) - $2
+            "#,
+            &[&document_id, &(keep as i32)],
+        )
+        .await
+        .map_err(|e| WorkspaceError::SearchFailed {
+            reason: format!("Delete failed: {}", e),
+        })?;
+
+        Ok(())
+    }
+
+    /// A document's revisions, newest first.
+    pub async fn list_document_versions(
+        &self,
+        document_id: Uuid,
+    ) -> Result<Vec<VersionInfo>, WorkspaceError> {
+        let conn = self.conn().await?;
+
+        let rows = conn
+            .query(
+                r#"
+                SELECT version, saved_at, LENGTH(content) AS size,
+                       SUBSTR(content, 1, 200) AS preview
+                FROM memory_document_versions
+                WHERE document_id = $1
+                ORDER BY version DESC
+                "#,
+                &[&document_id],
+            )
+            .await
+            .map_err(|e| WorkspaceError::SearchFailed {
+                reason: format!("Query failed: {}", e),
+            })?;
+
+        Ok(rows
+            .iter()
+            .map(|row| VersionInfo {
+                version: row.get("version"),
+                saved_at: row.get("saved_at"),
+                size: row.get::<_, i32>("size") as usize,
+                preview: row.get("preview"),
+            })
+            .collect())
+    }
+
+    /// One revision of a document, if it is stored.
+    pub async fn get_document_version(
+        &self,
+        document_id: Uuid,
+        version: i32,
+    ) -> Result<Option<DocumentVersion>, WorkspaceError> {
+        let conn = self.conn().await?;
+
+        let row = conn
+            .query_opt(
+                r#"
+                SELECT version, content, saved_at
+                FROM memory_document_versions
+                WHERE document_id = $1 AND version = $2
+                "#,
+                &[&document_id, &version],
+            )
+            .await
+            .map_err(|e| WorkspaceError::SearchFailed {
+                reason: format!("Query failed: {}", e),
+            })?;
+
+        Ok(row.map(|row| DocumentVersion {
+            version: row.get("version"),
+            content: row.get("content"),
+            saved_at: row.get("saved_at"),
+        }))
+    }
+
+    // ==================== Links ====================
+
+    /// Replace the `[[wiki link]]` targets stored for a document.
+    pub async fn set_document_links(
+        &self,
+        document_id: Uuid,
+        targets: &[String],
+    ) -> Result<(), WorkspaceError> {
+        let conn = self.conn().await?;
+
+        conn.execute(
+            "DELETE FROM memory_links WHERE document_id = $1",
+            &[&document_id],
+        )
+        .await
+        .map_err(|e| WorkspaceError::ChunkingFailed {
+            reason: format!("Delete failed: {}", e),
+        })?;
+        if targets.is_empty() {
+            return Ok(());
+        }
+        conn.execute(
+            r#"
+            INSERT INTO memory_links (document_id, target)
+            SELECT $1, t FROM unnest($2::text[]) AS t
+            ON CONFLICT DO NOTHING
+            "#,
+            &[&document_id, &targets],
+        )
+        .await
+        .map_err(|e| WorkspaceError::ChunkingFailed {
+            reason: format!("Insert failed: {}", e),
+        })?;
+
+        Ok(())
+    }
+
+    /// Links in the scope whose target equals `key` or is a `/`-bounded
+    /// suffix of it, with the path of the linking document.
+    pub async fn find_backlinks(
+        &self,
+        user_id: &str,
+        agent_id: Option<Uuid>,
+        key: &str,
+    ) -> Result<Vec<Backlink>, WorkspaceError> {
+        let conn = self.conn().await?;
+
+        let rows = conn
+            .query(
+                r#"
+                SELECT d.path, l.target
+                FROM memory_links l
+                JOIN memory_documents d ON d.id = l.document_id
+                WHERE d.user_id = $1 AND d.agent_id IS NOT DISTINCT FROM $2
+                  AND (l.target = $3 OR right($3, length(l.target) + 1) = '/' || l.target)
+                ORDER BY d.path
+                "#,
+                &[&user_id, &agent_id, &key],
+            )
+            .await
+            .map_err(|e| WorkspaceError::SearchFailed {
+                reason: format!("Query failed: {}", e),
+            })?;
+
+        Ok(rows
+            .iter()
+            .map(|row| Backlink {
+                path: row.get("path"),
+                target: row.get("target"),
+            })
+            .collect())
+    }
+
+    // ==================== Search Operations ====================
+
+    /// Perform hybrid search combining FTS, vector similarity, and (given
+    /// the query's sparse vector) learned sparse matching.
+    pub async fn hybrid_search(
+        &self,
+        user_id: &str,
+        agent_id: Option<Uuid>,
+        query: &str,
+        embedding: Option<&[f32]>,
+        sparse: Option<&SparseVector>,
         config: &SearchConfig,
     ) -> Result<Vec<SearchResult>, WorkspaceError> {
         let fts_results = if config.use_fts {
-            self.fts_search(user_id, agent_id, query, config.pre_fusion_limit)
-                .await?
+            self.fts_search(user_id, agent_id, query, config).await?
         } else {
             Vec::new()
         };
 
         let vector_results = if config.use_vector {
             if let Some(embedding) = embedding {
-                self.vector_search(user_id, agent_id, embedding, config.pre_fusion_limit)
+                self.vector_search(user_id, agent_id, embedding, config)
                     .await?
             } else {
                 Vec::new()
@@ -415,32 +1396,61 @@ impl Repository {
             Vec::new()
         };
 
-        Ok(reciprocal_rank_fusion(fts_results, vector_results, config))
+        let sparse_results = match sparse {
+            Some(sparse) if config.use_sparse && !sparse.is_empty() => {
+                self.sparse_search(user_id, agent_id, sparse, config)
+                    .await?
+            }
+            _ => Vec::new(),
+        };
+
+        Ok(reciprocal_rank_fusion_with_sparse(
+            fts_results,
+            vector_results,
+            sparse_results,
+            config,
+        ))
     }
 
-    /// Full-text search using PostgreSQL ts_rank_cd.
+    /// Full-text search using PostgreSQL ts_rank_cd. Each chunk is matched
+    /// against the query parsed with its own language's configuration.
     async fn fts_search(
         &self,
         user_id: &str,
         agent_id: Option<Uuid>,
         query: &str,
-        limit: usize,
+        config: &SearchConfig,
     ) -> Result<Vec<RankedResult>, WorkspaceError> {
         let conn = self.conn().await?;
 
         let rows = conn
             .query(
-                r#"
-                SELECT c.id as chunk_id, c.document_id, c.content,
-                       ts_rank_cd(c.content_tsv, plainto_tsquery('english', $3)) as rank
-                FROM memory_chunks c
-                JOIN memory_documents d ON d.id = c.document_id
-                WHERE d.user_id = $1 AND d.agent_id IS NOT DISTINCT FROM $2
-                  AND c.content_tsv @@ plainto_tsquery('english', $3)
-                ORDER BY rank DESC
-                LIMIT $4
-                "#,
-                &[&user_id, &agent_id, &query, &(limit as i64)],
+                &format!(
+                    r#"
+                    SELECT c.id as chunk_id, c.document_id, c.content,
+                           ts_rank_cd(c.content_tsv, plainto_tsquery(c.language, $3)) as rank
+                    FROM memory_chunks c
+                    JOIN memory_documents d ON d.id = c.document_id
+                    WHERE d.user_id = $1 AND d.agent_id IS NOT DISTINCT FROM $2
+                      AND c.content_tsv @@ plainto_tsquery(c.language, $3)
+                      AND {}
+                      AND {}
+                    ORDER BY rank DESC
+                    LIMIT $4
+                    "#,
+                    frontmatter_condition(5),
+                    document_condition(6)
+                ),
+                &[
+                    &user_id,
+                    &agent_id,
+                    &query,
+                    &(config.pre_fusion_limit as i64),
+                    &filters_json(&config.field_filters()),
+                    &config.path_prefix,
+                    &config.updated_after,
+                    &config.updated_before,
+                ],
             )
             .await
             .map_err(|e| WorkspaceError::SearchFailed {
@@ -455,6 +1465,7 @@ impl Repository {
                 document_id: row.get("document_id"),
                 content: row.get("content"),
                 rank: (i + 1) as u32,
+                score: row.get("rank"),
             })
             .collect())
     }
@@ -465,24 +1476,39 @@ impl Repository {
         user_id: &str,
         agent_id: Option<Uuid>,
         embedding: &[f32],
-        limit: usize,
+        config: &SearchConfig,
     ) -> Result<Vec<RankedResult>, WorkspaceError> {
         let conn = self.conn().await?;
         let embedding_vec = Vector::from(embedding.to_vec());
 
         let rows = conn
             .query(
-                r#"
-                SELECT c.id as chunk_id, c.document_id, c.content,
-                       1 - (c.embedding <=> $3) as similarity
-                FROM memory_chunks c
-                JOIN memory_documents d ON d.id = c.document_id
-                WHERE d.user_id = $1 AND d.agent_id IS NOT DISTINCT FROM $2
-                  AND c.embedding IS NOT NULL
-                ORDER BY c.embedding <=> $3
-                LIMIT $4
-                "#,
-                &[&user_id, &agent_id, &embedding_vec, &(limit as i64)],
+                &format!(
+                    r#"
+                    SELECT c.id as chunk_id, c.document_id, c.content,
+                           1 - (c.embedding <=> $3) as similarity
+                    FROM memory_chunks c
+                    JOIN memory_documents d ON d.id = c.document_id
+                    WHERE d.user_id = $1 AND d.agent_id IS NOT DISTINCT FROM $2
+                      AND c.embedding IS NOT NULL
+                      AND {}
+                      AND {}
+                    ORDER BY c.embedding <=> $3
+                    LIMIT $4
+                    "#,
+                    frontmatter_condition(5),
+                    document_condition(6)
+                ),
+                &[
+                    &user_id,
+                    &agent_id,
+                    &embedding_vec,
+                    &(config.pre_fusion_limit as i64),
+                    &filters_json(&config.field_filters()),
+                    &config.path_prefix,
+                    &config.updated_after,
+                    &config.updated_before,
+                ],
             )
             .await
             .map_err(|e| WorkspaceError::SearchFailed {
@@ -497,7 +1523,105 @@ impl Repository {
                 document_id: row.get("document_id"),
                 content: row.get("content"),
                 rank: (i + 1) as u32,
+                score: row.get::<_, f64>("similarity") as f32,
             })
             .collect())
     }
+
+    /// Learned sparse search: the dot product with the query's weights,
+    /// summed over the terms they share through the term index.
+    async fn sparse_search(
+        &self,
+        user_id: &str,
+        agent_id: Option<Uuid>,
+        sparse: &SparseVector,
+        config: &SearchConfig,
+    ) -> Result<Vec<RankedResult>, WorkspaceError> {
+        let conn = self.conn().await?;
+        let (terms, weights): (Vec<i32>, Vec<f32>) =
+            sparse.terms.iter().map(|&(t, w)| (t as i32, w)).unzip();
+
+        let rows = conn
+            .query(
+                &format!(
+                    r#"
+                    SELECT c.id as chunk_id, c.document_id, c.content,
+                           SUM(t.weight * q.weight)::real as score
+                    FROM UNNEST($3::int4[], $4::float4[]) AS q(term, weight)
+                    JOIN memory_chunk_terms t ON t.term = q.term
+                    JOIN memory_chunks c ON c.id = t.chunk_id
+                    JOIN memory_documents d ON d.id = c.document_id
+                    WHERE d.user_id = $1 AND d.agent_id IS NOT DISTINCT FROM $2
+                      AND {}
+                      AND {}
+                    GROUP BY c.id
+                    ORDER BY score DESC
+                    LIMIT $5
+                    "#,
+                    frontmatter_condition(6),
+                    document_condition(7)
+                ),
+                &[
+                    &user_id,
+                    &agent_id,
+                    &terms,
+                    &weights,
+                    &(config.pre_fusion_limit as i64),
+                    &filters_json(&config.field_filters()),
+                    &config.path_prefix,
+                    &config.updated_after,
+                    &config.updated_before,
+                ],
+            )
+            .await
+            .map_err(|e| WorkspaceError::SearchFailed {
+                reason: format!("Sparse query failed: {}", e),
+            })?;
+
+        Ok(rows
+            .iter()
+            .enumerate()
+            .map(|(i, row)| RankedResult {
+                chunk_id: row.get("chunk_id"),
+                document_id: row.get("document_id"),
+                content: row.get("content"),
+                rank: (i + 1) as u32,
+                score: row.get("score"),
+            })
+            .collect())
+    }
+}
+
+/// Filters as a JSONB array of `{key, value}` objects.
+fn filters_json(filters: &[FieldFilter]) -> serde_json::Value {
+    serde_json::to_value(filters).unwrap_or_else(|_| serde_json::json!([]))
+}
+
+/// SQL condition: the frontmatter of document `d` matches every filter in
+/// the JSONB parameter `$param` (see [`filters_json`]).
+fn frontmatter_condition(param: usize) -> String {
+    format!(
+        r#"NOT EXISTS (
+            SELECT 1 FROM jsonb_array_elements(${param}::jsonb) f
+            WHERE NOT EXISTS (
+                SELECT 1 FROM jsonb_each(COALESCE(d.metadata->'frontmatter', '{{}}'::jsonb)) m
+                WHERE m.key = f->>'key'
+                  AND (f->>'value' IS NULL
+                       OR (jsonb_typeof(m.value) = 'array'
+                           AND m.value @> jsonb_build_array(f->>'value'))
+                       OR m.value #>> '{{}}' = f->>'value')
+            )
+        )"#
+    )
+}
+
+/// SQL condition: document `d` is within the path prefix and update window
+/// in parameters `$param` to `$param + 2` (each NULL when unrestricted).
+fn document_condition(param: usize) -> String {
+    let (after, before) = (param + 1, param + 2);
+    format!(
+        r#"(${param}::text IS NULL OR d.path = ${param} OR d.path LIKE ${param} || '/%')
+           AND (${after}::timestamptz IS NULL OR d.updated_at >= ${after})
+           AND (${before}::timestamptz IS NULL OR d.updated_at < ${before})"#
+    )
 }
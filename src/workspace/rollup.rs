@@ -0,0 +1,452 @@
+//! Weekly and monthly rollups of daily logs.
+//!
+//! Raw daily logs are verbose and pile up. Once a week or month is over, an
+//! LLM condenses its logs into a rollup document:
+//!
+//! - `daily/weekly/2024-W03.md` for ISO week 3 of 2024
+//! - `daily/monthly/2024-01.md` for January 2024
+//!
+//! Rollups are ordinary workspace documents, so they are indexed for search
+//! (where they outrank raw logs, see [`super::importance`]) and the latest
+//! weekly rollup stands in for older logs in the system prompt. Existing
+//! rollups are never regenerated; delete one to have it rebuilt.
+
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::{Datelike, NaiveDate, Utc, Weekday};
+use rust_decimal::Decimal;
+use serde::Serialize;
+
+use crate::error::{LlmError, WorkspaceError};
+use crate::llm::{ChatMessage, CompletionRequest, LlmProvider};
+use crate::workspace::prompt_budget::fit_head;
+use crate::workspace::{PromptContext, Workspace, paths};
+
+/// Directory of weekly rollups.
+pub const WEEKLY_DIR: &str = "daily/weekly/";
+
+/// Directory of monthly rollups.
+pub const MONTHLY_DIR: &str = "daily/monthly/";
+
+/// Rollups written per pass, bounding the LLM cost of catching up on a
+/// long history.
+const MAX_ROLLUPS_PER_PASS: usize = 6;
+
+/// Days after its end that a weekly rollup still appears in the prompt.
+const PROMPT_WINDOW_DAYS: i64 = 14;
+
+/// Characters of each daily log sent to the LLM.
+const MAX_LOG_CHARS: usize = 6_000;
+
+/// Errors from generating rollups.
+#[derive(Debug, thiserror::Error)]
+pub enum RollupError {
+    #[error(transparent)]
+    Workspace(#[from] WorkspaceError),
+
+    #[error(transparent)]
+    Llm(#[from] LlmError),
+}
+
+/// A finished week or month.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum RollupPeriod {
+    /// ISO week.
+    Week {
+        year: i32,
+        week: u32,
+    },
+    Month {
+        year: i32,
+        month: u32,
+    },
+}
+
+impl RollupPeriod {
+    /// The ISO week containing `date`.
+    pub fn week_of(date: NaiveDate) -> Self {
+        let iso = date.iso_week();
+        Self::Week {
+            year: iso.year(),
+            week: iso.week(),
+        }
+    }
+
+    /// The month containing `date`.
+    pub fn month_of(date: NaiveDate) -> Self {
+        Self::Month {
+            year: date.year(),
+            month: date.month(),
+        }
+    }
+
+    /// First and last day of the period.
+    pub fn days(&self) -> (NaiveDate, NaiveDate) {
+        match *self {
+            Self::Week { year, week } => (
+                NaiveDate::from_isoywd_opt(year, week, Weekday::Mon).unwrap_or_default(),
+                NaiveDate::from_isoywd_opt(year, week, Weekday::Sun).unwrap_or_default(),
+            ),
+            Self::Month { year, month } => {
+                let first = NaiveDate::from_ymd_opt(year, month, 1).unwrap_or_default();
+                let next = first
+                    .checked_add_months(chrono::Months::new(1))
+                    .unwrap_or(first);
+                (first, next.pred_opt().unwrap_or(first))
+            }
+        }
+    }
+
+    /// The week of a weekly rollup path like `daily/weekly/2024-W03.md`.
+    pub fn parse_week(path: &str) -> Option<Self> {
+        let stem = path.strip_prefix(WEEKLY_DIR)?.strip_suffix(".md")?;
+        let (year, week) = stem.split_once("-W")?;
+        let (year, week) = (year.parse().ok()?, week.parse().ok()?);
+        NaiveDate::from_isoywd_opt(year, week, Weekday::Mon)?;
+        Some(Self::Week { year, week })
+    }
+
+    /// Workspace path of the period's rollup.
+    pub fn path(&self) -> String {
+        match *self {
+            Self::Week { year, week } => format!("{WEEKLY_DIR}{year}-W{week:02}.md"),
+            Self::Month { year, month } => format!("{MONTHLY_DIR}{year}-{month:02}.md"),
+        }
+    }
+
+    /// Heading for the rollup document.
+    pub fn title(&self) -> String {
+        let (first, last) = self.days();
+        match *self {
+            Self::Week { year, week } => format!("Week {year}-W{week:02} ({first} to {last})"),
+            Self::Month { .. } => first.format("%B %Y").to_string(),
+        }
+    }
+}
+
+/// Summarizes daily logs into rollups with an LLM.
+pub struct RollupSummarizer {
+    llm: Arc<dyn LlmProvider>,
+}
+
+impl RollupSummarizer {
+    pub fn new(llm: Arc<dyn LlmProvider>) -> Self {
+        Self { llm }
+    }
+
+    /// Summarize `logs` (date and content, oldest first) for `period`,
+    /// returning the summary and its LLM cost.
+    pub async fn summarize(
+        &self,
+        period: RollupPeriod,
+        logs: &[(NaiveDate, String)],
+    ) -> Result<(String, Decimal), LlmError> {
+        let prompt = "Condense the user's daily logs into a rollup for the period.\n\n\
+             Rules:\n\
+             - Keep decisions, commitments, facts about people and projects, and open items.\n\
+             - Drop greetings, chit-chat, and anything repeated.\n\
+             - Use short markdown headings and bullet points. Mention dates only when they matter.\n\
+             - Respond with the summary only, without a title.";
+        let transcript = logs
+            .iter()
+            .map(|(date, content)| format!("## {date}\n\n{}", truncate(content, MAX_LOG_CHARS)))
+            .collect::<Vec<_>>()
+            .join("\n\n");
+
+        let response = self
+            .llm
+            .complete(
+                CompletionRequest::new(vec![
+                    ChatMessage::system(prompt),
+                    ChatMessage::user(format!("Period: {}\n\n{}", period.title(), transcript)),
+                ])
+                .with_max_tokens(1024)
+                .with_temperature(0.2),
+            )
+            .await?;
+        let cost = self
+            .llm
+            .calculate_cost(response.input_tokens, response.output_tokens);
+        Ok((response.content.trim().to_string(), cost))
+    }
+}
+
+/// Result of a rollup pass.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct RollupReport {
+    /// Paths of the rollups written.
+    pub written: Vec<String>,
+    pub cost: Decimal,
+}
+
+/// Finished periods covered by `dates` that have no rollup yet, oldest
+/// first, weeks before months.
+fn pending_periods(
+    dates: &[NaiveDate],
+    existing: &[String],
+    today: NaiveDate,
+) -> Vec<(RollupPeriod, Vec<NaiveDate>)> {
+    let mut periods: BTreeMap<RollupPeriod, Vec<NaiveDate>> = BTreeMap::new();
+    for &date in dates {
+        for period in [RollupPeriod::week_of(date), RollupPeriod::month_of(date)] {
+            periods.entry(period).or_default().push(date);
+        }
+    }
+    periods
+        .into_iter()
+        .filter(|(period, _)| period.days().1 < today && !existing.contains(&period.path()))
+        .map(|(period, mut dates)| {
+            dates.sort();
+            (period, dates)
+        })
+        .collect()
+}
+
+impl Workspace {
+    /// Write rollups for finished weeks and months that don't have one.
+    ///
+    /// Writes at most a few rollups per call; the rest follow on later
+    /// passes.
+    pub async fn roll_up_daily_logs(
+        &self,
+        summarizer: &RollupSummarizer,
+    ) -> Result<RollupReport, RollupError> {
+        let dates: Vec<NaiveDate> = self
+            .list(paths::DAILY_DIR)
+            .await?
+            .iter()
+            .filter(|e| !e.is_directory)
+            .filter_map(|e| {
+                let stem = e.name().strip_suffix(".md")?;
+                NaiveDate::parse_from_str(stem, "%Y-%m-%d").ok()
+            })
+            .collect();
+        let mut existing = Vec::new();
+        for dir in [WEEKLY_DIR, MONTHLY_DIR] {
+            existing.extend(self.list(dir).await?.into_iter().map(|e| e.path));
+        }
+
+        let today = Utc::now().date_naive();
+        let mut report = RollupReport::default();
+        for (period, dates) in pending_periods(&dates, &existing, today)
+            .into_iter()
+            .take(MAX_ROLLUPS_PER_PASS)
+        {
+            let mut logs = Vec::with_capacity(dates.len());
+            for date in dates {
+                let path = format!("{}{}.md", paths::DAILY_DIR, date.format("%Y-%m-%d"));
+                let doc = self.read(&path).await?;
+                if !doc.content.trim().is_empty() {
+                    logs.push((date, doc.content));
+                }
+            }
+            if logs.is_empty() {
+                continue;
+            }
+
+            let (summary, cost) = summarizer.summarize(period, &logs).await?;
+            let content = format!(
+                "# {}\n\n{}\n\n_Rolled up from {} daily log(s)._\n",
+                period.title(),
+                summary,
+                logs.len()
+            );
+            let path = period.path();
+            self.write(&path, &content).await?;
+            report.cost += cost;
+            report.written.push(path);
+        }
+        Ok(report)
+    }
+
+    /// Run [`Workspace::roll_up_daily_logs`] every `interval`.
+    pub fn spawn_daily_rollups(
+        self: &Arc<Self>,
+        summarizer: RollupSummarizer,
+        interval: Duration,
+    ) -> tokio::task::JoinHandle<()> {
+        let workspace = Arc::clone(self);
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            loop {
+                ticker.tick().await;
+                match workspace.roll_up_daily_logs(&summarizer).await {
+                    Ok(report) if report.written.is_empty() => {}
+                    Ok(report) => tracing::info!(
+                        "Wrote {} daily log rollups (${}): {}",
+                        report.written.len(),
+                        report.cost,
+                        report.written.join(", ")
+                    ),
+                    Err(e) => tracing::warn!("Daily log rollup failed: {}", e),
+                }
+            }
+        })
+    }
+
+    /// The latest weekly rollup as a prompt section, standing in for the
+    /// raw logs before yesterday. Rollups of weeks that ended more than two
+    /// weeks ago are left to search. Rollups are private, so shared
+    /// conversations get none.
+    pub(super) async fn rollup_context(
+        &self,
+        user_id: &str,
+        ctx: &PromptContext<'_>,
+    ) -> Option<String> {
+        let latest = self
+            .list(WEEKLY_DIR)
+            .await
+            .ok()?
+            .into_iter()
+            .filter(|e| !e.is_directory && self.check_access(user_id, &e.path).is_ok())
+            .map(|e| e.path)
+            .max()?;
+        let (_, last_day) = RollupPeriod::parse_week(&latest)?.days();
+        if (Utc::now().date_naive() - last_day).num_days() > PROMPT_WINDOW_DAYS {
+            return None;
+        }
+        let doc = self.read(&latest).await.ok()?;
+        if doc.content.trim().is_empty() || !self.may_prompt(user_id, ctx, &doc) {
+            return None;
+        }
+        let content = fit_head(doc.content.trim(), self.prompt_budget.rollup, &latest);
+        Some(format!("## Recent Weeks\n\n{}", content))
+    }
+}
+
+/// At most `max` characters of `text`.
+fn truncate(text: &str, max: usize) -> &str {
+    match text.char_indices().nth(max) {
+        Some((cut, _)) => &text[..cut],
+        None => text,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(s: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap()
+    }
+
+    #[test]
+    fn test_period_paths_and_days() {
+        let week = RollupPeriod::week_of(date("2024-01-17"));
+        assert_eq!(week.path(), "daily/weekly/2024-W03.md");
+        assert_eq!(RollupPeriod::parse_week(&week.path()), Some(week));
+        assert_eq!(week.days(), (date("2024-01-15"), date("2024-01-21")));
+
+        // ISO weeks can belong to the previous year.
+        let week = RollupPeriod::week_of(date("2021-01-01"));
+        assert_eq!(week.path(), "daily/weekly/2020-W53.md");
+
+        let month = RollupPeriod::month_of(date("2024-02-10"));
+        assert_eq!(month.path(), "daily/monthly/2024-02.md");
+        assert_eq!(month.days().1, date("2024-02-29"));
+        assert_eq!(month.title(), "February 2024");
+    }
+
+    #[test]
+    fn test_pending_periods_skip_unfinished_and_existing() {
+        let dates = [
+            date("2024-01-15"),
+            date("2024-01-16"),
+            date("2024-01-22"),
+            date("2024-02-01"),
+        ];
+        let existing = vec!["daily/weekly/2024-W03.md".to_string()];
+        let pending = pending_periods(&dates, &existing, date("2024-02-02"));
+        let paths: Vec<String> = pending.iter().map(|(p, _)| p.path()).collect();
+        // W03 exists, W05 (Jan 29 - Feb 4) and February are unfinished.
+        assert_eq!(
+            paths,
+            vec!["daily/weekly/2024-W04.md", "daily/monthly/2024-01.md"]
+        );
+        assert_eq!(pending[1].1.len(), 3);
+    }
+
+    #[cfg(feature = "libsql")]
+    #[tokio::test]
+    async fn test_rollups_are_written_and_prompted() {
+        use async_trait::async_trait;
+
+        use crate::db::libsql_backend::test_db;
+        use crate::llm::{
+            CompletionResponse, FinishReason, ToolCompletionRequest, ToolCompletionResponse,
+        };
+
+        /// Echoes the period it was asked to summarize.
+        struct EchoPeriod;
+
+        #[async_trait]
+        impl LlmProvider for EchoPeriod {
+            fn model_name(&self) -> &str {
+                "echo"
+            }
+
+            fn cost_per_token(&self) -> (Decimal, Decimal) {
+                (Decimal::ZERO, Decimal::ZERO)
+            }
+
+            async fn complete(
+                &self,
+                request: CompletionRequest,
+            ) -> Result<CompletionResponse, LlmError> {
+                let text = &request.messages.last().unwrap().content;
+                let period = text.lines().next().unwrap_or_default();
+                Ok(CompletionResponse {
+                    content: format!("- Shipped the release ({period})"),
+                    input_tokens: 10,
+                    output_tokens: 10,
+                    finish_reason: FinishReason::Stop,
+                    response_id: None,
+                })
+            }
+
+            async fn complete_with_tools(
+                &self,
+                _request: ToolCompletionRequest,
+            ) -> Result<ToolCompletionResponse, LlmError> {
+                unimplemented!()
+            }
+        }
+
+        let (db, _dir) = test_db().await;
+        let workspace = Workspace::new_with_db("default", db);
+
+        // Two days of last week.
+        let last_week = Utc::now().date_naive() - chrono::Duration::days(7);
+        let monday =
+            last_week - chrono::Duration::days(last_week.weekday().num_days_from_monday() as i64);
+        for (day, entry) in [
+            (monday, "Release planning"),
+            (monday.succ_opt().unwrap(), "Release shipped"),
+        ] {
+            workspace
+                .write(
+                    &format!("daily/{}.md", day.format("%Y-%m-%d")),
+                    &format!("[09:00:00] {entry}"),
+                )
+                .await
+                .unwrap();
+        }
+        let week = RollupPeriod::week_of(monday);
+
+        let summarizer = RollupSummarizer::new(Arc::new(EchoPeriod));
+        let report = workspace.roll_up_daily_logs(&summarizer).await.unwrap();
+        assert_eq!(report.written[0], week.path());
+        let weekly = workspace.read(&week.path()).await.unwrap();
+        assert!(weekly.content.starts_with(&format!("# {}", week.title())));
+        assert!(weekly.content.contains("Rolled up from 2 daily log(s)"));
+
+        // Nothing left to do on the next pass.
+        let report = workspace.roll_up_daily_logs(&summarizer).await.unwrap();
+        assert!(report.written.is_empty());
+
+        let prompt = workspace.system_prompt().await.unwrap();
+        assert!(prompt.contains(&format!("## Recent Weeks\n\n# {}", week.title())));
+    }
+}
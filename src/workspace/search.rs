@@ -3,15 +3,30 @@
 //! Uses Reciprocal Rank Fusion (RRF) to combine results from:
 //! 1. PostgreSQL full-text search (ts_rank_cd)
 //! 2. pgvector cosine similarity search
+//! 3. Learned sparse vector matching, when a sparse encoder is set (see
+//!    [`sparse`](crate::workspace::sparse))
 //!
 //! RRF formula: score = sum(1 / (k + rank)) for each retrieval method
 //! This is robust to different score scales and produces better results
 //! than simple score averaging.
+//!
+//! Without an embedding provider only full-text search runs. [`SearchModes`]
+//! reports which methods a search used, so users can tell when recall is
+//! limited to keyword matches or to approximate (hash-based) embeddings.
+//!
+//! Results are chunks. For questions about which documents mention
+//! something, [`aggregate_by_document`] folds chunk scores into one result
+//! per document (see [`ChunkAggregation`]).
 
 use std::collections::HashMap;
 
+use chrono::{DateTime, Utc};
+use serde::Serialize;
 use uuid::Uuid;
 
+use crate::workspace::frontmatter::FieldFilter;
+use crate::workspace::tags::{TAGS_KEY, normalize_tag};
+
 /// Configuration for hybrid search.
 #[derive(Debug, Clone)]
 pub struct SearchConfig {
@@ -23,10 +38,25 @@ pub struct SearchConfig {
     pub use_fts: bool,
     /// Whether to include vector results.
     pub use_vector: bool,
+    /// Whether to include learned sparse results (only run when the
+    /// workspace has a sparse encoder).
+    pub use_sparse: bool,
     /// Minimum score threshold (0.0-1.0).
     pub min_score: f32,
     /// Maximum results to fetch from each method before fusion.
     pub pre_fusion_limit: usize,
+    /// Share of the final score taken by chunk importance (0.0-1.0).
+    pub importance_weight: f32,
+    /// Only search documents whose frontmatter matches all of these.
+    pub filters: Vec<FieldFilter>,
+    /// Only search documents with all of these tags.
+    pub tags: Vec<String>,
+    /// Only search the document at this path and those below it.
+    pub path_prefix: Option<String>,
+    /// Only search documents updated at or after this time.
+    pub updated_after: Option<DateTime<Utc>>,
+    /// Only search documents updated before this time.
+    pub updated_before: Option<DateTime<Utc>>,
 }
 
 impl Default for SearchConfig {
@@ -36,8 +66,15 @@ impl Default for SearchConfig {
             rrf_k: 60,
             use_fts: true,
             use_vector: true,
+            use_sparse: true,
             min_score: 0.0,
             pre_fusion_limit: 50,
+            importance_weight: 0.3,
+            filters: Vec::new(),
+            tags: Vec::new(),
+            path_prefix: None,
+            updated_after: None,
+            updated_before: None,
         }
     }
 }
@@ -55,17 +92,19 @@ impl SearchConfig {
         self
     }
 
-    /// Disable FTS (only use vector search).
+    /// Disable FTS and sparse search (only use vector search).
     pub fn vector_only(mut self) -> Self {
         self.use_fts = false;
         self.use_vector = true;
+        self.use_sparse = false;
         self
     }
 
-    /// Disable vector search (only use FTS).
+    /// Disable vector and sparse search (only use FTS).
     pub fn fts_only(mut self) -> Self {
         self.use_fts = true;
         self.use_vector = false;
+        self.use_sparse = false;
         self
     }
 
@@ -74,6 +113,63 @@ impl SearchConfig {
         self.min_score = score.clamp(0.0, 1.0);
         self
     }
+
+    /// Set how much chunk importance counts against relevance (0 disables it).
+    pub fn with_importance_weight(mut self, weight: f32) -> Self {
+        self.importance_weight = weight.clamp(0.0, 1.0);
+        self
+    }
+
+    /// Restrict results to documents whose frontmatter matches `filter`.
+    pub fn with_filter(mut self, filter: FieldFilter) -> Self {
+        self.filters.push(filter);
+        self
+    }
+
+    /// Restrict results to documents tagged with every one of `tags`.
+    /// Tags are normalized as by [`Workspace::tag`](crate::workspace::Workspace::tag);
+    /// invalid ones are kept as given, so they match nothing.
+    pub fn with_tags<S: AsRef<str>>(mut self, tags: &[S]) -> Self {
+        for tag in tags.iter().map(|t| t.as_ref()) {
+            let tag = normalize_tag(tag).unwrap_or_else(|| tag.to_string());
+            if !self.tags.contains(&tag) {
+                self.tags.push(tag);
+            }
+        }
+        self
+    }
+
+    /// The frontmatter conditions of [`Self::filters`] and [`Self::tags`],
+    /// as evaluated by the storage layer.
+    pub fn field_filters(&self) -> Vec<FieldFilter> {
+        let mut filters = self.filters.clone();
+        filters.extend(
+            self.tags
+                .iter()
+                .map(|tag| FieldFilter::equals(TAGS_KEY, tag.as_str())),
+        );
+        filters
+    }
+
+    /// Restrict results to the document at `prefix` and those below it
+    /// (an empty prefix removes the restriction).
+    pub fn with_path_prefix(mut self, prefix: impl Into<String>) -> Self {
+        let prefix = prefix.into().trim_matches('/').to_string();
+        self.path_prefix = (!prefix.is_empty()).then_some(prefix);
+        self
+    }
+
+    /// Restrict results to documents updated in `[after, before)`; either
+    /// bound may be open.
+    pub fn with_updated_between(
+        mut self,
+        after: Option<DateTime<Utc>>,
+        before: Option<DateTime<Utc>>,
+    ) -> Self {
+        self.updated_after = after;
+        self.updated_before = before;
+        self
+    }
 }
 
 /// A search result with hybrid scoring.
@@ -91,6 +187,16 @@ pub struct SearchResult {
     pub fts_rank: Option<u32>,
     /// Rank in vector results (1-based, None if not in vector results).
     pub vector_rank: Option<u32>,
+    /// Rank in sparse results (1-based, None if not in sparse results).
+    pub sparse_rank: Option<u32>,
+    /// Raw full-text relevance (higher is better; backend-specific scale).
+    pub fts_score: Option<f32>,
+    /// Cosine similarity to the query embedding.
+    pub vector_score: Option<f32>,
+    /// Dot product of the chunk's and the query's sparse vectors.
+    pub sparse_score: Option<f32>,
+    /// Chunk importance blended into `score` (None if not applied).
+    pub importance: Option<f32>,
 }
 
 impl SearchResult {
@@ -104,10 +210,234 @@ impl SearchResult {
         self.vector_rank.is_some()
     }
 
+    /// Check if this result came from sparse search.
+    pub fn from_sparse(&self) -> bool {
+        self.sparse_rank.is_some()
+    }
+
     /// Check if this result came from both methods (hybrid match).
     pub fn is_hybrid(&self) -> bool {
         self.fts_rank.is_some() && self.vector_rank.is_some()
     }
+
+    /// The methods that found this result: any of "keyword", "semantic",
+    /// and "sparse".
+    pub fn matched_by(&self) -> Vec<&'static str> {
+        let mut modes = Vec::with_capacity(3);
+        if self.from_fts() {
+            modes.push("keyword");
+        }
+        if self.from_vector() {
+            modes.push("semantic");
+        }
+        if self.from_sparse() {
+            modes.push("sparse");
+        }
+        modes
+    }
+}
+
+/// Which retrieval methods a search ran.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SearchModes {
+    /// Full-text keyword matching ran.
+    pub keyword: bool,
+    /// Embedding model used for semantic matching, if it ran.
+    pub semantic_model: Option<String>,
+    /// Semantic matching used approximate local embeddings rather than a
+    /// real model.
+    pub approximate: bool,
+    /// Semantic matching was asked for but no embedding provider is set.
+    pub semantic_unavailable: bool,
+    /// Sparse encoder used for learned sparse matching, if it ran.
+    pub sparse_model: Option<String>,
+}
+
+impl SearchModes {
+    /// Modes a search with `config` runs, given the workspace's embedding
+    /// model (`None` without a provider) and whether it is approximate.
+    pub fn for_search(config: &SearchConfig, model: Option<(&str, bool)>) -> Self {
+        let semantic = model.filter(|_| config.use_vector);
+        Self {
+            keyword: config.use_fts,
+            semantic_model: semantic.map(|(name, _)| name.to_string()),
+            approximate: semantic.is_some_and(|(_, approximate)| approximate),
+            semantic_unavailable: config.use_vector && model.is_none(),
+            sparse_model: None,
+        }
+    }
+
+    /// Add the workspace's sparse encoder model (`None` without one), used
+    /// if `config` asks for sparse matching.
+    pub fn with_sparse(mut self, config: &SearchConfig, model: Option<&str>) -> Self {
+        self.sparse_model = model.filter(|_| config.use_sparse).map(str::to_string);
+        self
+    }
+
+    /// Short description, e.g. "keyword + semantic".
+    pub fn label(&self) -> String {
+        let mut modes = Vec::with_capacity(3);
+        if self.keyword {
+            modes.push("keyword");
+        }
+        if self.semantic_model.is_some() {
+            modes.push(if self.approximate {
+                "approximate semantic"
+            } else {
+                "semantic"
+            });
+        }
+        if self.sparse_model.is_some() {
+            modes.push("sparse");
+        }
+        match modes.as_slice() {
+            [] => "none".to_string(),
+            [mode] => format!("{} only", mode),
+            _ => modes.join(" + "),
+        }
+    }
+
+    /// What the modes mean for recall, if it is limited.
+    pub fn note(&self) -> Option<&'static str> {
+        if self.semantic_unavailable {
+            Some(
+                "No embedding provider is configured, so only exact keyword matches were \
+                 found; differently worded memories may be missed. Configure embeddings \
+                 or set EMBEDDING_FALLBACK=true.",
+            )
+        } else if self.approximate {
+            Some(
+                "Semantic matching used local hash embeddings, which match similar \
+                 wording but not synonyms or paraphrases. Configure an embedding provider \
+                 for full recall.",
+            )
+        } else {
+            None
+        }
+    }
+}
+
+/// How chunk scores combine into a document's score in document-level
+/// search.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum ChunkAggregation {
+    /// The best chunk's score.
+    #[default]
+    Max,
+    /// Chunk scores summed best-first, the n-th weighted by `decay^n`, so a
+    /// document with several matching chunks outranks one with a single
+    /// match without long documents winning on volume alone.
+    Sum { decay: f32 },
+}
+
+impl ChunkAggregation {
+    /// Decay used by `sum` when none is given.
+    pub const DEFAULT_DECAY: f32 = 0.5;
+
+    /// Parse `max` or `sum`.
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "max" => Some(Self::Max),
+            "sum" => Some(Self::Sum {
+                decay: Self::DEFAULT_DECAY,
+            }),
+            _ => None,
+        }
+    }
+
+    /// Combine chunk scores sorted best-first.
+    fn combine(&self, scores: impl Iterator<Item = f32>) -> f32 {
+        match self {
+            Self::Max => scores.into_iter().next().unwrap_or(0.0),
+            Self::Sum { decay } => {
+                let mut weight = 1.0;
+                let mut total = 0.0;
+                for score in scores {
+                    total += score * weight;
+                    weight *= decay;
+                }
+                total
+            }
+        }
+    }
+}
+
+/// A document-level search result.
+#[derive(Debug, Clone)]
+pub struct DocumentResult {
+    pub document_id: Uuid,
+    /// Aggregated chunk score (0.0-1.0 normalized).
+    pub score: f32,
+    /// The document's matching chunks, best first.
+    pub chunks: Vec<SearchResult>,
+}
+
+impl DocumentResult {
+    /// The methods that found any of the document's chunks.
+    pub fn matched_by(&self) -> Vec<&'static str> {
+        let mut modes = Vec::with_capacity(3);
+        if self.chunks.iter().any(SearchResult::from_fts) {
+            modes.push("keyword");
+        }
+        if self.chunks.iter().any(SearchResult::from_vector) {
+            modes.push("semantic");
+        }
+        if self.chunks.iter().any(SearchResult::from_sparse) {
+            modes.push("sparse");
+        }
+        modes
+    }
+}
+
+/// Group chunk results by document and score each document from its
+/// chunks. Documents are returned best first, at most `limit` of them.
+pub fn aggregate_by_document(
+    results: Vec<SearchResult>,
+    aggregation: ChunkAggregation,
+    limit: usize,
+) -> Vec<DocumentResult> {
+    let mut documents: Vec<DocumentResult> = Vec::new();
+    let mut index: HashMap<Uuid, usize> = HashMap::new();
+    for result in results {
+        let i = *index.entry(result.document_id).or_insert_with(|| {
+            documents.push(DocumentResult {
+                document_id: result.document_id,
+                score: 0.0,
+                chunks: Vec::new(),
+            });
+            documents.len() - 1
+        });
+        documents[i].chunks.push(result);
+    }
+
+    for doc in &mut documents {
+        doc.chunks.sort_by(|a, b| b.score.total_cmp(&a.score));
+        doc.score = aggregation.combine(doc.chunks.iter().map(|c| c.score));
+    }
+    if let Some(max_score) = documents.iter().map(|d| d.score).reduce(f32::max)
+        && max_score > 0.0
+    {
+        for doc in &mut documents {
+            doc.score /= max_score;
+        }
+    }
+
+    // Stable, so equal scores keep the order of their best chunk.
+    documents.sort_by(|a, b| b.score.total_cmp(&a.score));
+    documents.truncate(limit);
+    documents
+}
+
+/// A document related to another, from [`Workspace::similar`].
+///
+/// [`Workspace::similar`]: crate::workspace::Workspace::similar
+#[derive(Debug, Clone, Serialize)]
+pub struct SimilarDocument {
+    pub document_id: Uuid,
+    pub path: String,
+    /// Best cosine similarity between one of this document's chunks and one
+    /// of the source document's.
+    pub similarity: f32,
 }
 
 /// Raw result from a single search method.
@@ -117,6 +447,8 @@ pub struct RankedResult {
     pub document_id: Uuid,
     pub content: String,
     pub rank: u32, // 1-based rank
+    /// The method's own relevance score (higher is better).
+    pub score: f32,
 }
 
 /// Reciprocal Rank Fusion algorithm.
@@ -137,68 +469,61 @@ pub fn reciprocal_rank_fusion(
     fts_results: Vec<RankedResult>,
     vector_results: Vec<RankedResult>,
     config: &SearchConfig,
+) -> Vec<SearchResult> {
+    reciprocal_rank_fusion_with_sparse(fts_results, vector_results, Vec::new(), config)
+}
+
+/// [`reciprocal_rank_fusion`] with a third list, `sparse_results`, from
+/// learned sparse search, ordered by dot product.
+pub fn reciprocal_rank_fusion_with_sparse(
+    fts_results: Vec<RankedResult>,
+    vector_results: Vec<RankedResult>,
+    sparse_results: Vec<RankedResult>,
+    config: &SearchConfig,
 ) -> Vec<SearchResult> {
     let k = config.rrf_k as f32;
 
-    // Track scores and metadata for each chunk
-    struct ChunkInfo {
-        document_id: Uuid,
-        content: String,
-        score: f32,
-        fts_rank: Option<u32>,
-        vector_rank: Option<u32>,
-    }
-
-    let mut chunk_scores: HashMap<Uuid, ChunkInfo> = HashMap::new();
-
-    // Process FTS results
-    for result in fts_results {
-        let rrf_score = 1.0 / (k + result.rank as f32);
-        chunk_scores
-            .entry(result.chunk_id)
-            .and_modify(|info| {
-                info.score += rrf_score;
-                info.fts_rank = Some(result.rank);
-            })
-            .or_insert(ChunkInfo {
-                document_id: result.document_id,
-                content: result.content,
-                score: rrf_score,
-                fts_rank: Some(result.rank),
-                vector_rank: None,
-            });
+    enum Method {
+        Fts,
+        Vector,
+        Sparse,
     }
 
-    // Process vector results
-    for result in vector_results {
-        let rrf_score = 1.0 / (k + result.rank as f32);
-        chunk_scores
-            .entry(result.chunk_id)
-            .and_modify(|info| {
-                info.score += rrf_score;
-                info.vector_rank = Some(result.rank);
-            })
-            .or_insert(ChunkInfo {
-                document_id: result.document_id,
-                content: result.content,
-                score: rrf_score,
-                fts_rank: None,
-                vector_rank: Some(result.rank),
-            });
+    // Accumulate each chunk's RRF score and its rank and score per method
+    let mut chunk_scores: HashMap<Uuid, SearchResult> = HashMap::new();
+    for (method, ranked) in [
+        (Method::Fts, fts_results),
+        (Method::Vector, vector_results),
+        (Method::Sparse, sparse_results),
+    ] {
+        for result in ranked {
+            let info = chunk_scores
+                .entry(result.chunk_id)
+                .or_insert_with(|| SearchResult {
+                    document_id: result.document_id,
+                    chunk_id: result.chunk_id,
+                    content: result.content,
+                    score: 0.0,
+                    fts_rank: None,
+                    vector_rank: None,
+                    sparse_rank: None,
+                    fts_score: None,
+                    vector_score: None,
+                    sparse_score: None,
+                    importance: None,
+                });
+            info.score += 1.0 / (k + result.rank as f32);
+            let (rank, score) = match method {
+                Method::Fts => (&mut info.fts_rank, &mut info.fts_score),
+                Method::Vector => (&mut info.vector_rank, &mut info.vector_score),
+                Method::Sparse => (&mut info.sparse_rank, &mut info.sparse_score),
+            };
+            *rank = Some(result.rank);
+            *score = Some(result.score);
+        }
     }
 
-    // Convert to SearchResult and sort by score
-    let mut results: Vec<SearchResult> = chunk_scores
-        .into_iter()
-        .map(|(chunk_id, info)| SearchResult {
-            document_id: info.document_id,
-            chunk_id,
-            content: info.content,
-            score: info.score,
-            fts_rank: info.fts_rank,
-            vector_rank: info.vector_rank,
-        })
-        .collect();
+    let mut results: Vec<SearchResult> = chunk_scores.into_values().collect();
 
     // Normalize scores to 0-1 range
     if let Some(max_score) = results.iter().map(|r| r.score).reduce(f32::max)
@@ -237,9 +562,132 @@ mod tests {
             document_id: doc_id,
             content: format!("content for chunk {}", chunk_id),
             rank,
+            score: 1.0 / rank as f32,
+        }
+    }
+
+    fn scored(doc_id: Uuid, score: f32) -> SearchResult {
+        SearchResult {
+            document_id: doc_id,
+            chunk_id: Uuid::new_v4(),
+            content: String::new(),
+            score,
+            fts_rank: Some(1),
+            vector_rank: None,
+            sparse_rank: None,
+            fts_score: None,
+            vector_score: None,
+            sparse_score: None,
+            importance: None,
         }
     }
 
+    #[test]
+    fn test_aggregate_by_document() {
+        let single = Uuid::new_v4();
+        let several = Uuid::new_v4();
+        let results = vec![
+            scored(single, 1.0),
+            scored(several, 0.8),
+            scored(several, 0.6),
+            scored(several, 0.9),
+        ];
+
+        let docs = aggregate_by_document(results.clone(), ChunkAggregation::Max, 10);
+        assert_eq!(docs.len(), 2);
+        assert_eq!(docs[0].document_id, single);
+        assert_eq!(docs[0].score, 1.0);
+        let scores: Vec<f32> = docs[1].chunks.iter().map(|c| c.score).collect();
+        assert_eq!(scores, vec![0.9, 0.8, 0.6]);
+        assert!((docs[1].score - 0.9).abs() < 1e-6);
+
+        // 0.9 + 0.8/2 + 0.6/4 = 1.45 beats the single 1.0 chunk.
+        let sum = ChunkAggregation::parse("sum").unwrap();
+        let docs = aggregate_by_document(results, sum, 1);
+        assert_eq!(docs.len(), 1);
+        assert_eq!(docs[0].document_id, several);
+        assert_eq!(docs[0].score, 1.0);
+        assert_eq!(docs[0].matched_by(), vec!["keyword"]);
+
+        assert_eq!(ChunkAggregation::parse("max"), Some(ChunkAggregation::Max));
+        assert!(ChunkAggregation::parse("mean").is_none());
+        assert!(aggregate_by_document(Vec::new(), sum, 5).is_empty());
+    }
+
+    #[test]
+    fn test_search_modes() {
+        let hybrid = SearchConfig::default();
+
+        let modes = SearchModes::for_search(&hybrid, None);
+        assert!(modes.semantic_unavailable);
+        assert_eq!(modes.label(), "keyword only");
+        assert!(modes.note().unwrap().contains("No embedding provider"));
+
+        let modes = SearchModes::for_search(&hybrid, Some(("text-embedding-3-small", false)));
+        assert_eq!(modes.label(), "keyword + semantic");
+        assert_eq!(
+            modes.semantic_model.as_deref(),
+            Some("text-embedding-3-small")
+        );
+        assert!(modes.note().is_none());
+
+        let modes = SearchModes::for_search(&hybrid, Some(("local-hash-v1", true)));
+        assert_eq!(modes.label(), "keyword + approximate semantic");
+        assert!(modes.note().unwrap().contains("hash embeddings"));
+
+        // Keyword-only searches aren't missing anything they asked for
+        let modes = SearchModes::for_search(&SearchConfig::default().fts_only(), None);
+        assert!(!modes.semantic_unavailable);
+        assert!(modes.note().is_none());
+
+        let modes = SearchModes::for_search(&hybrid, Some(("text-embedding-3-small", false)))
+            .with_sparse(&hybrid, Some("splade-v3"));
+        assert_eq!(modes.label(), "keyword + semantic + sparse");
+        let vector_only = SearchConfig::default().vector_only();
+        let modes = SearchModes::for_search(&vector_only, Some(("text-embedding-3-small", false)))
+            .with_sparse(&vector_only, Some("splade-v3"));
+        assert_eq!(modes.label(), "semantic only");
+    }
+
+    #[test]
+    fn test_matched_by() {
+        let fts = vec![make_result(Uuid::new_v4(), Uuid::new_v4(), 1)];
+        let mut result = reciprocal_rank_fusion(fts, Vec::new(), &SearchConfig::default())
+            .pop()
+            .unwrap();
+        assert_eq!(result.matched_by(), vec!["keyword"]);
+        result.vector_rank = Some(3);
+        assert_eq!(result.matched_by(), vec!["keyword", "semantic"]);
+    }
+
+    #[test]
+    fn test_rrf_with_sparse() {
+        let config = SearchConfig::default().with_limit(10);
+        let doc = Uuid::new_v4();
+        let (all, sparse_only, fts_only) = (Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4());
+
+        let results = reciprocal_rank_fusion_with_sparse(
+            vec![make_result(fts_only, doc, 1), make_result(all, doc, 2)],
+            vec![make_result(all, doc, 1)],
+            vec![make_result(sparse_only, doc, 1), make_result(all, doc, 2)],
+            &config,
+        );
+
+        assert_eq!(results.len(), 3);
+        assert_eq!(results[0].chunk_id, all);
+        assert_eq!(
+            results[0].matched_by(),
+            vec!["keyword", "semantic", "sparse"]
+        );
+        assert_eq!(results[0].sparse_rank, Some(2));
+        assert_eq!(results[0].sparse_score, Some(0.5));
+        let sparse = results.iter().find(|r| r.chunk_id == sparse_only).unwrap();
+        assert_eq!(sparse.matched_by(), vec!["sparse"]);
+        // One first place each, so the two single-method chunks tie.
+        let fts = results.iter().find(|r| r.chunk_id == fts_only).unwrap();
+        assert_eq!(sparse.score, fts.score);
+    }
+
     #[test]
     fn test_rrf_single_method() {
         let config = SearchConfig::default().with_limit(10);
@@ -388,4 +836,51 @@ mod tests {
         assert!(!vector_only.use_fts);
         assert!(vector_only.use_vector);
     }
+
+    #[cfg(feature = "libsql")]
+    #[tokio::test]
+    async fn test_search_by_path_prefix_and_update_time() {
+        use chrono::Duration;
+
+        use crate::db::libsql_backend::test_db;
+        use crate::workspace::Workspace;
+
+        let (db, _dir) = test_db().await;
+        let ws = Workspace::new_with_db("default", db);
+        ws.write("projects/alpha.md", "Alpha launch plan")
+            .await
+            .unwrap();
+        ws.write("projects-old/beta.md", "Beta launch plan")
+            .await
+            .unwrap();
+        ws.write("notes.md", "Launch plan notes").await.unwrap();
+
+        async fn paths(ws: &Workspace, results: Vec<SearchResult>) -> Vec<String> {
+            let mut paths = Vec::new();
+            for r in results {
+                paths.push(ws.document_path(r.document_id).await.unwrap());
+            }
+            paths.sort();
+            paths
+        }
+        let config = SearchConfig::default().with_path_prefix("/projects/");
+        let results = ws.search_with_config("launch", config).await.unwrap();
+        assert_eq!(paths(&ws, results).await, vec!["projects/alpha.md"]);
+
+        let now = Utc::now();
+        let config = SearchConfig::default().with_updated_between(
+            Some(now - Duration::hours(1)),
+            Some(now + Duration::hours(1)),
+        );
+        let results = ws.search_with_config("launch", config).await.unwrap();
+        assert_eq!(results.len(), 3);
+        let config =
+            SearchConfig::default().with_updated_between(Some(now + Duration::hours(1)), None);
+        assert!(
+            ws.search_with_config("launch", config)
+                .await
+                .unwrap()
+                .is_empty()
+        );
+    }
 }
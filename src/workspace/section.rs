@@ -0,0 +1,206 @@
+//! Markdown section editing for structured documents.
+//!
+//! A section is a heading line plus everything up to the next heading of the
+//! same or a higher level. Headings inside fenced code blocks are ignored.
+
+/// Level used when a heading is given without leading `#`s.
+const DEFAULT_LEVEL: usize = 2;
+
+/// Parse `line` as an ATX heading, returning its level and title.
+pub(super) fn parse_heading(line: &str) -> Option<(usize, &str)> {
+    let line = line.trim_end();
+    let level = line.chars().take_while(|&c| c == '#').count();
+    if level == 0 || level > 6 {
+        return None;
+    }
+    let rest = &line[level..];
+    if !rest.is_empty() && !rest.starts_with(' ') {
+        return None;
+    }
+    Some((level, rest.trim().trim_end_matches('#').trim_end()))
+}
+
+/// Whether a blank-line-separated block consists only of headings.
+pub fn is_heading_block(block: &str) -> bool {
+    let mut lines = block.lines().filter(|l| !l.trim().is_empty()).peekable();
+    lines.peek().is_some() && lines.all(|l| parse_heading(l).is_some())
+}
+
+/// Line range of the section titled `title`: the heading line and the index
+/// one past its last line.
+fn find_section(lines: &[&str], title: &str) -> Option<(usize, usize)> {
+    let mut in_fence = false;
+    let mut start: Option<(usize, usize)> = None;
+    for (i, line) in lines.iter().enumerate() {
+        if line.trim_start().starts_with("```") {
+            in_fence = !in_fence;
+            continue;
+        }
+        if in_fence {
+            continue;
+        }
+        let Some((line_level, line_title)) = parse_heading(line) else {
+            continue;
+        };
+        match start {
+            None if line_title.eq_ignore_ascii_case(title) => start = Some((i, line_level)),
+            Some((first, level)) if line_level <= level => return Some((first, i)),
+            _ => {}
+        }
+    }
+    start.map(|(first, _)| (first, lines.len()))
+}
+
+/// Split a heading argument into its level and title.
+fn heading_title(heading: &str) -> (usize, &str) {
+    parse_heading(heading.trim()).unwrap_or((DEFAULT_LEVEL, heading.trim()))
+}
+
+/// Body of the section titled `heading` (without the heading line), or
+/// `None` if the document has no such section.
+pub fn section_content(document: &str, heading: &str) -> Option<String> {
+    let (_, title) = heading_title(heading);
+    let lines: Vec<&str> = document.lines().collect();
+    let (start, end) = find_section(&lines, title)?;
+    Some(lines[start + 1..end].join("\n").trim().to_string())
+}
+
+/// Insert `content` at the end of the section titled `heading`.
+///
+/// `heading` may be a bare title (`Preferences`, matched case-insensitively
+/// at any level) or include its level (`### Preferences`). If no such
+/// section exists, it is appended to the end of the document at the given
+/// level, or level 2 for a bare title.
+pub fn insert_into_section(document: &str, heading: &str, content: &str) -> String {
+    let (level, title) = heading_title(heading);
+    let content = content.trim();
+    let lines: Vec<&str> = document.lines().collect();
+
+    let Some((_, end)) = find_section(&lines, title) else {
+        let heading_line = format!("{} {}", "#".repeat(level), title);
+        let body = document.trim_end();
+        return if body.is_empty() {
+            format!("{heading_line}\n\n{content}\n")
+        } else {
+            format!("{body}\n\n{heading_line}\n\n{content}\n")
+        };
+    };
+
+    // Drop blank lines at the end of the section so the new content sits
+    // one blank line below the last existing line.
+    let mut insert_at = end;
+    while insert_at > 0 && lines[insert_at - 1].trim().is_empty() {
+        insert_at -= 1;
+    }
+
+    let mut out: Vec<&str> = lines[..insert_at].to_vec();
+    out.push("");
+    out.push(content);
+    if end < lines.len() {
+        out.push("");
+        out.extend_from_slice(&lines[end..]);
+    }
+    let mut result = out.join("\n");
+    result.push('\n');
+    result
+}
+
+/// Replace the body of the section titled `heading` with `content`,
+/// keeping its heading (and anything after the section) as is. A missing
+/// section is created as in [`insert_into_section`].
+pub fn replace_section(document: &str, heading: &str, content: &str) -> String {
+    let (_, title) = heading_title(heading);
+    let lines: Vec<&str> = document.lines().collect();
+    let Some((start, end)) = find_section(&lines, title) else {
+        return insert_into_section(document, heading, content);
+    };
+
+    let content = content.trim();
+    let mut out: Vec<&str> = lines[..=start].to_vec();
+    if !content.is_empty() {
+        out.push("");
+        out.push(content);
+    }
+    if end < lines.len() {
+        out.push("");
+        out.extend_from_slice(&lines[end..]);
+    }
+    let mut result = out.join("\n");
+    result.push('\n');
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DOC: &str = "# Memory\n\n## Preferences\n\nLikes tea\n\n## Work\n\nWorks at Acme\n";
+
+    #[test]
+    fn test_insert_into_existing_section() {
+        let out = insert_into_section(DOC, "preferences", "Hates coffee");
+        assert_eq!(
+            out,
+            "# Memory\n\n## Preferences\n\nLikes tea\n\nHates coffee\n\n## Work\n\nWorks at Acme\n"
+        );
+
+        let out = insert_into_section(DOC, "## Work", "Team lead since May");
+        assert!(out.ends_with("Works at Acme\n\nTeam lead since May\n"));
+    }
+
+    #[test]
+    fn test_subsections_stay_inside_parent() {
+        let doc = "## Work\n\nAcme\n\n### Projects\n\nAlpha\n\n## Family\n\nTwo kids\n";
+        let out = insert_into_section(doc, "Work", "Remote on Fridays");
+        assert!(out.contains("Alpha\n\nRemote on Fridays\n\n## Family"));
+    }
+
+    #[test]
+    fn test_missing_section_is_created() {
+        let out = insert_into_section(DOC, "Health", "Allergic to penicillin");
+        assert!(out.ends_with("Works at Acme\n\n## Health\n\nAllergic to penicillin\n"));
+
+        let out = insert_into_section("", "### Travel", "Window seats");
+        assert_eq!(out, "### Travel\n\nWindow seats\n");
+    }
+
+    #[test]
+    fn test_headings_in_code_fences_are_ignored() {
+        let doc = "## Notes\n\n```\n## Work\n```\n";
+        let out = insert_into_section(doc, "Work", "Acme");
+        assert!(out.ends_with("```\n\n## Work\n\nAcme\n"));
+    }
+
+    #[test]
+    fn test_section_content() {
+        let doc = "## Work\n\nAcme\n\n### Projects\n\nAlpha\n\n## Family\n\nTwo kids\n";
+        assert_eq!(
+            section_content(doc, "work").unwrap(),
+            "Acme\n\n### Projects\n\nAlpha"
+        );
+        assert_eq!(section_content(doc, "## Family").unwrap(), "Two kids");
+        assert!(section_content(doc, "Health").is_none());
+    }
+
+    #[test]
+    fn test_replace_section() {
+        let doc = "## Work\n\nAcme\n\n### Projects\n\nAlpha\n\n## Family\n\nTwo kids\n";
+        assert_eq!(
+            replace_section(doc, "work", "Globex"),
+            "## Work\n\nGlobex\n\n## Family\n\nTwo kids\n"
+        );
+        assert_eq!(
+            replace_section(doc, "Family", "Three kids"),
+            "## Work\n\nAcme\n\n### Projects\n\nAlpha\n\n## Family\n\nThree kids\n"
+        );
+        assert!(replace_section(doc, "Health", "Fine").ends_with("## Health\n\nFine\n"));
+    }
+
+    #[test]
+    fn test_is_heading_block() {
+        assert!(is_heading_block("## Preferences"));
+        assert!(!is_heading_block("## Preferences\nLikes tea"));
+        assert!(!is_heading_block("#hashtag"));
+        assert!(!is_heading_block(""));
+    }
+}
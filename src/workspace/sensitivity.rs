@@ -0,0 +1,197 @@
+//! Document sensitivity labels.
+//!
+//! A `sensitivity` frontmatter field says where a document's content may
+//! go:
+//!
+//! ```text
+//! label      quoted in              sent by external actions
+//! public     any conversation       freely
+//! internal   any conversation       only with approval
+//! private    main sessions only     only with approval, never from a shared session
+//! ```
+//!
+//! Unlabelled documents are public, except the user's private memory
+//! (MEMORY.md, USER.md, daily logs, and documents tagged `private`), which
+//! is private. A label can raise that default but not lower it, so
+//! `sensitivity: public` in USER.md changes nothing.
+//!
+//! The runtime enforces the labels rather than asking the model to: private
+//! documents are left out of prompts and memory tools in shared
+//! conversations ([`MemoryDocument::is_private`]), and the arguments of
+//! external actions are compared against [`Workspace::egress_documents`] by
+//! the egress guard ([`crate::safety::check_egress`]).
+
+use serde::Serialize;
+
+use crate::error::WorkspaceError;
+use crate::workspace::frontmatter::FieldFilter;
+use crate::workspace::tags::{PRIVATE_TAG, TAGS_KEY};
+use crate::workspace::{MemoryDocument, Workspace, paths};
+
+/// Frontmatter key holding a document's label.
+pub const SENSITIVITY_KEY: &str = "sensitivity";
+
+/// Where a document's content may go, least restricted first.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Sensitivity {
+    /// Quoted anywhere and sent freely.
+    #[default]
+    Public,
+    /// Quoted anywhere; external actions carrying it need approval.
+    Internal,
+    /// Quoted only in main sessions; external actions carrying it need
+    /// approval and are refused in shared sessions.
+    Private,
+}
+
+impl std::str::FromStr for Sensitivity {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "public" => Ok(Self::Public),
+            "internal" => Ok(Self::Internal),
+            "private" => Ok(Self::Private),
+            _ => Err(format!(
+                "invalid sensitivity '{}', expected one of: public, internal, private",
+                s
+            )),
+        }
+    }
+}
+
+impl std::fmt::Display for Sensitivity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Public => write!(f, "public"),
+            Self::Internal => write!(f, "internal"),
+            Self::Private => write!(f, "private"),
+        }
+    }
+}
+
+impl MemoryDocument {
+    /// The document's effective sensitivity: its label, raised to private
+    /// for the user's private memory. Unknown labels are ignored.
+    pub fn sensitivity(&self) -> Sensitivity {
+        let default = if paths::is_private(&self.path) || self.has_tag(PRIVATE_TAG) {
+            Sensitivity::Private
+        } else {
+            Sensitivity::Public
+        };
+        self.frontmatter
+            .fields
+            .get(SENSITIVITY_KEY)
+            .and_then(|v| v.as_str())
+            .and_then(|v| v.parse::<Sensitivity>().ok())
+            .map_or(default, |label| label.max(default))
+    }
+
+    /// Whether the document stays out of shared conversations.
+    pub fn is_private(&self) -> bool {
+        self.sensitivity() == Sensitivity::Private
+    }
+}
+
+impl Workspace {
+    /// Documents at least as sensitive as `min`, which the egress guard
+    /// compares outgoing content against: MEMORY.md, USER.md, and every
+    /// labelled or `private`-tagged document. Unlabelled daily logs are not
+    /// included.
+    pub async fn egress_documents(
+        &self,
+        min: Sensitivity,
+    ) -> Result<Vec<MemoryDocument>, WorkspaceError> {
+        let mut docs = Vec::new();
+        for path in [paths::MEMORY, paths::USER] {
+            match self.read(path).await {
+                Ok(doc) => docs.push(doc),
+                Err(WorkspaceError::DocumentNotFound { .. }) => {}
+                Err(e) => return Err(e),
+            }
+        }
+        docs.extend(
+            self.find_by_frontmatter(&[FieldFilter::exists(SENSITIVITY_KEY)])
+                .await?,
+        );
+        docs.extend(
+            self.find_by_frontmatter(&[FieldFilter::equals(TAGS_KEY, PRIVATE_TAG)])
+                .await?,
+        );
+        let mut seen = std::collections::HashSet::new();
+        docs.retain(|d| seen.insert(d.id) && d.sensitivity() >= min);
+        Ok(docs)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::workspace::DocumentMetadata;
+
+    fn doc(path: &str, content: &str) -> MemoryDocument {
+        MemoryDocument {
+            content: content.to_string(),
+            frontmatter: DocumentMetadata::parse(content),
+            ..MemoryDocument::new("default", None, path)
+        }
+    }
+
+    #[test]
+    fn test_parse_and_display() {
+        assert_eq!("Internal".parse::<Sensitivity>(), Ok(Sensitivity::Internal));
+        assert!("secret".parse::<Sensitivity>().is_err());
+        assert_eq!(Sensitivity::Private.to_string(), "private");
+        assert!(Sensitivity::Public < Sensitivity::Internal);
+    }
+
+    #[test]
+    fn test_labels_raise_but_never_lower_the_default() {
+        assert_eq!(doc("notes.md", "Hi").sensitivity(), Sensitivity::Public);
+        let internal = doc("notes.md", "---\nsensitivity: internal\n---\nHi");
+        assert_eq!(internal.sensitivity(), Sensitivity::Internal);
+        assert!(!internal.is_private());
+        assert!(doc("notes.md", "---\nsensitivity: private\n---\nHi").is_private());
+        assert!(doc("notes.md", "---\ntags:\n  - Private\n---\nHi").is_private());
+
+        let user = doc(paths::USER, "---\nsensitivity: public\n---\nName: Alice");
+        assert_eq!(user.sensitivity(), Sensitivity::Private);
+        let unknown = doc("notes.md", "---\nsensitivity: secret\n---\nHi");
+        assert_eq!(unknown.sensitivity(), Sensitivity::Public);
+    }
+
+    #[cfg(feature = "libsql")]
+    #[tokio::test]
+    async fn test_egress_documents() {
+        use crate::db::libsql_backend::test_db;
+
+        let (db, _dir) = test_db().await;
+        let ws = Workspace::new_with_db("default", db);
+
+        ws.write(paths::USER, "Name: Alice").await.unwrap();
+        ws.write("roadmap.md", "---\nsensitivity: internal\n---\nQ3 plans")
+            .await
+            .unwrap();
+        ws.write("press.md", "---\nsensitivity: public\n---\nLaunch!")
+            .await
+            .unwrap();
+        ws.write("bid.md", "Floor price 40k").await.unwrap();
+        ws.tag("bid.md", &["private"]).await.unwrap();
+        ws.write("notes.md", "Nothing to see").await.unwrap();
+
+        let paths = |docs: Vec<MemoryDocument>| {
+            let mut paths: Vec<String> = docs.into_iter().map(|d| d.path).collect();
+            paths.sort();
+            paths
+        };
+        assert_eq!(
+            paths(ws.egress_documents(Sensitivity::Internal).await.unwrap()),
+            vec!["USER.md", "bid.md", "roadmap.md"]
+        );
+        assert_eq!(
+            paths(ws.egress_documents(Sensitivity::Private).await.unwrap()),
+            vec!["USER.md", "bid.md"]
+        );
+    }
+}
@@ -0,0 +1,298 @@
+//! Shared workspace namespaces.
+//!
+//! A workspace is normally scoped to a single agent. A shared namespace is a
+//! separate document scope that several agents mount at a path prefix, so a
+//! document one agent writes under `shared/research/` is immediately visible
+//! to every other member without copying. Each member mounts the namespace
+//! either read-only or read-write.
+//!
+//! Namespaces are stored as JSON in the owner's settings (key
+//! `workspace_namespaces`) and read at startup:
+//!
+//! ```json
+//! {
+//!   "namespaces": {
+//!     "research": {
+//!       "mount": "shared",
+//!       "members": {
+//!         "default": "read_write",
+//!         "6f1c2f1e-0d7a-4c55-9a43-1f1a3c9e2b10": "read"
+//!       }
+//!     }
+//!   }
+//! }
+//! ```
+//!
+//! Members are agent IDs, with `default` standing for the main agent (the
+//! workspace without an agent ID). `mount` defaults to `shared/<name>`.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+
+use crate::db::Database;
+use crate::error::DatabaseError;
+
+/// Settings key the namespace definitions are stored under.
+pub const SHARED_NAMESPACES_SETTING_KEY: &str = "workspace_namespaces";
+
+/// Member key for the main agent (no agent ID).
+pub const DEFAULT_MEMBER: &str = "default";
+
+/// Errors from loading or validating namespace definitions.
+#[derive(Debug, thiserror::Error)]
+pub enum NamespaceError {
+    #[error("Invalid workspace namespaces: {0}")]
+    Invalid(String),
+
+    #[error(transparent)]
+    Database(#[from] DatabaseError),
+}
+
+/// How a member may use a mounted namespace.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AccessMode {
+    Read,
+    ReadWrite,
+}
+
+/// One shared namespace and who mounts it.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NamespaceConfig {
+    /// Workspace directory members see the namespace under.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mount: Option<String>,
+    /// Agent ID (or `default`) to access mode.
+    #[serde(default)]
+    pub members: HashMap<String, AccessMode>,
+}
+
+/// All shared namespaces by name.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SharedNamespaces {
+    #[serde(default)]
+    pub namespaces: HashMap<String, NamespaceConfig>,
+}
+
+impl SharedNamespaces {
+    /// Load the definitions from the owner's settings. A missing setting
+    /// means no namespaces.
+    pub async fn load(store: Arc<dyn Database>, owner_id: &str) -> Result<Self, NamespaceError> {
+        let namespaces: Self = match store
+            .get_setting(owner_id, SHARED_NAMESPACES_SETTING_KEY)
+            .await?
+        {
+            Some(value) => {
+                serde_json::from_value(value).map_err(|e| NamespaceError::Invalid(e.to_string()))?
+            }
+            None => Self::default(),
+        };
+        namespaces.validate()?;
+        Ok(namespaces)
+    }
+
+    /// Check that names and mounts are usable and that no two mounts
+    /// overlap (a path must resolve to exactly one scope).
+    pub fn validate(&self) -> Result<(), NamespaceError> {
+        let mut mounts: Vec<(String, &str)> = Vec::new();
+        for (name, ns) in &self.namespaces {
+            if name.trim().is_empty() {
+                return Err(NamespaceError::Invalid("namespace name is empty".into()));
+            }
+            let mount = mount_path(name, ns);
+            if mount.is_empty() {
+                return Err(NamespaceError::Invalid(format!(
+                    "namespace '{}' has an empty mount",
+                    name
+                )));
+            }
+            for member in ns.members.keys() {
+                if member != DEFAULT_MEMBER && Uuid::parse_str(member).is_err() {
+                    return Err(NamespaceError::Invalid(format!(
+                        "namespace '{}' member '{}' is not an agent ID or '{}'",
+                        name, member, DEFAULT_MEMBER
+                    )));
+                }
+            }
+            if let Some((_, other)) = mounts
+                .iter()
+                .find(|(m, _)| is_within(&mount, m) || is_within(m, &mount))
+            {
+                return Err(NamespaceError::Invalid(format!(
+                    "mounts of '{}' and '{}' overlap",
+                    name, other
+                )));
+            }
+            mounts.push((mount, name));
+        }
+        Ok(())
+    }
+
+    /// Mounts visible to the agent with `agent_id` (`None` for the main
+    /// agent), sorted by mount path.
+    pub fn mounts_for(&self, agent_id: Option<Uuid>) -> Vec<SharedMount> {
+        let member = agent_id.map_or_else(|| DEFAULT_MEMBER.to_string(), |id| id.to_string());
+        let mut mounts: Vec<SharedMount> = self
+            .namespaces
+            .iter()
+            .filter_map(|(name, ns)| {
+                let mode = ns.members.get(&member)?;
+                Some(SharedMount::new(name, mount_path(name, ns), *mode))
+            })
+            .collect();
+        mounts.sort_by(|a, b| a.mount.cmp(&b.mount));
+        mounts
+    }
+}
+
+/// A namespace as seen from one workspace.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SharedMount {
+    /// Namespace name.
+    pub namespace: String,
+    /// Normalized directory the namespace appears under (no slashes at
+    /// either end).
+    pub mount: String,
+    pub mode: AccessMode,
+}
+
+impl SharedMount {
+    pub fn new(namespace: impl Into<String>, mount: impl AsRef<str>, mode: AccessMode) -> Self {
+        Self {
+            namespace: namespace.into(),
+            mount: super::normalize_directory(mount.as_ref()),
+            mode,
+        }
+    }
+
+    /// Agent ID the namespace's documents are stored under.
+    pub fn scope(&self) -> Uuid {
+        namespace_scope(&self.namespace)
+    }
+
+    pub fn is_writable(&self) -> bool {
+        self.mode == AccessMode::ReadWrite
+    }
+
+    /// Path inside the namespace for a workspace path under the mount.
+    pub(super) fn strip(&self, path: &str) -> Option<String> {
+        path.strip_prefix(&self.mount)?
+            .strip_prefix('/')
+            .map(str::to_string)
+    }
+
+    /// Workspace path for a path inside the namespace.
+    pub(super) fn join(&self, inner: &str) -> String {
+        if inner.is_empty() {
+            self.mount.clone()
+        } else {
+            format!("{}/{}", self.mount, inner)
+        }
+    }
+}
+
+/// Deterministic storage scope for a namespace, so every member resolves
+/// the same documents without a lookup table.
+pub fn namespace_scope(name: &str) -> Uuid {
+    let digest = Sha256::digest(format!("ironclaw:workspace-namespace:{}", name).as_bytes());
+    let mut bytes = [0u8; 16];
+    bytes.copy_from_slice(&digest[..16]);
+    uuid::Builder::from_custom_bytes(bytes).into_uuid()
+}
+
+/// Directory the namespace `name` is mounted under.
+pub fn mount_path(name: &str, ns: &NamespaceConfig) -> String {
+    match ns.mount {
+        Some(ref mount) => super::normalize_directory(mount),
+        None => format!("shared/{}", super::normalize_directory(name)),
+    }
+}
+
+/// Whether `path` is `dir` or lies under it, on whole segments.
+fn is_within(path: &str, dir: &str) -> bool {
+    path == dir
+        || path
+            .strip_prefix(dir)
+            .is_some_and(|rest| rest.starts_with('/'))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn namespaces(json: serde_json::Value) -> SharedNamespaces {
+        serde_json::from_value(json).unwrap()
+    }
+
+    #[test]
+    fn test_mounts_for_members() {
+        let writer = Uuid::new_v4();
+        let ns = namespaces(serde_json::json!({
+            "namespaces": {
+                "research": {
+                    "mount": "/library/",
+                    "members": { "default": "read", (writer.to_string()): "read_write" }
+                },
+                "drafts": { "members": { (writer.to_string()): "read" } }
+            }
+        }));
+        ns.validate().unwrap();
+
+        let main = ns.mounts_for(None);
+        assert_eq!(
+            main,
+            vec![SharedMount::new("research", "library", AccessMode::Read)]
+        );
+
+        let mounts = ns.mounts_for(Some(writer));
+        assert_eq!(mounts.len(), 2);
+        assert_eq!(mounts[0].mount, "library");
+        assert!(mounts[0].is_writable());
+        assert_eq!(mounts[1].mount, "shared/drafts");
+        assert!(!mounts[1].is_writable());
+
+        assert!(ns.mounts_for(Some(Uuid::new_v4())).is_empty());
+    }
+
+    #[test]
+    fn test_validate_rejects_overlap_and_bad_members() {
+        let overlapping = namespaces(serde_json::json!({
+            "namespaces": {
+                "a": { "mount": "shared" },
+                "b": { "mount": "shared/b" }
+            }
+        }));
+        assert!(overlapping.validate().is_err());
+
+        let siblings = namespaces(serde_json::json!({
+            "namespaces": {
+                "a": { "mount": "shared/a" },
+                "b": { "mount": "shared/ab" }
+            }
+        }));
+        assert!(siblings.validate().is_ok());
+
+        let bad_member = namespaces(serde_json::json!({
+            "namespaces": { "a": { "members": { "writer": "read" } } }
+        }));
+        assert!(bad_member.validate().is_err());
+    }
+
+    #[test]
+    fn test_mount_strip_and_join() {
+        let mount = SharedMount::new("research", "shared/research/", AccessMode::ReadWrite);
+        assert_eq!(
+            mount.strip("shared/research/notes.md").as_deref(),
+            Some("notes.md")
+        );
+        assert_eq!(mount.strip("shared/researchers/x.md"), None);
+        assert_eq!(mount.strip("shared/research"), None);
+        assert_eq!(mount.join("notes.md"), "shared/research/notes.md");
+        assert_eq!(mount.scope(), namespace_scope("research"));
+        assert_ne!(namespace_scope("research"), namespace_scope("drafts"));
+    }
+}
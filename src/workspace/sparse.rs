@@ -0,0 +1,377 @@
+//! Learned sparse vectors as a third search leg.
+//!
+//! Sparse encoders such as SPLADE and BM42 turn text into weights over a
+//! model vocabulary: a few hundred terms at most, each weighted by how much
+//! it says about the text, including related terms the text never uses.
+//! Matching on them is as precise as keyword search on identifiers, version
+//! numbers, and error codes, where dense embeddings blur near neighbours
+//! together, yet still bridges differently worded notes.
+//!
+//! With a [`SparseEncoder`] set ([`Workspace::with_sparse_encoder`]), each
+//! chunk's vector is stored in an inverted index as it is indexed, and
+//! search ranks chunks by the dot product of their weights with the
+//! query's. That ranking is fused by RRF with the full-text and vector
+//! results like any other leg (see [`SearchConfig::use_sparse`]).
+//! Chunks indexed before an encoder was set have no vector until their
+//! document is written again or reindexed.
+//!
+//! [`TeiSparseEncoder`] calls the `/embed_sparse` endpoint of Hugging Face
+//! text-embeddings-inference, which serves SPLADE models.
+
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+
+use crate::workspace::{EmbeddingError, SearchConfig, Workspace};
+
+/// Most terms kept per chunk; the lowest-weighted rest add little to any
+/// score but would grow the index.
+pub const MAX_CHUNK_TERMS: usize = 256;
+
+/// Texts sent per request (the text-embeddings-inference default limit).
+const BATCH_SIZE: usize = 32;
+
+/// Weights over a sparse encoder's vocabulary.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SparseVector {
+    /// `(vocabulary index, weight)` pairs with positive weights, ordered by
+    /// index.
+    pub terms: Vec<(u32, f32)>,
+}
+
+impl SparseVector {
+    /// Build from `(index, weight)` pairs, summing repeated indices and
+    /// dropping terms without a positive weight.
+    pub fn new(terms: impl IntoIterator<Item = (u32, f32)>) -> Self {
+        let mut merged: BTreeMap<u32, f32> = BTreeMap::new();
+        for (index, weight) in terms {
+            *merged.entry(index).or_default() += weight;
+        }
+        Self {
+            terms: merged
+                .into_iter()
+                .filter(|(_, w)| w.is_finite() && *w > 0.0)
+                .collect(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.terms.is_empty()
+    }
+
+    /// Keep only the `max` highest-weighted terms.
+    pub fn pruned(mut self, max: usize) -> Self {
+        if self.terms.len() > max {
+            self.terms.sort_by(|a, b| b.1.total_cmp(&a.1));
+            self.terms.truncate(max);
+            self.terms.sort_by_key(|(index, _)| *index);
+        }
+        self
+    }
+
+    /// Sum of the products of the weights of terms both vectors have.
+    pub fn dot(&self, other: &SparseVector) -> f32 {
+        let (mut i, mut j, mut sum) = (0, 0, 0.0);
+        while i < self.terms.len() && j < other.terms.len() {
+            let ((a, wa), (b, wb)) = (self.terms[i], other.terms[j]);
+            match a.cmp(&b) {
+                std::cmp::Ordering::Less => i += 1,
+                std::cmp::Ordering::Greater => j += 1,
+                std::cmp::Ordering::Equal => {
+                    sum += wa * wb;
+                    i += 1;
+                    j += 1;
+                }
+            }
+        }
+        sum
+    }
+}
+
+/// Trait for learned sparse encoders.
+#[async_trait]
+pub trait SparseEncoder: Send + Sync {
+    /// Get the model name.
+    fn model_name(&self) -> &str;
+
+    /// Encode texts for indexing, one vector per text.
+    async fn encode_batch(&self, texts: &[String]) -> Result<Vec<SparseVector>, EmbeddingError>;
+
+    /// Encode a search query.
+    ///
+    /// Default implementation encodes it like a document; models with a
+    /// separate query encoding (BM42 weighs query terms by IDF instead)
+    /// override this.
+    async fn encode_query(&self, query: &str) -> Result<SparseVector, EmbeddingError> {
+        self.encode_batch(&[query.to_string()])
+            .await?
+            .into_iter()
+            .next()
+            .ok_or_else(|| EmbeddingError::InvalidResponse("No sparse vector returned".to_string()))
+    }
+}
+
+/// Sparse encoder served by text-embeddings-inference.
+pub struct TeiSparseEncoder {
+    client: reqwest::Client,
+    base_url: String,
+    model: String,
+}
+
+impl TeiSparseEncoder {
+    /// Create an encoder for the server at `base_url`, recording `model` as
+    /// the model it serves.
+    pub fn new(base_url: impl Into<String>, model: impl Into<String>) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            base_url: base_url.into().trim_end_matches('/').to_string(),
+            model: model.into(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct TeiSparseRequest<'a> {
+    inputs: &'a [String],
+    truncate: bool,
+}
+
+#[derive(Debug, Deserialize)]
+struct TeiSparseValue {
+    index: u32,
+    value: f32,
+}
+
+fn from_tei(values: Vec<TeiSparseValue>) -> SparseVector {
+    SparseVector::new(values.into_iter().map(|v| (v.index, v.value)))
+}
+
+#[async_trait]
+impl SparseEncoder for TeiSparseEncoder {
+    fn model_name(&self) -> &str {
+        &self.model
+    }
+
+    async fn encode_batch(&self, texts: &[String]) -> Result<Vec<SparseVector>, EmbeddingError> {
+        let mut vectors = Vec::with_capacity(texts.len());
+        for batch in texts.chunks(BATCH_SIZE) {
+            let response = self
+                .client
+                .post(format!("{}/embed_sparse", self.base_url))
+                .json(&TeiSparseRequest {
+                    inputs: batch,
+                    truncate: true,
+                })
+                .send()
+                .await?;
+
+            let status = response.status();
+            if status == reqwest::StatusCode::TOO_MANY_REQUESTS {
+                let retry_after = response
+                    .headers()
+                    .get("retry-after")
+                    .and_then(|v| v.to_str().ok())
+                    .and_then(|s| s.parse::<u64>().ok())
+                    .map(std::time::Duration::from_secs);
+                return Err(EmbeddingError::RateLimited { retry_after });
+            }
+            if !status.is_success() {
+                let error_text = response.text().await.unwrap_or_default();
+                return Err(EmbeddingError::HttpError(format!(
+                    "Status {}: {}",
+                    status, error_text
+                )));
+            }
+
+            let result: Vec<Vec<TeiSparseValue>> = response.json().await.map_err(|e| {
+                EmbeddingError::InvalidResponse(format!("Failed to parse response: {}", e))
+            })?;
+            if result.len() != batch.len() {
+                return Err(EmbeddingError::InvalidResponse(format!(
+                    "Expected {} sparse vectors, got {}",
+                    batch.len(),
+                    result.len()
+                )));
+            }
+            vectors.extend(result.into_iter().map(from_tei));
+        }
+        Ok(vectors)
+    }
+}
+
+impl Workspace {
+    /// Set the sparse encoder, adding learned sparse matching to search.
+    /// Existing documents are only matched once reindexed.
+    pub fn with_sparse_encoder(mut self, encoder: Arc<dyn SparseEncoder>) -> Self {
+        self.sparse = Some(encoder);
+        self
+    }
+
+    /// Sparse vectors of `chunks` for indexing, each None without an
+    /// encoder or if encoding failed.
+    pub(super) async fn sparse_vectors(&self, chunks: &[String]) -> Vec<Option<SparseVector>> {
+        let Some(encoder) = &self.sparse else {
+            return vec![None; chunks.len()];
+        };
+        if chunks.is_empty() {
+            return Vec::new();
+        }
+        match encoder.encode_batch(chunks).await {
+            Ok(vectors) if vectors.len() == chunks.len() => vectors
+                .into_iter()
+                .map(|v| Some(v.pruned(MAX_CHUNK_TERMS)))
+                .collect(),
+            Ok(vectors) => {
+                tracing::warn!(
+                    "Sparse encoder returned {} vectors for {} chunks",
+                    vectors.len(),
+                    chunks.len()
+                );
+                vec![None; chunks.len()]
+            }
+            Err(e) => {
+                tracing::warn!("Failed to generate sparse vectors: {}", e);
+                vec![None; chunks.len()]
+            }
+        }
+    }
+
+    /// Sparse vector of a search query, if `config` uses the sparse leg and
+    /// an encoder is set. An encoder failure only drops that leg.
+    pub(super) async fn sparse_query(
+        &self,
+        query: &str,
+        config: &SearchConfig,
+    ) -> Option<SparseVector> {
+        let encoder = self.sparse.as_ref().filter(|_| config.use_sparse)?;
+        if query.trim().is_empty() {
+            return None;
+        }
+        match encoder.encode_query(query).await {
+            Ok(vector) => Some(vector),
+            Err(e) => {
+                tracing::warn!("Failed to encode query for sparse search: {}", e);
+                None
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sparse_vector_new_merges_and_drops() {
+        let v = SparseVector::new([(7, 0.5), (2, 1.0), (7, 0.25), (3, 0.0), (4, -1.0)]);
+        assert_eq!(v.terms, vec![(2, 1.0), (7, 0.75)]);
+        assert!(SparseVector::new([]).is_empty());
+    }
+
+    #[test]
+    fn test_sparse_vector_pruned_keeps_heaviest() {
+        let v = SparseVector::new([(1, 0.1), (2, 0.9), (3, 0.5), (4, 0.2)]).pruned(2);
+        assert_eq!(v.terms, vec![(2, 0.9), (3, 0.5)]);
+    }
+
+    #[test]
+    fn test_sparse_vector_dot() {
+        let a = SparseVector::new([(1, 2.0), (3, 1.0), (5, 4.0)]);
+        let b = SparseVector::new([(3, 0.5), (4, 1.0), (5, 0.25)]);
+        assert_eq!(a.dot(&b), 1.5);
+        assert_eq!(a.dot(&SparseVector::default()), 0.0);
+    }
+
+    #[test]
+    fn test_parse_tei_response() {
+        let body = r#"[[{"index": 2054, "value": 0.91}, {"index": 17, "value": 0.4}], []]"#;
+        let parsed: Vec<Vec<TeiSparseValue>> = serde_json::from_str(body).unwrap();
+        let vectors: Vec<SparseVector> = parsed.into_iter().map(from_tei).collect();
+        assert_eq!(vectors[0].terms, vec![(17, 0.4), (2054, 0.91)]);
+        assert!(vectors[1].is_empty());
+    }
+
+    /// Encodes each word as its own term and expands "car" to "automobile",
+    /// standing in for a model's learned expansion.
+    struct WordEncoder;
+
+    fn term(word: &str) -> u32 {
+        word.bytes()
+            .fold(17u32, |h, b| h.wrapping_mul(31).wrapping_add(u32::from(b)))
+            % 30_000
+    }
+
+    #[async_trait]
+    impl SparseEncoder for WordEncoder {
+        fn model_name(&self) -> &str {
+            "word-test"
+        }
+
+        async fn encode_batch(
+            &self,
+            texts: &[String],
+        ) -> Result<Vec<SparseVector>, EmbeddingError> {
+            Ok(texts
+                .iter()
+                .map(|text| {
+                    let lower = text.to_lowercase();
+                    let words = lower.split(|c: char| !c.is_alphanumeric());
+                    SparseVector::new(words.filter(|w| !w.is_empty()).flat_map(|w| {
+                        let expansion = (w == "car").then(|| (term("automobile"), 0.5));
+                        std::iter::once((term(w), 1.0)).chain(expansion)
+                    }))
+                })
+                .collect())
+        }
+    }
+
+    #[cfg(feature = "libsql")]
+    #[tokio::test]
+    async fn test_sparse_leg_finds_expanded_terms() {
+        use crate::db::libsql_backend::test_db;
+
+        let (db, _dir) = test_db().await;
+        let ws = Workspace::new_with_db("default", db).with_sparse_encoder(Arc::new(WordEncoder));
+
+        ws.write("notes/garage.md", "Bought a used automobile in May")
+            .await
+            .unwrap();
+        ws.write("notes/kitchen.md", "The kettle is broken")
+            .await
+            .unwrap();
+
+        // Keyword search can't connect "car" to "automobile"; the sparse leg
+        // does through the expansion.
+        let results = ws.search("car", 5).await.unwrap();
+        assert_eq!(results.len(), 1);
+        assert!(results[0].content.contains("automobile"));
+        assert_eq!(results[0].matched_by(), vec!["sparse"]);
+        assert_eq!(results[0].sparse_rank, Some(1));
+
+        let both = ws.search("kettle", 5).await.unwrap();
+        assert_eq!(both[0].matched_by(), vec!["keyword", "sparse"]);
+
+        // A rewritten document's old terms go with its old chunks.
+        ws.write("notes/garage.md", "Sold the bicycle")
+            .await
+            .unwrap();
+        assert!(ws.search("car", 5).await.unwrap().is_empty());
+
+        let config = SearchConfig {
+            use_sparse: false,
+            ..SearchConfig::default()
+        };
+        assert!(
+            ws.search_with_config("bicycle", config.clone())
+                .await
+                .unwrap()
+                .iter()
+                .all(|r| r.sparse_rank.is_none())
+        );
+        let modes = ws.search_modes(&SearchConfig::default());
+        assert_eq!(modes.label(), "keyword + sparse");
+        assert_eq!(ws.search_modes(&config).label(), "keyword only");
+    }
+}
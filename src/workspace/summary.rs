@@ -0,0 +1,299 @@
+//! Cached summaries of long documents.
+//!
+//! With a [`DocumentSummarizer`] configured (see
+//! [`Workspace::with_document_summaries`]), a document over the size
+//! threshold gets an LLM summary whenever it is indexed: written, appended
+//! to, imported, or reindexed. The summary is stored in the document's metadata under
+//! [`SUMMARY_KEY`] together with a hash of the content it was made from, so
+//! it is only regenerated when the document changes and a stale one is
+//! never used. Daily logs are left out; they have their own digests and
+//! rollups.
+//!
+//! Where an oversized document would otherwise be cut to fit the system
+//! prompt (identity files and `read_when` documents), its summary is used
+//! instead, and `memory_search` returns it alongside the matching chunks.
+
+use std::sync::Arc;
+
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::agent::context_monitor::estimate_text_tokens;
+use crate::error::{LlmError, WorkspaceError};
+use crate::llm::{ChatMessage, CompletionRequest, LlmProvider};
+use crate::workspace::importance::content_hash;
+use crate::workspace::prompt_budget::fit_head;
+use crate::workspace::{MemoryDocument, Workspace, paths};
+
+/// Metadata key holding a document's cached summary.
+pub const SUMMARY_KEY: &str = "summary";
+
+/// Default size, in tokens, above which documents are summarized.
+pub const DEFAULT_THRESHOLD_TOKENS: usize = 4_000;
+
+/// Characters of a document sent to the LLM.
+const MAX_SOURCE_CHARS: usize = 24_000;
+
+/// A document summary as stored in its metadata.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DocumentSummary {
+    pub text: String,
+    /// [`content_hash`] of the content that was summarized.
+    pub content_hash: String,
+    /// Estimated tokens of that content.
+    pub source_tokens: usize,
+    pub generated_at: DateTime<Utc>,
+}
+
+impl DocumentSummary {
+    /// The summary stored for `doc`, if it is of the current content.
+    pub fn of(doc: &MemoryDocument) -> Option<Self> {
+        let summary: Self = serde_json::from_value(doc.metadata.get(SUMMARY_KEY)?.clone()).ok()?;
+        (summary.content_hash == content_hash(&doc.content)).then_some(summary)
+    }
+}
+
+/// Summarizes long documents with an LLM.
+pub struct DocumentSummarizer {
+    llm: Arc<dyn LlmProvider>,
+}
+
+impl DocumentSummarizer {
+    pub fn new(llm: Arc<dyn LlmProvider>) -> Self {
+        Self { llm }
+    }
+
+    /// Summarize the document at `path`, returning the summary and its LLM
+    /// cost.
+    pub async fn summarize(
+        &self,
+        path: &str,
+        content: &str,
+    ) -> Result<(String, Decimal), LlmError> {
+        let prompt = "Summarize the document so it can stand in for the full text in an \
+             assistant's context.\n\n\
+             Rules:\n\
+             - Keep decisions, instructions, facts about people and projects, names, numbers, \
+             and open items.\n\
+             - Follow the document's structure with short markdown headings and bullet points.\n\
+             - Stay under 400 words.\n\
+             - Respond with the summary only, without a title.";
+        let (source, truncated) = match content.char_indices().nth(MAX_SOURCE_CHARS) {
+            Some((cut, _)) => (&content[..cut], true),
+            None => (content, false),
+        };
+        let note = if truncated {
+            "\n\n(The document continues; only its beginning is shown.)"
+        } else {
+            ""
+        };
+
+        let response = self
+            .llm
+            .complete(
+                CompletionRequest::new(vec![
+                    ChatMessage::system(prompt),
+                    ChatMessage::user(format!("Document: {}\n\n{}{}", path, source, note)),
+                ])
+                .with_max_tokens(1024)
+                .with_temperature(0.2),
+            )
+            .await?;
+        let cost = self
+            .llm
+            .calculate_cost(response.input_tokens, response.output_tokens);
+        Ok((response.content.trim().to_string(), cost))
+    }
+}
+
+/// Errors from summarizing a document.
+#[derive(Debug, thiserror::Error)]
+enum SummaryError {
+    #[error(transparent)]
+    Workspace(#[from] WorkspaceError),
+
+    #[error(transparent)]
+    Llm(#[from] LlmError),
+}
+
+/// When and how documents are summarized.
+#[derive(Clone)]
+pub(super) struct SummaryPolicy {
+    pub(super) summarizer: Arc<DocumentSummarizer>,
+    pub(super) threshold_tokens: usize,
+}
+
+/// Whether the document at `path` is summarized when oversized.
+fn summarizes(path: &str) -> bool {
+    !path.starts_with(paths::DAILY_DIR)
+}
+
+impl Workspace {
+    /// Summarize documents over `threshold_tokens` when they are indexed.
+    pub fn with_document_summaries(
+        mut self,
+        summarizer: Arc<DocumentSummarizer>,
+        threshold_tokens: usize,
+    ) -> Self {
+        self.summaries = Some(SummaryPolicy {
+            summarizer,
+            threshold_tokens,
+        });
+        self
+    }
+
+    /// The current summary of the document with `id`, if it has one.
+    pub async fn document_summary(&self, id: Uuid) -> Option<String> {
+        let doc = self.storage.get_document_by_id(id).await.ok()?;
+        DocumentSummary::of(&doc).map(|s| s.text)
+    }
+
+    /// Summarize the document with `id` if it is over the threshold and its
+    /// summary is missing or stale, or drop the summary of a document that
+    /// no longer needs one. Failures are logged, never returned: the write
+    /// that triggered this has already succeeded.
+    pub(super) async fn refresh_summary(&self, id: Uuid) {
+        let Some(policy) = &self.summaries else {
+            return;
+        };
+        if let Err(e) = self.try_refresh_summary(policy, id).await {
+            tracing::warn!("Failed to summarize document {}: {}", id, e);
+        }
+    }
+
+    async fn try_refresh_summary(
+        &self,
+        policy: &SummaryPolicy,
+        id: Uuid,
+    ) -> Result<(), SummaryError> {
+        let doc = self.storage.get_document_by_id(id).await?;
+        let tokens = estimate_text_tokens(&doc.content);
+        let oversized = tokens > policy.threshold_tokens && summarizes(&doc.path);
+
+        let mut metadata = doc.metadata.clone();
+        if !metadata.is_object() {
+            metadata = serde_json::Value::Object(serde_json::Map::new());
+        }
+        if !oversized {
+            if let Some(map) = metadata.as_object_mut()
+                && map.remove(SUMMARY_KEY).is_some()
+            {
+                self.update_metadata(id, &metadata).await?;
+            }
+            return Ok(());
+        }
+        if DocumentSummary::of(&doc).is_some() {
+            return Ok(());
+        }
+
+        let (text, cost) = policy.summarizer.summarize(&doc.path, &doc.content).await?;
+        let summary = DocumentSummary {
+            text,
+            content_hash: content_hash(&doc.content),
+            source_tokens: tokens,
+            generated_at: Utc::now(),
+        };
+        if let Some(map) = metadata.as_object_mut() {
+            map.insert(
+                SUMMARY_KEY.to_string(),
+                serde_json::to_value(&summary).unwrap_or_default(),
+            );
+        }
+        self.update_metadata(id, &metadata).await?;
+        tracing::debug!("Summarized {} ({} tokens, ${})", doc.path, tokens, cost);
+        Ok(())
+    }
+
+    /// `content` of `doc` within `budget` tokens for the system prompt: as
+    /// is if it fits, else the document's summary if it has one, else its
+    /// beginning.
+    pub(super) fn fit_document(
+        &self,
+        doc: &MemoryDocument,
+        content: &str,
+        budget: usize,
+    ) -> String {
+        if estimate_text_tokens(content) <= budget {
+            return content.to_string();
+        }
+        match DocumentSummary::of(doc) {
+            Some(summary) => {
+                let text = format!(
+                    "{}\n\n[Summary of a {}-token document; use memory_read(\"{}\") for the full file]",
+                    summary.text, summary.source_tokens, doc.path
+                );
+                fit_head(&text, budget, &doc.path)
+            }
+            None => fit_head(content, budget, &doc.path),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn doc(path: &str, content: &str) -> MemoryDocument {
+        let mut doc = MemoryDocument::new("user1", None, path);
+        doc.content = content.to_string();
+        doc
+    }
+
+    #[test]
+    fn test_stale_summary_is_ignored() {
+        let mut long = doc("notes/book.md", "Chapter one.");
+        assert_eq!(DocumentSummary::of(&long), None);
+
+        let summary = DocumentSummary {
+            text: "A book.".to_string(),
+            content_hash: content_hash(&long.content),
+            source_tokens: 5_000,
+            generated_at: Utc::now(),
+        };
+        long.metadata[SUMMARY_KEY] = serde_json::to_value(&summary).unwrap();
+        assert_eq!(DocumentSummary::of(&long), Some(summary));
+
+        long.content.push_str(" Chapter two.");
+        assert_eq!(DocumentSummary::of(&long), None);
+    }
+
+    #[test]
+    fn test_daily_logs_are_not_summarized() {
+        assert!(summarizes("notes/book.md"));
+        assert!(summarizes(paths::AGENTS));
+        assert!(!summarizes("daily/2024-01-15.md"));
+        assert!(!summarizes("daily/weekly/2024-W03.md"));
+    }
+
+    #[cfg(feature = "libsql")]
+    #[tokio::test]
+    async fn test_oversized_documents_are_summarized_once() {
+        use crate::db::libsql_backend::test_db;
+        use crate::llm::MockLlmProvider;
+
+        let (db, _dir) = test_db().await;
+        let llm = Arc::new(MockLlmProvider::new().with_text("- The agent's long rulebook."));
+        let ws = Workspace::new_with_db("default", db)
+            .with_document_summaries(Arc::new(DocumentSummarizer::new(llm.clone())), 50);
+
+        let long = "Always be precise and cite sources. ".repeat(400);
+        let doc = ws.write(paths::AGENTS, &long).await.unwrap();
+        assert_eq!(llm.request_count(), 1);
+        assert_eq!(
+            ws.document_summary(doc.id).await.as_deref(),
+            Some("- The agent's long rulebook.")
+        );
+
+        // Unchanged content keeps its summary; short documents get none.
+        ws.write(paths::AGENTS, &long).await.unwrap();
+        let short = ws.write("notes/short.md", "Short.").await.unwrap();
+        assert_eq!(llm.request_count(), 1);
+        assert_eq!(ws.document_summary(short.id).await, None);
+
+        let prompt = ws.system_prompt().await.unwrap();
+        assert!(prompt.contains("The agent's long rulebook."));
+        assert!(!prompt.contains(&long));
+    }
+}
@@ -0,0 +1,213 @@
+//! Document tags.
+//!
+//! Tags are kept in the `tags` list of a document's frontmatter, so they
+//! travel with the file and cut across directories: every document tagged
+//! `decisions` can be listed with [`Workspace::list_by_tag`] or searched
+//! with [`SearchConfig::with_tags`](crate::workspace::SearchConfig::with_tags)
+//! wherever it is stored. Tags are lowercase, without a leading `#`.
+//!
+//! The [`PRIVATE_TAG`] marks a document as the user's private memory, like
+//! MEMORY.md and USER.md (see [`sensitivity`](super::sensitivity)).
+
+use serde_json::Value;
+
+use crate::error::WorkspaceError;
+use crate::workspace::frontmatter::{DocumentMetadata, FieldFilter};
+use crate::workspace::{MemoryDocument, Workspace};
+
+/// Frontmatter key holding a document's tags.
+pub const TAGS_KEY: &str = "tags";
+
+/// Tag that makes a document private.
+pub const PRIVATE_TAG: &str = "private";
+
+/// `tag` lowercased and without a leading `#`, or None if it is empty or
+/// has characters other than letters, digits, `-`, `_`, `/`, and `.`.
+pub fn normalize_tag(tag: &str) -> Option<String> {
+    let tag = tag.trim().trim_start_matches('#').to_lowercase();
+    let valid = !tag.is_empty()
+        && tag
+            .chars()
+            .all(|c| c.is_alphanumeric() || matches!(c, '-' | '_' | '/' | '.'));
+    valid.then_some(tag)
+}
+
+impl DocumentMetadata {
+    /// The document's tags, in the order they are listed.
+    pub fn tags(&self) -> Vec<String> {
+        match self.fields.get(TAGS_KEY) {
+            Some(Value::Array(items)) => items
+                .iter()
+                .filter_map(|i| i.as_str().map(str::to_string))
+                .collect(),
+            Some(Value::String(tag)) if !tag.is_empty() => vec![tag.clone()],
+            _ => Vec::new(),
+        }
+    }
+
+    fn set_tags(&mut self, tags: Vec<String>) {
+        if tags.is_empty() {
+            self.fields.remove(TAGS_KEY);
+        } else {
+            let tags = tags.into_iter().map(Value::String).collect();
+            self.fields.insert(TAGS_KEY.to_string(), Value::Array(tags));
+        }
+    }
+}
+
+impl MemoryDocument {
+    /// Whether the document is tagged `tag` (compared after normalizing).
+    pub fn has_tag(&self, tag: &str) -> bool {
+        let tag = normalize_tag(tag);
+        tag.is_some()
+            && self
+                .frontmatter
+                .tags()
+                .iter()
+                .any(|t| normalize_tag(t) == tag)
+    }
+}
+
+fn normalize_all<S: AsRef<str>>(tags: &[S]) -> Result<Vec<String>, WorkspaceError> {
+    tags.iter()
+        .map(|t| {
+            normalize_tag(t.as_ref()).ok_or_else(|| WorkspaceError::InvalidTag {
+                tag: t.as_ref().to_string(),
+            })
+        })
+        .collect()
+}
+
+impl Workspace {
+    /// Tags of the document at `path`.
+    pub async fn tags(&self, path: &str) -> Result<Vec<String>, WorkspaceError> {
+        Ok(self.metadata(path).await?.tags())
+    }
+
+    /// Add `tags` to the document at `path`, returning its tags. The
+    /// document is only rewritten if a tag is new.
+    pub async fn tag<S: AsRef<str>>(
+        &self,
+        path: &str,
+        tags: &[S],
+    ) -> Result<Vec<String>, WorkspaceError> {
+        let added = normalize_all(tags)?;
+        let mut meta = self.metadata(path).await?;
+        let mut current = meta.tags();
+        let before = current.len();
+        for tag in added {
+            if !current.contains(&tag) {
+                current.push(tag);
+            }
+        }
+        if current.len() != before {
+            meta.set_tags(current.clone());
+            self.set_metadata(path, &meta).await?;
+        }
+        Ok(current)
+    }
+
+    /// Remove `tags` from the document at `path`, returning the tags it
+    /// keeps.
+    pub async fn untag<S: AsRef<str>>(
+        &self,
+        path: &str,
+        tags: &[S],
+    ) -> Result<Vec<String>, WorkspaceError> {
+        let removed = normalize_all(tags)?;
+        let mut meta = self.metadata(path).await?;
+        let current = meta.tags();
+        let kept: Vec<String> = current
+            .iter()
+            .filter(|t| !normalize_tag(t).is_some_and(|t| removed.contains(&t)))
+            .cloned()
+            .collect();
+        if kept.len() != current.len() {
+            meta.set_tags(kept.clone());
+            self.set_metadata(path, &meta).await?;
+        }
+        Ok(kept)
+    }
+
+    /// Documents tagged `tag`, sorted by path. Includes documents in
+    /// mounted namespaces.
+    pub async fn list_by_tag(&self, tag: &str) -> Result<Vec<MemoryDocument>, WorkspaceError> {
+        let tag = normalize_tag(tag).ok_or_else(|| WorkspaceError::InvalidTag {
+            tag: tag.to_string(),
+        })?;
+        self.find_by_frontmatter(&[FieldFilter::equals(TAGS_KEY, tag)])
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_tag() {
+        assert_eq!(normalize_tag("#Decisions").as_deref(), Some("decisions"));
+        assert_eq!(normalize_tag(" q3/plans ").as_deref(), Some("q3/plans"));
+        assert_eq!(normalize_tag("#"), None);
+        assert_eq!(normalize_tag("two words"), None);
+        assert_eq!(normalize_tag("a,b"), None);
+    }
+
+    #[cfg(feature = "libsql")]
+    #[tokio::test]
+    async fn test_tag_untag_and_search() {
+        use crate::db::libsql_backend::test_db;
+        use crate::workspace::SearchConfig;
+
+        let (db, _dir) = test_db().await;
+        let ws = Workspace::new_with_db("default", db);
+
+        ws.write("projects/alpha.md", "We chose Postgres for alpha.")
+            .await
+            .unwrap();
+        ws.write("daily/2025-01-02.md", "Decided to ship on Friday.")
+            .await
+            .unwrap();
+        ws.write("notes.md", "Postgres tuning notes.")
+            .await
+            .unwrap();
+
+        assert_eq!(
+            ws.tag("projects/alpha.md", &["#Decisions", "db"])
+                .await
+                .unwrap(),
+            vec!["decisions", "db"]
+        );
+        ws.tag("daily/2025-01-02.md", &["decisions"]).await.unwrap();
+        assert!(ws.tag("notes.md", &["not a tag"]).await.is_err());
+
+        let paths =
+            |docs: Vec<MemoryDocument>| docs.into_iter().map(|d| d.path).collect::<Vec<_>>();
+        assert_eq!(
+            paths(ws.list_by_tag("#decisions").await.unwrap()),
+            vec!["daily/2025-01-02.md", "projects/alpha.md"]
+        );
+        // The body is kept below the new frontmatter.
+        let doc = ws.read("projects/alpha.md").await.unwrap();
+        assert!(doc.content.ends_with("\n\nWe chose Postgres for alpha."));
+
+        let config = SearchConfig::default().with_tags(&["decisions"]);
+        let results = ws.search_with_config("postgres", config).await.unwrap();
+        assert_eq!(results.len(), 1);
+        assert!(results[0].content.contains("alpha"));
+
+        assert_eq!(
+            ws.untag("projects/alpha.md", &["decisions"]).await.unwrap(),
+            vec!["db"]
+        );
+        ws.untag("projects/alpha.md", &["db"]).await.unwrap();
+        assert_eq!(
+            ws.read("projects/alpha.md").await.unwrap().content,
+            "We chose Postgres for alpha."
+        );
+        assert_eq!(
+            paths(ws.list_by_tag("decisions").await.unwrap()),
+            vec!["daily/2025-01-02.md"]
+        );
+    }
+}
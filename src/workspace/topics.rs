@@ -0,0 +1,526 @@
+//! Topic index of the workspace.
+//!
+//! Chunk embeddings are clustered with spherical k-means (cosine
+//! similarity), each cluster is named by an LLM from the chunks nearest its
+//! centre, and the result is written to `memory/topics.md`: one heading per
+//! topic listing the documents with chunks in it. A document can appear
+//! under several topics, and clusters of a single chunk are left out as
+//! noise.
+//!
+//! The index records a fingerprint of the documents it was built from and
+//! is only rebuilt (and only costs LLM calls) once they change.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::Utc;
+use rand::SeedableRng;
+use rand::distributions::{Distribution, WeightedIndex};
+use rand::rngs::StdRng;
+use rust_decimal::Decimal;
+use serde::Serialize;
+
+use crate::error::{LlmError, WorkspaceError};
+use crate::llm::{ChatMessage, CompletionRequest, LlmProvider};
+use crate::workspace::{Workspace, trash};
+
+/// Where the topic index is written.
+pub const TOPICS_PATH: &str = "memory/topics.md";
+
+/// Chunks clustered per pass; larger workspaces are sampled evenly.
+const MAX_CHUNKS: usize = 5_000;
+
+/// Clusters smaller than this are noise rather than topics.
+const MIN_TOPIC_CHUNKS: usize = 2;
+
+const KMEANS_ITERATIONS: usize = 25;
+
+/// Chunks nearest a cluster's centre shown to the LLM to name it.
+const LABEL_SAMPLES: usize = 5;
+
+/// Characters of each sample chunk sent to the LLM.
+const SAMPLE_CHARS: usize = 400;
+
+/// Documents listed under one topic.
+const MAX_TOPIC_DOCUMENTS: usize = 20;
+
+/// Errors from building the topic index.
+#[derive(Debug, thiserror::Error)]
+pub enum TopicError {
+    #[error(transparent)]
+    Workspace(#[from] WorkspaceError),
+
+    #[error(transparent)]
+    Llm(#[from] LlmError),
+}
+
+/// Names topic clusters with an LLM.
+pub struct TopicLabeler {
+    llm: Arc<dyn LlmProvider>,
+}
+
+impl TopicLabeler {
+    pub fn new(llm: Arc<dyn LlmProvider>) -> Self {
+        Self { llm }
+    }
+
+    /// Name the topic shared by `samples` (chunk text) from `paths`,
+    /// returning the name and its LLM cost.
+    pub async fn label(
+        &self,
+        samples: &[&str],
+        paths: &[&str],
+    ) -> Result<(String, Decimal), LlmError> {
+        let prompt = "Name the topic these excerpts from the user's notes have in common.\n\n\
+             Rules:\n\
+             - Use 2 to 5 words, in the language of the excerpts.\n\
+             - Be specific: \"Kitchen renovation\" rather than \"Home\".\n\
+             - Respond with the name only.";
+        let excerpts = samples
+            .iter()
+            .map(|s| truncate(s.trim(), SAMPLE_CHARS))
+            .collect::<Vec<_>>()
+            .join("\n---\n");
+
+        let response = self
+            .llm
+            .complete(
+                CompletionRequest::new(vec![
+                    ChatMessage::system(prompt),
+                    ChatMessage::user(format!(
+                        "Documents: {}\n\nExcerpts:\n{}",
+                        paths.join(", "),
+                        excerpts
+                    )),
+                ])
+                .with_max_tokens(32)
+                .with_temperature(0.2),
+            )
+            .await?;
+        let cost = self
+            .llm
+            .calculate_cost(response.input_tokens, response.output_tokens);
+        Ok((clean_label(&response.content), cost))
+    }
+}
+
+/// Result of a topic index pass.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct TopicReport {
+    /// Topics written to the index.
+    pub topics: usize,
+    /// Documents the index was built from.
+    pub documents: usize,
+    /// The index was already up to date.
+    pub unchanged: bool,
+    pub cost: Decimal,
+}
+
+/// A chunk taking part in clustering.
+struct Point {
+    path: usize,
+    content: String,
+    embedding: Vec<f32>,
+}
+
+/// A cluster kept as a topic.
+struct Topic {
+    /// Indexes into the points, nearest the centre first.
+    members: Vec<usize>,
+}
+
+impl Workspace {
+    /// Rebuild `memory/topics.md` with at most `max_topics` topics, unless
+    /// no document has changed since it was last built.
+    ///
+    /// Documents without embeddings are left out; with none embedded there
+    /// is nothing to cluster and the index is not written.
+    pub async fn build_topic_index(
+        &self,
+        labeler: &TopicLabeler,
+        max_topics: usize,
+    ) -> Result<TopicReport, TopicError> {
+        let mut documents = self
+            .storage
+            .list_documents(&self.user_id, self.agent_id)
+            .await?;
+        documents.retain(|d| d.path != TOPICS_PATH && !trash::is_trashed(&d.path));
+        documents.sort_by(|a, b| a.path.cmp(&b.path));
+
+        let fingerprint = fingerprint(
+            documents
+                .iter()
+                .map(|d| (d.path.as_str(), d.updated_at.timestamp_micros())),
+        );
+        let marker = format!("<!-- topics: {} -->", fingerprint);
+        if let Ok(existing) = self.read(TOPICS_PATH).await
+            && existing.content.starts_with(&marker)
+        {
+            return Ok(TopicReport {
+                documents: documents.len(),
+                unchanged: true,
+                ..TopicReport::default()
+            });
+        }
+
+        let mut points = Vec::new();
+        for (path, doc) in documents.iter().enumerate() {
+            for chunk in self.storage.get_document_chunks(doc.id).await? {
+                if let Some(embedding) = chunk.embedding {
+                    points.push(Point {
+                        path,
+                        content: chunk.content,
+                        embedding: normalized(embedding),
+                    });
+                }
+            }
+        }
+        if points.len() > MAX_CHUNKS {
+            let step = points.len().div_ceil(MAX_CHUNKS);
+            points = points.into_iter().step_by(step).collect();
+        }
+        let mut report = TopicReport {
+            documents: documents.len(),
+            ..TopicReport::default()
+        };
+        if points.is_empty() || max_topics == 0 {
+            return Ok(report);
+        }
+
+        let embeddings: Vec<&[f32]> = points.iter().map(|p| p.embedding.as_slice()).collect();
+        let topics = find_topics(&embeddings, topic_count(points.len(), max_topics));
+
+        let mut content = format!(
+            "{}\n# Topics\n\n_What this workspace contains, grouped by similarity. \
+             Generated {} from {} documents; rebuilt when documents change._\n",
+            marker,
+            Utc::now().format("%Y-%m-%d"),
+            documents.len()
+        );
+        for (i, topic) in topics.iter().enumerate() {
+            // Documents by how many of the topic's chunks they hold.
+            let mut counts: HashMap<usize, usize> = HashMap::new();
+            for &m in &topic.members {
+                *counts.entry(points[m].path).or_default() += 1;
+            }
+            let mut paths: Vec<(usize, usize)> = counts.into_iter().collect();
+            paths.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+            let paths: Vec<&str> = paths
+                .iter()
+                .take(MAX_TOPIC_DOCUMENTS)
+                .map(|(p, _)| documents[*p].path.as_str())
+                .collect();
+
+            let samples: Vec<&str> = topic
+                .members
+                .iter()
+                .take(LABEL_SAMPLES)
+                .map(|&m| points[m].content.as_str())
+                .collect();
+            let (label, cost) = labeler.label(&samples, &paths).await?;
+            report.cost += cost;
+            let label = if label.is_empty() {
+                format!("Topic {}", i + 1)
+            } else {
+                label
+            };
+
+            content.push_str(&format!("\n## {}\n\n", label));
+            for path in paths {
+                content.push_str(&format!("- `{}`\n", path));
+            }
+        }
+
+        self.write(TOPICS_PATH, &content).await?;
+        report.topics = topics.len();
+        Ok(report)
+    }
+
+    /// Run [`Workspace::build_topic_index`] every `interval`.
+    pub fn spawn_topic_index(
+        self: &Arc<Self>,
+        labeler: TopicLabeler,
+        max_topics: usize,
+        interval: Duration,
+    ) -> tokio::task::JoinHandle<()> {
+        let workspace = Arc::clone(self);
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            loop {
+                ticker.tick().await;
+                match workspace.build_topic_index(&labeler, max_topics).await {
+                    Ok(report) if report.unchanged || report.topics == 0 => {}
+                    Ok(report) => tracing::info!(
+                        "Wrote {} topics for {} documents to {} (${})",
+                        report.topics,
+                        report.documents,
+                        TOPICS_PATH,
+                        report.cost
+                    ),
+                    Err(e) => tracing::warn!("Topic index failed: {}", e),
+                }
+            }
+        })
+    }
+}
+
+/// Clusters for `points` chunks: about one per two chunks' square root,
+/// capped at `max`.
+fn topic_count(points: usize, max: usize) -> usize {
+    ((points as f64 / 2.0).sqrt().round() as usize).clamp(1, max.max(1))
+}
+
+/// Cluster unit vectors into `k` groups and keep those large enough to be
+/// topics, largest first.
+fn find_topics(points: &[&[f32]], k: usize) -> Vec<Topic> {
+    let (assignments, centroids) = kmeans(points, k);
+    let mut topics: Vec<Topic> = centroids
+        .iter()
+        .enumerate()
+        .map(|(c, centroid)| {
+            let mut members: Vec<usize> =
+                (0..points.len()).filter(|&i| assignments[i] == c).collect();
+            members.sort_by(|&a, &b| dot(points[b], centroid).total_cmp(&dot(points[a], centroid)));
+            Topic { members }
+        })
+        .filter(|t| t.members.len() >= MIN_TOPIC_CHUNKS)
+        .collect();
+    topics.sort_by_key(|t| std::cmp::Reverse(t.members.len()));
+    topics
+}
+
+/// Spherical k-means with k-means++ seeding. Seeded deterministically so
+/// an unchanged workspace clusters the same way. Returns each point's
+/// cluster and the cluster centres.
+fn kmeans(points: &[&[f32]], k: usize) -> (Vec<usize>, Vec<Vec<f32>>) {
+    let k = k.min(points.len()).max(1);
+    let mut rng = StdRng::seed_from_u64(0x7091c5);
+
+    let mut centroids: Vec<Vec<f32>> = vec![points[0].to_vec()];
+    while centroids.len() < k {
+        // Distance to the nearest centre, squared.
+        let weights: Vec<f32> = points
+            .iter()
+            .map(|p| {
+                let nearest = centroids.iter().map(|c| dot(p, c)).fold(f32::MIN, f32::max);
+                (1.0 - nearest).max(0.0).powi(2)
+            })
+            .collect();
+        let Ok(dist) = WeightedIndex::new(&weights) else {
+            // Every point coincides with a centre already.
+            break;
+        };
+        centroids.push(points[dist.sample(&mut rng)].to_vec());
+    }
+
+    let mut assignments = vec![usize::MAX; points.len()];
+    for _ in 0..KMEANS_ITERATIONS {
+        let mut changed = false;
+        for (i, p) in points.iter().enumerate() {
+            let best = nearest(p, &centroids);
+            if assignments[i] != best {
+                assignments[i] = best;
+                changed = true;
+            }
+        }
+        if !changed {
+            break;
+        }
+
+        let dim = points[0].len();
+        let mut sums = vec![vec![0.0f32; dim]; centroids.len()];
+        for (p, &c) in points.iter().zip(&assignments) {
+            for (s, v) in sums[c].iter_mut().zip(p.iter()) {
+                *s += v;
+            }
+        }
+        for (centroid, sum) in centroids.iter_mut().zip(sums) {
+            // An empty cluster keeps its old centre.
+            if sum.iter().any(|v| *v != 0.0) {
+                *centroid = normalized(sum);
+            }
+        }
+    }
+    (assignments, centroids)
+}
+
+fn nearest(point: &[f32], centroids: &[Vec<f32>]) -> usize {
+    centroids
+        .iter()
+        .enumerate()
+        .map(|(i, c)| (i, dot(point, c)))
+        .max_by(|a, b| a.1.total_cmp(&b.1))
+        .map(|(i, _)| i)
+        .unwrap_or(0)
+}
+
+fn dot(a: &[f32], b: &[f32]) -> f32 {
+    a.iter().zip(b).map(|(x, y)| x * y).sum()
+}
+
+fn normalized(mut v: Vec<f32>) -> Vec<f32> {
+    let norm = v.iter().map(|x| x * x).sum::<f32>().sqrt();
+    if norm > 0.0 {
+        v.iter_mut().for_each(|x| *x /= norm);
+    }
+    v
+}
+
+/// Hash of the documents' paths and update times.
+fn fingerprint<'a>(documents: impl Iterator<Item = (&'a str, i64)>) -> String {
+    let mut hasher = blake3::Hasher::new();
+    for (path, updated) in documents {
+        hasher.update(path.as_bytes());
+        hasher.update(&updated.to_le_bytes());
+    }
+    hasher.finalize().to_hex()[..16].to_string()
+}
+
+/// The first line of an LLM reply, without markdown or quotes.
+fn clean_label(reply: &str) -> String {
+    let line = reply.trim().lines().next().unwrap_or_default();
+    let label = line
+        .trim_start_matches('#')
+        .trim()
+        .trim_matches(|c| matches!(c, '"' | '\'' | '*' | '`'))
+        .trim_end_matches('.')
+        .trim();
+    truncate(label, 60).to_string()
+}
+
+/// At most `max` characters of `text`.
+fn truncate(text: &str, max: usize) -> &str {
+    match text.char_indices().nth(max) {
+        Some((cut, _)) => &text[..cut],
+        None => text,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn unit(v: &[f32]) -> Vec<f32> {
+        normalized(v.to_vec())
+    }
+
+    #[test]
+    fn test_kmeans_separates_groups() {
+        let points = [
+            unit(&[1.0, 0.1, 0.0]),
+            unit(&[0.9, 0.0, 0.1]),
+            unit(&[1.0, 0.0, 0.0]),
+            unit(&[0.0, 1.0, 0.1]),
+            unit(&[0.1, 0.9, 0.0]),
+            unit(&[0.0, 0.0, 1.0]),
+        ];
+        let refs: Vec<&[f32]> = points.iter().map(Vec::as_slice).collect();
+        let topics = find_topics(&refs, 3);
+
+        // The lone third point is noise.
+        assert_eq!(topics.len(), 2);
+        let mut first = topics[0].members.clone();
+        first.sort();
+        assert_eq!(first, vec![0, 1, 2]);
+        // Nearest the centre first.
+        assert_eq!(topics[0].members[2], 1);
+        let mut second = topics[1].members.clone();
+        second.sort();
+        assert_eq!(second, vec![3, 4]);
+    }
+
+    #[test]
+    fn test_kmeans_identical_points() {
+        let points = [unit(&[1.0, 0.0]), unit(&[1.0, 0.0]), unit(&[1.0, 0.0])];
+        let refs: Vec<&[f32]> = points.iter().map(Vec::as_slice).collect();
+        let (assignments, centroids) = kmeans(&refs, 3);
+        assert_eq!(centroids.len(), 1);
+        assert_eq!(assignments, vec![0, 0, 0]);
+    }
+
+    #[test]
+    fn test_topic_count_and_labels() {
+        assert_eq!(topic_count(1, 12), 1);
+        assert_eq!(topic_count(50, 12), 5);
+        assert_eq!(topic_count(10_000, 12), 12);
+
+        assert_eq!(clean_label("## \"Tax filing.\"\nextra"), "Tax filing");
+        assert_eq!(clean_label("  **Garden** "), "Garden");
+        assert_eq!(clean_label(""), "");
+
+        let a = fingerprint([("a.md", 1), ("b.md", 2)].into_iter());
+        assert_eq!(a, fingerprint([("a.md", 1), ("b.md", 2)].into_iter()));
+        assert_ne!(a, fingerprint([("a.md", 1), ("b.md", 3)].into_iter()));
+    }
+
+    #[cfg(feature = "libsql")]
+    #[tokio::test]
+    async fn test_topic_index_written_once() {
+        use crate::db::libsql_backend::test_db;
+        use crate::llm::{MockLlmProvider, MockResponse};
+        use crate::workspace::HashEmbeddings;
+
+        let (db, _dir) = test_db().await;
+        let ws = Workspace::new_with_db("default", db)
+            .with_embeddings(Arc::new(HashEmbeddings::new(1536)));
+        for (path, text) in [
+            ("garden/tomatoes.md", "tomato seedlings watering schedule"),
+            ("garden/beans.md", "bean seedlings watering schedule"),
+            ("garden/herbs.md", "herb seedlings watering schedule"),
+            ("garden/squash.md", "squash seedlings watering schedule"),
+            ("taxes/2024.md", "income tax return filing deadline"),
+            ("taxes/receipts.md", "tax return receipts filing deadline"),
+            ("taxes/2023.md", "amended tax return filing deadline"),
+            ("taxes/advisor.md", "advisor tax return filing deadline"),
+        ] {
+            ws.write(path, text).await.unwrap();
+        }
+
+        let llm = Arc::new(
+            MockLlmProvider::new()
+                .with_text("\"Topic one\"")
+                .with_text("Topic two.")
+                .with_fallback(MockResponse::Text("Later topic".to_string())),
+        );
+        let labeler = TopicLabeler::new(llm.clone());
+        let report = ws.build_topic_index(&labeler, 2).await.unwrap();
+        assert_eq!(report.documents, 8);
+        assert_eq!(report.topics, 2);
+
+        let index = ws.read(TOPICS_PATH).await.unwrap().content;
+        assert!(index.contains("# Topics"));
+        let section = |name: &str| {
+            index
+                .split("\n## ")
+                .find(|s| s.starts_with(name))
+                .unwrap_or_default()
+                .to_string()
+        };
+        // Labels are answered in request order; the request naming the
+        // garden documents got the garden topic's label.
+        let requests = llm.requests();
+        assert_eq!(requests.len(), 2);
+        let (garden, taxes) = if requests[0].contains("garden/") {
+            (section("Topic one"), section("Topic two"))
+        } else {
+            (section("Topic two"), section("Topic one"))
+        };
+        assert!(garden.contains("`garden/tomatoes.md`") && garden.contains("`garden/beans.md`"));
+        assert!(!garden.contains("taxes/"));
+        assert!(taxes.contains("`taxes/2024.md`") && taxes.contains("`taxes/receipts.md`"));
+
+        // Unchanged documents: no rebuild, no LLM calls.
+        let report = ws.build_topic_index(&labeler, 2).await.unwrap();
+        assert!(report.unchanged);
+        assert_eq!(llm.request_count(), 2);
+
+        ws.write("garden/peas.md", "pea seedlings watering schedule")
+            .await
+            .unwrap();
+        let report = ws.build_topic_index(&labeler, 2).await.unwrap();
+        assert!(!report.unchanged);
+        let index = ws.read(TOPICS_PATH).await.unwrap().content;
+        assert!(index.contains("`garden/peas.md`"));
+    }
+}
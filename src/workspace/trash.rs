@@ -0,0 +1,259 @@
+//! Recoverable deletes.
+//!
+//! [`Workspace::delete`] moves a document to `.trash/<path>` in its own
+//! namespace instead of removing it. A trashed document keeps its ID and
+//! history but loses its search index, links and graph facts, and it is
+//! left out of [`Workspace::list_all`], [`Workspace::glob`] (unless the
+//! pattern starts in the trash), frontmatter queries, and the root listing;
+//! `list(".trash")` shows it. Trashing a path again replaces the older copy.
+//!
+//! [`Workspace::restore`] moves a document back and re-indexes it, marking
+//! it for the knowledge graph indexer to extract its facts again.
+//! [`Workspace::purge_trash`] deletes for good whatever has been in the
+//! trash longer than a retention window, and deleting a path already in the
+//! trash does so at once. Until then trashed documents count toward the
+//! [`WorkspaceQuota`](crate::workspace::WorkspaceQuota).
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::Utc;
+use uuid::Uuid;
+
+use crate::error::WorkspaceError;
+use crate::workspace::graph::INDEXED_AT_KEY;
+use crate::workspace::{MemoryDocument, PathGlob, Workspace, WorkspaceEvent};
+
+/// Directory deleted documents are moved to, in each namespace.
+pub const TRASH_DIR: &str = ".trash";
+
+/// Whether `path` is in a trash directory.
+pub fn is_trashed(path: &str) -> bool {
+    path.split('/').any(|segment| segment == TRASH_DIR)
+}
+
+impl Workspace {
+    /// Move a deleted document out of the way: to the trash, without its
+    /// chunks, links and graph edges.
+    pub(super) async fn trash_document(
+        &self,
+        agent_id: Option<Uuid>,
+        path: &str,
+    ) -> Result<(), WorkspaceError> {
+        let doc = self
+            .storage
+            .get_document_by_path(&self.user_id, agent_id, path)
+            .await?;
+        let trashed = format!("{}/{}", TRASH_DIR, path);
+        match self
+            .storage
+            .delete_document_by_path(&self.user_id, agent_id, &trashed)
+            .await
+        {
+            Ok(()) | Err(WorkspaceError::DocumentNotFound { .. }) => {}
+            Err(e) => return Err(e),
+        }
+        self.storage.rename_document(doc.id, &trashed).await?;
+        self.storage.delete_chunks(doc.id).await?;
+        self.storage.set_document_links(doc.id, &[]).await?;
+        self.storage.delete_graph_edges_for_document(doc.id).await?;
+        self.events.publish(WorkspaceEvent::Deleted {
+            user_id: self.user_id.clone(),
+            agent_id,
+            path: trashed,
+        });
+        Ok(())
+    }
+
+    /// Move the document deleted from `path` back out of the trash and
+    /// re-index it. `path` may be the original path or the one in the
+    /// trash. Fails if a document has since been created at the original
+    /// path.
+    pub async fn restore(&self, path: &str) -> Result<MemoryDocument, WorkspaceError> {
+        let path = self.canonical(path).await?;
+        let target = self.resolve_writable(&path)?;
+        let prefix = format!("{}/", TRASH_DIR);
+        let original = target
+            .path
+            .strip_prefix(&prefix)
+            .unwrap_or(&target.path)
+            .to_string();
+        let trashed = format!("{}{}", prefix, original);
+        let restored = match target.mount {
+            Some(mount) => mount.join(&original),
+            None => original.clone(),
+        };
+
+        let doc = self
+            .storage
+            .get_document_by_path(&self.user_id, target.agent_id, &trashed)
+            .await?;
+        if self.exists(&restored).await? {
+            return Err(WorkspaceError::InvalidPath {
+                path: restored,
+                reason: "a document already exists at this path".to_string(),
+            });
+        }
+
+        self.storage.rename_document(doc.id, &original).await?;
+        // Its graph edges went when it was trashed; have the indexer
+        // extract them again.
+        let mut metadata = doc.metadata.clone();
+        if let Some(map) = metadata.as_object_mut()
+            && map.remove(INDEXED_AT_KEY).is_some()
+        {
+            self.storage
+                .update_document_metadata(doc.id, &metadata)
+                .await?;
+        }
+        self.events.publish(WorkspaceEvent::Deleted {
+            user_id: self.user_id.clone(),
+            agent_id: target.agent_id,
+            path: trashed,
+        });
+        self.reindex_document(doc.id).await?;
+        self.audit_mutation("restore", &restored).await;
+        self.refresh_readmes(&restored, "", Some(&doc.content))
+            .await;
+        self.document_by_id(doc.id).await
+    }
+
+    /// Permanently delete documents that have been in the trash for longer
+    /// than `older_than`, in this workspace and its writable mounts.
+    /// Returns how many were deleted.
+    pub async fn purge_trash(&self, older_than: chrono::Duration) -> Result<u64, WorkspaceError> {
+        let cutoff = Utc::now() - older_than;
+        let glob = PathGlob::new(&format!("{}/**", TRASH_DIR))?;
+        let scopes = std::iter::once(self.agent_id).chain(
+            self.mounts
+                .iter()
+                .filter(|m| m.is_writable())
+                .map(|m| Some(m.scope())),
+        );
+
+        let mut purged = 0;
+        for agent_id in scopes {
+            let paths = self
+                .storage
+                .list_paths_matching(&self.user_id, agent_id, &glob)
+                .await?;
+            if paths.is_empty() {
+                continue;
+            }
+            // Trashing a document stamps it, so updated_at is when it was
+            // deleted.
+            let expired: Vec<String> = self
+                .storage
+                .get_documents_by_paths(&self.user_id, agent_id, &paths)
+                .await?
+                .into_iter()
+                .filter(|d| d.updated_at <= cutoff)
+                .map(|d| d.path)
+                .collect();
+            if expired.is_empty() {
+                continue;
+            }
+            purged += self
+                .storage
+                .delete_documents_by_paths(&self.user_id, agent_id, &expired)
+                .await?;
+            for path in expired {
+                self.events.publish(WorkspaceEvent::Deleted {
+                    user_id: self.user_id.clone(),
+                    agent_id,
+                    path,
+                });
+            }
+        }
+        Ok(purged)
+    }
+
+    /// Run [`Workspace::purge_trash`] every `interval`.
+    pub fn spawn_trash_purge(
+        self: &Arc<Self>,
+        retention: chrono::Duration,
+        interval: Duration,
+    ) -> tokio::task::JoinHandle<()> {
+        let workspace = Arc::clone(self);
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            loop {
+                ticker.tick().await;
+                match workspace.purge_trash(retention).await {
+                    Ok(0) => {}
+                    Ok(purged) => tracing::info!("Purged {} documents from the trash", purged),
+                    Err(e) => tracing::warn!("Failed to purge the trash: {}", e),
+                }
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_trashed() {
+        assert!(is_trashed(".trash"));
+        assert!(is_trashed(".trash/notes.md"));
+        assert!(is_trashed("shared/.trash/notes.md"));
+        assert!(!is_trashed("notes/trash.md"));
+        assert!(!is_trashed("notes/.trashcan/a.md"));
+    }
+
+    #[cfg(feature = "libsql")]
+    #[tokio::test]
+    async fn test_delete_moves_to_trash_and_restores() {
+        use crate::db::libsql_backend::test_db;
+
+        let (db, _dir) = test_db().await;
+        let ws = Workspace::new_with_db("default", db);
+
+        ws.write("notes/plan.md", "Launch the rocket on Friday")
+            .await
+            .unwrap();
+        ws.write("notes/plan.md", "Launch the rocket on Monday")
+            .await
+            .unwrap();
+        ws.delete("notes/plan.md").await.unwrap();
+
+        assert!(!ws.exists("notes/plan.md").await.unwrap());
+        assert!(ws.exists(".trash/notes/plan.md").await.unwrap());
+        assert!(ws.list_all().await.unwrap().is_empty());
+        assert!(ws.glob("**/*.md").await.unwrap().is_empty());
+        assert_eq!(
+            ws.glob(".trash/**").await.unwrap(),
+            vec![".trash/notes/plan.md"]
+        );
+        assert!(
+            ws.list("")
+                .await
+                .unwrap()
+                .iter()
+                .all(|e| e.path != TRASH_DIR)
+        );
+        assert!(ws.search("rocket", 5).await.unwrap().is_empty());
+
+        let doc = ws.restore("notes/plan.md").await.unwrap();
+        assert_eq!(doc.path, "notes/plan.md");
+        assert_eq!(doc.content, "Launch the rocket on Monday");
+        assert_eq!(ws.history("notes/plan.md").await.unwrap().len(), 1);
+        assert!(!ws.search("rocket", 5).await.unwrap().is_empty());
+        assert!(matches!(
+            ws.restore("notes/plan.md").await,
+            Err(WorkspaceError::DocumentNotFound { .. })
+        ));
+
+        // Nothing is purged before its time; deleting from the trash is final.
+        ws.delete("notes/plan.md").await.unwrap();
+        assert_eq!(ws.purge_trash(chrono::Duration::days(1)).await.unwrap(), 0);
+        assert_eq!(ws.purge_trash(chrono::Duration::zero()).await.unwrap(), 1);
+        assert!(!ws.exists(".trash/notes/plan.md").await.unwrap());
+
+        ws.write("draft.md", "x").await.unwrap();
+        ws.delete("draft.md").await.unwrap();
+        ws.delete(".trash/draft.md").await.unwrap();
+        assert!(ws.glob(".trash/**").await.unwrap().is_empty());
+    }
+}
@@ -0,0 +1,566 @@
+//! Weekly usage reports.
+//!
+//! Once a week is over, a report of how the agent spent it is written to
+//! `reports/usage-2024-03.md` (ISO year and week): jobs run and their
+//! outcome, tokens and cost, tools used, heartbeat activity, and notable
+//! errors. Reports are ordinary workspace documents, so the user can read
+//! them and the agent can find them through search.
+//!
+//! Jobs, cost, and tokens come from the job history (tokens only for jobs
+//! with a recorded transcript). Tool calls and heartbeat runs come from the
+//! audit log, so those sections are empty when auditing is off. Existing
+//! reports are never regenerated; delete one to have it rebuilt.
+
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::{DateTime, Datelike, NaiveDate, Utc};
+use rust_decimal::Decimal;
+
+use crate::audit::{AuditEventKind, AuditQuery, AuditRecord, MAX_QUERY_LIMIT};
+use crate::db::Database;
+use crate::error::{DatabaseError, WorkspaceError};
+use crate::history::JobUsageRecord;
+use crate::workspace::Workspace;
+use crate::workspace::rollup::RollupPeriod;
+
+/// Directory of usage reports.
+pub const REPORTS_DIR: &str = "reports/";
+
+/// Audit entries read per kind for one report, bounding the cost of a very
+/// busy week.
+const MAX_AUDIT_ENTRIES: usize = 20_000;
+
+/// Entries listed under "Notable errors".
+const MAX_ERRORS: usize = 10;
+
+/// Characters kept of each error message.
+const MAX_ERROR_CHARS: usize = 200;
+
+/// Errors from generating usage reports.
+#[derive(Debug, thiserror::Error)]
+pub enum UsageReportError {
+    #[error(transparent)]
+    Workspace(#[from] WorkspaceError),
+
+    #[error(transparent)]
+    Database(#[from] DatabaseError),
+}
+
+/// Calls of one tool during the week.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ToolUsage {
+    pub name: String,
+    pub calls: u64,
+    pub failures: u64,
+    /// The most recent failure's error.
+    pub last_error: Option<String>,
+}
+
+/// Heartbeat runs during the week, by outcome.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct HeartbeatActivity {
+    pub ok: u64,
+    pub alerts: u64,
+    pub skipped: u64,
+    pub failed: u64,
+    /// The most recent failure's error.
+    pub last_error: Option<String>,
+}
+
+impl HeartbeatActivity {
+    pub fn runs(&self) -> u64 {
+        self.ok + self.alerts + self.skipped + self.failed
+    }
+}
+
+/// A week of usage.
+#[derive(Debug, Clone)]
+pub struct UsageReport {
+    /// Monday of the ISO week.
+    pub week_start: NaiveDate,
+    /// Jobs created during the week, oldest first.
+    pub jobs: Vec<JobUsageRecord>,
+    /// Tools by number of calls, most used first.
+    pub tools: Vec<ToolUsage>,
+    pub heartbeat: HeartbeatActivity,
+}
+
+impl UsageReport {
+    /// Workspace path of the report for the week containing `date`.
+    pub fn path_for(date: NaiveDate) -> String {
+        let iso = date.iso_week();
+        format!("{REPORTS_DIR}usage-{}-{:02}.md", iso.year(), iso.week())
+    }
+
+    pub fn path(&self) -> String {
+        Self::path_for(self.week_start)
+    }
+
+    pub fn total_cost(&self) -> Decimal {
+        self.jobs.iter().map(|j| j.actual_cost).sum()
+    }
+
+    /// Input and output tokens across all jobs.
+    pub fn tokens(&self) -> (u64, u64) {
+        self.jobs.iter().fold((0, 0), |(i, o), j| {
+            (i + j.input_tokens, o + j.output_tokens)
+        })
+    }
+
+    /// Failed jobs, failing tools, and heartbeat failures, most severe
+    /// first.
+    pub fn notable_errors(&self) -> Vec<String> {
+        let mut errors = Vec::new();
+        for job in self.jobs.iter().filter(|j| j.status == "failed") {
+            errors.push(match &job.failure_reason {
+                Some(reason) => format!("Job \"{}\" failed: {}", job.title, clip(reason)),
+                None => format!("Job \"{}\" failed", job.title),
+            });
+        }
+        let mut failing: Vec<&ToolUsage> = self.tools.iter().filter(|t| t.failures > 0).collect();
+        failing.sort_by_key(|t| std::cmp::Reverse(t.failures));
+        for tool in failing {
+            let mut line = format!(
+                "`{}` failed {} of {} call(s)",
+                tool.name, tool.failures, tool.calls
+            );
+            if let Some(ref error) = tool.last_error {
+                line.push_str(&format!(", last: {}", clip(error)));
+            }
+            errors.push(line);
+        }
+        if self.heartbeat.failed > 0 {
+            let mut line = format!("Heartbeat failed {} time(s)", self.heartbeat.failed);
+            if let Some(ref error) = self.heartbeat.last_error {
+                line.push_str(&format!(", last: {}", clip(error)));
+            }
+            errors.push(line);
+        }
+        errors.truncate(MAX_ERRORS);
+        errors
+    }
+
+    /// The report as markdown.
+    pub fn render(&self, generated_at: DateTime<Utc>) -> String {
+        let mut out = format!(
+            "# Usage report: {}\n\n## Jobs\n\n",
+            RollupPeriod::week_of(self.week_start).title()
+        );
+
+        if self.jobs.is_empty() {
+            out.push_str("No jobs ran this week.\n");
+        } else {
+            let mut by_status: BTreeMap<&str, usize> = BTreeMap::new();
+            for job in &self.jobs {
+                *by_status.entry(job.status.as_str()).or_default() += 1;
+            }
+            let statuses: Vec<String> = by_status
+                .iter()
+                .map(|(status, n)| format!("{n} {status}"))
+                .collect();
+            let (input, output) = self.tokens();
+            out.push_str(&format!(
+                "- {} job(s): {}\n- Cost: ${}\n- Tokens: {} in, {} out\n",
+                self.jobs.len(),
+                statuses.join(", "),
+                self.total_cost().round_dp(4),
+                input,
+                output
+            ));
+        }
+
+        out.push_str("\n## Tools\n\n");
+        if self.tools.is_empty() {
+            out.push_str("No tool calls recorded.\n");
+        } else {
+            out.push_str("| Tool | Calls | Failed |\n|------|------:|-------:|\n");
+            for tool in &self.tools {
+                out.push_str(&format!(
+                    "| {} | {} | {} |\n",
+                    tool.name, tool.calls, tool.failures
+                ));
+            }
+        }
+
+        out.push_str("\n## Heartbeat\n\n");
+        let hb = &self.heartbeat;
+        if hb.runs() == 0 {
+            out.push_str("No heartbeat runs recorded.\n");
+        } else {
+            out.push_str(&format!(
+                "- {} run(s): {} ok, {} alert(s), {} skipped, {} failed\n",
+                hb.runs(),
+                hb.ok,
+                hb.alerts,
+                hb.skipped,
+                hb.failed
+            ));
+        }
+
+        out.push_str("\n## Notable errors\n\n");
+        let errors = self.notable_errors();
+        if errors.is_empty() {
+            out.push_str("None.\n");
+        } else {
+            for error in errors {
+                out.push_str(&format!("- {error}\n"));
+            }
+        }
+
+        out.push_str(&format!(
+            "\n_Generated {}._\n",
+            generated_at.format("%Y-%m-%d %H:%M UTC")
+        ));
+        out
+    }
+}
+
+/// Collects a week's usage from the job history and audit log.
+pub struct UsageReporter {
+    db: Arc<dyn Database>,
+}
+
+impl UsageReporter {
+    pub fn new(db: Arc<dyn Database>) -> Self {
+        Self { db }
+    }
+
+    /// Usage by `user_id` in the ISO week containing `date`.
+    pub async fn collect(
+        &self,
+        user_id: &str,
+        date: NaiveDate,
+    ) -> Result<UsageReport, DatabaseError> {
+        let (week_start, week_end) = RollupPeriod::week_of(date).days();
+        let since = week_start.and_time(chrono::NaiveTime::MIN).and_utc();
+        let until = (week_end + chrono::Days::new(1))
+            .and_time(chrono::NaiveTime::MIN)
+            .and_utc();
+
+        let jobs = self.db.list_job_usage(user_id, since, until).await?;
+        let tool_calls = self
+            .audit_entries(AuditEventKind::ToolExecution, Some(user_id), since, until)
+            .await?;
+        let heartbeats: Vec<AuditRecord> = self
+            .audit_entries(AuditEventKind::Heartbeat, None, since, until)
+            .await?
+            .into_iter()
+            .filter(|r| r.target.as_deref() == Some(user_id))
+            .collect();
+
+        Ok(UsageReport {
+            week_start,
+            jobs,
+            tools: tool_usage(&tool_calls),
+            heartbeat: heartbeat_activity(&heartbeats),
+        })
+    }
+
+    /// Audit entries of `kind` in `[since, until)`, newest first.
+    async fn audit_entries(
+        &self,
+        kind: AuditEventKind,
+        actor: Option<&str>,
+        since: DateTime<Utc>,
+        until: DateTime<Utc>,
+    ) -> Result<Vec<AuditRecord>, DatabaseError> {
+        let mut query = AuditQuery {
+            kind: Some(kind),
+            actor: actor.map(str::to_string),
+            since: Some(since),
+            until: Some(until),
+            before_id: None,
+            limit: Some(MAX_QUERY_LIMIT),
+        };
+        let mut entries = Vec::new();
+        loop {
+            let page = self.db.query_audit_log(&query).await?;
+            let full = page.len() as i64 == MAX_QUERY_LIMIT;
+            query.before_id = page.last().map(|r| r.id);
+            entries.extend(page);
+            if !full || entries.len() >= MAX_AUDIT_ENTRIES {
+                return Ok(entries);
+            }
+        }
+    }
+}
+
+/// Per-tool call counts from tool execution entries (newest first).
+fn tool_usage(entries: &[AuditRecord]) -> Vec<ToolUsage> {
+    let mut tools: BTreeMap<&str, ToolUsage> = BTreeMap::new();
+    for entry in entries {
+        let name = entry.target.as_deref().unwrap_or("unknown");
+        let tool = tools.entry(name).or_insert_with(|| ToolUsage {
+            name: name.to_string(),
+            ..Default::default()
+        });
+        tool.calls += 1;
+        if entry.outcome.as_deref() == Some("failure") {
+            tool.failures += 1;
+            if tool.last_error.is_none() {
+                tool.last_error = detail(entry, "error");
+            }
+        }
+    }
+    let mut tools: Vec<ToolUsage> = tools.into_values().collect();
+    tools.sort_by(|a, b| b.calls.cmp(&a.calls).then_with(|| a.name.cmp(&b.name)));
+    tools
+}
+
+/// Heartbeat outcomes from heartbeat entries (newest first).
+fn heartbeat_activity(entries: &[AuditRecord]) -> HeartbeatActivity {
+    let mut activity = HeartbeatActivity::default();
+    for entry in entries {
+        match entry.outcome.as_deref() {
+            Some("ok") => activity.ok += 1,
+            Some("needs_attention") => activity.alerts += 1,
+            Some("skipped") => activity.skipped += 1,
+            Some("failed") => {
+                activity.failed += 1;
+                if activity.last_error.is_none() {
+                    activity.last_error = detail(entry, "message");
+                }
+            }
+            _ => {}
+        }
+    }
+    activity
+}
+
+fn detail(entry: &AuditRecord, key: &str) -> Option<String> {
+    entry.details.get(key)?.as_str().map(str::to_string)
+}
+
+/// First line of `text`, cut to `MAX_ERROR_CHARS`.
+fn clip(text: &str) -> String {
+    let line = text.lines().next().unwrap_or_default().trim();
+    match line.char_indices().nth(MAX_ERROR_CHARS) {
+        Some((cut, _)) => format!("{}...", &line[..cut]),
+        None => line.to_string(),
+    }
+}
+
+impl Workspace {
+    /// Write the report for last week if it doesn't exist yet, returning
+    /// its path.
+    pub async fn write_usage_report(
+        &self,
+        reporter: &UsageReporter,
+    ) -> Result<Option<String>, UsageReportError> {
+        let last_week = Utc::now().date_naive() - chrono::Days::new(7);
+        let path = UsageReport::path_for(last_week);
+        let exists = self.list(REPORTS_DIR).await?.iter().any(|e| e.path == path);
+        if exists {
+            return Ok(None);
+        }
+
+        let report = reporter.collect(self.user_id(), last_week).await?;
+        self.write(&path, &report.render(Utc::now())).await?;
+        Ok(Some(path))
+    }
+
+    /// Run [`Workspace::write_usage_report`] every `interval`.
+    pub fn spawn_usage_reports(
+        self: &Arc<Self>,
+        reporter: UsageReporter,
+        interval: Duration,
+    ) -> tokio::task::JoinHandle<()> {
+        let workspace = Arc::clone(self);
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            loop {
+                ticker.tick().await;
+                match workspace.write_usage_report(&reporter).await {
+                    Ok(Some(path)) => tracing::info!("Wrote usage report {}", path),
+                    Ok(None) => {}
+                    Err(e) => tracing::warn!("Usage report failed: {}", e),
+                }
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use uuid::Uuid;
+
+    fn date(s: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap()
+    }
+
+    fn audit(target: &str, outcome: &str, details: serde_json::Value) -> AuditRecord {
+        AuditRecord {
+            id: 0,
+            kind: AuditEventKind::ToolExecution,
+            actor: "default".to_string(),
+            channel: None,
+            target: Some(target.to_string()),
+            outcome: Some(outcome.to_string()),
+            details,
+            created_at: Utc::now(),
+        }
+    }
+
+    fn job(title: &str, status: &str, cost: i64, reason: Option<&str>) -> JobUsageRecord {
+        JobUsageRecord {
+            id: Uuid::new_v4(),
+            title: title.to_string(),
+            status: status.to_string(),
+            actual_cost: Decimal::new(cost, 2),
+            input_tokens: 1000,
+            output_tokens: 100,
+            failure_reason: reason.map(str::to_string),
+            created_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_path_uses_iso_week() {
+        assert_eq!(
+            UsageReport::path_for(date("2024-01-17")),
+            "reports/usage-2024-03.md"
+        );
+        // ISO weeks can belong to the previous year.
+        assert_eq!(
+            UsageReport::path_for(date("2021-01-01")),
+            "reports/usage-2020-53.md"
+        );
+    }
+
+    #[test]
+    fn test_tool_usage_counts_and_keeps_latest_error() {
+        let entries = vec![
+            audit("http", "failure", serde_json::json!({ "error": "timeout" })),
+            audit("shell", "success", serde_json::json!({})),
+            audit("http", "failure", serde_json::json!({ "error": "dns" })),
+            audit("http", "success", serde_json::json!({})),
+        ];
+        let tools = tool_usage(&entries);
+        assert_eq!(
+            tools[0],
+            ToolUsage {
+                name: "http".to_string(),
+                calls: 3,
+                failures: 2,
+                last_error: Some("timeout".to_string()),
+            }
+        );
+        assert_eq!(tools[1].name, "shell");
+    }
+
+    #[test]
+    fn test_render() {
+        let report = UsageReport {
+            week_start: date("2024-01-15"),
+            jobs: vec![
+                job("Deploy", "completed", 12, None),
+                job("Backup", "failed", 3, Some("disk full\nstack trace")),
+            ],
+            tools: vec![ToolUsage {
+                name: "shell".to_string(),
+                calls: 4,
+                failures: 1,
+                last_error: Some("exit 1".to_string()),
+            }],
+            heartbeat: HeartbeatActivity {
+                ok: 10,
+                alerts: 1,
+                failed: 1,
+                last_error: Some("LLM call failed".to_string()),
+                ..Default::default()
+            },
+        };
+        let text = report.render(Utc::now());
+
+        assert!(text.starts_with("# Usage report: Week 2024-W03 (2024-01-15 to 2024-01-21)"));
+        assert!(text.contains("- 2 job(s): 1 completed, 1 failed"));
+        assert!(text.contains("- Cost: $0.15"));
+        assert!(text.contains("- Tokens: 2000 in, 200 out"));
+        assert!(text.contains("| shell | 4 | 1 |"));
+        assert!(text.contains("- 12 run(s): 10 ok, 1 alert(s), 0 skipped, 1 failed"));
+        assert!(text.contains("- Job \"Backup\" failed: disk full\n"));
+        assert!(text.contains("- `shell` failed 1 of 4 call(s), last: exit 1"));
+        assert!(text.contains("- Heartbeat failed 1 time(s), last: LLM call failed"));
+    }
+
+    #[test]
+    fn test_render_empty_week() {
+        let report = UsageReport {
+            week_start: date("2024-01-15"),
+            jobs: Vec::new(),
+            tools: Vec::new(),
+            heartbeat: HeartbeatActivity::default(),
+        };
+        let text = report.render(Utc::now());
+        assert!(text.contains("No jobs ran this week."));
+        assert!(text.contains("No tool calls recorded."));
+        assert!(text.contains("No heartbeat runs recorded."));
+        assert!(text.contains("## Notable errors\n\nNone."));
+    }
+
+    #[cfg(feature = "libsql")]
+    #[tokio::test]
+    async fn test_collect_and_write_report() {
+        use crate::audit::AuditEvent;
+        use crate::context::JobContext;
+        use crate::db::libsql_backend::test_db;
+
+        let (db, _dir) = test_db().await;
+
+        let mut mine = JobContext::with_user("default", "Summarize inbox", "");
+        mine.actual_cost = Decimal::new(25, 2);
+        db.save_job(&mine).await.unwrap();
+        db.save_job(&JobContext::with_user("someone-else", "Other", ""))
+            .await
+            .unwrap();
+        db.append_audit_event(&AuditEvent::tool_execution(
+            "default",
+            "http",
+            &serde_json::json!({}),
+            Some("connection refused"),
+            Duration::from_millis(5),
+        ))
+        .await
+        .unwrap();
+        db.append_audit_event(&AuditEvent::heartbeat("default", "ok", None))
+            .await
+            .unwrap();
+        db.append_audit_event(&AuditEvent::heartbeat("someone-else", "ok", None))
+            .await
+            .unwrap();
+
+        let reporter = UsageReporter::new(Arc::clone(&db));
+        let report = reporter
+            .collect("default", Utc::now().date_naive())
+            .await
+            .unwrap();
+        assert_eq!(report.jobs.len(), 1);
+        assert_eq!(report.jobs[0].title, "Summarize inbox");
+        assert_eq!(report.total_cost(), Decimal::new(25, 2));
+        assert_eq!(report.tools.len(), 1);
+        assert_eq!(
+            report.tools[0].last_error.as_deref(),
+            Some("connection refused")
+        );
+        assert_eq!(report.heartbeat.runs(), 1);
+
+        // Last week's report is written once.
+        let workspace = Workspace::new_with_db("default", db);
+        let path = workspace.write_usage_report(&reporter).await.unwrap();
+        let path = path.expect("report written");
+        assert!(path.starts_with(REPORTS_DIR));
+        let doc = workspace.read(&path).await.unwrap();
+        assert!(doc.content.contains("No jobs ran this week."));
+        assert!(
+            workspace
+                .write_usage_report(&reporter)
+                .await
+                .unwrap()
+                .is_none()
+        );
+    }
+}
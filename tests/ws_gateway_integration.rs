@@ -52,7 +52,10 @@ async fn start_test_server() -> (
         shutdown_tx: tokio::sync::RwLock::new(None),
         ws_tracker: Some(Arc::new(WsConnectionTracker::new())),
         llm_provider: None,
-        chat_rate_limiter: ironclaw::channels::web::server::RateLimiter::new(30, 60),
+        chat_rate_limiter: Arc::new(ironclaw::channels::web::server::RateLimiter::new(30, 60)),
+        api_keys: None,
+        health: None,
+        moderation: None,
     });
 
     let addr: SocketAddr = "127.0.0.1:0".parse().unwrap();